@@ -0,0 +1,128 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+// C API to embed the EndBASIC interpreter.
+//
+// Unless otherwise noted, functions that return a bool return false on failure, in which case
+// endbasic_last_error() returns the reason for the failure.
+
+#if !defined(ENDBASIC_H)
+#define ENDBASIC_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#if defined(__cplusplus)
+extern "C" {
+#endif
+
+// Opaque type representing an EndBASIC machine.
+typedef struct EndbasicMachine EndbasicMachine;
+
+// Identifier of the type of an EndbasicValue.
+typedef int EndbasicType;
+
+// Type for arguments that accept values of any type.  Not valid for values.
+#define ENDBASIC_TYPE_ANY 0
+
+// Type of boolean values, stored in EndbasicValue.boolean.
+#define ENDBASIC_TYPE_BOOLEAN 1
+
+// Type of double values, stored in EndbasicValue.double_.
+#define ENDBASIC_TYPE_DOUBLE 2
+
+// Type of integer values, stored in EndbasicValue.integer.
+#define ENDBASIC_TYPE_INTEGER 3
+
+// Type of long integer values, stored in EndbasicValue.long_.
+#define ENDBASIC_TYPE_LONG 4
+
+// Type of string values, stored in EndbasicValue.text.
+#define ENDBASIC_TYPE_TEXT 5
+
+// Representation of an EndBASIC value.  Only the field that corresponds to the vtype is
+// meaningful.
+typedef struct {
+    EndbasicType vtype;
+    bool boolean;
+    double double_;
+    int32_t integer;
+    int64_t long_;
+    const char* text;
+} EndbasicValue;
+
+// Host callback that receives console output.  text is only valid for the duration of the call.
+typedef void (*EndbasicOutputFn)(const char* text, void* user_data);
+
+// Host callback that provides console input one line at a time.  The callback must store a
+// NUL-terminated line, without the line terminator, into buf, which has space for size bytes.
+// Returns false to indicate the end of the input.
+typedef bool (*EndbasicInputFn)(char* buf, size_t size, void* user_data);
+
+// Host callback that implements a command or a function.
+//
+// The callback receives the nargs evaluated arguments in args, whose strings are only valid for
+// the duration of the call.  On success, functions must store their return value in result and
+// return true.  On failure, callables must return false and may store an error message as a
+// string in result.  Strings stored in result are copied as soon as the callback returns.  The
+// callback must not use the machine that invoked it: any such call fails.
+typedef bool (*EndbasicCallableFn)(
+    const EndbasicValue* args, size_t nargs, EndbasicValue* result, void* user_data);
+
+// Creates a new machine with the standard library whose console sends output to output and reads
+// input from input, passing user_data to them.  Either callback can be NULL.
+//
+// Returns NULL on failure.  The machine must be released with endbasic_machine_free().
+EndbasicMachine* endbasic_machine_new(
+    EndbasicOutputFn output, EndbasicInputFn input, void* user_data);
+
+// Releases a machine created with endbasic_machine_new().  m can be NULL.
+void endbasic_machine_free(EndbasicMachine* m);
+
+// Returns the message of the error raised by the last failed call on m, or NULL if the last call
+// succeeded.  The string is valid until the next call on m.
+const char* endbasic_last_error(const EndbasicMachine* m);
+
+// Executes the program in code on m.  Returns the exit code of the program, or -1 if execution
+// failed or if called from one of the callbacks of m.
+int endbasic_exec(EndbasicMachine* m, const char* code);
+
+// Registers a new command called name on m, documented by syntax and description, that takes
+// nargs arguments of the types in arg_types and that is implemented by callback.
+bool endbasic_add_command(
+    EndbasicMachine* m, const char* name, const char* syntax, const char* description,
+    const EndbasicType* arg_types, size_t nargs, EndbasicCallableFn callback, void* user_data);
+
+// Registers a new function called name on m that returns values of return_type, documented by
+// syntax and description, that takes nargs arguments of the types in arg_types and that is
+// implemented by callback.
+bool endbasic_add_function(
+    EndbasicMachine* m, const char* name, EndbasicType return_type, const char* syntax,
+    const char* description, const EndbasicType* arg_types, size_t nargs,
+    EndbasicCallableFn callback, void* user_data);
+
+// Sets the variable name on m to value.
+bool endbasic_set_var(EndbasicMachine* m, const char* name, const EndbasicValue* value);
+
+// Gets the variable name from m and stores it into value.  If the variable is a string, the string
+// is valid until the next call to this function.
+bool endbasic_get_var(EndbasicMachine* m, const char* name, EndbasicValue* value);
+
+#if defined(__cplusplus)
+}  // extern "C"
+#endif
+
+#endif  // !defined(ENDBASIC_H)
@@ -0,0 +1,166 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Console implementation that delegates all input and output to host callbacks.
+
+use async_trait::async_trait;
+use endbasic_std::console::{line_to_keys, remove_control_chars, CharsXY, ClearType, Console, Key};
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::{c_char, c_void};
+
+/// Number of columns reported by the console.
+const COLUMNS: u16 = 80;
+
+/// Number of lines reported by the console.
+const LINES: u16 = 24;
+
+/// Maximum length of a line of input read via the input callback, including the terminating NUL.
+const INPUT_BUFFER_SIZE: usize = 1024;
+
+/// Type of the host callback that receives console output.
+///
+/// `text` is a NUL-terminated string that is only valid for the duration of the call.
+pub type EndbasicOutputFn = extern "C" fn(text: *const c_char, user_data: *mut c_void);
+
+/// Type of the host callback that provides console input one line at a time.
+///
+/// The callback must store a NUL-terminated line, without the line terminator, into `buf`, which
+/// has space for `size` bytes.  Returns false to indicate the end of the input.
+pub type EndbasicInputFn =
+    extern "C" fn(buf: *mut c_char, size: usize, user_data: *mut c_void) -> bool;
+
+/// Console that delegates input and output to host callbacks.  Output is discarded and input is
+/// empty if the corresponding callbacks are missing.
+pub(crate) struct CallbackConsole {
+    output: Option<EndbasicOutputFn>,
+    input: Option<EndbasicInputFn>,
+    user_data: *mut c_void,
+
+    /// Line-oriented buffer to hold input received from the host.
+    buffer: VecDeque<Key>,
+}
+
+impl CallbackConsole {
+    /// Creates a new console that sends output to `output` and reads input from `input`, passing
+    /// `user_data` to every call.
+    pub(crate) fn new(
+        output: Option<EndbasicOutputFn>,
+        input: Option<EndbasicInputFn>,
+        user_data: *mut c_void,
+    ) -> Self {
+        Self { output, input, user_data, buffer: VecDeque::default() }
+    }
+
+    /// Sends `text` to the output callback, if any, optionally terminating it with a newline.
+    fn emit(&self, text: &str, newline: bool) {
+        if let Some(output) = self.output {
+            let mut text = remove_control_chars(text);
+            if newline {
+                text.push('\n');
+            }
+            let text = CString::new(text).expect("Control characters, including NUL, were removed");
+            output(text.as_ptr(), self.user_data);
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Console for CallbackConsole {
+    fn clear(&mut self, _how: ClearType) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn color(&self) -> (Option<u8>, Option<u8>) {
+        (None, None)
+    }
+
+    fn set_color(&mut self, _fg: Option<u8>, _bg: Option<u8>) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn enter_alt(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+
+    fn leave_alt(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn locate(&mut self, _pos: CharsXY) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_within_line(&mut self, _off: i16) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.emit(text, true);
+        Ok(())
+    }
+
+    async fn poll_key(&mut self) -> io::Result<Option<Key>> {
+        Ok(None)
+    }
+
+    async fn read_key(&mut self) -> io::Result<Key> {
+        if self.buffer.is_empty() {
+            let input = match self.input {
+                Some(input) => input,
+                None => return Ok(Key::Eof),
+            };
+            let mut buf = [0 as c_char; INPUT_BUFFER_SIZE];
+            if !input(buf.as_mut_ptr(), buf.len(), self.user_data) {
+                return Ok(Key::Eof);
+            }
+            buf[INPUT_BUFFER_SIZE - 1] = 0;
+            // SAFETY: The buffer is NUL-terminated because we just forced its last byte to be so.
+            let line = unsafe { CStr::from_ptr(buf.as_ptr()) };
+            self.buffer = line_to_keys(format!("{}\n", line.to_string_lossy()));
+        }
+        Ok(self.buffer.pop_front().unwrap_or(Key::Eof))
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn size_chars(&self) -> io::Result<CharsXY> {
+        Ok(CharsXY::new(COLUMNS, LINES))
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        self.emit(text, false);
+        Ok(())
+    }
+
+    fn sync_now(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_sync(&mut self, _enabled: bool) -> io::Result<bool> {
+        Ok(true)
+    }
+}
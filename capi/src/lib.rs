@@ -0,0 +1,914 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! C bindings to embed the EndBASIC interpreter in programs written in other languages.
+//!
+//! All functions in this crate take and return raw pointers and are thus unsafe.  The declarations
+//! of the C API live in `include/endbasic.h`, which documents the contract of every function.
+
+// Keep these in sync with other top-level files.
+#![allow(clippy::await_holding_refcell_ref)]
+#![allow(clippy::collapsible_else_if)]
+#![warn(anonymous_parameters, bad_style, missing_docs)]
+#![warn(unused, unused_extern_crates, unused_import_braces, unused_qualifications)]
+
+use endbasic_core::ast::{Value, VarType};
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    ArgSpecBuilder, Args, CallError, CallableMetadataBuilder, ClosureCommand, ClosureFunction,
+};
+use endbasic_std::MachineBuilder;
+use futures_lite::future::block_on;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::rc::Rc;
+
+mod console;
+pub use console::{EndbasicInputFn, EndbasicOutputFn};
+
+/// Category for all the callables registered via the C API.
+const CATEGORY: &str = "Host integration";
+
+/// Identifier of the type of an `EndbasicValue`.
+pub type EndbasicType = c_int;
+
+/// Type for arguments that accept values of any type.  Not valid for values.
+pub const ENDBASIC_TYPE_ANY: EndbasicType = 0;
+
+/// Type of boolean values, stored in `EndbasicValue::boolean`.
+pub const ENDBASIC_TYPE_BOOLEAN: EndbasicType = 1;
+
+/// Type of double values, stored in `EndbasicValue::double`.
+pub const ENDBASIC_TYPE_DOUBLE: EndbasicType = 2;
+
+/// Type of integer values, stored in `EndbasicValue::integer`.
+pub const ENDBASIC_TYPE_INTEGER: EndbasicType = 3;
+
+/// Type of long integer values, stored in `EndbasicValue::long`.
+pub const ENDBASIC_TYPE_LONG: EndbasicType = 4;
+
+/// Type of string values, stored in `EndbasicValue::text`.
+pub const ENDBASIC_TYPE_TEXT: EndbasicType = 5;
+
+/// Representation of an EndBASIC value across the C API.  Only the field that corresponds to the
+/// `vtype` is meaningful.
+#[repr(C)]
+pub struct EndbasicValue {
+    /// Type of the value.
+    pub vtype: EndbasicType,
+
+    /// Contents of a boolean value.
+    pub boolean: bool,
+
+    /// Contents of a double value.  Called `double_` in C to avoid clashing with the keyword.
+    pub double: f64,
+
+    /// Contents of an integer value.
+    pub integer: i32,
+
+    /// Contents of a long integer value.  Called `long_` in C to avoid clashing with the keyword.
+    pub long: i64,
+
+    /// Contents of a string value as a NUL-terminated string.
+    pub text: *const c_char,
+}
+
+impl Default for EndbasicValue {
+    fn default() -> Self {
+        Self {
+            vtype: ENDBASIC_TYPE_ANY,
+            boolean: false,
+            double: 0.0,
+            integer: 0,
+            long: 0,
+            text: ptr::null(),
+        }
+    }
+}
+
+/// Type of the host callbacks that implement commands and functions.
+///
+/// The callback receives the `nargs` evaluated arguments in `args`, whose strings are only valid
+/// for the duration of the call.  On success, functions must store their return value in
+/// `result` and return true.  On failure, callables must return false and may store an error
+/// message as a string in `result`.  Strings stored in `result` are copied as soon as the callback
+/// returns.  The callback must not use the machine that invoked it: any such call fails.
+pub type EndbasicCallableFn = extern "C" fn(
+    args: *const EndbasicValue,
+    nargs: usize,
+    result: *mut EndbasicValue,
+    user_data: *mut c_void,
+) -> bool;
+
+/// An EndBASIC machine exposed via the C API.
+pub struct EndbasicMachine {
+    machine: Machine,
+
+    /// Strings referenced by the metadata of the callables registered on `machine`.
+    ///
+    /// Callable metadata needs static strings, so `keep` hands out references to these with an
+    /// extended lifetime.  This is sound because the contents of a `String` never move, because
+    /// these strings are never modified, and because they are released after `machine`, which is
+    /// declared before this field and is thus dropped first.
+    strings: Vec<String>,
+
+    /// Whether `machine` is executing code.  Host callbacks run while this is true and must not
+    /// use the machine that invoked them.
+    running: bool,
+
+    /// Message of the last error, returned by `endbasic_last_error`.
+    last_error: Option<CString>,
+
+    /// Storage for the last string returned by `endbasic_get_var`.
+    last_text: Option<CString>,
+}
+
+/// Converts a type identifier into a variable type.
+fn to_vartype(vtype: EndbasicType) -> Option<VarType> {
+    match vtype {
+        ENDBASIC_TYPE_ANY => Some(VarType::Auto),
+        ENDBASIC_TYPE_BOOLEAN => Some(VarType::Boolean),
+        ENDBASIC_TYPE_DOUBLE => Some(VarType::Double),
+        ENDBASIC_TYPE_INTEGER => Some(VarType::Integer),
+        ENDBASIC_TYPE_LONG => Some(VarType::Long),
+        ENDBASIC_TYPE_TEXT => Some(VarType::Text),
+        _ => None,
+    }
+}
+
+/// Converts a C string into a Rust string.
+///
+/// # Safety
+///
+/// `s` must be null or point to a valid NUL-terminated string.
+unsafe fn from_c_str(s: *const c_char, what: &str) -> io::Result<String> {
+    if s.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} cannot be null", what),
+        ));
+    }
+    match CStr::from_ptr(s).to_str() {
+        Ok(s) => Ok(s.to_owned()),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} must be valid UTF-8", what),
+        )),
+    }
+}
+
+/// Converts a C value into an EndBASIC value.
+///
+/// # Safety
+///
+/// If the value is of text type, its `text` must be null or point to a valid NUL-terminated
+/// string.
+unsafe fn from_c_value(value: &EndbasicValue) -> io::Result<Value> {
+    match value.vtype {
+        ENDBASIC_TYPE_BOOLEAN => Ok(Value::Boolean(value.boolean)),
+        ENDBASIC_TYPE_DOUBLE => Ok(Value::Double(value.double)),
+        ENDBASIC_TYPE_INTEGER => Ok(Value::Integer(value.integer)),
+        ENDBASIC_TYPE_LONG => Ok(Value::Long(value.long)),
+        ENDBASIC_TYPE_TEXT => Ok(Value::Text(from_c_str(value.text, "Text value")?)),
+        vtype => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid value type {}", vtype),
+        )),
+    }
+}
+
+/// Converts an EndBASIC value into a C value.  The string of a text value, if any, is returned
+/// separately and the C value points to it, so the string must outlive the C value.
+fn to_c_value(value: &Value) -> (EndbasicValue, Option<CString>) {
+    let mut c_value = EndbasicValue::default();
+    let mut text = None;
+    match value {
+        Value::Boolean(b) => {
+            c_value.vtype = ENDBASIC_TYPE_BOOLEAN;
+            c_value.boolean = *b;
+        }
+        Value::Double(d) => {
+            c_value.vtype = ENDBASIC_TYPE_DOUBLE;
+            c_value.double = *d;
+        }
+        Value::Integer(i) => {
+            c_value.vtype = ENDBASIC_TYPE_INTEGER;
+            c_value.integer = *i;
+        }
+        Value::Long(i) => {
+            c_value.vtype = ENDBASIC_TYPE_LONG;
+            c_value.long = *i;
+        }
+        Value::Text(s) => {
+            let s = CString::new(s.replace('\0', " ")).expect("NUL characters were replaced");
+            c_value.vtype = ENDBASIC_TYPE_TEXT;
+            c_value.text = s.as_ptr();
+            text = Some(s);
+        }
+    }
+    (c_value, text)
+}
+
+/// Invokes the host `callback` with the evaluated `args` and returns its result.
+fn invoke(
+    callback: EndbasicCallableFn,
+    args: &Args,
+    user_data: *mut c_void,
+) -> Result<Option<Value>, CallError> {
+    let mut c_args = Vec::with_capacity(args.len());
+    let mut texts = Vec::with_capacity(args.len());
+    for i in 0..args.len() {
+        let (c_value, text) = to_c_value(&args.get::<Value>(i));
+        c_args.push(c_value);
+        texts.push(text);
+    }
+
+    let mut result = EndbasicValue::default();
+    let ok = callback(c_args.as_ptr(), c_args.len(), &mut result, user_data);
+    drop(texts);
+
+    // SAFETY: The callback contract requires text values to be valid strings.
+    let result = match unsafe { from_c_value(&result) } {
+        Ok(value) => Some(value),
+        Err(_) if result.vtype == ENDBASIC_TYPE_ANY => None,
+        Err(e) => return Err(CallError::IoError(e)),
+    };
+    if ok {
+        Ok(result)
+    } else {
+        let message = match result {
+            Some(Value::Text(message)) => message,
+            _ => "Host callback failed".to_owned(),
+        };
+        Err(CallError::IoError(io::Error::other(message)))
+    }
+}
+
+/// Converts `message` into the form stored in `EndbasicMachine::last_error`.
+fn to_error_message(message: String) -> CString {
+    CString::new(message.replace('\0', " ")).expect("NUL characters were replaced")
+}
+
+/// Obtains exclusive access to the machine `m`.
+///
+/// Returns none, and records the error, if `m` is executing code, which happens when a host
+/// callback tries to use the machine that invoked it.  Handing out a second mutable reference to
+/// the machine in that case would alias the one held by `endbasic_exec`.
+///
+/// # Safety
+///
+/// `m` must be a valid machine.
+unsafe fn enter<'a>(m: *mut EndbasicMachine) -> Option<&'a mut EndbasicMachine> {
+    // Only touch the fields that `endbasic_exec` does not borrow while it runs.
+    if (*m).running {
+        (*m).last_error =
+            Some(to_error_message("Cannot use the machine from one of its callbacks".to_owned()));
+        return None;
+    }
+    Some(&mut *m)
+}
+
+impl EndbasicMachine {
+    /// Stores `s` in the machine and returns a reference to it that lives as long as the
+    /// callables registered on it.
+    fn keep(&mut self, s: String) -> &'static str {
+        // SAFETY: See the documentation of `strings`.
+        let kept = unsafe { &*(s.as_str() as *const str) };
+        self.strings.push(s);
+        kept
+    }
+
+    /// Records the outcome of an operation so that `endbasic_last_error` can report it.
+    fn record<T, E: ToString>(&mut self, result: Result<T, E>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.last_error = None;
+                Some(value)
+            }
+            Err(e) => {
+                self.last_error = Some(to_error_message(e.to_string()));
+                None
+            }
+        }
+    }
+
+    /// Validates the details of a callable and returns its metadata builder and argument
+    /// specification.
+    ///
+    /// # Safety
+    ///
+    /// All strings must be valid and `arg_types` must point to `nargs` elements.
+    unsafe fn prepare_callable(
+        &mut self,
+        name: *const c_char,
+        return_type: VarType,
+        syntax: *const c_char,
+        description: *const c_char,
+        arg_types: *const EndbasicType,
+        nargs: usize,
+    ) -> io::Result<(CallableMetadataBuilder, ArgSpecBuilder)> {
+        let name = from_c_str(name, "Name")?.to_ascii_uppercase();
+        if self.machine.get_symbols().get_auto(&name).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is already defined", name),
+            ));
+        }
+        let syntax = from_c_str(syntax, "Syntax")?;
+        let description = from_c_str(description, "Description")?;
+        if description.lines().any(str::is_empty) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Description cannot contain empty lines",
+            ));
+        }
+
+        let mut vtypes = Vec::with_capacity(nargs);
+        if nargs > 0 {
+            if arg_types.is_null() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Argument types cannot be null",
+                ));
+            }
+            for (i, vtype) in std::slice::from_raw_parts(arg_types, nargs).iter().enumerate() {
+                vtypes.push(to_vartype(*vtype).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Invalid type for argument {}", i + 1),
+                    )
+                })?);
+            }
+        }
+
+        // Only keep strings once the callable is known to be valid so that failed registrations
+        // do not accumulate garbage in the machine.
+        let mut args = ArgSpecBuilder::new();
+        for (i, vtype) in vtypes.into_iter().enumerate() {
+            args = args.required(self.keep(format!("Argument {}", i + 1)), vtype);
+        }
+        let metadata = CallableMetadataBuilder::new(self.keep(name), return_type)
+            .with_syntax(self.keep(syntax))
+            .with_category(CATEGORY)
+            .with_description(self.keep(description));
+        Ok((metadata, args))
+    }
+}
+
+/// Creates a new machine with the standard library whose console sends output to `output` and
+/// reads input from `input`, passing `user_data` to them.  Either callback can be null.
+///
+/// Returns null on failure.  The machine must be released with `endbasic_machine_free`.
+#[no_mangle]
+pub extern "C" fn endbasic_machine_new(
+    output: Option<EndbasicOutputFn>,
+    input: Option<EndbasicInputFn>,
+    user_data: *mut c_void,
+) -> *mut EndbasicMachine {
+    let console = Rc::from(RefCell::from(console::CallbackConsole::new(output, input, user_data)));
+    match MachineBuilder::default().with_console(console).build() {
+        Ok(machine) => Box::into_raw(Box::from(EndbasicMachine {
+            machine,
+            strings: vec![],
+            running: false,
+            last_error: None,
+            last_text: None,
+        })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Releases a machine created with `endbasic_machine_new`.
+///
+/// # Safety
+///
+/// `m` must be null or a machine returned by `endbasic_machine_new` that was not yet released.
+#[no_mangle]
+pub unsafe extern "C" fn endbasic_machine_free(m: *mut EndbasicMachine) {
+    if !m.is_null() {
+        drop(Box::from_raw(m));
+    }
+}
+
+/// Returns the message of the error raised by the last failed call on `m`, or null if the last
+/// call succeeded.  The string is valid until the next call on `m`.
+///
+/// # Safety
+///
+/// `m` must be a valid machine.
+#[no_mangle]
+pub unsafe extern "C" fn endbasic_last_error(m: *const EndbasicMachine) -> *const c_char {
+    match (*m).last_error.as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Executes the program in `code` on `m`.
+///
+/// Returns the exit code of the program, or -1 if execution failed or if called from one of the
+/// callbacks of `m`.
+///
+/// # Safety
+///
+/// `m` must be a valid machine and `code` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn endbasic_exec(m: *mut EndbasicMachine, code: *const c_char) -> c_int {
+    let code = match enter(m).and_then(|m| m.record(from_c_str(code, "Code"))) {
+        Some(code) => code,
+        None => return -1,
+    };
+
+    // Host callbacks may call back into the API with `m` while the program runs, so only borrow
+    // the machine itself until execution completes.  `enter` uses `running` to reject such calls.
+    (*m).running = true;
+    let result = block_on((*m).machine.exec(&mut code.as_bytes()));
+    (*m).running = false;
+
+    let m = &mut *m;
+    match m.record(result) {
+        Some(stop_reason) => stop_reason.as_exit_code(),
+        None => -1,
+    }
+}
+
+/// Registers a new command called `name` on `m`, documented by `syntax` and `description`, that
+/// takes `nargs` arguments of the types in `arg_types` and that is implemented by `callback`.
+///
+/// Returns false on failure.
+///
+/// # Safety
+///
+/// `m` must be a valid machine, all strings must be valid NUL-terminated strings, and
+/// `arg_types` must point to `nargs` elements.
+#[no_mangle]
+pub unsafe extern "C" fn endbasic_add_command(
+    m: *mut EndbasicMachine,
+    name: *const c_char,
+    syntax: *const c_char,
+    description: *const c_char,
+    arg_types: *const EndbasicType,
+    nargs: usize,
+    callback: EndbasicCallableFn,
+    user_data: *mut c_void,
+) -> bool {
+    let m = match enter(m) {
+        Some(m) => m,
+        None => return false,
+    };
+    let prepared = m.prepare_callable(name, VarType::Void, syntax, description, arg_types, nargs);
+    let (metadata, args) = match m.record(prepared) {
+        Some(prepared) => prepared,
+        None => return false,
+    };
+    m.machine.add_command(ClosureCommand::new(metadata.build(), args.build(), move |args, _| {
+        invoke(callback, &args, user_data).map(|_| ())
+    }));
+    true
+}
+
+/// Registers a new function called `name` on `m` that returns values of `return_type`,
+/// documented by `syntax` and `description`, that takes `nargs` arguments of the types in
+/// `arg_types` and that is implemented by `callback`.
+///
+/// Returns false on failure.
+///
+/// # Safety
+///
+/// `m` must be a valid machine, all strings must be valid NUL-terminated strings, and
+/// `arg_types` must point to `nargs` elements.
+#[no_mangle]
+pub unsafe extern "C" fn endbasic_add_function(
+    m: *mut EndbasicMachine,
+    name: *const c_char,
+    return_type: EndbasicType,
+    syntax: *const c_char,
+    description: *const c_char,
+    arg_types: *const EndbasicType,
+    nargs: usize,
+    callback: EndbasicCallableFn,
+    user_data: *mut c_void,
+) -> bool {
+    let m = match enter(m) {
+        Some(m) => m,
+        None => return false,
+    };
+    let return_type = match to_vartype(return_type) {
+        Some(VarType::Auto) | None => {
+            m.record::<(), _>(Err(format!("Invalid return type {}", return_type)));
+            return false;
+        }
+        Some(vtype) => vtype,
+    };
+    let prepared = m.prepare_callable(name, return_type, syntax, description, arg_types, nargs);
+    let (metadata, args) = match m.record(prepared) {
+        Some(prepared) => prepared,
+        None => return false,
+    };
+    m.machine.add_function(ClosureFunction::new(metadata.build(), args.build(), move |args, _| {
+        match invoke(callback, &args, user_data)? {
+            Some(value) if value.as_vartype() == return_type => Ok(value),
+            _ => Err(CallError::IoError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Host callback returned a value of the wrong type",
+            ))),
+        }
+    }));
+    true
+}
+
+/// Sets the variable `name` on `m` to `value`.
+///
+/// Returns false on failure.
+///
+/// # Safety
+///
+/// `m` must be a valid machine, `name` must be a valid NUL-terminated string, and `value` must
+/// be a valid value.
+#[no_mangle]
+pub unsafe extern "C" fn endbasic_set_var(
+    m: *mut EndbasicMachine,
+    name: *const c_char,
+    value: *const EndbasicValue,
+) -> bool {
+    let m = match enter(m) {
+        Some(m) => m,
+        None => return false,
+    };
+    let result = from_c_str(name, "Name").and_then(|name| Ok((name, from_c_value(&*value)?)));
+    let (name, value) = match m.record(result) {
+        Some(pair) => pair,
+        None => return false,
+    };
+    let result = m.machine.set_var(&name, value);
+    m.record(result).is_some()
+}
+
+/// Gets the variable `name` from `m` and stores it into `value`.  If the variable is a string,
+/// the string is valid until the next call to this function.
+///
+/// Returns false on failure.
+///
+/// # Safety
+///
+/// `m` must be a valid machine, `name` must be a valid NUL-terminated string, and `value` must
+/// point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn endbasic_get_var(
+    m: *mut EndbasicMachine,
+    name: *const c_char,
+    value: *mut EndbasicValue,
+) -> bool {
+    let m = match enter(m) {
+        Some(m) => m,
+        None => return false,
+    };
+    let name = match m.record(from_c_str(name, "Name")) {
+        Some(name) => name,
+        None => return false,
+    };
+    let result = m.machine.get_var::<Value>(&name);
+    match m.record(result) {
+        Some(v) => {
+            let (c_value, text) = to_c_value(&v);
+            *value = c_value;
+            m.last_text = text;
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// State of the host shared with the console callbacks.
+    #[derive(Default)]
+    struct Host {
+        /// Captured console output.
+        output: String,
+
+        /// Lines to provide as console input, in reverse order.
+        input: Vec<&'static str>,
+    }
+
+    /// Output callback that appends the text to the `Host` pointed to by `user_data`.
+    extern "C" fn capture_output(text: *const c_char, user_data: *mut c_void) {
+        let host = unsafe { &mut *(user_data as *mut Host) };
+        host.output.push_str(unsafe { CStr::from_ptr(text) }.to_str().unwrap());
+    }
+
+    /// Input callback that provides lines from the `Host` pointed to by `user_data`.
+    extern "C" fn provide_input(buf: *mut c_char, size: usize, user_data: *mut c_void) -> bool {
+        let host = unsafe { &mut *(user_data as *mut Host) };
+        match host.input.pop() {
+            Some(line) => {
+                let line = CString::new(line).unwrap();
+                let bytes = line.as_bytes_with_nul();
+                assert!(bytes.len() <= size);
+                unsafe {
+                    ptr::copy_nonoverlapping(bytes.as_ptr() as *const c_char, buf, bytes.len())
+                };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Creates a new machine whose console is backed by `host`.
+    fn new_machine(host: &mut Host) -> *mut EndbasicMachine {
+        let m = endbasic_machine_new(
+            Some(capture_output),
+            Some(provide_input),
+            host as *mut Host as *mut c_void,
+        );
+        assert!(!m.is_null());
+        m
+    }
+
+    /// Returns the last error recorded in `m`, if any.
+    fn last_error(m: *const EndbasicMachine) -> Option<String> {
+        let message = unsafe { endbasic_last_error(m) };
+        if message.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(message) }.to_str().unwrap().to_owned())
+        }
+    }
+
+    /// Executes `code` on `m`.
+    fn exec(m: *mut EndbasicMachine, code: &str) -> c_int {
+        let code = CString::new(code).unwrap();
+        unsafe { endbasic_exec(m, code.as_ptr()) }
+    }
+
+    #[test]
+    fn test_exec_ok() {
+        let mut host = Host::default();
+        let m = new_machine(&mut host);
+        assert_eq!(0, exec(m, "PRINT \"hello\": PRINT 1 + 2"));
+        assert_eq!(3, exec(m, "END 3"));
+        assert_eq!(None, last_error(m));
+        unsafe { endbasic_machine_free(m) };
+        assert_eq!("hello\n 3\n", host.output);
+    }
+
+    #[test]
+    fn test_exec_errors() {
+        let mut host = Host::default();
+        let m = new_machine(&mut host);
+        assert_eq!(-1, exec(m, "PRINT 1 +"));
+        assert_eq!(Some("1:9: Not enough values to apply operator".to_owned()), last_error(m));
+        assert_eq!(0, exec(m, ""));
+        assert_eq!(None, last_error(m));
+        assert_eq!(-1, unsafe { endbasic_exec(m, ptr::null()) });
+        assert_eq!(Some("Code cannot be null".to_owned()), last_error(m));
+        unsafe { endbasic_machine_free(m) };
+    }
+
+    #[test]
+    fn test_input() {
+        let mut host = Host { input: vec!["7", "5"], ..Default::default() };
+        let m = new_machine(&mut host);
+        assert_eq!(0, exec(m, "INPUT a: INPUT b: PRINT a * b"));
+        assert_eq!(-1, exec(m, "INPUT c"));
+        assert_eq!(Some("1:1: In call to INPUT: EOF".to_owned()), last_error(m));
+        unsafe { endbasic_machine_free(m) };
+        assert_eq!(" 35\n", host.output);
+    }
+
+    #[test]
+    fn test_vars() {
+        let mut host = Host::default();
+        let m = new_machine(&mut host);
+
+        let name = CString::new("name").unwrap();
+        let text = CString::new("world").unwrap();
+        let value =
+            EndbasicValue { vtype: ENDBASIC_TYPE_TEXT, text: text.as_ptr(), ..Default::default() };
+        assert!(unsafe { endbasic_set_var(m, name.as_ptr(), &value) });
+        let n = CString::new("n").unwrap();
+        let value =
+            EndbasicValue { vtype: ENDBASIC_TYPE_INTEGER, integer: 3, ..Default::default() };
+        assert!(unsafe { endbasic_set_var(m, n.as_ptr(), &value) });
+
+        assert_eq!(0, exec(m, "greeting = \"hello \" + name: n = n * 2"));
+
+        let greeting = CString::new("greeting").unwrap();
+        let mut value = EndbasicValue::default();
+        assert!(unsafe { endbasic_get_var(m, greeting.as_ptr(), &mut value) });
+        assert_eq!(ENDBASIC_TYPE_TEXT, value.vtype);
+        assert_eq!("hello world", unsafe { CStr::from_ptr(value.text) }.to_str().unwrap());
+        assert!(unsafe { endbasic_get_var(m, n.as_ptr(), &mut value) });
+        assert_eq!(ENDBASIC_TYPE_INTEGER, value.vtype);
+        assert_eq!(6, value.integer);
+
+        let value = EndbasicValue { vtype: ENDBASIC_TYPE_BOOLEAN, ..Default::default() };
+        assert!(!unsafe { endbasic_set_var(m, n.as_ptr(), &value) });
+        assert_eq!(
+            Some("Cannot assign value of type BOOLEAN to variable of type INTEGER".to_owned()),
+            last_error(m)
+        );
+        let value = EndbasicValue { vtype: 42, ..Default::default() };
+        assert!(!unsafe { endbasic_set_var(m, n.as_ptr(), &value) });
+        assert_eq!(Some("Invalid value type 42".to_owned()), last_error(m));
+        let undefined = CString::new("undefined").unwrap();
+        let mut value = EndbasicValue::default();
+        assert!(!unsafe { endbasic_get_var(m, undefined.as_ptr(), &mut value) });
+        assert_eq!(Some("Undefined variable undefined".to_owned()), last_error(m));
+
+        unsafe { endbasic_machine_free(m) };
+    }
+
+    /// Callable that adds its two integer arguments, failing if the first one is negative.
+    extern "C" fn add(
+        args: *const EndbasicValue,
+        nargs: usize,
+        result: *mut EndbasicValue,
+        _user_data: *mut c_void,
+    ) -> bool {
+        let args = unsafe { std::slice::from_raw_parts(args, nargs) };
+        let result = unsafe { &mut *result };
+        if args[0].integer < 0 {
+            result.vtype = ENDBASIC_TYPE_TEXT;
+            result.text = b"Negative input\0".as_ptr() as *const c_char;
+            return false;
+        }
+        result.vtype = ENDBASIC_TYPE_INTEGER;
+        result.integer = args[0].integer + args[1].integer;
+        true
+    }
+
+    /// Callable that appends its string argument to the `String` pointed to by `user_data`.
+    extern "C" fn record(
+        args: *const EndbasicValue,
+        nargs: usize,
+        _result: *mut EndbasicValue,
+        user_data: *mut c_void,
+    ) -> bool {
+        let args = unsafe { std::slice::from_raw_parts(args, nargs) };
+        let output = unsafe { &mut *(user_data as *mut String) };
+        output.push_str(unsafe { CStr::from_ptr(args[0].text) }.to_str().unwrap());
+        true
+    }
+
+    #[test]
+    fn test_callables() {
+        let mut host = Host::default();
+        let mut recorded = String::new();
+        let m = new_machine(&mut host);
+
+        let name = CString::new("record").unwrap();
+        let syntax = CString::new("text$").unwrap();
+        let description = CString::new("Records text.").unwrap();
+        let types = [ENDBASIC_TYPE_TEXT];
+        assert!(unsafe {
+            endbasic_add_command(
+                m,
+                name.as_ptr(),
+                syntax.as_ptr(),
+                description.as_ptr(),
+                types.as_ptr(),
+                types.len(),
+                record,
+                &mut recorded as *mut String as *mut c_void,
+            )
+        });
+
+        let name = CString::new("ADD").unwrap();
+        let syntax = CString::new("a%, b%").unwrap();
+        let description = CString::new("Adds two numbers.").unwrap();
+        let types = [ENDBASIC_TYPE_INTEGER, ENDBASIC_TYPE_INTEGER];
+        assert!(unsafe {
+            endbasic_add_function(
+                m,
+                name.as_ptr(),
+                ENDBASIC_TYPE_INTEGER,
+                syntax.as_ptr(),
+                description.as_ptr(),
+                types.as_ptr(),
+                types.len(),
+                add,
+                ptr::null_mut(),
+            )
+        });
+        assert!(!unsafe {
+            endbasic_add_function(
+                m,
+                name.as_ptr(),
+                ENDBASIC_TYPE_INTEGER,
+                syntax.as_ptr(),
+                description.as_ptr(),
+                types.as_ptr(),
+                types.len(),
+                add,
+                ptr::null_mut(),
+            )
+        });
+        assert_eq!(Some("ADD is already defined".to_owned()), last_error(m));
+        assert!(!unsafe {
+            endbasic_add_function(
+                m,
+                name.as_ptr(),
+                ENDBASIC_TYPE_ANY,
+                syntax.as_ptr(),
+                description.as_ptr(),
+                types.as_ptr(),
+                types.len(),
+                add,
+                ptr::null_mut(),
+            )
+        });
+        assert_eq!(Some("Invalid return type 0".to_owned()), last_error(m));
+
+        assert_eq!(0, exec(m, "RECORD \"a\": RECORD STR$(ADD(2, 3))"));
+        assert_eq!("a 5", recorded);
+
+        assert_eq!(-1, exec(m, "RECORD 3"));
+        assert_eq!(
+            Some("1:1: In call to RECORD: 1:8: Argument 1 must be a string".to_owned()),
+            last_error(m)
+        );
+        assert_eq!(-1, exec(m, "PRINT ADD(-1, 3)"));
+        assert_eq!(Some("1:7: In call to ADD: Negative input".to_owned()), last_error(m));
+
+        unsafe { endbasic_machine_free(m) };
+    }
+
+    /// State of the `reenter` callable.
+    struct Reentry {
+        /// Machine that runs the callable.
+        m: *mut EndbasicMachine,
+
+        /// Exit code returned by the nested call to `endbasic_exec`.
+        code: c_int,
+
+        /// Error reported by the nested call to `endbasic_exec`.
+        error: Option<String>,
+    }
+
+    /// Callable that tries to execute code on the machine that invokes it, as described by the
+    /// `Reentry` pointed to by `user_data`.
+    extern "C" fn reenter(
+        _args: *const EndbasicValue,
+        _nargs: usize,
+        _result: *mut EndbasicValue,
+        user_data: *mut c_void,
+    ) -> bool {
+        let reentry = unsafe { &mut *(user_data as *mut Reentry) };
+        reentry.code = exec(reentry.m, "PRINT \"nested\"");
+        reentry.error = last_error(reentry.m);
+        true
+    }
+
+    #[test]
+    fn test_callables_cannot_reenter() {
+        let mut host = Host::default();
+        let m = new_machine(&mut host);
+        let mut reentry = Reentry { m, code: 0, error: None };
+
+        let name = CString::new("REENTER").unwrap();
+        let syntax = CString::new("").unwrap();
+        let description = CString::new("Executes code from a callback.").unwrap();
+        assert!(unsafe {
+            endbasic_add_command(
+                m,
+                name.as_ptr(),
+                syntax.as_ptr(),
+                description.as_ptr(),
+                ptr::null(),
+                0,
+                reenter,
+                &mut reentry as *mut Reentry as *mut c_void,
+            )
+        });
+
+        assert_eq!(0, exec(m, "REENTER: PRINT \"outer\""));
+        assert_eq!(None, last_error(m));
+        assert_eq!(-1, reentry.code);
+        assert_eq!(
+            Some("Cannot use the machine from one of its callbacks".to_owned()),
+            reentry.error
+        );
+
+        assert_eq!(0, exec(m, "PRINT \"again\""));
+        unsafe { endbasic_machine_free(m) };
+        assert_eq!("outer\nagain\n", host.output);
+    }
+}
@@ -25,31 +25,18 @@
 use anyhow::{anyhow, Result};
 use async_channel::Sender;
 use endbasic_core::exec::Signal;
-use endbasic_std::console::Console;
+use endbasic_std::console::{Console, HeadlessConsole, SizeInPixels};
+use endbasic_std::i18n::{self, Locale};
 use endbasic_std::storage::Storage;
 use getopts::Options;
 use std::cell::RefCell;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::rc::Rc;
 
-/// Errors caused by the user when invoking this binary (invalid options or arguments).
-#[derive(Debug, thiserror::Error)]
-#[error("{message}")]
-struct UsageError {
-    message: String,
-}
-
-impl UsageError {
-    /// Creates a new usage error with `message`.
-    fn new<T: Into<String>>(message: T) -> Self {
-        Self { message: message.into() }
-    }
-}
-
 /// Consumes and returns the program name from `env::Args`.
 ///
 /// If the program name cannot be obtained, return `default_name` instead.
@@ -69,19 +56,36 @@ fn program_name(mut args: env::Args, default_name: &'static str) -> (String, env
 
 /// Prints usage information for program `name` with `opts` following the GNU Standards format.
 fn help(name: &str, opts: &Options) {
-    let brief = format!("Usage: {} [options] [program-file]", name);
+    let brief = format!("Usage: {} [options] [program-file [arg1 .. argN]]", name);
     println!("{}", opts.usage(&brief));
     println!("CONSOLE-SPEC can be one of the following:");
     if cfg!(feature = "sdl") {
         println!("    graphics[:SPEC]     enables the graphical console and configures it");
         println!("                        with the settings in SPEC, which is of the form:");
-        println!("                        RESOLUTION,TTF_FONT_PATH,FONT_SIZE");
+        println!("                        RESOLUTION,TTF_FONT_PATH,FONT_SIZE,VSYNC");
         println!("                        individual components of the SPEC can be omitted");
         println!("                        RESOLUTION can be one of 'fs' (for full screen),");
         println!("                        'WIDTHxHEIGHT' or 'WIDTHxHEIGHTfs'");
+        println!("                        VSYNC can be 'vsync' to pace GFX_SYNC calls to the");
+        println!("                        display refresh rate, or 'novsync' (the default)");
+    }
+    println!("    headless[:SPEC]     enables an off-screen graphical console and configures");
+    println!("                        it with the settings in SPEC, which is of the form:");
+    println!("                        WIDTHxHEIGHT,FRAMES_DIR");
+    println!("                        FRAMES_DIR, if present, receives a PPM image of the");
+    println!("                        screen on every GFX_SYNC call");
+    if cfg!(feature = "minifb") {
+        println!("    minifb[:SPEC]       enables the lightweight graphical console and");
+        println!("                        configures it with the settings in SPEC, which is");
+        println!("                        of the form: WIDTHxHEIGHT,FONT_SIZE");
+        println!("                        individual components of the SPEC can be omitted");
     }
     println!("    text                enables the text-based console");
     println!();
+    println!("LOCALE can be one of 'en' (English) or 'es' (Spanish).  If not given, the");
+    println!("language is taken from the ENDBASIC_LANG, LC_ALL, LC_MESSAGES or LANG");
+    println!("environment variables.");
+    println!();
     println!("Report bugs to: https://github.com/endbasic/endbasic/issues");
     println!("EndBASIC home page: https://www.endbasic.dev/");
 }
@@ -95,23 +99,48 @@ fn version() {
 
 /// Creates a new EndBASIC machine builder based on the features enabled in this crate.
 fn new_machine_builder(console_spec: Option<&str>) -> io::Result<endbasic_std::MachineBuilder> {
-    /// Obtains the default set of pins for a Raspberry Pi.
+    /// Obtains the default set of pins and buses for a Raspberry Pi.
     #[cfg(feature = "rpi")]
-    fn add_gpio_pins(builder: endbasic_std::MachineBuilder) -> endbasic_std::MachineBuilder {
-        builder.with_gpio_pins(Rc::from(RefCell::from(endbasic_rpi::RppalPins::default())))
+    fn add_gpio_pins(
+        builder: endbasic_std::MachineBuilder,
+        signals_tx: Sender<Signal>,
+    ) -> endbasic_std::MachineBuilder {
+        builder
+            .with_gpio_pins(Rc::from(RefCell::from(endbasic_rpi::RppalPins::new(signals_tx))))
+            .with_i2c_bus(Rc::from(RefCell::from(endbasic_rpi::RppalI2cBus::default())))
+            .with_spi_bus(Rc::from(RefCell::from(endbasic_rpi::RppalSpiBus::default())))
     }
 
-    /// Obtains the default set of pins for a platform without GPIO support.
+    /// Obtains the default set of pins and buses for a platform without GPIO support.
     #[cfg(not(feature = "rpi"))]
-    fn add_gpio_pins(builder: endbasic_std::MachineBuilder) -> endbasic_std::MachineBuilder {
+    fn add_gpio_pins(
+        builder: endbasic_std::MachineBuilder,
+        _signals_tx: Sender<Signal>,
+    ) -> endbasic_std::MachineBuilder {
+        builder
+    }
+
+    /// Obtains the serial port for platforms that support it.
+    #[cfg(unix)]
+    fn add_serial_port(builder: endbasic_std::MachineBuilder) -> endbasic_std::MachineBuilder {
+        builder.with_serial_port(Rc::from(RefCell::from(endbasic_std::serial::TtyPort::default())))
+    }
+
+    /// Obtains the serial port for platforms without serial support.
+    #[cfg(not(unix))]
+    fn add_serial_port(builder: endbasic_std::MachineBuilder) -> endbasic_std::MachineBuilder {
         builder
     }
 
     let signals_chan = async_channel::unbounded();
     let mut builder = endbasic_std::MachineBuilder::default();
     builder = builder.with_console(setup_console(console_spec, signals_chan.0.clone())?);
+    builder = add_gpio_pins(builder, signals_chan.0.clone());
     builder = builder.with_signals_chan(signals_chan);
-    builder = add_gpio_pins(builder);
+    builder = add_serial_port(builder);
+    builder = builder
+        .with_env(Rc::from(RefCell::from(endbasic_std::process::SystemEnvironment::default())));
+    builder = builder.with_http_client(Rc::from(endbasic_client::ReqwestHttpClient::default()));
     Ok(builder)
 }
 
@@ -135,11 +164,21 @@ fn finish_interactive_build(
 ) -> endbasic_core::exec::Result<endbasic_core::exec::Machine> {
     let console = builder.get_console();
     let storage = builder.get_storage();
+    let program = builder.get_program();
 
     let mut machine = builder.build()?;
 
+    endbasic_repl::demos::add_all(&mut machine, console.clone(), storage.clone(), program.clone());
+
     let service = Rc::from(RefCell::from(endbasic_client::CloudService::new(service_url)?));
-    endbasic_client::add_all(&mut machine, service, console, storage, "https://repl.endbasic.dev/");
+    endbasic_client::add_all(
+        &mut machine,
+        service,
+        console,
+        storage,
+        program,
+        "https://repl.endbasic.dev/",
+    );
 
     Ok(machine)
 }
@@ -168,6 +207,31 @@ fn get_local_drive_spec(flag: Option<String>) -> Result<String> {
     }
 }
 
+/// Parses the optional quota, in bytes, of the `LOCAL` drive given in `flag`.
+fn get_local_drive_quota(flag: Option<String>) -> Result<Option<u64>> {
+    match flag {
+        Some(text) => match text.parse::<u64>() {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(_) => {
+                Err(anyhow!("Invalid --local-quota value '{}': must be a number of bytes", text))
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+/// Determines the language of the interpreter messages from the `--lang` flag or, if not given,
+/// from the environment.
+fn get_locale(flag: Option<String>) -> Result<Locale> {
+    match flag {
+        Some(name) => match Locale::parse(&name) {
+            Some(locale) => Ok(locale),
+            None => Err(anyhow!("Invalid --lang value '{}': unsupported language", name)),
+        },
+        None => Ok(Locale::from_env()),
+    }
+}
+
 /// Sets up the console.
 fn setup_console(
     console_spec: Option<&str>,
@@ -204,9 +268,65 @@ fn setup_console(
         Err(io::Error::new(io::ErrorKind::InvalidInput, "SDL support not compiled in"))
     }
 
+    /// Creates the lightweight graphical console when minifb support is built in.
+    #[cfg(feature = "minifb")]
+    pub fn setup_minifb_console(
+        signals_tx: Sender<Signal>,
+        spec: &str,
+    ) -> io::Result<Rc<RefCell<dyn Console>>> {
+        endbasic_minifb::setup(spec, signals_tx)
+    }
+
+    /// Errors out during the creation of the lightweight graphical console when minifb support is
+    /// not compiled in.
+    #[cfg(not(feature = "minifb"))]
+    pub fn setup_minifb_console(
+        _signals_tx: Sender<Signal>,
+        _spec: &str,
+    ) -> io::Result<Rc<RefCell<dyn Console>>> {
+        // TODO(jmmv): Make this io::ErrorKind::Unsupported when our MSRV allows it.
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "minifb support not compiled in"))
+    }
+
+    /// Creates the headless graphical console configured with the settings in `spec`.
+    fn setup_headless_console(spec: &str) -> io::Result<Rc<RefCell<dyn Console>>> {
+        let (resolution, frames_dir) = match spec.split_once(',') {
+            Some((resolution, frames_dir)) => (resolution, Some(PathBuf::from(frames_dir))),
+            None => (spec, None),
+        };
+
+        let size = if resolution.is_empty() {
+            SizeInPixels { width: 800, height: 600 }
+        } else {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid resolution {}", resolution),
+                )
+            };
+            let (width, height) = resolution.split_once('x').ok_or_else(invalid)?;
+            SizeInPixels {
+                width: width.parse::<u16>().map_err(|_| invalid())?,
+                height: height.parse::<u16>().map_err(|_| invalid())?,
+            }
+        };
+
+        Ok(Rc::from(RefCell::from(HeadlessConsole::new(size, frames_dir)?)))
+    }
+
     let console: Rc<RefCell<dyn Console>> = match console_spec {
         None | Some("text") => setup_text_console(signals_tx)?,
 
+        Some("minifb") => setup_minifb_console(signals_tx, "")?,
+        Some(text) if text.starts_with("minifb:") => {
+            setup_minifb_console(signals_tx, &text["minifb:".len()..])?
+        }
+
+        Some("headless") => setup_headless_console("")?,
+        Some(text) if text.starts_with("headless:") => {
+            setup_headless_console(&text["headless:".len()..])?
+        }
+
         Some("graphics") => setup_graphics_console(signals_tx, "")?,
         Some(text) if text.starts_with("graphics:") => {
             setup_graphics_console(signals_tx, &text["graphics:".len()..])?
@@ -225,15 +345,22 @@ fn setup_console(
 /// Sets up the common storage drives.
 ///
 /// This instantiates non-optional drives, such as `MEMORY:` and `DEMOS:`, maps `LOCAL` the
-/// location given in `local_drive_spec`.
-pub fn setup_storage(storage: &mut Storage, local_drive_spec: &str) -> io::Result<()> {
+/// location given in `local_drive_spec`, and limits its size to `local_drive_quota` bytes if given.
+pub fn setup_storage(
+    storage: &mut Storage,
+    local_drive_spec: &str,
+    local_drive_quota: Option<u64>,
+) -> io::Result<()> {
     storage.register_scheme("demos", Box::from(endbasic_repl::demos::DemoDriveFactory::default()));
-    storage.mount("demos", "demos://").expect("Demos drive shouldn't fail to mount");
+    storage.mount("demos", "demos://", false).expect("Demos drive shouldn't fail to mount");
     storage.register_scheme(
         "file",
         Box::from(endbasic_std::storage::DirectoryDriveFactory::default()),
     );
-    storage.mount("local", local_drive_spec)?;
+    storage.register_scheme("git", Box::from(endbasic_std::storage::GitDriveFactory::default()));
+    storage.register_scheme("zip", Box::from(endbasic_std::storage::ZipDriveFactory::default()));
+    storage.mount("local", local_drive_spec, false)?;
+    storage.set_quota("local", local_drive_quota).expect("Local drive was just registered");
     storage.cd("local:").expect("Local drive was just registered");
     Ok(())
 }
@@ -241,10 +368,12 @@ pub fn setup_storage(storage: &mut Storage, local_drive_spec: &str) -> io::Resul
 /// Enters the interactive interpreter.
 ///
 /// `local_drive` is the optional local drive to mount and use as the default location.
+/// `local_drive_quota` is the optional maximum size of the local drive in bytes.
 /// `service_url` is the base URL of the cloud service.
 async fn run_repl_loop(
     console_spec: Option<&str>,
     local_drive_spec: &str,
+    local_drive_quota: Option<u64>,
     service_url: &str,
 ) -> endbasic_core::exec::Result<i32> {
     let mut builder = make_interactive(new_machine_builder(console_spec)?);
@@ -253,7 +382,7 @@ async fn run_repl_loop(
     let program = builder.get_program();
 
     let storage = builder.get_storage();
-    setup_storage(&mut storage.borrow_mut(), local_drive_spec)?;
+    setup_storage(&mut storage.borrow_mut(), local_drive_spec, local_drive_quota)?;
 
     let mut machine = finish_interactive_build(builder, service_url)?;
     endbasic_repl::print_welcome(console.clone())?;
@@ -261,19 +390,77 @@ async fn run_repl_loop(
     Ok(endbasic_repl::run_repl_loop(&mut machine, console, program).await?)
 }
 
-/// Executes the `path` program in a fresh machine.
-async fn run_script<P: AsRef<Path>>(
-    path: P,
+/// Computes the arguments to expose to the `path` program given the extra `args` to pass to it.
+fn program_args(path: &str, args: &[String]) -> Vec<String> {
+    let mut program_args = Vec::with_capacity(args.len() + 1);
+    program_args.push(path.to_owned());
+    program_args.extend_from_slice(args);
+    program_args
+}
+
+/// Validates the `paths` programs without running them and reports all problems to stderr.
+///
+/// Programs are checked against the commands available to scripts, or against those available to
+/// the interactive interpreter if `interactive` is true.  `service_url` is the base URL of the
+/// cloud service.
+///
+/// Returns 0 if all programs are valid and 1 otherwise.
+fn check_scripts(
+    paths: &[String],
+    console_spec: Option<&str>,
+    interactive: bool,
+    service_url: &str,
+) -> Result<i32> {
+    let builder = new_machine_builder(console_spec)?;
+    let machine = if interactive {
+        finish_interactive_build(make_interactive(builder), service_url)?
+    } else {
+        builder.build()?
+    };
+
+    let mut code = 0;
+    for path in paths {
+        let errors = match File::open(path) {
+            Ok(mut input) => machine.check(&mut input),
+            Err(e) => vec![e.into()],
+        };
+        for e in errors {
+            match e {
+                endbasic_core::exec::Error::IoError(e) => eprintln!("{}: {}", path, e),
+                e => eprintln!("{}:{}", path, i18n::translate(&e.to_string())),
+            }
+            code = 1;
+        }
+    }
+    Ok(code)
+}
+
+/// Prints the reformatted version of the `path` program to stdout without running it.
+fn format_script(path: &str) -> Result<i32> {
+    let text = fs::read_to_string(path)?;
+    let formatted =
+        endbasic_core::format::format_program(&text).map_err(|e| anyhow!("{}:{}", path, e))?;
+    print!("{}", formatted);
+    Ok(0)
+}
+
+/// Executes the `path` program in a fresh machine passing it `args`.
+async fn run_script(
+    path: &str,
+    args: &[String],
     console_spec: Option<&str>,
 ) -> endbasic_core::exec::Result<i32> {
-    let mut machine = new_machine_builder(console_spec)?.build()?;
+    let mut machine =
+        new_machine_builder(console_spec)?.with_args(program_args(path, args)).build()?;
     let mut input = File::open(path)?;
     Ok(machine.exec(&mut input).await?.as_exit_code())
 }
 
-/// Executes the `path` program in a fresh machine allowing any interactive-only calls.
+/// Executes the `path` program in a fresh machine passing it `args` and allowing any
+/// interactive-only calls.
 ///
 /// `local_drive` is the optional local drive to mount and use as the default location.
+/// `local_drive_quota` is the optional maximum size of the local drive in bytes.
 /// `service_url` is the base URL of the cloud service.
 ///
 /// If `path` starts with `cloud://`, this uses the same auto-run features that the web UI
@@ -281,17 +468,20 @@ async fn run_script<P: AsRef<Path>>(
 /// just in the web and helps test this feature.
 async fn run_interactive(
     path: &str,
+    args: &[String],
     console_spec: Option<&str>,
     local_drive_spec: &str,
+    local_drive_quota: Option<u64>,
     service_url: &str,
 ) -> endbasic_core::exec::Result<i32> {
-    let mut builder = make_interactive(new_machine_builder(console_spec)?);
+    let mut builder =
+        make_interactive(new_machine_builder(console_spec)?.with_args(program_args(path, args)));
 
     let console = builder.get_console();
     let program = builder.get_program();
 
     let storage = builder.get_storage();
-    setup_storage(&mut storage.borrow_mut(), local_drive_spec)?;
+    setup_storage(&mut storage.borrow_mut(), local_drive_spec, local_drive_quota)?;
 
     let mut machine = finish_interactive_build(builder, service_url)?;
 
@@ -319,10 +509,14 @@ async fn safe_main(name: &str, args: env::Args) -> Result<i32> {
     let args: Vec<String> = args.collect();
 
     let mut opts = Options::new();
+    opts.optflag("", "check", "validate the program-files without running them and exit");
     opts.optopt("", "console", "type and properties of the console to use", "CONSOLE-SPEC");
+    opts.optflag("", "format", "print the reformatted program-file and exit");
     opts.optflag("h", "help", "show command-line usage information and exit");
     opts.optflag("i", "interactive", "force interactive mode when running a script");
+    opts.optopt("", "lang", "language of the interpreter messages", "LOCALE");
     opts.optopt("", "local-drive", "location of the drive to mount as LOCAL", "URI");
+    opts.optopt("", "local-quota", "maximum size of the LOCAL drive in bytes", "BYTES");
     opts.optopt("", "service-url", "base URL of the cloud service", "URL");
     opts.optflag("", "version", "show version information and exit");
     let matches = opts.parse(args)?;
@@ -337,27 +531,57 @@ async fn safe_main(name: &str, args: env::Args) -> Result<i32> {
         return Ok(0);
     }
 
+    i18n::set_locale(get_locale(matches.opt_str("lang"))?);
+
     let console_spec = matches.opt_str("console");
 
     let service_url = matches
         .opt_str("service-url")
         .unwrap_or_else(|| endbasic_client::PROD_API_ADDRESS.to_owned());
 
+    if matches.opt_present("format") {
+        return match matches.free.as_slice() {
+            [file] => format_script(file),
+            _ => Err(anyhow!("--format requires exactly one program-file")),
+        };
+    }
+
+    if matches.opt_present("check") {
+        if matches.free.is_empty() {
+            return Err(anyhow!("--check requires at least one program-file"));
+        }
+        return check_scripts(
+            &matches.free,
+            console_spec.as_deref(),
+            matches.opt_present("interactive"),
+            &service_url,
+        );
+    }
+
     match matches.free.as_slice() {
         [] => {
             let local_drive = get_local_drive_spec(matches.opt_str("local-drive"))?;
-            Ok(run_repl_loop(console_spec.as_deref(), &local_drive, &service_url).await?)
+            let local_quota = get_local_drive_quota(matches.opt_str("local-quota"))?;
+            Ok(run_repl_loop(console_spec.as_deref(), &local_drive, local_quota, &service_url)
+                .await?)
         }
-        [file] => {
+        [file, args @ ..] => {
             if matches.opt_present("interactive") {
                 let local_drive = get_local_drive_spec(matches.opt_str("local-drive"))?;
-                Ok(run_interactive(file, console_spec.as_deref(), &local_drive, &service_url)
-                    .await?)
+                let local_quota = get_local_drive_quota(matches.opt_str("local-quota"))?;
+                Ok(run_interactive(
+                    file,
+                    args,
+                    console_spec.as_deref(),
+                    &local_drive,
+                    local_quota,
+                    &service_url,
+                )
+                .await?)
             } else {
-                Ok(run_script(file, console_spec.as_deref()).await?)
+                Ok(run_script(file, args, console_spec.as_deref()).await?)
             }
         }
-        [_, ..] => Err(UsageError::new("Too many arguments").into()),
     }
 }
 
@@ -367,16 +591,12 @@ async fn main() {
     let exit_code = match safe_main(&name, args).await {
         Ok(code) => code,
         Err(e) => {
-            if let Some(e) = e.downcast_ref::<UsageError>() {
-                eprintln!("Usage error: {}", e);
-                eprintln!("Type {} --help for more information", name);
-                2
-            } else if let Some(e) = e.downcast_ref::<getopts::Fail>() {
+            if let Some(e) = e.downcast_ref::<getopts::Fail>() {
                 eprintln!("Usage error: {}", e);
                 eprintln!("Type {} --help for more information", name);
                 2
             } else {
-                eprintln!("{}: {}", name, e);
+                eprintln!("{}: {}", name, i18n::translate(&e.to_string()));
                 1
             }
         }
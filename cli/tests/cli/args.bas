@@ -0,0 +1,23 @@
+' EndBASIC
+' Copyright 2022 Julio Merino
+'
+' Licensed under the Apache License, Version 2.0 (the "License"); you may not
+' use this file except in compliance with the License.  You may obtain a copy
+' of the License at:
+'
+'     http://www.apache.org/licenses/LICENSE-2.0
+'
+' Unless required by applicable law or agreed to in writing, software
+' distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+' WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+' License for the specific language governing permissions and limitations
+' under the License.
+
+' Tests that arguments given after the program name in the command line are
+' visible to the program.
+
+PRINT "Program name matches:"; RIGHT$(COMMAND$(0), 8) = "args.bas"
+PRINT "Argument count:"; COMMANDC%
+FOR i = 1 TO COMMANDC%
+    PRINT "Argument"; i; "= ["; COMMAND$(i); "]"
+NEXT
@@ -0,0 +1,22 @@
+' EndBASIC
+' Copyright 2022 Julio Merino
+'
+' Licensed under the Apache License, Version 2.0 (the "License"); you may not
+' use this file except in compliance with the License.  You may obtain a copy
+' of the License at:
+'
+'     http://www.apache.org/licenses/LICENSE-2.0
+'
+' Unless required by applicable law or agreed to in writing, software
+' distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+' WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+' License for the specific language governing permissions and limitations
+' under the License.
+
+' Tests that the exit code given to EXIT or END becomes the exit code of the
+' interpreter.  The first argument selects the statement to use.
+
+PRINT "Exiting"
+IF COMMAND$(1) = "end" THEN END 12
+EXIT 7
+PRINT "Not reached"
@@ -0,0 +1,28 @@
+' EndBASIC
+' Copyright 2022 Julio Merino
+'
+' Licensed under the Apache License, Version 2.0 (the "License"); you may not
+' use this file except in compliance with the License.  You may obtain a copy
+' of the License at:
+'
+'     http://www.apache.org/licenses/LICENSE-2.0
+'
+' Unless required by applicable law or agreed to in writing, software
+' distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+' WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+' License for the specific language governing permissions and limitations
+' under the License.
+
+' Messy program used to validate the output of the --format flag.
+
+dim counter as integer
+for i=1 to 10 step 2
+if i mod 3=0 then print "fizz";i else counter=counter+1
+next
+
+
+select case counter  ' Trailing comment.
+case is >= 3 : print "many"
+case else
+' Nothing to do.
+end select
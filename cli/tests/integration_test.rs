@@ -20,168 +20,9 @@
 #![warn(unused, unused_extern_crates, unused_import_braces, unused_qualifications)]
 #![warn(unsafe_code)]
 
+use endbasic_testutils::golden::*;
 use std::env;
-use std::fs::{self, File};
-use std::io::Read;
-use std::path::{Path, PathBuf};
-use std::process;
-
-/// Matches a formatted date.
-const DATE_RE: &str = "[0-9]{4}-[0-9]{2}-[0-9]{2} [0-2][0-9]:[0-5][0-9]";
-
-/// Matches a `file://` URI.
-const FILE_URI_RE: &str = "file://[^ \n\"]+";
-
-/// Matches a version number.
-const VERSION_RE: &str = "[0-9]+\\.[0-9]+\\.[0-9]+";
-
-/// Matches a year range.
-const YEAR_RANGE_RE: &str = "[0-9]{4}-[0-9]{4}";
-
-/// Computes the path to the directory where this test's binary lives.
-fn self_dir() -> PathBuf {
-    let self_exe = env::current_exe().expect("Cannot get self's executable path");
-    let dir = self_exe.parent().expect("Cannot get self's directory");
-    assert!(dir.ends_with("target/debug/deps") || dir.ends_with("target/release/deps"));
-    dir.to_owned()
-}
-
-/// Computes the path to the built binary `name`.
-fn bin_path<P: AsRef<Path>>(name: P) -> PathBuf {
-    let test_dir = self_dir();
-    let debug_or_release_dir = test_dir.parent().expect("Failed to get parent directory");
-    debug_or_release_dir.join(name).with_extension(env::consts::EXE_EXTENSION)
-}
-
-/// Computes the path to the source file `name`.
-fn src_path(name: &str) -> PathBuf {
-    let test_dir = self_dir();
-    let debug_or_release_dir = test_dir.parent().expect("Failed to get parent directory");
-    let target_dir = debug_or_release_dir.parent().expect("Failed to get parent directory");
-    let dir = target_dir.parent().expect("Failed to get parent directory");
-
-    // Sanity-check that we landed in the right location.
-    assert!(dir.join("Cargo.toml").exists());
-
-    dir.join(name)
-}
-
-/// Same as `src_path` but returns a string reference for the few places where we need this.
-fn src_str(p: &str) -> String {
-    src_path(p).to_str().expect("Need paths to be valid strings").to_owned()
-}
-
-/// Describes the behavior for one of the three streams (stdin, stdout, stderr) connected to a
-/// program.
-enum Behavior {
-    /// Ensure the stream is silent.
-    Null,
-
-    /// If stdin, feed the given path as the program's input.  If stdout/stderr, expect the contents
-    /// of the stream to match this file.
-    File(PathBuf),
-
-    /// If stdin, this is not supported.  If stdout/stderr, expect the contents of the stream to
-    /// match this literal string.
-    Literal(String),
-}
-
-/// Reads the contents of a golden data file.
-fn read_golden(path: &Path) -> String {
-    let mut f = File::open(path).expect("Failed to open golden data file");
-    let mut golden = vec![];
-    f.read_to_end(&mut golden).expect("Failed to read golden data file");
-    let raw = String::from_utf8(golden).expect("Golden data file is not valid UTF-8");
-    let golden = if cfg!(target_os = "windows") { raw.replace("\r\n", "\n") } else { raw };
-
-    // This is the opposite of apply_mocks and ensures we don't leak actual values into the golden
-    // files by mistake.
-    let version_re = regex::Regex::new(VERSION_RE).unwrap();
-    assert!(
-        !version_re.is_match(&golden),
-        "Golden file {} contains a version number",
-        path.display()
-    );
-    let date_re = regex::Regex::new(DATE_RE).unwrap();
-    assert!(!date_re.is_match(&golden), "Golden file {} contains a date", path.display());
-    let year_range_re = regex::Regex::new(YEAR_RANGE_RE).unwrap();
-    assert!(
-        !year_range_re.is_match(&golden),
-        "Golden file {} contains a year range",
-        path.display()
-    );
-
-    golden
-}
-
-/// Replaces the parts of the output that can change due to the environment with placeholders.
-fn apply_mocks(input: String) -> String {
-    let version_re = regex::Regex::new(VERSION_RE).unwrap();
-    let input = version_re.replace_all(&input, "X.Y.Z").into_owned();
-
-    let date_re = regex::Regex::new(DATE_RE).unwrap();
-    let input = date_re.replace_all(&input, "YYYY-MM-DD HH:MM").into_owned();
-
-    let year_range_re = regex::Regex::new(YEAR_RANGE_RE).unwrap();
-    let input = year_range_re.replace_all(&input, "YYYY-YYYY").into_owned();
-
-    let file_uri_re = regex::Regex::new(FILE_URI_RE).unwrap();
-    file_uri_re.replace_all(&input, "file:///PATH/TO/TMPDIR").into()
-}
-
-/// Runs `bin` with arguments `args` and checks its behavior against expectations.
-///
-/// `exp_code` is the expected error code from the program.  `stdin_behavior` indicates what to feed
-/// to the program's stdin.  `stdout_behavior` and `stderr_behavior` indicate what to expect from
-/// the program's textual output.
-fn check<P: AsRef<Path>>(
-    bin: P,
-    args: &[&str],
-    exp_code: i32,
-    stdin_behavior: Behavior,
-    stdout_behavior: Behavior,
-    stderr_behavior: Behavior,
-) {
-    let golden_stdin = match stdin_behavior {
-        Behavior::Null => process::Stdio::null(),
-        Behavior::File(path) => File::open(path).unwrap().into(),
-        Behavior::Literal(_) => panic!("Literals not supported for stdin"),
-    };
-
-    let exp_stdout = match stdout_behavior {
-        Behavior::Null => "".to_owned(),
-        Behavior::File(path) => read_golden(&path),
-        Behavior::Literal(text) => text,
-    };
-
-    let exp_stderr = match stderr_behavior {
-        Behavior::Null => "".to_owned(),
-        Behavior::File(path) => read_golden(&path),
-        Behavior::Literal(text) => text,
-    };
-
-    let result = process::Command::new(bin.as_ref())
-        .args(args)
-        .stdin(golden_stdin)
-        .env("LINES", "24")
-        .env("COLUMNS", "80")
-        .output()
-        .expect("Failed to execute subprocess");
-    let code = result.status.code().expect("Subprocess didn't exit cleanly");
-    let stdout =
-        apply_mocks(String::from_utf8(result.stdout).expect("Stdout not is not valid UTF-8"));
-    let stderr =
-        apply_mocks(String::from_utf8(result.stderr).expect("Stderr not is not valid UTF-8"));
-
-    if exp_code != code || exp_stdout != stdout || exp_stderr != stderr {
-        eprintln!("Exit code: {}", code);
-        eprintln!("stdout:\n{}", stdout);
-        eprintln!("stderr:\n{}", stderr);
-        assert_eq!(exp_code, code);
-        assert_eq!(exp_stdout, stdout);
-        assert_eq!(exp_stderr, stderr);
-    }
-}
+use std::fs;
 
 #[test]
 fn test_cli_autoexec_is_ignored() {
@@ -200,6 +41,117 @@ fn test_cli_autoexec_is_ignored() {
     );
 }
 
+#[test]
+fn test_cli_check() {
+    check(
+        bin_path("endbasic"),
+        &["--check", &src_str("cli/tests/cli/args.bas"), &src_str("cli/tests/cli/exit.bas")],
+        0,
+        Behavior::Null,
+        Behavior::Null,
+        Behavior::Null,
+    );
+
+    check(
+        bin_path("endbasic"),
+        &[
+            "--check",
+            &src_str("cli/tests/cli/check.bas"),
+            &src_str("cli/tests/lang/parser-error.bas"),
+        ],
+        1,
+        Behavior::Null,
+        Behavior::Null,
+        Behavior::Literal(format!(
+            "{0}:19:1: Unknown builtin FOO\n{0}:20:14: Unknown builtin LOAD\n\
+             {1}:17:4: No expression in IF statement\n",
+            src_str("cli/tests/cli/check.bas"),
+            src_str("cli/tests/lang/parser-error.bas")
+        )),
+    );
+
+    check(
+        bin_path("endbasic"),
+        &["--check", "--interactive", &src_str("cli/tests/cli/check.bas")],
+        1,
+        Behavior::Null,
+        Behavior::Null,
+        Behavior::Literal(format!(
+            "{}:19:1: Unknown builtin FOO\n",
+            src_str("cli/tests/cli/check.bas")
+        )),
+    );
+}
+
+#[test]
+fn test_cli_exit_code() {
+    check(
+        bin_path("endbasic"),
+        &[&src_str("cli/tests/cli/exit.bas"), "exit"],
+        7,
+        Behavior::Null,
+        Behavior::Literal("Exiting\n".to_owned()),
+        Behavior::Null,
+    );
+
+    check(
+        bin_path("endbasic"),
+        &[&src_str("cli/tests/cli/exit.bas"), "end"],
+        12,
+        Behavior::Null,
+        Behavior::Literal("Exiting\n".to_owned()),
+        Behavior::Null,
+    );
+}
+
+#[test]
+fn test_cli_format() {
+    check(
+        bin_path("endbasic"),
+        &["--format", &src_str("cli/tests/cli/format.bas")],
+        0,
+        Behavior::Null,
+        Behavior::File(src_path("cli/tests/cli/format.out")),
+        Behavior::Null,
+    );
+
+    check(
+        bin_path("endbasic"),
+        &["--format", &src_str("cli/tests/lang/parser-error.bas")],
+        1,
+        Behavior::Null,
+        Behavior::Null,
+        Behavior::Literal(format!(
+            "endbasic: {}:17:4: No expression in IF statement\n",
+            src_str("cli/tests/lang/parser-error.bas")
+        )),
+    );
+}
+
+#[test]
+fn test_cli_headless_frames() {
+    let dir = tempfile::tempdir().unwrap();
+    check(
+        bin_path("endbasic"),
+        &[
+            &format!("--console=headless:16x32,{}", dir.path().to_str().unwrap()),
+            &src_str("cli/tests/cli/headless.bas"),
+        ],
+        0,
+        Behavior::Null,
+        Behavior::Literal("Two frames saved\n".to_owned()),
+        Behavior::Null,
+    );
+
+    let header = b"P6\n16 32\n255\n";
+    for name in &["frame-000000.ppm", "frame-000001.ppm"] {
+        let frame = fs::read(dir.path().join(name)).unwrap();
+        assert_eq!(header, &frame[0..header.len()]);
+        assert_eq!(header.len() + 16 * 32 * 3, frame.len());
+    }
+    assert!(!dir.path().join("frame-000002.ppm").exists());
+}
+
 #[test]
 fn test_cli_help() {
     fn check_with_args(args: &[&str]) {
@@ -246,6 +198,20 @@ fn test_cli_interactive() {
     );
 }
 
+#[test]
+fn test_cli_local_quota_invalid() {
+    check(
+        bin_path("endbasic"),
+        &["--local-drive=memory://", "--local-quota=abc"],
+        1,
+        Behavior::Null,
+        Behavior::Null,
+        Behavior::Literal(
+            "endbasic: Invalid --local-quota value 'abc': must be a number of bytes\n".to_owned(),
+        ),
+    );
+}
+
 #[test]
 #[ignore = "Requires environment configuration and is expensive"]
 fn test_cli_run_from_cloud() {
@@ -268,7 +234,7 @@ fn test_cli_run_from_cloud() {
 #[test]
 fn test_cli_program_name_uses_arg0() {
     struct DeleteOnDrop<'a> {
-        path: &'a Path,
+        path: &'a std::path::Path,
     }
 
     impl<'a> Drop for DeleteOnDrop<'a> {
@@ -283,29 +249,47 @@ fn test_cli_program_name_uses_arg0() {
     fs::copy(&original, &custom).unwrap();
     check(
         &custom,
-        &["one", "two", "three"],
+        &["-Z"],
         2,
         Behavior::Null,
         Behavior::Null,
         Behavior::Literal(
-            "Usage error: Too many arguments\nType custom-name --help for more information\n"
+            "Usage error: Unrecognized option: 'Z'\nType custom-name --help for more information\n"
                 .to_owned(),
         ),
     );
 }
 
 #[test]
-fn test_cli_too_many_args() {
+fn test_cli_program_args() {
     check(
         bin_path("endbasic"),
-        &["foo", "bar"],
-        2,
+        &[&src_str("cli/tests/cli/args.bas")],
+        0,
+        Behavior::Null,
+        Behavior::Literal("Program name matches:TRUE\nArgument count: 0\n".to_owned()),
+        Behavior::Null,
+    );
+
+    check(
+        bin_path("endbasic"),
+        &[&src_str("cli/tests/cli/args.bas"), "foo", "", "bar baz"],
+        0,
+        Behavior::Null,
+        Behavior::File(src_path("cli/tests/cli/args.out")),
         Behavior::Null,
+    );
+
+    check(
+        bin_path("endbasic"),
+        &["--", &src_str("cli/tests/cli/args.bas"), "-i", "--help"],
+        0,
         Behavior::Null,
         Behavior::Literal(
-            "Usage error: Too many arguments\nType endbasic --help for more information\n"
+            "Program name matches:TRUE\nArgument count: 2\nArgument 1 = [-i]\nArgument 2 = [--help]\n"
                 .to_owned(),
         ),
+        Behavior::Null,
     );
 }
 
@@ -436,6 +420,18 @@ fn test_lang_exec_error() {
     );
 }
 
+#[test]
+fn test_lang_exec_error_spanish() {
+    check(
+        bin_path("endbasic"),
+        &["--lang=es", "--local-drive=memory://", &src_str("cli/tests/lang/exec-error.bas")],
+        1,
+        Behavior::Null,
+        Behavior::File(src_path("cli/tests/lang/exec-error.out")),
+        Behavior::File(src_path("cli/tests/lang/exec-error-es.err")),
+    );
+}
+
 #[test]
 fn test_lang_hello() {
     check(
@@ -33,22 +33,31 @@ LOOP
 ' Help topics.
 DATA "ARRAY"
 DATA "CLOUD"
+DATA "COLLECTIONS"
 DATA "CONSOLE"
 DATA "DATA"
+DATA "DATE AND TIME"
+DATA "DEBUGGING"
 DATA "FILE SYSTEM"
 DATA "GRAPHICS"
 DATA "HARDWARE"
 DATA "INTERPRETER"
 DATA "LANG"
+DATA "NETWORKING"
 DATA "NUMERICAL"
+DATA "PROCESS"
+DATA "REGULAR"
+DATA "SERIAL COMMUNICATION"
+DATA "SOCKETS"
 DATA "STORED"
-DATA "STRING"
+DATA "STRING AND"
 
 ' Language reference.
 DATA "DO"
 DATA "EXPRESSIONS"
 DATA "FOR"
 DATA "IF"
+DATA "INCLUDE"
 DATA "JUMPS"
 DATA "ON ERROR"
 DATA "SELECT CASE"
@@ -58,11 +67,20 @@ DATA "VARIABLES"
 DATA "WHILE"
 
 ' Commands.
+DATA "BREAK"
 DATA "CD"
+DATA "CHAIN"
 DATA "CLEAR"
+DATA "CLIPBOARD_SET"
 DATA "CLS"
 DATA "COLOR"
+DATA "COMMON"
+DATA "CONT"
+DATA "CSV_READ"
+DATA "CSV_WRITE"
 DATA "DEG"
+DATA "DICT_REMOVE"
+DATA "DICT_SET"
 DATA "DIR"
 DATA "EDIT"
 DATA "GFX_CIRCLE"
@@ -73,59 +91,137 @@ DATA "GFX_RECT"
 DATA "GFX_RECTF"
 DATA "GFX_SYNC"
 DATA "GPIO_CLEAR"
+DATA "GPIO_PWM"
 DATA "GPIO_SETUP"
 DATA "GPIO_WRITE"
 DATA "HELP"
+DATA "I2C_OPEN"
+DATA "I2C_READ"
+DATA "I2C_WRITE"
+DATA "INDENT"
 DATA "INPUT"
 DATA "KILL"
 DATA "LIST"
+DATA "LIST_APPEND"
+DATA "LIST_INSERT"
+DATA "LIST_REMOVE"
+DATA "LIST_SET"
 DATA "LOAD"
+DATA "LOADSTATE"
 DATA "LOCATE"
 DATA "LOGIN"
 DATA "LOGOUT"
 DATA "MOUNT"
 DATA "NEW"
+DATA "PLUGINS"
 DATA "PRINT"
+DATA "PROFILE"
 DATA "PWD"
 DATA "RAD"
 DATA "RANDOMIZE"
 DATA "READ"
 DATA "RESTORE"
+DATA "REVERSE"
 DATA "RUN"
 DATA "SAVE"
+DATA "SAVESTATE"
+DATA "SCREEN"
+DATA "SERIAL_OPEN"
+DATA "SERIAL_WRITE"
+DATA "SETENV"
 DATA "SHARE"
+DATA "SHUFFLE"
 DATA "SIGNUP"
 DATA "SLEEP"
+DATA "SORT"
+DATA "SOCKET_CLOSE"
+DATA "SOCKET_SEND"
+DATA "SPI_OPEN"
+DATA "SPI_TRANSFER"
+DATA "SPI_WRITE"
+DATA "SPLIT"
+DATA "STEP"
+DATA "TEXT_STYLE"
+DATA "TROFF"
+DATA "TRON"
+DATA "UNBREAK"
 DATA "UNMOUNT"
+DATA "VARS"
+DATA "WINDOW"
 
 ' Functions.
 DATA "ASC"
+DATA "ATAN2"
 DATA "ATN"
+DATA "AVG"
+DATA "CEIL"
 DATA "CHR"
 DATA "CINT"
+DATA "CLAMP"
+DATA "CLIPBOARD_GET"
+DATA "COMMAND$"
+DATA "COMMANDC"
 DATA "COS"
+DATA "CSRLIN"
+DATA "DICT%"
+DATA "DICT_GET"
+DATA "DICT_HAS"
+DATA "DICT_KEY"
+DATA "DICT_LEN"
 DATA "ERRMSG"
+DATA "FIND"
+DATA "FLOOR"
+DATA "FORMAT$"
+DATA "GETENV"
 DATA "GFX_HEIGHT"
 DATA "GFX_WIDTH"
 DATA "GPIO_READ"
+DATA "HTTP_GET"
+DATA "HTTP_HEADER"
+DATA "HTTP_POST"
+DATA "HTTP_STATUS"
 DATA "INKEY"
+DATA "INSTR"
 DATA "INT%"
+DATA "JOIN"
+DATA "JSON_FORMAT"
+DATA "JSON_PARSE"
 DATA "LBOUND"
 DATA "LEFT"
 DATA "LEN"
+DATA "LIST_GET"
+DATA "LIST_LEN"
+DATA "LIST_NEW"
+DATA "LOG#"
 DATA "LTRIM"
 DATA "MAX"
 DATA "MID"
 DATA "MIN"
 DATA "PI"
+DATA "POS"
+DATA "REGEXP_FIND"
+DATA "REGEXP_MATCH"
+DATA "REGEXP_REPLACE"
+DATA "REPLACE"
 DATA "RIGHT"
-DATA "RND"
+DATA "RND#"
+DATA "RNDINT"
+DATA "ROUND"
 DATA "RTRIM"
 DATA "SCRCOLS"
 DATA "SCRROWS"
+DATA "SERIAL_READ$"
 DATA "SIN"
+DATA "SOCKET_ACCEPT"
+DATA "SOCKET_CONNECT"
+DATA "SOCKET_LISTEN"
+DATA "SOCKET_PORT"
+DATA "SOCKET_RECV"
+DATA "SPACE"
 DATA "SQR"
 DATA "STR$"
+DATA "STRING$"
+DATA "SUM"
 DATA "TAN"
 DATA "UBOUND"
 
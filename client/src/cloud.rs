@@ -23,14 +23,30 @@ use reqwest::header::HeaderMap;
 use reqwest::Response;
 use reqwest::StatusCode;
 use std::cell::RefCell;
+use std::future::Future;
 use std::io;
+use std::pin::Pin;
 use std::rc::Rc;
 use std::str;
+use std::time::Duration;
 use url::Url;
 
+/// Formats the optional `request_id` returned by the server as a suffix for error messages.
+fn format_request_id(request_id: Option<&str>) -> String {
+    match request_id {
+        Some(request_id) => format!(" (request id: {})", request_id),
+        None => "".to_owned(),
+    }
+}
+
 /// Converts a `reqwest::Response` to an `io::Error`.  The response should have a non-OK status.
 async fn http_response_to_io_error(response: Response) -> io::Error {
     let status = response.status();
+    let request_id = response
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| remove_control_chars(value.to_owned()));
 
     let kind = match status {
         StatusCode::OK => panic!("Should not have been called on a successful request"),
@@ -48,35 +64,126 @@ async fn http_response_to_io_error(response: Response) -> io::Error {
         _ => io::ErrorKind::Other,
     };
 
-    match response.text().await {
+    let message = match response.text().await {
         Ok(text) => match serde_json::from_str::<ErrorResponse>(&text) {
-            Ok(response) => io::Error::new(
-                kind,
-                format!("{} (server code: {})", remove_control_chars(response.message), status),
-            ),
-            _ => io::Error::new(
-                kind,
-                format!(
-                    "HTTP request returned status {} with text '{}'",
+            Ok(response) => match request_id {
+                Some(request_id) => format!(
+                    "{} (server code: {}; request id: {})",
+                    remove_control_chars(response.message),
                     status,
-                    remove_control_chars(text)
+                    request_id
                 ),
-            ),
-        },
-        Err(e) => io::Error::new(
-            kind,
-            format!(
-                "HTTP request returned status {} and failed to get text due to {}",
+                None => {
+                    format!("{} (server code: {})", remove_control_chars(response.message), status)
+                }
+            },
+            _ => format!(
+                "HTTP request returned status {} with text '{}'{}",
                 status,
-                remove_control_chars(e.to_string())
+                remove_control_chars(text),
+                format_request_id(request_id.as_deref())
             ),
+        },
+        Err(e) => format!(
+            "HTTP request returned status {} and failed to get text due to {}{}",
+            status,
+            remove_control_chars(e.to_string()),
+            format_request_id(request_id.as_deref())
         ),
+    };
+
+    if status == StatusCode::UNAUTHORIZED {
+        // Tag these errors so that callers can tell an expired session apart from a lack of
+        // permissions and refresh the access token.
+        io::Error::new(kind, UnauthorizedError(message))
+    } else {
+        io::Error::new(kind, message)
     }
 }
 
 /// Converts a `reqwest::Error` to an `io::Error`.
+///
+/// Failures to reach the server are reported with distinct error kinds so that the cloud drive can
+/// tell them apart and keep working offline.
 fn reqwest_error_to_io_error(e: reqwest::Error) -> io::Error {
-    io::Error::new(io::ErrorKind::Other, format!("{}", e))
+    let kind = if e.is_timeout() {
+        io::ErrorKind::TimedOut
+    } else if e.is_request() {
+        io::ErrorKind::NotConnected
+    } else {
+        io::ErrorKind::Other
+    };
+    io::Error::new(kind, format!("{}", e))
+}
+
+/// Returns true if a response with `status` indicates a transient server problem that may go away
+/// by retrying the same request.
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Returns true if `e` indicates a transient failure to talk to the server that may go away by
+/// retrying the same request.
+fn is_transient_error(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect() || e.is_request()
+}
+
+/// Configuration of how to retry requests that fail due to transient problems.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of times to issue a request, including the first attempt.
+    pub max_attempts: u32,
+
+    /// Time to wait before the first retry.  Every subsequent retry doubles this delay.
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the time to wait between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(4),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns a policy that never retries failed requests.
+    pub fn no_retries() -> Self {
+        Self { max_attempts: 1, ..Default::default() }
+    }
+
+    /// Computes how long to wait before issuing the request again after `failed` attempts.
+    fn backoff(&self, failed: u32) -> Duration {
+        debug_assert!(failed > 0);
+        let factor = 1u32.checked_shl(failed - 1).unwrap_or(u32::MAX);
+        self.initial_backoff.checked_mul(factor).unwrap_or(self.max_backoff).min(self.max_backoff)
+    }
+}
+
+/// Type of the function used to wait between retries of a failed request.
+pub type SleepFn = Rc<dyn Fn(Duration) -> Pin<Box<dyn Future<Output = ()>>>>;
+
+/// An implementation of a `SleepFn` that stops the current thread.
+fn system_sleep(d: Duration) -> Pin<Box<dyn Future<Output = ()>>> {
+    Box::pin(async move {
+        // Blocking the thread is not possible in a web browser, so the web frontend is expected
+        // to install its own function.  Retry right away if it doesn't.
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::sleep(d);
+        #[cfg(target_arch = "wasm32")]
+        let _ = d;
+    })
 }
 
 /// Container for authentication data to track after login.
@@ -91,11 +198,40 @@ pub struct CloudService {
     api_address: Url,
     client: reqwest::Client,
     auth_data: Rc<RefCell<Option<AuthData>>>,
+    retry_policy: RetryPolicy,
+    sleep_fn: SleepFn,
 }
 
 impl CloudService {
     /// Creates a new client for the cloud service that talks to `api_address`.
     pub fn new(api_address: &str) -> io::Result<Self> {
+        let url = Self::parse_api_address(api_address)?;
+
+        let auth_data = Rc::from(RefCell::from(None));
+
+        Ok(Self {
+            api_address: url,
+            client: reqwest::Client::default(),
+            auth_data,
+            retry_policy: RetryPolicy::default(),
+            sleep_fn: Rc::from(system_sleep),
+        })
+    }
+
+    /// Overrides the policy used to retry requests that fail due to transient problems.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Overrides the function used to wait between retries of a failed request.
+    pub fn with_sleep_fn(mut self, sleep_fn: SleepFn) -> Self {
+        self.sleep_fn = sleep_fn;
+        self
+    }
+
+    /// Parses and validates the base `api_address` of the service.
+    fn parse_api_address(api_address: &str) -> io::Result<Url> {
         let url = match Url::parse(api_address) {
             Ok(url) => url,
             Err(e) => {
@@ -113,9 +249,7 @@ impl CloudService {
             ));
         }
 
-        let auth_data = Rc::from(RefCell::from(None));
-
-        Ok(Self { api_address: url, client: reqwest::Client::default(), auth_data })
+        Ok(url)
     }
 
     /// Generates a service URL with the given `path`.
@@ -139,6 +273,42 @@ impl CloudService {
         headers
     }
 
+    /// Issues the request described by `builder` and returns its response, retrying the request
+    /// according to the configured policy if it fails due to transient problems.
+    ///
+    /// Responses with a non-OK status are returned as is for the caller to handle unless they are
+    /// deemed transient and there are retries left.
+    async fn send(&self, builder: reqwest::RequestBuilder) -> io::Result<Response> {
+        let mut failed = 0;
+        loop {
+            let attempt = match builder.try_clone() {
+                Some(attempt) => attempt,
+                None => {
+                    // Streaming bodies cannot be replayed so we only get one chance at them.
+                    return builder.send().await.map_err(reqwest_error_to_io_error);
+                }
+            };
+            failed += 1;
+            let retries_left = failed < self.retry_policy.max_attempts;
+
+            match attempt.send().await {
+                Ok(response) if retries_left && is_transient_status(response.status()) => (),
+                Ok(response) => return Ok(response),
+                Err(e) if retries_left && is_transient_error(&e) => (),
+                Err(e) if failed > 1 => {
+                    let e = reqwest_error_to_io_error(e);
+                    return Err(io::Error::new(
+                        e.kind(),
+                        format!("{} (gave up after {} attempts)", e, failed),
+                    ));
+                }
+                Err(e) => return Err(reqwest_error_to_io_error(e)),
+            }
+
+            (self.sleep_fn)(self.retry_policy.backoff(failed)).await;
+        }
+    }
+
     /// Checks if the given auth data object is present and returns it, or else returns a permission
     /// denied error.
     fn require_auth_data(data: Option<&AuthData>) -> io::Result<&AuthData> {
@@ -154,14 +324,12 @@ impl CloudService {
 #[async_trait(?Send)]
 impl Service for CloudService {
     async fn signup(&mut self, request: &SignupRequest) -> io::Result<()> {
-        let response = self
+        let builder = self
             .client
             .post(self.make_url("api/signup"))
             .headers(self.default_headers())
-            .body(serde_json::to_vec(&request)?)
-            .send()
-            .await
-            .map_err(reqwest_error_to_io_error)?;
+            .body(serde_json::to_vec(&request)?);
+        let response = self.send(builder).await?;
         match response.status() {
             reqwest::StatusCode::OK => Ok(()),
             _ => Err(http_response_to_io_error(response).await),
@@ -173,15 +341,13 @@ impl Service for CloudService {
         // call on the RequestBuilder once it is supported in WASM.
         let basic_auth = format!("Basic {}", base64::encode(format!("{}:{}", username, password)));
 
-        let response = self
+        let builder = self
             .client
             .post(self.make_url("api/login"))
             .headers(self.default_headers())
             .header("Authorization", basic_auth)
-            .header("Content-Length", 0)
-            .send()
-            .await
-            .map_err(reqwest_error_to_io_error)?;
+            .header("Content-Length", 0);
+        let response = self.send(builder).await?;
         match response.status() {
             reqwest::StatusCode::OK => {
                 let bytes = response.bytes().await.map_err(reqwest_error_to_io_error)?;
@@ -201,14 +367,13 @@ impl Service for CloudService {
         let mut auth_data = self.auth_data.borrow_mut();
         let response = {
             let auth_data = Self::require_auth_data(auth_data.as_ref())?;
-            self.client
+            let builder = self
+                .client
                 .post(self.make_url(&format!("api/users/{}/logout", auth_data.username)))
                 .headers(self.default_headers())
                 .header("Content-Length", 0)
-                .bearer_auth(auth_data.access_token.as_str())
-                .send()
-                .await
-                .map_err(reqwest_error_to_io_error)?
+                .bearer_auth(auth_data.access_token.as_str());
+            self.send(builder).await?
         };
         match response.status() {
             reqwest::StatusCode::OK => {
@@ -219,6 +384,89 @@ impl Service for CloudService {
         }
     }
 
+    async fn refresh_token(&mut self) -> io::Result<()> {
+        let mut auth_data = self.auth_data.borrow_mut();
+        let response = {
+            let auth_data = Self::require_auth_data(auth_data.as_ref())?;
+            let builder = self
+                .client
+                .post(self.make_url(&format!("api/users/{}/refresh", auth_data.username)))
+                .headers(self.default_headers())
+                .header("Content-Length", 0)
+                .bearer_auth(auth_data.access_token.as_str());
+            self.send(builder).await?
+        };
+        match response.status() {
+            reqwest::StatusCode::OK => {
+                let bytes = response.bytes().await.map_err(reqwest_error_to_io_error)?;
+                let response: RefreshTokenResponse = serde_json::from_reader(bytes.reader())?;
+                auth_data.as_mut().expect("Auth data checked above").access_token =
+                    response.access_token;
+                Ok(())
+            }
+            _ => Err(http_response_to_io_error(response).await),
+        }
+    }
+
+    async fn change_password(
+        &mut self,
+        username: &str,
+        request: &ChangePasswordRequest,
+    ) -> io::Result<()> {
+        let auth_data = self.auth_data.borrow();
+
+        let builder = self
+            .client
+            .post(self.make_url(&format!("api/users/{}/password", username)))
+            .headers(self.default_headers())
+            .body(serde_json::to_vec(&request)?)
+            .bearer_auth(Self::require_auth_data(auth_data.as_ref())?.access_token.as_str());
+        let response = self.send(builder).await?;
+        match response.status() {
+            reqwest::StatusCode::OK => Ok(()),
+            _ => Err(http_response_to_io_error(response).await),
+        }
+    }
+
+    async fn get_account(&mut self, username: &str) -> io::Result<GetAccountResponse> {
+        let auth_data = self.auth_data.borrow();
+
+        let builder = self
+            .client
+            .get(self.make_url(&format!("api/users/{}", username)))
+            .headers(self.default_headers())
+            .bearer_auth(Self::require_auth_data(auth_data.as_ref())?.access_token.as_str());
+        let response = self.send(builder).await?;
+        match response.status() {
+            reqwest::StatusCode::OK => {
+                let bytes = response.bytes().await.map_err(reqwest_error_to_io_error)?;
+                let response: GetAccountResponse = serde_json::from_reader(bytes.reader())?;
+                Ok(response)
+            }
+            _ => Err(http_response_to_io_error(response).await),
+        }
+    }
+
+    async fn patch_account(
+        &mut self,
+        username: &str,
+        request: &PatchAccountRequest,
+    ) -> io::Result<()> {
+        let auth_data = self.auth_data.borrow();
+
+        let builder = self
+            .client
+            .patch(self.make_url(&format!("api/users/{}", username)))
+            .headers(self.default_headers())
+            .body(serde_json::to_vec(&request)?)
+            .bearer_auth(Self::require_auth_data(auth_data.as_ref())?.access_token.as_str());
+        let response = self.send(builder).await?;
+        match response.status() {
+            reqwest::StatusCode::OK => Ok(()),
+            _ => Err(http_response_to_io_error(response).await),
+        }
+    }
+
     fn is_logged_in(&self) -> bool {
         self.auth_data.borrow().is_some()
     }
@@ -227,6 +475,15 @@ impl Service for CloudService {
         self.auth_data.borrow().as_ref().map(|x| x.username.to_owned())
     }
 
+    fn service_url(&self) -> String {
+        self.api_address.to_string()
+    }
+
+    fn set_service_url(&mut self, url: &str) -> io::Result<()> {
+        self.api_address = Self::parse_api_address(url)?;
+        Ok(())
+    }
+
     async fn get_files(&mut self, username: &str) -> io::Result<GetFilesResponse> {
         let mut builder = self
             .client
@@ -235,7 +492,7 @@ impl Service for CloudService {
         if let Some(auth_data) = self.auth_data.borrow().as_ref() {
             builder = builder.bearer_auth(auth_data.access_token.as_str());
         }
-        let response = builder.send().await.map_err(reqwest_error_to_io_error)?;
+        let response = self.send(builder).await?;
         match response.status() {
             reqwest::StatusCode::OK => {
                 let bytes = response.bytes().await.map_err(reqwest_error_to_io_error)?;
@@ -246,6 +503,23 @@ impl Service for CloudService {
         }
     }
 
+    async fn get_public_files(&mut self) -> io::Result<GetPublicFilesResponse> {
+        let mut builder =
+            self.client.get(self.make_url("api/public/files")).headers(self.default_headers());
+        if let Some(auth_data) = self.auth_data.borrow().as_ref() {
+            builder = builder.bearer_auth(auth_data.access_token.as_str());
+        }
+        let response = self.send(builder).await?;
+        match response.status() {
+            reqwest::StatusCode::OK => {
+                let bytes = response.bytes().await.map_err(reqwest_error_to_io_error)?;
+                let response: GetPublicFilesResponse = serde_json::from_reader(bytes.reader())?;
+                Ok(response)
+            }
+            _ => Err(http_response_to_io_error(response).await),
+        }
+    }
+
     async fn get_file(
         &mut self,
         username: &str,
@@ -260,7 +534,7 @@ impl Service for CloudService {
         if let Some(auth_data) = self.auth_data.borrow().as_ref() {
             builder = builder.bearer_auth(auth_data.access_token.as_str());
         }
-        let response = builder.send().await.map_err(reqwest_error_to_io_error)?;
+        let response = self.send(builder).await?;
         match response.status() {
             reqwest::StatusCode::OK => {
                 let bytes = response.bytes().await.map_err(reqwest_error_to_io_error)?;
@@ -279,33 +553,53 @@ impl Service for CloudService {
     ) -> io::Result<()> {
         let auth_data = self.auth_data.borrow();
 
-        let response = self
+        let builder = self
             .client
             .patch(self.make_url(&format!("api/users/{}/files/{}", username, filename)))
             .headers(self.default_headers())
             .body(serde_json::to_vec(&request)?)
-            .bearer_auth(Self::require_auth_data(auth_data.as_ref())?.access_token.as_str())
-            .send()
-            .await
-            .map_err(reqwest_error_to_io_error)?;
+            .bearer_auth(Self::require_auth_data(auth_data.as_ref())?.access_token.as_str());
+        let response = self.send(builder).await?;
         match response.status() {
             reqwest::StatusCode::OK | reqwest::StatusCode::CREATED => Ok(()),
             _ => Err(http_response_to_io_error(response).await),
         }
     }
 
+    async fn publish_file(
+        &mut self,
+        username: &str,
+        filename: &str,
+    ) -> io::Result<PublishFileResponse> {
+        let auth_data = self.auth_data.borrow();
+
+        let builder = self
+            .client
+            .post(self.make_url(&format!("api/users/{}/files/{}/publish", username, filename)))
+            .headers(self.default_headers())
+            .header("Content-Length", 0)
+            .bearer_auth(Self::require_auth_data(auth_data.as_ref())?.access_token.as_str());
+        let response = self.send(builder).await?;
+        match response.status() {
+            reqwest::StatusCode::OK | reqwest::StatusCode::CREATED => {
+                let bytes = response.bytes().await.map_err(reqwest_error_to_io_error)?;
+                let response: PublishFileResponse = serde_json::from_reader(bytes.reader())?;
+                Ok(response)
+            }
+            _ => Err(http_response_to_io_error(response).await),
+        }
+    }
+
     async fn delete_file(&mut self, username: &str, filename: &str) -> io::Result<()> {
         let auth_data = self.auth_data.borrow();
 
-        let response = self
+        let builder = self
             .client
             .delete(self.make_url(&format!("api/users/{}/files/{}", username, filename)))
             .headers(self.default_headers())
             .header("Content-Length", 0)
-            .bearer_auth(Self::require_auth_data(auth_data.as_ref())?.access_token.as_str())
-            .send()
-            .await
-            .map_err(reqwest_error_to_io_error)?;
+            .bearer_auth(Self::require_auth_data(auth_data.as_ref())?.access_token.as_str());
+        let response = self.send(builder).await?;
         match response.status() {
             reqwest::StatusCode::OK => Ok(()),
             _ => Err(http_response_to_io_error(response).await),
@@ -425,6 +719,63 @@ mod tests {
     use super::*;
     use std::env;
 
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(700),
+        };
+        assert_eq!(Duration::from_millis(100), policy.backoff(1));
+        assert_eq!(Duration::from_millis(200), policy.backoff(2));
+        assert_eq!(Duration::from_millis(400), policy.backoff(3));
+        assert_eq!(Duration::from_millis(700), policy.backoff(4));
+        assert_eq!(Duration::from_millis(700), policy.backoff(40));
+    }
+
+    #[test]
+    fn test_is_transient_status() {
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_transient_status(StatusCode::GATEWAY_TIMEOUT));
+        assert!(!is_transient_status(StatusCode::OK));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(!is_transient_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_transient_status(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+
+    #[test]
+    fn test_format_request_id() {
+        assert_eq!("", format_request_id(None));
+        assert_eq!(" (request id: abc-123)", format_request_id(Some("abc-123")));
+    }
+
+    #[tokio::test]
+    async fn test_send_gives_up_after_max_attempts() {
+        let sleeps = Rc::from(RefCell::from(vec![]));
+        let service = {
+            let sleeps = sleeps.clone();
+            CloudService::new("http://127.0.0.1:1/")
+                .unwrap()
+                .with_retry_policy(RetryPolicy {
+                    max_attempts: 3,
+                    initial_backoff: Duration::from_millis(10),
+                    max_backoff: Duration::from_secs(1),
+                })
+                .with_sleep_fn(Rc::from(move |d| {
+                    sleeps.borrow_mut().push(d);
+                    Box::pin(async {}) as Pin<Box<dyn Future<Output = ()>>>
+                }))
+        };
+
+        let builder = service.client.get(service.make_url("api/unreachable"));
+        let e = service.send(builder).await.unwrap_err();
+        assert_eq!(io::ErrorKind::NotConnected, e.kind());
+        assert!(format!("{}", e).contains("(gave up after 3 attempts)"));
+        assert_eq!(vec![Duration::from_millis(10), Duration::from_millis(20)], *sleeps.borrow());
+    }
+
     #[test]
     #[ignore = "Requires environment configuration and is expensive"]
     fn test_login_ok() {
@@ -20,12 +20,17 @@ use async_trait::async_trait;
 use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Value, VarType};
 use endbasic_core::exec::Machine;
 use endbasic_core::syms::{
-    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult,
+    ArgSpec, ArgSpecBuilder, CallError, CallableMetadata, CallableMetadataBuilder, Command,
+    CommandResult,
 };
 use endbasic_core::LineCol;
-use endbasic_std::console::{read_line, read_line_secure, refill_and_print, Console};
-use endbasic_std::storage::{FileAcls, Storage};
+use endbasic_std::console::{
+    read_line, read_line_secure, refill_and_print, remove_control_chars, Console,
+};
+use endbasic_std::program::{continue_if_modified, Program};
+use endbasic_std::storage::{has_wildcards, FileAcls, Storage};
 use std::cell::RefCell;
+use std::io;
 use std::rc::Rc;
 use std::str;
 
@@ -44,9 +49,256 @@ those people will be able to see them by mounting your drive.
 If you have any questions or experience any problems while interacting with the cloud service, \
 please contact support@endbasic.dev.";
 
+/// Returns the username of the active session in `service` or an error indicating that `command`
+/// requires logging in first.
+fn require_login(service: &dyn Service, command: &str) -> io::Result<String> {
+    match service.logged_in_username() {
+        Some(username) => Ok(username),
+        None => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("Must LOGIN before using {}", command),
+        )),
+    }
+}
+
+/// The `ACCOUNT` command.
+pub struct AccountCommand {
+    metadata: CallableMetadata,
+    service: Rc<RefCell<dyn Service>>,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl AccountCommand {
+    /// Creates a new `ACCOUNT` command.
+    pub fn new(service: Rc<RefCell<dyn Service>>, console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("ACCOUNT", VarType::Void)
+                .with_syntax("[field$, value$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Displays or modifies the details of your account.
+If given no arguments, this command prints out the profile details of the logged in account.
+Otherwise, sets the profile detail named by field$ to value$.  field$ can be \"email\" to change \
+the email address on file or \"name\" to change the display name.
+To change your password, use the PASSWD command instead.",
+                )
+                .build(),
+            service,
+            console,
+        })
+    }
+
+    /// Fetches and prints the profile details of `username`.
+    async fn show_account(&self, username: &str) -> CommandResult {
+        let response = self.service.borrow_mut().get_account(username).await?;
+
+        let mut console = self.console.borrow_mut();
+        console.print("")?;
+        console.print(&format!("    Username:      {}", username))?;
+        console.print(&format!(
+            "    Display name:  {}",
+            remove_control_chars(response.display_name.unwrap_or_default())
+        ))?;
+        console.print(&format!("    Email address: {}", remove_control_chars(response.email)))?;
+        console.print("")?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for AccountCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (field, value) = match span.args.as_slice() {
+            [] => (None, None),
+            [ArgSpan { expr: Some(field), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(value), sep: ArgSep::End, .. }] => {
+                (Some(field), Some(value))
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let username = require_login(&*self.service.borrow(), "ACCOUNT")?;
+
+        let (field, value) = match (field, value) {
+            (Some(field), Some(value)) => (field, value),
+            _ => return self.show_account(&username).await,
+        };
+
+        let field_pos = field.start_pos();
+        let field = match field.eval(machine.get_mut_symbols()).await? {
+            Value::Text(t) => t,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    field_pos,
+                    "ACCOUNT requires a string as the field".to_owned(),
+                ))
+            }
+        };
+        let value = match value.eval(machine.get_mut_symbols()).await? {
+            Value::Text(t) => t,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    value.start_pos(),
+                    "ACCOUNT requires a string as the value".to_owned(),
+                ))
+            }
+        };
+
+        let request = match field.to_lowercase().as_str() {
+            "email" => PatchAccountRequest::default().with_email(value),
+            "name" => PatchAccountRequest::default().with_display_name(value),
+            _ => {
+                return Err(CallError::ArgumentError(
+                    field_pos,
+                    format!("Invalid field '{}': must be \"email\" or \"name\"", field),
+                ))
+            }
+        };
+        self.service.borrow_mut().patch_account(&username, &request).await?;
+        Ok(())
+    }
+}
+
+/// The `BROWSE` command.
+pub struct BrowseCommand {
+    metadata: CallableMetadata,
+    service: Rc<RefCell<dyn Service>>,
+    console: Rc<RefCell<dyn Console>>,
+    program: Rc<RefCell<dyn Program>>,
+}
+
+impl BrowseCommand {
+    /// Creates a new `BROWSE` command.
+    pub fn new(
+        service: Rc<RefCell<dyn Service>>,
+        console: Rc<RefCell<dyn Console>>,
+        program: Rc<RefCell<dyn Program>>,
+    ) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("BROWSE", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Browses the gallery of featured public programs.
+This command queries the service for programs that other users have shared with the public and \
+prints them as a numbered list along with their descriptions.  You can then type the number of \
+a program to load it into memory, or press ENTER to leave the current program untouched.
+Loaded programs are not associated with any file, so use SAVE to keep a copy in your own drive.
+You do not need to be logged in to use this command.",
+                )
+                .build(),
+            service,
+            console,
+            program,
+        })
+    }
+
+    /// Asks the user to choose one of `count` entries and returns its zero-based index, or `None`
+    /// if the user chose to not load anything.
+    async fn choose(&self, count: usize) -> io::Result<Option<usize>> {
+        let console = &mut *self.console.borrow_mut();
+        loop {
+            let answer =
+                read_line(console, "Enter a number to load or press ENTER to cancel: ", "", None)
+                    .await?;
+            let answer = answer.trim();
+            if answer.is_empty() {
+                return Ok(None);
+            }
+            match answer.parse::<usize>() {
+                Ok(i) if i >= 1 && i <= count => return Ok(Some(i - 1)),
+                _ => console.print(&format!("Invalid choice; must be between 1 and {}", count))?,
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for BrowseCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+
+        let response = self.service.borrow_mut().get_public_files().await?;
+
+        {
+            let mut console = self.console.borrow_mut();
+            console.print("")?;
+            if response.files.is_empty() {
+                console.print("    No public programs available")?;
+                console.print("")?;
+                return Ok(());
+            }
+            for (i, entry) in response.files.iter().enumerate() {
+                console.print(&format!(
+                    "    {:>2}. {}/{}",
+                    i + 1,
+                    remove_control_chars(&entry.username),
+                    remove_control_chars(&entry.filename)
+                ))?;
+                if let Some(description) = entry.description.as_ref() {
+                    console.print(&format!("        {}", remove_control_chars(description)))?;
+                }
+            }
+            console.print("")?;
+        }
+
+        let entry = match self.choose(response.files.len()).await? {
+            Some(i) => &response.files[i],
+            None => return Ok(()),
+        };
+
+        let request = GetFileRequest::default().with_get_content();
+        let content = self
+            .service
+            .borrow_mut()
+            .get_file(&entry.username, &entry.filename, &request)
+            .await?
+            .decoded_content()?;
+        let content = match content {
+            Some(content) => match String::from_utf8(content) {
+                Ok(content) => content,
+                Err(e) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Requested file is not valid UTF-8: {}", e),
+                    )
+                    .into())
+                }
+            },
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Server response is missing the file content".to_string(),
+                )
+                .into())
+            }
+        };
+
+        if continue_if_modified(&*self.program.borrow(), &mut *self.console.borrow_mut()).await? {
+            self.program.borrow_mut().load(None, &content);
+            machine.clear();
+        } else {
+            self.console
+                .borrow_mut()
+                .print("BROWSE aborted; use SAVE to save your current changes.")?;
+        }
+        Ok(())
+    }
+}
+
 /// The `LOGIN` command.
 pub struct LoginCommand {
     metadata: CallableMetadata,
+    args: ArgSpec,
     service: Rc<RefCell<dyn Service>>,
     console: Rc<RefCell<dyn Console>>,
     storage: Rc<RefCell<Storage>>,
@@ -71,6 +323,10 @@ other people's drives with the MOUNT command.
 To create an account, use the SIGNUP command.",
                 )
                 .build(),
+            args: ArgSpecBuilder::new()
+                .required("username$", VarType::Text)
+                .optional("password$", VarType::Text)
+                .build(),
             service,
             console,
             storage,
@@ -95,7 +351,7 @@ To create an account, use the SIGNUP command.",
         }
 
         let mut storage = self.storage.borrow_mut();
-        storage.mount("CLOUD", &format!("cloud://{}", username))?;
+        storage.mount("CLOUD", &format!("cloud://{}", username), false)?;
 
         Ok(())
     }
@@ -116,45 +372,11 @@ impl Command for LoginCommand {
             .into());
         }
 
-        let (username, password) = match span.args.as_slice() {
-            [ArgSpan { expr: Some(username), sep: ArgSep::End, .. }] => {
-                match username.eval(machine.get_mut_symbols()).await? {
-                    Value::Text(username) => {
-                        let password =
-                            read_line_secure(&mut *self.console.borrow_mut(), "Password: ").await?;
-                        (username, password)
-                    }
-                    _ => {
-                        return Err(CallError::ArgumentError(
-                            username.start_pos(),
-                            "LOGIN requires a string as the username".to_owned(),
-                        ))
-                    }
-                }
-            }
-            [ArgSpan { expr: Some(username), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(password), sep: ArgSep::End, .. }] =>
-            {
-                let username = match username.eval(machine.get_mut_symbols()).await? {
-                    Value::Text(username) => username,
-                    _ => {
-                        return Err(CallError::ArgumentError(
-                            username.start_pos(),
-                            "LOGIN requires a string as the username".to_owned(),
-                        ))
-                    }
-                };
-                let password = match password.eval(machine.get_mut_symbols()).await? {
-                    Value::Text(password) => password,
-                    _ => {
-                        return Err(CallError::ArgumentError(
-                            password.start_pos(),
-                            "LOGIN requires a string as the password".to_owned(),
-                        ))
-                    }
-                };
-                (username, password)
-            }
-            _ => return Err(CallError::SyntaxError),
+        let args = self.args.eval_command_args(&span.args, machine.get_mut_symbols()).await?;
+        let username = args.get::<String>(0);
+        let password = match args.get_opt::<String>(1) {
+            Some(password) => password,
+            None => read_line_secure(&mut *self.console.borrow_mut(), "Password: ").await?,
         };
 
         self.do_login(&username, &password).await
@@ -242,6 +464,227 @@ impl Command for LogoutCommand {
     }
 }
 
+/// The `PASSWD` command.
+pub struct PasswdCommand {
+    metadata: CallableMetadata,
+    service: Rc<RefCell<dyn Service>>,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl PasswdCommand {
+    /// Creates a new `PASSWD` command.
+    pub fn new(service: Rc<RefCell<dyn Service>>, console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("PASSWD", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Changes the password of your account interactively.
+This command will ask you for your current password and for the new password you want to use.  \
+The new password is subject to the same complexity requirements as those used by SIGNUP.",
+                )
+                .build(),
+            service,
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for PasswdCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, _machine: &mut Machine) -> CommandResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+
+        let username = require_login(&*self.service.borrow(), "PASSWD")?;
+
+        let console = &mut *self.console.borrow_mut();
+        let old_password = read_line_secure(console, "Current password: ").await?;
+        let new_password =
+            read_new_password(console, "New password: ", "Retype new password: ").await?;
+
+        let request = ChangePasswordRequest { old_password, new_password };
+        self.service.borrow_mut().change_password(&username, &request).await?;
+
+        console.print("")?;
+        console.print("    Password changed")?;
+        console.print("")?;
+        Ok(())
+    }
+}
+
+/// The `PUBLISH` command.
+pub struct PublishCommand {
+    metadata: CallableMetadata,
+    service: Rc<RefCell<dyn Service>>,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl PublishCommand {
+    /// Creates a new `PUBLISH` command.
+    pub fn new(
+        service: Rc<RefCell<dyn Service>>,
+        console: Rc<RefCell<dyn Console>>,
+        storage: Rc<RefCell<Storage>>,
+    ) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("PUBLISH", VarType::Void)
+                .with_syntax("filename$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Obtains a short link to run a program via the web interface.
+This command asks the service for a short web URL that opens filename$ in the web interface and \
+prints it so that you can send it to others.  filename$ must be a file in your own cloud drive.
+Note that others will only be able to open the link if they have read access to the file, so \
+you will most likely want to use SHARE to make the file publicly readable first.",
+                )
+                .build(),
+            service,
+            console,
+            storage,
+        })
+    }
+
+    /// Maps `filename` to the owner and name of the file in the cloud service, ensuring that the
+    /// file lives in a cloud drive owned by the logged in user.
+    fn resolve(&self, filename: &str) -> io::Result<(String, String)> {
+        let username = require_login(&*self.service.borrow(), "PUBLISH")?;
+
+        let storage = self.storage.borrow();
+        let canonical = storage.make_canonical(filename)?;
+        let (drive, path) = canonical.split_once(':').expect("Canonical paths have a drive");
+        let path = path.trim_start_matches('/');
+
+        let mounted = storage.mounted();
+        let uri = mounted.get(drive).expect("Canonical paths refer to mounted drives");
+        match uri.split_once("://") {
+            Some((scheme, owner))
+                if scheme.eq_ignore_ascii_case("cloud")
+                    && owner.eq_ignore_ascii_case(&username) =>
+            {
+                Ok((owner.to_owned(), path.to_owned()))
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not in your cloud drive", canonical),
+            )),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for PublishCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if span.args.len() != 1 {
+            return Err(CallError::SyntaxError);
+        }
+        let arg0 = span.args[0].expr.as_ref().expect("Single argument must be present");
+        let filename = match arg0.eval(machine.get_mut_symbols()).await? {
+            Value::Text(t) => t,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    arg0.start_pos(),
+                    "PUBLISH requires a string as the filename".to_owned(),
+                ))
+            }
+        };
+
+        let (username, filename) = self.resolve(&filename)?;
+        let response = self.service.borrow_mut().publish_file(&username, &filename).await?;
+
+        let mut console = self.console.borrow_mut();
+        console.print("")?;
+        console.print(&format!("    {}", remove_control_chars(response.url)))?;
+        console.print("")?;
+        Ok(())
+    }
+}
+
+/// The `SERVICE` command.
+pub struct ServiceCommand {
+    metadata: CallableMetadata,
+    service: Rc<RefCell<dyn Service>>,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl ServiceCommand {
+    /// Creates a new `SERVICE` command.
+    pub fn new(service: Rc<RefCell<dyn Service>>, console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SERVICE", VarType::Void)
+                .with_syntax("[url$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Shows or changes the address of the cloud service.
+With no arguments, prints the base URL of the service that commands like LOGIN talk to.
+With url$, points all cloud commands and drives at the service located at that base URL, which \
+is useful to target self-hosted or staging instances of the service.  The address cannot be \
+changed while logged in, so you must LOGOUT first.",
+                )
+                .build(),
+            service,
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ServiceCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let url = match span.args.as_slice() {
+            [] => None,
+            [ArgSpan { expr: Some(url), sep: ArgSep::End, .. }] => {
+                match url.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(t) => Some(t),
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            url.start_pos(),
+                            "SERVICE requires a string as the URL".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        match url {
+            None => {
+                let url = self.service.borrow().service_url();
+                let mut console = self.console.borrow_mut();
+                console.print("")?;
+                console.print(&format!("    {}", url))?;
+                console.print("")?;
+            }
+            Some(url) => {
+                let mut service = self.service.borrow_mut();
+                if service.is_logged_in() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Cannot change the service address while logged in".to_owned(),
+                    )
+                    .into());
+                }
+                service.set_service_url(&url)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// The `SHARE` command.
 ///
 /// Note that this command is not exclusively for use by the cloud drive as this interacts with the
@@ -274,9 +717,14 @@ If given only a filename$, this command prints out the ACLs of the file.
 Otherwise, when given a list of ACL changes, applies those changes to the file.  The acl1$ to \
 aclN$ arguments are strings of the form \"username+r\" or \"username-r\", where the former adds \
 \"username\" to the users allowed to read the file, and the latter removes \"username\" from the \
-list of users allowed to read the file.
+list of users allowed to read the file.  Similarly, \"username+w\" and \"username-w\" add and \
+remove \"username\" from the users allowed to modify the file, which lets collaborators edit a \
+shared program.
 You can use the special \"public+r\" ACL to share a file with everyone.  These files can be \
 auto-run via the web interface using the special URL that the command prints on success.
+The filename$ can contain the * and ? wildcards in its file name part to display or modify the \
+ACLs of all matching files at once.  When modifying ACLs, failures on individual files are \
+reported and do not stop the processing of the remaining files.
 Note that this command only works for cloud-based drives as it is designed to share files \
 among users of the EndBASIC service.",
                 )
@@ -304,11 +752,16 @@ impl ShareCommand {
             (username, "+R") if !username.is_empty() => add.add_reader(username),
             (username, "-r") if !username.is_empty() => remove.add_reader(username),
             (username, "-R") if !username.is_empty() => remove.add_reader(username),
+            (username, "+w") if !username.is_empty() => add.add_writer(username),
+            (username, "+W") if !username.is_empty() => add.add_writer(username),
+            (username, "-w") if !username.is_empty() => remove.add_writer(username),
+            (username, "-W") if !username.is_empty() => remove.add_writer(username),
             (username, change) => {
                 return Err(CallError::ArgumentError(
                     acl_pos,
                     format!(
-                        "Invalid ACL '{}{}': must be of the form \"username+r\" or \"username-r\"",
+                        "Invalid ACL '{}{}': must be of the form \"username+r\", \"username-r\", \
+\"username+w\" or \"username-w\"",
                         username, change
                     ),
                 ))
@@ -333,15 +786,25 @@ impl ShareCommand {
 
         let mut console = self.console.borrow_mut();
         console.print("")?;
-        if acls.readers().is_empty() {
+        if acls.readers().is_empty() && acls.writers().is_empty() {
             console.print(&format!("    No ACLs on {}", filename))?;
-        } else {
+            console.print("")?;
+            return Ok(());
+        }
+        if !acls.readers().is_empty() {
             console.print(&format!("    Reader ACLs on {}:", filename))?;
             for acl in acls.readers() {
                 console.print(&format!("    {}", acl))?;
             }
+            console.print("")?;
+        }
+        if !acls.writers().is_empty() {
+            console.print(&format!("    Writer ACLs on {}:", filename))?;
+            for acl in acls.writers() {
+                console.print(&format!("    {}", acl))?;
+            }
+            console.print("")?;
         }
-        console.print("")?;
 
         Ok(())
     }
@@ -375,12 +838,21 @@ impl Command for ShareCommand {
                 ))
             }
         };
-        if span.args[0].sep == ArgSep::End {
-            return self.show_acls(&filename).await;
-        } else if span.args[0].sep != ArgSep::Long {
+        if span.args[0].sep != ArgSep::End && span.args[0].sep != ArgSep::Long {
             return Err(CallError::SyntaxError);
         }
 
+        let is_pattern = has_wildcards(&filename);
+        let filenames =
+            if is_pattern { self.storage.borrow().glob(&filename).await? } else { vec![filename] };
+
+        if span.args[0].sep == ArgSep::End {
+            for filename in &filenames {
+                self.show_acls(filename).await?;
+            }
+            return Ok(());
+        }
+
         let mut add = FileAcls::default();
         let mut remove = FileAcls::default();
         for arg in &span.args[1..] {
@@ -406,36 +878,60 @@ impl Command for ShareCommand {
                 }
             }
         }
-        self.storage.borrow_mut().update_acls(&filename, &add, &remove).await?;
+        // When operating on a pattern, keep going after a failure so that one problematic file
+        // does not leave the rest of the matching files unshared, and report all failures at once.
+        let mut shared = vec![];
+        let mut failures = 0;
+        for filename in &filenames {
+            match self.storage.borrow_mut().update_acls(filename, &add, &remove).await {
+                Ok(()) => shared.push(filename.as_str()),
+                Err(e) if !is_pattern => return Err(e.into()),
+                Err(e) => {
+                    self.console
+                        .borrow_mut()
+                        .print(&format!("Failed to update ACLs on {}: {}", filename, e))?;
+                    failures += 1;
+                }
+            }
+        }
 
-        if Self::has_public_acl(&add) {
-            let filename = match filename.split_once('/') {
-                Some((_drive, path)) => path,
-                None => &filename,
-            };
+        if Self::has_public_acl(&add) && !shared.is_empty() {
+            let username = self
+                .service
+                .borrow()
+                .logged_in_username()
+                .expect("SHARE can only succeed against logged in cloud drives");
+            let mut paragraphs = vec![if shared.len() == 1 {
+                "You have made the file publicly readable.  As a result, other people can now \
+auto-run your public file by visiting:"
+                    .to_owned()
+            } else {
+                "You have made the files publicly readable.  As a result, other people can now \
+auto-run your public files by visiting:"
+                    .to_owned()
+            }];
+            for filename in shared {
+                let filename = match filename.split_once('/') {
+                    Some((_drive, path)) => path,
+                    None => filename,
+                };
+                paragraphs.push(format!("{}?run={}/{}", self.exec_base_url, username, filename));
+            }
 
             let mut console = self.console.borrow_mut();
             console.print("")?;
-            refill_and_print(
-                &mut *console,
-                [
-                    "You have made the file publicly readable.  As a result, other people can now \
-auto-run your public file by visiting:",
-                    &format!(
-                        "{}?run={}/{}",
-                        self.exec_base_url,
-                        self.service
-                            .borrow()
-                            .logged_in_username()
-                            .expect("SHARE can only succeed against logged in cloud drives"),
-                        filename
-                    ),
-                ],
-                "    ",
-            )?;
+            refill_and_print(&mut *console, paragraphs, "    ")?;
             console.print("")?;
         }
 
+        if failures > 0 {
+            return Err(io::Error::other(format!(
+                "Failed to update ACLs on {} of {} files",
+                failures,
+                filenames.len()
+            ))
+            .into());
+        }
         Ok(())
     }
 }
@@ -463,6 +959,33 @@ fn validate_password_complexity(password: &str) -> Result<(), &'static str> {
     Ok(())
 }
 
+/// Tries to get a new password from the user until it is valid, asking for it with `prompt` and
+/// for its confirmation with `retype_prompt`.
+async fn read_new_password(
+    console: &mut dyn Console,
+    prompt: &str,
+    retype_prompt: &str,
+) -> io::Result<String> {
+    loop {
+        let password = read_line_secure(console, prompt).await?;
+        match validate_password_complexity(&password) {
+            Ok(()) => (),
+            Err(e) => {
+                console.print(&format!("Invalid password: {}; try again.", e))?;
+                continue;
+            }
+        }
+
+        let second_password = read_line_secure(console, retype_prompt).await?;
+        if second_password != password {
+            console.print("Passwords do not match; try again.")?;
+            continue;
+        }
+
+        return Ok(password);
+    }
+}
+
 /// The `SIGNUP` command.
 pub struct SignupCommand {
     metadata: CallableMetadata,
@@ -504,28 +1027,6 @@ cloud service.  You will be asked for confirmation before proceeding.",
             }
         }
     }
-
-    /// Tries to get a password from the user until it is valid.
-    async fn read_password(console: &mut dyn Console) -> io::Result<String> {
-        loop {
-            let password = read_line_secure(console, "Password: ").await?;
-            match validate_password_complexity(&password) {
-                Ok(()) => (),
-                Err(e) => {
-                    console.print(&format!("Invalid password: {}; try again.", e))?;
-                    continue;
-                }
-            }
-
-            let second_password = read_line_secure(console, "Retype password: ").await?;
-            if second_password != password {
-                console.print("Passwords do not match; try again.")?;
-                continue;
-            }
-
-            return Ok(password);
-        }
-    }
 }
 
 #[async_trait(?Send)]
@@ -547,7 +1048,7 @@ review your inputs before creating the account."],
         console.print("")?;
 
         let username = read_line(console, "Username: ", "", None).await?;
-        let password = Self::read_password(console).await?;
+        let password = read_new_password(console, "Password: ", "Retype password: ").await?;
 
         console.print("")?;
         refill_and_print(
@@ -608,20 +1109,27 @@ in it to activate your account.  Make sure to check your spam folder.",
 }
 
 /// Adds all remote manipulation commands for `service` to the `machine`, using `console` to
-/// display information and `storage` to manipulate the remote drives.
+/// display information, `storage` to manipulate the remote drives and `program` to load programs
+/// from the public gallery.
 pub fn add_all<S: Into<String>>(
     machine: &mut Machine,
     service: Rc<RefCell<dyn Service>>,
     console: Rc<RefCell<dyn Console>>,
     storage: Rc<RefCell<Storage>>,
+    program: Rc<RefCell<dyn Program>>,
     exec_base_url: S,
 ) {
     storage
         .borrow_mut()
         .register_scheme("cloud", Box::from(CloudDriveFactory::new(service.clone())));
 
+    machine.add_command(AccountCommand::new(service.clone(), console.clone()));
+    machine.add_command(BrowseCommand::new(service.clone(), console.clone(), program));
     machine.add_command(LoginCommand::new(service.clone(), console.clone(), storage.clone()));
     machine.add_command(LogoutCommand::new(service.clone(), console.clone(), storage.clone()));
+    machine.add_command(PasswdCommand::new(service.clone(), console.clone()));
+    machine.add_command(PublishCommand::new(service.clone(), console.clone(), storage.clone()));
+    machine.add_command(ServiceCommand::new(service.clone(), console.clone()));
     machine.add_command(ShareCommand::new(
         service.clone(),
         console.clone(),
@@ -635,7 +1143,7 @@ pub fn add_all<S: Into<String>>(
 mod tests {
     use super::*;
     use crate::testutils::*;
-    use endbasic_std::testutils::*;
+    use endbasic_testutils::*;
 
     #[test]
     fn test_cloud_scheme_always_available() {
@@ -643,6 +1151,160 @@ mod tests {
         assert!(t.get_storage().borrow().has_scheme("cloud"));
     }
 
+    #[tokio::test]
+    async fn test_account_show() {
+        let mut t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.get_service().borrow_mut().add_mock_get_account(
+            "logged-in-username",
+            Ok(GetAccountResponse {
+                email: "some@example.com".to_owned(),
+                display_name: Some("Some Name".to_owned()),
+            }),
+        );
+        t.run("ACCOUNT")
+            .expect_prints([
+                "",
+                "    Username:      logged-in-username",
+                "    Display name:  Some Name",
+                "    Email address: some@example.com",
+                "",
+            ])
+            .expect_access_token("$")
+            .check();
+    }
+
+    #[tokio::test]
+    async fn test_account_update() {
+        let mut t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.get_service().borrow_mut().add_mock_patch_account(
+            "logged-in-username",
+            PatchAccountRequest::default().with_email("new@example.com"),
+            Ok(()),
+        );
+        t.get_service().borrow_mut().add_mock_patch_account(
+            "logged-in-username",
+            PatchAccountRequest::default().with_display_name("New Name"),
+            Ok(()),
+        );
+        t.run(r#"ACCOUNT "Email", "new@example.com": ACCOUNT "name", "New Name""#)
+            .expect_access_token("$")
+            .check();
+    }
+
+    #[tokio::test]
+    async fn test_account_errors() {
+        client_check_stmt_err("1:1: In call to ACCOUNT: expected [field$, value$]", "ACCOUNT 1");
+        client_check_stmt_err(
+            "1:1: In call to ACCOUNT: expected [field$, value$]",
+            r#"ACCOUNT "a"; "b""#,
+        );
+        client_check_stmt_err(
+            "1:1: In call to ACCOUNT: Must LOGIN before using ACCOUNT",
+            r#"ACCOUNT"#,
+        );
+
+        let mut t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.run(r#"ACCOUNT "foo", "bar""#)
+            .expect_err(
+                "1:1: In call to ACCOUNT: 1:9: Invalid field 'foo': must be \"email\" or \"name\"",
+            )
+            .expect_access_token("$")
+            .check();
+        t.run(r#"ACCOUNT 1, "bar""#)
+            .expect_err("1:1: In call to ACCOUNT: 1:9: ACCOUNT requires a string as the field")
+            .expect_access_token("$")
+            .check();
+        t.run(r#"ACCOUNT "email", 2"#)
+            .expect_err("1:1: In call to ACCOUNT: 1:18: ACCOUNT requires a string as the value")
+            .expect_access_token("$")
+            .check();
+    }
+
+    /// Creates a gallery response with two public files for testing.
+    fn two_public_files() -> GetPublicFilesResponse {
+        GetPublicFilesResponse {
+            files: vec![
+                PublicFileEntry {
+                    username: "alice".to_owned(),
+                    filename: "GAME.BAS".to_owned(),
+                    description: Some("A fun game".to_owned()),
+                },
+                PublicFileEntry {
+                    username: "bob".to_owned(),
+                    filename: "DEMO.BAS".to_owned(),
+                    description: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_browse_empty() {
+        let mut t = ClientTester::default();
+        t.get_service()
+            .borrow_mut()
+            .add_mock_get_public_files(Ok(GetPublicFilesResponse { files: vec![] }));
+        t.run("BROWSE").expect_prints(["", "    No public programs available", ""]).check();
+    }
+
+    #[test]
+    fn test_browse_cancel() {
+        let t = ClientTester::default();
+        t.get_service().borrow_mut().add_mock_get_public_files(Ok(two_public_files()));
+        let mut t = t.add_input_chars("\n");
+        t.run("BROWSE")
+            .expect_prints([
+                "",
+                "     1. alice/GAME.BAS",
+                "        A fun game",
+                "     2. bob/DEMO.BAS",
+                "",
+            ])
+            .check();
+    }
+
+    #[tokio::test]
+    async fn test_browse_load() {
+        let t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.get_service().borrow_mut().add_mock_get_public_files(Ok(two_public_files()));
+        t.get_service().borrow_mut().add_mock_get_file(
+            "bob",
+            "DEMO.BAS",
+            GetFileRequest::default().with_get_content(),
+            Ok(GetFileResponse { content: Some(base64::encode("PRINT 1")), ..Default::default() }),
+        );
+        let mut t = t.add_input_chars("5\n").add_input_chars("2\n");
+        t.run("BROWSE")
+            .expect_prints([
+                "",
+                "     1. alice/GAME.BAS",
+                "        A fun game",
+                "     2. bob/DEMO.BAS",
+                "",
+                "Invalid choice; must be between 1 and 2",
+            ])
+            .expect_clear()
+            .expect_program(None as Option<&str>, "PRINT 1")
+            .expect_access_token("$")
+            .check();
+    }
+
+    #[test]
+    fn test_browse_errors() {
+        client_check_stmt_err("1:1: In call to BROWSE: expected no arguments", "BROWSE 1");
+
+        let mut t = ClientTester::default();
+        t.get_service().borrow_mut().add_mock_get_public_files(Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Service unavailable",
+        )));
+        t.run("BROWSE").expect_err("1:1: In call to BROWSE: Service unavailable").check();
+    }
+
     #[test]
     fn test_login_ok_with_password() {
         let mut t = ClientTester::default();
@@ -757,15 +1419,15 @@ mod tests {
             r#"LOGIN "a", "b", "c""#,
         );
         client_check_stmt_err(
-            "1:1: In call to LOGIN: 1:7: LOGIN requires a string as the username",
+            "1:1: In call to LOGIN: 1:7: username$ must be a string",
             r#"LOGIN 3"#,
         );
         client_check_stmt_err(
-            "1:1: In call to LOGIN: 1:7: LOGIN requires a string as the username",
+            "1:1: In call to LOGIN: 1:7: username$ must be a string",
             r#"LOGIN 3, "a""#,
         );
         client_check_stmt_err(
-            "1:1: In call to LOGIN: 1:12: LOGIN requires a string as the password",
+            "1:1: In call to LOGIN: 1:12: password$ must be a string",
             r#"LOGIN "a", 3"#,
         );
     }
@@ -782,7 +1444,7 @@ mod tests {
     async fn test_logout_ok_unmount_cloud() {
         let mut t = ClientTester::default();
         t.get_service().borrow_mut().do_login().await;
-        t.get_storage().borrow_mut().mount("CLOUD", "memory://").unwrap();
+        t.get_storage().borrow_mut().mount("CLOUD", "memory://", false).unwrap();
         t.run(r#"LOGOUT"#)
             .expect_prints(["", "    Unmounted CLOUD drive", "    Good bye!", ""])
             .check();
@@ -793,7 +1455,7 @@ mod tests {
     async fn test_logout_cloud_mounted_and_active() {
         let mut t = ClientTester::default();
         t.get_service().borrow_mut().do_login().await;
-        t.get_storage().borrow_mut().mount("CLOUD", "memory://").unwrap();
+        t.get_storage().borrow_mut().mount("CLOUD", "memory://", false).unwrap();
         t.get_storage().borrow_mut().cd("CLOUD:/").unwrap();
         t.run(r#"LOGOUT"#)
             .expect_err("1:1: In call to LOGOUT: Cannot log out while the CLOUD drive is active")
@@ -844,6 +1506,131 @@ mod tests {
         assert!(t.get_storage().borrow().mounted().contains_key("CLOUD"));
     }
 
+    #[tokio::test]
+    async fn test_passwd_ok() {
+        let t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.get_service().borrow_mut().add_mock_change_password(
+            "logged-in-username",
+            ChangePasswordRequest {
+                old_password: "OldPassword1".to_owned(),
+                new_password: "NewPassword2".to_owned(),
+            },
+            Ok(()),
+        );
+        t.get_console().borrow_mut().set_interactive(true);
+
+        let mut t = t
+            .add_input_chars("OldPassword1\n")
+            .add_input_chars("too simple\n") // Password complexity failure.
+            .add_input_chars("NewPassword2\n")
+            .add_input_chars("does not match\n") // Second password doesn't match.
+            .add_input_chars("NewPassword2\n")
+            .add_input_chars("NewPassword2\n");
+        let mut c = t.run("PASSWD");
+        let output = flatten_output(c.take_captured_out());
+        c.expect_access_token("$").check();
+
+        assert!(output.contains("Invalid password: Must contain"));
+        assert!(output.contains("Passwords do not match"));
+        assert!(output.contains("Password changed"));
+        assert!(!output.contains("OldPassword1"));
+        assert!(!output.contains("NewPassword2"));
+    }
+
+    #[test]
+    fn test_passwd_errors() {
+        client_check_stmt_err("1:1: In call to PASSWD: expected no arguments", "PASSWD 1");
+        client_check_stmt_err("1:1: In call to PASSWD: Must LOGIN before using PASSWD", "PASSWD");
+    }
+
+    #[tokio::test]
+    async fn test_publish_ok() {
+        let mut t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.get_storage().borrow_mut().mount("CLOUD", "cloud://logged-in-username", false).unwrap();
+        t.get_service().borrow_mut().add_mock_publish_file(
+            "logged-in-username",
+            "FOO.BAS",
+            Ok(PublishFileResponse { url: "https://example.com/r/abc".to_owned() }),
+        );
+        t.run(r#"PUBLISH "cloud:/FOO.BAS""#)
+            .expect_prints(["", "    https://example.com/r/abc", ""])
+            .expect_access_token("$")
+            .check();
+    }
+
+    #[tokio::test]
+    async fn test_publish_not_in_own_cloud_drive() {
+        let mut t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.get_storage().borrow_mut().mount("OTHER", "cloud://someone-else", false).unwrap();
+        t.run(r#"PUBLISH "MEMORY:/FOO.BAS""#)
+            .expect_err("1:1: In call to PUBLISH: MEMORY:/FOO.BAS is not in your cloud drive")
+            .expect_access_token("$")
+            .check();
+        t.run(r#"PUBLISH "OTHER:/FOO.BAS""#)
+            .expect_err("1:1: In call to PUBLISH: OTHER:/FOO.BAS is not in your cloud drive")
+            .expect_access_token("$")
+            .check();
+    }
+
+    #[test]
+    fn test_publish_errors() {
+        client_check_stmt_err("1:1: In call to PUBLISH: expected filename$", "PUBLISH");
+        client_check_stmt_err("1:1: In call to PUBLISH: expected filename$", r#"PUBLISH "a", "b""#);
+        client_check_stmt_err(
+            "1:1: In call to PUBLISH: 1:9: PUBLISH requires a string as the filename",
+            "PUBLISH 3",
+        );
+        client_check_stmt_err(
+            "1:1: In call to PUBLISH: Must LOGIN before using PUBLISH",
+            r#"PUBLISH "a""#,
+        );
+    }
+
+    #[test]
+    fn test_service_show_and_change() {
+        let mut t = ClientTester::default();
+        t.run(r#"SERVICE: SERVICE "https://staging.example.com/": SERVICE"#)
+            .expect_prints([
+                "",
+                &format!("    {}", PROD_API_ADDRESS),
+                "",
+                "",
+                "    https://staging.example.com/",
+                "",
+            ])
+            .check();
+        assert_eq!("https://staging.example.com/", t.get_service().borrow().service_url());
+    }
+
+    #[tokio::test]
+    async fn test_service_change_while_logged_in() {
+        let mut t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.run(r#"SERVICE "https://staging.example.com/""#)
+            .expect_err(
+                "1:1: In call to SERVICE: Cannot change the service address while logged in",
+            )
+            .expect_access_token("$")
+            .check();
+        assert_eq!(PROD_API_ADDRESS, t.get_service().borrow().service_url());
+    }
+
+    #[test]
+    fn test_service_errors() {
+        client_check_stmt_err("1:1: In call to SERVICE: expected [url$]", r#"SERVICE "a", "b""#);
+        client_check_stmt_err(
+            "1:1: In call to SERVICE: 1:9: SERVICE requires a string as the URL",
+            "SERVICE 3",
+        );
+        client_check_stmt_err(
+            "1:1: In call to SERVICE: Invalid base API address",
+            r#"SERVICE "foo""#,
+        );
+    }
+
     #[test]
     fn test_share_parse_acl_ok() {
         let mut add = FileAcls::default();
@@ -855,8 +1642,14 @@ mod tests {
         ShareCommand::parse_acl("user2+R".to_owned(), lc, &mut add, &mut remove).unwrap();
         ShareCommand::parse_acl("X-r".to_owned(), lc, &mut add, &mut remove).unwrap();
         ShareCommand::parse_acl("Y-R".to_owned(), lc, &mut add, &mut remove).unwrap();
+        ShareCommand::parse_acl("user3+w".to_owned(), lc, &mut add, &mut remove).unwrap();
+        ShareCommand::parse_acl("user4+W".to_owned(), lc, &mut add, &mut remove).unwrap();
+        ShareCommand::parse_acl("Z-w".to_owned(), lc, &mut add, &mut remove).unwrap();
+        ShareCommand::parse_acl("W-W".to_owned(), lc, &mut add, &mut remove).unwrap();
         assert_eq!(&["user1".to_owned(), "user2".to_owned()], add.readers());
         assert_eq!(&["X".to_owned(), "Y".to_owned()], remove.readers());
+        assert_eq!(&["user3".to_owned(), "user4".to_owned()], add.writers());
+        assert_eq!(&["Z".to_owned(), "W".to_owned()], remove.writers());
     }
 
     #[test]
@@ -874,7 +1667,7 @@ mod tests {
         let mut add = FileAcls::default().with_readers(["before1".to_owned()]);
         let mut remove = FileAcls::default().with_readers(["before2".to_owned()]);
 
-        for acl in &["", "r", "+r", "-r", "foo+", "bar-"] {
+        for acl in &["", "r", "+r", "-r", "+w", "-w", "foo+", "bar-", "baz+x"] {
             let err = ShareCommand::parse_acl(
                 acl.to_string(),
                 LineCol { line: 12, col: 34 },
@@ -889,6 +1682,8 @@ mod tests {
 
         assert_eq!(&["before1".to_owned()], add.readers());
         assert_eq!(&["before2".to_owned()], remove.readers());
+        assert!(add.writers().is_empty());
+        assert!(remove.writers().is_empty());
     }
 
     #[tokio::test]
@@ -923,6 +1718,61 @@ mod tests {
             .check();
     }
 
+    #[tokio::test]
+    async fn test_share_print_reader_and_writer_acls() {
+        let mut t = ClientTester::default();
+        {
+            let storage = t.get_storage();
+            let mut storage = storage.borrow_mut();
+            storage.put("MEMORY:/FOO", "").await.unwrap();
+            storage
+                .update_acls(
+                    "MEMORY:/FOO",
+                    &FileAcls::default()
+                        .with_readers(["some".to_owned()])
+                        .with_writers(["editor".to_owned(), "another".to_owned()]),
+                    &FileAcls::default(),
+                )
+                .await
+                .unwrap();
+        }
+        t.run(r#"SHARE "MEMORY:/FOO""#)
+            .expect_prints([
+                "",
+                "    Reader ACLs on MEMORY:/FOO:",
+                "    some",
+                "",
+                "    Writer ACLs on MEMORY:/FOO:",
+                "    another",
+                "    editor",
+                "",
+            ])
+            .expect_file("MEMORY:/FOO", "")
+            .check();
+    }
+
+    #[tokio::test]
+    async fn test_share_print_only_writer_acls() {
+        let mut t = ClientTester::default();
+        {
+            let storage = t.get_storage();
+            let mut storage = storage.borrow_mut();
+            storage.put("MEMORY:/FOO", "").await.unwrap();
+            storage
+                .update_acls(
+                    "MEMORY:/FOO",
+                    &FileAcls::default().with_writers(["editor".to_owned()]),
+                    &FileAcls::default(),
+                )
+                .await
+                .unwrap();
+        }
+        t.run(r#"SHARE "MEMORY:/FOO""#)
+            .expect_prints(["", "    Writer ACLs on MEMORY:/FOO:", "    editor", ""])
+            .expect_file("MEMORY:/FOO", "")
+            .check();
+    }
+
     #[tokio::test]
     async fn test_share_make_public() {
         let mut t = ClientTester::default();
@@ -934,7 +1784,77 @@ mod tests {
         assert!(output.contains("https://repl.example.com/?run=logged-in-username/FOO.BAS"));
     }
 
-    // TODO(jmmv): Add forgotten tests for SHARE modifying ACLs.
+    #[tokio::test]
+    async fn test_share_make_public_pattern() {
+        let mut t = ClientTester::default();
+        t.get_storage().borrow_mut().put("MEMORY:/FOO.BAS", "").await.unwrap();
+        t.get_storage().borrow_mut().put("MEMORY:/BAR.BAS", "").await.unwrap();
+        t.get_storage().borrow_mut().put("MEMORY:/BAZ.TXT", "").await.unwrap();
+        t.get_service().borrow_mut().do_login().await;
+        let mut checker = t.run(r#"SHARE "MEMORY:/*.BAS", "Public+r""#);
+        let output = flatten_output(checker.take_captured_out());
+        checker
+            .expect_file("MEMORY:/FOO.BAS", "")
+            .expect_file("MEMORY:/BAR.BAS", "")
+            .expect_file("MEMORY:/BAZ.TXT", "")
+            .expect_access_token("$")
+            .check();
+        assert!(output.contains("made the files publicly readable"));
+        assert!(output.contains("https://repl.example.com/?run=logged-in-username/BAR.BAS"));
+        assert!(output.contains("https://repl.example.com/?run=logged-in-username/FOO.BAS"));
+        assert!(!output.contains("BAZ.TXT"));
+        let storage = t.get_storage();
+        let storage = storage.borrow();
+        for name in ["MEMORY:/FOO.BAS", "MEMORY:/BAR.BAS"] {
+            assert_eq!(
+                FileAcls::default().with_readers(["Public".to_owned()]),
+                storage.get_acls(name).await.unwrap()
+            );
+        }
+        assert_eq!(FileAcls::default(), storage.get_acls("MEMORY:/BAZ.TXT").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_share_print_pattern() {
+        let mut t = ClientTester::default();
+        t.get_storage().borrow_mut().put("MEMORY:/A1", "").await.unwrap();
+        t.get_storage().borrow_mut().put("MEMORY:/A2", "").await.unwrap();
+        t.run(r#"SHARE "MEMORY:/A?""#)
+            .expect_prints(["", "    No ACLs on MEMORY:/A1", ""])
+            .expect_prints(["", "    No ACLs on MEMORY:/A2", ""])
+            .expect_file("MEMORY:/A1", "")
+            .expect_file("MEMORY:/A2", "")
+            .check();
+    }
+
+    #[tokio::test]
+    async fn test_share_modify_acls() {
+        let mut t = ClientTester::default();
+        {
+            let storage = t.get_storage();
+            let mut storage = storage.borrow_mut();
+            storage.put("MEMORY:/FOO", "").await.unwrap();
+            storage
+                .update_acls(
+                    "MEMORY:/FOO",
+                    &FileAcls::default()
+                        .with_readers(["old-reader".to_owned()])
+                        .with_writers(["old-writer".to_owned()]),
+                    &FileAcls::default(),
+                )
+                .await
+                .unwrap();
+        }
+        t.run(r#"SHARE "MEMORY:/FOO", "reader+r", "old-reader-r", "writer+w", "old-writer-w""#)
+            .expect_file("MEMORY:/FOO", "")
+            .check();
+        assert_eq!(
+            FileAcls::default()
+                .with_readers(["reader".to_owned()])
+                .with_writers(["writer".to_owned()]),
+            t.get_storage().borrow().get_acls("MEMORY:/FOO").await.unwrap()
+        );
+    }
 
     #[test]
     fn test_share_errors() {
@@ -967,7 +1887,7 @@ mod tests {
             r#"SHARE "a", 3, "b""#,
         );
         client_check_stmt_err(
-            r#"1:1: In call to SHARE: 1:12: Invalid ACL 'foobar': must be of the form "username+r" or "username-r""#,
+            r#"1:1: In call to SHARE: 1:12: Invalid ACL 'foobar': must be of the form "username+r", "username-r", "username+w" or "username-w""#,
             r#"SHARE "a", "foobar""#,
         );
     }
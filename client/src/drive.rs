@@ -17,7 +17,10 @@
 
 use crate::*;
 use async_trait::async_trait;
-use endbasic_std::storage::{Drive, DriveFactory, DriveFiles, FileAcls, Metadata};
+use endbasic_std::storage::{
+    guess_kind, CachingDrive, Drive, DriveFactory, DriveFiles, FileAcls, Metadata, OfflineDrive,
+    DEFAULT_CACHE_TTL,
+};
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::io;
@@ -36,23 +39,49 @@ impl CloudDrive {
         let username = username.into();
         Self { service, username }
     }
+
+    /// Checks if `result` failed because the access token expired and, if so, refreshes the token
+    /// so that the caller can retry the request.  Returns true if the request should be retried.
+    async fn refresh_if_expired<T>(&self, result: &io::Result<T>) -> io::Result<bool> {
+        match result {
+            Err(e) if is_unauthorized(e) && self.service.borrow().is_logged_in() => {
+                self.service.borrow_mut().refresh_token().await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
 }
 
 #[async_trait(?Send)]
 impl Drive for CloudDrive {
     async fn delete(&mut self, filename: &str) -> io::Result<()> {
-        self.service.borrow_mut().delete_file(&self.username, filename).await
+        let result = self.service.borrow_mut().delete_file(&self.username, filename).await;
+        if self.refresh_if_expired(&result).await? {
+            return self.service.borrow_mut().delete_file(&self.username, filename).await;
+        }
+        result
     }
 
     async fn enumerate(&self) -> io::Result<DriveFiles> {
-        let response = self.service.borrow_mut().get_files(&self.username).await?;
+        let mut result = self.service.borrow_mut().get_files(&self.username).await;
+        if self.refresh_if_expired(&result).await? {
+            result = self.service.borrow_mut().get_files(&self.username).await;
+        }
+        let response = result?;
         let mut entries = BTreeMap::default();
         for e in response.files {
             let date = match time::OffsetDateTime::from_unix_timestamp(e.mtime as i64) {
                 Ok(date) => date,
                 Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}", e))),
             };
-            entries.insert(e.filename, Metadata { date, length: e.length });
+            let metadata = Metadata {
+                date,
+                length: e.length,
+                kind: guess_kind(&e.filename),
+                read_only: false,
+            };
+            entries.insert(e.filename, metadata);
         }
         Ok(DriveFiles::new(
             entries,
@@ -63,8 +92,12 @@ impl Drive for CloudDrive {
 
     async fn get(&self, filename: &str) -> io::Result<String> {
         let request = GetFileRequest::default().with_get_content();
-        let response =
-            self.service.borrow_mut().get_file(&self.username, filename, &request).await?;
+        let mut result =
+            self.service.borrow_mut().get_file(&self.username, filename, &request).await;
+        if self.refresh_if_expired(&result).await? {
+            result = self.service.borrow_mut().get_file(&self.username, filename, &request).await;
+        }
+        let response = result?;
         match response.decoded_content()? {
             Some(content) => match String::from_utf8(content) {
                 Ok(s) => Ok(s),
@@ -81,11 +114,19 @@ impl Drive for CloudDrive {
     }
 
     async fn get_acls(&self, filename: &str) -> io::Result<FileAcls> {
-        let request = GetFileRequest::default().with_get_readers();
-        let response =
-            self.service.borrow_mut().get_file(&self.username, filename, &request).await?;
+        let request = GetFileRequest::default().with_get_readers().with_get_writers();
+        let mut result =
+            self.service.borrow_mut().get_file(&self.username, filename, &request).await;
+        if self.refresh_if_expired(&result).await? {
+            result = self.service.borrow_mut().get_file(&self.username, filename, &request).await;
+        }
+        let response = result?;
         match response.readers {
-            Some(readers) => Ok(FileAcls::default().with_readers(readers)),
+            // Servers that predate writer ACLs do not return the writers list, which is
+            // equivalent to the file having no writers other than its owner.
+            Some(readers) => Ok(FileAcls::default()
+                .with_readers(readers)
+                .with_writers(response.writers.unwrap_or_default())),
             None => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "Server response is missing the readers list".to_string(),
@@ -95,7 +136,11 @@ impl Drive for CloudDrive {
 
     async fn put(&mut self, filename: &str, content: &str) -> io::Result<()> {
         let request = PatchFileRequest::default().with_content(content.as_bytes());
-        self.service.borrow_mut().patch_file(&self.username, filename, &request).await
+        let result = self.service.borrow_mut().patch_file(&self.username, filename, &request).await;
+        if self.refresh_if_expired(&result).await? {
+            return self.service.borrow_mut().patch_file(&self.username, filename, &request).await;
+        }
+        result
     }
 
     async fn update_acls(
@@ -106,21 +151,39 @@ impl Drive for CloudDrive {
     ) -> io::Result<()> {
         let mut request = PatchFileRequest::default();
 
-        let add = add.readers();
-        if !add.is_empty() {
-            request.add_readers = Some(add.to_vec());
+        let add_readers = add.readers();
+        if !add_readers.is_empty() {
+            request.add_readers = Some(add_readers.to_vec());
         }
 
-        let remove = remove.readers();
-        if !remove.is_empty() {
-            request.remove_readers = Some(remove.to_vec());
+        let remove_readers = remove.readers();
+        if !remove_readers.is_empty() {
+            request.remove_readers = Some(remove_readers.to_vec());
         }
 
-        self.service.borrow_mut().patch_file(&self.username, filename, &request).await
+        let add_writers = add.writers();
+        if !add_writers.is_empty() {
+            request.add_writers = Some(add_writers.to_vec());
+        }
+
+        let remove_writers = remove.writers();
+        if !remove_writers.is_empty() {
+            request.remove_writers = Some(remove_writers.to_vec());
+        }
+
+        let result = self.service.borrow_mut().patch_file(&self.username, filename, &request).await;
+        if self.refresh_if_expired(&result).await? {
+            return self.service.borrow_mut().patch_file(&self.username, filename, &request).await;
+        }
+        result
     }
 }
 
 /// Factory for cloud drives.
+///
+/// The drives created by this factory cache the contents they fetch from the service for a short
+/// period of time to make browsing them over slow connections bearable.  They also keep working
+/// while the service is unreachable by queuing any changes until they are explicitly synced.
 pub struct CloudDriveFactory {
     service: Rc<RefCell<dyn Service>>,
 }
@@ -135,7 +198,8 @@ impl CloudDriveFactory {
 impl DriveFactory for CloudDriveFactory {
     fn create(&self, target: &str) -> io::Result<Box<dyn Drive>> {
         if !target.is_empty() {
-            Ok(Box::from(CloudDrive::new(self.service.clone(), target)))
+            let drive = OfflineDrive::new(Box::from(CloudDrive::new(self.service.clone(), target)));
+            Ok(Box::from(CachingDrive::new(Box::from(drive), DEFAULT_CACHE_TTL)))
         } else {
             Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -184,14 +248,18 @@ mod tests {
         assert_eq!(
             &Metadata {
                 date: time::OffsetDateTime::from_unix_timestamp(9000).unwrap(),
-                length: 15
+                length: 15,
+                kind: None,
+                read_only: false,
             },
             result.dirents().get("one").unwrap()
         );
         assert_eq!(
             &Metadata {
                 date: time::OffsetDateTime::from_unix_timestamp(8000).unwrap(),
-                length: 17
+                length: 17,
+                kind: None,
+                read_only: false,
             },
             result.dirents().get("two").unwrap()
         );
@@ -252,20 +320,126 @@ mod tests {
         service.take().verify_all_used();
     }
 
+    /// Creates an error that mimics the service rejecting an expired access token.
+    fn unauthorized_error() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            UnauthorizedError("Token expired (server code: 401)".to_owned()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_clouddrive_refresh_expired_token_and_retry() {
+        let service = Rc::from(RefCell::from(MockService::default()));
+        service.borrow_mut().do_login().await;
+        let mut drive = CloudDrive::new(service.clone(), "the-user");
+
+        service.borrow_mut().add_mock_get_file(
+            "the-user",
+            "the-filename",
+            GetFileRequest::default().with_get_content(),
+            Err(unauthorized_error()),
+        );
+        service.borrow_mut().add_mock_refresh_token(Ok(()));
+        let response =
+            GetFileResponse { content: Some(base64::encode("some content")), ..Default::default() };
+        service.borrow_mut().add_mock_get_file(
+            "the-user",
+            "the-filename",
+            GetFileRequest::default().with_get_content(),
+            Ok(response),
+        );
+        assert_eq!("some content", drive.get("the-filename").await.unwrap());
+
+        service.borrow_mut().add_mock_delete_file(
+            "the-user",
+            "the-filename",
+            Err(unauthorized_error()),
+        );
+        service.borrow_mut().add_mock_refresh_token(Ok(()));
+        service.borrow_mut().add_mock_delete_file("the-user", "the-filename", Ok(()));
+        drive.delete("the-filename").await.unwrap();
+
+        assert!(service.borrow().is_logged_in());
+        service.take().verify_all_used();
+    }
+
+    #[tokio::test]
+    async fn test_clouddrive_refresh_expired_token_fails() {
+        let service = Rc::from(RefCell::from(MockService::default()));
+        service.borrow_mut().do_login().await;
+        let mut drive = CloudDrive::new(service.clone(), "the-user");
+
+        let request = PatchFileRequest::default().with_content("data");
+        service.borrow_mut().add_mock_patch_file(
+            "the-user",
+            "the-filename",
+            request,
+            Err(unauthorized_error()),
+        );
+        service.borrow_mut().add_mock_refresh_token(Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Session is gone",
+        )));
+        let err = drive.put("the-filename", "data").await.unwrap_err();
+        assert_eq!("Session is gone", format!("{}", err));
+
+        service.take().verify_all_used();
+    }
+
+    #[tokio::test]
+    async fn test_clouddrive_no_refresh_on_other_errors() {
+        let service = Rc::from(RefCell::from(MockService::default()));
+        service.borrow_mut().do_login().await;
+        let mut drive = CloudDrive::new(service.clone(), "the-user");
+
+        service.borrow_mut().add_mock_delete_file(
+            "the-user",
+            "the-filename",
+            Err(io::Error::new(io::ErrorKind::PermissionDenied, "Forbidden")),
+        );
+        let err = drive.delete("the-filename").await.unwrap_err();
+        assert_eq!("Forbidden", format!("{}", err));
+
+        service.take().verify_all_used();
+    }
+
     #[tokio::test]
     async fn test_clouddrive_get_acls() {
         let service = Rc::from(RefCell::from(MockService::default()));
         service.borrow_mut().do_login().await;
         let drive = CloudDrive::new(service.clone(), "the-user");
 
-        let request = GetFileRequest::default().with_get_readers();
+        let request = GetFileRequest::default().with_get_readers().with_get_writers();
         let response = GetFileResponse {
             readers: Some(vec!["r1".to_owned(), "r2".to_owned()]),
+            writers: Some(vec!["w1".to_owned()]),
             ..Default::default()
         };
         service.borrow_mut().add_mock_get_file("the-user", "the-filename", request, Ok(response));
         let result = drive.get_acls("the-filename").await.unwrap();
-        assert_eq!(FileAcls::default().with_readers(["r1".to_owned(), "r2".to_owned()]), result);
+        assert_eq!(
+            FileAcls::default()
+                .with_readers(["r1".to_owned(), "r2".to_owned()])
+                .with_writers(["w1".to_owned()]),
+            result
+        );
+
+        service.take().verify_all_used();
+    }
+
+    #[tokio::test]
+    async fn test_clouddrive_get_acls_no_writers() {
+        let service = Rc::from(RefCell::from(MockService::default()));
+        service.borrow_mut().do_login().await;
+        let drive = CloudDrive::new(service.clone(), "the-user");
+
+        let request = GetFileRequest::default().with_get_readers().with_get_writers();
+        let response =
+            GetFileResponse { readers: Some(vec!["r1".to_owned()]), ..Default::default() };
+        service.borrow_mut().add_mock_get_file("the-user", "the-filename", request, Ok(response));
+        let result = drive.get_acls("the-filename").await.unwrap();
+        assert_eq!(FileAcls::default().with_readers(["r1".to_owned()]), result);
 
         service.take().verify_all_used();
     }
@@ -276,7 +450,7 @@ mod tests {
         service.borrow_mut().do_login().await;
         let drive = CloudDrive::new(service.clone(), "the-user");
 
-        let request = GetFileRequest::default().with_get_readers();
+        let request = GetFileRequest::default().with_get_readers().with_get_writers();
         let response = GetFileResponse::default();
         service.borrow_mut().add_mock_get_file("the-user", "the-filename", request, Ok(response));
         let err = drive.get_acls("the-filename").await.unwrap_err();
@@ -324,13 +498,19 @@ mod tests {
 
         let request = PatchFileRequest::default()
             .with_add_readers(["r1".to_owned(), "r2".to_owned()])
-            .with_remove_readers(["r2".to_owned(), "r3".to_owned()]);
+            .with_remove_readers(["r2".to_owned(), "r3".to_owned()])
+            .with_add_writers(["w1".to_owned()])
+            .with_remove_writers(["w2".to_owned()]);
         service.borrow_mut().add_mock_patch_file("the-user", "the-filename", request, Ok(()));
         drive
             .update_acls(
                 "the-filename",
-                &FileAcls::default().with_readers(["r1".to_owned(), "r2".to_owned()]),
-                &FileAcls::default().with_readers(["r2".to_owned(), "r3".to_owned()]),
+                &FileAcls::default()
+                    .with_readers(["r1".to_owned(), "r2".to_owned()])
+                    .with_writers(["w1".to_owned()]),
+                &FileAcls::default()
+                    .with_readers(["r2".to_owned(), "r3".to_owned()])
+                    .with_writers(["w2".to_owned()]),
             )
             .await
             .unwrap();
@@ -403,4 +583,43 @@ mod tests {
         ])
         .check();
     }
+
+    #[tokio::test]
+    async fn test_cloud_scheme_works_offline_and_syncs() {
+        let mut t = ClientTester::default();
+        t.get_service().borrow_mut().do_login().await;
+        t.get_storage().borrow_mut().mount("X", "cloud://the-user", false).unwrap();
+
+        t.get_service().borrow_mut().add_mock_patch_file(
+            "the-user",
+            "FOO.BAS",
+            PatchFileRequest::default().with_content("PRINT 1"),
+            Err(io::Error::new(io::ErrorKind::NotConnected, "Network is down")),
+        );
+        t.get_storage().borrow_mut().put("X:/FOO.BAS", "PRINT 1").await.unwrap();
+        assert_eq!("PRINT 1", t.get_storage().borrow().get("X:/FOO.BAS").await.unwrap());
+
+        t.get_service().borrow_mut().add_mock_get_file(
+            "the-user",
+            "FOO.BAS",
+            GetFileRequest::default().with_get_content(),
+            Err(io::Error::new(io::ErrorKind::NotFound, "Not found")),
+        );
+        t.get_service().borrow_mut().add_mock_patch_file(
+            "the-user",
+            "FOO.BAS",
+            PatchFileRequest::default().with_content("PRINT 1"),
+            Ok(()),
+        );
+        t.run(r#"SYNC "X""#)
+            .expect_prints([
+                "",
+                "    Synchronized X:/FOO.BAS",
+                "",
+                "    1 file(s) synchronized, 0 conflict(s)",
+                "",
+            ])
+            .expect_access_token("$")
+            .check();
+    }
 }
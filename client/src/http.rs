@@ -0,0 +1,68 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! HTTP client for EndBASIC programs backed by `reqwest`.
+//!
+//! On the web, `reqwest` is implemented on top of the browser's `fetch` API, so this same client
+//! works for all platforms.
+
+use async_trait::async_trait;
+use endbasic_std::http::{Client, Response};
+use std::io;
+
+/// Converts a `reqwest::Error` to an `io::Error`.
+fn reqwest_error_to_io_error(e: reqwest::Error) -> io::Error {
+    io::Error::other(format!("{}", e))
+}
+
+/// Converts a `reqwest::Response` to a `Response`, consuming its body.
+async fn into_response(response: reqwest::Response) -> io::Result<Response> {
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (name.as_str().to_owned(), String::from_utf8_lossy(value.as_bytes()).into_owned())
+        })
+        .collect();
+    let body = response.text().await.map_err(reqwest_error_to_io_error)?;
+    Ok(Response { status, headers, body })
+}
+
+/// An implementation of the EndBASIC HTTP client that issues real network requests.
+#[derive(Default)]
+pub struct ReqwestHttpClient {
+    client: reqwest::Client,
+}
+
+#[async_trait(?Send)]
+impl Client for ReqwestHttpClient {
+    async fn get(&self, url: &str) -> io::Result<Response> {
+        let response = self.client.get(url).send().await.map_err(reqwest_error_to_io_error)?;
+        into_response(response).await
+    }
+
+    async fn post(&self, url: &str, content_type: &str, body: String) -> io::Result<Response> {
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(reqwest_error_to_io_error)?;
+        into_response(response).await
+    }
+}
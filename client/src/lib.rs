@@ -25,6 +25,8 @@
 use async_trait::async_trait;
 use endbasic_std::storage::DiskSpace;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
 use std::io;
 
 mod cloud;
@@ -33,6 +35,8 @@ mod cmds;
 pub use cmds::add_all;
 mod drive;
 pub(crate) use drive::CloudDriveFactory;
+mod http;
+pub use http::ReqwestHttpClient;
 #[cfg(test)]
 pub(crate) mod testutils;
 
@@ -78,6 +82,28 @@ impl AccessToken {
     }
 }
 
+/// Error to indicate that the service rejected a request because the access token used to issue it
+/// is not valid, most likely because it expired.
+#[derive(Debug)]
+pub(crate) struct UnauthorizedError(pub(crate) String);
+
+impl fmt::Display for UnauthorizedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for UnauthorizedError {}
+
+/// Returns true if `e` was caused by the service rejecting a request due to an invalid access
+/// token.
+pub(crate) fn is_unauthorized(e: &io::Error) -> bool {
+    match e.get_ref() {
+        Some(inner) => inner.is::<UnauthorizedError>(),
+        None => false,
+    }
+}
+
 /// Representation of the details of an error response.
 #[derive(Deserialize)]
 #[cfg_attr(test, derive(Debug, Serialize))]
@@ -93,6 +119,13 @@ pub struct LoginResponse {
     motd: Vec<String>,
 }
 
+/// Representation of an access token refresh response.
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Debug, Serialize))]
+pub struct RefreshTokenResponse {
+    pub(crate) access_token: AccessToken,
+}
+
 /// Representation of a single directory entry as returned by the server.
 #[derive(Deserialize)]
 #[cfg_attr(test, derive(Debug, Serialize))]
@@ -111,12 +144,29 @@ pub struct GetFilesResponse {
     disk_free: Option<SerdeDiskSpace>,
 }
 
+/// Representation of a single publicly-shared file as returned by the server's gallery.
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Debug, Serialize))]
+pub struct PublicFileEntry {
+    username: String,
+    filename: String,
+    description: Option<String>,
+}
+
+/// Representation of a response to a query for featured public files.
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Debug, Serialize))]
+pub struct GetPublicFilesResponse {
+    files: Vec<PublicFileEntry>,
+}
+
 /// Representation of a file query.
 #[derive(Debug, Default, Eq, PartialEq, Serialize)]
 #[cfg_attr(test, derive(Deserialize))]
 pub struct GetFileRequest {
     get_content: bool,
     get_readers: bool,
+    get_writers: bool,
 }
 
 impl GetFileRequest {
@@ -131,6 +181,12 @@ impl GetFileRequest {
         self.get_readers = true;
         self
     }
+
+    /// Requests the file's writers from the server.
+    fn with_get_writers(mut self) -> Self {
+        self.get_writers = true;
+        self
+    }
 }
 
 /// Representation of the response to a file query.
@@ -141,6 +197,8 @@ pub struct GetFileResponse {
     content: Option<String>,
 
     readers: Option<Vec<String>>,
+
+    writers: Option<Vec<String>>,
 }
 
 impl GetFileResponse {
@@ -168,6 +226,8 @@ pub struct PatchFileRequest {
 
     add_readers: Option<Vec<String>>,
     remove_readers: Option<Vec<String>>,
+    add_writers: Option<Vec<String>>,
+    remove_writers: Option<Vec<String>>,
 }
 
 impl PatchFileRequest {
@@ -190,6 +250,66 @@ impl PatchFileRequest {
         self.remove_readers = Some(readers.into());
         self
     }
+
+    /// Adds `writers` to the file's writer ACLs.
+    #[cfg(test)]
+    fn with_add_writers<W: Into<Vec<String>>>(mut self, writers: W) -> Self {
+        self.add_writers = Some(writers.into());
+        self
+    }
+
+    /// Removes `writers` from the file's writer ACLs.
+    #[cfg(test)]
+    fn with_remove_writers<W: Into<Vec<String>>>(mut self, writers: W) -> Self {
+        self.remove_writers = Some(writers.into());
+        self
+    }
+}
+
+/// Representation of the response to a request to publish a file.
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Debug, Serialize))]
+pub struct PublishFileResponse {
+    /// Short web URL that opens the file in the web interface.
+    url: String,
+}
+
+/// Representation of a password change request.
+#[derive(Debug, Default, Eq, PartialEq, Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct ChangePasswordRequest {
+    old_password: String,
+    new_password: String,
+}
+
+/// Representation of the profile details of an account.
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Debug, Serialize))]
+pub struct GetAccountResponse {
+    email: String,
+    display_name: Option<String>,
+}
+
+/// Representation of an update to the profile details of an account.
+#[derive(Debug, Default, Eq, PartialEq, Serialize)]
+#[cfg_attr(test, derive(Deserialize))]
+pub struct PatchAccountRequest {
+    email: Option<String>,
+    display_name: Option<String>,
+}
+
+impl PatchAccountRequest {
+    /// Updates the account's email address with `email`.
+    fn with_email<E: Into<String>>(mut self, email: E) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Updates the account's display name with `display_name`.
+    fn with_display_name<N: Into<String>>(mut self, display_name: N) -> Self {
+        self.display_name = Some(display_name.into());
+        self
+    }
 }
 
 /// Representation of a signup request.
@@ -217,16 +337,55 @@ pub trait Service {
     /// Logs out from the service and clears the access token from this object.
     async fn logout(&mut self) -> io::Result<()>;
 
+    /// Exchanges the access token of the active session for a fresh one so that the session can
+    /// continue after the previous token expired.
+    ///
+    /// If the refresh is successful, the new access token replaces the cached one.
+    async fn refresh_token(&mut self) -> io::Result<()>;
+
+    /// Sends a request to the server to change the password of `username` as specified in
+    /// `request` with a previously-acquired `access_token`.
+    async fn change_password(
+        &mut self,
+        username: &str,
+        request: &ChangePasswordRequest,
+    ) -> io::Result<()>;
+
+    /// Sends a request to the server to obtain the profile details of `username` with a
+    /// previously-acquired `access_token`.
+    async fn get_account(&mut self, username: &str) -> io::Result<GetAccountResponse>;
+
+    /// Sends a request to the server to update the profile details of `username` as specified in
+    /// `request` with a previously-acquired `access_token`.
+    async fn patch_account(
+        &mut self,
+        username: &str,
+        request: &PatchAccountRequest,
+    ) -> io::Result<()>;
+
     /// Checks if there is an active session against the service.
     fn is_logged_in(&self) -> bool;
 
     /// Returns the logged in username if there is an active session.
     fn logged_in_username(&self) -> Option<String>;
 
+    /// Returns the base address of the service this client talks to.
+    fn service_url(&self) -> String;
+
+    /// Points this client at the service located at the base address `url`.
+    ///
+    /// Callers must ensure that there is no active session as access tokens are only valid for the
+    /// service that issued them.
+    fn set_service_url(&mut self, url: &str) -> io::Result<()>;
+
     /// Sends a request to the server to obtain the list of files owned by `username` with a
     /// previously-acquired `access_token`.
     async fn get_files(&mut self, username: &str) -> io::Result<GetFilesResponse>;
 
+    /// Sends a request to the server to obtain the list of featured files that have been shared
+    /// with the public.  This does not require an active session.
+    async fn get_public_files(&mut self) -> io::Result<GetPublicFilesResponse>;
+
     /// Sends a request to the server to obtain the metadata and/or the contents of `filename` owned
     /// by `username` as specified in `request` with a previously-acquired `access_token`.
     async fn get_file(
@@ -245,6 +404,14 @@ pub trait Service {
         request: &PatchFileRequest,
     ) -> io::Result<()>;
 
+    /// Sends a request to the server to obtain a short web URL that opens `filename` owned by
+    /// `username` in the web interface with a previously-acquired `access_token`.
+    async fn publish_file(
+        &mut self,
+        username: &str,
+        filename: &str,
+    ) -> io::Result<PublishFileResponse>;
+
     /// Sends a request to the server to delete `filename` owned by `username` with a
     /// previously-acquired `access_token`.
     async fn delete_file(&mut self, username: &str, filename: &str) -> io::Result<()>;
@@ -16,12 +16,13 @@
 //! Test utilities for the cloud service.
 
 use crate::{
-    add_all, AccessToken, GetFileRequest, GetFileResponse, GetFilesResponse, LoginResponse,
-    PatchFileRequest, Service, SignupRequest,
+    add_all, AccessToken, ChangePasswordRequest, GetAccountResponse, GetFileRequest,
+    GetFileResponse, GetFilesResponse, GetPublicFilesResponse, LoginResponse, PatchAccountRequest,
+    PatchFileRequest, PublishFileResponse, Service, SignupRequest, PROD_API_ADDRESS,
 };
 use async_trait::async_trait;
 use endbasic_std::storage::Storage;
-use endbasic_std::testutils::*;
+use endbasic_testutils::*;
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::io;
@@ -32,12 +33,19 @@ use std::rc::Rc;
 #[derive(Default)]
 pub struct MockService {
     access_token: Option<AccessToken>,
+    service_url: Option<String>,
 
     mock_signup: VecDeque<(SignupRequest, io::Result<()>)>,
     mock_login: VecDeque<((String, String), io::Result<LoginResponse>)>,
+    mock_refresh_token: VecDeque<io::Result<()>>,
+    mock_change_password: VecDeque<((String, ChangePasswordRequest), io::Result<()>)>,
+    mock_get_account: VecDeque<(String, io::Result<GetAccountResponse>)>,
+    mock_patch_account: VecDeque<((String, PatchAccountRequest), io::Result<()>)>,
     mock_get_files: VecDeque<(String, io::Result<GetFilesResponse>)>,
+    mock_get_public_files: VecDeque<io::Result<GetPublicFilesResponse>>,
     mock_get_file: VecDeque<((String, String, GetFileRequest), io::Result<GetFileResponse>)>,
     mock_patch_file: VecDeque<((String, String, PatchFileRequest), io::Result<()>)>,
+    mock_publish_file: VecDeque<((String, String), io::Result<PublishFileResponse>)>,
     mock_delete_file: VecDeque<((String, String), io::Result<()>)>,
 }
 
@@ -73,6 +81,47 @@ impl MockService {
         self.mock_login.push_back((exp_request, result));
     }
 
+    /// Records the behavior of an upcoming access token refresh operation that returns `result`.
+    #[cfg(test)]
+    pub(crate) fn add_mock_refresh_token(&mut self, result: io::Result<()>) {
+        self.mock_refresh_token.push_back(result);
+    }
+
+    /// Records the behavior of an upcoming password change operation for `username` with a
+    /// request that looks like `exp_request` and that returns `result`.
+    #[cfg(test)]
+    pub(crate) fn add_mock_change_password(
+        &mut self,
+        username: &str,
+        exp_request: ChangePasswordRequest,
+        result: io::Result<()>,
+    ) {
+        self.mock_change_password.push_back(((username.to_owned(), exp_request), result));
+    }
+
+    /// Records the behavior of an upcoming "get account" operation for `username` and that
+    /// returns `result`.
+    #[cfg(test)]
+    pub(crate) fn add_mock_get_account(
+        &mut self,
+        username: &str,
+        result: io::Result<GetAccountResponse>,
+    ) {
+        self.mock_get_account.push_back((username.to_owned(), result));
+    }
+
+    /// Records the behavior of an upcoming "patch account" operation for `username` with a
+    /// request that looks like `exp_request` and that returns `result`.
+    #[cfg(test)]
+    pub(crate) fn add_mock_patch_account(
+        &mut self,
+        username: &str,
+        exp_request: PatchAccountRequest,
+        result: io::Result<()>,
+    ) {
+        self.mock_patch_account.push_back(((username.to_owned(), exp_request), result));
+    }
+
     /// Records the behavior of an upcoming "get files" operation for `username` and that returns
     /// `result`.
     #[cfg(test)]
@@ -85,6 +134,12 @@ impl MockService {
         self.mock_get_files.push_back((exp_request, result));
     }
 
+    /// Records the behavior of an upcoming "get public files" operation that returns `result`.
+    #[cfg(test)]
+    pub(crate) fn add_mock_get_public_files(&mut self, result: io::Result<GetPublicFilesResponse>) {
+        self.mock_get_public_files.push_back(result);
+    }
+
     /// Records the behavior of an upcoming "get file" operation for the `username`/`filename`
     /// pair with a request that looks like `exp_request` and that returns `result`.
     #[cfg(test)]
@@ -113,6 +168,19 @@ impl MockService {
         self.mock_patch_file.push_back((exp_request, result));
     }
 
+    /// Records the behavior of an upcoming "publish file" operation for the `username`/`filename`
+    /// pair and that returns `result`.
+    #[cfg(test)]
+    pub(crate) fn add_mock_publish_file(
+        &mut self,
+        username: &str,
+        filename: &str,
+        result: io::Result<PublishFileResponse>,
+    ) {
+        let exp_request = (username.to_owned(), filename.to_owned());
+        self.mock_publish_file.push_back((exp_request, result));
+    }
+
     /// Records the behavior of an upcoming "delete file" operation for the `username`/`filename`
     /// pair and that returns `result`.
     #[cfg(test)]
@@ -130,9 +198,15 @@ impl MockService {
     pub(crate) fn verify_all_used(&mut self) {
         assert!(self.mock_signup.is_empty(), "Mock requests not fully consumed");
         assert!(self.mock_login.is_empty(), "Mock requests not fully consumed");
+        assert!(self.mock_refresh_token.is_empty(), "Mock requests not fully consumed");
+        assert!(self.mock_change_password.is_empty(), "Mock requests not fully consumed");
+        assert!(self.mock_get_account.is_empty(), "Mock requests not fully consumed");
+        assert!(self.mock_patch_account.is_empty(), "Mock requests not fully consumed");
         assert!(self.mock_get_files.is_empty(), "Mock requests not fully consumed");
+        assert!(self.mock_get_public_files.is_empty(), "Mock requests not fully consumed");
         assert!(self.mock_get_file.is_empty(), "Mock requests not fully consumed");
         assert!(self.mock_patch_file.is_empty(), "Mock requests not fully consumed");
+        assert!(self.mock_publish_file.is_empty(), "Mock requests not fully consumed");
         assert!(self.mock_delete_file.is_empty(), "Mock requests not fully consumed");
     }
 }
@@ -163,6 +237,50 @@ impl Service for MockService {
         Ok(())
     }
 
+    async fn refresh_token(&mut self) -> io::Result<()> {
+        let previous = self.access_token.as_ref().expect("login not called yet");
+
+        let mock = self.mock_refresh_token.pop_front().expect("No mock requests available");
+        if mock.is_ok() {
+            self.access_token = Some(AccessToken::new(format!("{}+", previous.as_str())));
+        }
+        mock
+    }
+
+    async fn change_password(
+        &mut self,
+        username: &str,
+        request: &ChangePasswordRequest,
+    ) -> io::Result<()> {
+        self.access_token.as_ref().expect("login not called yet");
+
+        let mock = self.mock_change_password.pop_front().expect("No mock requests available");
+        assert_eq!(&mock.0 .0, username);
+        assert_eq!(&mock.0 .1, request);
+        mock.1
+    }
+
+    async fn get_account(&mut self, username: &str) -> io::Result<GetAccountResponse> {
+        self.access_token.as_ref().expect("login not called yet");
+
+        let mock = self.mock_get_account.pop_front().expect("No mock requests available");
+        assert_eq!(&mock.0, username);
+        mock.1
+    }
+
+    async fn patch_account(
+        &mut self,
+        username: &str,
+        request: &PatchAccountRequest,
+    ) -> io::Result<()> {
+        self.access_token.as_ref().expect("login not called yet");
+
+        let mock = self.mock_patch_account.pop_front().expect("No mock requests available");
+        assert_eq!(&mock.0 .0, username);
+        assert_eq!(&mock.0 .1, request);
+        mock.1
+    }
+
     fn is_logged_in(&self) -> bool {
         self.access_token.is_some()
     }
@@ -174,6 +292,18 @@ impl Service for MockService {
         }
     }
 
+    fn service_url(&self) -> String {
+        self.service_url.clone().unwrap_or_else(|| PROD_API_ADDRESS.to_owned())
+    }
+
+    fn set_service_url(&mut self, url: &str) -> io::Result<()> {
+        if !url.starts_with("http") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid base API address"));
+        }
+        self.service_url = Some(url.to_owned());
+        Ok(())
+    }
+
     async fn get_files(&mut self, username: &str) -> io::Result<GetFilesResponse> {
         self.access_token.as_ref().expect("login not called yet");
         let mock = self.mock_get_files.pop_front().expect("No mock requests available");
@@ -181,6 +311,10 @@ impl Service for MockService {
         mock.1
     }
 
+    async fn get_public_files(&mut self) -> io::Result<GetPublicFilesResponse> {
+        self.mock_get_public_files.pop_front().expect("No mock requests available")
+    }
+
     async fn get_file(
         &mut self,
         username: &str,
@@ -211,6 +345,19 @@ impl Service for MockService {
         mock.1
     }
 
+    async fn publish_file(
+        &mut self,
+        username: &str,
+        filename: &str,
+    ) -> io::Result<PublishFileResponse> {
+        self.access_token.as_ref().expect("login not called yet");
+
+        let mock = self.mock_publish_file.pop_front().expect("No mock requests available");
+        assert_eq!(&mock.0 .0, username);
+        assert_eq!(&mock.0 .1, filename);
+        mock.1
+    }
+
     async fn delete_file(&mut self, username: &str, filename: &str) -> io::Result<()> {
         self.access_token.as_ref().expect("login not called yet");
 
@@ -233,12 +380,14 @@ impl Default for ClientTester {
         let mut tester = Tester::default();
         let console = tester.get_console();
         let storage = tester.get_storage();
+        let program = tester.get_program();
         let service = Rc::from(RefCell::from(MockService::default()));
         add_all(
             tester.get_machine(),
             service.clone(),
             console,
             storage,
+            program,
             "https://repl.example.com/",
         );
         ClientTester { tester, service }
@@ -294,6 +443,15 @@ impl<'a> ClientChecker<'a> {
         }
     }
 
+    /// See the wrapped `Checker::expect_clear` function for details.
+    pub fn expect_clear(self) -> Self {
+        Self {
+            checker: self.checker.expect_clear(),
+            service: self.service,
+            exp_access_token: self.exp_access_token,
+        }
+    }
+
     /// See the wrapped `Checker::expect_err` function for details.
     pub fn expect_err<S: Into<String>>(self, message: S) -> Self {
         Self {
@@ -330,6 +488,19 @@ impl<'a> ClientChecker<'a> {
         }
     }
 
+    /// See the wrapped `Checker::expect_program` function for details.
+    pub fn expect_program<S1: Into<String>, S2: Into<String>>(
+        self,
+        name: Option<S1>,
+        text: S2,
+    ) -> Self {
+        Self {
+            checker: self.checker.expect_program(name, text),
+            service: self.service,
+            exp_access_token: self.exp_access_token,
+        }
+    }
+
     /// See the wrapped `Checker::take_captured_out` function for details.
     #[must_use]
     pub fn take_captured_out(&mut self) -> Vec<CapturedOut> {
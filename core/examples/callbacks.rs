@@ -0,0 +1,92 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Host callbacks to script the behavior of a hypothetical game character.
+//!
+//! This example sets up a minimal EndBASIC interpreter and registers closures as the `HEAL`
+//! command and the `DISTANCE` function, which operate on state owned by the host.  The host then
+//! injects some variables into the machine before running the script and reads back the results
+//! computed by the script once it finishes.
+
+use endbasic_core::ast::{Value, VarType};
+use endbasic_core::exec::{Machine, StopReason};
+use endbasic_core::syms::{
+    ArgSpecBuilder, CallableMetadataBuilder, ClosureCommand, ClosureFunction,
+};
+use futures_lite::future::block_on;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Sample script that decides what the character does on every turn.
+const INPUT: &str = r#"
+IF health < 50 THEN HEAL 100 - health
+IF DISTANCE(enemy_x, enemy_y) < 10 THEN action = "attack" ELSE action = "wait"
+"#;
+
+fn main() {
+    // Health is owned by the host, but closures need to share it, so we put it behind an Rc.
+    let health = Rc::from(Cell::new(30));
+
+    let mut machine = Machine::default();
+    machine.add_command(ClosureCommand::new(
+        CallableMetadataBuilder::new("HEAL", VarType::Void)
+            .with_syntax("points%")
+            .with_category("Demonstration")
+            .with_description("Restores the given number of health points.")
+            .build(),
+        ArgSpecBuilder::new().required("points%", VarType::Integer).build(),
+        {
+            let health = health.clone();
+            move |args, _machine| {
+                let points = args.get::<i32>(0);
+                println!("Healing {} points", points);
+                health.set(health.get() + points);
+                Ok(())
+            }
+        },
+    ));
+    machine.add_function(ClosureFunction::new(
+        CallableMetadataBuilder::new("DISTANCE", VarType::Double)
+            .with_syntax("x#, y#")
+            .with_category("Demonstration")
+            .with_description("Computes the distance from the character to the given point.")
+            .build(),
+        ArgSpecBuilder::new()
+            .required("x#", VarType::Double)
+            .required("y#", VarType::Double)
+            .build(),
+        |args, _symbols| {
+            let (x, y) = (args.get::<f64>(0), args.get::<f64>(1));
+            Ok(Value::Double((x * x + y * y).sqrt()))
+        },
+    ));
+
+    // Inject the inputs to the script.
+    machine.set_var("health", health.get()).expect("Cannot set health");
+    machine.set_var("enemy_x", 3).expect("Cannot set enemy_x");
+    machine.set_var("enemy_y", 4.5).expect("Cannot set enemy_y");
+
+    match block_on(machine.exec(&mut INPUT.as_bytes())).expect("Execution error") {
+        StopReason::Eof => (),
+        StopReason::Exited(i) => println!("Script explicitly exited with code {}", i),
+        StopReason::Break => (), // Ignore signals.
+        StopReason::Paused(_) => unreachable!("Breakpoints only apply to exec_program"),
+        StopReason::LimitExceeded(_) => unreachable!("No limits were configured"),
+    }
+
+    // Read back the results of the script.
+    let action = machine.get_var::<String>("action").expect("Script did not set action");
+    println!("Health is now {}; next action is {}", health.get(), action);
+}
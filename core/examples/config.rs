@@ -41,6 +41,8 @@ fn main() {
             StopReason::Eof => break,
             StopReason::Exited(i) => println!("Script explicitly exited with code {}", i),
             StopReason::Break => (), // Ignore signals.
+            StopReason::Paused(_) => unreachable!("Breakpoints only apply to exec_program"),
+            StopReason::LimitExceeded(_) => unreachable!("No limits were configured"),
         }
     }
 
@@ -48,6 +48,16 @@ pub struct IntegerSpan {
     pub pos: LineCol,
 }
 
+/// Components of a long integer literal expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LongSpan {
+    /// The long integer literal.
+    pub value: i64,
+
+    /// Starting position of the literal.
+    pub pos: LineCol,
+}
+
 /// Components of a string literal expression.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TextSpan {
@@ -113,6 +123,8 @@ pub enum Expr {
     Double(DoubleSpan),
     /// A literal integer value.
     Integer(IntegerSpan),
+    /// A literal long integer value.
+    Long(LongSpan),
     /// A literal string value.
     Text(TextSpan),
 
@@ -182,6 +194,9 @@ pub enum VarType {
     /// An integer variable.
     Integer,
 
+    /// A 64-bit integer variable.
+    Long,
+
     /// A string variable.  This should really be called `String` but it would get confusing with
     /// the built-in Rust type.
     Text,
@@ -198,6 +213,7 @@ impl VarType {
             VarType::Boolean => "?",
             VarType::Double => "#",
             VarType::Integer => "%",
+            VarType::Long => "&",
             VarType::Text => "$",
             VarType::Void => "",
         }
@@ -210,6 +226,7 @@ impl VarType {
             VarType::Boolean => Value::Boolean(false),
             VarType::Double => Value::Double(0.0),
             VarType::Integer => Value::Integer(0),
+            VarType::Long => Value::Long(0),
             VarType::Text => Value::Text("".to_owned()),
             VarType::Void => panic!("Cannot represent a default value for void"),
         }
@@ -223,6 +240,7 @@ impl fmt::Display for VarType {
             VarType::Boolean => write!(f, "BOOLEAN"),
             VarType::Double => write!(f, "DOUBLE"),
             VarType::Integer => write!(f, "INTEGER"),
+            VarType::Long => write!(f, "LONG"),
             VarType::Text => write!(f, "STRING"),
             VarType::Void => panic!("Should not try to display a void type"),
         }
@@ -298,6 +316,9 @@ pub enum Value {
     /// An integer value.
     Integer(i32),
 
+    /// A 64-bit integer value.
+    Long(i64),
+
     /// A string value.
     Text(String), // Should be `String` but would get confusing with the built-in Rust type.
 }
@@ -320,12 +341,24 @@ impl From<i32> for Value {
     }
 }
 
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::Long(i)
+    }
+}
+
 impl From<&str> for Value {
     fn from(s: &str) -> Self {
         Value::Text(s.to_owned())
     }
 }
 
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -339,6 +372,7 @@ impl fmt::Display for Value {
                 write!(f, "{}", s)
             }
             Value::Integer(i) => write!(f, "{}", i),
+            Value::Long(i) => write!(f, "{}", i),
             Value::Text(s) => write!(f, "\"{}\"", s),
         }
     }
@@ -351,6 +385,7 @@ impl Value {
             Value::Boolean(_) => VarType::Boolean,
             Value::Double(_) => VarType::Double,
             Value::Integer(_) => VarType::Integer,
+            Value::Long(_) => VarType::Long,
             Value::Text(_) => VarType::Text,
         }
     }
@@ -371,6 +406,8 @@ impl Value {
             Value::Double(d) => format!(" {}", d),
             Value::Integer(i) if i.is_negative() => format!("{}", i),
             Value::Integer(i) => format!(" {}", i),
+            Value::Long(i) if i.is_negative() => format!("{}", i),
+            Value::Long(i) => format!(" {}", i),
             Value::Text(s) => s,
         }
     }
@@ -390,6 +427,9 @@ pub enum ArgSep {
 
     /// `AS` separator.
     As,
+
+    /// `TIMEOUT` separator.
+    Timeout,
 }
 
 /// Components of an array assignment statement.
@@ -600,6 +640,45 @@ pub struct ForSpan {
     pub body: Vec<Statement>,
 }
 
+/// Components of a `FOR EACH` statement.
+#[derive(Debug, PartialEq)]
+pub struct ForEachSpan {
+    /// Iterator name, expressed as a variable reference.
+    pub iter: VarRef,
+
+    /// Position of the iterator.
+    pub iter_pos: LineCol,
+
+    /// Reference to the array to iterate over.
+    pub array: VarRef,
+
+    /// Position of the array.
+    pub array_pos: LineCol,
+
+    /// Statements within the loop's body.
+    pub body: Vec<Statement>,
+
+    /// Position of the `NEXT` statement that closes the loop.
+    pub next_pos: LineCol,
+}
+
+/// Components of a `REDIM` statement.
+#[derive(Debug, PartialEq)]
+pub struct RedimSpan {
+    /// Name of the array to resize.  Type annotations are not allowed, hence why this is not a
+    /// `VarRef`.
+    pub name: String,
+
+    /// Position of the name.
+    pub name_pos: LineCol,
+
+    /// Expressions to compute the new dimensions of the array.
+    pub dimensions: Vec<Expr>,
+
+    /// Whether to keep the existing contents of the array.
+    pub preserve: bool,
+}
+
 /// Components of a `GOTO` or a `GOSUB` statement.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct GotoSpan {
@@ -636,6 +715,49 @@ pub enum OnErrorSpan {
     ResumeNext,
 }
 
+/// Components of an `ON FILECHANGE` statement.
+#[derive(Debug, Eq, PartialEq)]
+pub enum OnFileChangeSpan {
+    /// Components of an `ON FILECHANGE GOSUB @label` statement.
+    Gosub(GotoSpan),
+
+    /// Components of an `ON FILECHANGE GOSUB 0` statement.
+    Reset,
+}
+
+/// Components of an `ON GPIO` statement.
+#[derive(Debug, PartialEq)]
+pub struct OnGpioSpan {
+    /// Expression that yields the number of the pin to watch.
+    pub pin: Expr,
+
+    /// Expression that yields the name of the edge to react to.
+    pub edge: Expr,
+
+    /// Target of the subroutine to call when the edge is detected, or none to remove the handler.
+    pub handler: Option<GotoSpan>,
+}
+
+/// Components of an `ON INTERVAL` statement.
+#[derive(Debug, PartialEq)]
+pub struct OnIntervalSpan {
+    /// Expression that yields the number of milliseconds between calls to the handler.
+    pub period: Expr,
+
+    /// Target of the subroutine to call periodically, or none to remove the handler.
+    pub handler: Option<GotoSpan>,
+}
+
+/// Components of an `ON KEY` statement.
+#[derive(Debug, PartialEq)]
+pub struct OnKeySpan {
+    /// Expression that yields the name of the key to handle.
+    pub key: Expr,
+
+    /// Target of the subroutine to call when the key is pressed, or none to remove the handler.
+    pub handler: Option<GotoSpan>,
+}
+
 /// Components of a `RETURN` statement.
 #[derive(Debug, Eq, PartialEq)]
 pub struct ReturnSpan {
@@ -742,6 +864,9 @@ pub enum Statement {
     /// Represents a `FOR` statement.
     For(ForSpan),
 
+    /// Represents a `FOR EACH` statement.
+    ForEach(ForEachSpan),
+
     /// Represents a `GOSUB` statement.
     Gosub(GotoSpan),
 
@@ -757,6 +882,21 @@ pub enum Statement {
     /// Represents an `ON ERROR` statement.
     OnError(OnErrorSpan),
 
+    /// Represents an `ON FILECHANGE` statement.
+    OnFileChange(OnFileChangeSpan),
+
+    /// Represents an `ON GPIO` statement.
+    OnGpio(OnGpioSpan),
+
+    /// Represents an `ON INTERVAL` statement.
+    OnInterval(OnIntervalSpan),
+
+    /// Represents an `ON KEY` statement.
+    OnKey(OnKeySpan),
+
+    /// Represents a `REDIM` statement.
+    Redim(RedimSpan),
+
     /// Represents a `RETURN` statement.
     Return(ReturnSpan),
 
@@ -51,6 +51,31 @@ pub struct JumpIfBoolSpan {
     pub error_msg: &'static str,
 }
 
+/// Components of a step in the iteration over the elements of an array.
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct ForEachNextSpan {
+    /// Reference to the array being iterated over.
+    pub array: VarRef,
+
+    /// Position of the array.
+    pub array_pos: LineCol,
+
+    /// Iterator that receives the value of the next element.
+    pub iter: VarRef,
+
+    /// Position of the iterator.
+    pub iter_pos: LineCol,
+
+    /// Name of the variable that holds the index of the next element to visit.
+    pub index: String,
+
+    /// Position of the `NEXT` statement that closes the loop.
+    pub next_pos: LineCol,
+
+    /// The address to jump to once all elements have been visited.
+    pub addr: Address,
+}
+
 /// Components of a change to the error handler.
 #[derive(Clone, Copy)]
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
@@ -65,6 +90,50 @@ pub enum ErrorHandlerSpan {
     ResumeNext,
 }
 
+/// Components of a change to the file change handler.
+#[derive(Clone, Copy)]
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub enum FileChangeHandlerSpan {
+    /// Calls the included address when a watched file changes.
+    Call(Address),
+
+    /// Removes the handler so that file changes are ignored.
+    None,
+}
+
+/// Components of a change to the handler of a GPIO edge.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct GpioHandlerSpan {
+    /// Expression that yields the number of the pin.
+    pub pin: Expr,
+
+    /// Expression that yields the name of the edge.
+    pub edge: Expr,
+
+    /// Address to call when the edge is detected, or none to remove the handler.
+    pub addr: Option<Address>,
+}
+
+/// Components of a change to the periodic handler.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct IntervalHandlerSpan {
+    /// Expression that yields the number of milliseconds between calls to the handler.
+    pub period: Expr,
+
+    /// Address to call periodically, or none to remove the handler.
+    pub addr: Option<Address>,
+}
+
+/// Components of a change to the handler of a key.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct KeyHandlerSpan {
+    /// Expression that yields the name of the key.
+    pub key: Expr,
+
+    /// Address to call when the key is pressed, or none to remove the handler.
+    pub addr: Option<Address>,
+}
+
 /// Components of a request to unset a variable.
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct UnsetSpan {
@@ -99,6 +168,9 @@ pub enum Instruction {
     /// Represents a request to terminate the program.
     End(EndSpan),
 
+    /// Represents a step in a `FOR EACH` loop, which jumps once the array is exhausted.
+    ForEachNext(ForEachNextSpan),
+
     /// Represents an unconditional jump.
     Jump(JumpSpan),
 
@@ -114,16 +186,64 @@ pub enum Instruction {
     /// Represents an instruction that does nothing.
     Nop,
 
+    /// Represents an array resize.
+    Redim(RedimSpan),
+
     /// Represents a return after a call.
     Return(ReturnSpan),
 
     /// Represents a change in the error handler state.
     SetErrorHandler(ErrorHandlerSpan),
 
+    /// Represents a change in the file change handler state.
+    SetFileChangeHandler(FileChangeHandlerSpan),
+
+    /// Represents a change in the handler of a GPIO edge.
+    SetGpioHandler(GpioHandlerSpan),
+
+    /// Represents a change in the periodic handler.
+    SetIntervalHandler(IntervalHandlerSpan),
+
+    /// Represents a change in the handler of a key.
+    SetKeyHandler(KeyHandlerSpan),
+
     /// Represents a request to unset a variable.
     Unset(UnsetSpan),
 }
 
+impl Instruction {
+    /// Returns the position in the source code of the statement that generated this instruction,
+    /// if known.
+    ///
+    /// Instructions synthesized by the compiler to implement control flow (such as jumps) do not
+    /// carry a position.
+    pub fn pos(&self) -> Option<LineCol> {
+        match self {
+            Instruction::ArrayAssignment(span) => Some(span.vref_pos),
+            Instruction::Assignment(span) => Some(span.vref_pos),
+            Instruction::BuiltinCall(span) => Some(span.name_pos),
+            Instruction::Call(_) => None,
+            Instruction::Dim(span) => Some(span.name_pos),
+            Instruction::DimArray(span) => Some(span.name_pos),
+            Instruction::End(span) => span.code.as_ref().map(Expr::start_pos),
+            Instruction::ForEachNext(span) => Some(span.iter_pos),
+            Instruction::Jump(_) => None,
+            Instruction::JumpIfDefined(_) => None,
+            Instruction::JumpIfTrue(span) => Some(span.cond.start_pos()),
+            Instruction::JumpIfNotTrue(span) => Some(span.cond.start_pos()),
+            Instruction::Nop => None,
+            Instruction::Redim(span) => Some(span.name_pos),
+            Instruction::Return(span) => Some(span.pos),
+            Instruction::SetErrorHandler(_) => None,
+            Instruction::SetFileChangeHandler(_) => None,
+            Instruction::SetGpioHandler(span) => Some(span.pin.start_pos()),
+            Instruction::SetIntervalHandler(span) => Some(span.period.start_pos()),
+            Instruction::SetKeyHandler(span) => Some(span.key.start_pos()),
+            Instruction::Unset(span) => Some(span.pos),
+        }
+    }
+}
+
 /// Representation of a compiled program.
 #[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct Image {
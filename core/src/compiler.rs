@@ -45,6 +45,10 @@ enum FixupType {
     Gosub,
     Goto,
     OnError,
+    OnFileChange,
+    OnGpio,
+    OnInterval,
+    OnKey,
 }
 
 /// Describes a location in the code needs fixing up after all addresses have been laid out.
@@ -74,6 +78,26 @@ impl Fixup {
     fn from_on_error(span: GotoSpan) -> Self {
         Self { target: span.target, target_pos: span.target_pos, ftype: FixupType::OnError }
     }
+
+    /// Constructs a `Fixup` for a `ON FILECHANGE GOSUB` instruction.
+    fn from_on_file_change(span: GotoSpan) -> Self {
+        Self { target: span.target, target_pos: span.target_pos, ftype: FixupType::OnFileChange }
+    }
+
+    /// Constructs a `Fixup` for a `ON GPIO GOSUB` instruction.
+    fn from_on_gpio(span: GotoSpan) -> Self {
+        Self { target: span.target, target_pos: span.target_pos, ftype: FixupType::OnGpio }
+    }
+
+    /// Constructs a `Fixup` for a `ON INTERVAL GOSUB` instruction.
+    fn from_on_interval(span: GotoSpan) -> Self {
+        Self { target: span.target, target_pos: span.target_pos, ftype: FixupType::OnInterval }
+    }
+
+    /// Constructs a `Fixup` for a `ON KEY GOSUB` instruction.
+    fn from_on_key(span: GotoSpan) -> Self {
+        Self { target: span.target, target_pos: span.target_pos, ftype: FixupType::OnKey }
+    }
 }
 
 /// Compilation context to accumulate the results of the translation of various translation units.
@@ -92,6 +116,9 @@ struct Compiler {
     /// Current number of `SELECT` statements, needed to assign internal variable names.
     selects: usize,
 
+    /// Current number of `FOR EACH` statements, needed to assign internal variable names.
+    for_eachs: usize,
+
     /// Mapping of discovered labels to the addresses where they are.
     labels: HashMap<String, Address>,
 
@@ -133,6 +160,14 @@ impl Compiler {
         format!("0select{}", selects)
     }
 
+    /// Generates an internal variable name to hold the index of the next element to visit in a
+    /// `FOR EACH` loop.
+    ///
+    /// This relies on the same trick as `select_test_var_name`.
+    fn for_each_index_var_name(for_eachs: usize) -> String {
+        format!("0foreach{}", for_eachs)
+    }
+
     /// Compiles a `DO` loop and appends its instructions to the compilation context.
     fn compile_do(&mut self, span: DoSpan) -> Result<()> {
         self.exit_do_level.1 += 1;
@@ -204,6 +239,7 @@ impl Compiler {
             span.iter.ref_type() == VarType::Auto
                 || span.iter.ref_type() == VarType::Double
                 || span.iter.ref_type() == VarType::Integer
+                || span.iter.ref_type() == VarType::Long
         );
 
         if span.iter_double && span.iter.ref_type() == VarType::Auto {
@@ -249,6 +285,38 @@ impl Compiler {
         Ok(())
     }
 
+    /// Compiles a `FOR EACH` loop and appends its instructions to the compilation context.
+    fn compile_for_each(&mut self, span: ForEachSpan) -> Result<()> {
+        self.for_eachs += 1;
+        let index = Compiler::for_each_index_var_name(self.for_eachs);
+
+        self.emit(Instruction::Assignment(AssignmentSpan {
+            vref: VarRef::new(index.clone(), VarType::Auto),
+            vref_pos: span.iter_pos,
+            expr: Expr::Integer(IntegerSpan { value: 0, pos: span.iter_pos }),
+        }));
+
+        let start_pc = self.emit(Instruction::Nop);
+
+        self.compile_many(span.body)?;
+
+        self.emit(Instruction::Jump(JumpSpan { addr: start_pc }));
+
+        self.instrs[start_pc] = Instruction::ForEachNext(ForEachNextSpan {
+            array: span.array,
+            array_pos: span.array_pos,
+            iter: span.iter,
+            iter_pos: span.iter_pos,
+            index: index.clone(),
+            next_pos: span.next_pos,
+            addr: self.next_pc,
+        });
+
+        self.emit(Instruction::Unset(UnsetSpan { name: index, pos: span.next_pos }));
+
+        Ok(())
+    }
+
     /// Compiles an `IF` statement and appends its instructions to the compilation context.
     fn compile_if(&mut self, span: IfSpan) -> Result<()> {
         let mut end_pcs = vec![];
@@ -298,6 +366,56 @@ impl Compiler {
         }
     }
 
+    /// Compiles an `ON FILECHANGE` statement and appends its instructions to the compilation
+    /// context.
+    fn compile_on_file_change(&mut self, span: OnFileChangeSpan) {
+        match span {
+            OnFileChangeSpan::Gosub(span) => {
+                let gosub_pc = self.emit(Instruction::Nop);
+                self.fixups.insert(gosub_pc, Fixup::from_on_file_change(span));
+            }
+            OnFileChangeSpan::Reset => {
+                self.emit(Instruction::SetFileChangeHandler(FileChangeHandlerSpan::None));
+            }
+        }
+    }
+
+    /// Compiles an `ON GPIO` statement and appends its instructions to the compilation context.
+    fn compile_on_gpio(&mut self, span: OnGpioSpan) {
+        let pc = self.emit(Instruction::SetGpioHandler(GpioHandlerSpan {
+            pin: span.pin,
+            edge: span.edge,
+            addr: None,
+        }));
+        if let Some(handler) = span.handler {
+            // The address of the handler is filled in once all labels are known.
+            self.fixups.insert(pc, Fixup::from_on_gpio(handler));
+        }
+    }
+
+    /// Compiles an `ON INTERVAL` statement and appends its instructions to the compilation
+    /// context.
+    fn compile_on_interval(&mut self, span: OnIntervalSpan) {
+        let pc = self.emit(Instruction::SetIntervalHandler(IntervalHandlerSpan {
+            period: span.period,
+            addr: None,
+        }));
+        if let Some(handler) = span.handler {
+            // The address of the handler is filled in once all labels are known.
+            self.fixups.insert(pc, Fixup::from_on_interval(handler));
+        }
+    }
+
+    /// Compiles an `ON KEY` statement and appends its instructions to the compilation context.
+    fn compile_on_key(&mut self, span: OnKeySpan) {
+        let pc =
+            self.emit(Instruction::SetKeyHandler(KeyHandlerSpan { key: span.key, addr: None }));
+        if let Some(handler) = span.handler {
+            // The address of the handler is filled in once all labels are known.
+            self.fixups.insert(pc, Fixup::from_on_key(handler));
+        }
+    }
+
     /// Generates the expression to evaluate a list of `guards`, which are compared against the
     /// test expression stored in `test_vref`.
     fn compile_case_guards(test_vref: &VarRef, guards: Vec<CaseGuardSpan>) -> Option<Expr> {
@@ -467,6 +585,10 @@ impl Compiler {
                 self.compile_for(span)?;
             }
 
+            Statement::ForEach(span) => {
+                self.compile_for_each(span)?;
+            }
+
             Statement::Gosub(span) => {
                 let gosub_pc = self.emit(Instruction::Nop);
                 self.fixups.insert(gosub_pc, Fixup::from_gosub(span));
@@ -494,6 +616,26 @@ impl Compiler {
                 self.compile_on_error(span);
             }
 
+            Statement::OnFileChange(span) => {
+                self.compile_on_file_change(span);
+            }
+
+            Statement::OnGpio(span) => {
+                self.compile_on_gpio(span);
+            }
+
+            Statement::OnInterval(span) => {
+                self.compile_on_interval(span);
+            }
+
+            Statement::OnKey(span) => {
+                self.compile_on_key(span);
+            }
+
+            Statement::Redim(span) => {
+                self.emit(Instruction::Redim(span));
+            }
+
             Statement::Return(span) => {
                 self.emit(Instruction::Return(span));
             }
@@ -540,6 +682,22 @@ impl Compiler {
                 FixupType::OnError => {
                     self.instrs[pc] = Instruction::SetErrorHandler(ErrorHandlerSpan::Jump(addr))
                 }
+                FixupType::OnFileChange => {
+                    self.instrs[pc] =
+                        Instruction::SetFileChangeHandler(FileChangeHandlerSpan::Call(addr))
+                }
+                FixupType::OnGpio => match &mut self.instrs[pc] {
+                    Instruction::SetGpioHandler(span) => span.addr = Some(addr),
+                    _ => panic!("ON GPIO fixup must point to a GPIO handler instruction"),
+                },
+                FixupType::OnInterval => match &mut self.instrs[pc] {
+                    Instruction::SetIntervalHandler(span) => span.addr = Some(addr),
+                    _ => panic!("ON INTERVAL fixup must point to an interval handler instruction"),
+                },
+                FixupType::OnKey => match &mut self.instrs[pc] {
+                    Instruction::SetKeyHandler(span) => span.addr = Some(addr),
+                    _ => panic!("ON KEY fixup must point to a key handler instruction"),
+                },
             }
         }
         Ok(Image { instrs: self.instrs, data: self.data })
@@ -1033,6 +1191,47 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_compile_for_each() {
+        Tester::default()
+            .parse("FOR EACH x IN arr: a = FALSE: NEXT")
+            .compile()
+            .expect_instr(
+                0,
+                Instruction::Assignment(AssignmentSpan {
+                    vref: VarRef::new("0foreach1", VarType::Auto),
+                    vref_pos: lc(1, 10),
+                    expr: Expr::Integer(IntegerSpan { value: 0, pos: lc(1, 10) }),
+                }),
+            )
+            .expect_instr(
+                1,
+                Instruction::ForEachNext(ForEachNextSpan {
+                    array: VarRef::new("arr", VarType::Auto),
+                    array_pos: lc(1, 15),
+                    iter: VarRef::new("x", VarType::Auto),
+                    iter_pos: lc(1, 10),
+                    index: "0foreach1".to_owned(),
+                    next_pos: lc(1, 31),
+                    addr: 4,
+                }),
+            )
+            .expect_instr(
+                2,
+                Instruction::Assignment(AssignmentSpan {
+                    vref: VarRef::new("a", VarType::Auto),
+                    vref_pos: lc(1, 20),
+                    expr: Expr::Boolean(BooleanSpan { value: false, pos: lc(1, 24) }),
+                }),
+            )
+            .expect_instr(3, Instruction::Jump(JumpSpan { addr: 1 }))
+            .expect_instr(
+                4,
+                Instruction::Unset(UnsetSpan { name: "0foreach1".to_owned(), pos: lc(1, 31) }),
+            )
+            .check();
+    }
+
     #[test]
     fn test_compile_for_double_auto_iterator() {
         Tester::default()
@@ -1249,6 +1448,172 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_compile_on_file_change_reset() {
+        Tester::default()
+            .parse("ON FILECHANGE GOSUB 0")
+            .compile()
+            .expect_instr(0, Instruction::SetFileChangeHandler(FileChangeHandlerSpan::None))
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_file_change_gosub_label() {
+        Tester::default()
+            .parse(
+                "ON FILECHANGE GOSUB @foo
+
+
+@foo",
+            )
+            .compile()
+            .expect_instr(0, Instruction::SetFileChangeHandler(FileChangeHandlerSpan::Call(1)))
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_file_change_gosub_unknown_label() {
+        Tester::default()
+            .parse("ON FILECHANGE GOSUB @foo")
+            .compile()
+            .expect_err("1:21: Unknown label foo")
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_gpio_reset() {
+        Tester::default()
+            .parse("ON GPIO 5, \"RISING\" GOSUB 0")
+            .compile()
+            .expect_instr(
+                0,
+                Instruction::SetGpioHandler(GpioHandlerSpan {
+                    pin: Expr::Integer(IntegerSpan { value: 5, pos: lc(1, 9) }),
+                    edge: Expr::Text(TextSpan { value: "RISING".to_owned(), pos: lc(1, 12) }),
+                    addr: None,
+                }),
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_gpio_gosub_label() {
+        Tester::default()
+            .parse(
+                "ON GPIO 5, \"RISING\" GOSUB @foo
+
+
+@foo",
+            )
+            .compile()
+            .expect_instr(
+                0,
+                Instruction::SetGpioHandler(GpioHandlerSpan {
+                    pin: Expr::Integer(IntegerSpan { value: 5, pos: lc(1, 9) }),
+                    edge: Expr::Text(TextSpan { value: "RISING".to_owned(), pos: lc(1, 12) }),
+                    addr: Some(1),
+                }),
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_gpio_gosub_unknown_label() {
+        Tester::default()
+            .parse("ON GPIO 5, \"RISING\" GOSUB @foo")
+            .compile()
+            .expect_err("1:27: Unknown label foo")
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_interval_reset() {
+        Tester::default()
+            .parse("ON INTERVAL 100 GOSUB 0")
+            .compile()
+            .expect_instr(
+                0,
+                Instruction::SetIntervalHandler(IntervalHandlerSpan {
+                    period: Expr::Integer(IntegerSpan { value: 100, pos: lc(1, 13) }),
+                    addr: None,
+                }),
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_interval_gosub_label() {
+        Tester::default()
+            .parse(
+                "ON INTERVAL 100 GOSUB @foo
+
+
+@foo",
+            )
+            .compile()
+            .expect_instr(
+                0,
+                Instruction::SetIntervalHandler(IntervalHandlerSpan {
+                    period: Expr::Integer(IntegerSpan { value: 100, pos: lc(1, 13) }),
+                    addr: Some(1),
+                }),
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_interval_gosub_unknown_label() {
+        Tester::default()
+            .parse("ON INTERVAL 100 GOSUB @foo")
+            .compile()
+            .expect_err("1:23: Unknown label foo")
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_key_reset() {
+        Tester::default()
+            .parse("ON KEY \"F1\" GOSUB 0")
+            .compile()
+            .expect_instr(
+                0,
+                Instruction::SetKeyHandler(KeyHandlerSpan {
+                    key: Expr::Text(TextSpan { value: "F1".to_owned(), pos: lc(1, 8) }),
+                    addr: None,
+                }),
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_key_gosub_label() {
+        Tester::default()
+            .parse(
+                "ON KEY \"F1\" GOSUB @foo
+
+
+@foo",
+            )
+            .compile()
+            .expect_instr(
+                0,
+                Instruction::SetKeyHandler(KeyHandlerSpan {
+                    key: Expr::Text(TextSpan { value: "F1".to_owned(), pos: lc(1, 8) }),
+                    addr: Some(1),
+                }),
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_compile_on_key_gosub_unknown_label() {
+        Tester::default()
+            .parse("ON KEY \"F1\" GOSUB @foo")
+            .compile()
+            .expect_err("1:19: Unknown label foo")
+            .check();
+    }
+
     /// Tests that parsing one or more `guards` as supplied after `CASE` yields the expected
     /// expression in `exp_expr`.
     ///
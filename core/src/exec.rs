@@ -19,15 +19,20 @@ use crate::ast::*;
 use crate::bytecode::*;
 use crate::compiler;
 use crate::eval;
+use crate::optimizer;
 use crate::parser;
 use crate::reader::LineCol;
-use crate::syms::{CallError, CallableMetadata, Command, Function, Symbol, Symbols};
+use crate::syms::{CallError, CallableMetadata, Command, FromValue, Function, Symbol, Symbols};
 use crate::value;
 use async_channel::{Receiver, Sender, TryRecvError};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::future::Future;
 use std::io;
+use std::mem;
 use std::pin::Pin;
 use std::rc::Rc;
+use std::time::Duration;
 
 /// Execution errors.
 #[derive(Debug, thiserror::Error)]
@@ -133,6 +138,19 @@ fn new_syntax_error<T, S: Into<String>>(pos: LineCol, message: S) -> Result<T> {
 pub enum Signal {
     /// Asks the machine to stop execution of the currently-running program.
     Break,
+
+    /// Tells the machine that the watched file at the given location changed, which causes the
+    /// handler installed with `ON FILECHANGE` to be called, if any.
+    FileChange(String),
+
+    /// Tells the machine that the GPIO pin with the given number changed level, which causes the
+    /// handler installed with `ON GPIO` for that pin and edge to be called, if any.  The boolean
+    /// is true for rising edges and false for falling edges.
+    Gpio(u8, bool),
+
+    /// Tells the machine that the key with the given name was pressed, which causes the handler
+    /// installed with `ON KEY` for that key to be called, if any.
+    Key(String),
 }
 
 /// Describes how the machine stopped execution while it was running a script via `exec()`.
@@ -142,11 +160,19 @@ pub enum StopReason {
     /// Execution terminates because the machine reached the end of the input.
     Eof,
 
-    /// Execution terminated because the machine was asked to terminate with `END`.
+    /// Execution terminated because the machine was asked to terminate with `END` or `EXIT`.
     Exited(u8),
 
     /// Execution terminated because the machine received a break signal.
     Break,
+
+    /// Execution was suspended before running the statement at the given position because it hit
+    /// a breakpoint or because single-stepping was requested.  Use `Machine::resume` to continue.
+    Paused(LineCol),
+
+    /// Execution terminated because the program exceeded one of the limits configured with
+    /// `Machine::set_limits`.
+    LimitExceeded(Limit),
 }
 
 impl StopReason {
@@ -155,6 +181,8 @@ impl StopReason {
         match self {
             StopReason::Eof => 0,
             StopReason::Exited(i) => *i as i32,
+            StopReason::Paused(_) => 0,
+            StopReason::LimitExceeded(_) => 1,
             StopReason::Break => {
                 // This mimics the behavior of typical Unix shells, which translate a signal to a
                 // numerical exit code, but this is not accurate.  First, because a CTRL+C sequence
@@ -177,19 +205,250 @@ pub trait Clearable {
 /// Type of the function used by the execution loop to yield execution.
 pub type YieldNowFn = Box<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + 'static>>>;
 
+/// Type of the function used by the execution loop to report that it reached a new line when
+/// tracing is enabled.
+pub type TraceFn = Box<dyn Fn(LineCol) -> io::Result<()>>;
+
+/// Type of the function used by `FOR EACH` to fetch the elements of collections that are stored
+/// outside of the machine and identified by integer handles.
+///
+/// Given a handle and an index, returns the element at that index or none if the index is past the
+/// end of the collection.  Returns an error message if the handle does not identify a collection.
+pub type CollectionElementFn =
+    Box<dyn Fn(i32, usize) -> std::result::Result<Option<Value>, String>>;
+
+/// Type of the function used by the profiler to query the current time.
+///
+/// The returned value must be monotonically increasing, but its origin is irrelevant.
+pub type ClockFn = Rc<dyn Fn() -> Duration>;
+
+/// Profiling data for a single line of a program.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LineProfile {
+    /// The line number.
+    pub line: usize,
+
+    /// Number of times execution entered this line from a different line, which matches the
+    /// number of times the line is reported when tracing.  A loop that spans a single line is
+    /// therefore only counted once.
+    pub count: u64,
+
+    /// Total wall time spent executing this line.
+    pub time: Duration,
+}
+
+/// Resource limits to apply to the execution of programs, which is useful to safely run untrusted
+/// code.  All limits are disabled by default.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Limits {
+    /// Maximum number of instructions to execute.
+    pub max_instructions: Option<u64>,
+
+    /// Maximum wall time to spend executing, as measured by the clock configured with
+    /// `Machine::set_clock`.  Ignored if the machine has no clock.
+    pub max_time: Option<Duration>,
+
+    /// Maximum number of cells that all arrays can hold together.  Checked by `DIM` and `REDIM`.
+    pub max_array_cells: Option<usize>,
+
+    /// Maximum length in bytes of strings.  Checked when assigning to variables and arrays.
+    pub max_string_bytes: Option<usize>,
+
+    /// Maximum nesting of `GOSUB` calls.
+    pub max_call_depth: Option<usize>,
+}
+
+/// Identifies the limit that caused execution to stop.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Limit {
+    /// The `max_instructions` limit.
+    Instructions,
+
+    /// The `max_time` limit.
+    Time,
+
+    /// The `max_array_cells` limit.
+    ArrayCells,
+
+    /// The `max_string_bytes` limit.
+    StringBytes,
+
+    /// The `max_call_depth` limit.
+    CallDepth,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Limit::Instructions => "instruction count",
+            Limit::Time => "time",
+            Limit::ArrayCells => "array size",
+            Limit::StringBytes => "string length",
+            Limit::CallDepth => "call depth",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Resource usage of the running program, tracked to enforce the `Limits`.
+#[derive(Default)]
+struct Usage {
+    /// Number of nested runs in progress.  Usage is only reset when the outermost run starts so
+    /// that programs cannot escape their limits by starting other programs.
+    depth: usize,
+
+    /// Number of instructions executed so far.
+    instrs: u64,
+
+    /// Time at which the outermost run started, as reported by the machine's clock.
+    start: Option<Duration>,
+
+    /// Limit that was exceeded, if any, so that all nested runs stop.
+    exceeded: Option<Limit>,
+}
+
+/// Profiling state of the machine.
+#[derive(Default)]
+struct Profiler {
+    /// Function to query the current time while profiling is enabled, or none if disabled.
+    clock: Option<ClockFn>,
+
+    /// Profiling data collected so far, keyed by line number.
+    lines: HashMap<usize, LineProfile>,
+}
+
+/// Maximum number of events of each kind that can wait for their handler to be called.  Events
+/// that arrive while the queue is full are dropped.
+const MAX_PENDING_EVENTS: usize = 64;
+
+/// Tracks whether an event handler is running to prevent calling it again before it returns.
+#[derive(Default)]
+struct HandlerGuard {
+    /// Depth of the address stack while the handler runs, if it is running.
+    active_depth: Option<usize>,
+}
+
+impl HandlerGuard {
+    /// Returns true if the handler cannot be called given the current `depth` of the address
+    /// stack, which is the case while it runs and right after it returns.  This ensures that at
+    /// least one other instruction runs between two consecutive calls to the handler.
+    fn is_busy(&mut self, depth: usize) -> bool {
+        match self.active_depth {
+            Some(active_depth) => {
+                if depth < active_depth {
+                    self.active_depth = None;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records that the handler was just called, leaving the address stack at `depth`.
+    fn enter(&mut self, depth: usize) {
+        self.active_depth = Some(depth);
+    }
+}
+
+/// Appends `event` to the `queue` of pending events unless the queue is full.
+fn queue_event<T>(queue: &mut VecDeque<T>, event: T) {
+    if queue.len() < MAX_PENDING_EVENTS {
+        queue.push_back(event);
+    }
+}
+
+/// State of the periodic handler installed with `ON INTERVAL`.
+struct IntervalHandler {
+    /// Address of the handler.
+    addr: Address,
+
+    /// Amount of time between two consecutive calls to the handler.
+    period: Duration,
+
+    /// Time, as reported by the machine's clock, at which the handler must be called next.
+    next: Duration,
+
+    /// Prevents calling the handler again before it has returned.
+    guard: HandlerGuard,
+}
+
 /// Machine state for the execution of an individual chunk of code.
 struct Context {
     pc: Address,
     addr_stack: Vec<Address>,
     err_handler: ErrorHandlerSpan,
+    file_change_handler: FileChangeHandlerSpan,
+
+    /// Prevents calling the handler installed with `ON FILECHANGE` again before it has returned.
+    file_change_guard: HandlerGuard,
+
+    /// Prevents calling any of the handlers installed with `ON GPIO` before the running one, if
+    /// any, has returned.
+    gpio_guard: HandlerGuard,
+
+    /// Prevents calling any of the handlers installed with `ON KEY` before the running one, if
+    /// any, has returned.
+    key_guard: HandlerGuard,
+
+    /// Addresses of the handlers installed with `ON GPIO`, keyed by the pin number and by whether
+    /// the edge is rising or not.
+    gpio_handlers: HashMap<(u8, bool), Address>,
+
+    /// Addresses of the handlers installed with `ON KEY`, keyed by the uppercase key name.
+    key_handlers: HashMap<String, Address>,
+
+    /// Periodic handler installed with `ON INTERVAL`, if any.
+    interval_handler: Option<IntervalHandler>,
+
+    /// Line of the last executed instruction that carried a position, used to detect when
+    /// execution moves to a different line.
+    last_line: Option<usize>,
 }
 
 impl Default for Context {
     fn default() -> Self {
-        Self { pc: 0, addr_stack: vec![], err_handler: ErrorHandlerSpan::None }
+        Self {
+            pc: 0,
+            addr_stack: vec![],
+            err_handler: ErrorHandlerSpan::None,
+            file_change_handler: FileChangeHandlerSpan::None,
+            file_change_guard: HandlerGuard::default(),
+            gpio_guard: HandlerGuard::default(),
+            key_guard: HandlerGuard::default(),
+            gpio_handlers: HashMap::default(),
+            key_handlers: HashMap::default(),
+            interval_handler: None,
+            last_line: None,
+        }
     }
 }
 
+/// State of a program whose execution was paused by the debugger.
+struct Suspended {
+    instrs: Vec<Instruction>,
+    data: Vec<Option<Value>>,
+    context: Context,
+}
+
+/// Debugging facilities that apply to programs started via `Machine::exec_program`.
+#[derive(Default)]
+struct Debugger {
+    /// Lines on which execution must pause before running the first statement found on them.
+    breakpoints: HashSet<usize>,
+
+    /// Whether execution must pause as soon as it reaches a new line.
+    stepping: bool,
+
+    /// Function to call every time execution reaches a new line, if tracing is enabled.
+    tracer: Option<TraceFn>,
+
+    /// Profiling state and collected data.
+    profiler: Profiler,
+
+    /// Execution state of the paused program, if any.
+    suspended: Option<Suspended>,
+}
+
 /// Executes an EndBASIC program and tracks its state.
 pub struct Machine {
     symbols: Symbols,
@@ -197,7 +456,16 @@ pub struct Machine {
     yield_now_fn: Option<YieldNowFn>,
     signals_chan: (Sender<Signal>, Receiver<Signal>),
     stop_reason: Option<StopReason>,
+    pending_file_changes: VecDeque<String>,
+    pending_gpio: VecDeque<(u8, bool)>,
+    pending_keys: VecDeque<String>,
+    clock: Option<ClockFn>,
+    collection_element_fn: Option<CollectionElementFn>,
     data: Vec<Option<Value>>,
+    pending_chain: Option<Image>,
+    limits: Limits,
+    usage: Usage,
+    debugger: Debugger,
 }
 
 impl Default for Machine {
@@ -215,7 +483,16 @@ impl Machine {
             yield_now_fn: None,
             signals_chan: signals,
             stop_reason: None,
+            pending_file_changes: VecDeque::default(),
+            pending_gpio: VecDeque::default(),
+            pending_keys: VecDeque::default(),
+            clock: None,
+            collection_element_fn: None,
             data: vec![],
+            pending_chain: None,
+            limits: Limits::default(),
+            usage: Usage::default(),
+            debugger: Debugger::default(),
         }
     }
 
@@ -231,10 +508,42 @@ impl Machine {
             yield_now_fn,
             signals_chan: signals,
             stop_reason: None,
+            pending_file_changes: VecDeque::default(),
+            pending_gpio: VecDeque::default(),
+            pending_keys: VecDeque::default(),
+            clock: None,
+            collection_element_fn: None,
             data: vec![],
+            pending_chain: None,
+            limits: Limits::default(),
+            usage: Usage::default(),
+            debugger: Debugger::default(),
         }
     }
 
+    /// Sets the `clock` used to determine when to call the handler installed with `ON INTERVAL`.
+    ///
+    /// Machines without a clock reject attempts to install such a handler.
+    pub fn set_clock(&mut self, clock: ClockFn) {
+        self.clock = Some(clock);
+    }
+
+    /// Sets the function that `FOR EACH` uses to iterate over collections identified by the
+    /// integer handles stored in variables.
+    ///
+    /// Machines without this function only allow iterating over arrays.
+    pub fn set_collection_element_fn(&mut self, element_fn: CollectionElementFn) {
+        self.collection_element_fn = Some(element_fn);
+    }
+
+    /// Sets the resource `limits` that apply to the execution of programs.
+    ///
+    /// When a limit is exceeded, execution stops with `StopReason::LimitExceeded`.  Usage is
+    /// accounted for from the moment `exec`, `exec_program` or `resume` are called.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
     /// Registers the given clearable.
     ///
     /// In the common case, functions and commands hold a reference to the out-of-machine state
@@ -260,12 +569,86 @@ impl Machine {
         self.signals_chan.0.clone()
     }
 
+    /// Obtains a channel via which to wait for signals while a builtin call blocks execution.
+    ///
+    /// Signals consumed from this channel are not seen by the machine, so callers must send the
+    /// ones they do not handle back via the channel returned by `get_signals_tx`.
+    pub fn get_signals_rx(&self) -> Receiver<Signal> {
+        self.signals_chan.1.clone()
+    }
+
     /// Resets the state of the machine by clearing all variable.
+    ///
+    /// This also discards any program that was paused by the debugger.
     pub fn clear(&mut self) {
         for clearable in self.clearables.as_slice() {
             clearable.reset_state(&mut self.symbols);
         }
         self.symbols.clear();
+        self.debugger.suspended = None;
+    }
+
+    /// Sets a breakpoint on `line` so that programs started via `exec_program` pause before
+    /// running the first statement on that line.
+    pub fn add_breakpoint(&mut self, line: usize) {
+        self.debugger.breakpoints.insert(line);
+    }
+
+    /// Removes the breakpoint on `line`.  Returns false if there was no such breakpoint.
+    pub fn remove_breakpoint(&mut self, line: usize) -> bool {
+        self.debugger.breakpoints.remove(&line)
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.debugger.breakpoints.clear();
+    }
+
+    /// Returns the lines with breakpoints, in ascending order.
+    pub fn breakpoints(&self) -> Vec<usize> {
+        let mut lines = self.debugger.breakpoints.iter().copied().collect::<Vec<usize>>();
+        lines.sort_unstable();
+        lines
+    }
+
+    /// Enables tracing of programs started via `exec_program` by calling `tracer` every time
+    /// execution reaches a new line, or disables tracing if `tracer` is none.
+    pub fn set_tracer(&mut self, tracer: Option<TraceFn>) {
+        self.debugger.tracer = tracer;
+    }
+
+    /// Returns true if tracing is enabled.
+    pub fn is_tracing(&self) -> bool {
+        self.debugger.tracer.is_some()
+    }
+
+    /// Enables profiling of programs started via `exec_program`, using `clock` to measure the time
+    /// spent on each line.  Any previously-collected profiling data is discarded.
+    pub fn enable_profiling(&mut self, clock: ClockFn) {
+        self.debugger.profiler = Profiler { clock: Some(clock), lines: HashMap::default() };
+    }
+
+    /// Disables profiling.  The data collected so far is retained until profiling is reenabled.
+    pub fn disable_profiling(&mut self) {
+        self.debugger.profiler.clock = None;
+    }
+
+    /// Returns true if profiling is enabled.
+    pub fn is_profiling(&self) -> bool {
+        self.debugger.profiler.clock.is_some()
+    }
+
+    /// Returns the profiling data collected so far, sorted by line number.
+    pub fn profile(&self) -> Vec<LineProfile> {
+        let mut lines =
+            self.debugger.profiler.lines.values().cloned().collect::<Vec<LineProfile>>();
+        lines.sort_unstable_by_key(|p| p.line);
+        lines
+    }
+
+    /// Returns true if there is a paused program that can be continued via `resume`.
+    pub fn is_paused(&self) -> bool {
+        self.debugger.suspended.is_some()
     }
 
     /// Obtains immutable access to the data values available during the *current* execution.
@@ -322,24 +705,137 @@ impl Machine {
         }
     }
 
+    /// Retrieves the variable `name` converted to the native type `T`.  Fails if the variable is
+    /// not defined or if its type is not compatible with `T`.
+    ///
+    /// This is useful to read the results of a program once it has finished running.
+    pub fn get_var<T: FromValue>(&self, name: &str) -> Result<T> {
+        let value = self
+            .symbols
+            .get_var(&VarRef::new(name, VarType::Auto))
+            .map_err(Error::from_value_error_without_pos)?;
+        match T::from_value(value) {
+            Some(value) => Ok(value),
+            None => Err(Error::from_value_error_without_pos(value::Error::new(format!(
+                "Incompatible type {} in variable {}",
+                value.as_vartype(),
+                name
+            )))),
+        }
+    }
+
+    /// Sets the variable `name` to `value`, defining it if it does not yet exist.  Fails if the
+    /// variable already exists with a different type or if `name` is used by another symbol.
+    ///
+    /// This is useful to pass inputs to a program before it starts running.
+    pub fn set_var<V: Into<Value>>(&mut self, name: &str, value: V) -> Result<()> {
+        self.symbols
+            .set_var(&VarRef::new(name, VarType::Auto), value.into())
+            .map_err(Error::from_value_error_without_pos)
+    }
+
     /// Returns true if execution should stop because we have hit a stop condition.
     async fn should_stop(&mut self) -> bool {
         if let Some(yield_now) = self.yield_now_fn.as_ref() {
             (yield_now)().await;
         }
 
-        match self.signals_chan.1.try_recv() {
-            Ok(Signal::Break) => self.stop_reason = Some(StopReason::Break),
-            Err(TryRecvError::Empty) => (),
-            Err(TryRecvError::Closed) => panic!("Channel unexpectedly closed"),
+        loop {
+            match self.signals_chan.1.try_recv() {
+                Ok(Signal::Break) => self.stop_reason = Some(StopReason::Break),
+                Ok(Signal::FileChange(location)) => {
+                    queue_event(&mut self.pending_file_changes, location)
+                }
+                Ok(Signal::Gpio(pin, rising)) => queue_event(&mut self.pending_gpio, (pin, rising)),
+                Ok(Signal::Key(name)) => queue_event(&mut self.pending_keys, name),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Closed) => panic!("Channel unexpectedly closed"),
+            }
         }
 
         self.stop_reason.is_some()
     }
 
+    /// Records that `limit` was exceeded and requests execution to stop.
+    fn exceed(&mut self, limit: Limit) {
+        self.usage.exceeded = Some(limit);
+        self.stop_reason = Some(StopReason::LimitExceeded(limit));
+    }
+
+    /// Accounts for the execution of a new instruction and checks if doing so is within the
+    /// limits.  Returns false if execution must stop.
+    fn check_progress(&mut self) -> bool {
+        if let Some(limit) = self.usage.exceeded {
+            self.exceed(limit);
+            return false;
+        }
+
+        self.usage.instrs += 1;
+        if let Some(max) = self.limits.max_instructions {
+            if self.usage.instrs > max {
+                self.exceed(Limit::Instructions);
+                return false;
+            }
+        }
+
+        if let (Some(max), Some(start), Some(clock)) =
+            (self.limits.max_time, self.usage.start, self.clock.as_ref())
+        {
+            let elapsed = clock().saturating_sub(start);
+            if elapsed > max {
+                self.exceed(Limit::Time);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Checks if allocating an array with dimensions `ds` is within the limits, given that the
+    /// allocation replaces an existing array with `replaced` cells.  Returns false if execution
+    /// must stop.
+    fn check_array_cells(&mut self, ds: &[usize], replaced: usize) -> bool {
+        let max = match self.limits.max_array_cells {
+            Some(max) => max,
+            None => return true,
+        };
+
+        let mut used = 0;
+        for symbol in self.symbols.as_hashmap().values() {
+            if let Symbol::Array(array) = symbol {
+                used += array.values().len();
+            }
+        }
+        let total = ds
+            .iter()
+            .try_fold(1usize, |acc, d| acc.checked_mul(*d))
+            .and_then(|requested| (used - replaced).checked_add(requested));
+        match total {
+            Some(total) if total <= max => true,
+            _ => {
+                self.exceed(Limit::ArrayCells);
+                false
+            }
+        }
+    }
+
+    /// Checks if storing `value` is within the limits.  Returns false if execution must stop.
+    fn check_value(&mut self, value: &Value) -> bool {
+        if let (Some(max), Value::Text(s)) = (self.limits.max_string_bytes, value) {
+            if s.len() > max {
+                self.exceed(Limit::StringBytes);
+                return false;
+            }
+        }
+        true
+    }
+
     /// Handles a variable assignment.
     async fn assign(&mut self, span: &AssignmentSpan) -> Result<()> {
         let value = span.expr.eval(&mut self.symbols).await?;
+        if !self.check_value(&value) {
+            return Ok(());
+        }
         self.symbols
             .set_var(&span.vref, value)
             .map_err(|e| Error::from_value_error(e, span.vref_pos))?;
@@ -362,6 +858,9 @@ impl Machine {
         }
 
         let value = span.expr.eval(&mut self.symbols).await?;
+        if !self.check_value(&value) {
+            return Ok(());
+        }
 
         match self
             .symbols
@@ -383,21 +882,22 @@ impl Machine {
         }
     }
 
-    /// Handles a builtin call.
-    async fn call_builtin(&mut self, span: &BuiltinCallSpan) -> Result<()> {
-        let cmd = match self
+    /// Looks up the command invoked by the builtin call in `span`.
+    fn get_command(&self, span: &BuiltinCallSpan) -> Result<Rc<dyn Command>> {
+        match self
             .symbols
             .get(&VarRef::new(&span.name, VarType::Auto))
             .map_err(|e| Error::from_value_error(e, span.name_pos))?
         {
-            Some(Symbol::Command(cmd)) => cmd.clone(),
-            Some(_) => {
-                return new_syntax_error(span.name_pos, format!("{} is not a command", span.name))
-            }
-            None => {
-                return new_syntax_error(span.name_pos, format!("Unknown builtin {}", span.name))
-            }
-        };
+            Some(Symbol::Command(cmd)) => Ok(cmd.clone()),
+            Some(_) => new_syntax_error(span.name_pos, format!("{} is not a command", span.name)),
+            None => new_syntax_error(span.name_pos, format!("Unknown builtin {}", span.name)),
+        }
+    }
+
+    /// Handles a builtin call.
+    async fn call_builtin(&mut self, span: &BuiltinCallSpan) -> Result<()> {
+        let cmd = self.get_command(span)?;
         cmd.exec(span, self)
             .await
             .map_err(|e| Error::from_call_error(cmd.metadata(), e, span.name_pos))
@@ -406,14 +906,26 @@ impl Machine {
     /// Handles an array definition.  The array must not yet exist, and the name may not overlap
     /// function or variable names.
     pub async fn dim_array(&mut self, span: &DimArraySpan) -> Result<()> {
-        let mut ds = Vec::with_capacity(span.dimensions.len());
-        for dim_expr in &span.dimensions {
+        let ds = self.eval_dimensions(&span.dimensions, "DIM").await?;
+        if !self.check_array_cells(&ds, 0) {
+            return Ok(());
+        }
+        self.symbols
+            .dim_array(&span.name, span.subtype, ds)
+            .map_err(|e| Error::from_value_error(e, span.name_pos))?;
+        Ok(())
+    }
+
+    /// Evaluates the `exprs` that specify the dimensions of an array in statement `stmt`.
+    async fn eval_dimensions(&mut self, exprs: &[Expr], stmt: &str) -> Result<Vec<usize>> {
+        let mut ds = Vec::with_capacity(exprs.len());
+        for dim_expr in exprs {
             match dim_expr.eval(&mut self.symbols).await? {
                 Value::Integer(i) => {
                     if i <= 0 {
                         return new_syntax_error(
                             dim_expr.start_pos(),
-                            "Dimensions in DIM array must be positive",
+                            format!("Dimensions in {} array must be positive", stmt),
                         );
                     }
                     ds.push(i as usize);
@@ -421,15 +933,35 @@ impl Machine {
                 _ => {
                     return new_syntax_error(
                         dim_expr.start_pos(),
-                        "Dimensions in DIM array must be integers",
+                        format!("Dimensions in {} array must be integers", stmt),
                     )
                 }
             }
         }
-        self.symbols
-            .dim_array(&span.name, span.subtype, ds)
-            .map_err(|e| Error::from_value_error(e, span.name_pos))?;
-        Ok(())
+        Ok(ds)
+    }
+
+    /// Handles an array resize.
+    async fn redim(&mut self, span: &RedimSpan) -> Result<()> {
+        let ds = self.eval_dimensions(&span.dimensions, "REDIM").await?;
+        let vref = VarRef::new(&span.name, VarType::Auto);
+        let replaced = match self.symbols.get(&vref) {
+            Ok(Some(Symbol::Array(array))) => array.values().len(),
+            _ => 0,
+        };
+        if !self.check_array_cells(&ds, replaced) {
+            return Ok(());
+        }
+        match self.symbols.get_mut(&vref).map_err(|e| Error::from_value_error(e, span.name_pos))? {
+            Some(Symbol::Array(array)) => array
+                .resize(ds, span.preserve)
+                .map_err(|e| Error::from_value_error(e, span.name_pos)),
+            Some(_) => new_syntax_error(span.name_pos, format!("{} is not an array", span.name)),
+            None => new_syntax_error(
+                span.name_pos,
+                format!("Cannot REDIM undefined array {}", span.name),
+            ),
+        }
     }
 
     /// Consumes any pending signals so that they don't interfere with an upcoming execution.
@@ -437,6 +969,214 @@ impl Machine {
         while self.signals_chan.1.try_recv().is_ok() {
             // Do nothing.
         }
+        self.pending_file_changes.clear();
+        self.pending_gpio.clear();
+        self.pending_keys.clear();
+    }
+
+    /// Calls the file change handler configured in `context` for the oldest pending file change
+    /// notification, making the location of the changed file available to the handler.
+    ///
+    /// Notifications wait in the queue while the handler runs.  Notifications that arrive while
+    /// there is no handler are discarded.
+    fn dispatch_file_change(&mut self, context: &mut Context) {
+        if context.file_change_guard.is_busy(context.addr_stack.len()) {
+            return;
+        }
+        let location = match self.pending_file_changes.pop_front() {
+            Some(location) => location,
+            None => return,
+        };
+        if let FileChangeHandlerSpan::Call(addr) = context.file_change_handler {
+            self.symbols
+                .set_var(&VarRef::new("0filechange", VarType::Text), Value::Text(location))
+                .expect("Internal symbol must be of a specific type");
+            context.addr_stack.push(context.pc);
+            context.file_change_guard.enter(context.addr_stack.len());
+            context.pc = addr;
+        } else {
+            self.pending_file_changes.clear();
+        }
+    }
+
+    /// Calls the handler configured in `context` for the oldest pending GPIO edge that has one.
+    ///
+    /// Edges wait in the queue while a GPIO handler runs.  Edges for which there is no handler are
+    /// discarded.
+    fn dispatch_gpio(&mut self, context: &mut Context) {
+        if context.gpio_guard.is_busy(context.addr_stack.len()) {
+            return;
+        }
+        while let Some(key) = self.pending_gpio.pop_front() {
+            if let Some(addr) = context.gpio_handlers.get(&key) {
+                context.addr_stack.push(context.pc);
+                context.gpio_guard.enter(context.addr_stack.len());
+                context.pc = *addr;
+                return;
+            }
+        }
+    }
+
+    /// Calls the handler configured in `context` for the oldest pending key press that has one.
+    ///
+    /// Key presses wait in the queue while a key handler runs.  Key presses for which there is no
+    /// handler are discarded.
+    fn dispatch_key(&mut self, context: &mut Context) {
+        if context.key_guard.is_busy(context.addr_stack.len()) {
+            return;
+        }
+        while let Some(name) = self.pending_keys.pop_front() {
+            if let Some(addr) = context.key_handlers.get(&name.to_uppercase()) {
+                context.addr_stack.push(context.pc);
+                context.key_guard.enter(context.addr_stack.len());
+                context.pc = *addr;
+                return;
+            }
+        }
+    }
+
+    /// Calls the periodic handler configured in `context` if its period has elapsed since the
+    /// last call.
+    ///
+    /// The handler is not called again until it returns and at least one other instruction has
+    /// run, and periods that elapse while the handler runs or while the machine is busy are not
+    /// made up for later.
+    fn dispatch_interval(&mut self, context: &mut Context) {
+        let handler = match context.interval_handler.as_mut() {
+            Some(handler) => handler,
+            None => return,
+        };
+        if handler.guard.is_busy(context.addr_stack.len()) {
+            return;
+        }
+
+        let now = match self.clock.as_ref() {
+            Some(clock) => clock(),
+            None => return,
+        };
+        if now < handler.next {
+            return;
+        }
+        handler.next = now.saturating_add(handler.period);
+
+        context.addr_stack.push(context.pc);
+        handler.guard.enter(context.addr_stack.len());
+        context.pc = handler.addr;
+    }
+
+    /// Handles a change to the handler of a GPIO edge.
+    async fn set_gpio_handler(
+        &mut self,
+        context: &mut Context,
+        span: &GpioHandlerSpan,
+    ) -> Result<()> {
+        let pin = match span.pin.eval(&mut self.symbols).await? {
+            Value::Integer(i) if i < 0 => {
+                return new_syntax_error(
+                    span.pin.start_pos(),
+                    format!("Pin number {} must be positive", i),
+                )
+            }
+            Value::Integer(i) if i > i32::from(u8::MAX) => {
+                return new_syntax_error(
+                    span.pin.start_pos(),
+                    format!("Pin number {} is too large", i),
+                )
+            }
+            Value::Integer(i) => i as u8,
+            _ => return new_syntax_error(span.pin.start_pos(), "Pin number must be an integer"),
+        };
+
+        let edges: &[bool] = match span.edge.eval(&mut self.symbols).await? {
+            Value::Text(name) => match name.to_uppercase().as_str() {
+                "BOTH" => &[true, false],
+                "FALLING" => &[false],
+                "RISING" => &[true],
+                _ => {
+                    return new_syntax_error(
+                        span.edge.start_pos(),
+                        format!("Unknown GPIO edge {}", name),
+                    )
+                }
+            },
+            _ => return new_syntax_error(span.edge.start_pos(), "GPIO edge must be a string"),
+        };
+
+        for rising in edges {
+            match span.addr {
+                Some(addr) => {
+                    context.gpio_handlers.insert((pin, *rising), addr);
+                }
+                None => {
+                    context.gpio_handlers.remove(&(pin, *rising));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles a change to the periodic handler.
+    async fn set_interval_handler(
+        &mut self,
+        context: &mut Context,
+        span: &IntervalHandlerSpan,
+    ) -> Result<()> {
+        let addr = match span.addr {
+            Some(addr) => addr,
+            None => {
+                context.interval_handler = None;
+                return Ok(());
+            }
+        };
+
+        let ms = match span.period.eval(&mut self.symbols).await? {
+            Value::Double(d) => d,
+            Value::Integer(i) => i as f64,
+            Value::Long(i) => i as f64,
+            _ => return new_syntax_error(span.period.start_pos(), "Interval must be a number"),
+        };
+        if !ms.is_finite() || ms <= 0.0 {
+            return new_syntax_error(span.period.start_pos(), "Interval must be positive");
+        }
+        let period = Duration::from_secs_f64(ms / 1000.0);
+
+        let now = match self.clock.as_ref() {
+            Some(clock) => clock(),
+            None => {
+                return new_syntax_error(
+                    span.period.start_pos(),
+                    "ON INTERVAL is not supported by this machine",
+                )
+            }
+        };
+        context.interval_handler = Some(IntervalHandler {
+            addr,
+            period,
+            next: now.saturating_add(period),
+            guard: HandlerGuard::default(),
+        });
+        Ok(())
+    }
+
+    /// Handles a change to the handler of a key.
+    async fn set_key_handler(
+        &mut self,
+        context: &mut Context,
+        span: &KeyHandlerSpan,
+    ) -> Result<()> {
+        let name = match span.key.eval(&mut self.symbols).await? {
+            Value::Text(name) => name.to_uppercase(),
+            _ => return new_syntax_error(span.key.start_pos(), "Key name must be a string"),
+        };
+        match span.addr {
+            Some(addr) => {
+                context.key_handlers.insert(name, addr);
+            }
+            None => {
+                context.key_handlers.remove(&name);
+            }
+        }
+        Ok(())
     }
 
     /// Tells the machine to stop execution at the next statement boundary.
@@ -487,6 +1227,12 @@ impl Machine {
             }
 
             Instruction::Call(span) => {
+                if let Some(max) = self.limits.max_call_depth {
+                    if context.addr_stack.len() >= max {
+                        self.exceed(Limit::CallDepth);
+                        return Ok(());
+                    }
+                }
                 context.addr_stack.push(context.pc + 1);
                 context.pc = span.addr;
             }
@@ -511,6 +1257,59 @@ impl Machine {
                 context.pc = span.addr;
             }
 
+            Instruction::ForEachNext(span) => {
+                // The index is only missing if execution jumped into the body of the loop without
+                // going through its `FOR EACH` statement and then reached the `NEXT`.
+                let index = match self.symbols.get_auto(&span.index) {
+                    Some(Symbol::Variable(Value::Integer(i))) => *i as usize,
+                    _ => return new_syntax_error(span.next_pos, "NEXT without FOR EACH"),
+                };
+                let value = match self
+                    .symbols
+                    .get(&span.array)
+                    .map_err(|e| Error::from_value_error(e, span.array_pos))?
+                {
+                    Some(Symbol::Array(array)) => array.values().get(index).cloned(),
+                    Some(Symbol::Variable(Value::Integer(handle)))
+                        if self.collection_element_fn.is_some() =>
+                    {
+                        let element_fn =
+                            self.collection_element_fn.as_ref().expect("Checked above");
+                        match element_fn(*handle, index) {
+                            Ok(value) => value,
+                            Err(e) => return new_syntax_error(span.array_pos, e),
+                        }
+                    }
+                    Some(_) => {
+                        return new_syntax_error(
+                            span.array_pos,
+                            format!("{} is not an array", span.array),
+                        )
+                    }
+                    None => {
+                        return new_syntax_error(
+                            span.array_pos,
+                            format!("Unknown array {}", span.array),
+                        )
+                    }
+                };
+                match value {
+                    Some(value) => {
+                        self.symbols
+                            .set_var(&span.iter, value)
+                            .map_err(|e| Error::from_value_error(e, span.iter_pos))?;
+                        self.symbols
+                            .set_var(
+                                &VarRef::new(span.index.clone(), VarType::Auto),
+                                Value::Integer(index as i32 + 1),
+                            )
+                            .expect("FOR EACH index must be an integer");
+                        context.pc += 1;
+                    }
+                    None => context.pc = span.addr,
+                }
+            }
+
             Instruction::JumpIfDefined(span) => {
                 if self.symbols.get_auto(&span.var).is_some() {
                     context.pc = span.addr;
@@ -539,6 +1338,11 @@ impl Machine {
                 context.pc += 1;
             }
 
+            Instruction::Redim(span) => {
+                self.redim(span).await?;
+                context.pc += 1;
+            }
+
             Instruction::Return(span) => match context.addr_stack.pop() {
                 Some(addr) => context.pc = addr,
                 None => return new_syntax_error(span.pos, "No address to return to".to_owned()),
@@ -549,7 +1353,27 @@ impl Machine {
                 context.pc += 1;
             }
 
-            Instruction::Unset(span) => {
+            Instruction::SetFileChangeHandler(span) => {
+                context.file_change_handler = *span;
+                context.pc += 1;
+            }
+
+            Instruction::SetGpioHandler(span) => {
+                self.set_gpio_handler(context, span).await?;
+                context.pc += 1;
+            }
+
+            Instruction::SetIntervalHandler(span) => {
+                self.set_interval_handler(context, span).await?;
+                context.pc += 1;
+            }
+
+            Instruction::SetKeyHandler(span) => {
+                self.set_key_handler(context, span).await?;
+                context.pc += 1;
+            }
+
+            Instruction::Unset(span) => {
                 self.symbols.unset(&span.name).map_err(|e| Error::from_value_error(e, span.pos))?;
                 context.pc += 1;
             }
@@ -583,31 +1407,315 @@ impl Machine {
         result
     }
 
+    /// Checks if execution has to pause before running the instruction at `pos` because of the
+    /// debugger configuration, and records that execution reached this position.
+    ///
+    /// If tracing or profiling are enabled, this also accounts for every new line that execution
+    /// reaches.
+    fn should_pause(&mut self, context: &mut Context, pos: LineCol) -> Result<bool> {
+        if context.last_line == Some(pos.line) {
+            return Ok(false);
+        }
+        context.last_line = Some(pos.line);
+        if self.debugger.profiler.clock.is_some() {
+            self.debugger
+                .profiler
+                .lines
+                .entry(pos.line)
+                .or_insert_with(|| LineProfile { line: pos.line, ..Default::default() })
+                .count += 1;
+        }
+        if let Some(tracer) = self.debugger.tracer.as_ref() {
+            tracer(pos)?;
+        }
+        if self.debugger.stepping || self.debugger.breakpoints.contains(&pos.line) {
+            self.debugger.stepping = false;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Executes a single instruction like `exec_one` and accounts for the time it takes in the
+    /// profiling data.
+    ///
+    /// The time spent on instructions that do not carry a position is attributed to the line of
+    /// the last instruction that did.
+    async fn exec_one_profiled(
+        &mut self,
+        context: &mut Context,
+        instrs: &[Instruction],
+    ) -> Result<()> {
+        let clock = self.debugger.profiler.clock.clone().expect("Profiling must be enabled");
+        let line = context.last_line;
+
+        let start = clock();
+        let result = self.exec_one(context, instrs).await;
+        let elapsed = clock().saturating_sub(start);
+
+        if let Some(line) = line {
+            let profile = self
+                .debugger
+                .profiler
+                .lines
+                .entry(line)
+                .or_insert_with(|| LineProfile { line, ..Default::default() });
+            profile.time += elapsed;
+        }
+
+        result
+    }
+
+    /// Runs the `instrs` starting at the state given by `context` until termination.
+    ///
+    /// If `debug` is true, execution honors the debugger configuration and may be paused, in which
+    /// case the execution state is saved so that it can be later resumed.
+    async fn run(
+        &mut self,
+        instrs: Vec<Instruction>,
+        context: Context,
+        debug: bool,
+    ) -> Result<StopReason> {
+        if self.usage.depth == 0 {
+            self.usage =
+                Usage { start: self.clock.as_ref().map(|clock| clock()), ..Usage::default() };
+        }
+
+        self.usage.depth += 1;
+        let result = self.run_aux(instrs, context, debug).await;
+        self.usage.depth -= 1;
+
+        match (result, self.usage.exceeded) {
+            (Ok(StopReason::Eof), Some(limit)) => Ok(StopReason::LimitExceeded(limit)),
+            (result, _) => result,
+        }
+    }
+
+    /// Helper for `run` that executes the instructions without accounting for nested runs.
+    async fn run_aux(
+        &mut self,
+        mut instrs: Vec<Instruction>,
+        mut context: Context,
+        debug: bool,
+    ) -> Result<StopReason> {
+        let mut result = Ok(());
+        while result.is_ok() && context.pc < instrs.len() && !self.should_stop().await {
+            self.dispatch_file_change(&mut context);
+            self.dispatch_gpio(&mut context);
+            self.dispatch_key(&mut context);
+            self.dispatch_interval(&mut context);
+            if debug {
+                if let Some(pos) = instrs[context.pc].pos() {
+                    match self.should_pause(&mut context, pos) {
+                        Ok(true) => {
+                            let data = mem::take(&mut self.data);
+                            self.debugger.suspended = Some(Suspended { instrs, data, context });
+                            return Ok(StopReason::Paused(pos));
+                        }
+                        Ok(false) => (),
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+            }
+            if !self.check_progress() {
+                break;
+            }
+            if debug && self.debugger.profiler.clock.is_some() {
+                result = self.exec_one_profiled(&mut context, &instrs).await;
+            } else {
+                result = self.exec_one(&mut context, &instrs).await;
+            }
+            if let Some(image) = self.pending_chain.take() {
+                if result.is_ok() {
+                    self.data = image.data;
+                    instrs = image.instrs;
+                    context = Context::default();
+                }
+            }
+        }
+
+        self.data.clear();
+        result?;
+
+        Ok(self.stop_reason.take().unwrap_or(StopReason::Eof))
+    }
+
+    /// Compiles the program in `stmts` and starts its execution.
+    async fn exec_internal(&mut self, stmts: Vec<Statement>, debug: bool) -> Result<StopReason> {
+        debug_assert!(self.stop_reason.is_none());
+
+        let mut image = compiler::compile(stmts)?;
+        optimizer::optimize(&mut image, &self.symbols);
+
+        assert!(self.data.is_empty());
+        self.data = image.data;
+
+        self.run(image.instrs, Context::default(), debug).await
+    }
+
     /// Executes a program extracted from the `input` readable.
     ///
     /// Note that this does not consume `self`.  As a result, it is possible to execute multiple
     /// different programs on the same machine, all sharing state.
     pub async fn exec(&mut self, input: &mut dyn io::Read) -> Result<StopReason> {
-        debug_assert!(self.stop_reason.is_none());
-
         // TODO(jmmv): It should be possible to make the parser return statements one at a time and
         // stream them to the compiler, instead of buffering everything in a vector.
         let stmts = parser::parse(input)?;
-        let image = compiler::compile(stmts)?;
+        self.exec_internal(stmts, false).await
+    }
 
-        assert!(self.data.is_empty());
-        self.data = image.data;
+    /// Executes a full program extracted from the `input` readable honoring the debugger
+    /// configuration.
+    ///
+    /// This behaves like `exec` but execution may stop with `StopReason::Paused` when reaching a
+    /// breakpoint.  Debugging facilities are not applied to `exec` because they only make sense
+    /// when running a whole program, not when evaluating interactive commands, which would
+    /// otherwise hit breakpoints that match their line numbers.
+    ///
+    /// Any previously-paused program is discarded.
+    pub async fn exec_program(&mut self, input: &mut dyn io::Read) -> Result<StopReason> {
+        self.debugger.suspended = None;
+        self.debugger.stepping = false;
+        let stmts = parser::parse(input)?;
+        self.exec_internal(stmts, true).await
+    }
 
-        let mut context = Context::default();
-        let mut result = Ok(());
-        while result.is_ok() && context.pc < image.instrs.len() && !self.should_stop().await {
-            result = self.exec_one(&mut context, &image.instrs).await;
+    /// Executes a full program like `exec_program` does but processing its `'#INCLUDE`
+    /// directives, whose files are fetched via `resolve`.
+    ///
+    /// See `parser::parse_with_includes` for details on the semantics of the directives.
+    pub async fn exec_program_with_includes(
+        &mut self,
+        input: &str,
+        resolve: &dyn Fn(&str) -> io::Result<(String, String)>,
+    ) -> Result<StopReason> {
+        self.debugger.suspended = None;
+        self.debugger.stepping = false;
+        let stmts = parser::parse_with_includes(input, resolve)?;
+        self.exec_internal(stmts, true).await
+    }
+
+    /// Executes the subroutine that starts at `label` in the full program `input` honoring the
+    /// debugger configuration.
+    ///
+    /// This behaves like `exec_program_with_includes` but, instead of running the program from its
+    /// beginning, jumps to `label` as `GOSUB` would and stops once the subroutine returns.
+    pub async fn exec_gosub_with_includes(
+        &mut self,
+        input: &str,
+        label: &str,
+        resolve: &dyn Fn(&str) -> io::Result<(String, String)>,
+    ) -> Result<StopReason> {
+        self.debugger.suspended = None;
+        self.debugger.stepping = false;
+        let mut stmts = parser::parse_with_includes(input, resolve)?;
+        let target_pos = LineCol { line: 1, col: 1 };
+        stmts.insert(0, Statement::End(EndSpan { code: None }));
+        stmts.insert(0, Statement::Gosub(GotoSpan { target: label.to_owned(), target_pos }));
+        self.exec_internal(stmts, true).await
+    }
+
+    /// Returns the names of the labels defined at the top level of the full program `input` that
+    /// start with `prefix`, ignoring case, in the order in which they appear.
+    ///
+    /// Any `'#INCLUDE` directives in the program are processed by fetching their files via
+    /// `resolve` so that the labels they define are returned as well.
+    pub fn find_labels(
+        &self,
+        input: &str,
+        prefix: &str,
+        resolve: &dyn Fn(&str) -> io::Result<(String, String)>,
+    ) -> Result<Vec<String>> {
+        let stmts = parser::parse_with_includes(input, resolve)?;
+        let prefix = prefix.to_ascii_lowercase();
+        Ok(stmts
+            .into_iter()
+            .filter_map(|stmt| match stmt {
+                Statement::Label(span) if span.name.to_ascii_lowercase().starts_with(&prefix) => {
+                    Some(span.name)
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Replaces the running program with the one in `input` once the current statement finishes.
+    ///
+    /// The new program is parsed and compiled right away so that any errors are reported to the
+    /// caller, which is expected to be a command invoked by the running program.  Any `'#INCLUDE`
+    /// directives in the program are processed by fetching their files via `resolve`.  Variables
+    /// are left untouched, so the caller can `clear` the machine if necessary.
+    pub fn chain(
+        &mut self,
+        input: &str,
+        resolve: &dyn Fn(&str) -> io::Result<(String, String)>,
+    ) -> Result<()> {
+        let stmts = parser::parse_with_includes(input, resolve)?;
+        let mut image = compiler::compile(stmts)?;
+        optimizer::optimize(&mut image, &self.symbols);
+        self.pending_chain = Some(image);
+        Ok(())
+    }
+
+    /// Validates the program in `input` without executing it.
+    ///
+    /// This parses and compiles the program and then looks for calls to commands that this machine
+    /// does not know about.  Returns all the problems found, which is empty if the program is valid.
+    /// Parsing and compilation stop at their first error, so these can only yield one error each.
+    pub fn check(&self, input: &mut dyn io::Read) -> Vec<Error> {
+        let stmts = match parser::parse(input) {
+            Ok(stmts) => stmts,
+            Err(e) => return vec![e.into()],
+        };
+        let image = match compiler::compile(stmts) {
+            Ok(image) => image,
+            Err(e) => return vec![e.into()],
+        };
+
+        let mut errors = vec![];
+        for instr in &image.instrs {
+            if let Instruction::BuiltinCall(span) = instr {
+                if let Err(e) = self.get_command(span) {
+                    errors.push(e);
+                }
+            }
         }
+        errors
+    }
 
-        self.data.clear();
-        result?;
+    /// Resumes the execution of a program that was paused by the debugger.
+    ///
+    /// If `step` is true, execution pauses again as soon as it reaches a different line.
+    ///
+    /// Fails if there is no paused program or if the caller defined new `DATA`, as it would clash
+    /// with the data of the paused program.
+    pub async fn resume(&mut self, step: bool) -> Result<StopReason> {
+        debug_assert!(self.stop_reason.is_none());
 
-        Ok(self.stop_reason.take().unwrap_or(StopReason::Eof))
+        if !self.data.is_empty() {
+            return Err(Error::IoError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot continue after new DATA",
+            )));
+        }
+
+        let suspended = match self.debugger.suspended.take() {
+            Some(suspended) => suspended,
+            None => {
+                return Err(Error::IoError(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "No paused program to resume",
+                )))
+            }
+        };
+
+        self.data = suspended.data;
+
+        self.debugger.stepping = step;
+        self.run(suspended.instrs, suspended.context, true).await
     }
 }
 
@@ -616,7 +1724,7 @@ mod tests {
     use super::*;
     use crate::testutils::*;
     use futures_lite::future::block_on;
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
     use std::rc::Rc;
 
     /// A clearable that tracks whether it has been called.
@@ -655,6 +1763,341 @@ mod tests {
         assert!(*cleared.borrow());
     }
 
+    #[test]
+    fn test_breakpoints_management() {
+        let mut machine = Machine::default();
+        assert!(machine.breakpoints().is_empty());
+        machine.add_breakpoint(5);
+        machine.add_breakpoint(2);
+        machine.add_breakpoint(5);
+        assert_eq!(vec![2, 5], machine.breakpoints());
+        assert!(machine.remove_breakpoint(5));
+        assert!(!machine.remove_breakpoint(5));
+        assert_eq!(vec![2], machine.breakpoints());
+        machine.clear_breakpoints();
+        assert!(machine.breakpoints().is_empty());
+    }
+
+    #[test]
+    fn test_check_ok() {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.add_command(OutCommand::new(captured_out.clone()));
+        let program = b"a = 1\nIF a THEN OUT a\nGOTO @end\nb = UNKNOWN_FUNC(1)\n@end";
+        assert!(machine.check(&mut program.as_ref()).is_empty());
+        assert!(captured_out.borrow().is_empty());
+        assert!(machine.get_var_as_int("a").is_err());
+    }
+
+    #[test]
+    fn test_check_errors() {
+        let mut machine = Machine::default();
+        machine.add_command(OutCommand::new(Rc::from(RefCell::from(vec![]))));
+        machine
+            .get_mut_symbols()
+            .set_var(&VarRef::new("v", VarType::Auto), Value::Integer(3))
+            .unwrap();
+
+        let check = |program: &str| {
+            machine
+                .check(&mut program.as_bytes())
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            ["2:5: Not enough values to apply operator"],
+            check("OUT 1\na = = 2\nFOO").as_slice()
+        );
+        assert_eq!(["2:6: Unknown label foo"], check("FOO\nGOTO @foo").as_slice());
+        assert_eq!(
+            ["1:1: Unknown builtin FOO", "2:3: V is not a command", "3:11: Unknown builtin BAR"],
+            check("FOO 3\n  v\nIF 1 THEN BAR ELSE OUT 2").as_slice()
+        );
+    }
+
+    #[test]
+    fn test_exec_gosub_with_includes() {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.add_command(OutCommand::new(captured_out.clone()));
+
+        let program = "OUT \"main\"\nEND\n@first: OUT \"first\": RETURN\n'#INCLUDE \"LIB\"\n";
+        let resolve = |_: &str| Ok(("LIB".to_owned(), "@second: OUT a: RETURN\n".to_owned()));
+        machine.set_var("a", 3).unwrap();
+        assert_eq!(
+            StopReason::Exited(0),
+            block_on(machine.exec_gosub_with_includes(program, "first", &resolve)).unwrap()
+        );
+        assert_eq!(
+            StopReason::Exited(0),
+            block_on(machine.exec_gosub_with_includes(program, "second", &resolve)).unwrap()
+        );
+        assert_eq!(&["first", "3"], captured_out.borrow().as_slice());
+
+        assert_eq!(
+            "1:1: Unknown label missing",
+            block_on(machine.exec_gosub_with_includes(program, "missing", &resolve))
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_find_labels() {
+        let machine = Machine::default();
+        let resolve = |_: &str| Ok(("LIB".to_owned(), "@test_lib: RETURN\n".to_owned()));
+
+        let program =
+            "@Test_1\n@other\n'#INCLUDE \"LIB\"\nIF 1 THEN\n@test_nested\nEND IF\n@TEST_2";
+        assert_eq!(
+            ["Test_1", "test_lib", "TEST_2"],
+            machine.find_labels(program, "test_", &resolve).unwrap().as_slice()
+        );
+        assert!(machine.find_labels(program, "none", &resolve).unwrap().is_empty());
+
+        assert_eq!(
+            "1:5: Not enough values to apply operator",
+            machine.find_labels("a = +", "", &resolve).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_exec_program_pauses_at_breakpoints() {
+        let mut machine = Machine::default();
+        machine.add_breakpoint(3);
+        machine.add_breakpoint(5);
+
+        let program = b"a = 1\nFOR i = 1 TO 2\n  a = a + 1\nNEXT\nb = a";
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 3, col: 3 }),
+            block_on(machine.exec_program(&mut program.as_ref())).unwrap()
+        );
+        assert!(machine.is_paused());
+        assert_eq!(1, machine.get_var_as_int("a").unwrap());
+        assert_eq!(1, machine.get_var_as_int("i").unwrap());
+
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 3, col: 3 }),
+            block_on(machine.resume(false)).unwrap()
+        );
+        assert_eq!(2, machine.get_var_as_int("a").unwrap());
+        assert_eq!(2, machine.get_var_as_int("i").unwrap());
+
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 5, col: 1 }),
+            block_on(machine.resume(false)).unwrap()
+        );
+        assert_eq!(3, machine.get_var_as_int("a").unwrap());
+        assert!(machine.get_var_as_int("b").is_err());
+
+        assert_eq!(StopReason::Eof, block_on(machine.resume(false)).unwrap());
+        assert!(!machine.is_paused());
+        assert_eq!(3, machine.get_var_as_int("b").unwrap());
+    }
+
+    #[test]
+    fn test_exec_program_single_step() {
+        let mut machine = Machine::default();
+        machine.add_breakpoint(1);
+
+        let program = b"a = 1: b = 2\nc = 3\n\nd = 4";
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 1, col: 1 }),
+            block_on(machine.exec_program(&mut program.as_ref())).unwrap()
+        );
+        assert!(machine.get_var_as_int("a").is_err());
+
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 2, col: 1 }),
+            block_on(machine.resume(true)).unwrap()
+        );
+        assert_eq!(1, machine.get_var_as_int("a").unwrap());
+        assert_eq!(2, machine.get_var_as_int("b").unwrap());
+
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 4, col: 1 }),
+            block_on(machine.resume(true)).unwrap()
+        );
+        assert_eq!(3, machine.get_var_as_int("c").unwrap());
+
+        assert_eq!(StopReason::Eof, block_on(machine.resume(true)).unwrap());
+        assert_eq!(4, machine.get_var_as_int("d").unwrap());
+    }
+
+    #[test]
+    fn test_exec_program_keeps_data_while_paused() {
+        let captured_data = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.add_command(GetDataCommand::new(captured_data.clone()));
+        machine.add_breakpoint(2);
+
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 2, col: 1 }),
+            block_on(machine.exec_program(&mut b"DATA 3\nGETDATA".as_ref())).unwrap()
+        );
+        assert!(machine.get_data().is_empty());
+
+        assert_eq!(StopReason::Eof, block_on(machine.resume(false)).unwrap());
+        assert_eq!(&[Some(Value::Integer(3))], captured_data.borrow().as_slice());
+    }
+
+    #[test]
+    fn test_resume_rejects_new_data() {
+        let captured_data = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.add_command(GetDataCommand::new(captured_data.clone()));
+        machine.add_breakpoint(2);
+
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 2, col: 1 }),
+            block_on(machine.exec_program(&mut b"DATA 3\nGETDATA".as_ref())).unwrap()
+        );
+
+        // Simulate a `DATA 5: CONT` statement typed while the program is paused.
+        machine.data = vec![Some(Value::Integer(5))];
+        assert_eq!(
+            "Cannot continue after new DATA",
+            format!("{}", block_on(machine.resume(false)).unwrap_err())
+        );
+        assert!(machine.is_paused());
+
+        machine.data.clear();
+        assert_eq!(StopReason::Eof, block_on(machine.resume(false)).unwrap());
+        assert_eq!(&[Some(Value::Integer(3))], captured_data.borrow().as_slice());
+    }
+
+    #[test]
+    fn test_exec_program_traces_lines() {
+        let traced = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        assert!(!machine.is_tracing());
+        {
+            let traced = traced.clone();
+            machine.set_tracer(Some(Box::from(move |pos: LineCol| {
+                traced.borrow_mut().push(pos.line);
+                Ok(())
+            })));
+        }
+        assert!(machine.is_tracing());
+        machine.add_breakpoint(3);
+
+        let program = b"a = 1: b = 2\nFOR i = 1 TO 2\n  a = a + 1\nNEXT\n\nc = 3";
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 3, col: 3 }),
+            block_on(machine.exec_program(&mut program.as_ref())).unwrap()
+        );
+        assert_eq!(&[1, 2, 3], traced.borrow().as_slice());
+        machine.clear_breakpoints();
+        assert_eq!(StopReason::Eof, block_on(machine.resume(false)).unwrap());
+        assert_eq!(&[1, 2, 3, 2, 3, 2, 6], traced.borrow().as_slice());
+
+        traced.borrow_mut().clear();
+        assert_eq!(StopReason::Eof, block_on(machine.exec(&mut b"a = 1\nb = 2".as_ref())).unwrap());
+        assert!(traced.borrow().is_empty());
+
+        machine.set_tracer(None);
+        assert!(!machine.is_tracing());
+    }
+
+    #[test]
+    fn test_exec_program_trace_errors() {
+        let mut machine = Machine::default();
+        machine.set_tracer(Some(Box::from(|_pos| {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "Oops"))
+        })));
+        assert_eq!(
+            "Oops",
+            format!("{}", block_on(machine.exec_program(&mut b"a = 1".as_ref())).unwrap_err())
+        );
+        assert!(machine.get_var_as_int("a").is_err());
+    }
+
+    /// Returns a clock for the profiler that advances by one millisecond every time it is queried.
+    fn fake_clock() -> ClockFn {
+        let now = Cell::new(Duration::default());
+        Rc::from(move || {
+            now.set(now.get() + Duration::from_millis(1));
+            now.get()
+        })
+    }
+
+    #[test]
+    fn test_exec_program_profiles_line_entries() {
+        let mut machine = Machine::default();
+        machine.enable_profiling(fake_clock());
+        machine.add_breakpoint(3);
+
+        let program = b"a = 0\nDO: a = a + 1: LOOP UNTIL a = 3\nb = 1";
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 3, col: 1 }),
+            block_on(machine.exec_program(&mut program.as_ref())).unwrap()
+        );
+        assert_eq!(StopReason::Eof, block_on(machine.resume(false)).unwrap());
+        assert_eq!(
+            vec![(1, 1), (2, 1), (3, 1)],
+            machine.profile().iter().map(|p| (p.line, p.count)).collect::<Vec<(usize, u64)>>()
+        );
+    }
+
+    #[test]
+    fn test_exec_program_profiles_lines() {
+        let mut machine = Machine::default();
+        assert!(!machine.is_profiling());
+        machine.enable_profiling(fake_clock());
+        assert!(machine.is_profiling());
+
+        let program = b"a = 0\nFOR i = 1 TO 3\n  a = a + 1: a = a * 1\nNEXT";
+        assert_eq!(StopReason::Eof, block_on(machine.exec_program(&mut program.as_ref())).unwrap());
+        assert_eq!(
+            vec![
+                LineProfile { line: 1, count: 1, time: Duration::from_millis(1) },
+                LineProfile { line: 2, count: 4, time: Duration::from_millis(11) },
+                LineProfile { line: 3, count: 3, time: Duration::from_millis(6) },
+            ],
+            machine.profile()
+        );
+
+        // Interactive commands are not profiled.
+        assert_eq!(StopReason::Eof, block_on(machine.exec(&mut b"a = 1".as_ref())).unwrap());
+        assert_eq!(3, machine.profile().len());
+
+        machine.disable_profiling();
+        assert!(!machine.is_profiling());
+        assert_eq!(StopReason::Eof, block_on(machine.exec_program(&mut program.as_ref())).unwrap());
+        assert_eq!(3, machine.profile().len());
+
+        machine.enable_profiling(fake_clock());
+        assert!(machine.profile().is_empty());
+    }
+
+    #[test]
+    fn test_exec_ignores_breakpoints() {
+        let mut machine = Machine::default();
+        machine.add_breakpoint(1);
+        assert_eq!(StopReason::Eof, block_on(machine.exec(&mut b"a = 1".as_ref())).unwrap());
+        assert!(!machine.is_paused());
+    }
+
+    #[test]
+    fn test_resume_errors() {
+        let mut machine = Machine::default();
+        assert_eq!(
+            "No paused program to resume",
+            format!("{}", block_on(machine.resume(false)).unwrap_err())
+        );
+
+        machine.add_breakpoint(1);
+        assert_eq!(
+            StopReason::Paused(LineCol { line: 1, col: 1 }),
+            block_on(machine.exec_program(&mut b"a = 1".as_ref())).unwrap()
+        );
+        machine.clear();
+        assert!(!machine.is_paused());
+        block_on(machine.resume(false)).unwrap_err();
+    }
+
     #[test]
     fn test_get_data() {
         let captured_data = Rc::from(RefCell::from(vec![]));
@@ -766,6 +2209,185 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_var() {
+        let mut machine = Machine::default();
+        assert_eq!(
+            StopReason::Eof,
+            block_on(machine.exec(&mut b"a = 3: b = \"foo\": c = 1.5".as_ref()))
+                .expect("Execution failed")
+        );
+        assert_eq!(3, machine.get_var::<i32>("a").expect("Failed to query a"));
+        assert_eq!(3.0, machine.get_var::<f64>("A").expect("Failed to query A"));
+        assert_eq!("foo", machine.get_var::<String>("b").expect("Failed to query b"));
+        assert_eq!(Value::Double(1.5), machine.get_var::<Value>("c").expect("Failed to query c"));
+        assert_eq!(
+            "Incompatible type STRING in variable b",
+            format!("{}", machine.get_var::<i32>("b").expect_err("Querying b succeeded"))
+        );
+        assert_eq!(
+            "Undefined variable d",
+            format!("{}", machine.get_var::<bool>("d").expect_err("Querying d succeeded"))
+        );
+    }
+
+    #[test]
+    fn test_set_var() {
+        let mut machine = Machine::default();
+        machine.set_var("a", 3).expect("Failed to set a");
+        machine.set_var("b", "foo").expect("Failed to set b");
+        machine.set_var("c", true).expect("Failed to set c");
+        assert_eq!(
+            StopReason::Eof,
+            block_on(machine.exec(&mut b"a = a + 1: b = b + \"bar\": c = NOT c".as_ref()))
+                .expect("Execution failed")
+        );
+        assert_eq!(4, machine.get_var_as_int("a").expect("Failed to query a"));
+        assert_eq!("foobar", machine.get_var_as_string("b").expect("Failed to query b"));
+        assert!(!machine.get_var_as_bool("c").expect("Failed to query c"));
+
+        assert_eq!(
+            "Cannot assign value of type STRING to variable of type INTEGER",
+            format!("{}", machine.set_var("a", "x").expect_err("Setting a succeeded"))
+        );
+    }
+
+    #[test]
+    fn test_exec_keeps_syntactic_args() {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.add_command(RangeCommand::new(captured_out.clone()));
+        machine.add_command(OutCommand::new(captured_out.clone()));
+        assert_eq!(
+            StopReason::Eof,
+            block_on(
+                machine.exec(&mut b"RANGE 10 - 50: OUT 10 - 50: RANGE 1 + 2 - 2 * 3".as_ref())
+            )
+            .unwrap()
+        );
+        assert_eq!(["10..50", "-40", "3..6"], captured_out.borrow().as_slice());
+    }
+
+    /// Runs the `input` code on a new test machine configured with `limits` and a clock that
+    /// advances by one millisecond every time it is queried.  Returns the reason why execution
+    /// stopped and the captured output.
+    fn run_with_limits(input: &str, limits: Limits) -> (StopReason, Vec<String>) {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.set_clock(fake_clock());
+        machine.set_limits(limits);
+        machine.add_command(OutCommand::new(captured_out.clone()));
+        let stop_reason = block_on(machine.exec(&mut input.as_bytes())).expect("Execution failed");
+        let out = captured_out.borrow().clone();
+        (stop_reason, out)
+    }
+
+    #[test]
+    fn test_limits_not_exceeded() {
+        let limits = Limits {
+            max_instructions: Some(100),
+            max_time: Some(Duration::from_secs(1)),
+            max_array_cells: Some(10),
+            max_string_bytes: Some(3),
+            max_call_depth: Some(1),
+        };
+        let (stop_reason, out) = run_with_limits(
+            r#"DIM a(5): REDIM a(10): s$ = "abc": GOSUB @x: OUT s$: END
+            @x: OUT 1: RETURN"#,
+            limits,
+        );
+        assert_eq!(StopReason::Exited(0), stop_reason);
+        assert_eq!(["1", "abc"], out.as_slice());
+    }
+
+    #[test]
+    fn test_limits_instructions() {
+        let limits = Limits { max_instructions: Some(10), ..Limits::default() };
+        let (stop_reason, out) = run_with_limits("DO: OUT 1: LOOP", limits);
+        assert_eq!(StopReason::LimitExceeded(Limit::Instructions), stop_reason);
+        assert_eq!(5, out.len());
+    }
+
+    #[test]
+    fn test_limits_instructions_reset_on_every_exec() {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.set_limits(Limits { max_instructions: Some(2), ..Limits::default() });
+        machine.add_command(OutCommand::new(captured_out.clone()));
+        for _ in 0..3 {
+            assert_eq!(
+                StopReason::Eof,
+                block_on(machine.exec(&mut b"OUT 1: OUT 2".as_ref())).unwrap()
+            );
+        }
+        assert_eq!(
+            StopReason::LimitExceeded(Limit::Instructions),
+            block_on(machine.exec(&mut b"OUT 1: OUT 2: OUT 3".as_ref())).unwrap()
+        );
+        assert_eq!(["1", "2", "1", "2", "1", "2", "1", "2"], captured_out.borrow().as_slice());
+    }
+
+    #[test]
+    fn test_limits_time() {
+        let limits = Limits { max_time: Some(Duration::from_millis(5)), ..Limits::default() };
+        let (stop_reason, out) = run_with_limits("DO: OUT 1: LOOP", limits);
+        assert_eq!(StopReason::LimitExceeded(Limit::Time), stop_reason);
+        assert!(!out.is_empty());
+    }
+
+    #[test]
+    fn test_limits_time_without_clock() {
+        let mut machine = Machine::default();
+        machine.set_limits(Limits { max_time: Some(Duration::ZERO), ..Limits::default() });
+        assert_eq!(StopReason::Eof, block_on(machine.exec(&mut b"a = 1".as_ref())).unwrap());
+    }
+
+    #[test]
+    fn test_limits_array_cells() {
+        let limits = Limits { max_array_cells: Some(15), ..Limits::default() };
+        let (stop_reason, out) =
+            run_with_limits("DIM a(10): OUT 1: DIM b(5): OUT 2: DIM c(1): OUT 3", limits);
+        assert_eq!(StopReason::LimitExceeded(Limit::ArrayCells), stop_reason);
+        assert_eq!(["1", "2"], out.as_slice());
+
+        let limits = Limits { max_array_cells: Some(15), ..Limits::default() };
+        let (stop_reason, out) =
+            run_with_limits("DIM a(10): REDIM a(15): OUT 1: REDIM a(4, 4): OUT 2", limits);
+        assert_eq!(StopReason::LimitExceeded(Limit::ArrayCells), stop_reason);
+        assert_eq!(["1"], out.as_slice());
+
+        let limits = Limits { max_array_cells: Some(15), ..Limits::default() };
+        let (stop_reason, out) =
+            run_with_limits("DIM a(2147483647, 2147483647, 2147483647, 2147483647): OUT 1", limits);
+        assert_eq!(StopReason::LimitExceeded(Limit::ArrayCells), stop_reason);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_limits_string_bytes() {
+        let limits = Limits { max_string_bytes: Some(5), ..Limits::default() };
+        let (stop_reason, out) =
+            run_with_limits(r#"a$ = "abc": OUT a$: a$ = a$ + a$: OUT a$"#, limits);
+        assert_eq!(StopReason::LimitExceeded(Limit::StringBytes), stop_reason);
+        assert_eq!(["abc"], out.as_slice());
+
+        let limits = Limits { max_string_bytes: Some(5), ..Limits::default() };
+        let (stop_reason, out) = run_with_limits(
+            r#"DIM a(2) AS STRING: a$(0) = "abc": OUT 1: a$(1) = "abcdef": OUT 2"#,
+            limits,
+        );
+        assert_eq!(StopReason::LimitExceeded(Limit::StringBytes), stop_reason);
+        assert_eq!(["1"], out.as_slice());
+    }
+
+    #[test]
+    fn test_limits_call_depth() {
+        let limits = Limits { max_call_depth: Some(3), ..Limits::default() };
+        let (stop_reason, out) = run_with_limits("@a: OUT 1: GOSUB @a", limits);
+        assert_eq!(StopReason::LimitExceeded(Limit::CallDepth), stop_reason);
+        assert_eq!(["1", "1", "1", "1"], out.as_slice());
+    }
+
     /// Runs the `input` code on a new test machine.
     ///
     /// `golden_in` is the sequence of values to yield by `IN`.
@@ -875,6 +2497,9 @@ mod tests {
         do_ok_test("a = 3\nOUT a; a%", &[], &["3 3"]);
         do_ok_test("a% = 3\nOUT a; a%", &[], &["3 3"]);
 
+        do_ok_test("a = 5000000000\nOUT a; a&", &[], &["5000000000 5000000000"]);
+        do_ok_test("a& = 3\nOUT a; a&", &[], &["3 3"]);
+
         do_ok_test("a = \"some text\"\nOUT a; a$", &[], &["some text some text"]);
         do_ok_test("a$ = \"some text\"\nOUT a; a$", &[], &["some text some text"]);
 
@@ -894,6 +2519,10 @@ mod tests {
 
         do_ok_test("a# = 3\nOUT a; a#", &[], &["3 3"]);
         do_ok_test("a# = 2.8 + 3\nOUT a; a#", &[], &["5.8 5.8"]);
+
+        do_ok_test("a& = 3.6\nOUT a; a&", &[], &["4 4"]);
+        do_ok_test("a = 2147483647 + 1\nOUT a; a&", &[], &["2147483648 2147483648"]);
+        do_ok_test("a! = 3\nOUT a; a#", &[], &["3 3"]);
     }
 
     #[test]
@@ -915,9 +2544,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bitwise_ops_ok() {
+        do_ok_test(
+            "flags = 0\nflags = flags OR (1 << 3) OR 1\nOUT flags; flags AND &b1000; flags AND 2",
+            &[],
+            &["9 8 0"],
+        );
+        do_ok_test("flags = &b1010\nflags = flags AND NOT &b10\nOUT flags", &[], &["8"]);
+        do_ok_test(
+            "c = (&x12 << 16) OR (&x34 << 8) OR &x56\nOUT c; (c >> 8) AND &xff; c XOR c",
+            &[],
+            &["1193046 52 0"],
+        );
+        do_ok_test("OUT -16 >> 2; 1& << 40; (1& << 40) >> 39", &[], &["-4 1099511627776 2"]);
+        do_ok_test("OUT TRUE AND NOT FALSE; 3 AND 5", &[], &["TRUE 1"]);
+    }
+
+    #[test]
+    fn test_bitwise_ops_errors() {
+        do_simple_error_test("OUT 3.0 AND 1", "1:9: Cannot AND 3.0 and 1");
+        do_simple_error_test("OUT TRUE OR 1", "1:10: Cannot OR TRUE and 1");
+        do_simple_error_test("OUT 1 << 2.0", "1:7: Number of bits to << (2.0) must be an integer");
+        do_simple_error_test("OUT 1 >> -1", "1:7: Number of bits to >> (-1) must be positive");
+    }
+
     #[test]
     fn test_dim_ok() {
         do_ok_test("DIM foo\nDIM bar AS BOOLEAN\nOUT foo%; bar?", &[], &["0 FALSE"]);
+        do_ok_test("DIM foo AS LONG\nDIM bar AS SINGLE\nOUT foo&; bar#", &[], &["0 0"]);
     }
 
     #[test]
@@ -944,6 +2599,58 @@ mod tests {
         do_simple_error_test("DIM i\nDIM i(3)", "2:5: Cannot DIM already-defined symbol i");
     }
 
+    #[test]
+    fn test_redim_ok() {
+        do_ok_test(
+            "DIM a(2): a(0) = 5: a(1) = 6: REDIM a(3): OUT a(0); a(1); a(2)",
+            &[],
+            &["0 0 0"],
+        );
+        do_ok_test(
+            "DIM a(2): a(0) = 5: a(1) = 6: REDIM PRESERVE a(3): OUT a(0); a(1); a(2)",
+            &[],
+            &["5 6 0"],
+        );
+        do_ok_test(
+            "DIM a(3) AS STRING: a(0) = \"x\": a(2) = \"z\": REDIM PRESERVE a(1): OUT a$(0)",
+            &[],
+            &["x"],
+        );
+        do_ok_test(
+            "DIM m(2, 2): m(0, 1) = 1: m(1, 1) = 2: REDIM PRESERVE m(3, 1): OUT m(0, 0); m(1, 0); m(2, 0)",
+            &[],
+            &["0 0 0"],
+        );
+        do_ok_test(
+            "DIM m(2, 2): m(0, 1) = 1: m(1, 1) = 2: REDIM PRESERVE m(3, 3): OUT m(0, 1); m(1, 1); m(2, 2)",
+            &[],
+            &["1 2 0"],
+        );
+        do_ok_test("DIM m(2, 2): REDIM m(4): OUT m(3)", &[], &["0"]);
+    }
+
+    #[test]
+    fn test_redim_errors() {
+        do_simple_error_test("REDIM a(3)", "1:7: Cannot REDIM undefined array a");
+        do_simple_error_test("a = 1: REDIM a(3)", "1:14: a is not an array");
+        do_simple_error_test(
+            "DIM a(3): REDIM a(FALSE)",
+            "1:19: Dimensions in REDIM array must be integers",
+        );
+        do_simple_error_test(
+            "DIM a(3): REDIM a(0)",
+            "1:19: Dimensions in REDIM array must be positive",
+        );
+        do_simple_error_test(
+            "DIM a(3): REDIM PRESERVE a(3, 3)",
+            "1:26: Cannot preserve contents when changing the number of dimensions from 1 to 2",
+        );
+        do_simple_error_test(
+            "DIM a(3): REDIM a(3): OUT a(3)",
+            "1:27: Subscript 3 exceeds limit of 3",
+        );
+    }
+
     #[test]
     fn test_end_no_code() {
         let captured_out = Rc::from(RefCell::from(vec![]));
@@ -988,6 +2695,31 @@ mod tests {
         do_simple_error_test("END 128", "1:5: Exit code cannot be larger than 127");
     }
 
+    #[test]
+    fn test_exit_with_code() {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        assert_eq!(
+            StopReason::Exited(3),
+            run("OUT 1: EXIT 3: OUT 2", &[], captured_out.clone()).expect("Execution failed")
+        );
+        assert_eq!(&["1"], captured_out.borrow().as_slice());
+
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        assert_eq!(
+            StopReason::Exited(7),
+            run("DO\nOUT 1\nIF TRUE THEN EXIT 7\nLOOP", &[], captured_out.clone())
+                .expect("Execution failed")
+        );
+        assert_eq!(&["1"], captured_out.borrow().as_slice());
+    }
+
+    #[test]
+    fn test_exit_errors() {
+        do_simple_error_test("EXIT \"b\"", "1:6: \"b\" is not a number");
+        do_simple_error_test("EXIT -3", "1:6: Exit code must be a positive integer");
+        do_simple_error_test("EXIT 128", "1:6: Exit code cannot be larger than 127");
+    }
+
     #[test]
     fn test_end_if() {
         let captured_out = Rc::from(RefCell::from(vec![]));
@@ -1374,37 +3106,108 @@ mod tests {
     }
 
     #[test]
-    fn test_for_iterator_can_be_modified() {
-        let code = r#"
-            FOR something = 1 TO 5
-                OUT something
-                something = something + 1
-            NEXT
-        "#;
-        do_ok_test(code, &[], &["1", "3", "5"]);
+    fn test_for_iterator_can_be_modified() {
+        let code = r#"
+            FOR something = 1 TO 5
+                OUT something
+                something = something + 1
+            NEXT
+        "#;
+        do_ok_test(code, &[], &["1", "3", "5"]);
+    }
+
+    #[test]
+    fn test_for_errors() {
+        do_simple_error_test("FOR\nNEXT", "1:4: No iterator name in FOR statement");
+        do_simple_error_test("FOR a = 1 TO 10\nEND IF", "2:1: END IF without IF");
+
+        do_simple_error_test(
+            "FOR i = \"a\" TO 3\nNEXT",
+            "1:13: Cannot compare \"a\" and 3 with <=",
+        );
+        do_simple_error_test(
+            "FOR i = 1 TO \"a\"\nNEXT",
+            "1:11: Cannot compare 1 and \"a\" with <=",
+        );
+
+        do_simple_error_test(
+            "FOR i = \"b\" TO 7 STEP -8\nNEXT",
+            "1:13: Cannot compare \"b\" and 7 with >=",
+        );
+        do_simple_error_test(
+            "FOR i = 1 TO \"b\" STEP -8\nNEXT",
+            "1:11: Cannot compare 1 and \"b\" with >=",
+        );
+    }
+
+    #[test]
+    fn test_for_each() {
+        do_ok_test("DIM a(3): FOR EACH x IN a: OUT x: NEXT", &[], &["0", "0", "0"]);
+        do_ok_test(
+            "DIM a(2) AS STRING: a(0) = \"x\": a(1) = \"y\": FOR EACH s$ IN a$: OUT s$: NEXT",
+            &[],
+            &["x", "y"],
+        );
+        do_ok_test(
+            "DIM m(2, 2): m(0, 1) = 1: m(1, 0) = 2: FOR EACH v IN m: OUT v: NEXT",
+            &[],
+            &["0", "1", "2", "0"],
+        );
+        do_ok_test(
+            "DIM a(2): a(0) = 1: a(1) = 2: FOR EACH i IN a: FOR EACH j IN a: OUT i * 10 + j: NEXT: NEXT",
+            &[],
+            &["11", "12", "21", "22"],
+        );
+    }
+
+    #[test]
+    fn test_for_each_iterator_is_visible_after_next() {
+        do_ok_test("DIM a(2): a(1) = 5: FOR EACH x IN a: NEXT: OUT x", &[], &["5"]);
     }
 
     #[test]
-    fn test_for_errors() {
-        do_simple_error_test("FOR\nNEXT", "1:4: No iterator name in FOR statement");
-        do_simple_error_test("FOR a = 1 TO 10\nEND IF", "2:1: END IF without IF");
+    fn test_for_each_collection() {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.add_command(OutCommand::new(captured_out.clone()));
+        machine.set_collection_element_fn(Box::from(|handle: i32, index: usize| match handle {
+            1 => Ok(["a", "b", "c"].get(index).map(|s| Value::Text((*s).to_owned()))),
+            2 => Ok(None),
+            _ => Err(format!("Invalid list handle {}", handle)),
+        }));
 
-        do_simple_error_test(
-            "FOR i = \"a\" TO 3\nNEXT",
-            "1:13: Cannot compare \"a\" and 3 with <=",
+        assert_eq!(
+            StopReason::Eof,
+            block_on(machine.exec(
+                &mut b"l = 1: FOR EACH s$ IN l: OUT s$: NEXT: l = 2: FOR EACH s$ IN l: OUT s$: NEXT"
+                    .as_ref()
+            ))
+            .unwrap()
         );
-        do_simple_error_test(
-            "FOR i = 1 TO \"a\"\nNEXT",
-            "1:11: Cannot compare 1 and \"a\" with <=",
+        assert_eq!(["a", "b", "c"], captured_out.borrow().as_slice());
+
+        assert_eq!(
+            "1:22: Invalid list handle 3",
+            format!(
+                "{}",
+                block_on(machine.exec(&mut b"l = 3: FOR EACH x IN l\nNEXT".as_ref())).unwrap_err()
+            )
         );
+    }
 
-        do_simple_error_test(
-            "FOR i = \"b\" TO 7 STEP -8\nNEXT",
-            "1:13: Cannot compare \"b\" and 7 with >=",
+    #[test]
+    fn test_for_each_errors() {
+        do_simple_error_test("FOR EACH x IN a\nNEXT", "1:15: Unknown array a");
+        do_error_test(
+            "DIM a(2)\nGOTO @x\nFOR EACH v IN a\n@x\nOUT \"in\"\nNEXT",
+            &[],
+            &["in"],
+            "6:1: NEXT without FOR EACH",
         );
+        do_simple_error_test("a = 3: FOR EACH x IN a\nNEXT", "1:22: a is not an array");
         do_simple_error_test(
-            "FOR i = 1 TO \"b\" STEP -8\nNEXT",
-            "1:11: Cannot compare 1 and \"b\" with >=",
+            "DIM a(1): FOR EACH x$ IN a\nNEXT",
+            "1:20: Cannot assign value of type INTEGER to variable of type STRING",
         );
     }
 
@@ -1688,6 +3491,435 @@ mod tests {
         );
     }
 
+    /// Runs the `input` code on a new test machine that receives `signal` right before executing
+    /// its `at`-th instruction, and returns the captured output.
+    fn run_with_signal(input: &str, at: usize, signal: Signal) -> Vec<String> {
+        run_with_signals(input, vec![(at, signal)])
+    }
+
+    /// Runs the `input` code on a new machine and delivers each of the `signals` right before the
+    /// instruction whose number it is paired with.  Returns the captured output.
+    fn run_with_signals(input: &str, signals: Vec<(usize, Signal)>) -> Vec<String> {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        let signals_chan = async_channel::unbounded();
+        let yield_now_fn: YieldNowFn = {
+            let signals_tx = signals_chan.0.clone();
+            let calls = Cell::new(0);
+            Box::from(move || {
+                calls.set(calls.get() + 1);
+                for (at, signal) in &signals {
+                    if calls.get() == *at {
+                        signals_tx.try_send(signal.clone()).unwrap();
+                    }
+                }
+                Box::pin(async {}) as Pin<Box<dyn Future<Output = ()>>>
+            })
+        };
+        let mut machine =
+            Machine::with_signals_chan_and_yield_now_fn(signals_chan, Some(yield_now_fn));
+        machine.add_command(OutCommand::new(captured_out.clone()));
+        machine.add_function(GetHiddenFunction::new());
+        assert_eq!(
+            StopReason::Eof,
+            block_on(machine.exec(&mut input.as_bytes())).expect("Execution failed")
+        );
+        let out = captured_out.borrow().clone();
+        out
+    }
+
+    #[test]
+    fn test_on_file_change_gosub() {
+        let out = run_with_signal(
+            r#"
+            ON FILECHANGE GOSUB @changed
+            OUT 1
+            OUT 2
+            GOTO @end
+            @changed
+            OUT GETHIDDEN("0FILECHANGE")
+            RETURN
+            @end
+            "#,
+            3,
+            Signal::FileChange("MEMORY:/a.bas".to_owned()),
+        );
+        assert_eq!(["1", "MEMORY:/a.bas", "2"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_file_change_without_handler() {
+        let out = run_with_signal(
+            "OUT 1\nOUT 2\nOUT 3",
+            2,
+            Signal::FileChange("MEMORY:/a.bas".to_owned()),
+        );
+        assert_eq!(["1", "2", "3"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_file_change_reset() {
+        let out = run_with_signal(
+            r#"
+            ON FILECHANGE GOSUB @changed
+            ON FILECHANGE GOSUB 0
+            OUT 1
+            OUT 2
+            GOTO @end
+            @changed
+            OUT "changed"
+            RETURN
+            @end
+            "#,
+            4,
+            Signal::FileChange("MEMORY:/a.bas".to_owned()),
+        );
+        assert_eq!(["1", "2"], out.as_slice());
+    }
+
+    /// Runs the `input` code on a new test machine whose clock advances by 10ms before executing
+    /// every instruction, and returns the captured output.
+    fn run_with_clock(input: &str) -> Vec<String> {
+        let captured_out = Rc::from(RefCell::from(vec![]));
+        let now = Rc::from(Cell::new(Duration::ZERO));
+        let yield_now_fn: YieldNowFn = {
+            let now = now.clone();
+            Box::from(move || {
+                now.set(now.get() + Duration::from_millis(10));
+                Box::pin(async {}) as Pin<Box<dyn Future<Output = ()>>>
+            })
+        };
+        let mut machine = Machine::with_signals_chan_and_yield_now_fn(
+            async_channel::unbounded(),
+            Some(yield_now_fn),
+        );
+        machine.set_clock(Rc::from(move || now.get()));
+        machine.add_command(OutCommand::new(captured_out.clone()));
+        assert_eq!(
+            StopReason::Eof,
+            block_on(machine.exec(&mut input.as_bytes())).expect("Execution failed")
+        );
+        let out = captured_out.borrow().clone();
+        out
+    }
+
+    #[test]
+    fn test_on_interval_gosub() {
+        let out = run_with_clock(
+            r#"
+            ON INTERVAL 25 GOSUB @tick
+            OUT 1
+            OUT 2
+            OUT 3
+            OUT 4
+            GOTO @end
+            @tick
+            OUT "tick"
+            RETURN
+            @end
+            "#,
+        );
+        assert_eq!(["1", "2", "tick", "3", "tick", "4", "tick"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_interval_slow_handler() {
+        let out = run_with_clock(
+            r#"
+            ON INTERVAL 5 GOSUB @tick
+            OUT 1
+            OUT 2
+            GOTO @end
+            @tick
+            OUT "a"
+            OUT "b"
+            RETURN
+            @end
+            "#,
+        );
+        assert_eq!(["a", "b", "1", "a", "b", "2", "a", "b"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_interval_reset() {
+        let out = run_with_clock(
+            r#"
+            ms = 100
+            ON INTERVAL ms GOSUB @tick
+            ON INTERVAL 100 GOSUB 0
+            OUT 1
+            OUT 2
+            GOTO @end
+            @tick
+            OUT "tick"
+            RETURN
+            @end
+            "#,
+        );
+        assert_eq!(["1", "2"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_interval_errors() {
+        do_simple_error_test(
+            "ON INTERVAL 10 GOSUB @a\n@a",
+            "1:13: ON INTERVAL is not supported by this machine",
+        );
+        do_simple_error_test(
+            "ms = \"a\"\nON INTERVAL ms GOSUB @a\n@a",
+            "2:13: Interval must be a number",
+        );
+        do_simple_error_test("ON INTERVAL 0 GOSUB @a\n@a", "1:13: Interval must be positive");
+        do_simple_error_test(
+            "ms = -1\nON INTERVAL ms GOSUB @a\n@a",
+            "2:13: Interval must be positive",
+        );
+        do_simple_error_test("ON INTERVAL 10 GOSUB @a", "1:22: Unknown label a");
+    }
+
+    #[test]
+    fn test_on_gpio_gosub() {
+        let out = run_with_signal(
+            r#"
+            ON GPIO 5, "rising" GOSUB @up
+            ON GPIO 5, "FALLING" GOSUB @down
+            OUT 1
+            OUT 2
+            GOTO @end
+            @up
+            OUT "up"
+            RETURN
+            @down
+            OUT "down"
+            RETURN
+            @end
+            "#,
+            4,
+            Signal::Gpio(5, false),
+        );
+        assert_eq!(["1", "down", "2"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_gpio_both() {
+        let out = run_with_signal(
+            r#"
+            ON GPIO 5, "BOTH" GOSUB @edge
+            OUT 1
+            OUT 2
+            GOTO @end
+            @edge
+            OUT "edge"
+            RETURN
+            @end
+            "#,
+            3,
+            Signal::Gpio(5, true),
+        );
+        assert_eq!(["1", "edge", "2"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_gpio_other_pin_or_edge() {
+        let code = r#"
+            ON GPIO 5, "RISING" GOSUB @up
+            OUT 1
+            OUT 2
+            GOTO @end
+            @up
+            OUT "up"
+            RETURN
+            @end
+            "#;
+        assert_eq!(["1", "2"], run_with_signal(code, 3, Signal::Gpio(6, true)).as_slice());
+        assert_eq!(["1", "2"], run_with_signal(code, 3, Signal::Gpio(5, false)).as_slice());
+    }
+
+    #[test]
+    fn test_on_gpio_reset() {
+        let out = run_with_signal(
+            r#"
+            pin = 5
+            ON GPIO pin, "BOTH" GOSUB @edge
+            ON GPIO 5, "rising" GOSUB 0
+            OUT 1
+            OUT 2
+            GOTO @end
+            @edge
+            OUT "edge"
+            RETURN
+            @end
+            "#,
+            5,
+            Signal::Gpio(5, true),
+        );
+        assert_eq!(["1", "2"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_gpio_errors() {
+        do_simple_error_test(
+            "p = 1.5\nON GPIO p, \"RISING\" GOSUB @a\n@a",
+            "2:9: Pin number must be an integer",
+        );
+        do_simple_error_test(
+            "p = -1\nON GPIO p, \"RISING\" GOSUB @a\n@a",
+            "2:9: Pin number -1 must be positive",
+        );
+        do_simple_error_test(
+            "ON GPIO 256, \"RISING\" GOSUB @a\n@a",
+            "1:9: Pin number 256 is too large",
+        );
+        do_simple_error_test(
+            "e = 3\nON GPIO 5, e GOSUB @a\n@a",
+            "2:12: GPIO edge must be a string",
+        );
+        do_simple_error_test("ON GPIO 5, \"UP\" GOSUB @a\n@a", "1:12: Unknown GPIO edge UP");
+        do_simple_error_test("ON GPIO 5, \"RISING\" GOSUB @a", "1:27: Unknown label a");
+    }
+
+    #[test]
+    fn test_on_key_gosub() {
+        let out = run_with_signal(
+            r#"
+            ON KEY "f1" GOSUB @f1
+            ON KEY "ESC" GOSUB @esc
+            OUT 1
+            OUT 2
+            GOTO @end
+            @f1
+            OUT "f1"
+            RETURN
+            @esc
+            OUT "esc"
+            RETURN
+            @end
+            "#,
+            4,
+            Signal::Key("F1".to_owned()),
+        );
+        assert_eq!(["1", "f1", "2"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_key_handler_does_not_reenter() {
+        let code = r#"
+            ON KEY "F1" GOSUB @f1
+            OUT 1
+            OUT 2
+            OUT 3
+            GOTO @end
+            @f1
+            OUT "begin"
+            OUT "end"
+            RETURN
+            @end
+            "#;
+
+        // The second and third presses arrive while the handler for the first one is running, so
+        // they are queued and handled one at a time after it returns, letting the main program
+        // run one instruction in between as ON INTERVAL does.
+        let f1 = || Signal::Key("F1".to_owned());
+        let out = run_with_signals(code, vec![(3, f1()), (5, f1()), (5, f1())]);
+        assert_eq!(["1", "begin", "end", "2", "begin", "end", "3", "begin", "end"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_gpio_handler_does_not_reenter() {
+        let code = r#"
+            ON GPIO 5, "RISING" GOSUB @up
+            ON GPIO 5, "FALLING" GOSUB @down
+            OUT 1
+            OUT 2
+            OUT 3
+            GOTO @end
+            @up
+            OUT "up"
+            RETURN
+            @down
+            OUT "down"
+            RETURN
+            @end
+            "#;
+
+        let out =
+            run_with_signals(code, vec![(4, Signal::Gpio(5, true)), (6, Signal::Gpio(5, false))]);
+        assert_eq!(["1", "up", "2", "down", "3"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_file_change_handler_does_not_reenter() {
+        let code = r#"
+            ON FILECHANGE GOSUB @changed
+            OUT 1
+            OUT 2
+            OUT 3
+            GOTO @end
+            @changed
+            OUT GETHIDDEN("0FILECHANGE")
+            OUT "done"
+            RETURN
+            @end
+            "#;
+
+        let out = run_with_signals(
+            code,
+            vec![
+                (3, Signal::FileChange("MEMORY:/a.bas".to_owned())),
+                (5, Signal::FileChange("MEMORY:/b.bas".to_owned())),
+            ],
+        );
+        assert_eq!(
+            ["1", "MEMORY:/a.bas", "done", "2", "MEMORY:/b.bas", "done", "3"],
+            out.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_on_key_other_key() {
+        let out = run_with_signal(
+            r#"
+            ON KEY "F1" GOSUB @f1
+            OUT 1
+            OUT 2
+            GOTO @end
+            @f1
+            OUT "f1"
+            RETURN
+            @end
+            "#,
+            3,
+            Signal::Key("F2".to_owned()),
+        );
+        assert_eq!(["1", "2"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_key_reset() {
+        let out = run_with_signal(
+            r#"
+            k$ = "F1"
+            ON KEY k$ GOSUB @f1
+            ON KEY "f1" GOSUB 0
+            OUT 1
+            OUT 2
+            GOTO @end
+            @f1
+            OUT "f1"
+            RETURN
+            @end
+            "#,
+            5,
+            Signal::Key("F1".to_owned()),
+        );
+        assert_eq!(["1", "2"], out.as_slice());
+    }
+
+    #[test]
+    fn test_on_key_errors() {
+        do_simple_error_test("k = 3\nON KEY k GOSUB @a\n@a", "2:8: Key name must be a string");
+        do_simple_error_test("ON KEY \"F1\" GOSUB @a", "1:19: Unknown label a");
+    }
+
     #[test]
     fn test_select_ok() {
         let code = r#"
@@ -0,0 +1,949 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Pretty-printer for EndBASIC programs.
+//!
+//! The formatter re-emits a program from its AST with consistent indentation, spacing and keyword
+//! casing.  Comments and blank lines are not part of the AST, so they are recovered from the
+//! original source code and reattached to the statements that surround them.
+
+use crate::ast::*;
+use crate::highlight::{highlight_line, Highlight};
+use crate::lexer::{Lexer, Token};
+use crate::parser::{self, Result};
+use crate::reader::{LineCol, TAB_LENGTH};
+
+/// Number of spaces to use for each indentation level.
+const INDENT_WIDTH: usize = 4;
+
+/// Original source code of the program, used to recover details that the AST does not keep.
+struct Source {
+    /// Characters of each line of the program, indexed by line number.
+    lines: Vec<Vec<char>>,
+}
+
+impl Source {
+    /// Creates a new source code accessor for the program in `text`.
+    fn new(text: &str) -> Self {
+        let mut lines = vec![vec![]];
+        lines.extend(text.lines().map(|line| line.chars().collect()));
+        Self { lines }
+    }
+
+    /// Returns the text of the integer literal at `pos` if it was written in a base other than
+    /// decimal, as in `&x_0070`, so that the formatted program retains it.
+    fn based_integer(&self, pos: LineCol) -> Option<String> {
+        let line = self.lines.get(pos.line)?;
+        let mut col = 1;
+        let mut chars = line.iter();
+        for ch in chars.by_ref() {
+            if col == pos.col {
+                if *ch != '&' {
+                    return None;
+                }
+                let mut literal = String::from('&');
+                literal.extend(chars.take_while(|ch| ch.is_ascii_alphanumeric() || **ch == '_'));
+                return Some(literal);
+            }
+            if *ch == '\t' {
+                col = (col - 1 + TAB_LENGTH) / TAB_LENGTH * TAB_LENGTH + 1;
+            } else {
+                col += 1;
+            }
+        }
+        None
+    }
+}
+
+/// Returns the priority of the operator at the root of `expr`, or a value higher than any
+/// operator's priority if `expr` is not an operation.
+///
+/// This must be kept in sync with the priorities used by the parser.
+fn priority(expr: &Expr) -> i8 {
+    match expr {
+        Expr::Double(span) if span.value.is_sign_negative() => 5,
+        Expr::Integer(span) if span.value < 0 => 5,
+        Expr::Long(span) if span.value < 0 => 5,
+        Expr::Boolean(_)
+        | Expr::Double(_)
+        | Expr::Integer(_)
+        | Expr::Long(_)
+        | Expr::Text(_)
+        | Expr::Symbol(_)
+        | Expr::Call(_) => 7,
+
+        Expr::Power(_) => 6,
+
+        Expr::Negate(_) | Expr::Not(_) => 5,
+
+        Expr::Multiply(_) | Expr::Divide(_) | Expr::Modulo(_) => 4,
+
+        Expr::Add(_) | Expr::Subtract(_) => 3,
+
+        Expr::ShiftLeft(_) | Expr::ShiftRight(_) => 2,
+
+        Expr::Equal(_)
+        | Expr::NotEqual(_)
+        | Expr::Less(_)
+        | Expr::LessEqual(_)
+        | Expr::Greater(_)
+        | Expr::GreaterEqual(_) => 1,
+
+        Expr::And(_) | Expr::Or(_) | Expr::Xor(_) => 0,
+    }
+}
+
+/// Formats a string literal with `value`, escaping any characters that need it.
+fn format_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len() + 2);
+    text.push('"');
+    for ch in value.chars() {
+        if ch == '"' || ch == '\\' {
+            text.push('\\');
+        }
+        text.push(ch);
+    }
+    text.push('"');
+    text
+}
+
+/// Formats the literal `value`.
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Text(s) => format_text(s),
+        value => value.to_string(),
+    }
+}
+
+/// Formats the target of a `GOTO` or `GOSUB`, which can be a label or a line number.
+fn format_target(target: &str) -> String {
+    if target.chars().all(|ch| ch.is_ascii_digit()) {
+        target.to_owned()
+    } else {
+        format!("@{}", target)
+    }
+}
+
+/// Formats `expr` as an operand of an operator with priority `prio`, adding parenthesis if needed.
+///
+/// `strict` indicates whether operands with the same priority as the operator also need
+/// parenthesis, which is the case for right-hand side operands given that all operators are left
+/// associative.
+fn format_operand(src: &Source, expr: &Expr, prio: i8, strict: bool) -> String {
+    let operand_prio = priority(expr);
+    if operand_prio < prio || (strict && operand_prio == prio) {
+        format!("({})", format_expr(src, expr))
+    } else {
+        format_expr(src, expr)
+    }
+}
+
+/// Formats a binary operation with the `op` operator.
+fn format_binary(src: &Source, expr: &Expr, span: &BinaryOpSpan, op: &str) -> String {
+    let prio = priority(expr);
+    format!(
+        "{} {} {}",
+        format_operand(src, &span.lhs, prio, false),
+        op,
+        format_operand(src, &span.rhs, prio, true)
+    )
+}
+
+/// Formats a comma-separated list of expressions.
+fn format_exprs(src: &Source, exprs: &[Expr]) -> String {
+    exprs.iter().map(|e| format_expr(src, e)).collect::<Vec<String>>().join(", ")
+}
+
+/// Formats the expression `expr`.
+fn format_expr(src: &Source, expr: &Expr) -> String {
+    match expr {
+        Expr::Boolean(span) => Value::Boolean(span.value).to_string(),
+        Expr::Double(span) => Value::Double(span.value).to_string(),
+        Expr::Integer(span) => {
+            src.based_integer(span.pos).unwrap_or_else(|| span.value.to_string())
+        }
+        Expr::Long(span) => {
+            // Longs that would fit in an integer need the suffix to preserve their type.
+            if span.value >= i64::from(i32::MIN) && span.value <= i64::from(i32::MAX) {
+                format!("{}&", span.value)
+            } else {
+                span.value.to_string()
+            }
+        }
+        Expr::Text(span) => format_text(&span.value),
+        Expr::Symbol(span) => span.vref.to_string(),
+
+        Expr::Add(span) => format_binary(src, expr, span, "+"),
+        Expr::Subtract(span) => format_binary(src, expr, span, "-"),
+        Expr::Multiply(span) => format_binary(src, expr, span, "*"),
+        Expr::Divide(span) => format_binary(src, expr, span, "/"),
+        Expr::Modulo(span) => format_binary(src, expr, span, "MOD"),
+        Expr::Power(span) => format_binary(src, expr, span, "^"),
+        Expr::Negate(span) => {
+            format!("-{}", format_operand(src, &span.expr, priority(expr), false))
+        }
+
+        Expr::Equal(span) => format_binary(src, expr, span, "="),
+        Expr::NotEqual(span) => format_binary(src, expr, span, "<>"),
+        Expr::Less(span) => format_binary(src, expr, span, "<"),
+        Expr::LessEqual(span) => format_binary(src, expr, span, "<="),
+        Expr::Greater(span) => format_binary(src, expr, span, ">"),
+        Expr::GreaterEqual(span) => format_binary(src, expr, span, ">="),
+
+        Expr::And(span) => format_binary(src, expr, span, "AND"),
+        Expr::Not(span) => {
+            format!("NOT {}", format_operand(src, &span.expr, priority(expr), false))
+        }
+        Expr::Or(span) => format_binary(src, expr, span, "OR"),
+        Expr::Xor(span) => format_binary(src, expr, span, "XOR"),
+
+        Expr::ShiftLeft(span) => format_binary(src, expr, span, "<<"),
+        Expr::ShiftRight(span) => format_binary(src, expr, span, ">>"),
+
+        Expr::Call(span) => format!("{}({})", span.fref, format_exprs(src, &span.args)),
+    }
+}
+
+/// Formats the arguments to a builtin call, including the leading space if there are any.
+fn format_args(src: &Source, args: &[ArgSpan]) -> String {
+    let mut text = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        let is_last = i == args.len() - 1;
+        if arg.expr.is_none() && is_last && i > 0 {
+            // This is the filler argument after a trailing separator, as in `PRINT a;`.
+            break;
+        }
+        if i > 0 || arg.expr.is_some() || arg.sep != ArgSep::End {
+            text.push(' ');
+        }
+        if let Some(expr) = &arg.expr {
+            text.push_str(&format_expr(src, expr));
+        }
+        match arg.sep {
+            ArgSep::End => (),
+            ArgSep::Short => text.push(';'),
+            ArgSep::Long => text.push(','),
+            ArgSep::As => text.push_str(" AS"),
+            ArgSep::Timeout => text.push_str(" TIMEOUT"),
+        }
+    }
+    text
+}
+
+/// Formats a `CASE` guard.
+fn format_case_guard(src: &Source, guard: &CaseGuardSpan) -> String {
+    match guard {
+        CaseGuardSpan::Is(CaseRelOp::Equal, expr) => format_expr(src, expr),
+        CaseGuardSpan::Is(op, expr) => {
+            let op = match op {
+                CaseRelOp::Equal => "=",
+                CaseRelOp::NotEqual => "<>",
+                CaseRelOp::Less => "<",
+                CaseRelOp::LessEqual => "<=",
+                CaseRelOp::Greater => ">",
+                CaseRelOp::GreaterEqual => ">=",
+            };
+            format!("IS {} {}", op, format_expr(src, expr))
+        }
+        CaseGuardSpan::To(from, to) => {
+            format!("{} TO {}", format_expr(src, from), format_expr(src, to))
+        }
+    }
+}
+
+/// Extracts the comments of the program in `text`, indexed by line number.  The boolean in each
+/// entry indicates whether the comment follows code on the same line.
+fn find_comments(text: &str) -> Vec<Option<(String, bool)>> {
+    let mut comments = vec![None];
+    for line in text.lines() {
+        let comment = highlight_line(line)
+            .into_iter()
+            .find(|span| span.highlight == Highlight::Comment)
+            .map(|span| {
+                let chars = line.chars().collect::<Vec<char>>();
+                let mut comment =
+                    chars[span.start..span.start + span.len].iter().collect::<String>();
+                if comment.len() >= 3 && comment[..3].eq_ignore_ascii_case("REM") {
+                    comment.replace_range(..3, "REM");
+                }
+                let trailing = chars[..span.start].iter().any(|ch| !ch.is_whitespace());
+                (comment, trailing)
+            });
+        comments.push(comment);
+    }
+    comments
+}
+
+/// State of the pretty-printer.
+struct Formatter {
+    /// Tokens of the original program, used to locate the statements that lack positions in the
+    /// AST and the keywords that close blocks.
+    tokens: Vec<(Token, usize)>,
+
+    /// Index of the next token in `tokens` that has not been matched yet.
+    next_token: usize,
+
+    /// Original source code of the program.
+    source: Source,
+
+    /// Comments of the original program, indexed by line number.  Comments are removed from here
+    /// as they are emitted.
+    comments: Vec<Option<(String, bool)>>,
+
+    /// Whether each line of the original program is blank, indexed by line number.
+    blanks: Vec<bool>,
+
+    /// Last line of the original program that has been processed.
+    last_line: usize,
+
+    /// Current indentation level.
+    indent: usize,
+
+    /// Text to prepend to the next emitted line, used to keep line numbers next to the statements
+    /// that follow them.
+    prefix: String,
+
+    /// The formatted program.
+    output: String,
+}
+
+impl Formatter {
+    /// Creates a new formatter for the program in `text`.
+    fn new(text: &str) -> Self {
+        let mut tokens = vec![];
+        let mut input = text.as_bytes();
+        let mut lexer = Lexer::from(&mut input);
+        while let Ok(token_span) = lexer.read() {
+            match token_span.token {
+                Token::Eof => break,
+                Token::Eol => (),
+                token => tokens.push((token, token_span.pos.line)),
+            }
+        }
+
+        let mut blanks = vec![false];
+        blanks.extend(text.lines().map(|line| line.trim().is_empty()));
+
+        Self {
+            tokens,
+            next_token: 0,
+            source: Source::new(text),
+            comments: find_comments(text),
+            blanks,
+            last_line: 0,
+            indent: 0,
+            prefix: String::new(),
+            output: String::new(),
+        }
+    }
+
+    /// Finds the next token for which `matches` returns true and returns its line number.  The
+    /// token is consumed along with any tokens before it.
+    fn find<F: Fn(&Token, Option<&Token>) -> bool>(&mut self, matches: F) -> Option<usize> {
+        for i in self.next_token..self.tokens.len() {
+            let next = self.tokens.get(i + 1).map(|(token, _)| token);
+            if matches(&self.tokens[i].0, next) {
+                self.next_token = i + 1;
+                return Some(self.tokens[i].1);
+            }
+        }
+        None
+    }
+
+    /// Finds the next occurrence of `token` and returns its line number.
+    fn find_token(&mut self, token: Token) -> Option<usize> {
+        self.find(|t, _| *t == token)
+    }
+
+    /// Finds the next `END` keyword followed by `token` and returns its line number.
+    fn find_end(&mut self, token: Token) -> Option<usize> {
+        self.find(|t, next| *t == Token::End && next == Some(&token))
+    }
+
+    /// Consumes all tokens that appear before `line` and returns `line`.
+    fn skip_to(&mut self, line: usize) -> Option<usize> {
+        while self.next_token < self.tokens.len() && self.tokens[self.next_token].1 < line {
+            self.next_token += 1;
+        }
+        Some(line)
+    }
+
+    /// Emits all comments and blank lines that appear before `line` at the current indentation.
+    fn flush(&mut self, line: usize) {
+        let line = line.min(self.comments.len());
+        while self.last_line + 1 < line {
+            self.last_line += 1;
+            if let Some((comment, _)) = self.comments[self.last_line].take() {
+                self.write(&comment);
+            } else if self.blanks[self.last_line] && !self.output.is_empty() {
+                // Collapse runs of blank lines into a single one.
+                if !self.output.ends_with("\n\n") {
+                    self.output.push('\n');
+                }
+            }
+        }
+    }
+
+    /// Appends a single line of `text` to the output at the current indentation.
+    fn write(&mut self, text: &str) {
+        for _ in 0..self.indent * INDENT_WIDTH {
+            self.output.push(' ');
+        }
+        self.output.push_str(&self.prefix);
+        self.prefix.clear();
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    /// Emits a line of `text` that originally appeared in `line`, if known, along with any
+    /// comments and blank lines that precede it and any comment that follows it.
+    fn emit(&mut self, line: Option<usize>, text: &str) {
+        match line {
+            Some(line) if line > self.last_line => {
+                self.flush(line);
+                self.last_line = line;
+                match self.comments.get_mut(line).and_then(Option::take) {
+                    Some((comment, true)) => self.write(&format!("{} {}", text, comment)),
+                    Some((comment, false)) => {
+                        // Cannot happen for valid code, but handle it just in case.
+                        self.write(text);
+                        self.write(&comment);
+                    }
+                    None => self.write(text),
+                }
+            }
+            _ => self.write(text),
+        }
+    }
+
+    /// Emits the line that closes or splits a block, which originally appeared in `line`.  Any
+    /// comments that precede it are kept within the block.
+    fn emit_closing(&mut self, line: Option<usize>, text: &str) {
+        if let Some(line) = line {
+            self.indent += 1;
+            self.flush(line);
+            self.indent -= 1;
+        }
+        self.emit(line, text);
+    }
+
+    /// Emits the statements in `body`, one indentation level deeper than the current one.
+    fn emit_block(&mut self, body: &[Statement]) {
+        self.indent += 1;
+        self.emit_stmts(body);
+        self.indent -= 1;
+    }
+
+    /// Emits the sequence of statements in `stmts`.
+    fn emit_stmts(&mut self, stmts: &[Statement]) {
+        let mut iter = stmts.iter().peekable();
+        while let Some(stmt) = iter.next() {
+            match stmt {
+                Statement::Label(span)
+                    if iter.peek().is_some() && span.name.chars().all(|c| c.is_ascii_digit()) =>
+                {
+                    // Keep line numbers on the same line as the statement they tag.
+                    let line = self.skip_to(span.name_pos.line);
+                    if let Some(line) = line {
+                        if line > self.last_line {
+                            self.flush(line);
+                        }
+                    }
+                    self.prefix = format!("{} ", span.name);
+                }
+                stmt => self.emit_stmt(stmt),
+            }
+        }
+    }
+
+    /// Emits the `IF` statement in `span`.
+    fn emit_if(&mut self, span: &IfSpan) {
+        let line = self.skip_to(span.branches[0].guard.start_pos().line);
+        self.find_token(Token::Then);
+        let uniline = line.is_some() && self.tokens.get(self.next_token).map(|t| t.1) == line;
+
+        for (i, branch) in span.branches.iter().enumerate() {
+            if i == 0 {
+                self.emit(line, &format!("IF {} THEN", format_expr(&self.source, &branch.guard)));
+            } else {
+                let line = self.find(|t, _| *t == Token::Else || *t == Token::Elseif);
+                let is_else = match line {
+                    Some(_) => self.tokens[self.next_token - 1].0 == Token::Else,
+                    None => {
+                        i == span.branches.len() - 1
+                            && matches!(
+                                branch.guard,
+                                Expr::Boolean(BooleanSpan { value: true, .. })
+                            )
+                    }
+                };
+                if is_else {
+                    self.emit_closing(line, "ELSE");
+                } else {
+                    self.emit_closing(
+                        line,
+                        &format!("ELSEIF {} THEN", format_expr(&self.source, &branch.guard)),
+                    );
+                }
+            }
+            self.emit_block(&branch.body);
+        }
+
+        let line = if uniline { None } else { self.find_end(Token::If) };
+        self.emit_closing(line, "END IF");
+    }
+
+    /// Emits the single statement `stmt`.
+    fn emit_stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::ArrayAssignment(span) => {
+                let line = self.skip_to(span.vref_pos.line);
+                let text = format!(
+                    "{}({}) = {}",
+                    span.vref,
+                    format_exprs(&self.source, &span.subscripts),
+                    format_expr(&self.source, &span.expr)
+                );
+                self.emit(line, &text);
+            }
+
+            Statement::Assignment(span) => {
+                let line = self.skip_to(span.vref_pos.line);
+                self.emit(
+                    line,
+                    &format!("{} = {}", span.vref, format_expr(&self.source, &span.expr)),
+                );
+            }
+
+            Statement::BuiltinCall(span) => {
+                let line = self.skip_to(span.name_pos.line);
+                let text = format!(
+                    "{}{}",
+                    span.name.to_ascii_uppercase(),
+                    format_args(&self.source, &span.args)
+                );
+                self.emit(line, &text);
+            }
+
+            Statement::Data(span) => {
+                let line = self.find_token(Token::Data);
+                let values = span
+                    .values
+                    .iter()
+                    .map(|v| v.as_ref().map(format_value).unwrap_or_default())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                let values = values.trim_end();
+                if values.is_empty() {
+                    self.emit(line, "DATA");
+                } else {
+                    self.emit(line, &format!("DATA {}", values));
+                }
+            }
+
+            Statement::Dim(span) => {
+                let line = self.skip_to(span.name_pos.line);
+                self.emit(line, &format!("DIM {} AS {}", span.name, span.vtype));
+            }
+
+            Statement::DimArray(span) => {
+                let line = self.skip_to(span.name_pos.line);
+                let text = format!(
+                    "DIM {}({}) AS {}",
+                    span.name,
+                    format_exprs(&self.source, &span.dimensions),
+                    span.subtype
+                );
+                self.emit(line, &text);
+            }
+
+            Statement::Do(span) => {
+                let line = self.find_token(Token::Do);
+                match &span.guard {
+                    DoGuard::PreUntil(guard) => {
+                        self.emit(line, &format!("DO UNTIL {}", format_expr(&self.source, guard)))
+                    }
+                    DoGuard::PreWhile(guard) => {
+                        self.emit(line, &format!("DO WHILE {}", format_expr(&self.source, guard)))
+                    }
+                    DoGuard::Infinite | DoGuard::PostUntil(_) | DoGuard::PostWhile(_) => {
+                        self.emit(line, "DO")
+                    }
+                }
+                self.emit_block(&span.body);
+                let line = self.find_token(Token::Loop);
+                match &span.guard {
+                    DoGuard::PostUntil(guard) => self.emit_closing(
+                        line,
+                        &format!("LOOP UNTIL {}", format_expr(&self.source, guard)),
+                    ),
+                    DoGuard::PostWhile(guard) => self.emit_closing(
+                        line,
+                        &format!("LOOP WHILE {}", format_expr(&self.source, guard)),
+                    ),
+                    DoGuard::Infinite | DoGuard::PreUntil(_) | DoGuard::PreWhile(_) => {
+                        self.emit_closing(line, "LOOP")
+                    }
+                }
+            }
+
+            Statement::End(span) => {
+                let line = self.find(|t, _| *t == Token::End || *t == Token::Exit);
+                let keyword = match line {
+                    Some(_) if self.tokens[self.next_token - 1].0 == Token::Exit => "EXIT",
+                    _ => "END",
+                };
+                match &span.code {
+                    Some(code) => {
+                        let text = format!("{} {}", keyword, format_expr(&self.source, code));
+                        self.emit(line, &text)
+                    }
+                    None => self.emit(line, keyword),
+                }
+            }
+
+            Statement::ExitDo(span) => {
+                let line = self.skip_to(span.pos.line);
+                self.find_token(Token::Do);
+                self.emit(line, "EXIT DO");
+            }
+
+            Statement::For(span) => {
+                let line = self.skip_to(span.iter_pos.line);
+                let end = match &span.end {
+                    Expr::LessEqual(op) | Expr::GreaterEqual(op) => {
+                        format_expr(&self.source, &op.rhs)
+                    }
+                    _ => unreachable!("The parser only generates relational end conditions"),
+                };
+                let mut text = format!(
+                    "FOR {} = {} TO {}",
+                    span.iter,
+                    format_expr(&self.source, &span.start),
+                    end
+                );
+                match &span.next {
+                    Expr::Add(op) => match &op.rhs {
+                        Expr::Integer(step) if step.value == 1 => (),
+                        step => text += &format!(" STEP {}", format_expr(&self.source, step)),
+                    },
+                    _ => unreachable!("The parser only generates additions as the next value"),
+                }
+                self.emit(line, &text);
+                self.emit_block(&span.body);
+                let line = self.find_token(Token::Next);
+                self.emit_closing(line, "NEXT");
+            }
+
+            Statement::ForEach(span) => {
+                let line = self.skip_to(span.iter_pos.line);
+                self.emit(line, &format!("FOR EACH {} IN {}", span.iter, span.array));
+                self.emit_block(&span.body);
+                let line = self.find_token(Token::Next);
+                self.emit_closing(line, "NEXT");
+            }
+
+            Statement::Gosub(span) => {
+                let line = self.skip_to(span.target_pos.line);
+                self.emit(line, &format!("GOSUB {}", format_target(&span.target)));
+            }
+
+            Statement::Goto(span) => {
+                let line = self.skip_to(span.target_pos.line);
+                self.emit(line, &format!("GOTO {}", format_target(&span.target)));
+            }
+
+            Statement::If(span) => self.emit_if(span),
+
+            Statement::Label(span) => {
+                let line = self.skip_to(span.name_pos.line);
+                self.emit(line, &format_target(&span.name));
+            }
+
+            Statement::OnError(span) => {
+                let line = self.find_token(Token::On);
+                match span {
+                    OnErrorSpan::Goto(span) => {
+                        self.emit(line, &format!("ON ERROR GOTO {}", format_target(&span.target)))
+                    }
+                    OnErrorSpan::Reset => self.emit(line, "ON ERROR GOTO 0"),
+                    OnErrorSpan::ResumeNext => self.emit(line, "ON ERROR RESUME NEXT"),
+                }
+            }
+
+            Statement::OnFileChange(span) => {
+                let line = self.find_token(Token::On);
+                match span {
+                    OnFileChangeSpan::Gosub(span) => self.emit(
+                        line,
+                        &format!("ON FILECHANGE GOSUB {}", format_target(&span.target)),
+                    ),
+                    OnFileChangeSpan::Reset => self.emit(line, "ON FILECHANGE GOSUB 0"),
+                }
+            }
+
+            Statement::OnGpio(span) => {
+                let line = self.find_token(Token::On);
+                let target = match &span.handler {
+                    Some(span) => format_target(&span.target),
+                    None => "0".to_owned(),
+                };
+                let pin = format_expr(&self.source, &span.pin);
+                let edge = format_expr(&self.source, &span.edge);
+                self.emit(line, &format!("ON GPIO {}, {} GOSUB {}", pin, edge, target));
+            }
+
+            Statement::OnInterval(span) => {
+                let line = self.find_token(Token::On);
+                let target = match &span.handler {
+                    Some(span) => format_target(&span.target),
+                    None => "0".to_owned(),
+                };
+                let period = format_expr(&self.source, &span.period);
+                self.emit(line, &format!("ON INTERVAL {} GOSUB {}", period, target));
+            }
+
+            Statement::OnKey(span) => {
+                let line = self.find_token(Token::On);
+                let target = match &span.handler {
+                    Some(span) => format_target(&span.target),
+                    None => "0".to_owned(),
+                };
+                let key = format_expr(&self.source, &span.key);
+                self.emit(line, &format!("ON KEY {} GOSUB {}", key, target));
+            }
+
+            Statement::Redim(span) => {
+                let line = self.skip_to(span.name_pos.line);
+                let preserve = if span.preserve { "PRESERVE " } else { "" };
+                let text = format!(
+                    "REDIM {}{}({})",
+                    preserve,
+                    span.name,
+                    format_exprs(&self.source, &span.dimensions)
+                );
+                self.emit(line, &text);
+            }
+
+            Statement::Return(span) => {
+                let line = self.skip_to(span.pos.line);
+                self.emit(line, "RETURN");
+            }
+
+            Statement::Select(span) => {
+                let line = self.skip_to(span.expr.start_pos().line);
+                self.emit(line, &format!("SELECT CASE {}", format_expr(&self.source, &span.expr)));
+                self.indent += 1;
+                for case in &span.cases {
+                    let line = self.find_token(Token::Case);
+                    if case.guards.is_empty() {
+                        self.emit_closing(line, "CASE ELSE");
+                    } else {
+                        let guards = case
+                            .guards
+                            .iter()
+                            .map(|g| format_case_guard(&self.source, g))
+                            .collect::<Vec<String>>()
+                            .join(", ");
+                        self.emit_closing(line, &format!("CASE {}", guards));
+                    }
+                    self.emit_block(&case.body);
+                }
+                if !span.cases.is_empty() {
+                    // Keep any comments at the end of the last case within its body.
+                    self.indent += 1;
+                    self.flush(span.end_pos.line);
+                    self.indent -= 1;
+                }
+                self.indent -= 1;
+                let line = self.find_end(Token::Select);
+                self.emit_closing(line, "END SELECT");
+            }
+
+            Statement::While(span) => {
+                let line = self.skip_to(span.expr.start_pos().line);
+                self.emit(line, &format!("WHILE {}", format_expr(&self.source, &span.expr)));
+                self.emit_block(&span.body);
+                let line = self.find_token(Token::Wend);
+                self.emit_closing(line, "WEND");
+            }
+        }
+    }
+
+    /// Emits any remaining comments and returns the formatted program.
+    fn finish(mut self) -> String {
+        if !self.prefix.is_empty() {
+            let prefix = self.prefix.split_off(0);
+            self.write(prefix.trim_end());
+        }
+        self.flush(usize::MAX);
+        while self.output.ends_with("\n\n") {
+            self.output.pop();
+        }
+        self.output
+    }
+}
+
+/// Formats the program in `text` and returns the result.
+///
+/// Fails if the program has any syntax errors, in which case it cannot be formatted.
+pub fn format_program(text: &str) -> Result<String> {
+    let stmts = parser::parse(&mut text.as_bytes())?;
+    let mut formatter = Formatter::new(text);
+    formatter.emit_stmts(&stmts);
+    Ok(formatter.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Formats `input` and checks that the result matches `golden`.  Also checks that formatting
+    /// is idempotent: formatting the result again must yield the same result.
+    fn do_ok_test(input: &str, golden: &str) {
+        let formatted = format_program(input).unwrap();
+        assert_eq!(golden, formatted);
+        assert_eq!(golden, format_program(&formatted).unwrap());
+    }
+
+    #[test]
+    fn test_format_empty() {
+        do_ok_test("", "");
+        do_ok_test("\n\n   \n", "");
+    }
+
+    #[test]
+    fn test_format_simple_statements() {
+        do_ok_test(
+            "a=1\nb$ = \"x\" : print a ,b$;\ndim c as integer\n",
+            "a = 1\nb$ = \"x\"\nPRINT a, b$;\nDIM c AS INTEGER\n",
+        );
+        do_ok_test("print\nprint ;\nprint , 3", "PRINT\nPRINT ;\nPRINT , 3\n");
+        do_ok_test("input \"x\" ; a", "INPUT \"x\"; a\n");
+        do_ok_test("open \"a\" as 1", "OPEN \"a\" AS 1\n");
+        do_ok_test("input a timeout 5", "INPUT a TIMEOUT 5\n");
+        do_ok_test("a(1,2)=3:dim b(4) as double", "a(1, 2) = 3\nDIM b(4) AS DOUBLE\n");
+        do_ok_test("redim preserve b(5 ,6)", "REDIM PRESERVE b(5, 6)\n");
+        do_ok_test("end\nend 3\nexit 4", "END\nEND 3\nEXIT 4\n");
+    }
+
+    #[test]
+    fn test_format_literals() {
+        do_ok_test(
+            "a = 1.5 + 2.0 + true + \"a\\\"b\\\\c\"",
+            "a = 1.5 + 2.0 + TRUE + \"a\\\"b\\\\c\"\n",
+        );
+        do_ok_test("data 1, , \"x\",-2.5,", "DATA 1, , \"x\", -2.5,\n");
+        do_ok_test("data", "DATA\n");
+        do_ok_test("data ,", "DATA ,\n");
+        do_ok_test("a = 5& + 5000000000 + -3&", "a = 5& + 5000000000 + -3&\n");
+        do_ok_test("a! = 1.5! + 2#", "a# = 1.5 + 2.0\n");
+    }
+
+    #[test]
+    fn test_format_operators() {
+        do_ok_test("a = (1 + 2) * 3 - 4", "a = (1 + 2) * 3 - 4\n");
+        do_ok_test("a = 1 - (2 - 3)", "a = 1 - (2 - 3)\n");
+        do_ok_test("a = (1 - 2) - 3", "a = 1 - 2 - 3\n");
+        do_ok_test("a = -(b + 1) ^ 2", "a = -(b + 1) ^ 2\n");
+        do_ok_test("a = not (b and c) or d mod 2 = 0", "a = NOT (b AND c) OR d MOD 2 = 0\n");
+        do_ok_test("a = x<<1>>2 <> 3", "a = x << 1 >> 2 <> 3\n");
+        do_ok_test("a = len(\"x\", b + 1)", "a = len(\"x\", b + 1)\n");
+        do_ok_test("a = &x_00ff AND\t&b10 + 3", "a = &x_00ff AND &b10 + 3\n");
+    }
+
+    #[test]
+    fn test_format_if() {
+        do_ok_test(
+            "if a then\nprint 1\nelseif b then\nprint 2\nelse\nprint 3\nend if",
+            "IF a THEN\n    PRINT 1\nELSEIF b THEN\n    PRINT 2\nELSE\n    PRINT 3\nEND IF\n",
+        );
+        do_ok_test(
+            "if a then print 1 else print 2\nprint 3",
+            "IF a THEN\n    PRINT 1\nELSE\n    PRINT 2\nEND IF\nPRINT 3\n",
+        );
+    }
+
+    #[test]
+    fn test_format_loops() {
+        do_ok_test(
+            "for i = 1 to 10\nfor j = 10 to 1 step -1\nprint i\nnext\nnext",
+            "FOR i = 1 TO 10\n    FOR j = 10 TO 1 STEP -1\n        PRINT i\n    NEXT\nNEXT\n",
+        );
+        do_ok_test("for each x in arr\nnext", "FOR EACH x IN arr\nNEXT\n");
+        do_ok_test(
+            "do until a\nexit do\nloop\ndo\nloop while b\ndo\nloop",
+            "DO UNTIL a\n    EXIT DO\nLOOP\nDO\nLOOP WHILE b\nDO\nLOOP\n",
+        );
+        do_ok_test("while a < 3\na = a + 1\nwend", "WHILE a < 3\n    a = a + 1\nWEND\n");
+    }
+
+    #[test]
+    fn test_format_select() {
+        do_ok_test(
+            "select case a\ncase 1, 2\nprint 1\ncase is >= 5, 7 to 8\ncase else\nprint 2\n' last\nend select",
+            "SELECT CASE a\n    CASE 1, 2\n        PRINT 1\n    CASE IS >= 5, 7 TO 8\n    CASE ELSE\n        PRINT 2\n        ' last\nEND SELECT\n",
+        );
+    }
+
+    #[test]
+    fn test_format_jumps() {
+        do_ok_test(
+            "@a\ngoto @a\ngosub @b\n@b:return\non error goto @a\non error goto 0\non error resume next",
+            "@a\nGOTO @a\nGOSUB @b\n@b\nRETURN\nON ERROR GOTO @a\nON ERROR GOTO 0\nON ERROR RESUME NEXT\n",
+        );
+        do_ok_test("10 print 1\n20 goto 10", "10 PRINT 1\n20 GOTO 10\n");
+        do_ok_test(
+            "on filechange gosub @a\non filechange gosub 0",
+            "ON FILECHANGE GOSUB @a\nON FILECHANGE GOSUB 0\n",
+        );
+        do_ok_test(
+            "on gpio 5,\"rising\" gosub @a\non gpio p, e$ gosub 0",
+            "ON GPIO 5, \"rising\" GOSUB @a\nON GPIO p, e$ GOSUB 0\n",
+        );
+        do_ok_test(
+            "on interval 100 gosub @a\non interval ms gosub 0",
+            "ON INTERVAL 100 GOSUB @a\nON INTERVAL ms GOSUB 0\n",
+        );
+        do_ok_test(
+            "on key \"f1\" gosub @a\non key k$ gosub 0",
+            "ON KEY \"f1\" GOSUB @a\nON KEY k$ GOSUB 0\n",
+        );
+    }
+
+    #[test]
+    fn test_format_comments() {
+        do_ok_test(
+            "' Header\n\n\n\nrem second\na = 1 ' trailing\n  REM foo\nb = 2\n' footer\n\n",
+            "' Header\n\nREM second\na = 1 ' trailing\nREM foo\nb = 2\n' footer\n",
+        );
+        do_ok_test(
+            "while a\n' inside\nprint 1 'x\n' end of body\nwend 'closing\n' after",
+            "WHILE a\n    ' inside\n    PRINT 1 'x\n    ' end of body\nWEND 'closing\n' after\n",
+        );
+        do_ok_test(
+            "if a then\nprint 1\n' before else\nelse\nprint 2\nend if",
+            "IF a THEN\n    PRINT 1\n    ' before else\nELSE\n    PRINT 2\nEND IF\n",
+        );
+    }
+
+    #[test]
+    fn test_format_errors() {
+        assert_eq!(
+            "1:5: Not enough values to apply operator",
+            format_program("a = = 1").unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,289 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Syntax highlighting support for EndBASIC source code.
+
+use crate::lexer::{Lexer, Token};
+use crate::reader::TAB_LENGTH;
+
+/// Classes of source code fragments that deserve a distinct rendering.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Highlight {
+    /// A comment introduced by `'` or `REM`, which extends until the end of the line.
+    Comment,
+
+    /// A reserved word of the language, including the boolean literals and word operators.
+    Keyword,
+
+    /// A textual label such as `@label`.
+    Label,
+
+    /// An integer or double literal.
+    Number,
+
+    /// A string literal, including its delimiters.
+    Text,
+}
+
+/// A fragment of a line of source code that should be rendered in a specific way.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HighlightSpan {
+    /// The class of the fragment.
+    pub highlight: Highlight,
+
+    /// Position of the first character of the fragment within the line, starting at zero.
+    pub start: usize,
+
+    /// Length of the fragment in characters.
+    pub len: usize,
+}
+
+/// Returns the highlighting class of `token`, if any.
+fn classify(token: &Token) -> Option<Highlight> {
+    match token {
+        Token::Boolean(_) => Some(Highlight::Keyword),
+        Token::Double(_) | Token::Integer(_) | Token::Long(_) => Some(Highlight::Number),
+        Token::Text(_) => Some(Highlight::Text),
+        Token::Label(_) => Some(Highlight::Label),
+
+        Token::And
+        | Token::Modulo
+        | Token::Not
+        | Token::Or
+        | Token::Xor
+        | Token::Case
+        | Token::Data
+        | Token::Do
+        | Token::Else
+        | Token::Elseif
+        | Token::End
+        | Token::Error
+        | Token::Exit
+        | Token::For
+        | Token::Gosub
+        | Token::Goto
+        | Token::If
+        | Token::Is
+        | Token::Loop
+        | Token::Next
+        | Token::On
+        | Token::Resume
+        | Token::Return
+        | Token::Select
+        | Token::Step
+        | Token::Then
+        | Token::Timeout
+        | Token::To
+        | Token::Until
+        | Token::Wend
+        | Token::While
+        | Token::Dim
+        | Token::Redim
+        | Token::Preserve
+        | Token::As
+        | Token::BooleanName
+        | Token::DoubleName
+        | Token::IntegerName
+        | Token::LongName
+        | Token::TextName => Some(Highlight::Keyword),
+
+        _ => None,
+    }
+}
+
+/// Computes the fragments of `line` that deserve highlighting, sorted by their position.
+///
+/// The `line` is expected to be a single line of code.  Any text after a newline character is
+/// ignored.  Malformed code is tolerated, which is necessary to highlight code as it is being
+/// typed: for example, a string literal that lacks its closing quote is highlighted until the end
+/// of the line, and a malformed numeric literal is still highlighted as a number.
+pub fn highlight_line(line: &str) -> Vec<HighlightSpan> {
+    let line = match line.find('\n') {
+        Some(pos) => &line[..pos],
+        None => line,
+    };
+    let chars = line.chars().collect::<Vec<char>>();
+
+    // The lexer reports positions as columns, which account for tab stops.  Compute the column of
+    // every character (plus one past the end) so that we can map them back to character offsets.
+    let mut cols = Vec::with_capacity(chars.len() + 1);
+    let mut col = 1;
+    for ch in &chars {
+        cols.push(col);
+        if *ch == '\t' {
+            col = (col - 1 + TAB_LENGTH) / TAB_LENGTH * TAB_LENGTH + 1;
+        } else {
+            col += 1;
+        }
+    }
+    cols.push(col);
+    let to_index = |col: usize| match cols.binary_search(&col) {
+        Ok(i) => i,
+        Err(i) => i,
+    };
+
+    let mut spans: Vec<HighlightSpan> = vec![];
+    let mut input = line.as_bytes();
+    let mut lexer = Lexer::from(&mut input);
+    let mut last_end = 0;
+    // Set after a malformed token to indicate whether its span has to absorb the gap that follows.
+    let mut after_bad: Option<bool> = None;
+    while let Ok(token_span) = lexer.read() {
+        let start = to_index(token_span.pos.col);
+
+        // The lexer only skips over whitespace and comments, so any non-whitespace characters
+        // between two tokens are a comment... unless they are the remainder of a malformed token.
+        let gap = &chars[last_end..start];
+        if let Some(first) = gap.iter().position(|ch| !ch.is_whitespace()) {
+            let end = last_end + gap.iter().rposition(|ch| !ch.is_whitespace()).unwrap() + 1;
+            match after_bad {
+                Some(true) => {
+                    let last = spans.last_mut().expect("Malformed token must have a span");
+                    last.len = end - last.start;
+                }
+                Some(false) => (),
+                None => spans.push(HighlightSpan {
+                    highlight: Highlight::Comment,
+                    start: last_end + first,
+                    len: end - last_end - first,
+                }),
+            }
+        }
+
+        let len = token_span.length;
+        after_bad = None;
+        let len = match token_span.token {
+            Token::Eof => break,
+            Token::Bad(_) => {
+                // Malformed tokens do not report their full length, so we extend their span to
+                // cover any characters in the gap that follows them.  Strings can only be malformed
+                // due to a missing closing quote, so they always extend until the end of the line.
+                let (highlight, len) = match chars.get(start) {
+                    Some('"') => (Some(Highlight::Text), chars.len() - start),
+                    Some(ch) if ch.is_ascii_digit() || *ch == '&' => (Some(Highlight::Number), len),
+                    _ => (None, len),
+                };
+                if let Some(highlight) = highlight {
+                    spans.push(HighlightSpan { highlight, start, len });
+                }
+                after_bad = Some(highlight.is_some());
+                len
+            }
+            token => {
+                if let Some(highlight) = classify(&token) {
+                    spans.push(HighlightSpan { highlight, start, len });
+                }
+                len
+            }
+        };
+        last_end = start + len;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Syntactic sugar to instantiate a `HighlightSpan`.
+    fn hs(highlight: Highlight, start: usize, len: usize) -> HighlightSpan {
+        HighlightSpan { highlight, start, len }
+    }
+
+    #[test]
+    fn test_highlight_line_empty() {
+        assert!(highlight_line("").is_empty());
+        assert!(highlight_line("   ").is_empty());
+        assert_eq!(
+            vec![hs(Highlight::Number, 10, 1), hs(Highlight::Number, 14, 1)],
+            highlight_line("a = b + c(3 > 4)")
+        );
+    }
+
+    #[test]
+    fn test_highlight_line_tokens() {
+        assert_eq!(
+            vec![
+                hs(Highlight::Keyword, 0, 2),
+                hs(Highlight::Number, 3, 1),
+                hs(Highlight::Keyword, 5, 3),
+                hs(Highlight::Keyword, 9, 4),
+                hs(Highlight::Keyword, 14, 4),
+                hs(Highlight::Text, 25, 6),
+                hs(Highlight::Keyword, 33, 3),
+                hs(Highlight::Keyword, 37, 2),
+            ],
+            highlight_line(r#"IF 1 AND TRUE THEN PRINT "a\"b": END IF"#)
+        );
+
+        assert_eq!(
+            vec![
+                hs(Highlight::Label, 0, 4),
+                hs(Highlight::Keyword, 5, 3),
+                hs(Highlight::Keyword, 12, 2),
+                hs(Highlight::Keyword, 15, 7),
+                hs(Highlight::Number, 28, 3),
+            ],
+            highlight_line("@foo DIM a$ AS INTEGER: b = 1.5")
+        );
+    }
+
+    #[test]
+    fn test_highlight_line_comments() {
+        assert_eq!(vec![hs(Highlight::Comment, 2, 5)], highlight_line("  ' foo"));
+        assert_eq!(vec![hs(Highlight::Comment, 0, 8)], highlight_line("REM a: b "));
+        assert_eq!(
+            vec![hs(Highlight::Number, 6, 1), hs(Highlight::Comment, 8, 4)],
+            highlight_line("PRINT 3 'foo\n' ignored")
+        );
+        assert_eq!(
+            vec![hs(Highlight::Keyword, 0, 4), hs(Highlight::Comment, 5, 6)],
+            highlight_line("LOOP 'a \"b\"")
+        );
+    }
+
+    #[test]
+    fn test_highlight_line_tabs() {
+        assert_eq!(
+            vec![hs(Highlight::Keyword, 1, 3), hs(Highlight::Number, 6, 1)],
+            highlight_line("\tEND\t\t2")
+        );
+    }
+
+    #[test]
+    fn test_highlight_line_utf8() {
+        assert_eq!(
+            vec![hs(Highlight::Text, 4, 4), hs(Highlight::Comment, 9, 2)],
+            highlight_line("가 = \"나다\" '라")
+        );
+    }
+
+    #[test]
+    fn test_highlight_line_malformed() {
+        assert_eq!(
+            vec![hs(Highlight::Keyword, 0, 3), hs(Highlight::Text, 6, 7)],
+            highlight_line("NOT a \"open s")
+        );
+        assert_eq!(vec![hs(Highlight::Text, 0, 1)], highlight_line("\""));
+        assert_eq!(vec![hs(Highlight::Text, 2, 5)], highlight_line("a \"b c "));
+        assert_eq!(
+            vec![hs(Highlight::Number, 0, 5), hs(Highlight::Keyword, 6, 4)],
+            highlight_line("1.2.3 THEN")
+        );
+        assert_eq!(vec![hs(Highlight::Number, 0, 12)], highlight_line("123456789012"));
+        assert_eq!(vec![hs(Highlight::Number, 0, 4)], highlight_line("&xZZ"));
+        assert_eq!(vec![hs(Highlight::Keyword, 8, 3)], highlight_line("a = b~c AND"));
+    }
+}
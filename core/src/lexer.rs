@@ -38,6 +38,7 @@ pub enum Token {
     Boolean(bool),
     Double(f64),
     Integer(i32),
+    Long(i64),
     Text(String),
     Symbol(VarRef),
 
@@ -91,16 +92,20 @@ pub enum Token {
     Select,
     Step,
     Then,
+    Timeout,
     To,
     Until,
     Wend,
     While,
 
     Dim,
+    Redim,
+    Preserve,
     As,
     BooleanName,
     DoubleName,
     IntegerName,
+    LongName,
     TextName,
 }
 
@@ -119,6 +124,7 @@ impl fmt::Display for Token {
             Token::Boolean(true) => write!(f, "TRUE"),
             Token::Double(d) => write!(f, "{}", d),
             Token::Integer(i) => write!(f, "{}", i),
+            Token::Long(i) => write!(f, "{}", i),
             Token::Text(t) => write!(f, "{}", t),
             Token::Symbol(vref) => write!(f, "{}", vref),
 
@@ -172,16 +178,20 @@ impl fmt::Display for Token {
             Token::Select => write!(f, "SELECT"),
             Token::Step => write!(f, "STEP"),
             Token::Then => write!(f, "THEN"),
+            Token::Timeout => write!(f, "TIMEOUT"),
             Token::To => write!(f, "TO"),
             Token::Until => write!(f, "UNTIL"),
             Token::Wend => write!(f, "WEND"),
             Token::While => write!(f, "WHILE"),
 
             Token::Dim => write!(f, "DIM"),
+            Token::Redim => write!(f, "REDIM"),
+            Token::Preserve => write!(f, "PRESERVE"),
             Token::As => write!(f, "AS"),
             Token::BooleanName => write!(f, "BOOLEAN"),
             Token::DoubleName => write!(f, "DOUBLE"),
             Token::IntegerName => write!(f, "INTEGER"),
+            Token::LongName => write!(f, "LONG"),
             Token::TextName => write!(f, "STRING"),
         }
     }
@@ -239,8 +249,7 @@ pub struct TokenSpan {
     pub(crate) pos: LineCol,
 
     /// Length of the token in characters.
-    #[allow(unused)] // TODO(jmmv): Use this in the parser.
-    length: usize,
+    pub(crate) length: usize,
 }
 
 impl TokenSpan {
@@ -310,6 +319,24 @@ impl<'a> Lexer<'a> {
                     }
                     ch if ch.is_ascii_digit() => s.push(self.input.next().unwrap()?.ch),
                     ch if ch.is_separator() => break,
+                    '&' if !found_dot => {
+                        self.input.next().unwrap()?;
+                        return match s.parse::<i64>() {
+                            Ok(i) => Ok(TokenSpan::new(Token::Long(i), first.pos, s.len() + 1)),
+                            Err(e) => {
+                                self.handle_bad_read(format!("Bad long {}: {}", s, e), first.pos)
+                            }
+                        };
+                    }
+                    '#' | '!' if !s.ends_with('.') => {
+                        self.input.next().unwrap()?;
+                        return match s.parse::<f64>() {
+                            Ok(d) => Ok(TokenSpan::new(Token::Double(d), first.pos, s.len() + 1)),
+                            Err(e) => {
+                                self.handle_bad_read(format!("Bad double {}: {}", s, e), first.pos)
+                            }
+                        };
+                    }
                     ch => {
                         self.input.next().unwrap()?;
                         let msg = format!("Unexpected character in numeric literal: {}", ch);
@@ -332,9 +359,14 @@ impl<'a> Lexer<'a> {
                 Err(e) => self.handle_bad_read(format!("Bad double {}: {}", s, e), first.pos),
             }
         } else {
+            // Integer literals that do not fit in 32 bits are silently promoted to longs so that
+            // large constants can be written without the explicit & suffix.
             match s.parse::<i32>() {
                 Ok(i) => Ok(TokenSpan::new(Token::Integer(i), first.pos, s.len())),
-                Err(e) => self.handle_bad_read(format!("Bad integer {}: {}", s, e), first.pos),
+                Err(e) => match s.parse::<i64>() {
+                    Ok(i) => Ok(TokenSpan::new(Token::Long(i), first.pos, s.len())),
+                    Err(_) => self.handle_bad_read(format!("Bad integer {}: {}", s, e), first.pos),
+                },
             }
         }
     }
@@ -478,7 +510,7 @@ impl<'a> Lexer<'a> {
                         token_len += 1;
                         break;
                     }
-                    '#' => {
+                    '#' | '!' => {
                         vtype = VarType::Double;
                         self.input.next().unwrap()?;
                         token_len += 1;
@@ -490,6 +522,12 @@ impl<'a> Lexer<'a> {
                         token_len += 1;
                         break;
                     }
+                    '&' => {
+                        vtype = VarType::Long;
+                        self.input.next().unwrap()?;
+                        token_len += 1;
+                        break;
+                    }
                     '$' => {
                         vtype = VarType::Text;
                         self.input.next().unwrap()?;
@@ -508,7 +546,12 @@ impl<'a> Lexer<'a> {
         }
         debug_assert!(token_len <= 1);
 
-        token_len += s.len();
+        token_len += s.chars().count();
+        if vtype != VarType::Auto {
+            // Keywords never carry type annotations, so an annotated word is always a symbol.
+            // This allows defining functions like `STRING$` whose names collide with keywords.
+            return Ok(TokenSpan::new(Token::Symbol(VarRef::new(s, vtype)), first.pos, token_len));
+        }
         let token = match s.to_uppercase().as_str() {
             "AND" => Token::And,
             "AS" => Token::As,
@@ -530,19 +573,24 @@ impl<'a> Lexer<'a> {
             "IF" => Token::If,
             "IS" => Token::Is,
             "INTEGER" => Token::IntegerName,
+            "LONG" => Token::LongName,
             "LOOP" => Token::Loop,
             "MOD" => Token::Modulo,
             "NEXT" => Token::Next,
             "NOT" => Token::Not,
             "ON" => Token::On,
             "OR" => Token::Or,
+            "PRESERVE" => Token::Preserve,
+            "REDIM" => Token::Redim,
             "REM" => return self.consume_rest_of_line(),
             "RESUME" => Token::Resume,
             "RETURN" => Token::Return,
             "SELECT" => Token::Select,
+            "SINGLE" => Token::DoubleName,
             "STEP" => Token::Step,
             "STRING" => Token::TextName,
             "THEN" => Token::Then,
+            "TIMEOUT" => Token::Timeout,
             "TO" => Token::To,
             "TRUE" => Token::Boolean(true),
             "UNTIL" => Token::Until,
@@ -560,21 +608,25 @@ impl<'a> Lexer<'a> {
     /// This handles quoted characters within the string.
     fn consume_text(&mut self, delim: CharSpan) -> io::Result<TokenSpan> {
         let mut s = String::new();
+        let mut token_len = 2; // Count the delimiters.
         let mut escaping = false;
         loop {
             match self.input.peek() {
                 Some(Ok(ch_span)) => {
                     if escaping {
                         s.push(self.input.next().unwrap()?.ch);
+                        token_len += 1;
                         escaping = false;
                     } else if ch_span.ch == '\\' {
                         self.input.next().unwrap()?;
+                        token_len += 1;
                         escaping = true;
                     } else if ch_span.ch == delim.ch {
                         self.input.next().unwrap()?;
                         break;
                     } else {
                         s.push(self.input.next().unwrap()?.ch);
+                        token_len += 1;
                     }
                 }
                 Some(Err(_)) => return Err(self.input.next().unwrap().unwrap_err()),
@@ -586,7 +638,6 @@ impl<'a> Lexer<'a> {
                 }
             }
         }
-        let token_len = s.len() + 2;
         Ok(TokenSpan::new(Token::Text(s), delim.pos, token_len))
     }
 
@@ -621,7 +672,7 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let token_len = s.len() + 1;
+        let token_len = s.chars().count() + 1;
         Ok(TokenSpan::new(Token::Label(s), first.pos, token_len))
     }
 
@@ -882,6 +933,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_long_literals() {
+        do_ok_test(
+            "2147483647 2147483648 -9223372036854775807 5& 0&",
+            &[
+                ts(Token::Integer(2147483647), 1, 1, 10),
+                ts(Token::Long(2147483648), 1, 12, 10),
+                ts(Token::Minus, 1, 23, 1),
+                ts(Token::Long(9223372036854775807), 1, 24, 19),
+                ts(Token::Long(5), 1, 44, 2),
+                ts(Token::Long(0), 1, 47, 2),
+                ts(Token::Eof, 1, 49, 0),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_double_literal_suffixes() {
+        do_ok_test(
+            "5# 5! 3.25# 3.25!",
+            &[
+                ts(Token::Double(5.0), 1, 1, 2),
+                ts(Token::Double(5.0), 1, 4, 2),
+                ts(Token::Double(3.25), 1, 7, 5),
+                ts(Token::Double(3.25), 1, 13, 5),
+                ts(Token::Eof, 1, 18, 0),
+            ],
+        );
+    }
+
     #[test]
     fn test_integer_literals() {
         do_ok_test(
@@ -961,12 +1042,12 @@ mod tests {
         do_ok_test(
             "가 나=7 a다b \"라 마\"",
             &[
-                ts(new_auto_symbol("가"), 1, 1, 3),
-                ts(new_auto_symbol("나"), 1, 3, 3),
+                ts(new_auto_symbol("가"), 1, 1, 1),
+                ts(new_auto_symbol("나"), 1, 3, 1),
                 ts(Token::Equal, 1, 4, 1),
                 ts(Token::Integer(7), 1, 5, 1),
-                ts(new_auto_symbol("a다b"), 1, 7, 5),
-                ts(Token::Text("라 마".to_owned()), 1, 11, 9),
+                ts(new_auto_symbol("a다b"), 1, 7, 3),
+                ts(Token::Text("라 마".to_owned()), 1, 11, 5),
                 ts(Token::Eof, 1, 16, 0),
             ],
         );
@@ -998,14 +1079,29 @@ mod tests {
     #[test]
     fn test_var_types() {
         do_ok_test(
-            "a b? d# i% s$",
+            "a b? d# i% s$ l& f!",
             &[
                 ts(new_auto_symbol("a"), 1, 1, 1),
                 ts(Token::Symbol(VarRef::new("b", VarType::Boolean)), 1, 3, 2),
                 ts(Token::Symbol(VarRef::new("d", VarType::Double)), 1, 6, 2),
                 ts(Token::Symbol(VarRef::new("i", VarType::Integer)), 1, 9, 2),
                 ts(Token::Symbol(VarRef::new("s", VarType::Text)), 1, 12, 2),
-                ts(Token::Eof, 1, 14, 0),
+                ts(Token::Symbol(VarRef::new("l", VarType::Long)), 1, 15, 2),
+                ts(Token::Symbol(VarRef::new("f", VarType::Double)), 1, 18, 2),
+                ts(Token::Eof, 1, 20, 0),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_annotated_keywords_are_symbols() {
+        do_ok_test(
+            "STRING STRING$ end%",
+            &[
+                ts(Token::TextName, 1, 1, 6),
+                ts(Token::Symbol(VarRef::new("STRING", VarType::Text)), 1, 8, 7),
+                ts(Token::Symbol(VarRef::new("end", VarType::Integer)), 1, 16, 4),
+                ts(Token::Eof, 1, 20, 0),
             ],
         );
     }
@@ -1037,7 +1133,7 @@ mod tests {
         do_ok_test(
             "\"this \\\"is escaped\\\" \\\\ \\a\" 1",
             &[
-                ts(Token::Text("this \"is escaped\" \\ a".to_owned()), 1, 1, 23),
+                ts(Token::Text("this \"is escaped\" \\ a".to_owned()), 1, 1, 27),
                 ts(Token::Integer(1), 1, 29, 1),
                 ts(Token::Eof, 1, 30, 0),
             ],
@@ -1073,13 +1169,15 @@ mod tests {
             &[ts(Token::Dim, 1, 1, 3), ts(Token::As, 1, 5, 2), ts(Token::Eof, 1, 7, 0)],
         );
         do_ok_test(
-            "BOOLEAN DOUBLE INTEGER STRING",
+            "BOOLEAN DOUBLE INTEGER STRING LONG SINGLE",
             &[
                 ts(Token::BooleanName, 1, 1, 7),
                 ts(Token::DoubleName, 1, 9, 6),
                 ts(Token::IntegerName, 1, 16, 7),
                 ts(Token::TextName, 1, 24, 6),
-                ts(Token::Eof, 1, 30, 0),
+                ts(Token::LongName, 1, 31, 4),
+                ts(Token::DoubleName, 1, 36, 6),
+                ts(Token::Eof, 1, 42, 0),
             ],
         );
 
@@ -1237,6 +1335,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_redim() {
+        for s in ["REDIM PRESERVE", "redim preserve"] {
+            do_ok_test(
+                s,
+                &[
+                    ts(Token::Redim, 1, 1, 5),
+                    ts(Token::Preserve, 1, 7, 8),
+                    ts(Token::Eof, 1, 15, 0),
+                ],
+            );
+        }
+    }
+
     #[test]
     fn test_return() {
         do_ok_test("RETURN", &[ts(Token::Return, 1, 1, 6), ts(Token::Eof, 1, 7, 0)]);
@@ -1271,6 +1383,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_timeout() {
+        do_ok_test("TIMEOUT", &[ts(Token::Timeout, 1, 1, 7), ts(Token::Eof, 1, 8, 0)]);
+
+        do_ok_test("timeout", &[ts(Token::Timeout, 1, 1, 7), ts(Token::Eof, 1, 8, 0)]);
+    }
+
     #[test]
     fn test_while() {
         do_ok_test(
@@ -1429,27 +1548,55 @@ mod tests {
         );
 
         do_ok_test(
-            "9999999999+5",
+            "99999999999999999999+5",
             &[
                 ts(
                     Token::Bad(
-                        "Bad integer 9999999999: number too large to fit in target type".to_owned(),
+                        "Bad integer 99999999999999999999: number too large to fit in target type"
+                            .to_owned(),
                     ),
                     1,
                     1,
                     1,
                 ),
-                ts(Token::Plus, 1, 11, 1),
-                ts(Token::Integer(5), 1, 12, 1),
-                ts(Token::Eof, 1, 13, 0),
+                ts(Token::Plus, 1, 21, 1),
+                ts(Token::Integer(5), 1, 22, 1),
+                ts(Token::Eof, 1, 23, 0),
+            ],
+        );
+
+        do_ok_test(
+            "99999999999999999999&+5",
+            &[
+                ts(
+                    Token::Bad(
+                        "Bad long 99999999999999999999: number too large to fit in target type"
+                            .to_owned(),
+                    ),
+                    1,
+                    1,
+                    1,
+                ),
+                ts(Token::Plus, 1, 22, 1),
+                ts(Token::Integer(5), 1, 23, 1),
+                ts(Token::Eof, 1, 24, 0),
+            ],
+        );
+
+        do_ok_test(
+            "1.5& 1.#",
+            &[
+                ts(Token::Bad("Unexpected character in numeric literal: &".to_owned()), 1, 1, 1),
+                ts(Token::Bad("Unexpected character in numeric literal: #".to_owned()), 1, 6, 1),
+                ts(Token::Eof, 1, 9, 0),
             ],
         );
 
         do_ok_test(
-            "\n3!2 1",
+            "\n3|2 1",
             &[
                 ts(Token::Eol, 1, 1, 1),
-                ts(Token::Bad("Unexpected character in numeric literal: !".to_owned()), 2, 1, 2),
+                ts(Token::Bad("Unexpected character in numeric literal: |".to_owned()), 2, 1, 2),
                 ts(Token::Integer(1), 2, 5, 1),
                 ts(Token::Eof, 2, 6, 0),
             ],
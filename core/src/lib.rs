@@ -28,7 +28,10 @@ pub mod bytecode;
 pub mod compiler;
 pub mod eval;
 pub mod exec;
+pub mod format;
+pub mod highlight;
 mod lexer;
+mod optimizer;
 mod parser;
 mod reader;
 pub mod syms;
@@ -36,4 +39,5 @@ pub mod syms;
 mod testutils;
 mod value;
 
+pub use parser::find_includes;
 pub use reader::LineCol;
@@ -0,0 +1,398 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Simplifies the bytecode of a compiled program before execution.
+
+use crate::ast::*;
+use crate::bytecode::*;
+use crate::reader::LineCol;
+use crate::syms::{Symbol, Symbols};
+use crate::value;
+
+/// Converts a `value` computed at compilation time into a literal expression located at `pos`.
+fn value_to_literal(value: Value, pos: LineCol) -> Expr {
+    match value {
+        Value::Boolean(value) => Expr::Boolean(BooleanSpan { value, pos }),
+        Value::Double(value) => Expr::Double(DoubleSpan { value, pos }),
+        Value::Integer(value) => Expr::Integer(IntegerSpan { value, pos }),
+        Value::Long(value) => Expr::Long(LongSpan { value, pos }),
+        Value::Text(value) => Expr::Text(TextSpan { value, pos }),
+    }
+}
+
+/// Extracts the value of `expr` if it is a literal.
+fn literal_value(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Boolean(span) => Some(Value::Boolean(span.value)),
+        Expr::Double(span) => Some(Value::Double(span.value)),
+        Expr::Integer(span) => Some(Value::Integer(span.value)),
+        Expr::Long(span) => Some(Value::Long(span.value)),
+        Expr::Text(span) => Some(Value::Text(span.value.clone())),
+        _ => None,
+    }
+}
+
+/// Folds the operands of a binary operation and, if both are literals, computes the operation
+/// via `op`.
+///
+/// If the computation fails, the expression is left untouched (other than its operands) so that
+/// the error is raised at runtime, where it can be caught by `ON ERROR`.
+fn fold_binary<F, W>(mut span: Box<BinaryOpSpan>, op: F, wrap: W) -> Expr
+where
+    F: Fn(&Value, &Value) -> value::Result<Value>,
+    W: Fn(Box<BinaryOpSpan>) -> Expr,
+{
+    span.lhs = fold_expr(span.lhs);
+    span.rhs = fold_expr(span.rhs);
+    if let (Some(lhs), Some(rhs)) = (literal_value(&span.lhs), literal_value(&span.rhs)) {
+        if let Ok(result) = op(&lhs, &rhs) {
+            return value_to_literal(result, span.lhs.start_pos());
+        }
+    }
+    wrap(span)
+}
+
+/// Folds the operand of a unary operation and, if it is a literal, computes the operation via
+/// `op`.
+///
+/// If the computation fails, the expression is left untouched (other than its operand) so that
+/// the error is raised at runtime, where it can be caught by `ON ERROR`.
+fn fold_unary<F, W>(mut span: Box<UnaryOpSpan>, op: F, wrap: W) -> Expr
+where
+    F: Fn(&Value) -> value::Result<Value>,
+    W: Fn(Box<UnaryOpSpan>) -> Expr,
+{
+    span.expr = fold_expr(span.expr);
+    if let Some(value) = literal_value(&span.expr) {
+        if let Ok(result) = op(&value) {
+            return value_to_literal(result, span.pos);
+        }
+    }
+    wrap(span)
+}
+
+/// Replaces all constant subexpressions within `expr` with their precomputed values.
+pub(crate) fn fold_expr(expr: Expr) -> Expr {
+    match expr {
+        Expr::Boolean(_) | Expr::Double(_) | Expr::Integer(_) | Expr::Long(_) | Expr::Text(_) => {
+            expr
+        }
+
+        Expr::Symbol(_) => expr,
+
+        Expr::And(span) => fold_binary(span, Value::and, Expr::And),
+        Expr::Or(span) => fold_binary(span, Value::or, Expr::Or),
+        Expr::Xor(span) => fold_binary(span, Value::xor, Expr::Xor),
+        Expr::Not(span) => fold_unary(span, Value::not, Expr::Not),
+
+        Expr::ShiftLeft(span) => fold_binary(span, Value::shl, Expr::ShiftLeft),
+        Expr::ShiftRight(span) => fold_binary(span, Value::shr, Expr::ShiftRight),
+
+        Expr::Equal(span) => fold_binary(span, Value::eq, Expr::Equal),
+        Expr::NotEqual(span) => fold_binary(span, Value::ne, Expr::NotEqual),
+        Expr::Less(span) => fold_binary(span, Value::lt, Expr::Less),
+        Expr::LessEqual(span) => fold_binary(span, Value::le, Expr::LessEqual),
+        Expr::Greater(span) => fold_binary(span, Value::gt, Expr::Greater),
+        Expr::GreaterEqual(span) => fold_binary(span, Value::ge, Expr::GreaterEqual),
+
+        Expr::Add(span) => fold_binary(span, Value::add, Expr::Add),
+        Expr::Subtract(span) => fold_binary(span, Value::sub, Expr::Subtract),
+        Expr::Multiply(span) => fold_binary(span, Value::mul, Expr::Multiply),
+        Expr::Divide(span) => fold_binary(span, Value::div, Expr::Divide),
+        Expr::Modulo(span) => fold_binary(span, Value::modulo, Expr::Modulo),
+        Expr::Power(span) => fold_binary(span, Value::pow, Expr::Power),
+        Expr::Negate(span) => fold_unary(span, Value::neg, Expr::Negate),
+
+        Expr::Call(mut span) => {
+            // Function calls cannot be folded because functions may have side-effects, but their
+            // arguments can.
+            span.args = span.args.into_iter().map(fold_expr).collect();
+            Expr::Call(span)
+        }
+    }
+}
+
+/// Folds an optional expression.
+fn fold_opt_expr(expr: &mut Option<Expr>) {
+    if let Some(e) = expr.take() {
+        *expr = Some(fold_expr(e));
+    }
+}
+
+/// Folds an expression in place.
+fn fold_in_place(expr: &mut Expr) {
+    // Use a cheap placeholder to move the expression out of its location.
+    let pos = expr.start_pos();
+    let old = std::mem::replace(expr, Expr::Boolean(BooleanSpan { value: false, pos }));
+    *expr = fold_expr(old);
+}
+
+/// Returns true if the arguments to the command `name` can be folded, which is the case unless the
+/// command is known to interpret their syntax.
+fn can_fold_args(name: &str, symbols: &Symbols) -> bool {
+    match symbols.get(&VarRef::new(name, VarType::Auto)) {
+        Ok(Some(Symbol::Command(cmd))) => !cmd.metadata().has_syntactic_args(),
+        _ => true,
+    }
+}
+
+/// Simplifies a single `instr` in place, using `symbols` to query the callables it refers to.
+fn optimize_instr(instr: &mut Instruction, symbols: &Symbols) {
+    match instr {
+        Instruction::ArrayAssignment(span) => {
+            span.subscripts.iter_mut().for_each(fold_in_place);
+            fold_in_place(&mut span.expr);
+        }
+
+        Instruction::Assignment(span) => fold_in_place(&mut span.expr),
+
+        Instruction::BuiltinCall(span) => {
+            if can_fold_args(&span.name, symbols) {
+                span.args.iter_mut().for_each(|arg| fold_opt_expr(&mut arg.expr));
+            }
+        }
+
+        Instruction::DimArray(span) => span.dimensions.iter_mut().for_each(fold_in_place),
+
+        Instruction::End(span) => fold_opt_expr(&mut span.code),
+
+        Instruction::Redim(span) => span.dimensions.iter_mut().for_each(fold_in_place),
+
+        Instruction::JumpIfTrue(span) | Instruction::JumpIfNotTrue(span) => {
+            fold_in_place(&mut span.cond)
+        }
+
+        Instruction::SetGpioHandler(span) => {
+            fold_in_place(&mut span.pin);
+            fold_in_place(&mut span.edge);
+        }
+
+        Instruction::SetIntervalHandler(span) => fold_in_place(&mut span.period),
+
+        Instruction::SetKeyHandler(span) => fold_in_place(&mut span.key),
+
+        Instruction::Call(_)
+        | Instruction::Dim(_)
+        | Instruction::ForEachNext(_)
+        | Instruction::Jump(_)
+        | Instruction::JumpIfDefined(_)
+        | Instruction::Nop
+        | Instruction::Return(_)
+        | Instruction::SetErrorHandler(_)
+        | Instruction::SetFileChangeHandler(_)
+        | Instruction::Unset(_) => (),
+    }
+}
+
+/// Simplifies all instructions in `image`, using `symbols` to query the callables it refers to.
+///
+/// The only simplification done at the moment is the folding of constant subexpressions, which
+/// also covers the constant parts of `FOR` headers so that their bounds and steps are computed
+/// once instead of on every iteration.
+///
+/// TODO(jmmv): Hoisting non-constant, loop-invariant `FOR` bounds and pre-resolving symbol lookups
+/// were requested along with folding but are not done yet.  The bounds of a `FOR` loop are
+/// re-evaluated on every iteration by design, so hoisting them requires proving that neither the
+/// loop body nor anything it calls or jumps to can modify the variables they reference.  And
+/// pre-resolving symbols requires the bytecode to refer to variables by slot instead of by name,
+/// which `Symbols` does not support.
+///
+/// The simplifications done here must not alter the observable behavior of the program, including
+/// the errors it raises: these must still be raised at runtime and refer to the same positions.
+pub(crate) fn optimize(image: &mut Image, symbols: &Symbols) {
+    image.instrs.iter_mut().for_each(|instr| optimize_instr(instr, symbols));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Syntactic sugar to instantiate a `LineCol` for testing.
+    fn lc(line: usize, col: usize) -> LineCol {
+        LineCol { line, col }
+    }
+
+    /// Parses `input` as the right hand side of an assignment and returns the folded expression.
+    fn fold_rhs(input: &str) -> Expr {
+        let mut stmts = crate::parser::parse(&mut format!("x = {}", input).as_bytes()).unwrap();
+        assert_eq!(1, stmts.len());
+        match stmts.remove(0) {
+            Statement::Assignment(span) => fold_expr(span.expr),
+            _ => panic!("Expected an assignment"),
+        }
+    }
+
+    #[test]
+    fn test_fold_literals_untouched() {
+        assert_eq!(Expr::Integer(IntegerSpan { value: 3, pos: lc(1, 5) }), fold_rhs("3"));
+        assert_eq!(
+            Expr::Text(TextSpan { value: "a".to_owned(), pos: lc(1, 5) }),
+            fold_rhs("\"a\"")
+        );
+    }
+
+    #[test]
+    fn test_fold_arithmetic() {
+        assert_eq!(Expr::Integer(IntegerSpan { value: 7, pos: lc(1, 5) }), fold_rhs("1 + 2 * 3"));
+        assert_eq!(
+            Expr::Double(DoubleSpan { value: 2.0 * 3.5 / 7.0, pos: lc(1, 6) }),
+            fold_rhs("(2 * 3.5 / 7)")
+        );
+        assert_eq!(Expr::Integer(IntegerSpan { value: -8, pos: lc(1, 5) }), fold_rhs("-(2 ^ 3)"));
+    }
+
+    #[test]
+    fn test_fold_logical_and_relational() {
+        assert_eq!(
+            Expr::Boolean(BooleanSpan { value: true, pos: lc(1, 5) }),
+            fold_rhs("1 < 2 AND NOT FALSE")
+        );
+        assert_eq!(
+            Expr::Text(TextSpan { value: "ab".to_owned(), pos: lc(1, 5) }),
+            fold_rhs("\"a\" + \"b\"")
+        );
+    }
+
+    #[test]
+    fn test_fold_partial() {
+        assert_eq!(
+            Expr::Add(Box::from(BinaryOpSpan {
+                lhs: Expr::Symbol(SymbolSpan {
+                    vref: VarRef::new("a", VarType::Auto),
+                    pos: lc(1, 5)
+                }),
+                rhs: Expr::Integer(IntegerSpan { value: 6, pos: lc(1, 10) }),
+                pos: lc(1, 7),
+            })),
+            fold_rhs("a + (2 * 3)")
+        );
+    }
+
+    #[test]
+    fn test_fold_call_arguments() {
+        assert_eq!(
+            Expr::Call(FunctionCallSpan {
+                fref: VarRef::new("f", VarType::Auto),
+                args: vec![Expr::Integer(IntegerSpan { value: 3, pos: lc(1, 7) })],
+                pos: lc(1, 5),
+            }),
+            fold_rhs("f(1 + 2)")
+        );
+    }
+
+    #[test]
+    fn test_fold_errors_are_deferred() {
+        assert_eq!(
+            Expr::Divide(Box::from(BinaryOpSpan {
+                lhs: Expr::Integer(IntegerSpan { value: 1, pos: lc(1, 5) }),
+                rhs: Expr::Integer(IntegerSpan { value: 0, pos: lc(1, 9) }),
+                pos: lc(1, 7),
+            })),
+            fold_rhs("1 / 0")
+        );
+        assert_eq!(
+            Expr::Add(Box::from(BinaryOpSpan {
+                lhs: Expr::Integer(IntegerSpan { value: 3, pos: lc(1, 5) }),
+                rhs: Expr::Boolean(BooleanSpan { value: true, pos: lc(1, 13) }),
+                pos: lc(1, 11),
+            })),
+            fold_rhs("1 + 2 + TRUE")
+        );
+    }
+
+    #[test]
+    fn test_optimize_for_header() {
+        let stmts = crate::parser::parse(&mut "FOR i = 1 TO 2 * 3\nNEXT".as_bytes()).unwrap();
+        let mut image = crate::compiler::compile(stmts).unwrap();
+        optimize(&mut image, &Symbols::default());
+
+        let conds = image
+            .instrs
+            .into_iter()
+            .filter_map(|instr| match instr {
+                Instruction::JumpIfNotTrue(span) => Some(span.cond),
+                _ => None,
+            })
+            .collect::<Vec<Expr>>();
+        assert_eq!(
+            vec![Expr::LessEqual(Box::from(BinaryOpSpan {
+                lhs: Expr::Symbol(SymbolSpan {
+                    vref: VarRef::new("i", VarType::Auto),
+                    pos: lc(1, 5)
+                }),
+                rhs: Expr::Integer(IntegerSpan { value: 6, pos: lc(1, 14) }),
+                pos: lc(1, 11),
+            }))],
+            conds
+        );
+    }
+
+    /// Parses `input` as a single builtin call and returns its arguments after optimizing the call
+    /// against `symbols`.
+    fn optimize_call(input: &str, symbols: &Symbols) -> Vec<Option<Expr>> {
+        let stmts = crate::parser::parse(&mut input.as_bytes()).unwrap();
+        let mut instr = match stmts.into_iter().next() {
+            Some(Statement::BuiltinCall(span)) => Instruction::BuiltinCall(span),
+            _ => panic!("Expected a builtin call"),
+        };
+        optimize_instr(&mut instr, symbols);
+        match instr {
+            Instruction::BuiltinCall(span) => span.args.into_iter().map(|arg| arg.expr).collect(),
+            _ => panic!("Expected a builtin call"),
+        }
+    }
+
+    #[test]
+    fn test_optimize_builtin_call_folds_arguments() {
+        let symbols = SymbolsBuilder::default()
+            .add_command(OutCommand::new(Rc::from(RefCell::from(vec![]))))
+            .build();
+        assert_eq!(
+            vec![
+                Some(Expr::Integer(IntegerSpan { value: 1, pos: lc(1, 5) })),
+                Some(Expr::Integer(IntegerSpan { value: 15, pos: lc(1, 12) })),
+            ],
+            optimize_call("OUT 2 - 1, 3 * (4 + 1)", &symbols)
+        );
+
+        // Unknown commands are only detected at runtime.
+        assert_eq!(
+            vec![Some(Expr::Integer(IntegerSpan { value: 1, pos: lc(1, 9) }))],
+            optimize_call("UNKNOWN 2 - 1", &symbols)
+        );
+    }
+
+    #[test]
+    fn test_optimize_builtin_call_keeps_syntactic_args() {
+        let symbols = SymbolsBuilder::default()
+            .add_command(RangeCommand::new(Rc::from(RefCell::from(vec![]))))
+            .build();
+        assert_eq!(
+            vec![Some(Expr::Subtract(Box::from(BinaryOpSpan {
+                lhs: Expr::Integer(IntegerSpan { value: 2, pos: lc(1, 7) }),
+                rhs: Expr::Multiply(Box::from(BinaryOpSpan {
+                    lhs: Expr::Integer(IntegerSpan { value: 2, pos: lc(1, 11) }),
+                    rhs: Expr::Integer(IntegerSpan { value: 3, pos: lc(1, 15) }),
+                    pos: lc(1, 13),
+                })),
+                pos: lc(1, 9),
+            })))],
+            optimize_call("RANGE 2 - 2 * 3", &symbols)
+        );
+    }
+}
@@ -19,6 +19,7 @@ use crate::ast::*;
 use crate::lexer::{Lexer, PeekableLexer, Token, TokenSpan};
 use crate::reader::LineCol;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::io;
 
 /// Parser errors.
@@ -314,7 +315,18 @@ impl<'a> Parser<'a> {
 
         let mut args = vec![];
         loop {
-            let expr = self.parse_expr(first.take())?;
+            let expr = match self.lexer.peek()?.token {
+                // Let commands take the `ON` keyword as a bare word argument, as in `PROFILE ON`.
+                // The command is responsible for interpreting it.
+                Token::On if first.is_none() => {
+                    let on = self.lexer.consume_peeked();
+                    Some(Expr::Symbol(SymbolSpan {
+                        vref: VarRef::new("ON", VarType::Auto),
+                        pos: on.pos,
+                    }))
+                }
+                _ => self.parse_expr(first.take())?,
+            };
 
             let peeked = self.lexer.peek()?;
             match peeked.token {
@@ -336,6 +348,10 @@ impl<'a> Parser<'a> {
                     let peeked = self.lexer.consume_peeked();
                     args.push(ArgSpan { expr, sep: ArgSep::As, sep_pos: peeked.pos });
                 }
+                Token::Timeout => {
+                    let peeked = self.lexer.consume_peeked();
+                    args.push(ArgSpan { expr, sep: ArgSep::Timeout, sep_pos: peeked.pos });
+                }
                 _ => {
                     return Err(Error::Bad(
                         peeked.pos,
@@ -396,6 +412,7 @@ impl<'a> Parser<'a> {
                 Token::Boolean(b) => values.push(Some(Value::Boolean(b))),
                 Token::Double(d) => values.push(Some(Value::Double(d))),
                 Token::Integer(i) => values.push(Some(Value::Integer(i))),
+                Token::Long(i) => values.push(Some(Value::Long(i))),
                 Token::Text(t) => values.push(Some(Value::Text(t))),
 
                 Token::Minus => {
@@ -403,6 +420,7 @@ impl<'a> Parser<'a> {
                     match token_span.token {
                         Token::Double(d) => values.push(Some(Value::Double(-d))),
                         Token::Integer(i) => values.push(Some(Value::Integer(-i))),
+                        Token::Long(i) => values.push(Some(Value::Long(-i))),
                         _ => {
                             return Err(Error::Bad(
                                 token_span.pos,
@@ -463,6 +481,7 @@ impl<'a> Parser<'a> {
                     Token::BooleanName => (VarType::Boolean, token_span.pos),
                     Token::DoubleName => (VarType::Double, token_span.pos),
                     Token::IntegerName => (VarType::Integer, token_span.pos),
+                    Token::LongName => (VarType::Long, token_span.pos),
                     Token::TextName => (VarType::Text, token_span.pos),
                     t => {
                         return Err(Error::Bad(
@@ -525,6 +544,48 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parses a `REDIM` statement.
+    fn parse_redim(&mut self) -> Result<Statement> {
+        let preserve = match self.lexer.peek()?.token {
+            Token::Preserve => {
+                self.lexer.consume_peeked();
+                true
+            }
+            _ => false,
+        };
+
+        let token_span = self.lexer.read()?;
+        let vref = match token_span.token {
+            Token::Symbol(vref) => vref,
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "Expected array name after REDIM".to_owned(),
+                ))
+            }
+        };
+        let name = vref_to_unannotated_string(vref, token_span.pos)?;
+        let name_pos = token_span.pos;
+
+        let peeked = self.lexer.peek()?;
+        if peeked.token != Token::LeftParen {
+            return Err(Error::Bad(peeked.pos, "Expected dimensions after array name".to_owned()));
+        }
+        let peeked = self.lexer.consume_peeked();
+        let dimensions = self.parse_comma_separated_exprs()?;
+        if dimensions.is_empty() {
+            return Err(Error::Bad(peeked.pos, "Arrays require at least one dimension".to_owned()));
+        }
+
+        let next = self.lexer.peek()?;
+        match &next.token {
+            Token::Eof | Token::Eol => (),
+            t => return Err(Error::Bad(next.pos, format!("Unexpected {} in REDIM statement", t))),
+        }
+
+        Ok(Statement::Redim(RedimSpan { name, name_pos, dimensions, preserve }))
+    }
+
     /// Parses the `UNTIL` or `WHILE` clause of a `DO` loop.
     ///
     /// `part` is a string indicating where the clause is expected (either after `DO` or after
@@ -628,10 +689,23 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// Parses an `EXIT DO` statement.
-    fn parse_exit_do(&mut self, pos: LineCol) -> Result<Statement> {
-        self.expect_and_consume(Token::Do, "Expecting DO after EXIT")?;
-        Ok(Statement::ExitDo(ExitDoSpan { pos }))
+    /// Parses an `EXIT` statement, which can either be an `EXIT DO` or an `EXIT` with an exit code.
+    fn parse_exit(&mut self, pos: LineCol) -> Result<Statement> {
+        if self.lexer.peek()?.token == Token::Do {
+            self.lexer.consume_peeked();
+            return Ok(Statement::ExitDo(ExitDoSpan { pos }));
+        }
+
+        match self.parse_expr(None)? {
+            Some(code) => Ok(Statement::End(EndSpan { code: Some(code) })),
+            None => {
+                let token_span = self.lexer.peek()?;
+                Err(Error::Bad(
+                    token_span.pos,
+                    "Expecting DO or an exit code after EXIT".to_owned(),
+                ))
+            }
+        }
     }
 
     /// Parses a variable list of comma-separated expressions.  The caller must have consumed the
@@ -709,6 +783,7 @@ impl<'a> Parser<'a> {
                 | Token::Else
                 | Token::Semicolon
                 | Token::Then
+                | Token::Timeout
                 | Token::To
                 | Token::Step => break,
                 Token::RightParen => {
@@ -735,6 +810,9 @@ impl<'a> Parser<'a> {
                 Token::Integer(value) => {
                     handle_operand(Expr::Integer(IntegerSpan { value, pos: ts.pos }), ts.pos)?
                 }
+                Token::Long(value) => {
+                    handle_operand(Expr::Long(LongSpan { value, pos: ts.pos }), ts.pos)?
+                }
                 Token::Text(value) => {
                     handle_operand(Expr::Text(TextSpan { value, pos: ts.pos }), ts.pos)?
                 }
@@ -854,6 +932,7 @@ impl<'a> Parser<'a> {
                 | Token::Else
                 | Token::Semicolon
                 | Token::Then
+                | Token::Timeout
                 | Token::To
                 | Token::Step => {
                     panic!("Field separators handled above")
@@ -876,9 +955,12 @@ impl<'a> Parser<'a> {
                 | Token::Is
                 | Token::IntegerName
                 | Token::Label(_)
+                | Token::LongName
                 | Token::Loop
                 | Token::Next
                 | Token::On
+                | Token::Preserve
+                | Token::Redim
                 | Token::Resume
                 | Token::Return
                 | Token::Select
@@ -1123,6 +1205,10 @@ impl<'a> Parser<'a> {
                 let peeked = self.lexer.consume_peeked();
                 Ok((Expr::Integer(IntegerSpan { value: i, pos: peeked.pos }), i.cmp(&0), false))
             }
+            Token::Long(i) => {
+                let peeked = self.lexer.consume_peeked();
+                Ok((Expr::Long(LongSpan { value: i, pos: peeked.pos }), i.cmp(&0), false))
+            }
             Token::Minus => {
                 self.lexer.consume_peeked();
                 let peeked = self.lexer.peek()?;
@@ -1140,6 +1226,14 @@ impl<'a> Parser<'a> {
                             false,
                         ))
                     }
+                    Token::Long(i) => {
+                        let peeked = self.lexer.consume_peeked();
+                        Ok((
+                            Expr::Long(LongSpan { value: -i, pos: peeked.pos }),
+                            (-i).cmp(&0),
+                            false,
+                        ))
+                    }
                     _ => Err(Error::Bad(peeked.pos, "STEP needs a literal number".to_owned())),
                 }
             }
@@ -1150,9 +1244,18 @@ impl<'a> Parser<'a> {
     /// Parses a `FOR` statement.
     fn parse_for(&mut self, for_pos: LineCol) -> Result<Statement> {
         let token_span = self.lexer.read()?;
+        if let Token::Symbol(vref) = &token_span.token {
+            // EACH is not a keyword so that it remains usable as a variable name.  Disambiguate
+            // by looking at what follows it: only FOR EACH is followed by another symbol.
+            if vref.ref_type() == VarType::Auto && vref.name().eq_ignore_ascii_case("EACH") {
+                if let Token::Symbol(_) = self.lexer.peek()?.token {
+                    return self.parse_for_each(for_pos);
+                }
+            }
+        }
         let iterator = match token_span.token {
             Token::Symbol(iterator) => match iterator.ref_type() {
-                VarType::Auto | VarType::Double | VarType::Integer => iterator,
+                VarType::Auto | VarType::Double | VarType::Integer | VarType::Long => iterator,
                 _ => {
                     return Err(Error::Bad(
                         token_span.pos,
@@ -1217,6 +1320,41 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    /// Parses a `FOR EACH` statement.  Only `FOR EACH` has been consumed so far.
+    fn parse_for_each(&mut self, for_pos: LineCol) -> Result<Statement> {
+        let token_span = self.lexer.read()?;
+        let (iter, iter_pos) = match token_span.token {
+            Token::Symbol(vref) => (vref, token_span.pos),
+            _ => unreachable!("Caller must have checked that the iterator is a symbol"),
+        };
+
+        let token_span = self.lexer.read()?;
+        match token_span.token {
+            Token::Symbol(vref)
+                if vref.ref_type() == VarType::Auto && vref.name().eq_ignore_ascii_case("IN") => {}
+            _ => return Err(Error::Bad(token_span.pos, "No IN in FOR EACH statement".to_owned())),
+        }
+
+        let token_span = self.lexer.read()?;
+        let (array, array_pos) = match token_span.token {
+            Token::Symbol(vref) => (vref, token_span.pos),
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "No array name in FOR EACH statement".to_owned(),
+                ))
+            }
+        };
+
+        self.expect_and_consume(Token::Eol, "Expecting newline after FOR EACH")?;
+
+        let body = self.parse_until(Token::Next)?;
+        let next_pos = self.lexer.peek()?.pos;
+        self.expect_and_consume_with_pos(Token::Next, for_pos, "FOR without NEXT")?;
+
+        Ok(Statement::ForEach(ForEachSpan { iter, iter_pos, array, array_pos, body, next_pos }))
+    }
+
     /// Advances until the next statement after failing to parse a `FOR` statement.
     fn reset_for(&mut self) -> Result<()> {
         loop {
@@ -1234,10 +1372,53 @@ impl<'a> Parser<'a> {
         self.reset()
     }
 
-    /// Parses an `ON ERROR` statement.  Only `ON` has been consumed so far.
+    /// Parses an `ON ERROR`, `ON FILECHANGE`, `ON GPIO`, `ON INTERVAL` or `ON KEY` statement.  Only
+    /// `ON` has been consumed so far.
+    ///
+    /// `FILECHANGE`, `GPIO`, `INTERVAL` and `KEY` are not keywords so that programs can keep using
+    /// them as variable names.
     fn parse_on(&mut self) -> Result<Statement> {
-        self.expect_and_consume(Token::Error, "Expected ERROR after ON")?;
+        let peeked = self.lexer.peek()?;
+        match &peeked.token {
+            Token::Error => {
+                self.lexer.consume_peeked();
+                self.parse_on_error()
+            }
+            Token::Symbol(vref)
+                if vref.ref_type() == VarType::Auto
+                    && vref.name().eq_ignore_ascii_case("FILECHANGE") =>
+            {
+                self.lexer.consume_peeked();
+                self.parse_on_file_change()
+            }
+            Token::Symbol(vref)
+                if vref.ref_type() == VarType::Auto && vref.name().eq_ignore_ascii_case("GPIO") =>
+            {
+                self.lexer.consume_peeked();
+                self.parse_on_gpio()
+            }
+            Token::Symbol(vref)
+                if vref.ref_type() == VarType::Auto
+                    && vref.name().eq_ignore_ascii_case("INTERVAL") =>
+            {
+                self.lexer.consume_peeked();
+                self.parse_on_interval()
+            }
+            Token::Symbol(vref)
+                if vref.ref_type() == VarType::Auto && vref.name().eq_ignore_ascii_case("KEY") =>
+            {
+                self.lexer.consume_peeked();
+                self.parse_on_key()
+            }
+            _ => Err(Error::Bad(
+                peeked.pos,
+                "Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON".to_owned(),
+            )),
+        }
+    }
 
+    /// Parses an `ON ERROR` statement.  Only `ON ERROR` has been consumed so far.
+    fn parse_on_error(&mut self) -> Result<Statement> {
         let token_span = self.lexer.read()?;
         match token_span.token {
             Token::Goto => {
@@ -1268,6 +1449,146 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parses an `ON FILECHANGE` statement.  Only `ON FILECHANGE` has been consumed so far.
+    fn parse_on_file_change(&mut self) -> Result<Statement> {
+        self.expect_and_consume(Token::Gosub, "Expected GOSUB after ON FILECHANGE")?;
+
+        let token_span = self.lexer.read()?;
+        match token_span.token {
+            Token::Integer(0) => Ok(Statement::OnFileChange(OnFileChangeSpan::Reset)),
+            Token::Integer(i) => Ok(Statement::OnFileChange(OnFileChangeSpan::Gosub(GotoSpan {
+                target: format!("{}", i),
+                target_pos: token_span.pos,
+            }))),
+            Token::Label(target) => {
+                Ok(Statement::OnFileChange(OnFileChangeSpan::Gosub(GotoSpan {
+                    target,
+                    target_pos: token_span.pos,
+                })))
+            }
+            _ => Err(Error::Bad(
+                token_span.pos,
+                "Expected label name or 0 after ON FILECHANGE GOSUB".to_owned(),
+            )),
+        }
+    }
+
+    /// Parses an `ON GPIO` statement.  Only `ON GPIO` has been consumed so far.
+    fn parse_on_gpio(&mut self) -> Result<Statement> {
+        // Only accept simple pins and edge names to avoid confusing the expression parser with the
+        // `GOSUB` keyword that follows.
+        let token_span = self.lexer.read()?;
+        let pin = match token_span.token {
+            Token::Integer(value) => Expr::Integer(IntegerSpan { value, pos: token_span.pos }),
+            Token::Symbol(vref) => Expr::Symbol(SymbolSpan { vref, pos: token_span.pos }),
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "Expected number or variable after ON GPIO".to_owned(),
+                ))
+            }
+        };
+        self.expect_and_consume(Token::Comma, "Expected comma after ON GPIO pin%")?;
+
+        let token_span = self.lexer.read()?;
+        let edge = match token_span.token {
+            Token::Text(value) => Expr::Text(TextSpan { value, pos: token_span.pos }),
+            Token::Symbol(vref) => Expr::Symbol(SymbolSpan { vref, pos: token_span.pos }),
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "Expected string or variable after ON GPIO pin%,".to_owned(),
+                ))
+            }
+        };
+        self.expect_and_consume(Token::Gosub, "Expected GOSUB after ON GPIO pin%, edge$")?;
+
+        let token_span = self.lexer.read()?;
+        let handler = match token_span.token {
+            Token::Integer(0) => None,
+            Token::Integer(i) => {
+                Some(GotoSpan { target: format!("{}", i), target_pos: token_span.pos })
+            }
+            Token::Label(target) => Some(GotoSpan { target, target_pos: token_span.pos }),
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "Expected label name or 0 after ON GPIO pin%, edge$ GOSUB".to_owned(),
+                ))
+            }
+        };
+        Ok(Statement::OnGpio(OnGpioSpan { pin, edge, handler }))
+    }
+
+    /// Parses an `ON INTERVAL` statement.  Only `ON INTERVAL` has been consumed so far.
+    fn parse_on_interval(&mut self) -> Result<Statement> {
+        // Only accept simple periods to avoid confusing the expression parser with the `GOSUB`
+        // keyword that follows.
+        let token_span = self.lexer.read()?;
+        let period = match token_span.token {
+            Token::Double(value) => Expr::Double(DoubleSpan { value, pos: token_span.pos }),
+            Token::Integer(value) => Expr::Integer(IntegerSpan { value, pos: token_span.pos }),
+            Token::Symbol(vref) => Expr::Symbol(SymbolSpan { vref, pos: token_span.pos }),
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "Expected number or variable after ON INTERVAL".to_owned(),
+                ))
+            }
+        };
+        self.expect_and_consume(Token::Gosub, "Expected GOSUB after ON INTERVAL ms")?;
+
+        let token_span = self.lexer.read()?;
+        let handler = match token_span.token {
+            Token::Integer(0) => None,
+            Token::Integer(i) => {
+                Some(GotoSpan { target: format!("{}", i), target_pos: token_span.pos })
+            }
+            Token::Label(target) => Some(GotoSpan { target, target_pos: token_span.pos }),
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "Expected label name or 0 after ON INTERVAL ms GOSUB".to_owned(),
+                ))
+            }
+        };
+        Ok(Statement::OnInterval(OnIntervalSpan { period, handler }))
+    }
+
+    /// Parses an `ON KEY` statement.  Only `ON KEY` has been consumed so far.
+    fn parse_on_key(&mut self) -> Result<Statement> {
+        // Only accept simple key names to avoid confusing the expression parser with the `GOSUB`
+        // keyword that follows.
+        let token_span = self.lexer.read()?;
+        let key = match token_span.token {
+            Token::Text(value) => Expr::Text(TextSpan { value, pos: token_span.pos }),
+            Token::Symbol(vref) => Expr::Symbol(SymbolSpan { vref, pos: token_span.pos }),
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "Expected string or variable after ON KEY".to_owned(),
+                ))
+            }
+        };
+        self.expect_and_consume(Token::Gosub, "Expected GOSUB after ON KEY key$")?;
+
+        let token_span = self.lexer.read()?;
+        let handler = match token_span.token {
+            Token::Integer(0) => None,
+            Token::Integer(i) => {
+                Some(GotoSpan { target: format!("{}", i), target_pos: token_span.pos })
+            }
+            Token::Label(target) => Some(GotoSpan { target, target_pos: token_span.pos }),
+            _ => {
+                return Err(Error::Bad(
+                    token_span.pos,
+                    "Expected label name or 0 after ON KEY key$ GOSUB".to_owned(),
+                ))
+            }
+        };
+        Ok(Statement::OnKey(OnKeySpan { key, handler }))
+    }
+
     /// Parses the guards after a `CASE` keyword.
     fn parse_case_guards(&mut self) -> Result<Vec<CaseGuardSpan>> {
         let mut guards = vec![];
@@ -1531,7 +1852,7 @@ impl<'a> Parser<'a> {
             Token::Data => Ok(Some(self.parse_data()?)),
             Token::End => Ok(Some(self.parse_end(token_span.pos)?)),
             Token::Eof | Token::Eol => Ok(None),
-            Token::Exit => Ok(Some(self.parse_exit_do(token_span.pos)?)),
+            Token::Exit => Ok(Some(self.parse_exit(token_span.pos)?)),
             Token::Gosub => Ok(Some(self.parse_gosub()?)),
             Token::Goto => Ok(Some(self.parse_goto()?)),
             Token::On => Ok(Some(self.parse_on()?)),
@@ -1568,6 +1889,7 @@ impl<'a> Parser<'a> {
         let res = match token_span.token {
             Token::Data => Ok(Some(self.parse_data()?)),
             Token::Dim => Ok(Some(self.parse_dim()?)),
+            Token::Redim => Ok(Some(self.parse_redim()?)),
             Token::Do => {
                 let result = self.parse_do(token_span.pos);
                 if result.is_err() {
@@ -1578,7 +1900,7 @@ impl<'a> Parser<'a> {
             Token::End => Ok(Some(self.parse_end(token_span.pos)?)),
             Token::Eof => return Ok(None),
             Token::Eol => Ok(None),
-            Token::Exit => Ok(Some(self.parse_exit_do(token_span.pos)?)),
+            Token::Exit => Ok(Some(self.parse_exit(token_span.pos)?)),
             Token::If => {
                 let result = self.parse_if(token_span.pos);
                 if result.is_err() {
@@ -1614,6 +1936,13 @@ impl<'a> Parser<'a> {
             }
             Token::On => Ok(Some(self.parse_on()?)),
             Token::Return => Ok(Some(Statement::Return(ReturnSpan { pos: token_span.pos }))),
+            Token::Step => {
+                // STEP is a keyword because of FOR loops, but it is also the name of the debugger
+                // command to single-step a paused program.  Treat it as such when it appears at
+                // the beginning of a statement.
+                let vref = VarRef::new("STEP", VarType::Auto);
+                Ok(Some(self.parse_builtin_call(vref, token_span.pos, None)?))
+            }
             Token::Select => {
                 let result = self.parse_select(token_span.pos);
                 if result.is_err() {
@@ -1697,10 +2026,104 @@ pub fn parse(input: &mut dyn io::Read) -> Result<Vec<Statement>> {
     Ok(statements)
 }
 
+/// Extracts the path from the `'#INCLUDE "path"` directive in `line`, if the line holds one.
+///
+/// Returns an error message if the line looks like a directive but is malformed.
+fn parse_include_directive(line: &str) -> Option<std::result::Result<String, String>> {
+    let rest = line.trim_start().strip_prefix("'#")?;
+    match rest.get(..7) {
+        Some(keyword) if keyword.eq_ignore_ascii_case("INCLUDE") => (),
+        _ => return None,
+    }
+    let rest = &rest[7..];
+    if rest.starts_with(|ch: char| ch.is_alphanumeric() || ch == '_') {
+        return None;
+    }
+    match rest.trim().strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+        Some(path) if !path.is_empty() && !path.contains('"') => Some(Ok(path.to_owned())),
+        _ => Some(Err("Expected a quoted path in #INCLUDE directive".to_owned())),
+    }
+}
+
+/// Returns the paths referenced by the well-formed `'#INCLUDE` directives in `input`.
+///
+/// This does not follow the directives into the included files.
+pub fn find_includes(input: &str) -> Vec<String> {
+    input.lines().filter_map(parse_include_directive).filter_map(|r| r.ok()).collect()
+}
+
+/// Extracts all statements from `input`, which has `name`, replacing `'#INCLUDE` directives
+/// with the statements of the files they reference.
+///
+/// `active` holds the names of the files being included at the moment to detect cycles, and
+/// `done` holds the names of all files included so far so that they are only processed once.
+fn parse_with_includes_aux(
+    input: &str,
+    resolve: &dyn Fn(&str) -> io::Result<(String, String)>,
+    active: &mut Vec<String>,
+    done: &mut HashSet<String>,
+) -> Result<Vec<Statement>> {
+    let mut statements = vec![];
+    let mut segment = String::new();
+    for (i, line) in input.split_inclusive('\n').enumerate() {
+        let path = match parse_include_directive(line) {
+            Some(path) => path,
+            None => {
+                segment.push_str(line);
+                continue;
+            }
+        };
+
+        // Blank out the lines that were already consumed so that the positions of the statements
+        // in the following segment match those in the original input.
+        statements.extend(parse(&mut segment.as_bytes())?);
+        segment = "\n".repeat(i + 1);
+
+        let pos = LineCol { line: i + 1, col: line.len() - line.trim_start().len() + 1 };
+        let path = path.map_err(|e| Error::Bad(pos, e))?;
+        let (name, content) = resolve(&path)
+            .map_err(|e| Error::Bad(pos, format!("Cannot include {}: {}", path, e)))?;
+        if active.contains(&name) {
+            return Err(Error::Bad(pos, format!("Cyclic #INCLUDE of {}", name)));
+        }
+        if !done.insert(name.clone()) {
+            continue;
+        }
+
+        active.push(name);
+        let included =
+            parse_with_includes_aux(&content, resolve, active, done).map_err(|e| match e {
+                Error::Bad(ipos, msg) => {
+                    Error::Bad(pos, format!("In {}: {}:{}: {}", path, ipos.line, ipos.col, msg))
+                }
+                e => e,
+            })?;
+        active.pop();
+        statements.extend(included);
+    }
+    statements.extend(parse(&mut segment.as_bytes())?);
+    Ok(statements)
+}
+
+/// Extracts all statements from `input` and from the files referenced by its `'#INCLUDE "path"`
+/// directives, which must appear on lines of their own outside of any block.
+///
+/// `resolve` is used to fetch the referenced files: it receives the path as written in the
+/// directive and returns the canonical name of the file, used to detect cycles and to skip files
+/// that were already included, along with its contents.  Errors in the included files are
+/// reported at the position of the directive that pulled them in.
+pub fn parse_with_includes(
+    input: &str,
+    resolve: &dyn Fn(&str) -> io::Result<(String, String)>,
+) -> Result<Vec<Statement>> {
+    parse_with_includes_aux(input, resolve, &mut vec![], &mut HashSet::default())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::ast::VarType;
+    use std::collections::HashMap;
 
     /// Syntactic sugar to instantiate a `LineCol` for testing.
     fn lc(line: usize, col: usize) -> LineCol {
@@ -1913,7 +2336,7 @@ mod tests {
     #[test]
     fn test_builtin_calls() {
         do_ok_test(
-            "PRINT a\nPRINT ; 3 , c$\nNOARGS\nNAME 3 AS 4",
+            "PRINT a\nPRINT ; 3 , c$\nNOARGS\nNAME 3 AS 4\nWAIT 5 TIMEOUT 6",
             &[
                 Statement::BuiltinCall(BuiltinCallSpan {
                     name: "PRINT".to_owned(),
@@ -1962,6 +2385,79 @@ mod tests {
                         },
                     ],
                 }),
+                Statement::BuiltinCall(BuiltinCallSpan {
+                    name: "WAIT".to_owned(),
+                    name_pos: lc(5, 1),
+                    args: vec![
+                        ArgSpan {
+                            expr: Some(expr_integer(5, 5, 6)),
+                            sep: ArgSep::Timeout,
+                            sep_pos: lc(5, 8),
+                        },
+                        ArgSpan {
+                            expr: Some(expr_integer(6, 5, 16)),
+                            sep: ArgSep::End,
+                            sep_pos: lc(5, 17),
+                        },
+                    ],
+                }),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_builtin_call_on_keyword() {
+        do_ok_test(
+            "PROFILE ON\nFOO 1, on",
+            &[
+                Statement::BuiltinCall(BuiltinCallSpan {
+                    name: "PROFILE".to_owned(),
+                    name_pos: lc(1, 1),
+                    args: vec![ArgSpan {
+                        expr: Some(expr_symbol(VarRef::new("ON", VarType::Auto), 1, 9)),
+                        sep: ArgSep::End,
+                        sep_pos: lc(1, 11),
+                    }],
+                }),
+                Statement::BuiltinCall(BuiltinCallSpan {
+                    name: "FOO".to_owned(),
+                    name_pos: lc(2, 1),
+                    args: vec![
+                        ArgSpan {
+                            expr: Some(expr_integer(1, 2, 5)),
+                            sep: ArgSep::Long,
+                            sep_pos: lc(2, 6),
+                        },
+                        ArgSpan {
+                            expr: Some(expr_symbol(VarRef::new("ON", VarType::Auto), 2, 8)),
+                            sep: ArgSep::End,
+                            sep_pos: lc(2, 10),
+                        },
+                    ],
+                }),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_builtin_call_step() {
+        do_ok_test(
+            "STEP\nstep 2",
+            &[
+                Statement::BuiltinCall(BuiltinCallSpan {
+                    name: "STEP".to_owned(),
+                    name_pos: lc(1, 1),
+                    args: vec![],
+                }),
+                Statement::BuiltinCall(BuiltinCallSpan {
+                    name: "STEP".to_owned(),
+                    name_pos: lc(2, 1),
+                    args: vec![ArgSpan {
+                        expr: Some(expr_integer(2, 2, 6)),
+                        sep: ArgSep::End,
+                        sep_pos: lc(2, 7),
+                    }],
+                }),
             ],
         );
     }
@@ -2294,6 +2790,44 @@ mod tests {
         do_error_test("DIM a(1) AS INTEGER 3", "1:21: Unexpected 3 in DIM statement");
     }
 
+    #[test]
+    fn test_redim() {
+        do_ok_test(
+            "REDIM i(10)",
+            &[Statement::Redim(RedimSpan {
+                name: "i".to_owned(),
+                name_pos: lc(1, 7),
+                dimensions: vec![expr_integer(10, 1, 9)],
+                preserve: false,
+            })],
+        );
+
+        do_ok_test(
+            "redim preserve foo(3, n)",
+            &[Statement::Redim(RedimSpan {
+                name: "foo".to_owned(),
+                name_pos: lc(1, 16),
+                dimensions: vec![
+                    expr_integer(3, 1, 20),
+                    expr_symbol(VarRef::new("n", VarType::Auto), 1, 23),
+                ],
+                preserve: true,
+            })],
+        );
+    }
+
+    #[test]
+    fn test_redim_errors() {
+        do_error_test("REDIM", "1:6: Expected array name after REDIM");
+        do_error_test("REDIM PRESERVE", "1:15: Expected array name after REDIM");
+        do_error_test("REDIM 3", "1:7: Expected array name after REDIM");
+        do_error_test("REDIM a$(3)", "1:7: Type annotation not allowed in a$");
+        do_error_test("REDIM a", "1:8: Expected dimensions after array name");
+        do_error_test("REDIM a()", "1:8: Arrays require at least one dimension");
+        do_error_test("REDIM a(, 3)", "1:9: Missing expression");
+        do_error_test("REDIM a(3) AS INTEGER", "1:12: Unexpected AS in REDIM statement");
+    }
+
     #[test]
     fn test_do_until_empty() {
         do_ok_test(
@@ -2430,9 +2964,24 @@ mod tests {
     }
 
     #[test]
-    fn test_exit_do_errors() {
-        do_error_test("EXIT", "1:5: Expecting DO after EXIT");
-        do_error_test("EXIT 5", "1:6: Expecting DO after EXIT");
+    fn test_exit_code() {
+        do_ok_test("EXIT 5", &[Statement::End(EndSpan { code: Some(expr_integer(5, 1, 6)) })]);
+        do_ok_test(
+            "EXIT 2 + 3",
+            &[Statement::End(EndSpan {
+                code: Some(Expr::Add(Box::from(BinaryOpSpan {
+                    lhs: expr_integer(2, 1, 6),
+                    rhs: expr_integer(3, 1, 10),
+                    pos: lc(1, 8),
+                }))),
+            })],
+        );
+    }
+
+    #[test]
+    fn test_exit_errors() {
+        do_error_test("EXIT", "1:5: Expecting DO or an exit code after EXIT");
+        do_error_test("EXIT LOOP", "1:6: Unexpected keyword in expression");
     }
 
     /// Wrapper around `do_ok_test` to parse an expression.  Given that expressions alone are not
@@ -3013,8 +3562,8 @@ mod tests {
     fn test_expr_errors_due_to_keywords() {
         for kw in &[
             "BOOLEAN", "CASE", "DATA", "DIM", "DOUBLE", "ELSEIF", "END", "ERROR", "EXIT", "FOR",
-            "GOSUB", "GOTO", "IF", "IS", "INTEGER", "LOOP", "NEXT", "ON", "RESUME", "RETURN",
-            "SELECT", "STRING", "UNTIL", "WEND", "WHILE",
+            "GOSUB", "GOTO", "IF", "IS", "INTEGER", "LOOP", "NEXT", "ON", "PRESERVE", "REDIM",
+            "RESUME", "RETURN", "SELECT", "STRING", "UNTIL", "WEND", "WHILE",
         ] {
             do_expr_error_test(
                 &format!("2 + {} - 1", kw),
@@ -3442,7 +3991,11 @@ mod tests {
     fn test_if_uniline_allowed_exit() {
         do_if_uniline_allowed_test("EXIT DO", Statement::ExitDo(ExitDoSpan { pos: lc(1, 11) }));
 
-        do_error_test("IF 1 THEN EXIT", "1:15: Expecting DO after EXIT");
+        do_if_uniline_allowed_test(
+            "EXIT 3",
+            Statement::End(EndSpan { code: Some(expr_integer(3, 1, 16)) }),
+        );
+        do_error_test("IF 1 THEN EXIT", "1:15: Expecting DO or an exit code after EXIT");
     }
 
     #[test]
@@ -3472,7 +4025,10 @@ mod tests {
             Statement::OnError(OnErrorSpan::ResumeNext),
         );
 
-        do_error_test("IF 1 THEN ON", "1:13: Expected ERROR after ON");
+        do_error_test(
+            "IF 1 THEN ON",
+            "1:13: Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON",
+        );
     }
 
     #[test]
@@ -3531,6 +4087,73 @@ mod tests {
         }
     }
 
+    /// Parses `input` with `parse_with_includes`, serving the included files from `files`, which
+    /// maps paths to their contents.  The canonical name of a file is its uppercase path.
+    fn parse_with_files(input: &str, files: &[(&str, &str)]) -> Result<Vec<Statement>> {
+        let files = files.iter().copied().collect::<HashMap<&str, &str>>();
+        parse_with_includes(input, &|path| match files.get(path) {
+            Some(content) => Ok((path.to_uppercase(), (*content).to_owned())),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found")),
+        })
+    }
+
+    #[test]
+    fn test_include_ok() {
+        let statements = parse_with_files(
+            "A\n  '#INCLUDE \"lib\"\nB\n'#include \"other\" \n  C",
+            &[("lib", "X: Y\n'#INCLUDE \"other\""), ("other", "\n Z")],
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                make_bare_builtin_call("A", 1, 1),
+                make_bare_builtin_call("X", 1, 1),
+                make_bare_builtin_call("Y", 1, 4),
+                make_bare_builtin_call("Z", 2, 2),
+                make_bare_builtin_call("B", 3, 1),
+                make_bare_builtin_call("C", 5, 3),
+            ],
+            statements
+        );
+    }
+
+    #[test]
+    fn test_include_not_a_directive() {
+        let statements =
+            parse_with_files("' #INCLUDE \"lib\"\nREM #INCLUDE \"lib\"\n'#INCLUDES\nA", &[])
+                .unwrap();
+        assert_eq!(vec![make_bare_builtin_call("A", 4, 1)], statements);
+        assert!(
+            find_includes("' #INCLUDE \"lib\"\n'#INCLUDE lib\n'#Include \"a.bas\"").eq(&["a.bas"])
+        );
+    }
+
+    #[test]
+    fn test_include_errors() {
+        fn check(exp_error: &str, input: &str, files: &[(&str, &str)]) {
+            assert_eq!(
+                exp_error,
+                format!("{}", parse_with_files(input, files).expect_err("Parsing did not fail"))
+            );
+        }
+
+        check("2:3: Expected a quoted path in #INCLUDE directive", "A\n  '#INCLUDE lib", &[]);
+        check("1:1: Expected a quoted path in #INCLUDE directive", "'#INCLUDE \"\"", &[]);
+        check("1:1: Expected a quoted path in #INCLUDE directive", "'#INCLUDE \"a\" \"b\"", &[]);
+        check("3:1: Cannot include lib: Entry not found", "A\n\n'#INCLUDE \"lib\"", &[]);
+        check(
+            "2:1: In lib: 1:4: Missing expression in assignment",
+            "A\n'#INCLUDE \"lib\"\nB",
+            &[("lib", "A =")],
+        );
+        check(
+            "1:1: In a: 2:1: In b: 1:1: Cyclic #INCLUDE of A",
+            "'#INCLUDE \"a\"",
+            &[("a", "X\n'#INCLUDE \"b\""), ("b", "'#INCLUDE \"a\"")],
+        );
+        check("1:1: WHILE without WEND", "WHILE TRUE\n'#INCLUDE \"lib\"\nWEND", &[("lib", "")]);
+    }
+
     #[test]
     fn test_for_empty() {
         let auto_iter = VarRef::new("i", VarType::Auto);
@@ -3697,6 +4320,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_for_each() {
+        do_ok_test(
+            "FOR EACH x IN arr\nNEXT",
+            &[Statement::ForEach(ForEachSpan {
+                iter: VarRef::new("x", VarType::Auto),
+                iter_pos: lc(1, 10),
+                array: VarRef::new("arr", VarType::Auto),
+                array_pos: lc(1, 15),
+                body: vec![],
+                next_pos: lc(2, 1),
+            })],
+        );
+
+        do_ok_test(
+            "for each s$ in names$\n  REM Nothing to do\nnext",
+            &[Statement::ForEach(ForEachSpan {
+                iter: VarRef::new("s", VarType::Text),
+                iter_pos: lc(1, 10),
+                array: VarRef::new("names", VarType::Text),
+                array_pos: lc(1, 16),
+                body: vec![],
+                next_pos: lc(3, 1),
+            })],
+        );
+    }
+
+    #[test]
+    fn test_for_each_is_not_a_keyword() {
+        let iter = VarRef::new("each", VarType::Auto);
+        do_ok_test(
+            "FOR each = 1 TO 2\nNEXT",
+            &[Statement::For(ForSpan {
+                iter: iter.clone(),
+                iter_pos: lc(1, 5),
+                iter_double: false,
+                start: expr_integer(1, 1, 12),
+                end: Expr::LessEqual(Box::from(BinaryOpSpan {
+                    lhs: expr_symbol(iter.clone(), 1, 5),
+                    rhs: expr_integer(2, 1, 17),
+                    pos: lc(1, 14),
+                })),
+                next: Expr::Add(Box::from(BinaryOpSpan {
+                    lhs: expr_symbol(iter, 1, 5),
+                    rhs: expr_integer(1, 1, 18),
+                    pos: lc(1, 14),
+                })),
+                body: vec![],
+            })],
+        );
+    }
+
+    #[test]
+    fn test_for_each_errors() {
+        do_error_test("FOR EACH 3\n", "1:10: No equal sign in FOR statement");
+        do_error_test("FOR EACH x\n", "1:11: No IN in FOR EACH statement");
+        do_error_test("FOR EACH x ON a\n", "1:12: No IN in FOR EACH statement");
+        do_error_test("FOR EACH x IN\n", "1:14: No array name in FOR EACH statement");
+        do_error_test("FOR EACH x IN a()\n", "1:16: Expecting newline after FOR EACH");
+        do_error_test("FOR EACH x IN a\nPRINT x\n", "1:1: FOR without NEXT");
+    }
+
     #[test]
     fn test_for_errors() {
         do_error_test("FOR\n", "1:4: No iterator name in FOR statement");
@@ -3835,8 +4520,8 @@ mod tests {
 
     #[test]
     fn test_parse_on_error_errors() {
-        do_error_test("ON", "1:3: Expected ERROR after ON");
-        do_error_test("ON NEXT", "1:4: Expected ERROR after ON");
+        do_error_test("ON", "1:3: Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON");
+        do_error_test("ON NEXT", "1:4: Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON");
         do_error_test("ON ERROR", "1:9: Expected GOTO or RESUME after ON ERROR");
         do_error_test("ON ERROR FOR", "1:10: Expected GOTO or RESUME after ON ERROR");
 
@@ -3849,6 +4534,228 @@ mod tests {
         do_error_test("ON ERROR GOTO 0 @a", "1:17: Expected newline but found @a");
     }
 
+    #[test]
+    fn test_parse_on_file_change_ok() {
+        do_ok_test("ON FILECHANGE GOSUB 0", &[Statement::OnFileChange(OnFileChangeSpan::Reset)]);
+
+        do_ok_test(
+            "on filechange gosub 10",
+            &[Statement::OnFileChange(OnFileChangeSpan::Gosub(GotoSpan {
+                target: "10".to_owned(),
+                target_pos: lc(1, 21),
+            }))],
+        );
+
+        do_ok_test(
+            "ON FILECHANGE GOSUB @reload",
+            &[Statement::OnFileChange(OnFileChangeSpan::Gosub(GotoSpan {
+                target: "reload".to_owned(),
+                target_pos: lc(1, 21),
+            }))],
+        );
+    }
+
+    #[test]
+    fn test_parse_on_file_change_errors() {
+        do_error_test("ON FOO", "1:4: Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON");
+        do_error_test(
+            "ON FILECHANGE$",
+            "1:4: Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON",
+        );
+        do_error_test("ON FILECHANGE", "1:14: Expected GOSUB after ON FILECHANGE");
+        do_error_test("ON FILECHANGE GOTO @a", "1:15: Expected GOSUB after ON FILECHANGE");
+
+        do_error_test(
+            "ON FILECHANGE GOSUB",
+            "1:20: Expected label name or 0 after ON FILECHANGE GOSUB",
+        );
+        do_error_test(
+            "ON FILECHANGE GOSUB NEXT",
+            "1:21: Expected label name or 0 after ON FILECHANGE GOSUB",
+        );
+        do_error_test("ON FILECHANGE GOSUB 0 @a", "1:23: Expected newline but found @a");
+    }
+
+    #[test]
+    fn test_parse_on_gpio_ok() {
+        do_ok_test(
+            "ON GPIO 5, \"RISING\" GOSUB 0",
+            &[Statement::OnGpio(OnGpioSpan {
+                pin: expr_integer(5, 1, 9),
+                edge: Expr::Text(TextSpan { value: "RISING".to_owned(), pos: lc(1, 12) }),
+                handler: None,
+            })],
+        );
+
+        do_ok_test(
+            "on gpio p, e$ gosub 10",
+            &[Statement::OnGpio(OnGpioSpan {
+                pin: Expr::Symbol(SymbolSpan {
+                    vref: VarRef::new("p", VarType::Auto),
+                    pos: lc(1, 9),
+                }),
+                edge: Expr::Symbol(SymbolSpan {
+                    vref: VarRef::new("e", VarType::Text),
+                    pos: lc(1, 12),
+                }),
+                handler: Some(GotoSpan { target: "10".to_owned(), target_pos: lc(1, 21) }),
+            })],
+        );
+
+        do_ok_test(
+            "ON GPIO 17, \"FALLING\" GOSUB @pressed",
+            &[Statement::OnGpio(OnGpioSpan {
+                pin: expr_integer(17, 1, 9),
+                edge: Expr::Text(TextSpan { value: "FALLING".to_owned(), pos: lc(1, 13) }),
+                handler: Some(GotoSpan { target: "pressed".to_owned(), target_pos: lc(1, 29) }),
+            })],
+        );
+    }
+
+    #[test]
+    fn test_parse_on_gpio_errors() {
+        do_error_test(
+            "ON GPIO$",
+            "1:4: Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON",
+        );
+        do_error_test("ON GPIO", "1:8: Expected number or variable after ON GPIO");
+        do_error_test(
+            "ON GPIO \"a\", \"RISING\"",
+            "1:9: Expected number or variable after ON GPIO",
+        );
+        do_error_test("ON GPIO 5", "1:10: Expected comma after ON GPIO pin%");
+        do_error_test("ON GPIO 5 GOSUB @a", "1:11: Expected comma after ON GPIO pin%");
+        do_error_test("ON GPIO 5,", "1:11: Expected string or variable after ON GPIO pin%,");
+        do_error_test(
+            "ON GPIO 5, 1 GOSUB @a",
+            "1:12: Expected string or variable after ON GPIO pin%,",
+        );
+        do_error_test("ON GPIO 5, \"RISING\"", "1:20: Expected GOSUB after ON GPIO pin%, edge$");
+        do_error_test(
+            "ON GPIO 5, \"RISING\" GOTO @a",
+            "1:21: Expected GOSUB after ON GPIO pin%, edge$",
+        );
+
+        do_error_test(
+            "ON GPIO 5, \"RISING\" GOSUB",
+            "1:26: Expected label name or 0 after ON GPIO pin%, edge$ GOSUB",
+        );
+        do_error_test(
+            "ON GPIO 5, \"RISING\" GOSUB NEXT",
+            "1:27: Expected label name or 0 after ON GPIO pin%, edge$ GOSUB",
+        );
+        do_error_test("ON GPIO 5, \"RISING\" GOSUB 0 @a", "1:29: Expected newline but found @a");
+    }
+
+    #[test]
+    fn test_parse_on_interval_ok() {
+        do_ok_test(
+            "ON INTERVAL 100 GOSUB 0",
+            &[Statement::OnInterval(OnIntervalSpan {
+                period: Expr::Integer(IntegerSpan { value: 100, pos: lc(1, 13) }),
+                handler: None,
+            })],
+        );
+
+        do_ok_test(
+            "on interval 0.5 gosub 10",
+            &[Statement::OnInterval(OnIntervalSpan {
+                period: Expr::Double(DoubleSpan { value: 0.5, pos: lc(1, 13) }),
+                handler: Some(GotoSpan { target: "10".to_owned(), target_pos: lc(1, 23) }),
+            })],
+        );
+
+        do_ok_test(
+            "ON INTERVAL ms GOSUB @tick",
+            &[Statement::OnInterval(OnIntervalSpan {
+                period: Expr::Symbol(SymbolSpan {
+                    vref: VarRef::new("ms", VarType::Auto),
+                    pos: lc(1, 13),
+                }),
+                handler: Some(GotoSpan { target: "tick".to_owned(), target_pos: lc(1, 22) }),
+            })],
+        );
+    }
+
+    #[test]
+    fn test_parse_on_interval_errors() {
+        do_error_test(
+            "ON INTERVAL$",
+            "1:4: Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON",
+        );
+        do_error_test("ON INTERVAL", "1:12: Expected number or variable after ON INTERVAL");
+        do_error_test(
+            "ON INTERVAL GOSUB @a",
+            "1:13: Expected number or variable after ON INTERVAL",
+        );
+        do_error_test(
+            "ON INTERVAL \"a\" GOSUB @a",
+            "1:13: Expected number or variable after ON INTERVAL",
+        );
+        do_error_test("ON INTERVAL 10", "1:15: Expected GOSUB after ON INTERVAL ms");
+        do_error_test("ON INTERVAL 10 GOTO @a", "1:16: Expected GOSUB after ON INTERVAL ms");
+
+        do_error_test(
+            "ON INTERVAL 10 GOSUB",
+            "1:21: Expected label name or 0 after ON INTERVAL ms GOSUB",
+        );
+        do_error_test(
+            "ON INTERVAL 10 GOSUB NEXT",
+            "1:22: Expected label name or 0 after ON INTERVAL ms GOSUB",
+        );
+        do_error_test("ON INTERVAL 10 GOSUB 0 @a", "1:24: Expected newline but found @a");
+    }
+
+    #[test]
+    fn test_parse_on_key_ok() {
+        do_ok_test(
+            "ON KEY \"F1\" GOSUB 0",
+            &[Statement::OnKey(OnKeySpan {
+                key: Expr::Text(TextSpan { value: "F1".to_owned(), pos: lc(1, 8) }),
+                handler: None,
+            })],
+        );
+
+        do_ok_test(
+            "on key k$ gosub 10",
+            &[Statement::OnKey(OnKeySpan {
+                key: Expr::Symbol(SymbolSpan {
+                    vref: VarRef::new("k", VarType::Text),
+                    pos: lc(1, 8),
+                }),
+                handler: Some(GotoSpan { target: "10".to_owned(), target_pos: lc(1, 17) }),
+            })],
+        );
+
+        do_ok_test(
+            "ON KEY \"ESC\" GOSUB @quit",
+            &[Statement::OnKey(OnKeySpan {
+                key: Expr::Text(TextSpan { value: "ESC".to_owned(), pos: lc(1, 8) }),
+                handler: Some(GotoSpan { target: "quit".to_owned(), target_pos: lc(1, 20) }),
+            })],
+        );
+    }
+
+    #[test]
+    fn test_parse_on_key_errors() {
+        do_error_test("ON KEY$", "1:4: Expected ERROR, FILECHANGE, GPIO, INTERVAL or KEY after ON");
+        do_error_test("ON KEY", "1:7: Expected string or variable after ON KEY");
+        do_error_test("ON KEY GOSUB @a", "1:8: Expected string or variable after ON KEY");
+        do_error_test("ON KEY 1 GOSUB @a", "1:8: Expected string or variable after ON KEY");
+        do_error_test("ON KEY \"F1\"", "1:12: Expected GOSUB after ON KEY key$");
+        do_error_test("ON KEY \"F1\" GOTO @a", "1:13: Expected GOSUB after ON KEY key$");
+
+        do_error_test(
+            "ON KEY \"F1\" GOSUB",
+            "1:18: Expected label name or 0 after ON KEY key$ GOSUB",
+        );
+        do_error_test(
+            "ON KEY \"F1\" GOSUB NEXT",
+            "1:19: Expected label name or 0 after ON KEY key$ GOSUB",
+        );
+        do_error_test("ON KEY \"F1\" GOSUB 0 @a", "1:21: Expected newline but found @a");
+    }
+
     #[test]
     fn test_select_empty() {
         do_ok_test(
@@ -21,7 +21,7 @@ use std::io::{self, BufRead};
 use std::rc::Rc;
 
 /// Tab length used to compute the current position within a line when encountering a tab character.
-const TAB_LENGTH: usize = 8;
+pub(crate) const TAB_LENGTH: usize = 8;
 
 /// Representation of a position within a stream.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -15,7 +15,9 @@
 
 //! Symbol definitions and symbols table representation.
 
-use crate::ast::{BuiltinCallSpan, FunctionCallSpan, Value, VarRef, VarType};
+use crate::ast::{
+    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarRef, VarType,
+};
 use crate::eval;
 use crate::exec::Machine;
 use crate::reader::LineCol;
@@ -110,6 +112,11 @@ impl Array {
         &self.dimensions
     }
 
+    /// Returns all values in the array, flattened in row-major order.
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
     /// Returns the type of the elements in this array.
     pub fn subtype(&self) -> VarType {
         self.subtype
@@ -167,6 +174,44 @@ impl Array {
         Ok(())
     }
 
+    /// Changes the dimensions of the array to `dimensions`.
+    ///
+    /// If `preserve` is true, the elements whose subscripts are valid in both the old and the new
+    /// dimensions keep their values, which requires the number of dimensions to stay the same.
+    /// All other elements are set to the default value of the array's type.
+    pub fn resize(&mut self, dimensions: Vec<usize>, preserve: bool) -> Result<()> {
+        if preserve && dimensions.len() != self.dimensions.len() {
+            return Err(Error::new(format!(
+                "Cannot preserve contents when changing the number of dimensions from {} to {}",
+                self.dimensions.len(),
+                dimensions.len()
+            )));
+        }
+
+        let mut resized = Array::new(self.subtype, dimensions);
+        if preserve {
+            let mut subscripts = vec![0; self.dimensions.len()];
+            for value in self.values.drain(..) {
+                if let Ok(i) = Array::native_index(&resized.dimensions, &subscripts) {
+                    resized.values[i] = value;
+                }
+
+                // Advance to the subscripts of the next value in row-major order.
+                let mut k = subscripts.len();
+                while k > 0 {
+                    k -= 1;
+                    subscripts[k] += 1;
+                    if (subscripts[k] as usize) < self.dimensions[k] {
+                        break;
+                    }
+                    subscripts[k] = 0;
+                }
+            }
+        }
+        *self = resized;
+        Ok(())
+    }
+
     /// Obtains the value contained in the array position indicated by the `subscripts`.
     pub fn index(&self, subscripts: &[i32]) -> Result<&Value> {
         let i = Array::native_index(&self.dimensions, subscripts)?;
@@ -317,7 +362,8 @@ impl Symbols {
         let symbol = self.by_name.get(key);
         if let Some(symbol) = symbol {
             let stype = symbol.eval_type();
-            if !vref.accepts(stype) {
+            // Callables that return values of any type are checked when invoked.
+            if stype != VarType::Auto && !vref.accepts(stype) {
                 return Err(Error::new(format!("Incompatible types in {} reference", vref)));
             }
         }
@@ -435,6 +481,7 @@ pub struct CallableMetadataBuilder {
     category: Option<&'static str>,
     syntax: Option<&'static str>,
     description: Option<&'static str>,
+    syntactic_args: bool,
 }
 
 impl CallableMetadataBuilder {
@@ -443,10 +490,20 @@ impl CallableMetadataBuilder {
     /// All code except tests must populate the whole builder with details.  This is enforced at
     /// construction time, where we only allow some fields to be missing under the test
     /// configuration.
+    ///
+    /// Functions that can return values of different types, depending on their arguments, can
+    /// use `VarType::Auto` as their `return_type`.
     pub fn new(name: &'static str, return_type: VarType) -> Self {
         assert!(name == name.to_ascii_uppercase(), "Callable name must be in uppercase");
 
-        Self { name, return_type, syntax: None, category: None, description: None }
+        Self {
+            name,
+            return_type,
+            syntax: None,
+            category: None,
+            description: None,
+            syntactic_args: false,
+        }
     }
 
     /// Sets the syntax specification for this callable.  The `syntax` is provided as a free-form
@@ -476,6 +533,14 @@ impl CallableMetadataBuilder {
         self
     }
 
+    /// Marks this callable as interpreting the syntax of its argument expressions instead of just
+    /// their values (e.g. `LIST 2-5` denotes a range of lines, not the number -3), which keeps the
+    /// optimizer from precomputing them.
+    pub fn with_syntactic_args(mut self) -> Self {
+        self.syntactic_args = true;
+        self
+    }
+
     /// Generates the final `CallableMetadata` object, ensuring all values are present.
     pub fn build(self) -> CallableMetadata {
         CallableMetadata {
@@ -484,6 +549,7 @@ impl CallableMetadataBuilder {
             syntax: self.syntax.expect("All callables must specify a syntax"),
             category: self.category.expect("All callables must specify a category"),
             description: self.description.expect("All callables must specify a description"),
+            syntactic_args: self.syntactic_args,
         }
     }
 
@@ -496,6 +562,7 @@ impl CallableMetadataBuilder {
             syntax: self.syntax.unwrap_or(""),
             category: self.category.unwrap_or(""),
             description: self.description.unwrap_or(""),
+            syntactic_args: self.syntactic_args,
         }
     }
 }
@@ -511,6 +578,7 @@ pub struct CallableMetadata {
     syntax: &'static str,
     category: &'static str,
     description: &'static str,
+    syntactic_args: bool,
 }
 
 impl CallableMetadata {
@@ -545,6 +613,308 @@ impl CallableMetadata {
     pub fn is_argless(&self) -> bool {
         self.syntax.is_empty()
     }
+
+    /// Returns true if this callable interprets the syntax of its argument expressions, in which
+    /// case they must be passed to it exactly as written.
+    pub fn has_syntactic_args(&self) -> bool {
+        self.syntactic_args
+    }
+}
+
+/// Conversion of EndBASIC values into native Rust types.
+pub trait FromValue: Sized {
+    /// Converts `value` into the native type, or returns `None` if the types are incompatible.
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Double(_) | Value::Integer(_) | Value::Long(_) => value.as_f64().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for i32 {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Integer(i) => Some(i64::from(*i)),
+            Value::Long(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Text(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Definition of a single argument in an `ArgSpec`.
+struct ArgDef {
+    name: &'static str,
+    vtype: VarType,
+}
+
+/// Builder pattern for an `ArgSpec`.
+#[derive(Default)]
+pub struct ArgSpecBuilder {
+    args: Vec<ArgDef>,
+    required: usize,
+}
+
+impl ArgSpecBuilder {
+    /// Constructs a new builder for a callable that takes no arguments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a mandatory argument called `name` of type `vtype`.  The `name` should match the
+    /// one used in the callable's syntax specification as it appears in error messages.
+    ///
+    /// Arguments of type `VarType::Auto` accept any value.  Numeric arguments accept values of any
+    /// numeric type and cast them to `vtype`.
+    pub fn required(mut self, name: &'static str, vtype: VarType) -> Self {
+        assert!(self.required == self.args.len(), "Required arguments must come first");
+        assert!(vtype != VarType::Void, "Arguments cannot be void");
+        self.args.push(ArgDef { name, vtype });
+        self.required += 1;
+        self
+    }
+
+    /// Appends an optional argument called `name` of type `vtype`.  Optional arguments can only
+    /// be omitted from the end of an invocation.
+    pub fn optional(mut self, name: &'static str, vtype: VarType) -> Self {
+        assert!(vtype != VarType::Void, "Arguments cannot be void");
+        self.args.push(ArgDef { name, vtype });
+        self
+    }
+
+    /// Generates the final `ArgSpec` object.
+    pub fn build(self) -> ArgSpec {
+        ArgSpec { args: self.args, required: self.required }
+    }
+}
+
+/// Declarative specification of the arguments accepted by a callable.
+///
+/// The specification takes care of evaluating the arguments of an invocation, validating that
+/// their number and types are correct, and reporting errors in a consistent manner.  Commands
+/// accept arguments separated by commas only.
+pub struct ArgSpec {
+    args: Vec<ArgDef>,
+    required: usize,
+}
+
+impl ArgSpec {
+    /// Evaluates the argument `expr` that corresponds to the `i`th definition.
+    async fn eval_one(
+        &self,
+        i: usize,
+        expr: &Expr,
+        symbols: &mut Symbols,
+    ) -> std::result::Result<Value, CallError> {
+        let def = &self.args[i];
+        let pos = expr.start_pos();
+        let value = expr.eval(symbols).await?;
+
+        let expected = match (def.vtype, &value) {
+            (VarType::Auto, _) => return Ok(value),
+            (VarType::Boolean, Value::Boolean(_)) => return Ok(value),
+            (VarType::Text, Value::Text(_)) => return Ok(value),
+            (VarType::Double, Value::Double(_) | Value::Integer(_) | Value::Long(_))
+            | (VarType::Integer, Value::Double(_) | Value::Integer(_) | Value::Long(_))
+            | (VarType::Long, Value::Double(_) | Value::Integer(_) | Value::Long(_)) => {
+                return value
+                    .maybe_cast(def.vtype)
+                    .map_err(|e| CallError::ArgumentError(pos, format!("{}: {}", def.name, e)));
+            }
+            (VarType::Boolean, _) => "a boolean",
+            (VarType::Double, _) => "a number",
+            (VarType::Integer, _) | (VarType::Long, _) => "an integer",
+            (VarType::Text, _) => "a string",
+            (VarType::Void, _) => unreachable!("Prevented by the builder"),
+        };
+        Err(CallError::ArgumentError(pos, format!("{} must be {}", def.name, expected)))
+    }
+
+    /// Checks that `nargs` is a valid number of arguments for this specification.
+    fn check_count(&self, nargs: usize) -> std::result::Result<(), CallError> {
+        if nargs < self.required || nargs > self.args.len() {
+            return Err(CallError::SyntaxError);
+        }
+        Ok(())
+    }
+
+    /// Evaluates the `args` given to a command.
+    pub async fn eval_command_args(
+        &self,
+        args: &[ArgSpan],
+        symbols: &mut Symbols,
+    ) -> std::result::Result<Args, CallError> {
+        self.check_count(args.len())?;
+        let mut values = Vec::with_capacity(args.len());
+        for (i, arg) in args.iter().enumerate() {
+            let expr = match (&arg.expr, arg.sep) {
+                (Some(expr), ArgSep::End) => expr,
+                (Some(expr), ArgSep::Long) if i < args.len() - 1 => expr,
+                _ => return Err(CallError::SyntaxError),
+            };
+            values.push((self.eval_one(i, expr, symbols).await?, expr.start_pos()));
+        }
+        Ok(Args { values })
+    }
+
+    /// Evaluates the `args` given to a function.
+    pub async fn eval_function_args(
+        &self,
+        args: &[Expr],
+        symbols: &mut Symbols,
+    ) -> std::result::Result<Args, CallError> {
+        self.check_count(args.len())?;
+        let mut values = Vec::with_capacity(args.len());
+        for (i, expr) in args.iter().enumerate() {
+            values.push((self.eval_one(i, expr, symbols).await?, expr.start_pos()));
+        }
+        Ok(Args { values })
+    }
+}
+
+/// Values of the arguments of an invocation, as evaluated by an `ArgSpec`.
+///
+/// The values are guaranteed to match the types declared in the specification, so the accessors
+/// panic if the requested type is not compatible with the declared one.
+#[derive(Debug)]
+pub struct Args {
+    values: Vec<(Value, LineCol)>,
+}
+
+impl Args {
+    /// Returns the number of arguments that were given in the invocation.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if the invocation did not provide any arguments.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the value of the mandatory `i`th argument.
+    pub fn get<T: FromValue>(&self, i: usize) -> T {
+        self.get_opt(i).expect("Required arguments are validated by the specification")
+    }
+
+    /// Returns the value of the optional `i`th argument, or none if it was not provided.
+    pub fn get_opt<T: FromValue>(&self, i: usize) -> Option<T> {
+        self.values.get(i).map(|(value, _)| {
+            T::from_value(value).expect("Argument types are validated by the specification")
+        })
+    }
+
+    /// Returns the position of the `i`th argument, which is useful to report further errors.
+    pub fn pos(&self, i: usize) -> LineCol {
+        self.values[i].1
+    }
+}
+
+/// Type of the closures that implement a `ClosureCommand`.
+pub type CommandFn = Box<dyn Fn(Args, &mut Machine) -> CommandResult>;
+
+/// Type of the closures that implement a `ClosureFunction`.
+pub type FunctionFn = Box<dyn Fn(Args, &mut Symbols) -> FunctionResult>;
+
+/// A command backed by a closure, which is convenient for embedders that want to expose their
+/// own logic to EndBASIC without having to implement the `Command` trait.
+pub struct ClosureCommand {
+    metadata: CallableMetadata,
+    args: ArgSpec,
+    f: CommandFn,
+}
+
+impl ClosureCommand {
+    /// Creates a new command described by `metadata` that evaluates its arguments as specified
+    /// by `args` and then delegates its execution to `f`.
+    pub fn new<F>(metadata: CallableMetadata, args: ArgSpec, f: F) -> Rc<Self>
+    where
+        F: Fn(Args, &mut Machine) -> CommandResult + 'static,
+    {
+        assert!(metadata.return_type() == VarType::Void, "Commands cannot return values");
+        Rc::from(Self { metadata, args, f: Box::from(f) })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ClosureCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let args = self.args.eval_command_args(&span.args, machine.get_mut_symbols()).await?;
+        (self.f)(args, machine)
+    }
+}
+
+/// A function backed by a closure, which is convenient for embedders that want to expose their
+/// own logic to EndBASIC without having to implement the `Function` trait.
+pub struct ClosureFunction {
+    metadata: CallableMetadata,
+    args: ArgSpec,
+    f: FunctionFn,
+}
+
+impl ClosureFunction {
+    /// Creates a new function described by `metadata` that evaluates its arguments as specified
+    /// by `args` and then delegates the computation of its result to `f`.
+    pub fn new<F>(metadata: CallableMetadata, args: ArgSpec, f: F) -> Rc<Self>
+    where
+        F: Fn(Args, &mut Symbols) -> FunctionResult + 'static,
+    {
+        assert!(metadata.return_type() != VarType::Void, "Functions must return a value");
+        Rc::from(Self { metadata, args, f: Box::from(f) })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ClosureFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = self.args.eval_function_args(&span.args, symbols).await?;
+        (self.f)(args, symbols)
+    }
 }
 
 /// A trait to define a function that is executed by a `Machine`.
@@ -603,7 +973,9 @@ pub trait Command {
 mod tests {
     use super::*;
     use crate::ast::VarRef;
+    use crate::exec::StopReason;
     use crate::testutils::*;
+    use futures_lite::future::block_on;
     use std::cell::RefCell;
 
     #[test]
@@ -786,6 +1158,28 @@ mod tests {
         assert_eq!(&Value::Integer(6), array.index(&[2]).unwrap());
     }
 
+    #[test]
+    fn test_array_resize() {
+        let mut array = Array::new(VarType::Integer, vec![2, 3]);
+        array.assign(&[0, 1], 1.into()).unwrap();
+        array.assign(&[1, 2], 2.into()).unwrap();
+
+        array.resize(vec![3, 2], true).unwrap();
+        assert_eq!(&[3, 2], array.dimensions());
+        assert_eq!(&Value::Integer(1), array.index(&[0, 1]).unwrap());
+        assert_eq!(&Value::Integer(0), array.index(&[1, 1]).unwrap());
+        assert_eq!(&Value::Integer(0), array.index(&[2, 1]).unwrap());
+
+        array.resize(vec![4], false).unwrap();
+        assert_eq!(&[4], array.dimensions());
+        assert_eq!(vec![Value::Integer(0); 4].as_slice(), array.values());
+
+        assert_eq!(
+            "Cannot preserve contents when changing the number of dimensions from 1 to 2",
+            format!("{}", array.resize(vec![1, 1], true).unwrap_err())
+        );
+    }
+
     #[test]
     fn test_symbols_clear() {
         let mut syms = SymbolsBuilder::default()
@@ -1407,4 +1801,188 @@ mod tests {
         syms.unset("FOO").unwrap_err();
         assert_eq!(1, syms.as_hashmap().len());
     }
+
+    /// Command that evaluates its arguments with a fixed specification and captures them.
+    struct ArgsCommand {
+        metadata: CallableMetadata,
+        spec: ArgSpec,
+        data: Rc<RefCell<Vec<String>>>,
+    }
+
+    #[async_trait(?Send)]
+    impl Command for ArgsCommand {
+        fn metadata(&self) -> &CallableMetadata {
+            &self.metadata
+        }
+
+        async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+            let args = self.spec.eval_command_args(&span.args, machine.get_mut_symbols()).await?;
+            self.data.borrow_mut().push(format!(
+                "{} {} {:?} {:?}",
+                args.get::<String>(0),
+                args.get::<i32>(1),
+                args.get_opt::<f64>(2),
+                args.get_opt::<Value>(3),
+            ));
+            Ok(())
+        }
+    }
+
+    /// Function that evaluates its arguments with a fixed specification and returns them.
+    struct ArgsFunction {
+        metadata: CallableMetadata,
+        spec: ArgSpec,
+    }
+
+    #[async_trait(?Send)]
+    impl Function for ArgsFunction {
+        fn metadata(&self) -> &CallableMetadata {
+            &self.metadata
+        }
+
+        async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+            let args = self.spec.eval_function_args(&span.args, symbols).await?;
+            if !args.get::<bool>(0) {
+                return Err(CallError::ArgumentError(args.pos(0), "Must be true".to_owned()));
+            }
+            Ok(Value::Long(args.get_opt::<i64>(1).unwrap_or(-1)))
+        }
+    }
+
+    /// Runs `input` on a machine that provides the `ARGS` command and the `ARGSF` function, and
+    /// returns the captured calls to the command.
+    fn run_args(input: &str) -> crate::exec::Result<Vec<String>> {
+        let data = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.add_command(Rc::from(ArgsCommand {
+            metadata: CallableMetadataBuilder::new("ARGS", VarType::Void)
+                .with_syntax("name$, n%[, x#[, any]]")
+                .test_build(),
+            spec: ArgSpecBuilder::new()
+                .required("name$", VarType::Text)
+                .required("n%", VarType::Integer)
+                .optional("x#", VarType::Double)
+                .optional("any", VarType::Auto)
+                .build(),
+            data: data.clone(),
+        }));
+        machine.add_function(Rc::from(ArgsFunction {
+            metadata: CallableMetadataBuilder::new("ARGSF", VarType::Long)
+                .with_syntax("b?[, n&]")
+                .test_build(),
+            spec: ArgSpecBuilder::new()
+                .required("b?", VarType::Boolean)
+                .optional("n&", VarType::Long)
+                .build(),
+        }));
+        assert_eq!(StopReason::Eof, block_on(machine.exec(&mut input.as_bytes()))?);
+        let data = data.borrow().clone();
+        Ok(data)
+    }
+
+    #[test]
+    fn test_argspec_command_ok() {
+        assert_eq!(
+            [
+                "a 3 None None",
+                "b 4 Some(1.5) None",
+                "c 5 Some(2.0) Some(Boolean(true))",
+                "d 8 Some(0.0) Some(Long(4))",
+            ],
+            run_args(
+                r#"
+                ARGS "a", 3
+                ARGS "b", 3.6, 1.5
+                ARGS "c", 5, 2, TRUE
+                ARGS "d", 8, 0, ARGSF(TRUE, 4)
+                "#
+            )
+            .unwrap()
+            .as_slice()
+        );
+    }
+
+    #[test]
+    fn test_argspec_function_ok() {
+        assert_eq!(
+            ["x -1 None None", "y 5 None None"],
+            run_args(r#"ARGS "x", ARGSF(TRUE): ARGS "y", ARGSF(TRUE, 5.2)"#).unwrap().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_argspec_errors() {
+        for (input, expected) in [
+            ("ARGS", "1:1: In call to ARGS: expected name$, n%[, x#[, any]]"),
+            (r#"ARGS "a""#, "1:1: In call to ARGS: expected name$, n%[, x#[, any]]"),
+            (r#"ARGS "a", 1, 2, 3, 4"#, "1:1: In call to ARGS: expected name$, n%[, x#[, any]]"),
+            (r#"ARGS "a"; 1"#, "1:1: In call to ARGS: expected name$, n%[, x#[, any]]"),
+            (r#"ARGS "a", 1,"#, "1:1: In call to ARGS: expected name$, n%[, x#[, any]]"),
+            (r#"ARGS "a", , 1"#, "1:1: In call to ARGS: expected name$, n%[, x#[, any]]"),
+            ("ARGS 1, 2", "1:1: In call to ARGS: 1:6: name$ must be a string"),
+            (r#"ARGS "a", "b""#, "1:1: In call to ARGS: 1:11: n% must be an integer"),
+            (r#"ARGS "a", 1, FALSE"#, "1:1: In call to ARGS: 1:14: x# must be a number"),
+            (
+                r#"ARGS "a", 3000000000.0"#,
+                "1:1: In call to ARGS: 1:11: n%: Cannot cast 3000000000 to integer due to overflow",
+            ),
+            (r#"ARGS "a", undefined"#, "1:11: Undefined variable undefined"),
+            ("a = ARGSF()", "1:5: In call to ARGSF: expected b?[, n&]"),
+            ("a = ARGSF(1)", "1:5: In call to ARGSF: 1:11: b? must be a boolean"),
+            ("a = ARGSF(FALSE)", "1:5: In call to ARGSF: 1:11: Must be true"),
+            (r#"a = ARGSF(TRUE, "x")"#, "1:5: In call to ARGSF: 1:17: n& must be an integer"),
+        ] {
+            assert_eq!(expected, format!("{}", run_args(input).unwrap_err()), "Input: {}", input);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Required arguments must come first")]
+    fn test_argspec_builder_required_after_optional() {
+        ArgSpecBuilder::new().optional("a", VarType::Auto).required("b", VarType::Auto);
+    }
+
+    #[test]
+    fn test_closure_command_and_function() {
+        let calls = Rc::from(RefCell::from(vec![]));
+        let mut machine = Machine::default();
+        machine.add_command(ClosureCommand::new(
+            CallableMetadataBuilder::new("LOG", VarType::Void).with_syntax("msg$").test_build(),
+            ArgSpecBuilder::new().required("msg$", VarType::Text).build(),
+            {
+                let calls = calls.clone();
+                move |args, machine| {
+                    let msg = args.get::<String>(0);
+                    if msg.is_empty() {
+                        return Err(CallError::ArgumentError(args.pos(0), "Empty".to_owned()));
+                    }
+                    calls.borrow_mut().push(msg);
+                    machine.set_var("logged", true).unwrap();
+                    Ok(())
+                }
+            },
+        ));
+        machine.add_function(ClosureFunction::new(
+            CallableMetadataBuilder::new("TWICE", VarType::Integer).with_syntax("n%").test_build(),
+            ArgSpecBuilder::new().required("n%", VarType::Integer).build(),
+            |args, _symbols| Ok(Value::Integer(args.get::<i32>(0) * 2)),
+        ));
+
+        assert_eq!(
+            StopReason::Eof,
+            block_on(machine.exec(&mut b"LOG \"a\": n = TWICE(4)".as_ref())).unwrap()
+        );
+        assert_eq!(["a"], calls.borrow().as_slice());
+        assert_eq!(8, machine.get_var::<i32>("n").unwrap());
+        assert!(machine.get_var::<bool>("logged").unwrap());
+
+        assert_eq!(
+            "1:1: In call to LOG: 1:5: Empty",
+            format!("{}", block_on(machine.exec(&mut b"LOG \"\"".as_ref())).unwrap_err())
+        );
+        assert_eq!(
+            "1:5: In call to TWICE: 1:11: n% must be an integer",
+            format!("{}", block_on(machine.exec(&mut b"a = TWICE(\"x\")".as_ref())).unwrap_err())
+        );
+    }
 }
@@ -15,7 +15,9 @@
 
 //! Test utilities.
 
-use crate::ast::{ArgSep, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarRef, VarType};
+use crate::ast::{
+    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarRef, VarType,
+};
 use crate::eval::{self, eval_all, Error};
 use crate::exec::Machine;
 use crate::syms::{
@@ -35,6 +37,7 @@ fn format_value(v: Value, o: &mut String) {
         Value::Boolean(false) => o.push_str("FALSE"),
         Value::Double(d) => o.push_str(&format!("{}", d)),
         Value::Integer(i) => o.push_str(&format!("{}", i)),
+        Value::Long(i) => o.push_str(&format!("{}", i)),
         Value::Text(s) => o.push_str(&s),
     }
 }
@@ -300,7 +303,7 @@ impl Command for OutCommand {
             match arg.sep {
                 ArgSep::End => break,
                 ArgSep::Short => text += " ",
-                ArgSep::Long | ArgSep::As => return Err(CallError::SyntaxError),
+                ArgSep::Long | ArgSep::As | ArgSep::Timeout => return Err(CallError::SyntaxError),
             }
         }
         self.data.borrow_mut().push(text);
@@ -308,6 +311,48 @@ impl Command for OutCommand {
     }
 }
 
+/// Command that interprets its single argument as a `first-last` range of integers and captures
+/// the ends of each range into `data`.
+pub struct RangeCommand {
+    metadata: CallableMetadata,
+    data: Rc<RefCell<Vec<String>>>,
+}
+
+impl RangeCommand {
+    /// Creates a new command that captures all ranges into `data`.
+    pub fn new(data: Rc<RefCell<Vec<String>>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("RANGE", VarType::Void)
+                .with_syntax("first%-last%")
+                .with_syntactic_args()
+                .test_build(),
+            data,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for RangeCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let range = match span.args.as_slice() {
+            [ArgSpan { expr: Some(Expr::Subtract(range)), sep: ArgSep::End, .. }] => range,
+            _ => return Err(CallError::SyntaxError),
+        };
+        let first = range.lhs.eval(machine.get_mut_symbols()).await?;
+        let last = range.rhs.eval(machine.get_mut_symbols()).await?;
+        let mut text = String::new();
+        format_value(first, &mut text);
+        text += "..";
+        format_value(last, &mut text);
+        self.data.borrow_mut().push(text);
+        Ok(())
+    }
+}
+
 /// Simplified version of `PRINT` that captures all calls to it into `data` and that can be used
 /// in the context of a function by using the first argument as the return value of the function.
 pub struct OutfFunction {
@@ -441,6 +486,34 @@ impl SymbolsBuilder {
     }
 }
 
+/// Returns a value provided at construction time from a function that declares that it can return
+/// values of any type.
+pub struct AnyTypeFunction {
+    metadata: CallableMetadata,
+    value: Value,
+}
+
+impl AnyTypeFunction {
+    pub fn new(value: Value) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("ANY_TYPE", VarType::Auto).test_build(),
+            value,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for AnyTypeFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        assert!(span.args.is_empty());
+        Ok(self.value.clone())
+    }
+}
+
 /// Returns a value provided at construction time.  Note that the return type is fixed so we use
 /// this to verify if return values are correctly type-checked.
 pub struct TypeCheckFunction {
@@ -55,6 +55,13 @@ impl Value {
             }
         }
 
+        fn parse_i64(s: &str) -> Result<Value> {
+            match s.parse::<i64>() {
+                Ok(i) => Ok(Value::Long(i)),
+                Err(_) => Err(Error::new(format!("Invalid long integer literal {}", s))),
+            }
+        }
+
         let s = s.into();
         match vtype {
             VarType::Auto => parse_i32(&s),
@@ -70,6 +77,7 @@ impl Value {
             }
             VarType::Double => parse_f64(&s),
             VarType::Integer => parse_i32(&s),
+            VarType::Long => parse_i64(&s),
             VarType::Text => Ok(Value::Text(s)),
             VarType::Void => panic!("Void values are not supported"),
         }
@@ -87,6 +95,27 @@ impl Value {
                 }
             }
             Value::Integer(i) => Ok(*i),
+            Value::Long(i) => match i32::try_from(*i) {
+                Ok(i) => Ok(i),
+                Err(_) => Err(Error::new(format!("Cannot cast {} to integer due to overflow", i))),
+            },
+            _ => Err(Error::new(format!("{} is not a number", self))),
+        }
+    }
+
+    /// Reinterprets this value as an `i64` and fails if the conversion is not possible.
+    pub fn as_i64(&self) -> Result<i64> {
+        match self {
+            Value::Double(d) => {
+                let d = d.round();
+                if d.is_finite() && d >= (i64::MIN as f64) && (d < i64::MAX as f64) {
+                    Ok(d as i64)
+                } else {
+                    Err(Error::new(format!("Cannot cast {} to long due to overflow", d)))
+                }
+            }
+            Value::Integer(i) => Ok(i64::from(*i)),
+            Value::Long(i) => Ok(*i),
             _ => Err(Error::new(format!("{} is not a number", self))),
         }
     }
@@ -96,6 +125,7 @@ impl Value {
         match self {
             Value::Double(d) => Ok(*d),
             Value::Integer(i) => Ok(*i as f64),
+            Value::Long(i) => Ok(*i as f64),
             _ => Err(Error::new(format!("{} is not a number", self))),
         }
     }
@@ -107,8 +137,12 @@ impl Value {
     /// to cast a NaN to an integer.
     pub fn maybe_cast(self, target: VarType) -> Result<Value> {
         match (target, self) {
-            (VarType::Integer, d @ Value::Double(_)) => Ok(Value::Integer(d.as_i32()?)),
-            (VarType::Double, i @ Value::Integer(_)) => Ok(Value::Double(i.as_f64()?)),
+            (VarType::Integer, v @ Value::Double(_)) => Ok(Value::Integer(v.as_i32()?)),
+            (VarType::Integer, v @ Value::Long(_)) => Ok(Value::Integer(v.as_i32()?)),
+            (VarType::Double, v @ Value::Integer(_)) => Ok(Value::Double(v.as_f64()?)),
+            (VarType::Double, v @ Value::Long(_)) => Ok(Value::Double(v.as_f64()?)),
+            (VarType::Long, v @ Value::Double(_)) => Ok(Value::Long(v.as_i64()?)),
+            (VarType::Long, Value::Integer(i)) => Ok(Value::Long(i64::from(i))),
             (_, v) => Ok(v),
         }
     }
@@ -118,6 +152,9 @@ impl Value {
         match (self, other) {
             (Value::Boolean(lhs), Value::Boolean(rhs)) => Ok(Value::Boolean(*lhs && *rhs)),
             (Value::Integer(lhs), Value::Integer(rhs)) => Ok(Value::Integer(*lhs & *rhs)),
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Long(*lhs & *rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Long(i64::from(*lhs) & *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Long(*lhs & i64::from(*rhs))),
             (_, _) => Err(Error::new(format!("Cannot AND {} and {}", self, other))),
         }
     }
@@ -127,6 +164,9 @@ impl Value {
         match (self, other) {
             (Value::Boolean(lhs), Value::Boolean(rhs)) => Ok(Value::Boolean(*lhs || *rhs)),
             (Value::Integer(lhs), Value::Integer(rhs)) => Ok(Value::Integer(*lhs | *rhs)),
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Long(*lhs | *rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Long(i64::from(*lhs) | *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Long(*lhs | i64::from(*rhs))),
             (_, _) => Err(Error::new(format!("Cannot OR {} and {}", self, other))),
         }
     }
@@ -136,6 +176,9 @@ impl Value {
         match (self, other) {
             (Value::Boolean(lhs), Value::Boolean(rhs)) => Ok(Value::Boolean(*lhs ^ *rhs)),
             (Value::Integer(lhs), Value::Integer(rhs)) => Ok(Value::Integer(*lhs ^ *rhs)),
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Long(*lhs ^ *rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Long(i64::from(*lhs) ^ *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Long(*lhs ^ i64::from(*rhs))),
             (_, _) => Err(Error::new(format!("Cannot XOR {} and {}", self, other))),
         }
     }
@@ -145,61 +188,63 @@ impl Value {
         match self {
             Value::Boolean(b) => Ok(Value::Boolean(!b)),
             Value::Integer(b) => Ok(Value::Integer(!b)),
+            Value::Long(b) => Ok(Value::Long(!b)),
             _ => Err(Error::new(format!("Cannot apply NOT to {}", self))),
         }
     }
 
-    /// Performs a left shift.
-    pub fn shl(&self, other: &Self) -> Result<Self> {
+    /// Computes the number of bits to shift an integer by for the `op` shift operation, given
+    /// that `self` is the value to shift and `other` the number of bits.
+    fn shift_bits(&self, other: &Self, op: &str) -> Result<u32> {
         match (self, other) {
-            (Value::Integer(lhs), Value::Integer(rhs)) => {
-                let bits = match u32::try_from(*rhs) {
-                    Ok(n) => n,
-                    Err(_) => {
-                        return Err(Error::new(format!(
-                            "Number of bits to << ({}) must be positive",
-                            other
-                        )))
-                    }
-                };
-                match lhs.checked_shl(bits) {
-                    Some(i) => Ok(Value::Integer(i)),
-                    None => Ok(Value::Integer(0)),
-                }
+            (Value::Integer(_) | Value::Long(_), Value::Integer(rhs)) => u32::try_from(*rhs)
+                .map_err(|_| {
+                    Error::new(format!("Number of bits to {} ({}) must be positive", op, other))
+                }),
+            (Value::Integer(_) | Value::Long(_), Value::Long(rhs)) => {
+                u32::try_from(*rhs).map_err(|_| {
+                    Error::new(format!("Number of bits to {} ({}) must be positive", op, other))
+                })
             }
-            (Value::Integer(_), _) => {
-                Err(Error::new(format!("Number of bits to << ({}) must be an integer", other)))
+            (Value::Integer(_) | Value::Long(_), _) => {
+                Err(Error::new(format!("Number of bits to {} ({}) must be an integer", op, other)))
             }
 
-            (_, _) => Err(Error::new(format!("Cannot apply << to non-integer {}", self))),
+            (_, _) => Err(Error::new(format!("Cannot apply {} to non-integer {}", op, self))),
+        }
+    }
+
+    /// Performs a left shift.
+    pub fn shl(&self, other: &Self) -> Result<Self> {
+        let bits = self.shift_bits(other, "<<")?;
+        match self {
+            Value::Integer(lhs) => match lhs.checked_shl(bits) {
+                Some(i) => Ok(Value::Integer(i)),
+                None => Ok(Value::Integer(0)),
+            },
+            Value::Long(lhs) => match lhs.checked_shl(bits) {
+                Some(i) => Ok(Value::Long(i)),
+                None => Ok(Value::Long(0)),
+            },
+            _ => unreachable!("shift_bits only accepts integers"),
         }
     }
 
     /// Performs a right shift.
     pub fn shr(&self, other: &Self) -> Result<Self> {
-        match (self, other) {
-            (Value::Integer(lhs), Value::Integer(rhs)) => {
-                let bits = match u32::try_from(*rhs) {
-                    Ok(n) => n,
-                    Err(_) => {
-                        return Err(Error::new(format!(
-                            "Number of bits to >> ({}) must be positive",
-                            other
-                        )))
-                    }
-                };
-                match lhs.checked_shr(bits) {
-                    Some(i) => Ok(Value::Integer(i)),
-                    None if *lhs < 0 => Ok(Value::Integer(-1)),
-                    None => Ok(Value::Integer(0)),
-                }
-            }
-
-            (Value::Integer(_), _) => {
-                Err(Error::new(format!("Number of bits to >> ({}) must be an integer", other)))
-            }
-
-            (_, _) => Err(Error::new(format!("Cannot apply >> to non-integer {}", self))),
+        let bits = self.shift_bits(other, ">>")?;
+        match self {
+            Value::Integer(lhs) => match lhs.checked_shr(bits) {
+                Some(i) => Ok(Value::Integer(i)),
+                None if *lhs < 0 => Ok(Value::Integer(-1)),
+                None => Ok(Value::Integer(0)),
+            },
+            Value::Long(lhs) => match lhs.checked_shr(bits) {
+                Some(i) => Ok(Value::Long(i)),
+                None if *lhs < 0 => Ok(Value::Long(-1)),
+                None => Ok(Value::Long(0)),
+            },
+            _ => unreachable!("shift_bits only accepts integers"),
         }
     }
 
@@ -214,6 +259,12 @@ impl Value {
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs == *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Boolean(*lhs as f64 == *rhs)),
 
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Boolean(lhs == rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Boolean(i64::from(*lhs) == *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs == i64::from(*rhs))),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Boolean(*lhs == *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Boolean((*lhs as f64) == *rhs)),
+
             (_, _) => Err(Error::new(format!("Cannot compare {} and {} with =", self, other))),
         }
     }
@@ -229,6 +280,12 @@ impl Value {
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs != *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Boolean(*lhs as f64 != *rhs)),
 
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Boolean(lhs != rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Boolean(i64::from(*lhs) != *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs != i64::from(*rhs))),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Boolean(*lhs != *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Boolean((*lhs as f64) != *rhs)),
+
             (_, _) => Err(Error::new(format!("Cannot compare {} and {} with <>", self, other))),
         }
     }
@@ -243,6 +300,12 @@ impl Value {
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs < *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Boolean((*lhs as f64) < *rhs)),
 
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Boolean(lhs < rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Boolean(i64::from(*lhs) < *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs < i64::from(*rhs))),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Boolean(*lhs < *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Boolean((*lhs as f64) < *rhs)),
+
             (_, _) => Err(Error::new(format!("Cannot compare {} and {} with <", self, other))),
         }
     }
@@ -257,6 +320,12 @@ impl Value {
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs <= *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Boolean(*lhs as f64 <= *rhs)),
 
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Boolean(lhs <= rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Boolean(i64::from(*lhs) <= *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs <= i64::from(*rhs))),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Boolean(*lhs <= *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Boolean((*lhs as f64) <= *rhs)),
+
             (_, _) => Err(Error::new(format!("Cannot compare {} and {} with <=", self, other))),
         }
     }
@@ -271,6 +340,12 @@ impl Value {
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs > *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Boolean(*lhs as f64 > *rhs)),
 
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Boolean(lhs > rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Boolean(i64::from(*lhs) > *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs > i64::from(*rhs))),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Boolean(*lhs > *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Boolean((*lhs as f64) > *rhs)),
+
             (_, _) => Err(Error::new(format!("Cannot compare {} and {} with >", self, other))),
         }
     }
@@ -285,6 +360,12 @@ impl Value {
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs >= *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Boolean(*lhs as f64 >= *rhs)),
 
+            (Value::Long(lhs), Value::Long(rhs)) => Ok(Value::Boolean(lhs >= rhs)),
+            (Value::Integer(lhs), Value::Long(rhs)) => Ok(Value::Boolean(i64::from(*lhs) >= *rhs)),
+            (Value::Long(lhs), Value::Integer(rhs)) => Ok(Value::Boolean(*lhs >= i64::from(*rhs))),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Boolean(*lhs >= *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Boolean((*lhs as f64) >= *rhs)),
+
             (_, _) => Err(Error::new(format!("Cannot compare {} and {} with >=", self, other))),
         }
     }
@@ -295,12 +376,20 @@ impl Value {
             (Value::Double(lhs), Value::Double(rhs)) => Ok(Value::Double(lhs + rhs)),
             (Value::Integer(lhs), Value::Integer(rhs)) => match lhs.checked_add(*rhs) {
                 Some(i) => Ok(Value::Integer(i)),
+                None => Ok(Value::Long(i64::from(*lhs) + i64::from(*rhs))),
+            },
+            (Value::Long(lhs), Value::Long(rhs)) => match lhs.checked_add(*rhs) {
+                Some(i) => Ok(Value::Long(i)),
                 None => Ok(Value::Double(*lhs as f64 + *rhs as f64)),
             },
             (Value::Text(lhs), Value::Text(rhs)) => Ok(Value::Text(lhs.to_owned() + rhs)),
 
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Double(lhs + *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 + rhs)),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Double(lhs + *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 + rhs)),
+            (Value::Integer(lhs), Value::Long(_)) => Value::Long(i64::from(*lhs)).add(other),
+            (Value::Long(_), Value::Integer(rhs)) => self.add(&Value::Long(i64::from(*rhs))),
 
             (_, _) => Err(Error::new(format!("Cannot add {} and {}", self, other))),
         }
@@ -312,11 +401,19 @@ impl Value {
             (Value::Double(lhs), Value::Double(rhs)) => Ok(Value::Double(lhs - rhs)),
             (Value::Integer(lhs), Value::Integer(rhs)) => match lhs.checked_sub(*rhs) {
                 Some(i) => Ok(Value::Integer(i)),
+                None => Ok(Value::Long(i64::from(*lhs) - i64::from(*rhs))),
+            },
+            (Value::Long(lhs), Value::Long(rhs)) => match lhs.checked_sub(*rhs) {
+                Some(i) => Ok(Value::Long(i)),
                 None => Ok(Value::Double(*lhs as f64 - *rhs as f64)),
             },
 
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Double(lhs - *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 - rhs)),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Double(lhs - *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 - rhs)),
+            (Value::Integer(lhs), Value::Long(_)) => Value::Long(i64::from(*lhs)).sub(other),
+            (Value::Long(_), Value::Integer(rhs)) => self.sub(&Value::Long(i64::from(*rhs))),
 
             (_, _) => Err(Error::new(format!("Cannot subtract {} from {}", other, self))),
         }
@@ -328,11 +425,19 @@ impl Value {
             (Value::Double(lhs), Value::Double(rhs)) => Ok(Value::Double(lhs * rhs)),
             (Value::Integer(lhs), Value::Integer(rhs)) => match lhs.checked_mul(*rhs) {
                 Some(i) => Ok(Value::Integer(i)),
+                None => Ok(Value::Long(i64::from(*lhs) * i64::from(*rhs))),
+            },
+            (Value::Long(lhs), Value::Long(rhs)) => match lhs.checked_mul(*rhs) {
+                Some(i) => Ok(Value::Long(i)),
                 None => Ok(Value::Double(*lhs as f64 * *rhs as f64)),
             },
 
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Double(lhs * *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 * rhs)),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Double(lhs * *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 * rhs)),
+            (Value::Integer(lhs), Value::Long(_)) => Value::Long(i64::from(*lhs)).mul(other),
+            (Value::Long(_), Value::Integer(rhs)) => self.mul(&Value::Long(i64::from(*rhs))),
 
             (_, _) => Err(Error::new(format!("Cannot multiply {} by {}", self, other))),
         }
@@ -348,12 +453,25 @@ impl Value {
                 }
                 match lhs.checked_div(*rhs) {
                     Some(i) => Ok(Value::Integer(i)),
+                    None => Ok(Value::Long(i64::from(*lhs) / i64::from(*rhs))),
+                }
+            }
+            (Value::Long(lhs), Value::Long(rhs)) => {
+                if rhs == &0 {
+                    return Err(Error::new("Division by zero"));
+                }
+                match lhs.checked_div(*rhs) {
+                    Some(i) => Ok(Value::Long(i)),
                     None => Ok(Value::Double(*lhs as f64 / *rhs as f64)),
                 }
             }
 
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Double(lhs / *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 / rhs)),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Double(lhs / *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 / rhs)),
+            (Value::Integer(lhs), Value::Long(_)) => Value::Long(i64::from(*lhs)).div(other),
+            (Value::Long(_), Value::Integer(rhs)) => self.div(&Value::Long(i64::from(*rhs))),
 
             (_, _) => Err(Error::new(format!("Cannot divide {} by {}", self, other))),
         }
@@ -369,12 +487,25 @@ impl Value {
                 }
                 match lhs.checked_rem(*rhs) {
                     Some(i) => Ok(Value::Integer(i)),
+                    None => Ok(Value::Long(i64::from(*lhs) % i64::from(*rhs))),
+                }
+            }
+            (Value::Long(lhs), Value::Long(rhs)) => {
+                if rhs == &0 {
+                    return Err(Error::new("Modulo by zero"));
+                }
+                match lhs.checked_rem(*rhs) {
+                    Some(i) => Ok(Value::Long(i)),
                     None => Ok(Value::Double(*lhs as f64 % *rhs as f64)),
                 }
             }
 
             (Value::Double(lhs), Value::Integer(rhs)) => Ok(Value::Double(lhs % *rhs as f64)),
             (Value::Integer(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 % rhs)),
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Double(lhs % *rhs as f64)),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Double(*lhs as f64 % rhs)),
+            (Value::Integer(lhs), Value::Long(_)) => Value::Long(i64::from(*lhs)).modulo(other),
+            (Value::Long(_), Value::Integer(rhs)) => self.modulo(&Value::Long(i64::from(*rhs))),
 
             (_, _) => Err(Error::new(format!("Cannot modulo {} by {}", self, other))),
         }
@@ -393,6 +524,18 @@ impl Value {
                 };
                 match lhs.checked_pow(exp) {
                     Some(i) => Ok(Value::Integer(i)),
+                    None => Value::Long(i64::from(*lhs)).pow(other),
+                }
+            }
+            (Value::Long(lhs), Value::Long(rhs)) => {
+                let exp = match u32::try_from(*rhs) {
+                    Ok(exp) => exp,
+                    Err(_) => {
+                        return Ok(Value::Double((*lhs as f64).powf(*rhs as f64)));
+                    }
+                };
+                match lhs.checked_pow(exp) {
+                    Some(i) => Ok(Value::Long(i)),
                     None => Ok(Value::Double((*lhs as f64).powf(*rhs as f64))),
                 }
             }
@@ -401,6 +544,10 @@ impl Value {
             (Value::Integer(lhs), Value::Double(rhs)) => {
                 Ok(Value::Double((*lhs as f64).powf(*rhs)))
             }
+            (Value::Double(lhs), Value::Long(rhs)) => Ok(Value::Double(lhs.powf(*rhs as f64))),
+            (Value::Long(lhs), Value::Double(rhs)) => Ok(Value::Double((*lhs as f64).powf(*rhs))),
+            (Value::Integer(lhs), Value::Long(_)) => Value::Long(i64::from(*lhs)).pow(other),
+            (Value::Long(_), Value::Integer(rhs)) => self.pow(&Value::Long(i64::from(*rhs))),
 
             (_, _) => Err(Error::new(format!("Cannot raise {} to the power of {}", self, other))),
         }
@@ -412,6 +559,10 @@ impl Value {
             Value::Double(d) => Ok(Value::Double(-d)),
             Value::Integer(i) => match i.checked_neg() {
                 Some(i) => Ok(Value::Integer(i)),
+                None => Ok(Value::Long(-i64::from(*i))),
+            },
+            Value::Long(i) => match i.checked_neg() {
+                Some(i) => Ok(Value::Long(i)),
                 None => Ok(Value::Double(-(*i as f64))),
             },
             _ => Err(Error::new(format!("Cannot negate {}", self))),
@@ -507,6 +658,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_value_parse_as_long() {
+        assert_eq!(Long(10), Value::parse_as(VarType::Long, "10").unwrap());
+        assert_eq!(Long(-3000000000), Value::parse_as(VarType::Long, "-3000000000").unwrap());
+
+        assert_eq!(
+            "Invalid long integer literal 3.0",
+            format!("{}", Value::parse_as(VarType::Long, "3.0").unwrap_err())
+        );
+        assert_eq!(
+            "Invalid long integer literal 99999999999999999999",
+            format!("{}", Value::parse_as(VarType::Long, "99999999999999999999").unwrap_err())
+        );
+    }
+
     #[test]
     fn test_value_parse_as_text() {
         assert_eq!(Text("".to_owned()), Value::parse_as(VarType::Text, "").unwrap());
@@ -521,19 +687,42 @@ mod tests {
         assert_eq!(9, Double(8.5).as_i32().unwrap());
         assert_eq!(9, Double(8.6).as_i32().unwrap());
         assert_eq!(7, Integer(7).as_i32().unwrap());
+        assert_eq!(-7, Long(-7).as_i32().unwrap());
 
         Double(f64::NAN).as_i32().unwrap_err();
         Double(i32::MAX as f64 + 1.0).as_i32().unwrap_err();
         Double(i32::MIN as f64 - 1.0).as_i32().unwrap_err();
+        assert_eq!(
+            "Cannot cast 2147483648 to integer due to overflow",
+            format!("{}", Long(i32::MAX as i64 + 1).as_i32().unwrap_err())
+        );
 
         Boolean(false).as_i32().unwrap_err();
         Text("a".to_owned()).as_i32().unwrap_err();
     }
 
+    #[test]
+    fn test_value_as_i64() {
+        assert_eq!(9, Double(8.5).as_i64().unwrap());
+        assert_eq!(7, Integer(7).as_i64().unwrap());
+        assert_eq!(5000000000, Long(5000000000).as_i64().unwrap());
+        assert_eq!(5000000000, Double(5000000000.2).as_i64().unwrap());
+
+        Double(f64::NAN).as_i64().unwrap_err();
+        assert_eq!(
+            "Cannot cast 9223372036854776000 to long due to overflow",
+            format!("{}", Double(i64::MAX as f64).as_i64().unwrap_err())
+        );
+
+        Boolean(false).as_i64().unwrap_err();
+        Text("a".to_owned()).as_i64().unwrap_err();
+    }
+
     #[test]
     fn test_value_as_f64() {
         assert_eq!(8.4, Double(8.4).as_f64().unwrap());
         assert_eq!(7.0, Integer(7).as_f64().unwrap());
+        assert_eq!(5000000000.0, Long(5000000000).as_f64().unwrap());
 
         Boolean(false).as_f64().unwrap_err();
         Text("a".to_owned()).as_f64().unwrap_err();
@@ -548,21 +737,25 @@ mod tests {
             VarType::Boolean,
             VarType::Double,
             VarType::Integer,
+            VarType::Long,
             VarType::Text,
             VarType::Void,
         ];
         for target in all_types {
             assert_eq!(Boolean(true), Boolean(true).maybe_cast(target).unwrap());
-            if target != VarType::Integer {
+            if target != VarType::Integer && target != VarType::Long {
                 assert_eq!(Double(3.8), Double(3.8).maybe_cast(target).unwrap());
                 match Double(f64::NAN).maybe_cast(target).unwrap() {
                     Double(d) => assert!(d.is_nan()),
                     _ => panic!(),
                 }
             }
-            if target != VarType::Double {
+            if target != VarType::Double && target != VarType::Long {
                 assert_eq!(Integer(3), Integer(3).maybe_cast(target).unwrap());
             }
+            if target != VarType::Double && target != VarType::Integer {
+                assert_eq!(Long(3), Long(3).maybe_cast(target).unwrap());
+            }
             assert_eq!(Text("a".to_owned()), Text("a".to_owned()).maybe_cast(target).unwrap());
         }
 
@@ -576,6 +769,13 @@ mod tests {
         Double(i32::MAX as f64 + 1.0).maybe_cast(VarType::Integer).unwrap_err();
         assert_eq!(Double(i32::MIN as f64), Integer(i32::MIN).maybe_cast(VarType::Double).unwrap());
         Double(i32::MIN as f64 - 1.0).maybe_cast(VarType::Integer).unwrap_err();
+
+        assert_eq!(Long(7), Integer(7).maybe_cast(VarType::Long).unwrap());
+        assert_eq!(Long(9), Double(8.5).maybe_cast(VarType::Long).unwrap());
+        assert_eq!(Integer(7), Long(7).maybe_cast(VarType::Integer).unwrap());
+        assert_eq!(Double(7.0), Long(7).maybe_cast(VarType::Double).unwrap());
+        Long(i32::MAX as i64 + 1).maybe_cast(VarType::Integer).unwrap_err();
+        Double(f64::NAN).maybe_cast(VarType::Long).unwrap_err();
     }
 
     #[test]
@@ -589,6 +789,9 @@ mod tests {
         let v = Integer(-9);
         assert_eq!(v.clone(), Value::parse_as(VarType::Integer, v.to_text()).unwrap());
 
+        let v = Long(-9000000000);
+        assert_eq!(v.clone(), Value::parse_as(VarType::Long, v.to_text()).unwrap());
+
         let v = Text("Some long text".to_owned());
         assert_eq!(v.clone(), Value::parse_as(VarType::Text, v.to_text()).unwrap());
     }
@@ -604,6 +807,9 @@ mod tests {
         let v = Integer(9);
         assert_eq!(v, Value::parse_as(VarType::Integer, format!("{}", v)).unwrap());
 
+        let v = Long(9000000000);
+        assert_eq!(v, Value::parse_as(VarType::Long, format!("{}", v)).unwrap());
+
         // The string parsing and printing is not symmetrical on purpose given that user input
         // does not provide strings as quoted but we want to show them as quoted for clarity.
         let v = Text("Some long text".to_owned());
@@ -663,6 +869,8 @@ mod tests {
         assert_eq!(Integer(5), Integer(7).and(&Integer(5)).unwrap());
         assert_eq!(Integer(0), Integer(2).and(&Integer(4)).unwrap());
         assert_eq!(Integer(1234), Integer(-1).and(&Integer(1234)).unwrap());
+        assert_eq!(Long(5), Long(7).and(&Integer(5)).unwrap());
+        assert_eq!(Long(1 << 40), Long(-1).and(&Long(1 << 40)).unwrap());
 
         assert_eq!(
             "Cannot AND 3.0 and 4.0",
@@ -675,6 +883,8 @@ mod tests {
         assert_eq!(Integer(7), Integer(7).or(&Integer(5)).unwrap());
         assert_eq!(Integer(6), Integer(2).or(&Integer(4)).unwrap());
         assert_eq!(Integer(-1), Integer(-1).or(&Integer(1234)).unwrap());
+        assert_eq!(Long(7), Integer(7).or(&Long(5)).unwrap());
+        assert_eq!(Long((1 << 40) | 1), Long(1 << 40).or(&Long(1)).unwrap());
 
         assert_eq!(
             "Cannot OR 3.0 and 4.0",
@@ -687,6 +897,7 @@ mod tests {
         assert_eq!(Integer(2), Integer(7).xor(&Integer(5)).unwrap());
         assert_eq!(Integer(6), Integer(2).xor(&Integer(4)).unwrap());
         assert_eq!(Integer(-1235), Integer(-1).xor(&Integer(1234)).unwrap());
+        assert_eq!(Long(2), Long(7).xor(&Long(5)).unwrap());
 
         assert_eq!(
             "Cannot XOR 3.0 and 4.0",
@@ -697,6 +908,7 @@ mod tests {
     #[test]
     fn test_value_bitwise_not() {
         assert_eq!(Integer(-1), Integer(0).not().unwrap());
+        assert_eq!(Long(-1), Long(0).not().unwrap());
 
         assert_eq!("Cannot apply NOT to 3.0", format!("{}", Double(3.0).not().unwrap_err()));
     }
@@ -719,6 +931,11 @@ mod tests {
         assert_eq!(Integer(0), Integer(1).shl(&Integer(32)).unwrap());
         assert_eq!(Integer(0), Integer(1).shl(&Integer(64)).unwrap());
 
+        assert_eq!(Long(1 << 40), Long(1).shl(&Integer(40)).unwrap());
+        assert_eq!(Long(12), Long(3).shl(&Long(2)).unwrap());
+        assert_eq!(Integer(12), Integer(3).shl(&Long(2)).unwrap());
+        assert_eq!(Long(0), Long(1).shl(&Integer(64)).unwrap());
+
         assert_eq!(
             "Cannot apply << to non-integer FALSE",
             format!("{}", Boolean(false).shl(&Boolean(true)).unwrap_err())
@@ -776,6 +993,11 @@ mod tests {
         assert_eq!(Integer(-1), Integer(0x80000000u32 as i32).shr(&Integer(32)).unwrap());
         assert_eq!(Integer(-1), Integer(0x80000000u32 as i32).shr(&Integer(64)).unwrap());
 
+        assert_eq!(Long(1), Long(1 << 40).shr(&Integer(40)).unwrap());
+        assert_eq!(Long(-2), Long(-8).shr(&Long(2)).unwrap());
+        assert_eq!(Long(-1), Long(-8).shr(&Integer(64)).unwrap());
+        assert_eq!(Long(0), Long(8).shr(&Integer(64)).unwrap());
+
         assert_eq!(
             "Cannot apply >> to non-integer FALSE",
             format!("{}", Boolean(false).shr(&Boolean(true)).unwrap_err())
@@ -827,6 +1049,12 @@ mod tests {
         assert_eq!(Boolean(true), Integer(4).eq(&Double(4.0)).unwrap());
         assert_eq!(Boolean(false), Integer(1).eq(&Double(1.2)).unwrap());
 
+        assert_eq!(Boolean(true), Long(5000000000).eq(&Long(5000000000)).unwrap());
+        assert_eq!(Boolean(true), Long(4).eq(&Integer(4)).unwrap());
+        assert_eq!(Boolean(false), Integer(4).eq(&Long(5)).unwrap());
+        assert_eq!(Boolean(true), Long(4).eq(&Double(4.0)).unwrap());
+        assert_eq!(Boolean(false), Double(1.2).eq(&Long(1)).unwrap());
+
         assert_eq!(Boolean(true), Text("a".to_owned()).eq(&Text("a".to_owned())).unwrap());
         assert_eq!(Boolean(false), Text("b".to_owned()).eq(&Text("c".to_owned())).unwrap());
         assert_eq!(
@@ -881,6 +1109,12 @@ mod tests {
         assert_eq!(Boolean(false), Integer(4).lt(&Double(4.0)).unwrap());
         assert_eq!(Boolean(true), Integer(4).lt(&Double(4.1)).unwrap());
 
+        assert_eq!(Boolean(true), Long(4).lt(&Long(5000000000)).unwrap());
+        assert_eq!(Boolean(true), Integer(4).lt(&Long(5)).unwrap());
+        assert_eq!(Boolean(false), Long(5000000000).lt(&Integer(5)).unwrap());
+        assert_eq!(Boolean(true), Long(4).lt(&Double(4.5)).unwrap());
+        assert_eq!(Boolean(false), Double(4.5).lt(&Long(4)).unwrap());
+
         assert_eq!(Boolean(false), Text("a".to_owned()).lt(&Text("a".to_owned())).unwrap());
         assert_eq!(Boolean(true), Text("a".to_owned()).lt(&Text("c".to_owned())).unwrap());
         assert_eq!(
@@ -989,11 +1223,20 @@ mod tests {
         assert_eq!(Integer(5), Integer(2).add(&Integer(3)).unwrap());
         assert_eq!(Integer(std::i32::MAX), Integer(std::i32::MAX).add(&Integer(0)).unwrap());
         assert_eq!(
-            Double(std::i32::MAX as f64 + 1.0),
+            Long(i64::from(std::i32::MAX) + 1),
             Integer(std::i32::MAX).add(&Integer(1)).unwrap()
         );
         assert_eq!(Double(9.3), Integer(4).add(&Double(5.3)).unwrap());
 
+        assert_eq!(Long(5), Long(2).add(&Long(3)).unwrap());
+        assert_eq!(Long(5), Integer(2).add(&Long(3)).unwrap());
+        assert_eq!(Long(5), Long(2).add(&Integer(3)).unwrap());
+        assert_eq!(Double(5.5), Long(2).add(&Double(3.5)).unwrap());
+        assert_eq!(
+            Double(std::i64::MAX as f64 + 1.0),
+            Long(std::i64::MAX).add(&Integer(1)).unwrap()
+        );
+
         assert_eq!(Text("ab".to_owned()), Text("a".to_owned()).add(&Text("b".to_owned())).unwrap());
         assert_eq!(
             "Cannot add \"\" and FALSE",
@@ -1014,11 +1257,16 @@ mod tests {
         assert_eq!(Integer(-1), Integer(2).sub(&Integer(3)).unwrap());
         assert_eq!(Integer(std::i32::MIN), Integer(std::i32::MIN).sub(&Integer(0)).unwrap());
         assert_eq!(
-            Double(std::i32::MIN as f64 - 1.0),
+            Long(i64::from(std::i32::MIN) - 1),
             Integer(std::i32::MIN).sub(&Integer(1)).unwrap()
         );
         assert_eq!(Double(-1.5), Integer(4).sub(&Double(5.5)).unwrap());
 
+        assert_eq!(Long(-1), Long(2).sub(&Long(3)).unwrap());
+        assert_eq!(Long(-1), Integer(2).sub(&Long(3)).unwrap());
+        assert_eq!(Double(-1.5), Long(2).sub(&Double(3.5)).unwrap());
+        assert_eq!(Double(std::i64::MIN as f64 - 1.0), Long(std::i64::MIN).sub(&Long(1)).unwrap());
+
         assert_eq!(
             "Cannot subtract \"a\" from \"ab\"",
             format!("{}", Text("ab".to_owned()).sub(&Text("a".to_owned())).unwrap_err())
@@ -1038,11 +1286,16 @@ mod tests {
         assert_eq!(Integer(6), Integer(2).mul(&Integer(3)).unwrap());
         assert_eq!(Integer(std::i32::MAX), Integer(std::i32::MAX).mul(&Integer(1)).unwrap());
         assert_eq!(
-            Double(std::i32::MAX as f64 * 2.0),
+            Long(i64::from(std::i32::MAX) * 2),
             Integer(std::i32::MAX).mul(&Integer(2)).unwrap()
         );
         assert_eq!(Double(20.8), Integer(4).mul(&Double(5.2)).unwrap());
 
+        assert_eq!(Long(6), Long(2).mul(&Long(3)).unwrap());
+        assert_eq!(Long(6), Long(2).mul(&Integer(3)).unwrap());
+        assert_eq!(Double(5.0), Double(2.5).mul(&Long(2)).unwrap());
+        assert_eq!(Double(std::i64::MAX as f64 * 2.0), Long(std::i64::MAX).mul(&Long(2)).unwrap());
+
         assert_eq!(
             "Cannot multiply \"\" by \"a\"",
             format!("{}", Text("".to_owned()).mul(&Text("a".to_owned())).unwrap_err())
@@ -1065,11 +1318,16 @@ mod tests {
         assert_eq!(Integer(std::i32::MIN), Integer(std::i32::MIN).div(&Integer(1)).unwrap());
         assert_eq!("Division by zero", format!("{}", Integer(4).div(&Integer(0)).unwrap_err()));
         assert_eq!(
-            Double(std::i32::MIN as f64 / -1.0),
+            Long(-i64::from(std::i32::MIN)),
             Integer(std::i32::MIN).div(&Integer(-1)).unwrap()
         );
         assert_eq!(Double(4.0), Integer(10).div(&Double(2.5)).unwrap());
 
+        assert_eq!(Long(6), Long(20).div(&Long(3)).unwrap());
+        assert_eq!(Long(6), Integer(20).div(&Long(3)).unwrap());
+        assert_eq!(Double(4.0), Long(10).div(&Double(2.5)).unwrap());
+        assert_eq!("Division by zero", format!("{}", Long(4).div(&Integer(0)).unwrap_err()));
+
         assert_eq!(
             "Cannot divide \"\" by \"a\"",
             format!("{}", Text("".to_owned()).div(&Text("a".to_owned())).unwrap_err())
@@ -1093,12 +1351,13 @@ mod tests {
         assert_eq!(Integer(0), Integer(10).modulo(&Integer(5)).unwrap());
         assert_eq!(Integer(2), Integer(20).modulo(&Integer(3)).unwrap());
         assert_eq!("Modulo by zero", format!("{}", Integer(4).modulo(&Integer(0)).unwrap_err()));
-        assert_eq!(
-            Double(std::i32::MIN as f64 % -1.0),
-            Integer(std::i32::MIN).modulo(&Integer(-1)).unwrap()
-        );
+        assert_eq!(Long(0), Integer(std::i32::MIN).modulo(&Integer(-1)).unwrap());
         assert_eq!(Double(10.0 % 3.0), Integer(10).modulo(&Double(3.0)).unwrap());
 
+        assert_eq!(Long(2), Long(20).modulo(&Long(3)).unwrap());
+        assert_eq!(Long(2), Long(20).modulo(&Integer(3)).unwrap());
+        assert_eq!("Modulo by zero", format!("{}", Integer(4).modulo(&Long(0)).unwrap_err()));
+
         assert_eq!(
             "Cannot modulo \"\" by \"a\"",
             format!("{}", Text("".to_owned()).modulo(&Text("a".to_owned())).unwrap_err())
@@ -1120,12 +1379,21 @@ mod tests {
         assert_eq!(Integer(9), Integer(3).pow(&Integer(2)).unwrap());
         assert_eq!(Integer(std::i32::MAX), Integer(std::i32::MAX).pow(&Integer(1)).unwrap());
         assert_eq!(
-            Double((std::i32::MAX as f64).powf(2.0)),
+            Long(i64::from(std::i32::MAX).pow(2)),
             Integer(std::i32::MAX).pow(&Integer(2)).unwrap()
         );
+        assert_eq!(
+            Double((std::i32::MAX as f64).powf(3.0)),
+            Integer(std::i32::MAX).pow(&Integer(3)).unwrap()
+        );
         assert_eq!(Double(1f64.powf(-3.0)), Integer(1).pow(&Integer(-3)).unwrap());
         assert_eq!(Double(1024.0), Integer(4).pow(&Double(5.0)).unwrap());
 
+        assert_eq!(Long(1 << 40), Long(2).pow(&Long(40)).unwrap());
+        assert_eq!(Long(1 << 40), Integer(2).pow(&Long(40)).unwrap());
+        assert_eq!(Double(0.25), Long(2).pow(&Integer(-2)).unwrap());
+        assert_eq!(Double(1024.0), Long(4).pow(&Double(5.0)).unwrap());
+
         assert_eq!(
             "Cannot raise \"\" to the power of \"a\"",
             format!("{}", Text("".to_owned()).pow(&Text("a".to_owned())).unwrap_err())
@@ -1141,7 +1409,10 @@ mod tests {
 
         assert_eq!(Integer(-6), Integer(6).neg().unwrap());
         assert_eq!(Integer(5), Integer(-5).neg().unwrap());
-        assert_eq!(Double(-(std::i32::MIN as f64)), Integer(std::i32::MIN).neg().unwrap());
+        assert_eq!(Long(-i64::from(std::i32::MIN)), Integer(std::i32::MIN).neg().unwrap());
+
+        assert_eq!(Long(-6), Long(6).neg().unwrap());
+        assert_eq!(Double(-(std::i64::MIN as f64)), Long(std::i64::MIN).neg().unwrap());
 
         assert_eq!("Cannot negate \"\"", format!("{}", Text("".to_owned()).neg().unwrap_err()));
     }
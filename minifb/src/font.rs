@@ -0,0 +1,150 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Font rasterization helpers.
+
+use endbasic_std::console::SizeInPixels;
+use fontdue::{Font, FontSettings, Metrics};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+
+/// Font to use in the console.
+const DEFAULT_FONT_BYTES: &[u8] = include_bytes!("IBMPlexMono-Regular-6.0.0.ttf");
+
+/// A glyph rasterized as a coverage bitmap, along with its placement metrics.
+pub(crate) struct Glyph {
+    /// Placement and size of the bitmap.
+    pub(crate) metrics: Metrics,
+
+    /// Coverage of every pixel in the bitmap in row-major order, from 0 (transparent) to 255
+    /// (opaque).
+    pub(crate) bitmap: Vec<u8>,
+}
+
+/// Wrapper around a monospaced font rasterized at a fixed size.
+pub(crate) struct MonospacedFont {
+    /// The font face.
+    font: Font,
+
+    /// Size of the font in pixels.
+    size: f32,
+
+    /// Size of every character cell.
+    pub(crate) glyph_size: SizeInPixels,
+
+    /// Distance from the top of a character cell to the baseline.
+    pub(crate) ascent: i32,
+
+    /// Glyphs rasterized so far.  Cached to avoid rasterizing the same characters over and over.
+    cache: HashMap<char, Glyph>,
+}
+
+impl MonospacedFont {
+    /// Loads the default font at `size` pixels.
+    pub(crate) fn load(size: u16) -> io::Result<Self> {
+        let font = Font::from_bytes(DEFAULT_FONT_BYTES, FontSettings::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Self::with_font(font, size)
+    }
+
+    /// Creates a new instance of this same font face at `size` pixels.
+    pub(crate) fn resized(&self, size: u16) -> io::Result<Self> {
+        Self::with_font(self.font.clone(), size)
+    }
+
+    /// Computes the metrics of `font` at `size` pixels.
+    fn with_font(font: Font, size: u16) -> io::Result<Self> {
+        if size == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid font size 0"));
+        }
+        let size = f32::from(size);
+
+        let line = match font.horizontal_line_metrics(size) {
+            Some(line) => line,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Font lacks horizontal metrics; is it valid?",
+                ))
+            }
+        };
+        let width = font.metrics('A', size).advance_width.ceil();
+        let height = (line.ascent - line.descent).ceil();
+
+        let glyph_size = match (u16::try_from(width as i64), u16::try_from(height as i64)) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => SizeInPixels { width, height },
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid glyph size {}x{}", width, height),
+                ))
+            }
+        };
+
+        Ok(Self {
+            font,
+            size,
+            glyph_size,
+            ascent: line.ascent.round() as i32,
+            cache: HashMap::new(),
+        })
+    }
+
+    /// Returns the rasterized glyph for `ch`.
+    pub(crate) fn glyph(&mut self, ch: char) -> &Glyph {
+        let font = &self.font;
+        let size = self.size;
+        self.cache.entry(ch).or_insert_with(|| {
+            let (metrics, bitmap) = font.rasterize(ch, size);
+            Glyph { metrics, bitmap }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monospaced_font_metrics() {
+        let font = MonospacedFont::load(16).unwrap();
+        assert!(font.glyph_size.width > 0);
+        assert!(font.glyph_size.width < font.glyph_size.height);
+        assert!(font.ascent > 0);
+        assert!(font.ascent <= i32::from(font.glyph_size.height));
+
+        let bigger = font.resized(32).unwrap();
+        assert!(bigger.glyph_size.width > font.glyph_size.width);
+        assert!(bigger.glyph_size.height > font.glyph_size.height);
+    }
+
+    #[test]
+    fn test_monospaced_font_invalid_size() {
+        let e = MonospacedFont::load(0).err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidInput, e.kind());
+    }
+
+    #[test]
+    fn test_monospaced_font_glyph() {
+        let mut font = MonospacedFont::load(16).unwrap();
+        let glyph = font.glyph('A');
+        assert!(glyph.metrics.width > 0);
+        assert_eq!(glyph.metrics.width * glyph.metrics.height, glyph.bitmap.len());
+
+        let glyph = font.glyph(' ');
+        assert!(glyph.bitmap.iter().all(|c| *c == 0));
+    }
+}
@@ -0,0 +1,266 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Keyboard input for the minifb console.
+
+use crate::window::SharedWindow;
+use async_trait::async_trait;
+use endbasic_std::console::{InputOps, Key, KeyModifiers};
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+/// Time to wait between checks for new keys while blocked waiting for one.
+const READ_KEY_DELAY_MS: u64 = 10;
+
+/// Converts a minifb `key` press to our own `Key` representation, or none if the key has no
+/// meaning for us.
+///
+/// `ctrl` indicates whether the Control key was held down at the time of the press.  Printable
+/// characters are not handled here because they are reported separately as text input.
+pub(crate) fn parse_key(key: minifb::Key, ctrl: bool) -> Option<Key> {
+    use minifb::Key as K;
+
+    if ctrl {
+        match key {
+            K::A => return Some(Key::Home),
+            K::B => return Some(Key::ArrowLeft),
+            K::C => return Some(Key::Interrupt),
+            K::D => return Some(Key::Eof),
+            K::E => return Some(Key::End),
+            K::J | K::M => return Some(Key::NewLine),
+            K::N => return Some(Key::ArrowDown),
+            K::P => return Some(Key::ArrowUp),
+            key if (K::A as u8..=K::Z as u8).contains(&(key as u8)) => {
+                // minifb key codes for letters are contiguous and start at A.
+                return Some(Key::Control(char::from(b'a' + (key as u8 - K::A as u8))));
+            }
+            _ => (),
+        }
+    }
+
+    match key {
+        K::Backspace => Some(Key::Backspace),
+        K::End => Some(Key::End),
+        K::Escape => Some(Key::Escape),
+        K::Home => Some(Key::Home),
+        K::Enter | K::NumPadEnter => Some(Key::NewLine),
+        K::Tab => Some(Key::Tab),
+
+        K::Down => Some(Key::ArrowDown),
+        K::Left => Some(Key::ArrowLeft),
+        K::Right => Some(Key::ArrowRight),
+        K::Up => Some(Key::ArrowUp),
+
+        K::PageDown => Some(Key::PageDown),
+        K::PageUp => Some(Key::PageUp),
+
+        _ => None,
+    }
+}
+
+/// Returns the name of the function `key` as used by `ON KEY`, or none if `key` is not a function
+/// key.
+///
+/// Function keys have no `Key` representation so they can only be handled with `ON KEY`.
+pub(crate) fn function_key_name(key: minifb::Key) -> Option<String> {
+    use minifb::Key as K;
+
+    const FUNCTION_KEYS: [K; 12] =
+        [K::F1, K::F2, K::F3, K::F4, K::F5, K::F6, K::F7, K::F8, K::F9, K::F10, K::F11, K::F12];
+
+    FUNCTION_KEYS.iter().position(|k| *k == key).map(|i| format!("F{}", i + 1))
+}
+
+/// Converts our own `Key` representation to the minifb key that produces it, or none if there is
+/// no such key.
+///
+/// Characters are expected to be in lowercase as documented in `Console::is_key_down`.
+pub(crate) fn key_to_minifb(key: &Key) -> Option<minifb::Key> {
+    use minifb::Key as K;
+
+    const DIGITS: [K; 10] =
+        [K::Key0, K::Key1, K::Key2, K::Key3, K::Key4, K::Key5, K::Key6, K::Key7, K::Key8, K::Key9];
+    const LETTERS: [K; 26] = [
+        K::A,
+        K::B,
+        K::C,
+        K::D,
+        K::E,
+        K::F,
+        K::G,
+        K::H,
+        K::I,
+        K::J,
+        K::K,
+        K::L,
+        K::M,
+        K::N,
+        K::O,
+        K::P,
+        K::Q,
+        K::R,
+        K::S,
+        K::T,
+        K::U,
+        K::V,
+        K::W,
+        K::X,
+        K::Y,
+        K::Z,
+    ];
+
+    match key {
+        Key::ArrowDown => Some(K::Down),
+        Key::ArrowLeft => Some(K::Left),
+        Key::ArrowRight => Some(K::Right),
+        Key::ArrowUp => Some(K::Up),
+
+        Key::Backspace => Some(K::Backspace),
+        Key::CarriageReturn | Key::NewLine => Some(K::Enter),
+        Key::End => Some(K::End),
+        Key::Escape => Some(K::Escape),
+        Key::Home => Some(K::Home),
+        Key::PageDown => Some(K::PageDown),
+        Key::PageUp => Some(K::PageUp),
+        Key::Tab => Some(K::Tab),
+
+        Key::Char(ch @ '0'..='9') => Some(DIGITS[usize::from(*ch as u8 - b'0')]),
+        Key::Char(ch @ 'a'..='z') => Some(LETTERS[usize::from(*ch as u8 - b'a')]),
+        Key::Char(' ') => Some(K::Space),
+        Key::Char(',') => Some(K::Comma),
+        Key::Char('.') => Some(K::Period),
+        Key::Char('-') => Some(K::Minus),
+        Key::Char('=') => Some(K::Equal),
+        Key::Char('/') => Some(K::Slash),
+        Key::Char(';') => Some(K::Semicolon),
+        Key::Char('\'') => Some(K::Apostrophe),
+        Key::Char('[') => Some(K::LeftBracket),
+        Key::Char(']') => Some(K::RightBracket),
+        Key::Char('\\') => Some(K::Backslash),
+        Key::Char('`') => Some(K::Backquote),
+
+        _ => None,
+    }
+}
+
+/// Implementation of the console input operations on top of a minifb window.
+pub(crate) struct MinifbInputOps {
+    /// Window from which to read keys.
+    window: Rc<RefCell<SharedWindow>>,
+}
+
+impl MinifbInputOps {
+    /// Creates a new set of input operations that read keys from `window`.
+    pub(crate) fn new(window: Rc<RefCell<SharedWindow>>) -> Self {
+        Self { window }
+    }
+}
+
+#[async_trait(?Send)]
+impl InputOps for MinifbInputOps {
+    async fn poll_key(&mut self) -> io::Result<Option<Key>> {
+        let mut window = self.window.borrow_mut();
+        window.update()?;
+        Ok(window.pop_key())
+    }
+
+    async fn read_key(&mut self) -> io::Result<Key> {
+        loop {
+            {
+                let mut window = self.window.borrow_mut();
+                window.update()?;
+                if let Some(key) = window.pop_key() {
+                    return Ok(key);
+                }
+            }
+            thread::sleep(Duration::from_millis(READ_KEY_DELAY_MS));
+        }
+    }
+
+    fn is_key_down(&mut self, key: &Key) -> io::Result<bool> {
+        let key = match key_to_minifb(key) {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+        Ok(self.window.borrow_mut().keys_down()?.contains(&key))
+    }
+
+    fn key_modifiers(&mut self) -> io::Result<KeyModifiers> {
+        use minifb::Key as K;
+
+        let keys = self.window.borrow_mut().keys_down()?;
+        let any = |candidates: &[K]| keys.iter().any(|key| candidates.contains(key));
+        Ok(KeyModifiers {
+            shift: any(&[K::LeftShift, K::RightShift]),
+            ctrl: any(&[K::LeftCtrl, K::RightCtrl]),
+            alt: any(&[K::LeftAlt, K::RightAlt]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minifb::Key as K;
+
+    #[test]
+    fn test_parse_key_plain() {
+        assert_eq!(Some(Key::Backspace), parse_key(K::Backspace, false));
+        assert_eq!(Some(Key::NewLine), parse_key(K::Enter, false));
+        assert_eq!(Some(Key::NewLine), parse_key(K::NumPadEnter, false));
+        assert_eq!(Some(Key::ArrowUp), parse_key(K::Up, false));
+        assert_eq!(Some(Key::PageDown), parse_key(K::PageDown, false));
+        assert_eq!(None, parse_key(K::A, false));
+        assert_eq!(None, parse_key(K::LeftShift, false));
+    }
+
+    #[test]
+    fn test_parse_key_control() {
+        assert_eq!(Some(Key::Home), parse_key(K::A, true));
+        assert_eq!(Some(Key::Interrupt), parse_key(K::C, true));
+        assert_eq!(Some(Key::Eof), parse_key(K::D, true));
+        assert_eq!(Some(Key::NewLine), parse_key(K::M, true));
+        assert_eq!(Some(Key::Control('g')), parse_key(K::G, true));
+        assert_eq!(Some(Key::Control('z')), parse_key(K::Z, true));
+        assert_eq!(Some(Key::Escape), parse_key(K::Escape, true));
+        assert_eq!(None, parse_key(K::Key1, true));
+    }
+
+    #[test]
+    fn test_function_key_name() {
+        assert_eq!(Some("F1".to_owned()), function_key_name(K::F1));
+        assert_eq!(Some("F12".to_owned()), function_key_name(K::F12));
+        assert_eq!(None, function_key_name(K::F13));
+        assert_eq!(None, function_key_name(K::A));
+    }
+
+    #[test]
+    fn test_key_to_minifb() {
+        assert_eq!(Some(K::Left), key_to_minifb(&Key::ArrowLeft));
+        assert_eq!(Some(K::Enter), key_to_minifb(&Key::NewLine));
+        assert_eq!(Some(K::Escape), key_to_minifb(&Key::Escape));
+        assert_eq!(Some(K::Key0), key_to_minifb(&Key::Char('0')));
+        assert_eq!(Some(K::Key9), key_to_minifb(&Key::Char('9')));
+        assert_eq!(Some(K::A), key_to_minifb(&Key::Char('a')));
+        assert_eq!(Some(K::Z), key_to_minifb(&Key::Char('z')));
+        assert_eq!(Some(K::Space), key_to_minifb(&Key::Char(' ')));
+        assert_eq!(None, key_to_minifb(&Key::Char('A')));
+        assert_eq!(None, key_to_minifb(&Key::Char('!')));
+        assert_eq!(None, key_to_minifb(&Key::Eof));
+    }
+}
@@ -0,0 +1,46 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! minifb-based graphics terminal emulator.
+
+// Keep these in sync with other top-level files.
+#![allow(clippy::await_holding_refcell_ref)]
+#![allow(clippy::collapsible_else_if)]
+#![warn(anonymous_parameters, bad_style, missing_docs)]
+#![warn(unused, unused_extern_crates, unused_import_braces, unused_qualifications)]
+#![warn(unsafe_code)]
+
+use async_channel::Sender;
+use endbasic_core::exec::Signal;
+use endbasic_std::console::{Console, GraphicsConsole};
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+mod font;
+mod input;
+mod raster;
+mod spec;
+mod window;
+
+/// Creates the graphical console based on the given `spec`.
+pub fn setup(spec: &str, signals_tx: Sender<Signal>) -> io::Result<Rc<RefCell<dyn Console>>> {
+    let (size, font_size) = spec::parse_minifb_spec(spec)?;
+    let window = Rc::from(RefCell::from(window::SharedWindow::new(size, signals_tx)?));
+    let raster_ops = raster::MinifbRasterOps::new(window.clone(), size, font_size)?;
+    let input_ops = input::MinifbInputOps::new(window);
+    let console = GraphicsConsole::new(input_ops, raster_ops)?;
+    Ok(Rc::from(RefCell::from(console)))
+}
@@ -0,0 +1,295 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Software rendering for the minifb console.
+
+use crate::font::MonospacedFont;
+use crate::window::SharedWindow;
+use endbasic_std::console::{PixelsXY, RasterInfo, RasterOps, SizeInPixels, RGB};
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+/// Converts an `RGB` color to the 0RGB format that minifb expects.
+fn rgb_to_pixel(rgb: RGB) -> u32 {
+    (u32::from(rgb.0) << 16) | (u32::from(rgb.1) << 8) | u32::from(rgb.2)
+}
+
+/// Converts a `pixel` in the 0RGB format to an `RGB` color.
+fn pixel_to_rgb(pixel: u32) -> RGB {
+    ((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8)
+}
+
+/// Mixes the `fg` color on top of the `bg` color with the given `coverage` of `fg`, where 0 means
+/// that `fg` is fully transparent and 255 that it is fully opaque.
+fn blend(fg: u32, bg: u32, coverage: u8) -> u32 {
+    let coverage = u32::from(coverage);
+    let channel = |shift: u32| {
+        let fg = (fg >> shift) & 0xff;
+        let bg = (bg >> shift) & 0xff;
+        ((fg * coverage + bg * (255 - coverage)) / 255) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
+
+/// In-memory surface in the 0RGB format.
+struct Surface {
+    /// Size of the surface in pixels.
+    size: SizeInPixels,
+
+    /// Contents of the surface in row-major order.
+    pixels: Vec<u32>,
+}
+
+impl Surface {
+    /// Returns the offset of `xy` into the pixels, or none if `xy` lies outside of the surface.
+    fn offset(&self, xy: PixelsXY) -> Option<usize> {
+        if xy.x < 0 || xy.y < 0 || xy.x as u16 >= self.size.width || xy.y as u16 >= self.size.height
+        {
+            return None;
+        }
+        Some(usize::from(xy.y as u16) * usize::from(self.size.width) + usize::from(xy.x as u16))
+    }
+
+    /// Renders `text` starting at `xy` with `font` in the `color`.
+    fn write_text(&mut self, font: &mut MonospacedFont, xy: PixelsXY, text: &str, color: u32) {
+        let cell_width = i32::from(font.glyph_size.width);
+        let ascent = font.ascent;
+        for (i, ch) in text.chars().enumerate() {
+            let glyph = font.glyph(ch);
+            let left = i32::from(xy.x) + i as i32 * cell_width + glyph.metrics.xmin;
+            let top = i32::from(xy.y) + ascent - glyph.metrics.height as i32 - glyph.metrics.ymin;
+            for (j, coverage) in glyph.bitmap.iter().enumerate() {
+                if *coverage == 0 {
+                    continue;
+                }
+                let x = left + (j % glyph.metrics.width) as i32;
+                let y = top + (j / glyph.metrics.width) as i32;
+                if x < i32::from(i16::MIN)
+                    || x > i32::from(i16::MAX)
+                    || y < i32::from(i16::MIN)
+                    || y > i32::from(i16::MAX)
+                {
+                    continue;
+                }
+                if let Some(offset) = self.offset(PixelsXY::new(x as i16, y as i16)) {
+                    self.pixels[offset] = blend(color, self.pixels[offset], *coverage);
+                }
+            }
+        }
+    }
+}
+
+/// Implementation of the console rendering operations on top of a minifb window.
+///
+/// All rendering happens in software on an in-memory surface, which is copied to the window when
+/// presented.
+pub(crate) struct MinifbRasterOps {
+    /// Window in which to present the surface.
+    window: Rc<RefCell<SharedWindow>>,
+
+    /// Font to use for the character grid.
+    font: MonospacedFont,
+
+    /// Font loaded at a size other than that of `font` by the last call to `write_text`, along with
+    /// that size.  Cached to avoid recomputing the font on every call.
+    text_font: Option<(u16, MonospacedFont)>,
+
+    /// The surface on which all rendering happens.
+    surface: Surface,
+
+    /// Color to use in drawing operations.
+    draw_color: u32,
+}
+
+impl MinifbRasterOps {
+    /// Creates a new set of rendering operations for a surface of `size` pixels that is presented
+    /// in `window`, using the default font at `font_size` for the character grid.
+    pub(crate) fn new(
+        window: Rc<RefCell<SharedWindow>>,
+        size: SizeInPixels,
+        font_size: u16,
+    ) -> io::Result<Self> {
+        let font = MonospacedFont::load(font_size)?;
+        let pixels = vec![0; usize::from(size.width) * usize::from(size.height)];
+        Ok(Self { window, font, text_font: None, surface: Surface { size, pixels }, draw_color: 0 })
+    }
+}
+
+impl RasterOps for MinifbRasterOps {
+    fn get_info(&self) -> RasterInfo {
+        RasterInfo { size_pixels: self.surface.size, glyph_size: self.font.glyph_size }
+    }
+
+    fn set_draw_color(&mut self, color: RGB) {
+        self.draw_color = rgb_to_pixel(color);
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        for pixel in self.surface.pixels.iter_mut() {
+            *pixel = self.draw_color;
+        }
+        Ok(())
+    }
+
+    fn draw_pixel(&mut self, xy: PixelsXY) -> io::Result<()> {
+        if let Some(offset) = self.surface.offset(xy) {
+            self.surface.pixels[offset] = self.draw_color;
+        }
+        Ok(())
+    }
+
+    fn draw_rect_filled(&mut self, xy: PixelsXY, size: SizeInPixels) -> io::Result<()> {
+        let x1 = i32::from(xy.x).max(0);
+        let y1 = i32::from(xy.y).max(0);
+        let x2 = (i32::from(xy.x) + i32::from(size.width)).min(i32::from(self.surface.size.width));
+        let y2 =
+            (i32::from(xy.y) + i32::from(size.height)).min(i32::from(self.surface.size.height));
+        let width = i32::from(self.surface.size.width);
+        for y in y1..y2 {
+            let start = (y * width + x1) as usize;
+            let end = (y * width + x2.max(x1)) as usize;
+            for pixel in &mut self.surface.pixels[start..end] {
+                *pixel = self.draw_color;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_text(&mut self, xy: PixelsXY, text: &str, size: Option<u16>) -> io::Result<()> {
+        let font = match size {
+            None => &mut self.font,
+            Some(size) => {
+                let reuse = matches!(&self.text_font, Some((cached, _)) if *cached == size);
+                if !reuse {
+                    self.text_font = Some((size, self.font.resized(size)?));
+                }
+                &mut self.text_font.as_mut().expect("Font must have been loaded above").1
+            }
+        };
+        self.surface.write_text(font, xy, text, self.draw_color);
+        Ok(())
+    }
+
+    fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+        Ok(self.surface.offset(xy).map(|offset| pixel_to_rgb(self.surface.pixels[offset])))
+    }
+
+    fn read_pixels(&self, xy: PixelsXY, size: SizeInPixels) -> io::Result<Vec<RGB>> {
+        let mut pixels = Vec::with_capacity(usize::from(size.width) * usize::from(size.height));
+        for y in 0..size.height {
+            let start = self
+                .surface
+                .offset(PixelsXY::new(xy.x, xy.y + y as i16))
+                .expect("Rectangle must be within the surface");
+            let row = &self.surface.pixels[start..start + usize::from(size.width)];
+            pixels.extend(row.iter().map(|pixel| pixel_to_rgb(*pixel)));
+        }
+        Ok(pixels)
+    }
+
+    fn put_pixels(&mut self, xy: PixelsXY, size: SizeInPixels, pixels: &[RGB]) -> io::Result<()> {
+        let mut rows = pixels.chunks(usize::from(size.width));
+        for y in 0..size.height {
+            let start = self
+                .surface
+                .offset(PixelsXY::new(xy.x, xy.y + y as i16))
+                .expect("Rectangle must be within the surface");
+            let src = rows.next().expect("Pixels must cover the whole rectangle");
+            let dest = &mut self.surface.pixels[start..start + usize::from(size.width)];
+            for (dest, src) in dest.iter_mut().zip(src) {
+                *dest = rgb_to_pixel(*src);
+            }
+        }
+        Ok(())
+    }
+
+    fn move_pixels(&mut self, from: PixelsXY, to: PixelsXY, size: SizeInPixels) -> io::Result<()> {
+        let width = usize::from(self.surface.size.width);
+        let copy_row = |surface: &mut Surface, y: u16| {
+            let src = surface.offset(PixelsXY::new(from.x, from.y + y as i16));
+            let dest = surface.offset(PixelsXY::new(to.x, to.y + y as i16));
+            if let (Some(src), Some(dest)) = (src, dest) {
+                let len =
+                    usize::from(size.width).min(width - src % width).min(width - dest % width);
+                surface.pixels.copy_within(src..src + len, dest);
+            }
+        };
+
+        // Copy rows in the order that avoids overwriting rows that are yet to be copied.
+        if to.y <= from.y {
+            for y in 0..size.height {
+                copy_row(&mut self.surface, y);
+            }
+        } else {
+            for y in (0..size.height).rev() {
+                copy_row(&mut self.surface, y);
+            }
+        }
+        Ok(())
+    }
+
+    fn present_canvas(&mut self) -> io::Result<()> {
+        self.window.borrow_mut().present(&self.surface.pixels)
+    }
+
+    fn present_pixels(&mut self, pixels: &[RGB]) -> io::Result<()> {
+        let frame: Vec<u32> = pixels.iter().map(|rgb| rgb_to_pixel(*rgb)).collect();
+        self.window.borrow_mut().present(&frame)
+    }
+
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        self.window.borrow_mut().set_title(title);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_pixel_conversions() {
+        assert_eq!(0x00123456, rgb_to_pixel((0x12, 0x34, 0x56)));
+        assert_eq!((0x12, 0x34, 0x56), pixel_to_rgb(0x00123456));
+        assert_eq!((0x12, 0x34, 0x56), pixel_to_rgb(0xff123456));
+    }
+
+    #[test]
+    fn test_blend() {
+        assert_eq!(0x00102030, blend(0x00ffffff, 0x00102030, 0));
+        assert_eq!(0x00ffffff, blend(0x00ffffff, 0x00102030, 255));
+        assert_eq!(0x00808080, blend(0x00ffffff, 0x00000000, 128));
+    }
+
+    #[test]
+    fn test_surface_write_text() {
+        let mut font = MonospacedFont::load(16).unwrap();
+        let size =
+            SizeInPixels { width: font.glyph_size.width * 2, height: font.glyph_size.height };
+        let mut surface =
+            Surface { size, pixels: vec![0; usize::from(size.width) * usize::from(size.height)] };
+
+        surface.write_text(&mut font, PixelsXY::new(0, 0), " A", 0x00ffffff);
+
+        let width = usize::from(size.width);
+        let cell = usize::from(font.glyph_size.width);
+        let painted = |range: std::ops::Range<usize>| {
+            surface.pixels.iter().enumerate().any(|(i, p)| range.contains(&(i % width)) && *p != 0)
+        };
+        assert!(!painted(0..cell));
+        assert!(painted(cell..width));
+    }
+}
@@ -0,0 +1,118 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Configuration support for the minifb console.
+
+use endbasic_std::console::SizeInPixels;
+use std::io;
+use std::str::FromStr;
+
+/// Default resolution to use when none is provided.
+const DEFAULT_RESOLUTION: SizeInPixels = SizeInPixels { width: 800, height: 600 };
+
+/// Default font size.
+const DEFAULT_FONT_SIZE: u16 = 16;
+
+/// Wrapper over `str::parse` to return `io::Result` with a custom `error` message.
+fn parse_str<T: FromStr>(text: &str, error: &'static str) -> io::Result<T> {
+    match text.parse::<T>() {
+        Ok(value) => Ok(value),
+        Err(_) => Err(io::Error::new(io::ErrorKind::InvalidInput, error)),
+    }
+}
+
+/// Parses a `resolution` of the form `WIDTHxHEIGHT`.
+fn parse_resolution(resolution: &str) -> io::Result<SizeInPixels> {
+    let resolution: Vec<&str> = resolution.split('x').collect();
+    match resolution.as_slice() {
+        [width, height] => {
+            let width = parse_str(width, "Invalid width in resolution")?;
+            let height = parse_str(height, "Invalid height in resolution")?;
+            if width == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Console width cannot be 0",
+                ));
+            }
+            if height == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Console height cannot be 0",
+                ));
+            }
+            Ok(SizeInPixels { width, height })
+        }
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid resolution format")),
+    }
+}
+
+/// Parses a minifb console specification of the form `RESOLUTION,FONT_SIZE`.
+///
+/// Returns the resolution and the font size.
+pub(crate) fn parse_minifb_spec(params: &str) -> io::Result<(SizeInPixels, u16)> {
+    let invalid_spec =
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid minifb console spec"));
+
+    let mut params = params.split(',');
+    let resolution = match params.next() {
+        Some("") => DEFAULT_RESOLUTION,
+        Some(resolution) => parse_resolution(resolution)?,
+        None => return invalid_spec,
+    };
+    let font_size = match params.next() {
+        Some("") | None => DEFAULT_FONT_SIZE,
+        Some(font_size) => parse_str(font_size, "Invalid font size")?,
+    };
+    if params.next().is_some() {
+        return invalid_spec;
+    }
+
+    Ok((resolution, font_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_minifb_spec_ok() {
+        for (spec, exp_resolution, exp_font_size) in &[
+            ("", DEFAULT_RESOLUTION, DEFAULT_FONT_SIZE),
+            (",", DEFAULT_RESOLUTION, DEFAULT_FONT_SIZE),
+            ("123x45", SizeInPixels { width: 123, height: 45 }, DEFAULT_FONT_SIZE),
+            (",20", DEFAULT_RESOLUTION, 20),
+            ("640x480,12", SizeInPixels { width: 640, height: 480 }, 12),
+        ] {
+            let (resolution, font_size) = parse_minifb_spec(spec).unwrap();
+            assert_eq!(*exp_resolution, resolution, "Bad resolution for spec {}", spec);
+            assert_eq!(*exp_font_size, font_size, "Bad font size for spec {}", spec);
+        }
+    }
+
+    #[test]
+    fn test_parse_minifb_spec_errors() {
+        fn check(exp_error: &str, spec: &str) {
+            assert_eq!(exp_error, format!("{}", parse_minifb_spec(spec).unwrap_err()));
+        }
+        check("Invalid resolution format", "fs");
+        check("Invalid resolution format", "1x2x3");
+        check("Invalid width in resolution", "ax100");
+        check("Invalid height in resolution", "100xb");
+        check("Console width cannot be 0", "0x100");
+        check("Console height cannot be 0", "100x0");
+        check("Invalid font size", ",big");
+        check("Invalid minifb console spec", "800x600,16,extra");
+    }
+}
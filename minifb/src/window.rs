@@ -0,0 +1,199 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Window management for the minifb console.
+
+use crate::input::{function_key_name, parse_key};
+use async_channel::Sender;
+use endbasic_core::exec::Signal;
+use endbasic_std::console::{key_name, Key, SizeInPixels};
+use minifb::{InputCallback, KeyRepeat, Window, WindowOptions};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::rc::Rc;
+
+/// Converts a minifb `Error` to an `io::Error`.
+fn minifb_error_to_io_error(e: minifb::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{}", e))
+}
+
+/// Collector of the characters typed into the window.
+struct TextInput(Rc<RefCell<VecDeque<char>>>);
+
+impl InputCallback for TextInput {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(ch) = char::from_u32(uni_char) {
+            if !ch.is_control() {
+                self.0.borrow_mut().push_back(ch);
+            }
+        }
+    }
+}
+
+/// A minifb window shared by the rendering and input operations of the console.
+///
+/// minifb only processes window events when the contents of the window are updated, so this keeps
+/// a copy of the last presented frame to be able to poll for events at any time and queues the
+/// keys it sees until they are consumed.
+pub(crate) struct SharedWindow {
+    /// The window hosting the console.
+    window: Window,
+
+    /// Size of the window in pixels.
+    size: SizeInPixels,
+
+    /// Last frame presented in the window, in the 0RGB format that minifb expects.
+    frame: Vec<u32>,
+
+    /// Characters typed into the window and not yet processed.
+    text_input: Rc<RefCell<VecDeque<char>>>,
+
+    /// Keys pressed in the window and not yet consumed.
+    keys: VecDeque<Key>,
+
+    /// Whether the window was closed and this has already been reported.
+    closed: bool,
+
+    /// Channel through which to deliver signals when the user interrupts execution or presses a
+    /// key.
+    signals_tx: Sender<Signal>,
+}
+
+impl SharedWindow {
+    /// Opens a new window of `size` pixels.
+    pub(crate) fn new(size: SizeInPixels, signals_tx: Sender<Signal>) -> io::Result<Self> {
+        let title = format!("EndBASIC {}", env!("CARGO_PKG_VERSION"));
+        let mut window = Window::new(
+            &title,
+            usize::from(size.width),
+            usize::from(size.height),
+            WindowOptions::default(),
+        )
+        .map_err(minifb_error_to_io_error)?;
+
+        // We control when to refresh the window ourselves and waiting here would slow down
+        // rendering for no good reason.
+        window.limit_update_rate(None);
+
+        let text_input = Rc::from(RefCell::from(VecDeque::default()));
+        window.set_input_callback(Box::from(TextInput(text_input.clone())));
+
+        Ok(Self {
+            window,
+            size,
+            frame: vec![0; usize::from(size.width) * usize::from(size.height)],
+            text_input,
+            keys: VecDeque::default(),
+            closed: false,
+            signals_tx,
+        })
+    }
+
+    /// Replaces the contents of the window with `frame`, given in the 0RGB format, and processes
+    /// any pending events.
+    pub(crate) fn present(&mut self, frame: &[u32]) -> io::Result<()> {
+        self.frame.copy_from_slice(frame);
+        self.update()
+    }
+
+    /// Sets the title of the window.
+    pub(crate) fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    /// Processes any pending events and queues the keys pressed since the last call.
+    pub(crate) fn update(&mut self) -> io::Result<()> {
+        self.window
+            .update_with_buffer(
+                &self.frame,
+                usize::from(self.size.width),
+                usize::from(self.size.height),
+            )
+            .map_err(minifb_error_to_io_error)?;
+
+        if !self.window.is_open() {
+            if !self.closed {
+                // TODO(jmmv): This isn't really a key so we should be handling it in some other
+                // way, but doing so is consistent with how the SDL console behaves.
+                self.keys.push_back(Key::Eof);
+                self.closed = true;
+            }
+            return Ok(());
+        }
+
+        let ctrl = self.window.is_key_down(minifb::Key::LeftCtrl)
+            || self.window.is_key_down(minifb::Key::RightCtrl);
+        for key in self.window.get_keys_pressed(KeyRepeat::Yes) {
+            // Let the machine dispatch any handler installed with `ON KEY`.  Control combinations
+            // are excluded so that, e.g., `Ctrl-C` does not trigger the `C` key.
+            if !ctrl {
+                if let Some(name) = function_key_name(key) {
+                    self.send_signal(Signal::Key(name));
+                }
+            }
+
+            if let Some(key) = parse_key(key, ctrl) {
+                if key == Key::Interrupt {
+                    self.send_signal(Signal::Break);
+                } else if !ctrl {
+                    if let Some(name) = key_name(&key) {
+                        self.send_signal(Signal::Key(name));
+                    }
+                }
+                self.keys.push_back(key);
+            }
+        }
+
+        let text_input = self.text_input.clone();
+        let mut text_input = text_input.borrow_mut();
+        if ctrl {
+            // Control combinations have already been handled as keys.
+            text_input.clear();
+        } else {
+            for ch in text_input.drain(..) {
+                if let Some(name) = key_name(&Key::Char(ch)) {
+                    self.send_signal(Signal::Key(name));
+                }
+                self.keys.push_back(Key::Char(ch));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delivers `signal` to the machine.
+    fn send_signal(&self, signal: Signal) {
+        // Using "try_send" should be sufficient given that the channel we use is not bounded.
+        self.signals_tx.try_send(signal).expect("Channel must be alive and not full");
+    }
+
+    /// Processes any pending events and returns the keys that are currently held down.
+    pub(crate) fn keys_down(&mut self) -> io::Result<Vec<minifb::Key>> {
+        self.update()?;
+        Ok(self.window.get_keys())
+    }
+
+    /// Returns the next key pressed in the window, if any.
+    ///
+    /// Once the window is closed, this keeps returning `Key::Eof`.
+    pub(crate) fn pop_key(&mut self) -> Option<Key> {
+        match self.keys.pop_front() {
+            Some(key) => Some(key),
+            None if self.closed => Some(Key::Eof),
+            None => None,
+        }
+    }
+}
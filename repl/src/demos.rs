@@ -13,18 +13,31 @@
 // License for the specific language governing permissions and limitations
 // under the License.
 
-//! Exposes EndBASIC demos as a read-only drive.
+//! Exposes EndBASIC demos as a read-only drive and a command to browse them.
 
 use async_trait::async_trait;
-use endbasic_std::storage::{DiskSpace, Drive, DriveFactory, DriveFiles, Metadata};
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Value, VarType};
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult,
+};
+use endbasic_std::console::{read_line, Console};
+use endbasic_std::program::{continue_if_modified, Program, CATEGORY};
+use endbasic_std::storage::{
+    guess_kind, DiskSpace, Drive, DriveFactory, DriveFiles, Metadata, Storage,
+};
+use endbasic_std::tutorial;
+use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap};
 use std::io;
+use std::rc::Rc;
 use std::str;
 
 /// A read-only drive that exposes a bunch of read-only demo files.
 pub struct DemosDrive {
-    /// The demos to expose, expressed as a mapping of names to (metadata, content) pairs.
-    demos: HashMap<&'static str, (Metadata, String)>,
+    /// The demos to expose, expressed as a mapping of names to (metadata, content, description)
+    /// tuples.
+    demos: HashMap<&'static str, (Metadata, String, &'static str)>,
 }
 
 /// Converts the raw bytes of a demo file into the program string to expose.
@@ -52,40 +65,90 @@ impl Default for DemosDrive {
             let metadata = Metadata {
                 date: time::OffsetDateTime::from_unix_timestamp(1608693152).unwrap(),
                 length: content.len() as u64,
+                kind: guess_kind("GUESS.BAS"),
+                read_only: true,
             };
-            demos.insert("GUESS.BAS", (metadata, content));
+            demos.insert(
+                "GUESS.BAS",
+                (metadata, content, "Game to guess a number chosen by the computer."),
+            );
         }
         {
             let content = process_demo(include_bytes!("../examples/gpio.bas"));
             let metadata = Metadata {
                 date: time::OffsetDateTime::from_unix_timestamp(1613316558).unwrap(),
                 length: content.len() as u64,
+                kind: guess_kind("GPIO.BAS"),
+                read_only: true,
             };
-            demos.insert("GPIO.BAS", (metadata, content));
+            demos.insert(
+                "GPIO.BAS",
+                (
+                    metadata,
+                    content,
+                    "Flashes an LED when pressing a button attached to the GPIO pins.",
+                ),
+            );
         }
         {
             let content = process_demo(include_bytes!("../examples/hello.bas"));
             let metadata = Metadata {
                 date: time::OffsetDateTime::from_unix_timestamp(1608646800).unwrap(),
                 length: content.len() as u64,
+                kind: guess_kind("HELLO.BAS"),
+                read_only: true,
             };
-            demos.insert("HELLO.BAS", (metadata, content));
+            demos.insert("HELLO.BAS", (metadata, content, "Asks for your name and greets you."));
         }
         {
             let content = process_demo(include_bytes!("../examples/palette.bas"));
             let metadata = Metadata {
                 date: time::OffsetDateTime::from_unix_timestamp(1671243940).unwrap(),
                 length: content.len() as u64,
+                kind: guess_kind("PALETTE.BAS"),
+                read_only: true,
             };
-            demos.insert("PALETTE.BAS", (metadata, content));
+            demos.insert("PALETTE.BAS", (metadata, content, "Renders the full color palette."));
         }
         {
             let content = process_demo(include_bytes!("../examples/tour.bas"));
             let metadata = Metadata {
                 date: time::OffsetDateTime::from_unix_timestamp(1608774770).unwrap(),
                 length: content.len() as u64,
+                kind: guess_kind("TOUR.BAS"),
+                read_only: true,
             };
-            demos.insert("TOUR.BAS", (metadata, content));
+            demos.insert("TOUR.BAS", (metadata, content, "Guided tour of the EndBASIC features."));
+        }
+        {
+            let content = process_demo(include_bytes!("../lessons/lesson1.txt"));
+            let metadata = Metadata {
+                date: time::OffsetDateTime::from_unix_timestamp(1792224000).unwrap(),
+                length: content.len() as u64,
+                kind: guess_kind("LESSON1.TXT"),
+                read_only: true,
+            };
+            demos.insert("LESSON1.TXT", (metadata, content, "Tutorial lesson: Printing things."));
+        }
+        {
+            let content = process_demo(include_bytes!("../lessons/lesson2.txt"));
+            let metadata = Metadata {
+                date: time::OffsetDateTime::from_unix_timestamp(1792224000).unwrap(),
+                length: content.len() as u64,
+                kind: guess_kind("LESSON2.TXT"),
+                read_only: true,
+            };
+            demos.insert("LESSON2.TXT", (metadata, content, "Tutorial lesson: Variables."));
+        }
+        {
+            let content = process_demo(include_bytes!("../lessons/lesson3.txt"));
+            let metadata = Metadata {
+                date: time::OffsetDateTime::from_unix_timestamp(1792224000).unwrap(),
+                length: content.len() as u64,
+                kind: guess_kind("LESSON3.TXT"),
+                read_only: true,
+            };
+            demos.insert("LESSON3.TXT", (metadata, content, "Tutorial lesson: Loops."));
         }
         Self { demos }
     }
@@ -100,7 +163,7 @@ impl Drive for DemosDrive {
     async fn enumerate(&self) -> io::Result<DriveFiles> {
         let mut entries = BTreeMap::new();
         let mut bytes = 0;
-        for (name, (metadata, content)) in self.demos.iter() {
+        for (name, (metadata, content, _description)) in self.demos.iter() {
             entries.insert(name.to_string(), metadata.clone());
             bytes += content.len();
         }
@@ -121,7 +184,7 @@ impl Drive for DemosDrive {
         let uc_name = name.to_ascii_uppercase();
         match self.demos.get(&uc_name.as_ref()) {
             Some(value) => {
-                let (_metadata, content) = value;
+                let (_metadata, content, _description) = value;
                 Ok(content.to_string())
             }
             None => Err(io::Error::new(io::ErrorKind::NotFound, "Demo not found")),
@@ -150,9 +213,154 @@ impl DriveFactory for DemoDriveFactory {
     }
 }
 
+/// The `DEMOS` command.
+pub struct DemosCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    program: Rc<RefCell<dyn Program>>,
+    demos: DemosDrive,
+}
+
+impl DemosCommand {
+    /// Creates a new `DEMOS` command that loads demos into `program` and that uses `console` to
+    /// interact with the user.
+    pub fn new(console: Rc<RefCell<dyn Console>>, program: Rc<RefCell<dyn Program>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DEMOS", VarType::Void)
+                .with_syntax("[name$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Browses and loads the bundled demo programs.
+Without arguments, prints the list of demo programs that ship with EndBASIC along with their \
+descriptions.  You can then type the number of a demo to load it into memory, or press ENTER to \
+leave the current program untouched.
+With a single argument, which must be a string, loads the demo with the given name right away.  \
+The .BAS extension is optional.
+Once loaded, use RUN to start the demo or EDIT to look at its code.  Any previously stored \
+program is discarded from memory, but DEMOS will pause to ask before discarding any unsaved \
+modifications.
+The demos are also available in the read-only DEMOS: drive.",
+                )
+                .build(),
+            console,
+            program,
+            demos: DemosDrive::default(),
+        })
+    }
+
+    /// Prints the list of demos as a numbered list and returns their names in the same order.
+    fn list(&self) -> io::Result<Vec<&'static str>> {
+        let mut names: Vec<&'static str> =
+            self.demos.demos.keys().copied().filter(|name| name.ends_with(".BAS")).collect();
+        names.sort();
+
+        let mut console = self.console.borrow_mut();
+        console.print("")?;
+        for (i, name) in names.iter().enumerate() {
+            let (_metadata, _content, description) = &self.demos.demos[name];
+            console.print(&format!("    {:>2}. {}", i + 1, name))?;
+            console.print(&format!("        {}", description))?;
+        }
+        console.print("")?;
+        Ok(names)
+    }
+
+    /// Asks the user to choose one of `count` entries and returns its zero-based index, or `None`
+    /// if the user chose to not load anything.
+    async fn choose(&self, count: usize) -> io::Result<Option<usize>> {
+        let console = &mut *self.console.borrow_mut();
+        loop {
+            let answer =
+                read_line(console, "Enter a number to load or press ENTER to cancel: ", "", None)
+                    .await?;
+            let answer = answer.trim();
+            if answer.is_empty() {
+                return Ok(None);
+            }
+            match answer.parse::<usize>() {
+                Ok(i) if i >= 1 && i <= count => return Ok(Some(i - 1)),
+                _ => console.print(&format!("Invalid choice; must be between 1 and {}", count))?,
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for DemosCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let name = match span.args.as_slice() {
+            [] => {
+                let names = self.list()?;
+                match self.choose(names.len()).await? {
+                    Some(i) => names[i].to_owned(),
+                    None => return Ok(()),
+                }
+            }
+            [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => {
+                match expr.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(t) => {
+                        let mut name = t.to_ascii_uppercase();
+                        if !name.contains('.') {
+                            name.push_str(".BAS");
+                        }
+                        if !name.ends_with(".BAS") || !self.demos.demos.contains_key(name.as_str())
+                        {
+                            return Err(CallError::ArgumentError(
+                                expr.start_pos(),
+                                format!("Unknown demo {}", t),
+                            ));
+                        }
+                        name
+                    }
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            expr.start_pos(),
+                            "DEMOS requires a string as the demo name".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        if continue_if_modified(&*self.program.borrow(), &mut *self.console.borrow_mut()).await? {
+            let content = self.demos.get(&name).await?;
+            let full_name = format!("DEMOS:/{}", name);
+            self.program.borrow_mut().load(Some(&full_name), &content);
+            machine.clear();
+            self.console.borrow_mut().print(&format!(
+                "Loaded {}; type RUN to start it or EDIT to view its code.",
+                full_name
+            ))?;
+        } else {
+            self.console
+                .borrow_mut()
+                .print("DEMOS aborted; use SAVE to save your current changes.")?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the `DEMOS` command to the `machine` to load demos into `program` and the `TUTORIAL`
+/// command to run the lessons from the `DEMOS:` drive mounted in `storage`.
+pub fn add_all(
+    machine: &mut Machine,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+    program: Rc<RefCell<dyn Program>>,
+) {
+    machine.add_command(DemosCommand::new(console.clone(), program));
+    tutorial::add_all(machine, console, storage, "DEMOS:/LESSON*.TXT");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use endbasic_testutils::*;
     use futures_lite::future::block_on;
 
     #[test]
@@ -182,11 +390,14 @@ mod tests {
         assert!(files.dirents().contains_key("GPIO.BAS"));
         assert!(files.dirents().contains_key("GUESS.BAS"));
         assert!(files.dirents().contains_key("HELLO.BAS"));
+        assert!(files.dirents().contains_key("LESSON1.TXT"));
+        assert!(files.dirents().contains_key("LESSON2.TXT"));
+        assert!(files.dirents().contains_key("LESSON3.TXT"));
         assert!(files.dirents().contains_key("PALETTE.BAS"));
         assert!(files.dirents().contains_key("TOUR.BAS"));
 
         assert!(files.disk_quota().unwrap().bytes() > 0);
-        assert_eq!(5, files.disk_quota().unwrap().files());
+        assert_eq!(8, files.disk_quota().unwrap().files());
         assert_eq!(DiskSpace::new(0, 0), files.disk_free().unwrap());
     }
 
@@ -230,4 +441,89 @@ mod tests {
         let drive = DemosDrive::default();
         assert!(drive.system_path("foo").is_none());
     }
+
+    /// Creates a tester with the `DEMOS` command wired to its console and program.
+    fn tester() -> Tester {
+        let tester = Tester::default();
+        let (console, program) = (tester.get_console(), tester.get_program());
+        tester.add_command(DemosCommand::new(console, program))
+    }
+
+    /// Returns the expected output of the list of demos.
+    fn demos_list() -> Vec<&'static str> {
+        vec![
+            "",
+            "     1. GPIO.BAS",
+            "        Flashes an LED when pressing a button attached to the GPIO pins.",
+            "     2. GUESS.BAS",
+            "        Game to guess a number chosen by the computer.",
+            "     3. HELLO.BAS",
+            "        Asks for your name and greets you.",
+            "     4. PALETTE.BAS",
+            "        Renders the full color palette.",
+            "     5. TOUR.BAS",
+            "        Guided tour of the EndBASIC features.",
+            "",
+        ]
+    }
+
+    #[test]
+    fn test_demos_command_cancel() {
+        let mut t = tester().add_input_chars("\n");
+        t.run("DEMOS").expect_prints(demos_list()).check();
+    }
+
+    #[test]
+    fn test_demos_command_choose() {
+        let mut t = tester().add_input_chars("0\n").add_input_chars("3\n");
+        let mut exp_prints = demos_list();
+        exp_prints.push("Invalid choice; must be between 1 and 5");
+        t.run("DEMOS")
+            .expect_prints(exp_prints)
+            .expect_clear()
+            .expect_prints([
+                "Loaded DEMOS:/HELLO.BAS; type RUN to start it or EDIT to view its code.",
+            ])
+            .expect_program(
+                Some("DEMOS:/HELLO.BAS"),
+                process_demo(include_bytes!("../examples/hello.bas")),
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_demos_command_by_name() {
+        for name in ["guess", "Guess.Bas"] {
+            let mut t = tester();
+            t.run(format!(r#"DEMOS "{}""#, name))
+                .expect_clear()
+                .expect_prints([
+                    "Loaded DEMOS:/GUESS.BAS; type RUN to start it or EDIT to view its code.",
+                ])
+                .expect_program(
+                    Some("DEMOS:/GUESS.BAS"),
+                    process_demo(include_bytes!("../examples/guess.bas")),
+                )
+                .check();
+        }
+    }
+
+    #[test]
+    fn test_demos_command_errors() {
+        let mut t = tester();
+        t.run(r#"DEMOS "foo""#).expect_err("1:1: In call to DEMOS: 1:7: Unknown demo foo").check();
+
+        let mut t = tester();
+        t.run(r#"DEMOS "lesson1.txt""#)
+            .expect_err("1:1: In call to DEMOS: 1:7: Unknown demo lesson1.txt")
+            .check();
+
+        let mut t = tester();
+        t.run("DEMOS 3")
+            .expect_err("1:1: In call to DEMOS: 1:7: DEMOS requires a string as the demo name")
+            .check();
+
+        let mut t = tester();
+        t.run(r#"DEMOS "a", "b""#).expect_err("1:1: In call to DEMOS: expected [name$]").check();
+    }
 }
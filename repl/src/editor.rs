@@ -17,7 +17,7 @@
 
 use crate::console::{CharsXY, ClearType, Console, Key};
 use async_trait::async_trait;
-use endbasic_std::console::{AnsiColor, LineBuffer};
+use endbasic_std::console::{highlight_colors, write_highlighted, AnsiColor, LineBuffer};
 use endbasic_std::program::Program;
 use std::cmp;
 use std::convert::TryFrom;
@@ -30,6 +30,10 @@ const TEXT_COLOR: (Option<u8>, Option<u8>) = (Some(AnsiColor::White as u8), None
 const STATUS_COLOR: (Option<u8>, Option<u8>) =
     (Some(AnsiColor::BrightWhite as u8), Some(AnsiColor::Blue as u8));
 
+/// The color of the selected block of text.
+const SELECTION_COLOR: (Option<u8>, Option<u8>) =
+    (Some(AnsiColor::Black as u8), Some(AnsiColor::White as u8));
+
 /// Default indentation with.
 const INDENT_WIDTH: usize = 4;
 
@@ -48,6 +52,22 @@ fn copy_indent(line: &LineBuffer) -> String {
     indent
 }
 
+/// Returns true if the characters `a` and `b` are equal without regards to their case.
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Finds the first occurrence of `query` within `line` at or after the `from` position and returns
+/// its position.  The comparison is case insensitive.
+fn find_in_line(line: &[char], query: &[char], from: usize) -> Option<usize> {
+    if query.is_empty() || line.len() < query.len() {
+        return None;
+    }
+    (from..=line.len() - query.len()).find(|pos| {
+        line[*pos..*pos + query.len()].iter().zip(query).all(|(a, b)| eq_ignore_case(*a, *b))
+    })
+}
+
 /// Finds the first position within the line that is not an indentation character, or returns
 /// the line length if no such character is found.
 fn find_indent_end(line: &LineBuffer) -> usize {
@@ -63,7 +83,7 @@ fn find_indent_end(line: &LineBuffer) -> usize {
 }
 
 /// Represents a position within a file.
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
 struct FilePos {
     /// The column number, starting from zero.
     line: usize,
@@ -94,6 +114,16 @@ pub struct Editor {
     /// Last edited column, used when moving vertically to preserve the insertion point even when
     /// traversing shorter lines.
     insert_col: usize,
+
+    /// Start of the block selection, if any.  The selected text spans from this position to the
+    /// insertion position, in whichever order they appear in the file.
+    mark: Option<FilePos>,
+
+    /// Text cut or copied from the file and available for pasting.
+    clipboard: String,
+
+    /// Text of the last search, offered as the default for the next one.
+    last_search: String,
 }
 
 impl Default for Editor {
@@ -106,6 +136,9 @@ impl Default for Editor {
             viewport_pos: FilePos::default(),
             file_pos: FilePos::default(),
             insert_col: 0,
+            mark: None,
+            clipboard: String::new(),
+            last_search: String::new(),
         }
     }
 }
@@ -156,16 +189,64 @@ impl Editor {
         console.set_color(TEXT_COLOR.0, TEXT_COLOR.1)?;
         console.locate(CharsXY::default())?;
 
+        let selection = self.selection();
+
         let mut row = self.viewport_pos.line;
         let mut printed_rows = 0;
         while row < self.content.len() && printed_rows < console_size.y - 1 {
             let line = &self.content[row];
             let line_len = line.len();
             if line_len > self.viewport_pos.col {
-                console.print(&line.range(
-                    self.viewport_pos.col,
-                    self.viewport_pos.col + usize::from(console_size.x),
-                ))?;
+                let start = self.viewport_pos.col;
+                let end = cmp::min(line_len, start + usize::from(console_size.x));
+                let colors = highlight_colors(&line.to_string());
+
+                // Compute the part of the visible line that is selected, if any.
+                let selected = selection.and_then(|(sel_start, sel_end)| {
+                    if row < sel_start.line || row > sel_end.line {
+                        return None;
+                    }
+                    let from = if row == sel_start.line { sel_start.col } else { 0 };
+                    let to = if row == sel_end.line { sel_end.col } else { line_len };
+                    let from = cmp::min(cmp::max(from, start), end);
+                    let to = cmp::min(cmp::max(to, start), end);
+                    if from < to {
+                        Some((from, to))
+                    } else {
+                        None
+                    }
+                });
+
+                match selected {
+                    Some((from, to)) => {
+                        write_highlighted(
+                            console,
+                            &line.range(start, from),
+                            &colors[start..from],
+                            TEXT_COLOR,
+                        )?;
+                        console.set_color(SELECTION_COLOR.0, SELECTION_COLOR.1)?;
+                        console.write(&line.range(from, to))?;
+                        console.set_color(TEXT_COLOR.0, TEXT_COLOR.1)?;
+                        write_highlighted(
+                            console,
+                            &line.range(to, end),
+                            &colors[to..end],
+                            TEXT_COLOR,
+                        )?;
+                        console.print("")?;
+                    }
+                    None => {
+                        let visible = line.range(start, end);
+                        let colors = &colors[start..end];
+                        if colors.iter().all(Option::is_none) {
+                            console.print(&visible)?;
+                        } else {
+                            write_highlighted(console, &visible, colors, TEXT_COLOR)?;
+                            console.print("")?;
+                        }
+                    }
+                }
             } else {
                 console.print("")?;
             }
@@ -175,6 +256,166 @@ impl Editor {
         Ok(())
     }
 
+    /// Shows a `label` in the status line of the `console`, using the previously queried
+    /// `console_size`, and reads a line of text from the user pre-populated with `default`.
+    ///
+    /// Returns `None` if the user cancelled the input.  It is the responsibility of the caller to
+    /// restore the status line after calling this function.
+    async fn prompt(
+        &self,
+        console: &mut dyn Console,
+        console_size: CharsXY,
+        label: &str,
+        default: &str,
+    ) -> io::Result<Option<String>> {
+        let width = usize::from(console_size.x);
+        let mut text = String::from(default);
+        loop {
+            let mut status =
+                format!(" {}: {}", label, text).chars().take(width).collect::<String>();
+            let cursor_x = status.chars().count();
+            while status.chars().count() < width {
+                status.push(' ');
+            }
+
+            console.hide_cursor()?;
+            console.locate(CharsXY::new(0, console_size.y - 1))?;
+            console.set_color(STATUS_COLOR.0, STATUS_COLOR.1)?;
+            console.write(&status)?;
+            let cursor_x = u16::try_from(cmp::min(cursor_x, width - 1))
+                .expect("Cursor must have fit on screen");
+            console.locate(CharsXY::new(cursor_x, console_size.y - 1))?;
+            console.show_cursor()?;
+            console.sync_now()?;
+
+            match console.read_key().await? {
+                Key::Backspace => {
+                    text.pop();
+                }
+                Key::Char(ch) => text.push(ch),
+                Key::NewLine | Key::CarriageReturn => return Ok(Some(text)),
+                Key::Escape | Key::Eof | Key::Interrupt => return Ok(None),
+                _ => (),
+            }
+        }
+    }
+
+    /// Returns the start and end positions of the block selection, if any, in file order.
+    fn selection(&self) -> Option<(FilePos, FilePos)> {
+        self.mark.map(|mark| {
+            if mark <= self.file_pos {
+                (mark, self.file_pos)
+            } else {
+                (self.file_pos, mark)
+            }
+        })
+    }
+
+    /// Returns the text between the `start` and `end` positions, which must be in file order.
+    fn text_range(&self, start: FilePos, end: FilePos) -> String {
+        if start.line == end.line {
+            return self.content[start.line].range(start.col, end.col);
+        }
+
+        let mut text = self.content[start.line].end(start.col);
+        text.push('\n');
+        for line in &self.content[start.line + 1..end.line] {
+            text.push_str(&line.to_string());
+            text.push('\n');
+        }
+        text.push_str(&self.content[end.line].start(end.col));
+        text
+    }
+
+    /// Deletes the text between the `start` and `end` positions, which must be in file order, and
+    /// moves the insertion position to `start`.
+    fn delete_range(&mut self, start: FilePos, end: FilePos) {
+        let tail = self.content[end.line].split_off(end.col);
+        self.content[start.line].split_off(start.col);
+        self.content[start.line].push_str(&tail);
+        self.content.drain(start.line + 1..end.line + 1);
+
+        self.file_pos = start;
+        self.insert_col = start.col;
+    }
+
+    /// Inserts `text`, which may span multiple lines, at the insertion position and moves the
+    /// insertion position to the end of the inserted text.
+    fn insert_text(&mut self, text: &str) {
+        let mut lines = text.split('\n');
+        let first = lines.next().expect("split always returns at least one element");
+
+        let line = &mut self.content[self.file_pos.line];
+        let tail = line.split_off(self.file_pos.col);
+        line.insert_str(self.file_pos.col, first);
+        self.file_pos.col += first.chars().count();
+
+        for new in lines {
+            self.file_pos.line += 1;
+            self.content.insert(self.file_pos.line, LineBuffer::from(new));
+            self.file_pos.col = new.chars().count();
+        }
+        self.content[self.file_pos.line].push_str(&tail);
+        self.insert_col = self.file_pos.col;
+    }
+
+    /// Copies the selected text into the clipboard, or the current line if there is no selection,
+    /// and returns the copied range.  The range is empty if there was nothing to copy.
+    fn copy(&mut self) -> (FilePos, FilePos) {
+        let range = match self.selection() {
+            Some(range) => range,
+            None => {
+                let line = self.file_pos.line;
+                if line + 1 < self.content.len() {
+                    (FilePos { line, col: 0 }, FilePos { line: line + 1, col: 0 })
+                } else {
+                    // The last line has no trailing newline in our representation, so the range
+                    // takes the newline of the previous line instead.  We still want to paste the
+                    // text as a whole line later on though.
+                    self.clipboard = self.content[line].to_string() + "\n";
+                    let start = if line > 0 {
+                        FilePos { line: line - 1, col: self.content[line - 1].len() }
+                    } else {
+                        FilePos { line, col: 0 }
+                    };
+                    return (start, FilePos { line, col: self.content[line].len() });
+                }
+            }
+        };
+        if range.0 != range.1 {
+            self.clipboard = self.text_range(range.0, range.1);
+        }
+        range
+    }
+
+    /// Makes the contents of the clipboard available to other applications via the system
+    /// clipboard of `console`, if it has one.
+    async fn export_clipboard(&self, console: &mut dyn Console) {
+        if !self.clipboard.is_empty() {
+            // Not all consoles have a clipboard, and failing to share the copied text must not
+            // prevent pasting it within the editor, so errors are ignored.
+            let _ = console.clipboard_set(&self.clipboard).await;
+        }
+    }
+
+    /// Searches for `query` starting at the insertion position and wrapping around the end of the
+    /// file.  Returns the start and end positions of the first match, if any.
+    fn find(&self, query: &str) -> Option<(FilePos, FilePos)> {
+        let query = query.chars().collect::<Vec<char>>();
+        for i in 0..=self.content.len() {
+            let line_no = (self.file_pos.line + i) % self.content.len();
+            let from = if i == 0 { self.file_pos.col } else { 0 };
+            let line = self.content[line_no].chars().collect::<Vec<char>>();
+            if let Some(col) = find_in_line(&line, &query, from) {
+                return Some((
+                    FilePos { line: line_no, col },
+                    FilePos { line: line_no, col: col + query.len() },
+                ));
+            }
+        }
+        None
+    }
+
     /// Moves the cursor down by the given number of lines in `nlines` or to the last line if there
     /// are insufficient lines to perform the move.
     fn move_down(&mut self, nlines: usize) {
@@ -211,6 +452,12 @@ impl Editor {
 
         let mut need_refresh = true;
         loop {
+            // The selection is redrawn in full whenever it may have changed.
+            // TODO(jmmv): Refresh only the affected lines.
+            if self.mark.is_some() {
+                need_refresh = true;
+            }
+
             // The key handling below only deals with moving the insertion position within the file
             // but does not bother to update the viewport. Adjust it now, if necessary.
             let width = usize::from(console_size.x);
@@ -258,7 +505,30 @@ impl Editor {
             console.show_cursor()?;
             console.sync_now()?;
 
-            match console.read_key().await? {
+            let key = console.read_key().await?;
+
+            // Any edit discards the selection, which requires redrawing it without highlighting.
+            if self.mark.is_some()
+                && matches!(
+                    key,
+                    Key::Backspace
+                        | Key::Char(_)
+                        | Key::NewLine
+                        | Key::CarriageReturn
+                        | Key::Tab
+                        | Key::Control('v')
+                )
+            {
+                self.mark = None;
+                need_refresh = true;
+            }
+
+            match key {
+                Key::Escape if self.mark.is_some() => {
+                    self.mark = None;
+                    need_refresh = true;
+                }
+
                 Key::Escape | Key::Eof | Key::Interrupt => break,
 
                 Key::ArrowUp => self.move_up(1),
@@ -282,6 +552,7 @@ impl Editor {
                 Key::Backspace => {
                     if self.file_pos.col > 0 {
                         let line = &mut self.content[self.file_pos.line];
+                        let before = highlight_colors(&line.to_string());
 
                         let indent_pos = find_indent_end(line);
                         let is_indent = indent_pos >= self.file_pos.col;
@@ -317,6 +588,13 @@ impl Editor {
                         if nremove > 0 {
                             self.dirty = true;
                         }
+
+                        // Removing characters can change the highlighting of the rest of the line.
+                        let after = highlight_colors(&line.to_string());
+                        if after[..] != before[..after.len()] {
+                            // TODO(jmmv): Refresh only the affected line.
+                            need_refresh = true;
+                        }
                     } else if self.file_pos.line > 0 {
                         let line = self.content.remove(self.file_pos.line);
                         let prev = &mut self.content[self.file_pos.line - 1];
@@ -338,17 +616,103 @@ impl Editor {
                         need_refresh = true;
                     }
 
+                    let before = highlight_colors(&line.to_string());
                     line.insert(self.file_pos.col, ch);
                     self.file_pos.col += 1;
                     self.insert_col = self.file_pos.col;
 
                     if cursor_pos.x < console_size.x - 1 && !need_refresh {
-                        console.write(ch.encode_utf8(&mut buf))?;
+                        // Typing a character can change the highlighting of the characters before
+                        // it (as in completing a keyword), in which case we must redraw them.
+                        let after = highlight_colors(&line.to_string());
+                        if after[..before.len()] == before[..] {
+                            write_highlighted(
+                                console,
+                                ch.encode_utf8(&mut buf),
+                                &after[before.len()..],
+                                TEXT_COLOR,
+                            )?;
+                        } else {
+                            // TODO(jmmv): Refresh only the affected line.
+                            need_refresh = true;
+                        }
                     }
 
                     self.dirty = true;
                 }
 
+                Key::Control('f') => {
+                    let default = self.last_search.clone();
+                    if let Some(query) =
+                        self.prompt(console, console_size, "Find", &default).await?
+                    {
+                        if let Some((start, end)) = self.find(&query) {
+                            self.mark = Some(start);
+                            self.file_pos = end;
+                            self.insert_col = end.col;
+                        }
+                        self.last_search = query;
+                    }
+                }
+
+                Key::Control('g') => {
+                    if let Some(text) = self.prompt(console, console_size, "Go to line", "").await?
+                    {
+                        if let Ok(line) = text.trim().parse::<usize>() {
+                            if line > 0 {
+                                self.file_pos.line = cmp::min(line, self.content.len()) - 1;
+                                self.file_pos.col = 0;
+                                self.insert_col = 0;
+                            }
+                        }
+                    }
+                }
+
+                Key::Control('k') => {
+                    if self.mark.is_some() {
+                        self.mark = None;
+                        need_refresh = true;
+                    } else {
+                        self.mark = Some(self.file_pos);
+                    }
+                }
+
+                Key::Control('v') => {
+                    // Prefer the system clipboard so that text copied in other applications can be
+                    // pasted here, but keep our own when the console does not have one.
+                    if let Ok(text) = console.clipboard_get().await {
+                        if !text.is_empty() {
+                            self.clipboard = text.replace("\r\n", "\n");
+                        }
+                    }
+                    if !self.clipboard.is_empty() {
+                        let text = self.clipboard.clone();
+                        self.insert_text(&text);
+                        need_refresh = true;
+                        self.dirty = true;
+                    }
+                }
+
+                Key::Control('x') => {
+                    let (start, end) = self.copy();
+                    self.export_clipboard(console).await;
+                    if start != end {
+                        self.delete_range(start, end);
+                        self.dirty = true;
+                    }
+                    self.mark = None;
+                    need_refresh = true;
+                }
+
+                Key::Control('y') => {
+                    self.copy();
+                    self.export_clipboard(console).await;
+                    if self.mark.is_some() {
+                        self.mark = None;
+                        need_refresh = true;
+                    }
+                }
+
                 Key::End => {
                     self.file_pos.col = self.content[self.file_pos.line].len();
                     self.insert_col = self.file_pos.col;
@@ -410,7 +774,7 @@ impl Editor {
                 }
 
                 // TODO(jmmv): Should do something smarter with unknown keys.
-                Key::Unknown(_) => (),
+                Key::Control(_) | Key::Unknown(_) => (),
             }
         }
 
@@ -440,6 +804,7 @@ impl Program for Editor {
         self.viewport_pos = FilePos::default();
         self.file_pos = FilePos::default();
         self.insert_col = 0;
+        self.mark = None;
     }
 
     fn name(&self) -> Option<&str> {
@@ -451,6 +816,15 @@ impl Program for Editor {
         self.dirty = false;
     }
 
+    fn set_text(&mut self, text: &str) {
+        self.content = text.lines().map(LineBuffer::from).collect();
+        self.dirty = true;
+        self.viewport_pos = FilePos::default();
+        self.file_pos = FilePos::default();
+        self.insert_col = 0;
+        self.mark = None;
+    }
+
     fn text(&self) -> String {
         self.content
             .iter()
@@ -461,7 +835,7 @@ impl Program for Editor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use endbasic_std::testutils::*;
+    use endbasic_testutils::*;
     use futures_lite::future::block_on;
 
     /// Name of the program to inject into the editor for testing.  The name is very short because
@@ -489,6 +863,8 @@ mod tests {
         console_size: CharsXY,
         output: Vec<CapturedOut>,
         dirty: bool,
+        content: Option<Vec<&'static str>>,
+        selection: Option<(FilePos, FilePos)>,
     }
 
     impl OutputBuilder {
@@ -500,9 +876,26 @@ mod tests {
                 console_size,
                 output: vec![CapturedOut::EnterAlt, CapturedOut::SetSync(false)],
                 dirty: false,
+                content: None,
+                selection: None,
             }
         }
 
+        /// Sets the full `content` of the file being edited, which is necessary to compute the
+        /// highlighting of lines that are only partially visible due to horizontal scrolling.
+        /// Should only be used in tests that do not modify the file.
+        fn set_content(mut self, content: &[&'static str]) -> Self {
+            self.content = Some(content.to_vec());
+            self
+        }
+
+        /// Sets the block `selection` to render on the next refreshes, given as the start and end
+        /// positions in file order.
+        fn set_selection(mut self, selection: Option<(FilePos, FilePos)>) -> Self {
+            self.selection = selection;
+            self
+        }
+
         /// Records the console changes needed to update the status line to reflect a new `file_pos`
         /// position.  Should not be used directly by tests.
         ///
@@ -549,8 +942,55 @@ mod tests {
             self = self.refresh_status(file_pos);
             self.output.push(CapturedOut::SetColor(TEXT_COLOR.0, TEXT_COLOR.1));
             self.output.push(CapturedOut::Locate(yx(0, 0)));
-            for line in previous {
-                self.output.push(CapturedOut::Print(line.to_string()));
+            let viewport_col = file_pos.col - usize::from(cursor.x);
+            let viewport_line = file_pos.line - usize::from(cursor.y);
+            for (i, line) in previous.iter().enumerate() {
+                let colors = match self.content.as_ref() {
+                    Some(content) => {
+                        let colors = highlight_colors(content[viewport_line + i]);
+                        let start = cmp::min(viewport_col, colors.len());
+                        colors[start..start + line.chars().count()].to_vec()
+                    }
+                    None => highlight_colors(line),
+                };
+
+                let row = viewport_line + i;
+                let len = line.chars().count();
+                let selected = self.selection.and_then(|(start, end)| {
+                    if row < start.line || row > end.line {
+                        return None;
+                    }
+                    let from = if row == start.line { start.col } else { 0 };
+                    let to = if row == end.line { end.col } else { usize::MAX };
+                    let from = cmp::min(from.saturating_sub(viewport_col), len);
+                    let to = cmp::min(to.saturating_sub(viewport_col), len);
+                    if from < to {
+                        Some((from, to))
+                    } else {
+                        None
+                    }
+                });
+
+                if let Some((from, to)) = selected {
+                    let chars = line.chars().collect::<Vec<char>>();
+                    let mut console = MockConsole::default();
+                    let before = chars[..from].iter().collect::<String>();
+                    write_highlighted(&mut console, &before, &colors[..from], TEXT_COLOR).unwrap();
+                    console.set_color(SELECTION_COLOR.0, SELECTION_COLOR.1).unwrap();
+                    console.write(&chars[from..to].iter().collect::<String>()).unwrap();
+                    console.set_color(TEXT_COLOR.0, TEXT_COLOR.1).unwrap();
+                    let after = chars[to..].iter().collect::<String>();
+                    write_highlighted(&mut console, &after, &colors[to..], TEXT_COLOR).unwrap();
+                    self.output.extend(console.captured_out().iter().cloned());
+                    self.output.push(CapturedOut::Print("".to_owned()));
+                } else if colors.iter().all(Option::is_none) {
+                    self.output.push(CapturedOut::Print(line.to_string()));
+                } else {
+                    let mut console = MockConsole::default();
+                    write_highlighted(&mut console, line, &colors, TEXT_COLOR).unwrap();
+                    self.output.extend(console.captured_out().iter().cloned());
+                    self.output.push(CapturedOut::Print("".to_owned()));
+                }
             }
             self.output.push(CapturedOut::Locate(cursor));
             self.output.push(CapturedOut::ShowCursor);
@@ -558,6 +998,38 @@ mod tests {
             self
         }
 
+        /// Records the console changes needed to show a prompt in the status line with the given
+        /// `label` and the `text` typed so far.
+        fn prompt(mut self, label: &str, text: &str) -> Self {
+            let mut status = format!(" {}: {}", label, text);
+            let cursor_x = u16::try_from(status.chars().count()).unwrap();
+            while status.chars().count() < usize::from(self.console_size.x) {
+                status.push(' ');
+            }
+            self.output.push(CapturedOut::HideCursor);
+            self.output.push(CapturedOut::Locate(yx(self.console_size.y - 1, 0)));
+            self.output.push(CapturedOut::SetColor(STATUS_COLOR.0, STATUS_COLOR.1));
+            self.output.push(CapturedOut::Write(status));
+            self.output.push(CapturedOut::Locate(yx(self.console_size.y - 1, cursor_x)));
+            self.output.push(CapturedOut::ShowCursor);
+            self.output.push(CapturedOut::SyncNow);
+            self
+        }
+
+        /// Registers the expected side-effects of writing `text` in the highlighting `color`, or in
+        /// the regular text color if `color` is none.
+        fn add_write(mut self, text: &str, color: Option<AnsiColor>) -> Self {
+            match color {
+                None => self.output.push(CapturedOut::Write(text.to_owned())),
+                Some(color) => {
+                    self.output.push(CapturedOut::SetColor(Some(color as u8), TEXT_COLOR.1));
+                    self.output.push(CapturedOut::Write(text.to_owned()));
+                    self.output.push(CapturedOut::SetColor(TEXT_COLOR.0, TEXT_COLOR.1));
+                }
+            }
+            self
+        }
+
         /// Registers a new expected side-effect `co` on the console.
         fn add(mut self, co: CapturedOut) -> Self {
             self.output.push(co);
@@ -673,12 +1145,84 @@ mod tests {
         ob = ob.quick_refresh(linecol(2, 0), yx(2, 0));
 
         cb.add_input_chars("2");
-        ob = ob.add(CapturedOut::Write("2".to_string()));
+        ob = ob.add_write("2", Some(AnsiColor::BrightMagenta));
         ob = ob.quick_refresh(linecol(2, 1), yx(2, 1));
 
         run_editor("", "abcéà\n\n2\n", cb, ob);
     }
 
+    #[test]
+    fn test_syntax_highlighting() {
+        let mut cb = MockConsole::default();
+        cb.set_size_chars(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40));
+        ob = ob.add(CapturedOut::HideCursor);
+        ob = ob.add(CapturedOut::SetColor(TEXT_COLOR.0, TEXT_COLOR.1));
+        ob = ob.add(CapturedOut::Clear(ClearType::All));
+        ob = ob.refresh_status(linecol(0, 0));
+        ob = ob.add(CapturedOut::SetColor(TEXT_COLOR.0, TEXT_COLOR.1));
+        ob = ob.add(CapturedOut::Locate(yx(0, 0)));
+        ob = ob.add(CapturedOut::Write("a = ".to_owned()));
+        ob = ob.add_write("\"b\"", Some(AnsiColor::BrightGreen));
+        ob = ob.add(CapturedOut::Write(" ".to_owned()));
+        ob = ob.add_write("' c", Some(AnsiColor::Cyan));
+        ob = ob.add(CapturedOut::Print("".to_owned()));
+        ob = ob.add(CapturedOut::Print("".to_owned()));
+        ob = ob.add(CapturedOut::Locate(yx(0, 0)));
+        ob = ob.add(CapturedOut::ShowCursor);
+        ob = ob.add(CapturedOut::SyncNow);
+
+        cb.add_input_keys(&[Key::ArrowDown]);
+        ob = ob.quick_refresh(linecol(1, 0), yx(1, 0));
+        cb.add_input_chars("I");
+        ob = ob.set_dirty();
+        ob = ob.add_write("I", None);
+        ob = ob.quick_refresh(linecol(1, 1), yx(1, 1));
+
+        // Completing a keyword changes the color of the previous characters so this needs a
+        // refresh.
+        cb.add_input_chars("F");
+        ob = ob.add(CapturedOut::HideCursor);
+        ob = ob.add(CapturedOut::SetColor(TEXT_COLOR.0, TEXT_COLOR.1));
+        ob = ob.add(CapturedOut::Clear(ClearType::All));
+        ob = ob.refresh_status(linecol(1, 2));
+        ob = ob.add(CapturedOut::SetColor(TEXT_COLOR.0, TEXT_COLOR.1));
+        ob = ob.add(CapturedOut::Locate(yx(0, 0)));
+        ob = ob.add(CapturedOut::Write("a = ".to_owned()));
+        ob = ob.add_write("\"b\"", Some(AnsiColor::BrightGreen));
+        ob = ob.add(CapturedOut::Write(" ".to_owned()));
+        ob = ob.add_write("' c", Some(AnsiColor::Cyan));
+        ob = ob.add(CapturedOut::Print("".to_owned()));
+        ob = ob.add_write("IF", Some(AnsiColor::BrightYellow));
+        ob = ob.add(CapturedOut::Print("".to_owned()));
+        ob = ob.add(CapturedOut::Locate(yx(1, 2)));
+        ob = ob.add(CapturedOut::ShowCursor);
+        ob = ob.add(CapturedOut::SyncNow);
+
+        // Typing characters that do not alter the previous ones is done in place.
+        cb.add_input_chars(" 1");
+        ob = ob.add_write(" ", None);
+        ob = ob.quick_refresh(linecol(1, 3), yx(1, 3));
+        ob = ob.add_write("1", Some(AnsiColor::BrightMagenta));
+        ob = ob.quick_refresh(linecol(1, 4), yx(1, 4));
+
+        // Same for deleting them, until the keyword is broken up.
+        cb.add_input_keys(&[Key::Backspace, Key::Backspace]);
+        for col in [3, 2] {
+            ob = ob.add(CapturedOut::HideCursor);
+            ob = ob.add(CapturedOut::Clear(ClearType::PreviousChar));
+            ob = ob.add(CapturedOut::ShowCursor);
+            ob = ob.quick_refresh(linecol(1, col), yx(1, u16::try_from(col).unwrap()));
+        }
+        cb.add_input_keys(&[Key::Backspace]);
+        ob = ob.add(CapturedOut::HideCursor);
+        ob = ob.add(CapturedOut::Clear(ClearType::PreviousChar));
+        ob = ob.add(CapturedOut::ShowCursor);
+        ob = ob.refresh(linecol(1, 1), &["a = \"b\" ' c", "I"], yx(1, 1));
+
+        run_editor("a = \"b\" ' c\n\n", "a = \"b\" ' c\nI\n", cb, ob);
+    }
+
     #[test]
     fn test_insert_before_previous_content() {
         let mut cb = MockConsole::default();
@@ -1096,7 +1640,13 @@ mod tests {
     fn test_move_down_preserves_insertion_column_with_horizontal_scrolling() {
         let mut cb = MockConsole::default();
         cb.set_size_chars(yx(10, 40));
-        let mut ob = OutputBuilder::new(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40)).set_content(&[
+            "this is a line of text with more than 40 characters",
+            "short",
+            "a",
+            "",
+            "another line of text with more than 40 characters",
+        ]);
         ob = ob.refresh(
             linecol(0, 0),
             &[
@@ -1200,7 +1750,13 @@ mod tests {
     fn test_move_up_preserves_insertion_column_with_horizontal_scrolling() {
         let mut cb = MockConsole::default();
         cb.set_size_chars(yx(10, 40));
-        let mut ob = OutputBuilder::new(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40)).set_content(&[
+            "this is a line of text with more than 40 characters",
+            "",
+            "a",
+            "short",
+            "another line of text with more than 40 characters",
+        ]);
         ob = ob.refresh(
             linecol(0, 0),
             &[
@@ -1321,7 +1877,7 @@ mod tests {
             cb.add_input_keys(&[Key::Char(ch)]);
             ob = ob.set_dirty();
             let mut buf = [0u8; 4];
-            ob = ob.add(CapturedOut::Write(ch.encode_utf8(&mut buf).to_string()));
+            ob = ob.add_write(ch.encode_utf8(&mut buf), Some(AnsiColor::BrightMagenta));
             ob = ob.quick_refresh(linecol(1, col + 1), yx(1, u16::try_from(col + 1).unwrap()));
         }
 
@@ -1617,4 +2173,234 @@ mod tests {
             ob,
         );
     }
+
+    #[test]
+    fn test_block_selection_cut_and_paste() {
+        let mut cb = MockConsole::default();
+        cb.set_size_chars(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40));
+        ob = ob.refresh(linecol(0, 0), &["one", "two", "three"], yx(0, 0));
+
+        // Setting the mark does not select anything yet.
+        cb.add_input_keys(&[Key::Control('k')]);
+        ob = ob.refresh(linecol(0, 0), &["one", "two", "three"], yx(0, 0));
+
+        // Moving the cursor extends the selection, which can span multiple lines.
+        cb.add_input_keys(&[Key::ArrowRight]);
+        ob = ob.set_selection(Some((linecol(0, 0), linecol(0, 1))));
+        ob = ob.refresh(linecol(0, 1), &["one", "two", "three"], yx(0, 1));
+        cb.add_input_keys(&[Key::ArrowDown]);
+        ob = ob.set_selection(Some((linecol(0, 0), linecol(1, 1))));
+        ob = ob.refresh(linecol(1, 1), &["one", "two", "three"], yx(1, 1));
+
+        cb.add_input_keys(&[Key::Control('x')]);
+        ob = ob.set_dirty();
+        ob = ob.set_selection(None);
+        ob = ob.refresh(linecol(0, 0), &["wo", "three"], yx(0, 0));
+
+        cb.add_input_keys(&[Key::End]);
+        ob = ob.quick_refresh(linecol(0, 2), yx(0, 2));
+
+        cb.add_input_keys(&[Key::Control('v')]);
+        ob = ob.refresh(linecol(1, 1), &["woone", "t", "three"], yx(1, 1));
+
+        run_editor("one\ntwo\nthree\n", "woone\nt\nthree\n", cb, ob);
+    }
+
+    #[test]
+    fn test_block_selection_backwards_and_cancel() {
+        let mut cb = MockConsole::default();
+        cb.set_size_chars(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40));
+        ob = ob.refresh(linecol(0, 0), &["abc"], yx(0, 0));
+
+        cb.add_input_keys(&[Key::End, Key::Control('k'), Key::ArrowLeft]);
+        ob = ob.quick_refresh(linecol(0, 3), yx(0, 3));
+        ob = ob.refresh(linecol(0, 3), &["abc"], yx(0, 3));
+        ob = ob.set_selection(Some((linecol(0, 2), linecol(0, 3))));
+        ob = ob.refresh(linecol(0, 2), &["abc"], yx(0, 2));
+
+        // Copying keeps the text intact and clears the selection.
+        cb.add_input_keys(&[Key::Control('y')]);
+        ob = ob.set_selection(None);
+        ob = ob.refresh(linecol(0, 2), &["abc"], yx(0, 2));
+
+        // Escape clears the selection without leaving the editor.
+        cb.add_input_keys(&[Key::Control('k'), Key::ArrowLeft, Key::Escape]);
+        ob = ob.refresh(linecol(0, 2), &["abc"], yx(0, 2));
+        ob = ob.set_selection(Some((linecol(0, 1), linecol(0, 2))));
+        ob = ob.refresh(linecol(0, 1), &["abc"], yx(0, 1));
+        ob = ob.set_selection(None);
+        ob = ob.refresh(linecol(0, 1), &["abc"], yx(0, 1));
+
+        // Typing discards the selection before inserting the character.
+        cb.add_input_keys(&[Key::Control('k'), Key::Home, Key::Control('v')]);
+        ob = ob.refresh(linecol(0, 1), &["abc"], yx(0, 1));
+        ob = ob.set_selection(Some((linecol(0, 0), linecol(0, 1))));
+        ob = ob.refresh(linecol(0, 0), &["abc"], yx(0, 0));
+        ob = ob.set_dirty();
+        ob = ob.set_selection(None);
+        ob = ob.refresh(linecol(0, 1), &["cabc"], yx(0, 1));
+
+        run_editor("abc\n", "cabc\n", cb, ob);
+    }
+
+    #[test]
+    fn test_cut_and_copy_whole_lines() {
+        let mut cb = MockConsole::default();
+        cb.set_size_chars(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40));
+        ob = ob.refresh(linecol(0, 0), &["a", "b"], yx(0, 0));
+
+        cb.add_input_keys(&[Key::Control('x')]);
+        ob = ob.set_dirty();
+        ob = ob.refresh(linecol(0, 0), &["b"], yx(0, 0));
+
+        cb.add_input_keys(&[Key::Control('v')]);
+        ob = ob.refresh(linecol(1, 0), &["a", "b"], yx(1, 0));
+
+        cb.add_input_keys(&[Key::Control('y')]);
+        ob = ob.quick_refresh(linecol(1, 0), yx(1, 0));
+
+        cb.add_input_keys(&[Key::Control('v')]);
+        ob = ob.refresh(linecol(2, 0), &["a", "b", "b"], yx(2, 0));
+
+        // Cutting the last line removes the newline of the previous one.
+        cb.add_input_keys(&[Key::Control('x')]);
+        ob = ob.refresh(linecol(1, 1), &["a", "b"], yx(1, 1));
+
+        run_editor("a\nb\n", "a\nb\n", cb, ob);
+    }
+
+    #[test]
+    fn test_copy_exports_to_system_clipboard() {
+        let mut cb = MockConsole::default();
+        cb.set_size_chars(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40));
+        ob = ob.refresh(linecol(0, 0), &["a", "b"], yx(0, 0));
+
+        cb.add_input_keys(&[Key::Control('y')]);
+        ob = ob.quick_refresh(linecol(0, 0), yx(0, 0));
+
+        let mut editor = Editor::default();
+        editor.load(Some(TEST_FILENAME), "a\nb\n");
+        cb.add_input_keys(&[Key::Escape]);
+        block_on(editor.edit(&mut cb)).unwrap();
+        assert_eq!(ob.build(), cb.captured_out());
+        assert_eq!("a\n", cb.clipboard());
+    }
+
+    #[test]
+    fn test_paste_from_system_clipboard() {
+        let mut cb = MockConsole::default();
+        cb.set_size_chars(yx(10, 40));
+        cb.set_clipboard("x\r\ny");
+        let mut ob = OutputBuilder::new(yx(10, 40));
+        ob = ob.refresh(linecol(0, 0), &["ab"], yx(0, 0));
+
+        cb.add_input_keys(&[Key::ArrowRight, Key::Control('v')]);
+        ob = ob.quick_refresh(linecol(0, 1), yx(0, 1));
+        ob = ob.set_dirty();
+        ob = ob.refresh(linecol(1, 1), &["ax", "yb"], yx(1, 1));
+
+        run_editor("ab\n", "ax\nyb\n", cb, ob);
+    }
+
+    #[test]
+    fn test_goto_line() {
+        let mut cb = MockConsole::default();
+        cb.set_size_chars(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40));
+        ob = ob.refresh(linecol(0, 0), &["a", "b", "c"], yx(0, 0));
+
+        cb.add_input_keys(&[Key::Control('g')]);
+        ob = ob.prompt("Go to line", "");
+        cb.add_input_chars("2");
+        ob = ob.prompt("Go to line", "2");
+        cb.add_input_keys(&[Key::NewLine]);
+        ob = ob.quick_refresh(linecol(1, 0), yx(1, 0));
+
+        // Lines past the end of the file go to the last line.
+        cb.add_input_keys(&[Key::Control('g')]);
+        ob = ob.prompt("Go to line", "");
+        cb.add_input_chars("9");
+        ob = ob.prompt("Go to line", "9");
+        cb.add_input_keys(&[Key::NewLine]);
+        ob = ob.quick_refresh(linecol(2, 0), yx(2, 0));
+
+        // Invalid lines and cancellations do not move the cursor.
+        cb.add_input_keys(&[Key::Control('g')]);
+        ob = ob.prompt("Go to line", "");
+        cb.add_input_chars("x");
+        ob = ob.prompt("Go to line", "x");
+        cb.add_input_keys(&[Key::NewLine]);
+        ob = ob.quick_refresh(linecol(2, 0), yx(2, 0));
+        cb.add_input_keys(&[Key::Control('g')]);
+        ob = ob.prompt("Go to line", "");
+        cb.add_input_chars("1");
+        ob = ob.prompt("Go to line", "1");
+        cb.add_input_keys(&[Key::Escape]);
+        ob = ob.quick_refresh(linecol(2, 0), yx(2, 0));
+
+        run_editor("a\nb\nc\n", "a\nb\nc\n", cb, ob);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut cb = MockConsole::default();
+        cb.set_size_chars(yx(10, 40));
+        let mut ob = OutputBuilder::new(yx(10, 40));
+        ob = ob.refresh(linecol(0, 0), &["foo bar", "BAR baz"], yx(0, 0));
+
+        cb.add_input_keys(&[Key::Control('f')]);
+        ob = ob.prompt("Find", "");
+        cb.add_input_chars("bax");
+        ob = ob.prompt("Find", "b");
+        ob = ob.prompt("Find", "ba");
+        ob = ob.prompt("Find", "bax");
+        cb.add_input_keys(&[Key::Backspace]);
+        ob = ob.prompt("Find", "ba");
+        cb.add_input_chars("r");
+        ob = ob.prompt("Find", "bar");
+        cb.add_input_keys(&[Key::NewLine]);
+        ob = ob.set_selection(Some((linecol(0, 4), linecol(0, 7))));
+        ob = ob.refresh(linecol(0, 7), &["foo bar", "BAR baz"], yx(0, 7));
+
+        // Searching again reuses the previous text, ignores case and wraps around.
+        cb.add_input_keys(&[Key::Control('f')]);
+        ob = ob.prompt("Find", "bar");
+        cb.add_input_keys(&[Key::NewLine]);
+        ob = ob.set_selection(Some((linecol(1, 0), linecol(1, 3))));
+        ob = ob.refresh(linecol(1, 3), &["foo bar", "BAR baz"], yx(1, 3));
+        cb.add_input_keys(&[Key::Control('f')]);
+        ob = ob.prompt("Find", "bar");
+        cb.add_input_keys(&[Key::NewLine]);
+        ob = ob.set_selection(Some((linecol(0, 4), linecol(0, 7))));
+        ob = ob.refresh(linecol(0, 7), &["foo bar", "BAR baz"], yx(0, 7));
+
+        // Failed searches leave the cursor and the selection alone.
+        cb.add_input_keys(&[Key::Control('f')]);
+        ob = ob.prompt("Find", "bar");
+        cb.add_input_chars("x");
+        ob = ob.prompt("Find", "barx");
+        cb.add_input_keys(&[Key::NewLine]);
+        ob = ob.refresh(linecol(0, 7), &["foo bar", "BAR baz"], yx(0, 7));
+
+        cb.add_input_keys(&[Key::Escape]);
+        ob = ob.set_selection(None);
+        ob = ob.refresh(linecol(0, 7), &["foo bar", "BAR baz"], yx(0, 7));
+
+        run_editor("foo bar\nBAR baz\n", "foo bar\nBAR baz\n", cb, ob);
+    }
+
+    #[test]
+    fn test_find_in_line() {
+        let line = "Hello hello".chars().collect::<Vec<char>>();
+        let query = |s: &str| s.chars().collect::<Vec<char>>();
+        assert_eq!(Some(0), find_in_line(&line, &query("hello"), 0));
+        assert_eq!(Some(6), find_in_line(&line, &query("HELLO"), 1));
+        assert_eq!(None, find_in_line(&line, &query("hello"), 7));
+        assert_eq!(None, find_in_line(&line, &query(""), 0));
+        assert_eq!(None, find_in_line(&line, &query("Hello hello!"), 0));
+    }
 }
@@ -24,6 +24,8 @@
 
 use endbasic_core::exec::{Machine, StopReason};
 use endbasic_std::console::{self, refill_and_print, Console};
+use endbasic_std::http::Client;
+use endbasic_std::i18n::translate;
 use endbasic_std::program::{continue_if_modified, Program, BREAK_MSG};
 use endbasic_std::storage::Storage;
 use std::cell::RefCell;
@@ -41,6 +43,7 @@ pub fn print_welcome(console: Rc<RefCell<dyn Console>>) -> io::Result<()> {
     console.print("    Copyright 2020-2022 Julio Merino")?;
     console.print("")?;
     console.print("    Type HELP for interactive usage information.")?;
+    console.print("    Type DEMOS to browse and load the bundled demo programs.")?;
     console.print("")?;
     Ok(())
 }
@@ -75,15 +78,16 @@ pub async fn try_load_autoexec(
 }
 
 /// Loads the program given in `username_path` pair (which is of the form `user/path`) from the
-/// cloud and executes it on the `machine`.
-pub async fn run_from_cloud(
-    machine: &mut Machine,
+/// cloud into `program`.
+///
+/// Returns false if `username_path` is malformed, in which case the problem has already been
+/// reported to the `console`.
+pub async fn load_from_cloud(
     console: Rc<RefCell<dyn Console>>,
     storage: Rc<RefCell<Storage>>,
     program: Rc<RefCell<dyn Program>>,
     username_path: &str,
-    will_run_repl: bool,
-) -> endbasic_core::exec::Result<i32> {
+) -> io::Result<bool> {
     let (fs_uri, path) = match username_path.split_once('/') {
         Some((username, path)) => (format!("cloud://{}", username), format!("AUTORUN:/{}", path)),
         None => {
@@ -92,18 +96,79 @@ pub async fn run_from_cloud(
                 "Invalid program to run '{}'; must be of the form 'username/path'",
                 username_path
             ))?;
-            return Ok(1);
+            return Ok(false);
         }
     };
 
     console.borrow_mut().print(&format!("Mounting {} as AUTORUN...", fs_uri))?;
-    storage.borrow_mut().mount("AUTORUN", &fs_uri)?;
+    storage.borrow_mut().mount("AUTORUN", &fs_uri, false)?;
     storage.borrow_mut().cd("AUTORUN:/")?;
 
     console.borrow_mut().print(&format!("Loading {}...", path))?;
     let content = storage.borrow().get(&path).await?;
     program.borrow_mut().load(Some(&path), &content);
+    Ok(true)
+}
+
+/// Loads the program given in `username_path` pair (which is of the form `user/path`) from the
+/// cloud and executes it on the `machine`.
+pub async fn run_from_cloud(
+    machine: &mut Machine,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+    program: Rc<RefCell<dyn Program>>,
+    username_path: &str,
+    will_run_repl: bool,
+) -> endbasic_core::exec::Result<i32> {
+    if !load_from_cloud(console.clone(), storage, program, username_path).await? {
+        return Ok(1);
+    }
+    run_loaded(machine, console, will_run_repl).await
+}
 
+/// Loads the program served at `url` into `program` by fetching it with `client`.
+///
+/// The caller is responsible for ensuring that `url` is safe to access.
+pub async fn load_from_url(
+    console: Rc<RefCell<dyn Console>>,
+    program: Rc<RefCell<dyn Program>>,
+    client: &dyn Client,
+    url: &str,
+) -> io::Result<()> {
+    console.borrow_mut().print(&format!("Loading {}...", url))?;
+    let response = client.get(url).await?;
+    if !(200..300).contains(&response.status) {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Server returned HTTP status {}", response.status),
+        ));
+    }
+    program.borrow_mut().load(None, &response.body);
+    Ok(())
+}
+
+/// Loads the program served at `url` by fetching it with `client` and executes it on the
+/// `machine`.
+///
+/// The caller is responsible for ensuring that `url` is safe to access.
+pub async fn run_from_url(
+    machine: &mut Machine,
+    console: Rc<RefCell<dyn Console>>,
+    program: Rc<RefCell<dyn Program>>,
+    client: &dyn Client,
+    url: &str,
+    will_run_repl: bool,
+) -> endbasic_core::exec::Result<i32> {
+    load_from_url(console.clone(), program, client, url).await?;
+    run_loaded(machine, console, will_run_repl).await
+}
+
+/// Executes the program that was just loaded into the `machine` and reports how it terminated.
+pub async fn run_loaded(
+    machine: &mut Machine,
+    console: Rc<RefCell<dyn Console>>,
+    will_run_repl: bool,
+) -> endbasic_core::exec::Result<i32> {
     console.borrow_mut().print("Starting...")?;
     console.borrow_mut().print("")?;
 
@@ -113,7 +178,7 @@ pub async fn run_from_cloud(
 
     console.print("")?;
     let code = match result {
-        Ok(r @ StopReason::Eof) => {
+        Ok(r @ StopReason::Eof) | Ok(r @ StopReason::Paused(_)) => {
             console.print("**** Program exited due to EOF ****")?;
             r.as_exit_code()
         }
@@ -126,8 +191,12 @@ pub async fn run_from_cloud(
             console.print("**** Program stopped due to BREAK ****")?;
             r.as_exit_code()
         }
+        Ok(r @ StopReason::LimitExceeded(limit)) => {
+            console.print(&format!("**** Program stopped due to {} limit ****", limit))?;
+            r.as_exit_code()
+        }
         Err(e) => {
-            console.print(&format!("**** ERROR: {} ****", e))?;
+            console.print(&translate(&format!("**** ERROR: {} ****", e)))?;
             1
         }
     };
@@ -166,9 +235,9 @@ pub async fn run_repl_loop(
         let line = {
             let mut console = console.borrow_mut();
             if console.is_interactive() {
-                console.print("Ready")?;
+                console.print(&translate("Ready"))?;
             }
-            console::read_line(&mut *console, "", "", Some(&mut history)).await
+            console::read_code_line(&mut *console, "", "", Some(&mut history)).await
         };
 
         // Any signals entered during console input should not impact upcoming execution.  Drain
@@ -180,19 +249,19 @@ pub async fn run_repl_loop(
                 Ok(reason) => stop_reason = reason,
                 Err(e) => {
                     let mut console = console.borrow_mut();
-                    console.print(format!("ERROR: {}", e).as_str())?;
+                    console.print(&translate(&format!("ERROR: {}", e)))?;
                 }
             },
             Err(e) => {
                 if e.kind() == io::ErrorKind::Interrupted {
                     let mut console = console.borrow_mut();
-                    console.print(BREAK_MSG)?;
+                    console.print(&translate(BREAK_MSG))?;
                     // Do not exit the interpreter.  Other REPLs, such as Python's, do not do so,
                     // and it is actually pretty annoying to exit the REPL when one may be furiously
                     // pressing CTRL+C to stop a program inside of it.
                 } else if e.kind() == io::ErrorKind::UnexpectedEof {
                     let mut console = console.borrow_mut();
-                    console.print("End of input by CTRL-D")?;
+                    console.print(&translate("End of input by CTRL-D"))?;
                     stop_reason = StopReason::Exited(0);
                 } else {
                     stop_reason = StopReason::Exited(1);
@@ -201,9 +270,16 @@ pub async fn run_repl_loop(
         }
 
         match stop_reason {
-            StopReason::Eof => (),
+            // Interactive commands are not subject to debugging, so we cannot get a pause here.
+            StopReason::Eof | StopReason::Paused(_) => (),
             StopReason::Break => {
-                console.borrow_mut().print("**** BREAK ****")?;
+                console.borrow_mut().print(&translate("**** BREAK ****"))?;
+                stop_reason = StopReason::Eof;
+            }
+            StopReason::LimitExceeded(limit) => {
+                console
+                    .borrow_mut()
+                    .print(&format!("**** Program stopped due to {} limit ****", limit))?;
                 stop_reason = StopReason::Eof;
             }
             StopReason::Exited(_) => {
@@ -220,10 +296,12 @@ pub async fn run_repl_loop(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
     use endbasic_core::exec::Signal;
     use endbasic_std::console::Key;
+    use endbasic_std::http::Response;
     use endbasic_std::storage::{Drive, DriveFactory, InMemoryDrive};
-    use endbasic_std::testutils::*;
+    use endbasic_testutils::*;
     use futures_lite::future::block_on;
 
     #[test]
@@ -367,6 +445,84 @@ mod tests {
         assert!(output.contains("You are now being dropped into"));
     }
 
+    /// HTTP client that serves a fixed program from a single URL.
+    struct MockClient {}
+
+    impl MockClient {
+        /// URL from which the program is served.
+        const URL: &'static str = "https://example.com/program.bas";
+
+        /// Verbatim contents of the served program.
+        const SCRIPT: &'static str = r#"PRINT "Fetched""#;
+    }
+
+    #[async_trait(?Send)]
+    impl Client for MockClient {
+        async fn get(&self, url: &str) -> io::Result<Response> {
+            if url == Self::URL {
+                Ok(Response { status: 200, body: Self::SCRIPT.to_owned(), ..Default::default() })
+            } else {
+                Ok(Response { status: 404, ..Default::default() })
+            }
+        }
+
+        async fn post(
+            &self,
+            _url: &str,
+            _content_type: &str,
+            _body: String,
+        ) -> io::Result<Response> {
+            unreachable!("Programs are only fetched via GET");
+        }
+    }
+
+    #[test]
+    fn test_load_from_url_ok() {
+        let mut tester = Tester::default();
+        let (console, program) = (tester.get_console(), tester.get_program());
+
+        block_on(load_from_url(console, program, &MockClient {}, MockClient::URL)).unwrap();
+        tester
+            .run("")
+            .expect_prints(["Loading https://example.com/program.bas..."])
+            .expect_program(None as Option<&str>, MockClient::SCRIPT)
+            .check();
+    }
+
+    #[test]
+    fn test_load_from_url_http_error() {
+        let mut tester = Tester::default();
+        let (console, program) = (tester.get_console(), tester.get_program());
+
+        let url = "https://example.com/missing.bas";
+        let err = block_on(load_from_url(console, program, &MockClient {}, url)).unwrap_err();
+        assert_eq!("Server returned HTTP status 404", err.to_string());
+        tester.run("").expect_prints(["Loading https://example.com/missing.bas..."]).check();
+    }
+
+    #[test]
+    fn test_run_from_url_no_repl() {
+        let mut tester = Tester::default();
+        let (console, program) = (tester.get_console(), tester.get_program());
+
+        block_on(run_from_url(
+            tester.get_machine(),
+            console,
+            program,
+            &MockClient {},
+            MockClient::URL,
+            false,
+        ))
+        .unwrap();
+        tester
+            .run("")
+            .expect_prints(["Loading https://example.com/program.bas...", "Starting...", ""])
+            .expect_clear()
+            .expect_prints(["Fetched", "", "**** Program exited due to EOF ****"])
+            .expect_program(None as Option<&str>, MockClient::SCRIPT)
+            .check();
+    }
+
     #[test]
     fn test_run_repl_loop_signal_before_exec() {
         let mut tester = Tester::default();
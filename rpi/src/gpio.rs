@@ -15,17 +15,21 @@
 
 //! GPIO implementation for the Raspberry Pi.
 
+use async_channel::Sender;
+use endbasic_core::exec::Signal;
 use endbasic_std::gpio::{Pin, PinMode, Pins};
 use rppal::gpio;
 use std::collections::HashMap;
 use std::io;
 
 /// Implementation of the EndBASIC GPIO operations for a Raspberry Pi using the rppal library.
-#[derive(Default)]
 pub struct RppalPins {
     chip: Option<gpio::Gpio>,
     inputs: HashMap<Pin, gpio::InputPin>,
     outputs: HashMap<Pin, gpio::OutputPin>,
+
+    /// Channel on which to deliver edges detected on input pins for `ON GPIO` handlers.
+    signals_tx: Sender<Signal>,
 }
 
 /// Converts a `gpio::Error` to an `io::Error`.
@@ -41,6 +45,11 @@ fn gpio_error_to_io_error(e: gpio::Error) -> io::Error {
 }
 
 impl RppalPins {
+    /// Creates a new set of pins that reports edges on input pins via `signals_tx`.
+    pub fn new(signals_tx: Sender<Signal>) -> Self {
+        Self { chip: None, inputs: HashMap::default(), outputs: HashMap::default(), signals_tx }
+    }
+
     /// Gets access to the default GPIO chip and lazily opens it if not yet open.
     fn get_chip(&mut self) -> io::Result<&mut gpio::Gpio> {
         if self.chip.is_none() {
@@ -55,20 +64,26 @@ impl Pins for RppalPins {
         self.clear(pin)?;
         let chip = self.get_chip()?;
         let gpio_pin = chip.get(pin.0).map_err(gpio_error_to_io_error)?;
-        match mode {
-            PinMode::In => {
-                self.inputs.insert(pin, gpio_pin.into_input());
-            }
-            PinMode::InPullDown => {
-                self.inputs.insert(pin, gpio_pin.into_input_pulldown());
-            }
-            PinMode::InPullUp => {
-                self.inputs.insert(pin, gpio_pin.into_input_pullup());
-            }
+        let mut input = match mode {
+            PinMode::In => gpio_pin.into_input(),
+            PinMode::InPullDown => gpio_pin.into_input_pulldown(),
+            PinMode::InPullUp => gpio_pin.into_input_pullup(),
             PinMode::Out => {
                 self.outputs.insert(pin, gpio_pin.into_output());
+                return Ok(());
             }
         };
+
+        // Watch all input pins for edges so that `ON GPIO` handlers can be installed at any time
+        // without reconfiguring the pin.  Edges on pins without handlers are discarded by the
+        // machine.
+        let signals_tx = self.signals_tx.clone();
+        input
+            .set_async_interrupt(gpio::Trigger::Both, move |level| {
+                let _ = signals_tx.try_send(Signal::Gpio(pin.0, level == gpio::Level::High));
+            })
+            .map_err(gpio_error_to_io_error)?;
+        self.inputs.insert(pin, input);
         Ok(())
     }
 
@@ -106,6 +121,7 @@ impl Pins for RppalPins {
             ));
         }
         let pin = self.outputs.get_mut(&pin).unwrap();
+        pin.clear_pwm().map_err(gpio_error_to_io_error)?;
         if v {
             pin.write(gpio::Level::High);
         } else {
@@ -113,4 +129,15 @@ impl Pins for RppalPins {
         }
         Ok(())
     }
+
+    fn pwm(&mut self, pin: Pin, frequency: f64, duty_cycle: f64) -> io::Result<()> {
+        if self.inputs.contains_key(&pin) || !self.outputs.contains_key(&pin) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "Pin not configured for PWM; use GPIO_SETUP first",
+            ));
+        }
+        let pin = self.outputs.get_mut(&pin).unwrap();
+        pin.set_pwm_frequency(frequency, duty_cycle).map_err(gpio_error_to_io_error)
+    }
 }
@@ -0,0 +1,88 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! I2C implementation for the Raspberry Pi.
+
+use endbasic_std::i2c::Bus;
+use rppal::i2c;
+use std::io;
+
+/// Implementation of the EndBASIC I2C operations for a Raspberry Pi using the rppal library.
+#[derive(Default)]
+pub struct RppalI2cBus {
+    device: Option<i2c::I2c>,
+}
+
+/// Converts an `i2c::Error` to an `io::Error`.
+fn i2c_error_to_io_error(e: i2c::Error) -> io::Error {
+    match e {
+        i2c::Error::Io(e) => e,
+        i2c::Error::InvalidSlaveAddress(address) => io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid I2C device address {}", address),
+        ),
+        e => io::Error::new(io::ErrorKind::Other, e.to_string()),
+    }
+}
+
+impl RppalI2cBus {
+    /// Gets access to the open device.
+    fn get_device(&mut self) -> io::Result<&mut i2c::I2c> {
+        match self.device.as_mut() {
+            Some(device) => Ok(device),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "I2C device not open; use I2C_OPEN first",
+            )),
+        }
+    }
+}
+
+impl Bus for RppalI2cBus {
+    fn open(&mut self, bus: u8, address: u16) -> io::Result<()> {
+        self.device = None;
+        let mut device = i2c::I2c::with_bus(bus).map_err(i2c_error_to_io_error)?;
+        device.set_slave_address(address).map_err(i2c_error_to_io_error)?;
+        self.device = Some(device);
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.device = None;
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        let n = self.get_device()?.read(buffer).map_err(i2c_error_to_io_error)?;
+        if n != buffer.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Read {} bytes from the I2C device but wanted {}", n, buffer.len()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let n = self.get_device()?.write(data).map_err(i2c_error_to_io_error)?;
+        if n != data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                format!("Wrote {} bytes to the I2C device but wanted {}", n, data.len()),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -24,3 +24,7 @@
 
 mod gpio;
 pub use gpio::RppalPins;
+mod i2c;
+pub use i2c::RppalI2cBus;
+mod spi;
+pub use spi::RppalSpiBus;
@@ -0,0 +1,120 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! SPI implementation for the Raspberry Pi.
+
+use endbasic_std::spi::Bus;
+use rppal::spi;
+use std::io;
+
+/// Implementation of the EndBASIC SPI operations for a Raspberry Pi using the rppal library.
+#[derive(Default)]
+pub struct RppalSpiBus {
+    device: Option<spi::Spi>,
+}
+
+/// Converts an `spi::Error` to an `io::Error`.
+fn spi_error_to_io_error(e: spi::Error) -> io::Error {
+    match e {
+        spi::Error::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::Other, e.to_string()),
+    }
+}
+
+impl RppalSpiBus {
+    /// Gets access to the open device.
+    fn get_device(&mut self) -> io::Result<&mut spi::Spi> {
+        match self.device.as_mut() {
+            Some(device) => Ok(device),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "SPI device not open; use SPI_OPEN first",
+            )),
+        }
+    }
+}
+
+impl Bus for RppalSpiBus {
+    fn open(&mut self, bus: u8, device: u8, clock_hz: u32, mode: u8) -> io::Result<()> {
+        let bus = match bus {
+            0 => spi::Bus::Spi0,
+            1 => spi::Bus::Spi1,
+            2 => spi::Bus::Spi2,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown SPI bus {}", bus),
+                ))
+            }
+        };
+        let slave_select = match device {
+            0 => spi::SlaveSelect::Ss0,
+            1 => spi::SlaveSelect::Ss1,
+            2 => spi::SlaveSelect::Ss2,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown SPI device {}", device),
+                ))
+            }
+        };
+        let mode = match mode {
+            0 => spi::Mode::Mode0,
+            1 => spi::Mode::Mode1,
+            2 => spi::Mode::Mode2,
+            3 => spi::Mode::Mode3,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown SPI mode {}", mode),
+                ))
+            }
+        };
+
+        self.device = None;
+        self.device =
+            Some(spi::Spi::new(bus, slave_select, clock_hz, mode).map_err(spi_error_to_io_error)?);
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.device = None;
+        Ok(())
+    }
+
+    fn transfer(&mut self, data: &mut [u8]) -> io::Result<()> {
+        let device = self.get_device()?;
+        let write_buffer = data.to_vec();
+        let n = device.transfer(data, &write_buffer).map_err(spi_error_to_io_error)?;
+        if n != data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Transferred {} bytes with the SPI device but wanted {}", n, data.len()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let n = self.get_device()?.write(data).map_err(spi_error_to_io_error)?;
+        if n != data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                format!("Wrote {} bytes to the SPI device but wanted {}", n, data.len()),
+            ));
+        }
+        Ok(())
+    }
+}
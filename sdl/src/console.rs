@@ -15,13 +15,14 @@
 
 //! Implementation of the EndBASIC console using SDL.
 
-use crate::host::{self, Request, Response};
+use crate::host::{self, Request, Response, WindowSettings};
 use crate::spec::Resolution;
 use async_channel::Sender;
 use async_trait::async_trait;
 use endbasic_core::exec::Signal;
 use endbasic_std::console::{
-    remove_control_chars, CharsXY, ClearType, Console, Key, PixelsXY, SizeInPixels,
+    remove_control_chars, rgb_to_ansi_color, CharsXY, ClearType, Console, Key, KeyModifiers,
+    PixelsXY, SizeInPixels, TextAttributes, RGB,
 };
 use std::io;
 use std::path::PathBuf;
@@ -39,35 +40,32 @@ pub(crate) struct SdlConsole {
     on_key_rx: Receiver<Key>,
     fg_color: Option<u8>,
     bg_color: Option<u8>,
+    attributes: TextAttributes,
 }
 
 impl SdlConsole {
     /// Initializes a new SDL console.
     ///
     /// The console is sized to `resolution` pixels.  Also loads the desired font from
-    /// `font_path` at `font_size` and uses it to calculate the size of the console in characters.
+    /// `font_path` (or the default font if none) at `font_size` and uses it to calculate the size
+    /// of the console in characters.
+    /// If `vsync` is true, explicit video syncs are paced to the refresh rate of the display.
     ///
     /// There can only be one active `SdlConsole` at any given time given that this initializes and
     /// owns the SDL context.
     pub(crate) fn new(
         resolution: Resolution,
-        font_path: PathBuf,
+        font_path: Option<PathBuf>,
         font_size: u16,
+        vsync: bool,
         signals_tx: Sender<Signal>,
     ) -> io::Result<Self> {
         let (request_tx, request_rx) = mpsc::sync_channel(1);
         let (response_tx, response_rx) = mpsc::sync_channel(1);
         let (on_key_tx, on_key_rx) = mpsc::channel();
         let handle = thread::spawn(move || {
-            host::run(
-                resolution,
-                font_path,
-                font_size,
-                request_rx,
-                response_tx,
-                on_key_tx,
-                signals_tx,
-            );
+            let settings = WindowSettings { resolution, font_path, font_size, vsync };
+            host::run(settings, request_rx, response_tx, on_key_tx, signals_tx);
         });
 
         // Wait for the console to be up and running.  We must do this for error propagation but
@@ -80,6 +78,7 @@ impl SdlConsole {
                 on_key_rx,
                 fg_color: None,
                 bg_color: None,
+                attributes: TextAttributes::default(),
             }),
             Response::Empty(Err(e)) => Err(e),
             r => panic!("Unexpected response {:?}", r),
@@ -126,6 +125,31 @@ impl Console for SdlConsole {
         Ok(())
     }
 
+    fn set_color_rgb(&mut self, fg: Option<RGB>, bg: Option<RGB>) -> io::Result<()> {
+        self.call(Request::SetColorRgb(fg, bg))?;
+        self.fg_color = fg.map(rgb_to_ansi_color);
+        self.bg_color = bg.map(rgb_to_ansi_color);
+        Ok(())
+    }
+
+    fn attributes(&self) -> TextAttributes {
+        self.attributes
+    }
+
+    fn set_attributes(&mut self, attributes: TextAttributes) -> io::Result<()> {
+        self.call(Request::SetAttributes(attributes))?;
+        self.attributes = attributes;
+        Ok(())
+    }
+
+    fn cursor_pos(&self) -> io::Result<CharsXY> {
+        self.request_tx.send(Request::CursorPos).expect("Channel must be alive");
+        match self.response_rx.recv().expect("Channel must be alive") {
+            Response::CursorPos(pos) => Ok(pos),
+            _ => panic!("Unexpected response type"),
+        }
+    }
+
     fn enter_alt(&mut self) -> io::Result<()> {
         self.call(Request::EnterAlt)
     }
@@ -138,6 +162,22 @@ impl Console for SdlConsole {
         true
     }
 
+    fn is_key_down(&mut self, key: &Key) -> io::Result<bool> {
+        self.request_tx.send(Request::IsKeyDown(key.clone())).expect("Channel must be alive");
+        match self.response_rx.recv().expect("Channel must be alive") {
+            Response::IsKeyDown(down) => Ok(down),
+            _ => panic!("Unexpected response type"),
+        }
+    }
+
+    fn key_modifiers(&mut self) -> io::Result<KeyModifiers> {
+        self.request_tx.send(Request::KeyModifiers).expect("Channel must be alive");
+        match self.response_rx.recv().expect("Channel must be alive") {
+            Response::KeyModifiers(modifiers) => Ok(modifiers),
+            _ => panic!("Unexpected response type"),
+        }
+    }
+
     fn leave_alt(&mut self) -> io::Result<()> {
         self.call(Request::LeaveAlt)
     }
@@ -192,6 +232,10 @@ impl Console for SdlConsole {
         self.call(Request::Write(text))
     }
 
+    fn draw_arc(&mut self, center: PixelsXY, radius: u16, start: i32, end: i32) -> io::Result<()> {
+        self.call(Request::DrawArc(center, radius, start, end))
+    }
+
     fn draw_circle(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
         self.call(Request::DrawCircle(center, radius))
     }
@@ -200,6 +244,14 @@ impl Console for SdlConsole {
         self.call(Request::DrawCircleFilled(center, radius))
     }
 
+    fn draw_ellipse(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.call(Request::DrawEllipse(center, rx, ry))
+    }
+
+    fn draw_ellipse_filled(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.call(Request::DrawEllipseFilled(center, rx, ry))
+    }
+
     fn draw_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
         self.call(Request::DrawLine(x1y1, x2y2))
     }
@@ -208,6 +260,14 @@ impl Console for SdlConsole {
         self.call(Request::DrawPixel(xy))
     }
 
+    fn draw_poly(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.call(Request::DrawPoly(points.to_vec()))
+    }
+
+    fn draw_poly_filled(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.call(Request::DrawPolyFilled(points.to_vec()))
+    }
+
     fn draw_rect(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
         self.call(Request::DrawRect(x1y1, x2y2))
     }
@@ -216,6 +276,66 @@ impl Console for SdlConsole {
         self.call(Request::DrawRectFilled(x1y1, x2y2))
     }
 
+    fn draw_text(&mut self, xy: PixelsXY, text: &str, size: Option<u16>) -> io::Result<()> {
+        self.call(Request::DrawText(xy, text.to_owned(), size))
+    }
+
+    fn flood_fill(&mut self, xy: PixelsXY, border: Option<RGB>) -> io::Result<()> {
+        self.call(Request::FloodFill(xy, border))
+    }
+
+    fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+        self.request_tx.send(Request::ReadPixel(xy)).expect("Channel must be alive");
+        match self.response_rx.recv().expect("Channel must be alive") {
+            Response::ReadPixel(result) => result,
+            _ => panic!("Unexpected response type"),
+        }
+    }
+
+    fn set_clip(&mut self, area: Option<(PixelsXY, PixelsXY)>) -> io::Result<()> {
+        self.call(Request::SetClip(area))
+    }
+
+    fn set_draw_page(&mut self, page: u8) -> io::Result<()> {
+        self.call(Request::SetDrawPage(page))
+    }
+
+    fn flip_page(&mut self, page: u8) -> io::Result<()> {
+        self.call(Request::FlipPage(page))
+    }
+
+    fn set_font(&mut self, face: Option<&str>, size: u16) -> io::Result<()> {
+        self.call(Request::SetFont(face.map(str::to_owned), size))
+    }
+
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        self.call(Request::SetTitle(title.to_owned()))
+    }
+
+    fn save_screen(&mut self) -> io::Result<()> {
+        self.call(Request::SaveScreen)
+    }
+
+    fn restore_screen(&mut self) -> io::Result<()> {
+        self.call(Request::RestoreScreen)
+    }
+
+    async fn clipboard_get(&mut self) -> io::Result<String> {
+        self.request_tx.send(Request::ClipboardGet).expect("Channel must be alive");
+        match self.response_rx.recv().expect("Channel must be alive") {
+            Response::ClipboardGet(result) => result,
+            _ => panic!("Unexpected response type"),
+        }
+    }
+
+    async fn clipboard_set(&mut self, text: &str) -> io::Result<()> {
+        self.call(Request::ClipboardSet(text.to_owned()))
+    }
+
+    fn set_fullscreen(&mut self, enabled: bool) -> io::Result<()> {
+        self.call(Request::SetFullscreen(enabled))
+    }
+
     fn sync_now(&mut self) -> io::Result<()> {
         self.call(Request::SyncNow)
     }
@@ -296,8 +416,9 @@ mod testutils {
             let signals_chan = async_channel::unbounded();
             let console = SdlConsole::new(
                 Resolution::windowed(800, 600).unwrap(),
-                src_path("sdl/src/IBMPlexMono-Regular-6.0.0.ttf"),
+                Some(src_path("sdl/src/IBMPlexMono-Regular-6.0.0.ttf")),
                 16,
+                false,
                 signals_chan.0,
             )
             .unwrap();
@@ -540,6 +661,27 @@ mod tests {
         test.verify("sdl-leave-alt");
     }
 
+    #[test]
+    #[ignore = "Requires a graphical environment"]
+    fn test_sdl_console_save_and_restore_screen() {
+        let mut test = SdlTest::new();
+
+        test.console().print("Before entering the alternate console").unwrap();
+        test.console().save_screen().unwrap();
+        test.console().print("Drawn on top of the saved screen").unwrap();
+        test.console().save_screen().unwrap();
+        test.console().clear(ClearType::All).unwrap();
+        test.console().restore_screen().unwrap();
+        test.console().restore_screen().unwrap();
+        assert_eq!(
+            io::ErrorKind::InvalidInput,
+            test.console().restore_screen().unwrap_err().kind()
+        );
+
+        // Restoring the screen must yield the same results as leaving the alternate console.
+        test.verify("sdl-leave-alt");
+    }
+
     /// Synthesizes an `Event::KeyDown` event for a single key press.
     fn key_down(keycode: Keycode, keymod: Mod) -> Event {
         Event::KeyDown {
@@ -18,11 +18,15 @@
 use crate::string_error_to_io_error;
 use endbasic_std::console::{CharsXY, SizeInPixels};
 use once_cell::sync::Lazy;
+use sdl2::rwops::RWops;
 use sdl2::ttf::{Font, FontError, InitError, Sdl2TtfContext};
 use std::convert::TryFrom;
 use std::io;
 use std::path::Path;
 
+/// Font to use when none is provided.
+const DEFAULT_FONT_BYTES: &[u8] = include_bytes!("IBMPlexMono-Regular-6.0.0.ttf");
+
 /// Global instance of the SDL TTF font loader.  Trying to deal with the lifetime of the derived
 /// fonts seems to be incredibly hard because of how we hide the `SdlConsole` implementation behind
 /// the `Console` trait.  It might be possible to do this in a better way, but for now, keeping the
@@ -55,17 +59,26 @@ pub(crate) struct MonospacedFont<'a> {
 }
 
 impl<'a> MonospacedFont<'a> {
-    /// Loads the font from the file `path` with `point_size`.  If the loaded font is not
-    /// monospaced, returns an error.
-    pub(crate) fn load(path: &Path, point_size: u16) -> io::Result<MonospacedFont<'a>> {
+    /// Loads the font from the file `path` with `point_size`, or the default font if `path` is
+    /// none.  If the loaded font is not monospaced, returns an error.
+    pub(crate) fn load(path: Option<&Path>, point_size: u16) -> io::Result<MonospacedFont<'a>> {
         let ttf_context = TTF_CONTEXT.as_ref().map_err(init_error_to_io_error)?;
 
-        let font = ttf_context.load_font(path, point_size).map_err(string_error_to_io_error)?;
+        let font = match path {
+            Some(path) => ttf_context.load_font(path, point_size),
+            None => RWops::from_bytes(DEFAULT_FONT_BYTES)
+                .and_then(|rwops| ttf_context.load_font_from_rwops(rwops, point_size)),
+        }
+        .map_err(string_error_to_io_error)?;
 
         if !font.face_is_fixed_width() {
+            let name = match path {
+                Some(path) => format!("{}", path.display()),
+                None => "default".to_owned(),
+            };
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
-                format!("Font {} is not monospaced", path.display()),
+                format!("Font {} is not monospaced", name),
             ));
         }
 
@@ -23,25 +23,29 @@ use crate::spec::Resolution;
 use crate::string_error_to_io_error;
 use endbasic_core::exec::Signal;
 use endbasic_std::console::{
-    ansi_color_to_rgb, AnsiColor, CharsXY, ClearType, Key, LineBuffer, PixelsXY, SizeInPixels, RGB,
+    ansi_color_to_rgb, key_name, str_width, AnsiColor, CharsXY, ClearType, Key, KeyModifiers,
+    LineBuffer, PixelsXY, SizeInPixels, TextAttributes, NUM_PAGES, RGB,
 };
-use sdl2::event::Event;
-use sdl2::keyboard::{Keycode, Mod};
+use endbasic_std::gfx::raster;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod, Scancode};
 use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::{Point, Rect};
 use sdl2::render::{SurfaceCanvas, TextureCreator, TextureValueError, UpdateTextureError};
 use sdl2::surface::{Surface, SurfaceContext};
-use sdl2::video::{Window, WindowBuildError};
+use sdl2::ttf::FontStyle;
+use sdl2::video::{FullscreenType, Window, WindowBuildError};
 use sdl2::{EventPump, Sdl};
 use std::convert::TryFrom;
 use std::fmt::{self, Write};
 use std::io;
+use std::mem;
 #[cfg(test)]
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::mpsc::{Receiver, Sender, SyncSender, TryRecvError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// Default foreground color, used at console creation time and when requesting the default color
 /// via the `COLOR` command.
@@ -60,6 +64,9 @@ const LOOP_POLL_BUDGET: u16 = 10000;
 /// Delay between loop iterations when the polling budget in `LOOP_POLL_BUDGET` is exceeded.
 const LOOP_DELAY_MS: u64 = 1;
 
+/// Refresh rate to assume when pacing video syncs if the display does not report one.
+const DEFAULT_REFRESH_RATE: u32 = 60;
+
 /// Converts a `fmt::Error` to an `io::Error`.
 fn fmt_error_to_io_error(e: fmt::Error) -> io::Error {
     io::Error::new(io::ErrorKind::Other, e)
@@ -170,6 +177,23 @@ fn point_xy(xy: PixelsXY) -> Point {
     Point::new(i32::from(xy.x), i32::from(xy.y))
 }
 
+/// Draws a segment from `x1y1` to `x2y2` on `canvas` with the current drawing color.
+///
+/// Unlike `SurfaceCanvas::draw_line`, this paints a single dot when both ends match.  This papers
+/// over differences between platforms: on Linux, `draw_line` would paint the dot, but on Windows,
+/// it paints nothing.
+fn draw_segment(
+    canvas: &mut SurfaceCanvas<'static>,
+    x1y1: PixelsXY,
+    x2y2: PixelsXY,
+) -> io::Result<()> {
+    if x1y1 == x2y2 {
+        canvas.draw_point(point_xy(x1y1)).map_err(string_error_to_io_error)
+    } else {
+        canvas.draw_line(point_xy(x1y1), point_xy(x2y2)).map_err(string_error_to_io_error)
+    }
+}
+
 /// Constructs an SDL `Rect` from a `PixelsXY` `origin` and a `PixelsSize` `size`.
 fn rect_origin_size(origin: PixelsXY, size: SizeInPixels) -> Rect {
     Rect::new(
@@ -200,6 +224,18 @@ fn rgb_to_color(rgb: RGB) -> Color {
     Color::RGB(rgb.0, rgb.1, rgb.2)
 }
 
+/// Converts the text `attributes` that can be rendered by a font to an SDL `FontStyle`.
+fn attributes_to_font_style(attributes: TextAttributes) -> FontStyle {
+    let mut style = FontStyle::NORMAL;
+    if attributes.bold {
+        style |= FontStyle::BOLD;
+    }
+    if attributes.underline {
+        style |= FontStyle::UNDERLINE;
+    }
+    style
+}
+
 /// Given an SDL `event`, converts it to a `Key` event if it is a key press; otherwise, returns
 /// `None` for unknown events.
 fn parse_event(event: Event) -> Option<Key> {
@@ -222,9 +258,6 @@ fn parse_event(event: Event) -> Option<Key> {
             }
             Keycode::D if (keymod == Mod::LCTRLMOD || keymod == Mod::RCTRLMOD) => Some(Key::Eof),
             Keycode::E if (keymod == Mod::LCTRLMOD || keymod == Mod::RCTRLMOD) => Some(Key::End),
-            Keycode::F if (keymod == Mod::LCTRLMOD || keymod == Mod::RCTRLMOD) => {
-                Some(Key::ArrowRight)
-            }
             Keycode::J if (keymod == Mod::LCTRLMOD || keymod == Mod::RCTRLMOD) => {
                 Some(Key::NewLine)
             }
@@ -237,6 +270,13 @@ fn parse_event(event: Event) -> Option<Key> {
             Keycode::P if (keymod == Mod::LCTRLMOD || keymod == Mod::RCTRLMOD) => {
                 Some(Key::ArrowUp)
             }
+            keycode
+                if (keymod == Mod::LCTRLMOD || keymod == Mod::RCTRLMOD)
+                    && (Keycode::A as i32..=Keycode::Z as i32).contains(&(keycode as i32)) =>
+            {
+                // SDL key codes for letters match their lowercase ASCII values.
+                Some(Key::Control(char::from(keycode as u8)))
+            }
 
             Keycode::Backspace => Some(Key::Backspace),
             Keycode::End => Some(Key::End),
@@ -267,21 +307,94 @@ fn parse_event(event: Event) -> Option<Key> {
     }
 }
 
+/// Given an SDL `event`, returns the name of the key it represents as used by `ON KEY`, or `None`
+/// if the event is not a key press or the key has no name.
+///
+/// Control combinations are excluded so that, e.g., `Ctrl-C` does not trigger the `C` key.
+fn on_key_name(event: &Event) -> Option<String> {
+    const FUNCTION_KEYS: [Keycode; 12] = [
+        Keycode::F1,
+        Keycode::F2,
+        Keycode::F3,
+        Keycode::F4,
+        Keycode::F5,
+        Keycode::F6,
+        Keycode::F7,
+        Keycode::F8,
+        Keycode::F9,
+        Keycode::F10,
+        Keycode::F11,
+        Keycode::F12,
+    ];
+
+    match event {
+        Event::KeyDown { keymod, .. } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => None,
+        Event::KeyDown { keycode: Some(keycode), .. } => {
+            match FUNCTION_KEYS.iter().position(|k| k == keycode) {
+                // Function keys have no `Key` representation so they can only be handled with
+                // `ON KEY`.
+                Some(i) => Some(format!("F{}", i + 1)),
+                None => parse_event(event.clone()).as_ref().and_then(key_name),
+            }
+        }
+        Event::TextInput { .. } => parse_event(event.clone()).as_ref().and_then(key_name),
+        _ => None,
+    }
+}
+
+/// Converts our own `Key` representation to the SDL scancode of the key that produces it, or none
+/// if there is no such key.
+///
+/// Characters are expected to be in lowercase as documented in `Console::is_key_down`.
+fn key_to_scancode(key: &Key) -> Option<Scancode> {
+    let keycode = match key {
+        Key::ArrowDown => Keycode::Down,
+        Key::ArrowLeft => Keycode::Left,
+        Key::ArrowRight => Keycode::Right,
+        Key::ArrowUp => Keycode::Up,
+
+        Key::Backspace => Keycode::Backspace,
+        Key::CarriageReturn | Key::NewLine => Keycode::Return,
+        Key::End => Keycode::End,
+        Key::Escape => Keycode::Escape,
+        Key::Home => Keycode::Home,
+        Key::PageDown => Keycode::PageDown,
+        Key::PageUp => Keycode::PageUp,
+        Key::Tab => Keycode::Tab,
+
+        // SDL key codes for printable ASCII characters match their unshifted ASCII values.
+        Key::Char(ch) if ch.is_ascii_graphic() || *ch == ' ' => Keycode::from_i32(*ch as i32)?,
+
+        _ => return None,
+    };
+    Scancode::from_keycode(keycode)
+}
+
 /// Implementation of the EndBASIC console on top of an SDL2 window.
 ///
 /// The current struct-based code is derived from how this used to be a direct implementation of
 /// the `Console` trait and serves to keep track of all the state we need to maintain the console.
 /// However, this is incomplete without the main driver loop in `run`, which waits for events and
 /// manipulates the console.
+/// Snapshot of the contents of the console: its raw pixels, the cursor position, and the
+/// foreground and background colors.
+type ScreenBackup = (Vec<u8>, CharsXY, Color, Color);
+
 struct Context {
     /// SDL2 library context.  Must remain alive for the lifetime of the console: if it is dropped
     /// early, all further SDL operations fail.
-    #[cfg_attr(not(test), allow(unused))]
     sdl: Sdl,
 
     /// Monospaced font to use in the console.
     font: MonospacedFont<'static>,
 
+    /// Path to the file from which `font` was loaded, or none if it is the default font.
+    font_path: Option<PathBuf>,
+
+    /// Font loaded from `font_path` at a size other than that of `font` by the last call to
+    /// `draw_text`, along with that size.  Cached to avoid reloading the font on every call.
+    text_font: Option<(u16, MonospacedFont<'static>)>,
+
     /// Event pump to read keyboard events from.
     event_pump: EventPump,
 
@@ -321,23 +434,53 @@ struct Context {
     /// Current background color.  Used to clear text.
     bg_color: Color,
 
+    /// Current text attributes.  Bold and underline are applied to `font` and reverse is applied
+    /// when rendering text; blinking is not supported.
+    attributes: TextAttributes,
+
     /// State of the console right before entering the "alternate" console.
-    alt_backup: Option<(Vec<u8>, CharsXY, Color, Color)>,
+    alt_backup: Option<ScreenBackup>,
+
+    /// Stack of states of the console saved by `save_screen` and not yet restored.
+    saved_screens: Vec<ScreenBackup>,
 
     /// Whether video syncing is enabled or not.
     sync_enabled: bool,
+
+    /// Page that receives all output.  Its contents always live in `canvas`.
+    draw_page: u8,
+
+    /// Contents of all pages other than `draw_page`, which is stale.  Off-screen pages are created
+    /// on first use.
+    pages: Vec<Option<Surface<'static>>>,
+
+    /// Area to which graphical output is constrained, if any.  Only applied to the `canvas` while
+    /// handling graphical requests so that text output remains unconstrained.
+    clip: Option<Rect>,
+
+    /// Minimum time between explicit video syncs, or none if they are not paced.
+    frame_interval: Option<Duration>,
+
+    /// Time of the last explicit video sync, used to pace them to `frame_interval`.
+    last_frame: Option<Instant>,
 }
 
 impl Context {
     /// Initializes a new SDL console.
     ///
     /// The console is sized to `resolution` pixels.  Also loads the desired font from
-    /// `font_path` at `font_size` and uses it to calculate the size of the console in characters.
+    /// `font_path` (or the default font if none) at `font_size` and uses it to calculate the size
+    /// of the console in characters.  If `vsync` is true, explicit video syncs are paced to the refresh rate of the display.
     ///
     /// There can only be one active `SdlConsole` at any given time given that this initializes and
     /// owns the SDL context.
-    fn new(resolution: Resolution, font_path: PathBuf, font_size: u16) -> io::Result<Self> {
-        let font = MonospacedFont::load(&font_path, font_size)?;
+    fn new(
+        resolution: Resolution,
+        font_path: Option<PathBuf>,
+        font_size: u16,
+        vsync: bool,
+    ) -> io::Result<Self> {
+        let font = MonospacedFont::load(font_path.as_deref(), font_size)?;
 
         let sdl = sdl2::init().map_err(string_error_to_io_error)?;
         let event_pump = sdl.event_pump().map_err(string_error_to_io_error)?;
@@ -360,6 +503,7 @@ impl Context {
             Resolution::Windowed(size) => {
                 let mut window = video.window(&title, size.0, size.1);
                 window.position_centered();
+                window.resizable();
                 window
             }
         }
@@ -373,6 +517,18 @@ impl Context {
         };
         let size_chars = font.chars_in_area(size_pixels);
 
+        let frame_interval = if vsync {
+            // Some drivers do not know the refresh rate of the display and report 0, so assume
+            // the most common rate in that case.
+            let refresh_rate = match window.display_mode() {
+                Ok(mode) if mode.refresh_rate > 0 => mode.refresh_rate as u32,
+                _ => DEFAULT_REFRESH_RATE,
+            };
+            Some(Duration::from_secs(1) / refresh_rate)
+        } else {
+            None
+        };
+
         write!(
             &mut title,
             " - {}x{} pixels, {}x{} chars",
@@ -391,6 +547,8 @@ impl Context {
         let mut console = Self {
             sdl,
             font,
+            font_path,
+            text_font: None,
             event_pump,
             window,
             canvas,
@@ -402,9 +560,16 @@ impl Context {
             cursor_visible: true,
             cursor_backup: vec![],
             bg_color: rgb_to_color(ansi_color_to_rgb(DEFAULT_BG_COLOR)),
+            attributes: TextAttributes::default(),
             fg_color: rgb_to_color(ansi_color_to_rgb(DEFAULT_FG_COLOR)),
             alt_backup: None,
+            saved_screens: vec![],
             sync_enabled: true,
+            draw_page: 0,
+            clip: None,
+            pages: (0..NUM_PAGES).map(|_| None).collect(),
+            frame_interval,
+            last_frame: None,
         };
 
         console.clear(ClearType::All)?;
@@ -412,19 +577,20 @@ impl Context {
         Ok(console)
     }
 
-    /// Renders the current contents of `self.canvas` onto the output window irrespective of the
+    /// Renders the current contents of the visible page onto the output window irrespective of the
     /// status of the sync flag.
     fn force_present_canvas(&mut self) -> io::Result<()> {
         let mut window_surface =
             self.window.surface(&self.event_pump).map_err(string_error_to_io_error)?;
-        self.canvas
-            .surface()
-            .blit(None, &mut window_surface, None)
-            .map_err(string_error_to_io_error)?;
+        let visible = match self.draw_page {
+            0 => self.canvas.surface(),
+            _ => self.pages[0].as_ref().expect("Visible page must have been saved"),
+        };
+        visible.blit(None, &mut window_surface, None).map_err(string_error_to_io_error)?;
         window_surface.finish().map_err(string_error_to_io_error)
     }
 
-    /// Renders the current contents of `self.canvas` onto the output window.
+    /// Renders the current contents of the visible page onto the output window.
     fn present_canvas(&mut self) -> io::Result<()> {
         if self.sync_enabled {
             self.force_present_canvas()
@@ -538,16 +704,24 @@ impl Context {
     fn raw_write(&mut self, text: &str, start: PixelsXY) -> io::Result<()> {
         debug_assert!(!text.is_empty(), "SDL does not like empty strings");
 
-        let len = match u16::try_from(text.chars().count()) {
+        // The rendered text is stretched to cover exactly the cells it occupies, which keeps wide
+        // characters aligned to the grid even if the font lacks glyphs for them.
+        let len = match u16::try_from(str_width(text)) {
+            Ok(0) => return Ok(()),
             Ok(v) => v,
             Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "String too long")),
         };
 
+        let (fg_color, bg_color) = if self.attributes.reverse {
+            (self.bg_color, self.fg_color)
+        } else {
+            (self.fg_color, self.bg_color)
+        };
         let surface = self
             .font
             .font
             .render(text)
-            .shaded(self.fg_color, self.bg_color)
+            .shaded(fg_color, bg_color)
             .map_err(font_error_to_io_error)?;
         let texture = self
             .texture_creator
@@ -573,17 +747,24 @@ impl Context {
         let mut line_buffer = LineBuffer::from(text);
 
         loop {
-            let fit_chars = self.size_chars.x - self.cursor_pos.x;
-
-            let remaining = line_buffer.split_off(usize::from(fit_chars));
-            let len = line_buffer.len();
-            if len > 0 {
+            let fit_width = self.size_chars.x.saturating_sub(self.cursor_pos.x);
+
+            let mut remaining = line_buffer.split_off_width(usize::from(fit_width));
+            if line_buffer.is_empty() && self.cursor_pos.x == 0 {
+                // The next character is wider than the whole console.  Render it clipped instead
+                // of wrapping forever.
+                line_buffer = remaining;
+                remaining = line_buffer.split_off(line_buffer.next_boundary(0));
+            }
+            if !line_buffer.is_empty() {
+                let width = line_buffer.width();
                 self.raw_write(
                     &line_buffer.into_inner(),
                     self.cursor_pos.clamped_mul(self.font.glyph_size),
                 )?;
-                self.cursor_pos.x +=
-                    u16::try_from(len).expect("Partial length was computed to fit on the screen");
+                self.cursor_pos.x = self.cursor_pos.x.saturating_add(
+                    u16::try_from(width).expect("Partial width was computed to fit on the screen"),
+                );
             }
 
             line_buffer = remaining;
@@ -661,6 +842,20 @@ impl Context {
         Ok(())
     }
 
+    /// Handler for a `Request::SetColorRgb`.
+    fn set_color_rgb(&mut self, fg: Option<RGB>, bg: Option<RGB>) -> io::Result<()> {
+        self.fg_color = rgb_to_color(fg.unwrap_or_else(|| ansi_color_to_rgb(DEFAULT_FG_COLOR)));
+        self.bg_color = rgb_to_color(bg.unwrap_or_else(|| ansi_color_to_rgb(DEFAULT_BG_COLOR)));
+        Ok(())
+    }
+
+    /// Handler for a `Request::SetAttributes`.
+    fn set_attributes(&mut self, attributes: TextAttributes) -> io::Result<()> {
+        self.font.font.set_style(attributes_to_font_style(attributes));
+        self.attributes = attributes;
+        Ok(())
+    }
+
     /// Handler for a `Request::EnterAlt`.
     fn enter_alt(&mut self) -> io::Result<()> {
         if self.alt_backup.is_some() {
@@ -670,31 +865,21 @@ impl Context {
             ));
         }
 
-        let pixels =
-            self.canvas.read_pixels(None, self.pixel_format).map_err(string_error_to_io_error)?;
-        self.alt_backup = Some((pixels, self.cursor_pos, self.fg_color, self.bg_color));
+        self.alt_backup = Some(self.backup_screen()?);
 
         self.clear(ClearType::All)
     }
 
-    /// Handler for a `Request::HideCursor`.
-    fn hide_cursor(&mut self) -> io::Result<()> {
-        self.clear_cursor()?;
-        self.cursor_visible = false;
-        self.present_canvas()
+    /// Takes a snapshot of the contents of the console.
+    fn backup_screen(&self) -> io::Result<ScreenBackup> {
+        let pixels =
+            self.canvas.read_pixels(None, self.pixel_format).map_err(string_error_to_io_error)?;
+        Ok((pixels, self.cursor_pos, self.fg_color, self.bg_color))
     }
 
-    /// Handler for a `Request::LeaveAlt`.
-    fn leave_alt(&mut self) -> io::Result<()> {
-        let (pixels, cursor_pos, fg_color, bg_color) = match self.alt_backup.take() {
-            Some(t) => t,
-            None => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "Cannot leave alternate screen; not entered",
-                ))
-            }
-        };
+    /// Replaces the contents of the console with a snapshot taken by `backup_screen`.
+    fn restore_backup(&mut self, backup: ScreenBackup) -> io::Result<()> {
+        let (pixels, cursor_pos, fg_color, bg_color) = backup;
 
         self.clear_cursor()?;
 
@@ -724,7 +909,29 @@ impl Context {
         self.fg_color = fg_color;
         self.bg_color = bg_color;
         self.draw_cursor()?;
-        self.present_canvas()?;
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::HideCursor`.
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.clear_cursor()?;
+        self.cursor_visible = false;
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::LeaveAlt`.
+    fn leave_alt(&mut self) -> io::Result<()> {
+        let backup = match self.alt_backup.take() {
+            Some(t) => t,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Cannot leave alternate screen; not entered",
+                ))
+            }
+        };
+
+        self.restore_backup(backup)?;
 
         debug_assert!(self.alt_backup.is_none());
         Ok(())
@@ -819,6 +1026,16 @@ impl Context {
         self.present_canvas()
     }
 
+    /// Handler for a `Request::DrawArc`.
+    fn draw_arc(&mut self, center: PixelsXY, radius: u16, start: i32, end: i32) -> io::Result<()> {
+        self.canvas.set_draw_color(self.fg_color);
+        let canvas = &mut self.canvas;
+        raster::arc(center, radius, start, end, |xy| {
+            canvas.draw_point(point_xy(xy)).map_err(string_error_to_io_error)
+        })?;
+        self.present_canvas()
+    }
+
     /// Handler for a `Request::DrawCircle`.
     ///
     /// This implements the [Midpoint circle
@@ -939,6 +1156,24 @@ impl Context {
         self.present_canvas()
     }
 
+    /// Handler for a `Request::DrawEllipse`.
+    fn draw_ellipse(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.canvas.set_draw_color(self.fg_color);
+        let canvas = &mut self.canvas;
+        raster::ellipse(center, rx, ry, |xy| {
+            canvas.draw_point(point_xy(xy)).map_err(string_error_to_io_error)
+        })?;
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::DrawEllipseFilled`.
+    fn draw_ellipse_filled(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.canvas.set_draw_color(self.fg_color);
+        let canvas = &mut self.canvas;
+        raster::ellipse_filled(center, rx, ry, |x1y1, x2y2| draw_segment(canvas, x1y1, x2y2))?;
+        self.present_canvas()
+    }
+
     /// Handler for a `Request::DrawLine`.
     fn draw_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
         if x1y1 == x2y2 {
@@ -960,6 +1195,23 @@ impl Context {
         self.present_canvas()
     }
 
+    /// Handler for a `Request::DrawPoly`.
+    fn draw_poly(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.canvas.set_draw_color(self.fg_color);
+        let canvas = &mut self.canvas;
+        raster::poly(points, |x1y1, x2y2| draw_segment(canvas, x1y1, x2y2))?;
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::DrawPolyFilled`.
+    fn draw_poly_filled(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.canvas.set_draw_color(self.fg_color);
+        let canvas = &mut self.canvas;
+        raster::poly_filled(points, |x1y1, x2y2| draw_segment(canvas, x1y1, x2y2))?;
+        raster::poly(points, |x1y1, x2y2| draw_segment(canvas, x1y1, x2y2))?;
+        self.present_canvas()
+    }
+
     /// Handler for a `Request::DrawRect`.
     fn draw_rect(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
         let rect = rect_points(x1y1, x2y2);
@@ -976,15 +1228,390 @@ impl Context {
         self.present_canvas()
     }
 
+    /// Handler for a `Request::DrawText`.
+    fn draw_text(&mut self, xy: PixelsXY, text: &str, size: Option<u16>) -> io::Result<()> {
+        debug_assert!(!text.is_empty(), "SDL does not like empty strings");
+
+        let font = match size {
+            None => &self.font,
+            Some(size) => {
+                let cached =
+                    matches!(self.text_font, Some((cached_size, _)) if cached_size == size);
+                if !cached {
+                    let font = MonospacedFont::load(self.font_path.as_deref(), size)?;
+                    self.text_font = Some((size, font));
+                }
+                &self.text_font.as_ref().expect("Font was just loaded").1
+            }
+        };
+
+        let surface =
+            font.font.render(text).blended(self.fg_color).map_err(font_error_to_io_error)?;
+        let texture = self
+            .texture_creator
+            .create_texture_from_surface(&surface)
+            .map_err(texture_value_error_to_io_error)?;
+
+        let rect = Rect::new(i32::from(xy.x), i32::from(xy.y), surface.width(), surface.height());
+        self.canvas.copy(&texture, None, rect).map_err(string_error_to_io_error)?;
+        drop(texture);
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::FloodFill`.
+    fn flood_fill(&mut self, xy: PixelsXY, border: Option<RGB>) -> io::Result<()> {
+        // Take a snapshot of the canvas in a well-known format so that we can inspect the colors
+        // of the pixels without having to care about the format of the window.
+        let pixels = self
+            .canvas
+            .read_pixels(None, PixelFormatEnum::RGB24)
+            .map_err(string_error_to_io_error)?;
+        let width = usize::from(self.size_pixels.width);
+        let color_at = |xy: PixelsXY| {
+            let offset = (usize::from(xy.y as u16) * width + usize::from(xy.x as u16)) * 3;
+            (pixels[offset], pixels[offset + 1], pixels[offset + 2])
+        };
+
+        let target = match border {
+            Some(border) => border,
+            None => {
+                let x = usize::try_from(xy.x).ok();
+                let y = usize::try_from(xy.y).ok();
+                match (x, y) {
+                    (Some(x), Some(y)) if x < width && y < usize::from(self.size_pixels.height) => {
+                        color_at(xy)
+                    }
+                    _ => return Ok(()),
+                }
+            }
+        };
+        let fg = (self.fg_color.r, self.fg_color.g, self.fg_color.b);
+        if border.is_none() && target == fg {
+            // Filling the area with the color it already has would be a no-op.
+            return Ok(());
+        }
+
+        self.canvas.set_draw_color(self.fg_color);
+        let canvas = &mut self.canvas;
+        raster::flood_fill(
+            self.size_pixels,
+            xy,
+            |xy| match border {
+                Some(_) => color_at(xy) != target,
+                None => color_at(xy) == target,
+            },
+            |x1y1, x2y2| draw_segment(canvas, x1y1, x2y2),
+        )?;
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::ReadPixel`.
+    fn read_pixel(&mut self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+        let x = match u16::try_from(xy.x) {
+            Ok(x) if x < self.size_pixels.width => x,
+            _ => return Ok(None),
+        };
+        let y = match u16::try_from(xy.y) {
+            Ok(y) if y < self.size_pixels.height => y,
+            _ => return Ok(None),
+        };
+
+        let rect = Rect::new(i32::from(x), i32::from(y), 1, 1);
+        let pixel = self
+            .canvas
+            .read_pixels(rect, PixelFormatEnum::RGB24)
+            .map_err(string_error_to_io_error)?;
+        Ok(Some((pixel[0], pixel[1], pixel[2])))
+    }
+
+    /// Creates a new blank page of the same size as the console.
+    fn new_page(&self) -> io::Result<Surface<'static>> {
+        let mut page = Surface::new(
+            u32::from(self.size_pixels.width),
+            u32::from(self.size_pixels.height),
+            self.pixel_format,
+        )
+        .map_err(string_error_to_io_error)?;
+        page.fill_rect(None, self.bg_color).map_err(string_error_to_io_error)?;
+        Ok(page)
+    }
+
+    /// Handler for a `Request::SetClip`.
+    fn set_clip(&mut self, area: Option<(PixelsXY, PixelsXY)>) -> io::Result<()> {
+        self.clip = area.map(|(x1y1, x2y2)| {
+            let width = i32::from(x2y2.x) - i32::from(x1y1.x) + 1;
+            let height = i32::from(x2y2.y) - i32::from(x1y1.y) + 1;
+            Rect::new(
+                i32::from(x1y1.x),
+                i32::from(x1y1.y),
+                u32::try_from(width).expect("Corners must have been ordered"),
+                u32::try_from(height).expect("Corners must have been ordered"),
+            )
+        });
+        Ok(())
+    }
+
+    /// Handler for a `Request::SetDrawPage`.
+    fn set_draw_page(&mut self, page: u8) -> io::Result<()> {
+        if page >= NUM_PAGES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page out of range"));
+        }
+        if page == self.draw_page {
+            return Ok(());
+        }
+
+        // The canvas is tied to the texture creator we use to render text, so instead of swapping
+        // canvases, we swap the contents of the canvas with the saved copy of the new page.
+        self.clear_cursor()?;
+        let mut old = match self.pages[usize::from(self.draw_page)].take() {
+            Some(old) => old,
+            None => self.new_page()?,
+        };
+        self.canvas.surface().blit(None, &mut old, None).map_err(string_error_to_io_error)?;
+        self.pages[usize::from(self.draw_page)] = Some(old);
+
+        match self.pages[usize::from(page)].as_ref() {
+            Some(new) => {
+                new.blit(None, self.canvas.surface_mut(), None)
+                    .map_err(string_error_to_io_error)?;
+            }
+            None => {
+                self.canvas.set_draw_color(self.bg_color);
+                self.canvas.clear();
+            }
+        }
+        self.draw_page = page;
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::FlipPage`.
+    fn flip_page(&mut self, page: u8) -> io::Result<()> {
+        if page >= NUM_PAGES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page out of range"));
+        }
+
+        if page != 0 {
+            if self.draw_page == 0 {
+                self.clear_cursor()?;
+                match self.pages[usize::from(page)].as_ref() {
+                    Some(src) => {
+                        src.blit(None, self.canvas.surface_mut(), None)
+                            .map_err(string_error_to_io_error)?;
+                    }
+                    None => {
+                        self.canvas.set_draw_color(self.bg_color);
+                        self.canvas.clear();
+                    }
+                }
+                self.draw_cursor()?;
+            } else {
+                let mut visible = self.pages[0].take().expect("Visible page must have been saved");
+                let result = if page == self.draw_page {
+                    self.clear_cursor()?;
+                    let result = self.canvas.surface().blit(None, &mut visible, None).map(|_| ());
+                    self.draw_cursor()?;
+                    result
+                } else {
+                    match self.pages[usize::from(page)].as_ref() {
+                        Some(src) => src.blit(None, &mut visible, None).map(|_| ()),
+                        None => visible.fill_rect(None, self.bg_color),
+                    }
+                };
+                self.pages[0] = Some(visible);
+                result.map_err(string_error_to_io_error)?;
+            }
+        }
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::SetFont`.
+    fn set_font(&mut self, face: Option<&str>, size: u16) -> io::Result<()> {
+        let font_path = match face {
+            None => self.font_path.clone(),
+            Some("") => None,
+            Some(face) => Some(PathBuf::from(face)),
+        };
+        let font = MonospacedFont::load(font_path.as_deref(), size)?;
+
+        self.clear_cursor()?;
+
+        // Keep the cursor as close as possible to where it was on the screen.
+        let old_glyph_size = self.font.glyph_size;
+        let remap = |pos: &mut CharsXY, size: CharsXY| {
+            let x = u32::from(pos.x) * u32::from(old_glyph_size.width)
+                / u32::from(font.glyph_size.width);
+            let y = u32::from(pos.y) * u32::from(old_glyph_size.height)
+                / u32::from(font.glyph_size.height);
+            pos.x = u16::try_from(x).unwrap_or(u16::MAX).min(size.x.saturating_sub(1));
+            pos.y = u16::try_from(y).unwrap_or(u16::MAX).min(size.y.saturating_sub(1));
+        };
+        let size_chars = font.chars_in_area(self.size_pixels);
+        remap(&mut self.cursor_pos, size_chars);
+        for (_pixels, cursor_pos, _fg_color, _bg_color) in
+            self.alt_backup.iter_mut().chain(self.saved_screens.iter_mut())
+        {
+            remap(cursor_pos, size_chars);
+        }
+
+        self.font = font;
+        self.font.font.set_style(attributes_to_font_style(self.attributes));
+        self.font_path = font_path;
+        self.text_font = None;
+        self.size_chars = size_chars;
+
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    /// Handler for a `Request::SetTitle`.
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        self.window.set_title(title).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// Handler for a `Request::ClipboardGet`.
+    fn clipboard_get(&mut self) -> io::Result<String> {
+        self.window.subsystem().clipboard().clipboard_text().map_err(string_error_to_io_error)
+    }
+
+    /// Handler for a `Request::ClipboardSet`.
+    fn clipboard_set(&mut self, text: &str) -> io::Result<()> {
+        self.window
+            .subsystem()
+            .clipboard()
+            .set_clipboard_text(text)
+            .map_err(string_error_to_io_error)
+    }
+
+    /// Handler for a `Request::SaveScreen`.
+    fn save_screen(&mut self) -> io::Result<()> {
+        let backup = self.backup_screen()?;
+        self.saved_screens.push(backup);
+        Ok(())
+    }
+
+    /// Handler for a `Request::RestoreScreen`.
+    fn restore_screen(&mut self) -> io::Result<()> {
+        match self.saved_screens.pop() {
+            Some(backup) => self.restore_backup(backup),
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "No saved screen to restore")),
+        }
+    }
+
+    /// Handler for a `Request::SetFullscreen`.
+    fn set_fullscreen(&mut self, enabled: bool) -> io::Result<()> {
+        let mode = if enabled { FullscreenType::Desktop } else { FullscreenType::Off };
+        self.window.set_fullscreen(mode).map_err(string_error_to_io_error)?;
+        // The window will also deliver a resize event later on, but handle the change right away
+        // so that the client sees the new size of the console as soon as this returns.
+        self.resize()
+    }
+
+    /// Adjusts the console to the current size of the window, which can change at any time if the
+    /// user resizes the window or if we toggle full screen mode.
+    ///
+    /// The contents of the visible page, of the alternate console backup, and of the saved screens
+    /// are preserved as much as the new size allows, but off-screen pages are discarded.
+    fn resize(&mut self) -> io::Result<()> {
+        let size_pixels = {
+            let (width, height) = self.window.drawable_size();
+            SizeInPixels { width: width.clamped_into(), height: height.clamped_into() }
+        };
+        if size_pixels == self.size_pixels {
+            return Ok(());
+        }
+
+        self.set_draw_page(0)?;
+        self.clear_cursor()?;
+
+        let mut canvas = self.new_canvas(size_pixels)?;
+        self.canvas
+            .surface()
+            .blit(None, canvas.surface_mut(), None)
+            .map_err(string_error_to_io_error)?;
+
+        if let Some((pixels, cursor_pos, fg_color, bg_color)) = self.alt_backup.take() {
+            let pixels = self.resize_pixels(pixels, size_pixels)?;
+            self.alt_backup = Some((pixels, cursor_pos, fg_color, bg_color));
+        }
+        for (pixels, cursor_pos, fg_color, bg_color) in mem::take(&mut self.saved_screens) {
+            let pixels = self.resize_pixels(pixels, size_pixels)?;
+            self.saved_screens.push((pixels, cursor_pos, fg_color, bg_color));
+        }
+
+        self.texture_creator = canvas.texture_creator();
+        self.canvas = canvas;
+        self.pages = (0..NUM_PAGES).map(|_| None).collect();
+        self.size_pixels = size_pixels;
+        self.size_chars = self.font.chars_in_area(size_pixels);
+
+        let clamp = |pos: &mut CharsXY, size: CharsXY| {
+            pos.x = pos.x.min(size.x.saturating_sub(1));
+            pos.y = pos.y.min(size.y.saturating_sub(1));
+        };
+        clamp(&mut self.cursor_pos, self.size_chars);
+        for (_pixels, cursor_pos, _fg_color, _bg_color) in
+            self.alt_backup.iter_mut().chain(self.saved_screens.iter_mut())
+        {
+            clamp(cursor_pos, self.size_chars);
+        }
+
+        self.draw_cursor()?;
+        self.force_present_canvas()
+    }
+
+    /// Copies the raw `pixels` of a console of the current size onto a new buffer for a console
+    /// of `size`, cropping or padding them as necessary.
+    fn resize_pixels(&self, mut pixels: Vec<u8>, size: SizeInPixels) -> io::Result<Vec<u8>> {
+        let mut resized = self.new_canvas(size)?;
+        {
+            let old = Surface::from_data(
+                &mut pixels,
+                u32::from(self.size_pixels.width),
+                u32::from(self.size_pixels.height),
+                self.size_pixels.width.clamped_mul(self.pixel_format.byte_size_per_pixel() as u16),
+                self.pixel_format,
+            )
+            .map_err(string_error_to_io_error)?;
+            old.blit(None, resized.surface_mut(), None).map_err(string_error_to_io_error)?;
+        }
+        resized.read_pixels(None, self.pixel_format).map_err(string_error_to_io_error)
+    }
+
+    /// Creates a new canvas of `size` filled with the background color.
+    fn new_canvas(&self, size: SizeInPixels) -> io::Result<SurfaceCanvas<'static>> {
+        let surface =
+            Surface::new(u32::from(size.width), u32::from(size.height), self.pixel_format)
+                .map_err(string_error_to_io_error)?;
+        let mut canvas = surface.into_canvas().map_err(string_error_to_io_error)?;
+        canvas.set_draw_color(self.bg_color);
+        canvas.clear();
+        Ok(canvas)
+    }
+
     /// Handler for a `Request::SyncNow`.
     fn sync_now(&mut self) -> io::Result<()> {
         if self.sync_enabled {
             Ok(())
         } else {
+            self.wait_for_frame();
             self.force_present_canvas()
         }
     }
 
+    /// Blocks until it is time to present a new frame if explicit video syncs are being paced.
+    fn wait_for_frame(&mut self) {
+        if let Some(interval) = self.frame_interval {
+            if let Some(last_frame) = self.last_frame {
+                let elapsed = last_frame.elapsed();
+                if elapsed < interval {
+                    thread::sleep(interval - elapsed);
+                }
+            }
+            self.last_frame = Some(Instant::now());
+        }
+    }
+
     /// Handler for a `Request::SetSync`.
     fn set_sync(&mut self, enabled: bool) -> io::Result<bool> {
         if !self.sync_enabled {
@@ -994,6 +1621,24 @@ impl Context {
         self.sync_enabled = enabled;
         Ok(previous)
     }
+
+    /// Returns true if `key` is currently held down.
+    fn is_key_down(&self, key: &Key) -> bool {
+        match key_to_scancode(key) {
+            Some(scancode) => self.event_pump.keyboard_state().is_scancode_pressed(scancode),
+            None => false,
+        }
+    }
+
+    /// Returns the modifier keys that are currently held down.
+    fn key_modifiers(&self) -> KeyModifiers {
+        let mods = self.sdl.keyboard().mod_state();
+        KeyModifiers {
+            shift: mods.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            ctrl: mods.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+            alt: mods.intersects(Mod::LALTMOD | Mod::RALTMOD),
+        }
+    }
 }
 
 /// Representation of requests that the console host can handle.
@@ -1002,6 +1647,9 @@ pub(crate) enum Request {
 
     Clear(ClearType),
     SetColor(Option<u8>, Option<u8>),
+    SetColorRgb(Option<RGB>, Option<RGB>),
+    SetAttributes(TextAttributes),
+    CursorPos,
     EnterAlt,
     HideCursor,
     LeaveAlt,
@@ -1012,14 +1660,34 @@ pub(crate) enum Request {
     SizeChars,
     SizePixels,
     Write(String),
+    DrawArc(PixelsXY, u16, i32, i32),
     DrawCircle(PixelsXY, u16),
     DrawCircleFilled(PixelsXY, u16),
+    DrawEllipse(PixelsXY, u16, u16),
+    DrawEllipseFilled(PixelsXY, u16, u16),
     DrawLine(PixelsXY, PixelsXY),
     DrawPixel(PixelsXY),
+    DrawPoly(Vec<PixelsXY>),
+    DrawPolyFilled(Vec<PixelsXY>),
     DrawRect(PixelsXY, PixelsXY),
     DrawRectFilled(PixelsXY, PixelsXY),
+    DrawText(PixelsXY, String, Option<u16>),
+    FloodFill(PixelsXY, Option<RGB>),
+    ReadPixel(PixelsXY),
+    SetClip(Option<(PixelsXY, PixelsXY)>),
+    SetDrawPage(u8),
+    FlipPage(u8),
+    SetFont(Option<String>, u16),
+    SetTitle(String),
+    SaveScreen,
+    RestoreScreen,
+    ClipboardGet,
+    ClipboardSet(String),
+    SetFullscreen(bool),
     SyncNow,
     SetSync(bool),
+    IsKeyDown(Key),
+    KeyModifiers,
 
     #[cfg(test)]
     PushEvent(Event),
@@ -1031,28 +1699,69 @@ pub(crate) enum Request {
     SaveBmp(PathBuf),
 }
 
+impl Request {
+    /// Returns true if this request draws graphics, which are subject to the clipping area.
+    fn is_graphical(&self) -> bool {
+        matches!(
+            self,
+            Request::DrawArc(..)
+                | Request::DrawCircle(..)
+                | Request::DrawCircleFilled(..)
+                | Request::DrawEllipse(..)
+                | Request::DrawEllipseFilled(..)
+                | Request::DrawLine(..)
+                | Request::DrawPixel(..)
+                | Request::DrawPoly(..)
+                | Request::DrawPolyFilled(..)
+                | Request::DrawRect(..)
+                | Request::DrawRectFilled(..)
+                | Request::DrawText(..)
+                | Request::FloodFill(..)
+        )
+    }
+}
+
 /// Representation of responses that the host sends back to the client.
 #[derive(Debug)]
 pub(crate) enum Response {
     Empty(io::Result<()>),
+    CursorPos(CharsXY),
     SizeChars(CharsXY),
     SizePixels(SizeInPixels),
+    ReadPixel(io::Result<Option<RGB>>),
     SetSync(io::Result<bool>),
+    ClipboardGet(io::Result<String>),
+    IsKeyDown(bool),
+    KeyModifiers(KeyModifiers),
 
     #[cfg(test)]
     Pixels(io::Result<(Vec<u8>, PixelFormatEnum)>),
 }
 
+/// Settings of the window that hosts the console.
+pub(crate) struct WindowSettings {
+    /// Size of the console in pixels.
+    pub(crate) resolution: Resolution,
+
+    /// Path to the font to use, or none for the default font.
+    pub(crate) font_path: Option<PathBuf>,
+
+    /// Size of the font in points.
+    pub(crate) font_size: u16,
+
+    /// Whether explicit video syncs are paced to the refresh rate of the display.
+    pub(crate) vsync: bool,
+}
+
 pub(crate) fn run(
-    resolution: Resolution,
-    font_path: PathBuf,
-    font_size: u16,
+    settings: WindowSettings,
     request_rx: Receiver<Request>,
     response_tx: SyncSender<Response>,
     on_key_tx: Sender<Key>,
     signals_tx: async_channel::Sender<Signal>,
 ) {
-    let mut ctx = match Context::new(resolution, font_path, font_size) {
+    let WindowSettings { resolution, font_path, font_size, vsync } = settings;
+    let mut ctx = match Context::new(resolution, font_path, font_size, vsync) {
         Ok(ctx) => ctx,
         Err(e) => {
             response_tx.send(Response::Empty(Err(e))).expect("Channel must be alive");
@@ -1068,11 +1777,21 @@ pub(crate) fn run(
 
         match request_rx.try_recv() {
             Ok(request) => {
+                let clipped = request.is_graphical() && ctx.clip.is_some();
+                if clipped {
+                    ctx.canvas.set_clip_rect(ctx.clip);
+                }
+
                 let response = match request {
                     Request::Exit => break,
 
                     Request::Clear(how) => Response::Empty(ctx.clear(how)),
                     Request::SetColor(fg, bg) => Response::Empty(ctx.set_color(fg, bg)),
+                    Request::SetColorRgb(fg, bg) => Response::Empty(ctx.set_color_rgb(fg, bg)),
+                    Request::SetAttributes(attributes) => {
+                        Response::Empty(ctx.set_attributes(attributes))
+                    }
+                    Request::CursorPos => Response::CursorPos(ctx.cursor_pos),
                     Request::EnterAlt => Response::Empty(ctx.enter_alt()),
                     Request::HideCursor => Response::Empty(ctx.hide_cursor()),
                     Request::LeaveAlt => Response::Empty(ctx.leave_alt()),
@@ -1083,20 +1802,52 @@ pub(crate) fn run(
                     Request::SizeChars => Response::SizeChars(ctx.size_chars),
                     Request::SizePixels => Response::SizePixels(ctx.size_pixels),
                     Request::Write(text) => Response::Empty(ctx.write(&text)),
+                    Request::DrawArc(center, radius, start, end) => {
+                        Response::Empty(ctx.draw_arc(center, radius, start, end))
+                    }
                     Request::DrawCircle(center, radius) => {
                         Response::Empty(ctx.draw_circle(center, radius))
                     }
                     Request::DrawCircleFilled(center, radius) => {
                         Response::Empty(ctx.draw_circle_filled(center, radius))
                     }
+                    Request::DrawEllipse(center, rx, ry) => {
+                        Response::Empty(ctx.draw_ellipse(center, rx, ry))
+                    }
+                    Request::DrawEllipseFilled(center, rx, ry) => {
+                        Response::Empty(ctx.draw_ellipse_filled(center, rx, ry))
+                    }
                     Request::DrawLine(x1y1, x2y2) => Response::Empty(ctx.draw_line(x1y1, x2y2)),
                     Request::DrawPixel(xy) => Response::Empty(ctx.draw_pixel(xy)),
+                    Request::DrawPoly(points) => Response::Empty(ctx.draw_poly(&points)),
+                    Request::DrawPolyFilled(points) => {
+                        Response::Empty(ctx.draw_poly_filled(&points))
+                    }
                     Request::DrawRect(x1y1, x2y2) => Response::Empty(ctx.draw_rect(x1y1, x2y2)),
                     Request::DrawRectFilled(x1y1, x2y2) => {
                         Response::Empty(ctx.draw_rect_filled(x1y1, x2y2))
                     }
+                    Request::DrawText(xy, text, size) => {
+                        Response::Empty(ctx.draw_text(xy, &text, size))
+                    }
+                    Request::FloodFill(xy, border) => Response::Empty(ctx.flood_fill(xy, border)),
+                    Request::ReadPixel(xy) => Response::ReadPixel(ctx.read_pixel(xy)),
+                    Request::SetClip(area) => Response::Empty(ctx.set_clip(area)),
+                    Request::SetDrawPage(page) => Response::Empty(ctx.set_draw_page(page)),
+                    Request::FlipPage(page) => Response::Empty(ctx.flip_page(page)),
+                    Request::SetFont(face, size) => {
+                        Response::Empty(ctx.set_font(face.as_deref(), size))
+                    }
+                    Request::SetTitle(title) => Response::Empty(ctx.set_title(&title)),
+                    Request::SaveScreen => Response::Empty(ctx.save_screen()),
+                    Request::RestoreScreen => Response::Empty(ctx.restore_screen()),
+                    Request::ClipboardGet => Response::ClipboardGet(ctx.clipboard_get()),
+                    Request::ClipboardSet(text) => Response::Empty(ctx.clipboard_set(&text)),
+                    Request::SetFullscreen(enabled) => Response::Empty(ctx.set_fullscreen(enabled)),
                     Request::SyncNow => Response::Empty(ctx.sync_now()),
                     Request::SetSync(enabled) => Response::SetSync(ctx.set_sync(enabled)),
+                    Request::IsKeyDown(key) => Response::IsKeyDown(ctx.is_key_down(&key)),
+                    Request::KeyModifiers => Response::KeyModifiers(ctx.key_modifiers()),
 
                     #[cfg(test)]
                     Request::PushEvent(ev) => Response::Empty(ctx.push_event(ev)),
@@ -1113,6 +1864,10 @@ pub(crate) fn run(
                     Request::SaveBmp(path) => Response::Empty(ctx.save_bmp(&path)),
                 };
 
+                if clipped {
+                    ctx.canvas.set_clip_rect(None);
+                }
+
                 // TODO(jmmv): This is inefficient.  Most of the operations above could probably
                 // benefit from _not_ returning a response at all, being asynchronous from the
                 // client perspective -- but the code is like this right now because it is adapted
@@ -1126,6 +1881,18 @@ pub(crate) fn run(
         }
 
         if let Some(event) = ctx.event_pump.poll_event() {
+            if let Event::Window { win_event: WindowEvent::SizeChanged(..), .. } = event {
+                // There is no client waiting for the result of this operation, so the best we can
+                // do is to keep going with whatever state the console was left in.
+                let _ = ctx.resize();
+            }
+
+            if let Some(name) = on_key_name(&event) {
+                // Let the machine dispatch any handler installed with `ON KEY`.  See below for
+                // why "try_send" is fine here.
+                signals_tx.try_send(Signal::Key(name)).expect("Channel must be alive and not full")
+            }
+
             if let Some(key) = parse_event(event) {
                 if key == Key::Interrupt {
                     // signals_tx is an async channel because that's what the execution engine
@@ -1194,6 +1961,44 @@ mod tests {
         assert_eq!(usize::MAX, ClampedMul::<usize, usize>::clamped_mul(usize::MAX, usize::MAX));
     }
 
+    #[test]
+    fn test_on_key_name() {
+        let key_down = |keycode, keymod| Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            keymod,
+            repeat: false,
+        };
+        let text_input =
+            |text: &str| Event::TextInput { timestamp: 0, window_id: 0, text: text.to_owned() };
+
+        assert_eq!(Some("UP".to_owned()), on_key_name(&key_down(Keycode::Up, Mod::NOMOD)));
+        assert_eq!(Some("ESC".to_owned()), on_key_name(&key_down(Keycode::Escape, Mod::NOMOD)));
+        assert_eq!(Some("F1".to_owned()), on_key_name(&key_down(Keycode::F1, Mod::NOMOD)));
+        assert_eq!(Some("F12".to_owned()), on_key_name(&key_down(Keycode::F12, Mod::LSHIFTMOD)));
+        assert_eq!(Some("A".to_owned()), on_key_name(&text_input("a")));
+        assert_eq!(Some("SPACE".to_owned()), on_key_name(&text_input(" ")));
+
+        assert_eq!(None, on_key_name(&key_down(Keycode::A, Mod::NOMOD)));
+        assert_eq!(None, on_key_name(&key_down(Keycode::C, Mod::LCTRLMOD)));
+        assert_eq!(None, on_key_name(&key_down(Keycode::F1, Mod::RCTRLMOD)));
+        assert_eq!(None, on_key_name(&Event::Quit { timestamp: 0 }));
+    }
+
+    #[test]
+    fn test_key_to_scancode() {
+        assert_eq!(Some(Scancode::Left), key_to_scancode(&Key::ArrowLeft));
+        assert_eq!(Some(Scancode::Return), key_to_scancode(&Key::NewLine));
+        assert_eq!(Some(Scancode::Escape), key_to_scancode(&Key::Escape));
+        assert_eq!(Some(Scancode::A), key_to_scancode(&Key::Char('a')));
+        assert_eq!(Some(Scancode::Num5), key_to_scancode(&Key::Char('5')));
+        assert_eq!(Some(Scancode::Space), key_to_scancode(&Key::Char(' ')));
+        assert_eq!(None, key_to_scancode(&Key::Char('\u{e9}')));
+        assert_eq!(None, key_to_scancode(&Key::Eof));
+    }
+
     #[test]
     fn test_rect_origin_size() {
         assert_eq!(
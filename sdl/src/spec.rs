@@ -15,18 +15,13 @@
 
 //! Configuration support for the graphical console.
 
-use std::fs::File;
-use std::io::{self, Write};
-use std::path::{Path, PathBuf};
+use std::io;
+use std::path::Path;
 use std::str::FromStr;
-use tempfile::TempDir;
 
 /// Default resolution to use when none is provided.
 const DEFAULT_RESOLUTION_PIXELS: (u32, u32) = (800, 600);
 
-/// Default font to use when none is provided.
-const DEFAULT_FONT_BYTES: &[u8] = include_bytes!("IBMPlexMono-Regular-6.0.0.ttf");
-
 /// Default font size.
 const DEFAULT_FONT_SIZE: u16 = 16;
 
@@ -112,7 +107,12 @@ fn parse_resolution(mut resolution: &str) -> io::Result<Resolution> {
 }
 
 /// Parses a graphical console specification.
-pub(crate) fn parse_graphics_spec(params: &str) -> io::Result<(Resolution, Option<&Path>, u16)> {
+///
+/// Returns the resolution, the font path, the font size, and whether to pace explicit video syncs
+/// to the refresh rate of the display.
+pub(crate) fn parse_graphics_spec(
+    params: &str,
+) -> io::Result<(Resolution, Option<&Path>, u16, bool)> {
     let invalid_spec =
         Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid graphics console spec"));
 
@@ -132,31 +132,18 @@ pub(crate) fn parse_graphics_spec(params: &str) -> io::Result<(Resolution, Optio
         Some(font_size) => parse_str(font_size, "Invalid font size")?,
         None => DEFAULT_FONT_SIZE,
     };
+    let vsync = match params.next() {
+        Some("") | Some("novsync") | None => false,
+        Some("vsync") => true,
+        Some(_) => {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid vsync setting"))
+        }
+    };
     if params.next().is_some() {
         return invalid_spec;
     }
 
-    Ok((resolution, font_path, font_size))
-}
-
-/// Context to maintain a font on disk temporarily.
-pub(crate) struct TempFont {
-    dir: TempDir,
-}
-
-impl TempFont {
-    /// Gets an instance of the default font.
-    pub(crate) fn default_font() -> io::Result<Self> {
-        let dir = tempfile::tempdir()?;
-        let mut file = File::create(dir.path().join("font.ttf"))?;
-        file.write_all(DEFAULT_FONT_BYTES)?;
-        Ok(Self { dir })
-    }
-
-    /// Gets the path to the temporary font.
-    pub(crate) fn path(&self) -> PathBuf {
-        self.dir.path().join("font.ttf")
-    }
+    Ok((resolution, font_path, font_size, vsync))
 }
 
 #[cfg(test)]
@@ -197,11 +184,12 @@ mod tests {
 
     #[test]
     fn test_parse_graphics_spec_empty() {
-        for spec in ["", ",", ",,"] {
+        for spec in ["", ",", ",,", ",,,"] {
             let spec = parse_graphics_spec(spec).unwrap();
             assert_eq!(default_resolution(), spec.0);
             assert_eq!(None, spec.1);
             assert_eq!(DEFAULT_FONT_SIZE, spec.2);
+            assert!(!spec.3);
         }
     }
 
@@ -233,12 +221,25 @@ mod tests {
         assert_eq!(32, spec.2);
     }
 
+    #[test]
+    fn test_parse_graphics_spec_only_vsync() {
+        let spec = parse_graphics_spec(",,,vsync").unwrap();
+        assert_eq!(default_resolution(), spec.0);
+        assert_eq!(None, spec.1);
+        assert_eq!(DEFAULT_FONT_SIZE, spec.2);
+        assert!(spec.3);
+
+        let spec = parse_graphics_spec(",,,novsync").unwrap();
+        assert!(!spec.3);
+    }
+
     #[test]
     fn test_parse_graphics_spec_full() {
-        let spec = parse_graphics_spec("1x2,/path/to/font.ttf,24").unwrap();
+        let spec = parse_graphics_spec("1x2,/path/to/font.ttf,24,vsync").unwrap();
         assert_eq!(Resolution::windowed(1, 2).unwrap(), spec.0);
         assert_eq!(Some(Path::new("/path/to/font.ttf")), spec.1);
         assert_eq!(24, spec.2);
+        assert!(spec.3);
     }
 
     #[test]
@@ -246,8 +247,9 @@ mod tests {
         fn check(exp_error: &str, s: &str) {
             assert_eq!(exp_error, format!("{}", parse_graphics_spec(s).unwrap_err()));
         }
-        check("Invalid graphics console spec", ",,,,");
-        check("Invalid graphics console spec", "800x600,font.ttf,16,abc");
+        check("Invalid graphics console spec", ",,,,,");
+        check("Invalid graphics console spec", "800x600,font.ttf,16,vsync,abc");
+        check("Invalid vsync setting", "800x600,font.ttf,16,abc");
         check("Invalid resolution format", "a,font.ttf,16");
         check("Invalid font size", "100x200,font.ttf,a");
     }
@@ -16,18 +16,122 @@
 //! Array-related functions for EndBASIC.
 
 use async_trait::async_trait;
-use endbasic_core::ast::{Expr, FunctionCallSpan, Value, VarType};
+use endbasic_core::ast::{
+    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarRef, VarType,
+};
 use endbasic_core::eval;
 use endbasic_core::exec::Machine;
 use endbasic_core::syms::{
-    Array, CallError, CallableMetadata, CallableMetadataBuilder, Function, FunctionResult, Symbol,
-    Symbols,
+    Array, CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
+    FunctionResult, Symbol, Symbols,
 };
+use endbasic_core::LineCol;
+use std::cmp::Ordering;
 use std::rc::Rc;
 
 /// Category description for all symbols provided by this module.
 const CATEGORY: &str = "Array functions";
 
+/// Extracts the reference to an array and its position from an `expr` passed as an argument to a
+/// function, or returns `None` if the expression does not look like an array reference.
+///
+/// This accepts both `array` and `array()` as references to the array, as the latter makes it
+/// clearer that the argument is an array and not a scalar.
+pub(crate) fn expr_to_array_ref(expr: &Expr) -> Option<(&VarRef, LineCol)> {
+    match expr {
+        Expr::Symbol(span) => Some((&span.vref, span.pos)),
+        Expr::Call(span) if span.args.is_empty() => Some((&span.fref, span.pos)),
+        _ => None,
+    }
+}
+
+/// Looks up the array referenced by `arrayref` in `symbols` for reading.
+fn get_array<'a>(
+    symbols: &'a Symbols,
+    arrayref: &VarRef,
+    arraypos: LineCol,
+) -> Result<&'a Array, CallError> {
+    match symbols.get(arrayref).map_err(|e| eval::Error::from_value_error(e, arraypos))? {
+        Some(Symbol::Array(array)) => Ok(array),
+        Some(_) => Err(CallError::ArgumentError(
+            arraypos,
+            format!("{} must be an array reference", arrayref),
+        )),
+        None => Err(CallError::ArgumentError(arraypos, format!("{} is not defined", arrayref))),
+    }
+}
+
+/// Looks up the one-dimensional array referenced by `arrayref` in `symbols` for modification.
+///
+/// `action` describes what the caller intends to do with the array and is used to report errors
+/// on arrays with more than one dimension.
+fn get_one_dim_array_mut<'a>(
+    symbols: &'a mut Symbols,
+    arrayref: &VarRef,
+    arraypos: LineCol,
+    action: &str,
+) -> Result<&'a mut Array, CallError> {
+    let array = match symbols
+        .get_mut(arrayref)
+        .map_err(|e| eval::Error::from_value_error(e, arraypos))?
+    {
+        Some(Symbol::Array(array)) => array,
+        Some(_) => {
+            return Err(CallError::ArgumentError(
+                arraypos,
+                format!("{} must be an array reference", arrayref),
+            ))
+        }
+        None => {
+            return Err(CallError::ArgumentError(arraypos, format!("{} is not defined", arrayref)))
+        }
+    };
+    if array.dimensions().len() != 1 {
+        return Err(CallError::ArgumentError(
+            arraypos,
+            format!("Cannot {} multidimensional arrays", action),
+        ));
+    }
+    Ok(array)
+}
+
+/// Replaces the contents of the one-dimensional `array` with `values`.
+fn assign_all(array: &mut Array, values: Vec<Value>) {
+    for (i, value) in values.into_iter().enumerate() {
+        array.assign(&[i as i32], value).expect("Values come from the same array");
+    }
+}
+
+/// Computes the sum of all elements in the numeric `array`.
+fn sum_values(array: &Array, arrayref: &VarRef, arraypos: LineCol) -> Result<f64, CallError> {
+    let mut sum = 0.0;
+    for value in array.values() {
+        match value {
+            Value::Integer(_) | Value::Long(_) | Value::Double(_) => {
+                sum += value.as_f64().expect("Numeric values must convert to doubles")
+            }
+            _ => {
+                return Err(CallError::ArgumentError(
+                    arraypos,
+                    format!("{} must be an array of numbers", arrayref),
+                ))
+            }
+        }
+    }
+    Ok(sum)
+}
+
+/// Compares two elements of the same sortable array.
+fn compare_elements(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Double(a), Value::Double(b)) => a.total_cmp(b),
+        (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+        (Value::Long(a), Value::Long(b)) => a.cmp(b),
+        (Value::Text(a), Value::Text(b)) => a.cmp(b),
+        _ => unreachable!("Array elements must all have the same sortable type"),
+    }
+}
+
 /// Extracts the array reference and the dimension number from the list of arguments passed to
 /// either `LBOUND` or `UBOUND`.
 #[allow(clippy::needless_lifetimes)]
@@ -37,9 +141,9 @@ async fn parse_bound_args<'a>(
 ) -> Result<(&'a Array, usize), CallError> {
     let mut iter = args.iter();
 
-    let (arrayref, arraypos) = match iter.next() {
-        Some(Expr::Symbol(span)) => (&span.vref, span.pos),
-        _ => return Err(CallError::SyntaxError),
+    let (arrayref, arraypos) = match iter.next().and_then(expr_to_array_ref) {
+        Some(arrayref) => arrayref,
+        None => return Err(CallError::SyntaxError),
     };
 
     let dim = match iter.next() {
@@ -48,7 +152,7 @@ async fn parse_bound_args<'a>(
             let i = value
                 .as_i32()
                 .map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
-            if i < 0 {
+            if i < 1 {
                 return Err(CallError::ArgumentError(
                     expr.start_pos(),
                     format!("Dimension {} must be positive", i),
@@ -122,6 +226,7 @@ impl LboundFunction {
                     "Returns the lower bound for the given dimension of the array.
 The lower bound is the smallest available subscript that can be provided to array indexing \
 operations.
+The array can be referenced by its name alone or followed by empty parenthesis, as in a%().
 For one-dimensional arrays, the dimension% is optional.  For multi-dimensional arrays, the \
 dimension% is a 1-indexed integer.",
                 )
@@ -142,6 +247,285 @@ impl Function for LboundFunction {
     }
 }
 
+/// The `AVG` function.
+pub struct AvgFunction {
+    metadata: CallableMetadata,
+}
+
+impl AvgFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("AVG", VarType::Double)
+                .with_syntax("array")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Computes the average of all elements in a numeric array.
+The array can have any number of dimensions and all of its elements are taken into account.
+See SUM for the sum of the elements.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for AvgFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let (arrayref, arraypos) = match span.args.as_slice() {
+            [array] => expr_to_array_ref(array).ok_or(CallError::SyntaxError)?,
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let array = get_array(symbols, arrayref, arraypos)?;
+        let sum = sum_values(array, arrayref, arraypos)?;
+        Ok(Value::Double(sum / (array.values().len() as f64)))
+    }
+}
+
+/// The `FIND` function.
+pub struct FindFunction {
+    metadata: CallableMetadata,
+}
+
+impl FindFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("FIND", VarType::Integer)
+                .with_syntax("array, value[, start%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the position of the first element in an array equal to a value.
+The array must have a single dimension.  The search begins at subscript start%, which defaults \
+to 0, and returns -1 if no element matches value.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for FindFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let (arrayref, arraypos, value_expr, start_expr) = match span.args.as_slice() {
+            [array, value] => match expr_to_array_ref(array) {
+                Some((arrayref, arraypos)) => (arrayref, arraypos, value, None),
+                None => return Err(CallError::SyntaxError),
+            },
+            [array, value, start] => match expr_to_array_ref(array) {
+                Some((arrayref, arraypos)) => (arrayref, arraypos, value, Some(start)),
+                None => return Err(CallError::SyntaxError),
+            },
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let value = value_expr.eval(symbols).await?;
+        let start = match start_expr {
+            Some(expr) => {
+                let start =
+                    expr.eval(symbols).await?.as_i32().map_err(|e| {
+                        CallError::ArgumentError(expr.start_pos(), format!("{}", e))
+                    })?;
+                if start < 0 {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "start% cannot be negative".to_owned(),
+                    ));
+                }
+                start as usize
+            }
+            None => 0,
+        };
+
+        let array = get_array(symbols, arrayref, arraypos)?;
+        if array.dimensions().len() != 1 {
+            return Err(CallError::ArgumentError(
+                arraypos,
+                "Cannot search multidimensional arrays".to_owned(),
+            ));
+        }
+
+        for (i, element) in array.values().iter().enumerate().skip(start) {
+            match element.eq(&value) {
+                Ok(Value::Boolean(true)) => return Ok(Value::Integer(i as i32)),
+                Ok(_) => (),
+                Err(e) => {
+                    return Err(CallError::ArgumentError(value_expr.start_pos(), format!("{}", e)))
+                }
+            }
+        }
+        Ok(Value::Integer(-1))
+    }
+}
+
+/// The `REVERSE` command.
+pub struct ReverseCommand {
+    metadata: CallableMetadata,
+}
+
+impl ReverseCommand {
+    /// Creates a new instance of the command.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("REVERSE", VarType::Void)
+                .with_syntax("array")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Reverses the order of the elements of an array in place.
+The array must have a single dimension.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ReverseCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (arrayref, arraypos) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => {
+                expr_to_array_ref(expr).ok_or(CallError::SyntaxError)?
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let array =
+            get_one_dim_array_mut(machine.get_mut_symbols(), arrayref, arraypos, "reverse")?;
+        let mut values = array.values().to_vec();
+        values.reverse();
+        assign_all(array, values);
+        Ok(())
+    }
+}
+
+/// The `SORT` command.
+pub struct SortCommand {
+    metadata: CallableMetadata,
+}
+
+impl SortCommand {
+    /// Creates a new instance of the command.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SORT", VarType::Void)
+                .with_syntax("array[, descending?]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sorts the elements of an array in place.
+The array must have a single dimension and hold numbers or strings.  Elements are sorted in \
+ascending order unless descending? is TRUE.  Strings are compared character by character, so \
+uppercase letters sort before lowercase letters.
+The sort is stable: elements that compare equal keep their relative order.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SortCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (arrayref, arraypos, descending) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(array), sep: ArgSep::End, .. }] => {
+                let (arrayref, arraypos) =
+                    expr_to_array_ref(array).ok_or(CallError::SyntaxError)?;
+                (arrayref, arraypos, false)
+            }
+            [ArgSpan { expr: Some(array), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(descending), sep: ArgSep::End, .. }] =>
+            {
+                let (arrayref, arraypos) =
+                    expr_to_array_ref(array).ok_or(CallError::SyntaxError)?;
+                match descending.eval(machine.get_mut_symbols()).await? {
+                    Value::Boolean(b) => (arrayref, arraypos, b),
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            descending.start_pos(),
+                            "descending? must be a boolean".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let array = get_one_dim_array_mut(machine.get_mut_symbols(), arrayref, arraypos, "sort")?;
+        match array.subtype() {
+            VarType::Double | VarType::Integer | VarType::Long | VarType::Text => (),
+            _ => {
+                return Err(CallError::ArgumentError(
+                    arraypos,
+                    format!("Cannot sort {}: elements must be numbers or strings", arrayref),
+                ))
+            }
+        }
+
+        let mut values = array.values().to_vec();
+        if descending {
+            values.sort_by(|a, b| compare_elements(b, a));
+        } else {
+            values.sort_by(compare_elements);
+        }
+        assign_all(array, values);
+        Ok(())
+    }
+}
+
+/// The `SUM` function.
+pub struct SumFunction {
+    metadata: CallableMetadata,
+}
+
+impl SumFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SUM", VarType::Double)
+                .with_syntax("array")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Computes the sum of all elements in a numeric array.
+The array can have any number of dimensions and all of its elements are taken into account.
+See AVG for the average of the elements.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for SumFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let (arrayref, arraypos) = match span.args.as_slice() {
+            [array] => expr_to_array_ref(array).ok_or(CallError::SyntaxError)?,
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let array = get_array(symbols, arrayref, arraypos)?;
+        Ok(Value::Double(sum_values(array, arrayref, arraypos)?))
+    }
+}
+
 /// The `UBOUND` function.
 pub struct UboundFunction {
     metadata: CallableMetadata,
@@ -158,6 +542,7 @@ impl UboundFunction {
                     "Returns the upper bound for the given dimension of the array.
 The upper bound is the largest available subscript that can be provided to array indexing \
 operations.
+The array can be referenced by its name alone or followed by empty parenthesis, as in a%().
 For one-dimensional arrays, the dimension% is optional.  For multi-dimensional arrays, the \
 dimension% is a 1-indexed integer.",
                 )
@@ -180,7 +565,12 @@ impl Function for UboundFunction {
 
 /// Adds all symbols provided by this module to the given `machine`.
 pub fn add_all(machine: &mut Machine) {
+    machine.add_function(AvgFunction::new());
+    machine.add_function(FindFunction::new());
     machine.add_function(LboundFunction::new());
+    machine.add_command(ReverseCommand::new());
+    machine.add_command(SortCommand::new());
+    machine.add_function(SumFunction::new());
     machine.add_function(UboundFunction::new());
 }
 
@@ -189,6 +579,9 @@ mod tests {
     use crate::testutils::*;
     use endbasic_core::ast::{Value, VarType};
 
+    /// Code to define a one-dimensional integer array `a` with unsorted contents.
+    const UNSORTED_INTS: &str = "DIM a(5): a(0) = 3: a(1) = -1: a(2) = 8: a(3) = 3: a(4) = 0";
+
     /// Validates error handling of `LBOUND` and `UBOUND` as given in `func`.
     fn do_bound_errors_test(func: &str) {
         Tester::default()
@@ -209,6 +602,24 @@ mod tests {
             .expect_array("x", VarType::Integer, &[2], vec![])
             .check();
 
+        Tester::default()
+            .run(format!("DIM x(2): result = {}(x, 0)", func))
+            .expect_err(format!("1:20: In call to {}: 1:30: Dimension 0 must be positive", func))
+            .expect_array("x", VarType::Integer, &[2], vec![])
+            .check();
+
+        Tester::default()
+            .run(format!("DIM x(2): result = {}(x(), 1, 2)", func))
+            .expect_err(format!("1:20: In call to {}: expected array[, dimension%]", func))
+            .expect_array("x", VarType::Integer, &[2], vec![])
+            .check();
+
+        Tester::default()
+            .run(format!("DIM x(2): result = {}(x(0))", func))
+            .expect_err(format!("1:20: In call to {}: expected array[, dimension%]", func))
+            .expect_array("x", VarType::Integer, &[2], vec![])
+            .check();
+
         Tester::default()
             .run(&format!("DIM x(2): result = {}(x, TRUE)", func))
             .expect_err(format!("1:20: In call to {}: 1:30: TRUE is not a number", func))
@@ -254,6 +665,100 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_avg_ok() {
+        Tester::default()
+            .run(format!("{}: result = AVG(a)", UNSORTED_INTS))
+            .expect_var("result", 2.6)
+            .expect_array_simple(
+                "a",
+                VarType::Integer,
+                vec![3.into(), (-1).into(), 8.into(), 3.into(), 0.into()],
+            )
+            .check();
+
+        Tester::default()
+            .run("DIM a(2, 2) AS DOUBLE: a(0, 0) = 1.5: a(1, 1) = 4.5: result = AVG(a())")
+            .expect_var("result", 1.5)
+            .expect_array(
+                "a",
+                VarType::Double,
+                &[2, 2],
+                vec![(&[0, 0], 1.5.into()), (&[1, 1], 4.5.into())],
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_avg_errors() {
+        check_expr_error("1:10: In call to AVG: expected array", "AVG()");
+        check_expr_error("1:10: In call to AVG: expected array", "AVG(3)");
+        check_expr_error("1:10: In call to AVG: 1:14: a is not defined", "AVG(a)");
+
+        Tester::default()
+            .run("DIM a(2) AS STRING: result = AVG(a)")
+            .expect_err("1:30: In call to AVG: 1:34: a must be an array of numbers")
+            .expect_array("a", VarType::Text, &[2], vec![])
+            .check();
+    }
+
+    #[test]
+    fn test_find_ok() {
+        Tester::default()
+            .run(format!(
+                "{}: r1 = FIND(a, 3): r2 = FIND(a, 3, 1): r3 = FIND(a, 3.0, 4): r4 = FIND(a(), 8.2)",
+                UNSORTED_INTS
+            ))
+            .expect_var("r1", 0)
+            .expect_var("r2", 3)
+            .expect_var("r3", -1)
+            .expect_var("r4", -1)
+            .expect_array_simple(
+                "a",
+                VarType::Integer,
+                vec![3.into(), (-1).into(), 8.into(), 3.into(), 0.into()],
+            )
+            .check();
+
+        Tester::default()
+            .run("DIM a(3) AS STRING: a(1) = \"x\": r1 = FIND(a, \"x\"): r2 = FIND(a, \"\", 1)")
+            .expect_var("r1", 1)
+            .expect_var("r2", 2)
+            .expect_array("a", VarType::Text, &[3], vec![(&[1], "x".into())])
+            .check();
+
+        Tester::default()
+            .run("DIM a(3): result = FIND(a, 0, 10)")
+            .expect_var("result", -1)
+            .expect_array("a", VarType::Integer, &[3], vec![])
+            .check();
+    }
+
+    #[test]
+    fn test_find_errors() {
+        check_expr_error("1:10: In call to FIND: expected array, value[, start%]", "FIND()");
+        check_expr_error("1:10: In call to FIND: expected array, value[, start%]", "FIND(3, 3)");
+        check_expr_error("1:10: In call to FIND: 1:15: a is not defined", "FIND(a, 3)");
+
+        Tester::default()
+            .run("DIM a(3): result = FIND(a, \"x\")")
+            .expect_err("1:20: In call to FIND: 1:28: Cannot compare 0 and \"x\" with =")
+            .expect_array("a", VarType::Integer, &[3], vec![])
+            .check();
+
+        Tester::default()
+            .run("DIM a(3): result = FIND(a, 1, -1)")
+            .expect_err("1:20: In call to FIND: 1:31: start% cannot be negative")
+            .expect_array("a", VarType::Integer, &[3], vec![])
+            .check();
+
+        Tester::default()
+            .run("DIM a(3, 2): result = FIND(a, 1)")
+            .expect_err("1:23: In call to FIND: 1:28: Cannot search multidimensional arrays")
+            .expect_array("a", VarType::Integer, &[3, 2], vec![])
+            .check();
+    }
+
     #[test]
     fn test_lbound_ok() {
         Tester::default()
@@ -273,6 +778,12 @@ mod tests {
             .expect_var("result", 0i32)
             .expect_array("x", VarType::Integer, &[10, 20], vec![])
             .check();
+
+        Tester::default()
+            .run("DIM x(10, 20): result = LBOUND(x%(), 2)")
+            .expect_var("result", 0i32)
+            .expect_array("x", VarType::Integer, &[10, 20], vec![])
+            .check();
     }
 
     #[test]
@@ -280,6 +791,165 @@ mod tests {
         do_bound_errors_test("LBOUND");
     }
 
+    #[test]
+    fn test_reverse_ok() {
+        Tester::default()
+            .run(format!("{}: REVERSE a", UNSORTED_INTS))
+            .expect_array_simple(
+                "a",
+                VarType::Integer,
+                vec![0.into(), 3.into(), 8.into(), (-1).into(), 3.into()],
+            )
+            .check();
+
+        Tester::default()
+            .run("DIM a(2) AS STRING: a(0) = \"x\": REVERSE a()")
+            .expect_array_simple("a", VarType::Text, vec!["".into(), "x".into()])
+            .check();
+    }
+
+    #[test]
+    fn test_reverse_errors() {
+        check_stmt_err("1:1: In call to REVERSE: expected array", "REVERSE");
+        check_stmt_err("1:1: In call to REVERSE: expected array", "REVERSE 3");
+        check_stmt_err("1:1: In call to REVERSE: expected array", "REVERSE a, a");
+        check_stmt_err("1:1: In call to REVERSE: 1:9: a is not defined", "REVERSE a");
+
+        Tester::default()
+            .run("a = 3: REVERSE a")
+            .expect_err("1:8: In call to REVERSE: 1:16: a must be an array reference")
+            .expect_var("a", 3)
+            .check();
+
+        Tester::default()
+            .run("DIM a(3, 2): REVERSE a")
+            .expect_err("1:14: In call to REVERSE: 1:22: Cannot reverse multidimensional arrays")
+            .expect_array("a", VarType::Integer, &[3, 2], vec![])
+            .check();
+    }
+
+    #[test]
+    fn test_sort_ok() {
+        Tester::default()
+            .run(format!("{}: SORT a", UNSORTED_INTS))
+            .expect_array_simple(
+                "a",
+                VarType::Integer,
+                vec![(-1).into(), 0.into(), 3.into(), 3.into(), 8.into()],
+            )
+            .check();
+
+        Tester::default()
+            .run(format!("{}: SORT a(), TRUE", UNSORTED_INTS))
+            .expect_array_simple(
+                "a",
+                VarType::Integer,
+                vec![8.into(), 3.into(), 3.into(), 0.into(), (-1).into()],
+            )
+            .check();
+
+        Tester::default()
+            .run("DIM a(3) AS DOUBLE: a(0) = 2.5: a(1) = -0.5: SORT a")
+            .expect_array_simple("a", VarType::Double, vec![(-0.5).into(), 0.0.into(), 2.5.into()])
+            .check();
+
+        Tester::default()
+            .run("DIM a(4) AS STRING: a(0) = \"b\": a(1) = \"B\": a(2) = \"a\": SORT a")
+            .expect_array_simple(
+                "a",
+                VarType::Text,
+                vec!["".into(), "B".into(), "a".into(), "b".into()],
+            )
+            .check();
+
+        Tester::default()
+            .run("DIM a(2) AS LONG: a(0) = 5000000000: SORT a, FALSE")
+            .expect_array_simple("a", VarType::Long, vec![Value::Long(0), Value::Long(5000000000)])
+            .check();
+    }
+
+    #[test]
+    fn test_sort_large() {
+        Tester::default()
+            .run("DIM a(10000): FOR i = 0 TO 9999: a(i) = 9999 - i: NEXT: SORT a")
+            .expect_var("i", 10000)
+            .expect_array_simple("a", VarType::Integer, (0..10000).map(Value::Integer).collect())
+            .check();
+    }
+
+    #[test]
+    fn test_sort_errors() {
+        check_stmt_err("1:1: In call to SORT: expected array[, descending?]", "SORT");
+        check_stmt_err("1:1: In call to SORT: expected array[, descending?]", "SORT 3");
+        check_stmt_err("1:1: In call to SORT: expected array[, descending?]", "SORT a; TRUE");
+        check_stmt_err("1:1: In call to SORT: expected array[, descending?]", "SORT a, TRUE, 1");
+        check_stmt_err("1:1: In call to SORT: 1:6: a is not defined", "SORT a");
+
+        Tester::default()
+            .run("DIM a(3): SORT a, 1")
+            .expect_err("1:11: In call to SORT: 1:19: descending? must be a boolean")
+            .expect_array("a", VarType::Integer, &[3], vec![])
+            .check();
+
+        Tester::default()
+            .run("DIM a(3) AS BOOLEAN: SORT a")
+            .expect_err(
+                "1:22: In call to SORT: 1:27: Cannot sort a: elements must be numbers or strings",
+            )
+            .expect_array("a", VarType::Boolean, &[3], vec![])
+            .check();
+
+        Tester::default()
+            .run("DIM a(3, 2): SORT a")
+            .expect_err("1:14: In call to SORT: 1:19: Cannot sort multidimensional arrays")
+            .expect_array("a", VarType::Integer, &[3, 2], vec![])
+            .check();
+    }
+
+    #[test]
+    fn test_sum_ok() {
+        Tester::default()
+            .run(format!("{}: result = SUM(a)", UNSORTED_INTS))
+            .expect_var("result", 13.0)
+            .expect_array_simple(
+                "a",
+                VarType::Integer,
+                vec![3.into(), (-1).into(), 8.into(), 3.into(), 0.into()],
+            )
+            .check();
+
+        Tester::default()
+            .run("DIM a(2, 2) AS DOUBLE: a(0, 1) = 1.25: a(1, 0) = 2.5: result = SUM(a)")
+            .expect_var("result", 3.75)
+            .expect_array(
+                "a",
+                VarType::Double,
+                &[2, 2],
+                vec![(&[0, 1], 1.25.into()), (&[1, 0], 2.5.into())],
+            )
+            .check();
+    }
+
+    #[test]
+    fn test_sum_errors() {
+        check_expr_error("1:10: In call to SUM: expected array", "SUM()");
+        check_expr_error("1:10: In call to SUM: expected array", "SUM(3)");
+        check_expr_error("1:10: In call to SUM: expected array", "SUM(a, a)");
+        check_expr_error("1:10: In call to SUM: 1:14: a is not defined", "SUM(a)");
+
+        Tester::default()
+            .run("a = 3: result = SUM(a)")
+            .expect_err("1:17: In call to SUM: 1:21: a must be an array reference")
+            .expect_var("a", 3)
+            .check();
+
+        Tester::default()
+            .run("DIM a(2) AS BOOLEAN: result = SUM(a)")
+            .expect_err("1:31: In call to SUM: 1:35: a must be an array of numbers")
+            .expect_array("a", VarType::Boolean, &[2], vec![])
+            .check();
+    }
+
     #[test]
     fn test_ubound_ok() {
         Tester::default()
@@ -299,6 +969,12 @@ mod tests {
             .expect_var("result", 19i32)
             .expect_array("x", VarType::Integer, &[10, 20], vec![])
             .check();
+
+        Tester::default()
+            .run("DIM x(10, 20): result = UBOUND(x%(), 2)")
+            .expect_var("result", 19i32)
+            .expect_array("x", VarType::Integer, &[10, 20], vec![])
+            .check();
     }
 
     #[test]
@@ -0,0 +1,1429 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Dynamically-sized collections of values.
+
+use async_trait::async_trait;
+use endbasic_core::ast::{
+    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarType,
+};
+use endbasic_core::eval::eval_all;
+use endbasic_core::exec::{Clearable, Machine};
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
+    FunctionResult, Symbols,
+};
+use endbasic_core::LineCol;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Collections
+Collections hold an arbitrary number of values and grow and shrink as needed, unlike arrays, \
+whose size must be known upfront.
+Collections are not stored in variables directly.  Instead, the functions that create them return \
+an integer handle that identifies the collection, and this handle must be passed to all other \
+functions and commands that operate on the collection.  All collections are destroyed by CLEAR, \
+which also invalidates their handles.
+Dictionaries map string keys to values of any type.  Create a dictionary with DICT, add or modify \
+its entries with DICT_SET, and query them with DICT_GET.  Keys are case-sensitive and are kept \
+sorted so that DICT_KEY can be used to iterate over them in a predictable order.
+Lists hold a sequence of values of any type.  Create a list with LIST_NEW, add values to it with \
+LIST_APPEND or LIST_INSERT, and query them with LIST_GET.  List indexes start at 0, so iterating \
+from 0 to LIST_LEN%(handle%) - 1 visits all elements of a list in order.  Alternatively, FOR EACH \
+value IN handle visits all elements of the list whose handle is stored in the handle variable.
+Collections can be converted from and to JSON documents with JSON_PARSE and JSON_FORMAT$.";
+
+/// An element stored in a collection.
+#[derive(Clone)]
+enum Element {
+    /// A JSON `null`, which reads back as an empty string.
+    Null,
+
+    /// A regular value.
+    Value(Value),
+
+    /// A nested collection, identified by its handle.
+    Collection(i32),
+}
+
+impl Element {
+    /// Returns the value that programs see when reading this element.
+    fn to_value(&self) -> Value {
+        match self {
+            Element::Null => Value::Text("".to_owned()),
+            Element::Value(value) => value.clone(),
+            Element::Collection(handle) => Value::Integer(*handle),
+        }
+    }
+}
+
+/// Contents of a dictionary.
+///
+/// Entries are kept in a vector sorted by key instead of in a map so that `DICT_KEY` can fetch the
+/// key at any position in constant time, which makes iterating over all keys linear.
+#[derive(Default)]
+struct Dict {
+    entries: Vec<(String, Element)>,
+}
+
+impl Dict {
+    /// Locates `key` in the sorted entries, returning its position or where it should be inserted.
+    fn find(&self, key: &str) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| k.as_str().cmp(key))
+    }
+
+    /// Returns the element associated with `key`, if any.
+    fn get(&self, key: &str) -> Option<&Element> {
+        self.find(key).ok().map(|i| &self.entries[i].1)
+    }
+
+    /// Returns true if the dictionary has an entry for `key`.
+    fn contains_key(&self, key: &str) -> bool {
+        self.find(key).is_ok()
+    }
+
+    /// Associates `element` with `key`, replacing any previous element.
+    fn insert(&mut self, key: String, element: Element) {
+        match self.find(&key) {
+            Ok(i) => self.entries[i].1 = element,
+            Err(i) => self.entries.insert(i, (key, element)),
+        }
+    }
+
+    /// Removes the entry for `key`, if any.
+    fn remove(&mut self, key: &str) {
+        if let Ok(i) = self.find(key) {
+            self.entries.remove(i);
+        }
+    }
+
+    /// Returns the number of entries in the dictionary.
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the key at position `index` in sorted order.
+    fn key_at(&self, index: usize) -> &str {
+        &self.entries[index].0
+    }
+
+    /// Returns an iterator over all entries in sorted key order.
+    fn iter(&self) -> impl Iterator<Item = &(String, Element)> {
+        self.entries.iter()
+    }
+}
+
+impl FromIterator<(String, Element)> for Dict {
+    fn from_iter<I: IntoIterator<Item = (String, Element)>>(iter: I) -> Self {
+        let mut dict = Dict::default();
+        for (key, element) in iter {
+            dict.insert(key, element);
+        }
+        dict
+    }
+}
+
+/// Storage for all collections created by the program.
+#[derive(Default)]
+pub(crate) struct Collections {
+    /// Handle to assign to the next collection that is created.
+    next_handle: i32,
+
+    /// Mapping of handles to dictionaries.
+    dicts: HashMap<i32, Dict>,
+
+    /// Mapping of handles to lists.
+    lists: HashMap<i32, Vec<Element>>,
+}
+
+impl Collections {
+    /// Allocates a new handle.
+    fn new_handle(&mut self) -> i32 {
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    /// Obtains the dictionary identified by `handle`, which was provided at `pos`.
+    fn get_dict(&mut self, handle: i32, pos: LineCol) -> Result<&mut Dict, CallError> {
+        match self.dicts.get_mut(&handle) {
+            Some(dict) => Ok(dict),
+            None => {
+                Err(CallError::ArgumentError(pos, format!("Invalid dictionary handle {}", handle)))
+            }
+        }
+    }
+
+    /// Obtains the list identified by `handle`, which was provided at `pos`.
+    fn get_list(&mut self, handle: i32, pos: LineCol) -> Result<&mut Vec<Element>, CallError> {
+        match self.lists.get_mut(&handle) {
+            Some(list) => Ok(list),
+            None => Err(CallError::ArgumentError(pos, format!("Invalid list handle {}", handle))),
+        }
+    }
+
+    /// Converts a parsed JSON `value` into an element, creating new collections for all objects
+    /// and arrays found in it.
+    fn import_json(&mut self, value: serde_json::Value) -> Element {
+        match value {
+            serde_json::Value::Null => Element::Null,
+            serde_json::Value::Bool(b) => Element::Value(Value::Boolean(b)),
+            serde_json::Value::Number(n) => match n.as_i64().map(i32::try_from) {
+                Some(Ok(i)) => Element::Value(Value::Integer(i)),
+                _ => Element::Value(Value::Double(n.as_f64().unwrap_or(f64::NAN))),
+            },
+            serde_json::Value::String(s) => Element::Value(Value::Text(s)),
+            serde_json::Value::Array(values) => {
+                let handle = self.new_handle();
+                let list = values.into_iter().map(|v| self.import_json(v)).collect();
+                self.lists.insert(handle, list);
+                Element::Collection(handle)
+            }
+            serde_json::Value::Object(entries) => {
+                let handle = self.new_handle();
+                let dict = entries.into_iter().map(|(k, v)| (k, self.import_json(v))).collect();
+                self.dicts.insert(handle, dict);
+                Element::Collection(handle)
+            }
+        }
+    }
+
+    /// Converts the collection identified by `handle` into a JSON value.
+    fn export_json(&self, handle: i32) -> Result<serde_json::Value, String> {
+        if let Some(dict) = self.dicts.get(&handle) {
+            let mut entries = serde_json::Map::new();
+            for (key, element) in dict.iter() {
+                entries.insert(key.clone(), self.export_element(element)?);
+            }
+            Ok(serde_json::Value::Object(entries))
+        } else if let Some(list) = self.lists.get(&handle) {
+            let mut values = Vec::with_capacity(list.len());
+            for element in list {
+                values.push(self.export_element(element)?);
+            }
+            Ok(serde_json::Value::Array(values))
+        } else {
+            Err(format!("Invalid collection handle {}", handle))
+        }
+    }
+
+    /// Converts a single `element` into a JSON value.
+    fn export_element(&self, element: &Element) -> Result<serde_json::Value, String> {
+        match element {
+            Element::Null => Ok(serde_json::Value::Null),
+            Element::Value(Value::Boolean(b)) => Ok(serde_json::Value::Bool(*b)),
+            Element::Value(Value::Double(d)) => match serde_json::Number::from_f64(*d) {
+                Some(n) => Ok(serde_json::Value::Number(n)),
+                None => Err(format!("Cannot represent {} in JSON", d)),
+            },
+            Element::Value(Value::Integer(i)) => Ok(serde_json::Value::Number((*i).into())),
+            Element::Value(Value::Long(i)) => Ok(serde_json::Value::Number((*i).into())),
+            Element::Value(Value::Text(s)) => Ok(serde_json::Value::String(s.clone())),
+            Element::Collection(handle) => self.export_json(*handle),
+        }
+    }
+}
+
+/// Clearable for the collections state.
+struct CollectionsClearable {
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl Clearable for CollectionsClearable {
+    fn reset_state(&self, _syms: &mut Symbols) {
+        *self.collections.borrow_mut() = Collections::default();
+    }
+}
+
+/// Converts the `value` provided at `pos` to a collection handle.
+fn value_to_handle(value: &Value, pos: LineCol) -> Result<i32, CallError> {
+    match value {
+        Value::Integer(i) => Ok(*i),
+        _ => Err(CallError::ArgumentError(pos, "Collection handle must be an integer".to_owned())),
+    }
+}
+
+/// Converts the `value` provided at `pos` to a dictionary key.
+fn value_to_key(value: Value, pos: LineCol) -> Result<String, CallError> {
+    match value {
+        Value::Text(s) => Ok(s),
+        _ => Err(CallError::ArgumentError(pos, "Dictionary key must be a string".to_owned())),
+    }
+}
+
+/// Converts the `value` provided at `pos` to an index into a list.
+fn value_to_index(value: &Value, pos: LineCol) -> Result<i32, CallError> {
+    match value {
+        Value::Integer(i) => Ok(*i),
+        _ => Err(CallError::ArgumentError(pos, "List index must be an integer".to_owned())),
+    }
+}
+
+/// Validates that `index`, provided at `pos`, is within the bounds of a collection of length `len`
+/// and returns it as a `usize`.  If `allow_end` is true, `index` may also be equal to `len`.
+fn check_index(index: i32, len: usize, allow_end: bool, pos: LineCol) -> Result<usize, CallError> {
+    if index < 0 || (index as usize) > len || (!allow_end && (index as usize) == len) {
+        return Err(CallError::ArgumentError(pos, format!("Index {} out of range", index)));
+    }
+    Ok(index as usize)
+}
+
+/// Evaluates the `nargs` comma-separated arguments to a command and returns their values along
+/// with the positions where they were provided.
+async fn eval_command_args(
+    span: &BuiltinCallSpan,
+    machine: &mut Machine,
+    nargs: usize,
+) -> Result<Vec<(Value, LineCol)>, CallError> {
+    if span.args.len() != nargs {
+        return Err(CallError::SyntaxError);
+    }
+    let mut exprs = Vec::with_capacity(nargs);
+    for (i, arg) in span.args.iter().enumerate() {
+        let exp_sep = if i == nargs - 1 { ArgSep::End } else { ArgSep::Long };
+        match arg {
+            ArgSpan { expr: Some(expr), sep, .. } if *sep == exp_sep => exprs.push(expr),
+            _ => return Err(CallError::SyntaxError),
+        }
+    }
+
+    let mut values = Vec::with_capacity(nargs);
+    for expr in exprs {
+        values.push((expr.eval(machine.get_mut_symbols()).await?, expr.start_pos()));
+    }
+    Ok(values)
+}
+
+/// Returns the position of the `i`th expression in `exprs`.
+fn arg_pos(exprs: &[Expr], i: usize) -> LineCol {
+    exprs[i].start_pos()
+}
+
+/// The `DICT` function.
+pub struct DictFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl DictFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DICT", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Creates a new empty dictionary.
+Returns the handle of the new dictionary, which must be passed to all other DICT_* commands and \
+functions.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for DictFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let mut collections = self.collections.borrow_mut();
+        let handle = collections.new_handle();
+        collections.dicts.insert(handle, Dict::default());
+        Ok(Value::Integer(handle))
+    }
+}
+
+/// The `DICT_GET` function.
+pub struct DictGetFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl DictGetFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DICT_GET", VarType::Auto)
+                .with_syntax("handle%, key$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the value of an entry of a dictionary.
+The type of the returned value is the type of the value stored in the entry.  It is an error to \
+query a key that does not exist, so use DICT_HAS first if unsure.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for DictGetFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [handle, key] => {
+                let handle = value_to_handle(handle, arg_pos(&span.args, 0))?;
+                let key = value_to_key(key.clone(), arg_pos(&span.args, 1))?;
+                let mut collections = self.collections.borrow_mut();
+                let dict = collections.get_dict(handle, arg_pos(&span.args, 0))?;
+                match dict.get(&key) {
+                    Some(element) => Ok(element.to_value()),
+                    None => Err(CallError::ArgumentError(
+                        arg_pos(&span.args, 1),
+                        format!("Key \"{}\" not found", key),
+                    )),
+                }
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `DICT_HAS` function.
+pub struct DictHasFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl DictHasFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DICT_HAS", VarType::Boolean)
+                .with_syntax("handle%, key$")
+                .with_category(CATEGORY)
+                .with_description("Checks if a dictionary has an entry for a key.")
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for DictHasFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [handle, key] => {
+                let handle = value_to_handle(handle, arg_pos(&span.args, 0))?;
+                let key = value_to_key(key.clone(), arg_pos(&span.args, 1))?;
+                let mut collections = self.collections.borrow_mut();
+                let dict = collections.get_dict(handle, arg_pos(&span.args, 0))?;
+                Ok(Value::Boolean(dict.contains_key(&key)))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `DICT_KEY` function.
+pub struct DictKeyFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl DictKeyFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DICT_KEY", VarType::Text)
+                .with_syntax("handle%, index%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the key of a dictionary at a given position.
+Keys are sorted alphabetically and index% starts at 0, so iterating from 0 to DICT_LEN%(handle%) \
+- 1 yields all keys of the dictionary in order.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for DictKeyFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [handle, Value::Integer(index)] => {
+                let handle = value_to_handle(handle, arg_pos(&span.args, 0))?;
+                let mut collections = self.collections.borrow_mut();
+                let dict = collections.get_dict(handle, arg_pos(&span.args, 0))?;
+                let index = check_index(*index, dict.len(), false, arg_pos(&span.args, 1))?;
+                Ok(Value::Text(dict.key_at(index).to_owned()))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `DICT_LEN` function.
+pub struct DictLenFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl DictLenFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DICT_LEN", VarType::Integer)
+                .with_syntax("handle%")
+                .with_category(CATEGORY)
+                .with_description("Returns the number of entries in a dictionary.")
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for DictLenFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [handle] => {
+                let handle = value_to_handle(handle, arg_pos(&span.args, 0))?;
+                let mut collections = self.collections.borrow_mut();
+                let dict = collections.get_dict(handle, arg_pos(&span.args, 0))?;
+                Ok(Value::Integer(dict.len() as i32))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `DICT_REMOVE` command.
+pub struct DictRemoveCommand {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl DictRemoveCommand {
+    /// Creates a new instance of the command.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DICT_REMOVE", VarType::Void)
+                .with_syntax("handle%, key$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Removes an entry from a dictionary.
+Removing a key that does not exist is not an error.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for DictRemoveCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let args = eval_command_args(span, machine, 2).await?;
+        let handle = value_to_handle(&args[0].0, args[0].1)?;
+        let key = value_to_key(args[1].0.clone(), args[1].1)?;
+        let mut collections = self.collections.borrow_mut();
+        let dict = collections.get_dict(handle, args[0].1)?;
+        dict.remove(&key);
+        Ok(())
+    }
+}
+
+/// The `DICT_SET` command.
+pub struct DictSetCommand {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl DictSetCommand {
+    /// Creates a new instance of the command.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DICT_SET", VarType::Void)
+                .with_syntax("handle%, key$, value")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sets the value of an entry of a dictionary.
+If the dictionary already has an entry for key$, its value is replaced, even if the new value has \
+a different type.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for DictSetCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let mut args = eval_command_args(span, machine, 3).await?;
+        let (value, _) = args.pop().expect("Must have three arguments");
+        let handle = value_to_handle(&args[0].0, args[0].1)?;
+        let key = value_to_key(args[1].0.clone(), args[1].1)?;
+        let mut collections = self.collections.borrow_mut();
+        let dict = collections.get_dict(handle, args[0].1)?;
+        dict.insert(key, Element::Value(value));
+        Ok(())
+    }
+}
+
+/// The `JSON_FORMAT` function.
+pub struct JsonFormatFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl JsonFormatFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("JSON_FORMAT", VarType::Text)
+                .with_syntax("handle%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Formats a collection as a JSON document.
+Dictionaries become JSON objects and lists become JSON arrays.  Collections nested by JSON_PARSE \
+are formatted recursively, but integers stored by DICT_SET or the LIST_* commands are always \
+formatted as numbers, even if they happen to be collection handles.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for JsonFormatFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [handle] => {
+                let pos = arg_pos(&span.args, 0);
+                let handle = value_to_handle(handle, pos)?;
+                let collections = self.collections.borrow();
+                let json = collections
+                    .export_json(handle)
+                    .map_err(|e| CallError::ArgumentError(pos, e))?;
+                Ok(Value::Text(json.to_string()))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `JSON_PARSE` function.
+pub struct JsonParseFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl JsonParseFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("JSON_PARSE", VarType::Integer)
+                .with_syntax("json$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Parses a JSON document into collections.
+The document must contain an object or an array at the top level, which become a dictionary or \
+a list respectively.  Returns the handle of the new collection.
+Nested objects and arrays become collections of their own and are stored in their parent as \
+handles, so DICT_GET and LIST_GET return an integer handle for them.  JSON numbers become integers \
+if they fit and doubles otherwise, and null values read back as empty strings.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for JsonParseFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(text)] => {
+                let pos = arg_pos(&span.args, 0);
+                let json: serde_json::Value = serde_json::from_str(text)
+                    .map_err(|e| CallError::ArgumentError(pos, format!("Invalid JSON: {}", e)))?;
+                if !json.is_object() && !json.is_array() {
+                    return Err(CallError::ArgumentError(
+                        pos,
+                        "JSON document must contain an object or an array".to_owned(),
+                    ));
+                }
+                match self.collections.borrow_mut().import_json(json) {
+                    Element::Collection(handle) => Ok(Value::Integer(handle)),
+                    _ => unreachable!("Objects and arrays always become collections"),
+                }
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `LIST_APPEND` command.
+pub struct ListAppendCommand {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl ListAppendCommand {
+    /// Creates a new instance of the command.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LIST_APPEND", VarType::Void)
+                .with_syntax("handle%, value")
+                .with_category(CATEGORY)
+                .with_description("Adds a value to the end of a list.")
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ListAppendCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let mut args = eval_command_args(span, machine, 2).await?;
+        let (value, _) = args.pop().expect("Must have two arguments");
+        let handle = value_to_handle(&args[0].0, args[0].1)?;
+        let mut collections = self.collections.borrow_mut();
+        let list = collections.get_list(handle, args[0].1)?;
+        list.push(Element::Value(value));
+        Ok(())
+    }
+}
+
+/// The `LIST_GET` function.
+pub struct ListGetFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl ListGetFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LIST_GET", VarType::Auto)
+                .with_syntax("handle%, index%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the element of a list at a given position.
+The type of the returned value is the type of the value stored in the element.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ListGetFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [handle, index] => {
+                let handle = value_to_handle(handle, arg_pos(&span.args, 0))?;
+                let index = value_to_index(index, arg_pos(&span.args, 1))?;
+                let mut collections = self.collections.borrow_mut();
+                let list = collections.get_list(handle, arg_pos(&span.args, 0))?;
+                let index = check_index(index, list.len(), false, arg_pos(&span.args, 1))?;
+                Ok(list[index].to_value())
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `LIST_INSERT` command.
+pub struct ListInsertCommand {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl ListInsertCommand {
+    /// Creates a new instance of the command.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LIST_INSERT", VarType::Void)
+                .with_syntax("handle%, index%, value")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Inserts a value into a list at a given position.
+The elements at and after index% are shifted one position to the right.  index% can be equal to \
+the length of the list, in which case this is equivalent to LIST_APPEND.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ListInsertCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let mut args = eval_command_args(span, machine, 3).await?;
+        let (value, _) = args.pop().expect("Must have three arguments");
+        let handle = value_to_handle(&args[0].0, args[0].1)?;
+        let index = value_to_index(&args[1].0, args[1].1)?;
+        let mut collections = self.collections.borrow_mut();
+        let list = collections.get_list(handle, args[0].1)?;
+        let index = check_index(index, list.len(), true, args[1].1)?;
+        list.insert(index, Element::Value(value));
+        Ok(())
+    }
+}
+
+/// The `LIST_LEN` function.
+pub struct ListLenFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl ListLenFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LIST_LEN", VarType::Integer)
+                .with_syntax("handle%")
+                .with_category(CATEGORY)
+                .with_description("Returns the number of elements in a list.")
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ListLenFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [handle] => {
+                let handle = value_to_handle(handle, arg_pos(&span.args, 0))?;
+                let mut collections = self.collections.borrow_mut();
+                let list = collections.get_list(handle, arg_pos(&span.args, 0))?;
+                Ok(Value::Integer(list.len() as i32))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `LIST_NEW` function.
+pub struct ListNewFunction {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl ListNewFunction {
+    /// Creates a new instance of the function.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LIST_NEW", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Creates a new empty list.
+Returns the handle of the new list, which must be passed to all other LIST_* commands and \
+functions.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ListNewFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let mut collections = self.collections.borrow_mut();
+        let handle = collections.new_handle();
+        collections.lists.insert(handle, vec![]);
+        Ok(Value::Integer(handle))
+    }
+}
+
+/// The `LIST_REMOVE` command.
+pub struct ListRemoveCommand {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl ListRemoveCommand {
+    /// Creates a new instance of the command.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LIST_REMOVE", VarType::Void)
+                .with_syntax("handle%, index%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Removes the element of a list at a given position.
+The elements after index% are shifted one position to the left.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ListRemoveCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let args = eval_command_args(span, machine, 2).await?;
+        let handle = value_to_handle(&args[0].0, args[0].1)?;
+        let index = value_to_index(&args[1].0, args[1].1)?;
+        let mut collections = self.collections.borrow_mut();
+        let list = collections.get_list(handle, args[0].1)?;
+        let index = check_index(index, list.len(), false, args[1].1)?;
+        list.remove(index);
+        Ok(())
+    }
+}
+
+/// The `LIST_SET` command.
+pub struct ListSetCommand {
+    metadata: CallableMetadata,
+    collections: Rc<RefCell<Collections>>,
+}
+
+impl ListSetCommand {
+    /// Creates a new instance of the command.
+    pub(crate) fn new(collections: Rc<RefCell<Collections>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LIST_SET", VarType::Void)
+                .with_syntax("handle%, index%, value")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Replaces the element of a list at a given position.
+The new value need not have the same type as the value it replaces.",
+                )
+                .build(),
+            collections,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ListSetCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let mut args = eval_command_args(span, machine, 3).await?;
+        let (value, _) = args.pop().expect("Must have three arguments");
+        let handle = value_to_handle(&args[0].0, args[0].1)?;
+        let index = value_to_index(&args[1].0, args[1].1)?;
+        let mut collections = self.collections.borrow_mut();
+        let list = collections.get_list(handle, args[0].1)?;
+        let index = check_index(index, list.len(), false, args[1].1)?;
+        list[index] = Element::Value(value);
+        Ok(())
+    }
+}
+
+/// Instantiates all symbols in this module and adds them to the `machine`.
+pub fn add_all(machine: &mut Machine) {
+    let collections = Rc::from(RefCell::from(Collections::default()));
+    machine.add_clearable(Box::from(CollectionsClearable { collections: collections.clone() }));
+    machine.set_collection_element_fn({
+        let collections = collections.clone();
+        Box::from(move |handle, index| match collections.borrow().lists.get(&handle) {
+            Some(list) => Ok(list.get(index).map(Element::to_value)),
+            None => Err(format!("Invalid list handle {}", handle)),
+        })
+    });
+    machine.add_function(DictFunction::new(collections.clone()));
+    machine.add_function(DictGetFunction::new(collections.clone()));
+    machine.add_function(DictHasFunction::new(collections.clone()));
+    machine.add_function(DictKeyFunction::new(collections.clone()));
+    machine.add_function(DictLenFunction::new(collections.clone()));
+    machine.add_command(DictRemoveCommand::new(collections.clone()));
+    machine.add_command(DictSetCommand::new(collections.clone()));
+    machine.add_function(JsonFormatFunction::new(collections.clone()));
+    machine.add_function(JsonParseFunction::new(collections.clone()));
+    machine.add_command(ListAppendCommand::new(collections.clone()));
+    machine.add_function(ListGetFunction::new(collections.clone()));
+    machine.add_command(ListInsertCommand::new(collections.clone()));
+    machine.add_function(ListLenFunction::new(collections.clone()));
+    machine.add_function(ListNewFunction::new(collections.clone()));
+    machine.add_command(ListRemoveCommand::new(collections.clone()));
+    machine.add_command(ListSetCommand::new(collections));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testutils::*;
+
+    #[test]
+    fn test_dict_handles() {
+        Tester::default().run("a = DICT: b = DICT").expect_var("a", 1).expect_var("b", 2).check();
+
+        check_expr_error("1:10: In call to DICT: expected no arguments nor parenthesis", "DICT()");
+        check_expr_error("1:10: In call to DICT: expected no arguments nor parenthesis", "DICT(1)");
+    }
+
+    #[test]
+    fn test_dict_set_get() {
+        Tester::default()
+            .run(
+                r#"
+                d = DICT
+                DICT_SET d, "one", 1
+                DICT_SET d, "two", "2"
+                DICT_SET d, "One", TRUE
+                DICT_SET d, "half", 0.5
+                a = DICT_GET(d, "one")
+                b$ = DICT_GET(d, "two")
+                c? = DICT_GET(d, "One")
+                e = DICT_GET(d, "half")
+                DICT_SET d, "one", "replaced"
+                f = DICT_GET(d, "one")
+                "#,
+            )
+            .expect_var("d", 1)
+            .expect_var("a", 1)
+            .expect_var("b", "2")
+            .expect_var("c", true)
+            .expect_var("e", 0.5)
+            .expect_var("f", "replaced")
+            .check();
+    }
+
+    #[test]
+    fn test_dict_has_len_remove() {
+        Tester::default()
+            .run(
+                r#"
+                d = DICT
+                DICT_SET d, "a", 1
+                DICT_SET d, "b", 2
+                l1 = DICT_LEN(d)
+                h1 = DICT_HAS(d, "a")
+                DICT_REMOVE d, "a"
+                DICT_REMOVE d, "missing"
+                l2 = DICT_LEN(d)
+                h2 = DICT_HAS(d, "a")
+                "#,
+            )
+            .expect_var("d", 1)
+            .expect_var("l1", 2)
+            .expect_var("h1", true)
+            .expect_var("l2", 1)
+            .expect_var("h2", false)
+            .check();
+    }
+
+    #[test]
+    fn test_dict_key_iteration() {
+        Tester::default()
+            .run(
+                r#"
+                d = DICT
+                DICT_SET d, "b", 2
+                DICT_SET d, "c", 3
+                DICT_SET d, "a", 1
+                FOR i = 0 TO DICT_LEN(d) - 1
+                    k$ = DICT_KEY(d, i)
+                    PRINT k$; DICT_GET(d, k$)
+                NEXT
+                "#,
+            )
+            .expect_prints(["a 1", "b 2", "c 3"])
+            .expect_var("d", 1)
+            .expect_var("i", 3)
+            .expect_var("k", "c")
+            .check();
+
+        Tester::default()
+            .run(
+                r#"
+                d = DICT
+                DICT_SET d, "d", 4: DICT_SET d, "b", 2: DICT_SET d, "a", 1: DICT_SET d, "c", 3
+                DICT_SET d, "b", 20
+                DICT_REMOVE d, "a"
+                DICT_REMOVE d, "z"
+                FOR i = 0 TO DICT_LEN(d) - 1
+                    k$ = DICT_KEY(d, i)
+                    PRINT k$; DICT_GET(d, k$)
+                NEXT
+                "#,
+            )
+            .expect_prints(["b 20", "c 3", "d 4"])
+            .expect_var("d", 1)
+            .expect_var("i", 3)
+            .expect_var("k", "d")
+            .check();
+    }
+
+    #[test]
+    fn test_dict_clear() {
+        Tester::default()
+            .run("d = DICT: CLEAR: d = DICT")
+            .expect_clear()
+            .expect_var("d", 1)
+            .check();
+
+        Tester::default()
+            .run("d = DICT: CLEAR: PRINT DICT_LEN(1)")
+            .expect_clear()
+            .expect_err("1:24: In call to DICT_LEN: 1:33: Invalid dictionary handle 1")
+            .check();
+    }
+
+    #[test]
+    fn test_dict_errors() {
+        check_expr_error("1:10: In call to DICT_GET: expected handle%, key$", "DICT_GET(1)");
+        check_expr_error(
+            "1:10: In call to DICT_GET: 1:19: Invalid dictionary handle 1",
+            r#"DICT_GET(1, "a")"#,
+        );
+        check_expr_error(
+            "1:10: In call to DICT_GET: 1:19: Collection handle must be an integer",
+            r#"DICT_GET("1", "a")"#,
+        );
+        check_stmt_err(
+            "1:7: In call to DICT_GET: 1:22: Key \"x\" not found",
+            r#"PRINT DICT_GET(DICT, "x")"#,
+        );
+        check_stmt_err(
+            "1:7: In call to DICT_GET: 1:22: Dictionary key must be a string",
+            r#"PRINT DICT_GET(DICT, 1)"#,
+        );
+        Tester::default()
+            .run(r#"d = DICT: DICT_SET d, "x", 1: a = DICT_GET$(d, "x")"#)
+            .expect_var("d", 1)
+            .expect_err("1:35: Value returned by DICT_GET is incompatible with its type annotation")
+            .check();
+
+        check_expr_error("1:10: In call to DICT_HAS: expected handle%, key$", r#"DICT_HAS(1)"#);
+        check_expr_error(
+            "1:10: In call to DICT_KEY: 1:19: Invalid dictionary handle 3",
+            "DICT_KEY(3, 0)",
+        );
+        check_stmt_err(
+            "1:7: In call to DICT_KEY: 1:22: Index 0 out of range",
+            "PRINT DICT_KEY(DICT, 0)",
+        );
+        check_expr_error("1:10: In call to DICT_LEN: expected handle%", "DICT_LEN()");
+
+        check_stmt_err("1:1: In call to DICT_SET: expected handle%, key$, value", "DICT_SET 1, 2");
+        check_stmt_err(
+            "1:1: In call to DICT_SET: 1:10: Invalid dictionary handle 1",
+            r#"DICT_SET 1, "a", 2"#,
+        );
+        check_stmt_err(
+            "1:1: In call to DICT_SET: expected handle%, key$, value",
+            r#"DICT_SET 1; "a", 2"#,
+        );
+        check_stmt_err("1:1: In call to DICT_REMOVE: expected handle%, key$", "DICT_REMOVE 1");
+    }
+
+    #[test]
+    fn test_list_ops() {
+        Tester::default()
+            .run(
+                r#"
+                l = LIST_NEW
+                LIST_APPEND l, 10
+                LIST_APPEND l, "b"
+                LIST_INSERT l, 0, TRUE
+                LIST_INSERT l, 3, 2.5
+                n1 = LIST_LEN(l)
+                a? = LIST_GET(l, 0)
+                b = LIST_GET(l, 1)
+                c$ = LIST_GET(l, 2)
+                d# = LIST_GET(l, 3)
+                LIST_REMOVE l, 0
+                LIST_SET l, 0, "replaced"
+                n2 = LIST_LEN(l)
+                e$ = LIST_GET(l, 0)
+                "#,
+            )
+            .expect_var("l", 1)
+            .expect_var("n1", 4)
+            .expect_var("a", true)
+            .expect_var("b", 10)
+            .expect_var("c", "b")
+            .expect_var("d", 2.5)
+            .expect_var("n2", 3)
+            .expect_var("e", "replaced")
+            .check();
+    }
+
+    #[test]
+    fn test_list_iteration() {
+        Tester::default()
+            .run(
+                r#"
+                l = LIST_NEW
+                FOR i = 1 TO 3: LIST_APPEND l, i * i: NEXT
+                FOR i = 0 TO LIST_LEN(l) - 1: PRINT LIST_GET(l, i): NEXT
+                "#,
+            )
+            .expect_prints([" 1", " 4", " 9"])
+            .expect_var("l", 1)
+            .expect_var("i", 3)
+            .check();
+    }
+
+    #[test]
+    fn test_list_for_each() {
+        Tester::default()
+            .run(
+                r#"
+                l = LIST_NEW
+                LIST_APPEND l, "a": LIST_APPEND l, "b"
+                FOR EACH s$ IN l: PRINT s$: NEXT
+                n = LIST_NEW
+                LIST_APPEND n, 5: LIST_APPEND n, DICT
+                FOR EACH i IN n: PRINT i: NEXT
+                e = LIST_NEW
+                FOR EACH i IN e: PRINT "Not reached": NEXT
+                "#,
+            )
+            .expect_prints(["a", "b", " 5", " 3"])
+            .expect_var("l", 1)
+            .expect_var("s", "b")
+            .expect_var("n", 2)
+            .expect_var("i", 3)
+            .expect_var("e", 4)
+            .check();
+
+        // The index of the loop is an internal variable that remains set when the loop fails.
+        Tester::default()
+            .run("d = DICT: FOR EACH v IN d: NEXT")
+            .expect_err("1:25: Invalid list handle 1")
+            .expect_var("d", 1)
+            .expect_var("0foreach1", 0)
+            .check();
+    }
+
+    #[test]
+    fn test_list_and_dict_handles_are_distinct() {
+        check_expr_error(
+            "1:10: In call to LIST_LEN: 1:19: Invalid list handle 1",
+            "LIST_LEN(DICT)",
+        );
+        check_expr_error(
+            "1:10: In call to DICT_LEN: 1:19: Invalid dictionary handle 1",
+            "DICT_LEN(LIST_NEW)",
+        );
+    }
+
+    #[test]
+    fn test_list_errors() {
+        check_expr_error(
+            "1:10: In call to LIST_NEW: expected no arguments nor parenthesis",
+            "LIST_NEW()",
+        );
+
+        check_stmt_err("1:1: In call to LIST_APPEND: expected handle%, value", "LIST_APPEND 1");
+        check_stmt_err(
+            "1:1: In call to LIST_APPEND: 1:13: Invalid list handle 1",
+            "LIST_APPEND 1, 2",
+        );
+
+        check_expr_error("1:10: In call to LIST_GET: expected handle%, index%", "LIST_GET(1)");
+        check_expr_error(
+            "1:10: In call to LIST_GET: 1:29: Index 0 out of range",
+            "LIST_GET(LIST_NEW, 0)",
+        );
+        check_expr_error(
+            "1:10: In call to LIST_GET: 1:29: List index must be an integer",
+            "LIST_GET(LIST_NEW, 0.5)",
+        );
+
+        check_stmt_err(
+            "1:1: In call to LIST_INSERT: expected handle%, index%, value",
+            "LIST_INSERT 1, 2",
+        );
+        check_stmt_err(
+            "1:1: In call to LIST_INSERT: 1:23: Index 1 out of range",
+            "LIST_INSERT LIST_NEW, 1, 5",
+        );
+        check_stmt_err(
+            "1:1: In call to LIST_INSERT: 1:23: Index -1 out of range",
+            "LIST_INSERT LIST_NEW, -1, 5",
+        );
+
+        check_expr_error("1:10: In call to LIST_LEN: expected handle%", "LIST_LEN()");
+
+        check_stmt_err("1:1: In call to LIST_REMOVE: expected handle%, index%", "LIST_REMOVE 1");
+        check_stmt_err(
+            "1:1: In call to LIST_REMOVE: 1:23: Index 0 out of range",
+            "LIST_REMOVE LIST_NEW, 0",
+        );
+
+        check_stmt_err(
+            "1:1: In call to LIST_SET: expected handle%, index%, value",
+            "LIST_SET 1, 2",
+        );
+        check_stmt_err(
+            "1:1: In call to LIST_SET: 1:20: Index 0 out of range",
+            "LIST_SET LIST_NEW, 0, 5",
+        );
+    }
+
+    #[test]
+    fn test_json_parse() {
+        Tester::default()
+            .run(
+                r#"d = JSON_PARSE("{\"b\": 1.5, \"a\": true, \"c\": \"x\", \"n\": null}")
+                a = DICT_GET?(d, "a"): b = DICT_GET#(d, "b"): c = DICT_GET$(d, "c")
+                n = DICT_GET$(d, "n"): k$ = DICT_KEY(d, 3)"#,
+            )
+            .expect_var("d", 1)
+            .expect_var("a", true)
+            .expect_var("b", 1.5)
+            .expect_var("c", "x")
+            .expect_var("n", "")
+            .expect_var("k", "n")
+            .check();
+
+        Tester::default()
+            .run(
+                r#"l = JSON_PARSE("[1, [2, 3], {\"k\": 4}, 5000000000]")
+                n = LIST_LEN(l): inner = LIST_GET%(l, 1): i = LIST_GET%(inner, 1)
+                d = LIST_GET%(l, 2): k = DICT_GET%(d, "k"): big = LIST_GET#(l, 3)"#,
+            )
+            .expect_var("l", 1)
+            .expect_var("n", 4)
+            .expect_var("inner", 2)
+            .expect_var("i", 3)
+            .expect_var("d", 3)
+            .expect_var("k", 4)
+            .expect_var("big", 5000000000.0)
+            .check();
+
+        check_expr_error("1:10: In call to JSON_PARSE: expected json$", "JSON_PARSE()");
+        check_expr_error("1:10: In call to JSON_PARSE: expected json$", "JSON_PARSE(3)");
+        check_expr_error(
+            "1:10: In call to JSON_PARSE: 1:21: Invalid JSON: EOF while parsing an object at line 1 column 1",
+            r#"JSON_PARSE("{")"#,
+        );
+        check_expr_error(
+            "1:10: In call to JSON_PARSE: 1:21: JSON document must contain an object or an array",
+            r#"JSON_PARSE("3")"#,
+        );
+    }
+
+    #[test]
+    fn test_json_format() {
+        Tester::default()
+            .run(
+                r#"d = DICT: DICT_SET d, "s", "a\"b": DICT_SET d, "i", 3: DICT_SET d, "b", FALSE
+                DICT_SET d, "f", 2.5: j$ = JSON_FORMAT(d)"#,
+            )
+            .expect_var("d", 1)
+            .expect_var("j", r#"{"b":false,"f":2.5,"i":3,"s":"a\"b"}"#)
+            .check();
+
+        Tester::default()
+            .run(r#"l = LIST_NEW: d = DICT: LIST_APPEND l, d: j$ = JSON_FORMAT(l)"#)
+            .expect_var("l", 1)
+            .expect_var("d", 2)
+            .expect_var("j", "[2]")
+            .check();
+
+        Tester::default()
+            .run(r#"j$ = JSON_FORMAT(JSON_PARSE("{\"a\": [1, {\"b\": null}], \"c\": {}}"))"#)
+            .expect_var("j", r#"{"a":[1,{"b":null}],"c":{}}"#)
+            .check();
+
+        check_expr_error("1:10: In call to JSON_FORMAT: expected handle%", "JSON_FORMAT()");
+        check_expr_error(
+            "1:10: In call to JSON_FORMAT: 1:22: Invalid collection handle 1",
+            "JSON_FORMAT(1)",
+        );
+        check_expr_error(
+            "1:10: In call to JSON_FORMAT: 1:22: Collection handle must be an integer",
+            r#"JSON_FORMAT("a")"#,
+        );
+    }
+}
@@ -15,11 +15,16 @@
 
 //! Commands for console interaction.
 
-use crate::console::readline::read_line;
-use crate::console::{CharsXY, ClearType, Console, ConsoleClearable, Key};
+use crate::console::readline::{read_line, read_line_timeout};
+use crate::console::windows::WindowsClearable;
+use crate::console::{
+    CharsXY, ClearType, ColorNumber, Console, ConsoleClearable, Key, TextAttributes,
+    WindowedConsole,
+};
+use crate::i18n;
 use async_trait::async_trait;
 use endbasic_core::ast::{
-    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarType,
+    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarRef, VarType,
 };
 use endbasic_core::eval;
 use endbasic_core::exec::Machine;
@@ -27,10 +32,12 @@ use endbasic_core::syms::{
     CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
     FunctionResult, Symbols,
 };
+use endbasic_core::LineCol;
 use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::io;
 use std::rc::Rc;
+use std::time::Duration;
 
 /// Category description for all symbols provided by this module.
 const CATEGORY: &str = "Console
@@ -50,6 +57,138 @@ console, you should restrict the script to using only the INPUT and PRINT comman
 Be aware that the console currently reacts poorly to size changes.  Avoid resizing your terminal \
 or web browser.  If you do resize them, however, restart the interpreter.";
 
+/// Parses an expression that represents a color number.
+pub(crate) async fn parse_color(
+    expr: &Expr,
+    symbols: &mut Symbols,
+) -> Result<ColorNumber, CallError> {
+    let value = expr.eval(symbols).await?;
+    let i =
+        value.as_i32().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    ColorNumber::from_i32(i)
+        .ok_or_else(|| CallError::ArgumentError(expr.start_pos(), "Color out of range".to_owned()))
+}
+
+/// Parses an expression that represents a column or a row of the console and checks that it is
+/// smaller than `limit`.
+async fn parse_coordinate(
+    expr: &Expr,
+    symbols: &mut Symbols,
+    what: &str,
+    limit: u16,
+) -> Result<u16, CallError> {
+    let value = expr.eval(symbols).await?;
+    let i =
+        value.as_i32().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    match u16::try_from(i) {
+        Ok(v) if v < limit => Ok(v),
+        Ok(v) => Err(CallError::ArgumentError(
+            expr.start_pos(),
+            format!("{} {} exceeds visible range of {}", what, v, limit - 1),
+        )),
+        Err(_) => Err(CallError::ArgumentError(expr.start_pos(), format!("{} out of range", what))),
+    }
+}
+
+/// Returns the position of the cursor of `console`, clamped to the visible area so that the
+/// result can be fed back into `LOCATE`.
+fn visible_cursor_pos(console: &dyn Console) -> io::Result<CharsXY> {
+    let pos = console.cursor_pos()?;
+    let size = console.size_chars()?;
+    Ok(CharsXY::new(pos.x.min(size.x.saturating_sub(1)), pos.y.min(size.y.saturating_sub(1))))
+}
+
+/// The `CLIPBOARD_GET` function.
+pub struct ClipboardGetFunction {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl ClipboardGetFunction {
+    /// Creates a new `CLIPBOARD_GET` function that reads the clipboard of `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CLIPBOARD_GET", VarType::Text)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the text held in the system clipboard.
+The clipboard is shared with other applications when the console supports it: this is the case \
+of the graphical console, the web interface, and terminals that implement OSC 52.  When running in \
+a terminal, the clipboard of the system cannot be read back so this returns the last text set via \
+CLIPBOARD_SET or copied within the editor.
+See CLIPBOARD_SET to modify the clipboard.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ClipboardGetFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let text = self.console.borrow_mut().clipboard_get().await?;
+        Ok(Value::Text(text))
+    }
+}
+
+/// The `CLIPBOARD_SET` command.
+pub struct ClipboardSetCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl ClipboardSetCommand {
+    /// Creates a new `CLIPBOARD_SET` command that modifies the clipboard of `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CLIPBOARD_SET", VarType::Void)
+                .with_syntax("text$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Replaces the contents of the system clipboard with text$.
+See CLIPBOARD_GET$ to read the clipboard back and for details on which consoles share the \
+clipboard with other applications.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ClipboardSetCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [ArgSpan { expr: Some(text), sep: ArgSep::End, .. }] => {
+                match text.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(text) => {
+                        self.console.borrow_mut().clipboard_set(&text).await?;
+                        Ok(())
+                    }
+                    _ => Err(CallError::ArgumentError(
+                        text.start_pos(),
+                        "Argument to CLIPBOARD_SET must be a string".to_owned(),
+                    )),
+                }
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
 /// The `CLS` command.
 pub struct ClsCommand {
     metadata: CallableMetadata,
@@ -102,7 +241,9 @@ impl ColorCommand {
                     "Sets the foreground and background colors.
 Color numbers are given as ANSI numbers and can be between 0 and 255.  If a color number is not \
 specified, then the color is reset to the console's default.  The console default does not \
-necessarily match any other color specifiable in the 0 to 255 range, as it might be transparent.",
+necessarily match any other color specifiable in the 0 to 255 range, as it might be transparent.
+Color numbers can also represent arbitrary colors as returned by COLOR_RGB.  Consoles that cannot \
+display these colors use the closest ANSI colors instead.",
                 )
                 .build(),
             console,
@@ -131,22 +272,9 @@ impl Command for ColorCommand {
         async fn get_color(
             e: &Option<Expr>,
             machine: &mut Machine,
-        ) -> Result<Option<u8>, CallError> {
+        ) -> Result<Option<ColorNumber>, CallError> {
             match e {
-                Some(expr) => {
-                    let value = expr.eval(machine.get_mut_symbols()).await?;
-                    let i = value.as_i32().map_err(|e| {
-                        CallError::ArgumentError(expr.start_pos(), format!("{}", e))
-                    })?;
-                    if i >= 0 && i <= std::u8::MAX as i32 {
-                        Ok(Some(i as u8))
-                    } else {
-                        Err(CallError::ArgumentError(
-                            expr.start_pos(),
-                            "Color out of range".to_owned(),
-                        ))
-                    }
-                }
+                Some(expr) => Ok(Some(parse_color(expr, machine.get_mut_symbols()).await?)),
                 None => Ok(None),
             }
         }
@@ -154,7 +282,185 @@ impl Command for ColorCommand {
         let fg = get_color(fg_expr, machine).await?;
         let bg = get_color(bg_expr, machine).await?;
 
-        self.console.borrow_mut().set_color(fg, bg)?;
+        let mut console = self.console.borrow_mut();
+        match (fg, bg) {
+            (None | Some(ColorNumber::Ansi(_)), None | Some(ColorNumber::Ansi(_))) => {
+                let ansi = |color: Option<ColorNumber>| match color {
+                    Some(ColorNumber::Ansi(color)) => Some(color),
+                    _ => None,
+                };
+                console.set_color(ansi(fg), ansi(bg))?;
+            }
+            _ => console.set_color_rgb(fg.map(ColorNumber::to_rgb), bg.map(ColorNumber::to_rgb))?,
+        }
+        Ok(())
+    }
+}
+
+/// The `COLOR_RGB` function.
+pub struct ColorRgbFunction {
+    metadata: CallableMetadata,
+}
+
+impl ColorRgbFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("COLOR_RGB", VarType::Integer)
+                .with_syntax("r%, g%, b%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the color number of an arbitrary RGB color.
+r%, g% and b% are the intensities of the red, green and blue components of the color, and can be \
+between 0 and 255.  The returned number can be used anywhere a color number is accepted, such as \
+in COLOR or GFX_FILL.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ColorRgbFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let (rexpr, gexpr, bexpr) = match span.args.as_slice() {
+            [r, g, b] => (r, g, b),
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        async fn get_component(expr: &Expr, symbols: &mut Symbols) -> Result<u8, CallError> {
+            let value = expr.eval(symbols).await?;
+            let i = value
+                .as_i32()
+                .map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+            u8::try_from(i).map_err(|_| {
+                CallError::ArgumentError(
+                    expr.start_pos(),
+                    format!("Color component {} out of range", i),
+                )
+            })
+        }
+
+        let r = get_component(rexpr, symbols).await?;
+        let g = get_component(gexpr, symbols).await?;
+        let b = get_component(bexpr, symbols).await?;
+        Ok(Value::Integer(ColorNumber::Rgb((r, g, b)).to_i32()))
+    }
+}
+
+/// The `CSRLIN` function.
+pub struct CsrLinFunction {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl CsrLinFunction {
+    /// Creates a new instance of the function.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CSRLIN", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the row where the cursor is currently located.
+Rows are numbered from 0 as in LOCATE.  See POS to query the column.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for CsrLinFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let pos = visible_cursor_pos(&*self.console.borrow())?;
+        Ok(Value::Integer(i32::from(pos.y)))
+    }
+}
+
+/// The `FONT` command.
+pub struct FontCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl FontCommand {
+    /// Creates a new `FONT` command that changes the font of the `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("FONT", VarType::Void)
+                .with_syntax("[face$, ]size%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Changes the font used to render text.
+size% is the size of the font in points.  If face$ is not specified, the current font is kept \
+and only its size changes.  Otherwise, face$ selects the font to use: in the graphical console, \
+this is the path to a monospaced TrueType font file, and in the web interface, this is the name \
+of a font family.  An empty face$ selects the default font.
+The number of rows and columns in the console may change as a result of this.  Use SCRCOLS and \
+SCRROWS to query the new size.  Text already on the screen is not redrawn with the new font.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for FontCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (face_expr, size_expr) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(size), sep: ArgSep::End, .. }] => (None, size),
+            [ArgSpan { expr: Some(face), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(size), sep: ArgSep::End, .. }] => {
+                (Some(face), size)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let face = match face_expr {
+            Some(expr) => match expr.eval(machine.get_mut_symbols()).await? {
+                Value::Text(face) => Some(face),
+                _ => {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "Font face must be a string".to_owned(),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        let value = size_expr.eval(machine.get_mut_symbols()).await?;
+        let i = value
+            .as_i32()
+            .map_err(|e| CallError::ArgumentError(size_expr.start_pos(), format!("{}", e)))?;
+        let size = match u16::try_from(i) {
+            Ok(size) if size > 0 => size,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    size_expr.start_pos(),
+                    format!("Font size {} out of range", i),
+                ))
+            }
+        };
+
+        self.console.borrow_mut().set_font(face.as_deref(), size)?;
         Ok(())
     }
 }
@@ -213,6 +519,7 @@ impl Function for InKeyFunction {
             Some(Key::Backspace) => Value::Text("BS".to_owned()),
             Some(Key::CarriageReturn) => Value::Text("ENTER".to_owned()),
             Some(Key::Char(x)) => Value::Text(format!("{}", x)),
+            Some(Key::Control(_)) => Value::Text("".to_owned()),
             Some(Key::End) => Value::Text("END".to_owned()),
             Some(Key::Eof) => Value::Text("EOF".to_owned()),
             Some(Key::Escape) => Value::Text("ESC".to_owned()),
@@ -229,6 +536,39 @@ impl Function for InKeyFunction {
     }
 }
 
+/// Parses the `answer` typed by the user into one value per variable in `vrefs`.
+///
+/// If `range` is present, all values must be numbers within the given inclusive bounds.
+///
+/// Returns a message describing why the answer is invalid on failure.
+fn parse_input_values(
+    vrefs: &[(VarRef, LineCol)],
+    answer: &str,
+    range: Option<(f64, f64)>,
+) -> Result<Vec<Value>, String> {
+    let fields: Vec<&str> = if vrefs.len() == 1 {
+        vec![answer.trim_end()]
+    } else {
+        answer.split(',').map(str::trim).collect()
+    };
+    if fields.len() != vrefs.len() {
+        return Err(format!("Expected {} comma-separated values", vrefs.len()));
+    }
+
+    let mut values = Vec::with_capacity(vrefs.len());
+    for ((vref, _pos), field) in vrefs.iter().zip(fields) {
+        let value = Value::parse_as(vref.ref_type(), field).map_err(|e| e.to_string())?;
+        if let Some((min, max)) = range {
+            let number = value.as_f64().map_err(|e| e.to_string())?;
+            if number < min || number > max {
+                return Err(format!("Value must be between {} and {}", min, max));
+            }
+        }
+        values.push(value);
+    }
+    Ok(values)
+}
+
 /// The `INPUT` command.
 pub struct InputCommand {
     metadata: CallableMetadata,
@@ -240,15 +580,31 @@ impl InputCommand {
     pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
         Rc::from(Self {
             metadata: CallableMetadataBuilder::new("INPUT", VarType::Void)
-                .with_syntax("[\"prompt\" <;|,>] variableref")
+                .with_syntax(
+                    "[\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] \
+[TIMEOUT seconds]",
+                )
                 .with_category(CATEGORY)
                 .with_description(
                     "Obtains user input from the console.
-The first expression to this function must be empty or evaluate to a string, and specifies \
-the prompt to print.  If this first argument is followed by the short `;` separator, the \
-prompt is extended with a question mark.
-The second expression to this function must be a bare variable reference and indicates the \
-variable to update with the obtained input.",
+When given more than one argument, the first expression to this function must be empty or \
+evaluate to a string, and specifies the prompt to print.  If this first argument is followed by \
+the short `;` separator, the prompt is extended with a question mark.
+The remaining expressions must be bare variable references and indicate the variables to update \
+with the obtained input.  When reading more than one variable, the user must type as many \
+comma-separated values, which means that string values cannot contain commas in this case.  To \
+read more than one variable without a prompt, leave the prompt empty as in `INPUT ; a, b`.
+The optional AS clause specifies a default answer, which is pre-filled in the input line for the \
+user to edit, and optionally a range of valid numbers from min to max (inclusive) that all \
+values must fall within.  On consoles that are not interactive, an empty answer selects the \
+default instead.  Leave the default empty to only specify the range as in \
+`INPUT \"Age\"; age AS , 0, 120`.
+If the input is not valid for the types of the variables or falls outside of the range, INPUT \
+explains the problem and asks for the input again.
+The optional TIMEOUT clause makes INPUT give up when the user does not press any key for the \
+given number of seconds, which keeps unattended programs from waiting forever.  When this \
+happens, the variables are set to the default answer if there is one or to their zero values \
+otherwise.  To check for key presses without waiting at all, use INKEY$ instead.",
                 )
                 .build(),
             console,
@@ -263,14 +619,25 @@ impl Command for InputCommand {
     }
 
     async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
-        if span.args.is_empty() || span.args.len() > 2 {
+        let (args, timeout) = match span.args.iter().position(|a| a.sep == ArgSep::Timeout) {
+            Some(i) => match &span.args[i + 1..] {
+                [ArgSpan { expr: Some(timeout), sep: ArgSep::End, .. }] => {
+                    (&span.args[..=i], Some(timeout))
+                }
+                _ => return Err(CallError::SyntaxError),
+            },
+            None => (&span.args[..], None),
+        };
+        let (args, options) = match args.iter().position(|a| a.sep == ArgSep::As) {
+            Some(i) => (&args[..=i], &args[i + 1..]),
+            None => (args, &args[..0]),
+        };
+        if args.is_empty() {
             return Err(CallError::SyntaxError);
         }
-        let mut iter = span.args.iter();
 
-        let prompt = if span.args.len() == 2 {
-            let arg = iter.next().expect("Number of arguments validated above");
-            let mut prompt = match &arg.expr {
+        let (prompt, vargs) = if args.len() >= 2 {
+            let mut prompt = match &args[0].expr {
                 Some(e) => match e.eval(machine.get_mut_symbols()).await? {
                     Value::Text(t) => t,
                     _ => {
@@ -282,54 +649,280 @@ impl Command for InputCommand {
                 },
                 None => "".to_owned(),
             };
-            if let ArgSep::Short = span.args[0].sep {
+            if let ArgSep::Short = args[0].sep {
                 prompt += "? ";
             }
-            prompt
+            (prompt, &args[1..])
         } else {
-            "? ".to_owned()
+            ("? ".to_owned(), args)
         };
 
-        let (vref, pos) = match &iter.next().expect("Number of arguments validated above").expr {
-            Some(Expr::Symbol(span)) => (&span.vref, span.pos),
-            Some(expr) => {
-                return Err(CallError::ArgumentError(
-                    expr.start_pos(),
-                    "INPUT requires a variable reference".to_owned(),
-                ))
+        let mut vrefs = Vec::with_capacity(vargs.len());
+        for (i, arg) in vargs.iter().enumerate() {
+            if i < vargs.len() - 1 && arg.sep != ArgSep::Long {
+                return Err(CallError::SyntaxError);
+            }
+            let (vref, pos) = match &arg.expr {
+                Some(Expr::Symbol(span)) => (&span.vref, span.pos),
+                Some(expr) => {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "INPUT requires a variable reference".to_owned(),
+                    ))
+                }
+                None => return Err(CallError::SyntaxError),
+            };
+            let vref = machine
+                .get_symbols()
+                .qualify_varref(vref)
+                .map_err(|e| eval::Error::from_value_error(e, pos))?;
+            vrefs.push((vref, pos));
+        }
+
+        let (default, range) = match options {
+            [] => (None, None),
+            [ArgSpan { expr: Some(default), sep: ArgSep::End | ArgSep::Timeout, .. }] => {
+                (Some(default), None)
+            }
+            [ArgSpan { expr: default, sep: ArgSep::Long, .. }, ArgSpan { expr: Some(min), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(max), sep: ArgSep::End | ArgSep::Timeout, .. }] => {
+                (default.as_ref(), Some((min, max)))
             }
-            None => return Err(CallError::SyntaxError),
+            _ => return Err(CallError::SyntaxError),
         };
-        let vref = machine
-            .get_symbols()
-            .qualify_varref(vref)
-            .map_err(|e| eval::Error::from_value_error(e, pos))?;
 
-        assert!(iter.next().is_none(), "Number of arguments validated above");
+        let default_pos = default.map(|e| e.start_pos());
+        let default = match default {
+            Some(e) => match e.eval(machine.get_mut_symbols()).await? {
+                Value::Text(t) => t,
+                v => v.to_text().trim_start().to_owned(),
+            },
+            None => String::new(),
+        };
 
-        let mut console = self.console.borrow_mut();
-        let mut previous_answer = String::new();
-        loop {
-            match read_line(&mut *console, &prompt, &previous_answer, None).await {
-                Ok(answer) => match Value::parse_as(vref.ref_type(), answer.trim_end()) {
-                    Ok(value) => {
-                        machine
-                            .get_mut_symbols()
-                            .set_var(&vref, value)
-                            .map_err(|e| eval::Error::from_value_error(e, pos))?;
-                        return Ok(());
+        let range = match range {
+            Some((min, max)) => {
+                let mut bounds = [0.0; 2];
+                for (bound, e) in bounds.iter_mut().zip([min, max]) {
+                    *bound = e.eval(machine.get_mut_symbols()).await?.as_f64().map_err(|_| {
+                        CallError::ArgumentError(
+                            e.start_pos(),
+                            "INPUT range bounds must be numbers".to_owned(),
+                        )
+                    })?;
+                }
+                if bounds[0] > bounds[1] {
+                    return Err(CallError::ArgumentError(
+                        min.start_pos(),
+                        "INPUT range minimum must not exceed its maximum".to_owned(),
+                    ));
+                }
+                for (vref, pos) in &vrefs {
+                    if let VarType::Boolean | VarType::Text = vref.ref_type() {
+                        return Err(CallError::ArgumentError(
+                            *pos,
+                            "INPUT range requires numeric variables".to_owned(),
+                        ));
                     }
+                }
+                Some((bounds[0], bounds[1]))
+            }
+            None => None,
+        };
+
+        let timeout = match timeout {
+            Some(e) => {
+                let n = e
+                    .eval(machine.get_mut_symbols())
+                    .await?
+                    .as_f64()
+                    .map_err(|e2| CallError::ArgumentError(e.start_pos(), format!("{}", e2)))?;
+                if n < 0.0 {
+                    return Err(CallError::ArgumentError(
+                        e.start_pos(),
+                        "INPUT timeout must be positive".to_owned(),
+                    ));
+                }
+                Some(Duration::from_secs_f64(n))
+            }
+            None => None,
+        };
+
+        let mut console = self.console.borrow_mut();
+        let mut previous_answer = default.clone();
+        let values = loop {
+            let result = match timeout {
+                Some(timeout) => {
+                    read_line_timeout(&mut *console, &prompt, &previous_answer, timeout).await
+                }
+                None => read_line(&mut *console, &prompt, &previous_answer, None).await,
+            };
+            let result = result.map(|answer| {
+                // Non-interactive consoles cannot pre-fill the input with the default answer, so
+                // take an empty answer as a request to use it.
+                if answer.is_empty() && !console.is_interactive() {
+                    default.clone()
+                } else {
+                    answer
+                }
+            });
+            match result {
+                Ok(answer) => match parse_input_values(&vrefs, &answer, range) {
+                    Ok(values) => break values,
                     Err(e) => {
-                        console.print(&format!("Retry input: {}", e))?;
+                        console.print(&i18n::translate(&format!("Retry input: {}", e)))?;
                         previous_answer = answer;
                     }
                 },
                 Err(e) if e.kind() == io::ErrorKind::InvalidData => {
-                    console.print(&format!("Retry input: {}", e))?
+                    console.print(&i18n::translate(&format!("Retry input: {}", e)))?
                 }
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => match default_pos {
+                    Some(pos) => {
+                        break parse_input_values(&vrefs, &default, range)
+                            .map_err(|e| CallError::ArgumentError(pos, e))?
+                    }
+                    None => {
+                        break vrefs
+                            .iter()
+                            .map(|(vref, _pos)| vref.ref_type().default_value())
+                            .collect()
+                    }
+                },
                 Err(e) => return Err(e.into()),
             }
+        };
+        drop(console);
+
+        for ((vref, pos), value) in vrefs.iter().zip(values) {
+            machine
+                .get_mut_symbols()
+                .set_var(vref, value)
+                .map_err(|e| eval::Error::from_value_error(e, *pos))?;
         }
+        Ok(())
+    }
+}
+
+/// A key whose state can be queried with `KEYDOWN?`.
+enum HeldKey {
+    /// A regular key.
+    Key(Key),
+
+    /// Any of the Shift keys.
+    Shift,
+
+    /// Any of the Control keys.
+    Ctrl,
+
+    /// Any of the Alt keys.
+    Alt,
+}
+
+/// Parses the `name` of a key as given to `KEYDOWN?`, or returns none if the name is not valid.
+///
+/// Names match those returned by `INKEY` but are case-insensitive.
+fn parse_held_key(name: &str) -> Option<HeldKey> {
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Some(HeldKey::Key(Key::Char(ch.to_ascii_lowercase())));
+    }
+
+    let key = match name.to_ascii_uppercase().as_str() {
+        "ALT" => return Some(HeldKey::Alt),
+        "CTRL" => return Some(HeldKey::Ctrl),
+        "SHIFT" => return Some(HeldKey::Shift),
+
+        "DOWN" => Key::ArrowDown,
+        "LEFT" => Key::ArrowLeft,
+        "RIGHT" => Key::ArrowRight,
+        "UP" => Key::ArrowUp,
+
+        "BS" => Key::Backspace,
+        "END" => Key::End,
+        "ENTER" => Key::NewLine,
+        "ESC" => Key::Escape,
+        "HOME" => Key::Home,
+        "PGDOWN" => Key::PageDown,
+        "PGUP" => Key::PageUp,
+        "SPACE" => Key::Char(' '),
+        "TAB" => Key::Tab,
+
+        _ => return None,
+    };
+    Some(HeldKey::Key(key))
+}
+
+/// The `KEYDOWN` function.
+pub struct KeyDownFunction {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl KeyDownFunction {
+    /// Creates a new instance of the function.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("KEYDOWN", VarType::Boolean)
+                .with_syntax("key$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Checks if a key is currently held down.
+key$ is the name of the key to check and matches the names returned by INKEY, such as LEFT or \
+ESC, or a single character such as a or 5.  Names are case-insensitive and letters are reported \
+as held down regardless of the state of the SHIFT modifier.  In addition to these names, SPACE \
+refers to the space bar and ALT, CTRL, and SHIFT refer to the modifier keys.
+Unlike INKEY, this function does not consume any key presses, which makes it suitable for \
+programs such as games that need to react to keys for as long as they are pressed.  For example:
+    WHILE NOT KEYDOWN?(\"ESC\"): IF KEYDOWN?(\"LEFT\") THEN x = x - 1: SLEEP 0.01: WEND
+Not all consoles can track the state of the keyboard.  In particular, terminals do not report \
+when keys are released, so this function considers a key to be held down for a short period of \
+time after every press it receives.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for KeyDownFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let expr = match span.args.as_slice() {
+            [expr] => expr,
+            _ => return Err(CallError::SyntaxError),
+        };
+        let name = match expr.eval(symbols).await? {
+            Value::Text(name) => name,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    expr.start_pos(),
+                    "Key name must be a string".to_owned(),
+                ))
+            }
+        };
+
+        let key = match parse_held_key(&name) {
+            Some(key) => key,
+            None => {
+                return Err(CallError::ArgumentError(
+                    expr.start_pos(),
+                    format!("Unknown key name {}", name),
+                ))
+            }
+        };
+
+        let mut console = self.console.borrow_mut();
+        let down = match key {
+            HeldKey::Key(key) => console.is_key_down(&key)?,
+            HeldKey::Alt => console.key_modifiers()?.alt,
+            HeldKey::Ctrl => console.key_modifiers()?.ctrl,
+            HeldKey::Shift => console.key_modifiers()?.shift,
+        };
+        Ok(Value::Boolean(down))
     }
 }
 
@@ -344,9 +937,13 @@ impl LocateCommand {
     pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
         Rc::from(Self {
             metadata: CallableMetadataBuilder::new("LOCATE", VarType::Void)
-                .with_syntax("column%, row%")
+                .with_syntax("[column%], [row%]")
                 .with_category(CATEGORY)
-                .with_description("Moves the cursor to the given position.")
+                .with_description(
+                    "Moves the cursor to the given position.
+Omitting column% or row% keeps the current value of that coordinate.  See CSRLIN and POS to query \
+the current position of the cursor.",
+                )
                 .build(),
             console,
         })
@@ -369,62 +966,70 @@ impl Command for LocateCommand {
         }
         debug_assert!(row_arg.sep == ArgSep::End);
 
+        let size = self.console.borrow().size_chars()?;
+
         let column = match &column_arg.expr {
             Some(arg) => {
-                let value = arg.eval(machine.get_mut_symbols()).await?;
-                let i = value
-                    .as_i32()
-                    .map_err(|e| CallError::ArgumentError(arg.start_pos(), format!("{}", e)))?;
-                match u16::try_from(i) {
-                    Ok(v) => v,
-                    Err(_) => {
-                        return Err(CallError::ArgumentError(
-                            arg.start_pos(),
-                            "Column out of range".to_owned(),
-                        ))
-                    }
-                }
+                Some(parse_coordinate(arg, machine.get_mut_symbols(), "Column", size.x).await?)
             }
-            None => return Err(CallError::SyntaxError),
+            None => None,
         };
 
         let row = match &row_arg.expr {
             Some(arg) => {
-                let value = arg.eval(machine.get_mut_symbols()).await?;
-                let i = value
-                    .as_i32()
-                    .map_err(|e| CallError::ArgumentError(arg.start_pos(), format!("{}", e)))?;
-                match u16::try_from(i) {
-                    Ok(v) => v,
-                    Err(_) => {
-                        return Err(CallError::ArgumentError(
-                            arg.start_pos(),
-                            "Row out of range".to_owned(),
-                        ))
-                    }
-                }
+                Some(parse_coordinate(arg, machine.get_mut_symbols(), "Row", size.y).await?)
             }
-            None => return Err(CallError::SyntaxError),
+            None => None,
         };
 
         let mut console = self.console.borrow_mut();
-        let size = console.size_chars()?;
+        let pos = match (column, row) {
+            (Some(column), Some(row)) => CharsXY::new(column, row),
+            (column, row) => {
+                let current = visible_cursor_pos(&*console)?;
+                CharsXY::new(column.unwrap_or(current.x), row.unwrap_or(current.y))
+            }
+        };
+        console.locate(pos)?;
+        Ok(())
+    }
+}
 
-        if column >= size.x {
-            return Err(CallError::ArgumentError(
-                column_arg.expr.as_ref().expect("Presence checked above").start_pos(),
-                format!("Column {} exceeds visible range of {}", column, size.x - 1),
-            ));
-        }
-        if row >= size.y {
-            return Err(CallError::ArgumentError(
-                row_arg.expr.as_ref().expect("Presence checked above").start_pos(),
-                format!("Row {} exceeds visible range of {}", row, size.y - 1),
-            ));
-        }
+/// The `POS` function.
+pub struct PosFunction {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
 
-        console.locate(CharsXY::new(column, row))?;
-        Ok(())
+impl PosFunction {
+    /// Creates a new instance of the function.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("POS", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the column where the cursor is currently located.
+Columns are numbered from 0 as in LOCATE.  See CSRLIN to query the row.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for PosFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let pos = visible_cursor_pos(&*self.console.borrow())?;
+        Ok(Value::Integer(i32::from(pos.x)))
     }
 }
 
@@ -492,11 +1097,11 @@ impl Command for PrintCommand {
                 }
                 ArgSep::Long => {
                     text += " ";
-                    while text.len() % 14 != 0 {
+                    while !text.len().is_multiple_of(14) {
                         text += " ";
                     }
                 }
-                ArgSep::As => return Err(CallError::SyntaxError),
+                ArgSep::As | ArgSep::Timeout => return Err(CallError::SyntaxError),
             }
         }
         if nl {
@@ -546,6 +1151,61 @@ impl Function for ScrColsFunction {
     }
 }
 
+/// The `SCREEN` command.
+pub struct ScreenCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl ScreenCommand {
+    /// Creates a new `SCREEN` command that saves and restores the contents of `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SCREEN", VarType::Void)
+                .with_syntax("<SAVE|RESTORE>")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Saves or restores the contents of the console.
+SCREEN SAVE takes a snapshot of the text and graphics in the console along with the cursor \
+position and the current colors.  SCREEN RESTORE brings back the most recent snapshot that has not \
+been restored yet, so pairs of these commands can be nested.
+This is useful to draw temporary overlays, such as menus or dialogs, and later return the \
+console to exactly how it was before them.  Note that terminals cannot read back their contents, \
+so the console is cleared when saving it in them.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ScreenCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, _machine: &mut Machine) -> CommandResult {
+        let symspan = match span.args.as_slice() {
+            [ArgSpan { expr: Some(Expr::Symbol(symspan)), sep: ArgSep::End, .. }] => symspan,
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let name = symspan.vref.name();
+        if symspan.vref.ref_type() == VarType::Auto && name.eq_ignore_ascii_case("SAVE") {
+            self.console.borrow_mut().save_screen()?;
+        } else if symspan.vref.ref_type() == VarType::Auto && name.eq_ignore_ascii_case("RESTORE") {
+            self.console.borrow_mut().restore_screen()?;
+        } else {
+            return Err(CallError::ArgumentError(
+                symspan.pos,
+                format!("Unknown SCREEN operation {}", symspan.vref),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// The `SCRROWS` function.
 pub struct ScrRowsFunction {
     metadata: CallableMetadata,
@@ -579,28 +1239,270 @@ impl Function for ScrRowsFunction {
         if !span.args.is_empty() {
             return Err(CallError::SyntaxError);
         }
-        let size = self.console.borrow().size_chars()?;
-        Ok(Value::Integer(i32::from(size.y)))
+        let size = self.console.borrow().size_chars()?;
+        Ok(Value::Integer(i32::from(size.y)))
+    }
+}
+
+/// The `TEXT_STYLE` command.
+pub struct TextStyleCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl TextStyleCommand {
+    /// Creates a new `TEXT_STYLE` command that changes the text attributes of the `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("TEXT_STYLE", VarType::Void)
+                .with_syntax("[attr1[, .., attrN]]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sets the style attributes of text.
+Each attr can be one of BOLD, UNDERLINE, REVERSE or BLINK, and the attributes that are not given \
+are turned off.  Use TEXT_STYLE without arguments to go back to plain text.  These attributes \
+apply to text printed from then on and are independent from the colors set by COLOR.
+Support for each attribute depends on the console: for example, graphical consoles do not blink \
+text, and some consoles cannot render any attribute at all.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for TextStyleCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, _machine: &mut Machine) -> CommandResult {
+        let mut attributes = TextAttributes::default();
+        for (i, arg) in span.args.iter().enumerate() {
+            let exp_sep = if i == span.args.len() - 1 { ArgSep::End } else { ArgSep::Long };
+            let symspan = match arg {
+                ArgSpan { expr: Some(Expr::Symbol(symspan)), sep, .. } if *sep == exp_sep => {
+                    symspan
+                }
+                _ => return Err(CallError::SyntaxError),
+            };
+
+            let name = symspan.vref.name();
+            let flag = if symspan.vref.ref_type() != VarType::Auto {
+                None
+            } else if name.eq_ignore_ascii_case("BOLD") {
+                Some(&mut attributes.bold)
+            } else if name.eq_ignore_ascii_case("UNDERLINE") {
+                Some(&mut attributes.underline)
+            } else if name.eq_ignore_ascii_case("REVERSE") {
+                Some(&mut attributes.reverse)
+            } else if name.eq_ignore_ascii_case("BLINK") {
+                Some(&mut attributes.blink)
+            } else {
+                None
+            };
+            match flag {
+                Some(flag) => *flag = true,
+                None => {
+                    return Err(CallError::ArgumentError(
+                        symspan.pos,
+                        format!("Unknown TEXT_STYLE attribute {}", symspan.vref),
+                    ))
+                }
+            }
+        }
+
+        self.console.borrow_mut().set_attributes(attributes)?;
+        Ok(())
+    }
+}
+
+/// The `WINDOW` command.
+pub struct WindowCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<WindowedConsole>>,
+}
+
+impl WindowCommand {
+    /// Creates a new `WINDOW` command that manages the windows of `console`.
+    pub fn new(console: Rc<RefCell<WindowedConsole>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("WINDOW", VarType::Void)
+                .with_syntax("[n% [, x1%, y1%, x2%, y2%]]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Defines or selects a text window.
+A text window is a rectangular area of the console with its own cursor, colors and scrolling.  \
+While a window is active, CLS, COLOR, INPUT, LOCATE and PRINT operate within it: positions are \
+relative to its top-left corner, long lines wrap at its right edge, and output scrolls when it \
+reaches its bottom edge, all without touching the rest of the console.  Similarly, SCRCOLS and \
+SCRROWS return the size of the window.
+With five arguments, defines window n% (between 1 and 255) to span from column x1% and row y1% to \
+column x2% and row y2%, both inclusive, clears it, and makes it active.  Defining a window that \
+already exists replaces it.
+With only n%, makes the previously-defined window n% active again.  Each window remembers its \
+cursor position and colors, so you can switch between windows to build multi-pane interfaces.
+Without arguments, deactivates the active window so that output goes to the whole console again.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for WindowCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let exprs = match span.args.as_slice() {
+            [] => {
+                self.console.borrow_mut().select(None)?;
+                return Ok(());
+            }
+            [ArgSpan { expr: Some(n), sep: ArgSep::End, .. }] => vec![n],
+            [ArgSpan { expr: Some(n), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(x1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(x2), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y2), sep: ArgSep::End, .. }] =>
+            {
+                vec![n, x1, y1, x2, y2]
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let n = {
+            let value = exprs[0].eval(machine.get_mut_symbols()).await?;
+            let i = value
+                .as_i32()
+                .map_err(|e| CallError::ArgumentError(exprs[0].start_pos(), format!("{}", e)))?;
+            match u8::try_from(i) {
+                Ok(n) if n > 0 => n,
+                _ => {
+                    return Err(CallError::ArgumentError(
+                        exprs[0].start_pos(),
+                        format!("Window number {} out of range", i),
+                    ))
+                }
+            }
+        };
+
+        if exprs.len() == 1 {
+            if !self.console.borrow().has_window(n) {
+                return Err(CallError::ArgumentError(
+                    exprs[0].start_pos(),
+                    format!("Window {} is not defined", n),
+                ));
+            }
+            self.console.borrow_mut().select(Some(n))?;
+            return Ok(());
+        }
+
+        let screen = self.console.borrow().screen_size()?;
+        let symbols = machine.get_mut_symbols();
+        let x1 = parse_coordinate(exprs[1], symbols, "Column", screen.x).await?;
+        let y1 = parse_coordinate(exprs[2], symbols, "Row", screen.y).await?;
+        let x2 = parse_coordinate(exprs[3], symbols, "Column", screen.x).await?;
+        let y2 = parse_coordinate(exprs[4], symbols, "Row", screen.y).await?;
+        if x2 < x1 {
+            return Err(CallError::ArgumentError(
+                exprs[3].start_pos(),
+                format!("Column {} is left of column {}", x2, x1),
+            ));
+        }
+        if y2 < y1 {
+            return Err(CallError::ArgumentError(
+                exprs[4].start_pos(),
+                format!("Row {} is above row {}", y2, y1),
+            ));
+        }
+
+        self.console.borrow_mut().define(n, CharsXY::new(x1, y1), CharsXY::new(x2, y2))?;
+        Ok(())
     }
 }
 
 /// Adds all console-related commands for the given `console` to the `machine`.
 pub fn add_all(machine: &mut Machine, console: Rc<RefCell<dyn Console>>) {
-    machine.add_clearable(ConsoleClearable::new(console.clone()));
+    let windowed = Rc::from(RefCell::from(WindowedConsole::new(console.clone())));
+    machine.add_clearable(WindowsClearable::new(windowed.clone()));
+    machine.add_clearable(ConsoleClearable::new(console));
+
+    // Text output goes through the windowed console so that it honors the active window.
+    let console: Rc<RefCell<dyn Console>> = windowed.clone();
+    machine.add_function(ClipboardGetFunction::new(console.clone()));
+    machine.add_command(ClipboardSetCommand::new(console.clone()));
     machine.add_command(ClsCommand::new(console.clone()));
     machine.add_command(ColorCommand::new(console.clone()));
+    machine.add_function(ColorRgbFunction::new());
+    machine.add_function(CsrLinFunction::new(console.clone()));
+    machine.add_command(FontCommand::new(console.clone()));
     machine.add_function(InKeyFunction::new(console.clone()));
     machine.add_command(InputCommand::new(console.clone()));
+    machine.add_function(KeyDownFunction::new(console.clone()));
     machine.add_command(LocateCommand::new(console.clone()));
+    machine.add_function(PosFunction::new(console.clone()));
     machine.add_command(PrintCommand::new(console.clone()));
     machine.add_function(ScrColsFunction::new(console.clone()));
-    machine.add_function(ScrRowsFunction::new(console));
+    machine.add_command(ScreenCommand::new(console.clone()));
+    machine.add_function(ScrRowsFunction::new(console.clone()));
+    machine.add_command(TextStyleCommand::new(console));
+    machine.add_command(WindowCommand::new(windowed));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::console::{ansi_color_to_rgb, KeyModifiers};
     use crate::testutils::*;
+    use endbasic_core::exec::{Signal, StopReason};
+
+    #[test]
+    fn test_clipboard_get_ok() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_clipboard("Some text");
+        t.run("PRINT CLIPBOARD_GET$").expect_prints(["Some text"]).check();
+    }
+
+    #[test]
+    fn test_clipboard_get_errors() {
+        check_expr_error(
+            "1:10: In call to CLIPBOARD_GET: expected no arguments nor parenthesis",
+            "CLIPBOARD_GET()",
+        );
+        check_expr_error(
+            "1:10: In call to CLIPBOARD_GET: expected no arguments nor parenthesis",
+            "CLIPBOARD_GET(1)",
+        );
+    }
+
+    #[test]
+    fn test_clipboard_set_ok() {
+        let mut t = Tester::default();
+        t.run("CLIPBOARD_SET \"Copied\"").check();
+        assert_eq!("Copied", t.get_console().borrow().clipboard());
+
+        t.run("CLIPBOARD_SET \"\"").check();
+        assert_eq!("", t.get_console().borrow().clipboard());
+    }
+
+    #[test]
+    fn test_clipboard_set_roundtrip() {
+        Tester::default()
+            .run("CLIPBOARD_SET \"abc\": PRINT CLIPBOARD_GET$")
+            .expect_prints(["abc"])
+            .check();
+    }
+
+    #[test]
+    fn test_clipboard_set_errors() {
+        check_stmt_err("1:1: In call to CLIPBOARD_SET: expected text$", "CLIPBOARD_SET");
+        check_stmt_err("1:1: In call to CLIPBOARD_SET: expected text$", "CLIPBOARD_SET 1, 2");
+        check_stmt_err(
+            "1:1: In call to CLIPBOARD_SET: 1:15: Argument to CLIPBOARD_SET must be a string",
+            "CLIPBOARD_SET 2",
+        );
+    }
 
     #[test]
     fn test_cls_ok() {
@@ -627,6 +1529,15 @@ mod tests {
         t().run("COLOR 255, 255")
             .expect_output([CapturedOut::SetColor(Some(255), Some(255))])
             .check();
+        t().run("COLOR COLOR_RGB(1, 2, 3)")
+            .expect_output([CapturedOut::SetColorRgb(Some((1, 2, 3)), None)])
+            .check();
+        t().run("COLOR , COLOR_RGB(1, 2, 3)")
+            .expect_output([CapturedOut::SetColorRgb(None, Some((1, 2, 3)))])
+            .check();
+        t().run("COLOR 4, COLOR_RGB(1, 2, 3)")
+            .expect_output([CapturedOut::SetColorRgb(Some(ansi_color_to_rgb(4)), Some((1, 2, 3)))])
+            .check();
     }
 
     #[test]
@@ -635,11 +1546,99 @@ mod tests {
 
         check_stmt_err("1:1: In call to COLOR: 1:7: Color out of range", "COLOR 1000, 0");
         check_stmt_err("1:1: In call to COLOR: 1:10: Color out of range", "COLOR 0, 1000");
+        check_stmt_err("1:1: In call to COLOR: 1:7: Color out of range", "COLOR 33554432");
 
         check_stmt_err("1:1: In call to COLOR: 1:7: TRUE is not a number", "COLOR TRUE, 0");
         check_stmt_err("1:1: In call to COLOR: 1:10: TRUE is not a number", "COLOR 0, TRUE");
     }
 
+    #[test]
+    fn test_color_rgb_ok() {
+        check_expr_ok(0x1000000, "COLOR_RGB(0, 0, 0)");
+        check_expr_ok(0x1123456, "COLOR_RGB(&x12, &x34, &x56)");
+        check_expr_ok(0x1ffffff, "COLOR_RGB(255, 255, 254.9)");
+    }
+
+    #[test]
+    fn test_color_rgb_errors() {
+        check_expr_error("1:10: COLOR_RGB requires one or more arguments", "COLOR_RGB");
+        for args in &["(1, 2)", "(1, 2, 3, 4)"] {
+            check_expr_error(
+                "1:10: In call to COLOR_RGB: expected r%, g%, b%",
+                &format!("COLOR_RGB{}", args),
+            );
+        }
+        check_expr_error(
+            "1:10: In call to COLOR_RGB: 1:23: Color component 256 out of range",
+            "COLOR_RGB(1, 256, 3)",
+        );
+        check_expr_error(
+            "1:10: In call to COLOR_RGB: 1:26: Color component -1 out of range",
+            "COLOR_RGB(1, 2, -1)",
+        );
+        check_expr_error(
+            "1:10: In call to COLOR_RGB: 1:20: \"a\" is not a number",
+            "COLOR_RGB(\"a\", 2, 3)",
+        );
+    }
+
+    #[test]
+    fn test_csrlin() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_size_chars(CharsXY::new(10, 5));
+        t.run(
+            "LOCATE 3, 2: r1 = CSRLIN: PRINT \"abc\": r2 = CSRLIN: LOCATE 0, 4: PRINT: r3 = CSRLIN",
+        )
+        .expect_var("r1", 2i32)
+        .expect_var("r2", 3i32)
+        .expect_var("r3", 4i32)
+        .expect_output([
+            CapturedOut::Locate(CharsXY::new(3, 2)),
+            CapturedOut::Print("abc".to_owned()),
+            CapturedOut::Locate(CharsXY::new(0, 4)),
+            CapturedOut::Print("".to_owned()),
+        ])
+        .check();
+
+        check_expr_error(
+            "1:10: In call to CSRLIN: expected no arguments nor parenthesis",
+            "CSRLIN()",
+        );
+        check_expr_error(
+            "1:10: In call to CSRLIN: expected no arguments nor parenthesis",
+            "CSRLIN(1)",
+        );
+    }
+
+    #[test]
+    fn test_font_ok() {
+        Tester::default()
+            .run("FONT 24: FONT \"\", 16.2: FONT \"/some/font.ttf\", 8")
+            .expect_output([
+                CapturedOut::SetFont(None, 24),
+                CapturedOut::SetFont(Some("".to_owned()), 16),
+                CapturedOut::SetFont(Some("/some/font.ttf".to_owned()), 8),
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_font_errors() {
+        for args in &["", "1, 2, 3", "\"a\"; 2", ", 2"] {
+            check_stmt_err(
+                "1:1: In call to FONT: expected [face$, ]size%",
+                &format!("FONT {}", args),
+            );
+        }
+
+        check_stmt_err("1:1: In call to FONT: 1:6: Font size 0 out of range", "FONT 0");
+        check_stmt_err("1:1: In call to FONT: 1:11: Font size -1 out of range", "FONT \"a\", -1");
+        check_stmt_err("1:1: In call to FONT: 1:6: Font size 70000 out of range", "FONT 70000");
+
+        check_stmt_err("1:1: In call to FONT: 1:6: Font face must be a string", "FONT 1, 2");
+        check_stmt_err("1:1: In call to FONT: 1:11: \"b\" is not a number", "FONT \"a\", \"b\"");
+    }
+
     #[test]
     fn test_inkey_ok() {
         Tester::default()
@@ -662,6 +1661,45 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_inkey_delayed_input() {
+        Tester::default()
+            .add_input_delay(POLL_INTERVAL * 2 + POLL_INTERVAL / 2)
+            .add_input_chars("x")
+            .run("n = 0: DO: k = INKEY: n = n + 1: LOOP UNTIL k <> \"\"")
+            .expect_var("k", "x")
+            .expect_var("n", 4)
+            .check();
+    }
+
+    #[test]
+    fn test_inkey_resize() {
+        Tester::default()
+            .add_input_chars("a")
+            .add_input_resize(CharsXY::new(30, 10))
+            .add_input_chars("b")
+            .run("c1 = SCRCOLS: k1 = INKEY: c2 = SCRCOLS: k2 = INKEY: c3 = SCRCOLS: r = SCRROWS")
+            .expect_var("c1", i32::from(u16::MAX))
+            .expect_var("k1", "a")
+            .expect_var("c2", i32::from(u16::MAX))
+            .expect_var("k2", "b")
+            .expect_var("c3", 30)
+            .expect_var("r", 10)
+            .check();
+    }
+
+    #[test]
+    fn test_inkey_signal() {
+        Tester::default()
+            .add_input_chars("a")
+            .add_input_signal(Signal::Break)
+            .run("n = 0: DO: k = INKEY: n = n + 1: LOOP")
+            .expect_ok(StopReason::Break)
+            .expect_var("k", "")
+            .expect_var("n", 1)
+            .check();
+    }
+
     #[test]
     fn test_inkey_errors() {
         check_expr_error(
@@ -760,20 +1798,237 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_input_multiple_variables() {
+        Tester::default()
+            .add_input_chars("3, 4.5 ,  some text\n")
+            .run("INPUT \"Values\"; a, b#, c$")
+            .expect_var("a", 3)
+            .expect_var("b", 4.5)
+            .expect_var("c", "some text")
+            .check();
+
+        Tester::default()
+            .add_input_chars("1\n1, x\n1, 2\n")
+            .run("INPUT ; a, b")
+            .expect_prints([
+                "Retry input: Expected 2 comma-separated values",
+                "Retry input: Invalid integer literal x",
+            ])
+            .expect_var("a", 1)
+            .expect_var("b", 2)
+            .check();
+    }
+
+    #[test]
+    fn test_input_default() {
+        Tester::default()
+            .add_input_chars("\n")
+            .run("INPUT \"Name\"; n$ AS \"Jane\"")
+            .expect_var("n", "Jane")
+            .check();
+
+        Tester::default().add_input_chars("7\n").run("INPUT ; a AS 5").expect_var("a", 7).check();
+
+        Tester::default()
+            .add_input_chars("\n")
+            .run("d = 2.5\nINPUT ; d, e AS STR$(d + 1) + \", 7\"")
+            .expect_var("d", 3.5)
+            .expect_var("e", 7)
+            .check();
+    }
+
+    #[test]
+    fn test_input_default_is_editable() {
+        let t = Tester::default();
+        t.get_console().borrow_mut().set_interactive(true);
+        t.add_input_chars("0\n")
+            .run("INPUT ; a AS 5")
+            .expect_output([
+                CapturedOut::Write("? 5".to_owned()),
+                CapturedOut::SyncNow,
+                CapturedOut::Write("0".to_owned()),
+                CapturedOut::Print("".to_owned()),
+            ])
+            .expect_var("a", 50)
+            .check();
+    }
+
+    #[test]
+    fn test_input_range() {
+        Tester::default()
+            .add_input_chars("150\n20\n")
+            .run("INPUT \"Age\"; age AS , 0, 120")
+            .expect_prints(["Retry input: Value must be between 0 and 120"])
+            .expect_var("age", 20)
+            .check();
+
+        Tester::default()
+            .add_input_chars("\n0.5, 1\n")
+            .run("INPUT ; a#, b AS \"10, 2\", -0.5, 1")
+            .expect_prints(["Retry input: Value must be between -0.5 and 1"])
+            .expect_var("a", 0.5)
+            .expect_var("b", 1)
+            .check();
+    }
+
+    #[test]
+    fn test_input_timeout() {
+        Tester::default()
+            .add_input_chars("5\n")
+            .run("INPUT \"Value\"; a TIMEOUT 1")
+            .expect_var("a", 5)
+            .check();
+
+        Tester::default()
+            .add_input_chars("12")
+            .run("INPUT ; a, b$ TIMEOUT 0.5")
+            .expect_var("a", 0)
+            .expect_var("b", "")
+            .check();
+
+        Tester::default().run("INPUT ; n$ AS \"none\" TIMEOUT 0").expect_var("n", "none").check();
+
+        Tester::default()
+            .add_input_chars("7\n2\n")
+            .run("INPUT ; a AS 3, 1, 5 TIMEOUT 10")
+            .expect_prints(["Retry input: Value must be between 1 and 5"])
+            .expect_var("a", 2)
+            .check();
+
+        Tester::default()
+            .add_input_delay(Duration::from_millis(400))
+            .add_input_chars("3")
+            .add_input_delay(Duration::from_millis(400))
+            .add_input_chars("\n")
+            .run("INPUT ; a TIMEOUT 0.5")
+            .expect_var("a", 3)
+            .check();
+
+        Tester::default()
+            .add_input_delay(Duration::from_millis(1500))
+            .add_input_chars("7\n")
+            .run("INPUT ; a TIMEOUT 1: INPUT ; b")
+            .expect_var("a", 0)
+            .expect_var("b", 7)
+            .check();
+    }
+
     #[test]
     fn test_input_errors() {
-        check_stmt_err("1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref", "INPUT");
+        check_stmt_err("1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]", "INPUT");
         check_stmt_err(
-            "1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref",
+            "1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]",
             "INPUT ; ,",
         );
-        check_stmt_err("1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref", "INPUT ;");
+        check_stmt_err("1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]", "INPUT ;");
         check_stmt_err("1:1: In call to INPUT: 1:7: INPUT prompt must be a string", "INPUT 3 ; a");
         check_stmt_err(
             "1:1: In call to INPUT: 1:9: INPUT requires a variable reference",
             "INPUT ; a + 1",
         );
         check_stmt_err("1:11: Cannot add \"a\" and TRUE", "INPUT \"a\" + TRUE; b?");
+
+        check_stmt_err(
+            "1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]",
+            "INPUT ; a; b",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]",
+            "INPUT ; a AS 1, 2",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]",
+            "INPUT ; a AS 1, , 3",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: 1:16: INPUT range bounds must be numbers",
+            "INPUT ; a AS , \"x\", 3",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: 1:16: INPUT range minimum must not exceed its maximum",
+            "INPUT ; a AS , 5, 3",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: 1:9: INPUT range requires numeric variables",
+            "INPUT ; a$ AS , 1, 3",
+        );
+
+        check_stmt_err(
+            "1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]",
+            "INPUT ; a TIMEOUT",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]",
+            "INPUT ; a TIMEOUT 1, 2",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: expected [\"prompt\" <;|,>] variableref1[, .., variablerefN] [AS default[, min, max]] [TIMEOUT seconds]",
+            "INPUT ; a TIMEOUT 1 AS 2",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: 1:19: INPUT timeout must be positive",
+            "INPUT ; a TIMEOUT -1",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: 1:19: \"x\" is not a number",
+            "INPUT ; a TIMEOUT \"x\"",
+        );
+        check_stmt_err(
+            "1:1: In call to INPUT: 1:14: Invalid integer literal x",
+            "INPUT ; a AS \"x\" TIMEOUT 1",
+        );
+    }
+
+    #[test]
+    fn test_keydown_ok() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_keys_down(&[
+            Key::ArrowLeft,
+            Key::Char('a'),
+            Key::Char(' '),
+        ]);
+        t.run(
+            "r1 = KEYDOWN?(\"LEFT\"): r2 = KEYDOWN?(\"left\"): r3 = KEYDOWN?(\"RIGHT\")
+            r4 = KEYDOWN?(\"a\"): r5 = KEYDOWN?(\"A\"): r6 = KEYDOWN?(\"b\")
+            r7 = KEYDOWN?(\"SPACE\"): r8 = KEYDOWN?(\" \")",
+        )
+        .expect_var("r1", true)
+        .expect_var("r2", true)
+        .expect_var("r3", false)
+        .expect_var("r4", true)
+        .expect_var("r5", true)
+        .expect_var("r6", false)
+        .expect_var("r7", true)
+        .expect_var("r8", true)
+        .check();
+    }
+
+    #[test]
+    fn test_keydown_modifiers() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_key_modifiers(KeyModifiers {
+            shift: true,
+            ctrl: false,
+            alt: true,
+        });
+        t.run("r1 = KEYDOWN?(\"SHIFT\"): r2 = KEYDOWN?(\"ctrl\"): r3 = KEYDOWN?(\"Alt\")")
+            .expect_var("r1", true)
+            .expect_var("r2", false)
+            .expect_var("r3", true)
+            .check();
+    }
+
+    #[test]
+    fn test_keydown_errors() {
+        check_expr_error("1:10: In call to KEYDOWN: expected key$", "KEYDOWN?()");
+        check_expr_error("1:10: In call to KEYDOWN: expected key$", "KEYDOWN?(\"a\", \"b\")");
+        check_expr_error(
+            "1:10: In call to KEYDOWN: 1:19: Key name must be a string",
+            "KEYDOWN?(3)",
+        );
+        check_expr_error("1:10: In call to KEYDOWN: 1:19: Unknown key name F1", "KEYDOWN?(\"F1\")");
+        check_expr_error("1:10: In call to KEYDOWN: 1:19: Unknown key name ", "KEYDOWN?(\"\")");
     }
 
     #[test]
@@ -789,22 +2044,34 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_locate_omitted_coordinates() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_size_chars(CharsXY::new(10, 5));
+        t.run("LOCATE 3, 2: LOCATE , 4: LOCATE 1,: LOCATE ,")
+            .expect_output([
+                CapturedOut::Locate(CharsXY::new(3, 2)),
+                CapturedOut::Locate(CharsXY::new(3, 4)),
+                CapturedOut::Locate(CharsXY::new(1, 4)),
+                CapturedOut::Locate(CharsXY::new(1, 4)),
+            ])
+            .check();
+    }
+
     #[test]
     fn test_locate_errors() {
-        check_stmt_err("1:1: In call to LOCATE: expected column%, row%", "LOCATE");
-        check_stmt_err("1:1: In call to LOCATE: expected column%, row%", "LOCATE 1");
-        check_stmt_err("1:1: In call to LOCATE: expected column%, row%", "LOCATE 1, 2, 3");
-        check_stmt_err("1:1: In call to LOCATE: expected column%, row%", "LOCATE 1; 2");
+        check_stmt_err("1:1: In call to LOCATE: expected [column%], [row%]", "LOCATE");
+        check_stmt_err("1:1: In call to LOCATE: expected [column%], [row%]", "LOCATE 1");
+        check_stmt_err("1:1: In call to LOCATE: expected [column%], [row%]", "LOCATE 1, 2, 3");
+        check_stmt_err("1:1: In call to LOCATE: expected [column%], [row%]", "LOCATE 1; 2");
 
         check_stmt_err("1:1: In call to LOCATE: 1:8: Column out of range", "LOCATE -1, 2");
         check_stmt_err("1:1: In call to LOCATE: 1:8: Column out of range", "LOCATE 70000, 2");
         check_stmt_err("1:1: In call to LOCATE: 1:8: TRUE is not a number", "LOCATE TRUE, 2");
-        check_stmt_err("1:1: In call to LOCATE: expected column%, row%", "LOCATE , 2");
 
         check_stmt_err("1:1: In call to LOCATE: 1:11: Row out of range", "LOCATE 1, -2");
         check_stmt_err("1:1: In call to LOCATE: 1:11: Row out of range", "LOCATE 1, 70000");
         check_stmt_err("1:1: In call to LOCATE: 1:11: TRUE is not a number", "LOCATE 1, TRUE");
-        check_stmt_err("1:1: In call to LOCATE: expected column%, row%", "LOCATE 1,");
 
         let mut t = Tester::default();
         t.get_console().borrow_mut().set_size_chars(CharsXY { x: 30, y: 20 });
@@ -822,6 +2089,25 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_pos() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_size_chars(CharsXY::new(10, 5));
+        t.run("LOCATE 3, 2: c1 = POS: PRINT \"ab\";: c2 = POS: PRINT \"cdefg\";: c3 = POS")
+            .expect_var("c1", 3i32)
+            .expect_var("c2", 5i32)
+            .expect_var("c3", 9i32)
+            .expect_output([
+                CapturedOut::Locate(CharsXY::new(3, 2)),
+                CapturedOut::Write("ab".to_owned()),
+                CapturedOut::Write("cdefg".to_owned()),
+            ])
+            .check();
+
+        check_expr_error("1:10: In call to POS: expected no arguments nor parenthesis", "POS()");
+        check_expr_error("1:10: In call to POS: expected no arguments nor parenthesis", "POS(1)");
+    }
+
     #[test]
     fn test_print_ok() {
         Tester::default().run("PRINT").expect_prints([""]).check();
@@ -940,6 +2226,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_screen_ok() {
+        Tester::default()
+            .run("SCREEN SAVE: SCREEN save: SCREEN RESTORE: SCREEN Restore")
+            .expect_output([
+                CapturedOut::SaveScreen,
+                CapturedOut::SaveScreen,
+                CapturedOut::RestoreScreen,
+                CapturedOut::RestoreScreen,
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_screen_errors() {
+        check_stmt_err("1:1: In call to SCREEN: expected <SAVE|RESTORE>", "SCREEN");
+        check_stmt_err("1:1: In call to SCREEN: expected <SAVE|RESTORE>", "SCREEN SAVE, 1");
+        check_stmt_err("1:1: In call to SCREEN: expected <SAVE|RESTORE>", "SCREEN \"SAVE\"");
+        check_stmt_err("1:1: In call to SCREEN: 1:8: Unknown SCREEN operation FOO", "SCREEN FOO");
+        check_stmt_err(
+            "1:1: In call to SCREEN: 1:8: Unknown SCREEN operation SAVE$",
+            "SCREEN SAVE$",
+        );
+        check_stmt_err("1:1: In call to SCREEN: No saved screen to restore", "SCREEN RESTORE");
+    }
+
     #[test]
     fn test_scrrows() {
         let mut t = Tester::default();
@@ -955,4 +2267,155 @@ mod tests {
             "SCRROWS(1)",
         );
     }
+
+    #[test]
+    fn test_text_style_ok() {
+        let attrs = |bold, underline, reverse, blink| {
+            CapturedOut::SetAttributes(TextAttributes { bold, underline, reverse, blink })
+        };
+
+        Tester::default()
+            .run("TEXT_STYLE BOLD: TEXT_STYLE underline, Reverse: TEXT_STYLE BLINK, BOLD, BLINK: TEXT_STYLE")
+            .expect_output([
+                attrs(true, false, false, false),
+                attrs(false, true, true, false),
+                attrs(true, false, false, true),
+                attrs(false, false, false, false),
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_text_style_reset_on_clear() {
+        Tester::default()
+            .run("TEXT_STYLE REVERSE: CLEAR: CLEAR")
+            .expect_output([
+                CapturedOut::SetAttributes(TextAttributes { reverse: true, ..Default::default() }),
+                CapturedOut::LeaveAlt,
+                CapturedOut::SetColor(None, None),
+                CapturedOut::SetAttributes(TextAttributes::default()),
+                CapturedOut::SetDrawPage(0),
+                CapturedOut::SetClip(None),
+                CapturedOut::ShowCursor,
+                CapturedOut::SetSync(true),
+                CapturedOut::LeaveAlt,
+                CapturedOut::SetColor(None, None),
+                CapturedOut::SetDrawPage(0),
+                CapturedOut::SetClip(None),
+                CapturedOut::ShowCursor,
+                CapturedOut::SetSync(true),
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_text_style_errors() {
+        check_stmt_err(
+            "1:1: In call to TEXT_STYLE: expected [attr1[, .., attrN]]",
+            "TEXT_STYLE BOLD; BLINK",
+        );
+        check_stmt_err(
+            "1:1: In call to TEXT_STYLE: expected [attr1[, .., attrN]]",
+            "TEXT_STYLE \"BOLD\"",
+        );
+        check_stmt_err(
+            "1:1: In call to TEXT_STYLE: expected [attr1[, .., attrN]]",
+            "TEXT_STYLE BOLD,",
+        );
+        check_stmt_err(
+            "1:1: In call to TEXT_STYLE: 1:18: Unknown TEXT_STYLE attribute ITALIC",
+            "TEXT_STYLE BOLD, ITALIC",
+        );
+        check_stmt_err(
+            "1:1: In call to TEXT_STYLE: 1:12: Unknown TEXT_STYLE attribute BOLD$",
+            "TEXT_STYLE BOLD$",
+        );
+    }
+
+    #[test]
+    fn test_window_ok() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_size_chars(CharsXY::new(10, 5));
+        t.run("WINDOW 1, 2, 1, 5, 2: a = SCRCOLS: b = SCRROWS: WINDOW: c = SCRCOLS")
+            .expect_var("a", 4)
+            .expect_var("b", 2)
+            .expect_var("c", 10)
+            .expect_output([
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(2, 1)),
+                CapturedOut::Locate(CharsXY::new(2, 1)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("    ".to_owned()),
+                CapturedOut::Locate(CharsXY::new(2, 2)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("    ".to_owned()),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(2, 1)),
+                CapturedOut::SetColor(None, None),
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_window_select() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_size_chars(CharsXY::new(10, 5));
+        t.run("WINDOW 1, 0, 0, 1, 0: WINDOW 2, 0, 1, 1, 1: WINDOW 1: PRINT \"x\";")
+            .expect_output([
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(0, 0)),
+                CapturedOut::Locate(CharsXY::new(0, 0)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("  ".to_owned()),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(0, 0)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(0, 1)),
+                CapturedOut::Locate(CharsXY::new(0, 1)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("  ".to_owned()),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(0, 1)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(0, 0)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("x".to_owned()),
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_window_errors() {
+        fn check(exp_error: &str, stmt: &str) {
+            let mut t = Tester::default();
+            t.get_console().borrow_mut().set_size_chars(CharsXY::new(10, 5));
+            t.run(stmt).expect_err(exp_error).check();
+        }
+
+        check_stmt_err("1:1: In call to WINDOW: expected [n% [, x1%, y1%, x2%, y2%]]", "WINDOW 1,");
+        check_stmt_err(
+            "1:1: In call to WINDOW: expected [n% [, x1%, y1%, x2%, y2%]]",
+            "WINDOW 1, 2",
+        );
+        check_stmt_err(
+            "1:1: In call to WINDOW: expected [n% [, x1%, y1%, x2%, y2%]]",
+            "WINDOW 1; 2, 3, 4, 5",
+        );
+        check_stmt_err("1:1: In call to WINDOW: 1:8: Window number 0 out of range", "WINDOW 0");
+        check_stmt_err("1:1: In call to WINDOW: 1:8: Window number 256 out of range", "WINDOW 256");
+        check_stmt_err("1:1: In call to WINDOW: 1:8: Window 1 is not defined", "WINDOW 1");
+        check_stmt_err("1:1: In call to WINDOW: 1:8: \"a\" is not a number", "WINDOW \"a\"");
+
+        check("1:1: In call to WINDOW: 1:11: Column out of range", "WINDOW 1, -1, 0, 0, 0");
+        check(
+            "1:1: In call to WINDOW: 1:14: Row 5 exceeds visible range of 4",
+            "WINDOW 1, 0, 5, 0, 0",
+        );
+        check(
+            "1:1: In call to WINDOW: 1:17: Column 10 exceeds visible range of 9",
+            "WINDOW 1, 0, 0, 10, 0",
+        );
+        check("1:1: In call to WINDOW: 1:17: Column 1 is left of column 2", "WINDOW 1, 2, 0, 1, 0");
+        check("1:1: In call to WINDOW: 1:20: Row 1 is above row 2", "WINDOW 1, 0, 2, 0, 1");
+    }
 }
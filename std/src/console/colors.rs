@@ -305,3 +305,120 @@ static COLORS: &[RGB] = &[
 pub fn ansi_color_to_rgb(color: u8) -> RGB {
     COLORS[(color as usize) % COLORS.len()]
 }
+
+/// Converts an RGB color to the ANSI color number that represents it most closely.
+///
+/// The palette contains duplicate colors, in which case the lowest color number is returned.
+pub fn rgb_to_ansi_color(rgb: RGB) -> u8 {
+    let distance = |other: &RGB| {
+        let dr = i32::from(rgb.0) - i32::from(other.0);
+        let dg = i32::from(rgb.1) - i32::from(other.1);
+        let db = i32::from(rgb.2) - i32::from(other.2);
+        dr * dr + dg * dg + db * db
+    };
+
+    let mut best = 0;
+    for (i, color) in COLORS.iter().enumerate() {
+        if distance(color) < distance(&COLORS[best]) {
+            best = i;
+        }
+    }
+    best as u8
+}
+
+/// First color number that encodes an RGB color.  Color numbers below this one select colors from
+/// the ANSI palette.
+const RGB_COLOR_NUMBER_BASE: i32 = 0x1000000;
+
+/// Representation of the color numbers that programs use to refer to colors.
+///
+/// Numbers between 0 and 255 select colors from the ANSI palette, and numbers starting at
+/// `RGB_COLOR_NUMBER_BASE` encode arbitrary RGB colors in their lower 24 bits.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorNumber {
+    /// A color from the ANSI palette.
+    Ansi(u8),
+
+    /// An arbitrary RGB color.
+    Rgb(RGB),
+}
+
+impl ColorNumber {
+    /// Decodes the color number `i`, or returns none if `i` does not represent a color.
+    pub fn from_i32(i: i32) -> Option<Self> {
+        if (0..=i32::from(u8::MAX)).contains(&i) {
+            Some(ColorNumber::Ansi(i as u8))
+        } else if (RGB_COLOR_NUMBER_BASE..(RGB_COLOR_NUMBER_BASE << 1)).contains(&i) {
+            Some(ColorNumber::Rgb(((i >> 16) as u8, (i >> 8) as u8, i as u8)))
+        } else {
+            None
+        }
+    }
+
+    /// Encodes this color as a color number.
+    pub fn to_i32(self) -> i32 {
+        match self {
+            ColorNumber::Ansi(color) => i32::from(color),
+            ColorNumber::Rgb((r, g, b)) => {
+                RGB_COLOR_NUMBER_BASE | i32::from(r) << 16 | i32::from(g) << 8 | i32::from(b)
+            }
+        }
+    }
+
+    /// Returns the RGB color that this color number represents.
+    pub fn to_rgb(self) -> RGB {
+        match self {
+            ColorNumber::Ansi(color) => ansi_color_to_rgb(color),
+            ColorNumber::Rgb(rgb) => rgb,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_to_ansi_color_exact() {
+        for i in 0..=u8::MAX {
+            let rgb = ansi_color_to_rgb(i);
+            assert_eq!(rgb, ansi_color_to_rgb(rgb_to_ansi_color(rgb)));
+        }
+        assert_eq!(AnsiColor::BrightRed as u8, rgb_to_ansi_color((255, 0, 0)));
+        assert_eq!(AnsiColor::Black as u8, rgb_to_ansi_color((0, 0, 0)));
+    }
+
+    #[test]
+    fn test_rgb_to_ansi_color_nearest() {
+        assert_eq!(AnsiColor::BrightRed as u8, rgb_to_ansi_color((250, 3, 1)));
+        assert_eq!(AnsiColor::White as u8, rgb_to_ansi_color((191, 193, 192)));
+    }
+
+    #[test]
+    fn test_color_number_ansi() {
+        for i in 0..=u8::MAX {
+            let color = ColorNumber::from_i32(i32::from(i)).unwrap();
+            assert_eq!(ColorNumber::Ansi(i), color);
+            assert_eq!(i32::from(i), color.to_i32());
+            assert_eq!(ansi_color_to_rgb(i), color.to_rgb());
+        }
+    }
+
+    #[test]
+    fn test_color_number_rgb() {
+        let color = ColorNumber::Rgb((0x12, 0x34, 0x56));
+        assert_eq!(0x1123456, color.to_i32());
+        assert_eq!(Some(color), ColorNumber::from_i32(0x1123456));
+        assert_eq!((0x12, 0x34, 0x56), color.to_rgb());
+
+        assert_eq!(Some(ColorNumber::Rgb((0, 0, 0))), ColorNumber::from_i32(0x1000000));
+        assert_eq!(Some(ColorNumber::Rgb((255, 255, 255))), ColorNumber::from_i32(0x1ffffff));
+    }
+
+    #[test]
+    fn test_color_number_out_of_range() {
+        for i in &[-1, 256, 0xffffff, 0x2000000, i32::MIN, i32::MAX] {
+            assert_eq!(None, ColorNumber::from_i32(*i));
+        }
+    }
+}
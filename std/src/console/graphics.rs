@@ -0,0 +1,1199 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Generic graphical console built on top of backend-specific rendering primitives.
+
+use crate::console::{
+    ansi_color_to_rgb, rgb_to_ansi_color, str_width, AltScreen, AnsiColor, CharsXY, ClearType,
+    Console, Key, KeyModifiers, LineBuffer, PixelsXY, SizeInPixels, NUM_PAGES, RGB,
+};
+use crate::gfx::raster;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+use std::io;
+
+/// Default foreground color, used at console creation time and when requesting the default color
+/// via the `COLOR` command.
+const DEFAULT_FG_COLOR: u8 = AnsiColor::White as u8;
+
+/// Default background color, used at console creation time and when requesting the default color
+/// via the `COLOR` command.
+const DEFAULT_BG_COLOR: u8 = AnsiColor::Black as u8;
+
+/// Properties of the surface on which a graphical console renders.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RasterInfo {
+    /// Size of the surface in pixels.
+    pub size_pixels: SizeInPixels,
+
+    /// Size of every cell of the character grid in pixels.
+    pub glyph_size: SizeInPixels,
+}
+
+/// Rendering primitives that a graphical backend must provide to host a `GraphicsConsole`.
+///
+/// Coordinates given to these operations may fall outside of the surface, in which case the pixels
+/// that do must be silently ignored.
+pub trait RasterOps {
+    /// Returns the properties of the surface.
+    fn get_info(&self) -> RasterInfo;
+
+    /// Sets the color to use in all subsequent drawing operations.
+    fn set_draw_color(&mut self, color: RGB);
+
+    /// Paints the whole surface with the draw color.
+    fn clear(&mut self) -> io::Result<()>;
+
+    /// Paints the pixel at `xy` with the draw color.
+    fn draw_pixel(&mut self, xy: PixelsXY) -> io::Result<()>;
+
+    /// Paints the rectangle of `size` that starts at `xy` with the draw color.
+    fn draw_rect_filled(&mut self, xy: PixelsXY, size: SizeInPixels) -> io::Result<()>;
+
+    /// Renders `text` starting at `xy` with the draw color, leaving the background untouched.
+    ///
+    /// Uses the font of the character grid if `size` is none, or the same font face at `size`
+    /// otherwise.
+    fn write_text(&mut self, xy: PixelsXY, text: &str, size: Option<u16>) -> io::Result<()>;
+
+    /// Returns the color of the pixel at `xy`, or none if it falls outside of the surface.
+    fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>>;
+
+    /// Returns the colors of the pixels in the rectangle of `size` that starts at `xy` in row-major
+    /// order.  The rectangle must fall within the surface.
+    fn read_pixels(&self, xy: PixelsXY, size: SizeInPixels) -> io::Result<Vec<RGB>>;
+
+    /// Replaces the contents of the rectangle of `size` that starts at `xy` with `pixels`, given in
+    /// row-major order.  The rectangle must fall within the surface.
+    fn put_pixels(&mut self, xy: PixelsXY, size: SizeInPixels, pixels: &[RGB]) -> io::Result<()>;
+
+    /// Copies the rectangle of `size` that starts at `from` so that it starts at `to`.
+    fn move_pixels(&mut self, from: PixelsXY, to: PixelsXY, size: SizeInPixels) -> io::Result<()>;
+
+    /// Makes the current contents of the surface visible.
+    fn present_canvas(&mut self) -> io::Result<()>;
+
+    /// Makes `pixels`, which cover the whole surface in row-major order, visible instead of the
+    /// contents of the surface.
+    fn present_pixels(&mut self, pixels: &[RGB]) -> io::Result<()>;
+
+    /// Sets the title of the window that hosts the surface, if any.
+    fn set_title(&mut self, _title: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Input primitives that a graphical backend must provide to host a `GraphicsConsole`.
+#[async_trait(?Send)]
+pub trait InputOps {
+    /// Returns the next key press if any is available.
+    async fn poll_key(&mut self) -> io::Result<Option<Key>>;
+
+    /// Waits for and returns the next key press.
+    async fn read_key(&mut self) -> io::Result<Key>;
+
+    /// Returns true if `_key` is currently held down.  See `Console::is_key_down` for details.
+    fn is_key_down(&mut self, _key: &Key) -> io::Result<bool> {
+        Err(io::Error::new(io::ErrorKind::Other, "No keyboard state support in this console"))
+    }
+
+    /// Returns the modifier keys that are currently held down.
+    fn key_modifiers(&mut self) -> io::Result<KeyModifiers> {
+        Err(io::Error::new(io::ErrorKind::Other, "No keyboard state support in this console"))
+    }
+}
+
+/// Implementation of a graphical console on top of the rendering and input primitives of a
+/// backend.
+///
+/// This contains all the logic to maintain a character grid on top of a pixel-based surface, which
+/// means that backends only need to know how to paint pixels and how to read keys.
+pub struct GraphicsConsole<IO: InputOps, RO: RasterOps> {
+    /// Backend-specific input operations.
+    input_ops: IO,
+
+    /// Backend-specific rendering operations.
+    raster_ops: RO,
+
+    /// Size of the console in pixels.
+    size_pixels: SizeInPixels,
+
+    /// Size of every cell of the character grid in pixels.
+    glyph_size: SizeInPixels,
+
+    /// Size of the console in characters.  This is derived from `size_pixels` and `glyph_size`.
+    size_chars: CharsXY,
+
+    /// Location of the cursor.
+    cursor_pos: CharsXY,
+
+    /// Whether the cursor is visible or not.
+    cursor_visible: bool,
+
+    /// Pixels at the cursor position before the cursor was drawn.  Used to restore the previous
+    /// contents when the cursor moves.
+    cursor_backup: Option<Vec<RGB>>,
+
+    /// Current foreground color as an ANSI color number, or none for the default color.
+    ansi_fg_color: Option<u8>,
+
+    /// Current background color as an ANSI color number, or none for the default color.
+    ansi_bg_color: Option<u8>,
+
+    /// Current foreground color.  Used for text and graphical rendering.
+    fg_color: RGB,
+
+    /// Current background color.  Used to clear text.
+    bg_color: RGB,
+
+    /// State of the console right before entering the "alternate" console.
+    alt_backup: Option<AltScreen>,
+
+    /// Whether video syncing is enabled or not.
+    sync_enabled: bool,
+
+    /// Page that receives all output.  Its contents always live in the surface.
+    draw_page: u8,
+
+    /// Contents of all pages other than `draw_page`, which is stale.  Off-screen pages are created
+    /// on first use.
+    pages: Vec<Option<Vec<RGB>>>,
+
+    /// Top-left and bottom-right corners of the area to which graphical output is constrained, if
+    /// any.  Text output remains unconstrained.
+    clip: Option<(PixelsXY, PixelsXY)>,
+}
+
+impl<IO: InputOps, RO: RasterOps> GraphicsConsole<IO, RO> {
+    /// Initializes a new graphical console on top of the given backend operations.
+    pub fn new(input_ops: IO, raster_ops: RO) -> io::Result<Self> {
+        let info = raster_ops.get_info();
+        if info.glyph_size.width == 0 || info.glyph_size.height == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid glyph size"));
+        }
+        let size_chars = CharsXY::new(
+            info.size_pixels.width / info.glyph_size.width,
+            info.size_pixels.height / info.glyph_size.height,
+        );
+        if size_chars.x == 0 || size_chars.y == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Console too small to fit any characters",
+            ));
+        }
+
+        let mut console = Self {
+            input_ops,
+            raster_ops,
+            size_pixels: info.size_pixels,
+            glyph_size: info.glyph_size,
+            size_chars,
+            cursor_pos: CharsXY::default(),
+            cursor_visible: true,
+            cursor_backup: None,
+            ansi_fg_color: None,
+            ansi_bg_color: None,
+            fg_color: ansi_color_to_rgb(DEFAULT_FG_COLOR),
+            bg_color: ansi_color_to_rgb(DEFAULT_BG_COLOR),
+            alt_backup: None,
+            sync_enabled: true,
+            draw_page: 0,
+            pages: (0..NUM_PAGES).map(|_| None).collect(),
+            clip: None,
+        };
+
+        console.clear(ClearType::All)?;
+
+        Ok(console)
+    }
+
+    /// Returns the pixel coordinates of the top-left corner of the character cell at `pos`.
+    fn cell_xy(&self, pos: CharsXY) -> PixelsXY {
+        let x = i32::from(pos.x) * i32::from(self.glyph_size.width);
+        let y = i32::from(pos.y) * i32::from(self.glyph_size.height);
+        PixelsXY::new(i16::try_from(x).unwrap_or(i16::MAX), i16::try_from(y).unwrap_or(i16::MAX))
+    }
+
+    /// Renders the current contents of the visible page irrespective of the status of the sync
+    /// flag.
+    fn force_present_canvas(&mut self) -> io::Result<()> {
+        match self.draw_page {
+            0 => self.raster_ops.present_canvas(),
+            _ => {
+                let visible = self.pages[0].as_ref().expect("Visible page must have been saved");
+                self.raster_ops.present_pixels(visible)
+            }
+        }
+    }
+
+    /// Renders the current contents of the visible page if syncing is enabled.
+    fn present_canvas(&mut self) -> io::Result<()> {
+        if self.sync_enabled {
+            self.force_present_canvas()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Draws the cursor at the current position and saves the previous contents of the surface so
+    /// that `clear_cursor` can restore them.
+    ///
+    /// Does not present the canvas.
+    fn draw_cursor(&mut self) -> io::Result<()> {
+        if !self.cursor_visible {
+            return Ok(());
+        }
+
+        let xy = self.cell_xy(self.cursor_pos);
+        assert!(self.cursor_backup.is_none());
+        self.cursor_backup = Some(self.raster_ops.read_pixels(xy, self.glyph_size)?);
+
+        self.raster_ops.set_draw_color(self.fg_color);
+        self.raster_ops.draw_rect_filled(xy, self.glyph_size)
+    }
+
+    /// Clears the cursor at the current position by restoring the contents of the surface saved by
+    /// an earlier call to `draw_cursor`.
+    ///
+    /// Does not present the canvas.
+    fn clear_cursor(&mut self) -> io::Result<()> {
+        if !self.cursor_visible {
+            return Ok(());
+        }
+
+        match self.cursor_backup.take() {
+            Some(pixels) => {
+                let xy = self.cell_xy(self.cursor_pos);
+                self.raster_ops.put_pixels(xy, self.glyph_size, &pixels)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Moves the cursor to beginning of the next line, scrolling the console if necessary.
+    ///
+    /// Does not clear nor draw the cursor, and also does not present the canvas.
+    fn open_line(&mut self) -> io::Result<()> {
+        if self.cursor_pos.y < self.size_chars.y - 1 {
+            self.cursor_pos.x = 0;
+            self.cursor_pos.y += 1;
+            return Ok(());
+        }
+
+        let line_height = self.glyph_size.height;
+        let scrolled_height = self.size_chars.y * line_height;
+        self.raster_ops.move_pixels(
+            PixelsXY::new(0, line_height as i16),
+            PixelsXY::new(0, 0),
+            SizeInPixels { width: self.size_pixels.width, height: scrolled_height - line_height },
+        )?;
+        self.raster_ops.set_draw_color(self.bg_color);
+        self.raster_ops.draw_rect_filled(
+            self.cell_xy(CharsXY::new(0, self.size_chars.y - 1)),
+            SizeInPixels { width: self.size_pixels.width, height: line_height },
+        )?;
+
+        self.cursor_pos.x = 0;
+        Ok(())
+    }
+
+    /// Renders the given text at the current cursor position without wrapping.
+    ///
+    /// Does not handle overflow nor scrolling, and also does not present the canvas.
+    fn raw_write(&mut self, text: &str) -> io::Result<()> {
+        let len = match u16::try_from(str_width(text)) {
+            Ok(v) => v,
+            Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "String too long")),
+        };
+
+        let xy = self.cell_xy(self.cursor_pos);
+        self.raster_ops.set_draw_color(self.bg_color);
+        self.raster_ops.draw_rect_filled(
+            xy,
+            SizeInPixels {
+                width: len.saturating_mul(self.glyph_size.width),
+                height: self.glyph_size.height,
+            },
+        )?;
+        self.raster_ops.set_draw_color(self.fg_color);
+        self.raster_ops.write_text(xy, text, None)
+    }
+
+    /// Renders the given text at the current cursor position, with wrapping and scrolling if
+    /// necessary.
+    ///
+    /// Does not present the canvas.
+    fn raw_write_wrapped(&mut self, text: &str) -> io::Result<()> {
+        let mut line_buffer = LineBuffer::from(text);
+
+        loop {
+            let fit_width = self.size_chars.x.saturating_sub(self.cursor_pos.x);
+
+            let mut remaining = line_buffer.split_off_width(usize::from(fit_width));
+            if line_buffer.is_empty() && self.cursor_pos.x == 0 {
+                // The next character is wider than the whole console.  Render it clipped instead
+                // of wrapping forever.
+                line_buffer = remaining;
+                remaining = line_buffer.split_off(line_buffer.next_boundary(0));
+            }
+            if !line_buffer.is_empty() {
+                let width = line_buffer.width();
+                self.raw_write(&line_buffer.into_inner())?;
+                self.cursor_pos.x = self.cursor_pos.x.saturating_add(
+                    u16::try_from(width).expect("Partial width was computed to fit on the screen"),
+                );
+            }
+
+            line_buffer = remaining;
+            if line_buffer.is_empty() {
+                break;
+            } else {
+                self.open_line()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Paints the rectangle with the top-left and bottom-right corners `x1y1` and `x2y2`, both
+    /// included, with the foreground color, constrained to the clipping area.
+    fn fill_clipped(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        let (mut x1, mut y1) = (i32::from(x1y1.x), i32::from(x1y1.y));
+        let (mut x2, mut y2) = (i32::from(x2y2.x), i32::from(x2y2.y));
+        if let Some((clip1, clip2)) = self.clip {
+            x1 = x1.max(i32::from(clip1.x));
+            y1 = y1.max(i32::from(clip1.y));
+            x2 = x2.min(i32::from(clip2.x));
+            y2 = y2.min(i32::from(clip2.y));
+        }
+        x1 = x1.max(0);
+        y1 = y1.max(0);
+        x2 = x2.min(i32::from(self.size_pixels.width) - 1);
+        y2 = y2.min(i32::from(self.size_pixels.height) - 1);
+        if x1 > x2 || y1 > y2 {
+            return Ok(());
+        }
+
+        // All values are within the surface, which means they fit in the target types.
+        let xy = PixelsXY::new(x1 as i16, y1 as i16);
+        let size = SizeInPixels { width: (x2 - x1 + 1) as u16, height: (y2 - y1 + 1) as u16 };
+        self.raster_ops.draw_rect_filled(xy, size)
+    }
+
+    /// Paints the pixel at `xy` with the foreground color unless it falls outside of the clipping
+    /// area.
+    fn put_pixel(&mut self, xy: PixelsXY) -> io::Result<()> {
+        if let Some((x1y1, x2y2)) = self.clip {
+            if xy.x < x1y1.x || xy.x > x2y2.x || xy.y < x1y1.y || xy.y > x2y2.y {
+                return Ok(());
+            }
+        }
+        self.raster_ops.draw_pixel(xy)
+    }
+
+    /// Paints the line that goes from `x1y1` to `x2y2`, both included, with the foreground color.
+    fn put_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        if x1y1.y == x2y2.y {
+            return self.fill_clipped(
+                PixelsXY::new(x1y1.x.min(x2y2.x), x1y1.y),
+                PixelsXY::new(x1y1.x.max(x2y2.x), x1y1.y),
+            );
+        }
+        raster::line(x1y1, x2y2, |xy| self.put_pixel(xy))
+    }
+
+    /// Computes the top-left and bottom-right corners, both included, of the rectangle given by
+    /// `x1y1` and `x2y2`, which excludes the right and bottom edges for consistency with the other
+    /// consoles.  Returns none if the rectangle is empty.
+    fn rect_corners(x1y1: PixelsXY, x2y2: PixelsXY) -> Option<(PixelsXY, PixelsXY)> {
+        let (x1, x2) = (x1y1.x.min(x2y2.x), x1y1.x.max(x2y2.x));
+        let (y1, y2) = (x1y1.y.min(x2y2.y), x1y1.y.max(x2y2.y));
+        if x1 == x2 || y1 == y2 {
+            None
+        } else {
+            Some((PixelsXY::new(x1, y1), PixelsXY::new(x2 - 1, y2 - 1)))
+        }
+    }
+
+    /// Returns the contents of the whole surface.
+    fn read_all_pixels(&self) -> io::Result<Vec<RGB>> {
+        self.raster_ops.read_pixels(PixelsXY::new(0, 0), self.size_pixels)
+    }
+
+    /// Replaces the contents of the whole surface with `pixels`.
+    fn put_all_pixels(&mut self, pixels: &[RGB]) -> io::Result<()> {
+        self.raster_ops.put_pixels(PixelsXY::new(0, 0), self.size_pixels, pixels)
+    }
+
+    /// Returns a page filled with the background color.
+    fn new_page(&self) -> Vec<RGB> {
+        vec![
+            self.bg_color;
+            usize::from(self.size_pixels.width) * usize::from(self.size_pixels.height)
+        ]
+    }
+}
+
+#[async_trait(?Send)]
+impl<IO: InputOps, RO: RasterOps> Console for GraphicsConsole<IO, RO> {
+    fn clear(&mut self, how: ClearType) -> io::Result<()> {
+        match how {
+            ClearType::All => {
+                self.raster_ops.set_draw_color(self.bg_color);
+                self.raster_ops.clear()?;
+                self.cursor_pos.y = 0;
+                self.cursor_pos.x = 0;
+                self.cursor_backup = None;
+            }
+            ClearType::CurrentLine => {
+                self.clear_cursor()?;
+                self.raster_ops.set_draw_color(self.bg_color);
+                self.raster_ops.draw_rect_filled(
+                    self.cell_xy(CharsXY::new(0, self.cursor_pos.y)),
+                    SizeInPixels { width: self.size_pixels.width, height: self.glyph_size.height },
+                )?;
+                self.cursor_pos.x = 0;
+            }
+            ClearType::PreviousChar => {
+                if self.cursor_pos.x > 0 {
+                    self.clear_cursor()?;
+                    let previous_pos = CharsXY::new(self.cursor_pos.x - 1, self.cursor_pos.y);
+                    self.raster_ops.set_draw_color(self.bg_color);
+                    self.raster_ops
+                        .draw_rect_filled(self.cell_xy(previous_pos), self.glyph_size)?;
+                    self.cursor_pos = previous_pos;
+                }
+            }
+            ClearType::UntilNewLine => {
+                self.clear_cursor()?;
+                let xy = self.cell_xy(self.cursor_pos);
+                let width =
+                    u16::try_from(i32::from(self.size_pixels.width) - i32::from(xy.x)).unwrap_or(0);
+                self.raster_ops.set_draw_color(self.bg_color);
+                self.raster_ops
+                    .draw_rect_filled(xy, SizeInPixels { width, height: self.glyph_size.height })?;
+            }
+        }
+
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    fn color(&self) -> (Option<u8>, Option<u8>) {
+        (self.ansi_fg_color, self.ansi_bg_color)
+    }
+
+    fn set_color(&mut self, fg: Option<u8>, bg: Option<u8>) -> io::Result<()> {
+        self.ansi_fg_color = fg;
+        self.fg_color = ansi_color_to_rgb(fg.unwrap_or(DEFAULT_FG_COLOR));
+        self.ansi_bg_color = bg;
+        self.bg_color = ansi_color_to_rgb(bg.unwrap_or(DEFAULT_BG_COLOR));
+        Ok(())
+    }
+
+    fn set_color_rgb(&mut self, fg: Option<RGB>, bg: Option<RGB>) -> io::Result<()> {
+        self.ansi_fg_color = fg.map(rgb_to_ansi_color);
+        self.fg_color = fg.unwrap_or_else(|| ansi_color_to_rgb(DEFAULT_FG_COLOR));
+        self.ansi_bg_color = bg.map(rgb_to_ansi_color);
+        self.bg_color = bg.unwrap_or_else(|| ansi_color_to_rgb(DEFAULT_BG_COLOR));
+        Ok(())
+    }
+
+    fn cursor_pos(&self) -> io::Result<CharsXY> {
+        Ok(self.cursor_pos)
+    }
+
+    fn enter_alt(&mut self) -> io::Result<()> {
+        if self.alt_backup.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot nest alternate screens",
+            ));
+        }
+
+        self.clear_cursor()?;
+        let pixels = self.read_all_pixels()?;
+        self.alt_backup = Some(AltScreen {
+            pixels,
+            cursor_pos: self.cursor_pos,
+            ansi_fg_color: self.ansi_fg_color,
+            ansi_bg_color: self.ansi_bg_color,
+            fg_color: self.fg_color,
+            bg_color: self.bg_color,
+        });
+
+        self.clear(ClearType::All)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.clear_cursor()?;
+        self.cursor_visible = false;
+        self.present_canvas()
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+
+    fn is_key_down(&mut self, key: &Key) -> io::Result<bool> {
+        self.input_ops.is_key_down(key)
+    }
+
+    fn key_modifiers(&mut self) -> io::Result<KeyModifiers> {
+        self.input_ops.key_modifiers()
+    }
+
+    fn leave_alt(&mut self) -> io::Result<()> {
+        let alt = match self.alt_backup.take() {
+            Some(alt) => alt,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Cannot leave alternate screen; not entered",
+                ))
+            }
+        };
+
+        self.cursor_backup = None;
+        self.put_all_pixels(&alt.pixels)?;
+
+        self.cursor_pos = alt.cursor_pos;
+        self.ansi_fg_color = alt.ansi_fg_color;
+        self.ansi_bg_color = alt.ansi_bg_color;
+        self.fg_color = alt.fg_color;
+        self.bg_color = alt.bg_color;
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    fn locate(&mut self, pos: CharsXY) -> io::Result<()> {
+        debug_assert!(pos.x < self.size_chars.x);
+        debug_assert!(pos.y < self.size_chars.y);
+
+        self.clear_cursor()?;
+        self.cursor_pos = pos;
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    fn move_within_line(&mut self, off: i16) -> io::Result<()> {
+        self.clear_cursor()?;
+        if off < 0 {
+            self.cursor_pos.x -= -off as u16;
+        } else {
+            self.cursor_pos.x += off as u16;
+        }
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let text = crate::console::remove_control_chars(text);
+
+        self.clear_cursor()?;
+        self.raw_write_wrapped(&text)?;
+        self.open_line()?;
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    async fn poll_key(&mut self) -> io::Result<Option<Key>> {
+        self.input_ops.poll_key().await
+    }
+
+    async fn read_key(&mut self) -> io::Result<Key> {
+        self.input_ops.read_key().await
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        if !self.cursor_visible {
+            self.cursor_visible = true;
+            if let Err(e) = self.draw_cursor() {
+                self.cursor_visible = false;
+                return Err(e);
+            }
+        }
+        self.present_canvas()
+    }
+
+    fn size_chars(&self) -> io::Result<CharsXY> {
+        Ok(self.size_chars)
+    }
+
+    fn size_pixels(&self) -> io::Result<SizeInPixels> {
+        Ok(self.size_pixels)
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        let text = crate::console::remove_control_chars(text);
+
+        self.clear_cursor()?;
+        self.raw_write_wrapped(&text)?;
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    fn draw_arc(&mut self, center: PixelsXY, radius: u16, start: i32, end: i32) -> io::Result<()> {
+        self.raster_ops.set_draw_color(self.fg_color);
+        raster::arc(center, radius, start, end, |xy| self.put_pixel(xy))?;
+        self.present_canvas()
+    }
+
+    fn draw_circle(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
+        self.draw_ellipse(center, radius, radius)
+    }
+
+    fn draw_circle_filled(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
+        self.draw_ellipse_filled(center, radius, radius)
+    }
+
+    fn draw_ellipse(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.raster_ops.set_draw_color(self.fg_color);
+        raster::ellipse(center, rx, ry, |xy| self.put_pixel(xy))?;
+        self.present_canvas()
+    }
+
+    fn draw_ellipse_filled(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.raster_ops.set_draw_color(self.fg_color);
+        raster::ellipse_filled(center, rx, ry, |x1y1, x2y2| self.fill_clipped(x1y1, x2y2))?;
+        self.present_canvas()
+    }
+
+    fn draw_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        if x1y1 == x2y2 {
+            // For consistency with the other consoles, which paint nothing in this case.
+            return Ok(());
+        }
+
+        self.raster_ops.set_draw_color(self.fg_color);
+        self.put_line(x1y1, x2y2)?;
+        self.present_canvas()
+    }
+
+    fn draw_pixel(&mut self, xy: PixelsXY) -> io::Result<()> {
+        self.raster_ops.set_draw_color(self.fg_color);
+        self.put_pixel(xy)?;
+        self.present_canvas()
+    }
+
+    fn draw_poly(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.raster_ops.set_draw_color(self.fg_color);
+        raster::poly(points, |x1y1, x2y2| self.put_line(x1y1, x2y2))?;
+        self.present_canvas()
+    }
+
+    fn draw_poly_filled(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.raster_ops.set_draw_color(self.fg_color);
+        raster::poly_filled(points, |x1y1, x2y2| self.fill_clipped(x1y1, x2y2))?;
+        raster::poly(points, |x1y1, x2y2| self.put_line(x1y1, x2y2))?;
+        self.present_canvas()
+    }
+
+    fn draw_rect(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        if let Some((x1y1, x2y2)) = Self::rect_corners(x1y1, x2y2) {
+            self.raster_ops.set_draw_color(self.fg_color);
+            self.fill_clipped(x1y1, PixelsXY::new(x2y2.x, x1y1.y))?;
+            self.fill_clipped(PixelsXY::new(x1y1.x, x2y2.y), x2y2)?;
+            self.fill_clipped(x1y1, PixelsXY::new(x1y1.x, x2y2.y))?;
+            self.fill_clipped(PixelsXY::new(x2y2.x, x1y1.y), x2y2)?;
+        }
+        self.present_canvas()
+    }
+
+    fn draw_rect_filled(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        if let Some((x1y1, x2y2)) = Self::rect_corners(x1y1, x2y2) {
+            self.raster_ops.set_draw_color(self.fg_color);
+            self.fill_clipped(x1y1, x2y2)?;
+        }
+        self.present_canvas()
+    }
+
+    fn draw_text(&mut self, xy: PixelsXY, text: &str, size: Option<u16>) -> io::Result<()> {
+        self.raster_ops.set_draw_color(self.fg_color);
+        self.raster_ops.write_text(xy, text, size)?;
+        self.present_canvas()
+    }
+
+    fn flood_fill(&mut self, xy: PixelsXY, border: Option<RGB>) -> io::Result<()> {
+        let target = match border {
+            Some(border) => border,
+            None => match self.raster_ops.read_pixel(xy)? {
+                Some(target) => target,
+                None => return Ok(()),
+            },
+        };
+        if border.is_none() && target == self.fg_color {
+            // Filling the area with the color it already has would be a no-op.
+            return Ok(());
+        }
+
+        let snapshot = self.read_all_pixels()?;
+        let width = usize::from(self.size_pixels.width);
+        let color_at = |xy: PixelsXY| snapshot[usize::from(xy.y as u16) * width + xy.x as usize];
+
+        self.raster_ops.set_draw_color(self.fg_color);
+        raster::flood_fill(
+            self.size_pixels,
+            xy,
+            |xy| match border {
+                Some(_) => color_at(xy) != target,
+                None => color_at(xy) == target,
+            },
+            |x1y1, x2y2| self.fill_clipped(x1y1, x2y2),
+        )?;
+        self.present_canvas()
+    }
+
+    fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+        self.raster_ops.read_pixel(xy)
+    }
+
+    fn set_clip(&mut self, area: Option<(PixelsXY, PixelsXY)>) -> io::Result<()> {
+        self.clip = area;
+        Ok(())
+    }
+
+    fn set_draw_page(&mut self, page: u8) -> io::Result<()> {
+        if page >= NUM_PAGES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page out of range"));
+        }
+        if page == self.draw_page {
+            return Ok(());
+        }
+
+        self.clear_cursor()?;
+        let old = self.read_all_pixels()?;
+        self.pages[usize::from(self.draw_page)] = Some(old);
+        match self.pages[usize::from(page)].take() {
+            Some(new) => self.put_all_pixels(&new)?,
+            None => {
+                self.raster_ops.set_draw_color(self.bg_color);
+                self.raster_ops.clear()?;
+            }
+        }
+        self.draw_page = page;
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    fn flip_page(&mut self, page: u8) -> io::Result<()> {
+        if page >= NUM_PAGES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page out of range"));
+        }
+
+        if page != 0 {
+            let src = if page == self.draw_page {
+                self.clear_cursor()?;
+                let src = self.read_all_pixels()?;
+                self.draw_cursor()?;
+                src
+            } else {
+                match self.pages[usize::from(page)].as_ref() {
+                    Some(src) => src.clone(),
+                    None => self.new_page(),
+                }
+            };
+
+            if self.draw_page == 0 {
+                self.clear_cursor()?;
+                self.put_all_pixels(&src)?;
+                self.draw_cursor()?;
+            } else {
+                self.pages[0] = Some(src);
+            }
+        }
+        self.present_canvas()
+    }
+
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        self.raster_ops.set_title(title)
+    }
+
+    fn sync_now(&mut self) -> io::Result<()> {
+        if self.sync_enabled {
+            Ok(())
+        } else {
+            self.force_present_canvas()
+        }
+    }
+
+    fn set_sync(&mut self, enabled: bool) -> io::Result<bool> {
+        if !self.sync_enabled {
+            self.force_present_canvas()?;
+        }
+        let previous = self.sync_enabled;
+        self.sync_enabled = enabled;
+        Ok(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::future::block_on;
+    use std::collections::VecDeque;
+
+    /// Input operations that return keys from a predefined list.
+    #[derive(Default)]
+    struct MockInput {
+        keys: VecDeque<Key>,
+    }
+
+    #[async_trait(?Send)]
+    impl InputOps for MockInput {
+        async fn poll_key(&mut self) -> io::Result<Option<Key>> {
+            Ok(self.keys.pop_front())
+        }
+
+        async fn read_key(&mut self) -> io::Result<Key> {
+            Ok(self.keys.pop_front().unwrap_or(Key::Eof))
+        }
+    }
+
+    /// Rendering operations on an in-memory surface with 2x3 pixel glyphs.
+    ///
+    /// Text is rendered by painting the top-left pixel of every glyph with the draw color, which is
+    /// sufficient to validate where text ends up.
+    struct MockRaster {
+        size: SizeInPixels,
+        pixels: Vec<RGB>,
+        draw_color: RGB,
+        presented: Vec<Vec<RGB>>,
+    }
+
+    impl MockRaster {
+        /// Creates a new surface of `width` by `height` pixels.
+        fn new(width: u16, height: u16) -> Self {
+            Self {
+                size: SizeInPixels { width, height },
+                pixels: vec![(0, 0, 0); usize::from(width) * usize::from(height)],
+                draw_color: (0, 0, 0),
+                presented: vec![],
+            }
+        }
+
+        /// Computes the offset of `xy` into the pixels, if within bounds.
+        fn offset(&self, xy: PixelsXY) -> Option<usize> {
+            if xy.x < 0
+                || xy.y < 0
+                || xy.x as u16 >= self.size.width
+                || xy.y as u16 >= self.size.height
+            {
+                None
+            } else {
+                Some(xy.y as usize * usize::from(self.size.width) + xy.x as usize)
+            }
+        }
+    }
+
+    impl RasterOps for MockRaster {
+        fn get_info(&self) -> RasterInfo {
+            RasterInfo { size_pixels: self.size, glyph_size: SizeInPixels { width: 2, height: 3 } }
+        }
+
+        fn set_draw_color(&mut self, color: RGB) {
+            self.draw_color = color;
+        }
+
+        fn clear(&mut self) -> io::Result<()> {
+            for pixel in self.pixels.iter_mut() {
+                *pixel = self.draw_color;
+            }
+            Ok(())
+        }
+
+        fn draw_pixel(&mut self, xy: PixelsXY) -> io::Result<()> {
+            if let Some(offset) = self.offset(xy) {
+                self.pixels[offset] = self.draw_color;
+            }
+            Ok(())
+        }
+
+        fn draw_rect_filled(&mut self, xy: PixelsXY, size: SizeInPixels) -> io::Result<()> {
+            for y in 0..size.height as i16 {
+                for x in 0..size.width as i16 {
+                    self.draw_pixel(PixelsXY::new(xy.x + x, xy.y + y))?;
+                }
+            }
+            Ok(())
+        }
+
+        fn write_text(&mut self, xy: PixelsXY, text: &str, _size: Option<u16>) -> io::Result<()> {
+            let mut x = xy.x;
+            for ch in text.chars() {
+                let width = crate::console::char_width(ch) as i16;
+                if width > 0 {
+                    self.draw_pixel(PixelsXY::new(x, xy.y))?;
+                    x += width * 2;
+                }
+            }
+            Ok(())
+        }
+
+        fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+            Ok(self.offset(xy).map(|offset| self.pixels[offset]))
+        }
+
+        fn read_pixels(&self, xy: PixelsXY, size: SizeInPixels) -> io::Result<Vec<RGB>> {
+            let mut pixels = vec![];
+            for y in 0..size.height as i16 {
+                for x in 0..size.width as i16 {
+                    let offset = self.offset(PixelsXY::new(xy.x + x, xy.y + y)).unwrap();
+                    pixels.push(self.pixels[offset]);
+                }
+            }
+            Ok(pixels)
+        }
+
+        fn put_pixels(
+            &mut self,
+            xy: PixelsXY,
+            size: SizeInPixels,
+            pixels: &[RGB],
+        ) -> io::Result<()> {
+            let mut pixels = pixels.iter();
+            for y in 0..size.height as i16 {
+                for x in 0..size.width as i16 {
+                    let offset = self.offset(PixelsXY::new(xy.x + x, xy.y + y)).unwrap();
+                    self.pixels[offset] = *pixels.next().unwrap();
+                }
+            }
+            Ok(())
+        }
+
+        fn move_pixels(
+            &mut self,
+            from: PixelsXY,
+            to: PixelsXY,
+            size: SizeInPixels,
+        ) -> io::Result<()> {
+            let snapshot = self.pixels.clone();
+            for y in 0..size.height as i16 {
+                for x in 0..size.width as i16 {
+                    let src = self.offset(PixelsXY::new(from.x + x, from.y + y));
+                    let dst = self.offset(PixelsXY::new(to.x + x, to.y + y));
+                    if let (Some(src), Some(dst)) = (src, dst) {
+                        self.pixels[dst] = snapshot[src];
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        fn present_canvas(&mut self) -> io::Result<()> {
+            self.presented.push(self.pixels.clone());
+            Ok(())
+        }
+
+        fn present_pixels(&mut self, pixels: &[RGB]) -> io::Result<()> {
+            self.presented.push(pixels.to_vec());
+            Ok(())
+        }
+    }
+
+    /// Shorthand for the default foreground color.
+    const FG: RGB = (192, 192, 192);
+
+    /// Shorthand for the default background color.
+    const BG: RGB = (0, 0, 0);
+
+    /// Creates a new console backed by a `width` by `height` surface and hides its cursor to
+    /// simplify inspecting the rendered pixels.
+    fn new_console(width: u16, height: u16) -> GraphicsConsole<MockInput, MockRaster> {
+        let mut console =
+            GraphicsConsole::new(MockInput::default(), MockRaster::new(width, height)).unwrap();
+        console.hide_cursor().unwrap();
+        console
+    }
+
+    /// Returns the coordinates of all pixels in `console` that have the `rgb` color.
+    fn pixels_with(console: &GraphicsConsole<MockInput, MockRaster>, rgb: RGB) -> Vec<(i16, i16)> {
+        let raster = &console.raster_ops;
+        let mut pixels = vec![];
+        for y in 0..raster.size.height as i16 {
+            for x in 0..raster.size.width as i16 {
+                if raster.read_pixel(PixelsXY::new(x, y)).unwrap() == Some(rgb) {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_graphics_console_sizes() {
+        let console = new_console(9, 10);
+        assert_eq!(CharsXY::new(4, 3), console.size_chars().unwrap());
+        assert_eq!(SizeInPixels { width: 9, height: 10 }, console.size_pixels().unwrap());
+
+        let e = GraphicsConsole::new(MockInput::default(), MockRaster::new(1, 10)).err().unwrap();
+        assert_eq!("Console too small to fit any characters", format!("{}", e));
+    }
+
+    #[test]
+    fn test_graphics_console_cursor() {
+        let mut console = new_console(4, 6);
+        assert!(pixels_with(&console, FG).is_empty());
+
+        console.show_cursor().unwrap();
+        assert_eq!(vec![(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)], pixels_with(&console, FG));
+
+        console.locate(CharsXY::new(1, 1)).unwrap();
+        assert_eq!(vec![(2, 3), (3, 3), (2, 4), (3, 4), (2, 5), (3, 5)], pixels_with(&console, FG));
+
+        console.hide_cursor().unwrap();
+        assert!(pixels_with(&console, FG).is_empty());
+    }
+
+    #[test]
+    fn test_graphics_console_print_wraps_and_scrolls() {
+        let mut console = new_console(4, 9);
+
+        console.print("abc").unwrap();
+        assert_eq!(vec![(0, 0), (2, 0), (0, 3)], pixels_with(&console, FG));
+        assert_eq!(CharsXY::new(0, 2), console.cursor_pos);
+
+        console.print("d").unwrap();
+        assert_eq!(vec![(0, 0), (0, 3)], pixels_with(&console, FG));
+        assert_eq!(CharsXY::new(0, 2), console.cursor_pos);
+    }
+
+    #[test]
+    fn test_graphics_console_write_wide_chars() {
+        let mut console = new_console(8, 6);
+
+        console.write("a日e\u{301}").unwrap();
+        assert_eq!(vec![(0, 0), (2, 0), (6, 0)], pixels_with(&console, FG));
+        assert_eq!(CharsXY::new(4, 0), console.cursor_pos);
+
+        console.write("本").unwrap();
+        assert_eq!(vec![(0, 0), (2, 0), (6, 0), (0, 3)], pixels_with(&console, FG));
+        assert_eq!(CharsXY::new(2, 1), console.cursor_pos);
+
+        console.write("語x").unwrap();
+        assert_eq!(vec![(0, 0), (4, 0), (0, 3)], pixels_with(&console, FG));
+        assert_eq!(CharsXY::new(1, 1), console.cursor_pos);
+    }
+
+    #[test]
+    fn test_graphics_console_write_and_clear() {
+        let mut console = new_console(6, 3);
+
+        console.write("ab").unwrap();
+        assert_eq!(vec![(0, 0), (2, 0)], pixels_with(&console, FG));
+
+        console.clear(ClearType::PreviousChar).unwrap();
+        assert_eq!(vec![(0, 0)], pixels_with(&console, FG));
+        assert_eq!(CharsXY::new(1, 0), console.cursor_pos);
+
+        console.clear(ClearType::All).unwrap();
+        assert!(pixels_with(&console, FG).is_empty());
+        assert_eq!(CharsXY::new(0, 0), console.cursor_pos);
+    }
+
+    #[test]
+    fn test_graphics_console_alt() {
+        let mut console = new_console(6, 3);
+        console.write("ab").unwrap();
+        console.set_color(Some(AnsiColor::Red as u8), None).unwrap();
+
+        console.enter_alt().unwrap();
+        assert!(pixels_with(&console, FG).is_empty());
+        console.write("c").unwrap();
+
+        console.leave_alt().unwrap();
+        assert_eq!(vec![(0, 0), (2, 0)], pixels_with(&console, FG));
+        assert_eq!(CharsXY::new(2, 0), console.cursor_pos);
+        assert_eq!((Some(AnsiColor::Red as u8), None), console.color());
+
+        assert_eq!(io::ErrorKind::InvalidInput, console.leave_alt().unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_graphics_console_shapes_and_clip() {
+        let mut console = new_console(10, 10);
+
+        console.draw_rect_filled(PixelsXY::new(3, 2), PixelsXY::new(1, 1)).unwrap();
+        assert_eq!(vec![(1, 1), (2, 1)], pixels_with(&console, FG));
+
+        console.clear(ClearType::All).unwrap();
+        console.set_clip(Some((PixelsXY::new(2, 2), PixelsXY::new(3, 3)))).unwrap();
+        console.draw_line(PixelsXY::new(0, 2), PixelsXY::new(9, 2)).unwrap();
+        console.draw_pixel(PixelsXY::new(0, 0)).unwrap();
+        console.draw_pixel(PixelsXY::new(3, 3)).unwrap();
+        assert_eq!(vec![(2, 2), (3, 2), (3, 3)], pixels_with(&console, FG));
+
+        // Text is not subject to clipping.
+        console.set_clip(None).unwrap();
+        console.clear(ClearType::All).unwrap();
+        console.set_clip(Some((PixelsXY::new(2, 2), PixelsXY::new(3, 3)))).unwrap();
+        console.write("a").unwrap();
+        assert_eq!(vec![(0, 0)], pixels_with(&console, FG));
+    }
+
+    #[test]
+    fn test_graphics_console_flood_fill() {
+        let mut console = new_console(10, 10);
+        console.draw_rect(PixelsXY::new(0, 0), PixelsXY::new(4, 4)).unwrap();
+        console.set_color(Some(AnsiColor::Red as u8), None).unwrap();
+        console.flood_fill(PixelsXY::new(1, 1), None).unwrap();
+        assert_eq!(
+            vec![(1, 1), (2, 1), (1, 2), (2, 2)],
+            pixels_with(&console, ansi_color_to_rgb(AnsiColor::Red as u8))
+        );
+    }
+
+    #[test]
+    fn test_graphics_console_pages_and_sync() {
+        let mut console = new_console(4, 6);
+        assert!(console.set_sync(false).unwrap());
+        console.raster_ops.presented.clear();
+
+        console.set_draw_page(1).unwrap();
+        console.draw_pixel(PixelsXY::new(1, 1)).unwrap();
+        assert!(console.raster_ops.presented.is_empty());
+
+        console.sync_now().unwrap();
+        assert_eq!(1, console.raster_ops.presented.len());
+        assert!(!console.raster_ops.presented[0].contains(&FG));
+
+        console.flip_page(1).unwrap();
+        console.sync_now().unwrap();
+        assert_eq!(2, console.raster_ops.presented.len());
+        assert_eq!(FG, console.raster_ops.presented[1][4 + 1]);
+
+        console.set_draw_page(0).unwrap();
+        assert_eq!(vec![(1, 1)], pixels_with(&console, FG));
+        assert_eq!(23, pixels_with(&console, BG).len());
+
+        assert_eq!(
+            io::ErrorKind::InvalidInput,
+            console.set_draw_page(NUM_PAGES).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn test_graphics_console_input() {
+        let mut input = MockInput::default();
+        input.keys.push_back(Key::Char('a'));
+        let mut console = GraphicsConsole::new(input, MockRaster::new(4, 6)).unwrap();
+        assert_eq!(Some(Key::Char('a')), block_on(console.poll_key()).unwrap());
+        assert_eq!(None, block_on(console.poll_key()).unwrap());
+        assert_eq!(Key::Eof, block_on(console.read_key()).unwrap());
+
+        let e = console.is_key_down(&Key::Char('a')).unwrap_err();
+        assert_eq!("No keyboard state support in this console", format!("{}", e));
+    }
+}
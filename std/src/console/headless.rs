@@ -0,0 +1,665 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Graphical console that renders off-screen for environments without a display.
+
+use crate::console::{
+    ansi_color_to_rgb, read_key_from_stdin, remove_control_chars, rgb_to_ansi_color, AltScreen,
+    AnsiColor, CharsXY, ClearType, Console, Key, PixelsXY, SizeInPixels, NUM_PAGES, RGB,
+};
+use crate::gfx::raster;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, StdoutLock, Write};
+use std::mem;
+use std::path::PathBuf;
+
+/// Default foreground color, used at console creation time and when requesting the default color
+/// via the `COLOR` command.
+const DEFAULT_FG_COLOR: u8 = AnsiColor::White as u8;
+
+/// Default background color, used at console creation time and when requesting the default color
+/// via the `COLOR` command.
+const DEFAULT_BG_COLOR: u8 = AnsiColor::Black as u8;
+
+/// Size of the cells of the character grid.  Text is not rendered, but the size of the console in
+/// characters must still be derived from its size in pixels.
+const GLYPH_SIZE: SizeInPixels = SizeInPixels { width: 8, height: 16 };
+
+/// Implementation of the EndBASIC console that renders graphics into an in-memory framebuffer.
+///
+/// Text is written to stdout instead of being rendered into the framebuffer, which makes this
+/// console suitable to run programs that use graphics in environments without a display, such as
+/// automated tests.  The contents of the framebuffer can be saved to files on every explicit video
+/// sync.
+pub struct HeadlessConsole {
+    /// Line-oriented buffer to hold input.
+    buffer: VecDeque<Key>,
+
+    /// Size of the console in pixels.
+    size_pixels: SizeInPixels,
+
+    /// Contents of the page that receives all output, in row-major order.
+    pixels: Vec<RGB>,
+
+    /// Current foreground color as an ANSI color number, or none for the default color.
+    ansi_fg_color: Option<u8>,
+
+    /// Current background color as an ANSI color number, or none for the default color.
+    ansi_bg_color: Option<u8>,
+
+    /// Current foreground color.  Used for graphical rendering.
+    fg_color: RGB,
+
+    /// Current background color.  Used to clear the console.
+    bg_color: RGB,
+
+    /// Contents of the console right before entering the "alternate" console.
+    alt_backup: Option<AltScreen>,
+
+    /// Top-left and bottom-right corners of the area to which graphical output is constrained, if
+    /// any.
+    clip: Option<(PixelsXY, PixelsXY)>,
+
+    /// Page that receives all output.  Its contents always live in `pixels`.
+    draw_page: u8,
+
+    /// Contents of all pages other than `draw_page`, which is stale.  Off-screen pages are created
+    /// on first use.
+    pages: Vec<Option<Vec<RGB>>>,
+
+    /// Whether video syncing is enabled or not.
+    sync_enabled: bool,
+
+    /// Directory in which to save the frames produced by explicit video syncs, if any.
+    frames_dir: Option<PathBuf>,
+
+    /// Number of frames saved so far.
+    frames: usize,
+}
+
+impl HeadlessConsole {
+    /// Creates a new headless console of `size_pixels` that saves the frames produced by explicit
+    /// video syncs into `frames_dir`, if any.
+    pub fn new(size_pixels: SizeInPixels, frames_dir: Option<PathBuf>) -> io::Result<Self> {
+        if size_pixels.width < GLYPH_SIZE.width || size_pixels.height < GLYPH_SIZE.height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Resolution must be at least {}x{}", GLYPH_SIZE.width, GLYPH_SIZE.height),
+            ));
+        }
+
+        let bg_color = ansi_color_to_rgb(DEFAULT_BG_COLOR);
+        Ok(Self {
+            buffer: VecDeque::default(),
+            size_pixels,
+            pixels: vec![
+                bg_color;
+                usize::from(size_pixels.width) * usize::from(size_pixels.height)
+            ],
+            ansi_fg_color: None,
+            ansi_bg_color: None,
+            fg_color: ansi_color_to_rgb(DEFAULT_FG_COLOR),
+            bg_color,
+            alt_backup: None,
+            clip: None,
+            draw_page: 0,
+            pages: (0..NUM_PAGES).map(|_| None).collect(),
+            sync_enabled: true,
+            frames_dir,
+            frames: 0,
+        })
+    }
+
+    /// Flushes stdout, which has already been written to via `lock`, if syncing is enabled.
+    fn maybe_flush(&self, mut lock: StdoutLock<'_>) -> io::Result<()> {
+        if self.sync_enabled {
+            lock.flush()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the offset of `xy` into the framebuffer, or none if `xy` lies outside of it.
+    fn offset(&self, xy: PixelsXY) -> Option<usize> {
+        if xy.x < 0
+            || xy.y < 0
+            || xy.x as u16 >= self.size_pixels.width
+            || xy.y as u16 >= self.size_pixels.height
+        {
+            return None;
+        }
+        Some(
+            usize::from(xy.y as u16) * usize::from(self.size_pixels.width)
+                + usize::from(xy.x as u16),
+        )
+    }
+
+    /// Paints the pixel at `xy` with the foreground color unless it falls outside of the console
+    /// or of the clipping area.
+    fn put_pixel(&mut self, xy: PixelsXY) -> io::Result<()> {
+        if let Some((x1y1, x2y2)) = self.clip {
+            if xy.x < x1y1.x || xy.x > x2y2.x || xy.y < x1y1.y || xy.y > x2y2.y {
+                return Ok(());
+            }
+        }
+        if let Some(offset) = self.offset(xy) {
+            self.pixels[offset] = self.fg_color;
+        }
+        Ok(())
+    }
+
+    /// Paints the horizontal segment that goes from `x1y1` to `x2y2`, both included.
+    fn put_span(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        debug_assert_eq!(x1y1.y, x2y2.y);
+        for x in x1y1.x..=x2y2.x {
+            self.put_pixel(PixelsXY::new(x, x1y1.y))?;
+        }
+        Ok(())
+    }
+
+    /// Paints the line that goes from `x1y1` to `x2y2`, both included.
+    fn put_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        raster::line(x1y1, x2y2, |xy| self.put_pixel(xy))
+    }
+
+    /// Computes the top-left and bottom-right corners, both included, of the rectangle given by
+    /// `x1y1` and `x2y2`, which excludes the right and bottom edges for consistency with the other
+    /// consoles.  Returns none if the rectangle is empty.
+    fn rect_corners(x1y1: PixelsXY, x2y2: PixelsXY) -> Option<(PixelsXY, PixelsXY)> {
+        let (x1, x2) = (x1y1.x.min(x2y2.x), x1y1.x.max(x2y2.x));
+        let (y1, y2) = (x1y1.y.min(x2y2.y), x1y1.y.max(x2y2.y));
+        if x1 == x2 || y1 == y2 {
+            None
+        } else {
+            Some((PixelsXY::new(x1, y1), PixelsXY::new(x2 - 1, y2 - 1)))
+        }
+    }
+
+    /// Returns a page filled with the background color.
+    fn new_page(&self) -> Vec<RGB> {
+        vec![self.bg_color; self.pixels.len()]
+    }
+
+    /// Returns the contents of the visible page.
+    fn visible_page(&self) -> &[RGB] {
+        if self.draw_page == 0 {
+            &self.pixels
+        } else {
+            self.pages[0]
+                .as_ref()
+                .expect("The visible page must have been saved when switching away")
+        }
+    }
+
+    /// Saves the contents of the visible page as a new frame in `frames_dir`, if any.
+    ///
+    /// Frames are written as binary PPM files named after their sequence number.
+    fn save_frame(&mut self) -> io::Result<()> {
+        let path = match self.frames_dir.as_ref() {
+            Some(dir) => dir.join(format!("frame-{:06}.ppm", self.frames)),
+            None => return Ok(()),
+        };
+
+        let mut data = format!("P6\n{} {}\n255\n", self.size_pixels.width, self.size_pixels.height)
+            .into_bytes();
+        data.reserve(self.pixels.len() * 3);
+        for (r, g, b) in self.visible_page() {
+            data.extend_from_slice(&[*r, *g, *b]);
+        }
+        File::create(path)?.write_all(&data)?;
+
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Console for HeadlessConsole {
+    fn clear(&mut self, how: ClearType) -> io::Result<()> {
+        if how == ClearType::All {
+            for pixel in self.pixels.iter_mut() {
+                *pixel = self.bg_color;
+            }
+        }
+        Ok(())
+    }
+
+    fn color(&self) -> (Option<u8>, Option<u8>) {
+        (self.ansi_fg_color, self.ansi_bg_color)
+    }
+
+    fn set_color(&mut self, fg: Option<u8>, bg: Option<u8>) -> io::Result<()> {
+        self.ansi_fg_color = fg;
+        self.fg_color = ansi_color_to_rgb(fg.unwrap_or(DEFAULT_FG_COLOR));
+        self.ansi_bg_color = bg;
+        self.bg_color = ansi_color_to_rgb(bg.unwrap_or(DEFAULT_BG_COLOR));
+        Ok(())
+    }
+
+    fn set_color_rgb(&mut self, fg: Option<RGB>, bg: Option<RGB>) -> io::Result<()> {
+        self.set_color(fg.map(rgb_to_ansi_color), bg.map(rgb_to_ansi_color))?;
+        self.fg_color = fg.unwrap_or(self.fg_color);
+        self.bg_color = bg.unwrap_or(self.bg_color);
+        Ok(())
+    }
+
+    fn enter_alt(&mut self) -> io::Result<()> {
+        if self.alt_backup.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot nest alternate screens",
+            ));
+        }
+
+        self.alt_backup = Some(AltScreen {
+            pixels: self.pixels.clone(),
+            cursor_pos: CharsXY::default(),
+            ansi_fg_color: self.ansi_fg_color,
+            ansi_bg_color: self.ansi_bg_color,
+            fg_color: self.fg_color,
+            bg_color: self.bg_color,
+        });
+        self.clear(ClearType::All)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+
+    fn leave_alt(&mut self) -> io::Result<()> {
+        if let Some(alt) = self.alt_backup.take() {
+            self.pixels = alt.pixels;
+            self.ansi_fg_color = alt.ansi_fg_color;
+            self.ansi_bg_color = alt.ansi_bg_color;
+            self.fg_color = alt.fg_color;
+            self.bg_color = alt.bg_color;
+        }
+        Ok(())
+    }
+
+    fn locate(&mut self, _pos: CharsXY) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_within_line(&mut self, _off: i16) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let text = remove_control_chars(text);
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(text.as_bytes())?;
+        stdout.write_all(b"\n")?;
+        self.maybe_flush(stdout)
+    }
+
+    async fn poll_key(&mut self) -> io::Result<Option<Key>> {
+        Ok(None)
+    }
+
+    async fn read_key(&mut self) -> io::Result<Key> {
+        read_key_from_stdin(&mut self.buffer)
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn size_chars(&self) -> io::Result<CharsXY> {
+        Ok(CharsXY::new(
+            self.size_pixels.width / GLYPH_SIZE.width,
+            self.size_pixels.height / GLYPH_SIZE.height,
+        ))
+    }
+
+    fn size_pixels(&self) -> io::Result<SizeInPixels> {
+        Ok(self.size_pixels)
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        let text = remove_control_chars(text);
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(text.as_bytes())?;
+        self.maybe_flush(stdout)
+    }
+
+    fn draw_arc(&mut self, center: PixelsXY, radius: u16, start: i32, end: i32) -> io::Result<()> {
+        raster::arc(center, radius, start, end, |xy| self.put_pixel(xy))
+    }
+
+    fn draw_circle(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
+        raster::ellipse(center, radius, radius, |xy| self.put_pixel(xy))
+    }
+
+    fn draw_circle_filled(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
+        raster::ellipse_filled(center, radius, radius, |x1y1, x2y2| self.put_span(x1y1, x2y2))
+    }
+
+    fn draw_ellipse(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        raster::ellipse(center, rx, ry, |xy| self.put_pixel(xy))
+    }
+
+    fn draw_ellipse_filled(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        raster::ellipse_filled(center, rx, ry, |x1y1, x2y2| self.put_span(x1y1, x2y2))
+    }
+
+    fn draw_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        if x1y1 == x2y2 {
+            // For consistency with the other consoles, which paint nothing in this case.
+            return Ok(());
+        }
+        self.put_line(x1y1, x2y2)
+    }
+
+    fn draw_pixel(&mut self, xy: PixelsXY) -> io::Result<()> {
+        self.put_pixel(xy)
+    }
+
+    fn draw_poly(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        raster::poly(points, |x1y1, x2y2| self.put_line(x1y1, x2y2))
+    }
+
+    fn draw_poly_filled(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        raster::poly_filled(points, |x1y1, x2y2| self.put_span(x1y1, x2y2))?;
+        raster::poly(points, |x1y1, x2y2| self.put_line(x1y1, x2y2))
+    }
+
+    fn draw_rect(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        if let Some((x1y1, x2y2)) = Self::rect_corners(x1y1, x2y2) {
+            self.put_span(x1y1, PixelsXY::new(x2y2.x, x1y1.y))?;
+            self.put_span(PixelsXY::new(x1y1.x, x2y2.y), x2y2)?;
+            self.put_line(x1y1, PixelsXY::new(x1y1.x, x2y2.y))?;
+            self.put_line(PixelsXY::new(x2y2.x, x1y1.y), x2y2)?;
+        }
+        Ok(())
+    }
+
+    fn draw_rect_filled(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        if let Some((x1y1, x2y2)) = Self::rect_corners(x1y1, x2y2) {
+            for y in x1y1.y..=x2y2.y {
+                self.put_span(PixelsXY::new(x1y1.x, y), PixelsXY::new(x2y2.x, y))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_text(&mut self, _xy: PixelsXY, _text: &str, _size: Option<u16>) -> io::Result<()> {
+        // Text is never rendered into the framebuffer.
+        Ok(())
+    }
+
+    fn flood_fill(&mut self, xy: PixelsXY, border: Option<RGB>) -> io::Result<()> {
+        let target = match border {
+            Some(border) => border,
+            None => match self.offset(xy) {
+                Some(offset) => self.pixels[offset],
+                None => return Ok(()),
+            },
+        };
+        if border.is_none() && target == self.fg_color {
+            // Filling the area with the color it already has would be a no-op.
+            return Ok(());
+        }
+
+        let snapshot = self.pixels.clone();
+        let width = usize::from(self.size_pixels.width);
+        let color_at = |xy: PixelsXY| snapshot[usize::from(xy.y as u16) * width + xy.x as usize];
+        raster::flood_fill(
+            self.size_pixels,
+            xy,
+            |xy| match border {
+                Some(_) => color_at(xy) != target,
+                None => color_at(xy) == target,
+            },
+            |x1y1, x2y2| self.put_span(x1y1, x2y2),
+        )
+    }
+
+    fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+        Ok(self.offset(xy).map(|offset| self.pixels[offset]))
+    }
+
+    fn set_clip(&mut self, area: Option<(PixelsXY, PixelsXY)>) -> io::Result<()> {
+        self.clip = area;
+        Ok(())
+    }
+
+    fn set_draw_page(&mut self, page: u8) -> io::Result<()> {
+        if page >= NUM_PAGES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page out of range"));
+        }
+        if page == self.draw_page {
+            return Ok(());
+        }
+
+        let new = match self.pages[usize::from(page)].take() {
+            Some(new) => new,
+            None => self.new_page(),
+        };
+        let old = mem::replace(&mut self.pixels, new);
+        self.pages[usize::from(self.draw_page)] = Some(old);
+        self.draw_page = page;
+        Ok(())
+    }
+
+    fn flip_page(&mut self, page: u8) -> io::Result<()> {
+        if page >= NUM_PAGES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page out of range"));
+        }
+        if page == 0 {
+            return Ok(());
+        }
+
+        let src = if page == self.draw_page {
+            self.pixels.clone()
+        } else {
+            match self.pages[usize::from(page)].as_ref() {
+                Some(src) => src.clone(),
+                None => self.new_page(),
+            }
+        };
+        if self.draw_page == 0 {
+            self.pixels = src;
+        } else {
+            self.pages[0] = Some(src);
+        }
+        Ok(())
+    }
+
+    fn set_title(&mut self, _title: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_fullscreen(&mut self, _enabled: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn sync_now(&mut self) -> io::Result<()> {
+        io::stdout().flush()?;
+        self.save_frame()
+    }
+
+    fn set_sync(&mut self, enabled: bool) -> io::Result<bool> {
+        if !self.sync_enabled {
+            io::stdout().flush()?;
+        }
+        let previous = self.sync_enabled;
+        self.sync_enabled = enabled;
+        Ok(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Creates a headless console of a fixed size that does not save frames.
+    fn new_console() -> HeadlessConsole {
+        HeadlessConsole::new(SizeInPixels { width: 32, height: 32 }, None).unwrap()
+    }
+
+    /// Returns the coordinates of all pixels in `console` that have the `rgb` color.
+    fn pixels_with(console: &HeadlessConsole, rgb: RGB) -> Vec<(i16, i16)> {
+        let mut pixels = vec![];
+        for y in 0..console.size_pixels.height as i16 {
+            for x in 0..console.size_pixels.width as i16 {
+                if console.read_pixel(PixelsXY::new(x, y)).unwrap() == Some(rgb) {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_headless_new_too_small() {
+        for size in &[SizeInPixels { width: 7, height: 16 }, SizeInPixels { width: 8, height: 15 }]
+        {
+            let e = HeadlessConsole::new(*size, None).err().unwrap();
+            assert_eq!(io::ErrorKind::InvalidInput, e.kind());
+            assert_eq!("Resolution must be at least 8x16", format!("{}", e));
+        }
+    }
+
+    #[test]
+    fn test_headless_sizes() {
+        let console = HeadlessConsole::new(SizeInPixels { width: 100, height: 50 }, None).unwrap();
+        assert_eq!(SizeInPixels { width: 100, height: 50 }, console.size_pixels().unwrap());
+        assert_eq!(CharsXY::new(12, 3), console.size_chars().unwrap());
+    }
+
+    #[test]
+    fn test_headless_draw_and_read_pixels() {
+        let mut console = new_console();
+        let white = ansi_color_to_rgb(DEFAULT_FG_COLOR);
+        assert_eq!(
+            Some(ansi_color_to_rgb(DEFAULT_BG_COLOR)),
+            console.read_pixel(PixelsXY::new(0, 0)).unwrap()
+        );
+        assert_eq!(None, console.read_pixel(PixelsXY::new(32, 0)).unwrap());
+        assert_eq!(None, console.read_pixel(PixelsXY::new(0, -1)).unwrap());
+
+        console.draw_pixel(PixelsXY::new(3, 4)).unwrap();
+        console.draw_pixel(PixelsXY::new(-3, 4)).unwrap();
+        console.set_color_rgb(Some((1, 2, 3)), None).unwrap();
+        console.draw_line(PixelsXY::new(0, 10), PixelsXY::new(2, 10)).unwrap();
+        assert_eq!(vec![(3, 4)], pixels_with(&console, white));
+        assert_eq!(vec![(0, 10), (1, 10), (2, 10)], pixels_with(&console, (1, 2, 3)));
+    }
+
+    #[test]
+    fn test_headless_rects() {
+        let mut console = new_console();
+        let white = ansi_color_to_rgb(DEFAULT_FG_COLOR);
+
+        console.draw_rect_filled(PixelsXY::new(4, 3), PixelsXY::new(1, 1)).unwrap();
+        assert_eq!(
+            vec![(1, 1), (2, 1), (3, 1), (1, 2), (2, 2), (3, 2)],
+            pixels_with(&console, white)
+        );
+
+        console.clear(ClearType::All).unwrap();
+        console.draw_rect(PixelsXY::new(1, 1), PixelsXY::new(4, 4)).unwrap();
+        assert_eq!(8, pixels_with(&console, white).len());
+        assert_eq!(Some(white), console.read_pixel(PixelsXY::new(3, 3)).unwrap());
+        assert_ne!(Some(white), console.read_pixel(PixelsXY::new(2, 2)).unwrap());
+    }
+
+    #[test]
+    fn test_headless_clip() {
+        let mut console = new_console();
+        let white = ansi_color_to_rgb(DEFAULT_FG_COLOR);
+
+        console.set_clip(Some((PixelsXY::new(2, 2), PixelsXY::new(3, 3)))).unwrap();
+        console.draw_rect_filled(PixelsXY::new(0, 0), PixelsXY::new(10, 10)).unwrap();
+        assert_eq!(vec![(2, 2), (3, 2), (2, 3), (3, 3)], pixels_with(&console, white));
+
+        console.set_clip(None).unwrap();
+        console.draw_pixel(PixelsXY::new(0, 0)).unwrap();
+        assert_eq!(5, pixels_with(&console, white).len());
+    }
+
+    #[test]
+    fn test_headless_flood_fill() {
+        let mut console = new_console();
+        console.draw_rect(PixelsXY::new(0, 0), PixelsXY::new(4, 4)).unwrap();
+        console.set_color(Some(AnsiColor::Red as u8), None).unwrap();
+        console.flood_fill(PixelsXY::new(1, 1), None).unwrap();
+        assert_eq!(
+            vec![(1, 1), (2, 1), (1, 2), (2, 2)],
+            pixels_with(&console, ansi_color_to_rgb(AnsiColor::Red as u8))
+        );
+    }
+
+    #[test]
+    fn test_headless_pages() {
+        let mut console = new_console();
+        let white = ansi_color_to_rgb(DEFAULT_FG_COLOR);
+
+        console.set_draw_page(1).unwrap();
+        console.draw_pixel(PixelsXY::new(1, 1)).unwrap();
+        assert_eq!(vec![(1, 1)], pixels_with(&console, white));
+        console.set_draw_page(0).unwrap();
+        assert!(pixels_with(&console, white).is_empty());
+
+        console.flip_page(1).unwrap();
+        assert_eq!(vec![(1, 1)], pixels_with(&console, white));
+
+        assert_eq!(
+            io::ErrorKind::InvalidInput,
+            console.set_draw_page(NUM_PAGES).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn test_headless_save_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut console = HeadlessConsole::new(
+            SizeInPixels { width: 8, height: 16 },
+            Some(dir.path().to_owned()),
+        )
+        .unwrap();
+
+        console.sync_now().unwrap();
+        console.set_color_rgb(Some((10, 20, 30)), None).unwrap();
+        console.draw_pixel(PixelsXY::new(1, 0)).unwrap();
+        console.set_draw_page(1).unwrap();
+        console.draw_pixel(PixelsXY::new(0, 0)).unwrap();
+        console.sync_now().unwrap();
+
+        let header = b"P6\n8 16\n255\n";
+        let frame0 = fs::read(dir.path().join("frame-000000.ppm")).unwrap();
+        assert_eq!(header.len() + 8 * 16 * 3, frame0.len());
+        assert_eq!(header, &frame0[0..header.len()]);
+        assert_eq!(&[0, 0, 0, 0, 0, 0], &frame0[header.len()..header.len() + 6]);
+
+        // The second frame only shows the visible page, not the one being drawn.
+        let frame1 = fs::read(dir.path().join("frame-000001.ppm")).unwrap();
+        assert_eq!(&[0, 0, 0, 10, 20, 30], &frame1[header.len()..header.len() + 6]);
+
+        assert!(!dir.path().join("frame-000002.ppm").exists());
+    }
+}
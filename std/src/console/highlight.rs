@@ -0,0 +1,129 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Syntax highlighting of EndBASIC code on the console.
+
+use crate::console::{AnsiColor, Console};
+use endbasic_core::highlight::{highlight_line, Highlight};
+use std::io;
+
+/// Returns the color to use to render code fragments of the `highlight` class.
+fn highlight_color(highlight: Highlight) -> u8 {
+    match highlight {
+        Highlight::Comment => AnsiColor::Cyan as u8,
+        Highlight::Keyword => AnsiColor::BrightYellow as u8,
+        Highlight::Label => AnsiColor::BrightCyan as u8,
+        Highlight::Number => AnsiColor::BrightMagenta as u8,
+        Highlight::Text => AnsiColor::BrightGreen as u8,
+    }
+}
+
+/// Computes the foreground color of every character in `line`, which is a line of EndBASIC code.
+///
+/// Characters that do not need highlighting are returned as `None`, in which case the caller
+/// should render them with its regular text color.
+pub fn highlight_colors(line: &str) -> Vec<Option<u8>> {
+    let mut colors = vec![None; line.chars().count()];
+    for span in highlight_line(line) {
+        let color = highlight_color(span.highlight);
+        for entry in colors.iter_mut().skip(span.start).take(span.len) {
+            *entry = Some(color);
+        }
+    }
+    colors
+}
+
+/// Writes `text` to the `console` using the per-character `colors` as returned by
+/// `highlight_colors`, falling back to the `default` colors for characters without highlighting.
+///
+/// This only changes the console colors when necessary, so writing text that needs no highlighting
+/// is equivalent to a plain `write`.  The console is left with the `default` colors upon return.
+pub fn write_highlighted(
+    console: &mut dyn Console,
+    text: &str,
+    colors: &[Option<u8>],
+    default: (Option<u8>, Option<u8>),
+) -> io::Result<()> {
+    debug_assert_eq!(text.chars().count(), colors.len());
+
+    let mut current = None;
+    let mut run = String::new();
+    for (ch, color) in text.chars().zip(colors) {
+        if *color != current {
+            if !run.is_empty() {
+                console.write(&run)?;
+                run.clear();
+            }
+            console.set_color(color.or(default.0), default.1)?;
+            current = *color;
+        }
+        run.push(ch);
+    }
+    if !run.is_empty() {
+        console.write(&run)?;
+    }
+    if current.is_some() {
+        console.set_color(default.0, default.1)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    #[test]
+    fn test_highlight_colors() {
+        assert!(highlight_colors("").is_empty());
+        assert_eq!(vec![None, None, None], highlight_colors("a b"));
+
+        let k = Some(AnsiColor::BrightYellow as u8);
+        let n = Some(AnsiColor::BrightMagenta as u8);
+        let c = Some(AnsiColor::Cyan as u8);
+        assert_eq!(vec![k, k, None, n, None, None, None, c, c], highlight_colors("IF 3 a 'x"));
+    }
+
+    #[test]
+    fn test_write_highlighted_plain() {
+        let mut console = MockConsole::default();
+        write_highlighted(&mut console, "a b", &[None, None, None], (Some(1), Some(2))).unwrap();
+        assert_eq!(&[CapturedOut::Write("a b".to_owned())], console.captured_out());
+    }
+
+    #[test]
+    fn test_write_highlighted_mixed() {
+        let mut console = MockConsole::default();
+        let text = "IF a THEN 'x";
+        let colors = highlight_colors(text);
+        write_highlighted(&mut console, text, &colors, (Some(7), None)).unwrap();
+        assert_eq!(
+            &[
+                CapturedOut::SetColor(Some(AnsiColor::BrightYellow as u8), None),
+                CapturedOut::Write("IF".to_owned()),
+                CapturedOut::SetColor(Some(7), None),
+                CapturedOut::Write(" a ".to_owned()),
+                CapturedOut::SetColor(Some(AnsiColor::BrightYellow as u8), None),
+                CapturedOut::Write("THEN".to_owned()),
+                CapturedOut::SetColor(Some(7), None),
+                CapturedOut::Write(" ".to_owned()),
+                CapturedOut::SetColor(Some(AnsiColor::Cyan as u8), None),
+                CapturedOut::Write("'x".to_owned()),
+                CapturedOut::SetColor(Some(7), None),
+            ],
+            console.captured_out()
+        );
+    }
+}
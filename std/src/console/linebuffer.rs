@@ -19,6 +19,21 @@
 use std::fmt::Display;
 use std::iter;
 use std::str::Chars;
+use unicode_width::UnicodeWidthChar;
+
+/// Returns the number of console columns that `ch` occupies when displayed.
+///
+/// Wide characters, such as CJK ideographs and most emoji, occupy two columns.  Zero-width
+/// characters, such as combining marks, occupy none and are rendered on top of the preceding
+/// character.
+pub fn char_width(ch: char) -> usize {
+    ch.width().unwrap_or(0)
+}
+
+/// Returns the number of console columns that `s` occupies when displayed.
+pub fn str_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
 
 #[derive(Default, Debug)]
 /// Abstraction over a string to handle manipulation operations at char boundaries.
@@ -26,8 +41,9 @@ use std::str::Chars;
 /// This exists because Rust strings are indexed by bytes and manipulating string bytes is
 /// complicated.
 ///
-/// TODO(zenria): The current implementation of the buffer is using `String::chars`.  Should be
-/// converted to using graphemes instead.
+/// Positions are expressed in chars.  Zero-width chars are considered to be part of the char that
+/// precedes them, which approximates grapheme clusters for cursor movement purposes: see
+/// `prev_boundary` and `next_boundary`.
 pub struct LineBuffer {
     line: String,
 }
@@ -43,6 +59,37 @@ impl LineBuffer {
         self.line.chars().count()
     }
 
+    /// Returns the number of console columns that this buffer occupies when displayed.
+    pub fn width(&self) -> usize {
+        str_width(&self.line)
+    }
+
+    /// Returns the position of the start of the character that precedes `pos`, skipping over any
+    /// zero-width characters attached to it.
+    pub fn prev_boundary(&self, pos: usize) -> usize {
+        let chars: Vec<char> = self.chars().take(pos).collect();
+        let mut pos = chars.len();
+        while pos > 0 {
+            pos -= 1;
+            if char_width(chars[pos]) > 0 {
+                break;
+            }
+        }
+        pos
+    }
+
+    /// Returns the position that follows the character at `pos`, skipping over any zero-width
+    /// characters attached to it.
+    pub fn next_boundary(&self, pos: usize) -> usize {
+        let mut chars = self.chars().skip(pos);
+        let mut next = pos;
+        if chars.next().is_some() {
+            next += 1;
+            next += chars.take_while(|ch| char_width(*ch) == 0).count();
+        }
+        next
+    }
+
     /// Gets and iterator over buffer chars.
     pub fn chars(&self) -> Chars {
         self.line.chars()
@@ -132,6 +179,25 @@ impl LineBuffer {
         self.line = self.line.chars().take(at).collect();
         ret
     }
+
+    /// Splits the buffer in two parts so that the first part occupies at most `width` columns when
+    /// displayed.  Zero-width characters are kept with the character that precedes them.
+    ///
+    /// Returns the remaining part of the buffer.
+    pub fn split_off_width(&mut self, width: usize) -> LineBuffer {
+        let mut used = 0;
+        let mut at = 0;
+        if width > 0 {
+            for ch in self.line.chars() {
+                used += char_width(ch);
+                if used > width {
+                    break;
+                }
+                at += 1;
+            }
+        }
+        self.split_off(at)
+    }
 }
 
 impl From<&str> for LineBuffer {
@@ -159,7 +225,7 @@ impl Display for LineBuffer {
 
 #[cfg(test)]
 mod tests {
-    use super::LineBuffer;
+    use super::*;
 
     #[test]
     fn test_end() {
@@ -252,4 +318,56 @@ mod tests {
         assert_eq!(buffer.into_inner(), "Hello, ");
         assert_eq!(world.into_inner(), "World");
     }
+
+    #[test]
+    fn test_width() {
+        assert_eq!(0, str_width(""));
+        assert_eq!(5, str_width("Hello"));
+        assert_eq!(4, str_width("日本"));
+        assert_eq!(1, str_width("e\u{301}"));
+        assert_eq!(2, char_width('🙂'));
+
+        assert_eq!(0, LineBuffer::default().width());
+        assert_eq!(7, LineBuffer::from("a日本e\u{301}x").width());
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let buffer = LineBuffer::from("ae\u{301}\u{302}日b");
+        assert_eq!(0, buffer.prev_boundary(0));
+        assert_eq!(0, buffer.prev_boundary(1));
+        assert_eq!(1, buffer.prev_boundary(4));
+        assert_eq!(4, buffer.prev_boundary(5));
+        assert_eq!(5, buffer.prev_boundary(100));
+
+        assert_eq!(1, buffer.next_boundary(0));
+        assert_eq!(4, buffer.next_boundary(1));
+        assert_eq!(5, buffer.next_boundary(4));
+        assert_eq!(6, buffer.next_boundary(5));
+        assert_eq!(6, buffer.next_boundary(6));
+        assert_eq!(100, buffer.next_boundary(100));
+    }
+
+    #[test]
+    fn test_split_off_width() {
+        let mut buffer = LineBuffer::from("ab日本c");
+        let rest = buffer.split_off_width(3);
+        assert_eq!(buffer.into_inner(), "ab");
+        assert_eq!(rest.into_inner(), "日本c");
+
+        let mut buffer = LineBuffer::from("ab日本c");
+        let rest = buffer.split_off_width(4);
+        assert_eq!(buffer.into_inner(), "ab日");
+        assert_eq!(rest.into_inner(), "本c");
+
+        let mut buffer = LineBuffer::from("ae\u{301}b");
+        let rest = buffer.split_off_width(2);
+        assert_eq!(buffer.into_inner(), "ae\u{301}");
+        assert_eq!(rest.into_inner(), "b");
+
+        let mut buffer = LineBuffer::from("日");
+        let rest = buffer.split_off_width(1);
+        assert_eq!(buffer.into_inner(), "");
+        assert_eq!(rest.into_inner(), "日");
+    }
 }
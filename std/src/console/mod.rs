@@ -20,23 +20,36 @@ use endbasic_core::exec::Clearable;
 use endbasic_core::syms::Symbols;
 use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::convert::TryFrom;
 use std::env;
 use std::io;
 use std::rc::Rc;
 use std::str;
+use std::thread;
+use std::time::{Duration, Instant};
 
 mod cmds;
-pub(crate) use cmds::add_all;
+pub(crate) use cmds::{add_all, parse_color};
 mod colors;
-pub use colors::{ansi_color_to_rgb, AnsiColor, RGB};
+pub use colors::{ansi_color_to_rgb, rgb_to_ansi_color, AnsiColor, ColorNumber, RGB};
 mod format;
 pub use format::refill_and_print;
+mod graphics;
+pub use graphics::{GraphicsConsole, InputOps, RasterInfo, RasterOps};
+mod headless;
+pub use headless::HeadlessConsole;
+mod highlight;
+pub use highlight::{highlight_colors, write_highlighted};
+mod pager;
+pub use pager::Pager;
 mod readline;
-pub use readline::{read_line, read_line_secure};
+pub use readline::{read_code_line, read_line, read_line_secure, read_line_timeout};
 mod trivial;
 pub use trivial::TrivialConsole;
 mod linebuffer;
-pub use linebuffer::LineBuffer;
+pub use linebuffer::{char_width, str_width, LineBuffer};
+mod windows;
+pub use windows::WindowedConsole;
 
 /// Decoded key presses as returned by the console.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -62,6 +75,10 @@ pub enum Key {
     /// A printable character.
     Char(char),
 
+    /// A key combination involving the Control key and a letter that has no other meaning (e.g.
+    /// `Ctrl-G`).  The letter is always in lowercase.
+    Control(char),
+
     /// The end key or `Ctrl-E`.
     End,
 
@@ -96,6 +113,57 @@ pub enum Key {
     Unknown(String),
 }
 
+/// State of the modifier keys that can be held down along with other keys.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KeyModifiers {
+    /// Whether any of the Shift keys is held down.
+    pub shift: bool,
+
+    /// Whether any of the Control keys is held down.
+    pub ctrl: bool,
+
+    /// Whether any of the Alt keys is held down.
+    pub alt: bool,
+}
+
+/// Style attributes that alter how text is rendered, in addition to its colors.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TextAttributes {
+    /// Whether text is rendered in bold or with increased intensity.
+    pub bold: bool,
+
+    /// Whether text is underlined.
+    pub underline: bool,
+
+    /// Whether the foreground and background colors of text are swapped.
+    pub reverse: bool,
+
+    /// Whether text blinks.
+    pub blink: bool,
+}
+
+/// State of a pixel-based console saved when entering the alternate screen and restored when
+/// leaving it.
+pub(crate) struct AltScreen {
+    /// Contents of the console, one pixel at a time.
+    pub(crate) pixels: Vec<RGB>,
+
+    /// Position of the cursor, for consoles that track it.
+    pub(crate) cursor_pos: CharsXY,
+
+    /// Foreground color as an ANSI color number, or none for the default color.
+    pub(crate) ansi_fg_color: Option<u8>,
+
+    /// Background color as an ANSI color number, or none for the default color.
+    pub(crate) ansi_bg_color: Option<u8>,
+
+    /// Foreground color.
+    pub(crate) fg_color: RGB,
+
+    /// Background color.
+    pub(crate) bg_color: RGB,
+}
+
 /// Indicates what part of the console to clear on a `Console::clear()` call.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ClearType {
@@ -150,7 +218,7 @@ impl PixelsXY {
 }
 
 /// Represents a rectangular size in pixels.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct SizeInPixels {
     /// The width in pixels.
     pub width: u16,
@@ -159,6 +227,12 @@ pub struct SizeInPixels {
     pub height: u16,
 }
 
+/// Number of drawing pages supported by graphical consoles, including the visible one.
+pub const NUM_PAGES: u8 = 4;
+
+/// Delay between key polls when waiting for a key press with a timeout.
+const POLL_KEY_DELAY_MS: u64 = 10;
+
 /// Hooks to implement the commands that manipulate the console.
 #[async_trait(?Send)]
 pub trait Console {
@@ -173,6 +247,45 @@ pub trait Console {
     /// If any of the colors is `None`, the color is left unchanged.
     fn set_color(&mut self, fg: Option<u8>, bg: Option<u8>) -> io::Result<()>;
 
+    /// Sets the console's foreground and background colors to the arbitrary RGB colors `fg` and
+    /// `bg`, with `None` having the same meaning as in `set_color`.
+    ///
+    /// Consoles that cannot render arbitrary colors use the closest ANSI colors instead, which is
+    /// what the default implementation does.  `color` may then only return an approximation of
+    /// the colors set via this method.
+    fn set_color_rgb(&mut self, fg: Option<RGB>, bg: Option<RGB>) -> io::Result<()> {
+        self.set_color(fg.map(rgb_to_ansi_color), bg.map(rgb_to_ansi_color))
+    }
+
+    /// Returns the text attributes currently in effect.
+    fn attributes(&self) -> TextAttributes {
+        TextAttributes::default()
+    }
+
+    /// Sets the text attributes to use for subsequent text output.
+    ///
+    /// The default implementation only accepts resetting the attributes, which is what consoles
+    /// that cannot render any attribute need.  Consoles that can render some attributes but not
+    /// others should ignore the latter.
+    fn set_attributes(&mut self, attributes: TextAttributes) -> io::Result<()> {
+        if attributes == TextAttributes::default() {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::Other, "Cannot set text attributes on this console"))
+        }
+    }
+
+    /// Returns the current position of the cursor.
+    ///
+    /// The returned column may be equal to the width of the console when the last output filled
+    /// the line exactly, as the cursor only wraps when more text follows.
+    fn cursor_pos(&self) -> io::Result<CharsXY> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Cannot query the cursor position of this console",
+        ))
+    }
+
     /// Enters the alternate console.
     // TODO(jmmv): This API leads to misuse as callers can forget to leave the alternate console.
     fn enter_alt(&mut self) -> io::Result<()>;
@@ -185,6 +298,26 @@ pub trait Console {
     /// reading a line echoes back user input, for example.
     fn is_interactive(&self) -> bool;
 
+    /// Returns true if the console renders colors.  Decorative coloring, such as syntax
+    /// highlighting, is skipped on consoles that do not.
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    /// Returns true if `_key` is currently held down.
+    ///
+    /// Unlike `poll_key` and `read_key`, this does not consume any key presses.  Character keys
+    /// are identified by their lowercase form regardless of the state of the modifiers, so the
+    /// Shift and Control versions of a key are all reported as `Key::Char` of the plain key.
+    fn is_key_down(&mut self, _key: &Key) -> io::Result<bool> {
+        Err(io::Error::new(io::ErrorKind::Other, "No keyboard state support in this console"))
+    }
+
+    /// Returns the modifier keys that are currently held down.
+    fn key_modifiers(&mut self) -> io::Result<KeyModifiers> {
+        Err(io::Error::new(io::ErrorKind::Other, "No keyboard state support in this console"))
+    }
+
     /// Leaves the alternate console.
     fn leave_alt(&mut self) -> io::Result<()>;
 
@@ -207,6 +340,41 @@ pub trait Console {
     /// Waits for and returns the next key press.
     async fn read_key(&mut self) -> io::Result<Key>;
 
+    /// Waits for the next key press for at most `timeout` and returns it, or returns none if no key
+    /// was pressed in time.
+    ///
+    /// The default implementation polls for key presses and blocks the current thread in between,
+    /// so consoles that can wait for keys asynchronously should override it.
+    async fn read_key_timeout(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(key) = self.poll_key().await? {
+                return Ok(Some(key));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            thread::sleep((deadline - now).min(Duration::from_millis(POLL_KEY_DELAY_MS)));
+        }
+    }
+
+    /// Restores the contents of the console, the cursor position and the colors saved by the most
+    /// recent call to `save_screen` that has not been restored yet.
+    fn restore_screen(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Cannot restore the screen of this console"))
+    }
+
+    /// Saves the contents of the console, the cursor position and the colors so that they can be
+    /// brought back later with `restore_screen`.
+    ///
+    /// Saved screens form a stack so calls to this function can be nested.  The console is left
+    /// untouched so that new output can be drawn on top of the saved contents, except in consoles
+    /// that cannot read their contents back, which may have to clear the screen instead.
+    fn save_screen(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Cannot save the screen of this console"))
+    }
+
     /// Shows the cursor.
     fn show_cursor(&mut self) -> io::Result<()>;
 
@@ -224,6 +392,19 @@ pub trait Console {
     ///
     fn write(&mut self, text: &str) -> io::Result<()>;
 
+    /// Draws the outline of the arc of a circle at `_center` with `_radius` that goes
+    /// counterclockwise from the `_start` angle to the `_end` angle, both expressed in degrees, using
+    /// the current drawing color.
+    fn draw_arc(
+        &mut self,
+        _center: PixelsXY,
+        _radius: u16,
+        _start: i32,
+        _end: i32,
+    ) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
     /// Draws the outline of a circle at `_center` with `_radius` using the current drawing color.
     fn draw_circle(&mut self, _center: PixelsXY, _radius: u16) -> io::Result<()> {
         Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
@@ -234,6 +415,18 @@ pub trait Console {
         Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
     }
 
+    /// Draws the outline of an ellipse at `_center` with radii `_rx` and `_ry` using the current
+    /// drawing color.
+    fn draw_ellipse(&mut self, _center: PixelsXY, _rx: u16, _ry: u16) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
+    /// Draws a filled ellipse at `_center` with radii `_rx` and `_ry` using the current drawing
+    /// color.
+    fn draw_ellipse_filled(&mut self, _center: PixelsXY, _rx: u16, _ry: u16) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
     /// Draws a line from `_x1y1` to `_x2y2` using the current drawing color.
     fn draw_line(&mut self, _x1y1: PixelsXY, _x2y2: PixelsXY) -> io::Result<()> {
         Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
@@ -244,6 +437,17 @@ pub trait Console {
         Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
     }
 
+    /// Draws the outline of the closed polygon with vertices `_points` using the current drawing
+    /// color.
+    fn draw_poly(&mut self, _points: &[PixelsXY]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
+    /// Draws the filled closed polygon with vertices `_points` using the current drawing color.
+    fn draw_poly_filled(&mut self, _points: &[PixelsXY]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
     /// Draws the outline of a rectangle from `_x1y1` to `_x2y2` using the current drawing color.
     fn draw_rect(&mut self, _x1y1: PixelsXY, _x2y2: PixelsXY) -> io::Result<()> {
         Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
@@ -254,6 +458,84 @@ pub trait Console {
         Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
     }
 
+    /// Renders `_text` with its top-left corner at `_xy` using the current foreground color.
+    ///
+    /// The text is drawn over the existing contents of the console, independently of the
+    /// character grid, and does not move the cursor.  If `_size` is none, the text is rendered
+    /// at the size of the console font; otherwise, `_size` is the size of the font in points.
+    fn draw_text(&mut self, _xy: PixelsXY, _text: &str, _size: Option<u16>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
+    /// Fills the region that contains `_xy` using the current drawing color.
+    ///
+    /// If `_border` is none, the region is made of the pixels connected to `_xy` that have the same
+    /// color as `_xy`.  Otherwise, the region extends from `_xy` until it hits pixels of the
+    /// `_border` color.
+    fn flood_fill(&mut self, _xy: PixelsXY, _border: Option<RGB>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
+    /// Returns the color of the pixel at `_xy`, or none if `_xy` lies outside of the console.
+    fn read_pixel(&self, _xy: PixelsXY) -> io::Result<Option<RGB>> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
+    /// Constrains all further graphical output to the rectangle described by `_area`, or removes
+    /// the constraint if `_area` is none.
+    ///
+    /// `_area` contains the top-left and bottom-right corners of the rectangle, both included.
+    ///
+    /// Text output is not affected by this.
+    fn set_clip(&mut self, _area: Option<(PixelsXY, PixelsXY)>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
+    /// Selects the page that receives all further output.
+    ///
+    /// Page 0 is the visible page and pages 1 up to `NUM_PAGES - 1` are off-screen buffers of the
+    /// same size as the visible page, which are created blank on first use.  The contents of an
+    /// off-screen page only become visible once copied via `flip_page`.
+    fn set_draw_page(&mut self, _page: u8) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
+    /// Copies the contents of `_page` onto the visible page.
+    fn flip_page(&mut self, _page: u8) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
+    /// Changes the font used to render text to `_face` at `_size` points.
+    ///
+    /// If `_face` is none, keeps the current font face and only changes its size.  If `_face` is
+    /// empty, selects the default font of the console.  The size of the console in characters may
+    /// change as a result of this, so callers should query it again.
+    fn set_font(&mut self, _face: Option<&str>, _size: u16) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Cannot change the font of this console"))
+    }
+
+    /// Sets the title of the window that hosts the console to `_title`.
+    fn set_title(&mut self, _title: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Cannot set the title of this console"))
+    }
+
+    /// Returns the text held in the clipboard of the system that hosts the console.
+    async fn clipboard_get(&mut self) -> io::Result<String> {
+        Err(io::Error::new(io::ErrorKind::Other, "Cannot access the clipboard of this console"))
+    }
+
+    /// Replaces the contents of the clipboard of the system that hosts the console with `_text`.
+    async fn clipboard_set(&mut self, _text: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Cannot access the clipboard of this console"))
+    }
+
+    /// Enables or disables full screen mode for the console.
+    ///
+    /// The size of the console may change as a result of this, so callers should query it again.
+    fn set_fullscreen(&mut self, _enabled: bool) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "No graphics support in this console"))
+    }
+
     /// Causes any buffered output to be synced.
     ///
     /// This is a no-op when video syncing is enabled because output is never buffered in that case.
@@ -289,11 +571,48 @@ impl Clearable for ConsoleClearable {
         let mut console = self.console.borrow_mut();
         let _ = console.leave_alt();
         let _ = console.set_color(None, None);
+        if console.attributes() != TextAttributes::default() {
+            let _ = console.set_attributes(TextAttributes::default());
+        }
+        let _ = console.set_draw_page(0);
+        let _ = console.set_clip(None);
         let _ = console.show_cursor();
         let _ = console.set_sync(true);
     }
 }
 
+/// Computes where the cursor ends up after writing `text` at `pos` in a console of `size`,
+/// wrapping long lines and scrolling at the bottom of the console.  Wide characters that do not
+/// fit at the end of a line are moved to the next one.  If `newline` is true, the
+/// cursor is then moved to the beginning of the next line, as `Console::print` does.
+///
+/// `text` is expected to have had its control characters removed already.  This is useful for
+/// consoles that need to track the cursor position on their own.
+pub fn advance_cursor(pos: CharsXY, size: CharsXY, text: &str, newline: bool) -> CharsXY {
+    fn open_line(pos: &mut CharsXY, size: CharsXY) {
+        pos.x = 0;
+        if pos.y < size.y.saturating_sub(1) {
+            pos.y += 1;
+        }
+    }
+
+    let mut pos = pos;
+    for ch in text.chars() {
+        let width = u16::try_from(char_width(ch)).expect("Character widths are small");
+        if width == 0 {
+            continue;
+        }
+        if pos.x > 0 && usize::from(pos.x) + usize::from(width) > usize::from(size.x) {
+            open_line(&mut pos, size);
+        }
+        pos.x = pos.x.saturating_add(width);
+    }
+    if newline {
+        open_line(&mut pos, size);
+    }
+    pos
+}
+
 /// Checks if a given string has control characters.
 pub fn has_control_chars(s: &str) -> bool {
     for ch in s.chars() {
@@ -336,7 +655,7 @@ pub fn get_env_var_as_u16(name: &str) -> Option<u16> {
 }
 
 /// Converts a line of text into a collection of keys.
-fn line_to_keys(s: String) -> VecDeque<Key> {
+pub fn line_to_keys(s: String) -> VecDeque<Key> {
     let mut keys = VecDeque::default();
     for ch in s.chars() {
         if ch == '\x1b' {
@@ -357,6 +676,30 @@ fn line_to_keys(s: String) -> VecDeque<Key> {
     keys
 }
 
+/// Returns the name of `key` as used by `ON KEY`, or none if the key has no name.
+///
+/// Consoles that decode key presses into `Key`s use this to deliver `Signal::Key`s to the machine.
+pub fn key_name(key: &Key) -> Option<String> {
+    let name = match key {
+        Key::ArrowDown => "DOWN",
+        Key::ArrowLeft => "LEFT",
+        Key::ArrowRight => "RIGHT",
+        Key::ArrowUp => "UP",
+        Key::Backspace => "BS",
+        Key::CarriageReturn | Key::NewLine => "ENTER",
+        Key::Char(' ') => "SPACE",
+        Key::Char(ch) => return Some(ch.to_uppercase().to_string()),
+        Key::End => "END",
+        Key::Escape => "ESC",
+        Key::Home => "HOME",
+        Key::PageDown => "PGDOWN",
+        Key::PageUp => "PGUP",
+        Key::Tab => "TAB",
+        Key::Control(_) | Key::Eof | Key::Interrupt | Key::Unknown(_) => return None,
+    };
+    Some(name.to_owned())
+}
+
 /// Reads a single key from stdin when not attached to a TTY.  Because characters are not
 /// visible to us until a newline is received, this reads complete lines and buffers them in
 /// memory inside the given `buffer`.
@@ -378,6 +721,39 @@ pub fn read_key_from_stdin(buffer: &mut VecDeque<Key>) -> io::Result<Key> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_advance_cursor() {
+        let size = CharsXY::new(10, 3);
+        let xy = CharsXY::new;
+
+        assert_eq!(xy(2, 1), advance_cursor(xy(2, 1), size, "", false));
+        assert_eq!(xy(0, 2), advance_cursor(xy(2, 1), size, "", true));
+        assert_eq!(xy(5, 1), advance_cursor(xy(2, 1), size, "abc", false));
+        assert_eq!(xy(10, 0), advance_cursor(xy(0, 0), size, "0123456789", false));
+        assert_eq!(xy(0, 1), advance_cursor(xy(0, 0), size, "0123456789", true));
+        assert_eq!(xy(2, 1), advance_cursor(xy(8, 0), size, "abcd", false));
+        assert_eq!(xy(1, 2), advance_cursor(xy(0, 0), size, "012345678901234567890", false));
+        assert_eq!(xy(3, 2), advance_cursor(xy(0, 2), size, "0123456789012", false));
+        assert_eq!(xy(0, 2), advance_cursor(xy(5, 2), size, "x", true));
+
+        assert_eq!(xy(6, 1), advance_cursor(xy(2, 1), size, "日本", false));
+        assert_eq!(xy(3, 1), advance_cursor(xy(2, 1), size, "e\u{301}", false));
+        assert_eq!(xy(10, 0), advance_cursor(xy(6, 0), size, "日本", false));
+        assert_eq!(xy(2, 1), advance_cursor(xy(9, 0), size, "日", false));
+        assert_eq!(xy(2, 0), advance_cursor(xy(0, 0), CharsXY::new(1, 3), "日", false));
+    }
+
+    #[test]
+    fn test_key_name() {
+        assert_eq!(Some("UP".to_owned()), key_name(&Key::ArrowUp));
+        assert_eq!(Some("ENTER".to_owned()), key_name(&Key::NewLine));
+        assert_eq!(Some("ENTER".to_owned()), key_name(&Key::CarriageReturn));
+        assert_eq!(Some("SPACE".to_owned()), key_name(&Key::Char(' ')));
+        assert_eq!(Some("A".to_owned()), key_name(&Key::Char('a')));
+        assert_eq!(None, key_name(&Key::Interrupt));
+        assert_eq!(None, key_name(&Key::Control('g')));
+    }
+
     #[test]
     fn test_has_control_chars() {
         assert!(!has_control_chars(""));
@@ -0,0 +1,247 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Paginated output to the console.
+
+use crate::console::{highlight_colors, str_width, write_highlighted, ClearType, Console, Key};
+use std::io;
+
+/// Message to display when the console is full and there is more output to display.
+const MORE_PROMPT: &str = "-- More --";
+
+/// Prints lines to a console and pauses after every screenful of output until a key is pressed.
+///
+/// Pausing only happens when the console is interactive.  Otherwise, all output is printed right
+/// away.
+pub struct Pager<'a> {
+    /// The console to print to.
+    console: &'a mut dyn Console,
+
+    /// Number of rows to print before pausing, or `None` if output should never pause.
+    page_rows: Option<usize>,
+
+    /// Width of the console, used to compute how many rows long lines occupy.
+    width: usize,
+
+    /// Number of rows printed since the last pause.
+    printed_rows: usize,
+
+    /// Whether to apply syntax highlighting to code.  Only done on interactive consoles that
+    /// support colors so that, e.g., redirected output does not contain escape sequences.
+    highlight: bool,
+}
+
+impl<'a> Pager<'a> {
+    /// Creates a new pager that prints to `console`.
+    pub fn new(console: &'a mut dyn Console) -> io::Result<Self> {
+        let size = console.size_chars()?;
+        let page_rows = if console.is_interactive() && size.y > 1 {
+            // Leave one row for the prompt.
+            Some(usize::from(size.y) - 1)
+        } else {
+            None
+        };
+        let highlight = console.is_interactive() && console.supports_color();
+        Ok(Self {
+            console,
+            page_rows,
+            width: usize::from(size.x).max(1),
+            printed_rows: 0,
+            highlight,
+        })
+    }
+
+    /// Waits for confirmation to continue if printing `rows` more rows would overflow the screen.
+    ///
+    /// Returns false if the user asked to stop printing.
+    async fn make_room(&mut self, rows: usize) -> io::Result<bool> {
+        if let Some(page_rows) = self.page_rows {
+            if self.printed_rows > 0 && self.printed_rows + rows > page_rows {
+                self.console.write(MORE_PROMPT)?;
+                let key = self.console.read_key().await?;
+                self.console.clear(ClearType::CurrentLine)?;
+                self.console.move_within_line(-(MORE_PROMPT.len() as i16))?;
+                match key {
+                    Key::Char('q') | Key::Char('Q') | Key::Eof | Key::Escape | Key::Interrupt => {
+                        return Ok(false)
+                    }
+                    _ => self.printed_rows = 0,
+                }
+            }
+        }
+        self.printed_rows += rows;
+        Ok(true)
+    }
+
+    /// Returns the number of rows that printing `line` occupies on the console.
+    fn rows_for(&self, line: &str) -> usize {
+        str_width(line).div_ceil(self.width).max(1)
+    }
+
+    /// Prints `line`, pausing beforehand if the screen is full.
+    ///
+    /// Returns false if the user asked to stop printing, in which case `line` was not printed.
+    pub async fn print(&mut self, line: &str) -> io::Result<bool> {
+        if !self.make_room(self.rows_for(line)).await? {
+            return Ok(false);
+        }
+        self.console.print(line)?;
+        Ok(true)
+    }
+
+    /// Prints `line`, which contains EndBASIC code, with syntax highlighting if the console is
+    /// interactive and supports colors, pausing beforehand if the screen is full.
+    ///
+    /// Returns false if the user asked to stop printing, in which case `line` was not printed.
+    pub async fn print_code(&mut self, line: &str) -> io::Result<bool> {
+        if !self.make_room(self.rows_for(line)).await? {
+            return Ok(false);
+        }
+        let colors = if self.highlight { highlight_colors(line) } else { vec![] };
+        if colors.iter().all(Option::is_none) {
+            self.console.print(line)?;
+        } else {
+            let default = self.console.color();
+            write_highlighted(self.console, line, &colors, default)?;
+            self.console.print("")?;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::{AnsiColor, CharsXY};
+    use crate::testutils::*;
+    use futures_lite::future::block_on;
+
+    #[test]
+    fn test_pager_not_interactive() {
+        let mut console = MockConsole::default();
+        console.set_size_chars(CharsXY::new(10, 2));
+        let mut pager = Pager::new(&mut console).unwrap();
+        for line in &["a", "b", "c"] {
+            assert!(block_on(pager.print(line)).unwrap());
+        }
+        assert_eq!(
+            &[
+                CapturedOut::Print("a".to_owned()),
+                CapturedOut::Print("b".to_owned()),
+                CapturedOut::Print("c".to_owned()),
+            ],
+            console.captured_out()
+        );
+    }
+
+    #[test]
+    fn test_pager_pauses_and_continues() {
+        let mut console = MockConsole::default();
+        console.set_interactive(true);
+        console.set_size_chars(CharsXY::new(4, 3));
+        console.add_input_keys(&[Key::Char(' ')]);
+        let mut pager = Pager::new(&mut console).unwrap();
+        assert!(block_on(pager.print("a")).unwrap());
+        assert!(block_on(pager.print("")).unwrap());
+        assert!(block_on(pager.print("long")).unwrap());
+        assert!(block_on(pager.print("b")).unwrap());
+        assert_eq!(
+            &[
+                CapturedOut::Print("a".to_owned()),
+                CapturedOut::Print("".to_owned()),
+                CapturedOut::Write("-- More --".to_owned()),
+                CapturedOut::Clear(ClearType::CurrentLine),
+                CapturedOut::MoveWithinLine(-10),
+                CapturedOut::Print("long".to_owned()),
+                CapturedOut::Print("b".to_owned()),
+            ],
+            console.captured_out()
+        );
+    }
+
+    #[test]
+    fn test_pager_wraps_long_lines() {
+        let mut console = MockConsole::default();
+        console.set_interactive(true);
+        console.set_size_chars(CharsXY::new(4, 3));
+        console.add_input_keys(&[Key::NewLine]);
+        let mut pager = Pager::new(&mut console).unwrap();
+        assert!(block_on(pager.print("12345")).unwrap());
+        assert!(block_on(pager.print("b")).unwrap());
+        assert_eq!(
+            &[
+                CapturedOut::Print("12345".to_owned()),
+                CapturedOut::Write("-- More --".to_owned()),
+                CapturedOut::Clear(ClearType::CurrentLine),
+                CapturedOut::MoveWithinLine(-10),
+                CapturedOut::Print("b".to_owned()),
+            ],
+            console.captured_out()
+        );
+    }
+
+    #[test]
+    fn test_pager_stop() {
+        for key in [Key::Char('q'), Key::Escape, Key::Interrupt] {
+            let mut console = MockConsole::default();
+            console.set_interactive(true);
+            console.set_size_chars(CharsXY::new(10, 2));
+            console.add_input_keys(&[key]);
+            let mut pager = Pager::new(&mut console).unwrap();
+            assert!(block_on(pager.print("a")).unwrap());
+            assert!(!block_on(pager.print("b")).unwrap());
+            assert_eq!(
+                &[
+                    CapturedOut::Print("a".to_owned()),
+                    CapturedOut::Write("-- More --".to_owned()),
+                    CapturedOut::Clear(ClearType::CurrentLine),
+                    CapturedOut::MoveWithinLine(-10),
+                ],
+                console.captured_out()
+            );
+        }
+    }
+
+    #[test]
+    fn test_pager_print_code() {
+        let mut console = MockConsole::default();
+        console.set_interactive(true);
+        let mut pager = Pager::new(&mut console).unwrap();
+        assert!(block_on(pager.print_code("a = b")).unwrap());
+        assert!(block_on(pager.print_code("END")).unwrap());
+        assert_eq!(
+            &[
+                CapturedOut::Print("a = b".to_owned()),
+                CapturedOut::SetColor(Some(AnsiColor::BrightYellow as u8), None),
+                CapturedOut::Write("END".to_owned()),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Print("".to_owned()),
+            ],
+            console.captured_out()
+        );
+    }
+
+    #[test]
+    fn test_pager_print_code_not_interactive() {
+        let mut console = MockConsole::default();
+        let mut pager = Pager::new(&mut console).unwrap();
+        assert!(block_on(pager.print_code("a = b")).unwrap());
+        assert!(block_on(pager.print_code("END")).unwrap());
+        assert_eq!(
+            &[CapturedOut::Print("a = b".to_owned()), CapturedOut::Print("END".to_owned())],
+            console.captured_out()
+        );
+    }
+}
@@ -15,53 +15,110 @@
 
 //! Interactive line reader.
 
-use crate::console::{Console, Key, LineBuffer};
+use crate::console::{highlight_colors, str_width, write_highlighted, Console, Key, LineBuffer};
 use std::io;
+use std::time::Duration;
 
 /// Character to print when typing a secure string.
 const SECURE_CHAR: &str = "*";
 
+/// How to display the characters typed by the user.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum EchoMode {
+    /// Displays every character as `SECURE_CHAR`.
+    Secure,
+
+    /// Displays the characters as they are typed.
+    Plain,
+
+    /// Displays the characters as they are typed, applying syntax highlighting to them as if they
+    /// were EndBASIC code.  The contained value holds the console colors to restore after
+    /// highlighting a fragment.
+    Code((Option<u8>, Option<u8>)),
+}
+
+/// Writes `text` to the `console` according to the echo `mode`.
+fn write_echoed(console: &mut dyn Console, text: &str, mode: EchoMode) -> io::Result<()> {
+    match mode {
+        EchoMode::Secure => console.write(&SECURE_CHAR.repeat(text.chars().count())),
+        EchoMode::Plain => console.write(text),
+        EchoMode::Code(default) => {
+            write_highlighted(console, text, &highlight_colors(text), default)
+        }
+    }
+}
+
+/// Returns the number of console columns that `text` occupies once echoed according to `mode`.
+fn echo_width(text: &str, mode: EchoMode) -> usize {
+    match mode {
+        EchoMode::Secure => text.chars().count(),
+        EchoMode::Plain | EchoMode::Code(_) => str_width(text),
+    }
+}
+
 /// Refreshes the current input line to display `line` assuming that the cursor is currently
-/// offset by `pos` characters from the beginning of the input and that the previous line was
-/// `clear_len` characters long.
+/// offset by `pos_width` columns from the beginning of the input and that the previous line was
+/// `clear_width` columns wide.
 fn update_line(
     console: &mut dyn Console,
-    pos: usize,
-    clear_len: usize,
+    pos_width: usize,
+    clear_width: usize,
     line: &LineBuffer,
+    mode: EchoMode,
 ) -> io::Result<()> {
     console.hide_cursor()?;
-    if pos > 0 {
-        console.move_within_line(-(pos as i16))?;
+    if pos_width > 0 {
+        console.move_within_line(-(pos_width as i16))?;
     }
     if !line.is_empty() {
-        console.write(&line.to_string())?;
+        write_echoed(console, &line.to_string(), mode)?;
     }
-    let line_len = line.len();
-    if line_len < clear_len {
-        let diff = clear_len - line_len;
+    let line_width = echo_width(&line.to_string(), mode);
+    if line_width < clear_width {
+        let diff = clear_width - line_width;
         console.write(&" ".repeat(diff))?;
         console.move_within_line(-(diff as i16))?;
     }
     console.show_cursor()
 }
 
+/// Waits for the next key press from the `console`.  If `timeout` is not `None`, this gives up with
+/// a `TimedOut` error when no key is pressed within that time.
+async fn next_key(console: &mut dyn Console, timeout: Option<Duration>) -> io::Result<Key> {
+    match timeout {
+        Some(timeout) => match console.read_key_timeout(timeout).await? {
+            Some(key) => Ok(key),
+            None => Err(io::Error::new(io::ErrorKind::TimedOut, "Timed out waiting for input")),
+        },
+        None => console.read_key().await,
+    }
+}
+
 /// Reads a line of text interactively from the console, using the given `prompt` and pre-filling
 /// the input with `previous`.  If `history` is not `None`, then this appends the newly entered line
-/// into the history and allows navigating through it.
+/// into the history and allows navigating through it.  If `timeout` is not `None`, this gives up
+/// when the user does not press any key within that time.
 async fn read_line_interactive(
     console: &mut dyn Console,
     prompt: &str,
     previous: &str,
     mut history: Option<&mut Vec<String>>,
-    echo: bool,
+    mode: EchoMode,
+    timeout: Option<Duration>,
 ) -> io::Result<String> {
     let mut line = LineBuffer::from(previous);
     if !prompt.is_empty() || !line.is_empty() {
-        if echo {
-            console.write(&format!("{}{}", prompt, line))?;
-        } else {
-            console.write(&format!("{}{}", prompt, "*".repeat(line.len())))?;
+        match mode {
+            EchoMode::Secure => console.write(&format!("{}{}", prompt, "*".repeat(line.len())))?,
+            EchoMode::Plain => console.write(&format!("{}{}", prompt, line))?,
+            EchoMode::Code(_) => {
+                if !prompt.is_empty() {
+                    console.write(prompt)?;
+                }
+                if !line.is_empty() {
+                    write_echoed(console, &line.to_string(), mode)?;
+                }
+            }
         }
         console.sync_now()?;
     }
@@ -70,11 +127,11 @@ async fn read_line_interactive(
         // Assumes that the prompt was printed at column 0.  If that was not the case, line length
         // calculation does not work.
         let console_size = console.size_chars()?;
-        usize::from(console_size.x) - prompt.len()
+        usize::from(console_size.x) - str_width(prompt)
     };
 
-    // Insertion position *within* the line, without accounting for the prompt.
-    // TODO(zenria): Handle UTF-8 graphemes.
+    // Insertion position *within* the line, in characters and without accounting for the prompt.
+    // The cursor offsets we send to the console are derived from this in columns.
     let mut pos = line.len();
 
     let mut history_pos = match history.as_mut() {
@@ -86,20 +143,32 @@ async fn read_line_interactive(
     };
 
     loop {
-        match console.read_key().await? {
+        let key = match next_key(console, timeout).await {
+            Ok(key) => key,
+            Err(e) => {
+                if e.kind() == io::ErrorKind::TimedOut {
+                    // Leave the abandoned input behind and move to a new line like a regular
+                    // end of input would.
+                    console.print("")?;
+                }
+                return Err(e);
+            }
+        };
+        match key {
             Key::ArrowUp => {
                 if let Some(history) = history.as_mut() {
                     if history_pos == 0 {
                         continue;
                     }
 
-                    let clear_len = line.len();
+                    let pos_width = echo_width(&line.start(pos), mode);
+                    let clear_width = echo_width(&line.to_string(), mode);
 
                     history[history_pos] = line.into_inner();
                     history_pos -= 1;
                     line = LineBuffer::from(&history[history_pos]);
 
-                    update_line(console, pos, clear_len, &line)?;
+                    update_line(console, pos_width, clear_width, &line, mode)?;
 
                     pos = line.len();
                 }
@@ -111,13 +180,14 @@ async fn read_line_interactive(
                         continue;
                     }
 
-                    let clear_len = line.len();
+                    let pos_width = echo_width(&line.start(pos), mode);
+                    let clear_width = echo_width(&line.to_string(), mode);
 
                     history[history_pos] = line.to_string();
                     history_pos += 1;
                     line = LineBuffer::from(&history[history_pos]);
 
-                    update_line(console, pos, clear_len, &line)?;
+                    update_line(console, pos_width, clear_width, &line, mode)?;
 
                     pos = line.len();
                 }
@@ -125,32 +195,68 @@ async fn read_line_interactive(
 
             Key::ArrowLeft => {
                 if pos > 0 {
-                    console.move_within_line(-1)?;
-                    pos -= 1;
+                    let prev = line.prev_boundary(pos);
+                    let offset = echo_width(&line.range(prev, pos), mode);
+                    if offset > 0 {
+                        console.move_within_line(-(offset as i16))?;
+                    }
+                    pos = prev;
                 }
             }
 
-            Key::ArrowRight => {
+            // Keep the Emacs-style binding for Ctrl-F, which consoles report on its own so that
+            // other consumers like the editor can give it a different meaning.
+            Key::ArrowRight | Key::Control('f') => {
                 if pos < line.len() {
-                    console.move_within_line(1)?;
-                    pos += 1;
+                    let next = line.next_boundary(pos);
+                    let offset = echo_width(&line.range(pos, next), mode);
+                    if offset > 0 {
+                        console.move_within_line(offset as i16)?;
+                    }
+                    pos = next;
                 }
             }
 
             Key::Backspace => {
-                if pos > 0 {
+                // Delete the whole character cluster before the cursor so that combining marks
+                // go away together with their base character.
+                let prev = line.prev_boundary(pos);
+                if pos > 0 && matches!(mode, EchoMode::Code(_)) {
+                    // Removing a character can change the highlighting of the whole line, so
+                    // redraw it and move the cursor back to the new insertion point.
+                    let pos_width = echo_width(&line.start(pos), mode);
+                    let clear_width = echo_width(&line.to_string(), mode);
+                    for _ in prev..pos {
+                        line.remove(prev);
+                    }
+                    update_line(console, pos_width, clear_width, &line, mode)?;
+                    pos = prev;
+                    let tail_width = echo_width(&line.end(pos), mode);
+                    if tail_width > 0 {
+                        console.move_within_line(-(tail_width as i16))?;
+                    }
+                } else if pos > 0 {
+                    let deleted_width = echo_width(&line.range(prev, pos), mode);
+                    let tail = line.end(pos);
+                    let tail_width = echo_width(&tail, mode);
                     console.hide_cursor()?;
-                    console.move_within_line(-1)?;
-                    if echo {
-                        console.write(&line.end(pos))?;
+                    if deleted_width > 0 {
+                        console.move_within_line(-(deleted_width as i16))?;
+                    }
+                    if mode != EchoMode::Secure {
+                        console.write(&tail)?;
                     } else {
-                        console.write(&SECURE_CHAR.repeat(line.len() - pos))?;
+                        console.write(&SECURE_CHAR.repeat(tail_width))?;
+                    }
+                    console.write(&" ".repeat(deleted_width))?;
+                    if tail_width + deleted_width > 0 {
+                        console.move_within_line(-((tail_width + deleted_width) as i16))?;
                     }
-                    console.write(" ")?;
-                    console.move_within_line(-((line.len() - pos) as i16 + 1))?;
                     console.show_cursor()?;
-                    line.remove(pos - 1);
-                    pos -= 1;
+                    for _ in prev..pos {
+                        line.remove(prev);
+                    }
+                    pos = prev;
                 }
             }
 
@@ -167,27 +273,60 @@ async fn read_line_interactive(
 
             Key::Char(ch) => {
                 let line_len = line.len();
-                debug_assert!(line_len < width);
-                if line_len == width - 1 {
+                let line_width = echo_width(&line.to_string(), mode);
+                let ch_width = echo_width(ch.encode_utf8(&mut [0u8; 4]), mode);
+                debug_assert!(line_width < width);
+                if line_width + ch_width > width - 1 {
                     // TODO(jmmv): Implement support for lines that exceed the width of the input
                     // field (the width of the screen).
                     continue;
                 }
 
-                if pos < line_len {
+                if let EchoMode::Code(default) = mode {
+                    let before = highlight_colors(&line.to_string());
+                    line.insert(pos, ch);
+                    let after = highlight_colors(&line.to_string());
+                    if pos == line_len && after[..line_len] == before[..] {
+                        let mut buf = [0u8; 4];
+                        write_highlighted(
+                            console,
+                            ch.encode_utf8(&mut buf),
+                            &after[line_len..],
+                            default,
+                        )?;
+                    } else {
+                        // The new character changes the highlighting of the line, so redraw it
+                        // and move the cursor back to the new insertion point.
+                        update_line(
+                            console,
+                            echo_width(&line.start(pos), mode),
+                            line_width,
+                            &line,
+                            mode,
+                        )?;
+                        let tail_width = echo_width(&line.end(pos + 1), mode);
+                        if tail_width > 0 {
+                            console.move_within_line(-(tail_width as i16))?;
+                        }
+                    }
+                } else if pos < line_len {
+                    let tail = line.end(pos);
+                    let tail_width = echo_width(&tail, mode);
                     console.hide_cursor()?;
-                    if echo {
+                    if mode != EchoMode::Secure {
                         let mut buf = [0u8; 4];
                         console.write(ch.encode_utf8(&mut buf))?;
-                        console.write(&line.end(pos))?;
+                        console.write(&tail)?;
                     } else {
-                        console.write(&SECURE_CHAR.repeat(line_len - pos + 1))?;
+                        console.write(&SECURE_CHAR.repeat(tail_width + 1))?;
+                    }
+                    if tail_width > 0 {
+                        console.move_within_line(-(tail_width as i16))?;
                     }
-                    console.move_within_line(-((line_len - pos) as i16))?;
                     console.show_cursor()?;
                     line.insert(pos, ch);
                 } else {
-                    if echo {
+                    if mode != EchoMode::Secure {
                         let mut buf = [0u8; 4];
                         console.write(ch.encode_utf8(&mut buf))?;
                     } else {
@@ -199,11 +338,11 @@ async fn read_line_interactive(
             }
 
             Key::End => {
-                let offset = line.len() - pos;
+                let offset = echo_width(&line.end(pos), mode);
                 if offset > 0 {
                     console.move_within_line(offset as i16)?;
-                    pos += offset;
                 }
+                pos = line.len();
             }
 
             Key::Eof => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "EOF")),
@@ -213,10 +352,11 @@ async fn read_line_interactive(
             }
 
             Key::Home => {
-                if pos > 0 {
-                    console.move_within_line(-(pos as i16))?;
-                    pos = 0;
+                let offset = echo_width(&line.start(pos), mode);
+                if offset > 0 {
+                    console.move_within_line(-(offset as i16))?;
                 }
+                pos = 0;
             }
 
             Key::Interrupt => return Err(io::Error::new(io::ErrorKind::Interrupted, "Ctrl+C")),
@@ -235,7 +375,7 @@ async fn read_line_interactive(
             }
 
             // TODO(jmmv): Should do something smarter with unknown keys.
-            Key::Unknown(_) => (),
+            Key::Control(_) | Key::Unknown(_) => (),
         }
     }
 
@@ -250,11 +390,12 @@ async fn read_line_interactive(
     Ok(line.into_inner())
 }
 
-/// Reads a line of text interactively from the console, which is not expected to be a TTY.
-async fn read_line_raw(console: &mut dyn Console) -> io::Result<String> {
+/// Reads a line of text interactively from the console, which is not expected to be a TTY.  If
+/// `timeout` is not `None`, this gives up when no key arrives within that time.
+async fn read_line_raw(console: &mut dyn Console, timeout: Option<Duration>) -> io::Result<String> {
     let mut line = String::new();
     loop {
-        match console.read_key().await? {
+        match next_key(console, timeout).await? {
             Key::ArrowUp | Key::ArrowDown | Key::ArrowLeft | Key::ArrowRight => (),
             Key::Backspace => {
                 if !line.is_empty() {
@@ -271,6 +412,7 @@ async fn read_line_raw(console: &mut dyn Console) -> io::Result<String> {
                 }
             }
             Key::Char(ch) => line.push(ch),
+            Key::Control(_) => (),
             Key::End | Key::Home => (),
             Key::Escape => (),
             Key::Eof => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "EOF")),
@@ -293,9 +435,40 @@ pub async fn read_line(
     history: Option<&mut Vec<String>>,
 ) -> io::Result<String> {
     if console.is_interactive() {
-        read_line_interactive(console, prompt, previous, history, true).await
+        read_line_interactive(console, prompt, previous, history, EchoMode::Plain, None).await
+    } else {
+        read_line_raw(console, None).await
+    }
+}
+
+/// Reads a line from the console like `read_line` does, but gives up with a `TimedOut` error if the
+/// user does not press any key for `timeout`.
+pub async fn read_line_timeout(
+    console: &mut dyn Console,
+    prompt: &str,
+    previous: &str,
+    timeout: Duration,
+) -> io::Result<String> {
+    if console.is_interactive() {
+        read_line_interactive(console, prompt, previous, None, EchoMode::Plain, Some(timeout)).await
+    } else {
+        read_line_raw(console, Some(timeout)).await
+    }
+}
+
+/// Reads a line of EndBASIC code from the console.  This behaves like `read_line` but applies
+/// syntax highlighting to the input when the console is interactive.
+pub async fn read_code_line(
+    console: &mut dyn Console,
+    prompt: &str,
+    previous: &str,
+    history: Option<&mut Vec<String>>,
+) -> io::Result<String> {
+    if console.is_interactive() {
+        let mode = EchoMode::Code(console.color());
+        read_line_interactive(console, prompt, previous, history, mode, None).await
     } else {
-        read_line_raw(console).await
+        read_line_raw(console, None).await
     }
 }
 
@@ -310,13 +483,13 @@ pub async fn read_line_secure(console: &mut dyn Console, prompt: &str) -> io::Re
             "Cannot read secure strings from a raw console".to_owned(),
         ));
     }
-    read_line_interactive(console, prompt, "", None, false).await
+    read_line_interactive(console, prompt, "", None, EchoMode::Secure, None).await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::console::CharsXY;
+    use crate::console::{AnsiColor, CharsXY};
     use crate::testutils::*;
     use futures_lite::future::block_on;
 
@@ -327,7 +500,7 @@ mod tests {
         prompt: &'static str,
         previous: &'static str,
         history: Option<Vec<String>>,
-        echo: bool,
+        mode: EchoMode,
         exp_line: &'static str,
         exp_output: Vec<CapturedOut>,
         exp_history: Option<Vec<String>>,
@@ -342,7 +515,7 @@ mod tests {
                 prompt: "",
                 previous: "",
                 history: None,
-                echo: true,
+                mode: EchoMode::Plain,
                 exp_line: "",
                 exp_output: vec![],
                 exp_history: None,
@@ -412,7 +585,14 @@ mod tests {
 
         /// Sets whether read_line echoes characters or not.
         fn set_echo(mut self, echo: bool) -> Self {
-            self.echo = echo;
+            self.mode = if echo { EchoMode::Plain } else { EchoMode::Secure };
+            self
+        }
+
+        /// Enables syntax highlighting of the input, restoring the `default` colors after every
+        /// highlighted fragment.
+        fn set_code(mut self, default: (Option<u8>, Option<u8>)) -> Self {
+            self.mode = EchoMode::Code(default);
             self
         }
 
@@ -431,7 +611,8 @@ mod tests {
                     self.prompt,
                     self.previous,
                     Some(history),
-                    self.echo,
+                    self.mode,
+                    None,
                 ))
                 .unwrap(),
                 None => block_on(read_line_interactive(
@@ -439,7 +620,8 @@ mod tests {
                     self.prompt,
                     self.previous,
                     None,
-                    self.echo,
+                    self.mode,
+                    None,
                 ))
                 .unwrap(),
             };
@@ -613,6 +795,59 @@ mod tests {
             .accept();
     }
 
+    #[test]
+    fn test_read_line_interactive_utf8_navigate_wide_chars() {
+        ReadLineInteractiveTest::default()
+            .add_key_chars("語a")
+            .add_output(CapturedOut::Write("語".to_string()))
+            .add_output(CapturedOut::Write("a".to_string()))
+            // -
+            .add_key(Key::ArrowLeft)
+            .add_output(CapturedOut::MoveWithinLine(-1))
+            // -
+            .add_key(Key::ArrowLeft)
+            .add_output(CapturedOut::MoveWithinLine(-2))
+            // -
+            .add_key(Key::ArrowRight)
+            .add_output(CapturedOut::MoveWithinLine(2))
+            // -
+            .add_key(Key::Backspace)
+            .add_output(CapturedOut::HideCursor)
+            .add_output(CapturedOut::MoveWithinLine(-2))
+            .add_output(CapturedOut::Write("a".to_string()))
+            .add_output(CapturedOut::Write("  ".to_string()))
+            .add_output(CapturedOut::MoveWithinLine(-3))
+            .add_output(CapturedOut::ShowCursor)
+            // -
+            .set_line("a")
+            .accept();
+    }
+
+    #[test]
+    fn test_read_line_interactive_utf8_remove_combining_sequence() {
+        ReadLineInteractiveTest::default()
+            .add_key_chars("e\u{301}")
+            .add_output(CapturedOut::Write("e".to_string()))
+            .add_output(CapturedOut::Write("\u{301}".to_string()))
+            // -
+            .add_key(Key::ArrowLeft)
+            .add_output(CapturedOut::MoveWithinLine(-1))
+            // -
+            .add_key(Key::End)
+            .add_output(CapturedOut::MoveWithinLine(1))
+            // -
+            .add_key(Key::Backspace)
+            .add_output(CapturedOut::HideCursor)
+            .add_output(CapturedOut::MoveWithinLine(-1))
+            .add_output_bytes("")
+            .add_output_bytes(" ")
+            .add_output(CapturedOut::MoveWithinLine(-1))
+            .add_output(CapturedOut::ShowCursor)
+            // -
+            .set_line("")
+            .accept();
+    }
+
     #[test]
     fn test_read_line_interactive_trailing_backspace() {
         ReadLineInteractiveTest::default()
@@ -686,11 +921,11 @@ mod tests {
             .add_key(Key::ArrowRight)
             .add_output(CapturedOut::MoveWithinLine(1))
             // -
-            .add_key(Key::ArrowRight)
+            .add_key(Key::Control('f'))
             .add_output(CapturedOut::MoveWithinLine(1))
             // -
             .add_key(Key::ArrowRight)
-            .add_key(Key::ArrowRight)
+            .add_key(Key::Control('g'))
             // -
             .add_key_chars("3")
             .add_output_bytes("3")
@@ -1043,6 +1278,51 @@ mod tests {
             .accept();
     }
 
+    #[test]
+    fn test_read_line_with_highlighting() {
+        let keyword = Some(AnsiColor::BrightYellow as u8);
+        ReadLineInteractiveTest::default()
+            .set_code((Some(7), None))
+            .set_prompt("> ")
+            .set_previous("a")
+            .add_output(CapturedOut::Write("> ".to_string()))
+            .add_output(CapturedOut::Write("a".to_string()))
+            .add_output(CapturedOut::SyncNow)
+            // -
+            .add_key_chars(" I")
+            .add_output_bytes(" I")
+            // -
+            .add_key(Key::Char('F'))
+            .add_output(CapturedOut::HideCursor)
+            .add_output(CapturedOut::MoveWithinLine(-3))
+            .add_output(CapturedOut::Write("a ".to_string()))
+            .add_output(CapturedOut::SetColor(keyword, None))
+            .add_output(CapturedOut::Write("IF".to_string()))
+            .add_output(CapturedOut::SetColor(Some(7), None))
+            .add_output(CapturedOut::ShowCursor)
+            // -
+            .add_key(Key::Backspace)
+            .add_output(CapturedOut::HideCursor)
+            .add_output(CapturedOut::MoveWithinLine(-4))
+            .add_output(CapturedOut::Write("a I".to_string()))
+            .add_output(CapturedOut::Write(" ".to_string()))
+            .add_output(CapturedOut::MoveWithinLine(-1))
+            .add_output(CapturedOut::ShowCursor)
+            // -
+            .add_key(Key::ArrowLeft)
+            .add_output(CapturedOut::MoveWithinLine(-1))
+            // -
+            .add_key(Key::Char('x'))
+            .add_output(CapturedOut::HideCursor)
+            .add_output(CapturedOut::MoveWithinLine(-2))
+            .add_output(CapturedOut::Write("a xI".to_string()))
+            .add_output(CapturedOut::ShowCursor)
+            .add_output(CapturedOut::MoveWithinLine(-1))
+            // -
+            .set_line("a xI")
+            .accept();
+    }
+
     #[test]
     fn test_read_line_without_echo() {
         ReadLineInteractiveTest::default()
@@ -1079,6 +1359,47 @@ mod tests {
             .accept();
     }
 
+    #[test]
+    fn test_read_line_timeout_interactive() {
+        let mut console = MockConsole::default();
+        console.set_interactive(true);
+        console.add_input_keys(&[Key::Char('1'), Key::NewLine]);
+        console.set_size_chars(CharsXY::new(15, 5));
+        let line = block_on(read_line_timeout(&mut console, "> ", "", Duration::from_secs(1)));
+        assert_eq!("1", &line.unwrap());
+
+        console.add_input_keys(&[Key::Char('2')]);
+        let err = block_on(read_line_timeout(&mut console, "> ", "", Duration::from_secs(1)))
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::TimedOut, err.kind());
+        assert_eq!(
+            &[
+                CapturedOut::Write("> ".to_string()),
+                CapturedOut::SyncNow,
+                CapturedOut::Write("1".to_string()),
+                CapturedOut::Print("".to_owned()),
+                CapturedOut::Write("> ".to_string()),
+                CapturedOut::SyncNow,
+                CapturedOut::Write("2".to_string()),
+                CapturedOut::Print("".to_owned()),
+            ],
+            console.captured_out()
+        );
+    }
+
+    #[test]
+    fn test_read_line_timeout_raw() {
+        let mut console = MockConsole::default();
+        console.add_input_chars("abc\nde");
+        let line = block_on(read_line_timeout(&mut console, "> ", "", Duration::from_secs(1)));
+        assert_eq!("abc", &line.unwrap());
+
+        let err = block_on(read_line_timeout(&mut console, "> ", "", Duration::from_secs(1)))
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::TimedOut, err.kind());
+        assert!(console.captured_out().is_empty());
+    }
+
     #[test]
     fn test_read_line_secure_trivial_test() {
         let mut console = MockConsole::default();
@@ -75,6 +75,10 @@ impl Console for TrivialConsole {
         true
     }
 
+    fn supports_color(&self) -> bool {
+        false
+    }
+
     fn leave_alt(&mut self) -> io::Result<()> {
         Ok(())
     }
@@ -0,0 +1,840 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Rectangular text windows within the console.
+
+use crate::console::{
+    char_width, remove_control_chars, rgb_to_ansi_color, CharsXY, ClearType, Console, Key,
+    KeyModifiers, PixelsXY, SizeInPixels, TextAttributes, RGB,
+};
+use async_trait::async_trait;
+use endbasic_core::exec::Clearable;
+use endbasic_core::syms::Symbols;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Marker stored in the cells covered by the second half of a wide character.  Control characters
+/// are never stored in cells, so this cannot clash with actual contents.
+const WIDE_CONTINUATION: char = '\0';
+
+/// Colors of a window or of one of its cells, as set by either `set_color` or `set_color_rgb`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Colors {
+    /// Colors given as ANSI numbers, where `None` represents the default color of the console.
+    Ansi(Option<u8>, Option<u8>),
+
+    /// Colors given as RGB triplets, where `None` represents the default color of the console.
+    Rgb(Option<RGB>, Option<RGB>),
+}
+
+impl Colors {
+    /// Makes these colors the current colors of `console`.
+    fn apply(self, console: &mut dyn Console) -> io::Result<()> {
+        match self {
+            Colors::Ansi(fg, bg) => console.set_color(fg, bg),
+            Colors::Rgb(fg, bg) => console.set_color_rgb(fg, bg),
+        }
+    }
+
+    /// Returns these colors as ANSI numbers, approximating RGB colors if necessary.
+    fn to_ansi(self) -> (Option<u8>, Option<u8>) {
+        match self {
+            Colors::Ansi(fg, bg) => (fg, bg),
+            Colors::Rgb(fg, bg) => (fg.map(rgb_to_ansi_color), bg.map(rgb_to_ansi_color)),
+        }
+    }
+}
+
+/// A rectangular area of the console with its own cursor, colors and scrolling.
+struct Window {
+    /// Position of the top-left corner of the window within the console.
+    origin: CharsXY,
+
+    /// Size of the window in characters.
+    size: CharsXY,
+
+    /// Position of the cursor relative to the window.  The column can be one past the last one
+    /// after writing to the last column, in which case the next character wraps around.
+    cursor: CharsXY,
+
+    /// Colors for new output written to the window.
+    colors: Colors,
+
+    /// Contents of the window, used to redraw it when it scrolls.
+    cells: Vec<Vec<(char, Colors)>>,
+}
+
+impl Window {
+    /// Creates a new window at `origin` of `size` filled with blanks in `colors`.
+    fn new(origin: CharsXY, size: CharsXY, colors: Colors) -> Self {
+        let cells = vec![vec![(' ', colors); usize::from(size.x)]; usize::from(size.y)];
+        Self { origin, size, cursor: CharsXY::default(), colors, cells }
+    }
+
+    /// Moves the cursor of `console` to the cursor of this window.
+    fn locate_cursor(&self, console: &mut dyn Console) -> io::Result<()> {
+        let x = self.cursor.x.min(self.size.x - 1);
+        console.locate(CharsXY::new(self.origin.x + x, self.origin.y + self.cursor.y))
+    }
+
+    /// Draws row `y` of the window on `console` from its contents.
+    fn redraw_row(&self, console: &mut dyn Console, y: u16) -> io::Result<()> {
+        console.locate(CharsXY::new(self.origin.x, self.origin.y + y))?;
+        let mut run = String::new();
+        let mut run_colors = None;
+        for (ch, colors) in &self.cells[usize::from(y)] {
+            if *ch == WIDE_CONTINUATION {
+                continue;
+            }
+            if run_colors != Some(*colors) {
+                if let Some(run_colors) = run_colors {
+                    run_colors.apply(console)?;
+                    console.write(&run)?;
+                    run.clear();
+                }
+                run_colors = Some(*colors);
+            }
+            run.push(*ch);
+        }
+        if let Some(run_colors) = run_colors {
+            run_colors.apply(console)?;
+            console.write(&run)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the whole window on `console` from its contents.
+    fn redraw(&self, console: &mut dyn Console) -> io::Result<()> {
+        for y in 0..self.size.y {
+            self.redraw_row(console, y)?;
+        }
+        Ok(())
+    }
+
+    /// Blanks the columns `from` to the end of row `y` using the current colors.
+    fn blank(&mut self, y: u16, from: u16) {
+        let colors = self.colors;
+        for cell in &mut self.cells[usize::from(y)][usize::from(from)..] {
+            *cell = (' ', colors);
+        }
+    }
+
+    /// Clears the part of the window given by `how`.
+    fn clear(&mut self, console: &mut dyn Console, how: ClearType) -> io::Result<()> {
+        match how {
+            ClearType::All => {
+                for y in 0..self.size.y {
+                    self.blank(y, 0);
+                }
+                self.cursor = CharsXY::default();
+                self.redraw(console)?;
+            }
+            ClearType::CurrentLine => {
+                self.blank(self.cursor.y, 0);
+                self.cursor.x = 0;
+                self.redraw_row(console, self.cursor.y)?;
+            }
+            ClearType::PreviousChar => {
+                if self.cursor.x > 0 {
+                    self.cursor.x -= 1;
+                    let colors = self.colors;
+                    self.cells[usize::from(self.cursor.y)][usize::from(self.cursor.x)] =
+                        (' ', colors);
+                    self.redraw_row(console, self.cursor.y)?;
+                }
+            }
+            ClearType::UntilNewLine => {
+                self.blank(self.cursor.y, self.cursor.x.min(self.size.x));
+                self.redraw_row(console, self.cursor.y)?;
+            }
+        }
+        self.colors.apply(console)?;
+        self.locate_cursor(console)
+    }
+
+    /// Moves the cursor to the beginning of the next line, scrolling the window if necessary.
+    fn newline(&mut self, console: &mut dyn Console) -> io::Result<()> {
+        self.cursor.x = 0;
+        if self.cursor.y + 1 < self.size.y {
+            self.cursor.y += 1;
+        } else {
+            self.cells.remove(0);
+            self.cells.push(vec![(' ', self.colors); usize::from(self.size.x)]);
+            self.redraw(console)?;
+            self.colors.apply(console)?;
+        }
+        self.locate_cursor(console)
+    }
+
+    /// Writes `text` at the cursor position, wrapping long lines and scrolling the window as
+    /// necessary.
+    fn write(&mut self, console: &mut dyn Console, text: &str) -> io::Result<()> {
+        self.colors.apply(console)?;
+        let mut run = String::new();
+        for ch in remove_control_chars(text).chars() {
+            let width = u16::try_from(char_width(ch)).expect("Character widths are small");
+            if width == 0 {
+                // Zero-width characters are rendered on top of the previous cell, but there is no
+                // room to keep them in the cells so they are lost on redraws.
+                run.push(ch);
+                continue;
+            }
+            let width = width.min(self.size.x);
+
+            if self.cursor.x + width > self.size.x {
+                console.write(&run)?;
+                run.clear();
+                self.newline(console)?;
+            }
+            let row = &mut self.cells[usize::from(self.cursor.y)];
+            row[usize::from(self.cursor.x)] = (ch, self.colors);
+            for x in 1..width {
+                row[usize::from(self.cursor.x + x)] = (WIDE_CONTINUATION, self.colors);
+            }
+            self.cursor.x += width;
+            run.push(ch);
+        }
+        if !run.is_empty() {
+            console.write(&run)?;
+        }
+        Ok(())
+    }
+}
+
+/// A console that confines text output to rectangular windows defined by the user.
+///
+/// When no window is active, all operations are forwarded unmodified to the wrapped console.
+/// Otherwise, text operations are relative to and confined within the active window, and the
+/// size of the console is reported as the size of the window.
+pub struct WindowedConsole {
+    /// The wrapped console.
+    console: Rc<RefCell<dyn Console>>,
+
+    /// All defined windows, keyed by their number.
+    windows: HashMap<u8, Window>,
+
+    /// Number of the active window, if any.
+    active: Option<u8>,
+
+    /// Colors of the console before the active window was activated, to restore them when the
+    /// window is deactivated.
+    screen_colors: (Option<u8>, Option<u8>),
+}
+
+impl WindowedConsole {
+    /// Creates a new console that wraps `console` without any windows.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Self {
+        Self { console, windows: HashMap::default(), active: None, screen_colors: (None, None) }
+    }
+
+    /// Returns the active window, if any.
+    fn active_window(&mut self) -> Option<&mut Window> {
+        match self.active {
+            Some(n) => self.windows.get_mut(&n),
+            None => None,
+        }
+    }
+
+    /// Returns true if window `n` is defined.
+    pub fn has_window(&self, n: u8) -> bool {
+        self.windows.contains_key(&n)
+    }
+
+    /// Returns the size of the wrapped console, ignoring any active window.
+    pub fn screen_size(&self) -> io::Result<CharsXY> {
+        self.console.borrow().size_chars()
+    }
+
+    /// Defines window `n` to span from `x1y1` to `x2y2`, both inclusive, clears it and makes it
+    /// the active window.  Any previous window with the same number is replaced.
+    pub fn define(&mut self, n: u8, x1y1: CharsXY, x2y2: CharsXY) -> io::Result<()> {
+        let screen = self.screen_size()?;
+        if x1y1.x > x2y2.x || x1y1.y > x2y2.y || x2y2.x >= screen.x || x2y2.y >= screen.y {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Window out of the console"));
+        }
+
+        let colors = match self.active_window() {
+            Some(window) => window.colors,
+            None => {
+                let (fg, bg) = self.console.borrow().color();
+                Colors::Ansi(fg, bg)
+            }
+        };
+        let size = CharsXY::new(x2y2.x - x1y1.x + 1, x2y2.y - x1y1.y + 1);
+        self.windows.insert(n, Window::new(x1y1, size, colors));
+        self.select(Some(n))?;
+
+        let mut console = self.console.borrow_mut();
+        let window = self.windows.get_mut(&n).expect("Window just inserted");
+        window.clear(&mut *console, ClearType::All)
+    }
+
+    /// Makes window `n` the active window, or deactivates windows if `n` is none.
+    pub fn select(&mut self, n: Option<u8>) -> io::Result<()> {
+        if let Some(n) = n {
+            if !self.windows.contains_key(&n) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Window {} is not defined", n),
+                ));
+            }
+        }
+
+        if n.is_none() && self.active.is_none() {
+            return Ok(());
+        }
+
+        let mut console = self.console.borrow_mut();
+        if self.active.take().is_none() {
+            self.screen_colors = console.color();
+        }
+        match n {
+            Some(n) => {
+                let window = &self.windows[&n];
+                window.colors.apply(&mut *console)?;
+                window.locate_cursor(&mut *console)?;
+                self.active = Some(n);
+            }
+            None => {
+                console.set_color(self.screen_colors.0, self.screen_colors.1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forgets all windows and deactivates the active one, if any.
+    pub fn reset(&mut self) -> io::Result<()> {
+        let result = self.select(None);
+        self.windows.clear();
+        result
+    }
+}
+
+#[async_trait(?Send)]
+impl Console for WindowedConsole {
+    fn clear(&mut self, how: ClearType) -> io::Result<()> {
+        let console = self.console.clone();
+        match self.active_window() {
+            Some(window) => window.clear(&mut *console.borrow_mut(), how),
+            None => console.borrow_mut().clear(how),
+        }
+    }
+
+    fn color(&self) -> (Option<u8>, Option<u8>) {
+        match self.active {
+            Some(n) => self.windows[&n].colors.to_ansi(),
+            None => self.console.borrow().color(),
+        }
+    }
+
+    fn set_color(&mut self, fg: Option<u8>, bg: Option<u8>) -> io::Result<()> {
+        let console = self.console.clone();
+        match self.active_window() {
+            Some(window) => {
+                window.colors = Colors::Ansi(fg, bg);
+                window.colors.apply(&mut *console.borrow_mut())
+            }
+            None => console.borrow_mut().set_color(fg, bg),
+        }
+    }
+
+    fn set_color_rgb(&mut self, fg: Option<RGB>, bg: Option<RGB>) -> io::Result<()> {
+        let console = self.console.clone();
+        match self.active_window() {
+            Some(window) => {
+                window.colors = Colors::Rgb(fg, bg);
+                window.colors.apply(&mut *console.borrow_mut())
+            }
+            None => console.borrow_mut().set_color_rgb(fg, bg),
+        }
+    }
+
+    fn attributes(&self) -> TextAttributes {
+        self.console.borrow().attributes()
+    }
+
+    fn set_attributes(&mut self, attributes: TextAttributes) -> io::Result<()> {
+        self.console.borrow_mut().set_attributes(attributes)
+    }
+
+    fn cursor_pos(&self) -> io::Result<CharsXY> {
+        match self.active {
+            Some(n) => Ok(self.windows[&n].cursor),
+            None => self.console.borrow().cursor_pos(),
+        }
+    }
+
+    fn enter_alt(&mut self) -> io::Result<()> {
+        self.console.borrow_mut().enter_alt()
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.console.borrow_mut().hide_cursor()
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.console.borrow().is_interactive()
+    }
+
+    fn supports_color(&self) -> bool {
+        self.console.borrow().supports_color()
+    }
+
+    fn is_key_down(&mut self, key: &Key) -> io::Result<bool> {
+        self.console.borrow_mut().is_key_down(key)
+    }
+
+    fn key_modifiers(&mut self) -> io::Result<KeyModifiers> {
+        self.console.borrow_mut().key_modifiers()
+    }
+
+    fn leave_alt(&mut self) -> io::Result<()> {
+        self.console.borrow_mut().leave_alt()
+    }
+
+    fn locate(&mut self, pos: CharsXY) -> io::Result<()> {
+        let console = self.console.clone();
+        match self.active_window() {
+            Some(window) => {
+                debug_assert!(pos.x < window.size.x);
+                debug_assert!(pos.y < window.size.y);
+                window.cursor = pos;
+                window.locate_cursor(&mut *console.borrow_mut())
+            }
+            None => console.borrow_mut().locate(pos),
+        }
+    }
+
+    fn move_within_line(&mut self, off: i16) -> io::Result<()> {
+        let console = self.console.clone();
+        match self.active_window() {
+            Some(window) => {
+                let x = (i32::from(window.cursor.x) + i32::from(off))
+                    .clamp(0, i32::from(window.size.x) - 1);
+                window.cursor.x = x as u16;
+                window.locate_cursor(&mut *console.borrow_mut())
+            }
+            None => console.borrow_mut().move_within_line(off),
+        }
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let console = self.console.clone();
+        match self.active_window() {
+            Some(window) => {
+                let mut console = console.borrow_mut();
+                window.write(&mut *console, text)?;
+                window.newline(&mut *console)
+            }
+            None => console.borrow_mut().print(text),
+        }
+    }
+
+    async fn poll_key(&mut self) -> io::Result<Option<Key>> {
+        self.console.borrow_mut().poll_key().await
+    }
+
+    async fn read_key(&mut self) -> io::Result<Key> {
+        self.console.borrow_mut().read_key().await
+    }
+
+    async fn read_key_timeout(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        self.console.borrow_mut().read_key_timeout(timeout).await
+    }
+
+    fn restore_screen(&mut self) -> io::Result<()> {
+        self.console.borrow_mut().restore_screen()
+    }
+
+    fn save_screen(&mut self) -> io::Result<()> {
+        self.console.borrow_mut().save_screen()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.console.borrow_mut().show_cursor()
+    }
+
+    fn size_chars(&self) -> io::Result<CharsXY> {
+        match self.active {
+            Some(n) => Ok(self.windows[&n].size),
+            None => self.console.borrow().size_chars(),
+        }
+    }
+
+    fn size_pixels(&self) -> io::Result<SizeInPixels> {
+        self.console.borrow().size_pixels()
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        let console = self.console.clone();
+        match self.active_window() {
+            Some(window) => window.write(&mut *console.borrow_mut(), text),
+            None => console.borrow_mut().write(text),
+        }
+    }
+
+    fn draw_arc(&mut self, center: PixelsXY, radius: u16, start: i32, end: i32) -> io::Result<()> {
+        self.console.borrow_mut().draw_arc(center, radius, start, end)
+    }
+
+    fn draw_circle(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
+        self.console.borrow_mut().draw_circle(center, radius)
+    }
+
+    fn draw_circle_filled(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
+        self.console.borrow_mut().draw_circle_filled(center, radius)
+    }
+
+    fn draw_ellipse(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.console.borrow_mut().draw_ellipse(center, rx, ry)
+    }
+
+    fn draw_ellipse_filled(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.console.borrow_mut().draw_ellipse_filled(center, rx, ry)
+    }
+
+    fn draw_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        self.console.borrow_mut().draw_line(x1y1, x2y2)
+    }
+
+    fn draw_pixel(&mut self, xy: PixelsXY) -> io::Result<()> {
+        self.console.borrow_mut().draw_pixel(xy)
+    }
+
+    fn draw_poly(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.console.borrow_mut().draw_poly(points)
+    }
+
+    fn draw_poly_filled(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.console.borrow_mut().draw_poly_filled(points)
+    }
+
+    fn draw_rect(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        self.console.borrow_mut().draw_rect(x1y1, x2y2)
+    }
+
+    fn draw_rect_filled(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
+        self.console.borrow_mut().draw_rect_filled(x1y1, x2y2)
+    }
+
+    fn draw_text(&mut self, xy: PixelsXY, text: &str, size: Option<u16>) -> io::Result<()> {
+        self.console.borrow_mut().draw_text(xy, text, size)
+    }
+
+    fn flood_fill(&mut self, xy: PixelsXY, border: Option<RGB>) -> io::Result<()> {
+        self.console.borrow_mut().flood_fill(xy, border)
+    }
+
+    fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+        self.console.borrow().read_pixel(xy)
+    }
+
+    fn set_clip(&mut self, area: Option<(PixelsXY, PixelsXY)>) -> io::Result<()> {
+        self.console.borrow_mut().set_clip(area)
+    }
+
+    fn set_draw_page(&mut self, page: u8) -> io::Result<()> {
+        self.console.borrow_mut().set_draw_page(page)
+    }
+
+    fn flip_page(&mut self, page: u8) -> io::Result<()> {
+        self.console.borrow_mut().flip_page(page)
+    }
+
+    fn set_font(&mut self, face: Option<&str>, size: u16) -> io::Result<()> {
+        // Changing the font changes the size of the console, so the windows may no longer fit.
+        self.reset()?;
+        self.console.borrow_mut().set_font(face, size)
+    }
+
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        self.console.borrow_mut().set_title(title)
+    }
+
+    async fn clipboard_get(&mut self) -> io::Result<String> {
+        self.console.borrow_mut().clipboard_get().await
+    }
+
+    async fn clipboard_set(&mut self, text: &str) -> io::Result<()> {
+        self.console.borrow_mut().clipboard_set(text).await
+    }
+
+    fn set_fullscreen(&mut self, enabled: bool) -> io::Result<()> {
+        // Changing to or from full screen changes the size of the console, so the windows may no
+        // longer fit.
+        self.reset()?;
+        self.console.borrow_mut().set_fullscreen(enabled)
+    }
+
+    fn sync_now(&mut self) -> io::Result<()> {
+        self.console.borrow_mut().sync_now()
+    }
+
+    fn set_sync(&mut self, enabled: bool) -> io::Result<bool> {
+        self.console.borrow_mut().set_sync(enabled)
+    }
+}
+
+/// Forgets all windows of a console in a best-effort manner.
+pub(crate) struct WindowsClearable {
+    console: Rc<RefCell<WindowedConsole>>,
+}
+
+impl WindowsClearable {
+    /// Creates a new clearable for `console`.
+    pub(crate) fn new(console: Rc<RefCell<WindowedConsole>>) -> Box<Self> {
+        Box::from(Self { console })
+    }
+}
+
+impl Clearable for WindowsClearable {
+    fn reset_state(&self, _syms: &mut Symbols) {
+        let _ = self.console.borrow_mut().reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    /// Creates a windowed console on top of a mock console of 10x5 characters.
+    fn setup() -> (Rc<RefCell<MockConsole>>, WindowedConsole) {
+        let mock = Rc::from(RefCell::from(MockConsole::default()));
+        mock.borrow_mut().set_size_chars(CharsXY::new(10, 5));
+        let console = WindowedConsole::new(mock.clone());
+        (mock, console)
+    }
+
+    /// Returns the contents of window `n` of `console` as one string per row.
+    fn contents(console: &WindowedConsole, n: u8) -> Vec<String> {
+        console.windows[&n]
+            .cells
+            .iter()
+            .map(|row| row.iter().map(|(ch, _)| *ch).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_windowed_console_forwards_without_window() {
+        let (mock, mut console) = setup();
+        console.print("Hello").unwrap();
+        console.locate(CharsXY::new(9, 4)).unwrap();
+        console.clear(ClearType::All).unwrap();
+        assert_eq!(CharsXY::new(10, 5), console.size_chars().unwrap());
+        assert_eq!(
+            [
+                CapturedOut::Print("Hello".to_owned()),
+                CapturedOut::Locate(CharsXY::new(9, 4)),
+                CapturedOut::Clear(ClearType::All),
+            ],
+            mock.borrow().captured_out()
+        );
+    }
+
+    #[test]
+    fn test_windowed_console_define_and_select() {
+        let (mock, mut console) = setup();
+        console.define(1, CharsXY::new(2, 1), CharsXY::new(5, 2)).unwrap();
+        assert_eq!(CharsXY::new(4, 2), console.size_chars().unwrap());
+        assert_eq!(CharsXY::new(10, 5), console.screen_size().unwrap());
+        assert_eq!(
+            [
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(2, 1)),
+                CapturedOut::Locate(CharsXY::new(2, 1)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("    ".to_owned()),
+                CapturedOut::Locate(CharsXY::new(2, 2)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("    ".to_owned()),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(2, 1)),
+            ],
+            mock.borrow_mut().take_captured_out().as_slice()
+        );
+
+        console.select(None).unwrap();
+        assert_eq!(CharsXY::new(10, 5), console.size_chars().unwrap());
+        console.select(Some(1)).unwrap();
+        assert_eq!(CharsXY::new(4, 2), console.size_chars().unwrap());
+
+        assert_eq!(io::ErrorKind::InvalidInput, console.select(Some(2)).unwrap_err().kind());
+        assert_eq!(
+            io::ErrorKind::InvalidInput,
+            console.define(2, CharsXY::new(2, 1), CharsXY::new(10, 2)).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn test_windowed_console_write_wraps_and_scrolls() {
+        let (mock, mut console) = setup();
+        console.define(1, CharsXY::new(1, 1), CharsXY::new(3, 2)).unwrap();
+        let _ = mock.borrow_mut().take_captured_out();
+
+        console.write("abcdefg").unwrap();
+        assert_eq!(["def", "g  "], contents(&console, 1).as_slice());
+        assert_eq!(
+            [
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("abc".to_owned()),
+                CapturedOut::Locate(CharsXY::new(1, 2)),
+                CapturedOut::Write("def".to_owned()),
+                CapturedOut::Locate(CharsXY::new(1, 1)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("def".to_owned()),
+                CapturedOut::Locate(CharsXY::new(1, 2)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("   ".to_owned()),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(1, 2)),
+                CapturedOut::Write("g".to_owned()),
+            ],
+            mock.borrow_mut().take_captured_out().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_windowed_console_write_wide_chars() {
+        let (mock, mut console) = setup();
+        console.define(1, CharsXY::new(1, 1), CharsXY::new(5, 2)).unwrap();
+        let _ = mock.borrow_mut().take_captured_out();
+
+        console.write("ab日本").unwrap();
+        assert_eq!(["ab日\0 ", "本\0   "], contents(&console, 1).as_slice());
+        assert_eq!(CharsXY::new(2, 1), console.cursor_pos().unwrap());
+        assert_eq!(
+            [
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("ab日".to_owned()),
+                CapturedOut::Locate(CharsXY::new(1, 2)),
+                CapturedOut::Write("本".to_owned()),
+            ],
+            mock.borrow_mut().take_captured_out().as_slice()
+        );
+
+        console.write("e\u{301}").unwrap();
+        assert_eq!(["ab日\0 ", "本\0e  "], contents(&console, 1).as_slice());
+        assert_eq!(CharsXY::new(3, 1), console.cursor_pos().unwrap());
+
+        console.select(None).unwrap();
+        console.select(Some(1)).unwrap();
+        let _ = mock.borrow_mut().take_captured_out();
+        console.clear(ClearType::UntilNewLine).unwrap();
+        assert_eq!(
+            [
+                CapturedOut::Locate(CharsXY::new(1, 2)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("本e  ".to_owned()),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Locate(CharsXY::new(4, 2)),
+            ],
+            mock.borrow_mut().take_captured_out().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_windowed_console_print_locate_and_move() {
+        let (mock, mut console) = setup();
+        console.define(1, CharsXY::new(5, 0), CharsXY::new(9, 3)).unwrap();
+        let _ = mock.borrow_mut().take_captured_out();
+
+        console.print("ab").unwrap();
+        assert_eq!(CharsXY::new(0, 1), console.cursor_pos().unwrap());
+        console.locate(CharsXY::new(3, 2)).unwrap();
+        console.write("c").unwrap();
+        assert_eq!(CharsXY::new(4, 2), console.cursor_pos().unwrap());
+        console.move_within_line(-10).unwrap();
+        console.move_within_line(10).unwrap();
+        assert_eq!(CharsXY::new(4, 2), console.cursor_pos().unwrap());
+        assert_eq!(["ab   ", "     ", "   c ", "     "], contents(&console, 1).as_slice());
+        assert_eq!(
+            [
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("ab".to_owned()),
+                CapturedOut::Locate(CharsXY::new(5, 1)),
+                CapturedOut::Locate(CharsXY::new(8, 2)),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Write("c".to_owned()),
+                CapturedOut::Locate(CharsXY::new(5, 2)),
+                CapturedOut::Locate(CharsXY::new(9, 2)),
+            ],
+            mock.borrow_mut().take_captured_out().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_windowed_console_clear() {
+        let (mock, mut console) = setup();
+        console.define(1, CharsXY::new(0, 0), CharsXY::new(2, 1)).unwrap();
+        console.write("abcde").unwrap();
+        let _ = mock.borrow_mut().take_captured_out();
+
+        console.clear(ClearType::PreviousChar).unwrap();
+        assert_eq!(["abc", "d  "], contents(&console, 1).as_slice());
+
+        console.locate(CharsXY::new(1, 0)).unwrap();
+        console.clear(ClearType::UntilNewLine).unwrap();
+        assert_eq!(["a  ", "d  "], contents(&console, 1).as_slice());
+
+        console.locate(CharsXY::new(1, 1)).unwrap();
+        console.clear(ClearType::CurrentLine).unwrap();
+        assert_eq!(["a  ", "   "], contents(&console, 1).as_slice());
+        assert_eq!(CharsXY::new(0, 1), console.windows[&1].cursor);
+
+        console.clear(ClearType::All).unwrap();
+        assert_eq!(["   ", "   "], contents(&console, 1).as_slice());
+        assert_eq!(CharsXY::new(0, 0), console.windows[&1].cursor);
+    }
+
+    #[test]
+    fn test_windowed_console_colors() {
+        let (mock, mut console) = setup();
+        console.set_color(Some(1), None).unwrap();
+        console.define(1, CharsXY::new(0, 0), CharsXY::new(1, 0)).unwrap();
+        assert_eq!((Some(1), None), console.color());
+
+        console.set_color(Some(2), Some(3)).unwrap();
+        assert_eq!((Some(2), Some(3)), console.color());
+        console.set_color_rgb(Some((255, 0, 0)), None).unwrap();
+        assert_eq!((Some(9), None), console.color());
+        let _ = mock.borrow_mut().take_captured_out();
+
+        console.select(None).unwrap();
+        assert_eq!((Some(1), None), console.color());
+        console.select(Some(1)).unwrap();
+        assert_eq!(
+            [
+                CapturedOut::SetColor(Some(1), None),
+                CapturedOut::SetColorRgb(Some((255, 0, 0)), None),
+                CapturedOut::Locate(CharsXY::new(0, 0)),
+            ],
+            mock.borrow().captured_out()
+        );
+    }
+
+    #[test]
+    fn test_windowed_console_reset() {
+        let (_mock, mut console) = setup();
+        console.define(1, CharsXY::new(0, 0), CharsXY::new(1, 0)).unwrap();
+        console.reset().unwrap();
+        assert!(!console.has_window(1));
+        assert_eq!(CharsXY::new(10, 5), console.size_chars().unwrap());
+    }
+}
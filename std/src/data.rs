@@ -106,6 +106,7 @@ impl Command for ReadCommand {
                                     Ok(VarType::Boolean) => Value::Boolean(false),
                                     Ok(VarType::Double) => Value::Double(0.0),
                                     Ok(VarType::Integer) => Value::Integer(0),
+                                    Ok(VarType::Long) => Value::Long(0),
                                     Ok(VarType::Text) => Value::Text("".to_owned()),
                                     Ok(VarType::Void) => panic!(),
                                     Err(_) => Value::Integer(0),
@@ -114,6 +115,7 @@ impl Command for ReadCommand {
                             (VarType::Boolean, None) => Value::Boolean(false),
                             (VarType::Double, None) => Value::Double(0.0),
                             (VarType::Integer, None) => Value::Integer(0),
+                            (VarType::Long, None) => Value::Long(0),
                             (VarType::Text, None) => Value::Text("".to_owned()),
                             (VarType::Void, None) => panic!(),
                         }
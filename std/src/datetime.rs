@@ -0,0 +1,569 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Date and time functions for EndBASIC.
+
+use async_trait::async_trait;
+use endbasic_core::ast::{FunctionCallSpan, Value, VarType};
+use endbasic_core::eval::eval_all;
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Function, FunctionResult, Symbols,
+};
+use endbasic_core::LineCol;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use time::{format_description, Month, OffsetDateTime, UtcOffset};
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Date and time functions
+Dates are represented as the number of seconds elapsed since 1970-01-01 00:00:00 UTC, stored as a \
+double so that they can carry fractions of a second.  NOW returns the current date in this form.
+Functions that deal with calendar units, like DATEADD and DATEDIFF, and functions that render \
+dates as strings interpret these values in the local time zone.
+Calendar units are given as strings and can be one of YEAR, MONTH, WEEK, DAY, HOUR, MINUTE, or \
+SECOND, without regard to case.";
+
+/// Type of the function used to query the current date and time in the local time zone.
+pub type NowFn = Rc<dyn Fn() -> OffsetDateTime>;
+
+/// Returns the current date and time based on the system's clock and time zone.
+fn system_now() -> OffsetDateTime {
+    let offset = match UtcOffset::current_local_offset() {
+        Ok(offset) => offset,
+        Err(_) => UtcOffset::UTC,
+    };
+    OffsetDateTime::now_utc().to_offset(offset)
+}
+
+/// Calendar units that can be used in date arithmetic.
+#[derive(Clone, Copy)]
+enum Unit {
+    Year,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl Unit {
+    /// Parses the unit `name` that was provided at `pos`.
+    fn parse(name: &str, pos: LineCol) -> Result<Self, CallError> {
+        match name.to_ascii_uppercase().as_str() {
+            "YEAR" => Ok(Unit::Year),
+            "MONTH" => Ok(Unit::Month),
+            "WEEK" => Ok(Unit::Week),
+            "DAY" => Ok(Unit::Day),
+            "HOUR" => Ok(Unit::Hour),
+            "MINUTE" => Ok(Unit::Minute),
+            "SECOND" => Ok(Unit::Second),
+            _ => Err(CallError::ArgumentError(pos, format!("Unknown date unit {}", name))),
+        }
+    }
+
+    /// Returns the number of seconds in this unit, or none if the unit has a variable length.
+    fn seconds(self) -> Option<f64> {
+        match self {
+            Unit::Year | Unit::Month => None,
+            Unit::Week => Some(7.0 * 24.0 * 60.0 * 60.0),
+            Unit::Day => Some(24.0 * 60.0 * 60.0),
+            Unit::Hour => Some(60.0 * 60.0),
+            Unit::Minute => Some(60.0),
+            Unit::Second => Some(1.0),
+        }
+    }
+}
+
+/// Converts the `value` provided at `pos` into a date in the time zone given by `offset`.
+fn to_date(value: &Value, offset: UtcOffset, pos: LineCol) -> Result<OffsetDateTime, CallError> {
+    match value {
+        Value::Double(d) => secs_to_date(*d, offset, pos),
+        Value::Integer(i) => secs_to_date(f64::from(*i), offset, pos),
+        _ => Err(CallError::SyntaxError),
+    }
+}
+
+/// Converts the `secs` since the epoch provided at `pos` into a date in the time zone given by
+/// `offset`.
+fn secs_to_date(secs: f64, offset: UtcOffset, pos: LineCol) -> Result<OffsetDateTime, CallError> {
+    let nanos = secs * 1_000_000_000.0;
+    if !nanos.is_finite() {
+        return Err(CallError::ArgumentError(pos, "Date out of range".to_owned()));
+    }
+    match OffsetDateTime::from_unix_timestamp_nanos(nanos as i128) {
+        Ok(date) => Ok(date.to_offset(offset)),
+        Err(_) => Err(CallError::ArgumentError(pos, "Date out of range".to_owned())),
+    }
+}
+
+/// Converts the `date` to its numerical representation.
+fn from_date(date: OffsetDateTime) -> Value {
+    Value::Double(date.unix_timestamp_nanos() as f64 / 1_000_000_000.0)
+}
+
+/// Adds `months` to the `date`, clamping the day to the last day of the resulting month if needed.
+///
+/// Returns none if the resulting date is out of range.
+fn add_months(date: OffsetDateTime, months: i64) -> Option<OffsetDateTime> {
+    let total = i64::from(date.year()) * 12 + i64::from(u8::from(date.month()) - 1) + months;
+    let year = i32::try_from(total.div_euclid(12)).ok()?;
+    let month = Month::try_from(total.rem_euclid(12) as u8 + 1).ok()?;
+    let day = date.day().min(time::util::days_in_month(month, year));
+    let new_date = time::Date::from_calendar_date(year, month, day).ok()?;
+    Some(date.replace_date(new_date))
+}
+
+/// Computes the number of whole `months` between `from` and `to`, truncated towards zero.
+fn diff_months(from: OffsetDateTime, to: OffsetDateTime) -> Option<i64> {
+    let to = to.to_offset(from.offset());
+    let mut months = (i64::from(to.year()) - i64::from(from.year())) * 12
+        + (i64::from(u8::from(to.month())) - i64::from(u8::from(from.month())));
+    if months > 0 && add_months(from, months)? > to {
+        months -= 1;
+    } else if months < 0 && add_months(from, months)? < to {
+        months += 1;
+    }
+    Some(months)
+}
+
+/// The `DATE` function.
+pub struct DateFunction {
+    metadata: CallableMetadata,
+    now_fn: NowFn,
+}
+
+impl DateFunction {
+    /// Creates a new instance of the function.
+    pub fn new(now_fn: NowFn) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DATE", VarType::Text)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the current date.
+The date is returned in the local time zone in the YYYY-MM-DD form.",
+                )
+                .build(),
+            now_fn,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for DateFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let now = (self.now_fn)();
+        Ok(Value::Text(format!("{:04}-{:02}-{:02}", now.year(), u8::from(now.month()), now.day())))
+    }
+}
+
+/// The `DATEADD` function.
+pub struct DateAddFunction {
+    metadata: CallableMetadata,
+    now_fn: NowFn,
+}
+
+impl DateAddFunction {
+    /// Creates a new instance of the function.
+    pub fn new(now_fn: NowFn) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DATEADD", VarType::Double)
+                .with_syntax("unit$, n%, date#")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Adds a number of calendar units to a date.
+Returns the date that results from adding n% times the given unit$ to date#.  n% can be negative \
+to subtract units instead.
+Adding months or years keeps the day of the month unless the resulting month is shorter, in which \
+case the day is adjusted to the last day of that month.  For example, adding one MONTH to January \
+31st yields the last day of February.",
+                )
+                .build(),
+            now_fn,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for DateAddFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (unit, n, date) = match args.as_slice() {
+            [Value::Text(unit), Value::Integer(n), date] => (unit, *n, date),
+            _ => return Err(CallError::SyntaxError),
+        };
+        let unit = Unit::parse(unit, span.args[0].start_pos())?;
+        let date_pos = span.args[2].start_pos();
+        let date = to_date(date, (self.now_fn)().offset(), date_pos)?;
+
+        let result = match unit {
+            Unit::Year => add_months(date, i64::from(n) * 12),
+            Unit::Month => add_months(date, i64::from(n)),
+            _ => {
+                let secs = unit.seconds().expect("Only calendar units have a variable length");
+                let delta = time::Duration::seconds_f64(f64::from(n) * secs);
+                date.checked_add(delta)
+            }
+        };
+        match result {
+            Some(result) => Ok(from_date(result)),
+            None => Err(CallError::ArgumentError(date_pos, "Date out of range".to_owned())),
+        }
+    }
+}
+
+/// The `DATEDIFF` function.
+pub struct DateDiffFunction {
+    metadata: CallableMetadata,
+    now_fn: NowFn,
+}
+
+impl DateDiffFunction {
+    /// Creates a new instance of the function.
+    pub fn new(now_fn: NowFn) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DATEDIFF", VarType::Integer)
+                .with_syntax("unit$, date1#, date2#")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Computes the number of calendar units between two dates.
+Returns the number of whole unit$s that fit between date1# and date2#, which is negative if \
+date2# is before date1#.  Partial units are discarded, so the difference between January 31st \
+and March 30th is 1 MONTH.",
+                )
+                .build(),
+            now_fn,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for DateDiffFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (unit, date1, date2) = match args.as_slice() {
+            [Value::Text(unit), date1, date2] => (unit, date1, date2),
+            _ => return Err(CallError::SyntaxError),
+        };
+        let unit = Unit::parse(unit, span.args[0].start_pos())?;
+        let offset = (self.now_fn)().offset();
+        let date1 = to_date(date1, offset, span.args[1].start_pos())?;
+        let date2 = to_date(date2, offset, span.args[2].start_pos())?;
+
+        let diff = match unit {
+            Unit::Year => diff_months(date1, date2).map(|months| months / 12),
+            Unit::Month => diff_months(date1, date2),
+            _ => {
+                let secs = unit.seconds().expect("Only calendar units have a variable length");
+                Some(((date2 - date1).as_seconds_f64() / secs).trunc() as i64)
+            }
+        };
+        match diff.and_then(|diff| i32::try_from(diff).ok()) {
+            Some(diff) => Ok(Value::Integer(diff)),
+            None => Err(CallError::ArgumentError(
+                span.args[2].start_pos(),
+                "Date difference out of range".to_owned(),
+            )),
+        }
+    }
+}
+
+/// The `FORMATDATE` function.
+pub struct FormatDateFunction {
+    metadata: CallableMetadata,
+    now_fn: NowFn,
+}
+
+impl FormatDateFunction {
+    /// Creates a new instance of the function.
+    pub fn new(now_fn: NowFn) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("FORMATDATE", VarType::Text)
+                .with_syntax("date#, format$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Formats a date as a string.
+The format$ is a string in which components enclosed in square brackets are replaced by the \
+corresponding parts of date# in the local time zone, and all other characters are kept as is.  \
+Valid components include [year], [month], [day], [hour], [minute], [second], [weekday], and \
+[month repr:long].  For example:
+    PRINT FORMATDATE$(NOW, \"[day]/[month]/[year] [hour]:[minute]\")",
+                )
+                .build(),
+            now_fn,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for FormatDateFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (date, format) = match args.as_slice() {
+            [date, Value::Text(format)] => (date, format),
+            _ => return Err(CallError::SyntaxError),
+        };
+        let date = to_date(date, (self.now_fn)().offset(), span.args[0].start_pos())?;
+
+        let format_pos = span.args[1].start_pos();
+        let format = format_description::parse_borrowed::<1>(format).map_err(|e| {
+            CallError::ArgumentError(format_pos, format!("Invalid date format: {}", e))
+        })?;
+        match date.format(&format) {
+            Ok(s) => Ok(Value::Text(s)),
+            Err(e) => {
+                Err(CallError::ArgumentError(format_pos, format!("Cannot format date: {}", e)))
+            }
+        }
+    }
+}
+
+/// The `NOW` function.
+pub struct NowFunction {
+    metadata: CallableMetadata,
+    now_fn: NowFn,
+}
+
+impl NowFunction {
+    /// Creates a new instance of the function.
+    pub fn new(now_fn: NowFn) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("NOW", VarType::Double)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the current date and time.
+The returned value is the number of seconds since 1970-01-01 00:00:00 UTC and can be given to \
+any of the other date and time functions.",
+                )
+                .build(),
+            now_fn,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for NowFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        Ok(from_date((self.now_fn)()))
+    }
+}
+
+/// The `TIME` function.
+pub struct TimeFunction {
+    metadata: CallableMetadata,
+    now_fn: NowFn,
+}
+
+impl TimeFunction {
+    /// Creates a new instance of the function.
+    pub fn new(now_fn: NowFn) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("TIME", VarType::Text)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the current time.
+The time is returned in the local time zone in the HH:MM:SS form, using a 24-hour clock.",
+                )
+                .build(),
+            now_fn,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for TimeFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let now = (self.now_fn)();
+        Ok(Value::Text(format!("{:02}:{:02}:{:02}", now.hour(), now.minute(), now.second())))
+    }
+}
+
+/// Adds all symbols provided by this module to the given `machine`.
+///
+/// `now_fn` is the function used to query the current date and time.  If not provided, uses the
+/// system's clock and time zone.
+pub fn add_all(machine: &mut Machine, now_fn: Option<NowFn>) {
+    let now_fn = now_fn.unwrap_or_else(|| Rc::from(system_now));
+    machine.add_function(DateFunction::new(now_fn.clone()));
+    machine.add_function(DateAddFunction::new(now_fn.clone()));
+    machine.add_function(DateDiffFunction::new(now_fn.clone()));
+    machine.add_function(FormatDateFunction::new(now_fn.clone()));
+    machine.add_function(NowFunction::new(now_fn.clone()));
+    machine.add_function(TimeFunction::new(now_fn));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    /// 2023-01-31 22:30:15.5 in the +02:00 time zone.
+    const NOW: f64 = 1_675_197_015.5;
+
+    /// Creates a tester whose date and time functions see `NOW` as the current time.
+    fn tester() -> Tester {
+        let now_fn: NowFn = Rc::from(|| {
+            OffsetDateTime::from_unix_timestamp_nanos((NOW * 1_000_000_000.0) as i128)
+                .unwrap()
+                .to_offset(UtcOffset::from_hms(2, 0, 0).unwrap())
+        });
+        let mut t = Tester::empty();
+        add_all(t.get_machine(), Some(now_fn));
+        t
+    }
+
+    /// Evaluates `expr` with a fake current time and checks that it yields `exp_value`.
+    fn check_ok<V: Into<Value>>(exp_value: V, expr: &str) {
+        tester().run(format!("result = {}", expr)).expect_var("result", exp_value.into()).check();
+    }
+
+    /// Evaluates `expr` with a fake current time and checks that it fails with `exp_error`.
+    fn check_error(exp_error: &str, expr: &str) {
+        tester().run(format!("result = {}", expr)).expect_err(exp_error).check();
+    }
+
+    #[test]
+    fn test_now_date_time() {
+        check_ok(NOW, "NOW");
+        check_ok("2023-01-31", "DATE$");
+        check_ok("22:30:15", "TIME$");
+
+        check_error("1:10: In call to NOW: expected no arguments nor parenthesis", "NOW()");
+        check_error("1:10: In call to DATE: expected no arguments nor parenthesis", "DATE(1)");
+        check_error("1:10: In call to TIME: expected no arguments nor parenthesis", "TIME$()");
+    }
+
+    #[test]
+    fn test_dateadd() {
+        check_ok(NOW + 3.0, r#"DATEADD("second", 3, NOW)"#);
+        check_ok(NOW - 120.0, r#"DATEADD("MINUTE", -2, NOW)"#);
+        check_ok(NOW + 3600.0, r#"DATEADD("Hour", 1, NOW)"#);
+        check_ok(NOW + 86400.0, r#"DATEADD("DAY", 1, NOW)"#);
+        check_ok(NOW + 2.0 * 604800.0, r#"DATEADD("WEEK", 2, NOW)"#);
+        check_ok(10.0, r#"DATEADD("SECOND", 10, 0)"#);
+
+        check_ok(
+            "2023-02-28 22:30",
+            r#"FORMATDATE(DATEADD("MONTH", 1, NOW), "[year]-[month]-[day] [hour]:[minute]")"#,
+        );
+        check_ok("2024-02-29", r#"FORMATDATE(DATEADD("MONTH", 13, NOW), "[year]-[month]-[day]")"#);
+        check_ok("2022-12-31", r#"FORMATDATE(DATEADD("MONTH", -1, NOW), "[year]-[month]-[day]")"#);
+        check_ok("2020-01-31", r#"FORMATDATE(DATEADD("YEAR", -3, NOW), "[year]-[month]-[day]")"#);
+
+        check_error("1:10: In call to DATEADD: expected unit$, n%, date#", r#"DATEADD("DAY", 1)"#);
+        check_error(
+            "1:10: In call to DATEADD: expected unit$, n%, date#",
+            r#"DATEADD("DAY", 1.5, NOW)"#,
+        );
+        check_error(
+            "1:10: In call to DATEADD: expected unit$, n%, date#",
+            r#"DATEADD("DAY", 1, "2023-01-01")"#,
+        );
+        check_error(
+            "1:10: In call to DATEADD: 1:18: Unknown date unit fortnight",
+            r#"DATEADD("fortnight", 1, NOW)"#,
+        );
+        check_error(
+            "1:10: In call to DATEADD: 1:34: Date out of range",
+            r#"DATEADD("YEAR", 100000, NOW)"#,
+        );
+        check_error(
+            "1:10: In call to DATEADD: 1:28: Date out of range",
+            r#"DATEADD("DAY", 1, 10000000000000.0)"#,
+        );
+    }
+
+    #[test]
+    fn test_datediff() {
+        check_ok(90, r#"DATEDIFF("SECOND", NOW, NOW + 90.9)"#);
+        check_ok(-1, r#"DATEDIFF("MINUTE", NOW, NOW - 119)"#);
+        check_ok(0, r#"DATEDIFF("DAY", NOW, DATEADD("HOUR", 23, NOW))"#);
+        check_ok(2, r#"DATEDIFF("WEEK", NOW, DATEADD("DAY", 20, NOW))"#);
+
+        check_ok(0, r#"DATEDIFF("MONTH", NOW, DATEADD("DAY", 27, NOW))"#);
+        check_ok(1, r#"DATEDIFF("MONTH", NOW, DATEADD("DAY", 28, NOW))"#);
+        check_ok(1, r#"DATEDIFF("MONTH", NOW, DATEADD("DAY", 58, NOW))"#);
+        check_ok(2, r#"DATEDIFF("MONTH", NOW, DATEADD("DAY", 59, NOW))"#);
+        check_ok(-1, r#"DATEDIFF("MONTH", NOW, DATEADD("DAY", -31, NOW))"#);
+        check_ok(0, r#"DATEDIFF("YEAR", NOW, DATEADD("MONTH", 11, NOW))"#);
+        check_ok(-2, r#"DATEDIFF("YEAR", NOW, DATEADD("MONTH", -35, NOW))"#);
+
+        check_error(
+            "1:10: In call to DATEDIFF: expected unit$, date1#, date2#",
+            r#"DATEDIFF("DAY", NOW)"#,
+        );
+        check_error(
+            "1:10: In call to DATEDIFF: 1:19: Unknown date unit MONTHS",
+            r#"DATEDIFF("MONTHS", NOW, NOW)"#,
+        );
+        check_error(
+            "1:10: In call to DATEDIFF: 1:32: Date difference out of range",
+            r#"DATEDIFF("SECOND", 0, 10000000000.0)"#,
+        );
+    }
+
+    #[test]
+    fn test_formatdate() {
+        check_ok(
+            "2023-01-31 22:30:15",
+            r#"FORMATDATE$(NOW, "[year]-[month]-[day] [hour]:[minute]:[second]")"#,
+        );
+        check_ok("Tuesday, January 31", r#"FORMATDATE(NOW, "[weekday], [month repr:long] [day]")"#);
+        check_ok("1970-01-01 02:00", r#"FORMATDATE(0, "[year]-[month]-[day] [hour]:[minute]")"#);
+
+        check_error("1:10: In call to FORMATDATE: expected date#, format$", r#"FORMATDATE(NOW)"#);
+        check_error(
+            "1:10: In call to FORMATDATE: 1:26: Invalid date format: unclosed opening bracket at byte index 0",
+            r#"FORMATDATE(NOW, "[year")"#,
+        );
+    }
+}
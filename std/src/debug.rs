@@ -0,0 +1,828 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Commands to debug the stored program.
+
+use crate::console::Console;
+use crate::program::{report_stop_reason, Program};
+use async_trait::async_trait;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Expr, VarType};
+use endbasic_core::exec::{ClockFn, Machine};
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Symbol,
+};
+use endbasic_core::LineCol;
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Debugging
+The debugger lets you pause the execution of the stored program to inspect its state.
+Use BREAK to set breakpoints on specific lines of the stored program and then start the program \
+with RUN.  Execution will pause right before running the first statement on any of those lines.  \
+While the program is paused, you can inspect its variables with VARS or with any other command, \
+run the next line with STEP, or continue execution until the next breakpoint with CONT.
+Alternatively, use TRON to print every line of the program as it runs, which is useful to follow \
+the program's control flow without stopping it.
+To find out where a program spends its time, enable the profiler with PROFILE ON, RUN the \
+program, and then use PROFILE SHOW to see how many times execution entered each line and how long \
+it took.
+Breakpoints, tracing, and profiling only apply to programs started with RUN, not to commands typed at the \
+prompt.";
+
+/// Evaluates the optional line number given to a command that accepts `[line%]`.
+async fn parse_opt_line(
+    span: &BuiltinCallSpan,
+    machine: &mut Machine,
+) -> Result<Option<usize>, CallError> {
+    match span.args.as_slice() {
+        [] => Ok(None),
+        [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => {
+            Ok(Some(eval_line(expr, machine).await?))
+        }
+        _ => Err(CallError::SyntaxError),
+    }
+}
+
+/// Evaluates `expr` as a line number of the stored program.
+pub(crate) async fn eval_line(expr: &Expr, machine: &mut Machine) -> Result<usize, CallError> {
+    let value = expr.eval(machine.get_mut_symbols()).await?;
+    let line =
+        value.as_i32().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    if line <= 0 {
+        return Err(CallError::ArgumentError(
+            expr.start_pos(),
+            "Line number must be positive".to_owned(),
+        ));
+    }
+    Ok(line as usize)
+}
+
+/// The `BREAK` command.
+pub struct BreakCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl BreakCommand {
+    /// Creates a new `BREAK` command that sets breakpoints and reports them via `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("BREAK", VarType::Void)
+                .with_syntax("[line%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sets a breakpoint or lists the existing ones.
+When given a line number, sets a breakpoint on that line of the stored program so that execution \
+pauses before running it.  Lines are counted from 1, as displayed by the editor.
+When given no arguments, lists all breakpoints.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for BreakCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match parse_opt_line(span, machine).await? {
+            Some(line) => machine.add_breakpoint(line),
+            None => {
+                let mut console = self.console.borrow_mut();
+                for line in machine.breakpoints() {
+                    console.print(&format!("Breakpoint at line {}", line))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `UNBREAK` command.
+pub struct UnbreakCommand {
+    metadata: CallableMetadata,
+}
+
+impl UnbreakCommand {
+    /// Creates a new `UNBREAK` command that removes breakpoints.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("UNBREAK", VarType::Void)
+                .with_syntax("[line%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Removes a breakpoint or all of them.
+When given a line number, removes the breakpoint previously set on that line with BREAK.  When \
+given no arguments, removes all breakpoints.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for UnbreakCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match parse_opt_line(span, machine).await? {
+            Some(line) => {
+                if !machine.remove_breakpoint(line) {
+                    return Err(CallError::ArgumentError(
+                        span.args[0].expr.as_ref().expect("Validated above").start_pos(),
+                        format!("No breakpoint at line {}", line),
+                    ));
+                }
+            }
+            None => machine.clear_breakpoints(),
+        }
+        Ok(())
+    }
+}
+
+/// Resumes the paused program and reports why it stopped again.
+///
+/// `step` indicates whether execution should pause as soon as it reaches a new line.
+async fn resume(
+    span: &BuiltinCallSpan,
+    machine: &mut Machine,
+    console: &Rc<RefCell<dyn Console>>,
+    program: &Rc<RefCell<dyn Program>>,
+    step: bool,
+) -> CommandResult {
+    if !span.args.is_empty() {
+        return Err(CallError::SyntaxError);
+    }
+    if !machine.is_paused() {
+        return Err(CallError::IoError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No paused program",
+        )));
+    }
+    let stop_reason = match machine.resume(step).await {
+        Ok(stop_reason) => stop_reason,
+        Err(e) => return Err(CallError::NestedError(format!("{}", e))),
+    };
+    let program = program.borrow().text();
+    report_stop_reason(&mut *console.borrow_mut(), &program, stop_reason)?;
+    Ok(())
+}
+
+/// The `CONT` command.
+pub struct ContCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    program: Rc<RefCell<dyn Program>>,
+}
+
+impl ContCommand {
+    /// Creates a new `CONT` command that resumes the execution of the paused `program`.
+    pub fn new(console: Rc<RefCell<dyn Console>>, program: Rc<RefCell<dyn Program>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CONT", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Continues the execution of the paused program.
+Execution continues until the program terminates or until it reaches another breakpoint.",
+                )
+                .build(),
+            console,
+            program,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ContCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        resume(span, machine, &self.console, &self.program, false).await
+    }
+}
+
+/// Returns a clock for the profiler based on the system's monotonic clock.
+fn system_clock() -> ClockFn {
+    let origin = Instant::now();
+    Rc::from(move || origin.elapsed())
+}
+
+/// The `PROFILE` command.
+pub struct ProfileCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    program: Rc<RefCell<dyn Program>>,
+    clock_fn: Option<ClockFn>,
+}
+
+impl ProfileCommand {
+    /// Creates a new `PROFILE` command that profiles the `program` and reports its results via
+    /// `console`.
+    ///
+    /// `clock_fn` is the function used to measure time.  If not provided, uses the system's
+    /// monotonic clock.
+    pub fn new(
+        console: Rc<RefCell<dyn Console>>,
+        program: Rc<RefCell<dyn Program>>,
+        clock_fn: Option<ClockFn>,
+    ) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("PROFILE", VarType::Void)
+                .with_syntax("<ON|OFF|SHOW>")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Controls the profiler or shows its results.
+PROFILE ON enables the profiler and discards any previously-collected data.  PROFILE OFF disables \
+the profiler but keeps the collected data so that you can still inspect it.
+PROFILE SHOW shows the data collected so far: for every line of the program that ran, the number \
+of times execution entered it and the total time spent on it.  A loop that fits in a single line \
+therefore counts as a single entry.  Lines are sorted by time so that the hotspots of the program \
+come first.",
+                )
+                .with_syntactic_args()
+                .build(),
+            console,
+            program,
+            clock_fn,
+        })
+    }
+
+    /// Prints the profiling data collected by the `machine`.
+    fn show(&self, machine: &Machine) -> io::Result<()> {
+        let mut profile = machine.profile();
+        profile.sort_by(|a, b| b.time.cmp(&a.time).then(a.line.cmp(&b.line)));
+
+        let text = self.program.borrow().text();
+        let lines = text.lines().collect::<Vec<&str>>();
+
+        let mut console = self.console.borrow_mut();
+        if profile.is_empty() {
+            return console.print("No profiling data");
+        }
+        console
+            .print(&format!("{:>6} {:>10} {:>12}  {}", "LINE", "COUNT", "TIME (MS)", "SOURCE"))?;
+        for p in profile {
+            let source = lines.get(p.line - 1).copied().unwrap_or("").trim();
+            console.print(&format!(
+                "{:>6} {:>10} {:>12.3}  {}",
+                p.line,
+                p.count,
+                p.time.as_secs_f64() * 1000.0,
+                source
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ProfileCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let symspan = match span.args.as_slice() {
+            [ArgSpan { expr: Some(Expr::Symbol(symspan)), sep: ArgSep::End, .. }] => symspan,
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let name = symspan.vref.name();
+        if symspan.vref.ref_type() == VarType::Auto && name.eq_ignore_ascii_case("ON") {
+            let clock_fn = match self.clock_fn.as_ref() {
+                Some(clock_fn) => clock_fn.clone(),
+                None => system_clock(),
+            };
+            machine.enable_profiling(clock_fn);
+        } else if symspan.vref.ref_type() == VarType::Auto && name.eq_ignore_ascii_case("OFF") {
+            machine.disable_profiling();
+        } else if symspan.vref.ref_type() == VarType::Auto && name.eq_ignore_ascii_case("SHOW") {
+            self.show(machine)?;
+        } else {
+            return Err(CallError::ArgumentError(
+                symspan.pos,
+                format!("Unknown PROFILE operation {}", symspan.vref),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The `STEP` command.
+pub struct StepCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    program: Rc<RefCell<dyn Program>>,
+}
+
+impl StepCommand {
+    /// Creates a new `STEP` command that runs the next line of the paused `program`.
+    pub fn new(console: Rc<RefCell<dyn Console>>, program: Rc<RefCell<dyn Program>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("STEP", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Runs the next line of the paused program.
+Execution pauses again as soon as the program reaches a different line, which might not be the \
+line that follows the current one if the program jumps elsewhere.",
+                )
+                .build(),
+            console,
+            program,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for StepCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        resume(span, machine, &self.console, &self.program, true).await
+    }
+}
+
+/// The `TROFF` command.
+pub struct TroffCommand {
+    metadata: CallableMetadata,
+}
+
+impl TroffCommand {
+    /// Creates a new `TROFF` command that disables tracing.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("TROFF", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description("Disables tracing of the program's execution.")
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for TroffCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        machine.set_tracer(None);
+        Ok(())
+    }
+}
+
+/// The `TRON` command.
+pub struct TronCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    program: Rc<RefCell<dyn Program>>,
+}
+
+impl TronCommand {
+    /// Creates a new `TRON` command that traces the execution of the `program` to the `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>, program: Rc<RefCell<dyn Program>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("TRON", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Enables tracing of the program's execution.
+While tracing is enabled, every line of the stored program is printed, prefixed by its number in \
+brackets, right before it runs.  Tracing stays enabled until TROFF is used.",
+                )
+                .build(),
+            console,
+            program,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for TronCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let console = self.console.clone();
+        let program = self.program.clone();
+        machine.set_tracer(Some(Box::from(move |pos: LineCol| {
+            let text = program.borrow().text();
+            let line = text.lines().nth(pos.line - 1).unwrap_or("").trim();
+            console.borrow_mut().print(&format!("[{}] {}", pos.line, line))
+        })));
+        Ok(())
+    }
+}
+
+/// The `VARS` command.
+pub struct VarsCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl VarsCommand {
+    /// Creates a new `VARS` command that dumps variables to the `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("VARS", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Lists all defined variables and arrays.
+Variables are shown along with their current values.  Arrays are shown along with their \
+dimensions and their type, but not their contents.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for VarsCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+
+        let mut lines = vec![];
+        for (name, symbol) in machine.get_symbols().as_hashmap() {
+            if name.starts_with(|c: char| !c.is_ascii_alphabetic()) {
+                // Names that cannot be typed by the user are internal to the interpreter.
+                continue;
+            }
+            match symbol {
+                Symbol::Array(array) => {
+                    let dims = array
+                        .dimensions()
+                        .iter()
+                        .map(|d| d.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    lines.push(format!("{}({}) AS {}", name, dims, array.subtype()));
+                }
+                Symbol::Command(_) | Symbol::Function(_) => (),
+                Symbol::Variable(value) => {
+                    lines.push(format!("{}{} = {}", name, value.as_vartype().annotation(), value));
+                }
+            }
+        }
+        lines.sort();
+
+        let mut console = self.console.borrow_mut();
+        for line in lines {
+            console.print(&line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds all debugging commands against the stored `program` to the `machine`, using `console`
+/// to report the program's state.
+///
+/// `clock_fn` is the function used by the profiler to measure time.  If not provided, uses the
+/// system's monotonic clock.
+pub fn add_all(
+    machine: &mut Machine,
+    program: Rc<RefCell<dyn Program>>,
+    console: Rc<RefCell<dyn Console>>,
+    clock_fn: Option<ClockFn>,
+) {
+    machine.add_command(BreakCommand::new(console.clone()));
+    machine.add_command(ContCommand::new(console.clone(), program.clone()));
+    machine.add_command(ProfileCommand::new(console.clone(), program.clone(), clock_fn));
+    machine.add_command(StepCommand::new(console.clone(), program.clone()));
+    machine.add_command(TroffCommand::new());
+    machine.add_command(TronCommand::new(console.clone(), program));
+    machine.add_command(UnbreakCommand::new());
+    machine.add_command(VarsCommand::new(console));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::RunCommand;
+    use crate::testutils::*;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn test_break_list() {
+        let mut t = Tester::default();
+        t.run("BREAK").check();
+        t.run("BREAK 10: BREAK 3: BREAK 10").check();
+        t.run("BREAK").expect_prints(["Breakpoint at line 3", "Breakpoint at line 10"]).check();
+    }
+
+    #[test]
+    fn test_break_errors() {
+        check_stmt_err("1:1: In call to BREAK: expected [line%]", "BREAK 1, 2");
+        check_stmt_err("1:1: In call to BREAK: 1:7: Line number must be positive", "BREAK 0");
+        check_stmt_err("1:1: In call to BREAK: 1:7: \"a\" is not a number", "BREAK \"a\"");
+    }
+
+    #[test]
+    fn test_unbreak_ok() {
+        let mut t = Tester::default();
+        t.run("BREAK 1: BREAK 2: BREAK 3: UNBREAK 2: BREAK")
+            .expect_prints(["Breakpoint at line 1", "Breakpoint at line 3"])
+            .check();
+        t.run("UNBREAK: BREAK")
+            .expect_prints(["Breakpoint at line 1", "Breakpoint at line 3"])
+            .check();
+    }
+
+    #[test]
+    fn test_unbreak_errors() {
+        check_stmt_err("1:1: In call to UNBREAK: expected [line%]", "UNBREAK 1, 2");
+        check_stmt_err("1:1: In call to UNBREAK: 1:9: No breakpoint at line 5", "UNBREAK 5");
+    }
+
+    #[test]
+    fn test_run_break_cont() {
+        let program = "a = 1\nPRINT a\na = 2\nPRINT a\nPRINT \"done\"";
+        let mut t = Tester::default().set_program(None, program);
+        t.run("BREAK 3: RUN")
+            .expect_clear()
+            .expect_prints([" 1", "Paused at line 3: a = 2"])
+            .expect_var("a", 1)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("CONT")
+            .expect_clear()
+            .expect_prints([" 1", "Paused at line 3: a = 2", " 2", "done"])
+            .expect_var("a", 2)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("CONT")
+            .expect_err("1:1: In call to CONT: No paused program")
+            .expect_clear()
+            .expect_prints([" 1", "Paused at line 3: a = 2", " 2", "done"])
+            .expect_var("a", 2)
+            .expect_program(None as Option<&str>, program)
+            .check();
+    }
+
+    #[test]
+    fn test_run_break_step() {
+        let program = "a = 1\nIF a = 1 THEN\n    a = 5\nEND IF\nEND 3";
+        let mut t = Tester::default().set_program(None, program);
+        t.run("BREAK 2: RUN")
+            .expect_clear()
+            .expect_prints(["Paused at line 2: IF a = 1 THEN"])
+            .expect_var("a", 1)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("STEP")
+            .expect_clear()
+            .expect_prints(["Paused at line 2: IF a = 1 THEN", "Paused at line 3: a = 5"])
+            .expect_var("a", 1)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("STEP")
+            .expect_clear()
+            .expect_prints([
+                "Paused at line 2: IF a = 1 THEN",
+                "Paused at line 3: a = 5",
+                "Paused at line 5: END 3",
+            ])
+            .expect_var("a", 5)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("STEP")
+            .expect_clear()
+            .expect_prints([
+                "Paused at line 2: IF a = 1 THEN",
+                "Paused at line 3: a = 5",
+                "Paused at line 5: END 3",
+                "Program exited with code 3",
+            ])
+            .expect_var("a", 5)
+            .expect_program(None as Option<&str>, program)
+            .check();
+    }
+
+    #[test]
+    fn test_run_break_cont_after_new_data() {
+        let program = "a = 1\nREAD a\nPRINT a\nDATA 3";
+        let mut t = Tester::default().set_program(None, program);
+        t.run("BREAK 2: RUN")
+            .expect_clear()
+            .expect_prints(["Paused at line 2: READ a"])
+            .expect_var("a", 1)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("DATA 5: CONT")
+            .expect_uncatchable_err("Cannot continue after new DATA")
+            .expect_clear()
+            .expect_prints(["Paused at line 2: READ a"])
+            .expect_var("a", 1)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("CONT")
+            .expect_clear()
+            .expect_prints(["Paused at line 2: READ a", " 3"])
+            .expect_var("a", 3)
+            .expect_program(None as Option<&str>, program)
+            .check();
+    }
+
+    #[test]
+    fn test_run_discards_paused_program() {
+        let program = "a = 1\na = 2";
+        let mut t = Tester::default().set_program(None, program);
+        t.run("BREAK 2: RUN: UNBREAK: RUN")
+            .expect_clear()
+            .expect_prints(["Paused at line 2: a = 2"])
+            .expect_clear()
+            .expect_var("a", 2)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("CONT")
+            .expect_err("1:1: In call to CONT: No paused program")
+            .expect_clear()
+            .expect_prints(["Paused at line 2: a = 2"])
+            .expect_clear()
+            .expect_var("a", 2)
+            .expect_program(None as Option<&str>, program)
+            .check();
+    }
+
+    #[test]
+    fn test_cont_step_errors() {
+        check_stmt_err("1:1: In call to CONT: No paused program", "CONT");
+        check_stmt_err("1:1: In call to STEP: No paused program", "STEP");
+        check_stmt_err("1:1: In call to CONT: expected no arguments", "CONT 1");
+        check_stmt_err("1:1: In call to STEP: expected no arguments", "STEP 1");
+    }
+
+    /// Returns a clock for the profiler that advances by one millisecond every time it is queried.
+    fn fake_clock() -> ClockFn {
+        let now = Cell::new(Duration::default());
+        Rc::from(move || {
+            now.set(now.get() + Duration::from_millis(1));
+            now.get()
+        })
+    }
+
+    /// Creates a tester with just the commands needed to exercise the profiler with a fake clock.
+    fn profile_tester() -> Tester {
+        let tester = Tester::empty();
+        let console = tester.get_console();
+        let program = tester.get_program();
+        let storage = tester.get_storage();
+        tester
+            .add_command(ProfileCommand::new(console.clone(), program.clone(), Some(fake_clock())))
+            .add_command(RunCommand::new(console, storage, program))
+    }
+
+    #[test]
+    fn test_profile_ok() {
+        let program = "a = 0\nFOR i = 1 TO 2\n    a = a + 1\nNEXT";
+        profile_tester()
+            .set_program(None, program)
+            .run("PROFILE SHOW")
+            .expect_prints(["No profiling data"])
+            .expect_program(None as Option<&str>, program)
+            .check();
+
+        let mut t = profile_tester().set_program(None, program);
+        t.run("PROFILE ON: RUN: PROFILE OFF: PROFILE SHOW")
+            .expect_prints([
+                "  LINE      COUNT    TIME (MS)  SOURCE",
+                "     2          3        8.000  FOR i = 1 TO 2",
+                "     3          2        2.000  a = a + 1",
+                "     1          1        1.000  a = 0",
+            ])
+            .expect_var("a", 2)
+            .expect_var("i", 3)
+            .expect_program(None as Option<&str>, program)
+            .check();
+        t.run("profile on: profile show")
+            .expect_prints([
+                "  LINE      COUNT    TIME (MS)  SOURCE",
+                "     2          3        8.000  FOR i = 1 TO 2",
+                "     3          2        2.000  a = a + 1",
+                "     1          1        1.000  a = 0",
+                "No profiling data",
+            ])
+            .expect_var("a", 2)
+            .expect_var("i", 3)
+            .expect_program(None as Option<&str>, program)
+            .check();
+    }
+
+    #[test]
+    fn test_profile_errors() {
+        check_stmt_err("1:1: In call to PROFILE: expected <ON|OFF|SHOW>", "PROFILE");
+        check_stmt_err("1:1: In call to PROFILE: expected <ON|OFF|SHOW>", "PROFILE ON, OFF");
+        check_stmt_err("1:1: In call to PROFILE: expected <ON|OFF|SHOW>", "PROFILE TRUE");
+        check_stmt_err("1:1: In call to PROFILE: expected <ON|OFF|SHOW>", "PROFILE 1");
+        check_stmt_err(
+            "1:1: In call to PROFILE: 1:9: Unknown PROFILE operation FOO",
+            "PROFILE FOO",
+        );
+        check_stmt_err(
+            "1:1: In call to PROFILE: 1:9: Unknown PROFILE operation SHOW$",
+            "PROFILE SHOW$",
+        );
+    }
+
+    #[test]
+    fn test_tron_troff() {
+        let program = "a = 0\nWHILE a < 2\n    a = a + 1: PRINT a\nWEND";
+        let mut t = Tester::default().set_program(None, program);
+        t.run("TRON: RUN")
+            .expect_clear()
+            .expect_prints([
+                "[1] a = 0",
+                "[2] WHILE a < 2",
+                "[3] a = a + 1: PRINT a",
+                " 1",
+                "[2] WHILE a < 2",
+                "[3] a = a + 1: PRINT a",
+                " 2",
+                "[2] WHILE a < 2",
+            ])
+            .expect_var("a", 2)
+            .expect_program(None as Option<&str>, program)
+            .check();
+
+        let mut t = Tester::default().set_program(None, program);
+        t.run("TRON: TROFF: RUN")
+            .expect_clear()
+            .expect_prints([" 1", " 2"])
+            .expect_var("a", 2)
+            .expect_program(None as Option<&str>, program)
+            .check();
+    }
+
+    #[test]
+    fn test_tron_does_not_trace_immediate_commands() {
+        Tester::default().run("TRON\na = 1\nb = 2").expect_var("a", 1).expect_var("b", 2).check();
+    }
+
+    #[test]
+    fn test_tron_troff_errors() {
+        check_stmt_err("1:1: In call to TRON: expected no arguments", "TRON 1");
+        check_stmt_err("1:1: In call to TROFF: expected no arguments", "TROFF 1");
+    }
+
+    #[test]
+    fn test_vars() {
+        Tester::default().run("VARS").check();
+
+        Tester::default()
+            .run("b = TRUE: a$ = \"x\": DIM m(2, 3) AS DOUBLE: c# = 1.5: VARS")
+            .expect_var("a", "x")
+            .expect_var("b", true)
+            .expect_var("c", 1.5)
+            .expect_array("m", VarType::Double, &[2, 3], vec![])
+            .expect_prints(["A$ = \"x\"", "B? = TRUE", "C# = 1.5", "M(2, 3) AS DOUBLE"])
+            .check();
+    }
+
+    #[test]
+    fn test_vars_errors() {
+        check_stmt_err("1:1: In call to VARS: expected no arguments", "VARS 1");
+    }
+}
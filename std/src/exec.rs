@@ -17,13 +17,14 @@
 
 use async_trait::async_trait;
 use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, FunctionCallSpan, Value, VarType};
-use endbasic_core::exec::Machine;
+use endbasic_core::exec::{ClockFn, Machine};
 use endbasic_core::syms::{
     CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
     FunctionResult, Symbol, Symbols,
 };
 use endbasic_core::LineCol;
 use futures_lite::future::{BoxedLocal, FutureExt};
+use std::cell::Cell;
 use std::rc::Rc;
 use std::thread;
 use std::time::Duration;
@@ -31,6 +32,84 @@ use std::time::Duration;
 /// Category description for all symbols provided by this module.
 pub(crate) const CATEGORY: &str = "Interpreter";
 
+/// The `ASSERT` command.
+pub struct AssertCommand {
+    metadata: CallableMetadata,
+}
+
+impl AssertCommand {
+    /// Creates a new `ASSERT` command that stops execution when a condition does not hold.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("ASSERT", VarType::Void)
+                .with_syntax("condition?[, message$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Verifies that a condition holds.
+If the condition evaluates to FALSE, raises an error that stops the program.  The error contains \
+the optional message, which is useful to explain what went wrong.  For example:
+    ASSERT total = 10, \"The total should be 10\"
+Assertions are the building block of tests: see RUNTESTS to run all the tests in the stored \
+program.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for AssertCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (cond_expr, message_expr) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(cond), sep: ArgSep::End, .. }] => (cond, None),
+            [ArgSpan { expr: Some(cond), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(message), sep: ArgSep::End, .. }] => {
+                (cond, Some(message))
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let cond = match cond_expr.eval(machine.get_mut_symbols()).await? {
+            Value::Boolean(b) => b,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    cond_expr.start_pos(),
+                    "ASSERT requires a boolean condition".to_owned(),
+                ))
+            }
+        };
+
+        let message = match message_expr {
+            Some(expr) => match expr.eval(machine.get_mut_symbols()).await? {
+                Value::Text(t) => Some(t),
+                _ => {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "ASSERT requires a string as the message".to_owned(),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        if cond {
+            return Ok(());
+        }
+        match message {
+            Some(message) => Err(CallError::InternalError(
+                cond_expr.start_pos(),
+                format!("Assertion failed: {}", message),
+            )),
+            None => {
+                Err(CallError::InternalError(cond_expr.start_pos(), "Assertion failed".to_owned()))
+            }
+        }
+    }
+}
+
 /// The `CLEAR` command.
 pub struct ClearCommand {
     metadata: CallableMetadata,
@@ -179,22 +258,236 @@ impl Command for SleepCommand {
     }
 }
 
+/// The `TICKS` function.
+pub struct TicksFunction {
+    metadata: CallableMetadata,
+    clock: ClockFn,
+    origin: Duration,
+}
+
+impl TicksFunction {
+    /// Creates a new instance of the function that measures time with `clock` since now.
+    pub fn new(clock: ClockFn) -> Rc<Self> {
+        let origin = clock();
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("TICKS", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the number of milliseconds since the interpreter started.
+The counter wraps around to 0 after about 24 days.  Use TIMER instead if you need sub-millisecond \
+resolution.",
+                )
+                .build(),
+            clock,
+            origin,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for TicksFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let elapsed = (self.clock)().saturating_sub(self.origin);
+        let ms = elapsed.as_millis() % (i32::MAX as u128 + 1);
+        Ok(Value::Integer(ms as i32))
+    }
+}
+
+/// The `TIMER` function.
+pub struct TimerFunction {
+    metadata: CallableMetadata,
+    clock: ClockFn,
+    origin: Duration,
+}
+
+impl TimerFunction {
+    /// Creates a new instance of the function that measures time with `clock` since now.
+    pub fn new(clock: ClockFn) -> Rc<Self> {
+        let origin = clock();
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("TIMER", VarType::Double)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the number of seconds since the interpreter started.
+The returned value has sub-millisecond resolution, which makes it suitable to measure how long \
+parts of a program take to run.  For example:
+    start = TIMER: GOSUB @work: PRINT \"Took\"; TIMER - start; \"seconds\"",
+                )
+                .build(),
+            clock,
+            origin,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for TimerFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let elapsed = (self.clock)().saturating_sub(self.origin);
+        Ok(Value::Double(elapsed.as_secs_f64()))
+    }
+}
+
+/// The `WAITFRAME` command.
+pub struct WaitFrameCommand {
+    metadata: CallableMetadata,
+    clock: ClockFn,
+    sleep_fn: SleepFn,
+    last_frame: Cell<Option<Duration>>,
+}
+
+impl WaitFrameCommand {
+    /// Creates a new instance of the command that measures time with `clock` and pauses execution
+    /// with `sleep_fn`.
+    pub fn new(clock: ClockFn, sleep_fn: SleepFn) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("WAITFRAME", VarType::Void)
+                .with_syntax("fps<%|#>")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Waits until it is time to draw the next frame of an animation.
+Suspends program execution for as long as necessary so that consecutive calls to this command \
+happen fps times per second, which keeps animations running at a steady frame rate regardless of \
+how long it takes to draw each frame.  For example:
+    DO: GOSUB @draw: WAITFRAME 30: LOOP
+If drawing a frame takes longer than the time available for it, this returns immediately and the \
+next frame is timed from this point on instead of trying to catch up.",
+                )
+                .build(),
+            clock,
+            sleep_fn,
+            last_frame: Cell::new(None),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for WaitFrameCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (period, pos) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => {
+                let value = expr.eval(machine.get_mut_symbols()).await?;
+                let fps = value
+                    .as_f64()
+                    .map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+                if !fps.is_finite() || fps <= 0.0 {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "Frame rate must be positive".to_owned(),
+                    ));
+                }
+                (Duration::from_secs_f64(1.0 / fps), expr.start_pos())
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let now = (self.clock)();
+        let next_frame = match self.last_frame.get() {
+            Some(last_frame) => last_frame.saturating_add(period),
+            None => now,
+        };
+        if next_frame > now {
+            self.last_frame.set(Some(next_frame));
+            (self.sleep_fn)(next_frame - now, pos).await
+        } else {
+            self.last_frame.set(Some(now));
+            Ok(())
+        }
+    }
+}
+
 /// Instantiates all REPL commands and adds them to the `machine`.
 ///
 /// `sleep_fn` is an async function that implements a pause given a `Duration`.  If not provided,
 /// uses the `std::thread::sleep` function.
-pub fn add_all(machine: &mut Machine, sleep_fn: Option<SleepFn>) {
+///
+/// `clock_fn` is the function used to measure time.  If not provided, uses the system's clock.
+pub fn add_all(machine: &mut Machine, sleep_fn: Option<SleepFn>, clock_fn: Option<ClockFn>) {
+    let sleep_fn: Rc<SleepFn> = Rc::from(sleep_fn.unwrap_or_else(|| Box::from(system_sleep)));
+    let clock_fn = clock_fn.unwrap_or_else(crate::storage::system_clock);
+
+    machine.add_command(AssertCommand::new());
     machine.add_command(ClearCommand::new());
     machine.add_function(ErrmsgFunction::new());
-    machine.add_command(SleepCommand::new(sleep_fn.unwrap_or_else(|| Box::from(system_sleep))));
+    machine.add_command(SleepCommand::new({
+        let sleep_fn = sleep_fn.clone();
+        Box::from(move |d, pos| (sleep_fn)(d, pos))
+    }));
+    machine.add_function(TicksFunction::new(clock_fn.clone()));
+    machine.add_function(TimerFunction::new(clock_fn.clone()));
+    machine
+        .add_command(WaitFrameCommand::new(clock_fn, Box::from(move |d, pos| (sleep_fn)(d, pos))));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::testutils::*;
+    use std::cell::RefCell;
     use std::time::Instant;
 
+    #[test]
+    fn test_assert_ok() {
+        Tester::default().run("ASSERT TRUE").check();
+        Tester::default().run("a = 3: ASSERT a = 3, \"Not reached\"").expect_var("a", 3).check();
+    }
+
+    #[test]
+    fn test_assert_fails() {
+        check_stmt_err("1:1: In call to ASSERT: 1:8: Assertion failed", "ASSERT FALSE");
+        check_stmt_err(
+            "1:1: In call to ASSERT: 1:8: Assertion failed: Must be 4",
+            "ASSERT 2 + 2 = 5, \"Must be \" + \"4\"",
+        );
+
+        Tester::default()
+            .run("ON ERROR RESUME NEXT: ASSERT FALSE, \"Caught\": PRINT ERRMSG")
+            .expect_var("0ERRMSG", "1:23: In call to ASSERT: 1:30: Assertion failed: Caught")
+            .expect_prints(["1:23: In call to ASSERT: 1:30: Assertion failed: Caught"])
+            .check();
+    }
+
+    #[test]
+    fn test_assert_errors() {
+        check_stmt_err("1:1: In call to ASSERT: expected condition?[, message$]", "ASSERT");
+        check_stmt_err(
+            "1:1: In call to ASSERT: expected condition?[, message$]",
+            "ASSERT TRUE; \"a\"",
+        );
+        check_stmt_err(
+            "1:1: In call to ASSERT: expected condition?[, message$]",
+            "ASSERT TRUE, \"a\", \"b\"",
+        );
+        check_stmt_err(
+            "1:1: In call to ASSERT: 1:8: ASSERT requires a boolean condition",
+            "ASSERT 1",
+        );
+        check_stmt_err(
+            "1:1: In call to ASSERT: 1:14: ASSERT requires a string as the message",
+            "ASSERT TRUE, 3",
+        );
+    }
+
     #[test]
     fn test_clear_ok() {
         Tester::default().run("a = 1: CLEAR").expect_clear().check();
@@ -281,4 +574,95 @@ mod tests {
         check_stmt_err("1:1: In call to SLEEP: 1:7: Sleep time must be positive", "SLEEP -1");
         check_stmt_err("1:1: In call to SLEEP: 1:7: Sleep time must be positive", "SLEEP -0.001");
     }
+
+    /// Returns a fake clock that reports the time stored in the returned cell.
+    fn fake_clock(now: Duration) -> (ClockFn, Rc<Cell<Duration>>) {
+        let now = Rc::from(Cell::new(now));
+        let clock: ClockFn = {
+            let now = now.clone();
+            Rc::from(move || now.get())
+        };
+        (clock, now)
+    }
+
+    #[test]
+    fn test_ticks_and_timer_ok() {
+        let (clock, now) = fake_clock(Duration::from_secs(5));
+        let mut t = Tester::empty()
+            .add_function(TicksFunction::new(clock.clone()))
+            .add_function(TimerFunction::new(clock));
+        t.run("k = TICKS: s = TIMER").expect_var("k", 0).expect_var("s", 0.0).check();
+
+        now.set(Duration::from_millis(6250));
+        t.run("k = TICKS: s = TIMER").expect_var("k", 1250).expect_var("s", 1.25).check();
+    }
+
+    #[test]
+    fn test_ticks_wraps_around() {
+        let (clock, now) = fake_clock(Duration::ZERO);
+        let mut t = Tester::empty().add_function(TicksFunction::new(clock));
+        now.set(Duration::from_millis(i32::MAX as u64 + 8));
+        t.run("k = TICKS").expect_var("k", 7).check();
+    }
+
+    #[test]
+    fn test_ticks_and_timer_errors() {
+        check_expr_error(
+            "1:10: In call to TICKS: expected no arguments nor parenthesis",
+            r#"TICKS()"#,
+        );
+        check_expr_error(
+            "1:10: In call to TIMER: expected no arguments nor parenthesis",
+            r#"TIMER(3)"#,
+        );
+    }
+
+    #[test]
+    fn test_waitframe_ok() {
+        let (clock, now) = fake_clock(Duration::ZERO);
+        let sleeps = Rc::from(RefCell::from(vec![]));
+        let sleep_fake: SleepFn = {
+            let now = now.clone();
+            let sleeps = sleeps.clone();
+            Box::from(move |d: Duration, _pos: LineCol| -> BoxedLocal<CommandResult> {
+                now.set(now.get() + d);
+                sleeps.borrow_mut().push(d);
+                async move { Ok(()) }.boxed_local()
+            })
+        };
+
+        let mut t = Tester::empty().add_command(WaitFrameCommand::new(clock, sleep_fake));
+        t.run("WAITFRAME 10: WAITFRAME 10").check();
+        assert_eq!([Duration::from_millis(100)], sleeps.borrow().as_slice());
+
+        now.set(now.get() + Duration::from_millis(30));
+        t.run("WAITFRAME 10.0").check();
+        assert_eq!(Duration::from_millis(70), sleeps.borrow()[1]);
+
+        // A frame that took too long to draw does not cause the next ones to catch up.
+        now.set(now.get() + Duration::from_millis(500));
+        t.run("WAITFRAME 10: WAITFRAME 20").check();
+        assert_eq!(
+            [Duration::from_millis(100), Duration::from_millis(70), Duration::from_millis(50)],
+            sleeps.borrow().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_waitframe_errors() {
+        check_stmt_err("1:1: In call to WAITFRAME: expected fps<%|#>", "WAITFRAME");
+        check_stmt_err("1:1: In call to WAITFRAME: expected fps<%|#>", "WAITFRAME 2, 3");
+        check_stmt_err(
+            "1:1: In call to WAITFRAME: 1:11: \"foo\" is not a number",
+            "WAITFRAME \"foo\"",
+        );
+        check_stmt_err(
+            "1:1: In call to WAITFRAME: 1:11: Frame rate must be positive",
+            "WAITFRAME 0",
+        );
+        check_stmt_err(
+            "1:1: In call to WAITFRAME: 1:11: Frame rate must be positive",
+            "WAITFRAME -5",
+        );
+    }
 }
@@ -0,0 +1,1972 @@
+// EndBASIC
+// Copyright 2021 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Commands for graphical console interaction.
+
+use crate::console::{parse_color, rgb_to_ansi_color, Console, PixelsXY, NUM_PAGES};
+use async_trait::async_trait;
+use endbasic_core::ast::{
+    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarType,
+};
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
+    FunctionResult, Symbols,
+};
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+pub mod raster;
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Graphics
+The EndBASIC console overlays text and graphics in the same canvas.  The consequence of this \
+design choice is that the console has two coordinate systems: the character-based system, used by
+the commands described in HELP \"CONSOLE\", and the pixel-based system, used by the commands \
+described in this section.";
+
+/// Parses an expression that represents a single coordinate.
+async fn parse_coordinate(expr: &Expr, symbols: &mut Symbols) -> Result<i16, CallError> {
+    let value = expr.eval(symbols).await?;
+    let i =
+        value.as_i32().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    match i16::try_from(i) {
+        Ok(i) => Ok(i),
+        Err(_) => Err(CallError::ArgumentError(
+            expr.start_pos(),
+            format!("Coordinate {} out of range", i),
+        )),
+    }
+}
+
+/// Parses a pair of expressions that represent an (x,y) coordinate pair.
+async fn parse_coordinates(
+    xexpr: &Expr,
+    yexpr: &Expr,
+    machine: &mut Machine,
+) -> Result<PixelsXY, CallError> {
+    Ok(PixelsXY {
+        x: parse_coordinate(xexpr, machine.get_mut_symbols()).await?,
+        y: parse_coordinate(yexpr, machine.get_mut_symbols()).await?,
+    })
+}
+
+/// Parses an expression that represents a radius.
+async fn parse_radius(expr: &Expr, machine: &mut Machine) -> Result<u16, CallError> {
+    let value = expr.eval(machine.get_mut_symbols()).await?;
+    let i =
+        value.as_i32().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    match u16::try_from(i) {
+        Ok(i) => Ok(i),
+        Err(_) if i < 0 => Err(CallError::ArgumentError(
+            expr.start_pos(),
+            format!("Radius {} must be positive", i),
+        )),
+        Err(_) => {
+            Err(CallError::ArgumentError(expr.start_pos(), format!("Radius {} out of range", i)))
+        }
+    }
+}
+
+/// Parses an expression that represents an angle in degrees.
+async fn parse_angle(expr: &Expr, machine: &mut Machine) -> Result<i32, CallError> {
+    let value = expr.eval(machine.get_mut_symbols()).await?;
+    value.as_i32().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))
+}
+
+/// Parses a sequence of arguments that represent the (x,y) vertices of a polygon.
+async fn parse_polygon(
+    args: &[ArgSpan],
+    machine: &mut Machine,
+) -> Result<Vec<PixelsXY>, CallError> {
+    if args.len() < 6 || !args.len().is_multiple_of(2) {
+        return Err(CallError::SyntaxError);
+    }
+
+    let mut points = Vec::with_capacity(args.len() / 2);
+    for (i, pair) in args.chunks_exact(2).enumerate() {
+        let last = i == args.len() / 2 - 1;
+        match pair {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep, .. }]
+                if (last && *sep == ArgSep::End) || (!last && *sep == ArgSep::Long) =>
+            {
+                points.push(parse_coordinates(x, y, machine).await?);
+            }
+            _ => return Err(CallError::SyntaxError),
+        }
+    }
+    Ok(points)
+}
+
+/// Parses an expression that represents a drawing page.
+async fn parse_page(expr: &Expr, machine: &mut Machine) -> Result<u8, CallError> {
+    let value = expr.eval(machine.get_mut_symbols()).await?;
+    let i =
+        value.as_i32().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    match u8::try_from(i) {
+        Ok(page) if page < NUM_PAGES => Ok(page),
+        _ => Err(CallError::ArgumentError(expr.start_pos(), format!("Page {} out of range", i))),
+    }
+}
+
+/// The `GFX_ARC` command.
+pub struct GfxArcCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxArcCommand {
+    /// Creates a new `GFX_ARC` command that draws the arc of a circle on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_ARC", VarType::Void)
+                .with_syntax("x%, y%, r%, start%, end%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws the arc of a circle of radius r centered at (x,y).
+The arc goes counterclockwise from the start angle to the end angle, both expressed in degrees \
+regardless of the mode selected by DEG and RAD.  An angle of 0 points to the right of the center \
+and an angle of 90 points up.
+The arc is drawn using the foreground color as selected by COLOR.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxArcCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (xy, r, start, end) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(r), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(start), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(end), sep: ArgSep::End, .. }] => {
+                (
+                    parse_coordinates(x, y, machine).await?,
+                    parse_radius(r, machine).await?,
+                    parse_angle(start, machine).await?,
+                    parse_angle(end, machine).await?,
+                )
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_arc(xy, r, start, end)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_CIRCLE` command.
+pub struct GfxCircleCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxCircleCommand {
+    /// Creates a new `GFX_CIRCLE` command that draws an empty circle on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_CIRCLE", VarType::Void)
+                .with_syntax("x%, y%, r%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a circle of radius r centered at (x,y).
+The outline of the circle is drawn using the foreground color as selected by COLOR and the \
+area of the circle is left untouched.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxCircleCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (xy, r) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(r), sep: ArgSep::End, .. }] => {
+                (parse_coordinates(x, y, machine).await?, parse_radius(r, machine).await?)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_circle(xy, r)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_CIRCLEF` command.
+pub struct GfxCirclefCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxCirclefCommand {
+    /// Creates a new `GFX_CIRCLEF` command that draws a filled circle on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_CIRCLEF", VarType::Void)
+                .with_syntax("x%, y%, r%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a filled circle of radius r centered at (x,y).
+The outline and area of the circle are drawn using the foreground color as selected by COLOR.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxCirclefCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (xy, r) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(r), sep: ArgSep::End, .. }] => {
+                (parse_coordinates(x, y, machine).await?, parse_radius(r, machine).await?)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_circle_filled(xy, r)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_CLIP` command.
+pub struct GfxClipCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxClipCommand {
+    /// Creates a new `GFX_CLIP` command that constrains graphical output on the `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_CLIP", VarType::Void)
+                .with_syntax("[x1%, y1%, x2%, y2%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Constrains graphical output to a rectangular area.
+When given the corners (x1%,y1%) and (x2%,y2%) of a rectangle, all further graphics commands only \
+draw within that rectangle, including its borders, and leave the rest of the console untouched.  \
+The coordinates of the graphics commands remain relative to the top-left corner of the console.  \
+This is useful to implement split-screen views or scrollable areas without having to check the \
+bounds of every shape.
+With no arguments, removes the constraint so that graphics commands can draw anywhere again.
+Text output is never constrained by this command.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxClipCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let area = match span.args.as_slice() {
+            [] => None,
+            [ArgSpan { expr: Some(x1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(x2), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y2), sep: ArgSep::End, .. }] =>
+            {
+                let x1y1 = parse_coordinates(x1, y1, machine).await?;
+                let x2y2 = parse_coordinates(x2, y2, machine).await?;
+                Some((
+                    PixelsXY { x: x1y1.x.min(x2y2.x), y: x1y1.y.min(x2y2.y) },
+                    PixelsXY { x: x1y1.x.max(x2y2.x), y: x1y1.y.max(x2y2.y) },
+                ))
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().set_clip(area)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_ELLIPSE` command.
+pub struct GfxEllipseCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxEllipseCommand {
+    /// Creates a new `GFX_ELLIPSE` command that draws an empty ellipse on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_ELLIPSE", VarType::Void)
+                .with_syntax("x%, y%, rx%, ry%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws an ellipse of radii rx and ry centered at (x,y).
+rx is the horizontal radius and ry is the vertical radius.
+The outline of the ellipse is drawn using the foreground color as selected by COLOR and the \
+area of the ellipse is left untouched.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxEllipseCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (xy, rx, ry) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(rx), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(ry), sep: ArgSep::End, .. }] => {
+                (
+                    parse_coordinates(x, y, machine).await?,
+                    parse_radius(rx, machine).await?,
+                    parse_radius(ry, machine).await?,
+                )
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_ellipse(xy, rx, ry)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_ELLIPSEF` command.
+pub struct GfxEllipsefCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxEllipsefCommand {
+    /// Creates a new `GFX_ELLIPSEF` command that draws a filled ellipse on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_ELLIPSEF", VarType::Void)
+                .with_syntax("x%, y%, rx%, ry%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a filled ellipse of radii rx and ry centered at (x,y).
+rx is the horizontal radius and ry is the vertical radius.
+The outline and area of the ellipse are drawn using the foreground color as selected by COLOR.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxEllipsefCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (xy, rx, ry) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(rx), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(ry), sep: ArgSep::End, .. }] => {
+                (
+                    parse_coordinates(x, y, machine).await?,
+                    parse_radius(rx, machine).await?,
+                    parse_radius(ry, machine).await?,
+                )
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_ellipse_filled(xy, rx, ry)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_FILL` command.
+pub struct GfxFillCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxFillCommand {
+    /// Creates a new `GFX_FILL` command that flood-fills an area of `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_FILL", VarType::Void)
+                .with_syntax("x%, y%[, border%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Fills the area that contains (x,y).
+The area is painted using the foreground color as selected by COLOR.
+If border% is not specified, the area is made of all pixels connected to (x,y) that have the \
+same color as (x,y).  If border% is specified, the area extends from (x,y) in all directions \
+until it hits pixels of the border% color, which is given as a color number like in COLOR.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxFillCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (xy, border) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::End, .. }] => {
+                (parse_coordinates(x, y, machine).await?, None)
+            }
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(border), sep: ArgSep::End, .. }] =>
+            {
+                let xy = parse_coordinates(x, y, machine).await?;
+                let border = parse_color(border, machine.get_mut_symbols()).await?;
+                (xy, Some(border.to_rgb()))
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().flood_fill(xy, border)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_FLIP` command.
+pub struct GfxFlipCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxFlipCommand {
+    /// Creates a new `GFX_FLIP` command that makes the contents of a page of `console` visible.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_FLIP", VarType::Void)
+                .with_syntax("page%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Copies the contents of a page onto the visible page.
+This makes whatever was drawn on the off-screen page% visible at once.  The copied page retains \
+its contents so it can be drawn on again.  See GFX_PAGE for details on pages.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxFlipCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let page = match span.args.as_slice() {
+            [ArgSpan { expr: Some(page), sep: ArgSep::End, .. }] => {
+                parse_page(page, machine).await?
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().flip_page(page)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_FULLSCREEN` command.
+pub struct GfxFullscreenCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxFullscreenCommand {
+    /// Creates a new `GFX_FULLSCREEN` command that toggles full screen mode on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_FULLSCREEN", VarType::Void)
+                .with_syntax("enabled?")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Enables or disables full screen mode.
+The size of the console may change as a result of this, which also happens if the window that \
+hosts the console is resized.  Use GFX_WIDTH and GFX_HEIGHT to query the new size in pixels.  \
+Off-screen pages are discarded when the size changes.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxFullscreenCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [ArgSpan { expr: Some(b), sep: ArgSep::End, .. }] => {
+                match b.eval(machine.get_mut_symbols()).await? {
+                    Value::Boolean(b) => {
+                        self.console.borrow_mut().set_fullscreen(b)?;
+                        Ok(())
+                    }
+                    _ => Err(CallError::ArgumentError(
+                        b.start_pos(),
+                        "Argument to GFX_FULLSCREEN must be a boolean".to_owned(),
+                    )),
+                }
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `GFX_GETPIXEL` function.
+pub struct GfxGetpixelFunction {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxGetpixelFunction {
+    /// Creates a new instance of the function.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_GETPIXEL", VarType::Integer)
+                .with_syntax("x%, y%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the color of the pixel at (x,y).
+The color is returned as an ANSI number like the ones accepted by COLOR.  If the pixel does not \
+exactly match any of these colors, the closest one is returned, and if more than one number \
+represents the same color, the lowest number is returned.
+Returns -1 if (x,y) lies outside of the graphical console.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for GfxGetpixelFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let xy = match span.args.as_slice() {
+            [x, y] => PixelsXY {
+                x: parse_coordinate(x, symbols).await?,
+                y: parse_coordinate(y, symbols).await?,
+            },
+            _ => return Err(CallError::SyntaxError),
+        };
+        match self.console.borrow().read_pixel(xy)? {
+            Some(rgb) => Ok(Value::Integer(i32::from(rgb_to_ansi_color(rgb)))),
+            None => Ok(Value::Integer(-1)),
+        }
+    }
+}
+
+/// The `GFX_HEIGHT` function.
+pub struct GfxHeightFunction {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxHeightFunction {
+    /// Creates a new instance of the function.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_HEIGHT", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the height in pixels of the graphical console.
+See GFX_WIDTH to query the other dimension.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for GfxHeightFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let size = self.console.borrow().size_pixels()?;
+        Ok(Value::Integer(i32::from(size.height)))
+    }
+}
+
+/// The `GFX_LINE` command.
+pub struct GfxLineCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxLineCommand {
+    /// Creates a new `GFX_LINE` command that draws a line on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_LINE", VarType::Void)
+                .with_syntax("x1%, y1%, x2%, y2%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a line from (x1,y1) to (x2,y2).
+The line is drawn using the foreground color as selected by COLOR.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxLineCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (x1y1, x2y2) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(x2), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y2), sep: ArgSep::End, .. }] => {
+                (
+                    parse_coordinates(x1, y1, machine).await?,
+                    parse_coordinates(x2, y2, machine).await?,
+                )
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_line(x1y1, x2y2)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_PAGE` command.
+pub struct GfxPageCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxPageCommand {
+    /// Creates a new `GFX_PAGE` command that selects the drawing page of `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_PAGE", VarType::Void)
+                .with_syntax("page%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Selects the page on which all further output is drawn.
+Page 0 is the visible page and is the default.  Pages 1 to 3 are off-screen pages of the same \
+size as the visible page, which start blank.  Drawing on an off-screen page does not change what \
+is on the screen until the page is copied onto the visible page with GFX_FLIP.  This is useful \
+to render animations without flickering: draw each frame on an off-screen page and then flip it.
+Text printed while an off-screen page is selected is drawn on that page as well.
+The visible page is selected again when the program finishes.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxPageCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let page = match span.args.as_slice() {
+            [ArgSpan { expr: Some(page), sep: ArgSep::End, .. }] => {
+                parse_page(page, machine).await?
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().set_draw_page(page)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_PIXEL` command.
+pub struct GfxPixelCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxPixelCommand {
+    /// Creates a new `GFX_PIXEL` command that draws a single pixel on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_PIXEL", VarType::Void)
+                .with_syntax("x%, y%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a pixel at (x,y).
+The pixel is drawn using the foreground color as selected by COLOR.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxPixelCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let xy = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::End, .. }] => {
+                parse_coordinates(x, y, machine).await?
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_pixel(xy)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_POLY` command.
+pub struct GfxPolyCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxPolyCommand {
+    /// Creates a new `GFX_POLY` command that draws an empty polygon on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_POLY", VarType::Void)
+                .with_syntax("x1%, y1%, x2%, y2%, x3%, y3%[, .., xn%, yn%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a polygon with vertices at (x1,y1), (x2,y2), etc.
+The polygon is closed by connecting the last vertex with the first one.
+The outline of the polygon is drawn using the foreground color as selected by COLOR and the \
+area of the polygon is left untouched.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxPolyCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let points = parse_polygon(&span.args, machine).await?;
+        self.console.borrow_mut().draw_poly(&points)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_POLYF` command.
+pub struct GfxPolyfCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxPolyfCommand {
+    /// Creates a new `GFX_POLYF` command that draws a filled polygon on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_POLYF", VarType::Void)
+                .with_syntax("x1%, y1%, x2%, y2%, x3%, y3%[, .., xn%, yn%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a filled polygon with vertices at (x1,y1), (x2,y2), etc.
+The polygon is closed by connecting the last vertex with the first one.  For polygons whose \
+edges cross each other, areas are filled following the even-odd rule.
+The outline and area of the polygon are drawn using the foreground color as selected by COLOR.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxPolyfCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let points = parse_polygon(&span.args, machine).await?;
+        self.console.borrow_mut().draw_poly_filled(&points)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_RECT` command.
+pub struct GfxRectCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxRectCommand {
+    /// Creates a new `GFX_RECT` command that draws an empty rectangle on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_RECT", VarType::Void)
+                .with_syntax("x1%, y1%, x2%, y2%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a rectangle from (x1,y1) to (x2,y2).
+The outline of the rectangle is drawn using the foreground color as selected by COLOR and the \
+area of the rectangle is left untouched.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxRectCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (x1y1, x2y2) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(x2), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y2), sep: ArgSep::End, .. }] => {
+                (
+                    parse_coordinates(x1, y1, machine).await?,
+                    parse_coordinates(x2, y2, machine).await?,
+                )
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_rect(x1y1, x2y2)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_RECTF` command.
+pub struct GfxRectfCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxRectfCommand {
+    /// Creates a new `GFX_RECTF` command that draws a filled rectangle on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_RECTF", VarType::Void)
+                .with_syntax("x1%, y1%, x2%, y2%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Draws a filled rectangle from (x1,y1) to (x2,y2).
+The outline and area of the rectangle are drawn using the foreground color as selected by COLOR.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxRectfCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (x1y1, x2y2) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y1), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(x2), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y2), sep: ArgSep::End, .. }] => {
+                (
+                    parse_coordinates(x1, y1, machine).await?,
+                    parse_coordinates(x2, y2, machine).await?,
+                )
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.console.borrow_mut().draw_rect_filled(x1y1, x2y2)?;
+        Ok(())
+    }
+}
+
+/// The `GFX_SYNC` command.
+pub struct GfxSyncCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxSyncCommand {
+    /// Creates a new `GFX_SYNC` command that controls video syncing on `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_SYNC", VarType::Void)
+                .with_syntax("[enabled?]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Controls the video syncing flag and/or forces a sync.
+With no arguments, this command triggers a video sync without updating the video syncing flag.  \
+When enabled? is specified, this updates the video syncing flag accordingly and triggers a video \
+sync if enabled? is TRUE.
+When video syncing is enabled, all console commands immediately refresh the console.  This is \
+useful to see the effects of the commands right away, which is why this is the default mode in the \
+interpreter.  However, this is a *very* inefficient way of drawing.
+When video syncing is disabled, all console updates are buffered until video syncing is enabled \
+again.  This is perfect to draw complex graphics efficiently.  If this is what you want to do, \
+you should disable syncing first, render a frame, call GFX_SYNC to flush the frame, repeat until \
+you are done, and then enable video syncing again.  Note that the textual cursor is not visible \
+when video syncing is disabled.
+Some consoles can be configured to pace these explicit syncs to the refresh rate of the display, \
+in which case GFX_SYNC waits until the next frame is due.  This keeps animations running at a \
+steady speed without having to time them by hand.
+WARNING: Be aware that if you disable video syncing in the interactive interpreter, you will not \
+be able to see what you are typing any longer until you reenable video syncing.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxSyncCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [] => {
+                self.console.borrow_mut().sync_now()?;
+                Ok(())
+            }
+            [ArgSpan { expr: Some(b), sep: ArgSep::End, .. }] => {
+                match b.eval(machine.get_mut_symbols()).await? {
+                    Value::Boolean(b) => {
+                        let mut console = self.console.borrow_mut();
+                        if b {
+                            console.show_cursor()?;
+                        } else {
+                            console.hide_cursor()?;
+                        }
+                        console.set_sync(b)?;
+                        Ok(())
+                    }
+                    _ => Err(CallError::ArgumentError(
+                        b.start_pos(),
+                        "Argument to GFX_SYNC must be a boolean".to_owned(),
+                    )),
+                }
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `GFX_TEXT` command.
+pub struct GfxTextCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxTextCommand {
+    /// Creates a new `GFX_TEXT` command that renders text on the `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_TEXT", VarType::Void)
+                .with_syntax("x%, y%, msg$[, size%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Renders text at a pixel position.
+The top-left corner of msg$ is placed at (x%,y%), regardless of the character grid used by PRINT, \
+and the text is drawn over the existing contents of the console with the current foreground color \
+as set by COLOR.  The cursor does not move.
+size% is the size of the font in points.  If not specified, the text is rendered at the size of \
+the console font.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxTextCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (xexpr, yexpr, msgexpr, sizeexpr) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(msg), sep: ArgSep::End, .. }] => {
+                (x, y, msg, None)
+            }
+            [ArgSpan { expr: Some(x), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(y), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(msg), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(size), sep: ArgSep::End, .. }] => {
+                (x, y, msg, Some(size))
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let xy = parse_coordinates(xexpr, yexpr, machine).await?;
+
+        let msg = match msgexpr.eval(machine.get_mut_symbols()).await? {
+            Value::Text(msg) => msg,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    msgexpr.start_pos(),
+                    "Text to render must be a string".to_owned(),
+                ))
+            }
+        };
+
+        let size = match sizeexpr {
+            Some(expr) => {
+                let value = expr.eval(machine.get_mut_symbols()).await?;
+                let i = value
+                    .as_i32()
+                    .map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+                match u16::try_from(i) {
+                    Ok(size) if size > 0 => Some(size),
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            expr.start_pos(),
+                            format!("Font size {} out of range", i),
+                        ))
+                    }
+                }
+            }
+            None => None,
+        };
+
+        if !msg.is_empty() {
+            self.console.borrow_mut().draw_text(xy, &msg, size)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `GFX_TITLE` command.
+pub struct GfxTitleCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxTitleCommand {
+    /// Creates a new `GFX_TITLE` command that sets the window title of `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_TITLE", VarType::Void)
+                .with_syntax("title$")
+                .with_category(CATEGORY)
+                .with_description("Sets the title of the window that hosts the console.")
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GfxTitleCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [ArgSpan { expr: Some(title), sep: ArgSep::End, .. }] => {
+                match title.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(title) => {
+                        self.console.borrow_mut().set_title(&title)?;
+                        Ok(())
+                    }
+                    _ => Err(CallError::ArgumentError(
+                        title.start_pos(),
+                        "Argument to GFX_TITLE must be a string".to_owned(),
+                    )),
+                }
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `GFX_WIDTH` function.
+pub struct GfxWidthFunction {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+}
+
+impl GfxWidthFunction {
+    /// Creates a new instance of the function.
+    pub fn new(console: Rc<RefCell<dyn Console>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GFX_WIDTH", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the width in pixels of the graphical console.
+See GFX_HEIGHT to query the other dimension.",
+                )
+                .build(),
+            console,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for GfxWidthFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let size = self.console.borrow().size_pixels()?;
+        Ok(Value::Integer(i32::from(size.width)))
+    }
+}
+
+/// Adds all console-related commands for the given `console` to the `machine`.
+pub fn add_all(machine: &mut Machine, console: Rc<RefCell<dyn Console>>) {
+    machine.add_command(GfxArcCommand::new(console.clone()));
+    machine.add_command(GfxCircleCommand::new(console.clone()));
+    machine.add_command(GfxCirclefCommand::new(console.clone()));
+    machine.add_command(GfxClipCommand::new(console.clone()));
+    machine.add_command(GfxEllipseCommand::new(console.clone()));
+    machine.add_command(GfxEllipsefCommand::new(console.clone()));
+    machine.add_command(GfxFillCommand::new(console.clone()));
+    machine.add_command(GfxFlipCommand::new(console.clone()));
+    machine.add_command(GfxFullscreenCommand::new(console.clone()));
+    machine.add_function(GfxGetpixelFunction::new(console.clone()));
+    machine.add_function(GfxHeightFunction::new(console.clone()));
+    machine.add_command(GfxLineCommand::new(console.clone()));
+    machine.add_command(GfxPageCommand::new(console.clone()));
+    machine.add_command(GfxPixelCommand::new(console.clone()));
+    machine.add_command(GfxPolyCommand::new(console.clone()));
+    machine.add_command(GfxPolyfCommand::new(console.clone()));
+    machine.add_command(GfxRectCommand::new(console.clone()));
+    machine.add_command(GfxRectfCommand::new(console.clone()));
+    machine.add_command(GfxSyncCommand::new(console.clone()));
+    machine.add_command(GfxTextCommand::new(console.clone()));
+    machine.add_command(GfxTitleCommand::new(console.clone()));
+    machine.add_function(GfxWidthFunction::new(console));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::{ansi_color_to_rgb, AnsiColor, SizeInPixels};
+    use crate::testutils::*;
+
+    /// Verifies error conditions for a command named `name` that takes to X/Y pairs.
+    fn check_errors_two_xy(name: &'static str) {
+        for args in &["1, 2, , 4", "1, 2, 3", "1, 2, 3, 4, 5", "2; 3, 4"] {
+            check_stmt_err(
+                format!("1:1: In call to {}: expected x1%, y1%, x2%, y2%", name),
+                &format!("{} {}", name, args),
+            );
+        }
+
+        for args in &["-40000, 1, 1, 1", "1, -40000, 1, 1", "1, 1, -40000, 1", "1, 1, 1, -40000"] {
+            let pos = name.len() + 1 + args.find('-').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: Coordinate -40000 out of range", name, pos),
+                &format!("{} {}", name, args),
+            );
+        }
+
+        for args in &["40000, 1, 1, 1", "1, 40000, 1, 1", "1, 1, 40000, 1", "1, 1, 1, 40000"] {
+            let pos = name.len() + 1 + args.find('4').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: Coordinate 40000 out of range", name, pos),
+                &format!("{} {}", name, args),
+            );
+        }
+
+        for args in &["\"a\", 1, 1, 1", "1, \"a\", 1, 1", "1, 1, \"a\", 1", "1, 1, 1, \"a\""] {
+            let stmt = &format!("{} {}", name, args);
+            let pos = stmt.find('"').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: \"a\" is not a number", name, pos),
+                stmt,
+            );
+        }
+    }
+
+    /// Verifies error conditions for a command named `name` that takes an X/Y pair and a radius.
+    fn check_errors_xy_radius(name: &'static str) {
+        for args in &["1, , 3", "1, 2", "1, 2, 3, 4", "2; 3, 4"] {
+            check_stmt_err(
+                format!("1:1: In call to {}: expected x%, y%, r%", name),
+                &format!("{} {}", name, args),
+            );
+        }
+
+        for args in &["-40000, 1, 1", "1, -40000, 1"] {
+            let pos = name.len() + 1 + args.find('-').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: Coordinate -40000 out of range", name, pos),
+                &format!("{} {}", name, args),
+            );
+        }
+        check_stmt_err(
+            format!(
+                "1:1: In call to {}: 1:{}: Radius -40000 must be positive",
+                name,
+                name.len() + 8
+            ),
+            &format!("{} 1, 1, -40000", name),
+        );
+
+        for args in &["40000, 1, 1", "1, 40000, 1"] {
+            let pos = name.len() + 1 + args.find('4').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: Coordinate 40000 out of range", name, pos),
+                &format!("{} {}", name, args),
+            );
+        }
+        check_stmt_err(
+            format!("1:1: In call to {}: 1:{}: Radius 80000 out of range", name, name.len() + 8),
+            &format!("{} 1, 1, 80000", name),
+        );
+
+        for args in &["\"a\", 1, 1", "1, \"a\", 1", "1, 1, \"a\""] {
+            let stmt = &format!("{} {}", name, args);
+            let pos = stmt.find('"').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: \"a\" is not a number", name, pos),
+                stmt,
+            );
+        }
+
+        check_stmt_err(
+            format!("1:1: In call to {}: 1:{}: Radius -1 must be positive", name, name.len() + 8),
+            &format!("{} 1, 1, -1", name),
+        );
+    }
+
+    /// Verifies error conditions for a command named `name` that takes an X/Y pair and two radii.
+    fn check_errors_xy_two_radii(name: &'static str) {
+        for args in &["1, , 3, 4", "1, 2, 3", "1, 2, 3, 4, 5", "2; 3, 4, 5"] {
+            check_stmt_err(
+                format!("1:1: In call to {}: expected x%, y%, rx%, ry%", name),
+                &format!("{} {}", name, args),
+            );
+        }
+
+        for args in &["-40000, 1, 1, 1", "1, -40000, 1, 1"] {
+            let pos = name.len() + 1 + args.find('-').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: Coordinate -40000 out of range", name, pos),
+                &format!("{} {}", name, args),
+            );
+        }
+        for args in &["1, 1, -1, 1", "1, 1, 1, -1"] {
+            let pos = name.len() + 1 + args.find('-').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: Radius -1 must be positive", name, pos),
+                &format!("{} {}", name, args),
+            );
+        }
+        for args in &["1, 1, 80000, 1", "1, 1, 1, 80000"] {
+            let pos = name.len() + 1 + args.find('8').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: Radius 80000 out of range", name, pos),
+                &format!("{} {}", name, args),
+            );
+        }
+
+        for args in &["\"a\", 1, 1, 1", "1, \"a\", 1, 1", "1, 1, \"a\", 1", "1, 1, 1, \"a\""] {
+            let stmt = &format!("{} {}", name, args);
+            let pos = stmt.find('"').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: \"a\" is not a number", name, pos),
+                stmt,
+            );
+        }
+    }
+
+    /// Verifies error conditions for a command named `name` that takes the vertices of a polygon.
+    fn check_errors_polygon(name: &'static str) {
+        for args in &["1, 2, 3, 4", "1, 2, 3, 4, 5", "1, 2, 3, 4, 5, 6, 7", "1, 2, 3, 4, 5; 6"] {
+            check_stmt_err(
+                format!(
+                    "1:1: In call to {}: expected x1%, y1%, x2%, y2%, x3%, y3%[, .., xn%, yn%]",
+                    name
+                ),
+                &format!("{} {}", name, args),
+            );
+        }
+        check_stmt_err(
+            format!(
+                "1:1: In call to {}: expected x1%, y1%, x2%, y2%, x3%, y3%[, .., xn%, yn%]",
+                name
+            ),
+            &format!("{} 1, 2, , 4, 5, 6", name),
+        );
+
+        for args in &["-40000, 1, 1, 1, 1, 1", "1, 1, 1, 1, 1, -40000"] {
+            let pos = name.len() + 1 + args.find('-').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to {}: 1:{}: Coordinate -40000 out of range", name, pos),
+                &format!("{} {}", name, args),
+            );
+        }
+
+        let stmt = &format!("{} 1, 1, 1, \"a\", 1, 1", name);
+        let pos = stmt.find('"').unwrap() + 1;
+        check_stmt_err(format!("1:1: In call to {}: 1:{}: \"a\" is not a number", name, pos), stmt);
+    }
+
+    #[test]
+    fn test_gfx_arc_ok() {
+        Tester::default()
+            .run("GFX_ARC 0, 0, 0, 0, 0")
+            .expect_output([CapturedOut::DrawArc(PixelsXY { x: 0, y: 0 }, 0, 0, 0)])
+            .check();
+
+        Tester::default()
+            .run("GFX_ARC 1.1, 2.3, 2.5, -45, 400.2")
+            .expect_output([CapturedOut::DrawArc(PixelsXY { x: 1, y: 2 }, 3, -45, 400)])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_arc_errors() {
+        for args in &["1, 2, 3, 4", "1, 2, 3, 4, 5, 6", "1, 2, 3, , 5", "1, 2, 3, 4; 5"] {
+            check_stmt_err(
+                "1:1: In call to GFX_ARC: expected x%, y%, r%, start%, end%",
+                &format!("GFX_ARC {}", args),
+            );
+        }
+        check_stmt_err(
+            "1:1: In call to GFX_ARC: 1:15: Radius -1 must be positive",
+            "GFX_ARC 1, 2, -1, 4, 5",
+        );
+        check_stmt_err(
+            "1:1: In call to GFX_ARC: 1:18: \"a\" is not a number",
+            "GFX_ARC 1, 2, 3, \"a\", 5",
+        );
+        check_stmt_err(
+            "1:1: In call to GFX_ARC: 1:21: \"a\" is not a number",
+            "GFX_ARC 1, 2, 3, 4, \"a\"",
+        );
+    }
+
+    #[test]
+    fn test_gfx_circle_ok() {
+        Tester::default()
+            .run("GFX_CIRCLE 0, 0, 0")
+            .expect_output([CapturedOut::DrawCircle(PixelsXY { x: 0, y: 0 }, 0)])
+            .check();
+
+        Tester::default()
+            .run("GFX_CIRCLE 1.1, 2.3, 2.5")
+            .expect_output([CapturedOut::DrawCircle(PixelsXY { x: 1, y: 2 }, 3)])
+            .check();
+
+        Tester::default()
+            .run("GFX_CIRCLE -31000, -32000, 31000")
+            .expect_output([CapturedOut::DrawCircle(PixelsXY { x: -31000, y: -32000 }, 31000)])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_circle_errors() {
+        check_errors_xy_radius("GFX_CIRCLE");
+    }
+
+    #[test]
+    fn test_gfx_circlef_ok() {
+        Tester::default()
+            .run("GFX_CIRCLEF 0, 0, 0")
+            .expect_output([CapturedOut::DrawCircleFilled(PixelsXY { x: 0, y: 0 }, 0)])
+            .check();
+
+        Tester::default()
+            .run("GFX_CIRCLEF 1.1, 2.3, 2.5")
+            .expect_output([CapturedOut::DrawCircleFilled(PixelsXY { x: 1, y: 2 }, 3)])
+            .check();
+
+        Tester::default()
+            .run("GFX_CIRCLEF -31000, -32000, 31000")
+            .expect_output([CapturedOut::DrawCircleFilled(
+                PixelsXY { x: -31000, y: -32000 },
+                31000,
+            )])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_circlef_errors() {
+        check_errors_xy_radius("GFX_CIRCLEF");
+    }
+
+    #[test]
+    fn test_gfx_clip_ok() {
+        Tester::default()
+            .run("GFX_CLIP 1, 2, 30, 40: GFX_CLIP 30, 2, 1, 40: GFX_CLIP -5, 40, 5, -40: GFX_CLIP")
+            .expect_output([
+                CapturedOut::SetClip(Some((PixelsXY { x: 1, y: 2 }, PixelsXY { x: 30, y: 40 }))),
+                CapturedOut::SetClip(Some((PixelsXY { x: 1, y: 2 }, PixelsXY { x: 30, y: 40 }))),
+                CapturedOut::SetClip(Some((PixelsXY { x: -5, y: -40 }, PixelsXY { x: 5, y: 40 }))),
+                CapturedOut::SetClip(None),
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_clip_errors() {
+        for args in &["1", "1, 2, 3", "1, 2, 3, 4, 5", "1, 2; 3, 4", "1, , 3, 4"] {
+            check_stmt_err(
+                "1:1: In call to GFX_CLIP: expected [x1%, y1%, x2%, y2%]",
+                &format!("GFX_CLIP {}", args),
+            );
+        }
+        check_stmt_err(
+            "1:1: In call to GFX_CLIP: 1:16: Coordinate 40000 out of range",
+            "GFX_CLIP 1, 2, 40000, 4",
+        );
+        check_stmt_err(
+            "1:1: In call to GFX_CLIP: 1:13: \"a\" is not a number",
+            "GFX_CLIP 1, \"a\", 3, 4",
+        );
+    }
+
+    #[test]
+    fn test_gfx_ellipse_ok() {
+        Tester::default()
+            .run("GFX_ELLIPSE 0, 0, 0, 0")
+            .expect_output([CapturedOut::DrawEllipse(PixelsXY { x: 0, y: 0 }, 0, 0)])
+            .check();
+
+        Tester::default()
+            .run("GFX_ELLIPSE 1.1, 2.3, 2.5, 7")
+            .expect_output([CapturedOut::DrawEllipse(PixelsXY { x: 1, y: 2 }, 3, 7)])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_ellipse_errors() {
+        check_errors_xy_two_radii("GFX_ELLIPSE");
+    }
+
+    #[test]
+    fn test_gfx_ellipsef_ok() {
+        Tester::default()
+            .run("GFX_ELLIPSEF 0, 0, 0, 0")
+            .expect_output([CapturedOut::DrawEllipseFilled(PixelsXY { x: 0, y: 0 }, 0, 0)])
+            .check();
+
+        Tester::default()
+            .run("GFX_ELLIPSEF -31000, -32000, 31000, 5")
+            .expect_output([CapturedOut::DrawEllipseFilled(
+                PixelsXY { x: -31000, y: -32000 },
+                31000,
+                5,
+            )])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_ellipsef_errors() {
+        check_errors_xy_two_radii("GFX_ELLIPSEF");
+    }
+
+    #[test]
+    fn test_gfx_fill_ok() {
+        Tester::default()
+            .run("GFX_FILL 1.1, 2.3")
+            .expect_output([CapturedOut::FloodFill(PixelsXY { x: 1, y: 2 }, None)])
+            .check();
+
+        Tester::default()
+            .run("GFX_FILL -31000, 32000, 255")
+            .expect_output([CapturedOut::FloodFill(
+                PixelsXY { x: -31000, y: 32000 },
+                Some(ansi_color_to_rgb(255)),
+            )])
+            .check();
+
+        Tester::default()
+            .run("GFX_FILL 1, 2, COLOR_RGB(10, 20, 30)")
+            .expect_output([CapturedOut::FloodFill(PixelsXY { x: 1, y: 2 }, Some((10, 20, 30)))])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_fill_errors() {
+        for args in &["1", "1, 2, 3, 4", "1, , 3", "1; 2"] {
+            check_stmt_err(
+                "1:1: In call to GFX_FILL: expected x%, y%[, border%]",
+                &format!("GFX_FILL {}", args),
+            );
+        }
+        check_stmt_err(
+            "1:1: In call to GFX_FILL: 1:13: Coordinate -40000 out of range",
+            "GFX_FILL 1, -40000",
+        );
+        for border in &["-1", "256", "33554432"] {
+            check_stmt_err(
+                "1:1: In call to GFX_FILL: 1:16: Color out of range",
+                &format!("GFX_FILL 1, 2, {}", border),
+            );
+        }
+        check_stmt_err(
+            "1:1: In call to GFX_FILL: 1:16: \"a\" is not a number",
+            "GFX_FILL 1, 2, \"a\"",
+        );
+    }
+
+    #[test]
+    fn test_gfx_flip_ok() {
+        Tester::default()
+            .run("GFX_FLIP 0: GFX_FLIP 3.2")
+            .expect_output([CapturedOut::FlipPage(0), CapturedOut::FlipPage(3)])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_flip_errors() {
+        for args in &["", "1, 2", "1; 2"] {
+            check_stmt_err(
+                "1:1: In call to GFX_FLIP: expected page%",
+                &format!("GFX_FLIP {}", args),
+            );
+        }
+        for page in &["-1", "4", "256"] {
+            check_stmt_err(
+                format!("1:1: In call to GFX_FLIP: 1:10: Page {} out of range", page),
+                &format!("GFX_FLIP {}", page),
+            );
+        }
+        check_stmt_err("1:1: In call to GFX_FLIP: 1:10: \"a\" is not a number", "GFX_FLIP \"a\"");
+    }
+
+    #[test]
+    fn test_gfx_fullscreen_ok() {
+        Tester::default()
+            .run("GFX_FULLSCREEN TRUE: GFX_FULLSCREEN FALSE")
+            .expect_output([CapturedOut::SetFullscreen(true), CapturedOut::SetFullscreen(false)])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_fullscreen_errors() {
+        for args in &["", "TRUE, FALSE"] {
+            check_stmt_err(
+                "1:1: In call to GFX_FULLSCREEN: expected enabled?",
+                &format!("GFX_FULLSCREEN {}", args),
+            );
+        }
+        check_stmt_err(
+            "1:1: In call to GFX_FULLSCREEN: 1:16: Argument to GFX_FULLSCREEN must be a boolean",
+            "GFX_FULLSCREEN 1",
+        );
+    }
+
+    #[test]
+    fn test_gfx_getpixel() {
+        let mut t = Tester::default();
+        {
+            let console = t.get_console();
+            let mut console = console.borrow_mut();
+            console.set_size_pixels(SizeInPixels { width: 100, height: 50 });
+            console.set_pixel(PixelsXY::new(10, 20), (255, 0, 0));
+            console.set_pixel(PixelsXY::new(99, 49), (250, 250, 240));
+        }
+        t.run("a = GFX_GETPIXEL(10, 20): b = GFX_GETPIXEL(99, 49): c = GFX_GETPIXEL(0.2, 0)")
+            .expect_var("a", AnsiColor::BrightRed as i32)
+            .expect_var("b", AnsiColor::BrightWhite as i32)
+            .expect_var("c", AnsiColor::Black as i32)
+            .check();
+
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_size_pixels(SizeInPixels { width: 100, height: 50 });
+        t.run("a = GFX_GETPIXEL(-1, 0): b = GFX_GETPIXEL(0, 50): c = GFX_GETPIXEL(100, 0)")
+            .expect_var("a", -1)
+            .expect_var("b", -1)
+            .expect_var("c", -1)
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_getpixel_errors() {
+        check_expr_error(
+            "1:10: In call to GFX_GETPIXEL: Graphical console size not yet set",
+            "GFX_GETPIXEL(1, 2)",
+        );
+        check_expr_error("1:10: GFX_GETPIXEL requires one or more arguments", "GFX_GETPIXEL");
+        for args in &["(1)", "(1, 2, 3)"] {
+            check_expr_error(
+                "1:10: In call to GFX_GETPIXEL: expected x%, y%",
+                &format!("GFX_GETPIXEL{}", args),
+            );
+        }
+        check_expr_error(
+            "1:10: In call to GFX_GETPIXEL: 1:26: Coordinate 40000 out of range",
+            "GFX_GETPIXEL(1, 40000)",
+        );
+        check_expr_error(
+            "1:10: In call to GFX_GETPIXEL: 1:23: \"a\" is not a number",
+            "GFX_GETPIXEL(\"a\", 2)",
+        );
+    }
+
+    #[test]
+    fn test_gfx_height() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_size_pixels(SizeInPixels { width: 0, height: 768 });
+        t.run("result = GFX_HEIGHT").expect_var("result", 768i32).check();
+
+        check_expr_error(
+            "1:10: In call to GFX_HEIGHT: Graphical console size not yet set",
+            "GFX_HEIGHT",
+        );
+
+        check_expr_error(
+            "1:10: In call to GFX_HEIGHT: expected no arguments nor parenthesis",
+            "GFX_HEIGHT()",
+        );
+        check_expr_error(
+            "1:10: In call to GFX_HEIGHT: expected no arguments nor parenthesis",
+            "GFX_HEIGHT(1)",
+        );
+    }
+
+    #[test]
+    fn test_gfx_line_ok() {
+        Tester::default()
+            .run("GFX_LINE 1, 2, 3, 4")
+            .expect_output([CapturedOut::DrawLine(
+                PixelsXY { x: 1, y: 2 },
+                PixelsXY { x: 3, y: 4 },
+            )])
+            .check();
+
+        Tester::default()
+            .run("GFX_LINE -31000.3, -32000.2, 31000.4, 31999.8")
+            .expect_output([CapturedOut::DrawLine(
+                PixelsXY { x: -31000, y: -32000 },
+                PixelsXY { x: 31000, y: 32000 },
+            )])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_line_errors() {
+        check_errors_two_xy("GFX_LINE");
+    }
+
+    #[test]
+    fn test_gfx_page_ok() {
+        Tester::default()
+            .run("GFX_PAGE 0: GFX_PAGE 3.2")
+            .expect_output([CapturedOut::SetDrawPage(0), CapturedOut::SetDrawPage(3)])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_page_errors() {
+        for args in &["", "1, 2", "1; 2"] {
+            check_stmt_err(
+                "1:1: In call to GFX_PAGE: expected page%",
+                &format!("GFX_PAGE {}", args),
+            );
+        }
+        for page in &["-1", "4", "256"] {
+            check_stmt_err(
+                format!("1:1: In call to GFX_PAGE: 1:10: Page {} out of range", page),
+                &format!("GFX_PAGE {}", page),
+            );
+        }
+        check_stmt_err("1:1: In call to GFX_PAGE: 1:10: \"a\" is not a number", "GFX_PAGE \"a\"");
+    }
+
+    #[test]
+    fn test_gfx_pixel_ok() {
+        Tester::default()
+            .run("GFX_PIXEL 1, 2")
+            .expect_output([CapturedOut::DrawPixel(PixelsXY { x: 1, y: 2 })])
+            .check();
+
+        Tester::default()
+            .run("GFX_PIXEL -31000, -32000")
+            .expect_output([CapturedOut::DrawPixel(PixelsXY { x: -31000, y: -32000 })])
+            .check();
+
+        Tester::default()
+            .run("GFX_PIXEL 30999.5, 31999.7")
+            .expect_output([CapturedOut::DrawPixel(PixelsXY { x: 31000, y: 32000 })])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_pixel_errors() {
+        for cmd in &["GFX_PIXEL , 2", "GFX_PIXEL 1, 2, 3", "GFX_PIXEL 1", "GFX_PIXEL 1; 2"] {
+            check_stmt_err("1:1: In call to GFX_PIXEL: expected x%, y%", cmd);
+        }
+
+        for cmd in &["GFX_PIXEL -40000, 1", "GFX_PIXEL 1, -40000"] {
+            check_stmt_err(
+                format!(
+                    "1:1: In call to GFX_PIXEL: 1:{}: Coordinate -40000 out of range",
+                    cmd.find('-').unwrap() + 1
+                ),
+                cmd,
+            );
+        }
+
+        for cmd in &["GFX_PIXEL \"a\", 1", "GFX_PIXEL 1, \"a\""] {
+            let pos = cmd.find('"').unwrap() + 1;
+            check_stmt_err(
+                format!("1:1: In call to GFX_PIXEL: 1:{}: \"a\" is not a number", pos),
+                cmd,
+            );
+        }
+    }
+
+    #[test]
+    fn test_gfx_poly_ok() {
+        Tester::default()
+            .run("GFX_POLY 1, 2, 3, 4, 5, 6")
+            .expect_output([CapturedOut::DrawPoly(vec![
+                PixelsXY { x: 1, y: 2 },
+                PixelsXY { x: 3, y: 4 },
+                PixelsXY { x: 5, y: 6 },
+            ])])
+            .check();
+
+        Tester::default()
+            .run("GFX_POLY 0, 0, 10.2, 0, 10, 10, -0.7, 10")
+            .expect_output([CapturedOut::DrawPoly(vec![
+                PixelsXY { x: 0, y: 0 },
+                PixelsXY { x: 10, y: 0 },
+                PixelsXY { x: 10, y: 10 },
+                PixelsXY { x: -1, y: 10 },
+            ])])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_poly_errors() {
+        check_errors_polygon("GFX_POLY");
+    }
+
+    #[test]
+    fn test_gfx_polyf_ok() {
+        Tester::default()
+            .run("GFX_POLYF 1, 2, 3, 4, 5, 6")
+            .expect_output([CapturedOut::DrawPolyFilled(vec![
+                PixelsXY { x: 1, y: 2 },
+                PixelsXY { x: 3, y: 4 },
+                PixelsXY { x: 5, y: 6 },
+            ])])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_polyf_errors() {
+        check_errors_polygon("GFX_POLYF");
+    }
+
+    #[test]
+    fn test_gfx_rect_ok() {
+        Tester::default()
+            .run("GFX_RECT 1.1, 2.3, 2.5, 3.9")
+            .expect_output([CapturedOut::DrawRect(
+                PixelsXY { x: 1, y: 2 },
+                PixelsXY { x: 3, y: 4 },
+            )])
+            .check();
+
+        Tester::default()
+            .run("GFX_RECT -31000, -32000, 31000, 32000")
+            .expect_output([CapturedOut::DrawRect(
+                PixelsXY { x: -31000, y: -32000 },
+                PixelsXY { x: 31000, y: 32000 },
+            )])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_rect_errors() {
+        check_errors_two_xy("GFX_RECT");
+    }
+
+    #[test]
+    fn test_gfx_rectf_ok() {
+        Tester::default()
+            .run("GFX_RECTF 1.1, 2.3, 2.5, 3.9")
+            .expect_output([CapturedOut::DrawRectFilled(
+                PixelsXY { x: 1, y: 2 },
+                PixelsXY { x: 3, y: 4 },
+            )])
+            .check();
+
+        Tester::default()
+            .run("GFX_RECTF -31000, -32000, 31000, 32000")
+            .expect_output([CapturedOut::DrawRectFilled(
+                PixelsXY { x: -31000, y: -32000 },
+                PixelsXY { x: 31000, y: 32000 },
+            )])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_rectf_errors() {
+        check_errors_two_xy("GFX_RECTF");
+    }
+
+    #[test]
+    fn test_gfx_sync_ok() {
+        Tester::default().run("GFX_SYNC").expect_output([CapturedOut::SyncNow]).check();
+        Tester::default()
+            .run("GFX_SYNC TRUE")
+            .expect_output([CapturedOut::ShowCursor, CapturedOut::SetSync(true)])
+            .check();
+        Tester::default()
+            .run("GFX_SYNC FALSE")
+            .expect_output([CapturedOut::HideCursor, CapturedOut::SetSync(false)])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_sync_errors() {
+        check_stmt_err("1:1: In call to GFX_SYNC: expected [enabled?]", "GFX_SYNC 2, 3");
+        check_stmt_err(
+            "1:1: In call to GFX_SYNC: 1:10: Argument to GFX_SYNC must be a boolean",
+            "GFX_SYNC 2",
+        );
+    }
+
+    #[test]
+    fn test_gfx_text_ok() {
+        Tester::default()
+            .run("GFX_TEXT 10, -20, \"Hello\": GFX_TEXT 1, 2, \"Big\", 40.2: GFX_TEXT 1, 2, \"\"")
+            .expect_output([
+                CapturedOut::DrawText(PixelsXY { x: 10, y: -20 }, "Hello".to_owned(), None),
+                CapturedOut::DrawText(PixelsXY { x: 1, y: 2 }, "Big".to_owned(), Some(40)),
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_text_errors() {
+        for args in &["", "1, 2", "1, 2; \"a\"", "1, 2, \"a\", 3, 4"] {
+            check_stmt_err(
+                "1:1: In call to GFX_TEXT: expected x%, y%, msg$[, size%]",
+                &format!("GFX_TEXT {}", args),
+            );
+        }
+        check_stmt_err(
+            "1:1: In call to GFX_TEXT: 1:10: Coordinate 40000 out of range",
+            "GFX_TEXT 40000, 1, \"a\"",
+        );
+        check_stmt_err(
+            "1:1: In call to GFX_TEXT: 1:16: Text to render must be a string",
+            "GFX_TEXT 1, 2, 3",
+        );
+        for size in &["0", "-1", "65536"] {
+            check_stmt_err(
+                format!("1:1: In call to GFX_TEXT: 1:21: Font size {} out of range", size),
+                &format!("GFX_TEXT 1, 2, \"a\", {}", size),
+            );
+        }
+    }
+
+    #[test]
+    fn test_gfx_title_ok() {
+        Tester::default()
+            .run("GFX_TITLE \"My game\": GFX_TITLE \"\"")
+            .expect_output([
+                CapturedOut::SetTitle("My game".to_owned()),
+                CapturedOut::SetTitle("".to_owned()),
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_gfx_title_errors() {
+        for args in &["", "\"a\", \"b\"", "\"a\"; \"b\""] {
+            check_stmt_err(
+                "1:1: In call to GFX_TITLE: expected title$",
+                &format!("GFX_TITLE {}", args),
+            );
+        }
+        check_stmt_err(
+            "1:1: In call to GFX_TITLE: 1:11: Argument to GFX_TITLE must be a string",
+            "GFX_TITLE 2",
+        );
+    }
+
+    #[test]
+    fn test_gfx_width() {
+        let mut t = Tester::default();
+        t.get_console().borrow_mut().set_size_pixels(SizeInPixels { width: 12345, height: 0 });
+        t.run("result = GFX_WIDTH").expect_var("result", 12345i32).check();
+
+        check_expr_error(
+            "1:10: In call to GFX_WIDTH: Graphical console size not yet set",
+            "GFX_WIDTH",
+        );
+
+        check_expr_error(
+            "1:10: In call to GFX_WIDTH: expected no arguments nor parenthesis",
+            "GFX_WIDTH()",
+        );
+        check_expr_error(
+            "1:10: In call to GFX_WIDTH: expected no arguments nor parenthesis",
+            "GFX_WIDTH(1)",
+        );
+    }
+}
@@ -0,0 +1,617 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Rasterization algorithms for graphical primitives.
+//!
+//! Consoles that paint individual pixels, as opposed to those that delegate drawing to a
+//! higher-level graphics API, can use these to turn shapes into the pixels or horizontal spans
+//! that cover them.  Shapes are drawn with the same extents as the circles drawn by those
+//! consoles, which means that a radius of `r` covers `r - 1` pixels on each side of the center.
+
+use crate::console::{PixelsXY, SizeInPixels};
+use std::convert::TryFrom;
+use std::io;
+
+/// Converts an `(x, y)` pair computed with a wider type into a coordinate, clamping the values
+/// that fall outside of the range of a coordinate.  Such values are always off-screen.
+fn clamped_xy(x: i32, y: i32) -> PixelsXY {
+    fn clamp(v: i32) -> i16 {
+        i16::try_from(v).unwrap_or(if v < 0 { i16::MIN } else { i16::MAX })
+    }
+    PixelsXY { x: clamp(x), y: clamp(y) }
+}
+
+/// Computes the pixels of the line that goes from `x1y1` to `x2y2`, both included, using
+/// [Bresenham's line algorithm](https://en.wikipedia.org/wiki/Bresenham%27s_line_algorithm) and
+/// calls `point` for every one of them.
+pub fn line<F>(x1y1: PixelsXY, x2y2: PixelsXY, mut point: F) -> io::Result<()>
+where
+    F: FnMut(PixelsXY) -> io::Result<()>,
+{
+    let (mut x, mut y) = (i32::from(x1y1.x), i32::from(x1y1.y));
+    let (x2, y2) = (i32::from(x2y2.x), i32::from(x2y2.y));
+
+    let dx = (x2 - x).abs();
+    let dy = -(y2 - y).abs();
+    let sx = if x < x2 { 1 } else { -1 };
+    let sy = if y < y2 { 1 } else { -1 };
+    let mut err = dx + dy;
+    loop {
+        point(clamped_xy(x, y))?;
+        if x == x2 && y == y2 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    Ok(())
+}
+
+/// Walks the first quadrant of an ellipse with semi-axes `a` and `b` using the [Midpoint ellipse
+/// algorithm](https://en.wikipedia.org/wiki/Midpoint_circle_algorithm#Generalization) and calls
+/// `visit` with every `(x, y)` offset from the center that belongs to the outline.
+fn walk_ellipse<F>(a: i64, b: i64, mut visit: F) -> io::Result<()>
+where
+    F: FnMut(i64, i64) -> io::Result<()>,
+{
+    // The algorithm below assumes non-zero semi-axes, so handle flat ellipses as the lines they are.
+    if a == 0 {
+        return (0..=b).try_for_each(|y| visit(0, y));
+    } else if b == 0 {
+        return (0..=a).try_for_each(|x| visit(x, 0));
+    }
+
+    let a2 = a * a;
+    let b2 = b * b;
+
+    let mut x = 0;
+    let mut y = b;
+    let mut px = 0;
+    let mut py = 2 * a2 * y;
+
+    // Region where the slope of the curve is smaller than 1: step over x.
+    let mut p = b2 - a2 * b + a2 / 4;
+    while px < py {
+        visit(x, y)?;
+        x += 1;
+        px += 2 * b2;
+        if p < 0 {
+            p += b2 + px;
+        } else {
+            y -= 1;
+            py -= 2 * a2;
+            p += b2 + px - py;
+        }
+    }
+
+    // Region where the slope of the curve is larger than 1: step over y.
+    p = (b2 * (2 * x + 1) * (2 * x + 1)) / 4 + a2 * (y - 1) * (y - 1) - a2 * b2;
+    while y >= 0 {
+        visit(x, y)?;
+        y -= 1;
+        py -= 2 * a2;
+        if p > 0 {
+            p += a2 - py;
+        } else {
+            x += 1;
+            px += 2 * b2;
+            p += a2 - py + px;
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the outline of an ellipse centered at `center` with radii `rx` and `ry` and calls
+/// `point` for every pixel in it.
+pub fn ellipse<F>(center: PixelsXY, rx: u16, ry: u16, mut point: F) -> io::Result<()>
+where
+    F: FnMut(PixelsXY) -> io::Result<()>,
+{
+    if rx == 0 || ry == 0 {
+        return Ok(());
+    }
+
+    let cx = i32::from(center.x);
+    let cy = i32::from(center.y);
+    walk_ellipse(i64::from(rx) - 1, i64::from(ry) - 1, |x, y| {
+        let (x, y) = (x as i32, y as i32);
+        point(clamped_xy(cx + x, cy + y))?;
+        if x != 0 {
+            point(clamped_xy(cx - x, cy + y))?;
+        }
+        if y != 0 {
+            point(clamped_xy(cx + x, cy - y))?;
+            if x != 0 {
+                point(clamped_xy(cx - x, cy - y))?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Computes the area of an ellipse centered at `center` with radii `rx` and `ry` and calls `span`
+/// with the leftmost and rightmost pixels of every row in it.
+///
+/// Rows may be visited more than once, but the spans reported for a row never shrink.
+pub fn ellipse_filled<F>(center: PixelsXY, rx: u16, ry: u16, mut span: F) -> io::Result<()>
+where
+    F: FnMut(PixelsXY, PixelsXY) -> io::Result<()>,
+{
+    if rx == 0 || ry == 0 {
+        return Ok(());
+    }
+
+    let cx = i32::from(center.x);
+    let cy = i32::from(center.y);
+    walk_ellipse(i64::from(rx) - 1, i64::from(ry) - 1, |x, y| {
+        let (x, y) = (x as i32, y as i32);
+        span(clamped_xy(cx - x, cy + y), clamped_xy(cx + x, cy + y))?;
+        if y != 0 {
+            span(clamped_xy(cx - x, cy - y), clamped_xy(cx + x, cy - y))?;
+        }
+        Ok(())
+    })
+}
+
+/// Normalizes an angle in degrees to the `[0, 360)` range.
+fn normalize_degrees(angle: i32) -> i32 {
+    angle.rem_euclid(360)
+}
+
+/// Checks if `angle` falls within the arc that goes counterclockwise from `start` to `end`, with
+/// all angles expressed in degrees and normalized to the `[0, 360)` range.
+fn in_arc(angle: f64, start: i32, end: i32) -> bool {
+    let (start, end) = (f64::from(start), f64::from(end));
+    if start <= end {
+        angle >= start && angle <= end
+    } else {
+        angle >= start || angle <= end
+    }
+}
+
+/// Computes the outline of the arc of a circle centered at `center` with `radius` that goes
+/// counterclockwise from the `start` angle to the `end` angle and calls `point` for every pixel in
+/// it.
+///
+/// Angles are expressed in degrees, where 0 points to the right of the center and 90 points up.
+pub fn arc<F>(center: PixelsXY, radius: u16, start: i32, end: i32, mut point: F) -> io::Result<()>
+where
+    F: FnMut(PixelsXY) -> io::Result<()>,
+{
+    if i64::from(end) - i64::from(start) >= 360 {
+        return ellipse(center, radius, radius, point);
+    }
+
+    let start = normalize_degrees(start);
+    let end = normalize_degrees(end);
+    ellipse(center, radius, radius, |xy| {
+        let dx = f64::from(i32::from(xy.x) - i32::from(center.x));
+        let dy = f64::from(i32::from(center.y) - i32::from(xy.y));
+        let mut angle = dy.atan2(dx).to_degrees();
+        if angle < 0.0 {
+            angle += 360.0;
+        }
+        if in_arc(angle, start, end) {
+            point(xy)
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Computes the outline of the closed polygon with vertices `points` and calls `line` for every
+/// one of its edges.
+pub fn poly<F>(points: &[PixelsXY], mut line: F) -> io::Result<()>
+where
+    F: FnMut(PixelsXY, PixelsXY) -> io::Result<()>,
+{
+    if points.len() < 2 {
+        return Ok(());
+    }
+
+    for pair in points.windows(2) {
+        line(pair[0], pair[1])?;
+    }
+    if points.len() > 2 {
+        line(points[points.len() - 1], points[0])?;
+    }
+    Ok(())
+}
+
+/// Computes the area of the closed polygon with vertices `points` and calls `span` with the
+/// leftmost and rightmost pixels of every horizontal segment in it.
+///
+/// This follows the even-odd rule to determine which pixels are inside the polygon and samples
+/// edges in a half-open manner, which means that the bottom-most pixels of the edges are not part
+/// of the area.  Callers should draw the outline with `poly` as well to get a closed shape.
+pub fn poly_filled<F>(points: &[PixelsXY], mut span: F) -> io::Result<()>
+where
+    F: FnMut(PixelsXY, PixelsXY) -> io::Result<()>,
+{
+    if points.len() < 3 {
+        return Ok(());
+    }
+
+    let min_y = points.iter().map(|p| p.y).min().expect("Must have points");
+    let max_y = points.iter().map(|p| p.y).max().expect("Must have points");
+
+    let mut xs = Vec::with_capacity(points.len());
+    for y in min_y..max_y {
+        xs.clear();
+        let yf = f64::from(y) + 0.5;
+        for (i, p1) in points.iter().enumerate() {
+            let p2 = points[(i + 1) % points.len()];
+            let (y1, y2) = (f64::from(p1.y), f64::from(p2.y));
+            if (y1 <= yf && yf < y2) || (y2 <= yf && yf < y1) {
+                let (x1, x2) = (f64::from(p1.x), f64::from(p2.x));
+                xs.push((x1 + (yf - y1) * (x2 - x1) / (y2 - y1)).round() as i32);
+            }
+        }
+        xs.sort_unstable();
+
+        for pair in xs.chunks_exact(2) {
+            let y = i32::from(y);
+            span(clamped_xy(pair[0], y), clamped_xy(pair[1], y))?;
+        }
+    }
+    Ok(())
+}
+
+/// Computes the 4-connected region of a canvas of `size` that contains `seed` and calls `span`
+/// with the leftmost and rightmost pixels of every horizontal segment in it.
+///
+/// `inside` tells whether a pixel belongs to the region, which lets callers implement both seed
+/// fills (pixels of the same color as the seed) and boundary fills (pixels of any color other than
+/// the boundary's).  `inside` is only queried for pixels within the canvas and is expected to
+/// answer based on the contents of the canvas before the fill started, which means that callers
+/// need not worry about the region changing as `span` paints it.
+pub fn flood_fill<I, F>(
+    size: SizeInPixels,
+    seed: PixelsXY,
+    mut inside: I,
+    mut span: F,
+) -> io::Result<()>
+where
+    I: FnMut(PixelsXY) -> bool,
+    F: FnMut(PixelsXY, PixelsXY) -> io::Result<()>,
+{
+    let width = i32::from(size.width);
+    let height = i32::from(size.height);
+    let (x, y) = (i32::from(seed.x), i32::from(seed.y));
+    if x < 0 || x >= width || y < 0 || y >= height {
+        return Ok(());
+    }
+
+    // All values are within the canvas, which means they fit in a coordinate.
+    let xy = |x: i32, y: i32| PixelsXY::new(x as i16, y as i16);
+
+    let mut visited = vec![false; (width * height) as usize];
+    let index = |x: i32, y: i32| (y * width + x) as usize;
+
+    let mut pending = vec![(x, y)];
+    while let Some((x, y)) = pending.pop() {
+        if visited[index(x, y)] || !inside(xy(x, y)) {
+            continue;
+        }
+
+        let mut x1 = x;
+        while x1 > 0 && !visited[index(x1 - 1, y)] && inside(xy(x1 - 1, y)) {
+            x1 -= 1;
+        }
+        let mut x2 = x;
+        while x2 < width - 1 && !visited[index(x2 + 1, y)] && inside(xy(x2 + 1, y)) {
+            x2 += 1;
+        }
+        for x in x1..=x2 {
+            visited[index(x, y)] = true;
+        }
+        span(xy(x1, y), xy(x2, y))?;
+
+        // Queue one pixel for every run of pixels in the adjacent rows that continue the region.
+        for ny in &[y - 1, y + 1] {
+            let ny = *ny;
+            if ny < 0 || ny >= height {
+                continue;
+            }
+            let mut in_run = false;
+            for x in x1..=x2 {
+                if !visited[index(x, ny)] && inside(xy(x, ny)) {
+                    if !in_run {
+                        pending.push((x, ny));
+                        in_run = true;
+                    }
+                } else {
+                    in_run = false;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Collects the pixels produced by a rasterization function that reports individual points.
+    fn collect_points<F>(draw: F) -> BTreeSet<(i16, i16)>
+    where
+        F: FnOnce(&mut dyn FnMut(PixelsXY) -> io::Result<()>) -> io::Result<()>,
+    {
+        let mut points = BTreeSet::default();
+        draw(&mut |xy| {
+            points.insert((xy.x, xy.y));
+            Ok(())
+        })
+        .unwrap();
+        points
+    }
+
+    /// Collects the pixels produced by a rasterization function that reports horizontal spans.
+    fn collect_spans<F>(draw: F) -> BTreeSet<(i16, i16)>
+    where
+        F: FnOnce(&mut dyn FnMut(PixelsXY, PixelsXY) -> io::Result<()>) -> io::Result<()>,
+    {
+        let mut points = BTreeSet::default();
+        draw(&mut |x1y1, x2y2| {
+            assert_eq!(x1y1.y, x2y2.y);
+            assert!(x1y1.x <= x2y2.x);
+            for x in x1y1.x..=x2y2.x {
+                points.insert((x, x1y1.y));
+            }
+            Ok(())
+        })
+        .unwrap();
+        points
+    }
+
+    #[test]
+    fn test_clamped_xy() {
+        assert_eq!(PixelsXY::new(5, -7), clamped_xy(5, -7));
+        assert_eq!(PixelsXY::new(i16::MAX, i16::MIN), clamped_xy(100000, -100000));
+    }
+
+    #[test]
+    fn test_line_horizontal_and_vertical() {
+        assert_eq!(
+            [(1, 5), (2, 5), (3, 5)].iter().copied().collect::<BTreeSet<_>>(),
+            collect_points(|f| line(PixelsXY::new(3, 5), PixelsXY::new(1, 5), f))
+        );
+        assert_eq!(
+            [(-2, -1), (-2, 0), (-2, 1)].iter().copied().collect::<BTreeSet<_>>(),
+            collect_points(|f| line(PixelsXY::new(-2, -1), PixelsXY::new(-2, 1), f))
+        );
+        assert_eq!(
+            [(4, 4)].iter().copied().collect::<BTreeSet<_>>(),
+            collect_points(|f| line(PixelsXY::new(4, 4), PixelsXY::new(4, 4), f))
+        );
+    }
+
+    #[test]
+    fn test_line_diagonal() {
+        let points = collect_points(|f| line(PixelsXY::new(0, 0), PixelsXY::new(6, 3), f));
+        assert_eq!(7, points.len());
+        assert!(points.contains(&(0, 0)));
+        assert!(points.contains(&(6, 3)));
+        for (x, y) in &points {
+            assert!((i32::from(*x) - 2 * i32::from(*y)).abs() <= 1, "({}, {}) off the line", x, y);
+        }
+    }
+
+    #[test]
+    fn test_ellipse_degenerate() {
+        let center = PixelsXY::new(10, 20);
+        assert!(collect_points(|f| ellipse(center, 0, 5, f)).is_empty());
+        assert!(collect_points(|f| ellipse(center, 5, 0, f)).is_empty());
+        assert_eq!(
+            [(10, 20)].iter().copied().collect::<BTreeSet<_>>(),
+            collect_points(|f| ellipse(center, 1, 1, f))
+        );
+        assert_eq!(
+            [(10, 19), (10, 20), (10, 21)].iter().copied().collect::<BTreeSet<_>>(),
+            collect_points(|f| ellipse(center, 1, 2, f))
+        );
+    }
+
+    #[test]
+    fn test_ellipse_symmetric_and_bounded() {
+        let center = PixelsXY::new(0, 0);
+        let points = collect_points(|f| ellipse(center, 11, 6, f));
+        for (x, y) in &points {
+            assert!(points.contains(&(-x, *y)));
+            assert!(points.contains(&(*x, -y)));
+            assert!(x.abs() <= 10);
+            assert!(y.abs() <= 5);
+        }
+        assert!(points.contains(&(10, 0)));
+        assert!(points.contains(&(-10, 0)));
+        assert!(points.contains(&(0, 5)));
+        assert!(points.contains(&(0, -5)));
+    }
+
+    #[test]
+    fn test_ellipse_filled_covers_outline() {
+        let center = PixelsXY::new(3, 4);
+        let outline = collect_points(|f| ellipse(center, 8, 5, f));
+        let area = collect_spans(|f| ellipse_filled(center, 8, 5, f));
+        assert!(outline.is_subset(&area));
+        assert!(area.contains(&(3, 4)));
+        assert!(!area.contains(&(3 + 8, 4)));
+        assert!(!area.contains(&(3, 4 + 5)));
+    }
+
+    #[test]
+    fn test_arc_quadrants() {
+        let center = PixelsXY::new(0, 0);
+        let points = collect_points(|f| arc(center, 6, 0, 90, f));
+        assert!(points.contains(&(5, 0)));
+        assert!(points.contains(&(0, -5)));
+        for (x, y) in &points {
+            assert!(*x >= 0 && *y <= 0, "({}, {}) not in the top-right quadrant", x, y);
+        }
+
+        let points = collect_points(|f| arc(center, 6, 270, 450, f));
+        for (x, _y) in &points {
+            assert!(*x >= 0);
+        }
+        assert!(points.contains(&(0, 5)));
+        assert!(points.contains(&(0, -5)));
+    }
+
+    #[test]
+    fn test_arc_full_circle() {
+        let center = PixelsXY::new(0, 0);
+        assert_eq!(
+            collect_points(|f| ellipse(center, 7, 7, f)),
+            collect_points(|f| arc(center, 7, -30, 330, f))
+        );
+    }
+
+    #[test]
+    fn test_poly_edges() {
+        let mut edges = vec![];
+        let points = [PixelsXY::new(0, 0), PixelsXY::new(5, 0), PixelsXY::new(0, 5)];
+        poly(&points, |a, b| {
+            edges.push((a, b));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            vec![(points[0], points[1]), (points[1], points[2]), (points[2], points[0])],
+            edges
+        );
+
+        edges.clear();
+        poly(&points[0..2], |a, b| {
+            edges.push((a, b));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(vec![(points[0], points[1])], edges);
+    }
+
+    #[test]
+    fn test_poly_filled_square() {
+        let points =
+            [PixelsXY::new(0, 0), PixelsXY::new(3, 0), PixelsXY::new(3, 3), PixelsXY::new(0, 3)];
+        let area = collect_spans(|f| poly_filled(&points, f));
+        let mut exp = BTreeSet::default();
+        for y in 0..3 {
+            for x in 0..=3 {
+                exp.insert((x, y));
+            }
+        }
+        assert_eq!(exp, area);
+    }
+
+    #[test]
+    fn test_poly_filled_concave() {
+        // A "U" shape: the gap between the two arms must remain empty.
+        let points = [
+            PixelsXY::new(0, 0),
+            PixelsXY::new(2, 0),
+            PixelsXY::new(2, 4),
+            PixelsXY::new(6, 4),
+            PixelsXY::new(6, 0),
+            PixelsXY::new(8, 0),
+            PixelsXY::new(8, 6),
+            PixelsXY::new(0, 6),
+        ];
+        let area = collect_spans(|f| poly_filled(&points, f));
+        assert!(area.contains(&(1, 1)));
+        assert!(area.contains(&(7, 1)));
+        assert!(!area.contains(&(4, 1)));
+        assert!(area.contains(&(4, 5)));
+    }
+
+    /// Parses a textual representation of a canvas, where every line is a row and every
+    /// character is a pixel, and returns its size and the pixels that are set.
+    fn parse_canvas(rows: &[&str]) -> (SizeInPixels, BTreeSet<(i16, i16)>) {
+        let mut set = BTreeSet::default();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                if ch == '#' {
+                    set.insert((x as i16, y as i16));
+                }
+            }
+        }
+        let size = SizeInPixels { width: rows[0].len() as u16, height: rows.len() as u16 };
+        (size, set)
+    }
+
+    #[test]
+    fn test_flood_fill_enclosed_region() {
+        let (size, walls) = parse_canvas(&[
+            "..........",
+            ".#####....",
+            ".#...#....",
+            ".#..##....",
+            ".#..#.....",
+            ".####.....",
+        ]);
+        let area = collect_spans(|f| {
+            flood_fill(size, PixelsXY::new(2, 2), |xy| !walls.contains(&(xy.x, xy.y)), f)
+        });
+        let (_size, exp) = parse_canvas(&[
+            "..........",
+            "..........",
+            "..###.....",
+            "..##......",
+            "..##......",
+            "..........",
+        ]);
+        assert_eq!(exp, area);
+    }
+
+    #[test]
+    fn test_flood_fill_wraps_around_obstacles() {
+        let (size, walls) = parse_canvas(&["....", ".##.", "...."]);
+        let area = collect_spans(|f| {
+            flood_fill(size, PixelsXY::new(0, 0), |xy| !walls.contains(&(xy.x, xy.y)), f)
+        });
+        assert_eq!(size.width as usize * size.height as usize - walls.len(), area.len());
+        assert!(area.is_disjoint(&walls));
+    }
+
+    #[test]
+    fn test_flood_fill_seed_not_inside() {
+        let size = SizeInPixels { width: 5, height: 5 };
+        assert!(collect_spans(|f| flood_fill(size, PixelsXY::new(2, 2), |_| false, f)).is_empty());
+    }
+
+    #[test]
+    fn test_flood_fill_seed_off_canvas() {
+        let size = SizeInPixels { width: 5, height: 5 };
+        for seed in &[PixelsXY::new(-1, 0), PixelsXY::new(0, -1), PixelsXY::new(5, 0)] {
+            assert!(collect_spans(|f| flood_fill(size, *seed, |_| true, f)).is_empty());
+        }
+        assert_eq!(25, collect_spans(|f| flood_fill(size, PixelsXY::new(4, 4), |_| true, f)).len());
+    }
+
+    #[test]
+    fn test_poly_filled_too_few_points() {
+        let points = [PixelsXY::new(0, 0), PixelsXY::new(5, 5)];
+        assert!(collect_spans(|f| poly_filled(&points, f)).is_empty());
+    }
+}
@@ -44,6 +44,10 @@ impl Pins for NoopPins {
     fn write(&mut self, _pin: Pin, _v: bool) -> io::Result<()> {
         Err(io::Error::new(io::ErrorKind::Other, "GPIO backend not compiled in"))
     }
+
+    fn pwm(&mut self, _pin: Pin, _frequency: f64, _duty_cycle: f64) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "GPIO backend not compiled in"))
+    }
 }
 
 /// Mock GPIO implementation that tracks operations and supplies fake reads.
@@ -68,6 +72,11 @@ impl Pins for NoopPins {
 /// and will be updated with the affected pin number and the operation.  The meta operation to clear
 /// all pins has a special number.
 ///
+/// PWM calls are special in that they take three consecutive positions in `data`: the first one
+/// holds the pin number and the operation, the second one holds the frequency in Hz, and the third
+/// one holds the duty cycle as a percentage.  Both the frequency and the duty cycle are rounded to
+/// the nearest integer.
+///
 /// For read GPIO calls, `data[last]` has to contain the pin number that matches the read operation
 /// and the desired outcome of the operation.
 ///
@@ -93,6 +102,8 @@ enum MockOp {
 
     WriteLow = 20,
     WriteHigh = 21,
+
+    Pwm = 30,
 }
 
 impl MockOp {
@@ -254,4 +265,10 @@ impl<'a> Pins for MockPins<'a> {
             self.append(MockOp::encode(pin, MockOp::WriteLow))
         }
     }
+
+    fn pwm(&mut self, pin: Pin, frequency: f64, duty_cycle: f64) -> io::Result<()> {
+        self.append(MockOp::encode(pin, MockOp::Pwm))?;
+        self.append(frequency.round() as i32)?;
+        self.append((duty_cycle * 100.0).round() as i32)
+    }
 }
@@ -33,11 +33,11 @@ use std::result::Result;
 mod fakes;
 pub(crate) use fakes::{MockPins, NoopPins};
 
-/// Category description for all symbols provided by this module.
-const CATEGORY: &str = "Hardware interface
+/// Category description for all symbols provided by this module and by the `i2c` and `spi` modules.
+pub(crate) const CATEGORY: &str = "Hardware interface
 EndBASIC provides features to manipulate external hardware.  These features are currently limited \
-to GPIO interaction on a Raspberry Pi and are only available when EndBASIC has explicitly been \
-built with the --features=rpi option.  Support for other busses and platforms may come later.";
+to GPIO, I2C, and SPI interaction on a Raspberry Pi and are only available when EndBASIC has \
+explicitly been built with the --features=rpi option.  Support for other platforms may come later.";
 
 /// Pin identifier.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -119,6 +119,35 @@ async fn parse_value(expr: &Expr, machine: &mut Machine) -> Result<bool, CallErr
     }
 }
 
+/// Obtains a positive PWM frequency from an expression.
+async fn parse_frequency(expr: &Expr, machine: &mut Machine) -> Result<f64, CallError> {
+    let value = expr.eval(machine.get_mut_symbols()).await?;
+    let frequency =
+        value.as_f64().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    if frequency <= 0.0 {
+        return Err(CallError::ArgumentError(
+            expr.start_pos(),
+            format!("Frequency {} must be positive", value),
+        ));
+    }
+    Ok(frequency)
+}
+
+/// Obtains a PWM duty cycle from an expression expressed as a percentage and returns it as a
+/// fraction in the `[0.0, 1.0]` range.
+async fn parse_duty_cycle(expr: &Expr, machine: &mut Machine) -> Result<f64, CallError> {
+    let value = expr.eval(machine.get_mut_symbols()).await?;
+    let duty =
+        value.as_f64().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    if !(0.0..=100.0).contains(&duty) {
+        return Err(CallError::ArgumentError(
+            expr.start_pos(),
+            format!("Duty cycle {} must be between 0 and 100", value),
+        ));
+    }
+    Ok(duty / 100.0)
+}
+
 /// Generic abstraction over a GPIO chip to back all EndBASIC commands.
 pub trait Pins {
     /// Configures the `pin` as either input or output (per `mode`).
@@ -139,6 +168,12 @@ pub trait Pins {
 
     /// Writes `v` to the given `pin`, which must have been previously setup as an output pin.
     fn write(&mut self, pin: Pin, v: bool) -> io::Result<()>;
+
+    /// Starts emitting a PWM signal on the given `pin`, which must have been previously setup as
+    /// an output pin, with the given `frequency` in Hz and `duty_cycle` in the `[0.0, 1.0]` range.
+    ///
+    /// The signal continues until the pin is written to, cleared, or configured again.
+    fn pwm(&mut self, pin: Pin, frequency: f64, duty_cycle: f64) -> io::Result<()>;
 }
 
 /// Resets the state of the pins in a best-effort manner.
@@ -268,6 +303,59 @@ impl Command for GpioClearCommand {
     }
 }
 
+/// The `GPIO_PWM` command.
+pub struct GpioPwmCommand {
+    metadata: CallableMetadata,
+    pins: Rc<RefCell<dyn Pins>>,
+}
+
+impl GpioPwmCommand {
+    /// Creates a new instance of the command.
+    pub fn new(pins: Rc<RefCell<dyn Pins>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GPIO_PWM", VarType::Void)
+                .with_syntax("pin%, frequency#, duty#")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Emits a PWM signal on a GPIO pin.
+Pulse-width modulation (PWM) toggles the pin between high and low at the given frequency# (in \
+Hz), keeping it high for the duty# percentage of each period.  This is useful to dim LEDs or to \
+control servos and motors.  The pin must first be configured as an output with GPIO_SETUP.
+The duty# must be between 0 and 100, where 0 keeps the pin always low and 100 keeps it always \
+high.  Calling GPIO_PWM again on the same pin adjusts the signal.
+The signal keeps running in the background until the pin is written to with GPIO_WRITE, cleared \
+with GPIO_CLEAR, or reconfigured with GPIO_SETUP.",
+                )
+                .build(),
+            pins,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for GpioPwmCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [ArgSpan { expr: Some(pin), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(frequency), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(duty), sep: ArgSep::End, .. }] =>
+            {
+                let pin = Pin::parse(pin, machine).await?;
+                let frequency = parse_frequency(frequency, machine).await?;
+                let duty = parse_duty_cycle(duty, machine).await?;
+                match MockPins::try_new(machine.get_mut_symbols()) {
+                    Some(mut pins) => pins.pwm(pin, frequency, duty)?,
+                    None => self.pins.borrow_mut().pwm(pin, frequency, duty)?,
+                };
+                Ok(())
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
 /// The `GPIO_READ` function.
 pub struct GpioReadFunction {
     metadata: CallableMetadata,
@@ -363,6 +451,7 @@ impl Command for GpioWriteCommand {
 pub fn add_all(machine: &mut Machine, pins: Rc<RefCell<dyn Pins>>) {
     machine.add_clearable(PinsClearable::new(pins.clone()));
     machine.add_command(GpioClearCommand::new(pins.clone()));
+    machine.add_command(GpioPwmCommand::new(pins.clone()));
     machine.add_command(GpioSetupCommand::new(pins.clone()));
     machine.add_command(GpioWriteCommand::new(pins.clone()));
     machine.add_function(GpioReadFunction::new(pins));
@@ -425,6 +514,10 @@ mod tests {
             "1:1: In call to GPIO_WRITE: GPIO backend not compiled in",
             "GPIO_WRITE 0, TRUE",
         );
+        check_stmt_err(
+            "1:1: In call to GPIO_PWM: GPIO backend not compiled in",
+            "GPIO_PWM 0, 50, 10",
+        );
     }
 
     #[test]
@@ -528,4 +621,45 @@ mod tests {
             r#"GPIO_WRITE 1, 5"#,
         );
     }
+
+    #[test]
+    fn test_gpio_pwm_ok() {
+        do_mock_test("GPIO_PWM 18, 50, 7.5", &[1830, 50, 8]);
+        do_mock_test("GPIO_PWM 3.1, 1000.4, 0: GPIO_PWM 4, 0.5, 100", &[330, 1000, 0, 430, 1, 100]);
+    }
+
+    #[test]
+    fn test_gpio_pwm_errors() {
+        check_stmt_err(
+            "1:1: In call to GPIO_PWM: expected pin%, frequency#, duty#",
+            r#"GPIO_PWM 1, 2"#,
+        );
+        check_stmt_err(
+            "1:1: In call to GPIO_PWM: expected pin%, frequency#, duty#",
+            r#"GPIO_PWM 1, 2; 3"#,
+        );
+        check_stmt_err(
+            "1:1: In call to GPIO_PWM: expected pin%, frequency#, duty#",
+            r#"GPIO_PWM 1, 2, 3, 4"#,
+        );
+
+        check_pin_validation("1:1: In call to GPIO_PWM: 1:10: ", r#"GPIO_PWM _PIN_, 50, 10"#);
+
+        check_stmt_err(
+            "1:1: In call to GPIO_PWM: 1:13: \"50\" is not a number",
+            r#"GPIO_PWM 1, "50", 10"#,
+        );
+        check_stmt_err(
+            "1:1: In call to GPIO_PWM: 1:13: Frequency 0 must be positive",
+            r#"GPIO_PWM 1, 0, 10"#,
+        );
+        check_stmt_err(
+            "1:1: In call to GPIO_PWM: 1:17: Duty cycle -1 must be between 0 and 100",
+            r#"GPIO_PWM 1, 50, -1"#,
+        );
+        check_stmt_err(
+            "1:1: In call to GPIO_PWM: 1:17: Duty cycle 100.5 must be between 0 and 100",
+            r#"GPIO_PWM 1, 50, 100.5"#,
+        );
+    }
 }
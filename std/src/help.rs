@@ -17,6 +17,7 @@
 
 use crate::console::{refill_and_print, AnsiColor, Console};
 use crate::exec::CATEGORY;
+use crate::i18n;
 use async_trait::async_trait;
 use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Value, VarType};
 use endbasic_core::exec::Machine;
@@ -26,9 +27,10 @@ use endbasic_core::syms::{
 use endbasic_core::LineCol;
 use radix_trie::{Trie, TrieCommon};
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io;
 use std::rc::Rc;
+use std::str::Lines;
 
 /// Raw text for the language reference.
 const LANG_MD: &str = include_str!("lang.md");
@@ -43,13 +45,33 @@ const LINK_COLOR: u8 = AnsiColor::BrightCyan as u8;
 fn header() -> Vec<String> {
     vec![
         "".to_owned(),
-        format!("    This is EndBASIC {}.", env!("CARGO_PKG_VERSION")),
+        format!(
+            "    {}",
+            i18n::translate(&format!("This is EndBASIC {}.", env!("CARGO_PKG_VERSION")))
+        ),
         "".to_owned(),
-        format!("    Project page at <{}>", env!("CARGO_PKG_HOMEPAGE")),
-        "    License Apache Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>".to_owned(),
+        format!(
+            "    {}",
+            i18n::translate(&format!("Project page at <{}>", env!("CARGO_PKG_HOMEPAGE")))
+        ),
+        format!(
+            "    {}",
+            i18n::translate(
+                "License Apache Version 2.0 <http://www.apache.org/licenses/LICENSE-2.0>"
+            )
+        ),
     ]
 }
 
+/// Returns the lines of the description of the callable described by `metadata` in the language
+/// of the interpreter.
+fn description(metadata: &CallableMetadata) -> Lines<'static> {
+    match i18n::translate_description(metadata.name()) {
+        Some(description) => description.lines(),
+        None => metadata.description(),
+    }
+}
+
 /// Handler for a specific help topic.
 trait Topic {
     /// Returns the name of the topic.
@@ -61,14 +83,36 @@ trait Topic {
     /// Indicates whether this topic shows up in the topics summary or not.
     fn show_in_summary(&self) -> bool;
 
+    /// Checks if the text of this topic contains `needle`, which must be in lowercase.
+    fn mentions(&self, needle: &str) -> bool;
+
     /// Dumps the contents of this topic to the `_console`.
     fn describe(&self, _console: &mut dyn Console) -> io::Result<()>;
 }
 
+/// Finds the names of other callables mentioned in the description of `metadata`.
+///
+/// `names` maps the names of all known callables to the names of their help topics.
+fn see_also(metadata: &CallableMetadata, names: &HashMap<&str, String>) -> Vec<String> {
+    let mut refs = BTreeSet::default();
+    for line in description(metadata) {
+        for word in line.split(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_')) {
+            if word == metadata.name() {
+                continue;
+            }
+            if let Some(name) = names.get(word) {
+                refs.insert(name.clone());
+            }
+        }
+    }
+    refs.into_iter().collect()
+}
+
 /// A help topic to describe a callable.
 struct CallableTopic {
     name: String,
     metadata: CallableMetadata,
+    see_also: Vec<String>,
 }
 
 impl Topic for CallableTopic {
@@ -77,13 +121,18 @@ impl Topic for CallableTopic {
     }
 
     fn title(&self) -> &str {
-        self.metadata.description().next().unwrap()
+        description(&self.metadata).next().unwrap()
     }
 
     fn show_in_summary(&self) -> bool {
         false
     }
 
+    fn mentions(&self, needle: &str) -> bool {
+        self.name.to_lowercase().contains(needle)
+            || description(&self.metadata).any(|line| line.to_lowercase().contains(needle))
+    }
+
     fn describe(&self, console: &mut dyn Console) -> io::Result<()> {
         console.print("")?;
         let previous = console.color();
@@ -124,9 +173,23 @@ impl Topic for CallableTopic {
             }
         }
         console.set_color(previous.0, previous.1)?;
-        if !self.metadata.description().count() > 0 {
+        if !description(&self.metadata).count() > 0 {
+            console.print("")?;
+            refill_and_print(console, description(&self.metadata), "    ")?;
+        }
+        if !self.see_also.is_empty() {
+            console.print("")?;
+            // TODO(jmmv): Should use refill_and_print but the links need to be colored.
+            console.write(&format!("    {} ", i18n::translate("See also:")))?;
+            for (i, name) in self.see_also.iter().enumerate() {
+                if i > 0 {
+                    console.write(", ")?;
+                }
+                console.set_color(Some(LINK_COLOR), previous.1)?;
+                console.write(name)?;
+                console.set_color(previous.0, previous.1)?;
+            }
             console.print("")?;
-            refill_and_print(console, self.metadata.description(), "    ")?;
         }
         console.print("")?;
         Ok(())
@@ -134,7 +197,7 @@ impl Topic for CallableTopic {
 }
 
 /// Generates the index for a collection of `CallableMetadata`s to use in a `CategoryTopic`.
-fn callables_to_index(metadatas: &[CallableMetadata]) -> BTreeMap<String, &'static str> {
+fn callables_to_index(metadatas: &[CallableMetadata]) -> BTreeMap<String, String> {
     let category = metadatas.get(0).expect("Must have at least one symbol").category();
 
     let mut index = BTreeMap::default();
@@ -145,7 +208,7 @@ fn callables_to_index(metadatas: &[CallableMetadata]) -> BTreeMap<String, &'stat
             "All commands registered in this category must be equivalent"
         );
         let name = format!("{}{}", metadata.name(), metadata.return_type().annotation());
-        let blurb = metadata.description().next().unwrap();
+        let blurb = description(metadata).next().unwrap().to_owned();
         let previous = index.insert(name, blurb);
         assert!(previous.is_none(), "Names should have been unique");
     }
@@ -154,24 +217,28 @@ fn callables_to_index(metadatas: &[CallableMetadata]) -> BTreeMap<String, &'stat
 
 /// A help topic to describe a category of callables.
 struct CategoryTopic {
-    name: &'static str,
-    description: &'static str,
-    index: BTreeMap<String, &'static str>,
+    name: String,
+    description: String,
+    index: BTreeMap<String, String>,
 }
 
 impl Topic for CategoryTopic {
     fn name(&self) -> &str {
-        self.name
+        &self.name
     }
 
     fn title(&self) -> &str {
-        self.name
+        &self.name
     }
 
     fn show_in_summary(&self) -> bool {
         true
     }
 
+    fn mentions(&self, _needle: &str) -> bool {
+        false
+    }
+
     fn describe(&self, console: &mut dyn Console) -> io::Result<()> {
         let max_length = self
             .index
@@ -206,7 +273,7 @@ impl Topic for CategoryTopic {
         console.print("")?;
         refill_and_print(
             console,
-            ["Type HELP followed by the name of a topic for details."],
+            [&i18n::translate("Type HELP followed by the name of a topic for details.")],
             "    ",
         )?;
         console.print("")?;
@@ -233,6 +300,10 @@ impl Topic for LanguageTopic {
         false
     }
 
+    fn mentions(&self, needle: &str) -> bool {
+        self.text.to_lowercase().contains(needle)
+    }
+
     fn describe(&self, console: &mut dyn Console) -> io::Result<()> {
         let previous = console.color();
 
@@ -318,20 +389,30 @@ impl Topics {
 
             for (title, content) in parse_lang_reference(LANG_MD) {
                 let topic = LanguageTopic { name: title, text: content };
-                index.insert(topic.name.to_owned(), topic.text.lines().next().unwrap());
+                index.insert(topic.name.to_owned(), topic.title().to_owned());
                 insert(&mut topics, Box::from(topic));
             }
 
             insert(
                 &mut topics,
                 Box::from(CategoryTopic {
-                    name: "Language reference",
-                    description: "General language topics",
+                    name: i18n::translate("Language reference"),
+                    description: i18n::translate("General language topics"),
                     index,
                 }),
             );
         }
 
+        let mut names = HashMap::new();
+        for symbol in symbols.as_hashmap().values() {
+            if let Some(metadata) = symbol.metadata() {
+                names.insert(
+                    metadata.name(),
+                    format!("{}{}", metadata.name(), metadata.return_type().annotation()),
+                );
+            }
+        }
+
         let mut categories = HashMap::new();
         for (name, symbol) in symbols.as_hashmap().iter() {
             if let Some(metadata) = symbol.metadata() {
@@ -346,35 +427,42 @@ impl Topics {
                     Box::from(CallableTopic {
                         name: format!("{}{}", name, metadata.return_type().annotation()),
                         metadata: metadata.clone(),
+                        see_also: see_also(metadata, &names),
                     }),
                 );
             }
         }
-        for (name, metadatas) in categories.into_iter() {
-            let description = metadatas.get(0).expect("Must have at least one symbol").category();
+        for metadatas in categories.into_values() {
+            let description = i18n::translate_category(
+                metadatas.get(0).expect("Must have at least one symbol").category(),
+            );
+            let name = description.lines().next().unwrap().to_owned();
             let index = callables_to_index(&metadatas);
-            insert(&mut topics, Box::from(CategoryTopic { name, description, index }));
+            insert(
+                &mut topics,
+                Box::from(CategoryTopic { name, description: description.to_owned(), index }),
+            );
         }
 
         Self(topics)
     }
 
-    /// Returns the given topic named `name`, where `name` can be a prefix.
+    /// Returns the given topic named `name`, where `name` can be a prefix, or none if the topic
+    /// does not exist.
     ///
-    /// If `name` is not long enough to uniquely identify a topic or if the topic does not exist,
-    /// returns an error.
-    fn find(&self, name: &str, pos: LineCol) -> Result<&dyn Topic, CallError> {
+    /// If `name` is not long enough to uniquely identify a topic, returns an error.
+    fn find(&self, name: &str, pos: LineCol) -> Result<Option<&dyn Topic>, CallError> {
         let key = name.to_ascii_uppercase();
 
         if let Some(topic) = self.0.get(&key) {
-            return Ok(topic.as_ref());
+            return Ok(Some(topic.as_ref()));
         }
 
         match self.0.get_raw_descendant(&key) {
             Some(subtrie) => {
                 let children: Vec<(&String, &Box<dyn Topic>)> = subtrie.iter().collect();
                 match children[..] {
-                    [(_name, topic)] => Ok(topic.as_ref()),
+                    [(_name, topic)] => Ok(Some(topic.as_ref())),
                     _ => {
                         let completions: Vec<String> =
                             children.iter().map(|(name, _topic)| (*name).to_owned()).collect();
@@ -389,10 +477,28 @@ impl Topics {
                     }
                 }
             }
-            None => Err(CallError::ArgumentError(pos, format!("Unknown help topic {}", name))),
+            None => Ok(None),
         }
     }
 
+    /// Returns a topic that lists all topics whose text contains `text`, or none if there are no
+    /// such topics.
+    fn search(&self, text: &str) -> Option<CategoryTopic> {
+        let needle = text.to_lowercase();
+        let index: BTreeMap<String, String> = self
+            .0
+            .values()
+            .filter(|topic| topic.mentions(&needle))
+            .map(|topic| (topic.name().to_owned(), topic.title().to_owned()))
+            .collect();
+        if index.is_empty() {
+            return None;
+        }
+
+        let name = i18n::translate(&format!("Topics that mention \"{}\"", text));
+        Some(CategoryTopic { name: name.clone(), description: name, index })
+    }
+
     /// Returns an iterator over all the topics.
     fn values(&self) -> radix_trie::iter::Values<String, Box<dyn Topic>> {
         self.0.values()
@@ -419,7 +525,12 @@ With a single argument, which must be a string, shows detailed information about
 topic, command, or function.
 Topic names are case-insensitive and can be specified as prefixes, in which case the topic whose \
 name starts with the prefix will be shown.  For example, the following invocations are all \
-equivalent: HELP \"CON\", HELP \"console\", HELP \"Console manipulation\".",
+equivalent: HELP \"CON\", HELP \"console\", HELP \"Console manipulation\".
+If the argument does not name any topic, HELP searches for it in the text of all commands, \
+functions, and language topics, and lists those that mention it.  For example, HELP \"cursor\" \
+lists every symbol whose description talks about the cursor.
+The description of commands and functions ends with a list of other related symbols to look at \
+when their text refers to them.",
                 )
                 .build(),
             console,
@@ -436,7 +547,7 @@ equivalent: HELP \"CON\", HELP \"console\", HELP \"Console manipulation\".",
 
         console.print("")?;
         console.set_color(Some(TITLE_COLOR), previous.1)?;
-        refill_and_print(&mut *console, ["Top-level help topics"], "    ")?;
+        refill_and_print(&mut *console, [&i18n::translate("Top-level help topics")], "    ")?;
         console.set_color(previous.0, previous.1)?;
         console.print("")?;
         for topic in topics.values() {
@@ -452,20 +563,24 @@ equivalent: HELP \"CON\", HELP \"console\", HELP \"Console manipulation\".",
         console.print("")?;
         refill_and_print(
             &mut *console,
-            ["Type HELP followed by the name of a topic for details."],
+            [&i18n::translate("Type HELP followed by the name of a topic for details.")],
+            "    ",
+        )?;
+        refill_and_print(
+            &mut *console,
+            [&i18n::translate("Type HELP \"HELP\" for details on how to specify topic names.")],
             "    ",
         )?;
         refill_and_print(
             &mut *console,
-            ["Type HELP \"HELP\" for details on how to specify topic names."],
+            [&i18n::translate(r#"Type LOAD "DEMOS:/TOUR.BAS": RUN for a guided tour."#)],
             "    ",
         )?;
         refill_and_print(
             &mut *console,
-            [r#"Type LOAD "DEMOS:/TOUR.BAS": RUN for a guided tour."#],
+            [&i18n::translate(r#"Type END or press CTRL+D to exit."#)],
             "    ",
         )?;
-        refill_and_print(&mut *console, [r#"Type END or press CTRL+D to exit."#], "    ")?;
         console.print("")?;
 
         Ok(())
@@ -493,7 +608,19 @@ impl Command for HelpCommand {
                 let pos = expr.start_pos();
                 match expr.eval(machine.get_mut_symbols()).await? {
                     Value::Text(t) => {
-                        let topic = topics.find(&t, pos)?;
+                        let results;
+                        let topic = match topics.find(&t, pos)? {
+                            Some(topic) => topic,
+                            None => {
+                                results = topics.search(&t).ok_or_else(|| {
+                                    CallError::ArgumentError(
+                                        pos,
+                                        format!("Unknown help topic {}", t),
+                                    )
+                                })?;
+                                &results
+                            }
+                        };
                         let mut console = self.console.borrow_mut();
                         let previous = console.set_sync(false)?;
                         let result = topic.describe(&mut *console);
@@ -535,6 +662,19 @@ pub(crate) mod testutils {
 
         /// Creates a new instance of the command with a given `name`.
         pub fn new_with_name(name: &'static str) -> Rc<Self> {
+            DoNothingCommand::new_with_description(
+                name,
+                "This is the blurb.
+First paragraph of the extended description.
+Second paragraph of the extended description.",
+            )
+        }
+
+        /// Creates a new instance of the command with a given `name` and `description`.
+        pub(crate) fn new_with_description(
+            name: &'static str,
+            description: &'static str,
+        ) -> Rc<Self> {
             Rc::from(Self {
                 metadata: CallableMetadataBuilder::new(name, VarType::Void)
                     .with_syntax("this [would] <be|the> syntax \"specification\"")
@@ -542,11 +682,7 @@ pub(crate) mod testutils {
                         "Testing
 This is a sample category for testing.",
                     )
-                    .with_description(
-                        "This is the blurb.
-First paragraph of the extended description.
-Second paragraph of the extended description.",
-                    )
+                    .with_description(description)
                     .build(),
             })
         }
@@ -799,6 +935,37 @@ This is the first and only topic with just one line.
             .check();
     }
 
+    #[test]
+    fn test_help_describe_in_spanish() {
+        i18n::set_locale(i18n::Locale::Spanish);
+        let mut t = tester();
+        t.get_console().borrow_mut().set_color(Some(20), Some(21)).unwrap();
+        t.run(r#"help "intérprete""#)
+            .expect_output([CapturedOut::SetColor(Some(20), Some(21)), CapturedOut::SetSync(false)])
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::SetColor(Some(TITLE_COLOR), Some(21)),
+                CapturedOut::Print("    Intérprete".to_owned()),
+                CapturedOut::SetColor(Some(20), Some(21)),
+            ])
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::Write("    >> ".to_owned()),
+                CapturedOut::SetColor(Some(LINK_COLOR), Some(21)),
+                CapturedOut::Write("HELP".to_owned()),
+                CapturedOut::SetColor(Some(20), Some(21)),
+                CapturedOut::Print("    Muestra la ayuda interactiva.".to_owned()),
+            ])
+            .expect_prints([
+                "",
+                "    Escribe HELP seguido del nombre de un tema para ver los detalles.",
+                "",
+            ])
+            .expect_output([CapturedOut::SetSync(true)])
+            .check();
+        i18n::set_locale(i18n::Locale::English);
+    }
+
     fn do_help_describe_function_test(name: &str) {
         let mut t = tester().add_function(EmptyFunction::new());
         t.get_console().borrow_mut().set_color(Some(30), Some(26)).unwrap();
@@ -925,6 +1092,85 @@ This is the first and only topic with just one line.
             .check();
     }
 
+    #[test]
+    fn test_help_describe_see_also() {
+        let mut t = tester()
+            .add_command(DoNothingCommand::new())
+            .add_command(DoNothingCommand::new_with_description(
+                "LINKER",
+                "This is the blurb.
+Use EMPTY$ or DO_NOTHING instead, but not LINKER nor UNKNOWN.",
+            ))
+            .add_function(EmptyFunction::new());
+        t.get_console().borrow_mut().set_color(Some(20), Some(21)).unwrap();
+        t.run(r#"help "linker""#)
+            .expect_output([CapturedOut::SetColor(Some(20), Some(21)), CapturedOut::SetSync(false)])
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::SetColor(Some(TITLE_COLOR), Some(21)),
+                CapturedOut::Print(
+                    "    LINKER this [would] <be|the> syntax \"specification\"".to_owned(),
+                ),
+                CapturedOut::SetColor(Some(20), Some(21)),
+            ])
+            .expect_prints([
+                "",
+                "    This is the blurb.",
+                "",
+                "    Use EMPTY$ or DO_NOTHING instead, but not LINKER nor UNKNOWN.",
+                "",
+            ])
+            .expect_output([
+                CapturedOut::Write("    See also: ".to_owned()),
+                CapturedOut::SetColor(Some(LINK_COLOR), Some(21)),
+                CapturedOut::Write("DO_NOTHING".to_owned()),
+                CapturedOut::SetColor(Some(20), Some(21)),
+                CapturedOut::Write(", ".to_owned()),
+                CapturedOut::SetColor(Some(LINK_COLOR), Some(21)),
+                CapturedOut::Write("EMPTY$".to_owned()),
+                CapturedOut::SetColor(Some(20), Some(21)),
+                CapturedOut::Print("".to_owned()),
+            ])
+            .expect_prints([""])
+            .expect_output([CapturedOut::SetSync(true)])
+            .check();
+    }
+
+    #[test]
+    fn test_help_search() {
+        let mut t = tester()
+            .add_command(DoNothingCommand::new())
+            .add_command(DoNothingCommand::new_with_description("OTHER", "Does something else."))
+            .add_function(EmptyFunction::new());
+        t.get_console().borrow_mut().set_color(Some(70), Some(50)).unwrap();
+        t.run(r#"help "EXTENDED Description""#)
+            .expect_output([CapturedOut::SetColor(Some(70), Some(50)), CapturedOut::SetSync(false)])
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::SetColor(Some(TITLE_COLOR), Some(50)),
+                CapturedOut::Print("    Topics that mention \"EXTENDED Description\"".to_owned()),
+                CapturedOut::SetColor(Some(70), Some(50)),
+            ])
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::Write("    >> ".to_owned()),
+                CapturedOut::SetColor(Some(LINK_COLOR), Some(50)),
+                CapturedOut::Write("DO_NOTHING".to_owned()),
+                CapturedOut::SetColor(Some(70), Some(50)),
+                CapturedOut::Print("    This is the blurb.".to_owned()),
+            ])
+            .expect_output([
+                CapturedOut::Write("    >> ".to_owned()),
+                CapturedOut::SetColor(Some(LINK_COLOR), Some(50)),
+                CapturedOut::Write("EMPTY$    ".to_owned()),
+                CapturedOut::SetColor(Some(70), Some(50)),
+                CapturedOut::Print("    This is the blurb.".to_owned()),
+            ])
+            .expect_prints(["", "    Type HELP followed by the name of a topic for details.", ""])
+            .expect_output([CapturedOut::SetSync(true)])
+            .check();
+    }
+
     #[test]
     fn test_help_errors() {
         let mut t =
@@ -945,8 +1191,8 @@ This is the first and only topic with just one line.
         t.run(r#"HELP "foo$""#)
             .expect_err("1:1: In call to HELP: 1:6: Unknown help topic foo$")
             .check();
-        t.run(r#"HELP "foo""#)
-            .expect_err("1:1: In call to HELP: 1:6: Unknown help topic foo")
+        t.run(r#"HELP "xyzzy""#)
+            .expect_err("1:1: In call to HELP: 1:6: Unknown help topic xyzzy")
             .check();
 
         t.run(r#"HELP "do_nothing$""#)
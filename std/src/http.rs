@@ -0,0 +1,431 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! HTTP client functions for EndBASIC.
+
+use async_trait::async_trait;
+use endbasic_core::ast::{FunctionCallSpan, Value, VarType};
+use endbasic_core::eval::eval_all;
+use endbasic_core::exec::{Clearable, Machine};
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Function, FunctionResult, Symbols,
+};
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Networking
+EndBASIC can talk to web servers over HTTP, which allows programs to query public web APIs.
+HTTP_GET$ and HTTP_POST$ send a request to a URL and wait for the server to respond, returning \
+the body of the response.  The status code and headers of the most recent response can be \
+queried afterwards with HTTP_STATUS% and HTTP_HEADER$.
+Responses that carry JSON documents can be converted to collections with JSON_PARSE.";
+
+/// Content type to use for POST requests when the caller does not specify one.
+const DEFAULT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Response to an HTTP request.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Response {
+    /// The HTTP status code returned by the server.
+    pub status: u16,
+
+    /// The headers returned by the server as name/value pairs.
+    pub headers: Vec<(String, String)>,
+
+    /// The body of the response.
+    pub body: String,
+}
+
+/// Abstract operations to issue HTTP requests.
+#[async_trait(?Send)]
+pub trait Client {
+    /// Sends a GET request to `url`.
+    async fn get(&self, url: &str) -> io::Result<Response>;
+
+    /// Sends a POST request to `url` with the given `body` of type `content_type`.
+    async fn post(&self, url: &str, content_type: &str, body: String) -> io::Result<Response>;
+}
+
+/// Stand-in implementation of the HTTP client that always returns an error.
+#[derive(Default)]
+pub(crate) struct NoopClient {}
+
+#[async_trait(?Send)]
+impl Client for NoopClient {
+    async fn get(&self, _url: &str) -> io::Result<Response> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "HTTP client not compiled in"))
+    }
+
+    async fn post(&self, _url: &str, _content_type: &str, _body: String) -> io::Result<Response> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "HTTP client not compiled in"))
+    }
+}
+
+/// Clearable for the HTTP state.
+struct HttpClearable {
+    last_response: Rc<RefCell<Option<Response>>>,
+}
+
+impl Clearable for HttpClearable {
+    fn reset_state(&self, _syms: &mut Symbols) {
+        *self.last_response.borrow_mut() = None;
+    }
+}
+
+/// The `HTTP_GET` function.
+pub struct HttpGetFunction {
+    metadata: CallableMetadata,
+    client: Rc<dyn Client>,
+    last_response: Rc<RefCell<Option<Response>>>,
+}
+
+impl HttpGetFunction {
+    /// Creates a new instance of the function.
+    pub fn new(client: Rc<dyn Client>, last_response: Rc<RefCell<Option<Response>>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("HTTP_GET", VarType::Text)
+                .with_syntax("url$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sends a GET request to a web server and returns the body of the response.
+The response is returned even if the server reports an error, so check HTTP_STATUS% afterwards \
+to see if the request succeeded.",
+                )
+                .build(),
+            client,
+            last_response,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for HttpGetFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(url)] => {
+                let response = self.client.get(url).await?;
+                let body = response.body.clone();
+                *self.last_response.borrow_mut() = Some(response);
+                Ok(Value::Text(body))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `HTTP_HEADER` function.
+pub struct HttpHeaderFunction {
+    metadata: CallableMetadata,
+    last_response: Rc<RefCell<Option<Response>>>,
+}
+
+impl HttpHeaderFunction {
+    /// Creates a new instance of the function.
+    pub fn new(last_response: Rc<RefCell<Option<Response>>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("HTTP_HEADER", VarType::Text)
+                .with_syntax("name$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the value of a header in the most recent HTTP response.
+Header names are case-insensitive.  Returns an empty string if the header is not present or if \
+no request has been sent yet.",
+                )
+                .build(),
+            last_response,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for HttpHeaderFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(name)] => {
+                let last_response = self.last_response.borrow();
+                let value = last_response.as_ref().and_then(|response| {
+                    response
+                        .headers
+                        .iter()
+                        .find(|(key, _value)| key.eq_ignore_ascii_case(name))
+                        .map(|(_key, value)| value.clone())
+                });
+                Ok(Value::Text(value.unwrap_or_default()))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `HTTP_POST` function.
+pub struct HttpPostFunction {
+    metadata: CallableMetadata,
+    client: Rc<dyn Client>,
+    last_response: Rc<RefCell<Option<Response>>>,
+}
+
+impl HttpPostFunction {
+    /// Creates a new instance of the function.
+    pub fn new(client: Rc<dyn Client>, last_response: Rc<RefCell<Option<Response>>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("HTTP_POST", VarType::Text)
+                .with_syntax("url$, body$[, content_type$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sends a POST request to a web server and returns the body of the response.
+The body$ is sent as is to the server.  content_type$ indicates the format of body$ and defaults \
+to plain text; set it to \"application/json\" when sending a document built with JSON_FORMAT$.
+The response is returned even if the server reports an error, so check HTTP_STATUS% afterwards \
+to see if the request succeeded.",
+                )
+                .build(),
+            client,
+            last_response,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for HttpPostFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (url, body, content_type) = match args.as_slice() {
+            [Value::Text(url), Value::Text(body)] => (url, body, DEFAULT_CONTENT_TYPE),
+            [Value::Text(url), Value::Text(body), Value::Text(content_type)] => {
+                (url, body, content_type.as_str())
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let response = self.client.post(url, content_type, body.clone()).await?;
+        let body = response.body.clone();
+        *self.last_response.borrow_mut() = Some(response);
+        Ok(Value::Text(body))
+    }
+}
+
+/// The `HTTP_STATUS` function.
+pub struct HttpStatusFunction {
+    metadata: CallableMetadata,
+    last_response: Rc<RefCell<Option<Response>>>,
+}
+
+impl HttpStatusFunction {
+    /// Creates a new instance of the function.
+    pub fn new(last_response: Rc<RefCell<Option<Response>>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("HTTP_STATUS", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the status code of the most recent HTTP response.
+Codes in the 200 range indicate success.  Returns 0 if no request has been sent yet.",
+                )
+                .build(),
+            last_response,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for HttpStatusFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let last_response = self.last_response.borrow();
+        Ok(Value::Integer(last_response.as_ref().map(|r| i32::from(r.status)).unwrap_or(0)))
+    }
+}
+
+/// Adds all symbols provided by this module to the given `machine`.
+pub fn add_all(machine: &mut Machine, client: Rc<dyn Client>) {
+    let last_response = Rc::from(RefCell::from(None));
+    machine.add_clearable(Box::from(HttpClearable { last_response: last_response.clone() }));
+    machine.add_function(HttpGetFunction::new(client.clone(), last_response.clone()));
+    machine.add_function(HttpHeaderFunction::new(last_response.clone()));
+    machine.add_function(HttpPostFunction::new(client, last_response.clone()));
+    machine.add_function(HttpStatusFunction::new(last_response));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    /// HTTP client that records the requests it receives and replies with canned responses.
+    #[derive(Default)]
+    struct MockClient {
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl MockClient {
+        /// Records a request and returns a response that echoes it.
+        fn reply(&self, request: String) -> io::Result<Response> {
+            if request.contains("//bad") {
+                return Err(io::Error::new(io::ErrorKind::ConnectionRefused, "Connection refused"));
+            }
+            self.requests.borrow_mut().push(request.clone());
+            let status = if request.contains("missing") { 404 } else { 200 };
+            Ok(Response {
+                status,
+                headers: vec![("Content-Type".to_owned(), "text/plain".to_owned())],
+                body: format!("reply to {}", request),
+            })
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl Client for MockClient {
+        async fn get(&self, url: &str) -> io::Result<Response> {
+            self.reply(format!("GET {}", url))
+        }
+
+        async fn post(&self, url: &str, content_type: &str, body: String) -> io::Result<Response> {
+            self.reply(format!("POST {} {} {}", url, content_type, body))
+        }
+    }
+
+    /// Creates a tester with the HTTP functions backed by a `MockClient`.
+    fn tester() -> (Tester, Rc<MockClient>) {
+        let client = Rc::from(MockClient::default());
+        let mut tester = Tester::empty();
+        crate::exec::add_all(tester.get_machine(), None, None);
+        add_all(tester.get_machine(), client.clone());
+        (tester, client)
+    }
+
+    #[test]
+    fn test_http_get() {
+        let (mut t, client) = tester();
+        t.run(r#"b$ = HTTP_GET("http://example.com/"): s = HTTP_STATUS"#)
+            .expect_var("b", "reply to GET http://example.com/")
+            .expect_var("s", 200)
+            .check();
+        assert_eq!(&["GET http://example.com/"], client.requests.borrow().as_slice());
+
+        let (mut t, _client) = tester();
+        t.run(r#"b$ = HTTP_GET("http://example.com/missing"): s = HTTP_STATUS"#)
+            .expect_var("b", "reply to GET http://example.com/missing")
+            .expect_var("s", 404)
+            .check();
+
+        let (mut t, _client) = tester();
+        t.run(r#"b$ = HTTP_GET("http://bad/")"#)
+            .expect_err("1:6: In call to HTTP_GET: Connection refused")
+            .check();
+
+        let (mut t, _client) = tester();
+        t.run("b$ = HTTP_GET()").expect_err("1:6: In call to HTTP_GET: expected url$").check();
+
+        let (mut t, _client) = tester();
+        t.run("b$ = HTTP_GET(3)").expect_err("1:6: In call to HTTP_GET: expected url$").check();
+    }
+
+    #[test]
+    fn test_http_post() {
+        let (mut t, client) = tester();
+        t.run(r#"b$ = HTTP_POST("http://example.com/", "the body")"#)
+            .expect_var("b", "reply to POST http://example.com/ text/plain; charset=utf-8 the body")
+            .check();
+        assert_eq!(
+            &["POST http://example.com/ text/plain; charset=utf-8 the body"],
+            client.requests.borrow().as_slice()
+        );
+
+        let (mut t, _client) = tester();
+        t.run(r#"b$ = HTTP_POST$("http://example.com/", "{}", "application/json")"#)
+            .expect_var("b", "reply to POST http://example.com/ application/json {}")
+            .check();
+
+        let (mut t, _client) = tester();
+        t.run(r#"b$ = HTTP_POST("http://example.com/")"#)
+            .expect_err("1:6: In call to HTTP_POST: expected url$, body$[, content_type$]")
+            .check();
+
+        let (mut t, _client) = tester();
+        t.run(r#"b$ = HTTP_POST("http://example.com/", 3)"#)
+            .expect_err("1:6: In call to HTTP_POST: expected url$, body$[, content_type$]")
+            .check();
+    }
+
+    #[test]
+    fn test_http_status_and_header_before_request() {
+        let (mut t, _client) = tester();
+        t.run(r#"s = HTTP_STATUS: h$ = HTTP_HEADER("content-type")"#)
+            .expect_var("s", 0)
+            .expect_var("h", "")
+            .check();
+
+        let (mut t, _client) = tester();
+        t.run("s = HTTP_STATUS()")
+            .expect_err("1:5: In call to HTTP_STATUS: expected no arguments nor parenthesis")
+            .check();
+    }
+
+    #[test]
+    fn test_http_header() {
+        let (mut t, _client) = tester();
+        t.run(
+            r#"b$ = HTTP_GET("http://example.com/")
+            h1$ = HTTP_HEADER("content-type"): h2$ = HTTP_HEADER$("X-Missing")"#,
+        )
+        .expect_var("b", "reply to GET http://example.com/")
+        .expect_var("h1", "text/plain")
+        .expect_var("h2", "")
+        .check();
+
+        let (mut t, _client) = tester();
+        t.run("h$ = HTTP_HEADER(3)")
+            .expect_err("1:6: In call to HTTP_HEADER: expected name$")
+            .check();
+    }
+
+    #[test]
+    fn test_http_clear() {
+        let (mut t, _client) = tester();
+        t.run(r#"b$ = HTTP_GET("http://example.com/"): CLEAR: s = HTTP_STATUS"#)
+            .expect_var("s", 0)
+            .check();
+    }
+
+    #[test]
+    fn test_noop_client() {
+        Tester::default()
+            .run(r#"b$ = HTTP_GET("http://example.com/")"#)
+            .expect_err("1:6: In call to HTTP_GET: HTTP client not compiled in")
+            .check();
+    }
+}
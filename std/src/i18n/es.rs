@@ -0,0 +1,491 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Spanish message catalog.
+
+/// Translations of messages as pairs of English and Spanish templates.
+///
+/// Templates are matched in order, so more specific templates must come before more generic ones.
+/// Use `{message}` instead of `{}` for the parts that hold nested messages to translate.
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    // Errors raised by the language.
+    ("Division by zero", "División por cero"),
+    ("Modulo by zero", "Módulo por cero"),
+    ("Not enough values to apply operator", "Faltan valores para aplicar el operador"),
+    ("Unexpected value in expression", "Valor inesperado en la expresión"),
+    ("Unexpected keyword in expression", "Palabra clave inesperada en la expresión"),
+    ("Unexpected {} in expression", "{} inesperado en la expresión"),
+    ("Unexpected {} in statement", "{} inesperado en la sentencia"),
+    ("Unexpected {} in assignment", "{} inesperado en la asignación"),
+    ("Missing expression in assignment", "Falta la expresión en la asignación"),
+    (
+        "Expected comma, semicolon, or end of statement",
+        "Se esperaba una coma, un punto y coma o el final de la sentencia",
+    ),
+    ("Incomplete string due to EOF: {}", "Cadena incompleta al final del archivo: {}"),
+    ("Unknown character: {}", "Carácter desconocido: {}"),
+    ("IF/ELSEIF require a boolean condition", "IF/ELSEIF necesitan una condición booleana"),
+    ("{} requires a boolean condition", "{} necesita una condición booleana"),
+    ("FOR supports numeric iteration only", "FOR solo admite iteraciones numéricas"),
+    ("Undefined variable {}", "Variable no definida: {}"),
+    ("Unknown builtin {}", "Comando desconocido: {}"),
+    ("Unknown function or array {}", "Función o arreglo desconocido: {}"),
+    ("Unknown array {}", "Arreglo desconocido: {}"),
+    ("Unknown label {}", "Etiqueta desconocida: {}"),
+    ("Incompatible types in {} reference", "Tipos incompatibles en la referencia a {}"),
+    ("Cannot DIM already-defined symbol {}", "No se puede usar DIM con el símbolo ya definido {}"),
+    ("Subscript {} exceeds limit of {}", "El índice {} excede el límite de {}"),
+    ("Subscript {} cannot be negative", "El índice {} no puede ser negativo"),
+    (
+        "Cannot index array with {} subscripts; need {}",
+        "No se puede indexar el arreglo con {} índices; se necesitan {}",
+    ),
+    (
+        "Cannot assign value of type {} to variable of type {}",
+        "No se puede asignar un valor de tipo {} a una variable de tipo {}",
+    ),
+    (
+        "Cannot assign value of type {} to array of type {}",
+        "No se puede asignar un valor de tipo {} a un arreglo de tipo {}",
+    ),
+    (
+        "Cannot cast {} to integer due to overflow",
+        "No se puede convertir {} a entero: desbordamiento",
+    ),
+    ("Cannot add {} and {}", "No se puede sumar {} y {}"),
+    ("Cannot subtract {} from {}", "No se puede restar {} de {}"),
+    ("Cannot multiply {} by {}", "No se puede multiplicar {} por {}"),
+    ("Cannot divide {} by {}", "No se puede dividir {} entre {}"),
+    ("Cannot modulo {} by {}", "No se puede calcular el módulo de {} entre {}"),
+    ("Cannot raise {} to the power of {}", "No se puede elevar {} a la potencia {}"),
+    ("Cannot negate {}", "No se puede negar {}"),
+    ("Cannot compare {} and {} with {}", "No se puede comparar {} y {} con {}"),
+    ("Cannot AND {} and {}", "No se puede aplicar AND a {} y {}"),
+    ("Cannot OR {} and {}", "No se puede aplicar OR a {} y {}"),
+    ("Cannot XOR {} and {}", "No se puede aplicar XOR a {} y {}"),
+    ("Cannot apply NOT to {}", "No se puede aplicar NOT a {}"),
+    ("Invalid integer literal {}", "Número entero no válido: {}"),
+    ("Invalid double-precision floating point literal {}", "Número decimal no válido: {}"),
+    ("Invalid boolean literal {}", "Valor booleano no válido: {}"),
+    ("{} is not a number", "{} no es un número"),
+    ("{} is not a variable", "{} no es una variable"),
+    ("{} is not an array", "{} no es un arreglo"),
+    ("{} is not a command", "{} no es un comando"),
+    (
+        "In call to {}: expected no arguments nor parenthesis",
+        "En la llamada a {}: no se esperaban argumentos ni paréntesis",
+    ),
+    ("In call to {}: expected no arguments", "En la llamada a {}: no se esperaban argumentos"),
+    ("In call to {}: expected {}", "En la llamada a {}: se esperaba {}"),
+    ("In call to {}: {message}", "En la llamada a {}: {message}"),
+    // Errors raised by the standard library.
+    ("Sleep time must be positive", "El tiempo de espera debe ser positivo"),
+    ("No graphics support in this console", "Esta consola no admite gráficos"),
+    ("INPUT prompt must be a string", "El mensaje de INPUT debe ser una cadena"),
+    ("INPUT requires a variable reference", "INPUT necesita una variable"),
+    ("INPUT timeout must be positive", "El tiempo de espera de INPUT debe ser positivo"),
+    ("Expected {} comma-separated values", "Se esperaban {} valores separados por comas"),
+    ("Value must be between {} and {}", "El valor debe estar entre {} y {}"),
+    ("Retry input: {message}", "Vuelve a intentarlo: {message}"),
+    ("Unknown help topic {}", "Tema de ayuda desconocido: {}"),
+    (
+        "Ambiguous help topic {}; candidates are: {}",
+        "Tema de ayuda ambiguo {}; los candidatos son: {}",
+    ),
+    // Help and interpreter texts.
+    ("This is EndBASIC {}.", "Esto es EndBASIC {}."),
+    ("Project page at <{}>", "Página del proyecto en <{}>"),
+    ("License Apache Version 2.0 <{}>", "Licencia Apache versión 2.0 <{}>"),
+    ("Top-level help topics", "Temas de ayuda principales"),
+    ("Language reference", "Referencia del lenguaje"),
+    ("General language topics", "Temas generales del lenguaje"),
+    (
+        "Type HELP followed by the name of a topic for details.",
+        "Escribe HELP seguido del nombre de un tema para ver los detalles.",
+    ),
+    (
+        "Type HELP \"HELP\" for details on how to specify topic names.",
+        "Escribe HELP \"HELP\" para saber cómo indicar los nombres de los temas.",
+    ),
+    (
+        "Type LOAD \"DEMOS:/TOUR.BAS\": RUN for a guided tour.",
+        "Escribe LOAD \"DEMOS:/TOUR.BAS\": RUN para hacer una visita guiada.",
+    ),
+    ("Type END or press CTRL+D to exit.", "Escribe END o pulsa CTRL+D para salir."),
+    ("See also:", "Ver también:"),
+    ("Topics that mention \"{}\"", "Temas que mencionan \"{}\""),
+    ("Ready", "Listo"),
+    ("ERROR: {message}", "ERROR: {message}"),
+    ("**** ERROR: {message} ****", "**** ERROR: {message} ****"),
+    ("**** BREAK ****", "**** INTERRUMPIDO ****"),
+    ("End of input by CTRL-D", "Fin de la entrada por CTRL-D"),
+];
+
+/// Translations of help category descriptions keyed by the English category title.
+pub(super) const CATEGORIES: &[(&str, &str)] = &[
+    ("Array functions", "Funciones de arreglos"),
+    (
+        "Collections",
+        "Colecciones
+Las colecciones guardan un número arbitrario de valores y crecen y se encogen según haga falta, a \
+diferencia de los arreglos, cuyo tamaño debe conocerse de antemano.
+Las colecciones no se guardan directamente en variables.  En su lugar, las funciones que las crean \
+devuelven un identificador entero que representa la colección, y este identificador debe pasarse \
+a todas las demás funciones y comandos que operan sobre ella.  CLEAR destruye todas las \
+colecciones, lo que también invalida sus identificadores.
+Los diccionarios asocian claves de texto a valores de cualquier tipo.  Crea un diccionario con \
+DICT, añade o modifica sus entradas con DICT_SET y consúltalas con DICT_GET.  Las claves \
+distinguen mayúsculas y minúsculas y se mantienen ordenadas, de modo que DICT_KEY permite \
+recorrerlas en un orden predecible.
+Las listas guardan una secuencia de valores de cualquier tipo.  Crea una lista con LIST_NEW, añade \
+valores con LIST_APPEND o LIST_INSERT y consúltalos con LIST_GET.  Los índices de las listas \
+empiezan en 0, así que recorrer de 0 a LIST_LEN%(handle%) - 1 visita todos los elementos en orden.
+Las colecciones se pueden convertir desde y hacia documentos JSON con JSON_PARSE y JSON_FORMAT$.",
+    ),
+    (
+        "Console",
+        "Consola
+La consola de EndBASIC es la pantalla que estás viendo: tanto el intérprete como los efectos de \
+todos los comandos ocurren en la misma consola.  No hay una ventana de salida separada como en \
+otros intérpretes didácticos.  Esta consola unificada admite texto y, según el tipo de pantalla, \
+gráficos.  Esta sección de ayuda trata la consola de texto; para saber más sobre los gráficos, \
+ejecuta HELP \"Gráficos\".
+La consola de texto es una matriz de tamaño variable.  La posición superior izquierda es la fila 0 \
+y la columna 0.  Cada posición de esta matriz contiene un carácter y un atributo de color, que \
+indica los colores de primer plano y de fondo de ese carácter.  Hay un atributo por defecto que \
+coincide con la configuración de tu terminal, que puede no ser un color: por ejemplo, en un \
+terminal transparente, el color por defecto respeta la transparencia mientras que el color 0 \
+(negro) no lo hace.
+Si escribes un programa y no quieres que interfiera con otras partes de la consola, limítate a \
+usar los comandos INPUT y PRINT.
+Ten en cuenta que la consola reacciona mal a los cambios de tamaño.  Evita cambiar el tamaño del \
+terminal o del navegador y, si lo haces, reinicia el intérprete.",
+    ),
+    ("Data management", "Gestión de datos"),
+    (
+        "Date and time functions",
+        "Funciones de fecha y hora
+Las fechas se representan como el número de segundos transcurridos desde 1970-01-01 00:00:00 UTC, \
+guardado como un número decimal para que pueda incluir fracciones de segundo.  NOW devuelve la \
+fecha actual en este formato.
+Las funciones que trabajan con unidades del calendario, como DATEADD y DATEDIFF, y las que \
+convierten fechas en texto interpretan estos valores en la zona horaria local.
+Las unidades del calendario se indican como textos y pueden ser YEAR, MONTH, WEEK, DAY, HOUR, \
+MINUTE o SECOND, sin distinguir mayúsculas y minúsculas.",
+    ),
+    (
+        "Debugging",
+        "Depuración
+El depurador te permite pausar la ejecución del programa guardado para examinar su estado.
+Usa BREAK para poner puntos de parada en líneas concretas del programa y luego inícialo con RUN.  \
+La ejecución se detendrá justo antes de ejecutar la primera sentencia de cualquiera de esas \
+líneas.  Mientras el programa está en pausa, puedes examinar sus variables con VARS o con \
+cualquier otro comando, ejecutar la siguiente línea con STEP o continuar hasta el siguiente punto \
+de parada con CONT.
+También puedes usar TRON para mostrar cada línea del programa mientras se ejecuta, lo que sirve \
+para seguir el flujo del programa sin detenerlo.
+Para saber en qué pasa el tiempo un programa, activa el perfilador con PROFILE ON, ejecuta el \
+programa con RUN y luego usa PROFILE SHOW para ver cuántas veces entró la ejecución en cada línea \
+y cuánto tardó.
+Los puntos de parada, el seguimiento y el perfilado solo se aplican a programas iniciados con RUN, \
+no a los comandos escritos en el intérprete.",
+    ),
+    (
+        "File system",
+        "Sistema de archivos
+El almacenamiento de EndBASIC se organiza como una colección de unidades, cada una identificada \
+por un nombre que no distingue mayúsculas y minúsculas.  Las unidades pueden estar respaldadas por \
+distintos sistemas de archivos y sus destinos se indican como URIs.  Algunos destinos especiales \
+son: memory://, una unidad de lectura y escritura en memoria; y demos://, una unidad de solo \
+lectura con programas de ejemplo.  Según el contexto, puede haber otros destinos como file:// para \
+acceder a un directorio local, git:// para acceder a un directorio local que registra cada cambio \
+en un repositorio git, zip:// para acceder al contenido de un archivo zip, http:// y https:// para \
+leer archivos de un servidor web, o local:// para acceder al almacenamiento local del navegador.  \
+La salida del comando MOUNT ayuda a identificar qué destinos están disponibles.
+Todos los comandos que trabajan con archivos reciben una ruta.  Las rutas pueden tener la forma \
+ARCHIVO.EXT, en cuyo caso se refieren a un archivo de la unidad actual; o UNIDAD:/ARCHIVO.EXT y \
+UNIDAD:ARCHIVO.EXT, en cuyo caso se refieren a un archivo de la unidad indicada.  La barra antes \
+del nombre del archivo es opcional porque EndBASIC todavía no admite directorios.  Además, si \
+falta .EXT, se supone la extensión .BAS.
+Comandos como DIR, COPY y KILL también aceptan los comodines * y ? en el nombre del archivo para \
+trabajar con todos los archivos que coincidan: * coincide con cualquier secuencia de caracteres y \
+? con un único carácter, sin distinguir mayúsculas y minúsculas.
+Recuerda que estos comandos deben usarse con la sintaxis de EndBASIC.  En particular, las rutas \
+deben ir entre comillas dobles y los argumentos deben separarse con una coma (no con un espacio).  \
+Si has usado comandos como CD, DIR o MOUNT en otros contextos, esto puede confundirte.
+Consulta el tema de ayuda \"Programa guardado\" para saber cómo cargar, modificar y guardar \
+programas.",
+    ),
+    (
+        "Graphics",
+        "Gráficos
+La consola de EndBASIC superpone texto y gráficos en el mismo lienzo.  Por eso la consola tiene \
+dos sistemas de coordenadas: el basado en caracteres, que usan los comandos descritos en HELP \
+\"Consola\", y el basado en píxeles, que usan los comandos descritos en esta sección.",
+    ),
+    (
+        "Hardware interface",
+        "Interfaz de hardware
+EndBASIC ofrece funciones para manejar hardware externo.  Por ahora se limitan a GPIO, I2C y SPI \
+en una Raspberry Pi y solo están disponibles si EndBASIC se ha compilado con la opción \
+--features=rpi.  Puede que más adelante se admitan otras plataformas.",
+    ),
+    ("Interpreter", "Intérprete"),
+    (
+        "Networking",
+        "Red
+EndBASIC puede comunicarse con servidores web por HTTP, lo que permite a los programas consultar \
+APIs web públicas.
+HTTP_GET$ y HTTP_POST$ envían una petición a una URL y esperan a que el servidor responda, \
+devolviendo el cuerpo de la respuesta.  El código de estado y las cabeceras de la respuesta más \
+reciente se pueden consultar después con HTTP_STATUS% y HTTP_HEADER$.
+Las respuestas que contienen documentos JSON se pueden convertir en colecciones con JSON_PARSE.",
+    ),
+    ("Numerical functions", "Funciones numéricas"),
+    (
+        "Process environment",
+        "Entorno del proceso
+Estas funciones y comandos dan a los programas acceso al entorno en el que se ejecuta el \
+intérprete, lo que les permite obtener su configuración sin tenerla escrita en el código.
+Cuando EndBASIC se ejecuta desde la línea de comandos, las variables de entorno son las del \
+proceso del intérprete.  En plataformas sin variables de entorno, como la interfaz web, los \
+programas empiezan con un conjunto vacío de variables que solo dura mientras dure el intérprete.
+Cuando un programa se ejecuta desde la línea de comandos como en \"endbasic programa.bas arg1 \
+arg2\", los argumentos que siguen al nombre del programa se pueden consultar con COMMANDC% y \
+COMMAND$.  Los argumentos que parezcan opciones deben ir precedidos de -- para que el intérprete \
+no intente procesarlos.",
+    ),
+    (
+        "Regular expressions",
+        "Expresiones regulares
+Las expresiones regulares describen patrones de texto y permiten buscar y reemplazar el texto que \
+coincide con esos patrones sin tener que examinar las cadenas carácter a carácter.
+Los patrones siguen la sintaxis del crate regex de Rust, que se parece a la de Perl pero no admite \
+aserciones de anticipación ni referencias hacia atrás.  Por ejemplo, el patrón \"[0-9]+\" coincide \
+con cualquier secuencia de uno o más dígitos y el patrón \"^a.*z$\" coincide con cualquier cadena \
+que empiece por a y termine en z.
+Las coincidencias distinguen mayúsculas y minúsculas por defecto.  Añade (?i) al principio del \
+patrón para que no las distingan.",
+    ),
+    (
+        "Serial communication",
+        "Comunicación serie
+Los puertos serie permiten a los programas intercambiar datos con microcontroladores como Arduino \
+y con otros dispositivos conectados mediante una UART o un adaptador serie USB.
+Abre el puerto con SERIAL_OPEN y luego usa SERIAL_WRITE y SERIAL_READ$ para intercambiar datos.  \
+Solo puede haber un puerto abierto a la vez y CLEAR lo cierra.
+Por ahora, los puertos serie solo están disponibles en sistemas Unix.",
+    ),
+    (
+        "Sockets",
+        "Sockets
+Los sockets permiten a los programas intercambiar datos con otros programas mediante conexiones \
+TCP/IP.
+Para hablar con un servidor, abre una conexión con SOCKET_CONNECT, que devuelve un identificador \
+entero para la conexión.  Usa SOCKET_SEND y SOCKET_RECV$ para intercambiar datos y ciérrala con \
+SOCKET_CLOSE al terminar.
+Para actuar como servidor, empieza a escuchar conexiones con SOCKET_LISTEN y espera a que los \
+clientes se conecten con SOCKET_ACCEPT, que devuelve un identificador para la nueva conexión.
+Todas las operaciones esperan a completarse, pero las esperas se pueden interrumpir con CTRL+C.  \
+CLEAR cierra todos los sockets.",
+    ),
+    (
+        "Stored program",
+        "Programa guardado
+El intérprete de EndBASIC tiene una memoria de lectura y escritura llamada \"programa guardado\", \
+que sirve para mantener el código del programa que editas y manipulas desde el intérprete.
+Lo habitual es cargar un programa del disco con el comando LOAD, modificarlo con el comando EDIT, \
+ejecutarlo con el comando RUN y, por último, guardar el programa nuevo o modificado con el comando \
+SAVE.
+Ten en cuenta que el contenido del programa guardado se pierde cada vez que cargas un programa, \
+sales del intérprete o usas el comando NEW.  Estas operaciones te pedirán que guardes el programa \
+si se te ha olvidado, pero es mejor acostumbrarse a guardar a menudo.
+Consulta el tema de ayuda \"Sistema de archivos\" para saber dónde se pueden guardar y desde dónde \
+se pueden cargar los programas.",
+    ),
+    ("String and character functions", "Funciones de cadenas y caracteres"),
+];
+
+/// Translations of callable descriptions keyed by the callable name.
+pub(super) const DESCRIPTIONS: &[(&str, &str)] = &[
+    (
+        "CLEAR",
+        "Restaura el estado inicial de la máquina pero conserva el programa guardado.
+Este comando devuelve la máquina a un estado casi limpio borrando todas las variables definidas \
+por el usuario y restaurando el estado de los recursos compartidos.  Estos recursos incluyen: la \
+consola, cuyo color y sincronización de vídeo se restablecen; y los pines GPIO, que vuelven a su \
+estado por defecto.
+El programa guardado se conserva en memoria.  Para borrarlo también, usa NEW (¡pero no olvides \
+guardar antes tu programa con SAVE!).",
+    ),
+    ("CLS", "Borra la pantalla."),
+    (
+        "COLOR",
+        "Cambia los colores de primer plano y de fondo.
+Los números de color son números ANSI entre 0 y 255.  Si no se indica un número de color, se \
+restablece el color por defecto de la consola, que no tiene por qué coincidir con ningún color \
+del rango de 0 a 255, ya que puede ser transparente.
+Los números de color también pueden representar colores arbitrarios como los que devuelve \
+COLOR_RGB.  Las consolas que no pueden mostrar estos colores usan los colores ANSI más parecidos.",
+    ),
+    (
+        "DIR",
+        "Muestra la lista de archivos de la ruta actual o de la indicada.
+El nombre de archivo de path$ puede contener los comodines * y ? para mostrar solo los archivos \
+que coincidan, como en DIR \"*.BAS\".",
+    ),
+    (
+        "EDIT",
+        "Edita el programa guardado de forma interactiva.
+Además de las flechas, el editor reconoce estas teclas: Ctrl+F busca texto, Ctrl+G salta a un \
+número de línea, Ctrl+K empieza o cancela una selección, Ctrl+X corta la selección (o la línea \
+actual si no hay selección), Ctrl+Y copia la selección (o la línea actual), Ctrl+V pega el último \
+texto cortado o copiado y ESC sale del editor.
+El texto cortado y copiado se comparte con el portapapeles del sistema cuando la consola lo \
+permite, así que Ctrl+V también pega texto copiado en otras aplicaciones.  Consulta \
+CLIPBOARD_GET$ para más detalles.",
+    ),
+    (
+        "HELP",
+        "Muestra la ayuda interactiva.
+Sin argumentos, muestra un resumen de todos los temas de ayuda principales.
+Con un único argumento, que debe ser una cadena, muestra información detallada sobre el tema de \
+ayuda, comando o función indicado.
+Los nombres de los temas no distinguen mayúsculas y minúsculas y se pueden abreviar, en cuyo caso \
+se muestra el tema cuyo nombre empieza por la abreviatura.  Por ejemplo, estas llamadas son todas \
+equivalentes: HELP \"CON\", HELP \"consola\", HELP \"Consola\".
+Si el argumento no es el nombre de ningún tema, HELP lo busca en el texto de todos los comandos, \
+funciones y temas del lenguaje, y muestra los que lo mencionan.  Por ejemplo, HELP \"cursor\" \
+muestra todos los símbolos cuya descripción habla del cursor.
+La descripción de los comandos y funciones termina con una lista de otros símbolos relacionados \
+cuando su texto se refiere a ellos.",
+    ),
+    (
+        "INT",
+        "Convierte la expresión numérica dada en un entero (truncándola).
+Al convertir un número decimal en un entero, el número se trunca primero al mayor entero que no \
+sea mayor que él.  Por ejemplo, 4.4, 4.5 y 4.6 se convierten en 4.",
+    ),
+    (
+        "LEFT",
+        "Devuelve un número dado de caracteres del lado izquierdo de una cadena.
+Si n% es 0, devuelve una cadena vacía.
+Si n% es mayor o igual que el número de caracteres de expr$, devuelve expr$.",
+    ),
+    ("LEN", "Devuelve la longitud de la cadena expr$."),
+    (
+        "LIST",
+        "Muestra el programa cargado.
+Sin argumentos, muestra el programa entero.  Con un único argumento line%, muestra solo esa \
+línea.  Con un rango first%-last%, muestra las líneas entre first% y last%, ambas incluidas, y \
+con -last%, muestra las líneas desde el principio del programa hasta last%.  Las líneas se numeran \
+empezando por 1.
+El código se muestra con resaltado de sintaxis.  Si el programa no cabe en la pantalla, la salida \
+se detiene tras cada pantalla: pulsa cualquier tecla para continuar o pulsa ESC o Q para parar.",
+    ),
+    (
+        "LOAD",
+        "Carga el programa indicado.
+El nombre del archivo debe ser una cadena y una ruta válida de EndBASIC.  La extensión .BAS es \
+opcional pero, si se indica, debe ser .BAS.
+Cualquier programa guardado previamente se descarta de la memoria, pero LOAD preguntará antes de \
+descartar cambios sin guardar.
+Consulta el tema de ayuda \"Sistema de archivos\" para saber más sobre la sintaxis de las rutas.",
+    ),
+    (
+        "LOCATE",
+        "Mueve el cursor a la posición indicada.
+Si se omite column% o row%, se conserva el valor actual de esa coordenada.  Consulta CSRLIN y POS \
+para saber la posición actual del cursor.",
+    ),
+    (
+        "MID",
+        "Devuelve una parte de una cadena.
+start% indica la posición inicial de la subcadena a extraer y empieza en 1.
+length% indica el número de caracteres a extraer y, si no se indica, se extrae hasta el final de \
+la cadena.",
+    ),
+    (
+        "NEW",
+        "Restaura el estado inicial de la máquina y crea un programa nuevo.
+Este comando devuelve la máquina a un estado limpio borrando todas las variables definidas por el \
+usuario y restaurando el estado de los recursos compartidos.  Estos recursos incluyen: la consola, \
+cuyo color y sincronización de vídeo se restablecen; y los pines GPIO, que vuelven a su estado por \
+defecto.
+El programa guardado también se descarta de la memoria, pero NEW preguntará antes de descartar \
+cambios sin guardar.  Para restablecer los recursos sin borrar el programa guardado, usa CLEAR.",
+    ),
+    (
+        "PRINT",
+        "Muestra uno o más valores en la consola.
+Las expresiones dadas como argumentos se evalúan y se convierten en cadenas antes de mostrarse.  \
+Consulta la documentación de STR$() para conocer las reglas de conversión.
+Usar el separador `;` entre argumentos hace que los dos valores se muestren juntos.  En las \
+cadenas, esto significa que no se añade ningún espacio entre ellas; en los demás tipos, se añade \
+un espacio después del valor de la izquierda.
+Usar el separador `,` entre argumentos funciona igual que `;` salvo que los campos se alinean a \
+la izquierda en columnas de 14 caracteres de ancho.
+Si la última expresión está vacía (es decir, si la sentencia termina en punto y coma o en coma), \
+el cursor se queda en la misma línea justo después de lo que se ha mostrado.",
+    ),
+    (
+        "RIGHT",
+        "Devuelve un número dado de caracteres del lado derecho de una cadena.
+Si n% es 0, devuelve una cadena vacía.
+Si n% es mayor o igual que el número de caracteres de expr$, devuelve expr$.",
+    ),
+    (
+        "RND",
+        "Devuelve un número aleatorio en el rango [0..1].
+Si n% es cero, devuelve el último número aleatorio generado.  Si n% es positivo, devuelve un \
+número aleatorio nuevo.
+Si necesitas un número entero aleatorio dentro de un rango concreto, por ejemplo [0..100], usa \
+RNDINT%(0, 100) en su lugar.
+AVISO: Estos números aleatorios no ofrecen garantías criptográficas.",
+    ),
+    (
+        "RUN",
+        "Ejecuta el programa guardado.
+Antes de empezar, hace un CLEAR para que el estado de ejecuciones anteriores no interfiera con la \
+nueva.
+Las directivas '#INCLUDE del programa se procesan en este momento.  Consulta el tema de ayuda \
+\"INCLUDE\" para más detalles.",
+    ),
+    (
+        "SAVE",
+        "Guarda el programa que está en memoria con el nombre de archivo indicado.
+El nombre del archivo debe ser una cadena y una ruta válida de EndBASIC.  La extensión .BAS es \
+opcional pero, si se indica, debe ser .BAS.
+Si no se indica ningún nombre, SAVE intentará usar el nombre del programa cargado (si lo hay) y \
+fallará si todavía no se le ha dado un nombre.
+Consulta el tema de ayuda \"Sistema de archivos\" para saber más sobre la sintaxis de las rutas.",
+    ),
+    (
+        "SLEEP",
+        "Suspende la ejecución del programa.
+Pausa la ejecución del programa durante el número de segundos indicado, que puede ser un entero o \
+un número decimal para mayor precisión.",
+    ),
+    (
+        "STR",
+        "Convierte un valor escalar en una cadena.
+Si expr es una cadena, la devuelve sin modificar.
+Si expr es un booleano, devuelve las cadenas FALSE o TRUE.
+Si expr es un número, devuelve una cadena con la representación textual del número.  Si el \
+número NO tiene signo negativo, la cadena resultante lleva un espacio delante.
+Para obtener una representación de expr como cadena sin espacios artificiales, usa \
+LTRIM$(STR$(expr)).",
+    ),
+];
@@ -0,0 +1,491 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Translation of the messages presented by the interpreter.
+//!
+//! Messages are written in English throughout the code and are translated right before they are
+//! shown to the user by looking them up in per-language catalogs.  Messages that are not in the
+//! catalog of the active language are shown in English.  Because the catalogs are keyed by the
+//! English messages, the tests check that every catalog entry still matches a string that appears
+//! in the sources so that rewording a message does not silently drop its translations.
+
+use std::cell::Cell;
+use std::env;
+
+mod es;
+
+/// Names of the environment variables that select the language, in order of precedence.
+const LOCALE_ENV_VARS: &[&str] = &["ENDBASIC_LANG", "LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// Placeholder for the variable parts of a message template, which are inserted verbatim.
+const PLACEHOLDER: &str = "{}";
+
+/// Placeholder for a nested message within a message template, which is translated on its own.
+const MESSAGE_PLACEHOLDER: &str = "{message}";
+
+/// Languages in which the interpreter can present its messages.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Locale {
+    /// English, which is the language in which all messages are written.
+    #[default]
+    English,
+
+    /// Spanish.
+    Spanish,
+}
+
+impl Locale {
+    /// Parses a locale `name` such as `es` or `es_ES.UTF-8`, of which only the language matters.
+    ///
+    /// Returns none if the language is not supported.
+    pub fn parse(name: &str) -> Option<Self> {
+        let language = name.split(['_', '-', '.', '@']).next().unwrap_or("");
+        match language.to_ascii_lowercase().as_str() {
+            "c" | "en" | "posix" => Some(Locale::English),
+            "es" => Some(Locale::Spanish),
+            _ => None,
+        }
+    }
+
+    /// Determines the locale from the first set environment variable out of `ENDBASIC_LANG`,
+    /// `LC_ALL`, `LC_MESSAGES`, and `LANG`.  Unsupported languages select English.
+    pub fn from_env() -> Self {
+        for name in LOCALE_ENV_VARS {
+            match env::var(name) {
+                Ok(value) if !value.is_empty() => {
+                    return Locale::parse(&value).unwrap_or_default();
+                }
+                _ => (),
+            }
+        }
+        Locale::default()
+    }
+
+    /// Returns the catalog of message templates for this locale, if any.
+    fn messages(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::English => &[],
+            Locale::Spanish => es::MESSAGES,
+        }
+    }
+
+    /// Returns the catalog of help category descriptions for this locale, if any.
+    fn categories(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::English => &[],
+            Locale::Spanish => es::CATEGORIES,
+        }
+    }
+
+    /// Returns the catalog of callable descriptions for this locale, if any.
+    fn descriptions(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::English => &[],
+            Locale::Spanish => es::DESCRIPTIONS,
+        }
+    }
+}
+
+thread_local! {
+    /// The language in which to present messages.
+    static LOCALE: Cell<Locale> = Cell::new(Locale::default());
+}
+
+/// Returns the language in which messages are presented.
+pub fn locale() -> Locale {
+    LOCALE.with(|l| l.get())
+}
+
+/// Sets the language in which to present messages.
+pub fn set_locale(locale: Locale) {
+    LOCALE.with(|l| l.set(locale));
+}
+
+/// Matches `message` against `template` and returns the text that replaces each placeholder of
+/// the template, or none if the message does not match.
+fn match_template<'a>(template: &str, message: &'a str) -> Option<Vec<&'a str>> {
+    let template = template.replace(MESSAGE_PLACEHOLDER, PLACEHOLDER);
+    let mut literals = template.split(PLACEHOLDER);
+    let first = literals.next().expect("split always returns one element");
+    let mut rest = message.strip_prefix(first)?;
+
+    let literals: Vec<&str> = literals.collect();
+    let mut args = Vec::with_capacity(literals.len());
+    for (i, literal) in literals.iter().enumerate() {
+        if i == literals.len() - 1 {
+            args.push(rest.strip_suffix(literal)?);
+            rest = "";
+        } else {
+            let end = rest.find(literal)?;
+            args.push(&rest[..end]);
+            rest = &rest[end + literal.len()..];
+        }
+    }
+    if rest.is_empty() {
+        Some(args)
+    } else {
+        None
+    }
+}
+
+/// Finds the first placeholder in `template` and returns its position and the placeholder itself.
+fn find_placeholder(template: &str) -> Option<(usize, &'static str)> {
+    [PLACEHOLDER, MESSAGE_PLACEHOLDER]
+        .iter()
+        .filter_map(|placeholder| template.find(placeholder).map(|i| (i, *placeholder)))
+        .min()
+}
+
+/// Splits the `L:C: ` source position prefix, if any, from the beginning of an error `message`.
+fn split_position(message: &str) -> (&str, &str) {
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|ch| ch.is_ascii_digit());
+    let mut parts = message.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(line), Some(col), Some(rest))
+            if is_number(line) && is_number(col) && rest.starts_with(' ') =>
+        {
+            let prefix_len = line.len() + col.len() + 3;
+            (&message[..prefix_len], &message[prefix_len..])
+        }
+        _ => ("", message),
+    }
+}
+
+/// Translates `message` into the language given by `locale()`.
+///
+/// The message can be an error as formatted by the interpreter, including its source position and
+/// any nested errors, or any other text shown to the user.  Parts of the message that are not in
+/// the catalog are left untouched, and so are the arguments of the message (such as variable names
+/// or file names) unless they are nested messages themselves.
+pub fn translate(message: &str) -> String {
+    translate_to(locale(), message)
+}
+
+/// Translates `message` into the given `locale`.
+fn translate_to(locale: Locale, message: &str) -> String {
+    if locale == Locale::English {
+        return message.to_owned();
+    }
+
+    let (position, message) = split_position(message);
+    for (english, translated) in locale.messages() {
+        if let Some(args) = match_template(english, message) {
+            let mut result = position.to_owned();
+            let mut rest = *translated;
+            for arg in args {
+                let (i, placeholder) =
+                    find_placeholder(rest).expect("Translations must have the same placeholders");
+                result.push_str(&rest[..i]);
+                if placeholder == MESSAGE_PLACEHOLDER {
+                    result.push_str(&translate_to(locale, arg));
+                } else {
+                    result.push_str(arg);
+                }
+                rest = &rest[i + placeholder.len()..];
+            }
+            result.push_str(rest);
+            return result;
+        }
+    }
+    format!("{}{}", position, message)
+}
+
+/// Translates the help `category` description, whose first line is the category title, into the
+/// language given by `locale()`.
+pub fn translate_category(category: &'static str) -> &'static str {
+    let title = category.lines().next().unwrap_or("");
+    locale()
+        .categories()
+        .iter()
+        .find(|(english, _)| *english == title)
+        .map(|(_, translated)| *translated)
+        .unwrap_or(category)
+}
+
+/// Returns the description of the callable `name` in the language given by `locale()`, or none if
+/// the catalog does not have one and the original description should be used.
+pub fn translate_description(name: &str) -> Option<&'static str> {
+    locale()
+        .descriptions()
+        .iter()
+        .find(|(english, _)| *english == name)
+        .map(|(_, translated)| *translated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_locale_parse() {
+        assert_eq!(Some(Locale::English), Locale::parse("en"));
+        assert_eq!(Some(Locale::English), Locale::parse("en_US.UTF-8"));
+        assert_eq!(Some(Locale::English), Locale::parse("C"));
+        assert_eq!(Some(Locale::English), Locale::parse("POSIX"));
+        assert_eq!(Some(Locale::Spanish), Locale::parse("es"));
+        assert_eq!(Some(Locale::Spanish), Locale::parse("ES"));
+        assert_eq!(Some(Locale::Spanish), Locale::parse("es_AR.UTF-8"));
+        assert_eq!(Some(Locale::Spanish), Locale::parse("es-MX"));
+        assert_eq!(Some(Locale::Spanish), Locale::parse("es_ES@euro"));
+        assert_eq!(None, Locale::parse("fr_FR"));
+        assert_eq!(None, Locale::parse(""));
+    }
+
+    #[test]
+    fn test_match_template() {
+        assert_eq!(Some(vec![]), match_template("Division by zero", "Division by zero"));
+        assert_eq!(None, match_template("Division by zero", "Division by zero!"));
+        assert_eq!(
+            Some(vec!["x"]),
+            match_template("Undefined variable {}", "Undefined variable x")
+        );
+        assert_eq!(
+            Some(vec!["1", "\"a\""]),
+            match_template("Cannot add {} and {}", "Cannot add 1 and \"a\"")
+        );
+        assert_eq!(Some(vec!["3"]), match_template("{} is not a number", "3 is not a number"));
+        assert_eq!(None, match_template("Cannot add {} and {}", "Cannot add 1"));
+    }
+
+    #[test]
+    fn test_match_template_message_placeholder() {
+        assert_eq!(
+            Some(vec!["FOO", "1:2: Bar"]),
+            match_template("In call to {}: {message}", "In call to FOO: 1:2: Bar")
+        );
+    }
+
+    #[test]
+    fn test_find_placeholder() {
+        assert_eq!(None, find_placeholder("No placeholders"));
+        assert_eq!(Some((3, PLACEHOLDER)), find_placeholder("Or {} and {message}"));
+        assert_eq!(Some((3, MESSAGE_PLACEHOLDER)), find_placeholder("Or {message} and {}"));
+    }
+
+    #[test]
+    fn test_split_position() {
+        assert_eq!(("1:2: ", "Foo"), split_position("1:2: Foo"));
+        assert_eq!(("10:20: ", "Foo: bar"), split_position("10:20: Foo: bar"));
+        assert_eq!(("", "Foo: bar"), split_position("Foo: bar"));
+        assert_eq!(("", "1:a: Foo"), split_position("1:a: Foo"));
+        assert_eq!(("", "1:2:Foo"), split_position("1:2:Foo"));
+    }
+
+    #[test]
+    fn test_translate_english_is_identity() {
+        assert_eq!("1:7: Division by zero", translate_to(Locale::English, "1:7: Division by zero"));
+    }
+
+    #[test]
+    fn test_translate_spanish() {
+        assert_eq!(
+            "1:7: División por cero",
+            translate_to(Locale::Spanish, "1:7: Division by zero")
+        );
+        assert_eq!(
+            "1:7: Variable no definida: x",
+            translate_to(Locale::Spanish, "1:7: Undefined variable x")
+        );
+        assert_eq!(
+            "1:1: En la llamada a INPUT: 1:19: El tiempo de espera de INPUT debe ser positivo",
+            translate_to(
+                Locale::Spanish,
+                "1:1: In call to INPUT: 1:19: INPUT timeout must be positive"
+            )
+        );
+        assert_eq!(
+            "ERROR: 1:1: Comando desconocido: FOO",
+            translate_to(Locale::Spanish, "ERROR: 1:1: Unknown builtin FOO")
+        );
+    }
+
+    #[test]
+    fn test_translate_arguments_verbatim() {
+        assert_eq!(
+            "1:1: Tema de ayuda desconocido: Division by zero",
+            translate_to(Locale::Spanish, "1:1: Unknown help topic Division by zero")
+        );
+        assert_eq!(
+            "1:1: En la llamada a Ready: División por cero",
+            translate_to(Locale::Spanish, "1:1: In call to Ready: Division by zero")
+        );
+    }
+
+    #[test]
+    fn test_translate_unknown_message() {
+        assert_eq!("1:2: Some new error", translate_to(Locale::Spanish, "1:2: Some new error"));
+        assert_eq!(
+            "1:1: En la llamada a FOO: Some new error",
+            translate_to(Locale::Spanish, "1:1: In call to FOO: Some new error")
+        );
+    }
+
+    /// Returns the placeholders in `template` in the order in which they appear.
+    fn placeholders(mut template: &str) -> Vec<&'static str> {
+        let mut placeholders = vec![];
+        while let Some((i, placeholder)) = find_placeholder(template) {
+            placeholders.push(placeholder);
+            template = &template[i + placeholder.len()..];
+        }
+        placeholders
+    }
+
+    #[test]
+    fn test_catalogs_have_matching_placeholders() {
+        for (english, translated) in es::MESSAGES {
+            assert_eq!(placeholders(english), placeholders(translated), "In {}", english);
+        }
+    }
+
+    /// Appends the contents of all string literals in the Rust `source` file to `literals`,
+    /// ignoring those in the tests module.
+    fn extract_literals(source: &str, literals: &mut Vec<String>) {
+        let source = match source.find("#[cfg(test)]\nmod tests") {
+            Some(i) => &source[..i],
+            None => source,
+        };
+
+        let mut chars = source.chars().peekable();
+        let mut prev = ' ';
+        while let Some(ch) = chars.next() {
+            let is_ident = prev.is_alphanumeric() || prev == '_';
+            prev = ch;
+            match ch {
+                'r' if !is_ident && matches!(chars.peek(), Some('#') | Some('"')) => {
+                    let mut hashes = 0;
+                    while chars.peek() == Some(&'#') {
+                        chars.next();
+                        hashes += 1;
+                    }
+                    if chars.next() != Some('"') {
+                        continue;
+                    }
+                    let terminator = format!("\"{}", "#".repeat(hashes));
+                    let mut literal = String::new();
+                    for ch in chars.by_ref() {
+                        literal.push(ch);
+                        if literal.ends_with(&terminator) {
+                            break;
+                        }
+                    }
+                    literal.truncate(literal.len().saturating_sub(terminator.len()));
+                    literals.push(literal);
+                }
+                '/' if chars.peek() == Some(&'/') => {
+                    for ch in chars.by_ref() {
+                        if ch == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '\'' => {
+                    // Skip character literals so that '"' does not start a string.
+                    let mut lookahead = chars.clone();
+                    match (lookahead.next(), lookahead.next(), lookahead.next()) {
+                        (Some('\\'), Some(_), Some('\'')) => {
+                            chars.nth(2);
+                        }
+                        (Some(_), Some('\''), _) => {
+                            chars.nth(1);
+                        }
+                        _ => (),
+                    }
+                }
+                '"' => {
+                    let mut literal = String::new();
+                    while let Some(ch) = chars.next() {
+                        match ch {
+                            '"' => break,
+                            '\\' => match chars.next() {
+                                Some('n') => literal.push('\n'),
+                                Some('t') => literal.push('\t'),
+                                Some('\n') => {
+                                    while chars.peek().map(|ch| ch.is_whitespace()).unwrap_or(false)
+                                    {
+                                        chars.next();
+                                    }
+                                }
+                                Some(ch) => literal.push(ch),
+                                None => break,
+                            },
+                            ch => literal.push(ch),
+                        }
+                    }
+                    literals.push(literal);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Appends the contents of all string literals in the Rust files under `dir` to `literals`.
+    fn extract_literals_in_dir(dir: &Path, literals: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                if path.file_name() != Some(OsStr::new("i18n")) {
+                    extract_literals_in_dir(&path, literals);
+                }
+            } else if path.extension() == Some(OsStr::new("rs")) {
+                extract_literals(&fs::read_to_string(&path).unwrap(), literals);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extract_literals() {
+        let mut literals = vec![];
+        extract_literals(
+            "// \"Comment\"\nf('\"', \"a \\\"b\\\"\", '\\'', \"c\\\n    d\", r#\"\"e\"\"#, br\"f\");\n\
+             #[cfg(test)]\nmod tests {\"g\"}",
+            &mut literals,
+        );
+        assert_eq!(vec!["a \"b\"", "cd", "\"e\"", "f"], literals);
+    }
+
+    #[test]
+    fn test_catalog_messages_exist() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let mut literals = vec![];
+        for dir in ["cli/src", "core/src", "repl/src", "std/src"] {
+            extract_literals_in_dir(&root.join(dir), &mut literals);
+        }
+
+        for (english, _) in es::MESSAGES {
+            assert!(
+                literals.iter().any(|literal| match_template(english, literal).is_some()),
+                "Message '{}' is not emitted anywhere",
+                english
+            );
+        }
+    }
+
+    #[test]
+    fn test_translate_category_and_description() {
+        set_locale(Locale::English);
+        assert_eq!("Console\nText", translate_category("Console\nText"));
+        assert_eq!(None, translate_description("CLS"));
+
+        set_locale(Locale::Spanish);
+        assert!(translate_category("Console\nText").starts_with("Consola\n"));
+        assert_eq!("Unknown\nText", translate_category("Unknown\nText"));
+        assert_eq!(Some("Borra la pantalla."), translate_description("CLS"));
+        assert_eq!(None, translate_description("FOO"));
+        set_locale(Locale::English);
+    }
+}
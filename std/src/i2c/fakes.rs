@@ -0,0 +1,109 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Fake implementations of I2C buses that work on all platforms.
+
+use crate::i2c::Bus;
+use std::collections::VecDeque;
+use std::io;
+
+/// Stand-in implementation of the EndBASIC I2C operations that always returns an error.
+#[derive(Default)]
+pub(crate) struct NoopBus {}
+
+impl Bus for NoopBus {
+    fn open(&mut self, _bus: u8, _address: u16) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "I2C backend not compiled in"))
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "I2C backend not compiled in"))
+    }
+
+    fn read(&mut self, _buffer: &mut [u8]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "I2C backend not compiled in"))
+    }
+
+    fn write(&mut self, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "I2C backend not compiled in"))
+    }
+}
+
+/// Mock I2C implementation that records writes and supplies fake reads.
+///
+/// This allows scripting against I2C devices on machines without the hardware, such as during
+/// tests: the caller preloads the bytes that the device should return with `add_read_data` and
+/// later inspects the bytes that the program sent with `writes`.
+#[derive(Default)]
+pub struct MockBus {
+    device: Option<(u8, u16)>,
+    read_data: VecDeque<u8>,
+    writes: Vec<(u8, u16, Vec<u8>)>,
+}
+
+impl MockBus {
+    /// Appends `data` to the bytes that future reads will return.
+    pub fn add_read_data(&mut self, data: &[u8]) {
+        self.read_data.extend(data);
+    }
+
+    /// Returns the writes that happened so far as tuples of bus number, device address, and data.
+    pub fn writes(&self) -> &[(u8, u16, Vec<u8>)] {
+        &self.writes
+    }
+
+    /// Returns the currently open bus number and device address, if any.
+    fn get_device(&self) -> io::Result<(u8, u16)> {
+        match self.device {
+            Some(device) => Ok(device),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "I2C device not open; use I2C_OPEN first",
+            )),
+        }
+    }
+}
+
+impl Bus for MockBus {
+    fn open(&mut self, bus: u8, address: u16) -> io::Result<()> {
+        self.device = Some((bus, address));
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.device = None;
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        self.get_device()?;
+        if buffer.len() > self.read_data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Cannot read {} bytes from the I2C device", buffer.len()),
+            ));
+        }
+        for b in buffer.iter_mut() {
+            *b = self.read_data.pop_front().expect("Length validated above");
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let (bus, address) = self.get_device()?;
+        self.writes.push((bus, address, data.to_vec()));
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! I2C access commands for EndBASIC.
+
+use crate::gpio::CATEGORY;
+use async_trait::async_trait;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Expr, Value, VarType};
+use endbasic_core::exec::{Clearable, Machine};
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Symbols,
+};
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+use std::result::Result;
+
+mod fakes;
+pub use fakes::MockBus;
+pub(crate) use fakes::NoopBus;
+
+/// Generic abstraction over an I2C bus to back all EndBASIC commands.
+pub trait Bus {
+    /// Opens the I2C `bus` and selects the device at `address` for subsequent reads and writes.
+    ///
+    /// It is OK to open a device multiple times without calling `close()` in-between.
+    fn open(&mut self, bus: u8, address: u16) -> io::Result<()>;
+
+    /// Closes the currently-open device, if any.
+    fn close(&mut self) -> io::Result<()>;
+
+    /// Fills `buffer` with bytes read from the currently-open device.
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<()>;
+
+    /// Writes `data` to the currently-open device.
+    fn write(&mut self, data: &[u8]) -> io::Result<()>;
+}
+
+/// Evaluates `expr` as an integer and ensures it is within the `[0, max]` range.  `what` describes
+/// the value for error reporting purposes.
+pub(crate) async fn parse_int(
+    expr: &Expr,
+    machine: &mut Machine,
+    what: &str,
+    max: i32,
+) -> Result<i32, CallError> {
+    let value = expr.eval(machine.get_mut_symbols()).await?;
+    let i =
+        value.as_i32().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))?;
+    if !(0..=max).contains(&i) {
+        return Err(CallError::ArgumentError(
+            expr.start_pos(),
+            format!("{} {} must be between 0 and {}", what, i, max),
+        ));
+    }
+    Ok(i)
+}
+
+/// Closes the open I2C device in a best-effort manner.
+pub(crate) struct BusClearable {
+    bus: Rc<RefCell<dyn Bus>>,
+}
+
+impl BusClearable {
+    /// Creates a new clearable for `bus`.
+    pub(crate) fn new(bus: Rc<RefCell<dyn Bus>>) -> Box<Self> {
+        Box::from(Self { bus })
+    }
+}
+
+impl Clearable for BusClearable {
+    fn reset_state(&self, _syms: &mut Symbols) {
+        let _ = self.bus.borrow_mut().close();
+    }
+}
+
+/// The `I2C_OPEN` command.
+pub struct I2cOpenCommand {
+    metadata: CallableMetadata,
+    bus: Rc<RefCell<dyn Bus>>,
+}
+
+impl I2cOpenCommand {
+    /// Creates a new instance of the command.
+    pub fn new(bus: Rc<RefCell<dyn Bus>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("I2C_OPEN", VarType::Void)
+                .with_syntax("bus%, address%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Selects an I2C device for reads and writes.
+Before an I2C device such as a sensor can be read from or written to, the bus it is attached to \
+and its address on that bus must be selected.  On a Raspberry Pi, the bus% is typically 1.  The \
+address% is the 7-bit address of the device as given by its datasheet, which must be between 0 \
+and 127.
+Only one device can be open at any given time.  It is OK to open a different device without \
+closing the previous one first, and CLEAR closes the open device.",
+                )
+                .build(),
+            bus,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for I2cOpenCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [ArgSpan { expr: Some(bus), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(address), sep: ArgSep::End, .. }] =>
+            {
+                let bus = parse_int(bus, machine, "Bus number", u8::MAX as i32).await?;
+                let address = parse_int(address, machine, "Address", 127).await?;
+                self.bus.borrow_mut().open(bus as u8, address as u16)?;
+                Ok(())
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `I2C_READ` command.
+pub struct I2cReadCommand {
+    metadata: CallableMetadata,
+    bus: Rc<RefCell<dyn Bus>>,
+}
+
+impl I2cReadCommand {
+    /// Creates a new instance of the command.
+    pub fn new(bus: Rc<RefCell<dyn Bus>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("I2C_READ", VarType::Void)
+                .with_syntax("vref1 [, .., vrefN]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Reads bytes from the open I2C device.
+Reads as many bytes as variables are given in a single transfer and stores them, in order, into \
+vref1..vrefN as integers between 0 and 255.  The device must have been selected with I2C_OPEN \
+first.
+Many devices expose their data via registers, in which case you will first have to use I2C_WRITE \
+to send the number of the register to read from.",
+                )
+                .build(),
+            bus,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for I2cReadCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+
+        let mut symspans = Vec::with_capacity(span.args.len());
+        for arg in &span.args {
+            match (arg.expr.as_ref(), arg.sep) {
+                (Some(Expr::Symbol(symspan)), sep) if sep == ArgSep::Long || sep == ArgSep::End => {
+                    symspans.push(symspan)
+                }
+                _ => return Err(CallError::SyntaxError),
+            }
+        }
+
+        let mut buffer = vec![0; symspans.len()];
+        self.bus.borrow_mut().read(&mut buffer)?;
+
+        for (symspan, b) in symspans.into_iter().zip(buffer) {
+            machine
+                .get_mut_symbols()
+                .set_var(&symspan.vref, Value::Integer(i32::from(b)))
+                .map_err(|e| CallError::ArgumentError(symspan.pos, format!("{}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// The `I2C_WRITE` command.
+pub struct I2cWriteCommand {
+    metadata: CallableMetadata,
+    bus: Rc<RefCell<dyn Bus>>,
+}
+
+impl I2cWriteCommand {
+    /// Creates a new instance of the command.
+    pub fn new(bus: Rc<RefCell<dyn Bus>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("I2C_WRITE", VarType::Void)
+                .with_syntax("byte1% [, .., byteN%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Writes bytes to the open I2C device.
+Sends all of byte1%..byteN%, each of which must be between 0 and 255, in a single transfer.  The \
+device must have been selected with I2C_OPEN first.",
+                )
+                .build(),
+            bus,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for I2cWriteCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+
+        let mut data = Vec::with_capacity(span.args.len());
+        for arg in &span.args {
+            match (arg.expr.as_ref(), arg.sep) {
+                (Some(expr), sep) if sep == ArgSep::Long || sep == ArgSep::End => {
+                    data.push(parse_int(expr, machine, "Byte", u8::MAX as i32).await? as u8);
+                }
+                _ => return Err(CallError::SyntaxError),
+            }
+        }
+
+        self.bus.borrow_mut().write(&data)?;
+        Ok(())
+    }
+}
+
+/// Adds all symbols provided by this module to the given `machine`.
+pub fn add_all(machine: &mut Machine, bus: Rc<RefCell<dyn Bus>>) {
+    machine.add_clearable(BusClearable::new(bus.clone()));
+    machine.add_command(I2cOpenCommand::new(bus.clone()));
+    machine.add_command(I2cReadCommand::new(bus.clone()));
+    machine.add_command(I2cWriteCommand::new(bus));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    /// Creates a tester with the I2C commands backed by a new mock bus, which is returned too.
+    fn mock_tester() -> (Tester, Rc<RefCell<MockBus>>) {
+        let bus = Rc::from(RefCell::from(MockBus::default()));
+        let mut t = Tester::empty();
+        add_all(t.get_machine(), bus.clone());
+        (t, bus)
+    }
+
+    /// Tests that all I2C operations delegate to the real bus implementation, which defaults to
+    /// the no-op backend when using the tester.  All other tests in this file use a mock bus.
+    #[test]
+    fn test_real_backend() {
+        check_stmt_err("1:1: In call to I2C_OPEN: I2C backend not compiled in", "I2C_OPEN 1, 64");
+        check_stmt_err("1:1: In call to I2C_READ: I2C backend not compiled in", "I2C_READ a");
+        check_stmt_err("1:1: In call to I2C_WRITE: I2C backend not compiled in", "I2C_WRITE 1");
+    }
+
+    #[test]
+    fn test_i2c_write_and_read() {
+        let (mut t, bus) = mock_tester();
+        bus.borrow_mut().add_read_data(&[1, 200, 3]);
+        t.run("I2C_OPEN 1, 64: I2C_WRITE 250, 0.2: I2C_READ a, b%: I2C_OPEN 3, 5.1: I2C_WRITE 7")
+            .expect_var("a", 1)
+            .expect_var("b", 200)
+            .check();
+        assert_eq!(&[(1, 64, vec![250, 0]), (3, 5, vec![7])], bus.borrow().writes());
+    }
+
+    #[test]
+    fn test_i2c_clear_closes() {
+        let (mut t, bus) = mock_tester();
+        t.get_machine().add_command(crate::exec::ClearCommand::new());
+        t.run("I2C_OPEN 1, 64: CLEAR: I2C_WRITE 1")
+            .expect_err("1:24: In call to I2C_WRITE: I2C device not open; use I2C_OPEN first")
+            .check();
+        assert!(bus.borrow().writes().is_empty());
+    }
+
+    #[test]
+    fn test_i2c_open_errors() {
+        check_stmt_err("1:1: In call to I2C_OPEN: expected bus%, address%", "I2C_OPEN");
+        check_stmt_err("1:1: In call to I2C_OPEN: expected bus%, address%", "I2C_OPEN 1");
+        check_stmt_err("1:1: In call to I2C_OPEN: expected bus%, address%", "I2C_OPEN 1; 2");
+        check_stmt_err("1:1: In call to I2C_OPEN: expected bus%, address%", "I2C_OPEN 1, 2, 3");
+
+        check_stmt_err(
+            "1:1: In call to I2C_OPEN: 1:10: Bus number -1 must be between 0 and 255",
+            "I2C_OPEN -1, 2",
+        );
+        check_stmt_err(
+            "1:1: In call to I2C_OPEN: 1:10: Bus number 256 must be between 0 and 255",
+            "I2C_OPEN 256, 2",
+        );
+        check_stmt_err(
+            "1:1: In call to I2C_OPEN: 1:13: Address 128 must be between 0 and 127",
+            "I2C_OPEN 1, 128",
+        );
+        check_stmt_err(
+            "1:1: In call to I2C_OPEN: 1:13: \"a\" is not a number",
+            "I2C_OPEN 1, \"a\"",
+        );
+    }
+
+    #[test]
+    fn test_i2c_read_errors() {
+        check_stmt_err("1:1: In call to I2C_READ: expected vref1 [, .., vrefN]", "I2C_READ");
+        check_stmt_err("1:1: In call to I2C_READ: expected vref1 [, .., vrefN]", "I2C_READ 3");
+        check_stmt_err("1:1: In call to I2C_READ: expected vref1 [, .., vrefN]", "I2C_READ a; b");
+
+        let (mut t, _bus) = mock_tester();
+        t.run("I2C_READ a")
+            .expect_err("1:1: In call to I2C_READ: I2C device not open; use I2C_OPEN first")
+            .check();
+
+        let (mut t, _bus) = mock_tester();
+        t.run("I2C_OPEN 1, 2: I2C_READ a")
+            .expect_err("1:16: In call to I2C_READ: Cannot read 1 bytes from the I2C device")
+            .check();
+
+        let (mut t, bus) = mock_tester();
+        bus.borrow_mut().add_read_data(&[5]);
+        t.run("I2C_OPEN 1, 2: I2C_READ a$")
+            .expect_err("1:16: In call to I2C_READ: 1:25: Cannot assign value of type INTEGER to variable of type STRING")
+            .check();
+    }
+
+    #[test]
+    fn test_i2c_write_errors() {
+        check_stmt_err("1:1: In call to I2C_WRITE: expected byte1% [, .., byteN%]", "I2C_WRITE");
+        check_stmt_err(
+            "1:1: In call to I2C_WRITE: expected byte1% [, .., byteN%]",
+            "I2C_WRITE 1; 2",
+        );
+
+        check_stmt_err(
+            "1:1: In call to I2C_WRITE: 1:14: Byte 256 must be between 0 and 255",
+            "I2C_WRITE 1, 256",
+        );
+        check_stmt_err("1:1: In call to I2C_WRITE: 1:11: TRUE is not a number", "I2C_WRITE TRUE");
+
+        let (mut t, _bus) = mock_tester();
+        t.run("I2C_WRITE 1")
+            .expect_err("1:1: In call to I2C_WRITE: I2C device not open; use I2C_OPEN first")
+            .check();
+    }
+}
@@ -23,55 +23,127 @@
 #![warn(unsafe_code)]
 
 use async_channel::{Receiver, Sender};
-use endbasic_core::exec::{Machine, Result, Signal, YieldNowFn};
+use endbasic_core::exec::{ClockFn, Machine, Result, Signal, YieldNowFn};
 use std::cell::RefCell;
 use std::rc::Rc;
 
 // TODO(jmmv): Should narrow the exposed interface by 1.0.0.
 pub mod arrays;
+pub mod collections;
 pub mod console;
 pub mod data;
+pub mod datetime;
+pub mod debug;
 pub mod exec;
 pub mod gfx;
 pub mod gpio;
 pub mod help;
+pub mod http;
+pub mod i18n;
+pub mod i2c;
+#[cfg(feature = "net")]
+pub mod net;
 pub mod numerics;
+pub mod plugins;
+pub mod process;
 pub mod program;
+pub mod regexp;
+pub mod serial;
+pub mod spi;
 pub mod storage;
 pub mod strings;
 pub mod testutils;
+pub mod tutorial;
 
 /// Builder pattern to construct an EndBASIC interpreter.
 ///
 /// Unless otherwise specified, the interpreter is connected to a terminal-based console.
 #[derive(Default)]
 pub struct MachineBuilder {
+    args: process::ProgramArgs,
     console: Option<Rc<RefCell<dyn console::Console>>>,
+    env: Option<Rc<RefCell<dyn process::Environment>>>,
     gpio_pins: Option<Rc<RefCell<dyn gpio::Pins>>>,
+    http_client: Option<Rc<dyn http::Client>>,
+    i2c_bus: Option<Rc<RefCell<dyn i2c::Bus>>>,
+    plugins: Vec<Rc<dyn plugins::Plugin>>,
     sleep_fn: Option<exec::SleepFn>,
+    spi_bus: Option<Rc<RefCell<dyn spi::Bus>>>,
+    clock_fn: Option<ClockFn>,
     yield_now_fn: Option<YieldNowFn>,
+    serial_port: Option<Rc<RefCell<dyn serial::Port>>>,
     signals_chan: Option<(Sender<Signal>, Receiver<Signal>)>,
 }
 
 impl MachineBuilder {
+    /// Sets the arguments given to the program, where the first one is the program's name.
+    pub fn with_args(mut self, args: process::ProgramArgs) -> Self {
+        self.args = args;
+        self
+    }
+
     /// Overrides the default terminal-based console with the given one.
     pub fn with_console(mut self, console: Rc<RefCell<dyn console::Console>>) -> Self {
         self.console = Some(console);
         self
     }
 
+    /// Overrides the default in-memory environment variables with the given ones.
+    pub fn with_env(mut self, env: Rc<RefCell<dyn process::Environment>>) -> Self {
+        self.env = Some(env);
+        self
+    }
+
     /// Overrides the default hardware-based GPIO pins with the given ones.
     pub fn with_gpio_pins(mut self, pins: Rc<RefCell<dyn gpio::Pins>>) -> Self {
         self.gpio_pins = Some(pins);
         self
     }
 
+    /// Overrides the default HTTP client, which does not support any requests, with the given one.
+    pub fn with_http_client(mut self, client: Rc<dyn http::Client>) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Overrides the default I2C bus, which does not support any operations, with the given one.
+    pub fn with_i2c_bus(mut self, bus: Rc<RefCell<dyn i2c::Bus>>) -> Self {
+        self.i2c_bus = Some(bus);
+        self
+    }
+
+    /// Registers the given plugin, whose commands and functions will be added to the machine.
+    pub fn with_plugin(mut self, plugin: Rc<dyn plugins::Plugin>) -> Self {
+        self.plugins.push(plugin);
+        self
+    }
+
     /// Overrides the default sleep function with the given one.
     pub fn with_sleep_fn(mut self, sleep_fn: exec::SleepFn) -> Self {
         self.sleep_fn = Some(sleep_fn);
         self
     }
 
+    /// Overrides the default serial port, which does not support any operations, with the given
+    /// one.
+    pub fn with_serial_port(mut self, port: Rc<RefCell<dyn serial::Port>>) -> Self {
+        self.serial_port = Some(port);
+        self
+    }
+
+    /// Overrides the default SPI bus, which does not support any operations, with the given one.
+    pub fn with_spi_bus(mut self, bus: Rc<RefCell<dyn spi::Bus>>) -> Self {
+        self.spi_bus = Some(bus);
+        self
+    }
+
+    /// Overrides the default clock used by the profiler, by `ON INTERVAL`, and by the timing
+    /// functions with the given one.
+    pub fn with_clock_fn(mut self, clock_fn: ClockFn) -> Self {
+        self.clock_fn = Some(clock_fn);
+        self
+    }
+
     /// Overrides the default yielding function with the given one.
     pub fn with_yield_now_fn(mut self, yield_now_fn: YieldNowFn) -> Self {
         self.yield_now_fn = Some(yield_now_fn);
@@ -104,6 +176,28 @@ impl MachineBuilder {
     pub fn build(mut self) -> Result<Machine> {
         let console = self.get_console();
         let gpio_pins = self.get_gpio_pins();
+        let env = match self.env {
+            Some(env) => env,
+            None => Rc::from(RefCell::from(process::VirtualEnvironment::default())),
+        };
+        let http_client = match self.http_client {
+            Some(client) => client,
+            None => Rc::from(http::NoopClient::default()),
+        };
+
+        let i2c_bus = match self.i2c_bus {
+            Some(bus) => bus,
+            None => Rc::from(RefCell::from(i2c::NoopBus::default())),
+        };
+
+        let serial_port = match self.serial_port {
+            Some(port) => port,
+            None => Rc::from(RefCell::from(serial::NoopPort::default())),
+        };
+        let spi_bus = match self.spi_bus {
+            Some(bus) => bus,
+            None => Rc::from(RefCell::from(spi::NoopBus::default())),
+        };
 
         let signals_chan = match self.signals_chan {
             Some(pair) => pair,
@@ -112,14 +206,27 @@ impl MachineBuilder {
 
         let mut machine =
             Machine::with_signals_chan_and_yield_now_fn(signals_chan, self.yield_now_fn);
+        let clock_fn = self.clock_fn.unwrap_or_else(storage::system_clock);
+        machine.set_clock(clock_fn.clone());
         arrays::add_all(&mut machine);
+        collections::add_all(&mut machine);
         console::add_all(&mut machine, console.clone());
         data::add_all(&mut machine);
-        gfx::add_all(&mut machine, console);
+        datetime::add_all(&mut machine, None);
+        gfx::add_all(&mut machine, console.clone());
         gpio::add_all(&mut machine, gpio_pins);
-        exec::add_all(&mut machine, self.sleep_fn);
+        exec::add_all(&mut machine, self.sleep_fn, Some(clock_fn));
+        http::add_all(&mut machine, http_client);
+        i2c::add_all(&mut machine, i2c_bus);
+        #[cfg(feature = "net")]
+        net::add_all(&mut machine);
         numerics::add_all(&mut machine);
+        process::add_all(&mut machine, env, self.args);
+        regexp::add_all(&mut machine);
+        serial::add_all(&mut machine, serial_port);
+        spi::add_all(&mut machine, spi_bus);
         strings::add_all(&mut machine);
+        plugins::add_all(&mut machine, console, self.plugins);
         Ok(machine)
     }
 
@@ -177,8 +284,31 @@ impl InteractiveMachineBuilder {
         let console = self.builder.get_console();
         let program = self.get_program();
         let storage = self.get_storage();
+        let clock_fn = self.builder.clock_fn.clone();
+        if let Some(client) = self.builder.http_client.as_ref() {
+            let mut storage = storage.borrow_mut();
+            storage.register_scheme(
+                "http",
+                Box::from(storage::HttpDriveFactory::new(client.clone(), "http")),
+            );
+            storage.register_scheme(
+                "https",
+                Box::from(storage::HttpDriveFactory::new(client.clone(), "https")),
+            );
+        }
+
+        // Hook the delivery of file change notifications into the machine's yielding function,
+        // which is invoked before running every statement.
+        let signals_chan =
+            self.builder.signals_chan.take().unwrap_or_else(async_channel::unbounded);
+        let watcher = Rc::from(storage::FileWatcher::new(storage.clone(), signals_chan.0.clone()));
+        self.builder.signals_chan = Some(signals_chan);
+        self.builder.yield_now_fn =
+            Some(storage::FileWatcher::new_yield_now_fn(watcher, self.builder.yield_now_fn.take()));
+
         let mut machine = self.builder.build()?;
 
+        debug::add_all(&mut machine, program.clone(), console.clone(), clock_fn);
         help::add_all(&mut machine, console.clone());
         program::add_all(&mut machine, program, console.clone(), storage.clone());
         storage::add_all(&mut machine, console, storage);
@@ -0,0 +1,745 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! TCP socket functions and commands for EndBASIC.
+//!
+//! These require the program to run within a Tokio runtime, which is what the EndBASIC CLI uses
+//! to drive the machine.
+
+use async_channel::{Receiver, Sender};
+use async_trait::async_trait;
+use endbasic_core::ast::{
+    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarType,
+};
+use endbasic_core::eval::eval_all;
+use endbasic_core::exec::{Clearable, Machine, Signal};
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
+    FunctionResult, Symbols,
+};
+use endbasic_core::LineCol;
+use futures_lite::future;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::rc::Rc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Sockets
+Sockets allow programs to exchange data with other programs over TCP/IP connections.
+To talk to a server, open a connection with SOCKET_CONNECT, which returns an integer handle that \
+identifies the connection.  Use SOCKET_SEND and SOCKET_RECV$ to exchange data over it, and close \
+it with SOCKET_CLOSE once done.
+To act as a server, start listening for connections with SOCKET_LISTEN and wait for clients to \
+connect with SOCKET_ACCEPT, which returns a handle for the new connection.
+All operations wait until they complete, but waits can be interrupted with CTRL+C.  All sockets \
+are closed by CLEAR.";
+
+/// Maximum number of bytes that SOCKET_RECV reads when the caller does not specify a limit.
+const DEFAULT_RECV_SIZE: i32 = 4096;
+
+/// Storage for all sockets opened by the program.
+///
+/// Sockets are taken out of their maps while an operation on them is in progress so that we do not
+/// hold a borrow of the storage across await points.
+#[derive(Default)]
+struct Sockets {
+    /// Handle to assign to the next socket that is opened.
+    next_handle: i32,
+
+    /// Mapping of handles to established connections.
+    streams: HashMap<i32, TcpStream>,
+
+    /// Mapping of handles to listening sockets.
+    listeners: HashMap<i32, TcpListener>,
+}
+
+impl Sockets {
+    /// Allocates a new handle.
+    fn new_handle(&mut self) -> i32 {
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    /// Takes the connection identified by `handle`, which was provided at `pos`.
+    fn take_stream(&mut self, handle: i32, pos: LineCol) -> Result<TcpStream, CallError> {
+        match self.streams.remove(&handle) {
+            Some(stream) => Ok(stream),
+            None => {
+                Err(CallError::ArgumentError(pos, format!("Invalid connection handle {}", handle)))
+            }
+        }
+    }
+
+    /// Takes the listening socket identified by `handle`, which was provided at `pos`.
+    fn take_listener(&mut self, handle: i32, pos: LineCol) -> Result<TcpListener, CallError> {
+        match self.listeners.remove(&handle) {
+            Some(listener) => Ok(listener),
+            None => {
+                Err(CallError::ArgumentError(pos, format!("Invalid listener handle {}", handle)))
+            }
+        }
+    }
+}
+
+/// Channels to the machine's signals, used to interrupt operations that wait on the network.
+#[derive(Clone)]
+struct Signals {
+    /// Channel to send signals back to the machine.
+    tx: Sender<Signal>,
+
+    /// Channel to receive signals destined to the machine.
+    rx: Receiver<Signal>,
+}
+
+impl Signals {
+    /// Waits for `future` to complete unless a break signal arrives first, in which case the
+    /// operation is abandoned and fails with an `Interrupted` error.
+    ///
+    /// Any signals received while waiting, including the break signal, are sent back to the machine
+    /// once done so that it can process them as usual.
+    async fn interruptible<T, F>(&self, future: F) -> io::Result<T>
+    where
+        F: Future<Output = io::Result<T>>,
+    {
+        let mut deferred = vec![];
+        let wait_for_break = async {
+            loop {
+                match self.rx.recv().await {
+                    Ok(Signal::Break) => break,
+                    Ok(signal) => deferred.push(signal),
+                    Err(_) => future::pending::<()>().await,
+                }
+            }
+        };
+        let result = future::or(async { Some(future.await) }, async {
+            wait_for_break.await;
+            None
+        })
+        .await;
+
+        if result.is_none() {
+            deferred.push(Signal::Break);
+        }
+        for signal in deferred {
+            let _ = self.tx.try_send(signal);
+        }
+        result.unwrap_or_else(|| {
+            Err(io::Error::new(io::ErrorKind::Interrupted, "Interrupted by BREAK"))
+        })
+    }
+}
+
+/// Clearable for the sockets state.
+struct SocketsClearable {
+    sockets: Rc<RefCell<Sockets>>,
+}
+
+impl Clearable for SocketsClearable {
+    fn reset_state(&self, _syms: &mut Symbols) {
+        *self.sockets.borrow_mut() = Sockets::default();
+    }
+}
+
+/// Converts the `value` provided at `pos` to a socket handle.
+fn value_to_handle(value: &Value, pos: LineCol) -> Result<i32, CallError> {
+    match value {
+        Value::Integer(i) => Ok(*i),
+        _ => Err(CallError::ArgumentError(pos, "Socket handle must be an integer".to_owned())),
+    }
+}
+
+/// Converts the `value` provided at `pos` to a port number.
+fn value_to_port(value: &Value, pos: LineCol) -> Result<u16, CallError> {
+    match value {
+        Value::Integer(i) if *i >= 0 && *i <= i32::from(u16::MAX) => Ok(*i as u16),
+        Value::Integer(i) => {
+            Err(CallError::ArgumentError(pos, format!("Port number {} out of range", i)))
+        }
+        _ => Err(CallError::ArgumentError(pos, "Port number must be an integer".to_owned())),
+    }
+}
+
+/// Returns the position of the `i`th expression in `exprs`.
+fn arg_pos(exprs: &[Expr], i: usize) -> LineCol {
+    exprs[i].start_pos()
+}
+
+/// The `SOCKET_ACCEPT` function.
+pub struct SocketAcceptFunction {
+    metadata: CallableMetadata,
+    sockets: Rc<RefCell<Sockets>>,
+    signals: Signals,
+}
+
+impl SocketAcceptFunction {
+    /// Creates a new instance of the function.
+    fn new(sockets: Rc<RefCell<Sockets>>, signals: Signals) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SOCKET_ACCEPT", VarType::Integer)
+                .with_syntax("listener%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Waits for a client to connect to a listening socket.
+Returns the handle of the new connection, which can be used with SOCKET_SEND and SOCKET_RECV$.",
+                )
+                .build(),
+            sockets,
+            signals,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for SocketAcceptFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [listener] => {
+                let pos = arg_pos(&span.args, 0);
+                let handle = value_to_handle(listener, pos)?;
+                let listener = self.sockets.borrow_mut().take_listener(handle, pos)?;
+                let result = self.signals.interruptible(listener.accept()).await;
+                let mut sockets = self.sockets.borrow_mut();
+                sockets.listeners.insert(handle, listener);
+                let (stream, _addr) = result?;
+                let handle = sockets.new_handle();
+                sockets.streams.insert(handle, stream);
+                Ok(Value::Integer(handle))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `SOCKET_CLOSE` command.
+pub struct SocketCloseCommand {
+    metadata: CallableMetadata,
+    sockets: Rc<RefCell<Sockets>>,
+}
+
+impl SocketCloseCommand {
+    /// Creates a new instance of the command.
+    fn new(sockets: Rc<RefCell<Sockets>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SOCKET_CLOSE", VarType::Void)
+                .with_syntax("handle%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Closes a connection or a listening socket.
+The handle becomes invalid after this command.",
+                )
+                .build(),
+            sockets,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SocketCloseCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let expr = match span.args.as_slice() {
+            [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => expr,
+            _ => return Err(CallError::SyntaxError),
+        };
+        let pos = expr.start_pos();
+        let handle = value_to_handle(&expr.eval(machine.get_mut_symbols()).await?, pos)?;
+
+        let mut sockets = self.sockets.borrow_mut();
+        if sockets.streams.remove(&handle).is_none() && sockets.listeners.remove(&handle).is_none()
+        {
+            return Err(CallError::ArgumentError(pos, format!("Invalid socket handle {}", handle)));
+        }
+        Ok(())
+    }
+}
+
+/// The `SOCKET_CONNECT` function.
+pub struct SocketConnectFunction {
+    metadata: CallableMetadata,
+    sockets: Rc<RefCell<Sockets>>,
+    signals: Signals,
+}
+
+impl SocketConnectFunction {
+    /// Creates a new instance of the function.
+    fn new(sockets: Rc<RefCell<Sockets>>, signals: Signals) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SOCKET_CONNECT", VarType::Integer)
+                .with_syntax("host$, port%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Opens a connection to a server.
+host$ can be a host name or an IP address.  Returns the handle of the new connection.",
+                )
+                .build(),
+            sockets,
+            signals,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for SocketConnectFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(host), port] => {
+                let port = value_to_port(port, arg_pos(&span.args, 1))?;
+                let stream =
+                    self.signals.interruptible(TcpStream::connect((host.as_str(), port))).await?;
+                let mut sockets = self.sockets.borrow_mut();
+                let handle = sockets.new_handle();
+                sockets.streams.insert(handle, stream);
+                Ok(Value::Integer(handle))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `SOCKET_LISTEN` function.
+pub struct SocketListenFunction {
+    metadata: CallableMetadata,
+    sockets: Rc<RefCell<Sockets>>,
+}
+
+impl SocketListenFunction {
+    /// Creates a new instance of the function.
+    fn new(sockets: Rc<RefCell<Sockets>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SOCKET_LISTEN", VarType::Integer)
+                .with_syntax("address$, port%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Starts listening for connections on a local address and port.
+Use \"127.0.0.1\" as the address$ to only accept connections from the local machine or \
+\"0.0.0.0\" to accept connections from anywhere.  If port% is 0, a free port is chosen \
+automatically and can be queried with SOCKET_PORT%.
+Returns the handle of the listening socket, which can be used with SOCKET_ACCEPT.",
+                )
+                .build(),
+            sockets,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for SocketListenFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(address), port] => {
+                let port = value_to_port(port, arg_pos(&span.args, 1))?;
+                let listener = TcpListener::bind((address.as_str(), port)).await?;
+                let mut sockets = self.sockets.borrow_mut();
+                let handle = sockets.new_handle();
+                sockets.listeners.insert(handle, listener);
+                Ok(Value::Integer(handle))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `SOCKET_PORT` function.
+pub struct SocketPortFunction {
+    metadata: CallableMetadata,
+    sockets: Rc<RefCell<Sockets>>,
+}
+
+impl SocketPortFunction {
+    /// Creates a new instance of the function.
+    fn new(sockets: Rc<RefCell<Sockets>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SOCKET_PORT", VarType::Integer)
+                .with_syntax("handle%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the local port number of a connection or a listening socket.",
+                )
+                .build(),
+            sockets,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for SocketPortFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [handle] => {
+                let pos = arg_pos(&span.args, 0);
+                let handle = value_to_handle(handle, pos)?;
+                let sockets = self.sockets.borrow();
+                let addr = if let Some(stream) = sockets.streams.get(&handle) {
+                    stream.local_addr()?
+                } else if let Some(listener) = sockets.listeners.get(&handle) {
+                    listener.local_addr()?
+                } else {
+                    return Err(CallError::ArgumentError(
+                        pos,
+                        format!("Invalid socket handle {}", handle),
+                    ));
+                };
+                Ok(Value::Integer(i32::from(addr.port())))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `SOCKET_RECV` function.
+pub struct SocketRecvFunction {
+    metadata: CallableMetadata,
+    sockets: Rc<RefCell<Sockets>>,
+    signals: Signals,
+}
+
+impl SocketRecvFunction {
+    /// Creates a new instance of the function.
+    fn new(sockets: Rc<RefCell<Sockets>>, signals: Signals) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SOCKET_RECV", VarType::Text)
+                .with_syntax("handle%[, max_bytes%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Receives data from a connection.
+Waits until some data is available and returns it, which may be less than max_bytes% (4096 by \
+default).  Returns an empty string once the other end has closed the connection.",
+                )
+                .build(),
+            sockets,
+            signals,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for SocketRecvFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (handle, max_bytes) = match args.as_slice() {
+            [handle] => (handle, DEFAULT_RECV_SIZE),
+            [handle, Value::Integer(max_bytes)] => {
+                if *max_bytes <= 0 {
+                    return Err(CallError::ArgumentError(
+                        arg_pos(&span.args, 1),
+                        "max_bytes% must be positive".to_owned(),
+                    ));
+                }
+                (handle, *max_bytes)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+        let pos = arg_pos(&span.args, 0);
+        let handle = value_to_handle(handle, pos)?;
+
+        let mut stream = self.sockets.borrow_mut().take_stream(handle, pos)?;
+        let mut buffer = vec![0; max_bytes as usize];
+        let result = self.signals.interruptible(stream.read(&mut buffer)).await;
+        self.sockets.borrow_mut().streams.insert(handle, stream);
+        let n = result?;
+        Ok(Value::Text(String::from_utf8_lossy(&buffer[..n]).into_owned()))
+    }
+}
+
+/// The `SOCKET_SEND` command.
+pub struct SocketSendCommand {
+    metadata: CallableMetadata,
+    sockets: Rc<RefCell<Sockets>>,
+    signals: Signals,
+}
+
+impl SocketSendCommand {
+    /// Creates a new instance of the command.
+    fn new(sockets: Rc<RefCell<Sockets>>, signals: Signals) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SOCKET_SEND", VarType::Void)
+                .with_syntax("handle%, data$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sends data over a connection.
+Waits until all of data$ has been handed to the network.",
+                )
+                .build(),
+            sockets,
+            signals,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SocketSendCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (handle_expr, data_expr) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(handle), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(data), sep: ArgSep::End, .. }] => {
+                (handle, data)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+        let pos = handle_expr.start_pos();
+        let handle = value_to_handle(&handle_expr.eval(machine.get_mut_symbols()).await?, pos)?;
+        let data = match data_expr.eval(machine.get_mut_symbols()).await? {
+            Value::Text(data) => data,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    data_expr.start_pos(),
+                    "SOCKET_SEND requires a string as the data".to_owned(),
+                ))
+            }
+        };
+
+        let mut stream = self.sockets.borrow_mut().take_stream(handle, pos)?;
+        let result = self.signals.interruptible(stream.write_all(data.as_bytes())).await;
+        self.sockets.borrow_mut().streams.insert(handle, stream);
+        result?;
+        Ok(())
+    }
+}
+
+/// Adds all symbols provided by this module to the given `machine`.
+pub fn add_all(machine: &mut Machine) {
+    let sockets = Rc::from(RefCell::from(Sockets::default()));
+    let signals = Signals { tx: machine.get_signals_tx(), rx: machine.get_signals_rx() };
+    machine.add_clearable(Box::from(SocketsClearable { sockets: sockets.clone() }));
+    machine.add_function(SocketAcceptFunction::new(sockets.clone(), signals.clone()));
+    machine.add_command(SocketCloseCommand::new(sockets.clone()));
+    machine.add_function(SocketConnectFunction::new(sockets.clone(), signals.clone()));
+    machine.add_function(SocketListenFunction::new(sockets.clone()));
+    machine.add_function(SocketPortFunction::new(sockets.clone()));
+    machine.add_function(SocketRecvFunction::new(sockets.clone(), signals.clone()));
+    machine.add_command(SocketSendCommand::new(sockets, signals));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testutils::*;
+    use endbasic_core::exec::{Signal, StopReason};
+    use futures_lite::future::block_on;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Runs `script` on a new machine, which must fail, and returns the error message.
+    fn run_err(script: &str) -> String {
+        let mut tester = Tester::default();
+        block_on(tester.get_machine().exec(&mut script.as_bytes())).unwrap_err().to_string()
+    }
+
+    /// Creates a tester whose machine receives a break signal after a short delay.
+    fn tester_with_delayed_break() -> Tester {
+        let mut tester = Tester::default();
+        let signals_tx = tester.get_machine().get_signals_tx();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            signals_tx.try_send(Signal::Break).unwrap();
+        });
+        tester
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_client_server() {
+        Tester::default()
+            .run(
+                r#"
+                l = SOCKET_LISTEN("127.0.0.1", 0)
+                c = SOCKET_CONNECT("127.0.0.1", SOCKET_PORT(l))
+                s = SOCKET_ACCEPT(l)
+                SOCKET_SEND c, "ping"
+                r1$ = SOCKET_RECV(s)
+                SOCKET_SEND s, "pong"
+                r2$ = SOCKET_RECV$(c, 2)
+                r3$ = SOCKET_RECV$(c, 2)
+                SOCKET_CLOSE c
+                r4$ = SOCKET_RECV(s)
+                SOCKET_CLOSE s
+                SOCKET_CLOSE l
+                "#,
+            )
+            .expect_var("l", 1)
+            .expect_var("c", 2)
+            .expect_var("s", 3)
+            .expect_var("r1", "ping")
+            .expect_var("r2", "po")
+            .expect_var("r3", "ng")
+            .expect_var("r4", "")
+            .check();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_connect_errors() {
+        check_expr_error(
+            "1:10: In call to SOCKET_CONNECT: expected host$, port%",
+            r#"SOCKET_CONNECT("localhost")"#,
+        );
+        check_expr_error(
+            "1:10: In call to SOCKET_CONNECT: expected host$, port%",
+            r#"SOCKET_CONNECT(1, 2)"#,
+        );
+        check_expr_error(
+            "1:10: In call to SOCKET_CONNECT: 1:38: Port number 70000 out of range",
+            r#"SOCKET_CONNECT("localhost", 70000)"#,
+        );
+        check_expr_error(
+            "1:10: In call to SOCKET_CONNECT: 1:38: Port number must be an integer",
+            r#"SOCKET_CONNECT("localhost", "80")"#,
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_connect_refused() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let message = run_err(&format!(r#"c = SOCKET_CONNECT("127.0.0.1", {})"#, port));
+        assert!(message.starts_with("1:5: In call to SOCKET_CONNECT: "), "{}", message);
+        assert!(message.to_ascii_lowercase().contains("refused"), "{}", message);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bad_address() {
+        let message = run_err(r#"c = SOCKET_CONNECT("127.0.0.1:80", 80)"#);
+        assert!(message.starts_with("1:5: In call to SOCKET_CONNECT: "), "{}", message);
+
+        let message = run_err(r#"l = SOCKET_LISTEN("127.0.0.1:80", 0)"#);
+        assert!(message.starts_with("1:5: In call to SOCKET_LISTEN: "), "{}", message);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_closed_sockets() {
+        Tester::default()
+            .run(
+                r#"
+                l = SOCKET_LISTEN("127.0.0.1", 0)
+                c = SOCKET_CONNECT("127.0.0.1", SOCKET_PORT(l))
+                SOCKET_CLOSE c
+                r$ = SOCKET_RECV(c)
+                "#,
+            )
+            .expect_err("5:22: In call to SOCKET_RECV: 5:34: Invalid connection handle 2")
+            .expect_var("l", 1)
+            .expect_var("c", 2)
+            .check();
+
+        Tester::default()
+            .run(
+                r#"
+                l = SOCKET_LISTEN("127.0.0.1", 0)
+                SOCKET_CLOSE l
+                s = SOCKET_ACCEPT(l)
+                "#,
+            )
+            .expect_err("4:21: In call to SOCKET_ACCEPT: 4:35: Invalid listener handle 1")
+            .expect_var("l", 1)
+            .check();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_accept_break() {
+        let mut t = tester_with_delayed_break();
+        t.run(r#"l = SOCKET_LISTEN("127.0.0.1", 0): s = SOCKET_ACCEPT(l)"#)
+            .expect_err("1:40: In call to SOCKET_ACCEPT: Interrupted by BREAK")
+            .expect_var("l", 1)
+            .check();
+        assert_eq!(Ok(Signal::Break), t.get_machine().get_signals_rx().try_recv());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_recv_break() {
+        tester_with_delayed_break()
+            .run(
+                r#"
+                ON ERROR RESUME NEXT
+                l = SOCKET_LISTEN("127.0.0.1", 0)
+                c = SOCKET_CONNECT("127.0.0.1", SOCKET_PORT(l))
+                r$ = SOCKET_RECV(c)
+                r$ = "not reached"
+                "#,
+            )
+            .expect_ok(StopReason::Break)
+            .expect_var("0ERRMSG", "5:22: In call to SOCKET_RECV: Interrupted by BREAK")
+            .expect_var("l", 1)
+            .expect_var("c", 2)
+            .check();
+    }
+
+    #[test]
+    fn test_invalid_handles() {
+        check_expr_error(
+            "1:10: In call to SOCKET_ACCEPT: 1:24: Invalid listener handle 1",
+            "SOCKET_ACCEPT(1)",
+        );
+        check_expr_error(
+            "1:10: In call to SOCKET_RECV: 1:22: Invalid connection handle 1",
+            "SOCKET_RECV(1)",
+        );
+        check_expr_error(
+            "1:10: In call to SOCKET_RECV: 1:22: Socket handle must be an integer",
+            r#"SOCKET_RECV("a")"#,
+        );
+        check_expr_error(
+            "1:10: In call to SOCKET_RECV: 1:25: max_bytes% must be positive",
+            "SOCKET_RECV(1, 0)",
+        );
+        check_expr_error(
+            "1:10: In call to SOCKET_PORT: 1:22: Invalid socket handle 1",
+            "SOCKET_PORT(1)",
+        );
+        check_stmt_err(
+            "1:1: In call to SOCKET_CLOSE: 1:14: Invalid socket handle 1",
+            "SOCKET_CLOSE 1",
+        );
+        check_stmt_err("1:1: In call to SOCKET_CLOSE: expected handle%", "SOCKET_CLOSE");
+        check_stmt_err(
+            "1:1: In call to SOCKET_SEND: 1:13: Invalid connection handle 1",
+            r#"SOCKET_SEND 1, "a""#,
+        );
+        check_stmt_err(
+            "1:1: In call to SOCKET_SEND: 1:16: SOCKET_SEND requires a string as the data",
+            "SOCKET_SEND 1, 2",
+        );
+        check_stmt_err("1:1: In call to SOCKET_SEND: expected handle%, data$", "SOCKET_SEND 1");
+    }
+}
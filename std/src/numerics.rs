@@ -15,18 +15,20 @@
 
 //! Numerical functions for EndBASIC.
 
+use crate::arrays::expr_to_array_ref;
 use async_trait::async_trait;
 use endbasic_core::ast::{
     ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarType,
 };
-use endbasic_core::eval::eval_all;
+use endbasic_core::eval::{self, eval_all};
 use endbasic_core::exec::{Clearable, Machine};
 use endbasic_core::syms::{
     CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
-    FunctionResult, Symbols,
+    FunctionResult, Symbol, Symbols,
 };
 use rand::rngs::SmallRng;
-use rand::{RngCore, SeedableRng};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::rc::Rc;
@@ -73,6 +75,50 @@ async fn get_angle(
     }
 }
 
+/// Converts the `value` obtained from evaluating the `expr` argument to a double.
+fn arg_to_f64(value: &Value, expr: &Expr) -> Result<f64, CallError> {
+    value.as_f64().map_err(|e| CallError::ArgumentError(expr.start_pos(), format!("{}", e)))
+}
+
+/// Gets the arguments to a function that operates on a set of numbers, which can be provided
+/// either as individual expressions or as a single reference to an array.
+async fn get_number_set(args: &[Expr], symbols: &mut Symbols) -> Result<Vec<f64>, CallError> {
+    if let [expr] = args {
+        if let Some((arrayref, arraypos)) = expr_to_array_ref(expr) {
+            if let Ok(Some(Symbol::Array(array))) = symbols.get(arrayref) {
+                let mut ns = Vec::with_capacity(array.values().len());
+                for value in array.values() {
+                    ns.push(
+                        value
+                            .as_f64()
+                            .map_err(|e| CallError::ArgumentError(arraypos, format!("{}", e)))?,
+                    );
+                }
+                return Ok(ns);
+            }
+        }
+    }
+
+    if args.is_empty() {
+        return Err(CallError::SyntaxError);
+    }
+    let values = eval_all(args, symbols).await?;
+    let mut ns = Vec::with_capacity(values.len());
+    for (expr, value) in args.iter().zip(values) {
+        ns.push(arg_to_f64(&value, expr)?);
+    }
+    Ok(ns)
+}
+
+/// Rounds `n` to the nearest integer, resolving ties towards the closest even integer.
+fn round_half_even(n: f64) -> f64 {
+    if (n - n.trunc()).abs() == 0.5 {
+        2.0 * (n / 2.0).round()
+    } else {
+        n.round()
+    }
+}
+
 /// Tracks the state of the PRNG used by the random number manipulation functions and commands.
 ///
 /// The PRNG implemented here is intentionally simplistic and has no cryptographical guarantees.
@@ -106,6 +152,61 @@ impl Prng {
         self.last = self.prng.next_u32();
         self.last()
     }
+
+    /// Computes a random integer in the `[lo, hi]` range.
+    fn next_int(&mut self, lo: i32, hi: i32) -> i32 {
+        self.prng.gen_range(lo..=hi)
+    }
+
+    /// Randomly reorders the `values`.
+    fn shuffle<T>(&mut self, values: &mut [T]) {
+        values.shuffle(&mut self.prng);
+    }
+}
+
+/// The `ATAN2` function.
+pub struct Atan2Function {
+    metadata: CallableMetadata,
+    angle_mode: Rc<RefCell<AngleMode>>,
+}
+
+impl Atan2Function {
+    /// Creates a new instance of the function.
+    pub fn new(angle_mode: Rc<RefCell<AngleMode>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("ATAN2", VarType::Double)
+                .with_syntax("y<%|#>, x<%|#>")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Computes the arc-tangent of y/x using the signs of both to pick the quadrant.
+Unlike ATN, this returns the angle of the point (x, y) in the full (-PI, PI] range and handles \
+x being zero.
+The returned angle is measured in degrees or radians depending on the angle mode as selected by \
+the DEG and RAD commands.",
+                )
+                .build(),
+            angle_mode,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for Atan2Function {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (y, x) = match args.as_slice() {
+            [y, x] => (arg_to_f64(y, &span.args[0])?, arg_to_f64(x, &span.args[1])?),
+            _ => return Err(CallError::SyntaxError),
+        };
+        match *self.angle_mode.borrow() {
+            AngleMode::Degrees => Ok(Value::Double(y.atan2(x).to_degrees())),
+            AngleMode::Radians => Ok(Value::Double(y.atan2(x))),
+        }
+    }
 }
 
 /// The `ATN` function.
@@ -152,6 +253,42 @@ impl Function for AtnFunction {
     }
 }
 
+/// The `CEIL` function.
+pub struct CeilFunction {
+    metadata: CallableMetadata,
+}
+
+impl CeilFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CEIL", VarType::Double)
+                .with_syntax("n<%|#>")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the smallest integral number that is not smaller than n.
+For example, 4.2 becomes 5 and -4.2 becomes -4.  See FLOOR for the opposite operation.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for CeilFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [n] => Ok(Value::Double(arg_to_f64(n, &span.args[0])?.ceil())),
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
 /// The `CINT` function.
 pub struct CintFunction {
     metadata: CallableMetadata,
@@ -197,6 +334,53 @@ impl Function for CintFunction {
     }
 }
 
+/// The `CLAMP` function.
+pub struct ClampFunction {
+    metadata: CallableMetadata,
+}
+
+impl ClampFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CLAMP", VarType::Double)
+                .with_syntax("n<%|#>, lo<%|#>, hi<%|#>")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Restricts a number to the [lo..hi] range.
+Returns lo if n is smaller than lo, hi if n is larger than hi, or n otherwise.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ClampFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (n, lo, hi) = match args.as_slice() {
+            [n, lo, hi] => (
+                arg_to_f64(n, &span.args[0])?,
+                arg_to_f64(lo, &span.args[1])?,
+                arg_to_f64(hi, &span.args[2])?,
+            ),
+            _ => return Err(CallError::SyntaxError),
+        };
+        if lo > hi {
+            return Err(CallError::ArgumentError(
+                span.args[2].start_pos(),
+                "hi cannot be smaller than lo".to_owned(),
+            ));
+        }
+        Ok(Value::Double(n.clamp(lo, hi)))
+    }
+}
+
 /// The `COS` function.
 pub struct CosFunction {
     metadata: CallableMetadata,
@@ -272,6 +456,43 @@ impl Command for DegCommand {
     }
 }
 
+/// The `FLOOR` function.
+pub struct FloorFunction {
+    metadata: CallableMetadata,
+}
+
+impl FloorFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("FLOOR", VarType::Double)
+                .with_syntax("n<%|#>")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the largest integral number that is not larger than n.
+For example, 4.8 becomes 4 and -4.2 becomes -5.  Unlike INT, the result is a double so it can \
+represent numbers that do not fit in an integer.  See CEIL for the opposite operation.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for FloorFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [n] => Ok(Value::Double(arg_to_f64(n, &span.args[0])?.floor())),
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
 /// The `INT` function.
 pub struct IntFunction {
     metadata: CallableMetadata,
@@ -320,6 +541,58 @@ impl Function for IntFunction {
     }
 }
 
+/// The `LOG` function.
+pub struct LogFunction {
+    metadata: CallableMetadata,
+}
+
+impl LogFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LOG", VarType::Double)
+                .with_syntax("n<%|#>[, base<%|#>]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Computes the logarithm of a number.
+If base is not given, computes the natural logarithm of n.  Otherwise, computes the logarithm \
+of n in the given base, so LOG(1000, 10) is 3.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for LogFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (n, base) = match args.as_slice() {
+            [n] => (arg_to_f64(n, &span.args[0])?, None),
+            [n, base] => (arg_to_f64(n, &span.args[0])?, Some(arg_to_f64(base, &span.args[1])?)),
+            _ => return Err(CallError::SyntaxError),
+        };
+        if n <= 0.0 {
+            return Err(CallError::ArgumentError(
+                span.args[0].start_pos(),
+                "Cannot take logarithm of a non-positive number".to_owned(),
+            ));
+        }
+        match base {
+            None => Ok(Value::Double(n.ln())),
+            Some(base) if base <= 0.0 || base == 1.0 => Err(CallError::ArgumentError(
+                span.args[1].start_pos(),
+                "Logarithm base must be positive and not 1".to_owned(),
+            )),
+            Some(base) => Ok(Value::Double(n.log(base))),
+        }
+    }
+}
+
 /// The `MAX` function.
 pub struct MaxFunction {
     metadata: CallableMetadata,
@@ -332,7 +605,11 @@ impl MaxFunction {
             metadata: CallableMetadataBuilder::new("MAX", VarType::Double)
                 .with_syntax("expr<%|#>[, .., expr<%|#>]")
                 .with_category(CATEGORY)
-                .with_description("Returns the maximum number out of a set of numbers.")
+                .with_description(
+                    "Returns the maximum number out of a set of numbers.
+The numbers can be given as separate arguments or as a single reference to an array, as in \
+MAX(a()), in which case all of its elements are considered.",
+                )
                 .build(),
         })
     }
@@ -345,15 +622,8 @@ impl Function for MaxFunction {
     }
 
     async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
-        if span.args.is_empty() {
-            return Err(CallError::SyntaxError);
-        }
-        let args = eval_all(&span.args, symbols).await?;
         let mut max = f64::MIN;
-        for (arg, value) in span.args.iter().zip(args) {
-            let n = value
-                .as_f64()
-                .map_err(|e| CallError::ArgumentError(arg.start_pos(), format!("{}", e)))?;
+        for n in get_number_set(&span.args, symbols).await? {
             if n > max {
                 max = n;
             }
@@ -374,7 +644,11 @@ impl MinFunction {
             metadata: CallableMetadataBuilder::new("MIN", VarType::Double)
                 .with_syntax("expr<%|#>[, .., expr<%|#>]")
                 .with_category(CATEGORY)
-                .with_description("Returns the minimum number out of a set of numbers.")
+                .with_description(
+                    "Returns the minimum number out of a set of numbers.
+The numbers can be given as separate arguments or as a single reference to an array, as in \
+MIN(a()), in which case all of its elements are considered.",
+                )
                 .build(),
         })
     }
@@ -387,15 +661,8 @@ impl Function for MinFunction {
     }
 
     async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
-        if span.args.is_empty() {
-            return Err(CallError::SyntaxError);
-        }
-        let args = eval_all(&span.args, symbols).await?;
         let mut min = f64::MAX;
-        for (arg, value) in span.args.iter().zip(args) {
-            let n = value
-                .as_f64()
-                .map_err(|e| CallError::ArgumentError(arg.start_pos(), format!("{}", e)))?;
+        for n in get_number_set(&span.args, symbols).await? {
             if n < min {
                 min = n;
             }
@@ -491,6 +758,8 @@ impl RandomizeCommand {
                 .with_description(
                     "Reinitializes the pseudo-random number generator.
 If no seed is given, uses system entropy to create a new sequence of random numbers.
+If a seed% is given, the sequence of numbers returned by RND, RNDINT, and SHUFFLE is always the \
+same for the same seed%, which is useful to make the runs of a program reproducible.
 WARNING: These random numbers offer no cryptographic guarantees.",
                 )
                 .build(),
@@ -540,8 +809,8 @@ impl RndFunction {
                     "Returns a random number in the [0..1] range.
 If n% is zero, returns the previously generated random number.  If n% is positive, returns a new \
 random number.
-If you need to generate an integer random number within a specific range, say [0..100], compute it \
-with an expression like CINT%(RND#(1) * 100.0).
+If you need to generate an integer random number within a specific range, say [0..100], use \
+RNDINT%(0, 100) instead.
 WARNING: These random numbers offer no cryptographic guarantees.",
                 )
                 .build(),
@@ -578,6 +847,185 @@ impl Function for RndFunction {
     }
 }
 
+/// The `RNDINT` function.
+pub struct RndIntFunction {
+    metadata: CallableMetadata,
+    prng: Rc<RefCell<Prng>>,
+}
+
+impl RndIntFunction {
+    /// Creates a new instance of the function.
+    pub fn new(prng: Rc<RefCell<Prng>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("RNDINT", VarType::Integer)
+                .with_syntax("lo%, hi%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns a random integer in the [lo%..hi%] range.
+Both lo% and hi% are included in the range of possible values, so RNDINT%(1, 6) simulates the roll \
+of a die.
+WARNING: These random numbers offer no cryptographic guarantees.",
+                )
+                .build(),
+            prng,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for RndIntFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (lo, hi) = match args.as_slice() {
+            [lo, hi] => {
+                let lo = lo.as_i32().map_err(|e| {
+                    CallError::ArgumentError(span.args[0].start_pos(), format!("{}", e))
+                })?;
+                let hi = hi.as_i32().map_err(|e| {
+                    CallError::ArgumentError(span.args[1].start_pos(), format!("{}", e))
+                })?;
+                (lo, hi)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+        if lo > hi {
+            return Err(CallError::ArgumentError(
+                span.args[1].start_pos(),
+                "hi% cannot be smaller than lo%".to_owned(),
+            ));
+        }
+        Ok(Value::Integer(self.prng.borrow_mut().next_int(lo, hi)))
+    }
+}
+
+/// The `ROUND` function.
+pub struct RoundFunction {
+    metadata: CallableMetadata,
+}
+
+impl RoundFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("ROUND", VarType::Double)
+                .with_syntax("n<%|#>[, digits%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Rounds a number to the given number of decimal digits.
+If digits% is not given, rounds n to the closest integral number.  A negative digits% rounds to \
+tens, hundreds, and so on.
+Numbers that are exactly halfway between two candidates are rounded to the even one, so both \
+1.5 and 2.5 become 2.  This avoids the bias that rounding halves away from zero, as CINT does, \
+introduces when adding up many rounded numbers.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for RoundFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (n, digits) = match args.as_slice() {
+            [n] => (arg_to_f64(n, &span.args[0])?, 0),
+            [n, digits] => {
+                let digits = digits.as_i32().map_err(|e| {
+                    CallError::ArgumentError(span.args[1].start_pos(), format!("{}", e))
+                })?;
+                (arg_to_f64(n, &span.args[0])?, digits)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+        let factor = 10f64.powi(digits);
+        Ok(Value::Double(round_half_even(n * factor) / factor))
+    }
+}
+
+/// The `SHUFFLE` command.
+pub struct ShuffleCommand {
+    metadata: CallableMetadata,
+    prng: Rc<RefCell<Prng>>,
+}
+
+impl ShuffleCommand {
+    /// Creates a new instance of the command.
+    pub fn new(prng: Rc<RefCell<Prng>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SHUFFLE", VarType::Void)
+                .with_syntax("array")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Randomly reorders the elements of an array.
+The array must have a single dimension.  For example, to deal the cards of a deck in a random \
+order:
+    DIM deck(52): FOR i = 0 TO 51: deck(i) = i: NEXT: SHUFFLE deck
+WARNING: These random numbers offer no cryptographic guarantees.",
+                )
+                .build(),
+            prng,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ShuffleCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (arrayref, arraypos) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => match expr_to_array_ref(expr) {
+                Some(arrayref) => arrayref,
+                None => return Err(CallError::SyntaxError),
+            },
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let array = match machine
+            .get_mut_symbols()
+            .get_mut(arrayref)
+            .map_err(|e| eval::Error::from_value_error(e, arraypos))?
+        {
+            Some(Symbol::Array(array)) => array,
+            Some(_) => {
+                return Err(CallError::ArgumentError(
+                    arraypos,
+                    format!("{} must be an array reference", arrayref),
+                ))
+            }
+            None => {
+                return Err(CallError::ArgumentError(
+                    arraypos,
+                    format!("{} is not defined", arrayref),
+                ))
+            }
+        };
+        if array.dimensions().len() != 1 {
+            return Err(CallError::ArgumentError(
+                arraypos,
+                "Cannot shuffle multidimensional arrays".to_owned(),
+            ));
+        }
+
+        let mut values = array.values().to_vec();
+        self.prng.borrow_mut().shuffle(&mut values);
+        for (i, value) in values.into_iter().enumerate() {
+            array.assign(&[i as i32], value).expect("Values come from the same array");
+        }
+        Ok(())
+    }
+}
+
 /// The `SIN` function.
 pub struct SinFunction {
     metadata: CallableMetadata,
@@ -698,15 +1146,23 @@ pub fn add_all(machine: &mut Machine) {
     machine.add_clearable(Box::from(ClearableAngleMode { angle_mode: angle_mode.clone() }));
     machine.add_command(RandomizeCommand::new(prng.clone()));
     machine.add_command(DegCommand::new(angle_mode.clone()));
+    machine.add_function(Atan2Function::new(angle_mode.clone()));
     machine.add_function(AtnFunction::new(angle_mode.clone()));
+    machine.add_function(CeilFunction::new());
     machine.add_function(CintFunction::new());
+    machine.add_function(ClampFunction::new());
     machine.add_function(CosFunction::new(angle_mode.clone()));
+    machine.add_function(FloorFunction::new());
     machine.add_function(IntFunction::new());
+    machine.add_function(LogFunction::new());
     machine.add_function(MaxFunction::new());
     machine.add_function(MinFunction::new());
     machine.add_function(PiFunction::new());
     machine.add_command(RadCommand::new(angle_mode.clone()));
-    machine.add_function(RndFunction::new(prng));
+    machine.add_function(RndFunction::new(prng.clone()));
+    machine.add_function(RndIntFunction::new(prng.clone()));
+    machine.add_function(RoundFunction::new());
+    machine.add_command(ShuffleCommand::new(prng));
     machine.add_function(SinFunction::new(angle_mode.clone()));
     machine.add_function(SqrFunction::new());
     machine.add_function(TanFunction::new(angle_mode));
@@ -714,8 +1170,22 @@ pub fn add_all(machine: &mut Machine) {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::testutils::*;
 
+    #[test]
+    fn test_atan2() {
+        check_expr_ok(1f64.atan2(2.0), "ATAN2(1, 2)");
+        check_expr_ok((-1f64).atan2(-2.5), "ATAN2(-1, -2.5)");
+        check_expr_ok(std::f64::consts::FRAC_PI_2, "ATAN2(1, 0)");
+
+        Tester::default().run("DEG: result = ATAN2(-1, 0)").expect_var("result", -90.0).check();
+
+        check_expr_error("1:10: In call to ATAN2: expected y<%|#>, x<%|#>", "ATAN2(1)");
+        check_expr_error("1:10: In call to ATAN2: expected y<%|#>, x<%|#>", "ATAN2(1, 2, 3)");
+        check_expr_error("1:10: In call to ATAN2: 1:19: FALSE is not a number", "ATAN2(1, FALSE)");
+    }
+
     #[test]
     fn test_atn() {
         check_expr_ok(123f64.atan(), "ATN(123)");
@@ -726,6 +1196,18 @@ mod tests {
         check_expr_error("1:10: In call to ATN: expected n<%|#>", "ATN(3, 4)");
     }
 
+    #[test]
+    fn test_ceil() {
+        check_expr_ok(5.0, "CEIL(4.2)");
+        check_expr_ok(-4.0, "CEIL(-4.2)");
+        check_expr_ok(3.0, "CEIL(3)");
+        check_expr_ok(10000000000.0, "CEIL(9999999999.5)");
+
+        check_expr_error("1:10: In call to CEIL: expected n<%|#>", "CEIL()");
+        check_expr_error("1:10: In call to CEIL: expected n<%|#>", "CEIL(1, 2)");
+        check_expr_error("1:10: In call to CEIL: 1:15: FALSE is not a number", "CEIL(FALSE)");
+    }
+
     #[test]
     fn test_cint() {
         check_expr_ok(0, "CINT(0.1)");
@@ -743,6 +1225,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_clamp() {
+        check_expr_ok(5.0, "CLAMP(5, 0, 10)");
+        check_expr_ok(0.0, "CLAMP(-3, 0, 10)");
+        check_expr_ok(10.0, "CLAMP(12.5, 0, 10)");
+        check_expr_ok(1.5, "CLAMP(1, 1.5, 1.5)");
+
+        check_expr_error(
+            "1:10: In call to CLAMP: expected n<%|#>, lo<%|#>, hi<%|#>",
+            "CLAMP(1, 2)",
+        );
+        check_expr_error(
+            "1:10: In call to CLAMP: 1:19: FALSE is not a number",
+            "CLAMP(1, FALSE, 3)",
+        );
+        check_expr_error(
+            "1:10: In call to CLAMP: 1:23: hi cannot be smaller than lo",
+            "CLAMP(1, 10, 9)",
+        );
+    }
+
     #[test]
     fn test_cos() {
         check_expr_ok(123f64.cos(), "COS(123)");
@@ -775,6 +1278,18 @@ mod tests {
         check_stmt_err("1:1: In call to RAD: expected no arguments", "RAD 1");
     }
 
+    #[test]
+    fn test_floor() {
+        check_expr_ok(4.0, "FLOOR(4.8)");
+        check_expr_ok(-5.0, "FLOOR(-4.2)");
+        check_expr_ok(3.0, "FLOOR(3)");
+        check_expr_ok(10000000000.0, "FLOOR(10000000000.5)");
+
+        check_expr_error("1:10: In call to FLOOR: expected n<%|#>", "FLOOR()");
+        check_expr_error("1:10: In call to FLOOR: expected n<%|#>", "FLOOR(1, 2)");
+        check_expr_error("1:10: In call to FLOOR: 1:16: FALSE is not a number", "FLOOR(FALSE)");
+    }
+
     #[test]
     fn test_int() {
         check_expr_ok(0, "INT(0.1)");
@@ -792,6 +1307,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_log() {
+        check_expr_ok(0.0, "LOG(1)");
+        check_expr_ok(10f64.ln(), "LOG(10)");
+        check_expr_ok(1000f64.log(10.0), "LOG(1000, 10)");
+        check_expr_ok(10.0, "LOG(1024, 2)");
+        check_expr_ok(-1.0, "LOG(0.5, 2.0)");
+
+        check_expr_error("1:10: In call to LOG: expected n<%|#>[, base<%|#>]", "LOG()");
+        check_expr_error("1:10: In call to LOG: expected n<%|#>[, base<%|#>]", "LOG(1, 2, 3)");
+        check_expr_error("1:10: In call to LOG: 1:14: FALSE is not a number", "LOG(FALSE)");
+        check_expr_error(
+            "1:10: In call to LOG: 1:14: Cannot take logarithm of a non-positive number",
+            "LOG(0)",
+        );
+        check_expr_error(
+            "1:10: In call to LOG: 1:14: Cannot take logarithm of a non-positive number",
+            "LOG(-1, 10)",
+        );
+        for base in &["0", "-2", "1"] {
+            check_expr_error(
+                "1:10: In call to LOG: 1:17: Logarithm base must be positive and not 1",
+                &format!("LOG(5, {})", base),
+            );
+        }
+    }
+
     #[test]
     fn test_max() {
         check_expr_ok(0.0, "MAX(0)");
@@ -814,6 +1356,34 @@ mod tests {
         check_expr_error("1:10: In call to MAX: 1:14: FALSE is not a number", "MAX(FALSE)");
     }
 
+    #[test]
+    fn test_max_min_array() {
+        for func in &["MAX", "MIN"] {
+            let exp = if *func == "MAX" { 8.5 } else { -2.0 };
+            for arg in &["a", "a()"] {
+                Tester::default()
+                    .run(format!(
+                        "DIM a(2, 2) AS DOUBLE: a(0, 1) = 8.5: a(1, 0) = -2: result = {}({})",
+                        func, arg
+                    ))
+                    .expect_array(
+                        "a",
+                        VarType::Double,
+                        &[2, 2],
+                        vec![(&[0, 1], 8.5.into()), (&[1, 0], (-2.0).into())],
+                    )
+                    .expect_var("result", exp)
+                    .check();
+            }
+
+            Tester::default()
+                .run(format!("DIM a(3) AS STRING: result = {}(a)", func))
+                .expect_err(format!("1:30: In call to {}: 1:34: \"\" is not a number", func))
+                .expect_array("a", VarType::Text, &[3], vec![])
+                .check();
+        }
+    }
+
     #[test]
     fn test_min() {
         check_expr_ok(0.0, "MIN(0)");
@@ -873,6 +1443,93 @@ mod tests {
         check_stmt_err("1:1: In call to RANDOMIZE: 1:11: TRUE is not a number", "RANDOMIZE TRUE");
     }
 
+    #[test]
+    fn test_rndint() {
+        check_expr_ok(3, "RNDINT(3, 3)");
+        check_expr_ok(true, "RNDINT(1, 6) >= 1 AND RNDINT(1, 6) <= 6");
+
+        let mut t = Tester::default();
+        for _ in 0..2 {
+            t.run("RANDOMIZE 10: a = RNDINT(-10, 10): b = RNDINT(-10, 10): c = RNDINT(0, 1000)")
+                .expect_var("a", -6)
+                .expect_var("b", 7)
+                .expect_var("c", 731)
+                .check();
+        }
+
+        check_expr_error("1:10: In call to RNDINT: expected lo%, hi%", "RNDINT(1)");
+        check_expr_error(
+            "1:10: In call to RNDINT: 1:20: FALSE is not a number",
+            "RNDINT(1, FALSE)",
+        );
+        check_expr_error(
+            "1:10: In call to RNDINT: 1:21: hi% cannot be smaller than lo%",
+            "RNDINT(10, 9)",
+        );
+    }
+
+    #[test]
+    fn test_round() {
+        check_expr_ok(4.0, "ROUND(4.4)");
+        check_expr_ok(5.0, "ROUND(4.6)");
+        check_expr_ok(-5.0, "ROUND(-4.6)");
+        check_expr_ok(2.0, "ROUND(1.5)");
+        check_expr_ok(2.0, "ROUND(2.5)");
+        check_expr_ok(-2.0, "ROUND(-2.5)");
+        check_expr_ok(4.0, "ROUND(3.5)");
+        check_expr_ok(7.0, "ROUND(7)");
+
+        check_expr_ok(2.72, "ROUND(2.71828, 2)");
+        check_expr_ok(0.12, "ROUND(0.125, 2)");
+        check_expr_ok(1200.0, "ROUND(1234, -2)");
+        check_expr_ok(2000.0, "ROUND(2500, -3)");
+
+        check_expr_error("1:10: In call to ROUND: expected n<%|#>[, digits%]", "ROUND()");
+        check_expr_error("1:10: In call to ROUND: expected n<%|#>[, digits%]", "ROUND(1, 2, 3)");
+        check_expr_error("1:10: In call to ROUND: 1:16: FALSE is not a number", "ROUND(FALSE)");
+        check_expr_error("1:10: In call to ROUND: 1:19: \"a\" is not a number", "ROUND(1, \"a\")");
+    }
+
+    #[test]
+    fn test_shuffle_ok() {
+        Tester::default()
+            .run("DIM a(1) AS STRING: a(0) = \"x\": SHUFFLE a")
+            .expect_array("a", VarType::Text, &[1], vec![(&[0], "x".into())])
+            .check();
+
+        for _ in 0..2 {
+            Tester::default()
+                .run(
+                    "RANDOMIZE 5: DIM a(5): a(0) = 10: a(1) = 20: a(2) = 30: a(3) = 40: a(4) = 50
+                    SHUFFLE a()",
+                )
+                .expect_array_simple(
+                    "a",
+                    VarType::Integer,
+                    vec![10.into(), 30.into(), 50.into(), 20.into(), 40.into()],
+                )
+                .check();
+        }
+    }
+
+    #[test]
+    fn test_shuffle_errors() {
+        check_stmt_err("1:1: In call to SHUFFLE: expected array", "SHUFFLE");
+        check_stmt_err("1:1: In call to SHUFFLE: expected array", "SHUFFLE 3");
+        check_stmt_err("1:1: In call to SHUFFLE: expected array", "SHUFFLE a, a");
+        check_stmt_err("1:1: In call to SHUFFLE: 1:9: a is not defined", "SHUFFLE a");
+        Tester::default()
+            .run("a = 3: SHUFFLE a")
+            .expect_err("1:8: In call to SHUFFLE: 1:16: a must be an array reference")
+            .expect_var("a", 3)
+            .check();
+        Tester::default()
+            .run("DIM a(3, 2): SHUFFLE a")
+            .expect_err("1:14: In call to SHUFFLE: 1:22: Cannot shuffle multidimensional arrays")
+            .expect_array("a", VarType::Integer, &[3, 2], vec![])
+            .check();
+    }
+
     #[test]
     fn test_sin() {
         check_expr_ok(123f64.sin(), "SIN(123)");
@@ -0,0 +1,279 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Support for plugins that extend the interpreter with third-party commands and functions.
+
+use crate::console::Console;
+use crate::exec::CATEGORY;
+use async_trait::async_trait;
+use endbasic_core::ast::{BuiltinCallSpan, VarType};
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult,
+};
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::HashSet;
+use std::io;
+use std::rc::Rc;
+
+/// Interface for third-party crates that contribute a set of commands and functions.
+///
+/// Plugins are registered when constructing the machine via `MachineBuilder::with_plugin`, which
+/// allows custom builds of the interpreter to pick the integrations they need without the
+/// standard library having to know about them.
+pub trait Plugin {
+    /// Returns the name of the plugin, which must be unique.
+    fn name(&self) -> &'static str;
+
+    /// Returns the version of the plugin.
+    fn version(&self) -> &'static str;
+
+    /// Returns a short, single-line description of the plugin.
+    fn description(&self) -> &'static str;
+
+    /// Registers the plugin's commands and functions into the `machine`.  Their names must not
+    /// clash with those of any other callable.
+    fn add_all(&self, machine: &mut Machine);
+}
+
+/// Details about a plugin that was loaded into a machine.
+struct LoadedPlugin {
+    plugin: Rc<dyn Plugin>,
+
+    /// Names of the callables that the plugin registered, sorted alphabetically.
+    callables: Vec<String>,
+}
+
+/// The `PLUGINS` command.
+pub struct PluginsCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    plugins: Vec<LoadedPlugin>,
+}
+
+impl PluginsCommand {
+    /// Creates a new `PLUGINS` command that lists the loaded `plugins` on the `console`.
+    fn new(console: Rc<RefCell<dyn Console>>, plugins: Vec<LoadedPlugin>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("PLUGINS", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Lists the loaded plugins.
+Plugins extend the interpreter with commands and functions that are not part of the standard \
+library.  They are included when the interpreter is built, so the set of available plugins \
+depends on the build you are using.
+For every plugin, this shows its name, version, and description, as well as the commands and \
+functions it provides, which you can learn more about with HELP.",
+                )
+                .build(),
+            console,
+            plugins,
+        })
+    }
+
+    /// Prints the table of loaded plugins.
+    fn show(&self, console: &mut dyn Console) -> io::Result<()> {
+        let name_width =
+            self.plugins.iter().fold("Name".len(), |max, p| cmp::max(max, p.plugin.name().len()));
+        let version_width = self
+            .plugins
+            .iter()
+            .fold("Version".len(), |max, p| cmp::max(max, p.plugin.version().len()));
+
+        console.print("")?;
+        console.print(&format!(
+            "    {:name_width$}    {:version_width$}    Description",
+            "Name",
+            "Version",
+            name_width = name_width,
+            version_width = version_width,
+        ))?;
+        for loaded in &self.plugins {
+            console.print(&format!(
+                "    {:name_width$}    {:version_width$}    {}",
+                loaded.plugin.name(),
+                loaded.plugin.version(),
+                loaded.plugin.description(),
+                name_width = name_width,
+                version_width = version_width,
+            ))?;
+            if !loaded.callables.is_empty() {
+                console.print(&format!("        Provides: {}", loaded.callables.join(", ")))?;
+            }
+        }
+        console.print("")?;
+        console.print(&format!("    {} plugin(s)", self.plugins.len()))?;
+        console.print("")?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for PluginsCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, _machine: &mut Machine) -> CommandResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        self.show(&mut *self.console.borrow_mut())?;
+        Ok(())
+    }
+}
+
+/// Adds the callables of all `plugins` to the `machine` and the `PLUGINS` command to list them on
+/// the `console`.
+///
+/// Panics if two plugins have the same name or if their callables clash with existing ones.
+pub fn add_all(
+    machine: &mut Machine,
+    console: Rc<RefCell<dyn Console>>,
+    plugins: Vec<Rc<dyn Plugin>>,
+) {
+    let mut loaded = Vec::with_capacity(plugins.len());
+    let mut names = HashSet::new();
+    for plugin in plugins {
+        assert!(names.insert(plugin.name()), "Duplicate plugin {}", plugin.name());
+
+        let before: HashSet<String> = machine.get_symbols().as_hashmap().keys().cloned().collect();
+        plugin.add_all(machine);
+        let mut callables: Vec<String> = machine
+            .get_symbols()
+            .as_hashmap()
+            .keys()
+            .filter(|name| !before.contains(*name))
+            .cloned()
+            .collect();
+        callables.sort();
+
+        loaded.push(LoadedPlugin { plugin, callables });
+    }
+    machine.add_command(PluginsCommand::new(console, loaded));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+    use endbasic_core::ast::Value;
+    use endbasic_core::syms::{ArgSpecBuilder, ClosureFunction};
+
+    /// A plugin that registers the given test callables.
+    struct TestPlugin {
+        name: &'static str,
+        callables: Vec<&'static str>,
+    }
+
+    impl Plugin for TestPlugin {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn version(&self) -> &'static str {
+            "1.2.3"
+        }
+
+        fn description(&self) -> &'static str {
+            "A test plugin"
+        }
+
+        fn add_all(&self, machine: &mut Machine) {
+            for callable in &self.callables {
+                let metadata = CallableMetadataBuilder::new(callable, VarType::Integer)
+                    .with_syntax("a%, b%")
+                    .with_category("Testing")
+                    .with_description("Test function.")
+                    .build();
+                machine.add_function(ClosureFunction::new(
+                    metadata,
+                    ArgSpecBuilder::new()
+                        .required("a%", VarType::Integer)
+                        .required("b%", VarType::Integer)
+                        .build(),
+                    |args, _symbols| Ok(Value::Integer(args.get::<i32>(0) + args.get::<i32>(1))),
+                ));
+            }
+        }
+    }
+
+    /// Creates a tester whose machine has the given `plugins`.
+    fn tester_with_plugins(plugins: Vec<Rc<dyn Plugin>>) -> Tester {
+        let mut t = Tester::empty();
+        let console = t.get_console();
+        add_all(t.get_machine(), console, plugins);
+        t
+    }
+
+    #[test]
+    fn test_plugins_none() {
+        Tester::default()
+            .run("PLUGINS")
+            .expect_prints(["", "    Name    Version    Description", "", "    0 plugin(s)", ""])
+            .check();
+    }
+
+    #[test]
+    fn test_plugins_some() {
+        let mut t = tester_with_plugins(vec![
+            Rc::from(TestPlugin { name: "first", callables: vec!["SUM", "ADD"] }),
+            Rc::from(TestPlugin { name: "a-longer-name", callables: vec![] }),
+        ]);
+        t.run("PLUGINS")
+            .expect_prints([
+                "",
+                "    Name             Version    Description",
+                "    first            1.2.3      A test plugin",
+                "        Provides: ADD, SUM",
+                "    a-longer-name    1.2.3      A test plugin",
+                "",
+                "    2 plugin(s)",
+                "",
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_plugins_callables() {
+        let mut t = tester_with_plugins(vec![Rc::from(TestPlugin {
+            name: "first",
+            callables: vec!["SUM"],
+        })]);
+        t.run("a = SUM(1, 2)").expect_var("a", 3).check();
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate plugin dup")]
+    fn test_plugins_duplicate() {
+        let mut t = Tester::empty();
+        let console = t.get_console();
+        add_all(
+            t.get_machine(),
+            console,
+            vec![
+                Rc::from(TestPlugin { name: "dup", callables: vec![] }),
+                Rc::from(TestPlugin { name: "dup", callables: vec![] }),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_plugins_errors() {
+        check_stmt_err("1:1: In call to PLUGINS: expected no arguments", "PLUGINS 1");
+    }
+}
@@ -0,0 +1,420 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Access to the environment of the process that runs the interpreter.
+
+use async_trait::async_trait;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, FunctionCallSpan, Value, VarType};
+use endbasic_core::eval::eval_all;
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
+    FunctionResult, Symbols,
+};
+use endbasic_core::LineCol;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::rc::Rc;
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Process environment
+These functions and commands give programs access to the environment in which the interpreter \
+runs, which allows them to pick up configuration without hardcoding it.
+When EndBASIC runs from the command line, environment variables are those of the interpreter's \
+process.  On platforms without environment variables, such as the web interface, programs start \
+with an empty set of variables that only lives as long as the interpreter.
+When a program is run from the command line as in \"endbasic program.bas arg1 arg2\", the \
+arguments given after the program name can be queried with COMMANDC% and COMMAND$.  Arguments that \
+look like options must be preceded by -- so that the interpreter does not try to handle them.";
+
+/// Abstract operations to access environment variables.
+pub trait Environment {
+    /// Returns the value of the variable `name`, if set.
+    fn get(&self, name: &str) -> Option<String>;
+
+    /// Sets the variable `name` to `value`.
+    fn set(&mut self, name: &str, value: &str);
+}
+
+/// Environment backed by the variables of the current process.
+#[derive(Default)]
+pub struct SystemEnvironment {}
+
+impl Environment for SystemEnvironment {
+    fn get(&self, name: &str) -> Option<String> {
+        env::var_os(name).map(|value| value.to_string_lossy().into_owned())
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        env::set_var(name, value);
+    }
+}
+
+/// Environment that lives in memory only.
+#[derive(Default)]
+pub struct VirtualEnvironment {
+    vars: HashMap<String, String>,
+}
+
+impl Environment for VirtualEnvironment {
+    fn get(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned()
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        self.vars.insert(name.to_owned(), value.to_owned());
+    }
+}
+
+/// Arguments given to the program, where the first one is the name of the program itself.
+pub type ProgramArgs = Vec<String>;
+
+/// Validates that `name`, provided at `pos`, is a valid environment variable name.
+fn check_var_name(name: &str, pos: LineCol) -> Result<(), CallError> {
+    if name.is_empty() || name.contains('=') || name.contains('\0') {
+        return Err(CallError::ArgumentError(
+            pos,
+            format!("Invalid environment variable name \"{}\"", name),
+        ));
+    }
+    Ok(())
+}
+
+/// The `COMMAND` function.
+pub struct CommandFunction {
+    metadata: CallableMetadata,
+    args: Rc<ProgramArgs>,
+}
+
+impl CommandFunction {
+    /// Creates a new instance of the function.
+    pub fn new(args: Rc<ProgramArgs>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("COMMAND", VarType::Text)
+                .with_syntax("n%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns an argument given to the program.
+n% must be between 1 and COMMANDC%, both included, to fetch the arguments in the order in which \
+they were given.  If n% is 0, returns the name of the program, or an empty string if there is no \
+program (as is the case when using the interpreter interactively).",
+                )
+                .build(),
+            args,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for CommandFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Integer(n)] => {
+                if *n == 0 && self.args.is_empty() {
+                    return Ok(Value::Text("".to_owned()));
+                }
+                if *n < 0 || (*n as usize) >= self.args.len() {
+                    return Err(CallError::ArgumentError(
+                        span.args[0].start_pos(),
+                        format!("Argument {} out of range", n),
+                    ));
+                }
+                Ok(Value::Text(self.args[*n as usize].clone()))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `COMMANDC` function.
+pub struct CommandcFunction {
+    metadata: CallableMetadata,
+    args: Rc<ProgramArgs>,
+}
+
+impl CommandcFunction {
+    /// Creates a new instance of the function.
+    pub fn new(args: Rc<ProgramArgs>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("COMMANDC", VarType::Integer)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the number of arguments given to the program.
+The name of the program is not included in this count.",
+                )
+                .build(),
+            args,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for CommandcFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, _symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        Ok(Value::Integer(self.args.len().saturating_sub(1) as i32))
+    }
+}
+
+/// The `GETENV` function.
+pub struct GetenvFunction {
+    metadata: CallableMetadata,
+    env: Rc<RefCell<dyn Environment>>,
+}
+
+impl GetenvFunction {
+    /// Creates a new instance of the function.
+    pub fn new(env: Rc<RefCell<dyn Environment>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("GETENV", VarType::Text)
+                .with_syntax("name$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the value of an environment variable.
+Returns an empty string if the variable is not set.",
+                )
+                .build(),
+            env,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for GetenvFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(name)] => {
+                check_var_name(name, span.args[0].start_pos())?;
+                Ok(Value::Text(self.env.borrow().get(name).unwrap_or_default()))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `SETENV` command.
+pub struct SetenvCommand {
+    metadata: CallableMetadata,
+    env: Rc<RefCell<dyn Environment>>,
+}
+
+impl SetenvCommand {
+    /// Creates a new instance of the command.
+    pub fn new(env: Rc<RefCell<dyn Environment>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SETENV", VarType::Void)
+                .with_syntax("name$, value$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sets the value of an environment variable.
+The new value is visible to GETENV$ right away.  When running from the command line, the new \
+value is also visible to any other program that the interpreter may start.",
+                )
+                .build(),
+            env,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SetenvCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (name_expr, value_expr) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(name), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(value), sep: ArgSep::End, .. }] => {
+                (name, value)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let name = match name_expr.eval(machine.get_mut_symbols()).await? {
+            Value::Text(name) => name,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    name_expr.start_pos(),
+                    "SETENV requires a string as the variable name".to_owned(),
+                ))
+            }
+        };
+        check_var_name(&name, name_expr.start_pos())?;
+
+        let value = match value_expr.eval(machine.get_mut_symbols()).await? {
+            Value::Text(value) if value.contains('\0') => {
+                return Err(CallError::ArgumentError(
+                    value_expr.start_pos(),
+                    "Environment variable values cannot contain NUL characters".to_owned(),
+                ))
+            }
+            Value::Text(value) => value,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    value_expr.start_pos(),
+                    "SETENV requires a string as the value".to_owned(),
+                ))
+            }
+        };
+
+        self.env.borrow_mut().set(&name, &value);
+        Ok(())
+    }
+}
+
+/// Adds all symbols provided by this module to the given `machine`.
+pub fn add_all(machine: &mut Machine, env: Rc<RefCell<dyn Environment>>, args: ProgramArgs) {
+    let args: Rc<ProgramArgs> = Rc::from(args);
+    machine.add_function(CommandFunction::new(args.clone()));
+    machine.add_function(CommandcFunction::new(args));
+    machine.add_function(GetenvFunction::new(env.clone()));
+    machine.add_command(SetenvCommand::new(env));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    /// Creates a tester with the given program `args`.
+    fn tester(args: &[&str]) -> Tester {
+        let mut tester = Tester::empty();
+        let env = Rc::from(RefCell::from(VirtualEnvironment::default()));
+        add_all(tester.get_machine(), env, args.iter().map(|s| (*s).to_owned()).collect());
+        tester
+    }
+
+    #[test]
+    fn test_command() {
+        tester(&["prog.bas", "first", "", "third"])
+            .run(
+                "n = COMMANDC: a$ = COMMAND(0): b$ = COMMAND$(1): c$ = COMMAND(2): d$ = COMMAND(3)",
+            )
+            .expect_var("n", 3)
+            .expect_var("a", "prog.bas")
+            .expect_var("b", "first")
+            .expect_var("c", "")
+            .expect_var("d", "third")
+            .check();
+
+        tester(&["prog.bas"])
+            .run("n = COMMANDC%: a$ = COMMAND(0)")
+            .expect_var("n", 0)
+            .expect_var("a", "prog.bas")
+            .check();
+
+        Tester::default()
+            .run("n = COMMANDC: a$ = COMMAND(0)")
+            .expect_var("n", 0)
+            .expect_var("a", "")
+            .check();
+    }
+
+    #[test]
+    fn test_command_errors() {
+        check_expr_error("1:10: In call to COMMAND: expected n%", "COMMAND()");
+        check_expr_error("1:10: In call to COMMAND: expected n%", r#"COMMAND("1")"#);
+        check_expr_error("1:10: In call to COMMAND: 1:18: Argument 1 out of range", "COMMAND(1)");
+
+        tester(&["prog.bas", "first"])
+            .run("a$ = COMMAND(-1)")
+            .expect_err("1:6: In call to COMMAND: 1:14: Argument -1 out of range")
+            .check();
+        tester(&["prog.bas", "first"])
+            .run("a$ = COMMAND(2)")
+            .expect_err("1:6: In call to COMMAND: 1:14: Argument 2 out of range")
+            .check();
+
+        check_expr_error(
+            "1:10: In call to COMMANDC: expected no arguments nor parenthesis",
+            "COMMANDC()",
+        );
+    }
+
+    #[test]
+    fn test_getenv_setenv() {
+        Tester::default()
+            .run(r#"a$ = GETENV("FOO"): SETENV "FOO", "bar": b$ = GETENV$("FOO")"#)
+            .expect_var("a", "")
+            .expect_var("b", "bar")
+            .check();
+
+        Tester::default()
+            .run(r#"SETENV "FOO", "bar": SETENV "FOO", "": a$ = GETENV("FOO")"#)
+            .expect_var("a", "")
+            .check();
+    }
+
+    #[test]
+    fn test_getenv_errors() {
+        check_expr_error("1:10: In call to GETENV: expected name$", "GETENV()");
+        check_expr_error("1:10: In call to GETENV: expected name$", "GETENV(3)");
+        check_expr_error(
+            "1:10: In call to GETENV: 1:17: Invalid environment variable name \"\"",
+            r#"GETENV("")"#,
+        );
+        check_expr_error(
+            "1:10: In call to GETENV: 1:17: Invalid environment variable name \"a=b\"",
+            r#"GETENV("a=b")"#,
+        );
+    }
+
+    #[test]
+    fn test_setenv_errors() {
+        check_stmt_err("1:1: In call to SETENV: expected name$, value$", "SETENV");
+        check_stmt_err("1:1: In call to SETENV: expected name$, value$", r#"SETENV "a""#);
+        check_stmt_err("1:1: In call to SETENV: expected name$, value$", r#"SETENV "a"; "b""#);
+        check_stmt_err(
+            "1:1: In call to SETENV: 1:8: SETENV requires a string as the variable name",
+            r#"SETENV 3, "b""#,
+        );
+        check_stmt_err(
+            "1:1: In call to SETENV: 1:13: SETENV requires a string as the value",
+            r#"SETENV "a", 3"#,
+        );
+        check_stmt_err(
+            "1:1: In call to SETENV: 1:8: Invalid environment variable name \"\"",
+            r#"SETENV "", "b""#,
+        );
+    }
+
+    #[test]
+    fn test_system_environment() {
+        let name = "ENDBASIC_PROCESS_TEST_VAR";
+        let mut env = SystemEnvironment::default();
+        assert_eq!(None, env.get(name));
+        env.set(name, "some value");
+        assert_eq!(Some("some value".to_owned()), env.get(name));
+        assert_eq!("some value", env::var(name).unwrap());
+    }
+}
@@ -15,22 +15,28 @@
 
 //! Stored program manipulation.
 
-use crate::console::{read_line, Console};
+use crate::console::{read_line, Console, Pager};
+use crate::debug::eval_line;
 use crate::storage::Storage;
 use async_trait::async_trait;
-use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Value, VarType};
-use endbasic_core::exec::{Machine, StopReason};
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Expr, Value, VarRef, VarType};
+use endbasic_core::eval;
+use endbasic_core::exec::{Clearable, Machine, StopReason};
+use endbasic_core::format::format_program;
 use endbasic_core::syms::{
-    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult,
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Symbol, Symbols,
 };
+use endbasic_core::{find_includes, LineCol};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::io;
+use std::mem;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::str;
 
 /// Category description for all symbols provided by this module.
-const CATEGORY: &str = "Stored program
+pub const CATEGORY: &str = "Stored program
 The EndBASIC interpreter has a piece of read/write memory called the \"stored program\".  This \
 memory serves to maintain the code of a program you edit and manipulate right from the \
 interpreter.
@@ -46,6 +52,32 @@ from.";
 /// Message to print on the console when receiving a break signal.
 pub const BREAK_MSG: &str = "**** BREAK ****";
 
+/// Prints a message on the `console` describing why the execution of the `program` stopped, if
+/// the `stop_reason` is worth reporting.
+pub(crate) fn report_stop_reason(
+    console: &mut dyn Console,
+    program: &str,
+    stop_reason: StopReason,
+) -> io::Result<()> {
+    match stop_reason {
+        StopReason::Break => console.print(BREAK_MSG),
+        StopReason::LimitExceeded(limit) => {
+            console.print(&format!("**** Program stopped due to {} limit ****", limit))
+        }
+        StopReason::Paused(pos) => {
+            let line = program.lines().nth(pos.line - 1).unwrap_or("").trim();
+            console.print(&format!("Paused at line {}: {}", pos.line, line))
+        }
+        stop_reason => {
+            if stop_reason.as_exit_code() != 0 {
+                console
+                    .print(&format!("Program exited with code {}", stop_reason.as_exit_code()))?;
+            }
+            Ok(())
+        }
+    }
+}
+
 /// Representation of the single program that we can keep in memory.
 #[async_trait(?Send)]
 pub trait Program {
@@ -66,6 +98,10 @@ pub trait Program {
     /// Resets the name of the program.  Used when saving it.
     fn set_name(&mut self, name: &str);
 
+    /// Replaces the contents of the stored program with the given `text`, keeping its name and
+    /// marking it as modified.
+    fn set_text(&mut self, text: &str);
+
     /// Gets the contents of the stored program as a single string.
     fn text(&self) -> String;
 }
@@ -100,6 +136,10 @@ impl Program for ImmutableProgram {
         self.name = Some(name.to_owned());
     }
 
+    fn set_text(&mut self, text: &str) {
+        self.text = text.to_owned();
+    }
+
     fn text(&self) -> String {
         self.text.clone()
     }
@@ -129,6 +169,60 @@ fn add_extension<S: Into<PathBuf>>(path: S) -> io::Result<String> {
     Ok(path.to_str().expect("Path came from a String").to_owned())
 }
 
+/// Files referenced by the `'#INCLUDE` directives of a program, loaded ahead of its execution.
+///
+/// Storage access is asynchronous but parsing is not, so all the files that a program may need are
+/// fetched before the program is handed to the parser.
+struct Includes(HashMap<String, io::Result<(String, String)>>);
+
+impl Includes {
+    /// Loads all files transitively referenced by the directives in `text` from `storage`.
+    ///
+    /// Paths without a drive are looked up on the drive of `base`, which is the name of the
+    /// including program, or in the current directory if the program has no name.
+    async fn load(storage: &Storage, base: Option<&str>, text: &str) -> Self {
+        let drive = base.and_then(|base| base.split_once(':')).map(|(drive, _)| drive);
+
+        let mut files = HashMap::default();
+        let mut pending = find_includes(text);
+        while let Some(path) = pending.pop() {
+            if files.contains_key(&path) {
+                continue;
+            }
+
+            let result = match add_extension(path.as_str()) {
+                Ok(location) => {
+                    let location = match drive {
+                        Some(drive) if !location.contains(':') => {
+                            format!("{}:{}", drive, location)
+                        }
+                        _ => location,
+                    };
+                    match storage.make_canonical(&location) {
+                        Ok(name) => storage.get(&location).await.map(|content| (name, content)),
+                        Err(e) => Err(e),
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            if let Ok((_, content)) = &result {
+                pending.extend(find_includes(content));
+            }
+            files.insert(path, result);
+        }
+        Self(files)
+    }
+
+    /// Returns the canonical name and the contents of the file referenced as `path`.
+    fn resolve(&self, path: &str) -> io::Result<(String, String)> {
+        match self.0.get(path) {
+            Some(Ok(file)) => Ok(file.clone()),
+            Some(Err(e)) => Err(io::Error::new(e.kind(), e.to_string())),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "File was not preloaded")),
+        }
+    }
+}
+
 /// If the `program` is dirty, asks if it's OK to continue on `console` and discard its changes.
 pub async fn continue_if_modified(
     program: &dyn Program,
@@ -149,6 +243,53 @@ pub async fn continue_if_modified(
     }
 }
 
+/// The `INDENT` command.
+pub struct IndentCommand {
+    metadata: CallableMetadata,
+    program: Rc<RefCell<dyn Program>>,
+}
+
+impl IndentCommand {
+    /// Creates a new `INDENT` command that reformats the stored `program`.
+    pub fn new(program: Rc<RefCell<dyn Program>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("INDENT", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Reformats the stored program.
+Rewrites the stored program with consistent indentation, spacing, and keyword casing.  Comments \
+and blank lines separating statements are preserved, but runs of blank lines are collapsed into \
+a single one.
+The program must be free of syntax errors for this to work; otherwise, this reports the first \
+error and leaves the program untouched.",
+                )
+                .build(),
+            program,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for IndentCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, _machine: &mut Machine) -> CommandResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let mut program = self.program.borrow_mut();
+        let text = program.text();
+        let formatted = format_program(&text).map_err(|e| CallError::NestedError(e.to_string()))?;
+        if formatted != text {
+            program.set_text(&formatted);
+        }
+        Ok(())
+    }
+}
+
 /// The `KILL` command.
 // TODO(jmmv): This should be in the storage module because it isn't really tied to the stored
 // program.  However, this currently relies on the automatic addition of extensions to file names,
@@ -169,6 +310,8 @@ impl KillCommand {
                     "Deletes the given program.
 The filename must be a string and must be a valid EndBASIC path.  The .BAS extension is optional \
 but, if present, it must be .BAS.
+The filename may contain the * and ? wildcards to delete all matching programs at once, as in \
+KILL \"TEMP*\".
 See the \"File system\" help topic for information on the path syntax.",
                 )
                 .build(),
@@ -191,7 +334,10 @@ impl Command for KillCommand {
         match arg0.eval(machine.get_mut_symbols()).await? {
             Value::Text(t) => {
                 let name = add_extension(t)?;
-                self.storage.borrow_mut().delete(&name).await?;
+                let paths = self.storage.borrow().glob(&name).await?;
+                for path in paths {
+                    self.storage.borrow_mut().delete(&path).await?;
+                }
             }
             _ => {
                 return Err(CallError::ArgumentError(
@@ -204,6 +350,187 @@ impl Command for KillCommand {
     }
 }
 
+/// Variables marked by `COMMON` to survive a `CHAIN`, along with the position of their mention.
+type CommonVars = Rc<RefCell<Vec<(VarRef, LineCol)>>>;
+
+/// Forgets the variables marked by `COMMON` when the machine is cleared.
+struct ClearableCommon(CommonVars);
+
+impl Clearable for ClearableCommon {
+    fn reset_state(&self, _syms: &mut Symbols) {
+        self.0.borrow_mut().clear();
+    }
+}
+
+/// The `CHAIN` command.
+pub struct ChainCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+    common: CommonVars,
+}
+
+impl ChainCommand {
+    /// Creates a new `CHAIN` command that runs programs from `storage` and that preserves the
+    /// `common` variables across the transition.
+    fn new(storage: Rc<RefCell<Storage>>, common: CommonVars) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CHAIN", VarType::Void)
+                .with_syntax("filename$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Replaces the running program with another one and runs it.
+The filename must be a string and must be a valid EndBASIC path.  The .BAS extension is optional \
+but, if present, it must be .BAS.
+The new program starts from its beginning in a clean machine, as if RUN had been issued, except \
+for the variables and arrays marked with COMMON, which keep their values.  Execution does not \
+come back to the calling program once the new one finishes.
+The stored program is not modified, so LIST and EDIT keep operating on the original program.
+See the \"File system\" help topic for information on the path syntax.",
+                )
+                .build(),
+            storage,
+            common,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for ChainCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (name, name_pos) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => {
+                match expr.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(t) => (add_extension(t)?, expr.start_pos()),
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            expr.start_pos(),
+                            "CHAIN requires a string as the filename".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let content = self.storage.borrow().get(&name).await?;
+        let full_name = self.storage.borrow().make_canonical(&name)?;
+        let includes = Includes::load(&self.storage.borrow(), Some(&full_name), &content).await;
+        if let Err(e) = machine.chain(&content, &|path| includes.resolve(path)) {
+            return Err(CallError::ArgumentError(name_pos, format!("Cannot load {}: {}", name, e)));
+        }
+
+        let mut saved = vec![];
+        for (vref, pos) in mem::take(&mut *self.common.borrow_mut()) {
+            let symbols = machine.get_symbols();
+            match symbols.get(&vref).map_err(|e| eval::Error::from_value_error(e, pos))? {
+                Some(Symbol::Array(array)) => {
+                    saved.push((vref.name().to_owned(), Symbol::Array(array.clone())))
+                }
+                Some(Symbol::Variable(value)) => {
+                    saved.push((vref.name().to_owned(), Symbol::Variable(value.clone())))
+                }
+                Some(_) => {
+                    return Err(CallError::ArgumentError(
+                        pos,
+                        format!("{} is not a variable or an array", vref.name()),
+                    ))
+                }
+                None => (),
+            }
+        }
+
+        machine.clear();
+        let symbols = machine.get_mut_symbols();
+        for (name, symbol) in saved {
+            match symbol {
+                Symbol::Array(array) => {
+                    symbols
+                        .dim_array(&name, array.subtype(), array.dimensions().to_owned())
+                        .expect("Symbols were just cleared");
+                    let vref = VarRef::new(name, VarType::Auto);
+                    let slot = symbols.get_mut(&vref).expect("Array was just defined");
+                    *slot.expect("Array was just defined") = Symbol::Array(array);
+                }
+                Symbol::Variable(value) => symbols
+                    .set_var(&VarRef::new(name, VarType::Auto), value)
+                    .expect("Symbols were just cleared"),
+                _ => unreachable!("Only variables and arrays are saved"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The `COMMON` command.
+pub struct CommonCommand {
+    metadata: CallableMetadata,
+    common: CommonVars,
+}
+
+impl CommonCommand {
+    /// Creates a new `COMMON` command that marks variables in `common` to survive a `CHAIN`.
+    fn new(common: CommonVars) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("COMMON", VarType::Void)
+                .with_syntax("vref1[, .., vrefN]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Marks variables and arrays to be preserved by CHAIN.
+Each vref can name a variable or an array.  The values are captured when CHAIN runs, not when \
+COMMON runs, so COMMON can be issued before the variables are defined.  Names that are still \
+undefined when CHAIN runs are ignored.
+The list of marked names is forgotten after every CHAIN and whenever the machine is cleared, so \
+a chained program must issue COMMON again to pass values along to the next one.",
+                )
+                .build(),
+            common,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for CommonCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, _machine: &mut Machine) -> CommandResult {
+        if span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+
+        let mut vrefs = Vec::with_capacity(span.args.len());
+        for arg in &span.args {
+            match (arg.expr.as_ref(), arg.sep) {
+                (Some(Expr::Symbol(symspan)), ArgSep::Long | ArgSep::End) => {
+                    vrefs.push((symspan.vref.clone(), symspan.pos))
+                }
+                (Some(Expr::Symbol(_)), _) => return Err(CallError::SyntaxError),
+                (Some(expr), _) => {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "COMMON requires variable or array names".to_owned(),
+                    ))
+                }
+                (None, _) => return Err(CallError::SyntaxError),
+            }
+        }
+
+        let mut common = self.common.borrow_mut();
+        for (vref, pos) in vrefs {
+            if !common.iter().any(|(other, _)| other.name().eq_ignore_ascii_case(vref.name())) {
+                common.push((vref, pos));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// The `EDIT` command.
 pub struct EditCommand {
     metadata: CallableMetadata,
@@ -218,7 +545,15 @@ impl EditCommand {
             metadata: CallableMetadataBuilder::new("EDIT", VarType::Void)
                 .with_syntax("")
                 .with_category(CATEGORY)
-                .with_description("Interactively edits the stored program.")
+                .with_description(
+                    "Interactively edits the stored program.
+In addition to the cursor keys, the editor recognizes these key bindings: Ctrl+F searches for \
+text, Ctrl+G goes to a line number, Ctrl+K starts or cancels a block selection, Ctrl+X cuts the \
+selection (or the current line if there is no selection), Ctrl+Y copies the selection (or the \
+current line), Ctrl+V pastes the most recently cut or copied text, and ESC exits the editor.
+Cut and copied text is shared with the system clipboard when the console supports it, so Ctrl+V \
+also pastes text copied in other applications.  See CLIPBOARD_GET$ for details.",
+                )
                 .build(),
             console,
             program,
@@ -256,9 +591,18 @@ impl ListCommand {
     pub fn new(console: Rc<RefCell<dyn Console>>, program: Rc<RefCell<dyn Program>>) -> Rc<Self> {
         Rc::from(Self {
             metadata: CallableMetadataBuilder::new("LIST", VarType::Void)
-                .with_syntax("")
+                .with_syntax("[line%|first%-last%|-last%]")
+                .with_syntactic_args()
                 .with_category(CATEGORY)
-                .with_description("Prints the currently-loaded program.")
+                .with_description(
+                    "Prints the currently-loaded program.
+Without arguments, prints the whole program.  With a single line% argument, prints only that \
+line.  With a first%-last% range, prints the lines between first% and last%, both inclusive, \
+and with -last%, prints the lines from the beginning of the program until last%.  Lines are \
+numbered starting at 1.
+The code is printed with syntax highlighting.  If the program does not fit on the screen, the \
+output pauses after every screenful: press any key to continue or press ESC or Q to stop.",
+                )
                 .build(),
             console,
             program,
@@ -272,13 +616,42 @@ impl Command for ListCommand {
         &self.metadata
     }
 
-    async fn exec(&self, span: &BuiltinCallSpan, _machine: &mut Machine) -> CommandResult {
-        if !span.args.is_empty() {
-            return Err(CallError::SyntaxError);
-        }
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (first, last) = match span.args.as_slice() {
+            [] => (1, None),
+            [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => match expr {
+                Expr::Subtract(span) => {
+                    let first = eval_line(&span.lhs, machine).await?;
+                    let last = eval_line(&span.rhs, machine).await?;
+                    if last < first {
+                        return Err(CallError::ArgumentError(
+                            span.rhs.start_pos(),
+                            "Last line must not be before first line".to_owned(),
+                        ));
+                    }
+                    (first, Some(last))
+                }
+                Expr::Negate(span) => (1, Some(eval_line(&span.expr, machine).await?)),
+                expr => {
+                    let line = eval_line(expr, machine).await?;
+                    (line, Some(line))
+                }
+            },
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let text = self.program.borrow().text();
         let mut console = self.console.borrow_mut();
-        for line in self.program.borrow().text().lines() {
-            console.print(line)?;
+        let mut pager = Pager::new(&mut *console)?;
+        for (i, line) in text.lines().enumerate().skip(first - 1) {
+            if let Some(last) = last {
+                if i + 1 > last {
+                    break;
+                }
+            }
+            if !pager.print_code(line).await? {
+                break;
+            }
         }
         Ok(())
     }
@@ -413,14 +786,20 @@ impl Command for NewCommand {
 pub struct RunCommand {
     metadata: CallableMetadata,
     console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
     program: Rc<RefCell<dyn Program>>,
 }
 
 impl RunCommand {
-    /// Creates a new `RUN` command that executes the `program`.
+    /// Creates a new `RUN` command that executes the `program`, fetching the files it includes
+    /// from `storage`.
     ///
     /// Reports any non-successful return codes from the program to the console.
-    pub fn new(console: Rc<RefCell<dyn Console>>, program: Rc<RefCell<dyn Program>>) -> Rc<Self> {
+    pub fn new(
+        console: Rc<RefCell<dyn Console>>,
+        storage: Rc<RefCell<Storage>>,
+        program: Rc<RefCell<dyn Program>>,
+    ) -> Rc<Self> {
         Rc::from(Self {
             metadata: CallableMetadataBuilder::new("RUN", VarType::Void)
                 .with_syntax("")
@@ -428,10 +807,13 @@ impl RunCommand {
                 .with_description(
                     "Runs the stored program.
 This issues a CLEAR operation before starting the program to prevent previous leftover state \
-from interfering with the new execution.",
+from interfering with the new execution.
+Any '#INCLUDE directives in the program are processed at this point.  See the \"INCLUDE\" help \
+topic for details.",
                 )
                 .build(),
             console,
+            storage,
             program,
         })
     }
@@ -449,21 +831,121 @@ impl Command for RunCommand {
         }
         machine.clear();
         let program = self.program.borrow().text();
-        let stop_reason = match machine.exec(&mut program.as_bytes()).await {
+        let name = self.program.borrow().name().map(str::to_owned);
+        let includes = Includes::load(&self.storage.borrow(), name.as_deref(), &program).await;
+        let stop_reason = match machine
+            .exec_program_with_includes(&program, &|path| includes.resolve(path))
+            .await
+        {
             Ok(stop_reason) => stop_reason,
             Err(e) => return Err(CallError::NestedError(format!("{}", e))),
         };
-        match stop_reason {
-            StopReason::Break => self.console.borrow_mut().print(BREAK_MSG)?,
-            stop_reason => {
-                if stop_reason.as_exit_code() != 0 {
-                    self.console.borrow_mut().print(&format!(
-                        "Program exited with code {}",
-                        stop_reason.as_exit_code()
-                    ))?;
+        report_stop_reason(&mut *self.console.borrow_mut(), &program, stop_reason)?;
+        Ok(())
+    }
+}
+
+/// The `RUNTESTS` command.
+pub struct RunTestsCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+    program: Rc<RefCell<dyn Program>>,
+}
+
+impl RunTestsCommand {
+    /// Creates a new `RUNTESTS` command that executes the tests in the `program`, fetching the
+    /// files it includes from `storage`, and that reports their results to `console`.
+    pub fn new(
+        console: Rc<RefCell<dyn Console>>,
+        storage: Rc<RefCell<Storage>>,
+        program: Rc<RefCell<dyn Program>>,
+    ) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("RUNTESTS", VarType::Void)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Runs the tests defined in the stored program.
+A test is a subroutine whose label starts with @test_, such as @test_sum, that ends with RETURN.  \
+Tests use ASSERT to verify that the rest of the program behaves as expected.  A test passes if it \
+returns without raising any errors and fails otherwise, which is what happens when an ASSERT \
+does not hold.
+RUNTESTS runs every test in the order in which they appear in the program and prints whether \
+each one passed or failed.  The main program does not run, and RUNTESTS issues a CLEAR operation \
+before each test so that tests do not interfere with each other.  If any test fails, RUNTESTS \
+fails too once all tests have run.
+Any '#INCLUDE directives in the program are processed and the tests defined in the included \
+files run as well.  See the \"INCLUDE\" help topic for details.",
+                )
+                .build(),
+            console,
+            storage,
+            program,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for RunTestsCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        let program = self.program.borrow().text();
+        let name = self.program.borrow().name().map(str::to_owned);
+        let includes = Includes::load(&self.storage.borrow(), name.as_deref(), &program).await;
+        let tests = match machine.find_labels(&program, "test_", &|path| includes.resolve(path)) {
+            Ok(tests) => tests,
+            Err(e) => return Err(CallError::NestedError(format!("{}", e))),
+        };
+        if tests.is_empty() {
+            self.console.borrow_mut().print("No tests found; tests are labels like @test_name")?;
+            return Ok(());
+        }
+
+        let mut failed = 0;
+        for test in &tests {
+            machine.clear();
+            let result = machine
+                .exec_gosub_with_includes(&program, test, &|path| includes.resolve(path))
+                .await;
+            let mut console = self.console.borrow_mut();
+            match result {
+                Ok(StopReason::Eof) | Ok(StopReason::Exited(0)) => {
+                    console.print(&format!("PASS {}", test))?
+                }
+                Ok(stop_reason @ StopReason::Exited(_))
+                | Ok(stop_reason @ StopReason::LimitExceeded(_)) => {
+                    console.print(&format!("FAIL {}", test))?;
+                    report_stop_reason(&mut *console, &program, stop_reason)?;
+                    failed += 1;
+                }
+                Ok(stop_reason) => {
+                    // The user interrupted the tests or the debugger paused them, so there is no
+                    // point in running the rest.
+                    report_stop_reason(&mut *console, &program, stop_reason)?;
+                    return Ok(());
+                }
+                Err(e) => {
+                    console.print(&format!("FAIL {}: {}", test, e))?;
+                    failed += 1;
                 }
             }
         }
+
+        if failed > 0 {
+            return Err(CallError::NestedError(format!(
+                "{} of {} tests failed",
+                failed,
+                tests.len()
+            )));
+        }
+        self.console.borrow_mut().print(&format!("All {} tests passed", tests.len()))?;
         Ok(())
     }
 }
@@ -553,25 +1035,157 @@ pub fn add_all(
     console: Rc<RefCell<dyn Console>>,
     storage: Rc<RefCell<Storage>>,
 ) {
+    let common = Rc::from(RefCell::from(vec![]));
+    machine.add_clearable(Box::from(ClearableCommon(common.clone())));
+    machine.add_command(ChainCommand::new(storage.clone(), common.clone()));
+    machine.add_command(CommonCommand::new(common));
     machine.add_command(EditCommand::new(console.clone(), program.clone()));
+    machine.add_command(IndentCommand::new(program.clone()));
     machine.add_command(KillCommand::new(storage.clone()));
     machine.add_command(ListCommand::new(console.clone(), program.clone()));
     machine.add_command(LoadCommand::new(console.clone(), storage.clone(), program.clone()));
     machine.add_command(NewCommand::new(console.clone(), program.clone()));
-    machine.add_command(RunCommand::new(console.clone(), program.clone()));
+    machine.add_command(RunCommand::new(console.clone(), storage.clone(), program.clone()));
+    machine.add_command(RunTestsCommand::new(console.clone(), storage.clone(), program.clone()));
     machine.add_command(SaveCommand::new(console, storage, program));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::console::{AnsiColor, CharsXY, ClearType, Key};
     use crate::testutils::*;
+    use endbasic_core::exec::{Limit, Limits};
 
     const NO_ANSWERS: &[&str] =
         &["n\n", "N\n", "no\n", "NO\n", "false\n", "FALSE\n", "xyz\n", "\n", "1\n"];
 
     const YES_ANSWERS: &[&str] = &["y\n", "yes\n", "Y\n", "YES\n", "true\n", "TRUE\n"];
 
+    #[test]
+    fn test_chain_ok() {
+        let next = "PRINT \"next\"\nDATA 7\nREAD b\nPRINT b";
+        Tester::default()
+            .write_file("next.bas", next)
+            .run("a = 1: DATA 3, 4: CHAIN \"next\": PRINT \"never\"")
+            .expect_clear()
+            .expect_prints(["next", " 7"])
+            .expect_var("b", 7)
+            .expect_file("MEMORY:/next.bas", next)
+            .check();
+    }
+
+    #[test]
+    fn test_chain_keeps_common() {
+        let next = "PRINT a; b$; arr(1); n\nn = n + 1\nCOMMON a, b$, arr, n\nIF n < 3 THEN CHAIN \"next\"\nPRINT \"done\"";
+        let program = "COMMON a, b$\nCOMMON arr, n\nDIM arr(2): arr(1) = 5\na = 1: b$ = \"x\": c = 2: n = 1\nCHAIN \"next.bas\"\nPRINT \"never\"";
+        Tester::default()
+            .write_file("next.bas", next)
+            .set_program(Some("first.bas"), program)
+            .run("RUN")
+            .expect_clear()
+            .expect_clear()
+            .expect_prints([" 1 x 5  1"])
+            .expect_clear()
+            .expect_prints([" 1 x 5  2", "done"])
+            .expect_var("a", 1)
+            .expect_var("b", "x")
+            .expect_array("arr", VarType::Integer, &[2], vec![(&[1], 5.into())])
+            .expect_var("n", 3)
+            .expect_program(Some("first.bas"), program)
+            .expect_file("MEMORY:/next.bas", next)
+            .check();
+    }
+
+    #[test]
+    fn test_chain_with_includes() {
+        let next = "'#INCLUDE \"lib.bas\"\nPRINT \"next\"";
+        let lib = "PRINT \"lib\"";
+        Tester::default()
+            .write_file("next.bas", next)
+            .write_file("lib.bas", lib)
+            .run("CHAIN \"next\"")
+            .expect_clear()
+            .expect_prints(["lib", "next"])
+            .expect_file("MEMORY:/next.bas", next)
+            .expect_file("MEMORY:/lib.bas", lib)
+            .check();
+    }
+
+    #[test]
+    fn test_chain_errors() {
+        check_stmt_err("1:1: In call to CHAIN: expected filename$", "CHAIN");
+        check_stmt_err("1:1: In call to CHAIN: expected filename$", "CHAIN \"a\", \"b\"");
+        check_stmt_err(
+            "1:1: In call to CHAIN: 1:7: CHAIN requires a string as the filename",
+            "CHAIN 3",
+        );
+        check_stmt_err("1:1: In call to CHAIN: Invalid filename extension", "CHAIN \"a.txt\"");
+        check_stmt_err("1:1: In call to CHAIN: Entry not found", "CHAIN \"missing\"");
+
+        Tester::default()
+            .write_file("bad.bas", "PRINT 1\nPRINT (")
+            .run("a = 1: CHAIN \"bad\"")
+            .expect_err("1:8: In call to CHAIN: 1:14: Cannot load bad.bas: 2:8: Unexpected <<EOF>>")
+            .expect_var("a", 1)
+            .expect_file("MEMORY:/bad.bas", "PRINT 1\nPRINT (")
+            .check();
+
+        Tester::default()
+            .write_file("next.bas", "")
+            .run("a = 1: COMMON a$: CHAIN \"next\"")
+            .expect_err("1:15: Incompatible types in a$ reference")
+            .expect_var("a", 1)
+            .expect_file("MEMORY:/next.bas", "")
+            .check();
+    }
+
+    #[test]
+    fn test_common_errors() {
+        check_stmt_err("1:1: In call to COMMON: expected vref1[, .., vrefN]", "COMMON");
+        check_stmt_err(
+            "1:1: In call to COMMON: 1:8: COMMON requires variable or array names",
+            "COMMON 3",
+        );
+        check_stmt_err("1:1: In call to COMMON: expected vref1[, .., vrefN]", "COMMON a,");
+        check_stmt_err(
+            "1:1: In call to COMMON: 1:11: COMMON requires variable or array names",
+            "COMMON a, b + 1",
+        );
+        check_stmt_err("1:1: In call to COMMON: expected vref1[, .., vrefN]", "COMMON a; b");
+    }
+
+    #[test]
+    fn test_indent_ok() {
+        Tester::default().run("INDENT").check();
+
+        let mut t = Tester::default()
+            .set_program(Some("foo.bas"), "if a then\nprint 1 ' comment\nend if\n");
+        t.run("INDENT")
+            .expect_program(Some("foo.bas"), "IF a THEN\n    PRINT 1 ' comment\nEND IF\n")
+            .check();
+        assert!(t.get_program().borrow().is_dirty());
+    }
+
+    #[test]
+    fn test_indent_unchanged() {
+        let mut t = Tester::default().set_program(Some("foo.bas"), "PRINT 1\n");
+        t.run("INDENT").expect_program(Some("foo.bas"), "PRINT 1\n").check();
+        assert!(!t.get_program().borrow().is_dirty());
+    }
+
+    #[test]
+    fn test_indent_errors() {
+        check_stmt_err("1:1: In call to INDENT: expected no arguments", "INDENT 2");
+
+        Tester::default()
+            .set_program(Some("foo.bas"), "PRINT 1\nIF a\n")
+            .run("INDENT")
+            .expect_uncatchable_err("2:5: No THEN in IF statement")
+            .expect_program(Some("foo.bas"), "PRINT 1\nIF a\n")
+            .check();
+    }
+
     #[test]
     fn test_kill_ok() {
         for p in &["foo", "foo.bas"] {
@@ -586,6 +1200,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_kill_wildcards() {
+        Tester::default()
+            .write_file("temp1.bas", "")
+            .write_file("TEMP2.BAS", "")
+            .write_file("temp3.txt", "")
+            .write_file("keep.bas", "")
+            .run(r#"KILL "temp*""#)
+            .expect_file("MEMORY:/temp3.txt", "")
+            .expect_file("MEMORY:/keep.bas", "")
+            .check();
+
+        Tester::default()
+            .write_file("keep.bas", "")
+            .run(r#"KILL "temp?.bas""#)
+            .expect_err("1:1: In call to KILL: No files match 'temp?.bas'")
+            .expect_file("MEMORY:/keep.bas", "")
+            .check();
+    }
+
     #[test]
     fn test_kill_errors() {
         check_load_save_common_errors("KILL");
@@ -632,9 +1266,91 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_list_ranges() {
+        let program = "one\ntwo\nthree\nfour\n";
+        for (range, lines) in [
+            ("2", &["two"][..]),
+            ("2-3", &["two", "three"]),
+            ("3 - 10", &["three", "four"]),
+            ("-2", &["one", "two"]),
+            ("a - (a + 1)", &["two", "three"]),
+            ("9", &[]),
+        ] {
+            Tester::default()
+                .set_var("a", Value::Integer(2))
+                .set_program(None, program)
+                .run(format!("LIST {}", range))
+                .expect_var("a", Value::Integer(2))
+                .expect_prints(lines.to_vec())
+                .expect_program(None as Option<&str>, program)
+                .check();
+        }
+    }
+
+    #[test]
+    fn test_list_highlights_code() {
+        let t = Tester::default();
+        t.get_console().borrow_mut().set_interactive(true);
+        t.set_program(None, "a = 1\n")
+            .run("LIST")
+            .expect_output([
+                CapturedOut::Write("a = ".to_owned()),
+                CapturedOut::SetColor(Some(AnsiColor::BrightMagenta as u8), None),
+                CapturedOut::Write("1".to_owned()),
+                CapturedOut::SetColor(None, None),
+                CapturedOut::Print("".to_owned()),
+            ])
+            .expect_program(None as Option<&str>, "a = 1\n")
+            .check();
+    }
+
+    #[test]
+    fn test_list_not_interactive_does_not_highlight() {
+        Tester::default()
+            .set_program(None, "a = 1\n")
+            .run("LIST")
+            .expect_prints(["a = 1"])
+            .expect_program(None as Option<&str>, "a = 1\n")
+            .check();
+    }
+
+    #[test]
+    fn test_list_paginates() {
+        let t = Tester::default();
+        t.get_console().borrow_mut().set_interactive(true);
+        t.get_console().borrow_mut().set_size_chars(CharsXY::new(10, 3));
+        let mut t = t
+            .add_input_keys(&[Key::Char(' '), Key::Escape])
+            .set_program(None, "one\ntwo\nthree\nfour\nfive\nsix\n");
+        t.run("LIST")
+            .expect_output([
+                CapturedOut::Print("one".to_owned()),
+                CapturedOut::Print("two".to_owned()),
+                CapturedOut::Write("-- More --".to_owned()),
+                CapturedOut::Clear(ClearType::CurrentLine),
+                CapturedOut::MoveWithinLine(-10),
+                CapturedOut::Print("three".to_owned()),
+                CapturedOut::Print("four".to_owned()),
+                CapturedOut::Write("-- More --".to_owned()),
+                CapturedOut::Clear(ClearType::CurrentLine),
+                CapturedOut::MoveWithinLine(-10),
+            ])
+            .expect_program(None as Option<&str>, "one\ntwo\nthree\nfour\nfive\nsix\n")
+            .check();
+    }
+
     #[test]
     fn test_list_errors() {
-        check_stmt_err("1:1: In call to LIST: expected no arguments", "LIST 2");
+        check_stmt_err("1:1: In call to LIST: expected [line%|first%-last%|-last%]", "LIST 2, 3");
+        check_stmt_err("1:1: In call to LIST: expected [line%|first%-last%|-last%]", "LIST ; 3");
+        check_stmt_err("1:1: In call to LIST: 1:6: Line number must be positive", "LIST 0");
+        check_stmt_err("1:1: In call to LIST: 1:8: Line number must be positive", "LIST 3-0");
+        check_stmt_err(
+            "1:1: In call to LIST: 1:8: Last line must not be before first line",
+            "LIST 3-2",
+        );
+        check_stmt_err("1:1: In call to LIST: 1:6: \"a\" is not a number", "LIST \"a\"");
     }
 
     #[test]
@@ -896,11 +1612,151 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_run_exceeds_limits() {
+        let program = "PRINT 5: DO: LOOP";
+        let mut t = Tester::default().set_program(Some("untouched.bas"), program);
+        t.get_machine().set_limits(Limits { max_instructions: Some(100), ..Limits::default() });
+        t.run(r#"RUN: PRINT "after""#)
+            .expect_ok(StopReason::LimitExceeded(Limit::Instructions))
+            .expect_clear()
+            .expect_prints([" 5", "**** Program stopped due to instruction count limit ****"])
+            .expect_program(Some("untouched.bas"), program)
+            .check();
+    }
+
+    #[test]
+    fn test_run_with_includes() {
+        let program =
+            "'#INCLUDE \"lib\"\nn = 3: GOSUB @twice: PRINT n\n'#INCLUDE \"MEMORY:other.bas\"";
+        let lib = "'#INCLUDE \"other\"\nGOTO @lib_end\n@twice\nn = n * 2\nRETURN\n@lib_end";
+        let other = "PRINT \"other\"";
+        Tester::default()
+            .write_file("lib.bas", lib)
+            .write_file("other.bas", other)
+            .set_program(Some("MEMORY:/main.bas"), program)
+            .run("RUN")
+            .expect_clear()
+            .expect_prints(["other", " 6"])
+            .expect_var("n", 6)
+            .expect_program(Some("MEMORY:/main.bas"), program)
+            .expect_file("MEMORY:/lib.bas", lib)
+            .expect_file("MEMORY:/other.bas", other)
+            .check();
+    }
+
+    #[test]
+    fn test_run_with_includes_errors() {
+        let program = "PRINT 1\n'#INCLUDE \"lib\"";
+        Tester::default()
+            .set_program(None, program)
+            .run("RUN")
+            .expect_clear()
+            .expect_uncatchable_err("2:1: Cannot include lib: Entry not found")
+            .expect_program(None as Option<&str>, program)
+            .check();
+
+        let lib = "WHILE TRUE\n";
+        Tester::default()
+            .write_file("lib.bas", lib)
+            .set_program(None, program)
+            .run("RUN")
+            .expect_clear()
+            .expect_uncatchable_err("2:1: In lib: 1:1: WHILE without WEND")
+            .expect_program(None as Option<&str>, program)
+            .expect_file("MEMORY:/lib.bas", lib)
+            .check();
+    }
+
     #[test]
     fn test_run_errors() {
         check_stmt_err("1:1: In call to RUN: expected no arguments", "RUN 10");
     }
 
+    #[test]
+    fn test_runtests_none() {
+        let program = "PRINT 1\n@test\n@other_test\nRETURN";
+        Tester::default()
+            .set_program(None, program)
+            .run("RUNTESTS")
+            .expect_prints(["No tests found; tests are labels like @test_name"])
+            .expect_program(None as Option<&str>, program)
+            .check();
+    }
+
+    #[test]
+    fn test_runtests_all_pass() {
+        let program = "PRINT \"main\"\nEND\n@double: n = n * 2: RETURN\n\
+@test_double\nn = 3: GOSUB @double: ASSERT n = 6\nRETURN\n\
+@TEST_isolated\nDIM n AS INTEGER: ASSERT n = 0, \"Variables leaked\"\nRETURN\n";
+        Tester::default()
+            .set_program(Some("tests.bas"), program)
+            .run("RUNTESTS")
+            .expect_clear()
+            .expect_prints(["PASS test_double"])
+            .expect_clear()
+            .expect_prints(["PASS TEST_isolated", "All 2 tests passed"])
+            .expect_var("n", 0)
+            .expect_program(Some("tests.bas"), program)
+            .check();
+    }
+
+    #[test]
+    fn test_runtests_some_fail() {
+        let program = "@test_assert\nASSERT 1 = 2, \"Bad math\"\nRETURN\n\
+@test_exit\nEND 3\n\
+@test_error\nPRINT a\nRETURN\n\
+@test_ok\nRETURN\n";
+        Tester::default()
+            .set_program(Some("tests.bas"), program)
+            .run("RUNTESTS")
+            .expect_clear()
+            .expect_prints([
+                "FAIL test_assert: 2:1: In call to ASSERT: 2:8: Assertion failed: Bad math",
+            ])
+            .expect_clear()
+            .expect_prints(["FAIL test_exit", "Program exited with code 3"])
+            .expect_clear()
+            .expect_prints(["FAIL test_error: 7:7: Undefined variable a"])
+            .expect_clear()
+            .expect_prints(["PASS test_ok"])
+            .expect_uncatchable_err("3 of 4 tests failed")
+            .expect_program(Some("tests.bas"), program)
+            .check();
+    }
+
+    #[test]
+    fn test_runtests_with_includes() {
+        let program = "'#INCLUDE \"lib\"\n@test_main\nn = 1: GOSUB @triple: ASSERT n = 4\nRETURN";
+        let lib =
+            "GOTO @lib_end\n@triple: n = n + 3: RETURN\n@test_lib: ASSERT TRUE: RETURN\n@lib_end";
+        Tester::default()
+            .write_file("lib.bas", lib)
+            .set_program(Some("MEMORY:/main.bas"), program)
+            .run("RUNTESTS")
+            .expect_clear()
+            .expect_prints(["PASS test_lib"])
+            .expect_clear()
+            .expect_prints(["PASS test_main", "All 2 tests passed"])
+            .expect_var("n", 4)
+            .expect_program(Some("MEMORY:/main.bas"), program)
+            .expect_file("MEMORY:/lib.bas", lib)
+            .check();
+    }
+
+    #[test]
+    fn test_runtests_errors() {
+        check_stmt_err("1:1: In call to RUNTESTS: expected no arguments", "RUNTESTS 1");
+
+        let program = "@test_a\nWHILE TRUE\n";
+        Tester::default()
+            .set_program(None, program)
+            .run("RUNTESTS")
+            .expect_uncatchable_err("2:1: WHILE without WEND")
+            .expect_program(None as Option<&str>, program)
+            .check();
+    }
+
     #[test]
     fn test_save_ok_explicit_name() {
         let content = "\n some line   \n ";
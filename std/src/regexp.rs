@@ -0,0 +1,253 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Regular expression functions for EndBASIC.
+
+use async_trait::async_trait;
+use endbasic_core::ast::{FunctionCallSpan, Value, VarType};
+use endbasic_core::eval::eval_all;
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Function, FunctionResult, Symbols,
+};
+use endbasic_core::LineCol;
+use regex::Regex;
+use std::rc::Rc;
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Regular expressions
+Regular expressions describe patterns of text and allow searching for and replacing text that \
+matches those patterns without having to inspect strings one character at a time.
+Patterns follow the syntax of the Rust regex crate, which is similar to that of Perl but does not \
+support look-around assertions nor backreferences.  For example, the pattern \"[0-9]+\" matches \
+any sequence of one or more digits and the pattern \"^a.*z$\" matches any string that starts with \
+an a and ends with a z.
+Matching is case-sensitive by default.  Prefix the pattern with (?i) to make it case-insensitive.";
+
+/// Compiles the `pattern` that was provided at `pos`.
+fn compile(pattern: &str, pos: LineCol) -> Result<Regex, CallError> {
+    Regex::new(pattern).map_err(|e| {
+        let e = match e {
+            regex::Error::Syntax(e) => e.lines().last().unwrap_or("").to_owned(),
+            e => e.to_string(),
+        };
+        CallError::ArgumentError(pos, format!("Invalid regular expression: {}", e))
+    })
+}
+
+/// The `REGEXP_FIND` function.
+pub struct RegexpFindFunction {
+    metadata: CallableMetadata,
+}
+
+impl RegexpFindFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("REGEXP_FIND", VarType::Text)
+                .with_syntax("expr$, pattern$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the first portion of a string that matches a regular expression.
+Returns an empty string if pattern$ does not match anywhere in expr$.  Use REGEXP_MATCH?() \
+instead if you need to tell apart a pattern that does not match from one that matches an empty \
+string.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for RegexpFindFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(s), Value::Text(pattern)] => {
+                let re = compile(pattern, span.args[1].start_pos())?;
+                match re.find(s) {
+                    Some(m) => Ok(Value::Text(m.as_str().to_owned())),
+                    None => Ok(Value::Text("".to_owned())),
+                }
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `REGEXP_MATCH` function.
+pub struct RegexpMatchFunction {
+    metadata: CallableMetadata,
+}
+
+impl RegexpMatchFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("REGEXP_MATCH", VarType::Boolean)
+                .with_syntax("expr$, pattern$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Checks if a string matches a regular expression.
+The pattern$ can match any portion of expr$.  Anchor the pattern with ^ and $ to require the \
+whole string to match.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for RegexpMatchFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(s), Value::Text(pattern)] => {
+                let re = compile(pattern, span.args[1].start_pos())?;
+                Ok(Value::Boolean(re.is_match(s)))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `REGEXP_REPLACE` function.
+pub struct RegexpReplaceFunction {
+    metadata: CallableMetadata,
+}
+
+impl RegexpReplaceFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("REGEXP_REPLACE", VarType::Text)
+                .with_syntax("expr$, pattern$, replacement$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Replaces all portions of a string that match a regular expression.
+The replacement$ can refer to the groups captured by pattern$: $0 is the whole match, $1 is the \
+first group, and so on.  Named groups can be referenced as ${name}.  Use $$ to insert a literal \
+dollar sign.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for RegexpReplaceFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(s), Value::Text(pattern), Value::Text(replacement)] => {
+                let re = compile(pattern, span.args[1].start_pos())?;
+                Ok(Value::Text(re.replace_all(s, replacement.as_str()).into_owned()))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// Adds all symbols provided by this module to the given `machine`.
+pub fn add_all(machine: &mut Machine) {
+    machine.add_function(RegexpFindFunction::new());
+    machine.add_function(RegexpMatchFunction::new());
+    machine.add_function(RegexpReplaceFunction::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testutils::*;
+
+    #[test]
+    fn test_regexp_find() {
+        check_expr_ok("", r#"REGEXP_FIND("", "a")"#);
+        check_expr_ok("123", r#"REGEXP_FIND("abc123def456", "[0-9]+")"#);
+        check_expr_ok("", r#"REGEXP_FIND("abcdef", "[0-9]+")"#);
+        check_expr_ok("ABC", r#"REGEXP_FIND$("xABCx", "(?i)abc")"#);
+
+        check_expr_error(
+            "1:10: In call to REGEXP_FIND: expected expr$, pattern$",
+            r#"REGEXP_FIND("a")"#,
+        );
+        check_expr_error(
+            "1:10: In call to REGEXP_FIND: expected expr$, pattern$",
+            r#"REGEXP_FIND("a", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to REGEXP_FIND: 1:27: Invalid regular expression: error: unclosed group",
+            r#"REGEXP_FIND("a", "(")"#,
+        );
+    }
+
+    #[test]
+    fn test_regexp_match() {
+        check_expr_ok(true, r#"REGEXP_MATCH("", "")"#);
+        check_expr_ok(true, r#"REGEXP_MATCH("abc123", "[0-9]")"#);
+        check_expr_ok(false, r#"REGEXP_MATCH("abc", "[0-9]")"#);
+        check_expr_ok(false, r#"REGEXP_MATCH("abc123", "^[a-z]+$")"#);
+        check_expr_ok(true, r#"REGEXP_MATCH?("abc", "^[a-z]+$")"#);
+
+        check_expr_error(
+            "1:10: In call to REGEXP_MATCH: expected expr$, pattern$",
+            r#"REGEXP_MATCH()"#,
+        );
+        check_expr_error(
+            "1:10: In call to REGEXP_MATCH: expected expr$, pattern$",
+            r#"REGEXP_MATCH(1, "a")"#,
+        );
+        check_expr_error(
+            "1:10: In call to REGEXP_MATCH: 1:28: Invalid regular expression: error: unclosed character class",
+            r#"REGEXP_MATCH("a", "[a")"#,
+        );
+    }
+
+    #[test]
+    fn test_regexp_replace() {
+        check_expr_ok("", r#"REGEXP_REPLACE("", "a", "b")"#);
+        check_expr_ok("abc-def-", r#"REGEXP_REPLACE("abc123def456", "[0-9]+", "-")"#);
+        check_expr_ok("abcdef", r#"REGEXP_REPLACE("abcdef", "[0-9]+", "-")"#);
+        check_expr_ok("doe, john", r#"REGEXP_REPLACE$("john doe", "([a-z]+) ([a-z]+)", "$2, $1")"#);
+        check_expr_ok(
+            "doe, john",
+            r#"REGEXP_REPLACE$("john doe", "(?P<first>[a-z]+) (?P<last>[a-z]+)", "${last}, ${first}")"#,
+        );
+
+        check_expr_error(
+            "1:10: In call to REGEXP_REPLACE: expected expr$, pattern$, replacement$",
+            r#"REGEXP_REPLACE("a", "b")"#,
+        );
+        check_expr_error(
+            "1:10: In call to REGEXP_REPLACE: expected expr$, pattern$, replacement$",
+            r#"REGEXP_REPLACE("a", "b", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to REGEXP_REPLACE: 1:30: Invalid regular expression: error: unclosed group",
+            r#"REGEXP_REPLACE("a", "(", "")"#,
+        );
+    }
+}
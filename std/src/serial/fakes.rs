@@ -0,0 +1,109 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Fake implementations of serial ports that work on all platforms.
+
+use crate::serial::Port;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::io;
+
+/// Stand-in implementation of the EndBASIC serial operations that always returns an error.
+#[derive(Default)]
+pub(crate) struct NoopPort {}
+
+#[async_trait(?Send)]
+impl Port for NoopPort {
+    fn open(&mut self, _path: &str, _baud_rate: u32) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Serial port support not compiled in"))
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Serial port support not compiled in"))
+    }
+
+    async fn read(&mut self, _max_bytes: usize) -> io::Result<Vec<u8>> {
+        Err(io::Error::new(io::ErrorKind::Other, "Serial port support not compiled in"))
+    }
+
+    async fn write(&mut self, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Serial port support not compiled in"))
+    }
+}
+
+/// Mock serial port that records writes and supplies fake reads.
+///
+/// This allows scripting against serial devices on machines without the hardware, such as during
+/// tests: the caller preloads the bytes that the device should send with `add_read_data` and
+/// later inspects the bytes that the program sent with `written`.
+#[derive(Default)]
+pub struct MockPort {
+    open: Option<(String, u32)>,
+    read_data: VecDeque<u8>,
+    written: Vec<u8>,
+}
+
+impl MockPort {
+    /// Appends `data` to the bytes that future reads will return.
+    pub fn add_read_data(&mut self, data: &[u8]) {
+        self.read_data.extend(data);
+    }
+
+    /// Returns the path and baud rate of the currently open port, if any.
+    pub fn open_port(&self) -> Option<(&str, u32)> {
+        self.open.as_ref().map(|(path, baud_rate)| (path.as_str(), *baud_rate))
+    }
+
+    /// Returns all bytes written so far.
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
+
+    /// Fails if the port is not open.
+    fn check_open(&self) -> io::Result<()> {
+        match self.open {
+            Some(_) => Ok(()),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "Serial port not open; use SERIAL_OPEN first",
+            )),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Port for MockPort {
+    fn open(&mut self, path: &str, baud_rate: u32) -> io::Result<()> {
+        self.open = Some((path.to_owned(), baud_rate));
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.open = None;
+        Ok(())
+    }
+
+    async fn read(&mut self, max_bytes: usize) -> io::Result<Vec<u8>> {
+        self.check_open()?;
+        let n = std::cmp::min(max_bytes, self.read_data.len());
+        Ok(self.read_data.drain(..n).collect())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.check_open()?;
+        self.written.extend(data);
+        Ok(())
+    }
+}
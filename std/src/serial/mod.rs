@@ -0,0 +1,379 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Serial port access functions and commands for EndBASIC.
+
+use async_trait::async_trait;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, FunctionCallSpan, Value, VarType};
+use endbasic_core::eval::eval_all;
+use endbasic_core::exec::{Clearable, Machine};
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
+    FunctionResult, Symbols,
+};
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+mod fakes;
+pub use fakes::MockPort;
+pub(crate) use fakes::NoopPort;
+#[cfg(all(feature = "serial", unix))]
+mod unix;
+#[cfg(all(feature = "serial", unix))]
+pub use unix::TtyPort;
+
+/// Category description for all symbols provided by this module.
+const CATEGORY: &str = "Serial communication
+Serial ports allow programs to exchange data with microcontrollers such as Arduinos and with \
+other devices attached via a UART or a USB serial adapter.
+Open the port with SERIAL_OPEN and then use SERIAL_WRITE and SERIAL_READ$ to exchange data over \
+it.  Only one port can be open at any given time and CLEAR closes it.
+Serial port support is currently only available on Unix systems.";
+
+/// Maximum number of bytes that SERIAL_READ reads when the caller does not specify a limit.
+const DEFAULT_READ_SIZE: i32 = 4096;
+
+/// Generic abstraction over a serial port to back all EndBASIC commands.
+#[async_trait(?Send)]
+pub trait Port {
+    /// Opens the serial port at `path` and configures it to communicate at `baud_rate`.
+    ///
+    /// It is OK to open a port multiple times without calling `close()` in-between.
+    fn open(&mut self, path: &str, baud_rate: u32) -> io::Result<()>;
+
+    /// Closes the currently-open port, if any.
+    fn close(&mut self) -> io::Result<()>;
+
+    /// Reads up to `max_bytes` from the open port.
+    ///
+    /// This waits for a short period of time for data to arrive and returns an empty vector if
+    /// none did, which allows programs to poll the port.
+    async fn read(&mut self, max_bytes: usize) -> io::Result<Vec<u8>>;
+
+    /// Writes all of `data` to the open port.
+    async fn write(&mut self, data: &[u8]) -> io::Result<()>;
+}
+
+/// Closes the open serial port in a best-effort manner.
+struct PortClearable {
+    port: Rc<RefCell<dyn Port>>,
+}
+
+impl Clearable for PortClearable {
+    fn reset_state(&self, _syms: &mut Symbols) {
+        let _ = self.port.borrow_mut().close();
+    }
+}
+
+/// The `SERIAL_OPEN` command.
+pub struct SerialOpenCommand {
+    metadata: CallableMetadata,
+    port: Rc<RefCell<dyn Port>>,
+}
+
+impl SerialOpenCommand {
+    /// Creates a new instance of the command.
+    pub fn new(port: Rc<RefCell<dyn Port>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SERIAL_OPEN", VarType::Void)
+                .with_syntax("path$, baud%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Opens a serial port.
+The path$ is the name of the serial device to open, such as \"/dev/ttyUSB0\" or \
+\"/dev/ttyACM0\" for USB serial adapters or \"/dev/serial0\" for the Raspberry Pi's UART.
+The baud% is the speed of the connection and must match the speed the device was configured \
+with.  Supported values are 1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200, and 230400.  \
+The port is always configured for 8 data bits, no parity, and 1 stop bit.
+It is OK to open a port while another one is open, in which case the previous one is closed.",
+                )
+                .build(),
+            port,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SerialOpenCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [ArgSpan { expr: Some(path), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(baud), sep: ArgSep::End, .. }] =>
+            {
+                let path = match path.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(path) => path,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            path.start_pos(),
+                            "path$ must be a string".to_owned(),
+                        ))
+                    }
+                };
+                let baud_rate = match baud.eval(machine.get_mut_symbols()).await? {
+                    Value::Integer(i) if i > 0 => i as u32,
+                    Value::Integer(i) => {
+                        return Err(CallError::ArgumentError(
+                            baud.start_pos(),
+                            format!("Baud rate {} must be positive", i),
+                        ))
+                    }
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            baud.start_pos(),
+                            "baud% must be an integer".to_owned(),
+                        ))
+                    }
+                };
+                self.port.borrow_mut().open(&path, baud_rate)?;
+                Ok(())
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `SERIAL_READ` function.
+pub struct SerialReadFunction {
+    metadata: CallableMetadata,
+    port: Rc<RefCell<dyn Port>>,
+}
+
+impl SerialReadFunction {
+    /// Creates a new instance of the function.
+    pub fn new(port: Rc<RefCell<dyn Port>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SERIAL_READ", VarType::Text)
+                .with_syntax("[max_bytes%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Reads data from the open serial port.
+Returns up to max_bytes% bytes of the data received from the port, or up to 4096 bytes if not \
+specified.  If no data arrives within a tenth of a second, returns an empty string.  This allows \
+polling the port in a loop while the program remains responsive, similar to INKEY.",
+                )
+                .build(),
+            port,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for SerialReadFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let max_bytes = match args.as_slice() {
+            [] => DEFAULT_READ_SIZE,
+            [Value::Integer(max_bytes)] => {
+                if *max_bytes <= 0 {
+                    return Err(CallError::ArgumentError(
+                        span.args[0].start_pos(),
+                        "max_bytes% must be positive".to_owned(),
+                    ));
+                }
+                *max_bytes
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let data = self.port.borrow_mut().read(max_bytes as usize).await?;
+        Ok(Value::Text(String::from_utf8_lossy(&data).into_owned()))
+    }
+}
+
+/// The `SERIAL_WRITE` command.
+pub struct SerialWriteCommand {
+    metadata: CallableMetadata,
+    port: Rc<RefCell<dyn Port>>,
+}
+
+impl SerialWriteCommand {
+    /// Creates a new instance of the command.
+    pub fn new(port: Rc<RefCell<dyn Port>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SERIAL_WRITE", VarType::Void)
+                .with_syntax("data$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Writes data to the open serial port.
+Waits until all of data$ has been sent.  No line terminator is appended, so if the device \
+expects one, add it explicitly with CHR$(10) or CHR$(13).",
+                )
+                .build(),
+            port,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SerialWriteCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [ArgSpan { expr: Some(data), sep: ArgSep::End, .. }] => {
+                let data = match data.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(data) => data,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            data.start_pos(),
+                            "data$ must be a string".to_owned(),
+                        ))
+                    }
+                };
+                self.port.borrow_mut().write(data.as_bytes()).await?;
+                Ok(())
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// Adds all symbols provided by this module to the given `machine`.
+pub fn add_all(machine: &mut Machine, port: Rc<RefCell<dyn Port>>) {
+    machine.add_clearable(Box::from(PortClearable { port: port.clone() }));
+    machine.add_command(SerialOpenCommand::new(port.clone()));
+    machine.add_command(SerialWriteCommand::new(port.clone()));
+    machine.add_function(SerialReadFunction::new(port));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    /// Creates a tester with the serial commands backed by a new mock port, which is returned too.
+    fn mock_tester() -> (Tester, Rc<RefCell<MockPort>>) {
+        let port = Rc::from(RefCell::from(MockPort::default()));
+        let mut t = Tester::empty();
+        add_all(t.get_machine(), port.clone());
+        (t, port)
+    }
+
+    /// Tests that all serial operations delegate to the real port implementation, which defaults
+    /// to the no-op backend when using the tester.  All other tests in this file use a mock port.
+    #[test]
+    fn test_real_backend() {
+        check_stmt_err(
+            "1:1: In call to SERIAL_OPEN: Serial port support not compiled in",
+            r#"SERIAL_OPEN "/dev/ttyUSB0", 9600"#,
+        );
+        check_expr_error(
+            "1:10: In call to SERIAL_READ: Serial port support not compiled in",
+            "SERIAL_READ$()",
+        );
+        check_stmt_err(
+            "1:1: In call to SERIAL_WRITE: Serial port support not compiled in",
+            r#"SERIAL_WRITE "x""#,
+        );
+    }
+
+    #[test]
+    fn test_serial_write_and_read() {
+        let (mut t, port) = mock_tester();
+        port.borrow_mut().add_read_data(b"hello world");
+        t.run(
+            r#"SERIAL_OPEN "/dev/ttyACM0", 115200
+            SERIAL_WRITE "ping"
+            a = SERIAL_READ$(5): b = SERIAL_READ$(): c = SERIAL_READ$()"#,
+        )
+        .expect_var("a", "hello")
+        .expect_var("b", " world")
+        .expect_var("c", "")
+        .check();
+        assert_eq!(Some(("/dev/ttyACM0", 115200)), port.borrow().open_port());
+        assert_eq!(b"ping", port.borrow().written());
+    }
+
+    #[test]
+    fn test_serial_clear_closes() {
+        let (mut t, port) = mock_tester();
+        t.get_machine().add_command(crate::exec::ClearCommand::new());
+        t.run(r#"SERIAL_OPEN "/dev/ttyS0", 9600: CLEAR: SERIAL_WRITE "x""#)
+            .expect_err(
+                "1:40: In call to SERIAL_WRITE: Serial port not open; use SERIAL_OPEN first",
+            )
+            .check();
+        assert_eq!(None, port.borrow().open_port());
+        assert!(port.borrow().written().is_empty());
+    }
+
+    #[test]
+    fn test_serial_open_errors() {
+        check_stmt_err("1:1: In call to SERIAL_OPEN: expected path$, baud%", "SERIAL_OPEN");
+        check_stmt_err("1:1: In call to SERIAL_OPEN: expected path$, baud%", r#"SERIAL_OPEN "a""#);
+        check_stmt_err(
+            "1:1: In call to SERIAL_OPEN: expected path$, baud%",
+            r#"SERIAL_OPEN "a"; 9600"#,
+        );
+        check_stmt_err(
+            "1:1: In call to SERIAL_OPEN: expected path$, baud%",
+            r#"SERIAL_OPEN "a", 9600, 1"#,
+        );
+
+        check_stmt_err(
+            "1:1: In call to SERIAL_OPEN: 1:13: path$ must be a string",
+            "SERIAL_OPEN 1, 9600",
+        );
+        check_stmt_err(
+            "1:1: In call to SERIAL_OPEN: 1:18: baud% must be an integer",
+            r#"SERIAL_OPEN "a", "9600""#,
+        );
+        check_stmt_err(
+            "1:1: In call to SERIAL_OPEN: 1:18: Baud rate 0 must be positive",
+            r#"SERIAL_OPEN "a", 0"#,
+        );
+    }
+
+    #[test]
+    fn test_serial_read_errors() {
+        check_expr_error(
+            "1:10: In call to SERIAL_READ: expected [max_bytes%]",
+            "SERIAL_READ$(1, 2)",
+        );
+        check_expr_error(
+            "1:10: In call to SERIAL_READ: expected [max_bytes%]",
+            r#"SERIAL_READ$("a")"#,
+        );
+        check_expr_error(
+            "1:10: In call to SERIAL_READ: 1:23: max_bytes% must be positive",
+            "SERIAL_READ$(0)",
+        );
+
+        let (mut t, _port) = mock_tester();
+        t.run("a = SERIAL_READ$()")
+            .expect_err("1:5: In call to SERIAL_READ: Serial port not open; use SERIAL_OPEN first")
+            .check();
+    }
+
+    #[test]
+    fn test_serial_write_errors() {
+        check_stmt_err("1:1: In call to SERIAL_WRITE: expected data$", "SERIAL_WRITE");
+        check_stmt_err("1:1: In call to SERIAL_WRITE: expected data$", r#"SERIAL_WRITE "a", "b""#);
+        check_stmt_err(
+            "1:1: In call to SERIAL_WRITE: 1:14: data$ must be a string",
+            "SERIAL_WRITE 3",
+        );
+    }
+}
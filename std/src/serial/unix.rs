@@ -0,0 +1,198 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Serial port implementation for Unix systems backed by the terminal interface.
+
+use crate::serial::Port;
+use async_trait::async_trait;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Maps a numeric `baud_rate` to its terminal speed constant.
+fn baud_rate_to_speed(baud_rate: u32) -> io::Result<libc::speed_t> {
+    match baud_rate {
+        1200 => Ok(libc::B1200),
+        2400 => Ok(libc::B2400),
+        4800 => Ok(libc::B4800),
+        9600 => Ok(libc::B9600),
+        19200 => Ok(libc::B19200),
+        38400 => Ok(libc::B38400),
+        57600 => Ok(libc::B57600),
+        115200 => Ok(libc::B115200),
+        230400 => Ok(libc::B230400),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unsupported baud rate {}", baud_rate),
+        )),
+    }
+}
+
+/// Configures the terminal behind `file` for raw 8N1 communication at `baud_rate`.
+///
+/// Reads are set up to return whatever data arrives within a tenth of a second so that programs
+/// can poll the port without blocking indefinitely.
+#[allow(unsafe_code)]
+fn configure(file: &File, baud_rate: u32) -> io::Result<()> {
+    let speed = baud_rate_to_speed(baud_rate)?;
+    let fd = file.as_raw_fd();
+
+    // SAFETY: `fd` is a valid descriptor for the lifetime of `file` and `tio` is a plain struct
+    // that is fully initialized by `tcgetattr` before we use it.
+    unsafe {
+        let mut tio: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut tio) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut tio);
+        tio.c_cflag |= libc::CLOCAL | libc::CREAD;
+        tio.c_cc[libc::VMIN] = 0;
+        tio.c_cc[libc::VTIME] = 1;
+        if libc::cfsetispeed(&mut tio, speed) != 0 || libc::cfsetospeed(&mut tio, speed) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::tcsetattr(fd, libc::TCSANOW, &tio) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Serial port backed by a terminal device such as `/dev/ttyUSB0`.
+///
+/// I/O happens via Tokio so this requires the program to run within a Tokio runtime, which is
+/// what the EndBASIC CLI uses to drive the machine.
+#[derive(Default)]
+pub struct TtyPort {
+    file: Option<tokio::fs::File>,
+}
+
+impl TtyPort {
+    /// Gets access to the open port.
+    fn get_file(&mut self) -> io::Result<&mut tokio::fs::File> {
+        match self.file.as_mut() {
+            Some(file) => Ok(file),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "Serial port not open; use SERIAL_OPEN first",
+            )),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Port for TtyPort {
+    fn open(&mut self, path: &str, baud_rate: u32) -> io::Result<()> {
+        self.file = None;
+        let file =
+            OpenOptions::new().read(true).write(true).custom_flags(libc::O_NOCTTY).open(path)?;
+        configure(&file, baud_rate)?;
+        self.file = Some(tokio::fs::File::from_std(file));
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.file = None;
+        Ok(())
+    }
+
+    async fn read(&mut self, max_bytes: usize) -> io::Result<Vec<u8>> {
+        let file = self.get_file()?;
+        let mut buffer = vec![0; max_bytes];
+        let n = file.read(&mut buffer).await?;
+        buffer.truncate(n);
+        Ok(buffer)
+    }
+
+    async fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let file = self.get_file()?;
+        file.write_all(data).await?;
+        file.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::io::{Read, Write};
+    use std::os::unix::io::FromRawFd;
+
+    /// Creates a new pseudo-terminal and returns its master side and the path to its slave side.
+    #[allow(unsafe_code)]
+    fn open_pty() -> (File, String) {
+        // SAFETY: All calls operate on the descriptor we just opened and check for errors, and the
+        // string returned by `ptsname` is copied before any other call can overwrite it.
+        unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            assert!(fd >= 0);
+            let master = File::from_raw_fd(fd);
+            assert_eq!(0, libc::grantpt(fd));
+            assert_eq!(0, libc::unlockpt(fd));
+            let name = libc::ptsname(fd);
+            assert!(!name.is_null());
+            (master, CStr::from_ptr(name).to_str().unwrap().to_owned())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_and_write() {
+        let (mut master, path) = open_pty();
+
+        let mut port = TtyPort::default();
+        port.open(&path, 115200).unwrap();
+
+        port.write(b"ping").await.unwrap();
+        let mut buffer = [0; 4];
+        master.read_exact(&mut buffer).unwrap();
+        assert_eq!(b"ping", &buffer);
+
+        assert!(port.read(10).await.unwrap().is_empty());
+        master.write_all(b"pong").unwrap();
+        let mut data = vec![];
+        while data.len() < 4 {
+            data.extend(port.read(10).await.unwrap());
+        }
+        assert_eq!(b"pong", data.as_slice());
+
+        port.close().unwrap();
+        assert_eq!(io::ErrorKind::NotConnected, port.read(1).await.unwrap_err().kind());
+    }
+
+    #[tokio::test]
+    async fn test_open_missing_file() {
+        let mut port = TtyPort::default();
+        let err = port.open("/this/file/does/not/exist", 9600).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+        assert_eq!(io::ErrorKind::NotConnected, port.read(1).await.unwrap_err().kind());
+    }
+
+    #[tokio::test]
+    async fn test_open_not_a_tty() {
+        let mut port = TtyPort::default();
+        port.open("/dev/null", 9600).unwrap_err();
+        assert_eq!(io::ErrorKind::NotConnected, port.write(b"x").await.unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_open_bad_baud_rate() {
+        let mut port = TtyPort::default();
+        let err = port.open("/dev/null", 1234).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        assert_eq!("Unsupported baud rate 1234", err.to_string());
+    }
+}
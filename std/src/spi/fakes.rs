@@ -0,0 +1,111 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Fake implementations of SPI buses that work on all platforms.
+
+use crate::spi::Bus;
+use std::collections::VecDeque;
+use std::io;
+
+/// Stand-in implementation of the EndBASIC SPI operations that always returns an error.
+#[derive(Default)]
+pub(crate) struct NoopBus {}
+
+impl Bus for NoopBus {
+    fn open(&mut self, _bus: u8, _device: u8, _clock_hz: u32, _mode: u8) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "SPI backend not compiled in"))
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "SPI backend not compiled in"))
+    }
+
+    fn transfer(&mut self, _data: &mut [u8]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "SPI backend not compiled in"))
+    }
+
+    fn write(&mut self, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "SPI backend not compiled in"))
+    }
+}
+
+/// Mock SPI implementation that records sent data and supplies fake received data.
+///
+/// This allows scripting against SPI devices on machines without the hardware, such as during
+/// tests: the caller preloads the bytes that the device should return during transfers with
+/// `add_read_data` and later inspects the bytes that the program sent with `writes`.
+#[derive(Default)]
+pub struct MockBus {
+    device: Option<(u8, u8)>,
+    read_data: VecDeque<u8>,
+    writes: Vec<(u8, u8, Vec<u8>)>,
+}
+
+impl MockBus {
+    /// Appends `data` to the bytes that future transfers will receive.
+    pub fn add_read_data(&mut self, data: &[u8]) {
+        self.read_data.extend(data);
+    }
+
+    /// Returns the data sent so far, either via writes or via transfers, as tuples of bus number,
+    /// device number, and data.
+    pub fn writes(&self) -> &[(u8, u8, Vec<u8>)] {
+        &self.writes
+    }
+
+    /// Returns the currently open bus and device numbers, if any.
+    fn get_device(&self) -> io::Result<(u8, u8)> {
+        match self.device {
+            Some(device) => Ok(device),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "SPI device not open; use SPI_OPEN first",
+            )),
+        }
+    }
+}
+
+impl Bus for MockBus {
+    fn open(&mut self, bus: u8, device: u8, _clock_hz: u32, _mode: u8) -> io::Result<()> {
+        self.device = Some((bus, device));
+        Ok(())
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.device = None;
+        Ok(())
+    }
+
+    fn transfer(&mut self, data: &mut [u8]) -> io::Result<()> {
+        let (bus, device) = self.get_device()?;
+        if data.len() > self.read_data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Cannot transfer {} bytes with the SPI device", data.len()),
+            ));
+        }
+        self.writes.push((bus, device, data.to_vec()));
+        for b in data.iter_mut() {
+            *b = self.read_data.pop_front().expect("Length validated above");
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let (bus, device) = self.get_device()?;
+        self.writes.push((bus, device, data.to_vec()));
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! SPI access commands for EndBASIC.
+
+use crate::gpio::CATEGORY;
+use crate::i2c::parse_int;
+use async_trait::async_trait;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Expr, Value, VarType};
+use endbasic_core::exec::{Clearable, Machine};
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Symbols,
+};
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+mod fakes;
+pub use fakes::MockBus;
+pub(crate) use fakes::NoopBus;
+
+/// Generic abstraction over an SPI bus to back all EndBASIC commands.
+pub trait Bus {
+    /// Opens the device selected by the `device` chip select line on the SPI `bus` for subsequent
+    /// transfers, using the given `clock_hz` frequency and SPI `mode` (between 0 and 3).
+    ///
+    /// It is OK to open a device multiple times without calling `close()` in-between.
+    fn open(&mut self, bus: u8, device: u8, clock_hz: u32, mode: u8) -> io::Result<()>;
+
+    /// Closes the currently-open device, if any.
+    fn close(&mut self) -> io::Result<()>;
+
+    /// Sends `data` to the currently-open device and replaces it with the bytes received at the
+    /// same time.
+    fn transfer(&mut self, data: &mut [u8]) -> io::Result<()>;
+
+    /// Sends `data` to the currently-open device, discarding any received bytes.
+    fn write(&mut self, data: &[u8]) -> io::Result<()>;
+}
+
+/// Closes the open SPI device in a best-effort manner.
+pub(crate) struct BusClearable {
+    bus: Rc<RefCell<dyn Bus>>,
+}
+
+impl BusClearable {
+    /// Creates a new clearable for `bus`.
+    pub(crate) fn new(bus: Rc<RefCell<dyn Bus>>) -> Box<Self> {
+        Box::from(Self { bus })
+    }
+}
+
+impl Clearable for BusClearable {
+    fn reset_state(&self, _syms: &mut Symbols) {
+        let _ = self.bus.borrow_mut().close();
+    }
+}
+
+/// The `SPI_OPEN` command.
+pub struct SpiOpenCommand {
+    metadata: CallableMetadata,
+    bus: Rc<RefCell<dyn Bus>>,
+}
+
+impl SpiOpenCommand {
+    /// Creates a new instance of the command.
+    pub fn new(bus: Rc<RefCell<dyn Bus>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SPI_OPEN", VarType::Void)
+                .with_syntax("bus%, device%, clock%, mode%")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Selects an SPI device for transfers.
+Before an SPI device such as a display, an LED strip, or an ADC chip can be talked to, the bus it \
+is attached to and the chip select line it uses must be selected.  On a Raspberry Pi, the bus% is \
+typically 0 and the device% is 0 or 1 for the CE0 and CE1 pins.
+The clock% is the frequency of the bus in Hz and the mode% is the SPI mode between 0 and 3, both \
+of which are specified by the device's datasheet.
+Only one device can be open at any given time.  It is OK to open a different device without \
+closing the previous one first, and CLEAR closes the open device.",
+                )
+                .build(),
+            bus,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SpiOpenCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [ArgSpan { expr: Some(bus), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(device), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(clock), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(mode), sep: ArgSep::End, .. }] =>
+            {
+                let bus = parse_int(bus, machine, "Bus number", u8::MAX as i32).await?;
+                let device = parse_int(device, machine, "Device number", u8::MAX as i32).await?;
+                let clock_pos = clock.start_pos();
+                let clock = parse_int(clock, machine, "Clock frequency", i32::MAX).await?;
+                if clock == 0 {
+                    return Err(CallError::ArgumentError(
+                        clock_pos,
+                        "Clock frequency 0 must be positive".to_owned(),
+                    ));
+                }
+                let mode = parse_int(mode, machine, "Mode", 3).await?;
+                self.bus.borrow_mut().open(bus as u8, device as u8, clock as u32, mode as u8)?;
+                Ok(())
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `SPI_TRANSFER` command.
+pub struct SpiTransferCommand {
+    metadata: CallableMetadata,
+    bus: Rc<RefCell<dyn Bus>>,
+}
+
+impl SpiTransferCommand {
+    /// Creates a new instance of the command.
+    pub fn new(bus: Rc<RefCell<dyn Bus>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SPI_TRANSFER", VarType::Void)
+                .with_syntax("vref1 [, .., vrefN]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Exchanges bytes with the open SPI device.
+SPI transfers are full-duplex: for every byte sent to the device, a byte is received at the same \
+time.  This sends the values of vref1..vrefN, each of which must be between 0 and 255, in a \
+single transfer and then stores the received bytes into the same variables, in order.  The \
+device must have been selected with SPI_OPEN first.
+For example, to query an ADC chip, set the variables to the command bytes that the chip expects \
+and read the conversion result from them after the transfer.",
+                )
+                .build(),
+            bus,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SpiTransferCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+
+        let mut symspans = Vec::with_capacity(span.args.len());
+        let mut data = Vec::with_capacity(span.args.len());
+        for arg in &span.args {
+            match (arg.expr.as_ref(), arg.sep) {
+                (Some(expr @ Expr::Symbol(symspan)), sep)
+                    if sep == ArgSep::Long || sep == ArgSep::End =>
+                {
+                    data.push(parse_int(expr, machine, "Byte", u8::MAX as i32).await? as u8);
+                    symspans.push(symspan);
+                }
+                _ => return Err(CallError::SyntaxError),
+            }
+        }
+
+        self.bus.borrow_mut().transfer(&mut data)?;
+
+        for (symspan, b) in symspans.into_iter().zip(data) {
+            machine
+                .get_mut_symbols()
+                .set_var(&symspan.vref, Value::Integer(i32::from(b)))
+                .map_err(|e| CallError::ArgumentError(symspan.pos, format!("{}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// The `SPI_WRITE` command.
+pub struct SpiWriteCommand {
+    metadata: CallableMetadata,
+    bus: Rc<RefCell<dyn Bus>>,
+}
+
+impl SpiWriteCommand {
+    /// Creates a new instance of the command.
+    pub fn new(bus: Rc<RefCell<dyn Bus>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SPI_WRITE", VarType::Void)
+                .with_syntax("byte1% [, .., byteN%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Sends bytes to the open SPI device.
+Sends all of byte1%..byteN%, each of which must be between 0 and 255, in a single transfer and \
+discards the bytes received from the device.  This is useful to drive output-only devices such \
+as LED strips.  The device must have been selected with SPI_OPEN first.",
+                )
+                .build(),
+            bus,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SpiWriteCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        if span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+
+        let mut data = Vec::with_capacity(span.args.len());
+        for arg in &span.args {
+            match (arg.expr.as_ref(), arg.sep) {
+                (Some(expr), sep) if sep == ArgSep::Long || sep == ArgSep::End => {
+                    data.push(parse_int(expr, machine, "Byte", u8::MAX as i32).await? as u8);
+                }
+                _ => return Err(CallError::SyntaxError),
+            }
+        }
+
+        self.bus.borrow_mut().write(&data)?;
+        Ok(())
+    }
+}
+
+/// Adds all symbols provided by this module to the given `machine`.
+pub fn add_all(machine: &mut Machine, bus: Rc<RefCell<dyn Bus>>) {
+    machine.add_clearable(BusClearable::new(bus.clone()));
+    machine.add_command(SpiOpenCommand::new(bus.clone()));
+    machine.add_command(SpiTransferCommand::new(bus.clone()));
+    machine.add_command(SpiWriteCommand::new(bus));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    /// Creates a tester with the SPI commands backed by a new mock bus, which is returned too.
+    fn mock_tester() -> (Tester, Rc<RefCell<MockBus>>) {
+        let bus = Rc::from(RefCell::from(MockBus::default()));
+        let mut t = Tester::empty();
+        add_all(t.get_machine(), bus.clone());
+        (t, bus)
+    }
+
+    /// Tests that all SPI operations delegate to the real bus implementation, which defaults to
+    /// the no-op backend when using the tester.  All other tests in this file use a mock bus.
+    #[test]
+    fn test_real_backend() {
+        check_stmt_err(
+            "1:1: In call to SPI_OPEN: SPI backend not compiled in",
+            "SPI_OPEN 0, 0, 1000000, 0",
+        );
+        Tester::default()
+            .run("a = 1: SPI_TRANSFER a")
+            .expect_err("1:8: In call to SPI_TRANSFER: SPI backend not compiled in")
+            .expect_var("a", 1)
+            .check();
+        check_stmt_err("1:1: In call to SPI_WRITE: SPI backend not compiled in", "SPI_WRITE 1");
+    }
+
+    #[test]
+    fn test_spi_write_and_transfer() {
+        let (mut t, bus) = mock_tester();
+        bus.borrow_mut().add_read_data(&[0, 2, 255]);
+        t.run(
+            "SPI_OPEN 0, 1, 1000000, 3: SPI_WRITE 250, 0.2
+            a = 1: b% = 128: c = 0: SPI_TRANSFER a, b%, c
+            SPI_OPEN 1, 0, 500, 0: SPI_WRITE 7",
+        )
+        .expect_var("a", 0)
+        .expect_var("b", 2)
+        .expect_var("c", 255)
+        .check();
+        assert_eq!(
+            &[(0, 1, vec![250, 0]), (0, 1, vec![1, 128, 0]), (1, 0, vec![7])],
+            bus.borrow().writes()
+        );
+    }
+
+    #[test]
+    fn test_spi_clear_closes() {
+        let (mut t, bus) = mock_tester();
+        t.get_machine().add_command(crate::exec::ClearCommand::new());
+        t.run("SPI_OPEN 0, 0, 100, 0: CLEAR: SPI_WRITE 1")
+            .expect_err("1:31: In call to SPI_WRITE: SPI device not open; use SPI_OPEN first")
+            .check();
+        assert!(bus.borrow().writes().is_empty());
+    }
+
+    #[test]
+    fn test_spi_open_errors() {
+        for args in &["", "1, 2, 3", "1, 2, 3; 4", "1, 2, 3, 4, 5"] {
+            check_stmt_err(
+                "1:1: In call to SPI_OPEN: expected bus%, device%, clock%, mode%",
+                &format!("SPI_OPEN {}", args),
+            );
+        }
+
+        check_stmt_err(
+            "1:1: In call to SPI_OPEN: 1:10: Bus number 256 must be between 0 and 255",
+            "SPI_OPEN 256, 0, 100, 0",
+        );
+        check_stmt_err(
+            "1:1: In call to SPI_OPEN: 1:13: Device number -1 must be between 0 and 255",
+            "SPI_OPEN 0, -1, 100, 0",
+        );
+        check_stmt_err(
+            "1:1: In call to SPI_OPEN: 1:16: Clock frequency 0 must be positive",
+            "SPI_OPEN 0, 0, 0, 0",
+        );
+        check_stmt_err(
+            "1:1: In call to SPI_OPEN: 1:21: Mode 4 must be between 0 and 3",
+            "SPI_OPEN 0, 0, 100, 4",
+        );
+    }
+
+    #[test]
+    fn test_spi_transfer_errors() {
+        check_stmt_err(
+            "1:1: In call to SPI_TRANSFER: expected vref1 [, .., vrefN]",
+            "SPI_TRANSFER",
+        );
+        check_stmt_err(
+            "1:1: In call to SPI_TRANSFER: expected vref1 [, .., vrefN]",
+            "SPI_TRANSFER 3",
+        );
+        check_stmt_err(
+            "1:1: In call to SPI_TRANSFER: expected vref1 [, .., vrefN]",
+            "SPI_TRANSFER a; b",
+        );
+        check_stmt_err("1:14: Undefined variable a", "SPI_TRANSFER a");
+
+        let (mut t, _bus) = mock_tester();
+        t.run("a = 300: SPI_TRANSFER a")
+            .expect_err("1:10: In call to SPI_TRANSFER: 1:23: Byte 300 must be between 0 and 255")
+            .expect_var("a", 300)
+            .check();
+
+        let (mut t, _bus) = mock_tester();
+        t.run("a = 1: SPI_TRANSFER a")
+            .expect_err("1:8: In call to SPI_TRANSFER: SPI device not open; use SPI_OPEN first")
+            .expect_var("a", 1)
+            .check();
+
+        let (mut t, _bus) = mock_tester();
+        t.run("SPI_OPEN 0, 0, 100, 0: a = 1: SPI_TRANSFER a")
+            .expect_err(
+                "1:31: In call to SPI_TRANSFER: Cannot transfer 1 bytes with the SPI device",
+            )
+            .expect_var("a", 1)
+            .check();
+    }
+
+    #[test]
+    fn test_spi_write_errors() {
+        check_stmt_err("1:1: In call to SPI_WRITE: expected byte1% [, .., byteN%]", "SPI_WRITE");
+        check_stmt_err(
+            "1:1: In call to SPI_WRITE: expected byte1% [, .., byteN%]",
+            "SPI_WRITE 1; 2",
+        );
+
+        check_stmt_err(
+            "1:1: In call to SPI_WRITE: 1:14: Byte 256 must be between 0 and 255",
+            "SPI_WRITE 1, 256",
+        );
+
+        let (mut t, _bus) = mock_tester();
+        t.run("SPI_WRITE 1")
+            .expect_err("1:1: In call to SPI_WRITE: SPI device not open; use SPI_OPEN first")
+            .check();
+    }
+}
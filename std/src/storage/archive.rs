@@ -0,0 +1,347 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Zip archive-based implementation of the storage system.
+
+use crate::storage::{guess_kind, Drive, DriveFactory, DriveFiles, Metadata};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+/// Returns the offset of the local timezone, which we assume all zip timestamps are in.
+fn local_offset() -> time::UtcOffset {
+    match time::UtcOffset::current_local_offset() {
+        Ok(offset) => offset,
+        Err(_) => time::UtcOffset::UTC,
+    }
+}
+
+/// Converts a zip timestamp, which has no timezone information, to a local date.
+fn zip_to_date(dt: DateTime) -> io::Result<time::OffsetDateTime> {
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid date: {}", e));
+    let month = time::Month::try_from(dt.month()).map_err(invalid)?;
+    let date =
+        time::Date::from_calendar_date(i32::from(dt.year()), month, dt.day()).map_err(invalid)?;
+    let time = time::Time::from_hms(dt.hour(), dt.minute(), dt.second()).map_err(invalid)?;
+    Ok(time::PrimitiveDateTime::new(date, time).assume_offset(local_offset()))
+}
+
+/// Converts a local `date` to a zip timestamp.
+///
+/// Dates that cannot be represented in a zip archive are clamped to the archive's epoch.
+fn date_to_zip(date: time::OffsetDateTime) -> DateTime {
+    let date = date.to_offset(local_offset());
+    match u16::try_from(date.year()) {
+        Ok(year) => DateTime::from_date_and_time(
+            year,
+            u8::from(date.month()),
+            date.day(),
+            date.hour(),
+            date.minute(),
+            date.second(),
+        )
+        .unwrap_or_default(),
+        Err(_) => DateTime::default(),
+    }
+}
+
+/// Contents of a zip archive and their metadata, keyed by file name.
+pub(crate) type ZipEntries = BTreeMap<String, (Metadata, Vec<u8>)>;
+
+/// Reads the files at the top level of the zip archive in `path` along with their metadata.
+///
+/// Directories and files within subdirectories are silently ignored.
+pub(crate) fn read_zip(path: &Path) -> io::Result<ZipEntries> {
+    let file = File::open(path)?;
+    let mut archive = ZipArchive::new(io::BufReader::new(file))?;
+    let mut entries = BTreeMap::default();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if !file.is_file() || file.name().contains('/') {
+            continue;
+        }
+
+        let date = zip_to_date(file.last_modified())?;
+        let mut content = vec![];
+        file.read_to_end(&mut content)?;
+        let length = content.len() as u64;
+        let kind = guess_kind(file.name());
+        let metadata = Metadata { date, length, kind, read_only: false };
+        entries.insert(file.name().to_owned(), (metadata, content));
+    }
+    Ok(entries)
+}
+
+/// Writes `entries` to a new zip archive in `path`, replacing any previous archive.
+///
+/// The new archive is written to a temporary file first so that the original is not corrupted if
+/// anything goes wrong.
+pub(crate) fn write_zip(path: &Path, entries: &ZipEntries) -> io::Result<()> {
+    let mut temp = path.to_owned().into_os_string();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    let mut writer = ZipWriter::new(io::BufWriter::new(File::create(&temp)?));
+    for (name, (metadata, content)) in entries {
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(date_to_zip(metadata.date));
+        writer.start_file(name, options)?;
+        writer.write_all(content)?;
+    }
+    writer.finish()?.flush()?;
+
+    fs::rename(temp, path)
+}
+
+/// A drive that is backed by a zip archive.
+///
+/// The contents of the archive are loaded into memory when the drive is created.  Any
+/// modification to the drive rewrites the whole archive on disk, which is fine for the small
+/// archives that we expect to see.
+pub struct ZipDrive {
+    /// Path to the archive backing this drive.  The archive need not exist, in which case it is
+    /// created when the first file is written.
+    path: PathBuf,
+
+    /// Contents of the archive and their metadata.  Directories and files within subdirectories
+    /// are not accessible through this interface, so they are not tracked.
+    entries: ZipEntries,
+}
+
+impl ZipDrive {
+    /// Creates a new drive backed by the archive in `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+
+        let entries = match read_zip(&path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::default(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// Writes all in-memory entries to the archive on disk.
+    fn write_archive(&self) -> io::Result<()> {
+        write_zip(&self.path, &self.entries)
+    }
+}
+
+#[async_trait(?Send)]
+impl Drive for ZipDrive {
+    async fn delete(&mut self, name: &str) -> io::Result<()> {
+        match self.entries.remove(name) {
+            Some(_) => self.write_archive(),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found")),
+        }
+    }
+
+    async fn enumerate(&self) -> io::Result<DriveFiles> {
+        let mut entries = BTreeMap::default();
+        for (name, (metadata, _content)) in &self.entries {
+            entries.insert(name.clone(), metadata.clone());
+        }
+        Ok(DriveFiles::new(entries, None, None))
+    }
+
+    async fn get(&self, name: &str) -> io::Result<String> {
+        match self.entries.get(name) {
+            Some((_metadata, content)) => match str::from_utf8(content) {
+                Ok(content) => Ok(content.to_owned()),
+                Err(e) => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid file content: {}", e),
+                )),
+            },
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found")),
+        }
+    }
+
+    async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
+        let metadata = Metadata {
+            date: time::OffsetDateTime::now_utc().to_offset(local_offset()),
+            length: content.len() as u64,
+            kind: guess_kind(name),
+            read_only: false,
+        };
+        self.entries.insert(name.to_owned(), (metadata, content.as_bytes().to_owned()));
+        self.write_archive()
+    }
+}
+
+/// Factory for zip archive-backed drives.
+#[derive(Default)]
+pub struct ZipDriveFactory {}
+
+impl DriveFactory for ZipDriveFactory {
+    fn create(&self, target: &str) -> io::Result<Box<dyn Drive>> {
+        if !target.is_empty() {
+            Ok(Box::from(ZipDrive::new(target)?))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Must specify a file to mount a zip-backed drive",
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::future::block_on;
+
+    /// Creates a zip archive in `path` with the given `files`, all of them with a deterministic
+    /// modification time.
+    fn write_archive(path: &Path, files: &[(&str, &str)]) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        let options = FileOptions::default()
+            .last_modified_time(DateTime::from_date_and_time(2022, 5, 6, 9, 37, 54).unwrap());
+        for (name, content) in files {
+            if name.ends_with('/') {
+                writer.add_directory(*name, options).unwrap();
+            } else {
+                writer.start_file(*name, options).unwrap();
+                writer.write_all(content.as_bytes()).unwrap();
+            }
+        }
+        writer.finish().unwrap();
+    }
+
+    /// Reads the zip archive in `path` and returns the names and contents of all of its files.
+    fn read_archive(path: &Path) -> Vec<(String, String)> {
+        let mut archive = ZipArchive::new(File::open(path).unwrap()).unwrap();
+        let mut files = vec![];
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).unwrap();
+            let mut content = String::new();
+            file.read_to_string(&mut content).unwrap();
+            files.push((file.name().to_owned(), content));
+        }
+        files
+    }
+
+    #[test]
+    fn test_zipdrive_missing_archive_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let drive = ZipDrive::new(dir.path().join("missing.zip")).unwrap();
+        assert!(block_on(drive.enumerate()).unwrap().dirents().is_empty());
+        assert!(!dir.path().join("missing.zip").exists());
+    }
+
+    #[test]
+    fn test_zipdrive_enumerate_and_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.zip");
+        write_archive(
+            &path,
+            &[("empty.bas", ""), ("game.bas", "PRINT 1\n"), ("data/", ""), ("data/x.dat", "x")],
+        );
+
+        let drive = ZipDrive::new(&path).unwrap();
+        let files = block_on(drive.enumerate()).unwrap();
+        assert_eq!(2, files.dirents().len());
+        let date =
+            zip_to_date(DateTime::from_date_and_time(2022, 5, 6, 9, 37, 54).unwrap()).unwrap();
+        let kind = Some("text/x-basic".to_owned());
+        assert_eq!(
+            &Metadata { date, length: 0, kind: kind.clone(), read_only: false },
+            files.dirents().get("empty.bas").unwrap()
+        );
+        assert_eq!(
+            &Metadata { date, length: 8, kind, read_only: false },
+            files.dirents().get("game.bas").unwrap()
+        );
+
+        assert_eq!("PRINT 1\n", block_on(drive.get("game.bas")).unwrap());
+        assert_eq!(io::ErrorKind::NotFound, block_on(drive.get("x.dat")).unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_zipdrive_put_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.zip");
+        write_archive(&path, &[("a.bas", "first"), ("b.bas", "second")]);
+
+        let mut drive = ZipDrive::new(&path).unwrap();
+        block_on(drive.put("c.bas", "third")).unwrap();
+        block_on(drive.put("a.bas", "replaced")).unwrap();
+        block_on(drive.delete("b.bas")).unwrap();
+        assert_eq!(io::ErrorKind::NotFound, block_on(drive.delete("b.bas")).unwrap_err().kind());
+
+        assert_eq!(
+            vec![
+                ("a.bas".to_owned(), "replaced".to_owned()),
+                ("c.bas".to_owned(), "third".to_owned())
+            ],
+            read_archive(&path)
+        );
+        assert!(!dir.path().join("test.zip.tmp").exists());
+
+        let drive = ZipDrive::new(&path).unwrap();
+        assert_eq!("third", block_on(drive.get("c.bas")).unwrap());
+    }
+
+    #[test]
+    fn test_zipdrive_put_creates_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("new.zip");
+
+        let mut drive = ZipDrive::new(&path).unwrap();
+        block_on(drive.put("a.bas", "content")).unwrap();
+        assert_eq!(vec![("a.bas".to_owned(), "content".to_owned())], read_archive(&path));
+    }
+
+    #[test]
+    fn test_zipdrive_invalid_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.zip");
+        fs::write(&path, "not a zip file").unwrap();
+        assert!(ZipDrive::new(&path).is_err());
+    }
+
+    #[test]
+    fn test_zipdrive_date_roundtrip() {
+        let date = zip_to_date(DateTime::from_date_and_time(2023, 1, 2, 3, 4, 6).unwrap()).unwrap();
+        let dt = date_to_zip(date);
+        assert_eq!(
+            (2023, 1, 2, 3, 4, 6),
+            (dt.year(), dt.month(), dt.day(), dt.hour(), dt.minute(), dt.second())
+        );
+    }
+
+    #[test]
+    fn test_zipdrive_factory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.zip");
+        write_archive(&path, &[("a.bas", "content")]);
+
+        let factory = ZipDriveFactory::default();
+        let drive = factory.create(&path.display().to_string()).unwrap();
+        assert_eq!("content", block_on(drive.get("a.bas")).unwrap());
+
+        assert_eq!(
+            "Must specify a file to mount a zip-backed drive",
+            format!("{}", factory.create("").err().unwrap())
+        );
+    }
+}
@@ -0,0 +1,369 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Caching decorator for drives backed by slow media.
+
+use crate::storage::{Drive, DriveFiles, FileAcls, FileRevision, SyncReport};
+use async_trait::async_trait;
+use endbasic_core::exec::ClockFn;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Default amount of time during which cached data is considered fresh.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Returns a clock based on the system's wall clock.
+///
+/// We use the `time` crate instead of `std::time::Instant` because the latter is not available
+/// on the web.
+pub(crate) fn system_clock() -> ClockFn {
+    Rc::from(|| {
+        let elapsed = time::OffsetDateTime::now_utc() - time::OffsetDateTime::UNIX_EPOCH;
+        Duration::try_from(elapsed).unwrap_or_default()
+    })
+}
+
+/// A cached value along with the time at which it was fetched.
+struct Entry<T> {
+    fetched: Duration,
+    value: T,
+}
+
+/// A drive that wraps another drive and caches the results of read operations for a period of
+/// time.
+///
+/// Writes go straight to the wrapped drive and update the cache as they succeed, so changes done
+/// through this drive are visible right away.  Changes done by others to the underlying medium
+/// are only noticed once the cached data expires or once the cache is flushed.
+pub struct CachingDrive {
+    /// The drive whose contents are cached.
+    inner: Box<dyn Drive>,
+
+    /// Amount of time during which cached data is considered fresh.
+    ttl: Duration,
+
+    /// Clock used to determine the age of cached data.
+    clock: ClockFn,
+
+    /// Cached contents of the individual files.
+    files: RefCell<HashMap<String, Entry<String>>>,
+
+    /// Cached listing of the drive.
+    listing: RefCell<Option<Entry<DriveFiles>>>,
+}
+
+impl CachingDrive {
+    /// Creates a new drive that caches the contents of `inner` for `ttl`.
+    pub fn new(inner: Box<dyn Drive>, ttl: Duration) -> Self {
+        Self::with_clock(inner, ttl, system_clock())
+    }
+
+    /// Creates a new drive that caches the contents of `inner` for `ttl` as measured by `clock`.
+    pub fn with_clock(inner: Box<dyn Drive>, ttl: Duration, clock: ClockFn) -> Self {
+        Self {
+            inner,
+            ttl,
+            clock,
+            files: RefCell::from(HashMap::default()),
+            listing: RefCell::from(None),
+        }
+    }
+
+    /// Returns true if an entry fetched at `fetched` is still fresh.
+    fn is_fresh(&self, fetched: Duration) -> bool {
+        (self.clock)().saturating_sub(fetched) < self.ttl
+    }
+}
+
+#[async_trait(?Send)]
+impl Drive for CachingDrive {
+    async fn delete(&mut self, name: &str) -> io::Result<()> {
+        self.files.borrow_mut().remove(name);
+        *self.listing.borrow_mut() = None;
+        self.inner.delete(name).await
+    }
+
+    async fn enumerate(&self) -> io::Result<DriveFiles> {
+        if let Some(entry) = self.listing.borrow().as_ref() {
+            if self.is_fresh(entry.fetched) {
+                return Ok(entry.value.clone());
+            }
+        }
+
+        let fetched = (self.clock)();
+        let files = self.inner.enumerate().await?;
+        *self.listing.borrow_mut() = Some(Entry { fetched, value: files.clone() });
+        Ok(files)
+    }
+
+    fn flush(&mut self) {
+        self.files.borrow_mut().clear();
+        *self.listing.borrow_mut() = None;
+        self.inner.flush();
+    }
+
+    async fn get(&self, name: &str) -> io::Result<String> {
+        if let Some(entry) = self.files.borrow().get(name) {
+            if self.is_fresh(entry.fetched) {
+                return Ok(entry.value.clone());
+            }
+        }
+
+        let fetched = (self.clock)();
+        let content = self.inner.get(name).await?;
+        self.files.borrow_mut().insert(name.to_owned(), Entry { fetched, value: content.clone() });
+        Ok(content)
+    }
+
+    async fn get_acls(&self, name: &str) -> io::Result<FileAcls> {
+        self.inner.get_acls(name).await
+    }
+
+    async fn history(&self, name: &str) -> io::Result<Vec<FileRevision>> {
+        self.inner.history(name).await
+    }
+
+    async fn poll_changes(&self) -> io::Result<Vec<String>> {
+        let changes = self.inner.poll_changes().await?;
+        if !changes.is_empty() {
+            let mut files = self.files.borrow_mut();
+            for name in &changes {
+                files.remove(name);
+            }
+            *self.listing.borrow_mut() = None;
+        }
+        Ok(changes)
+    }
+
+    async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
+        self.files.borrow_mut().remove(name);
+        *self.listing.borrow_mut() = None;
+        self.inner.put(name, content).await?;
+        let fetched = (self.clock)();
+        self.files
+            .borrow_mut()
+            .insert(name.to_owned(), Entry { fetched, value: content.to_owned() });
+        Ok(())
+    }
+
+    async fn sync(&mut self) -> io::Result<SyncReport> {
+        self.files.borrow_mut().clear();
+        *self.listing.borrow_mut() = None;
+        self.inner.sync().await
+    }
+
+    async fn update_acls(
+        &mut self,
+        name: &str,
+        add: &FileAcls,
+        remove: &FileAcls,
+    ) -> io::Result<()> {
+        self.inner.update_acls(name, add, remove).await
+    }
+
+    fn system_path(&self, name: &str) -> Option<PathBuf> {
+        self.inner.system_path(name)
+    }
+
+    fn unwatch(&self, name: &str) {
+        self.inner.unwatch(name)
+    }
+
+    async fn watch(&self, name: &str) -> io::Result<()> {
+        self.inner.watch(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryDrive;
+    use futures_lite::future::block_on;
+    use std::cell::Cell;
+
+    /// Drive that counts the operations that reach it.
+    #[derive(Default)]
+    struct CountingDrive {
+        inner: InMemoryDrive,
+        enumerates: Rc<Cell<usize>>,
+        gets: Rc<Cell<usize>>,
+        changes: Rc<RefCell<Vec<String>>>,
+    }
+
+    #[async_trait(?Send)]
+    impl Drive for CountingDrive {
+        async fn delete(&mut self, name: &str) -> io::Result<()> {
+            self.inner.delete(name).await
+        }
+
+        async fn enumerate(&self) -> io::Result<DriveFiles> {
+            self.enumerates.set(self.enumerates.get() + 1);
+            self.inner.enumerate().await
+        }
+
+        async fn get(&self, name: &str) -> io::Result<String> {
+            self.gets.set(self.gets.get() + 1);
+            self.inner.get(name).await
+        }
+
+        async fn poll_changes(&self) -> io::Result<Vec<String>> {
+            Ok(self.changes.borrow_mut().drain(..).collect())
+        }
+
+        async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
+            self.inner.put(name, content).await
+        }
+    }
+
+    /// Container for a caching drive and the state needed to inspect its behavior.
+    struct TestContext {
+        drive: CachingDrive,
+        now: Rc<Cell<Duration>>,
+        enumerates: Rc<Cell<usize>>,
+        gets: Rc<Cell<usize>>,
+        changes: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl TestContext {
+        /// Creates a caching drive with a TTL of 10 seconds on top of an in-memory drive that
+        /// contains the given `files`.
+        fn new(files: &[(&str, &str)]) -> Self {
+            let mut inner = CountingDrive::default();
+            for (name, content) in files {
+                block_on(inner.inner.put(name, content)).unwrap();
+            }
+            let enumerates = inner.enumerates.clone();
+            let gets = inner.gets.clone();
+            let changes = inner.changes.clone();
+
+            let now = Rc::from(Cell::new(Duration::from_secs(1000)));
+            let clock = {
+                let now = now.clone();
+                Rc::from(move || now.get())
+            };
+            let drive = CachingDrive::with_clock(Box::from(inner), Duration::from_secs(10), clock);
+            Self { drive, now, enumerates, gets, changes }
+        }
+
+        /// Advances the fake clock by `secs` seconds.
+        fn advance(&self, secs: u64) {
+            self.now.set(self.now.get() + Duration::from_secs(secs));
+        }
+    }
+
+    #[test]
+    fn test_cachingdrive_get_is_cached_until_expiration() {
+        let context = TestContext::new(&[("a.bas", "content")]);
+
+        assert_eq!("content", block_on(context.drive.get("a.bas")).unwrap());
+        context.advance(9);
+        assert_eq!("content", block_on(context.drive.get("a.bas")).unwrap());
+        assert_eq!(1, context.gets.get());
+
+        context.advance(1);
+        assert_eq!("content", block_on(context.drive.get("a.bas")).unwrap());
+        assert_eq!(2, context.gets.get());
+    }
+
+    #[test]
+    fn test_cachingdrive_get_errors_are_not_cached() {
+        let context = TestContext::new(&[]);
+        block_on(context.drive.get("a.bas")).unwrap_err();
+        block_on(context.drive.get("a.bas")).unwrap_err();
+        assert_eq!(2, context.gets.get());
+    }
+
+    #[test]
+    fn test_cachingdrive_enumerate_is_cached_until_expiration() {
+        let context = TestContext::new(&[("a.bas", "content")]);
+
+        assert_eq!(1, block_on(context.drive.enumerate()).unwrap().dirents().len());
+        context.advance(5);
+        assert_eq!(1, block_on(context.drive.enumerate()).unwrap().dirents().len());
+        assert_eq!(1, context.enumerates.get());
+
+        context.advance(5);
+        assert_eq!(1, block_on(context.drive.enumerate()).unwrap().dirents().len());
+        assert_eq!(2, context.enumerates.get());
+    }
+
+    #[test]
+    fn test_cachingdrive_put_writes_through() {
+        let mut context = TestContext::new(&[("a.bas", "old")]);
+
+        assert_eq!("old", block_on(context.drive.get("a.bas")).unwrap());
+        assert_eq!(1, block_on(context.drive.enumerate()).unwrap().dirents().len());
+
+        block_on(context.drive.put("a.bas", "new")).unwrap();
+        block_on(context.drive.put("b.bas", "other")).unwrap();
+        assert_eq!("new", block_on(context.drive.get("a.bas")).unwrap());
+        assert_eq!("other", block_on(context.drive.get("b.bas")).unwrap());
+        assert_eq!(1, context.gets.get());
+
+        assert_eq!(2, block_on(context.drive.enumerate()).unwrap().dirents().len());
+        assert_eq!(2, context.enumerates.get());
+    }
+
+    #[test]
+    fn test_cachingdrive_delete_writes_through() {
+        let mut context = TestContext::new(&[("a.bas", "content")]);
+
+        assert_eq!("content", block_on(context.drive.get("a.bas")).unwrap());
+        assert_eq!(1, block_on(context.drive.enumerate()).unwrap().dirents().len());
+
+        block_on(context.drive.delete("a.bas")).unwrap();
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            block_on(context.drive.get("a.bas")).unwrap_err().kind()
+        );
+        assert_eq!(0, block_on(context.drive.enumerate()).unwrap().dirents().len());
+    }
+
+    #[test]
+    fn test_cachingdrive_flush() {
+        let mut context = TestContext::new(&[("a.bas", "content")]);
+
+        block_on(context.drive.get("a.bas")).unwrap();
+        block_on(context.drive.enumerate()).unwrap();
+        context.drive.flush();
+        block_on(context.drive.get("a.bas")).unwrap();
+        block_on(context.drive.enumerate()).unwrap();
+        assert_eq!(2, context.gets.get());
+        assert_eq!(2, context.enumerates.get());
+    }
+
+    #[test]
+    fn test_cachingdrive_poll_changes_invalidates() {
+        let context = TestContext::new(&[("a.bas", "content"), ("b.bas", "other")]);
+
+        block_on(context.drive.get("a.bas")).unwrap();
+        block_on(context.drive.get("b.bas")).unwrap();
+        block_on(context.drive.enumerate()).unwrap();
+        assert!(block_on(context.drive.poll_changes()).unwrap().is_empty());
+
+        context.changes.borrow_mut().push("a.bas".to_owned());
+        assert_eq!(vec!["a.bas".to_owned()], block_on(context.drive.poll_changes()).unwrap());
+        block_on(context.drive.get("a.bas")).unwrap();
+        block_on(context.drive.get("b.bas")).unwrap();
+        block_on(context.drive.enumerate()).unwrap();
+        assert_eq!(3, context.gets.get());
+        assert_eq!(2, context.enumerates.get());
+    }
+}
@@ -15,30 +15,37 @@
 
 //! File system interaction.
 
-use crate::console::Console;
-use crate::storage::Storage;
+use crate::console::{Console, Pager};
+use crate::storage::{
+    CsvReadCommand, CsvWriteCommand, LoadStateCommand, SaveStateCommand, Storage, SyncReport,
+};
 use async_trait::async_trait;
-use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Value, VarType};
+use endbasic_core::ast::{
+    ArgSep, ArgSpan, BuiltinCallSpan, Expr, FunctionCallSpan, Value, VarType,
+};
 use endbasic_core::exec::Machine;
 use endbasic_core::syms::{
-    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult,
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
+    FunctionResult, Symbol, Symbols,
 };
 use std::cell::RefCell;
 use std::cmp;
 use std::io;
 use std::rc::Rc;
 use std::str;
-use time::format_description;
+use time::format_description::{self, FormatItem};
 
 use super::time_format_error_to_io_error;
 
 /// Category description for all symbols provided by this module.
-const CATEGORY: &str = "File system
+pub(super) const CATEGORY: &str = "File system
 The EndBASIC storage subsystem is organized as a collection of drives, each identified by a \
 case-insensitive name.  Drives can be backed by a multitude of file systems with different \
 behaviors, and their targets are specified as URIs.  Special targets include: memory://, which \
 points to an in-memory read/write drive; and demos://, which points to a read-only drive with \
-sample programs.  Other targets may be available such as file:// to access a local directory or \
+sample programs.  Other targets may be available such as file:// to access a local directory, \
+git:// to access a local directory that records every change in a git repository, zip:// to \
+access the contents of a zip archive, http:// and https:// to read files from a web server, or \
 local:// to access web-local storage, depending on the context.  The output of the MOUNT command \
 can help to identify which targets are available.
 All commands that operate with files take a path.  Paths in EndBASIC can be of the form \
@@ -46,19 +53,27 @@ FILENAME.EXT, in which case they refer to a file in the current drive; or DRIVE:
 DRIVE:FILENAME.EXT, in which case they refer to a file in the specified drive.  Note that the \
 slash before the file name is currently optional because EndBASIC does not support directories \
 yet.  Furthermore, if .EXT is missing, a .BAS extension is assumed.
+Commands like DIR, COPY, and KILL also accept the * and ? wildcards in the file name to operate \
+on all matching files at once: * matches any sequence of characters and ? matches any single \
+character, without regard to case.
 Be aware that the commands below must be invoked using proper EndBASIC syntax.  In particular, \
 this means that path arguments must be double-quoted and multiple arguments have to be separated \
 by a comma (not a space).  If you have used commands like CD, DIR, or MOUNT in other contexts, \
 this is likely to confuse you.
 See the \"Stored program\" help topic for information on how to load, modify, and save programs.";
 
+/// Returns the format to use to display file dates.
+fn date_format() -> Vec<FormatItem<'static>> {
+    format_description::parse("[year]-[month]-[day] [hour]:[minute]")
+        .expect("Hardcoded format must be valid")
+}
+
 /// Shows the contents of the given storage location.
 async fn show_dir(storage: &Storage, console: &mut dyn Console, path: &str) -> io::Result<()> {
     let canonical_path = storage.make_canonical(path)?;
     let files = storage.enumerate(path).await?;
 
-    let format = format_description::parse("[year]-[month]-[day] [hour]:[minute]")
-        .expect("Hardcoded format must be valid");
+    let format = date_format();
 
     console.print("")?;
     console.print(&format!("    Directory of {}", canonical_path))?;
@@ -98,7 +113,11 @@ fn show_drives(storage: &Storage, console: &mut dyn Console) -> io::Result<()> {
     let num_drives = drive_info.len();
     for (name, uri) in drive_info {
         let filler = " ".repeat(max_length - name.len());
-        console.print(&format!("    {}{}    {}", name, filler, uri))?;
+        if storage.is_read_only(name)? {
+            console.print(&format!("    {}{}    {} (read-only)", name, filler, uri))?;
+        } else {
+            console.print(&format!("    {}{}    {}", name, filler, uri))?;
+        }
     }
     console.print("")?;
     console.print(&format!("    {} drive(s)", num_drives))?;
@@ -106,6 +125,66 @@ fn show_drives(storage: &Storage, console: &mut dyn Console) -> io::Result<()> {
     Ok(())
 }
 
+/// The `BACKUP` command.
+pub struct BackupCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl BackupCommand {
+    /// Creates a new `BACKUP` command that saves whole drives in `storage` into archives.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("BACKUP", VarType::Void)
+                .with_syntax("drive_name$, archive$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Saves all files in a drive into an archive.
+Stores every file in drive_name$, which is specified without a colon at the end, along with its \
+modification time into the zip archive$, replacing the archive if it already exists.  Use \
+RECOVER to bring the files back, possibly into a different drive, as in BACKUP \"LOCAL\", \
+\"LOCAL:BACKUP.ZIP\" followed by RECOVER \"LOCAL:BACKUP.ZIP\", \"CLOUD\".
+The archive$ must live in a drive backed by a local directory, such as the ones mounted with the \
+file:// scheme.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for BackupCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (name, target) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(name), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(target), sep: ArgSep::End, .. }] => {
+                (name, target)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let mut args = vec![];
+        for expr in [name, target] {
+            match expr.eval(machine.get_mut_symbols()).await? {
+                Value::Text(t) => args.push(t),
+                _ => {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "BACKUP requires strings as the drive name and the archive path".to_owned(),
+                    ))
+                }
+            }
+        }
+
+        self.storage.borrow_mut().backup(&args[0], &args[1]).await?;
+        Ok(())
+    }
+}
+
 /// The `CD` command.
 pub struct CdCommand {
     metadata: CallableMetadata,
@@ -152,6 +231,209 @@ impl Command for CdCommand {
     }
 }
 
+/// The `CHANGEDFILE` function.
+pub struct ChangedfileFunction {
+    metadata: CallableMetadata,
+}
+
+impl ChangedfileFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CHANGEDFILE", VarType::Text)
+                .with_syntax("")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the location of the last modified watched file.
+When used in combination with ON FILECHANGE to set a file change handler, this function returns \
+the location of the file that triggered the handler.  If this is called before any change is \
+notified, returns the empty string.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ChangedfileFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        if !span.args.is_empty() {
+            return Err(CallError::SyntaxError);
+        }
+        // Like ERRMSG, this relies on a private variable set by the machine when it calls the
+        // handler because functions do not have access to the machine.
+        match symbols.get_auto("0filechange") {
+            Some(Symbol::Variable(v @ Value::Text(_))) => Ok(v.clone()),
+            Some(_) => panic!("Internal symbol must be of a specific type"),
+            None => Ok(Value::Text("".to_owned())),
+        }
+    }
+}
+
+/// The `COPY` command.
+pub struct CopyCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl CopyCommand {
+    /// Creates a new `COPY` command that copies files within `storage`.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("COPY", VarType::Void)
+                .with_syntax("src$, dest$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Copies one or more files.
+The src$ path may contain the * and ? wildcards in its file name to copy all matching files at \
+once, as in COPY \"A:*.DAT\", \"B:\".  If dest$ names a drive without a file name, the files \
+are copied into that drive with their original names.  Otherwise, src$ must match a single file \
+and dest$ is the name of the copy.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for CopyCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (src, dest) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(src), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(dest), sep: ArgSep::End, .. }] => {
+                (src, dest)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let mut paths = vec![];
+        for expr in [src, dest] {
+            match expr.eval(machine.get_mut_symbols()).await? {
+                Value::Text(t) => paths.push(t),
+                _ => {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "COPY requires strings as the paths".to_owned(),
+                    ))
+                }
+            }
+        }
+
+        self.storage.borrow_mut().copy(&paths[0], &paths[1]).await?;
+        Ok(())
+    }
+}
+
+/// The `DF` command.
+pub struct DfCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl DfCommand {
+    /// Creates a new `DF` command that prints the disk usage of the drives in `storage` to the
+    /// `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>, storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("DF", VarType::Void)
+                .with_syntax("[drive_name$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Displays the disk usage of the drives.
+For every drive, shows the number of bytes used by its files, the number of bytes that can \
+still be saved, and the maximum number of bytes allowed by the drive's quota.  Values that are \
+unknown or unlimited are shown as a dash.
+With no arguments, shows all mounted drives.  Otherwise, shows only drive_name$, which is \
+specified without a colon at the end.",
+                )
+                .build(),
+            console,
+            storage,
+        })
+    }
+
+    /// Prints the disk usage of the drives in `names`.
+    async fn show_usage(&self, names: &[String]) -> io::Result<()> {
+        fn format_bytes(bytes: Option<u64>) -> String {
+            match bytes {
+                Some(bytes) => format!("{}", bytes),
+                None => "-".to_owned(),
+            }
+        }
+
+        let mut rows = vec![];
+        for name in names {
+            let usage = self.storage.borrow().usage(name).await?;
+            rows.push((
+                name.to_uppercase(),
+                format!("{}", usage.used),
+                format_bytes(usage.free),
+                format_bytes(usage.quota),
+            ));
+        }
+        let max_length = rows.iter().fold("Name".len(), |max, row| cmp::max(max, row.0.len()));
+
+        let mut console = self.console.borrow_mut();
+        console.print("")?;
+        let filler = " ".repeat(max_length - "Name".len());
+        console.print(&format!(
+            "    Name{}    {:>12}    {:>12}    {:>12}",
+            filler, "Used", "Free", "Quota"
+        ))?;
+        for (name, used, free, quota) in rows {
+            let filler = " ".repeat(max_length - name.len());
+            console.print(&format!(
+                "    {}{}    {:>12}    {:>12}    {:>12}",
+                name, filler, used, free, quota
+            ))?;
+        }
+        console.print("")?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for DfCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let names = match span.args.as_slice() {
+            [] => self
+                .storage
+                .borrow()
+                .mounted()
+                .keys()
+                .map(|name| (*name).to_owned())
+                .collect::<Vec<String>>(),
+            [ArgSpan { expr: Some(name), sep: ArgSep::End, .. }] => {
+                match name.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(name) => vec![name],
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            name.start_pos(),
+                            "Drive name must be a string".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+        self.show_usage(&names).await?;
+        Ok(())
+    }
+}
+
 /// The `DIR` command.
 pub struct DirCommand {
     metadata: CallableMetadata,
@@ -166,7 +448,11 @@ impl DirCommand {
             metadata: CallableMetadataBuilder::new("DIR", VarType::Void)
                 .with_syntax("[path$]")
                 .with_category(CATEGORY)
-                .with_description("Displays the list of files on the current or given path.")
+                .with_description(
+                    "Displays the list of files on the current or given path.
+The file name in path$ may contain the * and ? wildcards to only display the matching files, \
+as in DIR \"*.BAS\".",
+                )
                 .build(),
             console,
             storage,
@@ -206,6 +492,141 @@ impl Command for DirCommand {
     }
 }
 
+/// The `FLUSH` command.
+pub struct FlushCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl FlushCommand {
+    /// Creates a new `FLUSH` command that discards the data cached by the drives in `storage`.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("FLUSH", VarType::Void)
+                .with_syntax("[drive_name$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Discards the data cached by the drives.
+Drives backed by slow media, such as the ones mounted from the cloud or from a web server, \
+remember the files they fetch for a short period of time to speed up repeated accesses.  As a \
+result, changes done to those files from elsewhere may not be visible right away.  Use this \
+command to discard the cached data so that the next operations fetch fresh contents.
+With no arguments, flushes all drives.  Otherwise, flushes only drive_name$, which is specified \
+without a colon at the end.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for FlushCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        match span.args.as_slice() {
+            [] => {
+                self.storage.borrow_mut().flush(None)?;
+                Ok(())
+            }
+            [ArgSpan { expr: Some(name), sep: ArgSep::End, .. }] => {
+                match name.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(name) => {
+                        self.storage.borrow_mut().flush(Some(&name))?;
+                        Ok(())
+                    }
+                    _ => Err(CallError::ArgumentError(
+                        name.start_pos(),
+                        "Drive name must be a string".to_owned(),
+                    )),
+                }
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `HISTORY` command.
+pub struct HistoryCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl HistoryCommand {
+    /// Creates a new `HISTORY` command that lists the revisions of files in `storage` on the
+    /// `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>, storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("HISTORY", VarType::Void)
+                .with_syntax("path$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Displays the list of saved revisions of a file.
+Only drives that keep track of past versions of their files support this command, such as \
+the ones mounted with the git:// scheme, which record every change to a file in a git \
+repository.  Revisions are listed from newest to oldest.",
+                )
+                .build(),
+            console,
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for HistoryCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let path = match span.args.as_slice() {
+            [ArgSpan { expr: Some(path), sep: ArgSep::End, .. }] => {
+                match path.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(t) => t,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            path.start_pos(),
+                            "HISTORY requires a string as the path".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let revisions = self.storage.borrow().history(&path).await?;
+        let canonical_path = self.storage.borrow().make_canonical(&path)?;
+
+        let format = date_format();
+
+        let mut console = self.console.borrow_mut();
+        console.print("")?;
+        console.print(&format!("    History of {}", canonical_path))?;
+        console.print("")?;
+        console.print("    Modified            Revision    Description")?;
+        for revision in &revisions {
+            let id: String = revision.id.chars().take(8).collect();
+            console.print(&format!(
+                "    {}    {:8}    {}",
+                revision.date.format(&format).map_err(time_format_error_to_io_error)?,
+                id,
+                revision.message,
+            ))?;
+        }
+        if !revisions.is_empty() {
+            console.print("")?;
+        }
+        console.print(&format!("    {} revision(s)", revisions.len()))?;
+        console.print("")?;
+        Ok(())
+    }
+}
+
 /// The `MOUNT` command.
 pub struct MountCommand {
     metadata: CallableMetadata,
@@ -218,13 +639,16 @@ impl MountCommand {
     pub fn new(console: Rc<RefCell<dyn Console>>, storage: Rc<RefCell<Storage>>) -> Rc<Self> {
         Rc::from(Self {
             metadata: CallableMetadataBuilder::new("MOUNT", VarType::Void)
-                .with_syntax("[target$ AS drive_name$]")
+                .with_syntax("[target$ AS drive_name$[, mode$]]")
                 .with_category(CATEGORY)
                 .with_description(
                     "Lists the mounted drives or mounts a new drive.
 With no arguments, prints a list of mounted drives and their targets.
 With two arguments, mounts the drive_name$ to point to the target$.  Drive names are specified \
-without a colon at the end, and targets are given in the form of a URI.",
+without a colon at the end, and targets are given in the form of a URI.
+The optional mode$ can be \"rw\" to mount the drive in read/write mode, which is the default, \
+or \"ro\" to mount it in read-only mode.  Attempts to save, delete, or otherwise modify files \
+in a read-only drive fail.",
                 )
                 .build(),
             console,
@@ -245,34 +669,62 @@ impl Command for MountCommand {
                 show_drives(&self.storage.borrow_mut(), &mut *self.console.borrow_mut())?;
                 Ok(())
             }
-            [ArgSpan { expr: Some(target), sep: ArgSep::As, .. }, ArgSpan { expr: Some(name), sep: ArgSep::End, .. }] =>
-            {
-                let name = match name.eval(machine.get_mut_symbols()).await? {
-                    Value::Text(t) => t,
-                    _ => {
-                        return Err(CallError::ArgumentError(
-                            name.start_pos(),
-                            "Drive name must be a string".to_owned(),
-                        ))
-                    }
-                };
-                let target = match target.eval(machine.get_mut_symbols()).await? {
-                    Value::Text(t) => t,
-                    _ => {
-                        return Err(CallError::ArgumentError(
-                            target.start_pos(),
-                            "Mount target must be a string".to_owned(),
-                        ))
-                    }
-                };
-                self.storage.borrow_mut().mount(&name, &target)?;
-                Ok(())
+            [ArgSpan { expr: Some(target), sep: ArgSep::As, .. }, ArgSpan { expr: Some(name), sep: ArgSep::End, .. }] => {
+                self.mount(target, name, None, machine).await
+            }
+            [ArgSpan { expr: Some(target), sep: ArgSep::As, .. }, ArgSpan { expr: Some(name), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(mode), sep: ArgSep::End, .. }] => {
+                self.mount(target, name, Some(mode), machine).await
             }
             _ => Err(CallError::SyntaxError),
         }
     }
 }
 
+impl MountCommand {
+    /// Mounts the drive `name` pointing to `target` with the optional `mode`.
+    async fn mount(
+        &self,
+        target: &Expr,
+        name: &Expr,
+        mode: Option<&Expr>,
+        machine: &mut Machine,
+    ) -> CommandResult {
+        let name = match name.eval(machine.get_mut_symbols()).await? {
+            Value::Text(t) => t,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    name.start_pos(),
+                    "Drive name must be a string".to_owned(),
+                ))
+            }
+        };
+        let target = match target.eval(machine.get_mut_symbols()).await? {
+            Value::Text(t) => t,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    target.start_pos(),
+                    "Mount target must be a string".to_owned(),
+                ))
+            }
+        };
+        let read_only = match mode {
+            None => false,
+            Some(mode) => match mode.eval(machine.get_mut_symbols()).await? {
+                Value::Text(t) if t.eq_ignore_ascii_case("ro") => true,
+                Value::Text(t) if t.eq_ignore_ascii_case("rw") => false,
+                _ => {
+                    return Err(CallError::ArgumentError(
+                        mode.start_pos(),
+                        "Mount mode must be \"ro\" or \"rw\"".to_owned(),
+                    ))
+                }
+            },
+        };
+        self.storage.borrow_mut().mount(&name, &target, read_only)?;
+        Ok(())
+    }
+}
+
 /// The `PWD` command.
 pub struct PwdCommand {
     metadata: CallableMetadata,
@@ -327,21 +779,322 @@ impl Command for PwdCommand {
     }
 }
 
-/// The `UNMOUNT` command.
-pub struct UnmountCommand {
+/// The `RECOVER` command.
+pub struct RecoverCommand {
     metadata: CallableMetadata,
     storage: Rc<RefCell<Storage>>,
 }
 
-impl UnmountCommand {
-    /// Creates a new `UNMOUNT` command.
+impl RecoverCommand {
+    /// Creates a new `RECOVER` command that restores archived drives into `storage`.
     pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
         Rc::from(Self {
-            metadata: CallableMetadataBuilder::new("UNMOUNT", VarType::Void)
-                .with_syntax("drive_name$")
+            metadata: CallableMetadataBuilder::new("RECOVER", VarType::Void)
+                .with_syntax("archive$, drive_name$")
                 .with_category(CATEGORY)
                 .with_description(
-                    "Unmounts the given drive.
+                    "Restores all files saved in an archive into a drive.
+Writes every file stored in the zip archive$ into drive_name$, which is specified without a \
+colon at the end, replacing any existing files with the same names and leaving the rest of the \
+files in the drive untouched.  The archive is typically created with BACKUP.  Restored files get \
+the current time as their modification time.
+The archive$ must live in a drive backed by a local directory, such as the ones mounted with the \
+file:// scheme.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for RecoverCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (source, name) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(source), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(name), sep: ArgSep::End, .. }] => {
+                (source, name)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let mut args = vec![];
+        for expr in [source, name] {
+            match expr.eval(machine.get_mut_symbols()).await? {
+                Value::Text(t) => args.push(t),
+                _ => {
+                    return Err(CallError::ArgumentError(
+                        expr.start_pos(),
+                        "RECOVER requires strings as the archive path and the drive name"
+                            .to_owned(),
+                    ))
+                }
+            }
+        }
+
+        self.storage.borrow_mut().recover(&args[0], &args[1]).await?;
+        Ok(())
+    }
+}
+
+/// The `STAT` command.
+pub struct StatCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl StatCommand {
+    /// Creates a new `STAT` command that shows the details of files in `storage` on the `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>, storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("STAT", VarType::Void)
+                .with_syntax("path$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Displays the details of a file.
+The details include the size of the file, its last modification time, its type if it can be \
+determined, whether it can be modified, and the list of principals it is shared with for \
+drives that support sharing.  Unlike other file commands, no extension is assumed if path$ \
+lacks one.",
+                )
+                .build(),
+            console,
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for StatCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let path = match span.args.as_slice() {
+            [ArgSpan { expr: Some(path), sep: ArgSep::End, .. }] => {
+                match path.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(t) => t,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            path.start_pos(),
+                            "STAT requires a string as the path".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let metadata = self.storage.borrow().stat(&path).await?;
+        let readers = match self.storage.borrow().get_acls(&path).await {
+            Ok(acls) if acls.readers().is_empty() => "none".to_owned(),
+            Ok(acls) => acls.readers().join(", "),
+            // Drives that do not support ACLs report a generic error, which we don't want to
+            // propagate because the ACLs are only a small part of the output.
+            Err(e) if e.kind() == io::ErrorKind::Other => "unknown".to_owned(),
+            Err(e) => return Err(e.into()),
+        };
+        let canonical_path = self.storage.borrow().make_canonical(&path)?;
+
+        let format = date_format();
+
+        let mut console = self.console.borrow_mut();
+        console.print("")?;
+        console.print(&format!("    File:      {}", canonical_path))?;
+        console.print(&format!("    Size:      {} bytes", metadata.length))?;
+        console.print(&format!(
+            "    Modified:  {}",
+            metadata.date.format(&format).map_err(time_format_error_to_io_error)?
+        ))?;
+        console
+            .print(&format!("    Kind:      {}", metadata.kind.as_deref().unwrap_or("unknown")))?;
+        console.print(&format!(
+            "    Access:    {}",
+            if metadata.read_only { "read-only" } else { "read-write" }
+        ))?;
+        console.print(&format!("    Readers:   {}", readers))?;
+        console.print("")?;
+        Ok(())
+    }
+}
+
+/// The `SYNC` command.
+pub struct SyncCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl SyncCommand {
+    /// Creates a new `SYNC` command that applies the changes queued while offline by the drives in
+    /// `storage` and reports the outcome to `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>, storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SYNC", VarType::Void)
+                .with_syntax("[drive_name$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Uploads the changes done to drives while offline.
+Drives backed by remote media, such as the ones mounted from the cloud, keep working when the \
+network connection drops: the files you read before losing connectivity remain available and \
+any changes you make are queued.  Use this command once connectivity is back to apply the queued \
+changes.
+If a file was modified elsewhere while you were offline, your offline version is saved next to \
+it with a -CONFLICT suffix in its name so that you can reconcile the two by hand.
+With no arguments, synchronizes all drives.  Otherwise, synchronizes only drive_name$, which is \
+specified without a colon at the end.",
+                )
+                .build(),
+            console,
+            storage,
+        })
+    }
+
+    /// Prints the outcome of a synchronization described by `report`.
+    fn show_report(&self, report: SyncReport) -> io::Result<()> {
+        let mut console = self.console.borrow_mut();
+        console.print("")?;
+        if report.synced.is_empty() && report.conflicts.is_empty() {
+            console.print("    Nothing to synchronize")?;
+            console.print("")?;
+            return Ok(());
+        }
+        for name in &report.synced {
+            console.print(&format!("    Synchronized {}", name))?;
+        }
+        for (name, copy) in &report.conflicts {
+            match copy {
+                Some(copy) => console.print(&format!(
+                    "    Conflict in {}; offline version saved as {}",
+                    name, copy
+                ))?,
+                None => {
+                    console.print(&format!("    Conflict in {}; kept the remote version", name))?
+                }
+            }
+        }
+        console.print("")?;
+        console.print(&format!(
+            "    {} file(s) synchronized, {} conflict(s)",
+            report.synced.len(),
+            report.conflicts.len()
+        ))?;
+        console.print("")?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SyncCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let report = match span.args.as_slice() {
+            [] => self.storage.borrow_mut().sync(None).await?,
+            [ArgSpan { expr: Some(name), sep: ArgSep::End, .. }] => {
+                match name.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(name) => self.storage.borrow_mut().sync(Some(&name)).await?,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            name.start_pos(),
+                            "Drive name must be a string".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+        self.show_report(report)?;
+        Ok(())
+    }
+}
+
+/// The `TYPE` command.
+pub struct TypeCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl TypeCommand {
+    /// Creates a new `TYPE` command that prints files from `storage` to the `console`.
+    pub fn new(console: Rc<RefCell<dyn Console>>, storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("TYPE", VarType::Void)
+                .with_syntax("path$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Displays the contents of a file.
+Unlike LOAD, this does not replace the stored program, so it is safe to use to inspect data \
+files or other programs while there are unsaved changes.  Unlike other file commands, no \
+extension is assumed if path$ lacks one.
+On interactive consoles, the output pauses after every screenful.  Press any key to continue \
+or press Q or ESC to stop.",
+                )
+                .build(),
+            console,
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for TypeCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let path = match span.args.as_slice() {
+            [ArgSpan { expr: Some(path), sep: ArgSep::End, .. }] => {
+                match path.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(t) => t,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            path.start_pos(),
+                            "TYPE requires a string as the path".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let content = self.storage.borrow().get(&path).await?;
+
+        let mut console = self.console.borrow_mut();
+        let mut pager = Pager::new(&mut *console)?;
+        for line in content.lines() {
+            if !pager.print(line).await? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `UNMOUNT` command.
+pub struct UnmountCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl UnmountCommand {
+    /// Creates a new `UNMOUNT` command.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("UNMOUNT", VarType::Void)
+                .with_syntax("drive_name$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Unmounts the given drive.
 Drive names are specified without a colon at the end.",
                 )
                 .build(),
@@ -376,6 +1129,110 @@ impl Command for UnmountCommand {
     }
 }
 
+/// The `UNWATCH` command.
+pub struct UnwatchCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl UnwatchCommand {
+    /// Creates a new `UNWATCH` command that stops watching files in `storage`.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("UNWATCH", VarType::Void)
+                .with_syntax("path$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Stops watching a file for modifications.
+See WATCH for details.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for UnwatchCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let path = match span.args.as_slice() {
+            [ArgSpan { expr: Some(path), sep: ArgSep::End, .. }] => {
+                match path.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(t) => t,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            path.start_pos(),
+                            "UNWATCH requires a string as the path".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.storage.borrow().unwatch(&path)?;
+        Ok(())
+    }
+}
+
+/// The `WATCH` command.
+pub struct WatchCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl WatchCommand {
+    /// Creates a new `WATCH` command that watches files in `storage` for modifications.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("WATCH", VarType::Void)
+                .with_syntax("path$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Watches a file for modifications done outside of EndBASIC.
+When a watched file changes, for example because it was edited in an external editor, the \
+handler installed with ON FILECHANGE GOSUB is called while a program runs, and CHANGEDFILE$ \
+returns the location of the modified file.  Only some drives can detect such modifications, \
+like the ones mounted with the file:// scheme; watching files in other drives has no effect.  \
+Unlike other file commands, no extension is assumed if path$ lacks one.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for WatchCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let path = match span.args.as_slice() {
+            [ArgSpan { expr: Some(path), sep: ArgSep::End, .. }] => {
+                match path.eval(machine.get_mut_symbols()).await? {
+                    Value::Text(t) => t,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            path.start_pos(),
+                            "WATCH requires a string as the path".to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        self.storage.borrow().watch(&path).await?;
+        Ok(())
+    }
+}
+
 /// Adds all file system manipulation commands for `storage` to the `machine`, using `console` to
 /// display information.
 pub fn add_all(
@@ -383,36 +1240,215 @@ pub fn add_all(
     console: Rc<RefCell<dyn Console>>,
     storage: Rc<RefCell<Storage>>,
 ) {
+    machine.add_command(BackupCommand::new(storage.clone()));
     machine.add_command(CdCommand::new(storage.clone()));
+    machine.add_function(ChangedfileFunction::new());
+    machine.add_command(CopyCommand::new(storage.clone()));
+    machine.add_command(CsvReadCommand::new(storage.clone()));
+    machine.add_command(CsvWriteCommand::new(storage.clone()));
+    machine.add_command(DfCommand::new(console.clone(), storage.clone()));
     machine.add_command(DirCommand::new(console.clone(), storage.clone()));
+    machine.add_command(FlushCommand::new(storage.clone()));
+    machine.add_command(HistoryCommand::new(console.clone(), storage.clone()));
+    machine.add_command(LoadStateCommand::new(storage.clone()));
     machine.add_command(MountCommand::new(console.clone(), storage.clone()));
     machine.add_command(PwdCommand::new(console.clone(), storage.clone()));
-    machine.add_command(UnmountCommand::new(storage));
+    machine.add_command(RecoverCommand::new(storage.clone()));
+    machine.add_command(SaveStateCommand::new(storage.clone()));
+    machine.add_command(StatCommand::new(console.clone(), storage.clone()));
+    machine.add_command(SyncCommand::new(console.clone(), storage.clone()));
+    machine.add_command(TypeCommand::new(console.clone(), storage.clone()));
+    machine.add_command(UnmountCommand::new(storage.clone()));
+    machine.add_command(UnwatchCommand::new(storage.clone()));
+    machine.add_command(WatchCommand::new(storage));
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::storage::{DirectoryDriveFactory, DiskSpace, Drive, InMemoryDrive};
-    use crate::testutils::*;
-    use futures_lite::future::block_on;
-    use std::collections::BTreeMap;
+#[cfg(test)]
+mod tests {
+    use crate::console::{CharsXY, ClearType, Key};
+    use crate::storage::{
+        DirectoryDrive, DirectoryDriveFactory, DiskSpace, Drive, DriveFiles, FileAcls,
+        FileRevision, InMemoryDrive, SyncReport,
+    };
+    use crate::testutils::*;
+    use async_trait::async_trait;
+    use futures_lite::future::block_on;
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_cd_ok() {
+        let mut t = Tester::default();
+        t.get_storage().borrow_mut().mount("other", "memory://", false).unwrap();
+        t.run("CD \"other:\"").check();
+        assert_eq!("OTHER:/", t.get_storage().borrow().cwd());
+        t.run("CD \"memory:/\"").check();
+        assert_eq!("MEMORY:/", t.get_storage().borrow().cwd());
+    }
+
+    #[test]
+    fn test_backup_and_recover() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut t = Tester::default().write_file("a.bas", "first").write_file("b.txt", "second");
+        let drive = DirectoryDrive::new(dir.path()).unwrap();
+        t.get_storage().borrow_mut().attach("d", "file://", Box::from(drive)).unwrap();
+
+        let checker = t.run(
+            r#"BACKUP "memory", "d:backup.zip"
+MOUNT "memory://" AS "other"
+RECOVER "d:backup.zip", "other""#,
+        );
+        // The archive is not text so it cannot be validated as part of the drive contents.
+        std::fs::remove_file(dir.path().join("backup.zip")).unwrap();
+        checker
+            .expect_file("MEMORY:/a.bas", "first")
+            .expect_file("MEMORY:/b.txt", "second")
+            .expect_file("OTHER:/a.bas", "first")
+            .expect_file("OTHER:/b.txt", "second")
+            .check();
+    }
+
+    #[test]
+    fn test_backup_errors() {
+        check_stmt_err("1:1: In call to BACKUP: expected drive_name$, archive$", "BACKUP");
+        check_stmt_err(
+            "1:1: In call to BACKUP: expected drive_name$, archive$",
+            r#"BACKUP "a"; "b""#,
+        );
+        check_stmt_err(
+            "1:1: In call to BACKUP: 1:8: BACKUP requires strings as the drive name and the archive path",
+            r#"BACKUP 1, "b""#,
+        );
+        check_stmt_err(
+            "1:1: In call to BACKUP: Cannot use 'b.zip' as an archive: drive is not a local directory",
+            r#"BACKUP "memory", "b.zip""#,
+        );
+    }
+
+    #[test]
+    fn test_cd_errors() {
+        check_stmt_err("1:1: In call to CD: Drive 'A' is not mounted", "CD \"A:\"");
+        check_stmt_err("1:1: In call to CD: expected path$", "CD");
+        check_stmt_err("1:1: In call to CD: expected path$", "CD 2, 3");
+        check_stmt_err("1:1: In call to CD: 1:4: CD requires a string as the path", "CD 2");
+    }
+
+    #[test]
+    fn test_changedfile_before_change() {
+        check_expr_ok("", "CHANGEDFILE$");
+    }
+
+    #[test]
+    fn test_changedfile_errors() {
+        check_expr_error(
+            "1:10: In call to CHANGEDFILE: expected no arguments nor parenthesis",
+            "CHANGEDFILE$()",
+        );
+        check_expr_error(
+            "1:10: In call to CHANGEDFILE: expected no arguments nor parenthesis",
+            "CHANGEDFILE$(3)",
+        );
+    }
+
+    #[test]
+    fn test_copy_one() {
+        let t = Tester::default();
+        t.get_storage().borrow_mut().mount("other", "memory://", false).unwrap();
+        t.write_file("a.dat", "content")
+            .run(r#"COPY "a.dat", "b.dat": COPY "a.dat", "other:""#)
+            .expect_file("MEMORY:/a.dat", "content")
+            .expect_file("MEMORY:/b.dat", "content")
+            .expect_file("OTHER:/a.dat", "content")
+            .check();
+    }
+
+    #[test]
+    fn test_copy_wildcards() {
+        let t = Tester::default();
+        t.get_storage().borrow_mut().mount("b", "memory://", false).unwrap();
+        t.write_file("one.dat", "1")
+            .write_file("two.DAT", "2")
+            .write_file("three.bas", "3")
+            .run(r#"COPY "memory:*.dat", "b:/""#)
+            .expect_file("MEMORY:/one.dat", "1")
+            .expect_file("MEMORY:/two.DAT", "2")
+            .expect_file("MEMORY:/three.bas", "3")
+            .expect_file("B:/one.dat", "1")
+            .expect_file("B:/two.DAT", "2")
+            .check();
+    }
+
+    #[test]
+    fn test_copy_errors() {
+        check_stmt_err("1:1: In call to COPY: expected src$, dest$", "COPY");
+        check_stmt_err("1:1: In call to COPY: expected src$, dest$", r#"COPY "a""#);
+        check_stmt_err("1:1: In call to COPY: expected src$, dest$", r#"COPY "a"; "b""#);
+        check_stmt_err(
+            "1:1: In call to COPY: 1:11: COPY requires strings as the paths",
+            r#"COPY "a", 1"#,
+        );
+        check_stmt_err("1:1: In call to COPY: Entry not found", r#"COPY "a", "b""#);
+        check_stmt_err("1:1: In call to COPY: No files match '*.dat'", r#"COPY "*.dat", "b""#);
+
+        Tester::default()
+            .write_file("a.dat", "")
+            .write_file("b.dat", "")
+            .run(r#"COPY "*.dat", "c.dat""#)
+            .expect_err(
+                "1:1: In call to COPY: Cannot copy multiple files into the single file 'c.dat'",
+            )
+            .expect_file("MEMORY:/a.dat", "")
+            .expect_file("MEMORY:/b.dat", "")
+            .check();
+    }
 
     #[test]
-    fn test_cd_ok() {
-        let mut t = Tester::default();
-        t.get_storage().borrow_mut().mount("other", "memory://").unwrap();
-        t.run("CD \"other:\"").check();
-        assert_eq!("OTHER:/", t.get_storage().borrow().cwd());
-        t.run("CD \"memory:/\"").check();
-        assert_eq!("MEMORY:/", t.get_storage().borrow().cwd());
+    fn test_df_all() {
+        let mut other = InMemoryDrive::default();
+        other.fake_disk_quota = Some(DiskSpace::new(456, 0));
+        other.fake_disk_free = Some(DiskSpace::new(123, 0));
+
+        let t = Tester::default();
+        t.get_storage().borrow_mut().attach("other", "z://", Box::from(other)).unwrap();
+        t.get_storage().borrow_mut().set_quota("memory", Some(1000)).unwrap();
+        t.write_file("a.bas", "12345")
+            .write_file("b.bas", "67")
+            .run("DF")
+            .expect_prints([
+                "",
+                "    Name              Used            Free           Quota",
+                "    MEMORY               7             993            1000",
+                "    OTHER                0             123             456",
+                "",
+            ])
+            .expect_file("MEMORY:/a.bas", "12345")
+            .expect_file("MEMORY:/b.bas", "67")
+            .check();
     }
 
     #[test]
-    fn test_cd_errors() {
-        check_stmt_err("1:1: In call to CD: Drive 'A' is not mounted", "CD \"A:\"");
-        check_stmt_err("1:1: In call to CD: expected path$", "CD");
-        check_stmt_err("1:1: In call to CD: expected path$", "CD 2, 3");
-        check_stmt_err("1:1: In call to CD: 1:4: CD requires a string as the path", "CD 2");
+    fn test_df_one() {
+        Tester::default()
+            .write_file("a.bas", "12345")
+            .run(r#"DF "memory""#)
+            .expect_prints([
+                "",
+                "    Name              Used            Free           Quota",
+                "    MEMORY               5               -               -",
+                "",
+            ])
+            .expect_file("MEMORY:/a.bas", "12345")
+            .check();
+    }
+
+    #[test]
+    fn test_df_errors() {
+        check_stmt_err("1:1: In call to DF: expected [drive_name$]", r#"DF "a", "b""#);
+        check_stmt_err("1:1: In call to DF: 1:4: Drive name must be a string", "DF 1");
+        check_stmt_err("1:1: In call to DF: Invalid drive name 'a:'", r#"DF "a:""#);
+        check_stmt_err("1:1: In call to DF: Drive 'A' is not mounted", r#"DF "a""#);
     }
 
     #[test]
@@ -563,12 +1599,256 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_dir_wildcards() {
+        Tester::default()
+            .write_file("empty.bas", "")
+            .write_file("some other file.bas", "not empty\n")
+            .write_file("00AAA.BAS", "first\nfile\n")
+            .write_file("not a bas.txt", "")
+            .run(r#"DIR "*.BAS""#)
+            .expect_prints([
+                "",
+                "    Directory of MEMORY:*.BAS",
+                "",
+                "    Modified              Size    Name",
+                "    2020-05-06 09:37        11    00AAA.BAS",
+                "    2020-05-06 09:37         0    empty.bas",
+                "    2020-05-06 09:37        10    some other file.bas",
+                "",
+                "    3 file(s), 21 bytes",
+                "",
+            ])
+            .expect_file("MEMORY:/empty.bas", "")
+            .expect_file("MEMORY:/some other file.bas", "not empty\n")
+            .expect_file("MEMORY:/00AAA.BAS", "first\nfile\n")
+            .expect_file("MEMORY:/not a bas.txt", "")
+            .check();
+    }
+
     #[test]
     fn test_dir_errors() {
         check_stmt_err("1:1: In call to DIR: expected [path$]", "DIR 2, 3");
         check_stmt_err("1:1: In call to DIR: 1:5: DIR requires a string as the path", "DIR 2");
     }
 
+    /// Drive that counts how many times it has been flushed.
+    #[derive(Default)]
+    struct FlushCountingDrive {
+        inner: InMemoryDrive,
+        flushes: Rc<Cell<usize>>,
+    }
+
+    #[async_trait(?Send)]
+    impl Drive for FlushCountingDrive {
+        async fn delete(&mut self, name: &str) -> io::Result<()> {
+            self.inner.delete(name).await
+        }
+
+        async fn enumerate(&self) -> io::Result<DriveFiles> {
+            self.inner.enumerate().await
+        }
+
+        fn flush(&mut self) {
+            self.flushes.set(self.flushes.get() + 1);
+        }
+
+        async fn get(&self, name: &str) -> io::Result<String> {
+            self.inner.get(name).await
+        }
+
+        async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
+            self.inner.put(name, content).await
+        }
+    }
+
+    #[test]
+    fn test_flush() {
+        let a = FlushCountingDrive::default();
+        let a_flushes = a.flushes.clone();
+        let b = FlushCountingDrive::default();
+        let b_flushes = b.flushes.clone();
+
+        let mut t = Tester::default();
+        t.get_storage().borrow_mut().attach("a", "a://", Box::from(a)).unwrap();
+        t.get_storage().borrow_mut().attach("b", "b://", Box::from(b)).unwrap();
+
+        t.run("FLUSH").check();
+        assert_eq!((1, 1), (a_flushes.get(), b_flushes.get()));
+
+        t.run(r#"FLUSH "b""#).check();
+        assert_eq!((1, 2), (a_flushes.get(), b_flushes.get()));
+    }
+
+    #[test]
+    fn test_flush_errors() {
+        check_stmt_err("1:1: In call to FLUSH: expected [drive_name$]", r#"FLUSH "a", "b""#);
+        check_stmt_err("1:1: In call to FLUSH: 1:7: Drive name must be a string", "FLUSH 1");
+        check_stmt_err("1:1: In call to FLUSH: Invalid drive name 'a:'", r#"FLUSH "a:""#);
+        check_stmt_err("1:1: In call to FLUSH: Drive 'A' is not mounted", r#"FLUSH "a""#);
+    }
+
+    /// Drive that reports a fixed outcome when synchronized.
+    struct SyncDrive {
+        report: Option<SyncReport>,
+    }
+
+    #[async_trait(?Send)]
+    impl Drive for SyncDrive {
+        async fn delete(&mut self, _name: &str) -> io::Result<()> {
+            unreachable!();
+        }
+
+        async fn enumerate(&self) -> io::Result<DriveFiles> {
+            Ok(DriveFiles::new(BTreeMap::default(), None, None))
+        }
+
+        async fn get(&self, _name: &str) -> io::Result<String> {
+            unreachable!();
+        }
+
+        async fn put(&mut self, _name: &str, _content: &str) -> io::Result<()> {
+            unreachable!();
+        }
+
+        async fn sync(&mut self) -> io::Result<SyncReport> {
+            Ok(self.report.take().unwrap_or_default())
+        }
+    }
+
+    #[test]
+    fn test_sync_nothing() {
+        Tester::default().run("SYNC").expect_prints(["", "    Nothing to synchronize", ""]).check();
+    }
+
+    #[test]
+    fn test_sync_report() {
+        let drive = SyncDrive {
+            report: Some(SyncReport {
+                synced: vec!["a.bas".to_owned()],
+                conflicts: vec![
+                    ("b.bas".to_owned(), Some("b-CONFLICT.bas".to_owned())),
+                    ("c.bas".to_owned(), None),
+                ],
+            }),
+        };
+        let mut t = Tester::default();
+        t.get_storage().borrow_mut().attach("remote", "remote://", Box::from(drive)).unwrap();
+
+        t.run(r#"SYNC "remote""#)
+            .expect_prints([
+                "",
+                "    Synchronized REMOTE:/a.bas",
+                "    Conflict in REMOTE:/b.bas; offline version saved as REMOTE:/b-CONFLICT.bas",
+                "    Conflict in REMOTE:/c.bas; kept the remote version",
+                "",
+                "    1 file(s) synchronized, 2 conflict(s)",
+                "",
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_sync_errors() {
+        check_stmt_err("1:1: In call to SYNC: expected [drive_name$]", r#"SYNC "a", "b""#);
+        check_stmt_err("1:1: In call to SYNC: 1:6: Drive name must be a string", "SYNC 1");
+        check_stmt_err("1:1: In call to SYNC: Drive 'A' is not mounted", r#"SYNC "a""#);
+    }
+
+    /// Drive that contains no files but that reports a fixed history for `foo.bas`.
+    struct HistoryDrive {}
+
+    #[async_trait(?Send)]
+    impl Drive for HistoryDrive {
+        async fn delete(&mut self, _name: &str) -> io::Result<()> {
+            unreachable!();
+        }
+
+        async fn enumerate(&self) -> io::Result<DriveFiles> {
+            Ok(DriveFiles::new(BTreeMap::default(), None, None))
+        }
+
+        async fn get(&self, _name: &str) -> io::Result<String> {
+            unreachable!();
+        }
+
+        async fn history(&self, name: &str) -> io::Result<Vec<FileRevision>> {
+            if name != "foo.bas" {
+                return Ok(vec![]);
+            }
+            Ok(vec![
+                FileRevision {
+                    id: "0123456789abcdef".to_owned(),
+                    date: time::OffsetDateTime::from_unix_timestamp(1_651_830_000).unwrap(),
+                    message: "Save foo.bas".to_owned(),
+                },
+                FileRevision {
+                    id: "fedcba98".to_owned(),
+                    date: time::OffsetDateTime::from_unix_timestamp(1_588_757_875).unwrap(),
+                    message: "First version".to_owned(),
+                },
+            ])
+        }
+
+        async fn put(&mut self, _name: &str, _content: &str) -> io::Result<()> {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn test_history_some() {
+        let mut t = Tester::default();
+        t.get_storage().borrow_mut().attach("h", "h://", Box::from(HistoryDrive {})).unwrap();
+        t.run(r#"HISTORY "h:foo.bas""#)
+            .expect_prints([
+                "",
+                "    History of H:foo.bas",
+                "",
+                "    Modified            Revision    Description",
+                "    2022-05-06 09:40    01234567    Save foo.bas",
+                "    2020-05-06 09:37    fedcba98    First version",
+                "",
+                "    2 revision(s)",
+                "",
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_history_none() {
+        let mut t = Tester::default();
+        t.get_storage().borrow_mut().attach("h", "h://", Box::from(HistoryDrive {})).unwrap();
+        t.get_storage().borrow_mut().cd("h:").unwrap();
+        t.run(r#"HISTORY "bar.bas""#)
+            .expect_prints([
+                "",
+                "    History of H:bar.bas",
+                "",
+                "    Modified            Revision    Description",
+                "    0 revision(s)",
+                "",
+            ])
+            .check();
+    }
+
+    #[test]
+    fn test_history_errors() {
+        check_stmt_err("1:1: In call to HISTORY: expected path$", "HISTORY");
+        check_stmt_err("1:1: In call to HISTORY: expected path$", r#"HISTORY "a", "b""#);
+        check_stmt_err(
+            "1:1: In call to HISTORY: 1:9: HISTORY requires a string as the path",
+            "HISTORY 3",
+        );
+        check_stmt_err(
+            "1:1: In call to HISTORY: Operation not supported by drive",
+            r#"HISTORY "foo.bas""#,
+        );
+        check_stmt_err(
+            "1:1: In call to HISTORY: Missing file name in path 'memory:'",
+            r#"HISTORY "memory:""#,
+        );
+    }
+
     #[test]
     fn test_mount_list() {
         let mut t = Tester::default();
@@ -608,12 +1888,61 @@ mod tests {
         exp_info.insert("MEMORY", "memory://");
         exp_info.insert("ABC", "memory://");
         assert_eq!(exp_info, t.get_storage().borrow().mounted());
+        assert!(!t.get_storage().borrow().is_read_only("abc").unwrap());
+    }
+
+    #[test]
+    fn test_mount_read_only() {
+        let mut t = Tester::default();
+        t.run(r#"MOUNT "memory://" AS "rw1", "rw""#).check();
+        t.run(r#"MOUNT "memory://" AS "ro1", "ro""#).check();
+        t.run(r#"MOUNT "memory://" AS "ro2", "RO""#).check();
+        assert!(!t.get_storage().borrow().is_read_only("rw1").unwrap());
+        assert!(t.get_storage().borrow().is_read_only("ro1").unwrap());
+        assert!(t.get_storage().borrow().is_read_only("ro2").unwrap());
+
+        t.run("MOUNT")
+            .expect_prints([
+                "",
+                "    Name      Target",
+                "    MEMORY    memory://",
+                "    RO1       memory:// (read-only)",
+                "    RO2       memory:// (read-only)",
+                "    RW1       memory://",
+                "",
+                "    4 drive(s)",
+                "",
+            ])
+            .check();
+
+        Tester::default()
+            .run(r#"MOUNT "memory://" AS "ro1", "ro": KILL "ro1:foo.bas""#)
+            .expect_err("1:35: In call to KILL: Drive 'RO1' is read-only")
+            .check();
     }
 
     #[test]
     fn test_mount_errors() {
-        check_stmt_err("1:1: In call to MOUNT: expected [target$ AS drive_name$]", "MOUNT 1");
-        check_stmt_err("1:1: In call to MOUNT: expected [target$ AS drive_name$]", "MOUNT 1, 2, 3");
+        check_stmt_err(
+            "1:1: In call to MOUNT: expected [target$ AS drive_name$[, mode$]]",
+            "MOUNT 1",
+        );
+        check_stmt_err(
+            "1:1: In call to MOUNT: expected [target$ AS drive_name$[, mode$]]",
+            "MOUNT 1, 2, 3",
+        );
+        check_stmt_err(
+            "1:1: In call to MOUNT: expected [target$ AS drive_name$[, mode$]]",
+            r#"MOUNT "a" AS "b"; "ro""#,
+        );
+        check_stmt_err(
+            r#"1:1: In call to MOUNT: 1:27: Mount mode must be "ro" or "rw""#,
+            r#"MOUNT "memory://" AS "a", "rx""#,
+        );
+        check_stmt_err(
+            r#"1:1: In call to MOUNT: 1:27: Mount mode must be "ro" or "rw""#,
+            r#"MOUNT "memory://" AS "a", 1"#,
+        );
 
         check_stmt_err(
             "1:1: In call to MOUNT: 1:14: Drive name must be a string",
@@ -662,7 +1991,7 @@ mod tests {
             let storage = t.get_storage();
             let storage = &mut *storage.borrow_mut();
             storage.register_scheme("file", Box::from(DirectoryDriveFactory::default()));
-            storage.mount("other", &format!("file://{}", dir.display())).unwrap();
+            storage.mount("other", &format!("file://{}", dir.display()), false).unwrap();
             storage.cd("other:/").unwrap();
         }
 
@@ -676,10 +2005,167 @@ mod tests {
             .check();
     }
 
+    #[test]
+    fn test_recover_errors() {
+        check_stmt_err("1:1: In call to RECOVER: expected archive$, drive_name$", "RECOVER");
+        check_stmt_err(
+            "1:1: In call to RECOVER: expected archive$, drive_name$",
+            r#"RECOVER "a", "b", "c""#,
+        );
+        check_stmt_err(
+            "1:1: In call to RECOVER: 1:14: RECOVER requires strings as the archive path and the drive name",
+            r#"RECOVER "a", 1"#,
+        );
+        check_stmt_err(
+            "1:1: In call to RECOVER: Cannot use 'a.zip' as an archive: drive is not a local directory",
+            r#"RECOVER "a.zip", "memory""#,
+        );
+    }
+
+    #[test]
+    fn test_stat_shared_file() {
+        let mut t = Tester::default().write_file("game.bas", "PRINT 1\n");
+        let acls = FileAcls::default().with_readers(["bob".to_owned(), "alice".to_owned()]);
+        block_on(t.get_storage().borrow_mut().update_acls("game.bas", &acls, &FileAcls::default()))
+            .unwrap();
+        t.run(r#"STAT "game.bas""#)
+            .expect_prints([
+                "",
+                "    File:      MEMORY:game.bas",
+                "    Size:      8 bytes",
+                "    Modified:  2020-05-06 09:37",
+                "    Kind:      text/x-basic",
+                "    Access:    read-write",
+                "    Readers:   alice, bob",
+                "",
+            ])
+            .expect_file("MEMORY:/game.bas", "PRINT 1\n")
+            .check();
+    }
+
+    #[test]
+    fn test_stat_unknown_kind_and_no_readers() {
+        Tester::default()
+            .write_file("data", "")
+            .run(r#"STAT "memory:/data""#)
+            .expect_prints([
+                "",
+                "    File:      MEMORY:/data",
+                "    Size:      0 bytes",
+                "    Modified:  2020-05-06 09:37",
+                "    Kind:      unknown",
+                "    Access:    read-write",
+                "    Readers:   none",
+                "",
+            ])
+            .expect_file("MEMORY:/data", "")
+            .check();
+    }
+
+    #[test]
+    fn test_stat_read_only_file_without_acls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.txt");
+        std::fs::write(&path, "12345").unwrap();
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions).unwrap();
+
+        let mut t = Tester::default();
+        let drive = DirectoryDrive::new(dir.path()).unwrap();
+        t.get_storage().borrow_mut().attach("d", "file://", Box::from(drive)).unwrap();
+        let mut c = t.run(r#"STAT "d:data.txt""#);
+        let output = c.take_captured_out();
+        c.expect_file("D:/data.txt", "12345").check();
+
+        let lines = output
+            .iter()
+            .map(|o| match o {
+                CapturedOut::Print(line) => line.as_str(),
+                _ => panic!("Unexpected output {:?}", o),
+            })
+            .collect::<Vec<&str>>();
+        assert_eq!("    File:      D:data.txt", lines[1]);
+        assert_eq!("    Size:      5 bytes", lines[2]);
+        assert_eq!("    Kind:      text/plain", lines[4]);
+        assert_eq!("    Access:    read-only", lines[5]);
+        assert_eq!("    Readers:   unknown", lines[6]);
+    }
+
+    #[test]
+    fn test_stat_errors() {
+        check_stmt_err("1:1: In call to STAT: expected path$", "STAT");
+        check_stmt_err("1:1: In call to STAT: expected path$", r#"STAT "a", "b""#);
+        check_stmt_err("1:1: In call to STAT: 1:6: STAT requires a string as the path", "STAT 1");
+        check_stmt_err("1:1: In call to STAT: Entry not found", r#"STAT "missing.txt""#);
+        check_stmt_err(
+            "1:1: In call to STAT: Missing file name in path 'memory:'",
+            r#"STAT "memory:""#,
+        );
+    }
+
+    #[test]
+    fn test_type_ok() {
+        Tester::default()
+            .set_program(Some("program.bas"), "Leave me alone")
+            .write_file("data.txt", "first line\n\n  third line\n")
+            .run(r#"TYPE "data.txt""#)
+            .expect_prints(["first line", "", "  third line"])
+            .expect_program(Some("program.bas"), "Leave me alone")
+            .expect_file("MEMORY:/data.txt", "first line\n\n  third line\n")
+            .check();
+    }
+
+    #[test]
+    fn test_type_empty() {
+        Tester::default()
+            .write_file("empty", "")
+            .run(r#"TYPE "memory:/empty""#)
+            .expect_file("MEMORY:/empty", "")
+            .check();
+    }
+
+    #[test]
+    fn test_type_paginates() {
+        let t = Tester::default();
+        t.get_console().borrow_mut().set_interactive(true);
+        t.get_console().borrow_mut().set_size_chars(CharsXY::new(20, 3));
+        let mut t = t
+            .add_input_keys(&[Key::Char(' '), Key::Char('q')])
+            .write_file("data.txt", "1\n2\n3\n4\n5\n");
+        t.run(r#"TYPE "data.txt""#)
+            .expect_output([
+                CapturedOut::Print("1".to_owned()),
+                CapturedOut::Print("2".to_owned()),
+                CapturedOut::Write("-- More --".to_owned()),
+                CapturedOut::Clear(ClearType::CurrentLine),
+                CapturedOut::MoveWithinLine(-10),
+                CapturedOut::Print("3".to_owned()),
+                CapturedOut::Print("4".to_owned()),
+                CapturedOut::Write("-- More --".to_owned()),
+                CapturedOut::Clear(ClearType::CurrentLine),
+                CapturedOut::MoveWithinLine(-10),
+            ])
+            .expect_file("MEMORY:/data.txt", "1\n2\n3\n4\n5\n")
+            .check();
+    }
+
+    #[test]
+    fn test_type_errors() {
+        check_stmt_err("1:1: In call to TYPE: expected path$", "TYPE");
+        check_stmt_err("1:1: In call to TYPE: expected path$", r#"TYPE "a", "b""#);
+        check_stmt_err("1:1: In call to TYPE: 1:6: TYPE requires a string as the path", "TYPE 1");
+        check_stmt_err("1:1: In call to TYPE: Entry not found", r#"TYPE "missing.txt""#);
+        check_stmt_err(
+            "1:1: In call to TYPE: Missing file name in path 'memory:'",
+            r#"TYPE "memory:""#,
+        );
+    }
+
     #[test]
     fn test_unmount_ok() {
         let mut t = Tester::default();
-        t.get_storage().borrow_mut().mount("other", "memory://").unwrap();
+        t.get_storage().borrow_mut().mount("other", "memory://", false).unwrap();
         t.get_storage().borrow_mut().cd("other:").unwrap();
         t.run("UNMOUNT \"memory\"").check();
 
@@ -698,4 +2184,61 @@ mod tests {
         check_stmt_err("1:1: In call to UNMOUNT: Invalid drive name 'a:'", "UNMOUNT \"a:\"");
         check_stmt_err("1:1: In call to UNMOUNT: Drive 'a' is not mounted", "UNMOUNT \"a\"");
     }
+
+    #[test]
+    fn test_watch_and_unwatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.txt");
+
+        let mut t = Tester::default();
+        let drive = DirectoryDrive::new(dir.path()).unwrap();
+        t.get_storage().borrow_mut().attach("d", "file://", Box::from(drive)).unwrap();
+
+        t.run(r#"WATCH "d:data.txt""#).check();
+        std::fs::write(&path, "1").unwrap();
+        assert_eq!(
+            vec!["D:/data.txt".to_owned()],
+            block_on(t.get_storage().borrow().poll_changes()).unwrap()
+        );
+
+        t.run(r#"UNWATCH "d:data.txt""#).expect_file("D:/data.txt", "1").check();
+        std::fs::write(&path, "22").unwrap();
+        assert!(block_on(t.get_storage().borrow().poll_changes()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_watch_unsupported_drive_is_noop() {
+        let mut t = Tester::default().write_file("data.txt", "");
+        t.run(r#"WATCH "data.txt""#).expect_file("MEMORY:/data.txt", "").check();
+        block_on(t.get_storage().borrow_mut().put("data.txt", "new")).unwrap();
+        assert!(block_on(t.get_storage().borrow().poll_changes()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_watch_unwatch_errors() {
+        for cmd in &["UNWATCH", "WATCH"] {
+            check_stmt_err(format!("1:1: In call to {}: expected path$", cmd), cmd);
+            check_stmt_err(
+                format!("1:1: In call to {}: expected path$", cmd),
+                &format!(r#"{} "a", "b""#, cmd),
+            );
+            check_stmt_err(
+                format!(
+                    "1:1: In call to {}: 1:{}: {} requires a string as the path",
+                    cmd,
+                    cmd.len() + 2,
+                    cmd
+                ),
+                &format!("{} 1", cmd),
+            );
+            check_stmt_err(
+                format!("1:1: In call to {}: Missing file name in path 'memory:'", cmd),
+                &format!(r#"{} "memory:""#, cmd),
+            );
+            check_stmt_err(
+                format!("1:1: In call to {}: Drive 'X' is not mounted", cmd),
+                &format!(r#"{} "x:a.bas""#, cmd),
+            );
+        }
+    }
 }
@@ -0,0 +1,583 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Commands to read and write delimiter-separated value files.
+
+use super::cmds::CATEGORY;
+use crate::arrays::expr_to_array_ref;
+use crate::storage::Storage;
+use async_trait::async_trait;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Expr, Value, VarType};
+use endbasic_core::eval;
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Symbol, Symbols,
+};
+use endbasic_core::LineCol;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Parses the `content` of a CSV file into rows of fields separated by `delimiter`.
+///
+/// Fields can be enclosed in double quotes, in which case they can contain the delimiter, line
+/// breaks, and escaped double quotes written as `""`.
+fn parse_csv(content: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut after_quote = false;
+
+    let mut chars = content.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if quoted {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => {
+                    quoted = false;
+                    after_quote = true;
+                }
+                ch => field.push(ch),
+            }
+            continue;
+        }
+
+        match ch {
+            ch if ch == delimiter => {
+                row.push(std::mem::take(&mut field));
+                after_quote = false;
+            }
+            '\r' | '\n' => {
+                if ch == '\r' {
+                    chars.next_if_eq(&'\n');
+                }
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+                after_quote = false;
+            }
+            _ if after_quote => {
+                return Err(format!(
+                    "Unexpected character after closing quote in row {}",
+                    rows.len() + 1
+                ));
+            }
+            '"' if field.is_empty() => quoted = true,
+            ch => field.push(ch),
+        }
+    }
+    if quoted {
+        return Err(format!("Unterminated quoted field in row {}", rows.len() + 1));
+    }
+    if after_quote || !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Formats a single `field` for a CSV file, quoting it if it contains special characters.
+fn format_field(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Converts an array element `value` into the text to store in a CSV file.
+fn value_to_field(value: &Value) -> String {
+    match value {
+        Value::Text(s) => s.clone(),
+        Value::Double(d) => d.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Long(i) => i.to_string(),
+        value => value.clone().to_text(),
+    }
+}
+
+/// Evaluates the path and delimiter arguments shared by the CSV commands.
+///
+/// Returns the path and its position, the array argument, and the delimiter to use.
+async fn parse_args<'a>(
+    args: &'a [ArgSpan],
+    symbols: &mut Symbols,
+) -> Result<(String, LineCol, &'a Expr, char), CallError> {
+    let (path, array, delimiter) = match args {
+        [ArgSpan { expr: Some(path), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(array), sep: ArgSep::End, .. }] => {
+            (path, array, None)
+        }
+        [ArgSpan { expr: Some(path), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(array), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(delimiter), sep: ArgSep::End, .. }] => {
+            (path, array, Some(delimiter))
+        }
+        _ => return Err(CallError::SyntaxError),
+    };
+
+    let path_pos = path.start_pos();
+    let path = match path.eval(symbols).await? {
+        Value::Text(t) => t,
+        _ => {
+            return Err(CallError::ArgumentError(
+                path.start_pos(),
+                "path$ must be a string".to_owned(),
+            ))
+        }
+    };
+
+    let delimiter = match delimiter {
+        Some(expr) => match expr.eval(symbols).await? {
+            Value::Text(t) => {
+                let mut chars = t.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) if ch != '"' && ch != '\n' && ch != '\r' => ch,
+                    _ => {
+                        return Err(CallError::ArgumentError(
+                            expr.start_pos(),
+                            "delimiter$ must be a single character other than a quote or a \
+                             line break"
+                                .to_owned(),
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(CallError::ArgumentError(
+                    expr.start_pos(),
+                    "delimiter$ must be a string".to_owned(),
+                ))
+            }
+        },
+        None => ',',
+    };
+
+    Ok((path, path_pos, array, delimiter))
+}
+
+/// The `CSV_READ` command.
+pub struct CsvReadCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl CsvReadCommand {
+    /// Creates a new `CSV_READ` command that reads files from `storage`.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CSV_READ", VarType::Void)
+                .with_syntax("path$, array[, delimiter$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Loads a file of delimiter-separated values into an array.
+The array is (re)defined as a two-dimensional array with one row per line in the file and as \
+many columns as the longest line has fields, and its previous contents are lost.  Shorter lines \
+are padded with zero values.  If the array does not exist yet, its type comes from the type \
+annotation of its name and defaults to strings; if it exists, it keeps its type.  Fields are \
+converted to the type of the array and empty fields become zero values.
+Fields are separated by commas unless a different delimiter$ is given, such as \";\" or a tab.  \
+Fields can be surrounded by double quotes to include the delimiter or line breaks in them, and \
+a double quote within a quoted field is written as two double quotes.
+Unlike other file commands, no extension is assumed if path$ lacks one.
+See CSV_WRITE for the inverse of this command.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for CsvReadCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (path, path_pos, array, delimiter) =
+            parse_args(&span.args, machine.get_mut_symbols()).await?;
+        let (arrayref, arraypos) = expr_to_array_ref(array).ok_or(CallError::SyntaxError)?;
+
+        let content = self.storage.borrow().get(&path).await?;
+        let rows =
+            parse_csv(&content, delimiter).map_err(|e| CallError::ArgumentError(path_pos, e))?;
+        if rows.is_empty() {
+            return Err(CallError::ArgumentError(path_pos, format!("{} has no rows", path)));
+        }
+        let ncols = rows.iter().map(Vec::len).max().expect("Must have at least one row");
+
+        let symbols = machine.get_mut_symbols();
+        let subtype =
+            match symbols.get(arrayref).map_err(|e| eval::Error::from_value_error(e, arraypos))? {
+                Some(Symbol::Array(array)) => array.subtype(),
+                Some(_) => {
+                    return Err(CallError::ArgumentError(
+                        arraypos,
+                        format!("{} must be an array reference", arrayref),
+                    ))
+                }
+                None => match arrayref.ref_type() {
+                    VarType::Auto => VarType::Text,
+                    vtype => vtype,
+                },
+            };
+
+        let mut values = Vec::with_capacity(rows.len() * ncols);
+        for (i, row) in rows.iter().enumerate() {
+            for j in 0..ncols {
+                let value = match row.get(j).map(String::as_str) {
+                    Some(field) if subtype == VarType::Text => Value::Text(field.to_owned()),
+                    Some(field) if !field.trim().is_empty() => {
+                        Value::parse_as(subtype, field.trim()).map_err(|e| {
+                            CallError::ArgumentError(
+                                arraypos,
+                                format!("Bad value in row {}, column {}: {}", i + 1, j + 1, e),
+                            )
+                        })?
+                    }
+                    _ => subtype.default_value(),
+                };
+                values.push(value);
+            }
+        }
+
+        if symbols.get(arrayref).map_err(|e| eval::Error::from_value_error(e, arraypos))?.is_none()
+        {
+            symbols
+                .dim_array(arrayref.name(), subtype, vec![rows.len(), ncols])
+                .map_err(|e| eval::Error::from_value_error(e, arraypos))?;
+        }
+        match symbols.get_mut(arrayref).map_err(|e| eval::Error::from_value_error(e, arraypos))? {
+            Some(Symbol::Array(array)) => {
+                array
+                    .resize(vec![rows.len(), ncols], false)
+                    .expect("Resizing without preserving contents cannot fail");
+                let mut values = values.into_iter();
+                for i in 0..rows.len() {
+                    for j in 0..ncols {
+                        let value = values.next().expect("Must have one value per element");
+                        array
+                            .assign(&[i as i32, j as i32], value)
+                            .expect("Subscripts and types were validated above");
+                    }
+                }
+                Ok(())
+            }
+            _ => unreachable!("The array was validated or created above"),
+        }
+    }
+}
+
+/// The `CSV_WRITE` command.
+pub struct CsvWriteCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl CsvWriteCommand {
+    /// Creates a new `CSV_WRITE` command that writes files to `storage`.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("CSV_WRITE", VarType::Void)
+                .with_syntax("path$, array[, delimiter$]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Saves an array into a file of delimiter-separated values.
+Two-dimensional arrays are written with one line per row, and one-dimensional arrays are written \
+with one element per line.  Fields are separated by commas unless a different delimiter$ is \
+given, and fields that contain the delimiter, double quotes, or line breaks are quoted so that \
+CSV_READ can load them back.
+Unlike other file commands, no extension is assumed if path$ lacks one.
+See CSV_READ for the inverse of this command.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for CsvWriteCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (path, _path_pos, array, delimiter) =
+            parse_args(&span.args, machine.get_mut_symbols()).await?;
+        let (arrayref, arraypos) = expr_to_array_ref(array).ok_or(CallError::SyntaxError)?;
+
+        let array = match machine
+            .get_symbols()
+            .get(arrayref)
+            .map_err(|e| eval::Error::from_value_error(e, arraypos))?
+        {
+            Some(Symbol::Array(array)) => array,
+            Some(_) => {
+                return Err(CallError::ArgumentError(
+                    arraypos,
+                    format!("{} must be an array reference", arrayref),
+                ))
+            }
+            None => {
+                return Err(CallError::ArgumentError(
+                    arraypos,
+                    format!("{} is not defined", arrayref),
+                ))
+            }
+        };
+        let ncols = match array.dimensions() {
+            [_rows] => 1,
+            [_rows, cols] => *cols,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    arraypos,
+                    "Cannot write arrays of more than two dimensions".to_owned(),
+                ))
+            }
+        };
+
+        let mut content = String::new();
+        for row in array.values().chunks(ncols) {
+            let fields: Vec<String> =
+                row.iter().map(|v| format_field(&value_to_field(v), delimiter)).collect();
+            content.push_str(&fields.join(&delimiter.to_string()));
+            content.push('\n');
+        }
+
+        self.storage.borrow_mut().put(&path, &content).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    #[test]
+    fn test_parse_csv_simple() {
+        assert!(parse_csv("", ',').unwrap().is_empty());
+        assert_eq!(vec![vec![""]], parse_csv("\n", ',').unwrap());
+        assert_eq!(vec![vec!["a", "b"], vec!["c", ""]], parse_csv("a,b\nc,\n", ',').unwrap());
+        assert_eq!(vec![vec!["a", "b"], vec!["c"]], parse_csv("a;b\r\nc", ';').unwrap());
+        assert_eq!(vec![vec![" a ", "b,c"]], parse_csv(" a |b,c", '|').unwrap());
+    }
+
+    #[test]
+    fn test_parse_csv_quoted() {
+        assert_eq!(
+            vec![vec!["a,b", "say \"hi\"", ""], vec!["line 1\nline 2"]],
+            parse_csv("\"a,b\",\"say \"\"hi\"\"\",\"\"\n\"line 1\nline 2\"\n", ',').unwrap()
+        );
+        assert_eq!(vec![vec!["a\"b"]], parse_csv("a\"b", ',').unwrap());
+    }
+
+    #[test]
+    fn test_parse_csv_errors() {
+        assert_eq!("Unterminated quoted field in row 2", parse_csv("a\n\"b,c\n", ',').unwrap_err());
+        assert_eq!(
+            "Unexpected character after closing quote in row 1",
+            parse_csv("\"a\"b,c", ',').unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_format_field() {
+        assert_eq!("", format_field("", ','));
+        assert_eq!("abc", format_field("abc", ','));
+        assert_eq!("a;b", format_field("a;b", ','));
+        assert_eq!("\"a;b\"", format_field("a;b", ';'));
+        assert_eq!("\"a\"\"b\"", format_field("a\"b", ','));
+        assert_eq!("\"a\nb\"", format_field("a\nb", ','));
+    }
+
+    #[test]
+    fn test_csv_read_strings() {
+        Tester::default()
+            .write_file("data.csv", "name,score\n\"Doe, J\",15\nSmith\n")
+            .run(r#"CSV_READ "data.csv", rows"#)
+            .expect_array(
+                "rows",
+                VarType::Text,
+                &[3, 2],
+                vec![
+                    (&[0, 0], "name".into()),
+                    (&[0, 1], "score".into()),
+                    (&[1, 0], "Doe, J".into()),
+                    (&[1, 1], "15".into()),
+                    (&[2, 0], "Smith".into()),
+                ],
+            )
+            .expect_file("MEMORY:/data.csv", "name,score\n\"Doe, J\",15\nSmith\n")
+            .check();
+    }
+
+    #[test]
+    fn test_csv_read_numbers() {
+        Tester::default()
+            .write_file("data.csv", "1; 2.5\n;-3\n")
+            .run(r#"CSV_READ "data.csv", nums#, ";""#)
+            .expect_array(
+                "nums",
+                VarType::Double,
+                &[2, 2],
+                vec![(&[0, 0], 1.0.into()), (&[0, 1], 2.5.into()), (&[1, 1], (-3.0).into())],
+            )
+            .expect_file("MEMORY:/data.csv", "1; 2.5\n;-3\n")
+            .check();
+
+        Tester::default()
+            .write_file("data.csv", "7,8,9\n")
+            .run(r#"DIM nums(5, 5): nums(4, 4) = 1: CSV_READ "data.csv", nums"#)
+            .expect_array(
+                "nums",
+                VarType::Integer,
+                &[1, 3],
+                vec![(&[0, 0], 7.into()), (&[0, 1], 8.into()), (&[0, 2], 9.into())],
+            )
+            .expect_file("MEMORY:/data.csv", "7,8,9\n")
+            .check();
+    }
+
+    #[test]
+    fn test_csv_read_errors() {
+        check_stmt_err("1:1: In call to CSV_READ: expected path$, array[, delimiter$]", "CSV_READ");
+        check_stmt_err(
+            "1:1: In call to CSV_READ: expected path$, array[, delimiter$]",
+            r#"CSV_READ "a.csv""#,
+        );
+        check_stmt_err(
+            "1:1: In call to CSV_READ: expected path$, array[, delimiter$]",
+            r#"CSV_READ "a.csv", 3"#,
+        );
+        check_stmt_err(
+            "1:1: In call to CSV_READ: expected path$, array[, delimiter$]",
+            r#"CSV_READ "a.csv"; a"#,
+        );
+        check_stmt_err("1:1: In call to CSV_READ: 1:10: path$ must be a string", "CSV_READ 3, a");
+        check_stmt_err(
+            "1:1: In call to CSV_READ: 1:22: delimiter$ must be a string",
+            r#"CSV_READ "a.csv", a, 1"#,
+        );
+        check_stmt_err(
+            "1:1: In call to CSV_READ: 1:22: delimiter$ must be a single character other than a quote or a line break",
+            r#"CSV_READ "a.csv", a, ";;""#,
+        );
+        check_stmt_err("1:1: In call to CSV_READ: Entry not found", r#"CSV_READ "a.csv", a"#);
+
+        Tester::default()
+            .write_file("a.csv", "")
+            .run(r#"CSV_READ "a.csv", a"#)
+            .expect_err("1:1: In call to CSV_READ: 1:10: a.csv has no rows")
+            .expect_file("MEMORY:/a.csv", "")
+            .check();
+
+        Tester::default()
+            .write_file("a.csv", "\"x\n")
+            .run(r#"CSV_READ "a.csv", a"#)
+            .expect_err("1:1: In call to CSV_READ: 1:10: Unterminated quoted field in row 1")
+            .expect_file("MEMORY:/a.csv", "\"x\n")
+            .check();
+
+        Tester::default()
+            .write_file("a.csv", "1\nx\n")
+            .run(r#"CSV_READ "a.csv", a%"#)
+            .expect_err(
+                "1:1: In call to CSV_READ: 1:19: Bad value in row 2, column 1: Invalid integer literal x",
+            )
+            .expect_file("MEMORY:/a.csv", "1\nx\n")
+            .check();
+
+        Tester::default()
+            .write_file("a.csv", "1\n")
+            .run(r#"a = 3: CSV_READ "a.csv", a"#)
+            .expect_err("1:8: In call to CSV_READ: 1:26: a must be an array reference")
+            .expect_var("a", 3)
+            .expect_file("MEMORY:/a.csv", "1\n")
+            .check();
+    }
+
+    #[test]
+    fn test_csv_write_ok() {
+        Tester::default()
+            .run(
+                r#"DIM t(2, 3) AS STRING: t(0, 0) = "a,b": t(0, 1) = "say \"hi\"": t(1, 2) = "z"
+                CSV_WRITE "out.csv", t"#,
+            )
+            .expect_array(
+                "t",
+                VarType::Text,
+                &[2, 3],
+                vec![
+                    (&[0, 0], "a,b".into()),
+                    (&[0, 1], "say \"hi\"".into()),
+                    (&[1, 2], "z".into()),
+                ],
+            )
+            .expect_file("MEMORY:/out.csv", "\"a,b\",\"say \"\"hi\"\"\",\n,,z\n")
+            .check();
+
+        Tester::default()
+            .run(r#"DIM n(3) AS DOUBLE: n(0) = 1.5: n(2) = -2: CSV_WRITE "out.csv", n(), ";""#)
+            .expect_array_simple("n", VarType::Double, vec![1.5.into(), 0.0.into(), (-2.0).into()])
+            .expect_file("MEMORY:/out.csv", "1.5\n0\n-2\n")
+            .check();
+    }
+
+    #[test]
+    fn test_csv_write_read_roundtrip() {
+        Tester::default()
+            .run(
+                r#"DIM t(2, 2) AS STRING: t(0, 0) = "x;y": t(1, 1) = "line 1" + CHR(10) + "line 2"
+                CSV_WRITE "out.csv", t, ";"
+                CSV_READ "out.csv", u$, ";""#,
+            )
+            .expect_array(
+                "t",
+                VarType::Text,
+                &[2, 2],
+                vec![(&[0, 0], "x;y".into()), (&[1, 1], "line 1\nline 2".into())],
+            )
+            .expect_array(
+                "u",
+                VarType::Text,
+                &[2, 2],
+                vec![(&[0, 0], "x;y".into()), (&[1, 1], "line 1\nline 2".into())],
+            )
+            .expect_file("MEMORY:/out.csv", "\"x;y\";\n;\"line 1\nline 2\"\n")
+            .check();
+    }
+
+    #[test]
+    fn test_csv_write_errors() {
+        check_stmt_err(
+            "1:1: In call to CSV_WRITE: expected path$, array[, delimiter$]",
+            "CSV_WRITE",
+        );
+        check_stmt_err(
+            "1:1: In call to CSV_WRITE: 1:20: a is not defined",
+            r#"CSV_WRITE "a.csv", a"#,
+        );
+
+        Tester::default()
+            .run(r#"DIM a(2, 2, 2): CSV_WRITE "a.csv", a"#)
+            .expect_err(
+                "1:17: In call to CSV_WRITE: 1:36: Cannot write arrays of more than two dimensions",
+            )
+            .expect_array("a", VarType::Integer, &[2, 2, 2], vec![])
+            .check();
+    }
+}
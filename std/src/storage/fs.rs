@@ -15,13 +15,19 @@
 
 //! File system-based implementation of the storage system.
 
-use crate::storage::{Drive, DriveFactory, DriveFiles, Metadata};
+use crate::storage::{guess_kind, Drive, DriveFactory, DriveFiles, Metadata};
 use async_trait::async_trait;
-use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, File, OpenOptions};
 use std::io::{self, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str;
+use std::time::SystemTime;
+
+/// State of a file used to detect modifications: its modification time and size, or nothing if
+/// the file does not exist.
+type FileState = Option<(SystemTime, u64)>;
 
 /// A drive that is backed by an on-disk directory.
 pub struct DirectoryDrive {
@@ -29,6 +35,9 @@ pub struct DirectoryDrive {
     /// contain files that are not EndBASIC programs, and that's OK, but those files will not be
     /// accessible through this interface.
     dir: PathBuf,
+
+    /// Files being watched for modifications and their last known state.
+    watched: RefCell<HashMap<String, FileState>>,
 }
 
 impl DirectoryDrive {
@@ -47,7 +56,27 @@ impl DirectoryDrive {
             Err(e) => return Err(e),
         };
 
-        Ok(Self { dir })
+        Ok(Self { dir, watched: RefCell::from(HashMap::default()) })
+    }
+
+    /// Queries the current state of the file `name`.
+    fn file_state(&self, name: &str) -> FileState {
+        let metadata = fs::metadata(self.dir.join(name)).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// Records the current state of the file `name` if it is being watched so that changes done
+    /// through this drive are not reported as external modifications.
+    fn refresh_watched(&self, name: &str) {
+        let state = self.file_state(name);
+        if let Some(known) = self.watched.borrow_mut().get_mut(name) {
+            *known = state;
+        }
+    }
+
+    /// Returns the canonical path to the directory backing this drive.
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
     }
 }
 
@@ -55,7 +84,9 @@ impl DirectoryDrive {
 impl Drive for DirectoryDrive {
     async fn delete(&mut self, name: &str) -> io::Result<()> {
         let path = self.dir.join(name);
-        fs::remove_file(path)
+        fs::remove_file(path)?;
+        self.refresh_watched(name);
+        Ok(())
     }
 
     async fn enumerate(&self) -> io::Result<DriveFiles> {
@@ -81,11 +112,11 @@ impl Drive for DirectoryDrive {
                     };
                     let date = time::OffsetDateTime::from(metadata.modified()?).to_offset(offset);
                     let length = metadata.len();
+                    let read_only = metadata.permissions().readonly();
 
-                    entries.insert(
-                        de.file_name().to_string_lossy().to_string(),
-                        Metadata { date, length },
-                    );
+                    let name = de.file_name().to_string_lossy().to_string();
+                    let kind = guess_kind(&name);
+                    entries.insert(name, Metadata { date, length, kind, read_only });
                 }
             }
             Err(e) => {
@@ -106,16 +137,43 @@ impl Drive for DirectoryDrive {
         Ok(content)
     }
 
+    async fn poll_changes(&self) -> io::Result<Vec<String>> {
+        let mut changes = vec![];
+        for (name, known) in self.watched.borrow_mut().iter_mut() {
+            let state = self.file_state(name);
+            if state != *known {
+                *known = state;
+                changes.push(name.clone());
+            }
+        }
+        changes.sort();
+        Ok(changes)
+    }
+
     async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
         let path = self.dir.join(name);
         let output = OpenOptions::new().create(true).write(true).truncate(true).open(path)?;
         let mut writer = io::BufWriter::new(output);
-        writer.write_all(content.as_bytes())
+        writer.write_all(content.as_bytes())?;
+        writer.flush()?;
+        drop(writer);
+        self.refresh_watched(name);
+        Ok(())
     }
 
     fn system_path(&self, name: &str) -> Option<PathBuf> {
         Some(self.dir.join(name))
     }
+
+    fn unwatch(&self, name: &str) {
+        self.watched.borrow_mut().remove(name);
+    }
+
+    async fn watch(&self, name: &str) -> io::Result<()> {
+        let state = self.file_state(name);
+        self.watched.borrow_mut().insert(name.to_owned(), state);
+        Ok(())
+    }
 }
 
 /// Factory for directory-backed drives.
@@ -208,8 +266,30 @@ mod tests {
         let files = block_on(drive.enumerate()).unwrap();
         assert_eq!(2, files.dirents().len());
         let date = time::OffsetDateTime::from_unix_timestamp(1_588_757_875).unwrap();
-        assert_eq!(&Metadata { date, length: 0 }, files.dirents().get("empty.bas").unwrap());
-        assert_eq!(&Metadata { date, length: 18 }, files.dirents().get("some file.bas").unwrap());
+        let kind = Some("text/x-basic".to_owned());
+        assert_eq!(
+            &Metadata { date, length: 0, kind: kind.clone(), read_only: false },
+            files.dirents().get("empty.bas").unwrap()
+        );
+        assert_eq!(
+            &Metadata { date, length: 18, kind, read_only: false },
+            files.dirents().get("some file.bas").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_directorydrive_enumerate_read_only_files() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(&dir.path().join("data.txt"), &["locked"]);
+        let mut permissions = fs::metadata(dir.path().join("data.txt")).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(dir.path().join("data.txt"), permissions).unwrap();
+
+        let drive = DirectoryDrive::new(dir.path()).unwrap();
+        let files = block_on(drive.enumerate()).unwrap();
+        let metadata = files.dirents().get("data.txt").unwrap();
+        assert!(metadata.read_only);
+        assert_eq!(Some("text/plain"), metadata.kind.as_deref());
     }
 
     #[test]
@@ -242,6 +322,8 @@ mod tests {
         let metadata = Metadata {
             date: time::OffsetDateTime::from_unix_timestamp(1_588_757_875).unwrap(),
             length: 18,
+            kind: Some("text/x-basic".to_owned()),
+            read_only: false,
         };
         assert_eq!(&metadata, files.dirents().get("some file.bas").unwrap());
         assert_eq!(&metadata, files.dirents().get("a link.bas").unwrap());
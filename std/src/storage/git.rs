@@ -0,0 +1,273 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Git-backed implementation of the storage system.
+
+use crate::storage::{DirectoryDrive, Drive, DriveFactory, DriveFiles, FileRevision};
+use async_trait::async_trait;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A drive that is backed by an on-disk directory that is also a git repository.
+///
+/// Every modification to the drive is recorded as a separate commit, which makes the history of
+/// every file available via `Drive::history`.  Operations are implemented by invoking the `git`
+/// binary, which must be installed.
+pub struct GitDrive {
+    /// Drive that handles the file operations on the working copy.
+    inner: DirectoryDrive,
+
+    /// Path to the working copy of the repository.
+    dir: PathBuf,
+
+    /// Configuration flags to pass to every `git` invocation.
+    config: Vec<String>,
+}
+
+impl GitDrive {
+    /// Creates a new drive backed by the `dir` directory, initializing a git repository in it if
+    /// there isn't one yet.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> io::Result<Self> {
+        let inner = DirectoryDrive::new(dir)?;
+        let dir = inner.dir().to_owned();
+
+        let mut drive = Self { inner, dir, config: vec!["commit.gpgsign=false".to_owned()] };
+        if !drive.dir.join(".git").exists() {
+            drive.git(&["init", "--quiet"])?;
+        }
+
+        // Fall back to a generic identity if the user has not configured one so that commits
+        // can always be recorded.
+        if drive.git(&["config", "user.email"]).is_err() {
+            drive.config.push("user.name=EndBASIC".to_owned());
+            drive.config.push("user.email=endbasic@localhost".to_owned());
+        }
+
+        Ok(drive)
+    }
+
+    /// Runs `git` with `args` within the repository and returns its standard output.
+    fn git(&self, args: &[&str]) -> io::Result<String> {
+        let mut cmd = Command::new("git");
+        cmd.arg("-C").arg(&self.dir);
+        for config in &self.config {
+            cmd.arg("-c").arg(config);
+        }
+        let output = cmd
+            .args(args)
+            .output()
+            .map_err(|e| io::Error::new(e.kind(), format!("Cannot run git: {}", e)))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(io::Error::other(format!(
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+
+    /// Records the current state of the file `name` in a new commit described by `message`.
+    ///
+    /// Does nothing if the file has not changed since the last commit.
+    fn commit(&self, name: &str, message: &str) -> io::Result<()> {
+        if self.git(&["status", "--porcelain", "--", name])?.is_empty() {
+            return Ok(());
+        }
+        self.git(&["add", "--all", "--", name])?;
+        self.git(&["commit", "--quiet", "--message", message, "--", name])?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Drive for GitDrive {
+    async fn delete(&mut self, name: &str) -> io::Result<()> {
+        self.inner.delete(name).await?;
+        self.commit(name, &format!("Delete {}", name))
+    }
+
+    async fn enumerate(&self) -> io::Result<DriveFiles> {
+        self.inner.enumerate().await
+    }
+
+    async fn get(&self, name: &str) -> io::Result<String> {
+        self.inner.get(name).await
+    }
+
+    async fn history(&self, name: &str) -> io::Result<Vec<FileRevision>> {
+        let offset = match time::UtcOffset::current_local_offset() {
+            Ok(offset) => offset,
+            Err(_) => time::UtcOffset::UTC,
+        };
+
+        // Repositories without commits have no history at all, and git log fails on them.
+        if self.git(&["rev-parse", "--verify", "--quiet", "HEAD"]).is_err() {
+            return Ok(vec![]);
+        }
+
+        let log = self.git(&["log", "--format=%H%x09%ct%x09%s", "--", name])?;
+        let mut revisions = vec![];
+        for line in log.lines() {
+            let fields: Vec<&str> = line.splitn(3, '\t').collect();
+            let (id, timestamp, message) = match fields.as_slice() {
+                [id, timestamp, message] => (id, timestamp, message),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Unexpected git log output '{}'", line),
+                    ))
+                }
+            };
+            let date = timestamp
+                .parse::<i64>()
+                .ok()
+                .and_then(|ts| time::OffsetDateTime::from_unix_timestamp(ts).ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid commit timestamp '{}'", timestamp),
+                    )
+                })?;
+            revisions.push(FileRevision {
+                id: (*id).to_owned(),
+                date: date.to_offset(offset),
+                message: (*message).to_owned(),
+            });
+        }
+        Ok(revisions)
+    }
+
+    async fn poll_changes(&self) -> io::Result<Vec<String>> {
+        self.inner.poll_changes().await
+    }
+
+    async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
+        self.inner.put(name, content).await?;
+        self.commit(name, &format!("Save {}", name))
+    }
+
+    fn system_path(&self, name: &str) -> Option<PathBuf> {
+        self.inner.system_path(name)
+    }
+
+    fn unwatch(&self, name: &str) {
+        self.inner.unwatch(name)
+    }
+
+    async fn watch(&self, name: &str) -> io::Result<()> {
+        self.inner.watch(name).await
+    }
+}
+
+/// Factory for git-backed drives.
+#[derive(Default)]
+pub struct GitDriveFactory {}
+
+impl DriveFactory for GitDriveFactory {
+    fn create(&self, target: &str) -> io::Result<Box<dyn Drive>> {
+        if !target.is_empty() {
+            Ok(Box::from(GitDrive::new(target)?))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Must specify a directory to mount a git-backed drive",
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::future::block_on;
+
+    /// Returns the subjects of all commits in the repository backing `drive`, newest first.
+    fn log(drive: &GitDrive) -> Vec<String> {
+        drive.git(&["log", "--format=%s"]).unwrap().lines().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn test_gitdrive_initializes_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let drive = GitDrive::new(dir.path().join("programs")).unwrap();
+        assert!(dir.path().join("programs/.git").is_dir());
+        assert!(block_on(drive.enumerate()).unwrap().dirents().is_empty());
+        assert!(block_on(drive.history("a.bas")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gitdrive_put_and_delete_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut drive = GitDrive::new(dir.path()).unwrap();
+
+        block_on(drive.put("a.bas", "first")).unwrap();
+        block_on(drive.put("b.bas", "other")).unwrap();
+        block_on(drive.put("a.bas", "second")).unwrap();
+        block_on(drive.put("a.bas", "second")).unwrap();
+        block_on(drive.delete("a.bas")).unwrap();
+        assert_eq!(vec!["Delete a.bas", "Save a.bas", "Save b.bas", "Save a.bas"], log(&drive));
+
+        assert_eq!("other", block_on(drive.get("b.bas")).unwrap());
+        let files = block_on(drive.enumerate()).unwrap();
+        assert_eq!(vec!["b.bas"], files.dirents().keys().collect::<Vec<&String>>());
+    }
+
+    #[test]
+    fn test_gitdrive_delete_untracked_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.bas"), "").unwrap();
+        let mut drive = GitDrive::new(dir.path()).unwrap();
+        block_on(drive.delete("a.bas")).unwrap();
+        assert!(!dir.path().join("a.bas").exists());
+        assert_eq!(io::ErrorKind::NotFound, block_on(drive.delete("a.bas")).unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_gitdrive_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut drive = GitDrive::new(dir.path()).unwrap();
+
+        block_on(drive.put("a.bas", "first")).unwrap();
+        block_on(drive.put("b.bas", "other")).unwrap();
+        block_on(drive.put("a.bas", "second")).unwrap();
+
+        let history = block_on(drive.history("a.bas")).unwrap();
+        assert_eq!(
+            vec!["Save a.bas", "Save a.bas"],
+            history.iter().map(|r| r.message.as_str()).collect::<Vec<&str>>()
+        );
+        assert_eq!(40, history[0].id.len());
+        assert_ne!(history[0].id, history[1].id);
+
+        assert_eq!(1, block_on(drive.history("b.bas")).unwrap().len());
+        assert!(block_on(drive.history("c.bas")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gitdrive_factory() {
+        let dir = tempfile::tempdir().unwrap();
+        let factory = GitDriveFactory::default();
+        factory.create(&dir.path().display().to_string()).unwrap();
+        assert!(dir.path().join(".git").is_dir());
+
+        assert_eq!(
+            "Must specify a directory to mount a git-backed drive",
+            format!("{}", factory.create("").err().unwrap())
+        );
+    }
+}
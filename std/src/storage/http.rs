@@ -0,0 +1,344 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Web server-based implementation of the storage system.
+
+use crate::http::{Client, Response};
+use crate::storage::{
+    guess_kind, CachingDrive, Drive, DriveFactory, DriveFiles, Metadata, DEFAULT_CACHE_TTL,
+};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::io;
+use std::rc::Rc;
+use std::str;
+
+/// Percent-encodes all characters in `name` that are not safe to use in a URL path segment.
+fn encode_name(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for b in name.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            encoded.push(char::from(b));
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+/// Decodes the percent-encoded sequences in `name`, leaving malformed sequences untouched.
+fn decode_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Extracts the names of the files referenced by a directory index served in `response`.
+///
+/// HTML indexes, like the ones generated by most web servers, are scanned for links.  Any other
+/// content is interpreted as a plain list of file names, one per line.  In both cases, entries
+/// that do not look like files within the directory (parent directories, subdirectories, links to
+/// other sites, or sorting queries) are ignored.
+fn parse_index(response: &Response) -> Vec<String> {
+    let is_html = response.headers.iter().any(|(name, value)| {
+        name.eq_ignore_ascii_case("content-type") && value.to_ascii_lowercase().contains("html")
+    });
+
+    let candidates: Vec<String> = if is_html {
+        let re = regex::Regex::new(r#"(?i)href\s*=\s*["']([^"']*)["']"#)
+            .expect("Hardcoded regex must be valid");
+        re.captures_iter(&response.body).map(|c| decode_name(&c[1])).collect()
+    } else {
+        response.body.lines().map(|line| line.trim().to_owned()).collect()
+    };
+
+    candidates
+        .into_iter()
+        .filter(|name| {
+            !name.is_empty()
+                && !name.starts_with('.')
+                && !name.chars().any(|c| c == '/' || c == '?' || c == '#' || c == ':')
+        })
+        .collect()
+}
+
+/// Converts a non-successful `response` to an `io::Error`.
+fn response_to_io_error(response: &Response) -> io::Error {
+    let kind = match response.status {
+        401 | 403 => io::ErrorKind::PermissionDenied,
+        404 => io::ErrorKind::NotFound,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, format!("HTTP request returned status {}", response.status))
+}
+
+/// A read-only drive that is backed by a directory on a web server.
+///
+/// Files are read with GET requests against the base URL of the drive, and the list of files is
+/// obtained from the directory index that the server returns for the base URL itself.  Web
+/// servers do not report file details in a standard form, so all files are listed with a zero
+/// length and the Unix epoch as their modification time.
+pub struct HttpDrive {
+    /// Client to use to issue the HTTP requests.
+    client: Rc<dyn Client>,
+
+    /// Base URL of the drive, which always ends with a slash.
+    base_url: String,
+}
+
+impl HttpDrive {
+    /// Creates a new drive that serves the files under `base_url` via `client`.
+    pub fn new<S: Into<String>>(client: Rc<dyn Client>, base_url: S) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Self { client, base_url }
+    }
+
+    /// Sends a GET request for `url` and returns the response if it was successful.
+    async fn get_ok(&self, url: &str) -> io::Result<Response> {
+        let response = self.client.get(url).await?;
+        if response.status / 100 == 2 {
+            Ok(response)
+        } else {
+            Err(response_to_io_error(&response))
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Drive for HttpDrive {
+    async fn delete(&mut self, _name: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "HTTP drives are read-only"))
+    }
+
+    async fn enumerate(&self) -> io::Result<DriveFiles> {
+        let response = self.get_ok(&self.base_url).await?;
+        let mut entries = BTreeMap::default();
+        for name in parse_index(&response) {
+            let metadata = Metadata {
+                date: time::OffsetDateTime::UNIX_EPOCH,
+                length: 0,
+                kind: guess_kind(&name),
+                read_only: true,
+            };
+            entries.insert(name, metadata);
+        }
+        Ok(DriveFiles::new(entries, None, None))
+    }
+
+    async fn get(&self, name: &str) -> io::Result<String> {
+        let url = format!("{}{}", self.base_url, encode_name(name));
+        let response = self.get_ok(&url).await?;
+        Ok(response.body)
+    }
+
+    async fn put(&mut self, _name: &str, _content: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "HTTP drives are read-only"))
+    }
+}
+
+/// Factory for web server-backed drives.
+///
+/// Web servers can be slow to respond, so the drives created by this factory cache the files
+/// they fetch for a short period of time.
+pub struct HttpDriveFactory {
+    /// Client to use to issue the HTTP requests of the drives.
+    client: Rc<dyn Client>,
+
+    /// URI scheme handled by this factory, used to reconstruct the full URL of the drive.
+    scheme: &'static str,
+}
+
+impl HttpDriveFactory {
+    /// Creates a new factory for drives of the given `scheme` (`http` or `https`) that issue
+    /// their requests via `client`.
+    pub fn new(client: Rc<dyn Client>, scheme: &'static str) -> Self {
+        Self { client, scheme }
+    }
+}
+
+impl DriveFactory for HttpDriveFactory {
+    fn create(&self, target: &str) -> io::Result<Box<dyn Drive>> {
+        if !target.is_empty() {
+            let url = format!("{}://{}", self.scheme, target);
+            let drive = HttpDrive::new(self.client.clone(), url);
+            Ok(Box::from(CachingDrive::new(Box::from(drive), DEFAULT_CACHE_TTL)))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Must specify a host to mount a web-backed drive",
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::future::block_on;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// HTTP client that serves canned responses and records the requested URLs.
+    #[derive(Default)]
+    struct MockClient {
+        responses: HashMap<String, Response>,
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl MockClient {
+        /// Registers a successful `response` for GET requests to `url`.
+        fn add(&mut self, url: &str, content_type: &str, body: &str) {
+            let response = Response {
+                status: 200,
+                headers: vec![("Content-Type".to_owned(), content_type.to_owned())],
+                body: body.to_owned(),
+            };
+            self.responses.insert(url.to_owned(), response);
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl Client for MockClient {
+        async fn get(&self, url: &str) -> io::Result<Response> {
+            self.requests.borrow_mut().push(url.to_owned());
+            match self.responses.get(url) {
+                Some(response) => Ok(response.clone()),
+                None => Ok(Response { status: 404, ..Default::default() }),
+            }
+        }
+
+        async fn post(
+            &self,
+            _url: &str,
+            _content_type: &str,
+            _body: String,
+        ) -> io::Result<Response> {
+            unreachable!("Drives must not send POST requests");
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_name() {
+        assert_eq!("abc.BAS", encode_name("abc.BAS"));
+        assert_eq!("my%20game%2Bdata.bas", encode_name("my game+data.bas"));
+        assert_eq!("my game+data.bas", decode_name("my%20game%2Bdata.bas"));
+        assert_eq!("100%", decode_name("100%"));
+        assert_eq!("%zz%2", decode_name("%zz%2"));
+    }
+
+    #[test]
+    fn test_httpdrive_enumerate_html() {
+        let mut client = MockClient::default();
+        client.add(
+            "https://example.com/basic/",
+            "text/html; charset=utf-8",
+            r#"<html><body>
+<a href="?C=N;O=D">Name</a> <a href="../">Parent</a>
+<a href="game.bas">game.bas</a> <a HREF='my%20file.bas'>my file.bas</a>
+<a href="subdir/">subdir/</a> <a href="https://other.com/x.bas">other</a>
+<a href="game.bas">duplicate</a>
+</body></html>"#,
+        );
+        let drive = HttpDrive::new(Rc::from(client), "https://example.com/basic");
+
+        let files = block_on(drive.enumerate()).unwrap();
+        let metadata = Metadata {
+            date: time::OffsetDateTime::UNIX_EPOCH,
+            length: 0,
+            kind: Some("text/x-basic".to_owned()),
+            read_only: true,
+        };
+        assert_eq!(2, files.dirents().len());
+        assert_eq!(&metadata, files.dirents().get("game.bas").unwrap());
+        assert_eq!(&metadata, files.dirents().get("my file.bas").unwrap());
+    }
+
+    #[test]
+    fn test_httpdrive_enumerate_plain_text() {
+        let mut client = MockClient::default();
+        client.add("http://example.com/", "text/plain", "a.bas\n\n  b.bas  \n../c.bas\n");
+        let drive = HttpDrive::new(Rc::from(client), "http://example.com/");
+
+        let files = block_on(drive.enumerate()).unwrap();
+        assert_eq!(vec!["a.bas", "b.bas"], files.dirents().keys().collect::<Vec<&String>>());
+    }
+
+    #[test]
+    fn test_httpdrive_enumerate_error() {
+        let drive = HttpDrive::new(Rc::from(MockClient::default()), "http://example.com/");
+        let err = block_on(drive.enumerate()).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+        assert_eq!("HTTP request returned status 404", format!("{}", err));
+    }
+
+    #[test]
+    fn test_httpdrive_get() {
+        let mut client = MockClient::default();
+        client.add("https://example.com/basic/my%20game.bas", "text/plain", "PRINT 1\n");
+        let client = Rc::from(client);
+        let drive = HttpDrive::new(client.clone(), "https://example.com/basic/");
+
+        assert_eq!("PRINT 1\n", block_on(drive.get("my game.bas")).unwrap());
+        assert_eq!(io::ErrorKind::NotFound, block_on(drive.get("other.bas")).unwrap_err().kind());
+        assert_eq!(
+            vec![
+                "https://example.com/basic/my%20game.bas".to_owned(),
+                "https://example.com/basic/other.bas".to_owned()
+            ],
+            *client.requests.borrow()
+        );
+    }
+
+    #[test]
+    fn test_httpdrive_is_read_only() {
+        let mut drive = HttpDrive::new(Rc::from(MockClient::default()), "http://example.com/");
+        let err = block_on(drive.put("a.bas", "")).unwrap_err();
+        assert_eq!(io::ErrorKind::PermissionDenied, err.kind());
+        assert_eq!("HTTP drives are read-only", format!("{}", err));
+        let err = block_on(drive.delete("a.bas")).unwrap_err();
+        assert_eq!(io::ErrorKind::PermissionDenied, err.kind());
+    }
+
+    #[test]
+    fn test_httpdrive_factory() {
+        let mut client = MockClient::default();
+        client.add("https://example.com/basic/a.bas", "text/plain", "content");
+        let factory = HttpDriveFactory::new(Rc::from(client), "https");
+
+        let drive = factory.create("example.com/basic").unwrap();
+        assert_eq!("content", block_on(drive.get("a.bas")).unwrap());
+
+        assert_eq!(
+            "Must specify a host to mount a web-backed drive",
+            format!("{}", factory.create("").err().unwrap())
+        );
+    }
+}
@@ -15,7 +15,7 @@
 
 //! In-memory implementation of the storage system.
 
-use crate::storage::{DiskSpace, Drive, DriveFactory, DriveFiles, FileAcls, Metadata};
+use crate::storage::{guess_kind, DiskSpace, Drive, DriveFactory, DriveFiles, FileAcls, Metadata};
 use async_trait::async_trait;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io;
@@ -24,7 +24,8 @@ use std::str;
 /// A drive that records all data in memory only.
 #[derive(Default)]
 pub struct InMemoryDrive {
-    programs: HashMap<String, (String, HashSet<String>)>,
+    /// Mapping of file names to their contents, readers, and writers.
+    programs: HashMap<String, (String, HashSet<String>, HashSet<String>)>,
 
     // TODO(jmmv): These fields are currently exposed only to allow testing for the consumers of
     // these details and are not enforced in the drive.  It might be nice to actually implement
@@ -46,38 +47,46 @@ impl Drive for InMemoryDrive {
         let date = time::OffsetDateTime::from_unix_timestamp(1_588_757_875).unwrap();
 
         let mut entries = BTreeMap::new();
-        for (name, (contents, _readers)) in &self.programs {
-            entries.insert(name.clone(), Metadata { date, length: contents.len() as u64 });
+        for (name, (contents, _readers, _writers)) in &self.programs {
+            let metadata = Metadata {
+                date,
+                length: contents.len() as u64,
+                kind: guess_kind(name),
+                read_only: false,
+            };
+            entries.insert(name.clone(), metadata);
         }
         Ok(DriveFiles::new(entries, self.fake_disk_quota, self.fake_disk_free))
     }
 
     async fn get(&self, name: &str) -> io::Result<String> {
         match self.programs.get(name) {
-            Some((content, _readers)) => Ok(content.to_owned()),
+            Some((content, _readers, _writers)) => Ok(content.to_owned()),
             None => Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found")),
         }
     }
 
     async fn get_acls(&self, name: &str) -> io::Result<FileAcls> {
         match self.programs.get(name) {
-            Some((_content, readers)) => {
+            Some((_content, readers, writers)) => {
                 let mut readers = readers.iter().map(String::to_owned).collect::<Vec<String>>();
+                let mut writers = writers.iter().map(String::to_owned).collect::<Vec<String>>();
                 // There is no need to sort the returned ACLs, but doing so simplifies testing...
                 // and this in-memory drive exists mostly for testing only.
                 readers.sort();
-                Ok(FileAcls::default().with_readers(readers))
+                writers.sort();
+                Ok(FileAcls::default().with_readers(readers).with_writers(writers))
             }
             None => Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found")),
         }
     }
 
     async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
-        if let Some((prev_content, _readers)) = self.programs.get_mut(name) {
+        if let Some((prev_content, _readers, _writers)) = self.programs.get_mut(name) {
             *prev_content = content.to_owned();
             return Ok(());
         };
-        self.programs.insert(name.to_owned(), (content.to_owned(), HashSet::new()));
+        self.programs.insert(name.to_owned(), (content.to_owned(), HashSet::new(), HashSet::new()));
         Ok(())
     }
 
@@ -87,8 +96,8 @@ impl Drive for InMemoryDrive {
         add: &FileAcls,
         remove: &FileAcls,
     ) -> io::Result<()> {
-        let readers = match self.programs.get_mut(name) {
-            Some((_content, readers)) => readers,
+        let (readers, writers) = match self.programs.get_mut(name) {
+            Some((_content, readers, writers)) => (readers, writers),
             None => return Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found")),
         };
         for reader in remove.readers() {
@@ -97,6 +106,12 @@ impl Drive for InMemoryDrive {
         for reader in add.readers() {
             readers.insert(reader.to_owned());
         }
+        for writer in remove.writers() {
+            writers.remove(writer);
+        }
+        for writer in add.writers() {
+            writers.insert(writer.to_owned());
+        }
         Ok(())
     }
 }
@@ -168,6 +183,23 @@ mod tests {
         assert_eq!(FileAcls::default(), drive.get_acls("untouched").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_inmemorydrive_get_update_writer_acls() {
+        let mut drive = InMemoryDrive::default();
+        drive.put("file", "some content").await.unwrap();
+
+        let add = readers(&["r1"]).with_writers(["w1".to_owned(), "w2".to_owned()]);
+        drive.update_acls("file", &add, &FileAcls::default()).await.unwrap();
+        assert_eq!(add, drive.get_acls("file").await.unwrap());
+
+        let remove = FileAcls::default().with_writers(["w1".to_owned(), "r1".to_owned()]);
+        drive.update_acls("file", &FileAcls::default(), &remove).await.unwrap();
+        assert_eq!(
+            readers(&["r1"]).with_writers(["w2".to_owned()]),
+            drive.get_acls("file").await.unwrap()
+        );
+    }
+
     #[test]
     fn test_inmemorydrive_system_path() {
         let drive = InMemoryDrive::default();
@@ -16,6 +16,7 @@
 //! Storage-related abstractions and commands.
 
 use async_trait::async_trait;
+use std::cmp;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self};
 use std::io;
@@ -23,12 +24,28 @@ use std::path::PathBuf;
 use std::str;
 use time::error::Format;
 
+mod archive;
+pub use archive::*;
+mod cache;
+pub use cache::*;
 mod cmds;
 pub use cmds::*;
+mod csv;
+pub use csv::*;
 mod fs;
 pub use fs::*;
+mod git;
+pub use git::*;
+mod http;
+pub use http::*;
 mod mem;
 pub use mem::*;
+mod offline;
+pub use offline::*;
+mod state;
+pub use state::*;
+mod watch;
+pub use watch::*;
 
 /// Converts a time formatting error to an I/O error.
 pub(crate) fn time_format_error_to_io_error(e: Format) -> io::Error {
@@ -46,6 +63,43 @@ pub struct Metadata {
 
     /// Total size of the entry.
     pub length: u64,
+
+    /// MIME type of the entry, if known.
+    pub kind: Option<String>,
+
+    /// Whether the entry cannot be modified.
+    pub read_only: bool,
+}
+
+/// Guesses the MIME type of a file based on the extension of its `name`.
+pub fn guess_kind(name: &str) -> Option<String> {
+    let extension = match name.rfind('.') {
+        Some(pos) => name[pos + 1..].to_ascii_lowercase(),
+        None => return None,
+    };
+    let kind = match extension.as_str() {
+        "bas" => "text/x-basic",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "md" => "text/markdown",
+        "txt" => "text/plain",
+        "zip" => "application/zip",
+        _ => return None,
+    };
+    Some(kind.to_owned())
+}
+
+/// Details about a past revision of a file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileRevision {
+    /// Opaque identifier of the revision.
+    pub id: String,
+
+    /// Date when the revision was recorded.
+    pub date: time::OffsetDateTime,
+
+    /// Description of the revision.
+    pub message: String,
 }
 
 /// Describes the ACLs of a file.
@@ -53,6 +107,9 @@ pub struct Metadata {
 pub struct FileAcls {
     /// List of principals that are allowed to read the file.
     pub readers: Vec<String>,
+
+    /// List of principals that are allowed to modify the file.
+    pub writers: Vec<String>,
 }
 
 impl FileAcls {
@@ -62,15 +119,31 @@ impl FileAcls {
         self
     }
 
+    /// Extends this set of ACLs with the given `writers`.
+    pub fn with_writers<T: Into<Vec<String>>>(mut self, writers: T) -> Self {
+        self.writers.extend(writers.into());
+        self
+    }
+
     /// Gets the list of principals that are allowed to read the file.
     pub fn readers(&self) -> &[String] {
         &self.readers
     }
 
+    /// Gets the list of principals that are allowed to modify the file.
+    pub fn writers(&self) -> &[String] {
+        &self.writers
+    }
+
     /// Modifies the readers list by appending `reader` to it.
     pub fn add_reader<R: Into<String>>(&mut self, reader: R) {
         self.readers.push(reader.into());
     }
+
+    /// Modifies the writers list by appending `writer` to it.
+    pub fn add_writer<W: Into<String>>(&mut self, writer: W) {
+        self.writers.push(writer.into());
+    }
 }
 
 /// Representation of some amount of disk space.  Can be used to express both quotas and usage.
@@ -100,9 +173,22 @@ impl DiskSpace {
     }
 }
 
+/// Disk usage of a mounted drive, in bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DriveUsage {
+    /// Number of bytes occupied by the files in the drive.
+    pub used: u64,
+
+    /// Maximum number of bytes that the files in the drive can occupy, if limited.
+    pub quota: Option<u64>,
+
+    /// Number of bytes that can still be written to the drive, if known.
+    pub free: Option<u64>,
+}
+
 /// Collection of entries in the store and their metadata.  Used to represent the result of the
 /// `Drive::enumerate` call.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct DriveFiles {
     dirents: BTreeMap<String, Metadata>,
     disk_quota: Option<DiskSpace>,
@@ -144,17 +230,50 @@ pub trait Drive {
     /// Returns the entries in the store and their metadata.
     async fn enumerate(&self) -> io::Result<DriveFiles>;
 
+    /// Discards any data that the drive may have cached so that subsequent operations see the
+    /// latest contents of the underlying medium.
+    fn flush(&mut self) {}
+
     /// Loads the contents of the program given by `name`.
     async fn get(&self, name: &str) -> io::Result<String>;
 
+    /// Returns the names of the watched files that were modified outside of this drive since the
+    /// last call.
+    ///
+    /// Drives that cannot detect external modifications never report any changes.
+    async fn poll_changes(&self) -> io::Result<Vec<String>> {
+        Ok(vec![])
+    }
+
     /// Gets the ACLs of the file `_name`.
     async fn get_acls(&self, _name: &str) -> io::Result<FileAcls> {
         Err(io::Error::new(io::ErrorKind::Other, "Operation not supported by drive"))
     }
 
+    /// Gets the revisions of the file `_name`, newest first.
+    async fn history(&self, _name: &str) -> io::Result<Vec<FileRevision>> {
+        Err(io::Error::other("Operation not supported by drive"))
+    }
+
     /// Saves the in-memory program given by `content` into `name`.
     async fn put(&mut self, name: &str, content: &str) -> io::Result<()>;
 
+    /// Applies any changes that the drive had to queue while its underlying medium was
+    /// unreachable and reports the outcome.
+    ///
+    /// Drives that never queue changes have nothing to do.
+    async fn sync(&mut self) -> io::Result<SyncReport> {
+        Ok(SyncReport::default())
+    }
+
+    /// Returns the metadata of the file `name`.
+    async fn stat(&self, name: &str) -> io::Result<Metadata> {
+        match self.enumerate().await?.dirents().get(name) {
+            Some(metadata) => Ok(metadata.clone()),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found")),
+        }
+    }
+
     /// Updates the ACLs of the file `_name` by extending them with the contents of `_add` and
     /// removing the existing entries listed in `_remove`.
     async fn update_acls(
@@ -170,6 +289,15 @@ pub trait Drive {
     fn system_path(&self, _name: &str) -> Option<PathBuf> {
         None
     }
+
+    /// Stops watching the file `_name` for modifications.
+    fn unwatch(&self, _name: &str) {}
+
+    /// Starts watching the file `_name` for modifications done outside of this drive, which are
+    /// later reported by `poll_changes`.
+    async fn watch(&self, _name: &str) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Unique identifier for a drive.
@@ -286,6 +414,41 @@ impl fmt::Display for Location {
     }
 }
 
+/// Returns true if `path` contains wildcard characters and thus represents a pattern.
+pub fn has_wildcards(path: &str) -> bool {
+    path.contains('*') || path.contains('?')
+}
+
+/// Returns true if `name` matches the `pattern`, ignoring case.
+///
+/// The pattern can contain `*` to match any sequence of characters and `?` to match any single
+/// character.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<char>>();
+    let name = name.to_lowercase().chars().collect::<Vec<char>>();
+
+    // Position of the last `*` seen in the pattern and of the character in the name it was
+    // matched against, used to backtrack when a later part of the pattern fails to match.
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            star = Some((star_p, star_n + 1));
+            p = star_p + 1;
+            n = star_n + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
 /// Trait to instantiate drives of a given type.
 pub trait DriveFactory {
     /// Creates a new drive for `target`.
@@ -307,6 +470,8 @@ fn split_uri(uri: &str) -> io::Result<(&str, &str)> {
 struct MountedDrive {
     uri: String,
     drive: Box<dyn Drive>,
+    read_only: bool,
+    quota: Option<u64>,
 }
 
 /// Storage subsystem representation.
@@ -334,7 +499,8 @@ impl Default for Storage {
 
         let mut drives = HashMap::new();
         let key = DriveKey::new("MEMORY").expect("Hardcoded drive name must be valid");
-        let mounted_drive = MountedDrive { uri: "memory://".to_owned(), drive };
+        let mounted_drive =
+            MountedDrive { uri: "memory://".to_owned(), drive, read_only: false, quota: None };
         drives.insert(key.clone(), mounted_drive);
         Self { factories, drives, current: key }
     }
@@ -374,15 +540,17 @@ impl Storage {
                 format!("Drive '{}' is already mounted", name),
             ));
         }
-        let mounted_drive = MountedDrive { uri: uri.to_owned(), drive };
+        let mounted_drive =
+            MountedDrive { uri: uri.to_owned(), drive, read_only: false, quota: None };
         self.drives.insert(DriveKey::new(name)?, mounted_drive);
         Ok(())
     }
 
-    /// Instantiates and attaches a new `drive` with `name` that points to `uri`.
+    /// Instantiates and attaches a new `drive` with `name` that points to `uri`.  If `read_only` is
+    /// true, any attempt to modify the contents of the drive fails.
     ///
     /// The `name` must be valid and must not yet have been registered.
-    pub fn mount(&mut self, name: &str, uri: &str) -> io::Result<()> {
+    pub fn mount(&mut self, name: &str, uri: &str, read_only: bool) -> io::Result<()> {
         let (scheme, path) = split_uri(uri)?;
         let drive = match self.factories.get(&scheme.to_lowercase()) {
             Some(factory) => factory.create(path)?,
@@ -393,7 +561,12 @@ impl Storage {
                 ))
             }
         };
-        self.attach(name, uri, drive)
+        self.attach(name, uri, drive)?;
+        if read_only {
+            let key = DriveKey::new(name)?;
+            self.drives.get_mut(&key).expect("Drive was just attached").read_only = true;
+        }
+        Ok(())
     }
 
     /// Detaches an existing drive named `name`.
@@ -432,6 +605,135 @@ impl Storage {
         info
     }
 
+    /// Returns true if the drive `name` was mounted in read-only mode.
+    pub fn is_read_only(&self, name: &str) -> io::Result<bool> {
+        let key = DriveKey::new(name)?;
+        match self.drives.get(&key) {
+            Some(mounted_drive) => Ok(mounted_drive.read_only),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Drive '{}' is not mounted", key),
+            )),
+        }
+    }
+
+    /// Limits the number of bytes that the files in the drive `name` can occupy to `quota`, or
+    /// removes the limit if `quota` is none.
+    ///
+    /// The quota is only enforced when writing files via the storage subsystem, so a drive that
+    /// already exceeds its quota keeps its files but rejects any further growth.
+    pub fn set_quota(&mut self, name: &str, quota: Option<u64>) -> io::Result<()> {
+        let key = DriveKey::new(name)?;
+        match self.drives.get_mut(&key) {
+            Some(mounted_drive) => {
+                mounted_drive.quota = quota;
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Drive '{}' is not mounted", key),
+            )),
+        }
+    }
+
+    /// Computes the disk usage of the drive `name`.
+    ///
+    /// The quota and free space combine the limit configured via `set_quota` with the limits
+    /// reported by the drive itself, if any, and report the most restrictive of them.
+    pub async fn usage(&self, name: &str) -> io::Result<DriveUsage> {
+        let key = DriveKey::new(name)?;
+        let mounted_drive = match self.drives.get(&key) {
+            Some(mounted_drive) => mounted_drive,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Drive '{}' is not mounted", key),
+                ))
+            }
+        };
+
+        let files = mounted_drive.drive.enumerate().await?;
+        let used = files.dirents().values().map(|metadata| metadata.length).sum::<u64>();
+
+        let drive_quota = files.disk_quota().map(|quota| quota.bytes());
+        let quota = match (mounted_drive.quota, drive_quota) {
+            (Some(a), Some(b)) => Some(cmp::min(a, b)),
+            (a, b) => a.or(b),
+        };
+
+        let drive_free = files.disk_free().map(|free| free.bytes());
+        let quota_free = mounted_drive.quota.map(|quota| quota.saturating_sub(used));
+        let free = match (quota_free, drive_free) {
+            (Some(a), Some(b)) => Some(cmp::min(a, b)),
+            (a, b) => a.or(b),
+        };
+
+        Ok(DriveUsage { used, quota, free })
+    }
+
+    /// Discards the data cached by the drive `name`, or by all drives if `name` is not given.
+    pub fn flush(&mut self, name: Option<&str>) -> io::Result<()> {
+        match name {
+            Some(name) => {
+                let key = DriveKey::new(name)?;
+                match self.drives.get_mut(&key) {
+                    Some(mounted_drive) => mounted_drive.drive.flush(),
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("Drive '{}' is not mounted", key),
+                        ))
+                    }
+                }
+            }
+            None => {
+                for mounted_drive in self.drives.values_mut() {
+                    mounted_drive.drive.flush();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the changes queued while offline by the drive `name`, or by all drives if `name`
+    /// is not given.
+    ///
+    /// The file names in the returned report are in canonical form.
+    pub async fn sync(&mut self, name: Option<&str>) -> io::Result<SyncReport> {
+        let keys = match name {
+            Some(name) => {
+                let key = DriveKey::new(name)?;
+                if !self.drives.contains_key(&key) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("Drive '{}' is not mounted", key),
+                    ));
+                }
+                vec![key]
+            }
+            None => {
+                let mut keys = self.drives.keys().cloned().collect::<Vec<DriveKey>>();
+                keys.sort_by(|a, b| a.0.cmp(&b.0));
+                keys
+            }
+        };
+
+        let mut report = SyncReport::default();
+        for key in keys {
+            let mounted_drive = self.drives.get_mut(&key).expect("Drive existence checked above");
+            let drive_report = mounted_drive.drive.sync().await?;
+            for name in drive_report.synced {
+                report.synced.push(format!("{}:/{}", key, name));
+            }
+            for (name, copy) in drive_report.conflicts {
+                report
+                    .conflicts
+                    .push((format!("{}:/{}", key, name), copy.map(|c| format!("{}:/{}", key, c))));
+            }
+        }
+        Ok(report)
+    }
+
     /// Changes the current location.
     ///
     /// Given that we currently do not support directories, the location can only be of the forms
@@ -481,23 +783,58 @@ impl Storage {
         }
     }
 
-    /// Returns the drive referenced by `location`, or an error if it doesn't exist.
+    /// Returns the drive referenced by `location` for modification, or an error if it doesn't
+    /// exist or if it is read-only.
     fn get_drive_mut(&mut self, location: &Location) -> io::Result<&mut dyn Drive> {
-        match &location.drive {
+        let (key, mounted_drive) = match &location.drive {
             Some(key) => match self.drives.get_mut(key) {
-                Some(mounted_drive) => Ok(mounted_drive.drive.as_mut()),
-                None => Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    format!("Drive '{}' is not mounted", key),
-                )),
+                Some(mounted_drive) => (key, mounted_drive),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("Drive '{}' is not mounted", key),
+                    ))
+                }
             },
-            None => Ok(self
-                .drives
-                .get_mut(&self.current)
-                .expect("Current drive out of sync")
-                .drive
-                .as_mut()),
+            None => (
+                &self.current,
+                self.drives.get_mut(&self.current).expect("Current drive out of sync"),
+            ),
+        };
+        if mounted_drive.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Drive '{}' is read-only", key),
+            ));
+        }
+        Ok(mounted_drive.drive.as_mut())
+    }
+
+    /// Checks that writing `length` bytes to the file `name` of the drive referenced by `location`
+    /// does not make the drive exceed its quota, if any.
+    async fn check_quota(&self, location: &Location, name: &str, length: usize) -> io::Result<()> {
+        let key = location.drive.as_ref().unwrap_or(&self.current);
+        let quota = match self.drives.get(key).and_then(|mounted_drive| mounted_drive.quota) {
+            Some(quota) => quota,
+            None => return Ok(()),
+        };
+
+        // The file being written replaces any previous version of itself, so its current size
+        // does not count towards the usage.
+        let files = self.get_drive(location)?.enumerate().await?;
+        let used = files
+            .dirents()
+            .iter()
+            .filter(|(other, _)| *other != name)
+            .map(|(_, metadata)| metadata.length)
+            .sum::<u64>();
+        if used + length as u64 > quota {
+            return Err(io::Error::other(format!(
+                "Drive '{}' would exceed its quota of {} bytes",
+                key, quota
+            )));
         }
+        Ok(())
     }
 
     /// Deletes the program given by `raw_location`.
@@ -513,9 +850,22 @@ impl Storage {
     }
 
     /// Returns a sorted list of the entries in `raw_location` and their metadata.
+    ///
+    /// If the file name in `raw_location` contains wildcards, only the entries that match it are
+    /// returned.
     pub async fn enumerate(&self, raw_location: &str) -> io::Result<DriveFiles> {
         let location = Location::new(raw_location)?;
         match location.leaf_name() {
+            Some(pattern) if has_wildcards(pattern) => {
+                let files = self.get_drive(&location)?.enumerate().await?;
+                let dirents = files
+                    .dirents()
+                    .iter()
+                    .filter(|(name, _)| matches_pattern(pattern, name))
+                    .map(|(name, metadata)| (name.clone(), metadata.clone()))
+                    .collect();
+                Ok(DriveFiles::new(dirents, *files.disk_quota(), *files.disk_free()))
+            }
             Some(_) => Err(io::Error::new(
                 io::ErrorKind::NotFound,
                 format!("Location '{}' is not a directory", raw_location),
@@ -524,6 +874,147 @@ impl Storage {
         }
     }
 
+    /// Expands the wildcards in the file name of `raw_pattern` and returns the canonical
+    /// locations of all matching files, sorted by name.
+    ///
+    /// If `raw_pattern` does not contain wildcards, it is returned as is in canonical form even
+    /// if it does not exist so that the caller's operation reports the problem.  Otherwise, it is
+    /// an error for the pattern to not match any files.
+    pub async fn glob(&self, raw_pattern: &str) -> io::Result<Vec<String>> {
+        let mut location = Location::new(raw_pattern)?;
+        if location.drive.is_none() {
+            location.drive = Some(self.current.clone());
+        }
+        match location.leaf_name() {
+            Some(pattern) if has_wildcards(pattern) => {
+                let files = self.enumerate(&location.to_string()).await?;
+                if files.dirents().is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("No files match '{}'", raw_pattern),
+                    ));
+                }
+                let drive = location.drive.expect("Drive was set above");
+                Ok(files.dirents().keys().map(|name| format!("{}:/{}", drive, name)).collect())
+            }
+            Some(_) => Ok(vec![location.to_string()]),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Missing file name in path '{}'", raw_pattern),
+            )),
+        }
+    }
+
+    /// Copies the files matching `raw_src`, which may contain wildcards, to `raw_dest`.
+    ///
+    /// If `raw_dest` does not specify a file name, the files are copied into it with their
+    /// original names.  Otherwise, `raw_src` must match a single file.  Returns the number of
+    /// copied files.
+    pub async fn copy(&mut self, raw_src: &str, raw_dest: &str) -> io::Result<usize> {
+        let sources = self.glob(raw_src).await?;
+        let dest = Location::new(raw_dest)?;
+        if dest.leaf_name().is_some() && sources.len() > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Cannot copy multiple files into the single file '{}'", raw_dest),
+            ));
+        }
+
+        for src in &sources {
+            let content = self.get(src).await?;
+            let target = match dest.leaf_name() {
+                Some(_) => dest.to_string(),
+                None => {
+                    let src = Location::new(src)?;
+                    let name = src.leaf_name().expect("Globbed paths always have a file name");
+                    Location { drive: dest.drive.clone(), path: format!("/{}", name) }.to_string()
+                }
+            };
+            self.put(&target, &content).await?;
+        }
+        Ok(sources.len())
+    }
+
+    /// Returns the system path of the archive file given by `location`, which must live in a drive
+    /// backed by a local directory because archives cannot be represented as text.
+    fn archive_path(&self, location: &Location, raw_location: &str) -> io::Result<PathBuf> {
+        let name = match location.leaf_name() {
+            Some(name) => name,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Missing file name in path '{}'", raw_location),
+                ))
+            }
+        };
+        match self.get_drive(location)?.system_path(name) {
+            Some(path) => Ok(path),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Cannot use '{}' as an archive: drive is not a local directory",
+                    raw_location
+                ),
+            )),
+        }
+    }
+
+    /// Saves all files in the drive `name`, along with their metadata, into the zip archive given
+    /// by `raw_target`.  Returns the number of saved files.
+    ///
+    /// The archive must live in a drive backed by a local directory.  If it lives in the drive
+    /// being backed up, any previous version of the archive is not included in the new one.
+    pub async fn backup(&mut self, name: &str, raw_target: &str) -> io::Result<usize> {
+        let target = Location::new(raw_target)?;
+        self.get_drive_mut(&target)?;
+        let path = self.archive_path(&target, raw_target)?;
+
+        let drive = self.get_drive(&Location::with_drive_root(DriveKey::new(name)?))?;
+        let mut entries = ZipEntries::default();
+        for (file, metadata) in drive.enumerate().await?.dirents() {
+            if drive.system_path(file).as_ref() == Some(&path) {
+                continue;
+            }
+            let content = drive.get(file).await?;
+            entries.insert(file.clone(), (metadata.clone(), content.into_bytes()));
+        }
+
+        write_zip(&path, &entries)?;
+        Ok(entries.len())
+    }
+
+    /// Restores all files saved in the zip archive given by `raw_source` into the drive `name`,
+    /// replacing any existing files with the same names.  Returns the number of restored files.
+    ///
+    /// The archive must live in a drive backed by a local directory.  All files in the archive are
+    /// validated before any of them is written to the drive.
+    pub async fn recover(&mut self, raw_source: &str, name: &str) -> io::Result<usize> {
+        let source = Location::new(raw_source)?;
+        let path = self.archive_path(&source, raw_source)?;
+        let key = DriveKey::new(name)?;
+        self.get_drive_mut(&Location::with_drive_root(key.clone()))?;
+
+        let mut files = vec![];
+        for (file, (_metadata, content)) in read_zip(&path)? {
+            let content = match String::from_utf8(content) {
+                Ok(content) => content,
+                Err(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("File '{}' in archive '{}' is not text", file, raw_source),
+                    ))
+                }
+            };
+            let target = Location { drive: Some(key.clone()), path: format!("/{}", file) };
+            files.push((target.to_string(), content));
+        }
+
+        for (target, content) in &files {
+            self.put(target, content).await?;
+        }
+        Ok(files.len())
+    }
+
     /// Loads the contents of the program given by `raw_location`.
     pub async fn get(&self, raw_location: &str) -> io::Result<String> {
         let location = Location::new(raw_location)?;
@@ -548,11 +1039,26 @@ impl Storage {
         }
     }
 
+    /// Gets the revisions of the file at `raw_location`, newest first.
+    pub async fn history(&self, raw_location: &str) -> io::Result<Vec<FileRevision>> {
+        let location = Location::new(raw_location)?;
+        match location.leaf_name() {
+            Some(name) => self.get_drive(&location)?.history(name).await,
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Missing file name in path '{}'", raw_location),
+            )),
+        }
+    }
+
     /// Saves the in-memory program given by `content` into `raw_location`.
     pub async fn put(&mut self, raw_location: &str, content: &str) -> io::Result<()> {
         let location = Location::new(raw_location)?;
         match location.leaf_name() {
-            Some(name) => self.get_drive_mut(&location)?.put(name, content).await,
+            Some(name) => {
+                self.check_quota(&location, name, content.len()).await?;
+                self.get_drive_mut(&location)?.put(name, content).await
+            }
             None => Err(io::Error::new(
                 io::ErrorKind::NotFound,
                 format!("Missing file name in path '{}'", raw_location),
@@ -560,6 +1066,28 @@ impl Storage {
         }
     }
 
+    /// Returns the metadata of the file given by `raw_location`.
+    ///
+    /// Files in drives that were mounted in read-only mode are always reported as read-only.
+    pub async fn stat(&self, raw_location: &str) -> io::Result<Metadata> {
+        let location = Location::new(raw_location)?;
+        let name = match location.leaf_name() {
+            Some(name) => name,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Missing file name in path '{}'", raw_location),
+                ))
+            }
+        };
+        let mut metadata = self.get_drive(&location)?.stat(name).await?;
+        let key = location.drive.as_ref().unwrap_or(&self.current);
+        if let Some(mounted_drive) = self.drives.get(key) {
+            metadata.read_only |= mounted_drive.read_only;
+        }
+        Ok(metadata)
+    }
+
     /// Updates the ACLs of the file `raw_location` by extending them with the contents of `add` and
     /// removing the existing entries listed in `remove`.
     pub async fn update_acls(
@@ -586,12 +1114,59 @@ impl Storage {
             None => Ok(self.get_drive(&location)?.system_path("")),
         }
     }
+
+    /// Starts watching the file given by `raw_location` for modifications done outside of the
+    /// storage subsystem.
+    ///
+    /// Watching a file does not modify it, so this is allowed on read-only drives.
+    pub async fn watch(&self, raw_location: &str) -> io::Result<()> {
+        let location = Location::new(raw_location)?;
+        match location.leaf_name() {
+            Some(name) => self.get_drive(&location)?.watch(name).await,
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Missing file name in path '{}'", raw_location),
+            )),
+        }
+    }
+
+    /// Stops watching the file given by `raw_location` for modifications.
+    pub fn unwatch(&self, raw_location: &str) -> io::Result<()> {
+        let location = Location::new(raw_location)?;
+        match location.leaf_name() {
+            Some(name) => {
+                self.get_drive(&location)?.unwatch(name);
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Missing file name in path '{}'", raw_location),
+            )),
+        }
+    }
+
+    /// Returns the canonical locations of the watched files that were modified outside of the
+    /// storage subsystem since the last call, sorted by drive and file name.
+    pub async fn poll_changes(&self) -> io::Result<Vec<String>> {
+        let mut keys = self.drives.keys().collect::<Vec<&DriveKey>>();
+        keys.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut changes = vec![];
+        for key in keys {
+            let drive = self.drives.get(key).expect("Key must exist").drive.as_ref();
+            for name in drive.poll_changes().await? {
+                changes.push(format!("{}:/{}", key, name));
+            }
+        }
+        Ok(changes)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use futures_lite::future::block_on;
+    use std::path::Path;
 
     #[test]
     fn test_split_uri_ok() {
@@ -698,7 +1273,7 @@ mod tests {
     #[test]
     fn test_storage_make_canonical_ok() {
         let mut storage = Storage::default();
-        storage.mount("some", "memory://").unwrap();
+        storage.mount("some", "memory://", false).unwrap();
 
         assert_eq!("MEMORY:foo.bar", storage.make_canonical("foo.bar").unwrap());
         assert_eq!("MEMORY:/foo.bar", storage.make_canonical("/foo.bar").unwrap());
@@ -776,8 +1351,8 @@ mod tests {
     fn test_storage_mount_ok() {
         let mut storage = Storage::default();
         storage.register_scheme("fake", Box::from(InMemoryDriveFactory::default()));
-        storage.mount("a", "memory://").unwrap();
-        storage.mount("z", "fAkE://").unwrap();
+        storage.mount("a", "memory://", false).unwrap();
+        storage.mount("z", "fAkE://", false).unwrap();
 
         assert_eq!(["A", "MEMORY", "Z"], drive_names(&storage).as_slice());
     }
@@ -790,8 +1365,8 @@ mod tests {
 
         let mut storage = Storage::default();
         storage.register_scheme("file", Box::from(DirectoryDriveFactory::default()));
-        storage.mount("c", &format!("file://{}", dir1.display())).unwrap();
-        storage.mount("d", &format!("file://{}", dir2.display())).unwrap();
+        storage.mount("c", &format!("file://{}", dir1.display()), false).unwrap();
+        storage.mount("d", &format!("file://{}", dir2.display()), false).unwrap();
 
         block_on(storage.put("c:file1.txt", "hi")).unwrap();
         block_on(storage.put("d:file2.txt", "bye")).unwrap();
@@ -807,7 +1382,7 @@ mod tests {
         let mut storage = Storage::default();
         assert_eq!(
             "Unknown mount scheme 'fake'",
-            format!("{}", storage.mount("a", "fake://abc").unwrap_err())
+            format!("{}", storage.mount("a", "fake://abc", false).unwrap_err())
         );
     }
 
@@ -816,14 +1391,14 @@ mod tests {
         let mut storage = Storage::default();
         assert_eq!(
             "Cannot specify a path to mount an in-memory drive",
-            format!("{}", storage.mount("a", "memory://abc").unwrap_err())
+            format!("{}", storage.mount("a", "memory://abc", false).unwrap_err())
         );
     }
 
     #[test]
     fn test_storage_unmount_ok() {
         let mut storage = Storage::default();
-        storage.mount("other", "memory://").unwrap();
+        storage.mount("other", "memory://", false).unwrap();
         assert_eq!("MEMORY:/", storage.cwd());
         assert_eq!(["MEMORY", "OTHER"], drive_names(&storage).as_slice());
 
@@ -844,7 +1419,7 @@ mod tests {
     #[test]
     fn test_storage_unmount_current_drive_error() {
         let mut storage = Storage::default();
-        storage.mount("other", "memory://").unwrap();
+        storage.mount("other", "memory://", false).unwrap();
         assert_eq!(
             "Cannot unmount the current drive 'memory'",
             format!("{}", storage.unmount("memory").unwrap_err())
@@ -859,7 +1434,7 @@ mod tests {
     fn test_storage_mounted() {
         let mut storage = Storage::default();
         storage.register_scheme("fake", Box::from(InMemoryDriveFactory::default()));
-        storage.mount("z", "fAkE://").unwrap();
+        storage.mount("z", "fAkE://", false).unwrap();
 
         let mut exp_info = BTreeMap::default();
         exp_info.insert("MEMORY", "memory://");
@@ -867,10 +1442,281 @@ mod tests {
         assert_eq!(exp_info, storage.mounted());
     }
 
+    #[test]
+    fn test_storage_read_only() {
+        let mut storage = Storage::default();
+        storage.mount("ro", "memory://", true).unwrap();
+        assert!(storage.is_read_only("ro").unwrap());
+        assert!(!storage.is_read_only("memory").unwrap());
+        assert_eq!(
+            "Drive 'X' is not mounted",
+            format!("{}", storage.is_read_only("x").unwrap_err())
+        );
+
+        let err = block_on(storage.put("ro:foo.bas", "text")).unwrap_err();
+        assert_eq!(io::ErrorKind::PermissionDenied, err.kind());
+        assert_eq!("Drive 'RO' is read-only", format!("{}", err));
+        let err = block_on(storage.delete("ro:foo.bas")).unwrap_err();
+        assert_eq!(io::ErrorKind::PermissionDenied, err.kind());
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            block_on(storage.get("ro:foo.bas")).unwrap_err().kind()
+        );
+        assert_eq!(0, block_on(storage.enumerate("ro:")).unwrap().dirents().len());
+    }
+
+    #[test]
+    fn test_storage_quota() {
+        let mut storage = Storage::default();
+        storage.mount("other", "memory://", false).unwrap();
+        storage.set_quota("memory", Some(10)).unwrap();
+
+        block_on(storage.put("memory:a.bas", "12345")).unwrap();
+        block_on(storage.put("memory:b.bas", "123")).unwrap();
+        block_on(storage.put("memory:a.bas", "1234567")).unwrap();
+        let err = block_on(storage.put("b.bas", "1234")).unwrap_err();
+        assert_eq!(io::ErrorKind::Other, err.kind());
+        assert_eq!("Drive 'MEMORY' would exceed its quota of 10 bytes", format!("{}", err));
+        assert_eq!("123", block_on(storage.get("memory:b.bas")).unwrap());
+
+        let err = block_on(storage.copy("memory:a.bas", "memory:c.bas")).unwrap_err();
+        assert_eq!("Drive 'MEMORY' would exceed its quota of 10 bytes", format!("{}", err));
+        block_on(storage.copy("memory:a.bas", "other:c.bas")).unwrap();
+
+        storage.set_quota("memory", None).unwrap();
+        block_on(storage.put("memory:b.bas", "1234")).unwrap();
+
+        assert_eq!(
+            "Drive 'X' is not mounted",
+            format!("{}", storage.set_quota("x", Some(1)).unwrap_err())
+        );
+    }
+
+    #[test]
+    fn test_storage_usage() {
+        let mut storage = Storage::default();
+        block_on(storage.put("memory:a.bas", "12345")).unwrap();
+        block_on(storage.put("memory:b.bas", "123")).unwrap();
+        assert_eq!(
+            DriveUsage { used: 8, quota: None, free: None },
+            block_on(storage.usage("memory")).unwrap()
+        );
+
+        storage.set_quota("memory", Some(100)).unwrap();
+        assert_eq!(
+            DriveUsage { used: 8, quota: Some(100), free: Some(92) },
+            block_on(storage.usage("memory")).unwrap()
+        );
+
+        storage.set_quota("memory", Some(5)).unwrap();
+        assert_eq!(
+            DriveUsage { used: 8, quota: Some(5), free: Some(0) },
+            block_on(storage.usage("memory")).unwrap()
+        );
+
+        assert_eq!(
+            "Drive 'X' is not mounted",
+            format!("{}", block_on(storage.usage("x")).unwrap_err())
+        );
+    }
+
+    #[test]
+    fn test_storage_usage_combines_drive_limits() {
+        let mut drive = InMemoryDrive::default();
+        drive.fake_disk_quota = Some(DiskSpace::new(50, 10));
+        drive.fake_disk_free = Some(DiskSpace::new(20, 5));
+        let mut storage = Storage::default();
+        storage.attach("x", "memory://", Box::from(drive)).unwrap();
+        block_on(storage.put("x:a.bas", "12345")).unwrap();
+
+        assert_eq!(
+            DriveUsage { used: 5, quota: Some(50), free: Some(20) },
+            block_on(storage.usage("x")).unwrap()
+        );
+
+        storage.set_quota("x", Some(100)).unwrap();
+        assert_eq!(
+            DriveUsage { used: 5, quota: Some(50), free: Some(20) },
+            block_on(storage.usage("x")).unwrap()
+        );
+
+        storage.set_quota("x", Some(10)).unwrap();
+        assert_eq!(
+            DriveUsage { used: 5, quota: Some(10), free: Some(5) },
+            block_on(storage.usage("x")).unwrap()
+        );
+    }
+
+    /// Creates a storage subsystem with a `LOCAL` drive backed by the directory `dir`.
+    fn storage_with_local_dir(dir: &Path) -> Storage {
+        let mut storage = Storage::default();
+        let drive = DirectoryDrive::new(dir).unwrap();
+        storage.attach("local", &format!("file://{}", dir.display()), Box::from(drive)).unwrap();
+        storage
+    }
+
+    #[test]
+    fn test_storage_backup_and_recover() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = storage_with_local_dir(dir.path());
+        storage.mount("other", "memory://", false).unwrap();
+        block_on(storage.put("memory:a.bas", "first")).unwrap();
+        block_on(storage.put("memory:b.txt", "second")).unwrap();
+
+        assert_eq!(2, block_on(storage.backup("memory", "local:backup.zip")).unwrap());
+        assert!(dir.path().join("backup.zip").exists());
+
+        block_on(storage.put("other:b.txt", "old")).unwrap();
+        block_on(storage.put("other:c.bas", "untouched")).unwrap();
+        assert_eq!(2, block_on(storage.recover("local:backup.zip", "other")).unwrap());
+        assert_eq!("first", block_on(storage.get("other:a.bas")).unwrap());
+        assert_eq!("second", block_on(storage.get("other:b.txt")).unwrap());
+        assert_eq!("untouched", block_on(storage.get("other:c.bas")).unwrap());
+    }
+
+    #[test]
+    fn test_storage_backup_preserves_dates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = storage_with_local_dir(dir.path());
+        block_on(storage.put("memory:a.bas", "content")).unwrap();
+        let date = block_on(storage.stat("memory:a.bas")).unwrap().date;
+
+        block_on(storage.backup("memory", "local:backup.zip")).unwrap();
+        let entries = read_zip(&dir.path().join("backup.zip")).unwrap();
+        let (metadata, content) = entries.get("a.bas").unwrap();
+        assert_eq!(b"content", content.as_slice());
+        assert_eq!(7, metadata.length);
+        assert!((metadata.date - date).whole_seconds().abs() <= 2);
+    }
+
+    #[test]
+    fn test_storage_backup_into_same_drive() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = storage_with_local_dir(dir.path());
+        block_on(storage.put("local:a.bas", "content")).unwrap();
+
+        assert_eq!(1, block_on(storage.backup("local", "local:backup.zip")).unwrap());
+        assert_eq!(1, block_on(storage.backup("local", "local:backup.zip")).unwrap());
+        let entries = read_zip(&dir.path().join("backup.zip")).unwrap();
+        assert_eq!(vec!["a.bas"], entries.keys().collect::<Vec<&String>>());
+    }
+
+    #[test]
+    fn test_storage_backup_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = storage_with_local_dir(dir.path());
+        storage.mount("ro", "memory://", true).unwrap();
+
+        assert_eq!(
+            "Cannot use 'memory:x.zip' as an archive: drive is not a local directory",
+            format!("{}", block_on(storage.backup("local", "memory:x.zip")).unwrap_err())
+        );
+        assert_eq!(
+            "Missing file name in path 'local:'",
+            format!("{}", block_on(storage.backup("memory", "local:")).unwrap_err())
+        );
+        assert_eq!(
+            "Drive 'RO' is read-only",
+            format!("{}", block_on(storage.backup("memory", "ro:x.zip")).unwrap_err())
+        );
+        assert_eq!(
+            "Drive 'X' is not mounted",
+            format!("{}", block_on(storage.backup("x", "local:x.zip")).unwrap_err())
+        );
+        assert!(!dir.path().join("x.zip").exists());
+    }
+
+    #[test]
+    fn test_storage_recover_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = storage_with_local_dir(dir.path());
+        storage.mount("ro", "memory://", true).unwrap();
+        block_on(storage.put("local:a.bas", "content")).unwrap();
+        block_on(storage.backup("local", "local:backup.zip")).unwrap();
+
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            block_on(storage.recover("local:missing.zip", "memory")).unwrap_err().kind()
+        );
+        assert_eq!(
+            "Cannot use 'memory:x.zip' as an archive: drive is not a local directory",
+            format!("{}", block_on(storage.recover("memory:x.zip", "local")).unwrap_err())
+        );
+        assert_eq!(
+            "Drive 'RO' is read-only",
+            format!("{}", block_on(storage.recover("local:backup.zip", "ro")).unwrap_err())
+        );
+        assert_eq!(
+            "Drive 'X' is not mounted",
+            format!("{}", block_on(storage.recover("local:backup.zip", "x")).unwrap_err())
+        );
+
+        let mut entries = ZipEntries::default();
+        let metadata = block_on(storage.stat("local:a.bas")).unwrap();
+        entries.insert("a.bas".to_owned(), (metadata.clone(), b"text".to_vec()));
+        entries.insert("b.bin".to_owned(), (metadata, vec![0xff, 0xfe]));
+        write_zip(&dir.path().join("binary.zip"), &entries).unwrap();
+        assert_eq!(
+            "File 'b.bin' in archive 'local:binary.zip' is not text",
+            format!("{}", block_on(storage.recover("local:binary.zip", "memory")).unwrap_err())
+        );
+        assert!(block_on(storage.enumerate("memory:")).unwrap().dirents().is_empty());
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("", ""));
+        assert!(matches_pattern("*", ""));
+        assert!(matches_pattern("*", "foo.bas"));
+        assert!(matches_pattern("*.BAS", "foo.bas"));
+        assert!(matches_pattern("f?o.*", "FOO.bas"));
+        assert!(matches_pattern("*o*o*", "foo.boo"));
+        assert!(matches_pattern("a*b*c", "aXbXbXc"));
+        assert!(!matches_pattern("", "a"));
+        assert!(!matches_pattern("*.bas", "foo.bat"));
+        assert!(!matches_pattern("?", ""));
+        assert!(!matches_pattern("f?", "foo"));
+        assert!(!matches_pattern("a*b*c", "aXbXbX"));
+    }
+
+    #[test]
+    fn test_storage_glob() {
+        let mut storage = Storage::default();
+        storage.mount("other", "memory://", false).unwrap();
+        block_on(storage.put("memory:b.bas", "")).unwrap();
+        block_on(storage.put("memory:a.bas", "")).unwrap();
+        block_on(storage.put("memory:c.txt", "")).unwrap();
+        block_on(storage.put("other:d.bas", "")).unwrap();
+
+        assert_eq!(
+            vec!["MEMORY:/a.bas", "MEMORY:/b.bas"],
+            block_on(storage.glob("*.bas")).unwrap()
+        );
+        assert_eq!(vec!["OTHER:/d.bas"], block_on(storage.glob("other:/?.BAS")).unwrap());
+        assert_eq!(vec!["MEMORY:missing"], block_on(storage.glob("missing")).unwrap());
+        assert_eq!(
+            "No files match 'other:*.txt'",
+            format!("{}", block_on(storage.glob("other:*.txt")).unwrap_err())
+        );
+        assert_eq!(
+            "Missing file name in path 'other:'",
+            format!("{}", block_on(storage.glob("other:")).unwrap_err())
+        );
+    }
+
+    #[test]
+    fn test_storage_enumerate_pattern() {
+        let mut storage = Storage::default();
+        block_on(storage.put("a.bas", "")).unwrap();
+        block_on(storage.put("b.txt", "")).unwrap();
+        let files = block_on(storage.enumerate("memory:/*.bas")).unwrap();
+        assert_eq!(vec!["a.bas"], files.dirents().keys().collect::<Vec<&String>>());
+    }
+
     #[test]
     fn test_storage_cd_and_cwd_ok() {
         let mut storage = Storage::default();
-        storage.mount("other", "memory://").unwrap();
+        storage.mount("other", "memory://", false).unwrap();
         assert_eq!("MEMORY:/", storage.cwd());
         storage.cd("other:/").unwrap();
         assert_eq!("OTHER:/", storage.cwd());
@@ -890,7 +1736,7 @@ mod tests {
     #[test]
     fn test_storage_file_ops_with_absolute_paths() {
         let mut storage = Storage::default();
-        storage.mount("other", "memory://").unwrap();
+        storage.mount("other", "memory://", false).unwrap();
 
         block_on(storage.put("other:/f1", "some text")).unwrap();
         block_on(storage.put("other:f2", "other text")).unwrap();
@@ -921,7 +1767,7 @@ mod tests {
     #[test]
     fn test_storage_file_ops_with_relative_paths() {
         let mut storage = Storage::default();
-        storage.mount("other", "memory://").unwrap();
+        storage.mount("other", "memory://", false).unwrap();
 
         block_on(storage.put("/f1", "some text")).unwrap();
         block_on(storage.put("f2", "other text")).unwrap();
@@ -1017,6 +1863,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_guess_kind() {
+        assert_eq!(Some("text/x-basic".to_owned()), guess_kind("foo.bas"));
+        assert_eq!(Some("text/x-basic".to_owned()), guess_kind("A.B.BAS"));
+        assert_eq!(Some("application/zip".to_owned()), guess_kind("archive.Zip"));
+        assert_eq!(None, guess_kind("foo"));
+        assert_eq!(None, guess_kind("foo."));
+        assert_eq!(None, guess_kind("foo.unknown"));
+    }
+
+    #[test]
+    fn test_storage_stat() {
+        let mut storage = Storage::default();
+        block_on(storage.put("memory:foo.bas", "12345")).unwrap();
+        let metadata = block_on(storage.stat("foo.bas")).unwrap();
+        assert_eq!(5, metadata.length);
+        assert_eq!(Some("text/x-basic"), metadata.kind.as_deref());
+        assert!(!metadata.read_only);
+
+        storage.mount("ro", "memory://", false).unwrap();
+        block_on(storage.put("ro:data.txt", "")).unwrap();
+        storage.drives.get_mut(&DriveKey::new("ro").unwrap()).unwrap().read_only = true;
+        let metadata = block_on(storage.stat("ro:/data.txt")).unwrap();
+        assert_eq!(0, metadata.length);
+        assert_eq!(Some("text/plain"), metadata.kind.as_deref());
+        assert!(metadata.read_only);
+    }
+
+    #[test]
+    fn test_storage_stat_errors() {
+        let storage = Storage::default();
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            block_on(storage.stat("missing.bas")).unwrap_err().kind()
+        );
+        assert_eq!(
+            "Drive 'A' is not mounted",
+            format!("{}", block_on(storage.stat("a:foo.bas")).unwrap_err())
+        );
+        assert_eq!(
+            "Missing file name in path 'memory:'",
+            format!("{}", block_on(storage.stat("memory:")).unwrap_err())
+        );
+    }
+
     #[test]
     fn test_storage_system_path_ok() {
         let dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,512 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Decorator to keep using drives backed by remote media while disconnected.
+
+use crate::storage::{guess_kind, Drive, DriveFiles, FileAcls, FileRevision, Metadata};
+use async_trait::async_trait;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::PathBuf;
+
+/// Returns true if `e` indicates that the underlying medium could not be reached, as opposed to
+/// the medium rejecting the operation.
+fn is_offline_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::AddrNotAvailable
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::TimedOut
+    )
+}
+
+/// Computes the name under which to save the offline version of `name` when it conflicts with
+/// changes done to the underlying medium.
+fn conflict_name(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((base, ext)) if !base.is_empty() => format!("{}-CONFLICT.{}", base, ext),
+        _ => format!("{}-CONFLICT", name),
+    }
+}
+
+/// A modification done to a file while the underlying medium was unreachable.
+enum Change {
+    /// The file was written with the given content.
+    Put(String),
+
+    /// The file was deleted.
+    Delete,
+}
+
+/// A change pending to be applied to the underlying medium.
+struct PendingChange {
+    /// The modification to apply.
+    change: Change,
+
+    /// Content of the file in the underlying medium the last time we saw it before the change was
+    /// first queued, or `None` if the file did not exist or we never saw it.
+    base: Option<String>,
+}
+
+/// Outcome of reconciling the changes done while offline with the underlying medium.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct SyncReport {
+    /// Names of the files whose offline changes were applied.
+    pub synced: Vec<String>,
+
+    /// Names of the files whose offline changes conflicted with changes done to the underlying
+    /// medium, along with the name under which the offline version was saved, if any.
+    pub conflicts: Vec<(String, Option<String>)>,
+}
+
+/// A drive that wraps another drive backed by remote media and keeps working when that medium
+/// becomes unreachable.
+///
+/// While the wrapped drive is reachable, operations go straight to it and this drive remembers
+/// the contents it sees.  Once the wrapped drive becomes unreachable, reads are served from the
+/// remembered contents and writes are queued.  Queued writes are only applied when explicitly
+/// requested via `sync` so that conflicts with changes done elsewhere can be reported.
+pub struct OfflineDrive {
+    /// The drive whose contents are made available offline.
+    inner: Box<dyn Drive>,
+
+    /// Last known contents of the files in the wrapped drive.
+    known: RefCell<HashMap<String, String>>,
+
+    /// Last known listing of the wrapped drive.
+    listing: RefCell<Option<DriveFiles>>,
+
+    /// Changes queued while offline, keyed by file name.
+    pending: BTreeMap<String, PendingChange>,
+}
+
+impl OfflineDrive {
+    /// Creates a new drive that makes the contents of `inner` available while offline.
+    pub fn new(inner: Box<dyn Drive>) -> Self {
+        Self {
+            inner,
+            known: RefCell::from(HashMap::default()),
+            listing: RefCell::from(None),
+            pending: BTreeMap::default(),
+        }
+    }
+
+    /// Queues `change` to `name` for later application to the wrapped drive.
+    fn queue(&mut self, name: &str, change: Change) {
+        match self.pending.get_mut(name) {
+            Some(pending) => pending.change = change,
+            None => {
+                let base = self.known.borrow().get(name).cloned();
+                self.pending.insert(name.to_owned(), PendingChange { change, base });
+            }
+        }
+    }
+
+    /// Applies the pending change to `name` to the wrapped drive and records the outcome in
+    /// `report`.
+    async fn sync_one(
+        &mut self,
+        name: &str,
+        pending: &PendingChange,
+        report: &mut SyncReport,
+    ) -> io::Result<()> {
+        let current = match self.inner.get(name).await {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        if current == pending.base {
+            match &pending.change {
+                Change::Put(content) => {
+                    self.inner.put(name, content).await?;
+                    self.known.borrow_mut().insert(name.to_owned(), content.clone());
+                }
+                Change::Delete => {
+                    if current.is_some() {
+                        self.inner.delete(name).await?;
+                    }
+                    self.known.borrow_mut().remove(name);
+                }
+            }
+            report.synced.push(name.to_owned());
+        } else {
+            match &pending.change {
+                Change::Put(content) => {
+                    let copy = conflict_name(name);
+                    self.inner.put(&copy, content).await?;
+                    report.conflicts.push((name.to_owned(), Some(copy)));
+                }
+                Change::Delete => report.conflicts.push((name.to_owned(), None)),
+            }
+            match current {
+                Some(content) => self.known.borrow_mut().insert(name.to_owned(), content),
+                None => self.known.borrow_mut().remove(name),
+            };
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Drive for OfflineDrive {
+    async fn delete(&mut self, name: &str) -> io::Result<()> {
+        if let Some(pending) = self.pending.get(name) {
+            if let Change::Delete = pending.change {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found"));
+            }
+            self.queue(name, Change::Delete);
+            return Ok(());
+        }
+
+        match self.inner.delete(name).await {
+            Ok(()) => {
+                self.known.borrow_mut().remove(name);
+                Ok(())
+            }
+            Err(e) if is_offline_error(&e) => {
+                self.queue(name, Change::Delete);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn enumerate(&self) -> io::Result<DriveFiles> {
+        let files = match self.inner.enumerate().await {
+            Ok(files) => {
+                *self.listing.borrow_mut() = Some(files.clone());
+                files
+            }
+            Err(e) if is_offline_error(&e) => match self.listing.borrow().as_ref() {
+                Some(files) => files.clone(),
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
+
+        if self.pending.is_empty() {
+            return Ok(files);
+        }
+
+        let mut dirents = files.dirents().clone();
+        for (name, pending) in &self.pending {
+            match &pending.change {
+                Change::Put(content) => {
+                    let metadata = Metadata {
+                        date: time::OffsetDateTime::now_utc(),
+                        length: content.len() as u64,
+                        kind: guess_kind(name),
+                        read_only: false,
+                    };
+                    dirents.insert(name.clone(), metadata);
+                }
+                Change::Delete => {
+                    dirents.remove(name);
+                }
+            }
+        }
+        Ok(DriveFiles::new(dirents, *files.disk_quota(), *files.disk_free()))
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+
+    async fn get(&self, name: &str) -> io::Result<String> {
+        if let Some(pending) = self.pending.get(name) {
+            return match &pending.change {
+                Change::Put(content) => Ok(content.clone()),
+                Change::Delete => Err(io::Error::new(io::ErrorKind::NotFound, "Entry not found")),
+            };
+        }
+
+        match self.inner.get(name).await {
+            Ok(content) => {
+                self.known.borrow_mut().insert(name.to_owned(), content.clone());
+                Ok(content)
+            }
+            Err(e) if is_offline_error(&e) => match self.known.borrow().get(name) {
+                Some(content) => Ok(content.clone()),
+                None => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn get_acls(&self, name: &str) -> io::Result<FileAcls> {
+        self.inner.get_acls(name).await
+    }
+
+    async fn history(&self, name: &str) -> io::Result<Vec<FileRevision>> {
+        self.inner.history(name).await
+    }
+
+    async fn poll_changes(&self) -> io::Result<Vec<String>> {
+        self.inner.poll_changes().await
+    }
+
+    async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
+        if self.pending.contains_key(name) {
+            self.queue(name, Change::Put(content.to_owned()));
+            return Ok(());
+        }
+
+        match self.inner.put(name, content).await {
+            Ok(()) => {
+                self.known.borrow_mut().insert(name.to_owned(), content.to_owned());
+                Ok(())
+            }
+            Err(e) if is_offline_error(&e) => {
+                self.queue(name, Change::Put(content.to_owned()));
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn sync(&mut self) -> io::Result<SyncReport> {
+        let mut report = self.inner.sync().await?;
+        while let Some(name) = self.pending.keys().next().cloned() {
+            let pending = self.pending.remove(&name).expect("Key was just obtained from the map");
+            if let Err(e) = self.sync_one(&name, &pending, &mut report).await {
+                // Keep the change around so that a later sync can retry it.
+                self.pending.insert(name, pending);
+                return Err(e);
+            }
+        }
+        Ok(report)
+    }
+
+    async fn update_acls(
+        &mut self,
+        name: &str,
+        add: &FileAcls,
+        remove: &FileAcls,
+    ) -> io::Result<()> {
+        self.inner.update_acls(name, add, remove).await
+    }
+
+    fn system_path(&self, name: &str) -> Option<PathBuf> {
+        self.inner.system_path(name)
+    }
+
+    fn unwatch(&self, name: &str) {
+        self.inner.unwatch(name)
+    }
+
+    async fn watch(&self, name: &str) -> io::Result<()> {
+        self.inner.watch(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryDrive;
+    use futures_lite::future::block_on;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Drive backed by a shared in-memory drive that can be made unreachable on demand.
+    struct FlakyDrive {
+        server: Rc<RefCell<InMemoryDrive>>,
+        offline: Rc<Cell<bool>>,
+    }
+
+    impl FlakyDrive {
+        /// Fails with a connectivity error if the drive is currently unreachable.
+        fn check_online(&self) -> io::Result<()> {
+            if self.offline.get() {
+                Err(io::Error::new(io::ErrorKind::NotConnected, "Network is down"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl Drive for FlakyDrive {
+        async fn delete(&mut self, name: &str) -> io::Result<()> {
+            self.check_online()?;
+            block_on(self.server.borrow_mut().delete(name))
+        }
+
+        async fn enumerate(&self) -> io::Result<DriveFiles> {
+            self.check_online()?;
+            block_on(self.server.borrow().enumerate())
+        }
+
+        async fn get(&self, name: &str) -> io::Result<String> {
+            self.check_online()?;
+            block_on(self.server.borrow().get(name))
+        }
+
+        async fn put(&mut self, name: &str, content: &str) -> io::Result<()> {
+            self.check_online()?;
+            block_on(self.server.borrow_mut().put(name, content))
+        }
+    }
+
+    /// Container for an offline drive and the state needed to control its behavior.
+    struct TestContext {
+        drive: OfflineDrive,
+        server: Rc<RefCell<InMemoryDrive>>,
+        offline: Rc<Cell<bool>>,
+    }
+
+    impl TestContext {
+        /// Creates an offline drive on top of a reachable in-memory drive that contains the given
+        /// `files`.
+        fn new(files: &[(&str, &str)]) -> Self {
+            let server = Rc::from(RefCell::from(InMemoryDrive::default()));
+            for (name, content) in files {
+                block_on(server.borrow_mut().put(name, content)).unwrap();
+            }
+            let offline = Rc::from(Cell::new(false));
+            let inner = FlakyDrive { server: server.clone(), offline: offline.clone() };
+            Self { drive: OfflineDrive::new(Box::from(inner)), server, offline }
+        }
+
+        /// Returns the content of `name` in the wrapped drive.
+        fn server_get(&self, name: &str) -> io::Result<String> {
+            block_on(self.server.borrow().get(name))
+        }
+    }
+
+    #[test]
+    fn test_conflict_name() {
+        assert_eq!("FOO-CONFLICT.BAS", conflict_name("FOO.BAS"));
+        assert_eq!("a.b-CONFLICT.c", conflict_name("a.b.c"));
+        assert_eq!("FOO-CONFLICT", conflict_name("FOO"));
+        assert_eq!(".hidden-CONFLICT", conflict_name(".hidden"));
+    }
+
+    #[test]
+    fn test_offlinedrive_online_passthrough() {
+        let mut context = TestContext::new(&[("a.bas", "content")]);
+
+        assert_eq!("content", block_on(context.drive.get("a.bas")).unwrap());
+        block_on(context.drive.put("b.bas", "other")).unwrap();
+        block_on(context.drive.delete("a.bas")).unwrap();
+        assert_eq!("other", context.server_get("b.bas").unwrap());
+        assert_eq!(io::ErrorKind::NotFound, context.server_get("a.bas").unwrap_err().kind());
+        assert_eq!(SyncReport::default(), block_on(context.drive.sync()).unwrap());
+    }
+
+    #[test]
+    fn test_offlinedrive_reads_known_contents_while_offline() {
+        let context = TestContext::new(&[("a.bas", "content"), ("b.bas", "other")]);
+
+        assert_eq!("content", block_on(context.drive.get("a.bas")).unwrap());
+        assert_eq!(2, block_on(context.drive.enumerate()).unwrap().dirents().len());
+
+        context.offline.set(true);
+        assert_eq!("content", block_on(context.drive.get("a.bas")).unwrap());
+        assert_eq!(2, block_on(context.drive.enumerate()).unwrap().dirents().len());
+        assert_eq!(
+            io::ErrorKind::NotConnected,
+            block_on(context.drive.get("b.bas")).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn test_offlinedrive_queues_writes_and_syncs() {
+        let mut context = TestContext::new(&[("a.bas", "old"), ("b.bas", "doomed")]);
+        block_on(context.drive.enumerate()).unwrap();
+        block_on(context.drive.get("a.bas")).unwrap();
+        block_on(context.drive.get("b.bas")).unwrap();
+
+        context.offline.set(true);
+        block_on(context.drive.put("a.bas", "new")).unwrap();
+        block_on(context.drive.put("c.bas", "created")).unwrap();
+        block_on(context.drive.delete("b.bas")).unwrap();
+
+        assert_eq!("new", block_on(context.drive.get("a.bas")).unwrap());
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            block_on(context.drive.get("b.bas")).unwrap_err().kind()
+        );
+        let files = block_on(context.drive.enumerate()).unwrap();
+        assert_eq!(
+            vec!["a.bas", "c.bas"],
+            files.dirents().keys().map(String::as_str).collect::<Vec<&str>>()
+        );
+        assert_eq!(3, files.dirents()["a.bas"].length);
+        assert_eq!("old", context.server_get("a.bas").unwrap());
+
+        assert_eq!(io::ErrorKind::NotConnected, block_on(context.drive.sync()).unwrap_err().kind());
+
+        context.offline.set(false);
+        let report = block_on(context.drive.sync()).unwrap();
+        assert_eq!(
+            SyncReport {
+                synced: vec!["a.bas".to_owned(), "b.bas".to_owned(), "c.bas".to_owned()],
+                conflicts: vec![],
+            },
+            report
+        );
+        assert_eq!("new", context.server_get("a.bas").unwrap());
+        assert_eq!(io::ErrorKind::NotFound, context.server_get("b.bas").unwrap_err().kind());
+        assert_eq!("created", context.server_get("c.bas").unwrap());
+
+        assert_eq!(SyncReport::default(), block_on(context.drive.sync()).unwrap());
+    }
+
+    #[test]
+    fn test_offlinedrive_sync_reports_conflicts() {
+        let mut context = TestContext::new(&[("a.bas", "old"), ("b.bas", "old")]);
+        block_on(context.drive.get("a.bas")).unwrap();
+        block_on(context.drive.get("b.bas")).unwrap();
+
+        context.offline.set(true);
+        block_on(context.drive.put("a.bas", "mine")).unwrap();
+        block_on(context.drive.delete("b.bas")).unwrap();
+
+        block_on(context.server.borrow_mut().put("a.bas", "theirs")).unwrap();
+        block_on(context.server.borrow_mut().put("b.bas", "theirs")).unwrap();
+
+        context.offline.set(false);
+        let report = block_on(context.drive.sync()).unwrap();
+        assert_eq!(
+            SyncReport {
+                synced: vec![],
+                conflicts: vec![
+                    ("a.bas".to_owned(), Some("a-CONFLICT.bas".to_owned())),
+                    ("b.bas".to_owned(), None),
+                ],
+            },
+            report
+        );
+        assert_eq!("theirs", context.server_get("a.bas").unwrap());
+        assert_eq!("mine", context.server_get("a-CONFLICT.bas").unwrap());
+        assert_eq!("theirs", context.server_get("b.bas").unwrap());
+        assert_eq!("theirs", block_on(context.drive.get("a.bas")).unwrap());
+    }
+
+    #[test]
+    fn test_offlinedrive_other_errors_are_not_queued() {
+        let mut context = TestContext::new(&[]);
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            block_on(context.drive.delete("missing.bas")).unwrap_err().kind()
+        );
+        assert_eq!(SyncReport::default(), block_on(context.drive.sync()).unwrap());
+    }
+}
@@ -0,0 +1,453 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Commands to save and restore the variables of the machine.
+
+use super::cmds::CATEGORY;
+use crate::storage::Storage;
+use async_trait::async_trait;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Value, VarRef, VarType};
+use endbasic_core::exec::Machine;
+use endbasic_core::syms::{
+    Array, CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Symbol,
+    Symbols,
+};
+use endbasic_core::LineCol;
+use serde_json::json;
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+/// Version of the format of the state files, stored in them to detect incompatible files.
+const STATE_VERSION: u64 = 1;
+
+/// Returns true if `name` refers to a symbol that belongs to the state of the program.
+///
+/// Internal symbols, such as the one that holds the last error message, have names that cannot be
+/// typed in a program and are not part of the state.
+fn is_state_name(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_alphabetic())
+}
+
+/// Converts a type name as stored in a state file into a `VarType`.
+fn parse_vartype(name: &str) -> Result<VarType, String> {
+    match name {
+        "BOOLEAN" => Ok(VarType::Boolean),
+        "DOUBLE" => Ok(VarType::Double),
+        "INTEGER" => Ok(VarType::Integer),
+        "LONG" => Ok(VarType::Long),
+        "STRING" => Ok(VarType::Text),
+        _ => Err(format!("Unknown type {}", name)),
+    }
+}
+
+/// Converts a `value` into its representation in a state file.
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Boolean(b) => json!(b),
+        Value::Double(d) => json!(d),
+        Value::Integer(i) => json!(i),
+        Value::Long(i) => json!(i),
+        Value::Text(s) => json!(s),
+    }
+}
+
+/// Converts the `json` representation of a value of type `vtype` back into a `Value`.
+fn json_to_value(vtype: VarType, json: &serde_json::Value) -> Result<Value, String> {
+    let value = match (vtype, json) {
+        (VarType::Boolean, serde_json::Value::Bool(b)) => Some(Value::Boolean(*b)),
+        (VarType::Double, serde_json::Value::Number(n)) => n.as_f64().map(Value::Double),
+        (VarType::Integer, serde_json::Value::Number(n)) => {
+            n.as_i64().and_then(|i| i32::try_from(i).ok()).map(Value::Integer)
+        }
+        (VarType::Long, serde_json::Value::Number(n)) => n.as_i64().map(Value::Long),
+        (VarType::Text, serde_json::Value::String(s)) => Some(Value::Text(s.clone())),
+        _ => None,
+    };
+    value.ok_or_else(|| format!("Invalid {} value {}", vtype, json))
+}
+
+/// Serializes all the variables and arrays in `symbols` into the contents of a state file.
+fn save_state(symbols: &Symbols) -> String {
+    let mut names =
+        symbols.as_hashmap().keys().filter(|name| is_state_name(name)).collect::<Vec<&String>>();
+    names.sort();
+
+    let mut entries = vec![];
+    for name in names {
+        match &symbols.as_hashmap()[name] {
+            Symbol::Array(array) => entries.push(json!({
+                "name": name,
+                "type": array.subtype().to_string(),
+                "dimensions": array.dimensions(),
+                "values": array.values().iter().map(value_to_json).collect::<Vec<_>>(),
+            })),
+            Symbol::Variable(value) => entries.push(json!({
+                "name": name,
+                "type": value.as_vartype().to_string(),
+                "value": value_to_json(value),
+            })),
+            Symbol::Command(_) | Symbol::Function(_) => (),
+        }
+    }
+
+    let state = json!({ "version": STATE_VERSION, "symbols": entries });
+    let mut content = serde_json::to_string(&state).expect("Serialization cannot fail");
+    content.push('\n');
+    content
+}
+
+/// Deserializes the `content` of a state file into the symbols it defines.
+///
+/// The whole file is validated before returning so that a bad file does not leave the machine in
+/// a partially-restored state.
+fn load_state(content: &str) -> Result<Vec<(String, Symbol)>, String> {
+    let state: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    if state["version"].as_u64() != Some(STATE_VERSION) {
+        return Err("Unsupported or missing version".to_owned());
+    }
+    let entries = match state["symbols"].as_array() {
+        Some(entries) => entries,
+        None => return Err("Missing list of symbols".to_owned()),
+    };
+
+    let mut symbols = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let name = match entry["name"].as_str() {
+            Some(name) if is_state_name(name) => name.to_ascii_uppercase(),
+            _ => return Err(format!("Invalid symbol name {}", entry["name"])),
+        };
+        let vtype = parse_vartype(entry["type"].as_str().unwrap_or_default())
+            .map_err(|e| format!("{}: {}", name, e))?;
+
+        let symbol = match (entry["dimensions"].as_array(), entry["values"].as_array()) {
+            (Some(dimensions), Some(values)) => {
+                let mut dims = Vec::with_capacity(dimensions.len());
+                for dim in dimensions {
+                    match dim.as_u64() {
+                        Some(dim) if dim > 0 && dim <= i32::MAX as u64 => dims.push(dim as usize),
+                        _ => return Err(format!("{}: Invalid dimension {}", name, dim)),
+                    }
+                }
+                if dims.is_empty() || dims.iter().product::<usize>() != values.len() {
+                    return Err(format!("{}: Dimensions do not match the values", name));
+                }
+
+                let mut array = Array::new(vtype, dims.clone());
+                for (i, value) in values.iter().enumerate() {
+                    let value =
+                        json_to_value(vtype, value).map_err(|e| format!("{}: {}", name, e))?;
+                    let mut subscripts = vec![0; dims.len()];
+                    let mut rest = i;
+                    for (subscript, dim) in subscripts.iter_mut().zip(&dims).rev() {
+                        *subscript = (rest % dim) as i32;
+                        rest /= dim;
+                    }
+                    array.assign(&subscripts, value).expect("Subscripts and type are valid");
+                }
+                Symbol::Array(array)
+            }
+            (None, None) => Symbol::Variable(
+                json_to_value(vtype, &entry["value"]).map_err(|e| format!("{}: {}", name, e))?,
+            ),
+            _ => return Err(format!("{}: Incomplete array definition", name)),
+        };
+        symbols.push((name, symbol));
+    }
+    Ok(symbols)
+}
+
+/// Parses the arguments to the state commands, which take a single path, and evaluates them.
+async fn parse_path(
+    args: &[ArgSpan],
+    symbols: &mut Symbols,
+) -> Result<(String, LineCol), CallError> {
+    match args {
+        [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => match expr.eval(symbols).await? {
+            Value::Text(t) => Ok((t, expr.start_pos())),
+            _ => {
+                Err(CallError::ArgumentError(expr.start_pos(), "path$ must be a string".to_owned()))
+            }
+        },
+        _ => Err(CallError::SyntaxError),
+    }
+}
+
+/// The `LOADSTATE` command.
+pub struct LoadStateCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl LoadStateCommand {
+    /// Creates a new `LOADSTATE` command that reads state files from `storage`.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("LOADSTATE", VarType::Void)
+                .with_syntax("path$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Restores the variables and arrays saved by SAVESTATE.
+All existing variables and arrays are discarded and replaced by those in the file, which keep \
+the types and values they had when they were saved.  Other state, such as the position of READ \
+within DATA or the console colors, is not affected.
+The file is validated before making any changes, so a corrupt file leaves the variables \
+untouched.
+Unlike other file commands, no extension is assumed if path$ lacks one.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for LoadStateCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (path, path_pos) = parse_path(&span.args, machine.get_mut_symbols()).await?;
+
+        let content = self.storage.borrow().get(&path).await?;
+        let state = load_state(&content).map_err(|e| {
+            CallError::ArgumentError(path_pos, format!("Invalid state file {}: {}", path, e))
+        })?;
+
+        let symbols = machine.get_mut_symbols();
+        for (name, _) in &state {
+            if let Some(Symbol::Command(_) | Symbol::Function(_)) = symbols.get_auto(name) {
+                return Err(CallError::ArgumentError(
+                    path_pos,
+                    format!("Cannot restore {} because it is a builtin name", name),
+                ));
+            }
+        }
+
+        let old_names = symbols
+            .as_hashmap()
+            .iter()
+            .filter(|(name, symbol)| {
+                is_state_name(name) && matches!(symbol, Symbol::Array(_) | Symbol::Variable(_))
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<String>>();
+        for name in old_names {
+            symbols.unset(&name).expect("Symbol was just found");
+        }
+
+        for (name, symbol) in state {
+            match symbol {
+                Symbol::Array(array) => {
+                    symbols
+                        .dim_array(&name, array.subtype(), array.dimensions().to_owned())
+                        .expect("Name was checked to be available");
+                    let vref = VarRef::new(name, VarType::Auto);
+                    let slot = symbols.get_mut(&vref).expect("Array was just defined");
+                    *slot.expect("Array was just defined") = Symbol::Array(array);
+                }
+                Symbol::Variable(value) => symbols
+                    .set_var(&VarRef::new(name, VarType::Auto), value)
+                    .expect("Name was checked to be available"),
+                _ => unreachable!("Only variables and arrays are loaded"),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `SAVESTATE` command.
+pub struct SaveStateCommand {
+    metadata: CallableMetadata,
+    storage: Rc<RefCell<Storage>>,
+}
+
+impl SaveStateCommand {
+    /// Creates a new `SAVESTATE` command that writes state files to `storage`.
+    pub fn new(storage: Rc<RefCell<Storage>>) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SAVESTATE", VarType::Void)
+                .with_syntax("path$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Saves all variables and arrays into a file.
+The file records the name, type, and value of every variable and array, and can be loaded back \
+with LOADSTATE to resume a long-running program, such as a game, from where it was saved.  The \
+file is written in the JSON format.
+Unlike other file commands, no extension is assumed if path$ lacks one.",
+                )
+                .build(),
+            storage,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SaveStateCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (path, _path_pos) = parse_path(&span.args, machine.get_mut_symbols()).await?;
+        let content = save_state(machine.get_symbols());
+        self.storage.borrow_mut().put(&path, &content).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::*;
+
+    /// Contents of a state file with one variable or array of each type.
+    const STATE: &str = concat!(
+        r#"{"symbols":[{"name":"A","type":"INTEGER","value":3},"#,
+        r#"{"name":"B","type":"STRING","value":"hi"},"#,
+        r#"{"name":"C","type":"DOUBLE","value":1.5},"#,
+        r#"{"name":"D","type":"LONG","value":5000000000},"#,
+        r#"{"name":"E","type":"BOOLEAN","value":true},"#,
+        r#"{"dimensions":[2,3],"name":"M","type":"INTEGER","values":[0,0,0,0,7,0]}],"#,
+        r#""version":1}"#,
+        "\n"
+    );
+
+    #[test]
+    fn test_savestate_ok() {
+        Tester::default()
+            .run(
+                r#"a = 3: b$ = "hi": c# = 1.5: d& = 5000000000: e? = TRUE
+                DIM m(2, 3): m(1, 1) = 7: SAVESTATE "slot1""#,
+            )
+            .expect_var("a", 3)
+            .expect_var("b", "hi")
+            .expect_var("c", 1.5)
+            .expect_var("d", Value::Long(5000000000))
+            .expect_var("e", true)
+            .expect_array("m", VarType::Integer, &[2, 3], vec![(&[1, 1], 7.into())])
+            .expect_file("MEMORY:/slot1", STATE)
+            .check();
+    }
+
+    #[test]
+    fn test_loadstate_ok() {
+        Tester::default()
+            .write_file("slot1", STATE)
+            .run(r#"x = 1: DIM m(5) AS STRING: LOADSTATE "slot1""#)
+            .expect_var("a", 3)
+            .expect_var("b", "hi")
+            .expect_var("c", 1.5)
+            .expect_var("d", Value::Long(5000000000))
+            .expect_var("e", true)
+            .expect_array("m", VarType::Integer, &[2, 3], vec![(&[1, 1], 7.into())])
+            .expect_file("MEMORY:/slot1", STATE)
+            .check();
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let content = concat!(
+            r#"{"symbols":[{"dimensions":[2,2,2],"name":"Q","type":"DOUBLE","#,
+            r#""values":[0.0,0.0,0.0,0.0,0.0,-2.25,0.0,0.0]},"#,
+            r#"{"name":"S","type":"STRING","value":"a\"b"}],"version":1}"#,
+            "\n"
+        );
+        let mut t = Tester::default();
+        t.run(r#"s$ = "a\"b": DIM q(2, 2, 2) AS DOUBLE: q(1, 0, 1) = -2.25: SAVESTATE "s""#)
+            .expect_var("s", "a\"b")
+            .expect_array("q", VarType::Double, &[2, 2, 2], vec![(&[1, 0, 1], (-2.25).into())])
+            .expect_file("MEMORY:/s", content)
+            .check();
+        t.run(r#"s$ = "": q(1, 0, 1) = 0: t = 1: LOADSTATE "s""#)
+            .expect_var("s", "a\"b")
+            .expect_array("q", VarType::Double, &[2, 2, 2], vec![(&[1, 0, 1], (-2.25).into())])
+            .expect_file("MEMORY:/s", content)
+            .check();
+    }
+
+    #[test]
+    fn test_savestate_errors() {
+        check_stmt_err("1:1: In call to SAVESTATE: expected path$", "SAVESTATE");
+        check_stmt_err("1:1: In call to SAVESTATE: expected path$", "SAVESTATE \"a\", \"b\"");
+        check_stmt_err("1:1: In call to SAVESTATE: 1:11: path$ must be a string", "SAVESTATE 3");
+    }
+
+    #[test]
+    fn test_loadstate_errors() {
+        check_stmt_err("1:1: In call to LOADSTATE: expected path$", "LOADSTATE");
+        check_stmt_err("1:1: In call to LOADSTATE: 1:11: path$ must be a string", "LOADSTATE 3");
+        check_stmt_err("1:1: In call to LOADSTATE: Entry not found", "LOADSTATE \"missing\"");
+
+        fn check_bad_file(exp_error: &str, content: &str) {
+            Tester::default()
+                .write_file("bad", content)
+                .run("a = 1: LOADSTATE \"bad\"")
+                .expect_err(format!(
+                    "1:8: In call to LOADSTATE: 1:18: Invalid state file bad: {}",
+                    exp_error
+                ))
+                .expect_var("a", 1)
+                .expect_file("MEMORY:/bad", content)
+                .check();
+        }
+
+        check_bad_file("EOF while parsing a value at line 1 column 0", "");
+        check_bad_file("Unsupported or missing version", r#"{"symbols": []}"#);
+        check_bad_file("Unsupported or missing version", r#"{"version": 2, "symbols": []}"#);
+        check_bad_file("Missing list of symbols", r#"{"version": 1}"#);
+        check_bad_file(
+            "Invalid symbol name \"0ERRMSG\"",
+            r#"{"version": 1, "symbols": [{"name": "0ERRMSG", "type": "STRING", "value": ""}]}"#,
+        );
+        check_bad_file(
+            "X: Unknown type FOO",
+            r#"{"version": 1, "symbols": [{"name": "x", "type": "FOO", "value": 1}]}"#,
+        );
+        check_bad_file(
+            "X: Invalid INTEGER value \"1\"",
+            r#"{"version": 1, "symbols": [{"name": "x", "type": "INTEGER", "value": "1"}]}"#,
+        );
+        check_bad_file(
+            "X: Invalid INTEGER value 5000000000",
+            r#"{"version": 1, "symbols": [{"name": "x", "type": "INTEGER", "value": 5000000000}]}"#,
+        );
+        check_bad_file(
+            "X: Invalid dimension 0",
+            r#"{"version": 1, "symbols": [{"name": "x", "type": "INTEGER", "dimensions": [0], "values": []}]}"#,
+        );
+        check_bad_file(
+            "X: Dimensions do not match the values",
+            r#"{"version": 1, "symbols": [{"name": "x", "type": "INTEGER", "dimensions": [2], "values": [1]}]}"#,
+        );
+        check_bad_file(
+            "X: Incomplete array definition",
+            r#"{"version": 1, "symbols": [{"name": "x", "type": "INTEGER", "dimensions": [2]}]}"#,
+        );
+
+        let content =
+            r#"{"version": 1, "symbols": [{"name": "LEN", "type": "INTEGER", "value": 1}]}"#;
+        Tester::default()
+            .write_file("bad", content)
+            .run("a = 1: LOADSTATE \"bad\"")
+            .expect_err(
+                "1:8: In call to LOADSTATE: 1:18: Cannot restore LEN because it is a builtin name",
+            )
+            .expect_var("a", 1)
+            .expect_file("MEMORY:/bad", content)
+            .check();
+    }
+}
@@ -0,0 +1,254 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Delivery of file change notifications to the machine.
+
+use crate::storage::{system_clock, Storage};
+use async_channel::Sender;
+use endbasic_core::exec::{ClockFn, Signal, YieldNowFn};
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Default minimum amount of time between two consecutive checks for modified files.
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Periodically checks the files watched by a storage subsystem and notifies the machine about
+/// the modified ones by sending it `Signal::FileChange` signals.
+pub struct FileWatcher {
+    /// The storage subsystem whose watched files to check.
+    storage: Rc<RefCell<Storage>>,
+
+    /// Channel via which to deliver the notifications to the machine.
+    signals_tx: Sender<Signal>,
+
+    /// Minimum amount of time between two consecutive checks.
+    interval: Duration,
+
+    /// Clock used to determine when to check again.
+    clock: ClockFn,
+
+    /// Time of the last check, if any.
+    last_poll: Cell<Option<Duration>>,
+}
+
+impl FileWatcher {
+    /// Creates a new watcher that checks the files watched by `storage` at most once every
+    /// `DEFAULT_WATCH_INTERVAL` and notifies modifications via `signals_tx`.
+    pub fn new(storage: Rc<RefCell<Storage>>, signals_tx: Sender<Signal>) -> Self {
+        Self::with_clock(storage, signals_tx, DEFAULT_WATCH_INTERVAL, system_clock())
+    }
+
+    /// Creates a new watcher that checks the files watched by `storage` at most once every
+    /// `interval` as measured by `clock` and notifies modifications via `signals_tx`.
+    pub fn with_clock(
+        storage: Rc<RefCell<Storage>>,
+        signals_tx: Sender<Signal>,
+        interval: Duration,
+        clock: ClockFn,
+    ) -> Self {
+        Self { storage, signals_tx, interval, clock, last_poll: Cell::new(None) }
+    }
+
+    /// Checks the watched files for modifications unless the previous check was too recent, and
+    /// sends a signal for every modified file.
+    ///
+    /// Errors while querying the drives are ignored because there is nobody to report them to
+    /// while a program runs.  The check is retried later.
+    pub async fn poll(&self) {
+        let now = (self.clock)();
+        if let Some(last_poll) = self.last_poll.get() {
+            if now.saturating_sub(last_poll) < self.interval {
+                return;
+            }
+        }
+        self.last_poll.set(Some(now));
+
+        let changes = match self.storage.borrow().poll_changes().await {
+            Ok(changes) => changes,
+            Err(_) => return,
+        };
+        for location in changes {
+            // The channel is unbounded and owned by the machine, so sending cannot fail unless
+            // the machine is gone, in which case there is nobody left to notify.
+            let _ = self.signals_tx.try_send(Signal::FileChange(location));
+        }
+    }
+
+    /// Creates a yielding function that checks for modified files via `watcher` before
+    /// delegating to the `next` yielding function, if any.
+    ///
+    /// The machine calls the yielding function before running every statement, which makes this
+    /// a convenient hook to deliver notifications while a program runs.
+    pub fn new_yield_now_fn(watcher: Rc<FileWatcher>, next: Option<YieldNowFn>) -> YieldNowFn {
+        Box::from(move || {
+            let watcher = watcher.clone();
+            let next = next.as_ref().map(|next| next());
+            Box::pin(async move {
+                watcher.poll().await;
+                if let Some(next) = next {
+                    next.await;
+                }
+            }) as Pin<Box<dyn Future<Output = ()>>>
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DirectoryDrive;
+    use futures_lite::future::block_on;
+    use std::path::Path;
+
+    /// Sets the modification time of `path` to `secs` seconds after the epoch.
+    fn touch(path: &Path, secs: i64) {
+        filetime::set_file_mtime(path, filetime::FileTime::from_unix_time(secs, 0)).unwrap();
+    }
+
+    /// Container for a file watcher and the state needed to inspect its behavior.
+    struct TestContext {
+        dir: tempfile::TempDir,
+        storage: Rc<RefCell<Storage>>,
+        watcher: FileWatcher,
+        now: Rc<Cell<Duration>>,
+        signals_rx: async_channel::Receiver<Signal>,
+    }
+
+    impl TestContext {
+        /// Creates a watcher with a 10-second interval on a storage subsystem with a `D` drive
+        /// backed by a temporary directory.
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let mut storage = Storage::default();
+            let drive = DirectoryDrive::new(dir.path()).unwrap();
+            storage.attach("d", "file://", Box::from(drive)).unwrap();
+            let storage = Rc::from(RefCell::from(storage));
+
+            let now = Rc::from(Cell::new(Duration::from_secs(1000)));
+            let clock = {
+                let now = now.clone();
+                Rc::from(move || now.get())
+            };
+            let (signals_tx, signals_rx) = async_channel::unbounded();
+            let watcher = FileWatcher::with_clock(
+                storage.clone(),
+                signals_tx,
+                Duration::from_secs(10),
+                clock,
+            );
+            Self { dir, storage, watcher, now, signals_rx }
+        }
+
+        /// Advances the fake clock by `secs` seconds.
+        fn advance(&self, secs: u64) {
+            self.now.set(self.now.get() + Duration::from_secs(secs));
+        }
+
+        /// Returns all signals received so far.
+        fn signals(&self) -> Vec<Signal> {
+            let mut signals = vec![];
+            while let Ok(signal) = self.signals_rx.try_recv() {
+                signals.push(signal);
+            }
+            signals
+        }
+    }
+
+    #[test]
+    fn test_filewatcher_notifies_changes() {
+        let context = TestContext::new();
+        let path = context.dir.path().join("a.bas");
+        std::fs::write(&path, "old").unwrap();
+        touch(&path, 1_000_000);
+        block_on(context.storage.borrow().watch("d:a.bas")).unwrap();
+        block_on(context.storage.borrow().watch("d:missing.bas")).unwrap();
+
+        block_on(context.watcher.poll());
+        assert!(context.signals().is_empty());
+
+        std::fs::write(&path, "new").unwrap();
+        touch(&path, 2_000_000);
+        std::fs::write(context.dir.path().join("missing.bas"), "").unwrap();
+        context.advance(10);
+        block_on(context.watcher.poll());
+        assert_eq!(
+            vec![
+                Signal::FileChange("D:/a.bas".to_owned()),
+                Signal::FileChange("D:/missing.bas".to_owned())
+            ],
+            context.signals()
+        );
+
+        context.advance(10);
+        block_on(context.watcher.poll());
+        assert!(context.signals().is_empty());
+    }
+
+    #[test]
+    fn test_filewatcher_honors_interval() {
+        let context = TestContext::new();
+        let path = context.dir.path().join("a.bas");
+        block_on(context.storage.borrow().watch("d:a.bas")).unwrap();
+
+        block_on(context.watcher.poll());
+        std::fs::write(&path, "").unwrap();
+        context.advance(9);
+        block_on(context.watcher.poll());
+        assert!(context.signals().is_empty());
+
+        context.advance(1);
+        block_on(context.watcher.poll());
+        assert_eq!(vec![Signal::FileChange("D:/a.bas".to_owned())], context.signals());
+    }
+
+    #[test]
+    fn test_filewatcher_ignores_own_writes_and_unwatched_files() {
+        let context = TestContext::new();
+        block_on(context.storage.borrow().watch("d:a.bas")).unwrap();
+        block_on(context.storage.borrow().watch("d:b.bas")).unwrap();
+        context.storage.borrow().unwatch("d:b.bas").unwrap();
+
+        block_on(context.storage.borrow_mut().put("d:a.bas", "content")).unwrap();
+        std::fs::write(context.dir.path().join("b.bas"), "").unwrap();
+        block_on(context.watcher.poll());
+        assert!(context.signals().is_empty());
+    }
+
+    #[test]
+    fn test_filewatcher_yield_now_fn() {
+        let context = TestContext::new();
+        let path = context.dir.path().join("a.bas");
+        block_on(context.storage.borrow().watch("d:a.bas")).unwrap();
+
+        let calls = Rc::from(Cell::new(0));
+        let next: YieldNowFn = {
+            let calls = calls.clone();
+            Box::from(move || {
+                calls.set(calls.get() + 1);
+                Box::pin(async {}) as Pin<Box<dyn Future<Output = ()>>>
+            })
+        };
+
+        let TestContext { dir: _dir, storage: _storage, watcher, signals_rx, .. } = context;
+        let yield_now_fn = FileWatcher::new_yield_now_fn(Rc::from(watcher), Some(next));
+        std::fs::write(&path, "").unwrap();
+        block_on(yield_now_fn());
+        assert_eq!(1, calls.get());
+        assert_eq!(Signal::FileChange("D:/a.bas".to_owned()), signals_rx.try_recv().unwrap());
+    }
+}
@@ -15,12 +15,14 @@
 
 //! String functions for EndBASIC.
 
+use crate::arrays::expr_to_array_ref;
 use async_trait::async_trait;
-use endbasic_core::ast::{FunctionCallSpan, Value, VarType};
-use endbasic_core::eval::eval_all;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, FunctionCallSpan, Value, VarType};
+use endbasic_core::eval::{self, eval_all};
 use endbasic_core::exec::Machine;
 use endbasic_core::syms::{
-    CallError, CallableMetadata, CallableMetadataBuilder, Function, FunctionResult, Symbols,
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult, Function,
+    FunctionResult, Symbol, Symbols,
 };
 use std::cmp::min;
 use std::convert::TryFrom;
@@ -142,6 +144,326 @@ impl Function for ChrFunction {
     }
 }
 
+/// Parsed representation of the `:spec` part of a `FORMAT` placeholder.
+#[derive(Default)]
+struct FormatSpec {
+    /// Explicit alignment character (`<`, `>` or `^`), if any.
+    align: Option<char>,
+
+    /// Whether numbers should be padded with zeros after their sign.
+    zero: bool,
+
+    /// Minimum width of the formatted value, in characters.
+    width: usize,
+
+    /// Number of decimal digits for numbers or maximum length for other values.
+    precision: Option<usize>,
+}
+
+impl FormatSpec {
+    /// Parses a placeholder `spec` of the form `[<|>|^][0][width][.precision]`.
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut result = FormatSpec::default();
+        let mut chars = spec.chars().peekable();
+
+        if let Some(ch @ ('<' | '>' | '^')) = chars.peek().copied() {
+            result.align = Some(ch);
+            chars.next();
+        }
+        if chars.peek() == Some(&'0') {
+            result.zero = true;
+            chars.next();
+        }
+
+        let mut width = String::new();
+        while let Some(ch) = chars.next_if(char::is_ascii_digit) {
+            width.push(ch);
+        }
+        if !width.is_empty() {
+            result.width =
+                width.parse().map_err(|_| format!("Invalid width in format spec {}", spec))?;
+        }
+
+        if chars.next_if_eq(&'.').is_some() {
+            let mut precision = String::new();
+            while let Some(ch) = chars.next_if(char::is_ascii_digit) {
+                precision.push(ch);
+            }
+            result.precision = Some(
+                precision
+                    .parse()
+                    .map_err(|_| format!("Invalid precision in format spec {}", spec))?,
+            );
+        }
+
+        if chars.next().is_some() {
+            return Err(format!("Invalid format spec {}", spec));
+        }
+        Ok(result)
+    }
+
+    /// Formats `value` according to this spec.
+    fn apply(&self, value: &Value) -> String {
+        let (text, numeric) = match (value, self.precision) {
+            (Value::Integer(_) | Value::Long(_) | Value::Double(_), Some(precision)) => {
+                let d = value.as_f64().expect("Numeric values must convert to doubles");
+                (format!("{:.*}", precision, d), true)
+            }
+            (Value::Integer(i), None) => (i.to_string(), true),
+            (Value::Long(i), None) => (i.to_string(), true),
+            (Value::Double(d), None) => (d.to_string(), true),
+            (Value::Text(s), Some(precision)) => (s.chars().take(precision).collect(), false),
+            (value, _) => (value.clone().to_text(), false),
+        };
+
+        let len = text.chars().count();
+        if len >= self.width {
+            return text;
+        }
+        let fill = self.width - len;
+
+        if numeric && self.zero {
+            let (sign, digits) = match text.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", text.as_str()),
+            };
+            return format!("{}{}{}", sign, "0".repeat(fill), digits);
+        }
+
+        let align = self.align.unwrap_or(if numeric { '>' } else { '<' });
+        match align {
+            '<' => format!("{}{}", text, " ".repeat(fill)),
+            '>' => format!("{}{}", " ".repeat(fill), text),
+            '^' => format!("{}{}{}", " ".repeat(fill / 2), text, " ".repeat(fill - fill / 2)),
+            _ => unreachable!("Alignment was validated during parsing"),
+        }
+    }
+}
+
+/// Expands all placeholders in `format` with the given `args`.
+fn format_args(format: &str, args: &[Value]) -> Result<String, String> {
+    let mut result = String::with_capacity(format.len());
+    let mut chars = format.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('{') if placeholder.is_empty() => {
+                            result.push('{');
+                            break;
+                        }
+                        Some('}') => {
+                            let (index, spec) = match placeholder.split_once(':') {
+                                Some((index, spec)) => (index, spec),
+                                None => (placeholder.as_str(), ""),
+                            };
+                            let index: usize = index
+                                .trim()
+                                .parse()
+                                .map_err(|_| format!("Invalid placeholder {{{}}}", placeholder))?;
+                            let value = args.get(index).ok_or_else(|| {
+                                format!(
+                                    "Placeholder {{{}}} refers to a missing argument",
+                                    placeholder
+                                )
+                            })?;
+                            result.push_str(&FormatSpec::parse(spec)?.apply(value));
+                            break;
+                        }
+                        Some(ch) => placeholder.push(ch),
+                        None => return Err("Unterminated placeholder".to_owned()),
+                    }
+                }
+            }
+            '}' => match chars.next() {
+                Some('}') => result.push('}'),
+                _ => return Err("Unmatched } in format; use }} for a literal }".to_owned()),
+            },
+            ch => result.push(ch),
+        }
+    }
+    Ok(result)
+}
+
+/// The `FORMAT` function.
+pub struct FormatFunction {
+    metadata: CallableMetadata,
+}
+
+impl FormatFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("FORMAT", VarType::Text)
+                .with_syntax("format$[, expr1[, .., exprN]]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Formats a list of values according to a template string.
+format$ is copied to the result as is except for placeholders of the form {index} or \
+{index:spec}, which are replaced by the value of the argument at position index.  Arguments are \
+numbered from 0 and can be referenced any number of times.  Use {{ and }} to insert literal braces.
+spec has the form [align][0][width][.precision], where all parts are optional:
+align is one of <, > or ^ to align the value to the left, right or center of width.  Numbers \
+are aligned to the right and any other value to the left by default.
+0 pads numbers with zeros after their sign instead of with spaces.
+width is the minimum number of characters to produce.
+precision is the number of decimal digits to print for numbers, or the maximum number of \
+characters to take from strings.
+For example, FORMAT$(\"Score: {0:05} Time: {1:.2}\", 42, 3.14159) returns \
+\"Score: 00042 Time: 3.14\".",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for FormatFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.split_first() {
+            Some((Value::Text(format), args)) => match format_args(format, args) {
+                Ok(result) => Ok(Value::Text(result)),
+                Err(e) => Err(CallError::ArgumentError(span.args[0].start_pos(), e)),
+            },
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `INSTR` function.
+pub struct InstrFunction {
+    metadata: CallableMetadata,
+}
+
+impl InstrFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("INSTR", VarType::Integer)
+                .with_syntax("[start%, ]expr$, search$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns the position of the first occurrence of a string within another.
+start% indicates the position at which to start searching and it is 1-indexed.  If not \
+specified, the search starts at the beginning of expr$.
+Returns the 1-indexed position of search$ within expr$, or 0 if search$ does not appear in expr$.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for InstrFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (start, s, search) = match args.as_slice() {
+            [Value::Text(s), Value::Text(search)] => (1, s, search),
+            [start, Value::Text(s), Value::Text(search)] => {
+                let start = start.as_i32().map_err(|e| {
+                    CallError::ArgumentError(span.args[0].start_pos(), format!("{}", e))
+                })?;
+                if start < 1 {
+                    return Err(CallError::ArgumentError(
+                        span.args[0].start_pos(),
+                        "start% must be positive".to_owned(),
+                    ));
+                }
+                (start, s, search)
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let offset = (start - 1) as usize;
+        match s.get(offset..).and_then(|rest| rest.find(search.as_str())) {
+            Some(i) => Ok(Value::Integer((offset + i + 1) as i32)),
+            None => Ok(Value::Integer(0)),
+        }
+    }
+}
+
+/// The `JOIN` function.
+pub struct JoinFunction {
+    metadata: CallableMetadata,
+}
+
+impl JoinFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("JOIN", VarType::Text)
+                .with_syntax("array, delimiter$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Concatenates all elements of an array of strings.
+The array must be one-dimensional and its elements are separated by delimiter$ in the result.
+See SPLIT for the inverse of this function.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for JoinFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let (arrayref, arraypos, delimiter) = match span.args.as_slice() {
+            [array, delimiter] => match expr_to_array_ref(array) {
+                Some((arrayref, arraypos)) => match delimiter.eval(symbols).await? {
+                    Value::Text(delimiter) => (arrayref, arraypos, delimiter),
+                    _ => return Err(CallError::SyntaxError),
+                },
+                None => return Err(CallError::SyntaxError),
+            },
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        match symbols.get(arrayref).map_err(|e| eval::Error::from_value_error(e, arraypos))? {
+            Some(Symbol::Array(array)) => {
+                if array.dimensions().len() != 1 {
+                    return Err(CallError::ArgumentError(
+                        arraypos,
+                        "JOIN requires a one-dimensional array".to_owned(),
+                    ));
+                }
+                let mut parts = Vec::with_capacity(array.values().len());
+                for value in array.values() {
+                    match value {
+                        Value::Text(s) => parts.push(s.as_str()),
+                        _ => {
+                            return Err(CallError::ArgumentError(
+                                arraypos,
+                                "JOIN requires an array of strings".to_owned(),
+                            ))
+                        }
+                    }
+                }
+                Ok(Value::Text(parts.join(&delimiter)))
+            }
+            Some(_) => Err(CallError::ArgumentError(
+                arraypos,
+                format!("{} must be an array reference", arrayref),
+            )),
+            None => Err(CallError::ArgumentError(arraypos, format!("{} is not defined", arrayref))),
+        }
+    }
+}
+
 /// The `LEFT` function.
 pub struct LeftFunction {
     metadata: CallableMetadata,
@@ -327,6 +649,50 @@ impl Function for MidFunction {
     }
 }
 
+/// The `REPLACE` function.
+pub struct ReplaceFunction {
+    metadata: CallableMetadata,
+}
+
+impl ReplaceFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("REPLACE", VarType::Text)
+                .with_syntax("expr$, search$, replacement$")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Replaces all occurrences of a string within another.
+Returns expr$ with all non-overlapping occurrences of search$ replaced by replacement$.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for ReplaceFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [Value::Text(s), Value::Text(search), Value::Text(replacement)] => {
+                if search.is_empty() {
+                    return Err(CallError::ArgumentError(
+                        span.args[1].start_pos(),
+                        "search$ cannot be empty".to_owned(),
+                    ));
+                }
+                Ok(Value::Text(s.replace(search.as_str(), replacement)))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
 /// The `RIGHT` function.
 pub struct RightFunction {
     metadata: CallableMetadata,
@@ -410,6 +776,165 @@ impl Function for RtrimFunction {
     }
 }
 
+/// The `SPACE` function.
+pub struct SpaceFunction {
+    metadata: CallableMetadata,
+}
+
+impl SpaceFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SPACE", VarType::Text)
+                .with_syntax("n%")
+                .with_category(CATEGORY)
+                .with_description("Returns a string that contains n% spaces.")
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for SpaceFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        match args.as_slice() {
+            [n @ Value::Integer(_) | n @ Value::Double(_)] => {
+                let n = n.as_i32().map_err(|e| {
+                    CallError::ArgumentError(span.args[0].start_pos(), format!("{}", e))
+                })?;
+                if n < 0 {
+                    return Err(CallError::ArgumentError(
+                        span.args[0].start_pos(),
+                        "n% cannot be negative".to_owned(),
+                    ));
+                }
+                Ok(Value::Text(" ".repeat(n as usize)))
+            }
+            _ => Err(CallError::SyntaxError),
+        }
+    }
+}
+
+/// The `SPLIT` command.
+pub struct SplitCommand {
+    metadata: CallableMetadata,
+}
+
+impl SplitCommand {
+    /// Creates a new instance of the command.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("SPLIT", VarType::Void)
+                .with_syntax("expr$, delimiter$, array")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Splits a string into the pieces separated by a delimiter.
+The pieces are stored in array, which is (re)defined as a one-dimensional array of strings with \
+as many elements as pieces were found.  If array already exists, it must be an array of strings \
+and its previous contents are lost.
+See JOIN$() for the inverse of this command.",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for SplitCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let (expr, delimiter, arrayref, arraypos) = match span.args.as_slice() {
+            [ArgSpan { expr: Some(expr), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(delimiter), sep: ArgSep::Long, .. }, ArgSpan { expr: Some(array), sep: ArgSep::End, .. }] => {
+                match expr_to_array_ref(array) {
+                    Some((arrayref, arraypos)) => (expr, delimiter, arrayref, arraypos),
+                    None => return Err(CallError::SyntaxError),
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let s = match expr.eval(machine.get_mut_symbols()).await? {
+            Value::Text(s) => s,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    expr.start_pos(),
+                    "SPLIT requires a string as the input".to_owned(),
+                ))
+            }
+        };
+        let delimiter_pos = delimiter.start_pos();
+        let delimiter = match delimiter.eval(machine.get_mut_symbols()).await? {
+            Value::Text(delimiter) if delimiter.is_empty() => {
+                return Err(CallError::ArgumentError(
+                    delimiter_pos,
+                    "delimiter$ cannot be empty".to_owned(),
+                ))
+            }
+            Value::Text(delimiter) => delimiter,
+            _ => {
+                return Err(CallError::ArgumentError(
+                    delimiter_pos,
+                    "SPLIT requires a string as the delimiter".to_owned(),
+                ))
+            }
+        };
+        let pieces: Vec<&str> = s.split(delimiter.as_str()).collect();
+
+        let symbols = machine.get_mut_symbols();
+        let exists =
+            match symbols.get(arrayref).map_err(|e| eval::Error::from_value_error(e, arraypos))? {
+                Some(Symbol::Array(array)) if array.subtype() == VarType::Text => true,
+                Some(Symbol::Array(_)) => {
+                    return Err(CallError::ArgumentError(
+                        arraypos,
+                        "SPLIT requires an array of strings".to_owned(),
+                    ))
+                }
+                Some(_) => {
+                    return Err(CallError::ArgumentError(
+                        arraypos,
+                        format!("{} must be an array reference", arrayref),
+                    ))
+                }
+                None => false,
+            };
+        if !exists {
+            if !arrayref.accepts(VarType::Text) {
+                return Err(CallError::ArgumentError(
+                    arraypos,
+                    "SPLIT requires an array of strings".to_owned(),
+                ));
+            }
+            symbols
+                .dim_array(arrayref.name(), VarType::Text, vec![pieces.len()])
+                .map_err(|e| eval::Error::from_value_error(e, arraypos))?;
+        }
+
+        match symbols.get_mut(arrayref).map_err(|e| eval::Error::from_value_error(e, arraypos))? {
+            Some(Symbol::Array(array)) => {
+                array
+                    .resize(vec![pieces.len()], false)
+                    .expect("Resizing without preserving contents cannot fail");
+                for (i, piece) in pieces.into_iter().enumerate() {
+                    array
+                        .assign(&[i as i32], Value::Text(piece.to_owned()))
+                        .expect("Subscripts and types were validated above");
+                }
+                Ok(())
+            }
+            _ => unreachable!("The array was validated or created above"),
+        }
+    }
+}
+
 /// The `STR` function.
 pub struct StrFunction {
     metadata: CallableMetadata,
@@ -457,22 +982,93 @@ impl Function for StrFunction {
     }
 }
 
+/// The `STRING` function.
+pub struct StringFunction {
+    metadata: CallableMetadata,
+}
+
+impl StringFunction {
+    /// Creates a new instance of the function.
+    pub fn new() -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("STRING", VarType::Text)
+                .with_syntax("n%, char")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Returns a string that repeats a character n% times.
+char can either be a string, which is repeated as is, or a character code as accepted by CHR$().
+Because STRING is also the name of a type, this function must always be called with its type \
+annotation, as in STRING$(10, \"-\").",
+                )
+                .build(),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Function for StringFunction {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &FunctionCallSpan, symbols: &mut Symbols) -> FunctionResult {
+        let args = eval_all(&span.args, symbols).await?;
+        let (n, pattern) = match args.as_slice() {
+            [n, Value::Text(s)] => (n, s.clone()),
+            [n, code @ (Value::Integer(_) | Value::Double(_))] => {
+                let pos = span.args[1].start_pos();
+                let code =
+                    code.as_i32().map_err(|e| CallError::ArgumentError(pos, format!("{}", e)))?;
+                match u32::try_from(code).ok().and_then(char::from_u32) {
+                    Some(ch) => (n, ch.to_string()),
+                    None => {
+                        return Err(CallError::ArgumentError(
+                            pos,
+                            format!("Invalid character code {}", code),
+                        ))
+                    }
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        let n = n
+            .as_i32()
+            .map_err(|e| CallError::ArgumentError(span.args[0].start_pos(), format!("{}", e)))?;
+        if n < 0 {
+            return Err(CallError::ArgumentError(
+                span.args[0].start_pos(),
+                "n% cannot be negative".to_owned(),
+            ));
+        }
+        Ok(Value::Text(pattern.repeat(n as usize)))
+    }
+}
+
 /// Adds all symbols provided by this module to the given `machine`.
 pub fn add_all(machine: &mut Machine) {
     machine.add_function(AscFunction::new());
     machine.add_function(ChrFunction::new());
+    machine.add_function(FormatFunction::new());
+    machine.add_function(InstrFunction::new());
+    machine.add_function(JoinFunction::new());
     machine.add_function(LeftFunction::new());
     machine.add_function(LenFunction::new());
     machine.add_function(LtrimFunction::new());
     machine.add_function(MidFunction::new());
+    machine.add_function(ReplaceFunction::new());
     machine.add_function(RightFunction::new());
     machine.add_function(RtrimFunction::new());
+    machine.add_function(SpaceFunction::new());
+    machine.add_command(SplitCommand::new());
     machine.add_function(StrFunction::new());
+    machine.add_function(StringFunction::new());
 }
 
 #[cfg(test)]
 mod tests {
     use crate::testutils::*;
+    use endbasic_core::ast::VarType;
 
     #[test]
     fn test_asc() {
@@ -519,6 +1115,149 @@ mod tests {
         check_expr_ok('a' as i32, r#"ASC(CHR(97))"#);
     }
 
+    #[test]
+    fn test_format() {
+        check_expr_ok("", r#"FORMAT("")"#);
+        check_expr_ok("no placeholders", r#"FORMAT("no placeholders", 1, 2)"#);
+        check_expr_ok(
+            "Score: 00042  Time: 3.14",
+            r#"FORMAT$("Score: {0:05}  Time: {1:.2}", 42, 3.14159)"#,
+        );
+        check_expr_ok("b a b", r#"FORMAT("{1} {0} {1}", "a", "b")"#);
+        check_expr_ok("{x} }", r#"FORMAT("{{x}} }}")"#);
+
+        check_expr_ok(
+            "5 -3 2.5 5000000000",
+            r#"FORMAT("{0} {1} {2} {3}", 5, -3, 2.5, 5000000000)"#,
+        );
+        check_expr_ok("TRUE x", r#"FORMAT("{0} {1}", TRUE, "x")"#);
+
+        check_expr_ok("[   42]", r#"FORMAT("[{0:5}]", 42)"#);
+        check_expr_ok("[ab   ]", r#"FORMAT("[{0:5}]", "ab")"#);
+        check_expr_ok("[42   ]", r#"FORMAT("[{0:<5}]", 42)"#);
+        check_expr_ok("[   ab]", r#"FORMAT("[{0:>5}]", "ab")"#);
+        check_expr_ok("[ ab  ]", r#"FORMAT("[{0:^5}]", "ab")"#);
+        check_expr_ok("[toolong]", r#"FORMAT("[{0:3}]", "toolong")"#);
+
+        check_expr_ok("-0042", r#"FORMAT("{0:05}", -42)"#);
+        check_expr_ok("003.50", r#"FORMAT("{0:06.2}", 3.5)"#);
+        check_expr_ok("7.000", r#"FORMAT("{0:.3}", 7)"#);
+        check_expr_ok("2", r#"FORMAT("{0:.0}", 2.5)"#);
+        check_expr_ok("abc", r#"FORMAT("{0:.3}", "abcdef")"#);
+        check_expr_ok("  abc", r#"FORMAT("{0:>5.3}", "abcdef")"#);
+
+        check_expr_error(
+            "1:10: In call to FORMAT: expected format$[, expr1[, .., exprN]]",
+            r#"FORMAT()"#,
+        );
+        check_expr_error(
+            "1:10: In call to FORMAT: expected format$[, expr1[, .., exprN]]",
+            r#"FORMAT(3, 4)"#,
+        );
+        check_expr_error(
+            "1:10: In call to FORMAT: 1:17: Placeholder {1} refers to a missing argument",
+            r#"FORMAT("{1}", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to FORMAT: 1:17: Invalid placeholder {a}",
+            r#"FORMAT("{a}", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to FORMAT: 1:17: Invalid placeholder {}",
+            r#"FORMAT("{}", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to FORMAT: 1:17: Unterminated placeholder",
+            r#"FORMAT("{0", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to FORMAT: 1:17: Unmatched } in format; use }} for a literal }",
+            r#"FORMAT("0}", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to FORMAT: 1:17: Invalid format spec x5",
+            r#"FORMAT("{0:x5}", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to FORMAT: 1:17: Invalid precision in format spec 5.",
+            r#"FORMAT("{0:5.}", 3)"#,
+        );
+    }
+
+    #[test]
+    fn test_instr() {
+        check_expr_ok(0, r#"INSTR("", "a")"#);
+        check_expr_ok(1, r#"INSTR("abcabc", "")"#);
+        check_expr_ok(1, r#"INSTR("abcabc", "a")"#);
+        check_expr_ok(2, r#"INSTR("abcabc", "bc")"#);
+        check_expr_ok(0, r#"INSTR("abcabc", "x")"#);
+        check_expr_ok(4, r#"INSTR(2, "abcabc", "a")"#);
+        check_expr_ok(4, r#"INSTR(4.2, "abcabc", "a")"#);
+        check_expr_ok(0, r#"INSTR(5, "abcabc", "a")"#);
+        check_expr_ok(0, r#"INSTR(100, "abcabc", "a")"#);
+
+        check_expr_error("1:10: In call to INSTR: expected [start%, ]expr$, search$", r#"INSTR()"#);
+        check_expr_error(
+            "1:10: In call to INSTR: expected [start%, ]expr$, search$",
+            r#"INSTR("a")"#,
+        );
+        check_expr_error(
+            "1:10: In call to INSTR: expected [start%, ]expr$, search$",
+            r#"INSTR("a", 1)"#,
+        );
+        check_expr_error(
+            "1:10: In call to INSTR: 1:16: \"1\" is not a number",
+            r#"INSTR("1", "a", "b")"#,
+        );
+        check_expr_error(
+            "1:10: In call to INSTR: 1:16: start% must be positive",
+            r#"INSTR(0, "a", "b")"#,
+        );
+    }
+
+    #[test]
+    fn test_join() {
+        Tester::default()
+            .run(r#"DIM a(3) AS STRING: a(0) = "x": a(2) = "z": r = JOIN$(a, ", ")"#)
+            .expect_array_simple("a", VarType::Text, vec!["x".into(), "".into(), "z".into()])
+            .expect_var("r", "x, , z")
+            .check();
+
+        Tester::default()
+            .run(r#"DIM a(1) AS STRING: a(0) = "x": r = JOIN(a(), "-")"#)
+            .expect_array_simple("a", VarType::Text, vec!["x".into()])
+            .expect_var("r", "x")
+            .check();
+
+        check_expr_error("1:10: In call to JOIN: expected array, delimiter$", r#"JOIN()"#);
+        check_expr_error("1:10: In call to JOIN: expected array, delimiter$", r#"JOIN(3, "")"#);
+        check_expr_error("1:10: In call to JOIN: 1:15: a is not defined", r#"JOIN(a, "")"#);
+
+        Tester::default()
+            .run(r#"a = 3: r = JOIN(a, "")"#)
+            .expect_err("1:12: In call to JOIN: 1:17: a must be an array reference")
+            .expect_var("a", 3)
+            .check();
+
+        Tester::default()
+            .run(r#"DIM a(2): r = JOIN(a, "")"#)
+            .expect_err("1:15: In call to JOIN: 1:20: JOIN requires an array of strings")
+            .expect_array("a", VarType::Integer, &[2], vec![])
+            .check();
+
+        Tester::default()
+            .run(r#"DIM a(2, 2) AS STRING: r = JOIN(a, "")"#)
+            .expect_err("1:28: In call to JOIN: 1:33: JOIN requires a one-dimensional array")
+            .expect_array("a", VarType::Text, &[2, 2], vec![])
+            .check();
+
+        Tester::default()
+            .run(r#"DIM a(2) AS STRING: r = JOIN(a, 5)"#)
+            .expect_err("1:25: In call to JOIN: expected array, delimiter$")
+            .expect_array("a", VarType::Text, &[2], vec![])
+            .check();
+    }
+
     #[test]
     fn test_left() {
         check_expr_ok("", r#"LEFT("", 0)"#);
@@ -596,6 +1335,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_replace() {
+        check_expr_ok("", r#"REPLACE("", "a", "b")"#);
+        check_expr_ok("xbcxbc", r#"REPLACE("abcabc", "a", "x")"#);
+        check_expr_ok("aa", r#"REPLACE("abcabc", "bc", "")"#);
+        check_expr_ok("abcabc", r#"REPLACE("abcabc", "z", "y")"#);
+        check_expr_ok("bb", r#"REPLACE("aaaa", "aa", "b")"#);
+
+        check_expr_error(
+            "1:10: In call to REPLACE: expected expr$, search$, replacement$",
+            r#"REPLACE()"#,
+        );
+        check_expr_error(
+            "1:10: In call to REPLACE: expected expr$, search$, replacement$",
+            r#"REPLACE("a", "b")"#,
+        );
+        check_expr_error(
+            "1:10: In call to REPLACE: expected expr$, search$, replacement$",
+            r#"REPLACE("a", "b", 3)"#,
+        );
+        check_expr_error(
+            "1:10: In call to REPLACE: 1:25: search$ cannot be empty",
+            r#"REPLACE("abc", "", "x")"#,
+        );
+    }
+
     #[test]
     fn test_right() {
         check_expr_ok("", r#"RIGHT("", 0)"#);
@@ -626,6 +1391,84 @@ mod tests {
         check_expr_error("1:10: In call to RTRIM: expected expr$", r#"RTRIM(" ", 1)"#);
     }
 
+    #[test]
+    fn test_space() {
+        check_expr_ok("", r#"SPACE(0)"#);
+        check_expr_ok("   ", r#"SPACE(3)"#);
+        check_expr_ok("  ", r#"SPACE$(1.8)"#);
+
+        check_expr_error("1:10: In call to SPACE: expected n%", r#"SPACE()"#);
+        check_expr_error("1:10: In call to SPACE: expected n%", r#"SPACE("3")"#);
+        check_expr_error("1:10: In call to SPACE: expected n%", r#"SPACE(1, 2)"#);
+        check_expr_error("1:10: In call to SPACE: 1:16: n% cannot be negative", r#"SPACE(-1)"#);
+    }
+
+    #[test]
+    fn test_split() {
+        Tester::default()
+            .run(r#"SPLIT "a,b,,c", ",", parts"#)
+            .expect_array_simple(
+                "parts",
+                VarType::Text,
+                vec!["a".into(), "b".into(), "".into(), "c".into()],
+            )
+            .check();
+
+        Tester::default()
+            .run(r#"SPLIT "", ",", parts$()"#)
+            .expect_array_simple("parts", VarType::Text, vec!["".into()])
+            .check();
+
+        Tester::default()
+            .run(r#"DIM parts(10) AS STRING: parts(5) = "x": SPLIT "a -- b", " -- ", parts"#)
+            .expect_array_simple("parts", VarType::Text, vec!["a".into(), "b".into()])
+            .check();
+
+        Tester::default()
+            .run(r#"SPLIT "a b c", " ", parts: r$ = JOIN$(parts, "+")"#)
+            .expect_array_simple("parts", VarType::Text, vec!["a".into(), "b".into(), "c".into()])
+            .expect_var("r", "a+b+c")
+            .check();
+
+        check_stmt_err("1:1: In call to SPLIT: expected expr$, delimiter$, array", r#"SPLIT"#);
+        check_stmt_err(
+            "1:1: In call to SPLIT: expected expr$, delimiter$, array",
+            r#"SPLIT "a", ",""#,
+        );
+        check_stmt_err(
+            "1:1: In call to SPLIT: expected expr$, delimiter$, array",
+            r#"SPLIT "a", ",", 3"#,
+        );
+        check_stmt_err(
+            "1:1: In call to SPLIT: 1:7: SPLIT requires a string as the input",
+            r#"SPLIT 3, ",", a"#,
+        );
+        check_stmt_err(
+            "1:1: In call to SPLIT: 1:12: SPLIT requires a string as the delimiter",
+            r#"SPLIT "a", 3, a"#,
+        );
+        check_stmt_err(
+            "1:1: In call to SPLIT: 1:12: delimiter$ cannot be empty",
+            r#"SPLIT "a", "", a"#,
+        );
+        check_stmt_err(
+            "1:1: In call to SPLIT: 1:17: SPLIT requires an array of strings",
+            r#"SPLIT "a", ",", a%"#,
+        );
+
+        Tester::default()
+            .run(r#"DIM a(2): SPLIT "a", ",", a"#)
+            .expect_err("1:11: In call to SPLIT: 1:27: SPLIT requires an array of strings")
+            .expect_array("a", VarType::Integer, &[2], vec![])
+            .check();
+
+        Tester::default()
+            .run(r#"a = 3: SPLIT "a", ",", a"#)
+            .expect_err("1:8: In call to SPLIT: 1:24: a must be an array reference")
+            .expect_var("a", 3)
+            .check();
+    }
+
     #[test]
     fn test_str() {
         check_expr_ok("FALSE", r#"STR(FALSE)"#);
@@ -653,4 +1496,29 @@ mod tests {
         check_expr_ok("-1", r#"LTRIM(STR(-1))"#);
         check_expr_ok("100", r#"LTRIM$(STR$(100))"#);
     }
+
+    #[test]
+    fn test_string() {
+        check_expr_ok("", r#"STRING$(0, "-")"#);
+        check_expr_ok("---", r#"STRING$(3, "-")"#);
+        check_expr_ok("ababab", r#"STRING$(3, "ab")"#);
+        check_expr_ok("AAAA", r#"STRING$(4, 65)"#);
+        check_expr_ok("**", r#"STRING$(1.6, 42.2)"#);
+
+        check_expr_error("1:10: In call to STRING: expected n%, char", r#"STRING$()"#);
+        check_expr_error("1:10: In call to STRING: expected n%, char", r#"STRING$(3)"#);
+        check_expr_error("1:10: In call to STRING: expected n%, char", r#"STRING$(3, TRUE)"#);
+        check_expr_error(
+            "1:10: In call to STRING: 1:18: \"a\" is not a number",
+            r#"STRING$("a", "b")"#,
+        );
+        check_expr_error(
+            "1:10: In call to STRING: 1:18: n% cannot be negative",
+            r#"STRING$(-1, "b")"#,
+        );
+        check_expr_error(
+            "1:10: In call to STRING: 1:21: Invalid character code -1",
+            r#"STRING$(3, -1)"#,
+        );
+    }
 }
@@ -14,16 +14,21 @@
 // under the License.
 
 //! Test utilities for consumers of the EndBASIC interpreter.
+//!
+//! Consumers should use these via the `endbasic-testutils` crate, which re-exports them along with
+//! other test utilities.
 
 use crate::console::{
-    self, remove_control_chars, CharsXY, ClearType, Console, Key, PixelsXY, SizeInPixels,
+    self, advance_cursor, remove_control_chars, rgb_to_ansi_color, CharsXY, ClearType, Console,
+    Key, KeyModifiers, PixelsXY, SizeInPixels, TextAttributes, RGB,
 };
 use crate::gpio;
 use crate::program::Program;
 use crate::storage::Storage;
+use async_channel::Sender;
 use async_trait::async_trait;
 use endbasic_core::ast::{Value, VarRef, VarType};
-use endbasic_core::exec::{self, Machine, StopReason};
+use endbasic_core::exec::{self, Machine, Signal, StopReason};
 use endbasic_core::syms::{Array, Command, Function, Symbol};
 use futures_lite::future::block_on;
 use std::cell::RefCell;
@@ -32,6 +37,7 @@ use std::io;
 use std::rc::Rc;
 use std::result::Result;
 use std::str;
+use std::time::Duration;
 
 /// A captured command or messages sent to the mock console.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -42,6 +48,12 @@ pub enum CapturedOut {
     /// Represents a call to `Console::set_color`.
     SetColor(Option<u8>, Option<u8>),
 
+    /// Represents a call to `Console::set_color_rgb`.
+    SetColorRgb(Option<RGB>, Option<RGB>),
+
+    /// Represents a call to `Console::set_attributes`.
+    SetAttributes(TextAttributes),
+
     /// Represents a call to `Console::enter_alt`.
     EnterAlt,
 
@@ -60,30 +72,75 @@ pub enum CapturedOut {
     /// Represents a call to `Console::print`.
     Print(String),
 
+    /// Represents a call to `Console::restore_screen`.
+    RestoreScreen,
+
+    /// Represents a call to `Console::save_screen`.
+    SaveScreen,
+
     /// Represents a call to `Console::show_cursor`.
     ShowCursor,
 
     /// Represents a call to `Console::write`.
     Write(String),
 
+    /// Represents a call to `Console::draw_arc`.
+    DrawArc(PixelsXY, u16, i32, i32),
+
     /// Represents a call to `Console::draw_circle`.
     DrawCircle(PixelsXY, u16),
 
     /// Represents a call to `Console::draw_circle_filled`.
     DrawCircleFilled(PixelsXY, u16),
 
+    /// Represents a call to `Console::draw_ellipse`.
+    DrawEllipse(PixelsXY, u16, u16),
+
+    /// Represents a call to `Console::draw_ellipse_filled`.
+    DrawEllipseFilled(PixelsXY, u16, u16),
+
     /// Represents a call to `Console::draw_line`.
     DrawLine(PixelsXY, PixelsXY),
 
     /// Represents a call to `Console::draw_pixel`.
     DrawPixel(PixelsXY),
 
+    /// Represents a call to `Console::draw_poly`.
+    DrawPoly(Vec<PixelsXY>),
+
+    /// Represents a call to `Console::draw_poly_filled`.
+    DrawPolyFilled(Vec<PixelsXY>),
+
     /// Represents a call to `Console::draw_rect`.
     DrawRect(PixelsXY, PixelsXY),
 
     /// Represents a call to `Console::draw_rect_filled`.
     DrawRectFilled(PixelsXY, PixelsXY),
 
+    /// Represents a call to `Console::draw_text`.
+    DrawText(PixelsXY, String, Option<u16>),
+
+    /// Represents a call to `Console::flood_fill`.
+    FloodFill(PixelsXY, Option<RGB>),
+
+    /// Represents a call to `Console::set_clip`.
+    SetClip(Option<(PixelsXY, PixelsXY)>),
+
+    /// Represents a call to `Console::set_draw_page`.
+    SetDrawPage(u8),
+
+    /// Represents a call to `Console::flip_page`.
+    FlipPage(u8),
+
+    /// Represents a call to `Console::set_font`.
+    SetFont(Option<String>, u16),
+
+    /// Represents a call to `Console::set_title`.
+    SetTitle(String),
+
+    /// Represents a call to `Console::set_fullscreen`.
+    SetFullscreen(bool),
+
     /// Represents a call to `Console::sync_now`.
     SyncNow,
 
@@ -91,10 +148,33 @@ pub enum CapturedOut {
     SetSync(bool),
 }
 
+/// Amount of virtual time that elapses on every `Console::poll_key` call while a scripted delay
+/// is pending.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A scripted event in the golden input of the mock console.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum InputEvent {
+    /// A key press.
+    Key(Key),
+
+    /// A pause before the next event becomes available.
+    Delay(Duration),
+
+    /// A change in the size of the text console.
+    Resize(CharsXY),
+
+    /// A signal to deliver to the machine.
+    Signal(Signal),
+}
+
 /// A console that supplies golden input and captures all output.
 pub struct MockConsole {
-    /// Sequence of keys to yield on `read_key` calls.
-    golden_in: VecDeque<Key>,
+    /// Sequence of events to consume on `poll_key`, `read_key` and `read_key_timeout` calls.
+    golden_in: VecDeque<InputEvent>,
+
+    /// Channel to deliver scripted signals to the machine, if any.
+    signals_tx: Option<Sender<Signal>>,
 
     /// Sequence of all messages printed.
     captured_out: Vec<CapturedOut>,
@@ -105,18 +185,47 @@ pub struct MockConsole {
     /// The size of the mock graphical console.
     size_pixels: Option<SizeInPixels>,
 
+    /// Colors of the pixels of the mock graphical console that are not black.
+    pixels: HashMap<(i16, i16), RGB>,
+
     /// Whether the console is interactive or not.
     interactive: bool,
+
+    /// Keys reported as held down.
+    keys_down: Vec<Key>,
+
+    /// Modifier keys reported as held down.
+    key_modifiers: KeyModifiers,
+
+    /// Contents of the mock clipboard.
+    clipboard: String,
+
+    /// Number of saved screens that have not been restored yet.
+    saved_screens: usize,
+
+    /// Tracked position of the cursor.
+    cursor_pos: CharsXY,
+
+    /// Text attributes currently in effect.
+    attributes: TextAttributes,
 }
 
 impl Default for MockConsole {
     fn default() -> Self {
         Self {
             golden_in: VecDeque::new(),
+            signals_tx: None,
             captured_out: vec![],
             size_chars: CharsXY::new(u16::MAX, u16::MAX),
             size_pixels: None,
+            pixels: HashMap::default(),
             interactive: false,
+            keys_down: vec![],
+            key_modifiers: KeyModifiers::default(),
+            clipboard: String::new(),
+            saved_screens: 0,
+            cursor_pos: CharsXY::default(),
+            attributes: TextAttributes::default(),
         }
     }
 }
@@ -128,17 +237,48 @@ impl MockConsole {
     /// corresponding `Key`s for simplicity.
     pub fn add_input_chars(&mut self, s: &str) {
         for ch in s.chars() {
-            match ch {
-                '\n' => self.golden_in.push_back(Key::NewLine),
-                '\r' => self.golden_in.push_back(Key::CarriageReturn),
-                ch => self.golden_in.push_back(Key::Char(ch)),
-            }
+            let key = match ch {
+                '\n' => Key::NewLine,
+                '\r' => Key::CarriageReturn,
+                ch => Key::Char(ch),
+            };
+            self.golden_in.push_back(InputEvent::Key(key));
         }
     }
 
     /// Adds a bunch of keys as golden input.
     pub fn add_input_keys(&mut self, keys: &[Key]) {
-        self.golden_in.extend(keys.iter().cloned());
+        self.golden_in.extend(keys.iter().cloned().map(InputEvent::Key));
+    }
+
+    /// Adds a pause of `delay` before the next golden input becomes available.
+    ///
+    /// Delays are measured in virtual time: every `poll_key` call that finds a pending delay
+    /// consumes `POLL_INTERVAL` of it and returns no key, every `read_key_timeout` call consumes
+    /// up to its timeout, and `read_key` waits for the delay to pass.
+    pub fn add_input_delay(&mut self, delay: Duration) {
+        self.golden_in.push_back(InputEvent::Delay(delay));
+    }
+
+    /// Adds a change of the text console size to `size` as golden input.
+    ///
+    /// The new size takes effect once all previous golden input has been consumed and the
+    /// program polls or reads input again.
+    pub fn add_input_resize(&mut self, size: CharsXY) {
+        self.golden_in.push_back(InputEvent::Resize(size));
+    }
+
+    /// Adds the delivery of `signal` to the machine as golden input.
+    ///
+    /// The signal is sent once all previous golden input has been consumed and the program polls
+    /// or reads input again, which requires a channel configured via `set_signals_tx`.
+    pub fn add_input_signal(&mut self, signal: Signal) {
+        self.golden_in.push_back(InputEvent::Signal(signal));
+    }
+
+    /// Sets the channel used to deliver scripted signals to the machine.
+    pub fn set_signals_tx(&mut self, signals_tx: Sender<Signal>) {
+        self.signals_tx = Some(signals_tx);
     }
 
     /// Obtains a reference to the captured output.
@@ -164,32 +304,84 @@ impl MockConsole {
         self.size_pixels = Some(size);
     }
 
+    /// Sets the color of the pixel at `xy` of the mock graphical console.
+    pub fn set_pixel(&mut self, xy: PixelsXY, rgb: RGB) {
+        self.pixels.insert((xy.x, xy.y), rgb);
+    }
+
     /// Sets whether the mock console is interactive or not.
     pub fn set_interactive(&mut self, interactive: bool) {
         self.interactive = interactive;
     }
 
+    /// Sets the keys that the mock console reports as held down.
+    pub fn set_keys_down(&mut self, keys: &[Key]) {
+        self.keys_down = keys.to_vec();
+    }
+
+    /// Sets the modifier keys that the mock console reports as held down.
+    pub fn set_key_modifiers(&mut self, modifiers: KeyModifiers) {
+        self.key_modifiers = modifiers;
+    }
+
+    /// Obtains the contents of the mock clipboard.
+    pub fn clipboard(&self) -> &str {
+        &self.clipboard
+    }
+
+    /// Sets the contents of the mock clipboard.
+    pub fn set_clipboard(&mut self, text: &str) {
+        self.clipboard = text.to_owned();
+    }
+
     /// Ensures that all prerecorded input characters were consumed.
     fn verify_all_used(&mut self) {
         assert!(
             self.golden_in.is_empty(),
-            "Not all golden input chars were consumed; {} left",
+            "Not all golden input events were consumed; {} left",
             self.golden_in.len()
         );
     }
+
+    /// Applies any resize and signal events at the head of the golden input so that the next
+    /// event, if any, is either a key or a delay.
+    fn apply_pending_events(&mut self) {
+        loop {
+            match self.golden_in.front() {
+                Some(InputEvent::Resize(size)) => self.size_chars = *size,
+                Some(InputEvent::Signal(signal)) => {
+                    let signals_tx =
+                        self.signals_tx.as_ref().expect("No channel to deliver golden signals");
+                    signals_tx.try_send(signal.clone()).expect("Signals channel must be open");
+                }
+                _ => break,
+            }
+            self.golden_in.pop_front();
+        }
+    }
 }
 
 #[async_trait(?Send)]
 impl Console for MockConsole {
     fn clear(&mut self, how: ClearType) -> io::Result<()> {
+        match how {
+            ClearType::All => self.cursor_pos = CharsXY::default(),
+            ClearType::CurrentLine => self.cursor_pos.x = 0,
+            ClearType::PreviousChar => self.cursor_pos.x = self.cursor_pos.x.saturating_sub(1),
+            ClearType::UntilNewLine => (),
+        }
         self.captured_out.push(CapturedOut::Clear(how));
         Ok(())
     }
 
     fn color(&self) -> (Option<u8>, Option<u8>) {
         for o in self.captured_out.iter().rev() {
-            if let CapturedOut::SetColor(fg, bg) = o {
-                return (*fg, *bg);
+            match o {
+                CapturedOut::SetColor(fg, bg) => return (*fg, *bg),
+                CapturedOut::SetColorRgb(fg, bg) => {
+                    return (fg.map(rgb_to_ansi_color), bg.map(rgb_to_ansi_color))
+                }
+                _ => (),
             }
         }
         (None, None)
@@ -200,6 +392,25 @@ impl Console for MockConsole {
         Ok(())
     }
 
+    fn set_color_rgb(&mut self, fg: Option<RGB>, bg: Option<RGB>) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::SetColorRgb(fg, bg));
+        Ok(())
+    }
+
+    fn attributes(&self) -> TextAttributes {
+        self.attributes
+    }
+
+    fn set_attributes(&mut self, attributes: TextAttributes) -> io::Result<()> {
+        self.attributes = attributes;
+        self.captured_out.push(CapturedOut::SetAttributes(attributes));
+        Ok(())
+    }
+
+    fn cursor_pos(&self) -> io::Result<CharsXY> {
+        Ok(self.cursor_pos)
+    }
+
     fn enter_alt(&mut self) -> io::Result<()> {
         self.captured_out.push(CapturedOut::EnterAlt);
         Ok(())
@@ -214,6 +425,14 @@ impl Console for MockConsole {
         self.interactive
     }
 
+    fn is_key_down(&mut self, key: &Key) -> io::Result<bool> {
+        Ok(self.keys_down.contains(key))
+    }
+
+    fn key_modifiers(&mut self) -> io::Result<KeyModifiers> {
+        Ok(self.key_modifiers)
+    }
+
     fn leave_alt(&mut self) -> io::Result<()> {
         self.captured_out.push(CapturedOut::LeaveAlt);
         Ok(())
@@ -222,11 +441,17 @@ impl Console for MockConsole {
     fn locate(&mut self, pos: CharsXY) -> io::Result<()> {
         assert!(pos.x < self.size_chars.x);
         assert!(pos.y < self.size_chars.y);
+        self.cursor_pos = pos;
         self.captured_out.push(CapturedOut::Locate(pos));
         Ok(())
     }
 
     fn move_within_line(&mut self, off: i16) -> io::Result<()> {
+        self.cursor_pos.x = if off < 0 {
+            self.cursor_pos.x.saturating_sub(-off as u16)
+        } else {
+            self.cursor_pos.x.saturating_add(off as u16)
+        };
         self.captured_out.push(CapturedOut::MoveWithinLine(off));
         Ok(())
     }
@@ -234,24 +459,84 @@ impl Console for MockConsole {
     fn print(&mut self, text: &str) -> io::Result<()> {
         let text = remove_control_chars(text.to_owned());
 
+        self.cursor_pos = advance_cursor(self.cursor_pos, self.size_chars, &text, true);
         self.captured_out.push(CapturedOut::Print(text));
         Ok(())
     }
 
     async fn poll_key(&mut self) -> io::Result<Option<Key>> {
-        match self.golden_in.pop_front() {
-            Some(ch) => Ok(Some(ch)),
+        self.apply_pending_events();
+        match self.golden_in.front_mut() {
+            Some(InputEvent::Delay(delay)) => {
+                if *delay > POLL_INTERVAL {
+                    *delay -= POLL_INTERVAL;
+                } else {
+                    self.golden_in.pop_front();
+                }
+                Ok(None)
+            }
+            Some(InputEvent::Key(key)) => {
+                let key = key.clone();
+                self.golden_in.pop_front();
+                Ok(Some(key))
+            }
+            Some(e) => unreachable!("Event {:?} should have been applied", e),
             None => Ok(None),
         }
     }
 
     async fn read_key(&mut self) -> io::Result<Key> {
-        match self.golden_in.pop_front() {
-            Some(ch) => Ok(ch),
-            None => Ok(Key::Eof),
+        loop {
+            self.apply_pending_events();
+            match self.golden_in.pop_front() {
+                Some(InputEvent::Delay(_)) => (),
+                Some(InputEvent::Key(key)) => return Ok(key),
+                Some(e) => unreachable!("Event {:?} should have been applied", e),
+                None => return Ok(Key::Eof),
+            }
+        }
+    }
+
+    async fn read_key_timeout(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        let mut remaining = timeout;
+        loop {
+            self.apply_pending_events();
+            match self.golden_in.front_mut() {
+                Some(InputEvent::Delay(delay)) => {
+                    if *delay > remaining {
+                        *delay -= remaining;
+                        return Ok(None);
+                    }
+                    remaining -= *delay;
+                    self.golden_in.pop_front();
+                }
+                Some(InputEvent::Key(key)) => {
+                    let key = key.clone();
+                    self.golden_in.pop_front();
+                    return Ok(Some(key));
+                }
+                Some(e) => unreachable!("Event {:?} should have been applied", e),
+                // Running out of golden input simulates the user not pressing any key in time.
+                None => return Ok(None),
+            }
         }
     }
 
+    fn restore_screen(&mut self) -> io::Result<()> {
+        if self.saved_screens == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No saved screen to restore"));
+        }
+        self.saved_screens -= 1;
+        self.captured_out.push(CapturedOut::RestoreScreen);
+        Ok(())
+    }
+
+    fn save_screen(&mut self) -> io::Result<()> {
+        self.saved_screens += 1;
+        self.captured_out.push(CapturedOut::SaveScreen);
+        Ok(())
+    }
+
     fn show_cursor(&mut self) -> io::Result<()> {
         self.captured_out.push(CapturedOut::ShowCursor);
         Ok(())
@@ -271,10 +556,16 @@ impl Console for MockConsole {
     fn write(&mut self, text: &str) -> io::Result<()> {
         let text = remove_control_chars(text.to_owned());
 
+        self.cursor_pos = advance_cursor(self.cursor_pos, self.size_chars, &text, false);
         self.captured_out.push(CapturedOut::Write(text));
         Ok(())
     }
 
+    fn draw_arc(&mut self, xy: PixelsXY, r: u16, start: i32, end: i32) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::DrawArc(xy, r, start, end));
+        Ok(())
+    }
+
     fn draw_circle(&mut self, xy: PixelsXY, r: u16) -> io::Result<()> {
         self.captured_out.push(CapturedOut::DrawCircle(xy, r));
         Ok(())
@@ -285,6 +576,16 @@ impl Console for MockConsole {
         Ok(())
     }
 
+    fn draw_ellipse(&mut self, xy: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::DrawEllipse(xy, rx, ry));
+        Ok(())
+    }
+
+    fn draw_ellipse_filled(&mut self, xy: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::DrawEllipseFilled(xy, rx, ry));
+        Ok(())
+    }
+
     fn draw_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
         self.captured_out.push(CapturedOut::DrawLine(x1y1, x2y2));
         Ok(())
@@ -295,6 +596,16 @@ impl Console for MockConsole {
         Ok(())
     }
 
+    fn draw_poly(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::DrawPoly(points.to_vec()));
+        Ok(())
+    }
+
+    fn draw_poly_filled(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::DrawPolyFilled(points.to_vec()));
+        Ok(())
+    }
+
     fn draw_rect(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
         self.captured_out.push(CapturedOut::DrawRect(x1y1, x2y2));
         Ok(())
@@ -305,6 +616,63 @@ impl Console for MockConsole {
         Ok(())
     }
 
+    fn draw_text(&mut self, xy: PixelsXY, text: &str, size: Option<u16>) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::DrawText(xy, text.to_owned(), size));
+        Ok(())
+    }
+
+    fn flood_fill(&mut self, xy: PixelsXY, border: Option<RGB>) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::FloodFill(xy, border));
+        Ok(())
+    }
+
+    fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+        let size = self.size_pixels()?;
+        if xy.x < 0 || xy.y < 0 || xy.x as u16 >= size.width || xy.y as u16 >= size.height {
+            return Ok(None);
+        }
+        Ok(Some(self.pixels.get(&(xy.x, xy.y)).copied().unwrap_or((0, 0, 0))))
+    }
+
+    fn set_clip(&mut self, area: Option<(PixelsXY, PixelsXY)>) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::SetClip(area));
+        Ok(())
+    }
+
+    fn set_draw_page(&mut self, page: u8) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::SetDrawPage(page));
+        Ok(())
+    }
+
+    fn flip_page(&mut self, page: u8) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::FlipPage(page));
+        Ok(())
+    }
+
+    fn set_font(&mut self, face: Option<&str>, size: u16) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::SetFont(face.map(str::to_owned), size));
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::SetTitle(title.to_owned()));
+        Ok(())
+    }
+
+    async fn clipboard_get(&mut self) -> io::Result<String> {
+        Ok(self.clipboard.clone())
+    }
+
+    async fn clipboard_set(&mut self, text: &str) -> io::Result<()> {
+        self.clipboard = text.to_owned();
+        Ok(())
+    }
+
+    fn set_fullscreen(&mut self, enabled: bool) -> io::Result<()> {
+        self.captured_out.push(CapturedOut::SetFullscreen(enabled));
+        Ok(())
+    }
+
     fn sync_now(&mut self) -> io::Result<()> {
         self.captured_out.push(CapturedOut::SyncNow);
         Ok(())
@@ -375,6 +743,11 @@ impl Program for RecordedProgram {
         self.dirty = false;
     }
 
+    fn set_text(&mut self, text: &str) {
+        self.content = text.to_owned();
+        self.dirty = true;
+    }
+
     fn text(&self) -> String {
         self.content.clone()
     }
@@ -413,6 +786,7 @@ impl Default for Tester {
         let storage = builder.get_storage();
 
         let machine = builder.build().unwrap();
+        console.borrow_mut().set_signals_tx(machine.get_signals_tx());
 
         Self { console, storage, program, machine }
     }
@@ -426,6 +800,7 @@ impl Tester {
         let program = Rc::from(RefCell::from(RecordedProgram::default()));
 
         let machine = Machine::default();
+        console.borrow_mut().set_signals_tx(machine.get_signals_tx());
 
         Self { console, storage, program, machine }
     }
@@ -454,6 +829,24 @@ impl Tester {
         self
     }
 
+    /// Adds a pause of `delay` before the next golden input to the console becomes available.
+    pub fn add_input_delay(self, delay: Duration) -> Self {
+        self.console.borrow_mut().add_input_delay(delay);
+        self
+    }
+
+    /// Adds a change of the console size to `size` as golden input to the console.
+    pub fn add_input_resize(self, size: CharsXY) -> Self {
+        self.console.borrow_mut().add_input_resize(size);
+        self
+    }
+
+    /// Adds the delivery of `signal` to the machine as golden input to the console.
+    pub fn add_input_signal(self, signal: Signal) -> Self {
+        self.console.borrow_mut().add_input_signal(signal);
+        self
+    }
+
     /// Returns a mutable reference to the machine inside the tester.
     ///
     /// This method should generally not be used, except to run native methods that have
@@ -630,6 +1023,8 @@ impl<'a> Checker<'a> {
         self.exp_output.append(&mut vec![
             CapturedOut::LeaveAlt,
             CapturedOut::SetColor(None, None),
+            CapturedOut::SetDrawPage(0),
+            CapturedOut::SetClip(None),
             CapturedOut::ShowCursor,
             CapturedOut::SetSync(true),
         ]);
@@ -0,0 +1,508 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Interactive tutorial that walks users through a sequence of lessons.
+//!
+//! Lessons are plain text files with a simple structure:
+//!
+//! *   The first non-empty line starts with `# ` and contains the title of the lesson.
+//! *   Lines that start with `> ` contain code that the user must type to continue.  The code is
+//!     executed once the user types it.
+//! *   Any other lines are explanatory text.  Consecutive lines are joined into paragraphs and
+//!     paragraphs are separated by empty lines.
+
+use crate::console::{read_line, refill_and_print, AnsiColor, Console};
+use crate::exec::CATEGORY;
+use crate::storage::Storage;
+use async_trait::async_trait;
+use endbasic_core::ast::{ArgSep, ArgSpan, BuiltinCallSpan, Value, VarType};
+use endbasic_core::exec::{Machine, StopReason};
+use endbasic_core::syms::{
+    CallError, CallableMetadata, CallableMetadataBuilder, Command, CommandResult,
+};
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+/// Color for lesson titles.
+const TITLE_COLOR: u8 = AnsiColor::BrightYellow as u8;
+
+/// Color for the code that the user has to type.
+const CODE_COLOR: u8 = AnsiColor::BrightCyan as u8;
+
+/// A single step of a lesson.
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// Explanatory text, as a collection of paragraphs.
+    Text(Vec<String>),
+
+    /// Code that the user must type to continue.
+    Type(String),
+}
+
+/// A lesson parsed from its textual representation.
+#[derive(Debug, PartialEq)]
+struct Lesson {
+    /// The title of the lesson.
+    title: String,
+
+    /// The sequence of steps to walk through.
+    steps: Vec<Step>,
+}
+
+impl Lesson {
+    /// Parses a lesson from its textual representation in `text`.
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().skip_while(|l| l.trim().is_empty());
+        let title = match lines.next().and_then(|l| l.strip_prefix("# ")) {
+            Some(title) if !title.trim().is_empty() => title.trim().to_owned(),
+            _ => return Err("Lesson must start with a '# title' line".to_owned()),
+        };
+
+        let mut steps = vec![];
+        let mut paragraphs: Vec<String> = vec![];
+        let mut paragraph = String::new();
+        for line in lines {
+            let line = line.trim_end();
+            if let Some(code) = line.strip_prefix("> ") {
+                if !paragraph.is_empty() {
+                    paragraphs.push(paragraph.split_off(0));
+                }
+                if !paragraphs.is_empty() {
+                    steps.push(Step::Text(paragraphs.split_off(0)));
+                }
+                steps.push(Step::Type(code.trim().to_owned()));
+            } else if line.is_empty() {
+                if !paragraph.is_empty() {
+                    paragraphs.push(paragraph.split_off(0));
+                }
+            } else {
+                if paragraph.ends_with(['.', '!', '?']) {
+                    paragraph.push_str("  ");
+                } else if !paragraph.is_empty() {
+                    paragraph.push(' ');
+                }
+                paragraph.push_str(line.trim());
+            }
+        }
+        if !paragraph.is_empty() {
+            paragraphs.push(paragraph);
+        }
+        if !paragraphs.is_empty() {
+            steps.push(Step::Text(paragraphs));
+        }
+
+        if steps.is_empty() {
+            return Err("Lesson has no content".to_owned());
+        }
+        Ok(Self { title, steps })
+    }
+}
+
+/// Normalizes a line of `code` so that typed code can be compared to the expected code without
+/// caring about whitespace or casing.
+fn normalize(code: &str) -> String {
+    code.chars().filter(|ch| !ch.is_whitespace()).collect::<String>().to_uppercase()
+}
+
+/// The `TUTORIAL` command.
+pub struct TutorialCommand {
+    metadata: CallableMetadata,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+    lessons: String,
+}
+
+impl TutorialCommand {
+    /// Creates a new `TUTORIAL` command that runs the lessons in the files matched by the
+    /// `lessons` pattern from `storage` and that uses `console` to interact with the user.
+    pub fn new<S: Into<String>>(
+        console: Rc<RefCell<dyn Console>>,
+        storage: Rc<RefCell<Storage>>,
+        lessons: S,
+    ) -> Rc<Self> {
+        Rc::from(Self {
+            metadata: CallableMetadataBuilder::new("TUTORIAL", VarType::Void)
+                .with_syntax("[lesson%]")
+                .with_category(CATEGORY)
+                .with_description(
+                    "Walks you through an interactive tutorial of the language.
+The tutorial is a sequence of short lessons.  Each lesson explains a concept and then asks you \
+to type some code, which runs once you type it as shown.  The tutorial moves on to the next step \
+when you do so.
+Without arguments, starts the tutorial from the first lesson.  With a single argument, starts the \
+tutorial from the given lesson number, which is useful to resume a tutorial you left earlier.
+Press CTRL+C at any time to leave the tutorial.  Any variables you create during the tutorial \
+remain defined afterwards.",
+                )
+                .build(),
+            console,
+            storage,
+            lessons: lessons.into(),
+        })
+    }
+
+    /// Waits until the user types the `expected` code and then runs it on the `machine`.
+    ///
+    /// Returns false if the user asked to leave the tutorial.
+    async fn type_code(&self, expected: &str, machine: &mut Machine) -> Result<bool, CallError> {
+        {
+            let mut console = self.console.borrow_mut();
+            let previous = console.color();
+            console.set_color(Some(CODE_COLOR), previous.1)?;
+            console.print(&format!("    {}", expected))?;
+            console.set_color(previous.0, previous.1)?;
+            console.print("")?;
+        }
+
+        let line = loop {
+            let mut console = self.console.borrow_mut();
+            let line = match read_line(&mut *console, "> ", "", None).await {
+                Ok(line) => line,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(false),
+                Err(e) => return Err(e.into()),
+            };
+            if normalize(&line) == normalize(expected) {
+                break line;
+            }
+
+            let previous = console.color();
+            console.write("Not quite; type this to continue: ")?;
+            console.set_color(Some(CODE_COLOR), previous.1)?;
+            console.print(expected)?;
+            console.set_color(previous.0, previous.1)?;
+        };
+
+        match machine.exec(&mut line.as_bytes()).await {
+            Ok(StopReason::Eof) => Ok(true),
+            Ok(_) => Ok(false),
+            Err(e) => Err(CallError::NestedError(format!("{}", e))),
+        }
+    }
+
+    /// Walks the user through the `lesson` with the given `number` out of `count` lessons.
+    ///
+    /// Returns false if the user asked to leave the tutorial.
+    async fn run_lesson(
+        &self,
+        lesson: &Lesson,
+        number: usize,
+        count: usize,
+        machine: &mut Machine,
+    ) -> Result<bool, CallError> {
+        {
+            let mut console = self.console.borrow_mut();
+            let previous = console.color();
+            console.print("")?;
+            console.set_color(Some(TITLE_COLOR), previous.1)?;
+            refill_and_print(
+                &mut *console,
+                [format!("Lesson {} of {}: {}", number, count, lesson.title)],
+                "    ",
+            )?;
+            console.set_color(previous.0, previous.1)?;
+            console.print("")?;
+        }
+
+        for step in &lesson.steps {
+            match step {
+                Step::Text(paragraphs) => {
+                    let mut console = self.console.borrow_mut();
+                    refill_and_print(&mut *console, paragraphs, "    ")?;
+                    console.print("")?;
+                }
+                Step::Type(code) => {
+                    if !self.type_code(code, machine).await? {
+                        return Ok(false);
+                    }
+                    self.console.borrow_mut().print("")?;
+                }
+            }
+        }
+
+        if number < count {
+            let mut console = self.console.borrow_mut();
+            match read_line(
+                &mut *console,
+                "Press ENTER to continue to the next lesson... ",
+                "",
+                None,
+            )
+            .await
+            {
+                Ok(_) => (),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(false),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait(?Send)]
+impl Command for TutorialCommand {
+    fn metadata(&self) -> &CallableMetadata {
+        &self.metadata
+    }
+
+    async fn exec(&self, span: &BuiltinCallSpan, machine: &mut Machine) -> CommandResult {
+        let paths = self.storage.borrow().glob(&self.lessons).await?;
+
+        let first = match span.args.as_slice() {
+            [] => 1,
+            [ArgSpan { expr: Some(expr), sep: ArgSep::End, .. }] => {
+                match expr.eval(machine.get_mut_symbols()).await? {
+                    Value::Integer(i) if i >= 1 && (i as usize) <= paths.len() => i as usize,
+                    Value::Integer(_) => {
+                        return Err(CallError::ArgumentError(
+                            expr.start_pos(),
+                            format!("Lesson number must be between 1 and {}", paths.len()),
+                        ))
+                    }
+                    _ => return Err(CallError::SyntaxError),
+                }
+            }
+            _ => return Err(CallError::SyntaxError),
+        };
+
+        for (i, path) in paths.iter().enumerate().skip(first - 1) {
+            let text = self.storage.borrow().get(path).await?;
+            let lesson = Lesson::parse(&text).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid lesson {}: {}", path, e),
+                )
+            })?;
+            if !self.run_lesson(&lesson, i + 1, paths.len(), machine).await? {
+                self.console.borrow_mut().print(&format!(
+                    "Leaving the tutorial; type TUTORIAL {} to resume this lesson.",
+                    i + 1
+                ))?;
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds the `TUTORIAL` command to the `machine` to run the lessons matched by the `lessons`
+/// pattern in `storage`.
+pub fn add_all<S: Into<String>>(
+    machine: &mut Machine,
+    console: Rc<RefCell<dyn Console>>,
+    storage: Rc<RefCell<Storage>>,
+    lessons: S,
+) {
+    machine.add_command(TutorialCommand::new(console, storage, lessons));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::Key;
+    use crate::testutils::*;
+
+    #[test]
+    fn test_lesson_parse_ok() {
+        let lesson = Lesson::parse(
+            "
+
+# The title
+
+First paragraph
+continues here.  And
+here.
+And here too.
+
+Second paragraph.
+> PRINT 1
+>  PRINT 2
+Final words.
+",
+        )
+        .unwrap();
+        assert_eq!(
+            Lesson {
+                title: "The title".to_owned(),
+                steps: vec![
+                    Step::Text(vec![
+                        "First paragraph continues here.  And here.  And here too.".to_owned(),
+                        "Second paragraph.".to_owned()
+                    ]),
+                    Step::Type("PRINT 1".to_owned()),
+                    Step::Type("PRINT 2".to_owned()),
+                    Step::Text(vec!["Final words.".to_owned()]),
+                ],
+            },
+            lesson
+        );
+    }
+
+    #[test]
+    fn test_lesson_parse_errors() {
+        assert_eq!(
+            "Lesson must start with a '# title' line",
+            Lesson::parse("Some text\n> PRINT 1\n").unwrap_err()
+        );
+        assert_eq!("Lesson must start with a '# title' line", Lesson::parse("").unwrap_err());
+        assert_eq!("Lesson must start with a '# title' line", Lesson::parse("#  \n").unwrap_err());
+        assert_eq!("Lesson has no content", Lesson::parse("# Title\n\n").unwrap_err());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!("PRINT2+2", normalize("  print 2 + 2 "));
+        assert_eq!(normalize("A=1:PRINT A"), normalize("a = 1: print a"));
+    }
+
+    /// Contents of the first lesson used in tests.
+    const LESSON1: &str = "# First\nSet a variable.\n> a = 2 + 3\n";
+
+    /// Contents of the second lesson used in tests.
+    const LESSON2: &str = "# Second\nPrint it.\n> PRINT a\nDone.\n";
+
+    /// Creates a tester with the `TUTORIAL` command running the lessons in `MEMORY:/*.LSN`.
+    fn tester() -> Tester {
+        let tester = Tester::default().write_file("1.LSN", LESSON1).write_file("2.LSN", LESSON2);
+        let (console, storage) = (tester.get_console(), tester.get_storage());
+        tester.add_command(TutorialCommand::new(console, storage, "MEMORY:/*.LSN"))
+    }
+
+    #[test]
+    fn test_tutorial_all_lessons() {
+        let mut t = tester().add_input_chars("A=2+3\n\nprint 3\nprint a\n");
+        t.run("TUTORIAL")
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::SetColor(Some(TITLE_COLOR), None),
+                CapturedOut::Print("    Lesson 1 of 2: First".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints(["", "    Set a variable.", ""])
+            .expect_output([
+                CapturedOut::SetColor(Some(CODE_COLOR), None),
+                CapturedOut::Print("    a = 2 + 3".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints(["", "", ""])
+            .expect_output([
+                CapturedOut::SetColor(Some(TITLE_COLOR), None),
+                CapturedOut::Print("    Lesson 2 of 2: Second".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints(["", "    Print it.", ""])
+            .expect_output([
+                CapturedOut::SetColor(Some(CODE_COLOR), None),
+                CapturedOut::Print("    PRINT a".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::Write("Not quite; type this to continue: ".to_owned()),
+                CapturedOut::SetColor(Some(CODE_COLOR), None),
+                CapturedOut::Print("PRINT a".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints([" 5", "", "    Done.", ""])
+            .expect_var("a", 5)
+            .expect_file("MEMORY:/1.LSN", LESSON1)
+            .expect_file("MEMORY:/2.LSN", LESSON2)
+            .check();
+    }
+
+    #[test]
+    fn test_tutorial_start_at_lesson() {
+        let mut t = tester().add_input_chars("print a\n");
+        t.run("a = 7: TUTORIAL 2")
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::SetColor(Some(TITLE_COLOR), None),
+                CapturedOut::Print("    Lesson 2 of 2: Second".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints(["", "    Print it.", ""])
+            .expect_output([
+                CapturedOut::SetColor(Some(CODE_COLOR), None),
+                CapturedOut::Print("    PRINT a".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints(["", " 7", "", "    Done.", ""])
+            .expect_var("a", 7)
+            .expect_file("MEMORY:/1.LSN", LESSON1)
+            .expect_file("MEMORY:/2.LSN", LESSON2)
+            .check();
+    }
+
+    #[test]
+    fn test_tutorial_leave() {
+        let mut t = tester().add_input_keys(&[Key::Interrupt]);
+        t.run("TUTORIAL 2")
+            .expect_prints([""])
+            .expect_output([
+                CapturedOut::SetColor(Some(TITLE_COLOR), None),
+                CapturedOut::Print("    Lesson 2 of 2: Second".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints(["", "    Print it.", ""])
+            .expect_output([
+                CapturedOut::SetColor(Some(CODE_COLOR), None),
+                CapturedOut::Print("    PRINT a".to_owned()),
+                CapturedOut::SetColor(None, None),
+            ])
+            .expect_prints(["", "Leaving the tutorial; type TUTORIAL 2 to resume this lesson."])
+            .expect_file("MEMORY:/1.LSN", LESSON1)
+            .expect_file("MEMORY:/2.LSN", LESSON2)
+            .check();
+    }
+
+    #[test]
+    fn test_tutorial_errors() {
+        let mut t = tester();
+        t.run("TUTORIAL 0")
+            .expect_err("1:1: In call to TUTORIAL: 1:10: Lesson number must be between 1 and 2")
+            .expect_file("MEMORY:/1.LSN", LESSON1)
+            .expect_file("MEMORY:/2.LSN", LESSON2)
+            .check();
+
+        let mut t = tester();
+        t.run("TUTORIAL 3")
+            .expect_err("1:1: In call to TUTORIAL: 1:10: Lesson number must be between 1 and 2")
+            .expect_file("MEMORY:/1.LSN", LESSON1)
+            .expect_file("MEMORY:/2.LSN", LESSON2)
+            .check();
+
+        let mut t = tester();
+        t.run("TUTORIAL \"a\"")
+            .expect_err("1:1: In call to TUTORIAL: expected [lesson%]")
+            .expect_file("MEMORY:/1.LSN", LESSON1)
+            .expect_file("MEMORY:/2.LSN", LESSON2)
+            .check();
+
+        let mut t = tester();
+        t.run("TUTORIAL 1, 2")
+            .expect_err("1:1: In call to TUTORIAL: expected [lesson%]")
+            .expect_file("MEMORY:/1.LSN", LESSON1)
+            .expect_file("MEMORY:/2.LSN", LESSON2)
+            .check();
+
+        let mut t = tester().write_file("3.LSN", "No title");
+        t.run("TUTORIAL 3")
+            .expect_err("1:1: In call to TUTORIAL: Invalid lesson MEMORY:/3.LSN: Lesson must start with a '# title' line")
+            .expect_file("MEMORY:/1.LSN", LESSON1)
+            .expect_file("MEMORY:/2.LSN", LESSON2)
+            .expect_file("MEMORY:/3.LSN", "No title")
+            .check();
+    }
+}
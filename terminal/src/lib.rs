@@ -27,11 +27,21 @@ use async_trait::async_trait;
 use crossterm::{cursor, event, style, terminal, tty::IsTty, QueueableCommand};
 use endbasic_core::exec::Signal;
 use endbasic_std::console::{
-    get_env_var_as_u16, read_key_from_stdin, remove_control_chars, CharsXY, ClearType, Console, Key,
+    advance_cursor, get_env_var_as_u16, read_key_from_stdin, remove_control_chars, CharsXY,
+    ClearType, Console, Key, KeyModifiers, TextAttributes,
 };
 use std::cmp::Ordering;
 use std::collections::VecDeque;
 use std::io::{self, StdoutLock, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Period of time during which a key is considered held down after its first press.  This has to
+/// be long enough to cover the delay before the terminal starts repeating a held key.
+const INITIAL_HOLD: Duration = Duration::from_millis(500);
+
+/// Period of time during which a key is considered held down after a repeated press.
+const REPEAT_HOLD: Duration = Duration::from_millis(100);
 
 /// Converts a `crossterm::ErrorKind` to an `io::Error`.
 fn crossterm_error_to_io_error(e: crossterm::ErrorKind) -> io::Error {
@@ -44,6 +54,90 @@ fn crossterm_error_to_io_error(e: crossterm::ErrorKind) -> io::Error {
     }
 }
 
+/// Returns the name of the key in `code` as used by `ON KEY`, or none if the key has no name.
+fn key_code_name(code: &event::KeyCode) -> Option<String> {
+    use event::KeyCode;
+
+    let name = match code {
+        KeyCode::Backspace => "BS",
+        KeyCode::Down => "DOWN",
+        KeyCode::End => "END",
+        KeyCode::Enter => "ENTER",
+        KeyCode::Esc => "ESC",
+        KeyCode::Home => "HOME",
+        KeyCode::Left => "LEFT",
+        KeyCode::PageDown => "PGDOWN",
+        KeyCode::PageUp => "PGUP",
+        KeyCode::Right => "RIGHT",
+        KeyCode::Tab => "TAB",
+        KeyCode::Up => "UP",
+        KeyCode::Char(' ') => "SPACE",
+        KeyCode::Char(ch) => return Some(ch.to_ascii_uppercase().to_string()),
+        KeyCode::F(n) => return Some(format!("F{}", n)),
+        _ => return None,
+    };
+    Some(name.to_owned())
+}
+
+/// Approximation of the state of the keyboard based on the key presses reported by the terminal.
+///
+/// Terminals do not report key releases, so we consider a key to be held down for a short period
+/// of time after each press.  Keeping a key pressed makes the terminal repeat it, which in turn
+/// keeps the key marked as held down.
+#[derive(Default)]
+struct KeyboardState {
+    /// The last key pressed along with its modifiers.
+    last: Option<(Key, KeyModifiers)>,
+
+    /// Time until which the last key is considered to be held down.
+    until: Option<Instant>,
+}
+
+impl KeyboardState {
+    /// Records the press of `ev`.
+    fn record(&mut self, ev: &event::KeyEvent) {
+        use event::KeyCode;
+
+        let key = match ev.code {
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::End => Key::End,
+            KeyCode::Enter => Key::NewLine,
+            KeyCode::Esc => Key::Escape,
+            KeyCode::Home => Key::Home,
+            KeyCode::Tab => Key::Tab,
+            KeyCode::Up => Key::ArrowUp,
+            KeyCode::Down => Key::ArrowDown,
+            KeyCode::Left => Key::ArrowLeft,
+            KeyCode::Right => Key::ArrowRight,
+            KeyCode::PageDown => Key::PageDown,
+            KeyCode::PageUp => Key::PageUp,
+            KeyCode::Char(ch) => Key::Char(ch.to_ascii_lowercase()),
+            _ => Key::Unknown(format!("{:?}", ev.code)),
+        };
+        let modifiers = KeyModifiers {
+            shift: ev.modifiers.contains(event::KeyModifiers::SHIFT),
+            ctrl: ev.modifiers.contains(event::KeyModifiers::CONTROL),
+            alt: ev.modifiers.contains(event::KeyModifiers::ALT),
+        };
+
+        let now = Instant::now();
+        let repeated = match (&self.last, self.until) {
+            (Some((last, _)), Some(until)) => *last == key && now < until,
+            _ => false,
+        };
+        self.until = Some(now + if repeated { REPEAT_HOLD } else { INITIAL_HOLD });
+        self.last = Some((key, modifiers));
+    }
+
+    /// Returns the key and modifiers that are considered to be held down, if any.
+    fn held(&self) -> Option<&(Key, KeyModifiers)> {
+        match self.until {
+            Some(until) if Instant::now() < until => self.last.as_ref(),
+            _ => None,
+        }
+    }
+}
+
 /// Implementation of the EndBASIC console to interact with stdin and stdout.
 pub struct TerminalConsole {
     /// Whether stdin and stdout are attached to a TTY.  When this is true, the console is put in
@@ -56,17 +150,40 @@ pub struct TerminalConsole {
     /// Current background color.
     bg_color: Option<u8>,
 
+    /// Current text attributes.
+    attributes: TextAttributes,
+
     /// Whether the cursor is visible or not.
     cursor_visible: bool,
 
     /// Whether we are in the alternate console or not.
     alt_active: bool,
 
+    /// Position of the cursor as tracked from our own output.  We cannot query the terminal for
+    /// it once the task that reads key presses is running because the reply would be consumed by
+    /// that task.
+    cursor_pos: CharsXY,
+
+    /// Position of the cursor in the primary console while the alternate console is active.
+    main_cursor_pos: CharsXY,
+
     /// Whether video syncing is enabled or not.
     sync_enabled: bool,
 
+    /// Colors in effect when each screen not yet restored was saved.
+    saved_screens: Vec<(Option<u8>, Option<u8>)>,
+
+    /// Whether we entered the alternate screen to preserve the saved screens or not.
+    saved_in_alt: bool,
+
+    /// Text most recently placed in the clipboard during this session.
+    clipboard: String,
+
     /// Channel to receive key presses from the terminal.
     on_key_rx: Receiver<Key>,
+
+    /// Approximate state of the keyboard, updated by the task that reads key presses.
+    keyboard: Arc<Mutex<KeyboardState>>,
 }
 
 impl Drop for TerminalConsole {
@@ -86,10 +203,19 @@ impl TerminalConsole {
         let (on_key_tx, on_key_rx) = async_channel::unbounded();
 
         let is_tty = io::stdin().is_tty() && io::stdout().is_tty();
+        let keyboard = Arc::from(Mutex::from(KeyboardState::default()));
 
+        let mut cursor_pos = CharsXY::default();
         if is_tty {
             terminal::enable_raw_mode().map_err(crossterm_error_to_io_error)?;
-            tokio::task::spawn(TerminalConsole::raw_key_handler(on_key_tx, signals_tx));
+            if let Ok((x, y)) = cursor::position() {
+                cursor_pos = CharsXY::new(x, y);
+            }
+            tokio::task::spawn(TerminalConsole::raw_key_handler(
+                on_key_tx,
+                signals_tx,
+                keyboard.clone(),
+            ));
         } else {
             tokio::task::spawn(TerminalConsole::stdio_key_handler(on_key_tx));
         }
@@ -98,21 +224,47 @@ impl TerminalConsole {
             is_tty,
             fg_color: None,
             bg_color: None,
+            attributes: TextAttributes::default(),
             cursor_visible: true,
             alt_active: false,
+            cursor_pos,
+            main_cursor_pos: CharsXY::default(),
             sync_enabled: true,
+            saved_screens: vec![],
+            saved_in_alt: false,
+            clipboard: String::new(),
             on_key_rx,
+            keyboard,
         })
     }
 
     /// Async task to wait for key events on a raw terminal and translate them into events for the
     /// console or the machine.
-    async fn raw_key_handler(on_key_tx: Sender<Key>, signals_tx: Sender<Signal>) {
+    async fn raw_key_handler(
+        on_key_tx: Sender<Key>,
+        signals_tx: Sender<Signal>,
+        keyboard: Arc<Mutex<KeyboardState>>,
+    ) {
         use event::{KeyCode, KeyModifiers};
 
         let mut done = false;
         while !done {
-            let key = match event::read().map_err(crossterm_error_to_io_error) {
+            let ev = event::read().map_err(crossterm_error_to_io_error);
+            if let Ok(event::Event::Key(ev)) = &ev {
+                keyboard.lock().expect("Keyboard state must not be poisoned").record(ev);
+
+                // Let the machine dispatch any handler installed with `ON KEY`.  Control
+                // combinations are excluded so that, e.g., `Ctrl-C` does not trigger the `C` key.
+                if !ev.modifiers.contains(KeyModifiers::CONTROL) {
+                    if let Some(name) = key_code_name(&ev.code) {
+                        signals_tx
+                            .send(Signal::Key(name))
+                            .await
+                            .expect("Send to unbounded channel should not have failed")
+                    }
+                }
+            }
+            let key = match ev {
                 Ok(event::Event::Key(ev)) => match ev.code {
                     KeyCode::Backspace => Key::Backspace,
                     KeyCode::End => Key::End,
@@ -130,11 +282,13 @@ impl TerminalConsole {
                     KeyCode::Char('c') if ev.modifiers == KeyModifiers::CONTROL => Key::Interrupt,
                     KeyCode::Char('d') if ev.modifiers == KeyModifiers::CONTROL => Key::Eof,
                     KeyCode::Char('e') if ev.modifiers == KeyModifiers::CONTROL => Key::End,
-                    KeyCode::Char('f') if ev.modifiers == KeyModifiers::CONTROL => Key::ArrowRight,
                     KeyCode::Char('j') if ev.modifiers == KeyModifiers::CONTROL => Key::NewLine,
                     KeyCode::Char('m') if ev.modifiers == KeyModifiers::CONTROL => Key::NewLine,
                     KeyCode::Char('n') if ev.modifiers == KeyModifiers::CONTROL => Key::ArrowDown,
                     KeyCode::Char('p') if ev.modifiers == KeyModifiers::CONTROL => Key::ArrowUp,
+                    KeyCode::Char(ch) if ev.modifiers == KeyModifiers::CONTROL => {
+                        Key::Control(ch.to_ascii_lowercase())
+                    }
                     KeyCode::Char(ch) => Key::Char(ch),
                     KeyCode::Enter => Key::NewLine,
                     _ => Key::Unknown(format!("{:?}", ev)),
@@ -208,6 +362,12 @@ impl TerminalConsole {
         on_key_tx.close();
     }
 
+    /// Updates the tracked cursor position after writing `text`, optionally followed by a newline.
+    fn track_output(&mut self, text: &str, newline: bool) {
+        let size = self.size_chars().unwrap_or_else(|_| CharsXY::new(u16::MAX, u16::MAX));
+        self.cursor_pos = advance_cursor(self.cursor_pos, size, text, newline);
+    }
+
     /// Flushes the console, which has already been written to via `lock`, if syncing is enabled.
     fn maybe_flush(&self, mut lock: StdoutLock<'_>) -> io::Result<()> {
         if self.sync_enabled {
@@ -228,6 +388,7 @@ impl Console for TerminalConsole {
                 let stdout = io::stdout();
                 let mut stdout = stdout.lock();
                 stdout.write_all(b"\x08 \x08")?;
+                self.cursor_pos.x = self.cursor_pos.x.saturating_sub(1);
                 return self.maybe_flush(stdout);
             }
             ClearType::UntilNewLine => terminal::ClearType::UntilNewLine,
@@ -237,6 +398,7 @@ impl Console for TerminalConsole {
         stdout.queue(terminal::Clear(how)).map_err(crossterm_error_to_io_error)?;
         if how == terminal::ClearType::All {
             stdout.queue(cursor::MoveTo(0, 0)).map_err(crossterm_error_to_io_error)?;
+            self.cursor_pos = CharsXY::default();
         }
         self.maybe_flush(stdout)
     }
@@ -271,12 +433,48 @@ impl Console for TerminalConsole {
         self.maybe_flush(stdout)
     }
 
+    fn attributes(&self) -> TextAttributes {
+        self.attributes
+    }
+
+    fn set_attributes(&mut self, attributes: TextAttributes) -> io::Result<()> {
+        use style::Attribute;
+
+        let changes = [
+            (self.attributes.bold, attributes.bold, Attribute::Bold, Attribute::NormalIntensity),
+            (
+                self.attributes.underline,
+                attributes.underline,
+                Attribute::Underlined,
+                Attribute::NoUnderline,
+            ),
+            (self.attributes.reverse, attributes.reverse, Attribute::Reverse, Attribute::NoReverse),
+            (self.attributes.blink, attributes.blink, Attribute::SlowBlink, Attribute::NoBlink),
+        ];
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        for (old, new, on, off) in changes.iter() {
+            if old != new {
+                let attr = if *new { *on } else { *off };
+                stdout.queue(style::SetAttribute(attr)).map_err(crossterm_error_to_io_error)?;
+            }
+        }
+        self.attributes = attributes;
+        self.maybe_flush(stdout)
+    }
+
+    fn cursor_pos(&self) -> io::Result<CharsXY> {
+        Ok(self.cursor_pos)
+    }
+
     fn enter_alt(&mut self) -> io::Result<()> {
         if !self.alt_active {
             let stdout = io::stdout();
             let mut stdout = stdout.lock();
             stdout.queue(terminal::EnterAlternateScreen).map_err(crossterm_error_to_io_error)?;
             self.alt_active = true;
+            self.main_cursor_pos = self.cursor_pos;
             self.maybe_flush(stdout)
         } else {
             Ok(())
@@ -299,12 +497,23 @@ impl Console for TerminalConsole {
         self.is_tty
     }
 
+    fn is_key_down(&mut self, key: &Key) -> io::Result<bool> {
+        let keyboard = self.keyboard.lock().expect("Keyboard state must not be poisoned");
+        Ok(matches!(keyboard.held(), Some((held, _)) if held == key))
+    }
+
+    fn key_modifiers(&mut self) -> io::Result<KeyModifiers> {
+        let keyboard = self.keyboard.lock().expect("Keyboard state must not be poisoned");
+        Ok(keyboard.held().map(|(_, modifiers)| *modifiers).unwrap_or_default())
+    }
+
     fn leave_alt(&mut self) -> io::Result<()> {
         if self.alt_active {
             let stdout = io::stdout();
             let mut stdout = stdout.lock();
             stdout.queue(terminal::LeaveAlternateScreen).map_err(crossterm_error_to_io_error)?;
             self.alt_active = false;
+            self.cursor_pos = self.main_cursor_pos;
             self.maybe_flush(stdout)
         } else {
             Ok(())
@@ -322,6 +531,7 @@ impl Console for TerminalConsole {
         let stdout = io::stdout();
         let mut stdout = stdout.lock();
         stdout.queue(cursor::MoveTo(pos.x, pos.y)).map_err(crossterm_error_to_io_error)?;
+        self.cursor_pos = pos;
         self.maybe_flush(stdout)
     }
 
@@ -334,11 +544,17 @@ impl Console for TerminalConsole {
             Ordering::Greater => stdout.queue(cursor::MoveRight(off as u16)),
         }
         .map_err(crossterm_error_to_io_error)?;
+        self.cursor_pos.x = if off < 0 {
+            self.cursor_pos.x.saturating_sub(-off as u16)
+        } else {
+            self.cursor_pos.x.saturating_add(off as u16)
+        };
         self.maybe_flush(stdout)
     }
 
     fn print(&mut self, text: &str) -> io::Result<()> {
         let text = remove_control_chars(text.to_owned());
+        self.track_output(&text, true);
 
         let stdout = io::stdout();
         let mut stdout = stdout.lock();
@@ -366,6 +582,36 @@ impl Console for TerminalConsole {
         }
     }
 
+    fn restore_screen(&mut self) -> io::Result<()> {
+        let (fg, bg) = match self.saved_screens.pop() {
+            Some(colors) => colors,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "No saved screen to restore",
+                ))
+            }
+        };
+
+        if self.saved_screens.is_empty() && self.saved_in_alt {
+            self.leave_alt()?;
+            self.saved_in_alt = false;
+        }
+        self.set_color(fg, bg)
+    }
+
+    fn save_screen(&mut self) -> io::Result<()> {
+        // The terminal cannot give us its contents back, so we rely on the alternate screen to
+        // preserve them.  This means that the new screen starts blank and that nested saves can
+        // only bring back the outermost screen.
+        if self.saved_screens.is_empty() && !self.alt_active {
+            self.enter_alt()?;
+            self.saved_in_alt = true;
+        }
+        self.saved_screens.push((self.fg_color, self.bg_color));
+        Ok(())
+    }
+
     fn show_cursor(&mut self) -> io::Result<()> {
         if !self.cursor_visible {
             let stdout = io::stdout();
@@ -397,6 +643,7 @@ impl Console for TerminalConsole {
 
     fn write(&mut self, text: &str) -> io::Result<()> {
         let text = remove_control_chars(text.to_owned());
+        self.track_output(&text, false);
 
         let stdout = io::stdout();
         let mut stdout = stdout.lock();
@@ -404,6 +651,42 @@ impl Console for TerminalConsole {
         self.maybe_flush(stdout)
     }
 
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        if !self.is_tty {
+            // There is no window to set the title of, and emitting the escape sequence would
+            // pollute the output.
+            return Ok(());
+        }
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        stdout
+            .queue(terminal::SetTitle(&remove_control_chars(title)))
+            .map_err(crossterm_error_to_io_error)?;
+        self.maybe_flush(stdout)
+    }
+
+    async fn clipboard_get(&mut self) -> io::Result<String> {
+        // Few terminals let applications read the system clipboard via OSC 52 and the reply would
+        // have to be fished out of the input stream, so we can only return what we set.
+        Ok(self.clipboard.clone())
+    }
+
+    async fn clipboard_set(&mut self, text: &str) -> io::Result<()> {
+        self.clipboard = text.to_owned();
+
+        if !self.is_tty {
+            // There is no terminal to forward the text to, and emitting the escape sequence would
+            // pollute the output.
+            return Ok(());
+        }
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        write!(stdout, "\x1b]52;c;{}\x07", base64::encode(text))?;
+        self.maybe_flush(stdout)
+    }
+
     fn sync_now(&mut self) -> io::Result<()> {
         if self.sync_enabled {
             Ok(())
@@ -0,0 +1,211 @@
+// EndBASIC
+// Copyright 2020 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Runner for integration tests that use golden input and output files.
+//!
+//! These helpers are meant to be used from the integration tests of a crate (those in its `tests`
+//! directory) to execute the binaries built from the same workspace.
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Matches a formatted date.
+const DATE_RE: &str = "[0-9]{4}-[0-9]{2}-[0-9]{2} [0-2][0-9]:[0-5][0-9]";
+
+/// Matches a `file://` URI.
+const FILE_URI_RE: &str = "file://[^ \n\"]+";
+
+/// Matches a version number.
+const VERSION_RE: &str = "[0-9]+\\.[0-9]+\\.[0-9]+";
+
+/// Matches a year range.
+const YEAR_RANGE_RE: &str = "[0-9]{4}-[0-9]{4}";
+
+/// Computes the path to the directory where the running test's binary lives.
+pub fn self_dir() -> PathBuf {
+    let self_exe = env::current_exe().expect("Cannot get self's executable path");
+    let dir = self_exe.parent().expect("Cannot get self's directory");
+    assert!(dir.ends_with("target/debug/deps") || dir.ends_with("target/release/deps"));
+    dir.to_owned()
+}
+
+/// Computes the path to the built binary `name`.
+///
+/// `name` is relative to the directory that holds the build artifacts, so examples can be found as
+/// `examples/NAME`.
+pub fn bin_path<P: AsRef<Path>>(name: P) -> PathBuf {
+    let test_dir = self_dir();
+    let debug_or_release_dir = test_dir.parent().expect("Failed to get parent directory");
+    debug_or_release_dir.join(name).with_extension(env::consts::EXE_EXTENSION)
+}
+
+/// Computes the path to the source file `name`, which is relative to the root of the workspace.
+pub fn src_path(name: &str) -> PathBuf {
+    let test_dir = self_dir();
+    let debug_or_release_dir = test_dir.parent().expect("Failed to get parent directory");
+    let target_dir = debug_or_release_dir.parent().expect("Failed to get parent directory");
+    let dir = target_dir.parent().expect("Failed to get parent directory");
+
+    // Sanity-check that we landed in the right location.
+    assert!(dir.join("Cargo.toml").exists());
+
+    dir.join(name)
+}
+
+/// Same as `src_path` but returns a string for the few places where we need this.
+pub fn src_str(p: &str) -> String {
+    src_path(p).to_str().expect("Need paths to be valid strings").to_owned()
+}
+
+/// Describes the behavior for one of the three streams (stdin, stdout, stderr) connected to a
+/// program.
+pub enum Behavior {
+    /// Ensure the stream is silent.
+    Null,
+
+    /// If stdin, feed the given path as the program's input.  If stdout/stderr, expect the contents
+    /// of the stream to match this file.
+    File(PathBuf),
+
+    /// If stdin, this is not supported.  If stdout/stderr, expect the contents of the stream to
+    /// match this literal string.
+    Literal(String),
+}
+
+/// Reads the contents of a golden data file.
+///
+/// Golden files must not contain any of the values that `apply_mocks` replaces.
+pub fn read_golden(path: &Path) -> String {
+    let mut f = File::open(path).expect("Failed to open golden data file");
+    let mut golden = vec![];
+    f.read_to_end(&mut golden).expect("Failed to read golden data file");
+    let raw = String::from_utf8(golden).expect("Golden data file is not valid UTF-8");
+    let golden = if cfg!(target_os = "windows") { raw.replace("\r\n", "\n") } else { raw };
+
+    // This is the opposite of apply_mocks and ensures we don't leak actual values into the golden
+    // files by mistake.
+    let version_re = regex::Regex::new(VERSION_RE).unwrap();
+    assert!(
+        !version_re.is_match(&golden),
+        "Golden file {} contains a version number",
+        path.display()
+    );
+    let date_re = regex::Regex::new(DATE_RE).unwrap();
+    assert!(!date_re.is_match(&golden), "Golden file {} contains a date", path.display());
+    let year_range_re = regex::Regex::new(YEAR_RANGE_RE).unwrap();
+    assert!(
+        !year_range_re.is_match(&golden),
+        "Golden file {} contains a year range",
+        path.display()
+    );
+
+    golden
+}
+
+/// Replaces the parts of the output that can change due to the environment with placeholders.
+///
+/// Version numbers become `X.Y.Z`, dates become `YYYY-MM-DD HH:MM`, year ranges become
+/// `YYYY-YYYY`, and `file://` URIs become `file:///PATH/TO/TMPDIR`.
+pub fn apply_mocks(input: String) -> String {
+    let version_re = regex::Regex::new(VERSION_RE).unwrap();
+    let input = version_re.replace_all(&input, "X.Y.Z").into_owned();
+
+    let date_re = regex::Regex::new(DATE_RE).unwrap();
+    let input = date_re.replace_all(&input, "YYYY-MM-DD HH:MM").into_owned();
+
+    let year_range_re = regex::Regex::new(YEAR_RANGE_RE).unwrap();
+    let input = year_range_re.replace_all(&input, "YYYY-YYYY").into_owned();
+
+    let file_uri_re = regex::Regex::new(FILE_URI_RE).unwrap();
+    file_uri_re.replace_all(&input, "file:///PATH/TO/TMPDIR").into()
+}
+
+/// Runs `bin` with arguments `args` and checks its behavior against expectations.
+///
+/// `exp_code` is the expected error code from the program.  `stdin_behavior` indicates what to feed
+/// to the program's stdin.  `stdout_behavior` and `stderr_behavior` indicate what to expect from
+/// the program's textual output, which is passed through `apply_mocks` before the comparison.
+///
+/// The program runs with a fixed 80x24 console size and with English messages so that its output
+/// does not depend on the environment.
+pub fn check<P: AsRef<Path>>(
+    bin: P,
+    args: &[&str],
+    exp_code: i32,
+    stdin_behavior: Behavior,
+    stdout_behavior: Behavior,
+    stderr_behavior: Behavior,
+) {
+    let golden_stdin = match stdin_behavior {
+        Behavior::Null => process::Stdio::null(),
+        Behavior::File(path) => File::open(path).unwrap().into(),
+        Behavior::Literal(_) => panic!("Literals not supported for stdin"),
+    };
+
+    let exp_stdout = match stdout_behavior {
+        Behavior::Null => "".to_owned(),
+        Behavior::File(path) => read_golden(&path),
+        Behavior::Literal(text) => text,
+    };
+
+    let exp_stderr = match stderr_behavior {
+        Behavior::Null => "".to_owned(),
+        Behavior::File(path) => read_golden(&path),
+        Behavior::Literal(text) => text,
+    };
+
+    let result = process::Command::new(bin.as_ref())
+        .args(args)
+        .stdin(golden_stdin)
+        .env("LINES", "24")
+        .env("COLUMNS", "80")
+        .env("ENDBASIC_LANG", "en")
+        .output()
+        .expect("Failed to execute subprocess");
+    let code = result.status.code().expect("Subprocess didn't exit cleanly");
+    let stdout =
+        apply_mocks(String::from_utf8(result.stdout).expect("Stdout not is not valid UTF-8"));
+    let stderr =
+        apply_mocks(String::from_utf8(result.stderr).expect("Stderr not is not valid UTF-8"));
+
+    if exp_code != code || exp_stdout != stdout || exp_stderr != stderr {
+        eprintln!("Exit code: {}", code);
+        eprintln!("stdout:\n{}", stdout);
+        eprintln!("stderr:\n{}", stderr);
+        assert_eq!(exp_code, code);
+        assert_eq!(exp_stdout, stdout);
+        assert_eq!(exp_stderr, stderr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_mocks() {
+        assert_eq!("nothing to mock", apply_mocks("nothing to mock".to_owned()));
+        assert_eq!(
+            "EndBASIC X.Y.Z\nCopyright YYYY-YYYY\nYYYY-MM-DD HH:MM  a.bas\nfile:///PATH/TO/TMPDIR end",
+            apply_mocks(
+                "EndBASIC 0.10.0\nCopyright 2020-2026\n2026-10-17 08:00  a.bas\nfile:///tmp/x end"
+                    .to_owned()
+            )
+        );
+    }
+}
@@ -0,0 +1,36 @@
+// EndBASIC
+// Copyright 2022 Julio Merino
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License.  You may obtain a copy
+// of the License at:
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// License for the specific language governing permissions and limitations
+// under the License.
+
+//! Test utilities for EndBASIC and for the applications that embed it.
+//!
+//! The `Tester` type, along with the mock console, storage and program it is wired to, lets you
+//! run snippets of code against a machine that contains your own commands and functions and then
+//! validate the side-effects of their execution.  To test features that need extra state, such as
+//! a service client, wrap `Tester` and `Checker` in your own types that inject the state when
+//! building the machine and that verify it when checking the results.
+//!
+//! The `golden` module lets you run your binaries and compare their behavior against golden files
+//! stored in your source tree.
+
+// Keep these in sync with other top-level files.
+#![warn(anonymous_parameters, bad_style, missing_docs)]
+#![warn(unused, unused_extern_crates, unused_import_braces, unused_qualifications)]
+#![warn(unsafe_code)]
+
+// The mocks live in `endbasic-std` because its own unit tests need them and they cannot depend
+// on this crate without ending up with two incompatible copies of the library's types.
+pub use endbasic_std::testutils::*;
+
+pub mod golden;
@@ -20,24 +20,27 @@
 //! framebuffer-based console is the same -- so we should make it so to minimize bugs here
 //! as we cannot easily test this implementation.
 
-use crate::input::WebInput;
-use crate::{log_and_panic, Yielder};
+use crate::input::{CursorState, WebInput};
+use crate::{do_sleep, log_and_panic, Yielder};
 use async_trait::async_trait;
 use endbasic_std::console::AnsiColor;
 use endbasic_std::console::{
-    ansi_color_to_rgb, remove_control_chars, CharsXY, ClearType, Console, Key, LineBuffer,
-    PixelsXY, SizeInPixels, RGB,
+    ansi_color_to_rgb, char_width, remove_control_chars, rgb_to_ansi_color, str_width, CharsXY,
+    ClearType, Console, Key, LineBuffer, PixelsXY, SizeInPixels, NUM_PAGES, RGB,
 };
+use endbasic_std::gfx::raster;
 use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::f64::consts::PI;
 use std::io;
 use std::rc::Rc;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
 use web_sys::HtmlCanvasElement;
 use web_sys::ImageData;
-use web_sys::{CanvasRenderingContext2d, ContextAttributes2d};
+use web_sys::{CanvasRenderingContext2d, CanvasWindingRule, Clipboard, ContextAttributes2d};
 
 /// Default foreground color, used at console creation time and when requesting the default color
 /// via the `COLOR` command.
@@ -55,6 +58,9 @@ const DEFAULT_FONT_FACE: &str = "\"IBM Plex Mono\", SFMono-Regular, Menlo, Monac
 /// Size of the default font to use in pixels.
 const DEFAULT_FONT_SIZE: u16 = 16;
 
+/// Delay between key polls when waiting for a key press with a timeout.
+const POLL_KEY_DELAY_MS: u64 = 10;
+
 /// Converts a `JsValue` error to an `io::Error`.
 pub(crate) fn js_value_to_io_error(e: JsValue) -> io::Error {
     if let Some(str) = e.as_string() {
@@ -63,6 +69,14 @@ pub(crate) fn js_value_to_io_error(e: JsValue) -> io::Error {
     io::Error::new(io::ErrorKind::Other, "Unknown error")
 }
 
+/// Obtains the asynchronous clipboard of the browser.
+fn clipboard() -> io::Result<Clipboard> {
+    match web_sys::window() {
+        Some(window) => Ok(window.navigator().clipboard()),
+        None => Err(io::Error::new(io::ErrorKind::Other, "Cannot access the window")),
+    }
+}
+
 /// Multiplication of values into a narrower type with silent value clamping.
 trait ClampedMul<T, O> {
     /// Multiplies self by `rhs` and clamps the result to fit in `O`.
@@ -133,11 +147,56 @@ fn html_canvas_to_2d_context(canvas: HtmlCanvasElement) -> io::Result<CanvasRend
     Ok(context)
 }
 
+/// Configures `context` to render text with the `face` font family at `size` pixels and returns
+/// the size of each character and the number of characters that fit in `size_pixels`.
+fn set_font(
+    context: &CanvasRenderingContext2d,
+    face: &str,
+    size: u16,
+    size_pixels: SizeInPixels,
+) -> io::Result<(SizeInPixels, CharsXY)> {
+    context.set_font(&format!("{}px {}", size, face));
+
+    let glyph_size = {
+        let text_metrics = context.measure_text("X").map_err(js_value_to_io_error)?;
+        let width = text_metrics.width().ceil() as u16;
+        let height = size.saturating_add(2); // Pad lines a little bit.
+        SizeInPixels { width, height }
+    };
+
+    let size_chars = {
+        let width = match size_pixels.width.checked_div(glyph_size.width) {
+            Some(v) => v,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid glyph width {}", glyph_size.width),
+                ))
+            }
+        };
+        let height = match size_pixels.height.checked_div(glyph_size.height) {
+            Some(v) => v,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid glyph height {}", glyph_size.height),
+                ))
+            }
+        };
+        CharsXY::new(width, height)
+    };
+
+    Ok((glyph_size, size_chars))
+}
+
 /// Implementation of a console that renders on an HTML canvas.
 pub(crate) struct CanvasConsole {
     /// The HTML canvas context on which to render the console.
     context: CanvasRenderingContext2d,
 
+    /// The font family used to render text.
+    font_face: String,
+
     yielder: Rc<RefCell<Yielder>>,
 
     /// Keyboard input handler for the web.
@@ -179,6 +238,16 @@ pub(crate) struct CanvasConsole {
 
     /// Whether video syncing is enabled or not.
     sync_enabled: bool,
+
+    /// Page that receives all output.  Its context always lives in `context`.
+    draw_page: u8,
+
+    /// Contexts of all pages other than `draw_page`.  Off-screen pages are created on first use.
+    pages: Vec<Option<CanvasRenderingContext2d>>,
+
+    /// Top-left and bottom-right corners of the area to which graphical output is constrained, if
+    /// any.
+    clip: Option<(PixelsXY, PixelsXY)>,
 }
 
 impl CanvasConsole {
@@ -212,40 +281,13 @@ impl CanvasConsole {
         };
 
         let context = html_canvas_to_2d_context(canvas)?;
-        context.set_font(&format!("{}px {}", DEFAULT_FONT_SIZE, DEFAULT_FONT_FACE));
         context.set_text_baseline("middle");
-
-        let glyph_size = {
-            let text_metrics = context.measure_text("X").map_err(js_value_to_io_error)?;
-            let width = text_metrics.width().ceil() as u16;
-            let height = DEFAULT_FONT_SIZE + 2; // Pad lines a little bit.
-            SizeInPixels { width, height }
-        };
-
-        let size_chars = {
-            let width = match size_pixels.width.checked_div(glyph_size.width) {
-                Some(v) => v,
-                None => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!("Invalid glyph width {}", glyph_size.width),
-                    ))
-                }
-            };
-            let height = match size_pixels.height.checked_div(glyph_size.height) {
-                Some(v) => v,
-                None => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!("Invalid glyph height {}", glyph_size.height),
-                    ))
-                }
-            };
-            CharsXY::new(width, height)
-        };
+        let (glyph_size, size_chars) =
+            set_font(&context, DEFAULT_FONT_FACE, DEFAULT_FONT_SIZE, size_pixels)?;
 
         let mut console = Self {
             context,
+            font_face: DEFAULT_FONT_FACE.to_owned(),
             yielder,
             input,
             size_pixels,
@@ -260,11 +302,26 @@ impl CanvasConsole {
             bg_color: ansi_color_to_rgb(DEFAULT_BG_COLOR),
             alt_backup: None,
             sync_enabled: true,
+            draw_page: 0,
+            clip: None,
+            pages: (0..NUM_PAGES).map(|_| None).collect(),
         };
         console.clear(ClearType::All)?;
         Ok(console)
     }
 
+    /// Returns the state of the cursor needed to translate taps into cursor movements.
+    ///
+    /// Taps can only move the cursor across lines in the alternate console, which is where
+    /// full-screen programs such as the editor run.
+    fn cursor_state(&self) -> CursorState {
+        CursorState {
+            glyph_size: self.glyph_size,
+            cursor_pos: self.cursor_pos,
+            vertical: self.alt_backup.is_some(),
+        }
+    }
+
     /// Returns the size of the console in pixels.
     pub(crate) fn size_pixels(&self) -> SizeInPixels {
         self.size_pixels
@@ -290,6 +347,58 @@ impl CanvasConsole {
             .set_stroke_style(&JsValue::from_str(&format!("rgb({}, {}, {})", rgb.0, rgb.1, rgb.2)));
     }
 
+    /// Starts a new path that traces the closed polygon with vertices `points`.
+    fn trace_poly(&mut self, points: &[PixelsXY]) {
+        self.context.begin_path();
+        if let Some((first, rest)) = points.split_first() {
+            self.context.move_to(f64::from(first.x), f64::from(first.y));
+            for xy in rest {
+                self.context.line_to(f64::from(xy.x), f64::from(xy.y));
+            }
+            self.context.close_path();
+        }
+    }
+
+    /// Creates a new blank off-screen page of the same size as the console.
+    fn new_page(&mut self) -> io::Result<CanvasRenderingContext2d> {
+        let document = match web_sys::window().and_then(|w| w.document()) {
+            Some(document) => document,
+            None => return Err(io::Error::new(io::ErrorKind::Other, "Cannot access the document")),
+        };
+        let canvas = document
+            .create_element("canvas")
+            .map_err(js_value_to_io_error)?
+            .dyn_into::<HtmlCanvasElement>()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Created element is not a canvas"))?;
+        canvas.set_width(u32::from(self.size_pixels.width));
+        canvas.set_height(u32::from(self.size_pixels.height));
+
+        let context = html_canvas_to_2d_context(canvas)?;
+        context.set_font(&self.context.font());
+        context.set_text_baseline(&self.context.text_baseline());
+        context.set_fill_style(&JsValue::from_str(&format!(
+            "rgb({}, {}, {})",
+            self.bg_color.0, self.bg_color.1, self.bg_color.2
+        )));
+        context.fill_rect(
+            0.0,
+            0.0,
+            f64::from(self.size_pixels.width),
+            f64::from(self.size_pixels.height),
+        );
+        Ok(context)
+    }
+
+    /// Returns the context of `page`, creating it if it does not exist yet.  `page` must not be
+    /// the current drawing page.
+    fn page_context(&mut self, page: u8) -> io::Result<&CanvasRenderingContext2d> {
+        debug_assert_ne!(page, self.draw_page);
+        if self.pages[usize::from(page)].is_none() {
+            self.pages[usize::from(page)] = Some(self.new_page()?);
+        }
+        Ok(self.pages[usize::from(page)].as_ref().expect("Page must have been created"))
+    }
+
     /// Forces the rendering of the current contents of the canvas onto the output window.
     fn force_present_canvas(&mut self) -> io::Result<()> {
         self.yielder.borrow_mut().schedule();
@@ -388,13 +497,40 @@ impl CanvasConsole {
         Ok(())
     }
 
+    /// Runs `draw` with the graphical output constrained to the clipping area, if any, and then
+    /// presents the canvas.
+    fn clipped<F>(&mut self, draw: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut Self) -> io::Result<()>,
+    {
+        let result = match self.clip {
+            Some((x1y1, x2y2)) => {
+                self.context.save();
+                self.context.begin_path();
+                self.context.rect(
+                    f64::from(x1y1.x),
+                    f64::from(x1y1.y),
+                    f64::from(i32::from(x2y2.x) - i32::from(x1y1.x) + 1),
+                    f64::from(i32::from(x2y2.y) - i32::from(x1y1.y) + 1),
+                );
+                self.context.clip();
+                let result = draw(self);
+                self.context.restore();
+                result
+            }
+            None => draw(self),
+        };
+        result?;
+        self.present_canvas()
+    }
+
     /// Renders the given text at the `start` position.
     ///
     /// Does not handle overflow nor scrolling.
     fn raw_write(&mut self, text: &str, start: PixelsXY) -> io::Result<()> {
         debug_assert!(!text.is_empty(), "It doesn't make sense to render an empty string");
 
-        let len = match u16::try_from(text.chars().count()) {
+        let len = match u16::try_from(str_width(text)) {
             Ok(v) => v,
             Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "Text too long")),
         };
@@ -409,8 +545,10 @@ impl CanvasConsole {
 
         self.set_fill_style_rgb(self.fg_color);
         // We must render one character at a time because the glyph width of the original font is
-        // not guaranteed to be an integer pixel size.
+        // not guaranteed to be an integer pixel size.  Wide characters advance by two cells and
+        // zero-width characters are drawn on top of the character that precedes them.
         let mut x = start.x;
+        let mut last_x = start.x;
         let advance = match i16::try_from(self.glyph_size.width) {
             Ok(width) => width,
             Err(e) => log_and_panic!("Glyph size is too big: {}", e),
@@ -423,11 +561,14 @@ impl CanvasConsole {
             let mut buf = [0u8; 4];
             let sb = ch.encode_utf8(&mut buf);
 
+            let width = char_width(ch) as i16;
+            let ch_x = if width == 0 { last_x } else { x };
             self.context
-                .fill_text(sb, f64::from(x), f64::from(start.y + y_offset))
+                .fill_text(sb, f64::from(ch_x), f64::from(start.y + y_offset))
                 .map_err(js_value_to_io_error)?;
 
-            x += advance;
+            last_x = ch_x;
+            x += advance * width;
         }
 
         Ok(())
@@ -439,21 +580,28 @@ impl CanvasConsole {
         let mut line_buffer = LineBuffer::from(text);
 
         loop {
-            let fit_chars = self.size_chars.x - self.cursor_pos.x;
-
-            let remaining = line_buffer.split_off(usize::from(fit_chars));
-            let len = line_buffer.len();
-            if len > 0 {
+            let fit_width = self.size_chars.x.saturating_sub(self.cursor_pos.x);
+
+            let mut remaining = line_buffer.split_off_width(usize::from(fit_width));
+            if line_buffer.is_empty() && self.cursor_pos.x == 0 {
+                // The next character is wider than the whole console.  Render it clipped instead
+                // of wrapping forever.
+                line_buffer = remaining;
+                remaining = line_buffer.split_off(line_buffer.next_boundary(0));
+            }
+            if !line_buffer.is_empty() {
+                let width = line_buffer.width();
                 self.raw_write(
                     &line_buffer.into_inner(),
                     self.cursor_pos.clamped_mul(self.glyph_size),
                 )?;
-                self.cursor_pos.x += match u16::try_from(len) {
-                    Ok(len) => len,
+                let width = match u16::try_from(width) {
+                    Ok(width) => width,
                     Err(e) => {
-                        log_and_panic!("Partial length was computed to fit on the screen: {}", e)
+                        log_and_panic!("Partial width was computed to fit on the screen: {}", e)
                     }
                 };
+                self.cursor_pos.x = self.cursor_pos.x.saturating_add(width);
             }
 
             line_buffer = remaining;
@@ -541,6 +689,18 @@ impl Console for CanvasConsole {
         Ok(())
     }
 
+    fn set_color_rgb(&mut self, fg: Option<RGB>, bg: Option<RGB>) -> io::Result<()> {
+        self.ansi_fg_color = fg.map(rgb_to_ansi_color);
+        self.fg_color = fg.unwrap_or_else(|| ansi_color_to_rgb(DEFAULT_FG_COLOR));
+        self.ansi_bg_color = bg.map(rgb_to_ansi_color);
+        self.bg_color = bg.unwrap_or_else(|| ansi_color_to_rgb(DEFAULT_BG_COLOR));
+        Ok(())
+    }
+
+    fn cursor_pos(&self) -> io::Result<CharsXY> {
+        Ok(self.cursor_pos)
+    }
+
     fn enter_alt(&mut self) -> io::Result<()> {
         if self.alt_backup.is_some() {
             return Err(io::Error::new(
@@ -630,11 +790,30 @@ impl Console for CanvasConsole {
     }
 
     async fn poll_key(&mut self) -> io::Result<Option<Key>> {
-        self.input.try_recv().await
+        let cursor = self.cursor_state();
+        self.input.try_recv(&cursor).await
     }
 
     async fn read_key(&mut self) -> io::Result<Key> {
-        self.input.recv().await
+        let cursor = self.cursor_state();
+        self.input.recv(&cursor).await
+    }
+
+    async fn read_key_timeout(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        // The default implementation blocks the thread to wait, which we cannot do in the browser,
+        // so wait by yielding to the JavaScript event loop instead.
+        let mut remaining = timeout;
+        loop {
+            if let Some(key) = self.poll_key().await? {
+                return Ok(Some(key));
+            }
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            let delay = remaining.min(Duration::from_millis(POLL_KEY_DELAY_MS));
+            do_sleep(delay.as_millis() as i32, ()).await;
+            remaining -= delay;
+        }
     }
 
     fn show_cursor(&mut self) -> io::Result<()> {
@@ -665,63 +844,365 @@ impl Console for CanvasConsole {
         self.present_canvas()
     }
 
+    fn draw_arc(&mut self, center: PixelsXY, radius: u16, start: i32, end: i32) -> io::Result<()> {
+        self.clipped(|console| {
+            // Angles grow counterclockwise in EndBASIC but the y axis of the canvas points down,
+            // so negate them and trace the arc in the opposite direction.
+            let (start, end) = if i64::from(end) - i64::from(start) >= 360 {
+                (0.0, 2.0 * PI)
+            } else {
+                (-f64::from(start).to_radians(), -f64::from(end).to_radians())
+            };
+            console.set_stroke_style_rgb(console.fg_color);
+            console.context.begin_path();
+            console
+                .context
+                .arc_with_anticlockwise(
+                    f64::from(center.x),
+                    f64::from(center.y),
+                    f64::from(radius),
+                    start,
+                    end,
+                    true,
+                )
+                .map_err(js_value_to_io_error)?;
+            console.context.stroke();
+            Ok(())
+        })
+    }
+
     fn draw_circle(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
-        self.set_stroke_style_rgb(self.fg_color);
-        self.context.begin_path();
-        self.context
-            .arc(f64::from(center.x), f64::from(center.y), f64::from(radius), 0.0, 2.0 * PI)
-            .map_err(js_value_to_io_error)?;
-        self.context.stroke();
-        self.present_canvas()
+        self.clipped(|console| {
+            console.set_stroke_style_rgb(console.fg_color);
+            console.context.begin_path();
+            console
+                .context
+                .arc(f64::from(center.x), f64::from(center.y), f64::from(radius), 0.0, 2.0 * PI)
+                .map_err(js_value_to_io_error)?;
+            console.context.stroke();
+            Ok(())
+        })
     }
 
     fn draw_circle_filled(&mut self, center: PixelsXY, radius: u16) -> io::Result<()> {
-        self.set_fill_style_rgb(self.fg_color);
-        self.context.begin_path();
-        self.context
-            .arc(f64::from(center.x), f64::from(center.y), f64::from(radius), 0.0, 2.0 * PI)
-            .map_err(js_value_to_io_error)?;
-        self.context.fill();
-        self.present_canvas()
+        self.clipped(|console| {
+            console.set_fill_style_rgb(console.fg_color);
+            console.context.begin_path();
+            console
+                .context
+                .arc(f64::from(center.x), f64::from(center.y), f64::from(radius), 0.0, 2.0 * PI)
+                .map_err(js_value_to_io_error)?;
+            console.context.fill();
+            Ok(())
+        })
+    }
+
+    fn draw_ellipse(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.clipped(|console| {
+            console.set_stroke_style_rgb(console.fg_color);
+            console.context.begin_path();
+            console
+                .context
+                .ellipse(
+                    f64::from(center.x),
+                    f64::from(center.y),
+                    f64::from(rx),
+                    f64::from(ry),
+                    0.0,
+                    0.0,
+                    2.0 * PI,
+                )
+                .map_err(js_value_to_io_error)?;
+            console.context.stroke();
+            Ok(())
+        })
+    }
+
+    fn draw_ellipse_filled(&mut self, center: PixelsXY, rx: u16, ry: u16) -> io::Result<()> {
+        self.clipped(|console| {
+            console.set_fill_style_rgb(console.fg_color);
+            console.context.begin_path();
+            console
+                .context
+                .ellipse(
+                    f64::from(center.x),
+                    f64::from(center.y),
+                    f64::from(rx),
+                    f64::from(ry),
+                    0.0,
+                    0.0,
+                    2.0 * PI,
+                )
+                .map_err(js_value_to_io_error)?;
+            console.context.fill();
+            Ok(())
+        })
     }
 
     fn draw_line(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
-        self.context.begin_path();
-        self.set_stroke_style_rgb(self.fg_color);
-        self.context.move_to(f64::from(x1y1.x), f64::from(x1y1.y));
-        self.context.line_to(f64::from(x2y2.x), f64::from(x2y2.y));
-        self.context.stroke();
-        self.present_canvas()
+        self.clipped(|console| {
+            console.context.begin_path();
+            console.set_stroke_style_rgb(console.fg_color);
+            console.context.move_to(f64::from(x1y1.x), f64::from(x1y1.y));
+            console.context.line_to(f64::from(x2y2.x), f64::from(x2y2.y));
+            console.context.stroke();
+            Ok(())
+        })
     }
 
     fn draw_pixel(&mut self, xy: PixelsXY) -> io::Result<()> {
-        self.set_fill_style_rgb(self.fg_color);
-        self.context.fill_rect(f64::from(xy.x), f64::from(xy.y), 1.0, 1.0);
-        self.present_canvas()
+        self.clipped(|console| {
+            console.set_fill_style_rgb(console.fg_color);
+            console.context.fill_rect(f64::from(xy.x), f64::from(xy.y), 1.0, 1.0);
+            Ok(())
+        })
+    }
+
+    fn draw_poly(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.clipped(|console| {
+            console.set_stroke_style_rgb(console.fg_color);
+            console.trace_poly(points);
+            console.context.stroke();
+            Ok(())
+        })
+    }
+
+    fn draw_poly_filled(&mut self, points: &[PixelsXY]) -> io::Result<()> {
+        self.clipped(|console| {
+            console.set_fill_style_rgb(console.fg_color);
+            console.trace_poly(points);
+            console.context.fill_with_canvas_winding_rule(CanvasWindingRule::Evenodd);
+            Ok(())
+        })
     }
 
     fn draw_rect(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
-        self.set_stroke_style_rgb(self.fg_color);
-        self.context.stroke_rect(
-            f64::from(x1y1.x),
-            f64::from(x1y1.y),
-            f64::from(x2y2.x - x1y1.x),
-            f64::from(x2y2.y - x1y1.y),
-        );
-        self.present_canvas()
+        self.clipped(|console| {
+            console.set_stroke_style_rgb(console.fg_color);
+            console.context.stroke_rect(
+                f64::from(x1y1.x),
+                f64::from(x1y1.y),
+                f64::from(x2y2.x - x1y1.x),
+                f64::from(x2y2.y - x1y1.y),
+            );
+            Ok(())
+        })
     }
 
     fn draw_rect_filled(&mut self, x1y1: PixelsXY, x2y2: PixelsXY) -> io::Result<()> {
-        self.set_fill_style_rgb(self.fg_color);
-        self.context.fill_rect(
-            f64::from(x1y1.x),
-            f64::from(x1y1.y),
-            f64::from(x2y2.x - x1y1.x),
-            f64::from(x2y2.y - x1y1.y),
+        self.clipped(|console| {
+            console.set_fill_style_rgb(console.fg_color);
+            console.context.fill_rect(
+                f64::from(x1y1.x),
+                f64::from(x1y1.y),
+                f64::from(x2y2.x - x1y1.x),
+                f64::from(x2y2.y - x1y1.y),
+            );
+            Ok(())
+        })
+    }
+
+    fn draw_text(&mut self, xy: PixelsXY, text: &str, size: Option<u16>) -> io::Result<()> {
+        self.clipped(|console| {
+            let old_font = console.context.font();
+            if let Some(size) = size {
+                console.context.set_font(&format!("{}px {}", size, console.font_face));
+            }
+            console.context.set_text_baseline("top");
+            console.set_fill_style_rgb(console.fg_color);
+            let result = console.context.fill_text(text, f64::from(xy.x), f64::from(xy.y));
+            console.context.set_text_baseline("middle");
+            console.context.set_font(&old_font);
+            result.map_err(js_value_to_io_error)?;
+            Ok(())
+        })
+    }
+
+    fn flood_fill(&mut self, xy: PixelsXY, border: Option<RGB>) -> io::Result<()> {
+        self.clipped(|console| {
+            let snapshot = console
+                .context
+                .get_image_data(
+                    0.0,
+                    0.0,
+                    f64::from(console.size_pixels.width),
+                    f64::from(console.size_pixels.height),
+                )
+                .map_err(js_value_to_io_error)?
+                .data();
+            let width = usize::from(console.size_pixels.width);
+            let color_at = |xy: PixelsXY| {
+                let offset = (usize::from(xy.y as u16) * width + usize::from(xy.x as u16)) * 4;
+                (snapshot[offset], snapshot[offset + 1], snapshot[offset + 2])
+            };
+
+            let target = match border {
+                Some(border) => border,
+                None => {
+                    if xy.x < 0
+                        || xy.y < 0
+                        || xy.x as u16 >= console.size_pixels.width
+                        || xy.y as u16 >= console.size_pixels.height
+                    {
+                        return Ok(());
+                    }
+                    color_at(xy)
+                }
+            };
+            if border.is_none() && target == console.fg_color {
+                // Filling the area with the color it already has would be a no-op.
+                return Ok(());
+            }
+
+            console.set_fill_style_rgb(console.fg_color);
+            let context = &console.context;
+            raster::flood_fill(
+                console.size_pixels,
+                xy,
+                |xy| match border {
+                    Some(_) => color_at(xy) != target,
+                    None => color_at(xy) == target,
+                },
+                |x1y1, x2y2| {
+                    context.fill_rect(
+                        f64::from(x1y1.x),
+                        f64::from(x1y1.y),
+                        f64::from(x2y2.x - x1y1.x + 1),
+                        1.0,
+                    );
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        })
+    }
+
+    fn read_pixel(&self, xy: PixelsXY) -> io::Result<Option<RGB>> {
+        if xy.x < 0
+            || xy.y < 0
+            || xy.x as u16 >= self.size_pixels.width
+            || xy.y as u16 >= self.size_pixels.height
+        {
+            return Ok(None);
+        }
+
+        let pixel = self
+            .context
+            .get_image_data(f64::from(xy.x), f64::from(xy.y), 1.0, 1.0)
+            .map_err(js_value_to_io_error)?
+            .data();
+        Ok(Some((pixel[0], pixel[1], pixel[2])))
+    }
+
+    fn set_clip(&mut self, area: Option<(PixelsXY, PixelsXY)>) -> io::Result<()> {
+        self.clip = area;
+        Ok(())
+    }
+
+    fn set_draw_page(&mut self, page: u8) -> io::Result<()> {
+        if page >= NUM_PAGES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page out of range"));
+        }
+        if page == self.draw_page {
+            return Ok(());
+        }
+
+        self.clear_cursor()?;
+        let new = self.page_context(page)?.clone();
+        self.pages[usize::from(page)] = None;
+        let old = std::mem::replace(&mut self.context, new);
+        self.pages[usize::from(self.draw_page)] = Some(old);
+        self.draw_page = page;
+        self.draw_cursor()?;
+        self.present_canvas()
+    }
+
+    fn flip_page(&mut self, page: u8) -> io::Result<()> {
+        if page >= NUM_PAGES {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Page out of range"));
+        }
+        if page == 0 {
+            return self.present_canvas();
+        }
+
+        self.clear_cursor()?;
+        let src = if page == self.draw_page {
+            self.context.clone()
+        } else {
+            self.page_context(page)?.clone()
+        };
+        let dest = match self.draw_page {
+            0 => self.context.clone(),
+            _ => self.page_context(0)?.clone(),
+        };
+        let src = src.canvas().expect("Pages are always backed by a canvas");
+        let result =
+            dest.draw_image_with_html_canvas_element(&src, 0.0, 0.0).map_err(js_value_to_io_error);
+        self.draw_cursor()?;
+        result?;
+        self.present_canvas()
+    }
+
+    fn set_font(&mut self, face: Option<&str>, size: u16) -> io::Result<()> {
+        let face = match face {
+            None => self.font_face.clone(),
+            Some("") => DEFAULT_FONT_FACE.to_owned(),
+            Some(face) => face.to_owned(),
+        };
+
+        self.clear_cursor()?;
+        let old_font = self.context.font();
+        let (glyph_size, size_chars) = match set_font(&self.context, &face, size, self.size_pixels)
+        {
+            Ok(sizes) => sizes,
+            Err(e) => {
+                self.context.set_font(&old_font);
+                self.draw_cursor()?;
+                return Err(e);
+            }
+        };
+        for context in self.pages.iter().flatten() {
+            context.set_font(&self.context.font());
+        }
+
+        // Keep the cursor as close as possible to where it was on the screen.
+        let origin = self.cursor_pos.clamped_mul(self.glyph_size);
+        self.cursor_pos = CharsXY::new(
+            (origin.x as u16 / glyph_size.width).min(size_chars.x.saturating_sub(1)),
+            (origin.y as u16 / glyph_size.height).min(size_chars.y.saturating_sub(1)),
         );
+
+        self.font_face = face;
+        self.glyph_size = glyph_size;
+        self.size_chars = size_chars;
+        self.draw_cursor()?;
         self.present_canvas()
     }
 
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        match web_sys::window().and_then(|w| w.document()) {
+            Some(document) => {
+                document.set_title(&remove_control_chars(title));
+                Ok(())
+            }
+            None => Err(io::Error::new(io::ErrorKind::Other, "Cannot access the document")),
+        }
+    }
+
+    async fn clipboard_get(&mut self) -> io::Result<String> {
+        let promise = clipboard()?.read_text();
+        let text = JsFuture::from(promise).await.map_err(js_value_to_io_error)?;
+        text.as_string().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "Clipboard does not hold text")
+        })
+    }
+
+    async fn clipboard_set(&mut self, text: &str) -> io::Result<()> {
+        let promise = clipboard()?.write_text(text);
+        JsFuture::from(promise).await.map_err(js_value_to_io_error)?;
+        Ok(())
+    }
+
     fn sync_now(&mut self) -> io::Result<()> {
         if self.sync_enabled {
             Ok(())
@@ -77,8 +77,57 @@ if (isMobile) {
         osk.press_arrow_right();
         mobileInput.focus();
     });
+    $('#button-enter').on('click', function() {
+        osk.press_enter();
+        mobileInput.focus();
+    });
+    for (let n = 1; n <= 6; n++) {
+        $('#button-f' + n).on('click', function() {
+            osk.press_function_key(n);
+            mobileInput.focus();
+        });
+    }
 
     $('#controls').css('visibility', 'visible');
+    $('#button-keyboard').css('visibility', 'visible');
+    $('#button-keyboard').on('click', function() {
+        let controls = $('#controls');
+        if (controls.css('visibility') == 'visible') {
+            controls.css('visibility', 'hidden');
+        } else {
+            controls.css('visibility', 'visible');
+        }
+        mobileInput.focus();
+    });
+
+    // Converts the client coordinates of a touch into canvas pixels.
+    function touchToCanvas(touch) {
+        let rect = terminal.getBoundingClientRect();
+        return {
+            x: (touch.clientX - rect.left) * terminal.width / rect.width,
+            y: (touch.clientY - rect.top) * terminal.height / rect.height,
+        };
+    }
+
+    // Taps position the cursor, swipes generate arrow keys, and long presses generate Enter.
+    // The gesture is only classified once the touch ends.
+    var touchStart = null;
+    terminal.addEventListener("touchstart", function(ev) {
+        if (ev.touches.length == 1) {
+            touchStart = touchToCanvas(ev.touches[0]);
+            touchStart.time = Date.now();
+        } else {
+            touchStart = null;
+        }
+    });
+    terminal.addEventListener("touchend", function(ev) {
+        if (touchStart !== null && ev.changedTouches.length == 1) {
+            let end = touchToCanvas(ev.changedTouches[0]);
+            osk.inject_touch(
+                touchStart.x, touchStart.y, end.x, end.y, Date.now() - touchStart.time);
+        }
+        touchStart = null;
+    });
 
     if (isAndroid) {
         // Handling the keyboard on Android is messy.  If we have a real keyboard, we get keydown
@@ -13,18 +13,88 @@
 // License for the specific language governing permissions and limitations
 // under the License.
 
-//! Keyboard input tools for the web UI.
+//! Keyboard and touch input tools for the web UI.
 
 use crate::{log_and_panic, Yielder};
 use async_channel::{self, Receiver, Sender, TryRecvError};
 use endbasic_core::exec::Signal;
-use endbasic_std::console::Key;
+use endbasic_std::console::{key_name, CharsXY, Key, PixelsXY, SizeInPixels};
 use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::io;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use web_sys::{InputEvent, KeyboardEvent};
 
+/// Minimum distance in pixels that a touch has to travel to be considered a swipe.
+const SWIPE_MIN_PIXELS: f64 = 30.0;
+
+/// Minimum duration in milliseconds of a touch that does not move to be considered a long press.
+const LONG_PRESS_MILLIS: f64 = 500.0;
+
+/// Input event captured from the browser.
+#[derive(Debug, PartialEq)]
+enum Event {
+    /// A key press.
+    Key(Key),
+
+    /// A tap on the console at the given pixel coordinates.
+    Tap(PixelsXY),
+}
+
+/// Converts a touch that started at `start` and ended at `end`, both in canvas pixels, and that
+/// lasted `duration_ms` into an input event.
+///
+/// Touches that travel far enough are swipes and map to the arrow key in their main direction.
+/// Touches that stay in place are long presses, which map to Enter, if they last long enough, or
+/// taps otherwise.
+fn touch_into_event(start: (f64, f64), end: (f64, f64), duration_ms: f64) -> Event {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    if dx.abs().max(dy.abs()) >= SWIPE_MIN_PIXELS {
+        if dx.abs() >= dy.abs() {
+            Event::Key(if dx > 0.0 { Key::ArrowRight } else { Key::ArrowLeft })
+        } else {
+            Event::Key(if dy > 0.0 { Key::ArrowDown } else { Key::ArrowUp })
+        }
+    } else if duration_ms >= LONG_PRESS_MILLIS {
+        Event::Key(Key::NewLine)
+    } else {
+        let clamp = |v: f64| v.max(f64::from(i16::MIN)).min(f64::from(i16::MAX)) as i16;
+        Event::Tap(PixelsXY::new(clamp(start.0), clamp(start.1)))
+    }
+}
+
+/// Computes the arrow key presses that move the cursor at `cursor` to the character cell under
+/// the `tap` coordinates, given that each cell is `glyph_size` pixels big.
+///
+/// Vertical movement is only generated if `vertical` is true.  Otherwise, taps on lines other than
+/// the cursor's are ignored so that, e.g., tapping the console to bring up the keyboard does not
+/// browse the REPL history.
+fn tap_into_keys(
+    tap: PixelsXY,
+    glyph_size: SizeInPixels,
+    cursor: CharsXY,
+    vertical: bool,
+) -> Vec<Key> {
+    if tap.x < 0 || tap.y < 0 || glyph_size.width == 0 || glyph_size.height == 0 {
+        return vec![];
+    }
+    let column = i32::from(tap.x) / i32::from(glyph_size.width);
+    let row = i32::from(tap.y) / i32::from(glyph_size.height);
+    let dx = column - i32::from(cursor.x);
+    let dy = row - i32::from(cursor.y);
+    if dy != 0 && !vertical {
+        return vec![];
+    }
+
+    let mut keys = vec![];
+    let vertical_key = if dy < 0 { Key::ArrowUp } else { Key::ArrowDown };
+    keys.extend((0..dy.abs()).map(|_| vertical_key.clone()));
+    let horizontal_key = if dx < 0 { Key::ArrowLeft } else { Key::ArrowRight };
+    keys.extend((0..dx.abs()).map(|_| horizontal_key.clone()));
+    keys
+}
+
 /// Converts an HTML input event into our own `Key` representation.
 fn on_input_event_into_key(dom_event: InputEvent) -> Key {
     let chars = match dom_event.data() {
@@ -59,11 +129,11 @@ fn on_key_event_into_key(dom_event: KeyboardEvent) -> Key {
         b'C' if dom_event.ctrl_key() => Key::Interrupt,
         b'D' if dom_event.ctrl_key() => Key::Eof,
         b'E' if dom_event.ctrl_key() => Key::End,
-        b'F' if dom_event.ctrl_key() => Key::ArrowRight,
         b'J' if dom_event.ctrl_key() => Key::NewLine,
         b'M' if dom_event.ctrl_key() => Key::NewLine,
         b'N' if dom_event.ctrl_key() => Key::ArrowDown,
         b'P' if dom_event.ctrl_key() => Key::ArrowUp,
+        ch @ b'A'..=b'Z' if dom_event.ctrl_key() => Key::Control(ch.to_ascii_lowercase() as char),
         _ => {
             let printable = !dom_event.alt_key() && !dom_event.ctrl_key() && !dom_event.meta_key();
             let chars = dom_event.key().chars().collect::<Vec<char>>();
@@ -81,15 +151,31 @@ fn on_key_event_into_key(dom_event: KeyboardEvent) -> Key {
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct OnScreenKeyboard {
-    on_key_tx: Sender<Key>,
+    on_event_tx: Sender<Event>,
     signals_tx: Sender<Signal>,
 }
 
 #[wasm_bindgen]
 impl OnScreenKeyboard {
-    /// Wrapper around `self.on_key_tx.try_send` that always expects to succeed.
-    fn safe_try_send(&self, key: Key) {
-        if let Err(e) = self.on_key_tx.try_send(key) {
+    /// Wrapper around `self.signals_tx.try_send` that always expects to succeed.
+    fn safe_try_send_signal(&self, signal: Signal) {
+        if let Err(e) = self.signals_tx.try_send(signal) {
+            log_and_panic!("Send to unbounded channel must succeed: {}", e);
+        }
+    }
+
+    /// Wrapper around `self.on_event_tx.try_send` that always expects to succeed.
+    ///
+    /// Key presses are also delivered to the machine so that any handler installed with `ON KEY`
+    /// can react to them.
+    fn safe_try_send(&self, event: Event) {
+        if let Event::Key(key) = &event {
+            if let Some(name) = key_name(key) {
+                self.safe_try_send_signal(Signal::Key(name));
+            }
+        }
+
+        if let Err(e) = self.on_event_tx.try_send(event) {
             log_and_panic!("Send to unbounded channel must succeed: {}", e);
         }
     }
@@ -97,84 +183,225 @@ impl OnScreenKeyboard {
     /// Pushes a new captured `dom_event` input event into the input.
     pub fn inject_input_event(&self, dom_event: InputEvent) {
         // TODO(jmmv): Add an on-screen button to send CTRL+C events.
-        self.safe_try_send(on_input_event_into_key(dom_event))
+        self.safe_try_send(Event::Key(on_input_event_into_key(dom_event)))
     }
 
     /// Pushes a new captured `dom_event` keyboard event into the input.
     pub fn inject_keyboard_event(&self, dom_event: KeyboardEvent) {
+        // Function keys have no `Key` representation so they can only be handled with `ON KEY`.
+        let key_code = dom_event.key_code();
+        if (112..=123).contains(&key_code) {
+            self.safe_try_send_signal(Signal::Key(format!("F{}", key_code - 111)));
+            return;
+        }
+
         let key = on_key_event_into_key(dom_event);
         if key == Key::Interrupt {
-            if let Err(e) = self.signals_tx.try_send(Signal::Break) {
-                log_and_panic!("Send to unbounded channel must succeed: {}", e);
-            }
+            self.safe_try_send_signal(Signal::Break);
         }
 
-        self.safe_try_send(key)
+        self.safe_try_send(Event::Key(key))
+    }
+
+    /// Pushes a touch on the console into the input.
+    ///
+    /// The touch started at (`start_x`, `start_y`), ended at (`end_x`, `end_y`), and lasted
+    /// `duration_ms`.  All coordinates are in canvas pixels.
+    pub fn inject_touch(
+        &self,
+        start_x: f64,
+        start_y: f64,
+        end_x: f64,
+        end_y: f64,
+        duration_ms: f64,
+    ) {
+        self.safe_try_send(touch_into_event((start_x, start_y), (end_x, end_y), duration_ms))
+    }
+
+    /// Generates a fake press of the function key `n`.
+    ///
+    /// Function keys have no `Key` representation so they can only be handled with `ON KEY`.
+    pub fn press_function_key(&self, n: u8) {
+        self.safe_try_send_signal(Signal::Key(format!("F{}", n)))
+    }
+
+    /// Generates a fake Enter key press.
+    pub fn press_enter(&self) {
+        self.safe_try_send(Event::Key(Key::NewLine))
     }
 
     /// Generates a fake Escape key press.
     pub fn press_escape(&self) {
-        self.safe_try_send(Key::Escape)
+        self.safe_try_send(Event::Key(Key::Escape))
     }
 
     /// Generates a fake arrow up key press.
     pub fn press_arrow_up(&self) {
-        self.safe_try_send(Key::ArrowUp)
+        self.safe_try_send(Event::Key(Key::ArrowUp))
     }
 
     /// Generates a fake arrow down key press.
     pub fn press_arrow_down(&self) {
-        self.safe_try_send(Key::ArrowDown)
+        self.safe_try_send(Event::Key(Key::ArrowDown))
     }
 
     /// Generates a fake arrow left key press.
     pub fn press_arrow_left(&self) {
-        self.safe_try_send(Key::ArrowLeft)
+        self.safe_try_send(Event::Key(Key::ArrowLeft))
     }
 
     /// Generates a fake arrow up key press.
     pub fn press_arrow_right(&self) {
-        self.safe_try_send(Key::ArrowRight)
+        self.safe_try_send(Event::Key(Key::ArrowRight))
     }
 }
 
-/// Interface to interact with the browser's input, be it via a real keyboard or our custom
-/// on-screen keyboard.
+/// Location of the console's cursor, used to translate taps into cursor movements.
+pub(crate) struct CursorState {
+    /// Size of each character.
+    pub(crate) glyph_size: SizeInPixels,
+
+    /// Location of the cursor.
+    pub(crate) cursor_pos: CharsXY,
+
+    /// Whether taps can move the cursor to other lines.
+    pub(crate) vertical: bool,
+}
+
+/// Interface to interact with the browser's input, be it via a real keyboard, our custom
+/// on-screen keyboard, or touches on the console.
 pub struct WebInput {
-    on_key_rx: Receiver<Key>,
-    on_key_tx: Sender<Key>,
+    on_event_rx: Receiver<Event>,
+    on_event_tx: Sender<Event>,
     signals_tx: Sender<Signal>,
     yielder: Rc<RefCell<Yielder>>,
+
+    /// Keys synthesized from a previous tap that have not been consumed yet.
+    pending: VecDeque<Key>,
 }
 
 impl WebInput {
     /// Creates a new `WebInput` that can inject events into the interpreter via `signals_tx`.
     pub(crate) fn new(signals_tx: Sender<Signal>, yielder: Rc<RefCell<Yielder>>) -> Self {
-        let (on_key_tx, on_key_rx) = async_channel::unbounded();
-        Self { on_key_rx, on_key_tx, signals_tx, yielder }
+        let (on_event_tx, on_event_rx) = async_channel::unbounded();
+        Self { on_event_rx, on_event_tx, signals_tx, yielder, pending: VecDeque::default() }
     }
 
     /// Generates a new `OnScreenKeyboard` that can inject key events.
     pub(crate) fn on_screen_keyboard(&self) -> OnScreenKeyboard {
-        OnScreenKeyboard { on_key_tx: self.on_key_tx.clone(), signals_tx: self.signals_tx.clone() }
+        OnScreenKeyboard {
+            on_event_tx: self.on_event_tx.clone(),
+            signals_tx: self.signals_tx.clone(),
+        }
+    }
+
+    /// Processes a received `event`, queuing the keys it yields given the `cursor` state.
+    fn process(&mut self, event: Event, cursor: &CursorState) {
+        match event {
+            Event::Key(key) => self.pending.push_back(key),
+            Event::Tap(xy) => self.pending.extend(tap_into_keys(
+                xy,
+                cursor.glyph_size,
+                cursor.cursor_pos,
+                cursor.vertical,
+            )),
+        }
     }
 
     /// Gets the next key event, if one is available.
-    pub(crate) async fn try_recv(&mut self) -> io::Result<Option<Key>> {
-        match self.on_key_rx.try_recv() {
-            Ok(k) => {
-                self.yielder.borrow_mut().reset();
-                Ok(Some(k))
+    pub(crate) async fn try_recv(&mut self, cursor: &CursorState) -> io::Result<Option<Key>> {
+        while self.pending.is_empty() {
+            match self.on_event_rx.try_recv() {
+                Ok(event) => self.process(event, cursor),
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Closed) => log_and_panic!("Channel unexpectedly closed"),
             }
-            Err(TryRecvError::Empty) => Ok(None),
-            Err(TryRecvError::Closed) => log_and_panic!("Channel unexpectedly closed"),
         }
+        self.yielder.borrow_mut().reset();
+        Ok(self.pending.pop_front())
     }
 
     /// Gets the next key event, waiting until one is available.
-    pub(crate) async fn recv(&mut self) -> io::Result<Key> {
-        let key = self.on_key_rx.recv().await.unwrap();
+    pub(crate) async fn recv(&mut self, cursor: &CursorState) -> io::Result<Key> {
+        while self.pending.is_empty() {
+            let event = self.on_event_rx.recv().await.unwrap();
+            self.process(event, cursor);
+        }
         self.yielder.borrow_mut().reset();
-        Ok(key)
+        Ok(self.pending.pop_front().expect("Loop only exits once there are pending keys"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_touch_into_event_swipes() {
+        assert_eq!(
+            Event::Key(Key::ArrowRight),
+            touch_into_event((10.0, 10.0), (50.0, 20.0), 100.0)
+        );
+        assert_eq!(Event::Key(Key::ArrowLeft), touch_into_event((50.0, 10.0), (10.0, 20.0), 100.0));
+        assert_eq!(Event::Key(Key::ArrowDown), touch_into_event((10.0, 10.0), (20.0, 50.0), 900.0));
+        assert_eq!(Event::Key(Key::ArrowUp), touch_into_event((10.0, 50.0), (20.0, 10.0), 900.0));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_touch_into_event_long_press() {
+        assert_eq!(Event::Key(Key::NewLine), touch_into_event((10.0, 10.0), (15.0, 12.0), 500.0));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_touch_into_event_tap() {
+        assert_eq!(
+            Event::Tap(PixelsXY::new(10, 20)),
+            touch_into_event((10.4, 20.6), (12.0, 25.0), 100.0)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_tap_into_keys_same_line() {
+        let glyph_size = SizeInPixels { width: 8, height: 16 };
+        let cursor = CharsXY::new(5, 2);
+        assert_eq!(
+            vec![Key::ArrowLeft, Key::ArrowLeft],
+            tap_into_keys(PixelsXY::new(25, 40), glyph_size, cursor, false)
+        );
+        assert_eq!(
+            vec![Key::ArrowRight],
+            tap_into_keys(PixelsXY::new(48, 32), glyph_size, cursor, false)
+        );
+        assert!(tap_into_keys(PixelsXY::new(40, 32), glyph_size, cursor, false).is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_tap_into_keys_other_line() {
+        let glyph_size = SizeInPixels { width: 8, height: 16 };
+        let cursor = CharsXY::new(5, 2);
+        assert!(tap_into_keys(PixelsXY::new(40, 0), glyph_size, cursor, false).is_empty());
+        assert_eq!(
+            vec![Key::ArrowUp, Key::ArrowUp, Key::ArrowLeft],
+            tap_into_keys(PixelsXY::new(32, 0), glyph_size, cursor, true)
+        );
+        assert_eq!(
+            vec![Key::ArrowDown, Key::ArrowRight],
+            tap_into_keys(PixelsXY::new(48, 48), glyph_size, cursor, true)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_tap_into_keys_out_of_bounds() {
+        let glyph_size = SizeInPixels { width: 8, height: 16 };
+        let cursor = CharsXY::new(5, 2);
+        assert!(tap_into_keys(PixelsXY::new(-1, 32), glyph_size, cursor, true).is_empty());
+        assert!(tap_into_keys(
+            PixelsXY::new(40, 32),
+            SizeInPixels { width: 0, height: 16 },
+            cursor,
+            true
+        )
+        .is_empty());
     }
 }
@@ -16,7 +16,7 @@
 //! Implementation of a drive that uses the browser's local storage.
 
 use async_trait::async_trait;
-use endbasic_std::storage::{Drive, DriveFactory, DriveFiles, Metadata};
+use endbasic_std::storage::{guess_kind, Drive, DriveFactory, DriveFiles, Metadata};
 use std::collections::BTreeMap;
 use std::io;
 use time::{OffsetDateTime, UtcOffset};
@@ -40,7 +40,7 @@ impl Clock for JsClock {
     }
 }
 
-/// Key for a program stored in the browser's local storage.
+/// Key for a file stored in the browser's local storage.
 #[derive(Debug, Eq, PartialEq)]
 struct Key(String);
 
@@ -48,12 +48,12 @@ impl Key {
     /// Prefix for all keys that belong to us.
     const PREFIX: &'static str = "endbasic-program:";
 
-    /// Creates a new key for a program name.
+    /// Creates a new key for a file name.
     ///
     /// The file name is unconditionally converted to uppercase to facilitate case-insensitive
     /// matching.
     fn for_name(name: &str) -> Self {
-        debug_assert!(name.to_ascii_uppercase().ends_with(".BAS"));
+        debug_assert!(!name.is_empty());
         Self(format!("{}{}", Key::PREFIX, name.to_ascii_uppercase()))
     }
 
@@ -70,15 +70,18 @@ impl Key {
     ///
     /// Note that this *does* respect the case of the file name provided in the string.  This is
     /// necessary to migrate older versions of the drive that were case sensitive.
+    ///
+    /// The prefix is historical: older versions of the drive only stored programs, but any file
+    /// can be stored now so that data files written by programs also persist.
     fn parse(raw: &str) -> Option<Key> {
-        if raw.starts_with(Key::PREFIX) && raw.to_ascii_uppercase().ends_with(".BAS") {
+        if raw.starts_with(Key::PREFIX) && raw.len() > Key::PREFIX.len() {
             Some(Key(raw.to_owned()))
         } else {
             None
         }
     }
 
-    /// Returns the program name for this key.
+    /// Returns the file name for this key.
     fn name(&self) -> &str {
         &self.0[Key::PREFIX.len()..]
     }
@@ -95,7 +98,7 @@ struct Entry {
     /// Version of the schema used to write out this entry.
     version: u16,
 
-    /// The textual content of the program.
+    /// The textual content of the file.
     content: String,
 
     /// The last modification time of the program, in UTC.
@@ -111,8 +114,8 @@ impl Entry {
         Self { version: Entry::VERSION, content: content.into(), mtime }
     }
 
-    /// Returns the generic `Metadata` object for this entry.
-    fn metadata(&self) -> Metadata {
+    /// Returns the generic `Metadata` object for this entry, which is stored as `name`.
+    fn metadata(&self, name: &str) -> Metadata {
         // I'm sure there is something wrong with this timezone adjustment.
         let tz_offset = match UtcOffset::from_whole_seconds(
             -js_sys::Date::new_0().get_timezone_offset() as i32 * 60,
@@ -120,7 +123,12 @@ impl Entry {
             Ok(tz_offset) => tz_offset,
             Err(_) => UtcOffset::UTC,
         };
-        Metadata { date: self.mtime.to_offset(tz_offset), length: self.content.len() as u64 }
+        Metadata {
+            date: self.mtime.to_offset(tz_offset),
+            length: self.content.len() as u64,
+            kind: guess_kind(name),
+            read_only: false,
+        }
     }
 }
 
@@ -274,7 +282,7 @@ impl Drive for WebDrive {
 
             if let Some(key) = Key::parse(&key) {
                 let entry = self.get_entry(&key)?;
-                entries.insert(key.name().to_owned(), entry.metadata());
+                entries.insert(key.name().to_owned(), entry.metadata(key.name()));
             }
         }
 
@@ -360,7 +368,11 @@ mod tests {
             Key::parse("endbasic-program:hello.bas")
         );
 
-        assert_eq!(None, Key::parse("endbasic-program:unknown.bat"));
+        assert_eq!(
+            Some(Key("endbasic-program:SCORES.CSV".to_owned())),
+            Key::parse("endbasic-program:SCORES.CSV")
+        );
+
         assert_eq!(None, Key::parse("endbasic-program:"));
         assert_eq!(None, Key::parse("foo-program:hello.bas"));
     }
@@ -434,13 +446,21 @@ mod tests {
             .storage
             .set("endbasic-program:SECOND SPACES.BAS", &serde_json::to_string(&entry2).unwrap())
             .unwrap();
+        webdrive
+            .storage
+            .set("endbasic-program:SCORES.CSV", &serde_json::to_string(&entry1).unwrap())
+            .unwrap();
         webdrive.storage.set("first.bas", "ignore me").unwrap();
         webdrive.storage.set("endbasic-program:", "ignore me").unwrap();
 
         let files = webdrive.enumerate().await.unwrap();
-        assert_eq!(2, files.dirents().len());
-        assert_eq!(&entry1.metadata(), files.dirents().get("FIRST.BAS").unwrap());
-        assert_eq!(&entry2.metadata(), files.dirents().get("SECOND SPACES.BAS").unwrap());
+        assert_eq!(3, files.dirents().len());
+        assert_eq!(&entry1.metadata("FIRST.BAS"), files.dirents().get("FIRST.BAS").unwrap());
+        assert_eq!(
+            &entry2.metadata("SECOND SPACES.BAS"),
+            files.dirents().get("SECOND SPACES.BAS").unwrap()
+        );
+        assert_eq!(&entry1.metadata("SCORES.CSV"), files.dirents().get("SCORES.CSV").unwrap());
     }
 
     #[wasm_bindgen_test]
@@ -483,6 +503,16 @@ mod tests {
         );
     }
 
+    #[wasm_bindgen_test]
+    async fn test_webdrive_put_data_file() {
+        let mut webdrive = WebDrive::from_window();
+        webdrive.storage.clear().unwrap();
+        webdrive.put("scores.csv", "1,2,3\n").await.unwrap();
+
+        assert_eq!("1,2,3\n", webdrive.get("SCORES.CSV").await.unwrap());
+        assert!(webdrive.enumerate().await.unwrap().dirents().contains_key("SCORES.CSV"));
+    }
+
     #[wasm_bindgen_test]
     fn test_webdrive_system_path() {
         let webdrive = WebDrive::from_window();